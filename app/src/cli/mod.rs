@@ -1,9 +1,12 @@
 use std::path::PathBuf;
 
+use ambient_project::Identifier;
 use clap::{Args, Parser};
 
 pub mod new_project;
 
+use new_project::{ProjectFeature, ProjectTemplate};
+
 #[derive(Parser, Clone)]
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
@@ -20,6 +23,18 @@ pub enum Commands {
         project_args: ProjectCli,
         #[arg(short, long)]
         name: Option<String>,
+        /// The id of the project; defaults to the name in snake case
+        #[arg(long, value_parser = |id: &str| Identifier::new(id))]
+        id: Option<Identifier>,
+        /// The template to create the project from
+        #[arg(short, long, value_enum, default_value_t)]
+        template: ProjectTemplate,
+        /// The optional parts to add to the project on top of the default ones (`vscode`), separated by commas
+        #[arg(long, value_enum, value_delimiter = ',')]
+        features: Vec<ProjectFeature>,
+        /// Leave out the default optional parts of the project
+        #[arg(long)]
+        no_default_features: bool,
     },
     /// Builds and runs the project locally
     Run {
@@ -35,6 +50,24 @@ pub enum Commands {
         #[command(flatten)]
         project_args: ProjectCli,
     },
+    /// Builds the project with full optimization and packages it into distributable client and server builds
+    Package {
+        #[command(flatten)]
+        project_args: ProjectCli,
+        /// The directory to write the builds to; defaults to `package` in the project directory
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Encrypt the packed assets with the key in `AMBIENT_PAK_KEY`, or with a new key if it isn't set
+        #[arg(long)]
+        encrypt: bool,
+    },
+    /// Updates a packaged server build to a newer release, downloading only what changed
+    Update {
+        /// The URL of the build directory of the release
+        release_url: String,
+        /// The path of the packaged server build; if not specified, this will default to the current directory
+        path: Option<String>,
+    },
     /// Deploys the project
     #[cfg(feature = "deploy")]
     Deploy {
@@ -88,9 +121,17 @@ pub struct RunCli {
     #[clap(short, long)]
     pub user_id: Option<String>,
 
+    /// Join as a spectator, which sees the world without a player entity
+    #[arg(long)]
+    pub spectate: bool,
+
     /// Specify a trusted certificate authority
     #[arg(long)]
     pub ca: Option<PathBuf>,
+
+    /// Show the activity of the player on Discord as this Discord application, with the `discord` feature
+    #[arg(long)]
+    pub discord_app_id: Option<i64>,
 }
 
 #[derive(Args, Clone, Debug)]
@@ -122,6 +163,28 @@ pub struct HostCli {
     #[arg(long)]
     pub quic_interface_port: Option<u16>,
 
+    /// Serve the runtime statistics of the server in the Prometheus format at `/metrics` on this port
+    #[arg(long)]
+    pub metrics_port: Option<u16>,
+
+    /// Run as a shard of a cluster, with the configuration in this TOML file
+    #[arg(long)]
+    pub cluster: Option<PathBuf>,
+
+    /// The index of the shard of the cluster this server runs
+    #[arg(long, requires("cluster"), default_value_t = 0)]
+    pub shard: usize,
+
+    /// Let the player with this user ID administer the server, like enabling mods with the debugger, which the host
+    /// (the client on the same machine) always can. Can be given more than once
+    #[arg(long = "admin")]
+    pub admins: Vec<String>,
+
+    /// Only let the Steam users of this Steam app join, after checking their session tickets with the Steam Web API.
+    /// The publisher Web API key of the app is read from the `STEAM_WEB_API_KEY` environment variable
+    #[arg(long)]
+    pub steam_app_id: Option<u32>,
+
     /// Don't use proxy for NAT traversal
     #[arg(long)]
     pub no_proxy: bool,
@@ -140,6 +203,44 @@ pub struct HostCli {
     /// Private key for the certificate
     #[arg(long)]
     pub key: Option<PathBuf>,
+
+    /// Forward the logs of the server modules to all connected clients, instead of only to the players with `module_log_receiver`
+    #[arg(long)]
+    pub forward_module_logs: bool,
+
+    /// Load the mods in this directory, or in the listing at this URL
+    ///
+    /// Defaults to the `mods` directory of the project
+    #[arg(long)]
+    pub mods: Option<String>,
+
+    /// Run the project in the deterministic mode for N ticks at a fixed time step, without players, compare the hash of
+    /// its world after every tick to the ones in the determinism trace and then exit with an exit code of 1 if they are
+    /// different or there is no trace, reporting the first tick and components that differ
+    #[arg(long)]
+    pub determinism_test: Option<usize>,
+
+    /// Record the determinism trace of the `--determinism-test` run instead of comparing to it
+    #[arg(long, requires("determinism_test"))]
+    pub determinism_record: bool,
+
+    /// The determinism trace to compare to, which can be recorded on another platform or by another build
+    ///
+    /// Defaults to `determinism.json` in the project directory
+    #[arg(long, requires("determinism_test"))]
+    pub determinism_trace: Option<PathBuf>,
+
+    /// Host the project as untrusted content: cap its entities, the sizes of its components and assets and the fuel of
+    /// its modules, only let it read files in the project and only download assets from its own content and the
+    /// allowed hosts
+    #[arg(long)]
+    pub hardened: bool,
+
+    /// The limits of the hardened mode, in this TOML file
+    ///
+    /// Defaults to the built-in limits, with no other allowed hosts
+    #[arg(long, requires("hardened"))]
+    pub hardened_limits: Option<PathBuf>,
 }
 
 impl Cli {
@@ -149,6 +250,8 @@ impl Cli {
             Commands::New { .. } => None,
             Commands::Run { run_args, .. } => Some(run_args),
             Commands::Build { .. } => None,
+            Commands::Package { .. } => None,
+            Commands::Update { .. } => None,
             #[cfg(feature = "deploy")]
             Commands::Deploy { .. } => None,
             Commands::Serve { .. } => None,
@@ -162,6 +265,8 @@ impl Cli {
             Commands::New { project_args, .. } => Some(project_args),
             Commands::Run { project_args, .. } => Some(project_args),
             Commands::Build { project_args, .. } => Some(project_args),
+            Commands::Package { project_args, .. } => Some(project_args),
+            Commands::Update { .. } => None,
             #[cfg(feature = "deploy")]
             Commands::Deploy { project_args, .. } => Some(project_args),
             Commands::Serve { project_args, .. } => Some(project_args),
@@ -175,6 +280,8 @@ impl Cli {
             Commands::New { .. } => None,
             Commands::Run { host_args, .. } => Some(host_args),
             Commands::Build { .. } => None,
+            Commands::Package { .. } => None,
+            Commands::Update { .. } => None,
             #[cfg(feature = "deploy")]
             Commands::Deploy { .. } => None,
             Commands::Serve { host_args, .. } => Some(host_args),
@@ -1,12 +1,106 @@
-use std::path::Path;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
 use ambient_project::Identifier;
 use anyhow::Context;
+use clap::ValueEnum;
 use convert_case::Casing;
 
-pub(crate) fn new_project(project_path: &Path, name: Option<&str>) -> anyhow::Result<()> {
+/// The skeletons that a project can be created from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ProjectTemplate {
+    /// A camera looking at a quad
+    #[default]
+    Empty,
+    /// A first-person character that walks and looks around with the keyboard and mouse, among some crates
+    Fps,
+    /// A client-only user interface
+    Ui,
+    /// The head and hands of every player in XR, following their headset and controllers
+    Vr,
+}
+
+/// The optional parts of a project, on top of its template
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ProjectFeature {
+    /// Settings, launch configurations and recommended extensions for Visual Studio Code
+    Vscode,
+    /// An `assets` folder with an asset pipeline, for the templates that don't have one
+    Assets,
+}
+impl ProjectFeature {
+    /// The features that every project gets unless they are turned off
+    pub const DEFAULT: &[Self] = &[Self::Vscode];
+}
+
+macro_rules! include_template {
+    ($path:expr) => {
+        (Path::new($path), include_str!(concat!("templates/", $path)))
+    };
+}
+
+impl ProjectTemplate {
+    /// The modules of the project, each of which is built from `src/{module}.rs`
+    fn modules(self) -> &'static [&'static str] {
+        match self {
+            Self::Ui => &["client"],
+            Self::Empty | Self::Fps | Self::Vr => &["client", "server"],
+        }
+    }
+
+    /// The files of the template, which take precedence over the common ones
+    fn files(self) -> Vec<(&'static Path, &'static str)> {
+        match self {
+            Self::Empty => vec![
+                include_template!("empty/ambient.toml"),
+                include_template!("empty/src/client.rs"),
+                include_template!("empty/src/server.rs"),
+            ],
+            Self::Fps => vec![
+                include_template!("fps/ambient.toml"),
+                include_template!("fps/assets/pipeline.json"),
+                include_template!("fps/assets/crate.obj"),
+                include_template!("fps/src/client.rs"),
+                include_template!("fps/src/server.rs"),
+            ],
+            Self::Ui => vec![
+                include_template!("ui/ambient.toml"),
+                include_template!("ui/src/client.rs"),
+            ],
+            Self::Vr => vec![
+                include_template!("vr/ambient.toml"),
+                include_template!("vr/src/client.rs"),
+                include_template!("vr/src/server.rs"),
+            ],
+        }
+    }
+}
+
+/// The parameters of a new project
+#[derive(Clone, Debug)]
+pub(crate) struct NewProject<'a> {
+    /// The name of the project, which is also the directory it is created in under the project path
+    pub name: Option<&'a str>,
+    /// The id of the project; defaults to the name in snake case
+    pub id: Option<&'a Identifier>,
+    pub template: ProjectTemplate,
+    /// The features to add on top of the default ones
+    pub features: &'a [ProjectFeature],
+    /// Whether the project gets the [ProjectFeature::DEFAULT] features
+    pub default_features: bool,
+}
+impl NewProject<'_> {
+    fn has_feature(&self, feature: ProjectFeature) -> bool {
+        self.features.contains(&feature)
+            || (self.default_features && ProjectFeature::DEFAULT.contains(&feature))
+    }
+}
+
+pub(crate) fn new_project(project_path: &Path, params: NewProject) -> anyhow::Result<()> {
     // Build the identifier.
-    let project_path = if let Some(name) = name {
+    let project_path = if let Some(name) = params.name {
         project_path.join(name)
     } else {
         project_path.to_owned()
@@ -21,23 +115,14 @@ pub(crate) fn new_project(project_path: &Path, name: Option<&str>) -> anyhow::Re
         anyhow::bail!("project path {project_path:?} is not empty");
     }
 
-    let id = name.to_case(convert_case::Case::Snake);
-    let id = Identifier::new(id).map_err(anyhow::Error::msg)?;
-
-    // Create the folders.
-    let dot_cargo_path = project_path.join(".cargo");
-    let dot_vscode_path = project_path.join(".vscode");
-    let src_path = project_path.join("src");
-
-    std::fs::create_dir_all(&project_path).context("Failed to create project directory")?;
-    std::fs::create_dir_all(&dot_cargo_path).context("Failed to create .cargo directory")?;
-    std::fs::create_dir_all(&dot_vscode_path).context("Failed to create .vscode directory")?;
-    std::fs::create_dir_all(&src_path).context("Failed to create src directory")?;
-
-    // Write the files to disk.
-    let ambient_toml = include_str!("new_project_template/ambient.toml")
-        .replace("{{id}}", id.as_ref())
-        .replace("{{name}}", name);
+    let id = match params.id {
+        Some(id) => id.clone(),
+        None => Identifier::new(name.to_case(convert_case::Case::Snake))
+            .map_err(anyhow::Error::msg)
+            .with_context(|| {
+                format!("{name:?} can't be used as a project id; pass one with --id")
+            })?,
+    };
 
     #[cfg(not(feature = "production"))]
     log::info!("Ambient git version: {}", git_version::git_version!());
@@ -74,7 +159,19 @@ pub(crate) fn new_project(project_path: &Path, name: Option<&str>) -> anyhow::Re
             ),
         };
 
-        let mut template_cargo_toml = include_str!("new_project_template/Cargo.toml")
+        let bins = params
+            .template
+            .modules()
+            .iter()
+            .map(|module| {
+                format!(
+                    "[[bin]]\nname = \"{module}_{id}\"\npath = \"src/{module}.rs\"\nrequired-features = [\"{module}\"]\n\n"
+                )
+            })
+            .collect::<String>();
+
+        let mut template_cargo_toml = include_str!("templates/common/Cargo.toml")
+            .replace("{{bins}}", &bins)
             .replace("{{id}}", id.as_ref())
             .replace(
                 "ambient_api = { path = \"../../../../guest/rust/api\" }",
@@ -91,38 +188,47 @@ pub(crate) fn new_project(project_path: &Path, name: Option<&str>) -> anyhow::Re
         template_cargo_toml
     };
 
-    macro_rules! include_template {
-        ($path:expr) => {
-            (
-                Path::new($path),
-                include_str!(concat!("new_project_template/", $path)),
-            )
-        };
-    }
-
-    let files_to_write = &[
-        // root
-        (Path::new("ambient.toml"), ambient_toml.as_str()),
-        (Path::new("Cargo.toml"), cargo_toml.as_str()),
-        include_template!(".gitignore"),
-        include_template!("rust-toolchain.toml"),
-        // .cargo
-        include_template!(".cargo/config.toml"),
-        // .vscode
-        include_template!(".vscode/settings.json"),
-        include_template!(".vscode/launch.json"),
-        include_template!(".vscode/extensions.json"),
-        // src
-        include_template!("src/client.rs"),
-        include_template!("src/server.rs"),
+    let mut template_files = vec![
+        include_template!("common/.gitignore"),
+        include_template!("common/rust-toolchain.toml"),
+        include_template!("common/.cargo/config.toml"),
     ];
+    if params.has_feature(ProjectFeature::Vscode) {
+        template_files.extend([
+            include_template!("common/.vscode/settings.json"),
+            include_template!("common/.vscode/launch.json"),
+            include_template!("common/.vscode/extensions.json"),
+        ]);
+    }
+    if params.has_feature(ProjectFeature::Assets) {
+        template_files.push(include_template!("common/assets/pipeline.json"));
+    }
+    // The files of the template come last, so they replace the common ones at the same paths.
+    template_files.extend(params.template.files());
+
+    let mut files = BTreeMap::from([(PathBuf::from("Cargo.toml"), cargo_toml)]);
+    for (path, contents) in template_files {
+        // Strip the directory of the template from the path
+        let path = path.iter().skip(1).collect::<PathBuf>();
+        let contents = contents
+            .replace("{{id}}", id.as_ref())
+            .replace("{{name}}", name);
+        files.insert(path, contents);
+    }
 
-    for (filename, contents) in files_to_write {
-        std::fs::write(project_path.join(filename), contents)
-            .with_context(|| format!("failed to create {filename:?}"))?;
+    for (filename, contents) in &files {
+        let path = project_path.join(filename);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create the directory of {filename:?}"))?;
+        }
+        std::fs::write(path, contents).with_context(|| format!("failed to create {filename:?}"))?;
     }
 
-    log::info!("Project \"{name}\" with id `{id}` created at {project_path:?}");
+    log::info!(
+        "Project \"{name}\" with id `{id}` created at {project_path:?} from the {:?} template",
+        params.template
+    );
 
     Ok(())
 }
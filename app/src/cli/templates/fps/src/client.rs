@@ -0,0 +1,28 @@
+use ambient_api::prelude::*;
+
+#[main]
+fn main() {
+    let mut cursor_lock = input::CursorLockGuard::new(true);
+    ambient_api::messages::Frame::subscribe(move |_| {
+        let input = input::get();
+        if !cursor_lock.auto_unlock_on_escape(&input) {
+            return;
+        }
+
+        let mut displace = Vec2::ZERO;
+        if input.keys.contains(&KeyCode::W) {
+            displace.y -= 1.0;
+        }
+        if input.keys.contains(&KeyCode::S) {
+            displace.y += 1.0;
+        }
+        if input.keys.contains(&KeyCode::A) {
+            displace.x -= 1.0;
+        }
+        if input.keys.contains(&KeyCode::D) {
+            displace.x += 1.0;
+        }
+
+        messages::Input::new(displace, input.mouse_delta).send_server_unreliable();
+    });
+}
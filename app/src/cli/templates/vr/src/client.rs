@@ -0,0 +1,73 @@
+use ambient_api::{
+    components::core::{
+        player::player,
+        primitives::cube,
+        rendering::color,
+        transform::{rotation, scale, translation},
+        xr::{head_pose, left_hand_pose, right_hand_pose},
+    },
+    concepts::{make_sphere, make_transformable},
+    prelude::*,
+};
+
+use components::{head_of, left_hand_of, right_hand_of};
+
+#[main]
+pub fn main() {
+    // Every player gets a head and two hands, which follow the poses of their headset and controllers.
+    // Players that are not in XR have no poses, so their avatar stays hidden below the ground.
+    spawn_query(player()).bind(move |players| {
+        for (id, _) in players {
+            Entity::new()
+                .with_merge(make_transformable())
+                .with_default(cube())
+                .with(scale(), vec3(0.2, 0.25, 0.25))
+                .with(translation(), -Vec3::Z)
+                .with(head_of(), id)
+                .spawn();
+            for hand in [left_hand_of(), right_hand_of()] {
+                Entity::new()
+                    .with_merge(make_transformable())
+                    .with_merge(make_sphere())
+                    .with(scale(), Vec3::ONE * 0.1)
+                    .with(color(), vec4(0.9, 0.7, 0.5, 1.))
+                    .with(translation(), -Vec3::Z)
+                    .with(hand, id)
+                    .spawn();
+            }
+        }
+    });
+
+    despawn_query(player()).bind(move |players| {
+        for (id, _) in players {
+            for part in [head_of(), left_hand_of(), right_hand_of()] {
+                for (part_id, _) in query(part)
+                    .build()
+                    .evaluate()
+                    .into_iter()
+                    .filter(|(_, owner)| *owner == id)
+                {
+                    entity::despawn(part_id);
+                }
+            }
+        }
+    });
+
+    follow_pose(head_of(), head_pose());
+    follow_pose(left_hand_of(), left_hand_pose());
+    follow_pose(right_hand_of(), right_hand_pose());
+}
+
+/// Moves the avatar parts with the `part` component to the `pose` of their player, every frame
+fn follow_pose(part: Component<EntityId>, pose: Component<Mat4>) {
+    query(part).each_frame(move |parts| {
+        for (id, owner) in parts {
+            let Some(pose) = entity::get_component(owner, pose) else {
+                continue;
+            };
+            let (_, rot, pos) = pose.to_scale_rotation_translation();
+            entity::set_component(id, translation(), pos);
+            entity::set_component(id, rotation(), rot);
+        }
+    });
+}
@@ -0,0 +1,210 @@
+//! Discord integration, with the `discord` feature and a `--discord-app-id`. While the Discord client runs, it shows
+//! what the player is doing, from the `rich_presence` and `rich_presence_party_size` of the client modules, and lets
+//! their friends ask to join them; the join links of the activities the user joins are handed to the client, which
+//! switches over to their servers.
+
+use std::{num::NonZeroU32, time::SystemTime};
+
+use ambient_core::{project_name, runtime};
+use ambient_ecs::{
+    generated::components::core::player::{
+        rich_presence, rich_presence_party_max, rich_presence_party_size,
+    },
+    World,
+};
+use ambient_element::{element_component, Element, ElementComponentExt, Hooks};
+use ambient_std::{cb, Cb};
+use ambient_ui_native::Alert;
+use discord_sdk::{
+    activity::{events::ActivityEvent, ActivityBuilder, JoinRequestReply, PartyPrivacy, Secrets},
+    user::UserId,
+    wheel::Wheel,
+    DiscordApp, Subscriptions,
+};
+use once_cell::sync::OnceCell;
+
+static DISCORD: OnceCell<Discord> = OnceCell::new();
+
+pub struct Discord {
+    discord: discord_sdk::Discord,
+    /// The join links of the activities the user joined
+    joins: flume::Receiver<String>,
+    /// The users that asked to join the activity of the user
+    join_requests: flume::Receiver<(UserId, String)>,
+}
+
+/// Connects to the Discord client as the Discord application `app_id`
+pub fn init(app_id: i64) {
+    let (wheel, handler) = Wheel::new(Box::new(|err| {
+        log::warn!("Discord error: {err}");
+    }));
+    let discord = match discord_sdk::Discord::new(
+        DiscordApp::PlainId(app_id),
+        Subscriptions::ACTIVITY,
+        Box::new(handler),
+    ) {
+        Ok(discord) => discord,
+        Err(err) => {
+            log::warn!("Not using Discord: {err}");
+            return;
+        }
+    };
+
+    let (join_tx, joins) = flume::unbounded();
+    let (join_request_tx, join_requests) = flume::unbounded();
+    let mut events = wheel.activity().0;
+    tokio::spawn(async move {
+        // The wheel stops sending events when it's dropped
+        let _wheel = wheel;
+        while let Ok(event) = events.recv().await {
+            match event {
+                ActivityEvent::Join(join) => {
+                    join_tx.send(join.secret).ok();
+                }
+                ActivityEvent::JoinRequest(request) => {
+                    join_request_tx
+                        .send((request.user.id, request.user.username))
+                        .ok();
+                }
+                _ => {}
+            }
+        }
+    });
+
+    DISCORD
+        .set(Discord {
+            discord,
+            joins,
+            join_requests,
+        })
+        .ok();
+}
+
+fn get() -> Option<&'static Discord> {
+    DISCORD.get()
+}
+
+/// The join links of the activities that the user joined since the last call
+pub fn received_join_links() -> Vec<String> {
+    get().map_or_else(Vec::new, |discord| discord.joins.try_iter().collect())
+}
+
+/// What the game world shows as the activity of the player
+#[derive(Debug, Clone, PartialEq)]
+struct Activity {
+    project_name: Option<String>,
+    status: Option<String>,
+    party_size: Option<u32>,
+    party_max: Option<u32>,
+}
+impl Activity {
+    fn from_world(world: &World) -> Self {
+        Self {
+            project_name: world.resource_opt(project_name()).cloned(),
+            status: world.resource_opt(rich_presence()).cloned(),
+            party_size: world.resource_opt(rich_presence_party_size()).copied(),
+            party_max: world.resource_opt(rich_presence_party_max()).copied(),
+        }
+    }
+}
+
+/// Shows the activity of the player in the game world on Discord, and lets their friends join its server with
+/// `join_link`
+#[element_component]
+pub fn DiscordPresence(hooks: &mut Hooks, join_link: String) -> Element {
+    let Some(discord) = get() else {
+        return Element::new();
+    };
+    let (started, _) = hooks.use_state_with(|_| SystemTime::now());
+    let (activity, set_activity) = hooks.use_state(None);
+    hooks.use_frame(move |world| {
+        let current = Activity::from_world(world);
+        if activity.as_ref() == Some(&current) {
+            return;
+        }
+
+        let mut builder = ActivityBuilder::default().start_timestamp(started);
+        if let Some(project_name) = &current.project_name {
+            builder = builder.details(project_name.clone());
+        }
+        if let Some(status) = &current.status {
+            builder = builder.state(status.clone());
+        }
+        // Friends can only ask to join a party
+        if let Some(size) = current.party_size {
+            builder = builder
+                .party(
+                    join_link.clone(),
+                    NonZeroU32::new(size),
+                    current.party_max.and_then(NonZeroU32::new),
+                    PartyPrivacy::Private,
+                )
+                .secrets(Secrets {
+                    join: Some(join_link.clone()),
+                    ..Default::default()
+                });
+        }
+        world.resource(runtime()).spawn(async move {
+            if let Err(err) = discord.discord.update_activity(builder).await {
+                log::warn!("Failed to update the Discord activity: {err}");
+            }
+        });
+        set_activity(Some(current));
+    });
+    hooks.use_spawn(move |world| {
+        let runtime = world.resource(runtime()).clone();
+        move |_| {
+            runtime.spawn(async move {
+                discord.discord.clear_activity().await.ok();
+            });
+        }
+    });
+    Element::new()
+}
+
+/// Asks the user whether the friends that ask to join them on Discord may, one at a time
+#[element_component]
+pub fn DiscordJoinRequests(hooks: &mut Hooks) -> Element {
+    let Some(discord) = get() else {
+        return Element::new();
+    };
+    let (request, set_request) = hooks.use_state(None);
+    {
+        let set_request = set_request.clone();
+        let pending = request.is_some();
+        hooks.use_frame(move |_| {
+            if !pending {
+                if let Ok(request) = discord.join_requests.try_recv() {
+                    set_request(Some(request));
+                }
+            }
+        });
+    }
+
+    let Some((user, name)) = request else {
+        return Element::new();
+    };
+    let reply = move |accept: bool| -> Cb<dyn Fn(&mut World) + Sync + Send> {
+        let set_request = set_request.clone();
+        cb(move |world| {
+            let reply = if accept {
+                JoinRequestReply::Yes
+            } else {
+                JoinRequestReply::No
+            };
+            world.resource(runtime()).spawn(async move {
+                if let Err(err) = discord.discord.send_join_request_reply(user, reply).await {
+                    log::warn!("Failed to reply to the Discord join request: {err}");
+                }
+            });
+            set_request(None);
+        })
+    };
+    Alert::new(
+        format!("{name} wants to join you. Let them?"),
+        cb(|_: Option<Element>| {}),
+        Some(reply(true)),
+        Some(reply(false)),
+    )
+    .el()
+}
@@ -2,37 +2,85 @@ use std::{
     collections::HashMap, net::SocketAddr, path::PathBuf, process::exit, sync::Arc, time::Duration,
 };
 
-use ambient_app::{fps_stats, window_title, AppBuilder};
+use ambient_accessibility::{accessibility_tree_update, accessibility_view_offset};
+use ambient_app::{fps_stats, golden, window_title, AppBuilder};
+use ambient_behavior_tree::BehaviorTreeDebug;
 use ambient_cameras::UICamera;
+use ambient_captions::Captions;
 use ambient_core::{
     runtime,
+    transform::translation,
     window::{
         cursor_position, window_ctl, window_logical_size, window_physical_size,
         window_scale_factor, WindowCtl,
     },
 };
 use ambient_debugger::Debugger;
-use ambient_ecs::{Entity, EntityId, SystemGroup};
+use ambient_ecs::{Entity, EntityId, SystemGroup, World};
 use ambient_element::{element_component, Element, ElementComponentExt, Hooks};
+use ambient_minimap::Minimap;
 use ambient_network::{
+    auth::AuthTicket,
     client::{client_network_stats, GameClient, GameClientRenderTarget, GameClientWorld},
     hooks::use_remote_resource,
     native::client::GameClientView,
 };
-use ambient_std::{asset_cache::AssetCache, cb, friendly_id};
+use ambient_std::{asset_cache::AssetCache, cb, friendly_id, Cb};
 use ambient_ui_native::{
-    Button, Dock, FlowColumn, FocusRoot, MeasureSize, ScrollArea, ScrollAreaSizing, StylesExt,
-    Text, UIExt, WindowSized, STREET,
+    Alert, Button, Dock, FlowColumn, FocusRoot, ImageFromUrl, MeasureSize, ScrollArea,
+    ScrollAreaSizing, StylesExt, Text, UIExt, WindowSized, STREET,
 };
-use glam::{uvec2, vec4, Vec2};
+use glam::{uvec2, vec2, vec4, Vec2};
 
 use crate::{cli::RunCli, shared};
 use ambient_ecs_editor::{ECSEditor, InspectableAsyncWorld};
-use ambient_layout::{docking, padding, Borders};
+use ambient_input::{
+    cursor::{cursor_image_hotspot, cursor_image_size, cursor_image_url},
+    touch_ids, touch_positions,
+};
+use ambient_layout::{docking, height, padding, width, Borders};
+use ambient_wasm::shared::clipboard;
 
+#[cfg(feature = "discord")]
+mod discord;
 pub mod player;
+mod spectator;
+#[cfg(feature = "steam")]
+mod steam;
 mod wasm;
 
+/// The prefix of links that join a server, like `ambient://join/203.0.113.1:9000`, which Steam invites carry
+pub const JOIN_LINK_PREFIX: &str = "ambient://join/";
+
+/// The host that a join link joins, if `link` is one
+pub fn parse_join_link(link: &str) -> Option<&str> {
+    link.strip_prefix(JOIN_LINK_PREFIX)
+        .map(|host| host.trim_end_matches('/'))
+        .filter(|host| !host.is_empty())
+}
+
+/// The link that joins the server at `server_addr`, for the friends of the user
+#[cfg(any(feature = "steam", feature = "discord"))]
+fn join_link(server_addr: SocketAddr) -> String {
+    // A server on this computer is reached by the others on the local network
+    let address = match local_ip_address::local_ip() {
+        Ok(ip) if server_addr.ip().is_loopback() => SocketAddr::new(ip, server_addr.port()),
+        _ => server_addr,
+    };
+    format!("{JOIN_LINK_PREFIX}{address}")
+}
+
+/// The join links that the user accepted on the platforms the client integrates with since the last call, e.g. by
+/// accepting an invite
+fn received_join_links() -> impl Iterator<Item = String> {
+    let links = std::iter::empty();
+    #[cfg(feature = "steam")]
+    let links = links.chain(steam::received_join_links());
+    #[cfg(feature = "discord")]
+    let links = links.chain(discord::received_join_links());
+    links
+}
+
 /// Construct an app and enter the main client view
 pub async fn run(
     assets: AssetCache,
@@ -40,10 +88,25 @@ pub async fn run(
     run: &RunCli,
     golden_image_output_dir: Option<PathBuf>,
 ) {
-    let user_id = run
-        .user_id
-        .clone()
-        .unwrap_or_else(|| format!("user_{}", friendly_id()));
+    #[cfg(feature = "discord")]
+    if let Some(app_id) = run.discord_app_id {
+        discord::init(app_id);
+    }
+    // Steam users join as themselves, with a ticket that proves it
+    #[cfg(feature = "steam")]
+    let steam_user = steam::init().map(|steam| (steam.user_id(), steam.auth_ticket()));
+    #[cfg(not(feature = "steam"))]
+    let steam_user: Option<(String, AuthTicket)> = None;
+    let (user_id, auth_ticket) = match (run.user_id.clone(), steam_user) {
+        (None, Some((user_id, ticket))) => (user_id, Some(ticket)),
+        (Some(user_id), Some((steam_user_id, ticket))) if user_id == steam_user_id => {
+            (user_id, Some(ticket))
+        }
+        (user_id, _) => (
+            user_id.unwrap_or_else(|| format!("user_{}", friendly_id())),
+            None,
+        ),
+    };
     let headless = if run.headless {
         Some(uvec2(600, 600))
     } else {
@@ -81,6 +144,8 @@ pub async fn run(
             MainApp {
                 server_addr,
                 user_id,
+                auth_ticket,
+                spectator: run.spectate,
                 show_debug: is_debug,
                 golden_image_test: run.golden_image_test,
                 golden_image_output_dir,
@@ -123,11 +188,23 @@ fn MainApp(
     server_addr: SocketAddr,
     golden_image_output_dir: Option<PathBuf>,
     user_id: String,
+    auth_ticket: Option<AuthTicket>,
+    spectator: bool,
     show_debug: bool,
     golden_image_test: Option<f32>,
     cert: Option<Vec<u8>>,
 ) -> Element {
     let (loaded, set_loaded) = hooks.use_state(false);
+    // Accepting an invite to another server switches over to it
+    let (server_addr, set_server_addr) = hooks.use_state(server_addr);
+    hooks.use_frame(move |_| {
+        for link in received_join_links() {
+            match parse_join_link(&link).and_then(|host| host.parse().ok()) {
+                Some(address) => set_server_addr(address),
+                None => log::warn!("Invalid join link: {link:?}"),
+            }
+        }
+    });
 
     FocusRoot::el([
         UICamera.el(),
@@ -135,13 +212,39 @@ fn MainApp(
         WindowSized::el([GameClientView {
             server_addr,
             user_id,
+            auth_ticket,
+            spectator,
             on_loaded: cb(move |client| {
                 let mut game_state = client.game_state.lock();
                 let world = &mut game_state.world;
 
-                wasm::initialize(world).unwrap();
+                wasm::initialize(world);
 
                 UICamera.el().spawn_static(world);
+                let captions_resources = ambient_captions::client_resources(world);
+                world
+                    .add_components(world.resource_entity(), captions_resources)
+                    .unwrap();
+                let localization_resources = ambient_localization::client_resources(world);
+                world
+                    .add_components(world.resource_entity(), localization_resources)
+                    .unwrap();
+                Captions.el().spawn_interactive(world);
+                Minimap.el().spawn_interactive(world);
+                BehaviorTreeDebug.el().spawn_interactive(world);
+                ClipboardPermissionPrompt.el().spawn_interactive(world);
+                CustomCursor.el().spawn_interactive(world);
+                #[cfg(feature = "steam")]
+                {
+                    let join_link = join_link(server_addr);
+                    steam::SteamPresence::el(join_link.clone()).spawn_interactive(world);
+                    steam::SteamFriends::el(join_link).spawn_interactive(world);
+                }
+                #[cfg(feature = "discord")]
+                {
+                    discord::DiscordPresence::el(join_link(server_addr)).spawn_interactive(world);
+                    discord::DiscordJoinRequests.el().spawn_interactive(world);
+                }
                 set_loaded(true);
 
                 Ok(Box::new(|| {
@@ -166,10 +269,15 @@ fn MainApp(
                     unistream_handlers,
                 );
 
-                let dgram_handlers = HashMap::new();
+                let mut dgram_handlers = HashMap::new();
+                ambient_network::ownership::register_client_datagram_handler(&mut dgram_handlers);
+                ambient_network::pose_sync::register_client_datagram_handler(&mut dgram_handlers);
+                ambient_surface_effects::register_client_datagram_handler(&mut dgram_handlers);
                 resources.set(ambient_network::client::datagram_handlers(), dgram_handlers);
+                resources.merge(ambient_tweakables::resources());
+                resources.merge(ambient_accessibility::resources());
 
-                (systems(), resources)
+                (systems(), wasm::render_systems(), resources)
             }),
             cert,
             create_rpc_registry: cb(shared::create_server_rpc_registry),
@@ -184,10 +292,74 @@ fn MainApp(
                 GameView { show_debug }.el(),
             ]),
         }
-        .el()]),
+        .el()
+        .key(server_addr.to_string())]),
     ])
 }
 
+/// Asks the user whether a client module may access the clipboard, the first time it tries to.
+#[element_component]
+fn ClipboardPermissionPrompt(hooks: &mut Hooks) -> Element {
+    let (module, set_module) = hooks.use_state(None);
+    hooks.use_frame(move |world| {
+        let pending = clipboard::pending_permission(world);
+        if pending != module {
+            set_module(pending);
+        }
+    });
+
+    let Some(module) = module else {
+        return Element::new();
+    };
+    let name = hooks
+        .world
+        .get_cloned(module, ambient_core::name())
+        .unwrap_or_default();
+    let allow: Cb<dyn Fn(&mut World) + Sync + Send> =
+        cb(move |world| clipboard::set_permission(world, module, true));
+    let deny: Cb<dyn Fn(&mut World) + Sync + Send> =
+        cb(move |world| clipboard::set_permission(world, module, false));
+    Alert::new(
+        format!("Allow the module {name} to access the clipboard?"),
+        cb(|_: Option<Element>| {}),
+        Some(allow),
+        Some(deny),
+    )
+    .el()
+}
+
+/// Draws the `cursor_image_url` of the client modules in place of the system cursor, which is hidden meanwhile.
+#[element_component]
+fn CustomCursor(hooks: &mut Hooks) -> Element {
+    let (cursor, set_cursor) = hooks.use_state(None);
+    hooks.use_frame(move |world| {
+        let current = world.resource_opt(cursor_image_url()).cloned().map(|url| {
+            let size = world
+                .resource_opt(cursor_image_size())
+                .copied()
+                .unwrap_or(Vec2::splat(32.));
+            let hotspot = world
+                .resource_opt(cursor_image_hotspot())
+                .copied()
+                .unwrap_or_default();
+            (url, size, *world.resource(cursor_position()) - hotspot)
+        });
+        if current != cursor {
+            set_cursor(current);
+        }
+    });
+
+    let Some((url, size, position)) = cursor else {
+        return Element::new();
+    };
+    ImageFromUrl { url }
+        .el()
+        .with(width(), size.x)
+        .with(height(), size.y)
+        // In front of the rest of the UI
+        .with(translation(), position.extend(-0.99))
+}
+
 #[element_component]
 fn GoldenImageTest(
     hooks: &mut Hooks,
@@ -202,7 +374,7 @@ fn GoldenImageTest(
         .join("screenshot.png");
     let (old_screnshot, _) = hooks.use_state_with(|_| {
         tracing::info!("Loading screenshot from {:?}", screenshot_path);
-        Some(Arc::new(image::open(&screenshot_path).ok()?))
+        Some(Arc::new(image::open(&screenshot_path).ok()?.into_rgba8()))
     });
 
     let rt = hooks.world.resource(runtime()).clone();
@@ -227,12 +399,8 @@ fn GoldenImageTest(
                             .unwrap()
                             .into_rgba8();
 
-                        let hasher = image_hasher::HasherConfig::new().to_hasher();
-
-                        let hash1 = hasher.hash_image(&new);
-                        let hash2 = hasher.hash_image(&*old);
-                        let dist = hash1.dist(&hash2);
-                        if dist <= 2 {
+                        let dist = golden::image_distance(&new, &old);
+                        if dist <= golden::DEFAULT_MAX_DISTANCE {
                             tracing::info!("Screenshots are identical, exiting");
                             exit(0);
                         } else {
@@ -285,6 +453,23 @@ fn GameView(hooks: &mut Hooks, show_debug: bool) -> Element {
                 .world
                 .set_if_changed(EntityId::resources(), cursor_position(), mouse_pos)
                 .unwrap();
+            state
+                .world
+                .set_if_changed(
+                    EntityId::resources(),
+                    touch_ids(),
+                    world.resource(touch_ids()).clone(),
+                )
+                .unwrap();
+            let touches = world
+                .resource(touch_positions())
+                .iter()
+                .map(|position| *position - vec2(ecs_size.x, 0.))
+                .collect();
+            state
+                .world
+                .set_if_changed(EntityId::resources(), touch_positions(), touches)
+                .unwrap();
             let size = uvec2(
                 render_target.0.color_buffer.size.width,
                 render_target.0.color_buffer.size.height,
@@ -305,6 +490,27 @@ fn GameView(hooks: &mut Hooks, show_debug: bool) -> Element {
                 .world
                 .set_if_changed(EntityId::resources(), window_scale_factor(), scale_factor)
                 .unwrap();
+
+            // The screen reader is given the tree of the game's UI, which is drawn next to the ECS editor
+            state
+                .world
+                .set_if_changed(
+                    EntityId::resources(),
+                    accessibility_view_offset(),
+                    vec2(ecs_size.x, 0.),
+                )
+                .unwrap();
+            // The updates build on each other, so the next one waits until the screen reader has been handed the
+            // last one
+            let handed_over = world
+                .resource_opt(accessibility_tree_update())
+                .map_or(true, Option::is_none);
+            if handed_over {
+                let update = state.world.resource_mut(accessibility_tree_update()).take();
+                if let Some(update) = update {
+                    world.add_resource(accessibility_tree_update(), Some(update));
+                }
+            }
         }
     });
 
@@ -391,8 +597,22 @@ fn systems() -> SystemGroup {
             Box::new(ambient_primitives::systems()),
             Box::new(ambient_sky::systems()),
             Box::new(ambient_water::systems()),
+            Box::new(ambient_captions::systems()),
+            Box::new(ambient_captions::client_systems()),
+            Box::new(ambient_accessibility::client_systems()),
+            Box::new(ambient_localization::client_systems()),
+            Box::new(ambient_minimap::client_systems()),
+            Box::new(ambient_sequencer::client_systems()),
+            Box::new(ambient_voxel::client_systems()),
             Box::new(ambient_physics::client_systems()),
+            Box::new(ambient_input::cursor::client_systems()),
+            Box::new(ambient_save::systems("client")),
+            Box::new(ambient_tweakables::systems("client")),
             Box::new(wasm::systems()),
+            Box::new(ambient_surface_effects::client_systems()),
+            Box::new(ambient_network::ownership::client_systems()),
+            Box::new(ambient_network::pose_sync::client_systems()),
+            Box::new(spectator::systems()),
             Box::new(player::systems_final()),
         ],
     )
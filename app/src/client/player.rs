@@ -3,7 +3,7 @@ use std::str::FromStr;
 use ambient_core::window::cursor_position;
 use ambient_ecs::{generated::messages, query_mut, SystemGroup, World};
 use ambient_element::{element_component, Element, Hooks};
-use ambient_input::{player_prev_raw_input, player_raw_input, PlayerRawInput};
+use ambient_input::{player_prev_raw_input, player_raw_input};
 use ambient_network::client::game_client;
 use ambient_shared_types::VirtualKeyCode;
 
@@ -29,7 +29,7 @@ pub fn PlayerRawInputHandler(hooks: &mut Hooks) -> Element {
         set_has_focus(event.focused);
     });
 
-    fn process_input(ui_world: &World, has_focus: bool, processor: impl Fn(&mut PlayerRawInput)) {
+    fn process_input(ui_world: &World, has_focus: bool, processor: impl Fn(&mut World)) {
         if !has_focus {
             return;
         }
@@ -37,14 +37,16 @@ pub fn PlayerRawInputHandler(hooks: &mut Hooks) -> Element {
         let Some(Some(gc)) = ui_world.resource_opt(game_client()).cloned() else {
             return;
         };
-        gc.with_physics_world(|w| {
-            let input = w.resource_mut(player_raw_input());
-            processor(input);
-        });
+        gc.with_physics_world(processor);
     }
 
     hooks.use_runtime_message::<messages::WindowKeyboardInput>(move |world, event| {
-        process_input(world, has_focus, |input| {
+        process_input(world, has_focus, |game_world| {
+            // The keys that the keyboard navigation of the UI uses don't reach the game
+            if ambient_accessibility::uses_keyboard_input(game_world, event) {
+                return;
+            }
+            let input = game_world.resource_mut(player_raw_input());
             if let Some(keycode) = event.keycode.as_deref() {
                 let keycode = VirtualKeyCode::from_str(keycode).unwrap();
                 if event.pressed {
@@ -57,7 +59,8 @@ pub fn PlayerRawInputHandler(hooks: &mut Hooks) -> Element {
     });
 
     hooks.use_runtime_message::<messages::WindowMouseInput>(move |world, event| {
-        process_input(world, has_focus, |input| {
+        process_input(world, has_focus, |game_world| {
+            let input = game_world.resource_mut(player_raw_input());
             if event.pressed {
                 input.mouse_buttons.insert(event.button.into());
             } else {
@@ -67,14 +70,16 @@ pub fn PlayerRawInputHandler(hooks: &mut Hooks) -> Element {
     });
 
     hooks.use_runtime_message::<messages::WindowMouseMotion>(move |world, msg| {
-        process_input(world, has_focus, |input| {
+        process_input(world, has_focus, |game_world| {
+            let input = game_world.resource_mut(player_raw_input());
             input.mouse_position = *world.resource(cursor_position());
             input.mouse_delta += msg.delta;
         });
     });
 
     hooks.use_runtime_message::<messages::WindowMouseWheel>(move |world, event| {
-        process_input(world, has_focus, |input| {
+        process_input(world, has_focus, |game_world| {
+            let input = game_world.resource_mut(player_raw_input());
             let delta = event.delta;
             input.mouse_wheel += match event.pixels {
                 false => delta.y * PIXELS_PER_LINE,
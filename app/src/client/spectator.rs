@@ -0,0 +1,181 @@
+use ambient_core::{
+    camera::{
+        active_camera, aspect_ratio, aspect_ratio_from_window, fovy, near,
+        perspective_infinite_reverse, projection, projection_view,
+    },
+    dtime, main_scene,
+    player::{get_spectator_by_user_id, local_user_id, player, user_id},
+    transform::{inv_local_to_world, local_to_world, lookat_target, lookat_up, translation},
+};
+use ambient_ecs::{query, Entity, EntityId, FnSystem, SystemGroup, World};
+use ambient_input::{player_prev_raw_input, player_raw_input};
+use ambient_shared_types::VirtualKeyCode;
+use glam::{vec3, Vec3};
+
+/// Units per second
+const FLY_SPEED: f32 = 10.;
+/// Radians per pixel
+const LOOK_SPEED: f32 = 0.005;
+const FOLLOW_DISTANCE: f32 = 8.;
+/// Above the default, so that it is used instead of the global cameras of the project. Modules can still override it
+/// with a camera with a higher value for the spectator.
+const CAMERA_PRIORITY: f32 = 1.;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Free,
+    Follow(EntityId),
+}
+
+struct SpectatorCamera {
+    id: EntityId,
+    mode: Mode,
+    yaw: f32,
+    pitch: f32,
+}
+impl SpectatorCamera {
+    fn spawn(world: &mut World, local_user_id: String) -> Self {
+        let position = vec3(5., 5., 5.);
+        let (yaw, pitch) = yaw_pitch(-position);
+        let id = Entity::new()
+            .with_default(local_to_world())
+            .with_default(inv_local_to_world())
+            .with(near(), 0.1)
+            .with(fovy(), 1.0)
+            .with(perspective_infinite_reverse(), ())
+            .with(aspect_ratio(), 1.)
+            .with(aspect_ratio_from_window(), EntityId::resources())
+            .with_default(projection())
+            .with_default(projection_view())
+            .with_default(main_scene())
+            .with(active_camera(), CAMERA_PRIORITY)
+            .with(user_id(), local_user_id)
+            .with(translation(), position)
+            .with(lookat_target(), Vec3::ZERO)
+            .with(lookat_up(), Vec3::Z)
+            .spawn(world);
+        Self {
+            id,
+            mode: Mode::Free,
+            yaw,
+            pitch,
+        }
+    }
+
+    fn forward(&self) -> Vec3 {
+        vec3(
+            self.pitch.cos() * self.yaw.cos(),
+            self.pitch.cos() * self.yaw.sin(),
+            self.pitch.sin(),
+        )
+    }
+
+    /// The player to follow after the current one, in the order of their entity IDs
+    fn next_player(&self, world: &World) -> Option<EntityId> {
+        let mut players = query(())
+            .incl(player())
+            .incl(translation())
+            .iter(world, None)
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        players.sort();
+        match self.mode {
+            Mode::Follow(current) => players
+                .iter()
+                .find(|&&id| id > current)
+                .or(players.first())
+                .copied(),
+            Mode::Free => players.first().copied(),
+        }
+    }
+
+    fn update(&mut self, world: &mut World) {
+        let input = world.resource(player_raw_input()).clone();
+        let prev_input = world.resource(player_prev_raw_input());
+        let just_pressed =
+            |key: VirtualKeyCode| input.keys.contains(&key) && !prev_input.keys.contains(&key);
+
+        if just_pressed(VirtualKeyCode::Tab) {
+            if let Some(id) = self.next_player(world) {
+                self.mode = Mode::Follow(id);
+            }
+        }
+        if just_pressed(VirtualKeyCode::F) {
+            self.mode = Mode::Free;
+        }
+
+        self.yaw -= input.mouse_delta.x * LOOK_SPEED;
+        self.pitch = (self.pitch - input.mouse_delta.y * LOOK_SPEED).clamp(-1.5, 1.5);
+
+        let Ok(position) = world.get(self.id, translation()) else {
+            return;
+        };
+        let (position, target) = match self.mode {
+            Mode::Follow(id) => match world.get(id, translation()) {
+                Ok(target) => (target - self.forward() * FOLLOW_DISTANCE, target),
+                // The followed player left, so keep looking from where the camera is
+                Err(_) => {
+                    self.mode = Mode::Free;
+                    (position, position + self.forward())
+                }
+            },
+            Mode::Free => {
+                let forward = self.forward();
+                let right = forward.cross(Vec3::Z).normalize_or_zero();
+                let mut velocity = Vec3::ZERO;
+                for (key, direction) in [
+                    (VirtualKeyCode::W, forward),
+                    (VirtualKeyCode::S, -forward),
+                    (VirtualKeyCode::D, right),
+                    (VirtualKeyCode::A, -right),
+                    (VirtualKeyCode::E, Vec3::Z),
+                    (VirtualKeyCode::Q, -Vec3::Z),
+                ] {
+                    if input.keys.contains(&key) {
+                        velocity += direction;
+                    }
+                }
+                let position =
+                    position + velocity.normalize_or_zero() * FLY_SPEED * *world.resource(dtime());
+                (position, position + forward)
+            }
+        };
+        world.set(self.id, translation(), position).ok();
+        world.set(self.id, lookat_target(), target).ok();
+    }
+}
+
+/// The yaw and pitch of a camera looking in `direction`
+fn yaw_pitch(direction: Vec3) -> (f32, f32) {
+    let direction = direction.normalize();
+    (direction.y.atan2(direction.x), direction.z.asin())
+}
+
+/// Is the local user a spectator?
+fn is_spectating(world: &World) -> bool {
+    world
+        .resource_opt(local_user_id())
+        .and_then(|user_id| get_spectator_by_user_id(world, user_id))
+        .is_some()
+}
+
+/// Spawns a camera for the local user when they joined as a spectator, which flies freely with WASD, QE and the mouse,
+/// follows the next player with Tab and goes back to flying freely with F.
+pub fn systems() -> SystemGroup {
+    let mut camera: Option<SpectatorCamera> = None;
+    SystemGroup::new(
+        "spectator/client_systems",
+        vec![Box::new(FnSystem::new(move |world, _| {
+            if !is_spectating(world) {
+                if let Some(camera) = camera.take() {
+                    world.despawn(camera.id);
+                }
+                return;
+            }
+            let local_user = world.resource(local_user_id()).clone();
+            camera
+                .get_or_insert_with(|| SpectatorCamera::spawn(world, local_user))
+                .update(world);
+        }))],
+    )
+}
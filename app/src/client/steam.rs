@@ -0,0 +1,200 @@
+//! Steam integration, with the `steam` feature. When the Steam client runs, the user joins servers as their Steam user
+//! with a session ticket, which servers that authenticate with Steam check; their friends can be invited to the server
+//! they are on, or join it from the Steam friends list; and the `rich_presence` of the client modules is shown to
+//! their friends.
+
+use std::{thread, time::Duration};
+
+use ambient_ecs::generated::components::core::player::{rich_presence, rich_presence_party_size};
+use ambient_element::{element_component, Element, ElementComponentExt, Hooks};
+use ambient_network::auth::{steam_user_id, AuthTicket};
+use ambient_shared_types::{ModifiersState, VirtualKeyCode};
+use ambient_ui_native::{
+    space_between_items, Button, ButtonStyle, FlowColumn, FlowRow, Text, UIExt, STREET,
+};
+use once_cell::sync::OnceCell;
+use steamworks::{
+    CallbackHandle, Client, FriendFlags, FriendState, GameRichPresenceJoinRequested, SteamId,
+};
+
+static STEAM: OnceCell<Steam> = OnceCell::new();
+
+/// The rich presence key of the status that is shown to the friends of the user
+const STATUS_KEY: &str = "status";
+/// The rich presence key of the join link of the server the user is on, which lets their friends join it
+const CONNECT_KEY: &str = "connect";
+/// The rich presence keys that group the friends of the user that play together
+const PLAYER_GROUP_KEY: &str = "steam_player_group";
+const PLAYER_GROUP_SIZE_KEY: &str = "steam_player_group_size";
+
+pub struct Steam {
+    client: Client,
+    /// The session ticket of the user, which is requested early as it takes Steam a moment to validate it
+    ticket: Vec<u8>,
+    /// The join links of the invites the user accepted, or the friends they joined, while already playing
+    join_requests: flume::Receiver<String>,
+    _join_requested: CallbackHandle,
+}
+impl Steam {
+    pub fn user_id(&self) -> String {
+        steam_user_id(self.client.user().steam_id().raw())
+    }
+
+    pub fn auth_ticket(&self) -> AuthTicket {
+        AuthTicket::Steam(self.ticket.clone())
+    }
+
+    /// The friends of the user that are online, by their Steam id
+    fn online_friends(&self) -> Vec<(u64, String)> {
+        let mut friends = self
+            .client
+            .friends()
+            .get_friends(FriendFlags::IMMEDIATE)
+            .into_iter()
+            .filter(|friend| friend.state() != FriendState::Offline)
+            .map(|friend| (friend.id().raw(), friend.name()))
+            .collect::<Vec<_>>();
+        friends.sort_by(|(_, a), (_, b)| a.to_lowercase().cmp(&b.to_lowercase()));
+        friends
+    }
+
+    fn invite(&self, friend: u64, join_link: &str) {
+        self.client
+            .friends()
+            .get_friend(SteamId::from_raw(friend))
+            .invite_user_to_game(join_link);
+    }
+
+    fn set_rich_presence(&self, key: &str, value: Option<&str>) {
+        if !self.client.friends().set_rich_presence(key, value) {
+            log::warn!("Steam refused the rich presence {key:?}: {value:?}");
+        }
+    }
+}
+
+/// Connects to the Steam client, if it runs
+pub fn init() -> Option<&'static Steam> {
+    STEAM
+        .get_or_try_init(|| {
+            let (client_tx, client_rx) = flume::bounded(1);
+            // The callbacks of the Steam API have to be run on the thread that initialized it
+            thread::Builder::new()
+                .name("steam".to_string())
+                .spawn(move || {
+                    let (client, single) = match Client::init() {
+                        Ok(client) => client,
+                        Err(err) => {
+                            client_tx.send(Err(err)).ok();
+                            return;
+                        }
+                    };
+                    client_tx.send(Ok(client)).ok();
+                    loop {
+                        single.run_callbacks();
+                        thread::sleep(Duration::from_millis(50));
+                    }
+                })?;
+            let client = client_rx.recv()??;
+
+            let (_, ticket) = client.user().authentication_session_ticket();
+            let (join_tx, join_requests) = flume::unbounded();
+            let _join_requested =
+                client.register_callback(move |request: GameRichPresenceJoinRequested| {
+                    join_tx.send(request.connect).ok();
+                });
+            anyhow::Ok(Steam {
+                client,
+                ticket,
+                join_requests,
+                _join_requested,
+            })
+        })
+        .map_err(|err| log::warn!("Not using Steam: {err:#}"))
+        .ok()
+}
+
+pub fn get() -> Option<&'static Steam> {
+    STEAM.get()
+}
+
+/// The join links of the invites that the user accepted since the last call
+pub fn received_join_links() -> Vec<String> {
+    get().map_or_else(Vec::new, |steam| steam.join_requests.try_iter().collect())
+}
+
+/// Shows the `rich_presence` and `rich_presence_party_size` of the game world to the friends of the user, and lets
+/// them join its server with `join_link`
+#[element_component]
+pub fn SteamPresence(hooks: &mut Hooks, join_link: String) -> Element {
+    let Some(steam) = get() else {
+        return Element::new();
+    };
+    hooks.use_spawn({
+        let join_link = join_link.clone();
+        move |_| {
+            steam.set_rich_presence(CONNECT_KEY, Some(&join_link));
+            |_| steam.client.friends().clear_rich_presence()
+        }
+    });
+    let (status, set_status) = hooks.use_state(None);
+    let (party_size, set_party_size) = hooks.use_state(None);
+    hooks.use_frame(move |world| {
+        let current = world.resource_opt(rich_presence()).cloned();
+        if current != status {
+            steam.set_rich_presence(STATUS_KEY, current.as_deref());
+            set_status(current);
+        }
+        let current = world.resource_opt(rich_presence_party_size()).copied();
+        if current != party_size {
+            // The players on the same server are grouped together
+            let group = current.map(|_| join_link.as_str());
+            steam.set_rich_presence(PLAYER_GROUP_KEY, group);
+            steam.set_rich_presence(
+                PLAYER_GROUP_SIZE_KEY,
+                current.map(|size| size.to_string()).as_deref(),
+            );
+            set_party_size(current);
+        }
+    });
+    Element::new()
+}
+
+/// Lists the friends of the user that are online, to invite them to the server with `join_link`. Toggled with
+/// Shift+F9
+#[element_component]
+pub fn SteamFriends(hooks: &mut Hooks, join_link: String) -> Element {
+    let Some(steam) = get() else {
+        return Element::new();
+    };
+    let (open, set_open) = hooks.use_state(false);
+    let (friends, set_friends) = hooks.use_state(Vec::new());
+    hooks.use_interval_deps(Duration::from_secs(5), true, open, move |&open| {
+        if open {
+            set_friends(steam.online_friends());
+        }
+    });
+
+    FlowColumn::el([
+        Button::new("Invite friends", move |_| set_open(!open))
+            .hotkey_modifier(ModifiersState::SHIFT)
+            .hotkey(VirtualKeyCode::F12)
+            .style(ButtonStyle::Flat)
+            .toggled(open)
+            .el(),
+        if open {
+            FlowColumn::el(friends.into_iter().map(|(friend, name)| {
+                let join_link = join_link.clone();
+                FlowRow::el([
+                    Text::el(name),
+                    Button::new("Invite", move |_| steam.invite(friend, &join_link))
+                        .style(ButtonStyle::Flat)
+                        .el(),
+                ])
+                .with(space_between_items(), STREET)
+            }))
+        } else {
+            Element::new()
+        },
+    ])
+    .with_padding_even(STREET)
+}
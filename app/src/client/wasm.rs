@@ -1,16 +1,24 @@
-use ambient_audio::Source;
+use ambient_audio::{Source, MASTER_BUS, MUSIC_BUS, SFX_BUS, VOICE_BUS};
+use ambient_core::asset_cache;
 use ambient_ecs::{EntityId, SystemGroup, World};
+use ambient_gpu::settings::SettingsKey;
+use ambient_network::client_game_state::RenderEvent;
+use ambient_std::asset_cache::SyncAssetKeyExt;
 use ambient_wasm::shared::{get_module_name, MessageType};
-use ambient_world_audio::{audio_sender, AudioMessage, SoundInfo};
+use ambient_world_audio::{audio_mixer, audio_sender, user_bus_volumes, AudioMessage, SoundInfo};
 use flume::{Receiver, Sender};
 use parking_lot::Mutex;
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 pub fn systems() -> SystemGroup {
     ambient_wasm::client::systems()
 }
 
-pub fn initialize(world: &mut World) -> anyhow::Result<()> {
+pub fn render_systems() -> SystemGroup<RenderEvent> {
+    ambient_wasm::client::render_systems()
+}
+
+pub fn initialize(world: &mut World) {
     let messenger = Arc::new(|world: &World, id: EntityId, type_: MessageType, message: &str| {
         let name = get_module_name(world, id);
         let (prefix, level) = match type_ {
@@ -22,26 +30,35 @@ pub fn initialize(world: &mut World) -> anyhow::Result<()> {
         };
 
         log::log!(level, "[{name}] {prefix}: {}", message.strip_suffix('\n').unwrap_or(message));
+        ambient_wasm::client::record_module_log(world, id, type_, message);
     });
 
     let (tx, rx): (Sender<AudioMessage>, Receiver<AudioMessage>) = flume::unbounded();
+    let (mixer_tx, mixer_rx) = flume::bounded(1);
 
     std::thread::spawn(move || {
-        let stream = ambient_audio::AudioStream::new().unwrap();
+        let stream = match ambient_audio::AudioStream::new() {
+            Ok(stream) => stream,
+            Err(err) => {
+                log::error!("Failed to open the audio stream: {err}");
+                return;
+            }
+        };
+        mixer_tx.send(stream.mixer().clone()).ok();
         let mut sound_info_lib = std::collections::HashMap::new();
         while let Ok(message) = rx.recv() {
             match message {
-                AudioMessage::Spatial(source) => {
-                    let sound = stream.mixer().play(source);
+                AudioMessage::Spatial(source, bus) => {
+                    let sound = stream.mixer().play_on_bus(&bus, source);
                     sound.wait();
                 },
-                AudioMessage::Track(t, looping, amp, url, uid) => {
+                AudioMessage::Track(t, looping, amp, url, uid, bus) => {
                     let gain = Arc::new(Mutex::new(amp));
                     let gain_clone = gain.clone();
 
                     let sound = match looping {
-                        true => stream.mixer().play(t.decode().repeat().gain(gain_clone)),
-                        false => stream.mixer().play(t.decode().gain(gain_clone)),
+                        true => stream.mixer().play_on_bus(&bus, t.decode().repeat().gain(gain_clone)),
+                        false => stream.mixer().play_on_bus(&bus, t.decode().gain(gain_clone)),
                     };
                     sound.wait();
                     let sound_info = SoundInfo { url, looping, gain, id: sound.id };
@@ -89,8 +106,20 @@ pub fn initialize(world: &mut World) -> anyhow::Result<()> {
     });
 
     world.add_resource(audio_sender(), Arc::new(tx));
+    // The mixer is shared with the audio thread, so that its buses can be controlled from the world
+    if let Ok(mixer) = mixer_rx.recv() {
+        world.add_resource(audio_mixer(), mixer);
+    }
+    let settings = SettingsKey.get(world.resource(asset_cache())).audio;
+    world.add_resource(
+        user_bus_volumes(),
+        HashMap::from([
+            (MASTER_BUS.to_string(), settings.master_volume),
+            (SFX_BUS.to_string(), settings.sfx_volume),
+            (MUSIC_BUS.to_string(), settings.music_volume),
+            (VOICE_BUS.to_string(), settings.voice_volume),
+        ]),
+    );
 
-    ambient_wasm::client::initialize(world, messenger)?;
-
-    Ok(())
+    ambient_wasm::client::initialize(world, messenger);
 }
@@ -99,6 +99,22 @@ fn setup_logging() -> anyhow::Result<()> {
             .with_default_directive(Level::INFO.into())
             .from_env_lossy();
 
+        // Export the spans to an OpenTelemetry collector when one is configured, so that RPCs can be traced from the
+        // client into the server
+        #[cfg(feature = "otlp")]
+        let otlp = match std::env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT") {
+            Some(_) => {
+                let tracer = opentelemetry_otlp::new_pipeline()
+                    .tracing()
+                    .with_exporter(opentelemetry_otlp::new_exporter().tonic())
+                    .install_batch(opentelemetry::runtime::Tokio)?;
+                Some(tracing_opentelemetry::layer().with_tracer(tracer))
+            }
+            None => None,
+        };
+        #[cfg(not(feature = "otlp"))]
+        let otlp: Option<tracing_subscriber::layer::Identity> = None;
+
         registry()
             .with(filter)
             .with(env_filter)
@@ -107,6 +123,7 @@ fn setup_logging() -> anyhow::Result<()> {
                 tracing_tree::HierarchicalLayer::new(4).with_indent_lines(true), // .with_timer(tracing_tree::time::Uptime::from(std::time::Instant::now())),
             )
             // .with(tracing_subscriber::fmt::Layer::new().pretty())
+            .with(otlp)
             .try_init()?;
 
         Ok(())
@@ -170,12 +187,16 @@ impl TryFrom<Option<String>> for ProjectPath {
 }
 
 fn main() -> anyhow::Result<()> {
-    setup_logging()?;
-
-    shared::components::init()?;
     let runtime = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
         .build()?;
+    {
+        // The OpenTelemetry exporter runs on the runtime
+        let _guard = runtime.enter();
+        setup_logging()?;
+    }
+
+    shared::components::init()?;
     let assets = AssetCache::new(runtime.handle().clone());
     PhysicsKey.get(&assets); // Load physics
     AssetsCacheOnDisk.insert(&assets, false); // Disable disk caching for now; see https://github.com/AmbientRun/Ambient/issues/81
@@ -188,10 +209,57 @@ fn main() -> anyhow::Result<()> {
         ContentBaseUrlKey.insert(&assets, project_path.url.push("build/")?);
     }
 
+    // If update: update the packaged build, immediately exit
+    if let Commands::Update { release_url, path } = &cli.command {
+        let build_path: ProjectPath = path.clone().try_into()?;
+        let Some(build_path) = build_path.fs_path.map(|path| path.join("build")) else {
+            anyhow::bail!("Can only update a local build");
+        };
+        let release_url = AbsAssetUrl::parse(release_url)?;
+        match runtime.block_on(ambient_build::update::update(
+            &assets,
+            &build_path,
+            &release_url,
+        ))? {
+            Some(summary) => log::info!(
+                "Updated to {release_url}: downloaded {} chunks ({} bytes), reused {}",
+                summary.downloaded_chunks,
+                summary.downloaded_bytes,
+                summary.reused_chunks
+            ),
+            None => log::info!("Already up to date with {release_url}"),
+        }
+        return Ok(());
+    }
+
+    // If the project was packaged, read the files of its build from its pak
+    if let Some(path) = &project_path.fs_path {
+        let build_path = path.join("build");
+        let pak_path = build_path.join(ambient_build::package::ASSETS_PAK);
+        if pak_path.exists() {
+            ambient_std::pak::mount(&assets, build_path, ambient_std::pak::Pak::open(pak_path)?);
+        }
+    }
+
     // If new: create project, immediately exit
-    if let Commands::New { name, .. } = &cli.command {
+    if let Commands::New {
+        name,
+        id,
+        template,
+        features,
+        no_default_features,
+        ..
+    } = &cli.command
+    {
         if let Some(path) = &project_path.fs_path {
-            if let Err(err) = cli::new_project::new_project(path, name.as_deref()) {
+            let params = cli::new_project::NewProject {
+                name: name.as_deref(),
+                id: id.as_ref(),
+                template: *template,
+                features,
+                default_features: !no_default_features,
+            };
+            if let Err(err) = cli::new_project::new_project(path, params) {
                 eprintln!("Failed to create project: {err:?}");
             }
         } else {
@@ -236,7 +304,8 @@ fn main() -> anyhow::Result<()> {
                     .clone()
                     .expect("should be present as it's already checked above"),
                 manifest,
-                cli.project().map(|p| p.release).unwrap_or(false),
+                cli.project().map(|p| p.release).unwrap_or(false)
+                    || matches!(&cli.command, Commands::Package { .. }),
             ));
             log::info!("Done building {}", project_name);
             Some(metadata)
@@ -256,6 +325,22 @@ fn main() -> anyhow::Result<()> {
         return Ok(());
     }
 
+    // If this is a package, package the build and exit
+    if let Commands::Package {
+        output, encrypt, ..
+    } = &cli.command
+    {
+        let Some(project_fs_path) = &project_path.fs_path else {
+            anyhow::bail!("Can only package a local project");
+        };
+        let output = output
+            .clone()
+            .unwrap_or_else(|| project_fs_path.join("package"));
+        ambient_build::package::package(project_fs_path, &output, *encrypt)?;
+        log::info!("Packaged the project to {output:?}");
+        return Ok(());
+    }
+
     // If this is just a deploy then deploy and exit
     #[cfg(feature = "deploy")]
     if let Commands::Deploy {
@@ -285,6 +370,10 @@ fn main() -> anyhow::Result<()> {
     // Otherwise, either connect to a server or host one
     let server_addr = if let Commands::Join { host, .. } = &cli.command {
         if let Some(mut host) = host.clone() {
+            // Join links, like the ones of Steam invites, can be joined directly
+            if let Some(linked) = client::parse_join_link(&host) {
+                host = linked.to_string();
+            }
             if !host.contains(':') {
                 host = format!("{host}:{QUIC_INTERFACE_PORT}");
             }
@@ -296,6 +385,10 @@ fn main() -> anyhow::Result<()> {
             format!("127.0.0.1:{QUIC_INTERFACE_PORT}").parse()?
         }
     } else if let Some(host) = &cli.host() {
+        // The ids of the entities of a client would be drawn from the same generator as those of the server
+        if host.determinism_test.is_some() && cli.run().is_some() {
+            anyhow::bail!("--determinism-test can only be used with `serve`");
+        }
         let crypto = if let (Some(cert_file), Some(key_file)) = (&host.cert, &host.key) {
             let cert = std::fs::read(cert_file).context("Failed to read certificate file")?;
             let key = std::fs::read(key_file).context("Failed to read certificate key")?;
@@ -323,7 +416,7 @@ fn main() -> anyhow::Result<()> {
             manifest.as_ref().expect("no manifest"),
             metadata.as_ref().expect("no build metadata"),
             crypto,
-        );
+        )?;
         format!("127.0.0.1:{port}").parse()?
     } else {
         unreachable!()
@@ -343,5 +436,10 @@ fn main() -> anyhow::Result<()> {
             }
         });
     }
+
+    // Send the spans that haven't been exported yet
+    #[cfg(feature = "otlp")]
+    opentelemetry::global::shutdown_tracer_provider();
+
     Ok(())
 }
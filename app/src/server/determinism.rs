@@ -0,0 +1,79 @@
+//! Runs the project without players at a fixed time step, and checks the hash of its world after every tick against a
+//! trace recorded earlier, possibly on another platform or by another build, to catch nondeterminism in the simulation.
+use std::{path::Path, time::Duration};
+
+use ambient_core::{dtime, time};
+use ambient_ecs::{FrameEvent, World, WorldHashTrace};
+use ambient_network::server::DEFAULT_TICK_RATE;
+use anyhow::Context;
+
+/// The seed of the entity ids in the deterministic mode
+pub const SEED: u64 = 0;
+
+/// The trace that is compared with when no path is given, in the project directory
+pub const DEFAULT_TRACE: &str = "determinism.json";
+
+/// Steps `world` for `ticks` ticks and compares its hashes with the trace at `trace_path`, logging the first tick and
+/// the components that diverged. With `record`, the trace is written there instead; a missing trace is an error
+/// otherwise, so that a test can't pass by recording its own reference.
+///
+/// Returns whether the simulation matched the trace.
+pub async fn run(
+    mut world: World,
+    ticks: usize,
+    trace_path: &Path,
+    record: bool,
+) -> anyhow::Result<bool> {
+    let reference = if record {
+        None
+    } else {
+        let reference = std::fs::read_to_string(trace_path).with_context(|| {
+            format!("Failed to read the determinism trace {trace_path:?}; record it with --determinism-record")
+        })?;
+        Some(
+            serde_json::from_str::<WorldHashTrace>(&reference)
+                .with_context(|| format!("Invalid determinism trace {trace_path:?}"))?,
+        )
+    };
+
+    let tick_duration = Duration::from_secs_f32(1. / DEFAULT_TICK_RATE);
+    let mut systems = super::systems(&mut world);
+    let mut trace = WorldHashTrace::default();
+    for tick in 0..ticks {
+        let resources = world.resource_entity();
+        world
+            .set(resources, time(), tick_duration * tick as u32)
+            .unwrap();
+        world
+            .set(resources, dtime(), tick_duration.as_secs_f32())
+            .unwrap();
+        systems.run(&mut world, &FrameEvent);
+        world.next_frame();
+        trace.push(&world);
+        // Lets the tasks the systems spawned make progress, as they would between the ticks of the server
+        tokio::task::yield_now().await;
+    }
+
+    let Some(reference) = reference else {
+        std::fs::write(trace_path, serde_json::to_string(&trace)?)
+            .context("Failed to write the determinism trace")?;
+        log::info!("Recorded {ticks} ticks to {trace_path:?}");
+        return Ok(true);
+    };
+    match reference.first_divergence(&trace) {
+        None => {
+            log::info!("The simulation matches {trace_path:?} over {ticks} ticks");
+            Ok(true)
+        }
+        Some(divergence) => {
+            // Kept for comparing the traces of several platforms or builds
+            let diverged_path = trace_path.with_extension("diverged.json");
+            std::fs::write(&diverged_path, serde_json::to_string(&trace)?)
+                .context("Failed to write the diverged determinism trace")?;
+            log::error!(
+                "The simulation doesn't match {trace_path:?}: {divergence}. Its trace was written to {diverged_path:?}"
+            );
+            Ok(false)
+        }
+    }
+}
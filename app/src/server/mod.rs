@@ -1,25 +1,40 @@
-use std::{collections::HashMap, net::SocketAddr, path::Path, sync::Arc};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
+use ambient_build::mods::{self, MODS_DIR};
 use ambient_core::{app_start_time, asset_cache, dtime, name, no_sync, project_name, time};
 use ambient_ecs::{
     dont_store, world_events, ComponentDesc, ComponentRegistry, Entity, Networked, SystemGroup,
     World, WorldEventsSystem, WorldStreamCompEvent,
 };
+use ambient_gpu::{multi_buffer::MultiBuffer, texture::Texture, typed_buffer::UntypedBuffer};
 use ambient_network::{
-    native::server::{Crypto, GameServer},
+    auth::SteamAuthenticator,
+    native::{
+        cluster::{ClusterConfig, ClusterSettings},
+        server::{Crypto, GameServer},
+    },
     persistent_resources,
-    server::{ForkingEvent, ProxySettings, ShutdownEvent},
+    server::{server_admins, ForkingEvent, ProxySettings, ShutdownEvent},
     synced_resources,
 };
 use ambient_prefab::PrefabFromUrl;
 use ambient_std::{
     asset_cache::{AssetCache, AsyncAssetKeyExt, SyncAssetKeyExt},
     asset_url::{AbsAssetUrl, ServerBaseUrlKey},
+    metrics::{self, MetricKind},
+    sandbox::{self, SandboxLimits},
 };
 use ambient_sys::{task::RuntimeHandle, time::SystemTime};
 use anyhow::Context;
 use axum::{
-    http::{Method, StatusCode},
+    body::Body,
+    http::{header, Method, Request, StatusCode},
     response::IntoResponse,
     routing::{get, get_service},
     Router,
@@ -31,6 +46,7 @@ use crate::{
     shared,
 };
 
+pub mod determinism;
 pub mod wasm;
 
 pub fn start(
@@ -41,10 +57,78 @@ pub fn start(
     manifest: &ambient_project::Manifest,
     metadata: &ambient_build::Metadata,
     crypto: Crypto,
-) -> u16 {
+) -> anyhow::Result<u16> {
     log::info!("Creating server");
     let host_cli = cli.host().unwrap();
-    let quic_interface_port = host_cli.quic_interface_port;
+    let cluster = host_cli
+        .cluster
+        .as_ref()
+        .map(|path| {
+            let config =
+                std::fs::read_to_string(path).context("Failed to read the cluster config")?;
+            let config: ClusterConfig =
+                toml::from_str(&config).context("Invalid cluster config")?;
+            ClusterSettings::new(config, host_cli.shard)
+        })
+        .transpose()?;
+    // A shard listens on the address the other shards know it by
+    let quic_interface_port = host_cli.quic_interface_port.or_else(|| {
+        cluster
+            .as_ref()
+            .map(|settings| settings.own().address.port())
+    });
+    let forward_module_logs = host_cli.forward_module_logs;
+    let admins = host_cli.admins.clone();
+    // Before anything is spawned, so that the entity ids are the same in every run
+    let determinism_test = host_cli.determinism_test;
+    let determinism_record = host_cli.determinism_record;
+    if determinism_test.is_some() {
+        ambient_ecs::set_deterministic(Some(determinism::SEED));
+    }
+    let determinism_trace = host_cli.determinism_trace.clone().unwrap_or_else(|| {
+        project_path
+            .to_file_path()
+            .ok()
+            .flatten()
+            .unwrap_or_default()
+            .join(determinism::DEFAULT_TRACE)
+    });
+    // Enabled before anything is recorded, and before the modules are loaded so that their fuel is metered
+    if let Some(metrics_port) = host_cli.metrics_port {
+        start_metrics_endpoint(runtime, metrics_port);
+    }
+    let mods_source = match &host_cli.mods {
+        Some(mods) if mods.starts_with("http://") || mods.starts_with("https://") => {
+            Some(AbsAssetUrl::parse(mods).context("Invalid mods URL")?)
+        }
+        Some(mods) => Some(AbsAssetUrl::from_directory_path(mods)),
+        None => project_path
+            .to_file_path()
+            .ok()
+            .flatten()
+            .map(|path| AbsAssetUrl::from_directory_path(path.join(MODS_DIR))),
+    };
+    // Before the modules are loaded, so that their fuel is limited from the start
+    if host_cli.hardened {
+        let mut limits = match &host_cli.hardened_limits {
+            Some(path) => {
+                let limits =
+                    std::fs::read_to_string(path).context("Failed to read the hardened limits")?;
+                toml::from_str::<SandboxLimits>(&limits).context("Invalid hardened limits")?
+            }
+            None => SandboxLimits::default(),
+        };
+        limits
+            .allowed_roots
+            .extend(project_path.to_file_path().ok().flatten());
+        limits.allowed_roots.extend(
+            mods_source
+                .as_ref()
+                .and_then(|source| source.to_file_path().ok().flatten()),
+        );
+        log::info!("Hosting in the hardened mode: {limits:?}");
+        sandbox::enable(limits);
+    }
     let proxy_settings = (!host_cli.no_proxy).then(|| {
         ProxySettings {
             // default to getting a proxy from the dims-web Google App Engine app
@@ -57,9 +141,19 @@ pub fn start(
             project_id: manifest.project.id.to_string(),
         }
     });
-    let server = runtime.block_on(async move {
+    let authenticator = host_cli
+        .steam_app_id
+        .map(|app_id| {
+            anyhow::Ok(SteamAuthenticator {
+                app_id,
+                web_api_key: std::env::var("STEAM_WEB_API_KEY")
+                    .context("STEAM_WEB_API_KEY is required to authenticate Steam users")?,
+            })
+        })
+        .transpose()?;
+    let mut server = runtime.block_on(async move {
         if let Some(port) = quic_interface_port {
-            GameServer::new_with_port(port, false, proxy_settings, &crypto)
+            GameServer::new_with_port(port, false, proxy_settings, cluster, &crypto)
                 .await
                 .context("failed to create game server with port")
                 .unwrap()
@@ -68,6 +162,7 @@ pub fn start(
                 QUIC_INTERFACE_PORT..(QUIC_INTERFACE_PORT + 10),
                 false,
                 proxy_settings,
+                cluster,
                 &crypto,
             )
             .await
@@ -75,6 +170,9 @@ pub fn start(
             .unwrap()
         }
     });
+    if let Some(authenticator) = authenticator {
+        server.authenticator = Arc::new(authenticator);
+    }
     let port = server.port;
 
     let public_host = cli
@@ -93,7 +191,15 @@ pub fn start(
     if let Ok(Some(project_path_fs)) = project_path.to_file_path() {
         let key = format!("http://{public_host}:{http_interface_port}/content/");
         ServerBaseUrlKey.insert(&assets, AbsAssetUrl::parse(key).unwrap());
-        start_http_interface(runtime, &project_path_fs, http_interface_port);
+        start_http_interface(
+            runtime,
+            assets.clone(),
+            &project_path_fs,
+            mods_source
+                .as_ref()
+                .and_then(|source| source.to_file_path().ok().flatten()),
+            http_interface_port,
+        );
     } else {
         ServerBaseUrlKey.insert(&assets, project_path.push("build/").unwrap());
     }
@@ -110,9 +216,17 @@ pub fn start(
         server_world
             .add_components(
                 server_world.resource_entity(),
-                create_resources(assets.clone()),
+                create_resources(assets.clone()).with(server_admins(), admins),
             )
             .unwrap();
+        if determinism_test.is_some() {
+            // The simulation starts at the same time in every run
+            let resources = server_world.resource_entity();
+            server_world.set(resources, time(), Duration::ZERO).unwrap();
+            server_world
+                .set(resources, app_start_time(), Duration::ZERO)
+                .unwrap();
+        }
 
         // Keep track of the project name
         let name = manifest
@@ -144,6 +258,8 @@ pub fn start(
             project_path.clone(),
             &manifest,
             &metadata,
+            forward_module_logs,
+            mods_source.as_ref(),
         )
         .await
         .unwrap();
@@ -160,6 +276,16 @@ pub fn start(
             let obj = PrefabFromUrl(asset_path.into()).get(&assets).await.unwrap();
             obj.spawn_into_world(&mut server_world, None);
         }
+        if let Some(ticks) = determinism_test {
+            let matches =
+                determinism::run(server_world, ticks, &determinism_trace, determinism_record)
+                    .await
+                    .unwrap_or_else(|err| {
+                        log::error!("{err:?}");
+                        false
+                    });
+            std::process::exit(if matches { 0 } else { 1 });
+        }
         log::info!("Starting server");
         server
             .run(
@@ -171,7 +297,7 @@ pub fn start(
             )
             .await;
     });
-    port
+    Ok(port)
 }
 
 fn systems(_world: &mut World) -> SystemGroup {
@@ -189,8 +315,21 @@ fn systems(_world: &mut World) -> SystemGroup {
             ambient_core::remove_at_time_system(),
             Box::new(WorldEventsSystem),
             Box::new(ambient_core::camera::camera_systems()),
+            Box::new(ambient_core::environment::environment_systems()),
             Box::new(ambient_physics::server_systems()),
+            Box::new(ambient_captions::systems()),
+            Box::new(ambient_behavior_tree::systems()),
+            Box::new(ambient_dialogue::systems()),
+            Box::new(ambient_sequencer::systems()),
+            Box::new(ambient_voxel::server_systems()),
+            Box::new(ambient_save::systems("server")),
+            Box::new(ambient_tweakables::systems("server")),
             Box::new(wasm::systems()),
+            Box::new(ambient_surface_effects::server_systems()),
+            Box::new(ambient_network::ownership::server_systems()),
+            Box::new(ambient_network::pose_sync::server_systems()),
+            Box::new(ambient_physics::lag_compensation::systems()),
+            Box::new(ambient_physics::scene_query::systems()),
         ],
     )
 }
@@ -225,6 +364,9 @@ fn create_resources(assets: AssetCache) -> Entity {
         .with_default(world_events());
     ambient_physics::create_server_resources(&assets, &mut server_resources);
     server_resources.merge(ambient_core::async_ecs::async_ecs_resources());
+    server_resources.merge(ambient_dialogue::server_resources());
+    server_resources.merge(ambient_sequencer::server_resources());
+    server_resources.merge(ambient_tweakables::resources());
     server_resources.set(ambient_core::runtime(), RuntimeHandle::current());
 
     let now = SystemTime::now()
@@ -250,7 +392,9 @@ fn create_resources(assets: AssetCache) -> Entity {
         unistream_handlers,
     );
 
-    let dgram_handlers = HashMap::new();
+    let mut dgram_handlers = HashMap::new();
+    ambient_network::ownership::register_server_datagram_handler(&mut dgram_handlers);
+    ambient_network::pose_sync::register_server_datagram_handler(&mut dgram_handlers);
     server_resources.set(ambient_network::server::datagram_handlers(), dgram_handlers);
 
     server_resources
@@ -260,14 +404,69 @@ pub const HTTP_INTERFACE_PORT: u16 = 8999;
 pub const QUIC_INTERFACE_PORT: u16 = 9000;
 fn start_http_interface(
     runtime: &tokio::runtime::Runtime,
+    assets: AssetCache,
     project_path: &Path,
+    mods_path: Option<PathBuf>,
     http_interface_port: u16,
 ) {
+    let build_path = project_path.join("build");
+    // Files that aren't on disk may be packed into a pak, if the project was packaged. The build directories of local
+    // mods are served under `mods/<mod>/`, so that clients can download their modules.
+    let serve_fallback = tower::service_fn({
+        let build_path = build_path.clone();
+        move |request: Request<Body>| {
+            let relative = PathBuf::from(request.uri().path().trim_start_matches('/'));
+            // Only plain names, so that the path can't leave the served directories
+            let plain = relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)));
+            let path = if !plain {
+                None
+            } else {
+                match (relative.strip_prefix(MODS_DIR), &mods_path) {
+                    (Ok(relative), Some(mods_path)) => {
+                        let mut components = relative.components();
+                        components.next().map(|mod_dir| {
+                            mods_path
+                                .join(mod_dir)
+                                .join(mods::MOD_BUILD_DIR)
+                                .join(components.as_path())
+                        })
+                    }
+                    _ => Some(build_path.join(&relative)),
+                }
+            };
+            let assets = assets.clone();
+            async move {
+                let Some(path) = path else {
+                    return Ok::<_, std::io::Error>(StatusCode::NOT_FOUND.into_response());
+                };
+                let data = match ambient_std::pak::read_mounted(&assets, &path).await {
+                    Ok(None) => match tokio::fs::metadata(&path).await {
+                        Ok(metadata) if metadata.is_file() => {
+                            tokio::fs::read(&path).await.map(Some).map_err(Into::into)
+                        }
+                        _ => Ok(None),
+                    },
+                    result => result,
+                };
+                Ok(match data {
+                    Ok(Some(data)) => data.into_response(),
+                    Ok(None) => StatusCode::NOT_FOUND.into_response(),
+                    Err(err) => {
+                        log::warn!("Failed to read {path:?}: {err:?}");
+                        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+                    }
+                })
+            }
+        }
+    });
     let router = Router::new()
         .route("/ping", get(|| async move { "ok" }))
         .nest_service(
             "/content",
-            get_service(ServeDir::new(project_path.join("build"))).handle_error(handle_error),
+            get_service(ServeDir::new(build_path).fallback(serve_fallback))
+                .handle_error(handle_error),
         )
         .layer(
             CorsLayer::new()
@@ -293,6 +492,56 @@ fn start_http_interface(
     });
 }
 
+const GPU_MEMORY_METRIC: &str = "ambient_gpu_memory_bytes";
+
+/// Serves the runtime statistics of the server in the Prometheus text format, for monitoring
+fn start_metrics_endpoint(runtime: &tokio::runtime::Runtime, port: u16) {
+    metrics::enable();
+    ambient_network::metrics::describe();
+    ambient_wasm::shared::describe_metrics();
+    metrics::describe(
+        GPU_MEMORY_METRIC,
+        MetricKind::Gauge,
+        "The GPU memory used by the process, if it has a GPU",
+    );
+
+    let router = Router::new().route(
+        "/metrics",
+        get(|| async move {
+            let gpu_memory = [
+                ("texture", Texture::total_bytes_used() as f64),
+                ("buffer", UntypedBuffer::total_bytes_used() as f64),
+                ("multi_buffer", MultiBuffer::total_bytes_used() as f64),
+            ];
+            if gpu_memory.iter().any(|(_, bytes)| *bytes > 0.) {
+                for (kind, bytes) in gpu_memory {
+                    metrics::set(GPU_MEMORY_METRIC, &[("kind", kind)], bytes);
+                }
+            }
+            (
+                [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+                metrics::render(),
+            )
+        }),
+    );
+
+    let serve = |addr| async move {
+        axum::Server::try_bind(&addr)?
+            .serve(router.into_make_service())
+            .await?;
+
+        Ok::<_, anyhow::Error>(())
+    };
+
+    runtime.spawn(async move {
+        let addr = SocketAddr::from(([0, 0, 0, 0], port));
+        log::info!("Serving metrics at http://{addr}/metrics");
+        if let Err(err) = serve(addr).await {
+            tracing::error!("Failed to start the metrics endpoint on: {addr}\n\n{err:?}");
+        }
+    });
+}
+
 async fn handle_error(_err: std::io::Error) -> impl IntoResponse {
     (StatusCode::INTERNAL_SERVER_ERROR, "Something went wrong...")
 }
@@ -1,11 +1,13 @@
 use std::{collections::HashMap, sync::Arc};
 
-use ambient_ecs::{EntityId, SystemGroup, World};
-use ambient_project::Identifier;
-use ambient_std::{asset_url::AbsAssetUrl, asset_cache::AssetCache};
+use ambient_build::mods;
+use ambient_ecs::{ComponentRegistry, EntityId, SystemGroup, World};
+use ambient_project::{Identifier, Permissions};
+use ambient_std::{asset_cache::AssetCache, asset_url::AbsAssetUrl, path::path_to_unix_string, sandbox};
 pub use ambient_wasm::server::{on_forking_systems, on_shutdown_systems};
 use ambient_wasm::shared::{
-    client_bytecode_from_url, get_module_name, module_bytecode, remote_paired_id, spawn_module, MessageType, ModuleBytecode,
+    client_bytecode_from_url, get_module_name, mod_dependencies, mod_id, module_bytecode, module_permissions, module_restricted,
+    remote_paired_id, spawn_module, MessageType, ModuleBytecode,
 };
 use anyhow::Context;
 
@@ -13,8 +15,8 @@ pub fn systems() -> SystemGroup {
     ambient_wasm::server::systems()
 }
 
-pub async fn initialize(world: &mut World, assets: AssetCache, project_path: AbsAssetUrl, manifest: &ambient_project::Manifest, build_metadata: &ambient_build::Metadata) -> anyhow::Result<()> {
-    let messenger = Arc::new(|world: &World, id: EntityId, type_: MessageType, message: &str| {
+pub async fn initialize(world: &mut World, assets: AssetCache, project_path: AbsAssetUrl, manifest: &ambient_project::Manifest, build_metadata: &ambient_build::Metadata, forward_module_logs: bool, mods_source: Option<&AbsAssetUrl>) -> anyhow::Result<()> {
+    let messenger = Arc::new(move |world: &World, id: EntityId, type_: MessageType, message: &str| {
         let name = get_module_name(world, id);
         let (prefix, level) = match type_ {
             MessageType::Info => ("info", log::Level::Info),
@@ -25,12 +27,35 @@ pub async fn initialize(world: &mut World, assets: AssetCache, project_path: Abs
         };
 
         log::log!(level, "[{name}] {prefix}: {}", message.strip_suffix('\n').unwrap_or(message));
+        ambient_wasm::server::forward_module_log(world, id, type_, message, forward_module_logs);
     });
 
-    ambient_wasm::server::initialize(world, messenger)?;
+    ambient_wasm::server::initialize(world, messenger);
 
     let build_dir = project_path.push("build").unwrap();
+    spawn_modules(world, &assets, &build_dir, manifest, build_metadata, |path| Ok(AbsAssetUrl::from_asset_key(path)?)).await?;
 
+    if let Some(mods_source) = mods_source {
+        if let Err(err) = load_mods(world, &assets, mods_source).await {
+            log::warn!("Failed to load the mods at {mods_source}: {err:#}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Spawns the modules of the project or mod with the build at `build_dir`, and pairs its client and server modules.
+/// `client_url` gives the URL that clients download a client module from, given its path in the build.
+async fn spawn_modules(
+    world: &mut World,
+    assets: &AssetCache,
+    build_dir: &AbsAssetUrl,
+    manifest: &ambient_project::Manifest,
+    build_metadata: &ambient_build::Metadata,
+    client_url: impl Fn(&str) -> anyhow::Result<AbsAssetUrl>,
+) -> anyhow::Result<Vec<EntityId>> {
+    // Projects that don't declare their permissions can use every capability, except in the hardened mode
+    let permissions = manifest.permissions.clone().or_else(|| sandbox::is_enabled().then(Permissions::default));
     let mut modules_to_entity_ids = HashMap::new();
     for target in ["client", "server"] {
         let wasm_component_paths: &[String] = build_metadata.component_paths(target);
@@ -45,6 +70,9 @@ pub async fn initialize(world: &mut World, assets: AssetCache, project_path: Abs
             let description = if is_sole_module { description } else { format!("{description} ({name})") };
 
             let id = spawn_module(world, &name, description, true);
+            if let Some(permissions) = &permissions {
+                world.add_component(id, module_permissions(), permissions.clone())?;
+            }
             modules_to_entity_ids.insert(
                 (
                     target,
@@ -60,10 +88,9 @@ pub async fn initialize(world: &mut World, assets: AssetCache, project_path: Abs
             );
 
             if target == "client" {
-                let bytecode_url = AbsAssetUrl::from_asset_key(path)?.to_string();
-                world.add_component(id, client_bytecode_from_url(), bytecode_url)?;
+                world.add_component(id, client_bytecode_from_url(), client_url(path)?.to_string())?;
             } else {
-                let bytecode = component_url.download_bytes(&assets).await?;
+                let bytecode = component_url.download_bytes(assets).await?;
                 world.add_component(id, module_bytecode(), ModuleBytecode(bytecode))?;
             }
         }
@@ -80,5 +107,43 @@ pub async fn initialize(world: &mut World, assets: AssetCache, project_path: Abs
         }
     }
 
+    Ok(modules_to_entity_ids.into_values().collect())
+}
+
+/// Loads the mods at `source` in the order of their dependencies, and spawns their modules in the restricted capability
+/// profile (see `module_restricted`). Mods that can't be loaded are skipped with a warning.
+async fn load_mods(world: &mut World, assets: &AssetCache, source: &AbsAssetUrl) -> anyhow::Result<()> {
+    let mods_path = source.to_file_path()?;
+    let resolved = mods::resolve(mods::enumerate(assets, source).await?);
+    for (package, reason) in &resolved.rejected {
+        log::warn!("Skipping the mod `{}` at {}: {reason}", package.id(), package.build_url);
+    }
+
+    for package in resolved.load_order {
+        let components = ambient_project_native::all_defined_components(&package.manifest, false).map_err(anyhow::Error::msg)?;
+        ComponentRegistry::get_mut().add_external(components);
+
+        // Clients download the modules of local mods from the server, which serves their build directories under
+        // `mods/<mod>/`, and the modules of remote mods from where they are hosted
+        let client_url = |path: &str| -> anyhow::Result<AbsAssetUrl> {
+            match (&mods_path, package.build_url.to_file_path()?) {
+                (Some(mods_path), Some(build_path)) => {
+                    let mod_dir = build_path.parent().context("A local mod has no directory")?.strip_prefix(mods_path)?;
+                    let mod_dir = path_to_unix_string(mod_dir);
+                    Ok(AbsAssetUrl::from_asset_key(format!("{}/{mod_dir}/{path}", mods::MODS_DIR))?)
+                }
+                _ => Ok(package.build_url.push(path)?),
+            }
+        };
+        let modules = spawn_modules(world, assets, &package.build_url, &package.manifest, &package.metadata, client_url).await?;
+
+        let dependencies = package.dependencies().iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        for id in modules {
+            world.add_component(id, module_restricted(), ())?;
+            world.add_component(id, mod_id(), package.id().to_string())?;
+            world.add_component(id, mod_dependencies(), dependencies.clone())?;
+        }
+        log::info!("Loaded the mod `{}` from {}", package.id(), package.build_url);
+    }
     Ok(())
 }
@@ -4,10 +4,19 @@ pub(crate) fn init() -> anyhow::Result<()> {
     ambient_physics::init_all_components();
     ambient_wasm::shared::init_all_components();
     ambient_decals::init_components();
+    ambient_prefab::init_components();
     ambient_world_audio::init_components();
     ambient_primitives::init_components();
     ambient_sky::init_components();
     ambient_water::init_components();
+    ambient_behavior_tree::init_components();
+    ambient_captions::init_components();
+    ambient_dialogue::init_components();
+    ambient_minimap::init_components();
+    ambient_save::init_components();
+    ambient_sequencer::init_all_components();
+    ambient_tweakables::init_components();
+    ambient_voxel::init_components();
 
     Ok(())
 }
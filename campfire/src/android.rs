@@ -0,0 +1,118 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use clap::Parser;
+
+/// Where the project is staged before packaging. The Android crate being packaged should point
+/// `[package.metadata.android] assets` at this directory.
+const ASSETS_DIR: &str = "target/android-assets";
+/// Must match `ambient_sys::android::ASSET_INDEX`.
+const ASSET_INDEX: &str = "ambient_assets.txt";
+
+#[derive(Parser, Clone)]
+pub enum Android {
+    /// Build a project and package it into an APK, ready to be installed on an Android device or standalone Quest
+    Package(Package),
+}
+
+#[derive(Parser, Clone)]
+pub struct Package {
+    /// The path of the project to package
+    pub project: PathBuf,
+    /// The crate (with `crate-type = ["cdylib"]`) containing the `android_main` entrypoint
+    #[arg(short, long)]
+    pub package: String,
+    /// The target to build for
+    #[arg(long, default_value = "aarch64-linux-android")]
+    pub target: String,
+    /// Whether or not to build in release mode
+    #[arg(short, long, default_value_t = false)]
+    pub release: bool,
+    /// Install and run the APK on the connected device after packaging
+    #[arg(long, default_value_t = false)]
+    pub run: bool,
+}
+
+pub(crate) fn main(args: &Android) -> anyhow::Result<()> {
+    match args {
+        Android::Package(args) => package(args),
+    }
+}
+
+fn package(args: &Package) -> anyhow::Result<()> {
+    let Package {
+        project,
+        package,
+        target,
+        release,
+        run,
+    } = args;
+
+    log::info!("Building project {}...", project.display());
+    let mut command = std::process::Command::new("cargo");
+    command.arg("run");
+    if *release {
+        command.arg("--release");
+    }
+    command.args(["-p", "ambient", "--", "build"]).arg(project);
+    if *release {
+        command.arg("--release");
+    }
+    if !command.spawn()?.wait()?.success() {
+        anyhow::bail!("Failed to build project {}", project.display());
+    }
+
+    log::info!("Staging project into {ASSETS_DIR}...");
+    let staging = Path::new(ASSETS_DIR).join("project");
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)?;
+    }
+    let mut index = Vec::new();
+    copy_file(project, &staging, Path::new("ambient.toml"), &mut index)?;
+    for entry in walkdir::WalkDir::new(project.join("build")) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            let relative = entry.path().strip_prefix(project)?;
+            copy_file(project, &staging, relative, &mut index)?;
+        }
+    }
+    std::fs::write(staging.join(ASSET_INDEX), index.join("\n"))?;
+    log::info!("Staged {} files.", index.len());
+
+    log::info!("Packaging {package} for {target}...");
+    let mut command = std::process::Command::new("cargo");
+    command
+        .arg("apk")
+        .arg(if *run { "run" } else { "build" })
+        .args(["-p", package, "--target", target]);
+    if *release {
+        command.arg("--release");
+    }
+    let status = command
+        .spawn()
+        .context("Failed to run cargo-apk; install it with `cargo install cargo-apk`")?
+        .wait()?;
+    if !status.success() {
+        anyhow::bail!("Failed to package {package}");
+    }
+
+    log::info!("Done packaging {}.", project.display());
+    Ok(())
+}
+
+fn copy_file(
+    project: &Path,
+    staging: &Path,
+    relative: &Path,
+    index: &mut Vec<String>,
+) -> anyhow::Result<()> {
+    let target = staging.join(relative);
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::copy(project.join(relative), &target)
+        .with_context(|| format!("Failed to copy {}", relative.display()))?;
+    // The asset manager always uses forward slashes
+    index.push(relative.to_string_lossy().replace('\\', "/"));
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::Context;
 use clap::Parser;
@@ -6,6 +6,7 @@ use clap::Parser;
 mod context;
 mod helpers;
 mod parser;
+mod schema;
 mod typescript;
 
 #[derive(Parser, Clone)]
@@ -18,17 +19,30 @@ pub enum Doc {
         /// The args to pass through to `cargo doc`
         args: Vec<String>,
     },
+    /// Generate a machine-readable schema and TypeScript typings for the components, concepts and messages of a
+    /// project, so that external tooling and non-Rust bindings can stay in sync with them
+    Schema {
+        /// The `ambient.toml` of the project
+        manifest: PathBuf,
+        /// The directory to write `schema.json` and `schema.d.ts` to
+        #[clap(long, default_value = ".")]
+        output: PathBuf,
+    },
 }
 
+const RUNTIME_MANIFEST: &str = "shared_crates/schema/src/ambient.toml";
+
 pub(crate) fn main(args: &Doc) -> anyhow::Result<()> {
     match args {
         Doc::Runtime => runtime(),
         Doc::Api { args } => api(args),
+        Doc::Schema { manifest, output } => schema::generate(manifest, false, output),
     }
 }
 
 fn runtime() -> anyhow::Result<()> {
-    pipeline()
+    pipeline()?;
+    runtime_schema()
 }
 
 fn api(args: &[String]) -> anyhow::Result<()> {
@@ -73,3 +87,17 @@ fn pipeline() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+fn runtime_schema() -> anyhow::Result<()> {
+    log::info!("Generating schema.json and schema.d.ts...");
+
+    schema::generate(
+        Path::new(RUNTIME_MANIFEST),
+        true,
+        Path::new("docs/src/reference"),
+    )?;
+
+    log::info!("Done generating schema.json and schema.d.ts.");
+
+    Ok(())
+}
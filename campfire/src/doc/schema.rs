@@ -0,0 +1,287 @@
+use std::{collections::BTreeMap, fmt::Write, path::Path};
+
+use ambient_project::{Component, ComponentType, IdentifierPathBuf, Manifest, Namespace, Version};
+use anyhow::Context;
+use serde::Serialize;
+
+/// The primitive component types, and the TypeScript types they are represented with.
+/// Keep this in sync with `primitive_component_definitions` in `ambient_shared_types`.
+const PRIMITIVE_TYPES: &[(&str, &str)] = &[
+    ("Empty", "null"),
+    ("Bool", "boolean"),
+    ("EntityId", "string"),
+    ("F32", "number"),
+    ("F64", "number"),
+    (
+        "Mat4",
+        "[number, number, number, number, number, number, number, number, number, number, number, number, number, number, number, number]",
+    ),
+    ("I32", "number"),
+    ("Quat", "[number, number, number, number]"),
+    ("String", "string"),
+    ("U8", "number"),
+    ("U32", "number"),
+    ("U64", "bigint"),
+    ("Vec2", "[number, number]"),
+    ("Vec3", "[number, number, number]"),
+    ("Vec4", "[number, number, number, number]"),
+    ("Uvec2", "[number, number]"),
+    ("Uvec3", "[number, number, number]"),
+    ("Uvec4", "[number, number, number, number]"),
+];
+
+/// A machine-readable description of the components, concepts and messages of a manifest.
+#[derive(Serialize)]
+struct Schema<'a> {
+    version: &'a Version,
+    /// By the path of the namespace
+    namespaces: BTreeMap<String, &'a Namespace>,
+    /// By the ID the component is registered with at runtime
+    components: BTreeMap<String, &'a Component>,
+    concepts: BTreeMap<String, SchemaConcept<'a>>,
+    /// By the ID the message is sent with
+    messages: BTreeMap<String, SchemaMessage<'a>>,
+}
+
+#[derive(Serialize)]
+struct SchemaConcept<'a> {
+    name: Option<&'a str>,
+    description: Option<&'a str>,
+    extends: Vec<String>,
+    /// The components of the concept by component ID, with their default values
+    components: BTreeMap<String, &'a toml::Value>,
+}
+
+#[derive(Serialize)]
+struct SchemaMessage<'a> {
+    description: Option<&'a str>,
+    /// In the order they are serialized in
+    fields: Vec<SchemaField<'a>>,
+}
+
+#[derive(Serialize)]
+struct SchemaField<'a> {
+    name: &'a str,
+    #[serde(rename = "type")]
+    type_: &'a ComponentType,
+}
+
+/// Writes `schema.json` and `schema.d.ts` for the manifest at `manifest_path` to `output_dir`.
+///
+/// The components of the runtime manifest are registered without a prefix; the components of other manifests are
+/// prefixed with the path of their project.
+pub fn generate(
+    manifest_path: &Path,
+    is_api_manifest: bool,
+    output_dir: &Path,
+) -> anyhow::Result<()> {
+    let manifest = Manifest::from_file(manifest_path)?;
+    let project_path = if is_api_manifest {
+        IdentifierPathBuf::empty()
+    } else {
+        manifest.project_path()
+    };
+    let component_id = |path: &IdentifierPathBuf| {
+        let is_local = manifest
+            .components
+            .get(path)
+            .and_then(|c| c.other())
+            .is_some();
+        if is_local && !project_path.is_empty() {
+            format!("{project_path}::{path}")
+        } else {
+            path.to_string()
+        }
+    };
+
+    let namespaces = manifest
+        .components
+        .iter()
+        .filter_map(|(path, c)| Some((path.to_string(), c.namespace()?)))
+        .collect();
+    let components = manifest
+        .components
+        .iter()
+        .filter_map(|(path, c)| Some((component_id(path), c.other()?)))
+        .collect::<BTreeMap<_, _>>();
+    let concepts = manifest
+        .concepts
+        .iter()
+        .filter_map(|(path, c)| Some((path.to_string(), c.other()?)))
+        .map(|(id, concept)| {
+            let concept = SchemaConcept {
+                name: concept.name.as_deref(),
+                description: concept.description.as_deref(),
+                extends: concept.extends.iter().map(|e| e.to_string()).collect(),
+                components: concept
+                    .components
+                    .iter()
+                    .map(|(path, default)| (component_id(path), default))
+                    .collect(),
+            };
+            (id, concept)
+        })
+        .collect::<BTreeMap<_, _>>();
+    let messages = manifest
+        .messages
+        .iter()
+        .filter_map(|(path, m)| Some((message_id(path), m.other()?)))
+        .map(|(id, message)| {
+            let message = SchemaMessage {
+                description: message.description.as_deref(),
+                fields: message
+                    .fields
+                    .iter()
+                    .map(|(name, type_)| SchemaField {
+                        name: name.as_ref(),
+                        type_,
+                    })
+                    .collect(),
+            };
+            (id, message)
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    let schema = Schema {
+        version: &manifest.project.version,
+        namespaces,
+        components,
+        concepts,
+        messages,
+    };
+
+    std::fs::create_dir_all(output_dir)?;
+    std::fs::write(
+        output_dir.join("schema.json"),
+        serde_json::to_string_pretty(&schema)? + "\n",
+    )?;
+    std::fs::write(output_dir.join("schema.d.ts"), typescript(&schema)?)?;
+
+    Ok(())
+}
+
+/// Messages are identified by the last segment of their path, like the generated guest code does.
+fn message_id(path: &IdentifierPathBuf) -> String {
+    path.last().map(|id| id.to_string()).unwrap_or_default()
+}
+
+fn typescript(schema: &Schema) -> anyhow::Result<String> {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "// This file is generated by `campfire`. Please do not manually edit it."
+    )?;
+    for (name, ts_type) in PRIMITIVE_TYPES {
+        writeln!(out, "export type {name} = {ts_type};")?;
+    }
+
+    writeln!(out)?;
+    writeln!(out, "/// The types of the components, by component ID.")?;
+    writeln!(out, "export type Components = {{")?;
+    for (id, component) in &schema.components {
+        let name = component.name.as_deref().unwrap_or(id);
+        write_doc(&mut out, 1, Some(name), component.description.as_deref())?;
+        writeln!(out, "  \"{id}\": {},", typescript_type(&component.type_)?)?;
+    }
+    writeln!(out, "}};")?;
+
+    writeln!(out)?;
+    writeln!(out, "/// The fields of the messages, by message ID.")?;
+    writeln!(out, "export type Messages = {{")?;
+    for (id, message) in &schema.messages {
+        write_doc(&mut out, 1, None, message.description)?;
+        writeln!(out, "  \"{id}\": {{")?;
+        for field in &message.fields {
+            writeln!(
+                out,
+                "    {}: {},",
+                field.name,
+                typescript_type(field.type_)?
+            )?;
+        }
+        writeln!(out, "  }},")?;
+    }
+    writeln!(out, "}};")?;
+
+    for (id, concept) in &schema.concepts {
+        writeln!(out)?;
+        write_doc(&mut out, 0, concept.name, concept.description)?;
+        write!(out, "export type {} = ", pascal_case(id))?;
+        for parent in &concept.extends {
+            write!(out, "{} & ", pascal_case(parent))?;
+        }
+        writeln!(out, "{{")?;
+        for component in concept.components.keys() {
+            let component_type = match schema.components.get(component) {
+                Some(c) => typescript_type(&c.type_)?,
+                // The component comes from another manifest (e.g. the runtime's), which this schema doesn't describe
+                None => "unknown".to_string(),
+            };
+            writeln!(out, "  \"{component}\": {component_type},")?;
+        }
+        writeln!(out, "}};")?;
+    }
+
+    Ok(out)
+}
+
+fn typescript_type(ty: &ComponentType) -> anyhow::Result<String> {
+    let primitive = |name: &str| {
+        PRIMITIVE_TYPES
+            .iter()
+            .any(|(primitive, _)| *primitive == name)
+            .then(|| name.to_string())
+            .with_context(|| format!("unknown component type {name}"))
+    };
+    match ty {
+        ComponentType::String(name) => primitive(name),
+        ComponentType::ContainerType {
+            type_,
+            element_type,
+        } => {
+            // A container type without an element type is a primitive type
+            let Some(element_type) = element_type else {
+                return primitive(type_);
+            };
+            let element_type = primitive(element_type)?;
+            match type_.as_str() {
+                "Vec" => Ok(format!("{element_type}[]")),
+                "Option" => Ok(format!("{element_type} | null")),
+                _ => anyhow::bail!("unknown container type {type_}"),
+            }
+        }
+    }
+}
+
+fn write_doc(
+    out: &mut String,
+    indent: usize,
+    name: Option<&str>,
+    description: Option<&str>,
+) -> anyhow::Result<()> {
+    let indent = "  ".repeat(indent);
+    if let Some(name) = name {
+        writeln!(out, "{indent}/// **{name}**")?;
+    }
+    if let Some(description) = description.filter(|d| !d.is_empty()) {
+        for line in description.lines() {
+            writeln!(out, "{indent}/// {line}")?;
+        }
+    }
+    Ok(())
+}
+
+fn pascal_case(path: &str) -> String {
+    path.rsplit("::")
+        .next()
+        .unwrap_or(path)
+        .split('_')
+        .map(|segment| {
+            let mut c = segment.chars();
+            match c.next() {
+                None => String::new(),
+                Some(f) => f.to_uppercase().collect::<String>() + c.as_str(),
+            }
+        })
+        .collect()
+}
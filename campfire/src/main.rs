@@ -1,5 +1,6 @@
 use clap::Parser;
 
+mod android;
 mod doc;
 mod example;
 mod release;
@@ -7,6 +8,9 @@ mod release;
 #[derive(Parser, Clone)]
 #[command(author, version, about, long_about = None, propagate_version = true, trailing_var_arg = true)]
 pub enum Cli {
+    /// Android packaging functionality
+    #[command(subcommand)]
+    Android(android::Android),
     /// Generate documentation for Ambient
     #[command(subcommand)]
     Doc(doc::Doc),
@@ -34,6 +38,7 @@ fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
     match cli {
+        Cli::Android(android) => android::main(&android),
         Cli::Doc(doc) => doc::main(&doc),
         Cli::Example(ex) => example::main(&ex),
         Cli::Release(re) => release::main(&re),
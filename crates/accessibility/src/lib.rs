@@ -0,0 +1,661 @@
+//! Exposes the UI to screen readers as an accessibility tree, and lets the focusable entities of the UI be navigated
+//! with the keyboard.
+//!
+//! The tree is built from the UI entities of a world: the entities with an `accessibility_role`, an
+//! `accessibility_label` or `accessibility_focusable`, and the text that isn't part of one of them. The app hands the
+//! nodes that changed to the platform's screen reader, and turns the screen reader's requests back into messages.
+//! The keys that move or use the keyboard focus don't reach the game; see [uses_keyboard_input].
+use std::{collections::HashMap, num::NonZeroU128, str::FromStr};
+
+use accesskit::{
+    Action, ActionHandler, ActionRequest, DefaultActionVerb, NodeBuilder, NodeClassSet, NodeId,
+    Rect, Role, Tree, TreeUpdate,
+};
+use ambient_core::{
+    hierarchy::{children, parent},
+    transform::local_to_world,
+    ui_scene,
+    window::window_scale_factor,
+};
+use ambient_ecs::{
+    components,
+    generated::{
+        components::core::{
+            layout::{height, width},
+            text::text,
+        },
+        messages,
+    },
+    query, world_events, Entity, EntityId, FrameEvent, FramedEventsReader, Message, System,
+    SystemGroup, World, WorldEventsExt,
+};
+use ambient_shared_types::{ModifiersState, VirtualKeyCode};
+use glam::{vec3, Vec2, Vec3};
+use itertools::Itertools;
+
+pub use ambient_ecs::generated::components::core::accessibility::{
+    accessibility_focus, accessibility_focus_order, accessibility_focusable, accessibility_label,
+    accessibility_role,
+};
+
+components!("accessibility", {
+    /// The changes of the accessibility tree of the UI that haven't been handed to the screen reader yet
+    @[Resource]
+    accessibility_tree_update: Option<TreeUpdate>,
+    /// Where the UI of this world is drawn in the window, in logical pixels
+    @[Resource]
+    accessibility_view_offset: Vec2,
+    /// Whether anything in the UI can be focused, in which case Tab moves the focus
+    @[Resource]
+    accessibility_navigable: bool,
+});
+
+/// The resources for the accessibility tree and keyboard navigation of a world.
+pub fn resources() -> Entity {
+    Entity::new()
+        .with(accessibility_focus(), EntityId::null())
+        .with_default(accessibility_tree_update())
+        .with_default(accessibility_view_offset())
+        .with_default(accessibility_navigable())
+}
+
+/// Moves the keyboard focus in response to keyboard input and screen readers, and updates the accessibility tree.
+pub fn client_systems() -> SystemGroup {
+    SystemGroup::new(
+        "accessibility",
+        vec![Box::new(AccessibilitySystem::default())],
+    )
+}
+
+/// The tree that is shown to screen readers before the UI has been read: the window, without any children.
+pub fn initial_tree() -> TreeUpdate {
+    let root = node_id(EntityId::resources());
+    TreeUpdate {
+        nodes: vec![(
+            root,
+            NodeBuilder::new(Role::Window).build(&mut NodeClassSet::lock_global()),
+        )],
+        tree: Some(Tree::new(root)),
+        focus: Some(root),
+    }
+}
+
+/// Whether the keyboard navigation uses the key that `event` presses, in which case it shouldn't reach the game.
+pub fn uses_keyboard_input(world: &World, event: &messages::WindowKeyboardInput) -> bool {
+    let Some(key) = event
+        .keycode
+        .as_deref()
+        .and_then(|key| VirtualKeyCode::from_str(key).ok())
+    else {
+        return false;
+    };
+    event.pressed && uses_key(world, key)
+}
+
+fn uses_key(world: &World, key: VirtualKeyCode) -> bool {
+    let focused = world
+        .resource_opt(accessibility_focus())
+        .map_or(false, |focus| !focus.is_null());
+    match key {
+        VirtualKeyCode::Tab => world
+            .resource_opt(accessibility_navigable())
+            .copied()
+            .unwrap_or(false),
+        // These are left to the game until something has been focused
+        VirtualKeyCode::Up
+        | VirtualKeyCode::Down
+        | VirtualKeyCode::Return
+        | VirtualKeyCode::Space
+        | VirtualKeyCode::Escape => focused,
+        _ => false,
+    }
+}
+
+/// Passes the requests of a screen reader on to the app, which turns them into messages with [action_message].
+pub struct ChannelActionHandler(pub flume::Sender<ActionRequest>);
+impl ActionHandler for ChannelActionHandler {
+    fn do_action(&self, request: ActionRequest) {
+        self.0.send(request).ok();
+    }
+}
+
+/// Adds the message for the `request` of a screen reader to `world`, if it is one that the UI handles.
+pub fn action_message(world: &mut World, request: &ActionRequest) {
+    let entity = EntityId(request.target.0.get());
+    let events = world.resource_mut(world_events());
+    match request.action {
+        Action::Focus => events.add_message(messages::AccessibilityFocusRequest::new(entity)),
+        Action::Default => events.add_message(messages::AccessibilityActivate::new(entity)),
+        _ => {}
+    }
+}
+
+/// The role of an `accessibility_role`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AccessibleRole {
+    Button,
+    Checkbox,
+    Link,
+    Slider,
+    TextInput,
+    Label,
+    Heading,
+    Image,
+    Group,
+    List,
+    ListItem,
+    Dialog,
+    Menu,
+    MenuItem,
+    Tab,
+}
+impl FromStr for AccessibleRole {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "button" => Self::Button,
+            "checkbox" => Self::Checkbox,
+            "link" => Self::Link,
+            "slider" => Self::Slider,
+            "text_input" => Self::TextInput,
+            "label" => Self::Label,
+            "heading" => Self::Heading,
+            "image" => Self::Image,
+            "group" => Self::Group,
+            "list" => Self::List,
+            "list_item" => Self::ListItem,
+            "dialog" => Self::Dialog,
+            "menu" => Self::Menu,
+            "menu_item" => Self::MenuItem,
+            "tab" => Self::Tab,
+            _ => return Err(()),
+        })
+    }
+}
+impl AccessibleRole {
+    fn role(self) -> Role {
+        match self {
+            Self::Button => Role::Button,
+            Self::Checkbox => Role::CheckBox,
+            Self::Link => Role::Link,
+            Self::Slider => Role::Slider,
+            Self::TextInput => Role::TextField,
+            Self::Label => Role::StaticText,
+            Self::Heading => Role::Heading,
+            Self::Image => Role::Image,
+            Self::Group => Role::Group,
+            Self::List => Role::List,
+            Self::ListItem => Role::ListItem,
+            Self::Dialog => Role::Dialog,
+            Self::Menu => Role::Menu,
+            Self::MenuItem => Role::MenuItem,
+            Self::Tab => Role::Tab,
+        }
+    }
+    /// Whether the text inside of an element with this role is its name, rather than separate labels
+    fn is_named_by_text(self) -> bool {
+        !matches!(self, Self::Group | Self::List | Self::Dialog | Self::Menu)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct AccessibleNode {
+    id: EntityId,
+    role: AccessibleRole,
+    name: String,
+    /// In physical pixels of the window
+    bounds: [f64; 4],
+    children: Vec<EntityId>,
+    focusable: bool,
+}
+
+/// The accessible nodes of the UI, and the focusable entities in focus order
+#[derive(Debug, Clone, Default, PartialEq)]
+struct AccessibleTree {
+    roots: Vec<EntityId>,
+    nodes: Vec<AccessibleNode>,
+    focus_order: Vec<EntityId>,
+}
+impl AccessibleTree {
+    fn build(world: &World) -> Self {
+        let scale_factor = world
+            .resource_opt(window_scale_factor())
+            .copied()
+            .unwrap_or(1.);
+        let offset = world
+            .resource_opt(accessibility_view_offset())
+            .copied()
+            .unwrap_or_default();
+        let mut builder = TreeBuilder {
+            world,
+            scale_factor,
+            offset,
+            tree: Self::default(),
+            focusable: Vec::new(),
+        };
+        let roots = query(())
+            .incl(ui_scene())
+            .excl(parent())
+            .iter(world, None)
+            .map(|(id, _)| id)
+            .sorted_by_key(|id| id.0)
+            .collect_vec();
+        for root in roots {
+            builder.visit(root, None, None);
+        }
+        let TreeBuilder {
+            mut tree,
+            focusable,
+            ..
+        } = builder;
+        tree.focus_order = focus_order(focusable);
+        tree
+    }
+
+    /// The update that turns `last`, the tree and focus that the screen reader has, into this tree and `focus`. Only
+    /// the nodes that changed are sent; the removed ones are dropped by their parents.
+    fn update(&self, last: Option<&(Self, EntityId)>, focus: EntityId) -> TreeUpdate {
+        let last_nodes: HashMap<_, _> = last
+            .iter()
+            .flat_map(|(tree, _)| &tree.nodes)
+            .map(|node| (node.id, node))
+            .collect();
+        let mut classes = NodeClassSet::lock_global();
+        let root = node_id(EntityId::resources());
+        let mut nodes = Vec::new();
+        if last.map_or(true, |(tree, _)| tree.roots != self.roots) {
+            let mut window = NodeBuilder::new(Role::Window);
+            window.set_children(self.roots.iter().copied().map(node_id).collect_vec());
+            nodes.push((root, window.build(&mut classes)));
+        }
+        for node in &self.nodes {
+            if last_nodes.get(&node.id) == Some(&node) {
+                continue;
+            }
+            let mut builder = NodeBuilder::new(node.role.role());
+            if !node.name.is_empty() {
+                builder.set_name(node.name.clone());
+            }
+            let [x0, y0, x1, y1] = node.bounds;
+            builder.set_bounds(Rect { x0, y0, x1, y1 });
+            builder.set_children(node.children.iter().copied().map(node_id).collect_vec());
+            if node.focusable {
+                builder.add_action(Action::Focus);
+                builder.add_action(Action::Default);
+                builder.set_default_action_verb(DefaultActionVerb::Click);
+            }
+            nodes.push((node_id(node.id), builder.build(&mut classes)));
+        }
+        TreeUpdate {
+            nodes,
+            tree: last.is_none().then(|| Tree::new(root)),
+            focus: Some(if focus.is_null() {
+                root
+            } else {
+                node_id(focus)
+            }),
+        }
+    }
+}
+
+/// Applies `update` to `tree`, a whole tree, and drops the nodes that are no longer in it.
+pub fn apply_tree_update(tree: &mut TreeUpdate, update: &TreeUpdate) {
+    let mut nodes: HashMap<_, _> = std::mem::take(&mut tree.nodes)
+        .into_iter()
+        .chain(update.nodes.iter().cloned())
+        .collect();
+    if let Some(new_tree) = &update.tree {
+        tree.tree = Some(new_tree.clone());
+    }
+    tree.focus = update.focus;
+    let mut stack = tree.tree.iter().map(|tree| tree.root).collect_vec();
+    while let Some(id) = stack.pop() {
+        if let Some(node) = nodes.remove(&id) {
+            stack.extend(node.children().iter().rev().copied());
+            tree.nodes.push((id, node));
+        }
+    }
+}
+
+struct TreeBuilder<'a> {
+    world: &'a World,
+    scale_factor: f64,
+    offset: Vec2,
+    tree: AccessibleTree,
+    /// The focusable entities in tree order, with their `accessibility_focus_order`
+    focusable: Vec<(i32, EntityId)>,
+}
+impl<'a> TreeBuilder<'a> {
+    /// Adds `id` and its descendants to the tree, under the node at `parent`; their text is added to the name of the
+    /// node at `named_by_text`
+    fn visit(&mut self, id: EntityId, mut parent: Option<usize>, mut named_by_text: Option<usize>) {
+        let world = self.world;
+        // Unknown roles are exposed as groups
+        let role = world
+            .get_ref(id, accessibility_role())
+            .ok()
+            .map(|role| AccessibleRole::from_str(role).unwrap_or(AccessibleRole::Group));
+        let label = world.get_ref(id, accessibility_label()).ok();
+        let focusable = world.has_component(id, accessibility_focusable());
+        let text = world
+            .get_ref(id, text())
+            .ok()
+            .filter(|text| !text.is_empty());
+
+        if role.is_some() || label.is_some() || focusable {
+            let role = role.unwrap_or(AccessibleRole::Group);
+            let index = self.add_node(AccessibleNode {
+                id,
+                role,
+                name: label.cloned().unwrap_or_default(),
+                bounds: self.bounds(id),
+                children: Vec::new(),
+                focusable,
+            });
+            if focusable {
+                let order = world.get(id, accessibility_focus_order()).unwrap_or(0);
+                self.focusable.push((order, id));
+            }
+            match parent {
+                Some(parent) => self.tree.nodes[parent].children.push(id),
+                None => self.tree.roots.push(id),
+            }
+            parent = Some(index);
+            named_by_text = role.is_named_by_text().then_some(index);
+            if let Some(text) = text.filter(|_| named_by_text.is_some() && label.is_none()) {
+                self.tree.nodes[index].name = text.clone();
+            }
+        } else if let Some(text) = text {
+            match named_by_text {
+                // The text of a labelled node is replaced by its label
+                Some(node) if self.is_labelled(node) => {}
+                Some(node) => {
+                    let name = &mut self.tree.nodes[node].name;
+                    if !name.is_empty() {
+                        name.push(' ');
+                    }
+                    name.push_str(text);
+                }
+                None => {
+                    self.add_node(AccessibleNode {
+                        id,
+                        role: AccessibleRole::Label,
+                        name: text.clone(),
+                        bounds: self.bounds(id),
+                        children: Vec::new(),
+                        focusable: false,
+                    });
+                    match parent {
+                        Some(parent) => self.tree.nodes[parent].children.push(id),
+                        None => self.tree.roots.push(id),
+                    }
+                }
+            }
+        }
+
+        if let Ok(children) = world.get_ref(id, children()) {
+            for &child in children {
+                self.visit(child, parent, named_by_text);
+            }
+        }
+    }
+
+    fn add_node(&mut self, node: AccessibleNode) -> usize {
+        self.tree.nodes.push(node);
+        self.tree.nodes.len() - 1
+    }
+
+    fn is_labelled(&self, node: usize) -> bool {
+        self.world
+            .has_component(self.tree.nodes[node].id, accessibility_label())
+    }
+
+    fn bounds(&self, id: EntityId) -> [f64; 4] {
+        let transform = self.world.get(id, local_to_world()).unwrap_or_default();
+        let size = vec3(
+            self.world.get(id, width()).unwrap_or(0.),
+            self.world.get(id, height()).unwrap_or(0.),
+            0.,
+        );
+        let min = transform.transform_point3(Vec3::ZERO).truncate() + self.offset;
+        let max = transform.transform_point3(size).truncate() + self.offset;
+        let (min, max) = (min.min(max), min.max(max));
+        [min.x, min.y, max.x, max.y].map(|value| value as f64 * self.scale_factor)
+    }
+}
+
+/// The focusable entities, sorted by their `accessibility_focus_order` and then by the order they are in the tree
+fn focus_order(mut focusable: Vec<(i32, EntityId)>) -> Vec<EntityId> {
+    // The sort is stable, so equal orders stay in tree order
+    focusable.sort_by_key(|(order, _)| *order);
+    focusable.into_iter().map(|(_, id)| id).collect()
+}
+
+/// The entity after `current` in `order`, or before it if `backwards`, wrapping around at either end
+fn next_focus(order: &[EntityId], current: EntityId, backwards: bool) -> Option<EntityId> {
+    if order.is_empty() {
+        return None;
+    }
+    let index = match order.iter().position(|id| *id == current) {
+        Some(index) if backwards => (index + order.len() - 1) % order.len(),
+        Some(index) => (index + 1) % order.len(),
+        None if backwards => order.len() - 1,
+        None => 0,
+    };
+    Some(order[index])
+}
+
+fn node_id(id: EntityId) -> NodeId {
+    NodeId(NonZeroU128::new(id.0).expect("The null entity is never in the accessibility tree"))
+}
+
+#[derive(Debug, Default)]
+struct AccessibilitySystem {
+    reader: Option<FramedEventsReader<(String, Vec<u8>)>>,
+    /// The tree and focus that the screen reader has
+    sent: Option<(AccessibleTree, EntityId)>,
+    /// The tree and focus of the update that hasn't been handed to the screen reader yet
+    pending: Option<(AccessibleTree, EntityId)>,
+}
+impl System for AccessibilitySystem {
+    fn run(&mut self, world: &mut World, _: &FrameEvent) {
+        let tree = AccessibleTree::build(world);
+        let mut focus = world
+            .resource_opt(accessibility_focus())
+            .copied()
+            .unwrap_or_default();
+        // The focused entity may have been despawned, or have stopped being focusable
+        if !focus.is_null() && !tree.focus_order.contains(&focus) {
+            focus = EntityId::null();
+        }
+
+        let reader = self
+            .reader
+            .get_or_insert_with(|| world.resource(world_events()).reader());
+        let events = reader
+            .iter(world.resource(world_events()))
+            .map(|(_, event)| event.clone())
+            .collect_vec();
+        let mut activated = Vec::new();
+        for (name, data) in events {
+            if name == messages::WindowKeyboardInput::id() {
+                let Ok(event) = messages::WindowKeyboardInput::deserialize_message(&data) else {
+                    continue;
+                };
+                let Some(key) = event
+                    .keycode
+                    .as_deref()
+                    .and_then(|key| VirtualKeyCode::from_str(key).ok())
+                else {
+                    continue;
+                };
+                if !event.pressed {
+                    continue;
+                }
+                let shift = ModifiersState::from_bits(event.modifiers)
+                    .map_or(false, |modifiers| modifiers.shift());
+                match key {
+                    VirtualKeyCode::Tab => {
+                        focus = next_focus(&tree.focus_order, focus, shift).unwrap_or(focus);
+                    }
+                    // The arrow keys are left to the game until something has been focused
+                    VirtualKeyCode::Up | VirtualKeyCode::Down if !focus.is_null() => {
+                        focus = next_focus(&tree.focus_order, focus, key == VirtualKeyCode::Up)
+                            .unwrap_or(focus);
+                    }
+                    VirtualKeyCode::Return | VirtualKeyCode::Space if !focus.is_null() => {
+                        activated.push(focus);
+                    }
+                    VirtualKeyCode::Escape => focus = EntityId::null(),
+                    _ => {}
+                }
+            } else if name == messages::AccessibilityFocusRequest::id() {
+                if let Ok(event) = messages::AccessibilityFocusRequest::deserialize_message(&data) {
+                    if tree.focus_order.contains(&event.entity) {
+                        focus = event.entity;
+                    }
+                }
+            }
+        }
+        for entity in activated {
+            world
+                .resource_mut(world_events())
+                .add_message(messages::AccessibilityActivate::new(entity));
+        }
+        world
+            .set_if_changed(world.resource_entity(), accessibility_focus(), focus)
+            .ok();
+        world
+            .set_if_changed(
+                world.resource_entity(),
+                accessibility_navigable(),
+                !tree.focus_order.is_empty(),
+            )
+            .ok();
+
+        let handed_over = world
+            .resource_opt(accessibility_tree_update())
+            .map_or(false, Option::is_none);
+        if handed_over {
+            if let Some(pending) = self.pending.take() {
+                self.sent = Some(pending);
+            }
+        }
+        let changed = match self.pending.as_ref().or(self.sent.as_ref()) {
+            Some((latest, latest_focus)) => *latest != tree || *latest_focus != focus,
+            None => true,
+        };
+        if changed {
+            // The update replaces the pending one, so it starts from what the screen reader has
+            let update = tree.update(self.sent.as_ref(), focus);
+            world
+                .set(
+                    world.resource_entity(),
+                    accessibility_tree_update(),
+                    Some(update),
+                )
+                .ok();
+            self.pending = Some((tree, focus));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn focus_order_keeps_tree_order_among_equal_orders() {
+        let ids = (2..6).map(EntityId).collect_vec();
+        let order = focus_order(vec![(0, ids[0]), (1, ids[1]), (0, ids[2]), (-1, ids[3])]);
+        assert_eq!(order, vec![ids[3], ids[0], ids[2], ids[1]]);
+    }
+
+    #[test]
+    fn next_focus_wraps_around() {
+        let ids = (2..5).map(EntityId).collect_vec();
+        assert_eq!(next_focus(&ids, EntityId::null(), false), Some(ids[0]));
+        assert_eq!(next_focus(&ids, EntityId::null(), true), Some(ids[2]));
+        assert_eq!(next_focus(&ids, ids[2], false), Some(ids[0]));
+        assert_eq!(next_focus(&ids, ids[0], true), Some(ids[2]));
+        assert_eq!(next_focus(&[], ids[0], false), None);
+    }
+
+    fn node(id: u128, name: &str, children: &[u128]) -> AccessibleNode {
+        AccessibleNode {
+            id: EntityId(id),
+            role: AccessibleRole::Button,
+            name: name.to_string(),
+            bounds: [0.; 4],
+            children: children.iter().copied().map(EntityId).collect(),
+            focusable: false,
+        }
+    }
+
+    fn node_ids(update: &TreeUpdate) -> Vec<NodeId> {
+        update.nodes.iter().map(|(id, _)| *id).collect()
+    }
+
+    #[test]
+    fn updates_only_send_changed_nodes() {
+        let root = node_id(EntityId::resources());
+        let mut tree = AccessibleTree {
+            roots: vec![EntityId(2)],
+            nodes: vec![node(2, "", &[3, 4]), node(3, "a", &[]), node(4, "b", &[])],
+            focus_order: Vec::new(),
+        };
+        let first = tree.update(None, EntityId::null());
+        assert!(first.tree.is_some());
+        assert_eq!(first.nodes.len(), 4);
+
+        let last = (tree.clone(), EntityId::null());
+        tree.nodes[2].name = "c".to_string();
+        let update = tree.update(Some(&last), EntityId(4));
+        assert!(update.tree.is_none());
+        assert_eq!(node_ids(&update), vec![node_id(EntityId(4))]);
+        assert_eq!(update.focus, Some(node_id(EntityId(4))));
+
+        // Removing a node changes its parent
+        let last = (tree.clone(), EntityId::null());
+        tree.nodes[0].children.pop();
+        tree.nodes.pop();
+        let removal = tree.update(Some(&last), EntityId::null());
+        assert_eq!(node_ids(&removal), vec![node_id(EntityId(2))]);
+
+        let mut whole = first;
+        apply_tree_update(&mut whole, &update);
+        apply_tree_update(&mut whole, &removal);
+        assert_eq!(
+            node_ids(&whole),
+            vec![root, node_id(EntityId(2)), node_id(EntityId(3))]
+        );
+        assert_eq!(whole.focus, Some(root));
+    }
+
+    #[test]
+    fn keyboard_navigation_uses_keys_only_when_needed() {
+        ambient_ecs::init_components();
+        init_components();
+        let mut world = World::new("keyboard_navigation_uses_keys_only_when_needed");
+        world
+            .add_components(world.resource_entity(), resources())
+            .unwrap();
+        let press = |key: VirtualKeyCode| {
+            messages::WindowKeyboardInput::new(Some(key.to_string()), 0, true)
+        };
+
+        assert!(!uses_keyboard_input(&world, &press(VirtualKeyCode::Tab)));
+        world.add_resource(accessibility_navigable(), true);
+        assert!(uses_keyboard_input(&world, &press(VirtualKeyCode::Tab)));
+        assert!(!uses_keyboard_input(&world, &press(VirtualKeyCode::Return)));
+        assert!(!uses_keyboard_input(&world, &press(VirtualKeyCode::Space)));
+
+        world.add_resource(accessibility_focus(), EntityId(2));
+        assert!(uses_keyboard_input(&world, &press(VirtualKeyCode::Return)));
+        assert!(uses_keyboard_input(&world, &press(VirtualKeyCode::Space)));
+        assert!(!uses_keyboard_input(&world, &press(VirtualKeyCode::W)));
+        let release =
+            messages::WindowKeyboardInput::new(Some(VirtualKeyCode::Space.to_string()), 0, false);
+        assert!(!uses_keyboard_input(&world, &release));
+    }
+}
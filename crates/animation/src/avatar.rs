@@ -0,0 +1,376 @@
+use std::f32::consts::PI;
+
+use ambient_core::{
+    dtime,
+    hierarchy::parent,
+    transform::{get_world_transform, rotation, scale, translation},
+};
+use ambient_ecs::{query, Component, ComponentValue, EntityId, SystemGroup, World};
+use ambient_model::animation_binder;
+use glam::{Mat4, Quat, Vec3};
+
+use crate::{
+    animated_world_transform, avatar_state, ik_chain_length, ik_target, ik_target_position,
+};
+
+pub use ambient_ecs::generated::components::core::animation::{
+    avatar_head_target, avatar_left_hand_target, avatar_right_hand_target, avatar_step_length,
+};
+
+const DEFAULT_STEP_LENGTH: f32 = 0.3;
+/// How long a step takes, in seconds
+const STEP_DURATION: f32 = 0.25;
+/// How high a foot is lifted halfway through a step
+const STEP_HEIGHT: f32 = 0.08;
+/// Feet that are this many step lengths away from the body are moved under it at once, instead of stepping there
+const TELEPORT_STEPS: f32 = 4.;
+/// The joints above the head that bend towards the head target: the neck and the spine
+const SPINE_CHAIN_LENGTH: u32 = 3;
+
+const FEET: [&str; 2] = ["LeftFoot", "RightFoot"];
+
+#[derive(Debug, Clone, Default)]
+pub struct AvatarState {
+    rest: Option<RestPose>,
+    /// The local rotation and translation that the hips were given on the last frame, which they still have unless an
+    /// animation has moved them since
+    solved_hips: Option<(Quat, Vec3)>,
+    planted: [Option<Vec3>; 2],
+    step: Option<AvatarStep>,
+}
+
+/// The pose of a skeleton that its avatar is solved from: the animated pose if it is animated, and the pose it was first
+/// seen in otherwise. The avatar moves the hips and the IK bends the spine and legs on every frame, so solving from the
+/// pose they were left in would add up the moves of each frame.
+#[derive(Debug, Clone, Copy)]
+struct RestPose {
+    /// The local rotation and translation of the hips
+    hips: (Quat, Vec3),
+    /// The position of the head, relative to the hips
+    head: Vec3,
+    /// The positions of the feet, relative to the hips
+    feet: [Vec3; 2],
+}
+
+#[derive(Debug, Clone)]
+struct AvatarStep {
+    foot: usize,
+    from: Vec3,
+    to: Vec3,
+    progress: f32,
+}
+
+/// Poses the skeletons of the models with an `avatar_head_target`, by moving their hips and setting up the IK of their
+/// spine, hands and feet. Must run before the [ik_systems](crate::ik_systems).
+pub fn avatar_systems() -> SystemGroup {
+    SystemGroup::new(
+        "avatar_systems",
+        vec![query(avatar_head_target()).to_system(|q, world, qs, _| {
+            let dt = *world.resource(dtime());
+            for (id, head_target) in q.collect_cloned(world, qs) {
+                solve_avatar(world, id, head_target, dt);
+            }
+        })],
+    )
+}
+
+fn solve_avatar(world: &mut World, id: EntityId, head_target: EntityId, dt: f32) -> Option<()> {
+    let binder = world.get_ref(id, animation_binder()).ok()?.clone();
+    let hips = *binder.get("Hips")?;
+    let head = *binder.get("Head")?;
+    let feet = [*binder.get(FEET[0])?, *binder.get(FEET[1])?];
+    let (_, target_rotation, target_position) = get_world_transform(world, head_target)
+        .ok()?
+        .to_scale_rotation_translation();
+    let (_, root_rotation, _) = get_world_transform(world, id)
+        .ok()?
+        .to_scale_rotation_translation();
+    let mut state = world
+        .get_ref(id, avatar_state())
+        .cloned()
+        .unwrap_or_default();
+
+    let hips_local = (
+        world.get(hips, rotation()).unwrap_or(Quat::IDENTITY),
+        world.get(hips, translation()).unwrap_or(Vec3::ZERO),
+    );
+    let rest = match state.rest {
+        Some(rest) if state.solved_hips == Some(hips_local) => rest,
+        _ => {
+            let to_hips = animated_world_transform(world, hips).inverse();
+            let relative = |joint| {
+                to_hips.transform_point3(animated_world_transform(world, joint).w_axis.truncate())
+            };
+            RestPose {
+                hips: hips_local,
+                head: relative(head),
+                feet: feet.map(relative),
+            }
+        }
+    };
+    state.rest = Some(rest);
+
+    // Move the hips so that the head is under the target, and turn them the way the target faces. The head can go down
+    // to crouch, but not up, which would lift the feet off the ground.
+    let hips_parent = world
+        .get(hips, parent())
+        .map(|parent| animated_world_transform(world, parent))
+        .unwrap_or_default();
+    let hips_before = hips_parent
+        * Mat4::from_scale_rotation_translation(
+            world.get(hips, scale()).unwrap_or(Vec3::ONE),
+            rest.hips.0,
+            rest.hips.1,
+        );
+    let head_before = hips_before.transform_point3(rest.head);
+    let feet_before = rest.feet.map(|foot| hips_before.transform_point3(foot));
+    let mut offset = target_position - head_before;
+    offset.z = offset.z.min(0.);
+    let head_after = head_before + offset;
+    let turn = Quat::from_rotation_z(yaw(target_rotation) - yaw(root_rotation));
+    let (hips_scale, hips_rotation, hips_position) = hips_before.to_scale_rotation_translation();
+    let hips_after = Mat4::from_scale_rotation_translation(
+        hips_scale,
+        turn * hips_rotation,
+        head_after + turn * (hips_position + offset - head_after),
+    );
+    let (_, local_rotation, local_translation) =
+        (hips_parent.inverse() * hips_after).to_scale_rotation_translation();
+    world.set_if_changed(hips, rotation(), local_rotation).ok();
+    world
+        .set_if_changed(hips, translation(), local_translation)
+        .ok();
+    state.solved_hips = Some((local_rotation, local_translation));
+
+    // The spine and arms reach for their targets
+    set_or_add(world, head, ik_target(), head_target);
+    if !world.has_component(head, ik_chain_length()) {
+        world
+            .add_component(head, ik_chain_length(), SPINE_CHAIN_LENGTH)
+            .ok();
+    }
+    for (component, joint) in [
+        (avatar_left_hand_target(), "LeftHand"),
+        (avatar_right_hand_target(), "RightHand"),
+    ] {
+        if let (Ok(target), Some(&joint)) = (world.get(id, component), binder.get(joint)) {
+            set_or_add(world, joint, ik_target(), target);
+        }
+    }
+
+    // The feet rest where the animation puts them relative to the hips, and step there when the body has moved too far
+    // away from where they are planted
+    let body_motion = hips_after * hips_before.inverse();
+    let rest = feet_before.map(|foot| {
+        let moved = body_motion.transform_point3(foot);
+        Vec3::new(moved.x, moved.y, foot.z)
+    });
+    let step_length = world
+        .get(id, avatar_step_length())
+        .unwrap_or(DEFAULT_STEP_LENGTH);
+    for (planted, rest) in state.planted.iter_mut().zip(rest) {
+        match planted {
+            Some(planted) if planted.distance(rest) < step_length * TELEPORT_STEPS => {}
+            _ => *planted = Some(rest),
+        }
+    }
+    if let Some(step) = &mut state.step {
+        step.progress += dt / STEP_DURATION;
+        if step.progress >= 1. {
+            state.planted[step.foot] = Some(step.to);
+            state.step = None;
+        }
+    }
+    if state.step.is_none() {
+        // The foot that is furthest behind goes first
+        let (foot, distance) = (0..2)
+            .map(|i| (i, state.planted[i].unwrap().distance(rest[i])))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap();
+        if distance > step_length {
+            state.step = Some(AvatarStep {
+                foot,
+                from: state.planted[foot].unwrap(),
+                to: rest[foot],
+                progress: 0.,
+            });
+        }
+    }
+    for (i, &foot) in feet.iter().enumerate() {
+        let position = match &state.step {
+            Some(step) if step.foot == i => {
+                step.from.lerp(step.to, step.progress)
+                    + Vec3::Z * STEP_HEIGHT * (step.progress * PI).sin()
+            }
+            _ => state.planted[i].unwrap(),
+        };
+        set_or_add(world, foot, ik_target_position(), position);
+    }
+    match world.get_mut(id, avatar_state()) {
+        Ok(old) => *old = state,
+        Err(_) => world.add_component(id, avatar_state(), state).unwrap(),
+    }
+    Some(())
+}
+
+/// Sets a component that the avatar drives, and adds it the first time, so that the joints aren't moved between
+/// archetypes or marked as changed on every frame
+fn set_or_add<T: ComponentValue + PartialEq>(
+    world: &mut World,
+    id: EntityId,
+    component: Component<T>,
+    value: T,
+) {
+    if world.has_component(id, component) {
+        world.set_if_changed(id, component, value).ok();
+    } else {
+        world.add_component(id, component, value).ok();
+    }
+}
+
+/// The angle of a rotation around the up axis
+fn yaw(rotation: Quat) -> f32 {
+    let forward = rotation * Vec3::X;
+    forward.y.atan2(forward.x)
+}
+
+#[cfg(test)]
+mod test {
+    use ambient_core::transform::local_to_parent;
+    use ambient_ecs::Entity;
+    use glam::vec3;
+
+    use super::*;
+
+    fn init() {
+        ambient_ecs::init_components();
+        ambient_core::init_all_components();
+        ambient_model::init_components();
+        crate::init_components();
+    }
+
+    struct Avatar {
+        id: EntityId,
+        hips: EntityId,
+        feet: [EntityId; 2],
+        target: EntityId,
+    }
+
+    /// A skeleton standing at the origin with its head 1.7 m up, and a head target where its head is
+    fn spawn_avatar(world: &mut World) -> Avatar {
+        let id = Entity::new()
+            .with(translation(), Vec3::ZERO)
+            .with(rotation(), Quat::IDENTITY)
+            .spawn(world);
+        let joint = |world: &mut World, parent_id, position| {
+            Entity::new()
+                .with(parent(), parent_id)
+                .with(local_to_parent(), Mat4::IDENTITY)
+                .with(translation(), position)
+                .with(rotation(), Quat::IDENTITY)
+                .spawn(world)
+        };
+        let hips = joint(world, id, vec3(0., 0., 1.));
+        let head = joint(world, hips, vec3(0., 0., 0.7));
+        let feet = [
+            joint(world, hips, vec3(0.1, 0., -1.)),
+            joint(world, hips, vec3(-0.1, 0., -1.)),
+        ];
+        world
+            .add_component(
+                id,
+                animation_binder(),
+                [
+                    ("Hips", hips),
+                    ("Head", head),
+                    (FEET[0], feet[0]),
+                    (FEET[1], feet[1]),
+                ]
+                .into_iter()
+                .map(|(name, joint)| (name.to_string(), joint))
+                .collect(),
+            )
+            .unwrap();
+        let target = Entity::new()
+            .with(translation(), vec3(0., 0., 1.7))
+            .with(rotation(), Quat::IDENTITY)
+            .spawn(world);
+        Avatar {
+            id,
+            hips,
+            feet,
+            target,
+        }
+    }
+
+    #[test]
+    fn hips_turn_with_the_head_once() {
+        init();
+        let mut world = World::new("hips_turn_with_the_head_once");
+        let avatar = spawn_avatar(&mut world);
+        world
+            .set(avatar.target, rotation(), Quat::from_rotation_z(PI / 2.))
+            .unwrap();
+        for _ in 0..3 {
+            solve_avatar(&mut world, avatar.id, avatar.target, 0.1).unwrap();
+            let hips = world.get(avatar.hips, rotation()).unwrap();
+            assert!(hips.angle_between(Quat::from_rotation_z(PI / 2.)) < 0.001);
+            let hips = world.get(avatar.hips, translation()).unwrap();
+            assert!(hips.distance(vec3(0., 0., 1.)) < 0.001);
+        }
+    }
+
+    #[test]
+    fn hips_crouch_but_dont_lift_off() {
+        init();
+        let mut world = World::new("hips_crouch_but_dont_lift_off");
+        let avatar = spawn_avatar(&mut world);
+        world
+            .set(avatar.target, translation(), vec3(0., 0., 1.2))
+            .unwrap();
+        solve_avatar(&mut world, avatar.id, avatar.target, 0.1).unwrap();
+        let hips = world.get(avatar.hips, translation()).unwrap();
+        assert!(hips.distance(vec3(0., 0., 0.5)) < 0.001);
+
+        world
+            .set(avatar.target, translation(), vec3(0., 0., 2.5))
+            .unwrap();
+        solve_avatar(&mut world, avatar.id, avatar.target, 0.1).unwrap();
+        let hips = world.get(avatar.hips, translation()).unwrap();
+        assert!(hips.distance(vec3(0., 0., 1.)) < 0.001);
+    }
+
+    #[test]
+    fn feet_step_after_the_body() {
+        init();
+        let mut world = World::new("feet_step_after_the_body");
+        let avatar = spawn_avatar(&mut world);
+        solve_avatar(&mut world, avatar.id, avatar.target, 0.1).unwrap();
+        world
+            .set(avatar.target, translation(), vec3(1., 0., 1.7))
+            .unwrap();
+        for _ in 0..20 {
+            solve_avatar(&mut world, avatar.id, avatar.target, 0.1).unwrap();
+        }
+        for (foot, x) in avatar.feet.into_iter().zip([1.1, 0.9]) {
+            let position = world.get(foot, ik_target_position()).unwrap();
+            assert!(position.distance(vec3(x, 0., 0.)) < 0.001, "{position}");
+        }
+
+        // Once the feet are planted, their targets are left alone
+        let versions = avatar.feet.map(|foot| {
+            world
+                .get_component_content_version(foot, ik_target_position().index())
+                .unwrap()
+        });
+        solve_avatar(&mut world, avatar.id, avatar.target, 0.1).unwrap();
+        for (foot, version) in avatar.feet.into_iter().zip(versions) {
+            assert_eq!(
+                world
+                    .get_component_content_version(foot, ik_target_position().index())
+                    .unwrap(),
+                version
+            );
+        }
+    }
+}
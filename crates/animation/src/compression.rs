@@ -0,0 +1,338 @@
+use std::f32::consts::SQRT_2;
+
+use ambient_core::transform::euler_rotation;
+use glam::Quat;
+use serde::{Deserialize, Serialize};
+
+use crate::{AnimationClip, AnimationOutput, AnimationOutputs, AnimationTrack};
+
+/// The largest value of a quantized quaternion component; each one gets 15 bits
+const QUANTIZED_MAX: f32 = 32767.;
+const QUANTIZED_MASK: u16 = 0x7fff;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnimationCompression {
+    /// How far translation, scale and morph weight keyframes may be from the original curve when keyframes are removed, in
+    /// model units.
+    pub max_error: f32,
+    /// How far rotation keyframes may be from the original curve when keyframes are removed, in radians.
+    pub max_rotation_error: f32,
+    /// Store rotations in 6 bytes per keyframe instead of 16.
+    pub quantize_rotations: bool,
+}
+impl Default for AnimationCompression {
+    fn default() -> Self {
+        Self {
+            max_error: 0.0001,
+            max_rotation_error: 0.0005,
+            quantize_rotations: true,
+        }
+    }
+}
+
+impl AnimationClip {
+    /// Removes the keyframes that interpolating their neighbours reproduces, and quantizes the rotations, as configured
+    pub fn compress(&mut self, config: &AnimationCompression) {
+        for track in &mut self.tracks {
+            let max_error = match &track.outputs {
+                AnimationOutputs::Quat { .. } | AnimationOutputs::QuatQuantized { .. } => {
+                    config.max_rotation_error
+                }
+                outputs if outputs.component() == euler_rotation() => config.max_rotation_error,
+                _ => config.max_error,
+            };
+            track.reduce_keyframes(max_error);
+            if config.quantize_rotations {
+                track.outputs.quantize_rotations();
+            }
+        }
+    }
+}
+
+impl AnimationTrack {
+    /// Fits line segments (or arcs, for rotations) through the keyframes, and removes the keyframes the segments pass within
+    /// `max_error` of. The first and last keyframes are always kept, so the duration of the track stays the same.
+    pub fn reduce_keyframes(&mut self, max_error: f32) {
+        let n = self.inputs.len();
+        if n <= 2 {
+            return;
+        }
+        let mut keep = vec![0];
+        let mut start = 0;
+        for end in 2..n {
+            let span = self.inputs[end] - self.inputs[start];
+            let fits = (start + 1..end).all(|i| {
+                let p = if span == 0. {
+                    0.
+                } else {
+                    (self.inputs[i] - self.inputs[start]) / span
+                };
+                let fitted = self.outputs.value(start).mix(self.outputs.value(end), p);
+                fitted.distance(&self.outputs.value(i)) <= max_error
+            });
+            if !fits {
+                keep.push(end - 1);
+                start = end - 1;
+            }
+        }
+        keep.push(n - 1);
+        if keep.len() == n {
+            return;
+        }
+        self.inputs = keep.iter().map(|&i| self.inputs[i]).collect();
+        self.outputs = self.outputs.select(&keep);
+    }
+}
+
+impl AnimationOutputs {
+    /// Replaces [AnimationOutputs::Quat] data with [AnimationOutputs::QuatQuantized] data
+    pub fn quantize_rotations(&mut self) {
+        if let AnimationOutputs::Quat { component, data } = self {
+            *self = AnimationOutputs::QuatQuantized {
+                component: *component,
+                data: data.iter().map(|&q| quantize_quat(q)).collect(),
+            };
+        }
+    }
+    /// Replaces [AnimationOutputs::QuatQuantized] data with [AnimationOutputs::Quat] data, so that it can be edited
+    pub fn dequantize_rotations(&mut self) {
+        if let AnimationOutputs::QuatQuantized { component, data } = self {
+            *self = AnimationOutputs::Quat {
+                component: *component,
+                data: data.iter().map(|&q| dequantize_quat(q)).collect(),
+            };
+        }
+    }
+    fn select(&self, indices: &[usize]) -> Self {
+        match self {
+            AnimationOutputs::Vec3 { component, data } => AnimationOutputs::Vec3 {
+                component: *component,
+                data: indices.iter().map(|&i| data[i]).collect(),
+            },
+            AnimationOutputs::Quat { component, data } => AnimationOutputs::Quat {
+                component: *component,
+                data: indices.iter().map(|&i| data[i]).collect(),
+            },
+            AnimationOutputs::Vec3Field {
+                component,
+                field,
+                data,
+            } => AnimationOutputs::Vec3Field {
+                component: *component,
+                field: *field,
+                data: indices.iter().map(|&i| data[i]).collect(),
+            },
+            AnimationOutputs::QuatQuantized { component, data } => {
+                AnimationOutputs::QuatQuantized {
+                    component: *component,
+                    data: indices.iter().map(|&i| data[i]).collect(),
+                }
+            }
+            AnimationOutputs::Weights { component, data } => AnimationOutputs::Weights {
+                component: *component,
+                data: indices.iter().map(|&i| data[i].clone()).collect(),
+            },
+        }
+    }
+}
+
+impl AnimationOutput {
+    /// The distance between two values of the same kind; the angle between them for rotations
+    fn distance(&self, other: &AnimationOutput) -> f32 {
+        match (self, other) {
+            (AnimationOutput::Vec3 { value: a, .. }, AnimationOutput::Vec3 { value: b, .. }) => {
+                a.distance(*b)
+            }
+            (AnimationOutput::Quat { value: a, .. }, AnimationOutput::Quat { value: b, .. }) => {
+                rotation_angle(*a, *b)
+            }
+            (
+                AnimationOutput::Vec3Field { value: a, .. },
+                AnimationOutput::Vec3Field { value: b, .. },
+            ) => (a - b).abs(),
+            (
+                AnimationOutput::Weights { value: a, .. },
+                AnimationOutput::Weights { value: b, .. },
+            ) => a
+                .iter()
+                .zip(b)
+                .map(|(a, b)| (a - b).abs())
+                .fold(0., f32::max),
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// The angle between two rotations. Unlike [Quat::angle_between], which goes through the dot product, this stays precise
+/// for the tiny angles the rotation errors are compared with.
+fn rotation_angle(a: Quat, b: Quat) -> f32 {
+    let chord = (a - b).length().min((a + b).length());
+    4. * (chord * 0.5).min(1.).asin()
+}
+
+/// Encodes a rotation as its three smallest components, with 15 bits each. The largest component is left out, as it
+/// follows from the others, and its index is kept in the top bits of the first two.
+pub fn quantize_quat(value: Quat) -> [u16; 3] {
+    let mut v = value.normalize().to_array();
+    let largest = (0..4)
+        .max_by(|&a, &b| v[a].abs().total_cmp(&v[b].abs()))
+        .unwrap();
+    if v[largest] < 0. {
+        v = v.map(|x| -x);
+    }
+    let mut res = [0; 3];
+    for (out, x) in res.iter_mut().zip(
+        v.iter()
+            .enumerate()
+            .filter(|(i, _)| *i != largest)
+            .map(|(_, x)| *x),
+    ) {
+        // The smallest components are within -1/sqrt(2)..1/sqrt(2)
+        *out = ((x * SQRT_2 * 0.5 + 0.5).clamp(0., 1.) * QUANTIZED_MAX).round() as u16;
+    }
+    res[0] |= ((largest >> 1) as u16) << 15;
+    res[1] |= ((largest & 1) as u16) << 15;
+    res
+}
+
+/// Decodes a rotation encoded by [quantize_quat]
+pub fn dequantize_quat(value: [u16; 3]) -> Quat {
+    let largest = (((value[0] >> 15) << 1) | (value[1] >> 15)) as usize;
+    let mut v = [0.; 4];
+    let mut smallest = value
+        .iter()
+        .map(|x| ((x & QUANTIZED_MASK) as f32 / QUANTIZED_MAX - 0.5) * SQRT_2);
+    for (i, x) in v.iter_mut().enumerate() {
+        if i != largest {
+            *x = smallest.next().unwrap();
+        }
+    }
+    v[largest] = (1. - v.iter().map(|x| x * x).sum::<f32>()).max(0.).sqrt();
+    Quat::from_array(v).normalize()
+}
+
+#[cfg(test)]
+mod test {
+    use std::f32::consts::PI;
+
+    use ambient_core::transform::{rotation, translation};
+    use glam::{vec3, EulerRot};
+
+    use super::*;
+    use crate::{AnimationTarget, AnimationTrackInterpolator};
+
+    fn init() {
+        ambient_ecs::init_components();
+        ambient_core::init_all_components();
+    }
+
+    fn rotations() -> impl Iterator<Item = Quat> {
+        (0..8).flat_map(|x| {
+            (0..8).flat_map(move |y| {
+                (0..8).map(move |z| {
+                    let angle = |i: i32| i as f32 * PI / 4. - PI + 0.1;
+                    Quat::from_euler(EulerRot::XYZ, angle(x), angle(y), angle(z))
+                })
+            })
+        })
+    }
+
+    #[test]
+    fn rotation_angles_are_precise() {
+        let a = Quat::from_rotation_z(0.3);
+        for angle in [0.0001, 0.001, 0.1, 1., PI] {
+            let b = a * Quat::from_rotation_x(angle);
+            assert!((rotation_angle(a, b) - angle).abs() < 1e-5);
+            assert!((rotation_angle(a, -b) - angle).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn quantized_rotations_round_trip() {
+        for q in rotations().chain([Quat::IDENTITY, -Quat::IDENTITY]) {
+            let res = dequantize_quat(quantize_quat(q));
+            assert!(res.is_normalized());
+            assert!(rotation_angle(q, res) < 0.0002, "{q} became {res}");
+        }
+    }
+
+    #[test]
+    fn reduced_keyframes_stay_within_max_error() {
+        init();
+        let inputs = (0..100).map(|i| i as f32 / 30.).collect::<Vec<_>>();
+        let data = inputs
+            .iter()
+            .map(|&t| vec3(t.sin(), (2. * t).cos(), t))
+            .collect::<Vec<_>>();
+        let mut track = AnimationTrack {
+            target: AnimationTarget::BinderId("".to_string()),
+            inputs: inputs.clone(),
+            outputs: AnimationOutputs::Vec3 {
+                component: translation(),
+                data: data.clone(),
+            },
+        };
+        let max_error = 0.01;
+        track.reduce_keyframes(max_error);
+        assert!(track.inputs.len() < inputs.len());
+        assert_eq!(track.inputs.first(), inputs.first());
+        assert_eq!(track.inputs.last(), inputs.last());
+
+        let mut interpolator = AnimationTrackInterpolator::new();
+        for (t, expected) in inputs.iter().zip(data) {
+            let value = *interpolator.value(&track, *t).as_vec3_value().unwrap();
+            assert!(value.distance(expected) <= max_error + 1e-5);
+        }
+    }
+
+    #[test]
+    fn linear_tracks_keep_their_ends() {
+        init();
+        let mut track = AnimationTrack {
+            target: AnimationTarget::BinderId("".to_string()),
+            inputs: (0..10).map(|i| i as f32).collect(),
+            outputs: AnimationOutputs::Vec3 {
+                component: translation(),
+                data: (0..10).map(|i| vec3(i as f32, 0., 0.)).collect(),
+            },
+        };
+        track.reduce_keyframes(0.0001);
+        assert_eq!(track.inputs, vec![0., 9.]);
+    }
+
+    #[test]
+    fn compressed_rotations_stay_within_max_error() {
+        init();
+        let inputs = (0..60).map(|i| i as f32 / 30.).collect::<Vec<_>>();
+        let data = inputs
+            .iter()
+            .map(|&t| Quat::from_euler(EulerRot::XYZ, t, 0.5 * t * t, -t))
+            .collect::<Vec<_>>();
+        let mut clip = AnimationClip::from_tracks(vec![AnimationTrack {
+            target: AnimationTarget::BinderId("".to_string()),
+            inputs: inputs.clone(),
+            outputs: AnimationOutputs::Quat {
+                component: rotation(),
+                data: data.clone(),
+            },
+        }]);
+        let config = AnimationCompression::default();
+        clip.compress(&config);
+        let track = &clip.tracks[0];
+        assert!(matches!(
+            track.outputs,
+            AnimationOutputs::QuatQuantized { .. }
+        ));
+        assert!(track.inputs.len() < inputs.len());
+
+        let mut interpolator = AnimationTrackInterpolator::new();
+        for (t, expected) in inputs.iter().zip(data) {
+            let AnimationOutput::Quat { value, .. } = interpolator.value(track, *t) else {
+                unreachable!()
+            };
+            // Quantizing adds its own small error on top of the one removing keyframes allows
+            assert!(rotation_angle(value, expected) <= config.max_rotation_error + 0.0002);
+        }
+    }
+}
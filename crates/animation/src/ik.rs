@@ -0,0 +1,232 @@
+use ambient_core::{
+    hierarchy::parent,
+    transform::{get_world_transform, local_to_parent, rotation, scale, translation},
+};
+use ambient_ecs::{query, EntityId, SystemGroup, World};
+use glam::{Mat4, Quat, Vec3};
+
+pub use ambient_ecs::generated::components::core::animation::{
+    ik_chain_length, ik_ground_probe, ik_ground_probe_range, ik_pole, ik_target,
+    ik_target_position, ik_weight,
+};
+
+const DEFAULT_CHAIN_LENGTH: u32 = 2;
+const FABRIK_ITERATIONS: usize = 10;
+/// How close the end of a FABRIK chain has to get to the target to stop iterating
+const FABRIK_TOLERANCE: f32 = 0.001;
+/// Keeps two-bone chains from straightening or folding completely, as the bend direction would be lost
+const TWO_BONE_EPSILON: f32 = 0.0001;
+
+/// Bends the chains of the joints with an `ik_target` or `ik_target_position`. Runs after the animations are applied, and
+/// before the transforms are updated.
+pub fn ik_systems() -> SystemGroup {
+    SystemGroup::new(
+        "ik_systems",
+        vec![
+            query(ik_target()).to_system(|q, world, qs, _| {
+                for (id, target) in q.collect_cloned(world, qs) {
+                    // Ground probes are cast on the server, which sets the `ik_target_position` of the probe
+                    if let Ok(position) = world.get(target, ik_target_position()) {
+                        solve_chain(world, id, position, None);
+                        continue;
+                    }
+                    let Ok(transform) = get_world_transform(world, target) else {
+                        continue;
+                    };
+                    let (_, target_rotation, target_position) =
+                        transform.to_scale_rotation_translation();
+                    solve_chain(world, id, target_position, Some(target_rotation));
+                }
+            }),
+            query(ik_target_position())
+                .excl(ik_target())
+                .to_system(|q, world, qs, _| {
+                    for (id, target_position) in q.collect_cloned(world, qs) {
+                        solve_chain(world, id, target_position, None);
+                    }
+                }),
+        ],
+    )
+}
+
+/// The world transform of `id` with the animation of this frame applied, which `local_to_world` won't have until the
+/// transform systems have run
+pub(crate) fn animated_world_transform(world: &World, id: EntityId) -> Mat4 {
+    let local = || {
+        Mat4::from_scale_rotation_translation(
+            world.get(id, scale()).unwrap_or(Vec3::ONE),
+            world.get(id, rotation()).unwrap_or(Quat::IDENTITY),
+            world.get(id, translation()).unwrap_or(Vec3::ZERO),
+        )
+    };
+    match world.get(id, parent()) {
+        Ok(parent) if world.has_component(id, local_to_parent()) => {
+            animated_world_transform(world, parent) * local()
+        }
+        _ if world.has_component(id, translation()) || world.has_component(id, rotation()) => {
+            local()
+        }
+        _ => get_world_transform(world, id).unwrap_or_default(),
+    }
+}
+
+/// Rotates `end` and the `ik_chain_length` joints above it, so that `end` reaches `target`
+fn solve_chain(world: &mut World, end: EntityId, target: Vec3, target_rotation: Option<Quat>) {
+    let chain_length = world
+        .get(end, ik_chain_length())
+        .unwrap_or(DEFAULT_CHAIN_LENGTH) as usize;
+    let weight = world.get(end, ik_weight()).unwrap_or(1.).clamp(0., 1.);
+    if chain_length == 0 || weight <= 0. {
+        return;
+    }
+
+    // From the top of the chain down to `end`
+    let mut joints = vec![end];
+    while joints.len() <= chain_length {
+        let joint = *joints.last().unwrap();
+        let Ok(parent) = world.get(joint, parent()) else {
+            return;
+        };
+        if !world.has_component(joint, local_to_parent()) {
+            return;
+        }
+        joints.push(parent);
+    }
+    joints.reverse();
+
+    let transforms = joints
+        .iter()
+        .map(|&joint| animated_world_transform(world, joint))
+        .collect::<Vec<_>>();
+    let positions = transforms
+        .iter()
+        .map(|transform| transform.w_axis.truncate())
+        .collect::<Vec<_>>();
+    let mut solved = positions.clone();
+    if let [root, mid, end] = positions[..] {
+        let pole = world.get(joints[1], ik_pole()).ok();
+        (solved[1], solved[2]) = solve_two_bone(root, mid, end, target, pole);
+    } else {
+        solve_fabrik(&mut solved, target);
+    }
+
+    let world_rotation = |transform: &Mat4| transform.to_scale_rotation_translation().1;
+    let top_rotation = world.get(joints[0], rotation()).unwrap_or(Quat::IDENTITY);
+    let mut parent_rotation = world_rotation(&transforms[0]) * top_rotation.inverse();
+    for (i, &joint) in joints.iter().enumerate() {
+        let local = world.get(joint, rotation()).unwrap_or(Quat::IDENTITY);
+        let current = parent_rotation * local;
+        let desired = if i + 1 < joints.len() {
+            // Where the bone to the next joint points now that the joints above have been rotated, and where it should
+            let bone = world_rotation(&transforms[i]).inverse() * (positions[i + 1] - positions[i]);
+            match (
+                (current * bone).try_normalize(),
+                (solved[i + 1] - solved[i]).try_normalize(),
+            ) {
+                (Some(from), Some(to)) => Quat::from_rotation_arc(from, to) * current,
+                _ => current,
+            }
+        } else if let Some(target_rotation) = target_rotation {
+            target_rotation
+        } else {
+            break;
+        };
+        let new_local = local.slerp(parent_rotation.inverse() * desired, weight);
+        world.set(joint, rotation(), new_local).ok();
+        parent_rotation *= new_local;
+    }
+}
+
+/// Solves a two-bone chain (like an arm or a leg) exactly. Returns the new positions of `mid` and `end`, such that `end`
+/// is as close to `target` as the bone lengths allow, and `mid` bends towards `pole`, or the way it is already bent.
+pub fn solve_two_bone(
+    root: Vec3,
+    mid: Vec3,
+    end: Vec3,
+    target: Vec3,
+    pole: Option<Vec3>,
+) -> (Vec3, Vec3) {
+    let upper = root.distance(mid);
+    let lower = mid.distance(end);
+    let Some(dir) = (target - root).try_normalize() else {
+        return (mid, end);
+    };
+    let dist = root
+        .distance(target)
+        .min(upper + lower - TWO_BONE_EPSILON)
+        .max((upper - lower).abs() + TWO_BONE_EPSILON);
+    let hint = pole.unwrap_or(mid) - root;
+    let bend = (hint - dir * hint.dot(dir))
+        .try_normalize()
+        .unwrap_or_else(|| dir.any_orthonormal_vector());
+    // The distance from the root to the middle joint, along and across the line to the target
+    let along = (upper * upper - lower * lower + dist * dist) / (2. * dist);
+    let across = (upper * upper - along * along).max(0.).sqrt();
+    (root + dir * along + bend * across, root + dir * dist)
+}
+
+/// Moves the joints of a chain with FABRIK (forward and backward reaching inverse kinematics), keeping the first one in
+/// place and the bone lengths the same. If `target` is out of reach, the chain is stretched straight towards it.
+pub fn solve_fabrik(positions: &mut [Vec3], target: Vec3) {
+    let n = positions.len();
+    if n < 2 {
+        return;
+    }
+    let lengths = positions
+        .windows(2)
+        .map(|bone| bone[0].distance(bone[1]))
+        .collect::<Vec<_>>();
+    let root = positions[0];
+    if root.distance(target) >= lengths.iter().sum::<f32>() {
+        let dir = (target - root).normalize_or_zero();
+        for i in 1..n {
+            positions[i] = positions[i - 1] + dir * lengths[i - 1];
+        }
+        return;
+    }
+    for _ in 0..FABRIK_ITERATIONS {
+        if positions[n - 1].distance(target) <= FABRIK_TOLERANCE {
+            break;
+        }
+        positions[n - 1] = target;
+        for i in (0..n - 1).rev() {
+            let dir = (positions[i] - positions[i + 1]).normalize_or_zero();
+            positions[i] = positions[i + 1] + dir * lengths[i];
+        }
+        positions[0] = root;
+        for i in 1..n {
+            let dir = (positions[i] - positions[i - 1]).normalize_or_zero();
+            positions[i] = positions[i - 1] + dir * lengths[i - 1];
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use glam::vec3;
+
+    use super::*;
+
+    #[test]
+    fn two_bone_reaches_target() {
+        let (root, mid, end) = (Vec3::ZERO, vec3(0., 0., -1.), vec3(0., 0., -2.));
+        let target = vec3(0.5, 0., -1.2);
+        let (new_mid, new_end) = solve_two_bone(root, mid, end, target, Some(vec3(0., 1., -1.)));
+        assert!(new_end.distance(target) < 0.001);
+        assert!((root.distance(new_mid) - 1.).abs() < 0.001);
+        assert!((new_mid.distance(new_end) - 1.).abs() < 0.001);
+        assert!(new_mid.y > 0.);
+    }
+
+    #[test]
+    fn fabrik_keeps_bone_lengths() {
+        let mut positions = (0..5).map(|i| vec3(0., 0., i as f32)).collect::<Vec<_>>();
+        let target = vec3(1., 1., 2.);
+        solve_fabrik(&mut positions, target);
+        assert_eq!(positions[0], Vec3::ZERO);
+        assert!(positions[4].distance(target) < 0.01);
+        for bone in positions.windows(2) {
+            assert!((bone[0].distance(bone[1]) - 1.).abs() < 0.001);
+        }
+    }
+}
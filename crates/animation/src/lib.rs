@@ -13,9 +13,15 @@ use derive_more::Display;
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, sync::Arc, time::Duration};
 
+mod avatar;
+mod compression;
+mod ik;
 mod resources;
 mod retargeting;
 
+pub use avatar::*;
+pub use compression::*;
+pub use ik::*;
 pub use resources::*;
 pub use retargeting::*;
 
@@ -44,6 +50,9 @@ components!("animation", {
     animation_binder_mask: Vec<String>,
     @[Debuggable, Networked, Store]
     animation_binder_weights: Vec<Vec<f32>>,
+
+    /// The pose an avatar is solved from, where its feet are planted, and the step it is taking
+    avatar_state: AvatarState,
 });
 
 // Running
@@ -377,6 +386,9 @@ pub fn animation_systems() -> SystemGroup {
                             AnimationOutput::Quat { component, value } => {
                                 world.set(output.target, component, value).ok();
                             }
+                            AnimationOutput::Weights { component, value } => {
+                                world.set(output.target, component, value).ok();
+                            }
                             AnimationOutput::Vec3Field {
                                 component,
                                 field,
@@ -594,6 +606,9 @@ pub fn animation_systems() -> SystemGroup {
                             AnimationOutput::Quat { component, value } => {
                                 world.set(target, component, value).ok();
                             }
+                            AnimationOutput::Weights { component, value } => {
+                                world.set(target, component, value).ok();
+                            }
                             AnimationOutput::Vec3Field { component, field, value } => {
                                 if let Ok(d) = world.get_mut(target, component) {
                                     match field {
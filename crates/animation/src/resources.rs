@@ -8,11 +8,14 @@ use itertools::Itertools;
 use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 
+use crate::dequantize_quat;
+
 #[derive(Debug)]
 pub enum AnimationOutput {
     Vec3 { component: Component<glam::Vec3>, value: glam::Vec3 },
     Quat { component: Component<glam::Quat>, value: glam::Quat },
     Vec3Field { component: Component<glam::Vec3>, field: Vec3Field, value: f32 },
+    Weights { component: Component<Vec<f32>>, value: Vec<f32> },
 }
 impl AnimationOutput {
     pub fn mix(&self, value: AnimationOutput, p: f32) -> Self {
@@ -30,6 +33,13 @@ impl AnimationOutput {
                 AnimationOutput::Vec3Field { component, field, value: mix(*left, right, p) }
             }
 
+            (AnimationOutput::Weights { value: left, .. }, AnimationOutput::Weights { value: right, component }) => {
+                AnimationOutput::Weights {
+                    component,
+                    value: left.iter().zip(right).map(|(left, right)| mix(*left, right, p)).collect(),
+                }
+            }
+
             _ => unreachable!(),
         }
     }
@@ -57,6 +67,10 @@ pub enum AnimationOutputs {
     Vec3 { component: Component<glam::Vec3>, data: Vec<glam::Vec3> },
     Quat { component: Component<glam::Quat>, data: Vec<glam::Quat> },
     Vec3Field { component: Component<glam::Vec3>, field: Vec3Field, data: Vec<f32> },
+    /// Rotations encoded with [quantize_quat](crate::quantize_quat); each one is decoded when it is read
+    QuatQuantized { component: Component<glam::Quat>, data: Vec<[u16; 3]> },
+    /// Morph target weights; one set of weights per keyframe
+    Weights { component: Component<Vec<f32>>, data: Vec<Vec<f32>> },
 }
 impl AnimationOutputs {
     pub fn component(&self) -> ComponentDesc {
@@ -64,6 +78,8 @@ impl AnimationOutputs {
             AnimationOutputs::Vec3 { component, .. } => component.desc(),
             AnimationOutputs::Quat { component, .. } => component.desc(),
             AnimationOutputs::Vec3Field { component, .. } => component.desc(),
+            AnimationOutputs::QuatQuantized { component, .. } => component.desc(),
+            AnimationOutputs::Weights { component, .. } => component.desc(),
         }
     }
     pub fn field(&self) -> Option<Vec3Field> {
@@ -85,6 +101,12 @@ impl AnimationOutputs {
             AnimationOutputs::Vec3Field { data, component, field } => {
                 AnimationOutput::Vec3Field { component: *component, field: *field, value: data[index] }
             }
+            AnimationOutputs::QuatQuantized { data, component } => {
+                AnimationOutput::Quat { component: *component, value: dequantize_quat(data[index]) }
+            }
+            AnimationOutputs::Weights { data, component } => {
+                AnimationOutput::Weights { component: *component, value: data[index].clone() }
+            }
         }
     }
 }
@@ -92,6 +92,7 @@ impl AsyncAssetKey<Result<Arc<AnimationClip>, AssetError>> for AnimationClipReta
                         let zup = retarget_root_rot.inverse() * anim_root_rot;
 
                         if track.outputs.component() == rotation() {
+                            track.outputs.dequantize_rotations();
                             if let AnimationOutputs::Quat { data, .. } = &mut track.outputs {
                                 for v in data {
                                     *v = zup * *v;
@@ -136,7 +137,9 @@ fn retarget_track(
                 *v *= scale;
             }
         }
-        AnimationOutputs::Quat { .. } => unreachable!(),
+        AnimationOutputs::Quat { .. }
+        | AnimationOutputs::QuatQuantized { .. }
+        | AnimationOutputs::Weights { .. } => unreachable!(),
         AnimationOutputs::Vec3Field { data, .. } => {
             for v in data.iter_mut() {
                 *v *= scale;
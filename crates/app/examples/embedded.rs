@@ -0,0 +1,67 @@
+//! Embeds Ambient in an application that owns the window and the event loop, and steps it every frame.
+
+use std::sync::Arc;
+
+use ambient_app::AppBuilder;
+use ambient_core::{camera::active_camera, main_scene, transform::translation};
+use ambient_element::ElementComponentExt;
+use ambient_primitives::Cube;
+use ambient_renderer::color;
+use ambient_std::math::SphericalCoords;
+use glam::{vec3, vec4, Vec3};
+use winit::{
+    event::{Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    window::WindowBuilder,
+};
+
+fn main() {
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let _guard = rt.enter();
+
+    // The host application creates the event loop and the window
+    let event_loop = EventLoop::new();
+    let window = Arc::new(
+        WindowBuilder::new()
+            .with_title("Host application")
+            .build(&event_loop)
+            .unwrap(),
+    );
+
+    let mut app = rt
+        .block_on(AppBuilder::simple().with_window(window.clone()).build())
+        .unwrap();
+
+    let world = &mut app.world;
+    Cube.el()
+        .with(color(), vec4(0.8, 0.3, 0.3, 1.))
+        .with(translation(), Vec3::Z)
+        .spawn_static(world);
+    ambient_cameras::spherical::new(
+        vec3(0., 0., 0.),
+        SphericalCoords::new(std::f32::consts::PI / 4., std::f32::consts::PI / 4., 5.),
+    )
+    .with(active_camera(), 0.)
+    .with(main_scene(), ())
+    .spawn(world);
+
+    event_loop.run(move |event, _, control_flow| {
+        // The host handles the events it is interested in itself, and passes them on to Ambient
+        if let Event::WindowEvent {
+            event: WindowEvent::Focused(focused),
+            ..
+        } = &event
+        {
+            tracing::info!("Host window focused: {focused}");
+        }
+        let is_frame = matches!(event, Event::MainEventsCleared);
+
+        *control_flow = app.handle_event(event);
+        if is_frame && *control_flow != ControlFlow::Exit {
+            *control_flow = app.frame();
+        }
+    });
+}
@@ -0,0 +1,58 @@
+//! Renders a scene offscreen and compares it to its golden image in `golden_images/`, recording it if there is none.
+//! Exits with an error if the rendering changed.
+
+use ambient_app::{
+    golden::{GoldenImageOutcome, GoldenImageTest},
+    AppBuilder,
+};
+use ambient_core::{
+    camera::active_camera,
+    main_scene,
+    transform::{scale, translation},
+};
+use ambient_element::ElementComponentExt;
+use ambient_primitives::{Cube, Quad};
+use ambient_renderer::{cast_shadows, color};
+use ambient_std::math::SphericalCoords;
+use glam::{uvec2, vec3, vec4, Vec3};
+
+fn main() -> anyhow::Result<()> {
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    rt.block_on(run())
+}
+
+async fn run() -> anyhow::Result<()> {
+    let mut app = AppBuilder::new()
+        .headless(Some(uvec2(512, 512)))
+        .build()
+        .await?;
+
+    let world = &mut app.world;
+    Cube.el()
+        .with(color(), vec4(0.5, 0.5, 0.5, 1.))
+        .with(translation(), Vec3::Z)
+        .with_default(cast_shadows())
+        .spawn_static(world);
+    Quad.el().with(scale(), Vec3::ONE * 10.).spawn_static(world);
+    ambient_cameras::spherical::new(
+        vec3(0., 0., 0.),
+        SphericalCoords::new(std::f32::consts::PI / 4., std::f32::consts::PI / 4., 5.),
+    )
+    .with(active_camera(), 0.)
+    .with(main_scene(), ())
+    .spawn(world);
+
+    let outcome = GoldenImageTest::new("cube")
+        .frames(30)
+        .run(&mut app)
+        .await?;
+    println!("{outcome:?}");
+    anyhow::ensure!(
+        !matches!(outcome, GoldenImageOutcome::Mismatched { .. }),
+        "The rendering differs from the golden image"
+    );
+    Ok(())
+}
@@ -0,0 +1,57 @@
+//! Hands the accessibility tree of the UI to the platform's screen reader, and its requests back to the UI.
+use std::sync::Arc;
+
+use accesskit::{ActionRequest, TreeUpdate};
+use accesskit_winit::Adapter;
+use ambient_accessibility::{
+    accessibility_tree_update, action_message, apply_tree_update, ChannelActionHandler,
+};
+use ambient_ecs::World;
+use parking_lot::Mutex;
+use winit::{event::WindowEvent, window::Window};
+
+pub(crate) struct AccessibilityAdapter {
+    adapter: Adapter,
+    actions: flume::Receiver<ActionRequest>,
+    /// The whole tree, which is read out when a screen reader starts
+    tree: Arc<Mutex<TreeUpdate>>,
+}
+impl AccessibilityAdapter {
+    /// Must be created before the window is first shown.
+    pub fn new(window: &Window) -> Self {
+        let (tx, actions) = flume::unbounded();
+        let tree = Arc::new(Mutex::new(ambient_accessibility::initial_tree()));
+        let adapter = Adapter::with_action_handler(
+            window,
+            {
+                let tree = tree.clone();
+                move || tree.lock().clone()
+            },
+            Box::new(ChannelActionHandler(tx)),
+        );
+        Self {
+            adapter,
+            actions,
+            tree,
+        }
+    }
+
+    pub fn on_event(&self, window: &Window, event: &WindowEvent) {
+        self.adapter.on_event(window, event);
+    }
+
+    /// Passes the requests of the screen reader on to the UI as messages, and the latest changes of the tree to the
+    /// screen reader
+    pub fn update(&self, world: &mut World) {
+        for request in self.actions.try_iter() {
+            action_message(world, &request);
+        }
+        let update = world
+            .resource_mut_opt(accessibility_tree_update())
+            .and_then(Option::take);
+        if let Some(update) = update {
+            apply_tree_update(&mut self.tree.lock(), &update);
+            self.adapter.update_if_active(|| update);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! Golden image tests: render a scene offscreen for a fixed number of frames, and compare the result to an image that
+//! was recorded before, so that rendering regressions are caught automatically.
+//!
+//! Build the app with [AppBuilder::headless](crate::AppBuilder::headless) so that it renders into an offscreen render
+//! target instead of a window, set up the scene, and pass the app to [GoldenImageTest::run].
+
+use std::path::PathBuf;
+
+use ambient_core::gpu;
+use futures::future::{self, Either};
+use image::RgbaImage;
+
+use crate::{renderers::main_renderer, App};
+
+/// How far the perceptual hashes of two images may be apart by default for them to count as the same
+pub const DEFAULT_MAX_DISTANCE: u32 = 2;
+
+/// Set this to record the current output of every test as its golden image, instead of comparing to it
+pub const UPDATE_GOLDEN_IMAGES_VAR: &str = "AMBIENT_UPDATE_GOLDEN_IMAGES";
+
+#[derive(Debug, Clone)]
+pub struct GoldenImageTest {
+    pub name: String,
+    /// The number of frames to render before the image is captured
+    pub frames: usize,
+    /// The directory the golden images are kept in, as `<name>.png`
+    pub golden_dir: PathBuf,
+    /// The directory the captured images are written to, as `<name>.png`, for inspecting failures
+    pub output_dir: PathBuf,
+    /// How far the perceptual hashes of the images may be apart for them to count as the same
+    pub max_distance: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GoldenImageOutcome {
+    Matched {
+        distance: u32,
+    },
+    /// There was no golden image yet, or [UPDATE_GOLDEN_IMAGES_VAR] was set, and the captured image was recorded
+    Recorded,
+    Mismatched {
+        distance: u32,
+    },
+}
+
+impl GoldenImageTest {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            frames: 60,
+            golden_dir: PathBuf::from("golden_images"),
+            output_dir: PathBuf::from("tmp/golden_images"),
+            max_distance: DEFAULT_MAX_DISTANCE,
+        }
+    }
+    pub fn frames(mut self, value: usize) -> Self {
+        self.frames = value;
+        self
+    }
+    pub fn golden_dir(mut self, value: impl Into<PathBuf>) -> Self {
+        self.golden_dir = value.into();
+        self
+    }
+    pub fn output_dir(mut self, value: impl Into<PathBuf>) -> Self {
+        self.output_dir = value.into();
+        self
+    }
+    pub fn max_distance(mut self, value: u32) -> Self {
+        self.max_distance = value;
+        self
+    }
+
+    /// Steps `app` for [Self::frames] frames, captures what it rendered and compares it to the golden image
+    pub async fn run(&self, app: &mut App) -> anyhow::Result<GoldenImageOutcome> {
+        for _ in 0..self.frames {
+            app.frame();
+        }
+        let image = capture(app).await?;
+
+        let file_name = format!("{}.png", self.name);
+        std::fs::create_dir_all(&self.output_dir)?;
+        image.save(self.output_dir.join(&file_name))?;
+
+        let golden_path = self.golden_dir.join(&file_name);
+        if std::env::var_os(UPDATE_GOLDEN_IMAGES_VAR).is_some() || !golden_path.exists() {
+            std::fs::create_dir_all(&self.golden_dir)?;
+            image.save(&golden_path)?;
+            tracing::info!("Recorded the golden image {golden_path:?}");
+            return Ok(GoldenImageOutcome::Recorded);
+        }
+
+        let golden = image::open(&golden_path)?.into_rgba8();
+        let distance = image_distance(&image, &golden);
+        Ok(if distance <= self.max_distance {
+            GoldenImageOutcome::Matched { distance }
+        } else {
+            tracing::warn!(
+                "{} differs from {golden_path:?}, distance={distance}",
+                self.output_dir.join(&file_name).display()
+            );
+            GoldenImageOutcome::Mismatched { distance }
+        })
+    }
+}
+
+/// How far apart the perceptual hashes of `a` and `b` are; see [DEFAULT_MAX_DISTANCE]
+pub fn image_distance(a: &RgbaImage, b: &RgbaImage) -> u32 {
+    let hasher = image_hasher::HasherConfig::new().to_hasher();
+    hasher.hash_image(a).dist(&hasher.hash_image(b))
+}
+
+/// Reads back the last frame that the main renderer of `app` rendered
+pub async fn capture(app: &App) -> anyhow::Result<RgbaImage> {
+    let world = &app.world;
+    let renderer = world
+        .resource_opt(main_renderer())
+        .ok_or_else(|| anyhow::anyhow!("The app has no main renderer"))?;
+    let reader = renderer.lock().render_target().color_buffer.reader();
+
+    // The app polls the device itself, once per frame, so it has to be polled here until the read completes
+    let gpu = world.resource(gpu()).clone();
+    let read = reader.read_image();
+    let poll = async {
+        loop {
+            gpu.device.poll(wgpu::Maintain::Wait);
+            tokio::task::yield_now().await;
+        }
+    };
+    futures::pin_mut!(read, poll);
+    let image = match future::select(read, poll).await {
+        Either::Left((image, _)) => image,
+        Either::Right(_) => unreachable!(),
+    };
+    Ok(image
+        .ok_or_else(|| anyhow::anyhow!("Failed to read the render target"))?
+        .into_rgba8())
+}
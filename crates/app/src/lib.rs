@@ -13,23 +13,29 @@ use ambient_core::{
     name, remove_at_time_system, runtime, time,
     transform::TransformSystem,
     window::{
-        cursor_position, get_window_sizes, window_logical_size, window_physical_size,
-        window_scale_factor, WindowCtl,
+        cursor_position, get_window_sizes, set_cursor_grab, window_logical_size,
+        window_physical_size, window_scale_factor, WindowCtl,
     },
     RuntimeKey, TimeResourcesSystem,
 };
 use ambient_ecs::{
-    components, world_events, Debuggable, DynSystem, Entity, FrameEvent, MakeDefault,
-    MaybeResource, System, SystemGroup, World, WorldEventsSystem,
+    components,
+    generated::components::core::{
+        accessibility::{color_vision_deficiency, color_vision_simulate, high_contrast},
+        rendering::{global_illumination, global_illumination_quality},
+    },
+    world_events, Debuggable, DynSystem, Entity, FrameEvent, MakeDefault, MaybeResource,
+    ParallelSystemGroup, System, SystemGroup, World, WorldEventsSystem,
 };
 use ambient_element::ambient_system;
 use ambient_gizmos::{gizmos, Gizmos};
 use ambient_gpu::{
     gpu::{Gpu, GpuKey},
     mesh_buffer::MeshBufferKey,
-    settings::Settings,
+    settings::{Settings, SettingsKey},
 };
 use ambient_renderer::lod::lod_system;
+use ambient_shared_types::ColorVisionFilter;
 use ambient_std::{
     asset_cache::{AssetCache, SyncAssetKeyExt},
     fps_counter::{FpsCounter, FpsSample},
@@ -41,9 +47,12 @@ use renderers::{main_renderer, ui_renderer, UiRenderer, MainRenderer};
 use winit::{
     event::{ElementState, Event, KeyboardInput, ModifiersState, VirtualKeyCode, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
-    window::{Fullscreen, Window, WindowBuilder},
+    window::{CursorGrabMode, Fullscreen, Window, WindowBuilder},
 };
 
+#[cfg(not(target_os = "unknown"))]
+mod accessibility;
+pub mod golden;
 mod renderers;
 
 fn default_title() -> String {
@@ -64,6 +73,7 @@ pub fn init_all_components() {
     ambient_gizmos::init_components();
     ambient_cameras::init_all_components();
     init_components();
+    ambient_accessibility::init_components();
     ambient_renderer::init_all_components();
     ambient_ui_native::init_all_components();
     ambient_input::init_all_components();
@@ -110,9 +120,18 @@ pub fn world_instance_systems(full: bool) -> SystemGroup {
             },
             Box::new(ambient_model::model_systems()),
             Box::new(ambient_animation::animation_systems()),
+            Box::new(ambient_animation::avatar_systems()),
+            Box::new(ambient_animation::ik_systems()),
             Box::new(TransformSystem::new()),
-            Box::new(ambient_renderer::skinning::skinning_systems()),
+            Box::new(ParallelSystemGroup::new(
+                "skinning_and_morphing",
+                vec![
+                    ambient_renderer::skinning::skinning_system(),
+                    ambient_renderer::morphing::morphing_system(),
+                ],
+            )),
             Box::new(bounding_systems()),
+            Box::new(ambient_renderer::skinning::skinned_bounds_systems()),
             Box::new(camera_systems()),
         ],
     )
@@ -154,6 +173,8 @@ pub fn world_instance_resources(resources: AppResources) -> Entity {
         .with(self::window_title(), "".to_string())
         .with(self::fps_stats(), FpsSample::default())
         .with(self::asset_cache(), resources.assets.clone())
+        .with_merge(accessibility_resources(&resources.assets))
+        .with_merge(rendering_resources(&resources.assets))
         .with_default(world_events())
         .with(frame_index(), 0_usize)
         .with(ambient_core::window::cursor_position(), Vec2::ZERO)
@@ -179,12 +200,44 @@ pub fn world_instance_resources(resources: AppResources) -> Entity {
         .with(ambient_core::window::window_ctl(), resources.ctl_tx)
 }
 
+fn accessibility_resources(assets: &AssetCache) -> Entity {
+    let settings = SettingsKey.get(assets).accessibility;
+    Entity::new()
+        .with(
+            color_vision_deficiency(),
+            settings
+                .color_vision_deficiency
+                .map(|x| x.to_string())
+                .unwrap_or_default(),
+        )
+        .with(
+            color_vision_simulate(),
+            settings.color_vision_filter == ColorVisionFilter::Simulate,
+        )
+        .with(high_contrast(), settings.high_contrast)
+}
+
+fn rendering_resources(assets: &AssetCache) -> Entity {
+    let settings = SettingsKey.get(assets).rendering;
+    Entity::new()
+        .with(
+            global_illumination(),
+            settings.global_illumination.as_str().to_string(),
+        )
+        .with(
+            global_illumination_quality(),
+            settings.global_illumination_quality.clamp(0., 1.),
+        )
+}
+
 pub fn get_time_since_app_start(world: &World) -> Duration {
     *world.resource(time()) - *world.resource(app_start_time())
 }
 
 pub struct AppBuilder {
     pub event_loop: Option<EventLoop<()>>,
+    pub window: Option<Arc<Window>>,
+    pub gpu: Option<Arc<Gpu>>,
     pub asset_cache: Option<AssetCache>,
     pub ui_renderer: bool,
     pub main_renderer: bool,
@@ -216,6 +269,8 @@ impl AppBuilder {
     pub fn new() -> Self {
         Self {
             event_loop: None,
+            window: None,
+            gpu: None,
             asset_cache: None,
             ui_renderer: false,
             main_renderer: true,
@@ -243,6 +298,21 @@ impl AppBuilder {
         self
     }
 
+    /// Renders into a window of the host application instead of creating one, to embed Ambient in an application that
+    /// owns the event loop. The app is then driven with [App::handle_event] and [App::frame] instead of
+    /// [App::run_blocking]; see the `embedded` example.
+    pub fn with_window(mut self, window: Arc<Window>) -> Self {
+        self.window = Some(window);
+        self
+    }
+
+    /// Uses a [Gpu] of the host application, e.g. one wrapping its own wgpu device, instead of creating one. Its surface
+    /// must be for the window of the app, if it has one.
+    pub fn with_gpu(mut self, gpu: Arc<Gpu>) -> Self {
+        self.gpu = Some(gpu);
+        self
+    }
+
     pub fn with_asset_cache(mut self, asset_cache: AssetCache) -> Self {
         self.asset_cache = Some(asset_cache);
         self
@@ -273,6 +343,15 @@ impl AppBuilder {
         self
     }
 
+    /// Runs the app inside the given Android activity.
+    #[cfg(target_os = "android")]
+    pub fn with_android_app(mut self, app: winit::platform::android::activity::AndroidApp) -> Self {
+        use winit::{event_loop::EventLoopBuilder, platform::android::EventLoopBuilderExtAndroid};
+
+        self.event_loop = Some(EventLoopBuilder::new().with_android_app(app).build());
+        self
+    }
+
     #[cfg(target_os = "unknown")]
     pub fn parent_element(mut self, value: Option<web_sys::HtmlElement>) -> Self {
         self.parent_element = value;
@@ -294,18 +373,39 @@ impl AppBuilder {
             }
         };
 
-        let (window, event_loop) = if self.headless.is_some() {
+        let (window, event_loop) = if let Some(window) = self.window {
+            (Some(window), None)
+        } else if self.headless.is_some() {
             (None, None)
         } else {
             let event_loop = self.event_loop.unwrap_or_else(EventLoop::new);
-            let window = WindowBuilder::new().with_inner_size(winit::dpi::LogicalSize {
-                width: settings.resolution().0,
-                height: settings.resolution().1,
-            });
+            // The window is shown once the screen reader adapter has been created
+            let window = WindowBuilder::new()
+                .with_inner_size(winit::dpi::LogicalSize {
+                    width: settings.resolution().0,
+                    height: settings.resolution().1,
+                })
+                .with_visible(false);
             let window = Arc::new(window.build(&event_loop).unwrap());
             (Some(window), Some(event_loop))
         };
 
+        // Screen readers are only supported for the windows that the app creates itself, as the adapter must be
+        // created before the window is shown
+        #[cfg(not(target_os = "unknown"))]
+        let accessibility = match (&window, &event_loop) {
+            (Some(window), Some(_)) => {
+                let adapter = accessibility::AccessibilityAdapter::new(window);
+                window.set_visible(true);
+                Some(adapter)
+            }
+            _ => None,
+        };
+        #[cfg(target_os = "unknown")]
+        if let (Some(window), Some(_)) = (&window, &event_loop) {
+            window.set_visible(true);
+        }
+
         #[cfg(target_os = "unknown")]
         // Insert a canvas element for the window to attach to
         if let Some(window) = &window {
@@ -374,11 +474,15 @@ impl AppBuilder {
             .unwrap_or_else(|| AssetCache::new(runtime.clone()));
 
         let mut world = World::new("main_app");
-        let gpu = Arc::new(Gpu::with_config(window.as_deref(), true, &settings).await);
+        let gpu = match self.gpu {
+            Some(gpu) => gpu,
+            None => Arc::new(Gpu::with_config(window.as_deref(), true, &settings).await),
+        };
 
         tracing::debug!("Inserting runtime");
         RuntimeKey.insert(&assets, runtime.clone());
         GpuKey.insert(&assets, gpu.clone());
+        SettingsKey.insert(&assets, settings.clone());
         // WindowKey.insert(&assets, window.clone());
 
         tracing::debug!("Inserting app resources");
@@ -440,7 +544,12 @@ impl AppBuilder {
 
         Ok(App {
             window_focused: true,
+            cursor_grab: CursorGrabMode::None,
+            cursor_visible: true,
+            suspended: false,
             window,
+            #[cfg(not(target_os = "unknown"))]
+            accessibility,
             runtime,
             systems: SystemGroup::new(
                 "app",
@@ -502,6 +611,8 @@ pub struct App {
     pub window_event_systems: SystemGroup<Event<'static, ()>>,
     pub runtime: RuntimeHandle,
     pub window: Option<Arc<Window>>,
+    #[cfg(not(target_os = "unknown"))]
+    accessibility: Option<accessibility::AccessibilityAdapter>,
     event_loop: Option<EventLoop<()>>,
     fps: FpsCounter,
     #[cfg(feature = "profile")]
@@ -509,6 +620,10 @@ pub struct App {
     modifiers: ModifiersState,
 
     window_focused: bool,
+    /// The cursor state requested with [WindowCtl], which is restored when the window regains focus
+    cursor_grab: CursorGrabMode,
+    cursor_visible: bool,
+    suspended: bool,
     update_title_with_fps_stats: bool,
 }
 
@@ -522,7 +637,10 @@ impl std::fmt::Debug for App {
             .field("runtime", &self.runtime)
             .field("window", &self.window)
             .field("fps", &self.fps)
-            .field("window_focused", &self.window_focused);
+            .field("window_focused", &self.window_focused)
+            .field("cursor_grab", &self.cursor_grab)
+            .field("cursor_visible", &self.cursor_visible)
+            .field("suspended", &self.suspended);
 
         #[cfg(feature = "profile")]
         d.field("puffin", &true);
@@ -557,7 +675,13 @@ impl App {
                     },
             } = &event
             {
-                *self.world.resource_mut(window_scale_factor()) = *scale_factor;
+                self.world
+                    .set_if_changed(
+                        self.world.resource_entity(),
+                        window_scale_factor(),
+                        *scale_factor,
+                    )
+                    .unwrap();
                 self.handle_static_event(
                     &Event::WindowEvent {
                         window_id: *window_id,
@@ -577,28 +701,7 @@ impl App {
     pub fn run_blocking(mut self) {
         if let Some(event_loop) = self.event_loop.take() {
             event_loop.run(move |event, _, control_flow| {
-                // HACK(philpax): treat dpi changes as resize events. Ideally we'd handle this in handle_event proper,
-                // but https://github.com/rust-windowing/winit/issues/1968 restricts us
-                if let Event::WindowEvent {
-                    window_id,
-                    event:
-                        WindowEvent::ScaleFactorChanged {
-                            new_inner_size,
-                            scale_factor,
-                        },
-                } = &event
-                {
-                    *self.world.resource_mut(window_scale_factor()) = *scale_factor;
-                    self.handle_static_event(
-                        &Event::WindowEvent {
-                            window_id: *window_id,
-                            event: WindowEvent::Resized(**new_inner_size),
-                        },
-                        control_flow,
-                    );
-                } else if let Some(event) = event.to_static() {
-                    self.handle_static_event(&event, control_flow);
-                }
+                self.handle_any_event(event, control_flow);
             });
         } else {
             // Fake event loop in headless mode
@@ -612,6 +715,66 @@ impl App {
         }
     }
 
+    /// Handles an event of the event loop of a host application that Ambient is embedded in (see
+    /// [AppBuilder::with_window]). Events of other windows, user events and the events that [App::frame] stands in for
+    /// are ignored.
+    ///
+    /// Returns [ControlFlow::Exit] if the app asks to be closed.
+    pub fn handle_event<T>(&mut self, event: Event<'_, T>) -> ControlFlow {
+        let mut control_flow = ControlFlow::Poll;
+        let Ok(event) = event.map_nonuser_event::<()>() else {
+            return control_flow;
+        };
+        match &event {
+            Event::WindowEvent { window_id, .. }
+                if self.window.as_ref().map(|window| window.id()) != Some(*window_id) => {}
+            Event::MainEventsCleared | Event::RedrawRequested(_) => {}
+            _ => self.handle_any_event(event, &mut control_flow),
+        }
+        control_flow
+    }
+
+    /// Runs the systems of the app for a frame and renders it. An embedding host application calls this once per
+    /// iteration of its event loop, e.g. on [Event::MainEventsCleared].
+    ///
+    /// Returns [ControlFlow::Exit] if the app asks to be closed.
+    pub fn frame(&mut self) -> ControlFlow {
+        let mut control_flow = ControlFlow::Poll;
+        self.handle_static_event(&Event::MainEventsCleared, &mut control_flow);
+        control_flow
+    }
+
+    fn handle_any_event(&mut self, event: Event<'_, ()>, control_flow: &mut ControlFlow) {
+        // HACK(philpax): treat dpi changes as resize events. Ideally we'd handle this in handle_event proper,
+        // but https://github.com/rust-windowing/winit/issues/1968 restricts us
+        if let Event::WindowEvent {
+            window_id,
+            event:
+                WindowEvent::ScaleFactorChanged {
+                    new_inner_size,
+                    scale_factor,
+                },
+        } = &event
+        {
+            self.world
+                .set_if_changed(
+                    self.world.resource_entity(),
+                    window_scale_factor(),
+                    *scale_factor,
+                )
+                .unwrap();
+            self.handle_static_event(
+                &Event::WindowEvent {
+                    window_id: *window_id,
+                    event: WindowEvent::Resized(**new_inner_size),
+                },
+                control_flow,
+            );
+        } else if let Some(event) = event.to_static() {
+            self.handle_static_event(&event, control_flow);
+        }
+    }
+
     pub fn handle_static_event(
         &mut self,
         event: &Event<'static, ()>,
@@ -626,12 +789,23 @@ impl App {
             *control_flow = ControlFlow::Wait;
         }
 
+        // Don't spin while suspended; there is nothing to present to
+        if self.suspended {
+            *control_flow = ControlFlow::Wait;
+        }
+
         let world = &mut self.world;
         let systems = &mut self.systems;
         let gpu_world_sync_systems = &mut self.gpu_world_sync_systems;
         world.resource(gpu()).device.poll(wgpu::Maintain::Poll);
 
         self.window_event_systems.run(world, event);
+        #[cfg(not(target_os = "unknown"))]
+        if let (Some(accessibility), Some(window), Event::WindowEvent { event, .. }) =
+            (&self.accessibility, &self.window, event)
+        {
+            accessibility.on_event(window, event);
+        }
         match event {
             Event::MainEventsCleared => {
                 // Handle window control events
@@ -639,12 +813,14 @@ impl App {
                     tracing::debug!("Window control: {v:?}");
                     match v {
                         WindowCtl::GrabCursor(mode) => {
-                            if let Some(window) = &self.window {
-                                window.set_cursor_grab(mode).ok();
+                            self.cursor_grab = mode;
+                            if let (Some(window), true) = (&self.window, self.window_focused) {
+                                set_cursor_grab(window, mode);
                             }
                         }
                         WindowCtl::ShowCursor(show) => {
-                            if let Some(window) = &self.window {
+                            self.cursor_visible = show;
+                            if let (Some(window), true) = (&self.window, self.window_focused) {
                                 window.set_cursor_visible(show);
                             }
                         }
@@ -679,6 +855,11 @@ impl App {
                     gpu_world_sync_systems.run(world, &GpuWorldSyncEvent);
                 }
 
+                #[cfg(not(target_os = "unknown"))]
+                if let Some(accessibility) = &self.accessibility {
+                    accessibility.update(world);
+                }
+
                 if let Some(fps) = self.fps.frame_next() {
                     world
                         .set(world.resource_entity(), self::fps_stats(), fps.clone())
@@ -698,15 +879,70 @@ impl App {
                 if let Some(window) = &self.window {
                     window.request_redraw();
                 }
+                ambient_std::frame_arena::take_frame_arena_stats();
                 ambient_profiling::finish_frame!();
             }
 
+            Event::Suspended => {
+                // The native window (and with it the surface) may be destroyed while suspended,
+                // e.g. when an Android activity is paused
+                tracing::info!("Suspended");
+                self.suspended = true;
+                world.resource(gpu()).suspend();
+            }
+            Event::Resumed => {
+                tracing::info!("Resumed");
+                self.suspended = false;
+                if let Some(window) = &self.window {
+                    world.resource(gpu()).resume(window);
+
+                    let (physical_size, logical_size, scale_factor) = get_window_sizes(window);
+                    world
+                        .set_if_changed(
+                            world.resource_entity(),
+                            window_physical_size(),
+                            physical_size,
+                        )
+                        .unwrap();
+                    world
+                        .set_if_changed(
+                            world.resource_entity(),
+                            window_logical_size(),
+                            logical_size,
+                        )
+                        .unwrap();
+                    world
+                        .set_if_changed(
+                            world.resource_entity(),
+                            window_scale_factor(),
+                            scale_factor,
+                        )
+                        .unwrap();
+                }
+            }
+
             Event::WindowEvent { event, .. } => match event {
                 WindowEvent::Focused(focused) => {
                     self.window_focused = *focused;
+                    // The cursor is released while the window isn't focused, so that it can be used in other windows
+                    if let Some(window) = &self.window {
+                        if *focused {
+                            set_cursor_grab(window, self.cursor_grab);
+                            window.set_cursor_visible(self.cursor_visible);
+                        } else {
+                            set_cursor_grab(window, CursorGrabMode::None);
+                            window.set_cursor_visible(true);
+                        }
+                    }
                 }
                 WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
-                    *self.world.resource_mut(window_scale_factor()) = *scale_factor;
+                    self.world
+                        .set_if_changed(
+                            self.world.resource_entity(),
+                            window_scale_factor(),
+                            *scale_factor,
+                        )
+                        .unwrap();
                 }
                 WindowEvent::Resized(size) => {
                     let gpu = world.resource(gpu()).clone();
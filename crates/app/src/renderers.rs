@@ -1,7 +1,11 @@
 use std::sync::Arc;
 
 use ambient_core::{asset_cache, gpu, main_scene, ui_scene, window::window_physical_size};
-use ambient_ecs::{components, query, FrameEvent, System, SystemGroup, World};
+use ambient_ecs::{
+    components,
+    generated::components::core::accessibility::{color_vision_deficiency, color_vision_simulate},
+    query, FrameEvent, System, SystemGroup, World,
+};
 use ambient_gizmos::render::GizmoRenderer;
 use ambient_gpu::{
     blit::{Blitter, BlitterKey},
@@ -9,10 +13,13 @@ use ambient_gpu::{
     shader_module::DEPTH_FORMAT,
     texture::{Texture, TextureView},
 };
-use ambient_renderer::{renderer_stats, RenderTarget, Renderer, RendererConfig, RendererTarget};
+use ambient_renderer::{
+    renderer_stats, RenderTarget, Renderer, RendererConfig, RendererTarget, VELOCITY_FORMAT,
+};
+use ambient_shared_types::{ColorVisionDeficiency, ColorVisionFilter};
 use ambient_std::{asset_cache::SyncAssetKeyExt, color::Color};
 use ambient_ui_native::app_background_color;
-use glam::{uvec2, UVec2};
+use glam::{uvec2, Mat3, UVec2};
 use parking_lot::Mutex;
 use tracing::info_span;
 use wgpu::FilterMode;
@@ -76,6 +83,8 @@ pub struct MainRenderer {
     main: Option<Renderer>,
     ui: Option<Renderer>,
     blit: Arc<Blitter>,
+    gamma_correction: Option<f32>,
+    color_matrix: Option<Mat3>,
     render_target: RenderTarget,
     size: UVec2,
 }
@@ -140,8 +149,11 @@ impl MainRenderer {
                 format: gpu.swapchain_format().into(),
                 min_filter: FilterMode::Nearest,
                 gamma_correction,
+                color_matrix: None,
             }
             .get(&world.resource(asset_cache()).clone()),
+            gamma_correction,
+            color_matrix: None,
             render_target,
             gpu,
             size: wind_size,
@@ -156,6 +168,21 @@ impl MainRenderer {
         }
     }
 
+    /// Swaps the final blit when the color vision filter resources change
+    fn update_color_filter(&mut self, world: &World) {
+        let color_matrix = color_vision_matrix(world);
+        if color_matrix != self.color_matrix {
+            self.color_matrix = color_matrix;
+            self.blit = BlitterKey {
+                format: self.gpu.swapchain_format().into(),
+                min_filter: FilterMode::Nearest,
+                gamma_correction: self.gamma_correction,
+                color_matrix,
+            }
+            .get(world.resource(asset_cache()));
+        }
+    }
+
     pub fn dump_to_tmp_file(&self) {
         std::fs::create_dir_all("tmp").unwrap();
         let mut f = std::fs::File::create("tmp/renderer.txt").expect("Unable to create file");
@@ -167,6 +194,10 @@ impl MainRenderer {
         self.main.as_ref().map(|x| x.n_entities()).unwrap_or(0)
             + self.ui.as_ref().map(|x| x.n_entities()).unwrap_or(0)
     }
+    /// The offscreen target the scene is rendered into, before it is blitted to the window
+    pub fn render_target(&self) -> &RenderTarget {
+        &self.render_target
+    }
     pub fn stats(&self) -> String {
         if let Some(main) = &self.main {
             main.stats()
@@ -229,56 +260,59 @@ impl System for MainRenderer {
             );
         }
 
-        if let Some(surface) = &self.gpu.surface {
-            if self.size.x > 0 && self.size.y > 0 {
-                let frame = {
-                    ambient_profiling::scope!("Get swapchain texture");
-                    match surface.get_current_texture() {
-                        Ok(v) => v,
-                        // Reconfigure the surface if lost
-                        Err(wgpu::SurfaceError::Lost) => {
-                            tracing::warn!("Surface lost");
-                            self.gpu.resize(PhysicalSize {
-                                width: self.size.x,
-                                height: self.size.y,
-                            });
-                            return;
-                        }
-                        // The system is out of memory, we should probably quit
-                        Err(wgpu::SurfaceError::OutOfMemory) => panic!("Out of memory"),
-                        // All other errors (Outdated, Timeout) should be resolved by the next frame
-                        Err(err) => {
-                            tracing::warn!("{err:?}");
-                            return;
-                        }
-                    }
-                };
-                let frame_view = frame
-                    .texture
-                    .create_view(&wgpu::TextureViewDescriptor::default());
-                self.blit.run(
-                    &mut encoder,
-                    &self.render_target.color_buffer_view,
-                    &frame_view,
-                );
+        self.update_color_filter(world);
+
+        let frame = if self.size.x > 0 && self.size.y > 0 {
+            // The surface is absent while the app is suspended; we still submit the frame's work.
+            let surface = self.gpu.surface.lock();
+            surface.as_ref().map(|surface| {
+                ambient_profiling::scope!("Get swapchain texture");
+                surface.get_current_texture()
+            })
+        } else {
+            None
+        };
 
-                {
-                    ambient_profiling::scope!("Submit");
-                    self.gpu.queue.submit(Some(encoder.finish()));
+        if let Some(frame) = frame {
+            let frame = match frame {
+                Ok(v) => v,
+                // Reconfigure the surface if lost
+                Err(wgpu::SurfaceError::Lost) => {
+                    tracing::warn!("Surface lost");
+                    self.gpu.resize(PhysicalSize {
+                        width: self.size.x,
+                        height: self.size.y,
+                    });
+                    return;
                 }
-                {
-                    ambient_profiling::scope!("Present");
-                    frame.present();
+                // The system is out of memory, we should probably quit
+                Err(wgpu::SurfaceError::OutOfMemory) => panic!("Out of memory"),
+                // All other errors (Outdated, Timeout) should be resolved by the next frame
+                Err(err) => {
+                    tracing::warn!("{err:?}");
+                    return;
                 }
-            } else {
+            };
+            let frame_view = frame
+                .texture
+                .create_view(&wgpu::TextureViewDescriptor::default());
+            self.blit.run(
+                &mut encoder,
+                &self.render_target.color_buffer_view,
+                &frame_view,
+            );
+
+            {
                 ambient_profiling::scope!("Submit");
                 self.gpu.queue.submit(Some(encoder.finish()));
             }
-        } else {
             {
-                ambient_profiling::scope!("Submit");
-                self.gpu.queue.submit(Some(encoder.finish()));
+                ambient_profiling::scope!("Present");
+                frame.present();
             }
+        } else {
+            ambient_profiling::scope!("Submit");
+            self.gpu.queue.submit(Some(encoder.finish()));
         }
 
         for action in post_submit.into_iter() {
@@ -291,11 +325,29 @@ impl System for MainRenderer {
     }
 }
 
+fn color_vision_matrix(world: &World) -> Option<Mat3> {
+    let deficiency = world
+        .resource_opt(color_vision_deficiency())?
+        .parse::<ColorVisionDeficiency>()
+        .ok()?;
+    let filter = if world
+        .resource_opt(color_vision_simulate())
+        .copied()
+        .unwrap_or(false)
+    {
+        ColorVisionFilter::Simulate
+    } else {
+        ColorVisionFilter::Correct
+    };
+    Some(filter.matrix(deficiency))
+}
+
 pub struct UiRenderer {
     gpu: Arc<Gpu>,
     ui_renderer: Renderer,
     depth_buffer_view: Arc<TextureView>,
     normals_view: Arc<TextureView>,
+    velocity_view: Arc<TextureView>,
 }
 
 impl UiRenderer {
@@ -327,6 +379,24 @@ impl UiRenderer {
             },
         ));
 
+        let velocity = Arc::new(Texture::new(
+            gpu.clone(),
+            &wgpu::TextureDescriptor {
+                label: Some("UiRenderer.velocity"),
+                size: wgpu::Extent3d {
+                    width: size.x,
+                    height: size.y,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: VELOCITY_FORMAT,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[],
+            },
+        ));
+
         let assets = world.resource(asset_cache()).clone();
         let mut ui_renderer = Renderer::new(
             world,
@@ -343,6 +413,7 @@ impl UiRenderer {
             depth_buffer_view: Arc::new(depth_buffer.create_view(&Default::default())),
             gpu,
             normals_view: Arc::new(normals.create_view(&Default::default())),
+            velocity_view: Arc::new(velocity.create_view(&Default::default())),
         }
     }
 
@@ -383,11 +454,14 @@ impl UiRenderer {
             });
         let frame = {
             ambient_profiling::scope!("Get swapchain texture");
-            gpu.surface
-                .as_ref()
-                .unwrap()
-                .get_current_texture()
-                .expect("Failed to acquire next swap chain texture")
+            let surface = gpu.surface.lock();
+            match surface.as_ref() {
+                Some(surface) => surface
+                    .get_current_texture()
+                    .expect("Failed to acquire next swap chain texture"),
+                // Suspended; nothing to draw to
+                None => return,
+            }
         };
 
         let window_size = world.resource(window_physical_size());
@@ -411,6 +485,7 @@ impl UiRenderer {
                     depth_or_array_layers: 1,
                 },
                 normals: &self.normals_view,
+                velocity: &self.velocity_view,
             },
             Some(app_background_color()),
         );
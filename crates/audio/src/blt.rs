@@ -112,10 +112,156 @@ impl TransferFunction for Bpf {
     }
 }
 
+/// Boosts or cuts the frequencies below `freq` by `gain` dB
+#[derive(Debug, Clone, PartialEq)]
+pub struct LowShelf {
+    pub freq: f32,
+    pub gain: f32,
+}
+
+impl TransferFunction for LowShelf {
+    fn get_coeffs(&self, sample_freq: SampleRate) -> BltCoeffs {
+        let w0 = TAU * self.freq / sample_freq as f32;
+        let re = w0.cos();
+        let a = 10f32.powf(self.gain / 40.0);
+        // A shelf slope of 1
+        let alpha = w0.sin() / 2.0 * 2f32.sqrt();
+        let k = 2.0 * a.sqrt() * alpha;
+
+        let b0 = a * ((a + 1.0) - (a - 1.0) * re + k);
+        let b1 = 2.0 * a * ((a - 1.0) - (a + 1.0) * re);
+        let b2 = a * ((a + 1.0) - (a - 1.0) * re - k);
+
+        let a0 = (a + 1.0) + (a - 1.0) * re + k;
+        let a1 = -2.0 * ((a - 1.0) + (a + 1.0) * re);
+        let a2 = (a + 1.0) + (a - 1.0) * re - k;
+
+        // Normalization step
+        BltCoeffs {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+        }
+    }
+}
+
+/// Boosts or cuts the frequencies above `freq` by `gain` dB
+#[derive(Debug, Clone, PartialEq)]
+pub struct HighShelf {
+    pub freq: f32,
+    pub gain: f32,
+}
+
+impl TransferFunction for HighShelf {
+    fn get_coeffs(&self, sample_freq: SampleRate) -> BltCoeffs {
+        let w0 = TAU * self.freq / sample_freq as f32;
+        let re = w0.cos();
+        let a = 10f32.powf(self.gain / 40.0);
+        // A shelf slope of 1
+        let alpha = w0.sin() / 2.0 * 2f32.sqrt();
+        let k = 2.0 * a.sqrt() * alpha;
+
+        let b0 = a * ((a + 1.0) + (a - 1.0) * re + k);
+        let b1 = -2.0 * a * ((a - 1.0) + (a + 1.0) * re);
+        let b2 = a * ((a + 1.0) + (a - 1.0) * re - k);
+
+        let a0 = (a + 1.0) - (a - 1.0) * re + k;
+        let a1 = 2.0 * ((a - 1.0) - (a + 1.0) * re);
+        let a2 = (a + 1.0) - (a - 1.0) * re - k;
+
+        // Normalization step
+        BltCoeffs {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+        }
+    }
+}
+
+/// Boosts or cuts the frequencies around `freq` by `gain` dB
+#[derive(Debug, Clone, PartialEq)]
+pub struct Peak {
+    pub freq: f32,
+    pub gain: f32,
+    pub q: f32,
+}
+
+impl TransferFunction for Peak {
+    fn get_coeffs(&self, sample_freq: SampleRate) -> BltCoeffs {
+        let w0 = TAU * self.freq / sample_freq as f32;
+        let re = w0.cos();
+        let a = 10f32.powf(self.gain / 40.0);
+        let alpha = w0.sin() / (2.0 * self.q);
+
+        let b0 = 1.0 + alpha * a;
+        let b1 = -2.0 * re;
+        let b2 = 1.0 - alpha * a;
+
+        let a0 = 1.0 + alpha / a;
+        let a1 = -2.0 * re;
+        let a2 = 1.0 - alpha / a;
+
+        // Normalization step
+        BltCoeffs {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+        }
+    }
+}
+
 pub trait TransferFunction {
     fn get_coeffs(&self, sample_freq: SampleRate) -> BltCoeffs;
 }
 
+/// The state of a biquad filter which is fed one frame at a time, for filtering buffers rather than sources
+#[derive(Debug, Clone)]
+pub struct Biquad {
+    c: BltCoeffs,
+    x1: Vec2,
+    x2: Vec2,
+    y1: Vec2,
+    y2: Vec2,
+}
+
+impl Biquad {
+    pub fn new(c: BltCoeffs) -> Self {
+        Self {
+            c,
+            x1: Vec2::ZERO,
+            x2: Vec2::ZERO,
+            y1: Vec2::ZERO,
+            y2: Vec2::ZERO,
+        }
+    }
+
+    /// Changes the filter without resetting its state, so that it doesn't click
+    pub fn set_coeffs(&mut self, c: BltCoeffs) {
+        self.c = c;
+    }
+
+    pub fn process(&mut self, sample: Vec2) -> Vec2 {
+        let y = self.c.b0 * sample + self.c.b1 * self.x1 + self.c.b2 * self.x2
+            - self.c.a1 * self.y1
+            - self.c.a2 * self.y2;
+
+        // Slide
+        self.x2 = self.x1;
+        self.x1 = sample;
+
+        self.y2 = self.y1;
+        self.y1 = y;
+
+        y
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct BilinearTransform<S, H, Vh>
 where
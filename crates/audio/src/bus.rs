@@ -0,0 +1,268 @@
+use crate::{
+    effects::{Effect, EffectState},
+    error::{Error, Result},
+    Frame, SampleRate,
+};
+
+/// The bus every other bus is mixed into, which plays on the output device
+pub const MASTER_BUS: &str = "master";
+/// The default buses for sound effects, music and speech, which are mixed into the [MASTER_BUS]
+pub const SFX_BUS: &str = "sfx";
+pub const MUSIC_BUS: &str = "music";
+pub const VOICE_BUS: &str = "voice";
+pub const DEFAULT_BUSES: [&str; 4] = [MASTER_BUS, SFX_BUS, MUSIC_BUS, VOICE_BUS];
+
+/// A volume which moves linearly towards its target
+#[derive(Debug, Clone)]
+struct Ramp {
+    value: f32,
+    target: f32,
+    step: f32,
+}
+
+impl Ramp {
+    fn new(value: f32) -> Self {
+        Self {
+            value,
+            target: value,
+            step: 0.0,
+        }
+    }
+
+    fn set(&mut self, target: f32, samples: u64) {
+        self.target = target;
+        if samples == 0 {
+            self.value = target;
+            self.step = 0.0;
+        } else {
+            self.step = (target - self.value) / samples as f32;
+        }
+    }
+
+    fn next(&mut self) -> f32 {
+        if self.value != self.target {
+            self.value += self.step;
+            if (self.step >= 0.0 && self.value >= self.target)
+                || (self.step <= 0.0 && self.value <= self.target)
+            {
+                self.value = self.target;
+            }
+        }
+        self.value
+    }
+}
+
+struct Bus {
+    name: String,
+    /// The index of the bus this one is mixed into, or `None` for the master bus
+    parent: Option<usize>,
+    volume: Ramp,
+    effects: Vec<(Effect, EffectState)>,
+    buffer: Vec<Frame>,
+}
+
+impl Bus {
+    fn new(name: &str, parent: Option<usize>) -> Self {
+        Self {
+            name: name.to_string(),
+            parent,
+            volume: Ramp::new(1.0),
+            effects: Vec::new(),
+            buffer: Vec::new(),
+        }
+    }
+
+    fn process(&mut self) {
+        for (_, effect) in &mut self.effects {
+            effect.process(&mut self.buffer);
+        }
+        if self.volume.value != self.volume.target || self.volume.value != 1.0 {
+            for frame in &mut self.buffer {
+                *frame *= self.volume.next();
+            }
+        }
+    }
+}
+
+/// The buses of the [crate::AudioMixer]. Every bus is mixed into its parent, through its insert effects and volume,
+/// and the master bus is the output of the mixer.
+pub(crate) struct Buses {
+    sample_rate: SampleRate,
+    buses: Vec<Bus>,
+    /// The indices of the buses, with every bus before its parent
+    order: Vec<usize>,
+}
+
+impl Buses {
+    pub(crate) fn new(sample_rate: SampleRate) -> Self {
+        let mut buses = Self {
+            sample_rate,
+            buses: vec![Bus::new(MASTER_BUS, None)],
+            order: Vec::new(),
+        };
+        for name in &DEFAULT_BUSES[1..] {
+            buses.get_or_insert(name);
+        }
+        buses
+    }
+
+    pub(crate) fn names(&self) -> Vec<String> {
+        self.buses.iter().map(|bus| bus.name.clone()).collect()
+    }
+
+    fn get(&self, name: &str) -> Option<usize> {
+        self.buses.iter().position(|bus| bus.name == name)
+    }
+
+    /// The index of the bus `name`, which is added under the master bus if it doesn't exist yet
+    pub(crate) fn get_or_insert(&mut self, name: &str) -> usize {
+        match self.get(name) {
+            Some(index) => index,
+            None => {
+                self.buses.push(Bus::new(name, Some(0)));
+                self.update_order();
+                self.buses.len() - 1
+            }
+        }
+    }
+
+    pub(crate) fn set_parent(&mut self, name: &str, parent: &str) -> Result<()> {
+        if name == MASTER_BUS {
+            return Err(Error::BusCycle(name.to_string(), parent.to_string()));
+        }
+        let index = self.get_or_insert(name);
+        let parent_index = self.get_or_insert(parent);
+        // Walk up from the new parent to the master bus; meeting the bus itself on the way would make a loop
+        let mut ancestor = Some(parent_index);
+        while let Some(i) = ancestor {
+            if i == index {
+                return Err(Error::BusCycle(name.to_string(), parent.to_string()));
+            }
+            ancestor = self.buses[i].parent;
+        }
+        self.buses[index].parent = Some(parent_index);
+        self.update_order();
+        Ok(())
+    }
+
+    pub(crate) fn set_volume(&mut self, name: &str, volume: f32, samples: u64) {
+        let index = self.get_or_insert(name);
+        self.buses[index].volume.set(volume.max(0.0), samples);
+    }
+
+    pub(crate) fn set_effects(&mut self, name: &str, effects: &[Effect]) {
+        let sample_rate = self.sample_rate;
+        let index = self.get_or_insert(name);
+        let bus = &mut self.buses[index];
+        // Effects that stay in the same place keep their state, so that changing their settings doesn't interrupt
+        // their tails
+        let mut states = std::mem::take(&mut bus.effects).into_iter();
+        bus.effects = effects
+            .iter()
+            .map(|effect| match states.next() {
+                Some((previous, mut state))
+                    if previous == *effect || state.update(effect, sample_rate) =>
+                {
+                    (*effect, state)
+                }
+                _ => (*effect, EffectState::new(effect, sample_rate)),
+            })
+            .collect();
+    }
+
+    fn update_order(&mut self) {
+        let depth = |mut index: usize| {
+            let mut depth = 0;
+            while let Some(parent) = self.buses[index].parent {
+                index = parent;
+                depth += 1;
+            }
+            depth
+        };
+        let mut order: Vec<usize> = (0..self.buses.len()).collect();
+        order.sort_by_key(|&index| std::cmp::Reverse(depth(index)));
+        self.order = order;
+    }
+
+    /// Clears the buffers of the buses for a block of `len` frames
+    pub(crate) fn begin(&mut self, len: usize) {
+        for bus in &mut self.buses {
+            bus.buffer.clear();
+            bus.buffer.resize(len, Frame::ZERO);
+        }
+    }
+
+    /// The buffer that the sounds playing on the bus `index` are mixed into
+    pub(crate) fn buffer(&mut self, index: usize) -> &mut [Frame] {
+        &mut self.buses[index].buffer
+    }
+
+    /// Mixes every bus into its parent, and adds the master bus to `output`
+    pub(crate) fn mix(&mut self, output: &mut [Frame]) {
+        for i in 0..self.order.len() {
+            let index = self.order[i];
+            self.buses[index].process();
+            let buffer = std::mem::take(&mut self.buses[index].buffer);
+            let target = match self.buses[index].parent {
+                Some(parent) => &mut self.buses[parent].buffer[..],
+                None => &mut *output,
+            };
+            for (dst, src) in target.iter_mut().zip(&buffer) {
+                *dst += *src;
+            }
+            self.buses[index].buffer = buffer;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn buses_mix_into_their_parents() {
+        let mut buses = Buses::new(48000);
+        buses.set_parent("ambience", SFX_BUS).unwrap();
+        buses.set_volume(SFX_BUS, 0.5, 0);
+        buses.set_volume(MUSIC_BUS, 0.25, 0);
+
+        let ambience = buses.get_or_insert("ambience");
+        let music = buses.get_or_insert(MUSIC_BUS);
+        buses.begin(2);
+        buses.buffer(ambience).fill(Frame::splat(2.0));
+        buses.buffer(music).fill(Frame::splat(1.0));
+        let mut output = [Frame::ZERO; 2];
+        buses.mix(&mut output);
+
+        // The ambience goes through the volume of the sfx bus, which it is mixed into
+        assert_eq!(output, [Frame::splat(2.0 * 0.5 + 1.0 * 0.25); 2]);
+    }
+
+    #[test]
+    fn volumes_ramp() {
+        let mut buses = Buses::new(48000);
+        buses.set_volume(SFX_BUS, 0.0, 4);
+
+        let sfx = buses.get_or_insert(SFX_BUS);
+        buses.begin(6);
+        buses.buffer(sfx).fill(Frame::ONE);
+        let mut output = [Frame::ZERO; 6];
+        buses.mix(&mut output);
+
+        assert_eq!(
+            output.map(|frame| frame.x),
+            [0.75, 0.5, 0.25, 0.0, 0.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn cycles_are_rejected() {
+        let mut buses = Buses::new(48000);
+        buses.set_parent("a", "b").unwrap();
+        assert!(buses.set_parent("b", "a").is_err());
+        assert!(buses.set_parent("a", "a").is_err());
+        assert!(buses.set_parent(MASTER_BUS, "a").is_err());
+        // The buses that were rejected keep their parents
+        buses.set_parent("b", SFX_BUS).unwrap();
+    }
+}
@@ -0,0 +1,414 @@
+use crate::{
+    blt::{Biquad, BltCoeffs, HighShelf, LowShelf, Peak, TransferFunction},
+    Frame, SampleRate,
+};
+
+/// The bands of [Effect::Eq]
+const EQ_LOW_FREQ: f32 = 250.0;
+const EQ_MID_FREQ: f32 = 1000.0;
+const EQ_MID_Q: f32 = 0.7;
+const EQ_HIGH_FREQ: f32 = 4000.0;
+
+/// The delays of the comb and allpass filters of [Effect::Reverb] at 44.1 kHz, from Freeverb
+const COMB_TUNING: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
+const ALLPASS_TUNING: [usize; 4] = [556, 441, 341, 225];
+/// How many samples longer the delays of the right channel are, which decorrelates the channels
+const STEREO_SPREAD: usize = 23;
+const REVERB_INPUT_GAIN: f32 = 0.015;
+const REVERB_WET_SCALE: f32 = 3.0;
+
+/// An insert effect of a bus of the [crate::AudioMixer], which processes everything that is mixed into the bus
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum Effect {
+    /// A three band equalizer, with the gains in dB of the frequencies below 250 Hz, around 1 kHz and above 4 kHz
+    Eq { low: f32, mid: f32, high: f32 },
+    /// Reduces the gain of the bus by `ratio` as far as its level goes over `threshold` dB, reacting within `attack`
+    /// seconds and recovering within `release` seconds
+    Compressor {
+        threshold: f32,
+        ratio: f32,
+        attack: f32,
+        release: f32,
+    },
+    /// A room reverb. `room_size` and `damping` range from 0 to 1, and `wet` is how much of the reverberated signal is
+    /// mixed in, from 0 to 1
+    Reverb {
+        room_size: f32,
+        damping: f32,
+        wet: f32,
+    },
+}
+
+/// The running state of an [Effect]
+#[derive(Debug, Clone)]
+pub(crate) enum EffectState {
+    Eq([Biquad; 3]),
+    Compressor(Compressor),
+    Reverb(Box<Reverb>),
+}
+
+impl EffectState {
+    pub(crate) fn new(effect: &Effect, sample_rate: SampleRate) -> Self {
+        match *effect {
+            Effect::Eq { low, mid, high } => {
+                Self::Eq(eq_coeffs(low, mid, high, sample_rate).map(Biquad::new))
+            }
+            Effect::Compressor { .. } => {
+                let mut compressor = Compressor::default();
+                compressor.set(effect, sample_rate);
+                Self::Compressor(compressor)
+            }
+            Effect::Reverb { .. } => {
+                let mut reverb = Box::new(Reverb::new(sample_rate));
+                reverb.set(effect);
+                Self::Reverb(reverb)
+            }
+        }
+    }
+
+    /// Changes the settings of the effect while keeping its state, if `effect` is of the same kind. Returns false
+    /// otherwise.
+    pub(crate) fn update(&mut self, effect: &Effect, sample_rate: SampleRate) -> bool {
+        match (self, *effect) {
+            (Self::Eq(filters), Effect::Eq { low, mid, high }) => {
+                for (filter, c) in filters
+                    .iter_mut()
+                    .zip(eq_coeffs(low, mid, high, sample_rate))
+                {
+                    filter.set_coeffs(c);
+                }
+                true
+            }
+            (Self::Compressor(compressor), Effect::Compressor { .. }) => {
+                compressor.set(effect, sample_rate);
+                true
+            }
+            (Self::Reverb(reverb), Effect::Reverb { .. }) => {
+                reverb.set(effect);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub(crate) fn process(&mut self, buffer: &mut [Frame]) {
+        match self {
+            Self::Eq(filters) => {
+                for frame in buffer {
+                    *frame = filters
+                        .iter_mut()
+                        .fold(*frame, |frame, filter| filter.process(frame));
+                }
+            }
+            Self::Compressor(compressor) => compressor.process(buffer),
+            Self::Reverb(reverb) => reverb.process(buffer),
+        }
+    }
+}
+
+fn eq_coeffs(low: f32, mid: f32, high: f32, sample_rate: SampleRate) -> [BltCoeffs; 3] {
+    [
+        LowShelf {
+            freq: EQ_LOW_FREQ,
+            gain: low,
+        }
+        .get_coeffs(sample_rate),
+        Peak {
+            freq: EQ_MID_FREQ,
+            gain: mid,
+            q: EQ_MID_Q,
+        }
+        .get_coeffs(sample_rate),
+        HighShelf {
+            freq: EQ_HIGH_FREQ,
+            gain: high,
+        }
+        .get_coeffs(sample_rate),
+    ]
+}
+
+/// A feed forward compressor following the peak level of both channels
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Compressor {
+    threshold: f32,
+    ratio: f32,
+    attack: f32,
+    release: f32,
+    /// The current gain reduction, in dB
+    reduction: f32,
+}
+
+impl Compressor {
+    fn set(&mut self, effect: &Effect, sample_rate: SampleRate) {
+        let Effect::Compressor {
+            threshold,
+            ratio,
+            attack,
+            release,
+        } = *effect
+        else {
+            return;
+        };
+        // The smoothing factors which reach 1 - 1/e of a change within the attack and release times
+        let smoothing = |time: f32| (-1.0 / (time.max(1e-4) * sample_rate as f32)).exp();
+        self.threshold = threshold;
+        self.ratio = ratio.max(1.0);
+        self.attack = smoothing(attack);
+        self.release = smoothing(release);
+    }
+
+    fn process(&mut self, buffer: &mut [Frame]) {
+        for frame in buffer {
+            let level = 20.0 * frame.abs().max_element().max(1e-6).log10();
+            let target = (level - self.threshold).max(0.0) * (1.0 - 1.0 / self.ratio);
+            let smoothing = if target > self.reduction {
+                self.attack
+            } else {
+                self.release
+            };
+            self.reduction = target + smoothing * (self.reduction - target);
+            *frame *= 10f32.powf(-self.reduction / 20.0);
+        }
+    }
+}
+
+/// A damped feedback delay line
+#[derive(Debug, Clone)]
+struct Comb {
+    buffer: Vec<f32>,
+    index: usize,
+    filtered: f32,
+}
+
+impl Comb {
+    fn new(len: usize) -> Self {
+        Self {
+            buffer: vec![0.0; len.max(1)],
+            index: 0,
+            filtered: 0.0,
+        }
+    }
+
+    fn process(&mut self, input: f32, feedback: f32, damping: f32) -> f32 {
+        let output = self.buffer[self.index];
+        self.filtered = output * (1.0 - damping) + self.filtered * damping;
+        self.buffer[self.index] = input + self.filtered * feedback;
+        self.index = (self.index + 1) % self.buffer.len();
+        output
+    }
+}
+
+/// Diffuses the echoes of the combs
+#[derive(Debug, Clone)]
+struct Allpass {
+    buffer: Vec<f32>,
+    index: usize,
+}
+
+impl Allpass {
+    fn new(len: usize) -> Self {
+        Self {
+            buffer: vec![0.0; len.max(1)],
+            index: 0,
+        }
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        let delayed = self.buffer[self.index];
+        self.buffer[self.index] = input + delayed * 0.5;
+        self.index = (self.index + 1) % self.buffer.len();
+        delayed - input
+    }
+}
+
+/// A Schroeder reverb with the topology of Freeverb: parallel combs into serial allpasses, per channel
+#[derive(Debug, Clone)]
+pub(crate) struct Reverb {
+    combs: [Vec<Comb>; 2],
+    allpasses: [Vec<Allpass>; 2],
+    feedback: f32,
+    damping: f32,
+    wet: f32,
+}
+
+impl Reverb {
+    fn new(sample_rate: SampleRate) -> Self {
+        let scale = |len: usize| len * sample_rate as usize / 44100;
+        let channel = |spread: usize| {
+            (
+                COMB_TUNING
+                    .iter()
+                    .map(|&len| Comb::new(scale(len + spread)))
+                    .collect(),
+                ALLPASS_TUNING
+                    .iter()
+                    .map(|&len| Allpass::new(scale(len + spread)))
+                    .collect(),
+            )
+        };
+        let (left_combs, left_allpasses) = channel(0);
+        let (right_combs, right_allpasses) = channel(STEREO_SPREAD);
+        Self {
+            combs: [left_combs, right_combs],
+            allpasses: [left_allpasses, right_allpasses],
+            feedback: 0.0,
+            damping: 0.0,
+            wet: 0.0,
+        }
+    }
+
+    fn set(&mut self, effect: &Effect) {
+        let Effect::Reverb {
+            room_size,
+            damping,
+            wet,
+        } = *effect
+        else {
+            return;
+        };
+        self.feedback = room_size.clamp(0.0, 1.0) * 0.28 + 0.7;
+        self.damping = damping.clamp(0.0, 1.0) * 0.4;
+        self.wet = wet.clamp(0.0, 1.0);
+    }
+
+    fn process(&mut self, buffer: &mut [Frame]) {
+        for frame in buffer {
+            let input = (frame.x + frame.y) * REVERB_INPUT_GAIN;
+            let mut output = [0.0; 2];
+            for (channel, output) in output.iter_mut().enumerate() {
+                let mut sum = self.combs[channel]
+                    .iter_mut()
+                    .map(|comb| comb.process(input, self.feedback, self.damping))
+                    .sum::<f32>();
+                for allpass in &mut self.allpasses[channel] {
+                    sum = allpass.process(sum);
+                }
+                *output = sum;
+            }
+            *frame = *frame * (1.0 - self.wet) + Frame::from(output) * self.wet * REVERB_WET_SCALE;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f32::consts::TAU;
+
+    use super::*;
+
+    const SAMPLE_RATE: SampleRate = 48000;
+
+    fn sine(freq: f32, amplitude: f32, len: usize) -> Vec<Frame> {
+        (0..len)
+            .map(|i| Frame::splat((TAU * freq * i as f32 / SAMPLE_RATE as f32).sin() * amplitude))
+            .collect()
+    }
+
+    /// The peak level of the second half of `buffer`, after the effect settled
+    fn settled_peak(buffer: &[Frame]) -> f32 {
+        buffer[buffer.len() / 2..]
+            .iter()
+            .map(|frame| frame.abs().max_element())
+            .fold(0.0, f32::max)
+    }
+
+    fn process(effect: Effect, mut buffer: Vec<Frame>) -> Vec<Frame> {
+        EffectState::new(&effect, SAMPLE_RATE).process(&mut buffer);
+        buffer
+    }
+
+    #[test]
+    fn flat_eq_passes_through() {
+        let input = sine(440.0, 0.5, 1000);
+        let output = process(
+            Effect::Eq {
+                low: 0.0,
+                mid: 0.0,
+                high: 0.0,
+            },
+            input.clone(),
+        );
+        for (input, output) in input.iter().zip(&output) {
+            assert!((*input - *output).length() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn eq_boosts_only_its_band() {
+        let eq = Effect::Eq {
+            low: 12.0,
+            mid: 0.0,
+            high: 0.0,
+        };
+        let low = settled_peak(&process(eq, sine(50.0, 0.1, 48000)));
+        let high = settled_peak(&process(eq, sine(10000.0, 0.1, 48000)));
+        assert!(low > 0.3, "low={low}");
+        assert!((high - 0.1).abs() < 0.01, "high={high}");
+    }
+
+    #[test]
+    fn compressor_reduces_only_loud_signals() {
+        let compressor = Effect::Compressor {
+            threshold: -20.0,
+            ratio: 4.0,
+            attack: 0.001,
+            release: 0.1,
+        };
+        // 0 dB is 20 dB over the threshold, which is reduced to 5 dB over it
+        let loud = settled_peak(&process(compressor, vec![Frame::ONE; 4800]));
+        assert!(
+            (loud - 10f32.powf(-15.0 / 20.0)).abs() < 0.01,
+            "loud={loud}"
+        );
+
+        let quiet = settled_peak(&process(compressor, vec![Frame::splat(0.01); 4800]));
+        assert!((quiet - 0.01).abs() < 1e-4, "quiet={quiet}");
+    }
+
+    #[test]
+    fn reverb_has_a_tail() {
+        let mut impulse = vec![Frame::ZERO; 48000];
+        impulse[0] = Frame::ONE;
+        let reverb = |wet| Effect::Reverb {
+            room_size: 0.8,
+            damping: 0.5,
+            wet,
+        };
+
+        let output = process(reverb(1.0), impulse.clone());
+        let tail = settled_peak(&output[..24000]);
+        assert!(tail > 1e-4, "tail={tail}");
+        assert!(output.iter().all(|frame| frame.is_finite()));
+
+        // Without the wet signal, only the dry impulse is left
+        assert_eq!(process(reverb(0.0), impulse.clone()), impulse);
+    }
+
+    #[test]
+    fn updates_keep_the_state_of_the_same_kind_of_effect() {
+        let mut state = EffectState::new(
+            &Effect::Reverb {
+                room_size: 0.5,
+                damping: 0.5,
+                wet: 0.5,
+            },
+            SAMPLE_RATE,
+        );
+        assert!(state.update(
+            &Effect::Reverb {
+                room_size: 0.9,
+                damping: 0.1,
+                wet: 0.3,
+            },
+            SAMPLE_RATE
+        ));
+        assert!(!state.update(
+            &Effect::Eq {
+                low: 0.0,
+                mid: 0.0,
+                high: 0.0,
+            },
+            SAMPLE_RATE
+        ));
+    }
+}
@@ -36,6 +36,8 @@ pub enum Error {
 
     #[error("Too many channels in ogg stream. Expected a maximum of 2 channels, found {0}")]
     TooManyOggChannels(usize),
+    #[error("Mixing the audio bus {0:?} into {1:?} would make the buses loop")]
+    BusCycle(String, String),
 }
 
 pub(crate) type Result<T> = std::result::Result<T, Error>;
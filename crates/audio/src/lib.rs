@@ -1,4 +1,6 @@
 mod assets;
+mod bus;
+mod effects;
 mod error;
 mod mixer;
 // mod sink;
@@ -18,6 +20,8 @@ pub mod vorbis;
 pub mod wav;
 
 pub use assets::*;
+pub use bus::*;
+pub use effects::*;
 pub use error::*;
 pub use mixer::*;
 // pub use sink::*;
@@ -10,8 +10,10 @@ use parking_lot::Mutex;
 use slotmap::{new_key_type, SlotMap};
 
 use crate::{
+    bus::Buses,
+    error::Result,
     signal::{AsyncSignal, BlockingSignal, Signal},
-    Frame, SampleConversion, SampleRate, Source,
+    Effect, Frame, SampleConversion, SampleRate, Source, MASTER_BUS,
 };
 
 new_key_type! {
@@ -23,6 +25,8 @@ type SignalVec = Vec<(SoundId, Arc<dyn Signal>)>;
 struct PlayingSound {
     #[allow(dead_code)]
     cursor: usize,
+    /// The index of the bus the sound plays on
+    bus: usize,
     source: Box<dyn Source>,
 }
 
@@ -104,6 +108,7 @@ struct AudioMixerInner {
     sample_rate: SampleRate,
     waiters: Mutex<SignalVec>,
     sources: Mutex<SlotMap<SoundId, PlayingSound>>,
+    buses: Mutex<Buses>,
 }
 
 impl std::fmt::Debug for AudioMixerInner {
@@ -122,6 +127,7 @@ impl AudioMixer {
                 sample_rate,
                 sources: Mutex::default(),
                 waiters: Default::default(),
+                buses: Mutex::new(Buses::new(sample_rate)),
             }),
         }
     }
@@ -132,8 +138,13 @@ impl AudioMixer {
         }
     }
 
-    /// Play a source on the mixer, returning a handle which can be used to control it
+    /// Play a source on the master bus of the mixer, returning a handle which can be used to control it
     pub fn play<S: Source + 'static>(&self, source: S) -> Sound {
+        self.play_on_bus(MASTER_BUS, source)
+    }
+
+    /// Play a source on the bus `bus`, which is added under the master bus if it doesn't exist yet
+    pub fn play_on_bus<S: Source + 'static>(&self, bus: &str, source: S) -> Sound {
         let sample_rate = source.sample_rate();
 
         let source = if sample_rate == self.inner.sample_rate {
@@ -142,11 +153,12 @@ impl AudioMixer {
             Box::new(SampleConversion::new(source, self.inner.sample_rate as _)) as Box<dyn Source>
         };
 
-        let id = self
-            .inner
-            .sources
-            .lock()
-            .insert(PlayingSound { cursor: 0, source });
+        let bus = self.inner.buses.lock().get_or_insert(bus);
+        let id = self.inner.sources.lock().insert(PlayingSound {
+            cursor: 0,
+            bus,
+            source,
+        });
         Sound {
             id,
             mixer: self.clone(),
@@ -157,6 +169,27 @@ impl AudioMixer {
         self.inner.sources.lock().remove(key);
     }
 
+    /// The names of the buses of the mixer
+    pub fn bus_names(&self) -> Vec<String> {
+        self.inner.buses.lock().names()
+    }
+
+    /// Mixes the bus `bus` into `parent` rather than the master bus. Fails if it would make the buses loop.
+    pub fn set_bus_parent(&self, bus: &str, parent: &str) -> Result<()> {
+        self.inner.buses.lock().set_parent(bus, parent)
+    }
+
+    /// Moves the volume of the bus `bus` to `volume` over `transition`
+    pub fn set_bus_volume(&self, bus: &str, volume: f32, transition: Duration) {
+        let samples = (transition.as_secs_f64() * self.inner.sample_rate as f64) as u64;
+        self.inner.buses.lock().set_volume(bus, volume, samples);
+    }
+
+    /// Replaces the insert effects of the bus `bus`, which process its mix in order before its volume is applied
+    pub fn set_bus_effects(&self, bus: &str, effects: &[Effect]) {
+        self.inner.buses.lock().set_effects(bus, effects);
+    }
+
     fn notify_sound_waiters(&self, id: SoundId) {
         // Wake the wakers which are parked on this id, and remove them from the waiting list
         self.inner.waiters.lock().retain_mut(|(sound_id, signal)| {
@@ -177,22 +210,9 @@ impl AudioMixer {
 
 impl Source for AudioMixer {
     fn next_sample(&mut self) -> Option<crate::Frame> {
-        let mut sources = self.inner.sources.lock();
-        let mut res = Frame::ZERO;
-        sources.retain(|id, source| {
-            let sample = match source.source.next_sample() {
-                Some(v) => v,
-                None => {
-                    self.terminate_source(id, source);
-                    return false;
-                }
-            };
-            res += sample;
-
-            true
-        });
-
-        Some(res)
+        let mut res = [Frame::ZERO];
+        self.sample_buffered(&mut res);
+        Some(res[0])
     }
 
     fn sample_rate(&self) -> crate::SampleRate {
@@ -200,9 +220,13 @@ impl Source for AudioMixer {
     }
 
     fn sample_buffered(&mut self, output: &mut [Frame]) -> usize {
+        let mut buses = self.inner.buses.lock();
+        buses.begin(output.len());
+
         let mut sources = self.inner.sources.lock();
         sources.retain(|id, source| {
-            let written = source.source.sample_buffered(output);
+            let buffer = buses.buffer(source.bus);
+            let written = source.source.sample_buffered(buffer);
 
             // No more samples in source
             if written != output.len() {
@@ -212,7 +236,9 @@ impl Source for AudioMixer {
 
             true
         });
+        drop(sources);
 
+        buses.mix(output);
         output.len()
     }
 
@@ -3,7 +3,7 @@ use std::{ops::Deref, time::Duration};
 use glam::Vec3;
 
 use crate::{
-    hrtf::{Hrtf, HrtfContext, HrtfLib}, value::Value, AudioEmitter, AudioListener, Frame, Source, MAX_ANGULAR_SPEED, MAX_SPEED
+    blt::{Biquad, Lpf, TransferFunction}, hrtf::{Hrtf, HrtfContext, HrtfLib}, value::Value, AudioEmitter, AudioListener, Frame, Source, MAX_ANGULAR_SPEED, MAX_SPEED
 };
 
 #[derive(Debug)]
@@ -17,10 +17,14 @@ pub struct Spatial<S, L, E> {
     cur: usize,
     listener: L,
     emitter: E,
+    /// The low-pass of the [crate::Propagation] of the emitter, and its cutoff frequency
+    lowpass: Option<(Biquad, f32)>,
 }
 
 const BLOCK_DURATION: Duration = Duration::from_millis(15);
 const INTERPOLATION_STEPS: u32 = 8;
+/// The bandwidth of the propagation low-pass, in octaves, for a Butterworth response
+const LOWPASS_BANDWIDTH: f32 = 1.9;
 
 impl<S, L, E> Spatial<S, L, E>
 where
@@ -42,7 +46,7 @@ where
                     .deref()
                     .transform()
                     .inverse()
-                    .transform_point3(emitter.pos),
+                    .transform_point3(emitter.perceived_pos()),
                 &listener,
                 &emitter,
             )
@@ -56,6 +60,30 @@ where
             len: 0,
             cur: 0,
             prev_to_source: ctx.to_source(),
+            lowpass: None,
+        }
+    }
+
+    /// Keeps the low-pass filter in line with the cutoff frequency the emitter asks for
+    fn update_lowpass(&mut self, cutoff: Option<f32>) {
+        let sample_rate = self.hrtf.source().sample_rate();
+        let Some(cutoff) = cutoff.map(|cutoff| cutoff.clamp(20.0, sample_rate as f32 * 0.45))
+        else {
+            self.lowpass = None;
+            return;
+        };
+        let coeffs = Lpf {
+            freq: cutoff,
+            bandwidth: LOWPASS_BANDWIDTH,
+        }
+        .get_coeffs(sample_rate);
+        match &mut self.lowpass {
+            Some((_, current)) if *current == cutoff => {}
+            Some((filter, current)) => {
+                filter.set_coeffs(coeffs);
+                *current = cutoff;
+            }
+            None => self.lowpass = Some((Biquad::new(coeffs), cutoff)),
         }
     }
 
@@ -67,7 +95,7 @@ where
         let listener_inv = listener.transform.inverse();
 
         // Limit the velocity of the source to avoid clipping
-        let to_source = listener_inv.transform_point3(emitter.pos);
+        let to_source = listener_inv.transform_point3(emitter.perceived_pos());
         let mut rel = to_source - prev_to_source;
 
         // Limit the angular velocity to avoid IR sphere clipping
@@ -87,7 +115,7 @@ where
             to_source,
             listener.ear_distance / 2.0,
             emitter.attenuation,
-            emitter.amplitude,
+            emitter.amplitude * emitter.propagation.gain,
         )
     }
 }
@@ -111,15 +139,16 @@ where
             self.cur += 1;
             Some(s)
         } else {
-            let ctx = {
+            let (ctx, cutoff) = {
                 let listener = self.listener.get();
                 let emitter = self.emitter.get();
 
                 let ctx = Self::calculate_hrtf_context(self.prev_to_source, &listener, &emitter);
                 self.prev_to_source = ctx.to_source();
 
-                ctx
+                (ctx, emitter.propagation.cutoff)
             };
+            self.update_lowpass(cutoff);
 
             let new_len = self.hrtf.process(ctx, &mut self.output_buffer);
             self.len = new_len;
@@ -128,6 +157,12 @@ where
                 return None;
             }
 
+            if let Some((filter, _)) = &mut self.lowpass {
+                for frame in &mut self.output_buffer[..new_len] {
+                    *frame = filter.process(*frame);
+                }
+            }
+
             Some(self.output_buffer[0])
         }
     }
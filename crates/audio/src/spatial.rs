@@ -25,6 +25,9 @@ pub struct AudioEmitter {
     pub amplitude: f32,
     pub pos: Vec3,
     pub attenuation: Attenuation,
+    #[editor(hidden)]
+    #[serde(default)]
+    pub propagation: Propagation,
 }
 
 impl Default for AudioEmitter {
@@ -33,6 +36,38 @@ impl Default for AudioEmitter {
             amplitude: 1.0,
             pos: Default::default(),
             attenuation: Default::default(),
+            propagation: Default::default(),
+        }
+    }
+}
+
+impl AudioEmitter {
+    /// The position the sound seems to come from
+    pub fn perceived_pos(&self) -> Vec3 {
+        self.propagation.pos.unwrap_or(self.pos)
+    }
+}
+
+/// How the sound of an [AudioEmitter] reaches the listener when it can't go straight to it, like around corners or
+/// through walls
+#[derive(Copy, Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Propagation {
+    /// Where the sound seems to come from instead of the emitter: in the direction of the opening it reaches the
+    /// listener through, as far away as the path it travels, so that it is attenuated and delayed by the length of the
+    /// path
+    pub pos: Option<Vec3>,
+    /// The gain of the sound for what it passes through
+    pub gain: f32,
+    /// The cutoff frequency in Hz of the low-pass filter that muffles the sound, if any
+    pub cutoff: Option<f32>,
+}
+
+impl Default for Propagation {
+    fn default() -> Self {
+        Self {
+            pos: None,
+            gain: 1.0,
+            cutoff: None,
         }
     }
 }
@@ -0,0 +1,238 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use ambient_core::{
+    asset_cache,
+    async_ecs::async_run,
+    camera::{get_active_camera, inv_local_to_world, projection},
+    dtime, main_scene,
+    player::local_user_id,
+    runtime,
+    transform::{get_world_position, translation},
+    window::clip_to_screen_space,
+};
+use ambient_ecs::{
+    children, components,
+    generated::components::core::{rendering::color, text::font_size},
+    query, Entity, EntityId, FnSystem, SystemGroup, World,
+};
+use ambient_element::{element_component, Element, ElementComponentExt, Hooks};
+use ambient_std::{
+    asset_cache::AsyncAssetKeyExt, asset_url::AbsAssetUrl, download_asset::BytesFromUrl,
+};
+use ambient_ui_native::{Text, UIBase};
+use glam::{vec4, Vec2, Vec4Swizzles};
+
+mod tree;
+pub use tree::*;
+
+pub use ambient_ecs::generated::components::core::behavior_tree::{
+    behavior_tree_blackboard_keys, behavior_tree_blackboard_values, behavior_tree_debug,
+    behavior_tree_from_url, behavior_tree_running_path, behavior_tree_task,
+    behavior_tree_task_result,
+};
+
+components!("behavior_tree", {
+    behavior_tree: Arc<Node>,
+    behavior_tree_state: Vec<NodeState>,
+});
+
+/// An agent in the world, whose blackboard is read from and written back to its components
+struct WorldAgent<'a> {
+    world: &'a mut World,
+    id: EntityId,
+    blackboard: BTreeMap<String, String>,
+    /// The blackboard before the tick, to only write it back if it changed
+    original_blackboard: BTreeMap<String, String>,
+}
+impl<'a> WorldAgent<'a> {
+    fn new(world: &'a mut World, id: EntityId) -> Self {
+        let keys = world
+            .get_cloned(id, behavior_tree_blackboard_keys())
+            .unwrap_or_default();
+        let values = world
+            .get_cloned(id, behavior_tree_blackboard_values())
+            .unwrap_or_default();
+        let blackboard: BTreeMap<_, _> = keys.into_iter().zip(values).collect();
+        Self {
+            world,
+            id,
+            original_blackboard: blackboard.clone(),
+            blackboard,
+        }
+    }
+
+    fn write_blackboard(self) {
+        if self.blackboard == self.original_blackboard {
+            return;
+        }
+        let (keys, values): (Vec<_>, Vec<_>) = self.blackboard.into_iter().unzip();
+        self.world
+            .add_components(
+                self.id,
+                Entity::new()
+                    .with(behavior_tree_blackboard_keys(), keys)
+                    .with(behavior_tree_blackboard_values(), values),
+            )
+            .ok();
+    }
+}
+impl<'a> Agent for WorldAgent<'a> {
+    fn blackboard(&mut self) -> &mut BTreeMap<String, String> {
+        &mut self.blackboard
+    }
+
+    fn task(&mut self, name: &str) -> Status {
+        let current = self.world.get_ref(self.id, behavior_tree_task()).ok();
+        if current.map(|task| task.as_str()) != Some(name) {
+            self.world
+                .add_component(self.id, behavior_tree_task(), name.to_string())
+                .ok();
+            self.world
+                .remove_component(self.id, behavior_tree_task_result())
+                .ok();
+            return Status::Running;
+        }
+        match self.world.get(self.id, behavior_tree_task_result()) {
+            Ok(success) => {
+                self.world
+                    .remove_components(
+                        self.id,
+                        vec![
+                            behavior_tree_task().desc(),
+                            behavior_tree_task_result().desc(),
+                        ],
+                    )
+                    .ok();
+                if success {
+                    Status::Success
+                } else {
+                    Status::Failure
+                }
+            }
+            Err(_) => Status::Running,
+        }
+    }
+}
+
+/// Loads behavior trees and runs them on the server.
+pub fn systems() -> SystemGroup {
+    SystemGroup::new(
+        "behavior_tree",
+        vec![
+            query(behavior_tree_from_url().changed()).to_system(|q, world, qs, _| {
+                for (id, url) in q.collect_cloned(world, qs) {
+                    let url = match AbsAssetUrl::parse(url) {
+                        Ok(value) => value,
+                        Err(err) => {
+                            log::warn!("Failed to parse behavior_tree_from_url url: {:?}", err);
+                            continue;
+                        }
+                    };
+                    let assets = world.resource(asset_cache()).clone();
+                    let async_run = world.resource(async_run()).clone();
+                    world.resource(runtime()).spawn(async move {
+                        let tree = BytesFromUrl::new(url, true)
+                            .get(&assets)
+                            .await
+                            .map_err(anyhow::Error::from)
+                            .and_then(|bytes| Ok(serde_json::from_slice::<Node>(&bytes)?));
+                        match tree {
+                            Err(err) => {
+                                log::warn!("Failed to load behavior tree: {:?}", err);
+                            }
+                            Ok(tree) => {
+                                async_run.run(move |world| {
+                                    let state = vec![NodeState::default(); tree.size()];
+                                    world
+                                        .add_components(
+                                            id,
+                                            Entity::new()
+                                                .with(behavior_tree(), Arc::new(tree))
+                                                .with(behavior_tree_state(), state),
+                                        )
+                                        .ok();
+                                    world.remove_component(id, behavior_tree_task()).ok();
+                                });
+                            }
+                        }
+                    });
+                }
+            }),
+            Box::new(FnSystem::new(|world, _| {
+                ambient_profiling::scope!("behavior_tree_tick");
+                let dt = *world.resource(dtime());
+                for (id, (tree, mut state)) in
+                    query((behavior_tree(), behavior_tree_state())).collect_cloned(world, None)
+                {
+                    let mut agent = WorldAgent::new(world, id);
+                    tree.tick(&mut state, &mut agent, dt);
+                    agent.write_blackboard();
+                    if world.has_component(id, behavior_tree_debug()) {
+                        let path = tree.running_path(&state).join(" > ");
+                        if world.get_ref(id, behavior_tree_running_path()).ok() != Some(&path) {
+                            world
+                                .add_component(id, behavior_tree_running_path(), path)
+                                .ok();
+                        }
+                    }
+                    world.set(id, behavior_tree_state(), state).ok();
+                }
+            })),
+        ],
+    )
+}
+
+/// Shows the running nodes of the behavior trees of agents with `behavior_tree_debug` above them.
+#[element_component]
+pub fn BehaviorTreeDebug(hooks: &mut Hooks) -> Element {
+    let (labels, set_labels) = hooks.use_state(Vec::<(Vec2, String)>::new());
+    {
+        let labels = labels.clone();
+        hooks.use_frame(move |world| {
+            let next = collect_labels(world);
+            if next != labels {
+                set_labels(next);
+            }
+        });
+    }
+
+    UIBase.el().init_default(children()).children(
+        labels
+            .into_iter()
+            .map(|(position, path)| {
+                Text::el(path)
+                    .with(font_size(), 12.)
+                    .with(color(), vec4(1., 1., 0.3, 1.))
+                    .with(translation(), position.extend(-0.1))
+            })
+            .collect(),
+    )
+}
+
+fn collect_labels(world: &World) -> Vec<(Vec2, String)> {
+    let Some(camera) = get_active_camera(world, main_scene(), world.resource_opt(local_user_id()))
+    else {
+        return Vec::new();
+    };
+    let (Ok(projection), Ok(view)) = (
+        world.get(camera, projection()),
+        world.get(camera, inv_local_to_world()),
+    ) else {
+        return Vec::new();
+    };
+    let projection_view = projection * view;
+    query(behavior_tree_running_path())
+        .incl(behavior_tree_debug())
+        .iter(world, None)
+        .filter_map(|(id, path)| {
+            let clip = projection_view * get_world_position(world, id).ok()?.extend(1.);
+            if clip.w <= 0. {
+                return None;
+            }
+            Some((
+                clip_to_screen_space(world, clip.xy() / clip.w),
+                path.clone(),
+            ))
+        })
+        .collect()
+}
@@ -0,0 +1,348 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// The result of running a node for a tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Success,
+    Failure,
+    /// The node hasn't finished yet, and will continue where it left off on the next tick
+    Running,
+}
+
+/// A node of a behavior tree. Trees are loaded from JSON, with the kind of node in `type`, e.g.
+/// `{ "type": "sequence", "children": [{ "type": "task", "name": "patrol" }] }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Node {
+    /// Runs its children in order until one of them fails
+    Sequence { children: Vec<Node> },
+    /// Runs its children in order until one of them succeeds
+    Selector { children: Vec<Node> },
+    /// Succeeds if its child fails, and fails if it succeeds
+    Inverter { child: Box<Node> },
+    /// Succeeds once its child is done, whatever the result
+    Succeeder { child: Box<Node> },
+    /// Runs its child again each time it succeeds, up to `count` times (or forever if there is no count); fails as
+    /// soon as the child fails. The child is run at most once per tick.
+    Repeat {
+        child: Box<Node>,
+        #[serde(default)]
+        count: Option<u32>,
+    },
+    /// Runs for `seconds`, then succeeds
+    Wait { seconds: f32 },
+    /// Succeeds if the blackboard's `key` is `value`, or if it is set at all when there is no value
+    BlackboardIs {
+        key: String,
+        #[serde(default)]
+        value: Option<String>,
+    },
+    /// Sets the blackboard's `key` to `value`, or removes it if there is no value, and succeeds
+    BlackboardSet {
+        key: String,
+        #[serde(default)]
+        value: Option<String>,
+    },
+    /// A leaf task implemented by a module
+    Task { name: String },
+}
+
+/// The per-agent state of a node, which lets running nodes continue where they left off.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NodeState {
+    /// The child a composite is at, or the number of repetitions of a `Repeat`
+    pub index: u32,
+    /// How long a `Wait` has been running for
+    pub elapsed: f32,
+}
+
+/// What the tree needs from the agent it runs on.
+pub trait Agent {
+    fn blackboard(&mut self) -> &mut BTreeMap<String, String>;
+    /// Runs the task with the given name for a tick
+    fn task(&mut self, name: &str) -> Status;
+}
+
+impl Node {
+    /// The number of nodes in this subtree, including this node. Nodes are numbered in pre-order.
+    pub fn size(&self) -> usize {
+        1 + self.children().map(Node::size).sum::<usize>()
+    }
+
+    fn children(&self) -> Box<dyn Iterator<Item = &Node> + '_> {
+        match self {
+            Node::Sequence { children } | Node::Selector { children } => Box::new(children.iter()),
+            Node::Inverter { child } | Node::Succeeder { child } | Node::Repeat { child, .. } => {
+                Box::new(std::iter::once(child.as_ref()))
+            }
+            Node::Wait { .. }
+            | Node::BlackboardIs { .. }
+            | Node::BlackboardSet { .. }
+            | Node::Task { .. } => Box::new(std::iter::empty()),
+        }
+    }
+
+    /// A short description of the node, for debugging
+    pub fn label(&self) -> String {
+        match self {
+            Node::Sequence { .. } => "Sequence".to_string(),
+            Node::Selector { .. } => "Selector".to_string(),
+            Node::Inverter { .. } => "Inverter".to_string(),
+            Node::Succeeder { .. } => "Succeeder".to_string(),
+            Node::Repeat { .. } => "Repeat".to_string(),
+            Node::Wait { seconds } => format!("Wait({seconds}s)"),
+            Node::BlackboardIs { key, .. } => format!("BlackboardIs({key})"),
+            Node::BlackboardSet { key, .. } => format!("BlackboardSet({key})"),
+            Node::Task { name } => format!("Task({name})"),
+        }
+    }
+
+    /// Runs the tree for a tick of `dt` seconds. `state` must have an entry for each node of the tree (see
+    /// [Node::size]).
+    pub fn tick(&self, state: &mut [NodeState], agent: &mut dyn Agent, dt: f32) -> Status {
+        self.tick_node(0, state, agent, dt)
+    }
+
+    fn tick_node(
+        &self,
+        id: usize,
+        state: &mut [NodeState],
+        agent: &mut dyn Agent,
+        dt: f32,
+    ) -> Status {
+        let status = match self {
+            Node::Sequence { children } | Node::Selector { children } => {
+                let stop_at = match self {
+                    Node::Sequence { .. } => Status::Failure,
+                    _ => Status::Success,
+                };
+                let start = state[id].index as usize;
+                let mut child_id = id
+                    + 1
+                    + children[..start.min(children.len())]
+                        .iter()
+                        .map(Node::size)
+                        .sum::<usize>();
+                let mut status = match stop_at {
+                    Status::Failure => Status::Success,
+                    _ => Status::Failure,
+                };
+                for (i, child) in children.iter().enumerate().skip(start) {
+                    match child.tick_node(child_id, state, agent, dt) {
+                        Status::Running => {
+                            state[id].index = i as u32;
+                            return Status::Running;
+                        }
+                        result if result == stop_at => {
+                            status = stop_at;
+                            break;
+                        }
+                        _ => {}
+                    }
+                    child_id += child.size();
+                }
+                status
+            }
+            Node::Inverter { child } => match child.tick_node(id + 1, state, agent, dt) {
+                Status::Success => Status::Failure,
+                Status::Failure => Status::Success,
+                Status::Running => Status::Running,
+            },
+            Node::Succeeder { child } => match child.tick_node(id + 1, state, agent, dt) {
+                Status::Running => Status::Running,
+                _ => Status::Success,
+            },
+            Node::Repeat { child, count } => match child.tick_node(id + 1, state, agent, dt) {
+                Status::Running => Status::Running,
+                Status::Failure => Status::Failure,
+                Status::Success => {
+                    state[id].index += 1;
+                    if matches!(count, Some(count) if state[id].index >= *count) {
+                        Status::Success
+                    } else {
+                        return Status::Running;
+                    }
+                }
+            },
+            Node::Wait { seconds } => {
+                state[id].elapsed += dt;
+                if state[id].elapsed >= *seconds {
+                    Status::Success
+                } else {
+                    return Status::Running;
+                }
+            }
+            Node::BlackboardIs { key, value } => {
+                let matches = match (agent.blackboard().get(key), value) {
+                    (Some(current), Some(value)) => current == value,
+                    (current, None) => current.is_some(),
+                    (None, Some(_)) => false,
+                };
+                if matches {
+                    Status::Success
+                } else {
+                    Status::Failure
+                }
+            }
+            Node::BlackboardSet { key, value } => {
+                match value {
+                    Some(value) => agent.blackboard().insert(key.clone(), value.clone()),
+                    None => agent.blackboard().remove(key),
+                };
+                Status::Success
+            }
+            Node::Task { name } => agent.task(name),
+        };
+        if status != Status::Running {
+            // Finished nodes start over the next time they are run
+            state[id..id + self.size()].fill(NodeState::default());
+        }
+        status
+    }
+
+    /// The labels of the running nodes, from the root to the deepest running node
+    pub fn running_path(&self, state: &[NodeState]) -> Vec<String> {
+        let mut path = vec![self.label()];
+        let (mut node, mut id) = (self, 0);
+        loop {
+            let index = match node {
+                Node::Sequence { .. } | Node::Selector { .. } => state[id].index as usize,
+                _ => 0,
+            };
+            let mut child_id = id + 1;
+            let Some(child) = node.children().enumerate().find_map(|(i, child)| {
+                if i == index {
+                    Some(child)
+                } else {
+                    child_id += child.size();
+                    None
+                }
+            }) else {
+                return path;
+            };
+            path.push(child.label());
+            (node, id) = (child, child_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct TestAgent {
+        blackboard: BTreeMap<String, String>,
+        /// The results of the tasks; tasks that aren't in here are running
+        results: BTreeMap<String, Status>,
+        ran: Vec<String>,
+    }
+    impl Agent for TestAgent {
+        fn blackboard(&mut self) -> &mut BTreeMap<String, String> {
+            &mut self.blackboard
+        }
+        fn task(&mut self, name: &str) -> Status {
+            self.ran.push(name.to_string());
+            self.results.get(name).copied().unwrap_or(Status::Running)
+        }
+    }
+
+    fn task(name: &str) -> Node {
+        Node::Task {
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let tree: Node = serde_json::from_str(
+            r#"{ "type": "selector", "children": [
+                { "type": "sequence", "children": [
+                    { "type": "blackboard_is", "key": "enemy_visible" },
+                    { "type": "task", "name": "attack" }
+                ] },
+                { "type": "repeat", "child": { "type": "wait", "seconds": 1.5 } }
+            ] }"#,
+        )
+        .unwrap();
+        assert_eq!(tree.size(), 6);
+        assert_eq!(
+            tree.running_path(&[NodeState::default(); 6]),
+            ["Selector", "Sequence", "BlackboardIs(enemy_visible)"]
+        );
+    }
+
+    #[test]
+    fn sequence_resumes_running_child() {
+        let tree = Node::Sequence {
+            children: vec![task("a"), task("b"), task("c")],
+        };
+        let mut state = vec![NodeState::default(); tree.size()];
+        let mut agent = TestAgent::default();
+        agent.results.insert("a".to_string(), Status::Success);
+
+        assert_eq!(tree.tick(&mut state, &mut agent, 0.1), Status::Running);
+        assert_eq!(tree.running_path(&state), ["Sequence", "Task(b)"]);
+        agent.results.insert("b".to_string(), Status::Success);
+        agent.results.insert("c".to_string(), Status::Failure);
+        agent.ran.clear();
+        assert_eq!(tree.tick(&mut state, &mut agent, 0.1), Status::Failure);
+        assert_eq!(agent.ran, ["b", "c"]);
+        // The sequence starts over after finishing
+        assert_eq!(state, vec![NodeState::default(); 4]);
+    }
+
+    #[test]
+    fn selector_and_blackboard() {
+        let tree = Node::Selector {
+            children: vec![
+                Node::Sequence {
+                    children: vec![
+                        Node::BlackboardIs {
+                            key: "mode".to_string(),
+                            value: Some("flee".to_string()),
+                        },
+                        task("flee"),
+                    ],
+                },
+                Node::BlackboardSet {
+                    key: "mode".to_string(),
+                    value: Some("flee".to_string()),
+                },
+            ],
+        };
+        let mut state = vec![NodeState::default(); tree.size()];
+        let mut agent = TestAgent::default();
+        assert_eq!(tree.tick(&mut state, &mut agent, 0.1), Status::Success);
+        assert_eq!(agent.blackboard["mode"], "flee");
+        assert!(agent.ran.is_empty());
+        assert_eq!(tree.tick(&mut state, &mut agent, 0.1), Status::Running);
+        assert_eq!(agent.ran, ["flee"]);
+    }
+
+    #[test]
+    fn wait_and_repeat() {
+        let tree = Node::Repeat {
+            child: Box::new(Node::Wait { seconds: 0.25 }),
+            count: Some(2),
+        };
+        let mut state = vec![NodeState::default(); tree.size()];
+        let mut agent = TestAgent::default();
+        let statuses = (0..5)
+            .map(|_| tree.tick(&mut state, &mut agent, 0.1))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            [
+                Status::Running,
+                Status::Running,
+                Status::Running,
+                Status::Running,
+                Status::Running
+            ]
+        );
+        assert_eq!(tree.tick(&mut state, &mut agent, 0.1), Status::Success);
+    }
+}
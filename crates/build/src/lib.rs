@@ -10,10 +10,13 @@ use ambient_std::{asset_url::AbsAssetUrl, path::path_to_unix_string};
 use anyhow::Context;
 use futures::FutureExt;
 use itertools::Itertools;
-use pipelines::{FileCollection, ProcessCtx, ProcessCtxKey};
+use pipelines::{cache::BuildCache, FileCollection, ProcessCtx, ProcessCtxKey};
 use walkdir::WalkDir;
 
+pub mod mods;
+pub mod package;
 pub mod pipelines;
+pub mod update;
 
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
 pub struct Metadata {
@@ -70,11 +73,14 @@ async fn build_assets(physics: Physics, assets_path: &Path, build_path: &Path) {
         .collect_vec();
     let assets = AssetCache::new_with_config(tokio::runtime::Handle::current(), None);
     PhysicsKey.insert(&assets, physics);
+    let in_root = AbsAssetUrl::from_directory_path(assets_path);
+    let cache = Arc::new(BuildCache::load(build_path.join(".cache").join("pipelines.json"), in_root.clone(), build_path.join("assets")));
     let ctx = ProcessCtx {
         assets: assets.clone(),
         files: FileCollection(Arc::new(files)),
-        in_root: AbsAssetUrl::from_directory_path(assets_path),
+        in_root,
         out_root: AbsAssetUrl::from_directory_path(build_path.join("assets")),
+        cache: Some(cache.clone()),
         input_file_filter: None,
         package_name: "".to_string(),
         write_file: Arc::new({
@@ -100,6 +106,9 @@ async fn build_assets(physics: Physics, assets_path: &Path, build_path: &Path) {
     };
     ProcessCtxKey.insert(&ctx.assets, ctx.clone());
     pipelines::process_pipelines(&ctx).await;
+    if let Err(err) = cache.save() {
+        log::warn!("{:?}", err);
+    }
 }
 
 async fn build_rust_if_available(project_path: &Path, manifest: &ProjectManifest, build_path: &Path, optimize: bool) -> anyhow::Result<()> {
@@ -0,0 +1,250 @@
+//! Mods are built Ambient projects that are loaded into the server of another project, next to its own modules, and
+//! run in a restricted capability profile.
+//!
+//! They are found in a local directory, where every subdirectory is a mod with its build directory in `build/`, or in a
+//! listing on a server: a JSON array of the URLs of the build directories of the mods, relative to the listing. A mod lists the mods it needs in the `[mod]` section of its
+//! manifest, and is loaded after them.
+
+use std::{collections::BTreeMap, path::Path};
+
+use ambient_asset_cache::AssetCache;
+use ambient_project::{Identifier, Manifest as ProjectManifest};
+use ambient_std::{
+    asset_url::AbsAssetUrl,
+    pak::{self, Pak},
+};
+use anyhow::Context;
+
+use crate::{package::ASSETS_PAK, Metadata};
+
+/// The directory of a project that its mods are loaded from, unless another source is given
+pub const MODS_DIR: &str = "mods";
+/// The build directory of a mod in a local directory of mods; only it is served to clients
+pub const MOD_BUILD_DIR: &str = "build";
+
+#[derive(Clone, Debug)]
+pub struct ModPackage {
+    pub manifest: ProjectManifest,
+    pub metadata: Metadata,
+    /// The URL of the build directory of the mod
+    pub build_url: AbsAssetUrl,
+}
+
+impl ModPackage {
+    /// Loads the manifest and build metadata of the mod built to `build_url`, and mounts its pak if it was packaged
+    pub async fn load(assets: &AssetCache, build_url: AbsAssetUrl) -> anyhow::Result<Self> {
+        if let Some(build_path) = build_url.to_file_path()? {
+            let pak_path = build_path.join(ASSETS_PAK);
+            if pak_path.exists() {
+                pak::mount(assets, &build_path, Pak::open(pak_path)?);
+            }
+        }
+        let manifest = build_url
+            .push("ambient.toml")?
+            .download_string(assets)
+            .await
+            .context("Failed to download ambient.toml")?;
+        let metadata = build_url
+            .push("metadata.toml")?
+            .download_string(assets)
+            .await
+            .context("Failed to download metadata.toml")?;
+        Ok(Self {
+            manifest: ProjectManifest::parse(&manifest)?,
+            metadata: Metadata::parse(&metadata)?,
+            build_url,
+        })
+    }
+
+    pub fn id(&self) -> &Identifier {
+        &self.manifest.project.id
+    }
+
+    pub fn dependencies(&self) -> &[Identifier] {
+        &self.manifest.mod_.dependencies
+    }
+}
+
+/// Finds the mods at `source`, which is either a local directory of mods or a listing of them (see [self]). Mods that
+/// can't be loaded are skipped with a warning.
+pub async fn enumerate(
+    assets: &AssetCache,
+    source: &AbsAssetUrl,
+) -> anyhow::Result<Vec<ModPackage>> {
+    let build_urls = match source.to_file_path()? {
+        Some(dir) => local_build_urls(&dir)?,
+        None => {
+            let listing: Vec<String> = source
+                .download_json(assets)
+                .await
+                .with_context(|| format!("Failed to download the mod listing at {source}"))?;
+            listing
+                .iter()
+                .map(|url| source.join(url))
+                .collect::<Result<_, _>>()?
+        }
+    };
+
+    let mut mods = Vec::new();
+    for build_url in build_urls {
+        match ModPackage::load(assets, build_url.clone()).await {
+            Ok(package) => mods.push(package),
+            Err(err) => log::warn!("Failed to load the mod at {build_url}: {err:#}"),
+        }
+    }
+    Ok(mods)
+}
+
+fn local_build_urls(dir: &Path) -> anyhow::Result<Vec<AbsAssetUrl>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read {dir:?}"))? {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let build_path = path.join(MOD_BUILD_DIR);
+        if build_path.is_dir() {
+            paths.push(build_path);
+        } else {
+            log::warn!("Skipping {path:?}, which has no {MOD_BUILD_DIR} directory");
+        }
+    }
+    paths.sort();
+    Ok(paths
+        .into_iter()
+        .map(AbsAssetUrl::from_directory_path)
+        .collect())
+}
+
+/// The mods to load, in order, and the ones that can't be loaded with the reason why
+#[derive(Clone, Debug, Default)]
+pub struct ResolvedMods {
+    pub load_order: Vec<ModPackage>,
+    pub rejected: Vec<(ModPackage, String)>,
+}
+
+/// Orders `mods` so that every mod comes after the mods it depends on, and by ID otherwise.
+///
+/// Mods that depend on a mod that isn't among `mods` or is rejected itself are rejected, as are mods that depend on
+/// each other and all but the first of the mods with the same ID.
+pub fn resolve(mods: Vec<ModPackage>) -> ResolvedMods {
+    let mut resolved = ResolvedMods::default();
+    let mut pending = BTreeMap::new();
+    for package in mods {
+        if pending.contains_key(package.id()) {
+            let reason = format!("Another mod with the ID `{}` was found first", package.id());
+            resolved.rejected.push((package, reason));
+        } else {
+            pending.insert(package.id().clone(), package);
+        }
+    }
+
+    // Rejecting a mod can leave the mods that depend on it with a missing dependency in turn
+    loop {
+        let missing = pending.values().find_map(|package| {
+            let dependency = package
+                .dependencies()
+                .iter()
+                .find(|dependency| !pending.contains_key(*dependency))?;
+            Some((package.id().clone(), dependency.clone()))
+        });
+        let Some((id, dependency)) = missing else {
+            break;
+        };
+        let package = pending.remove(&id).unwrap();
+        let reason = format!("It depends on `{dependency}`, which is missing or can't be loaded");
+        resolved.rejected.push((package, reason));
+    }
+
+    loop {
+        let next = pending
+            .values()
+            .find(|package| {
+                package
+                    .dependencies()
+                    .iter()
+                    .all(|dependency| !pending.contains_key(dependency))
+            })
+            .map(|package| package.id().clone());
+        let Some(id) = next else {
+            break;
+        };
+        resolved.load_order.push(pending.remove(&id).unwrap());
+    }
+
+    for (_, package) in pending {
+        let reason = "Its dependencies depend on it in turn".to_string();
+        resolved.rejected.push((package, reason));
+    }
+    resolved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(id: &str, dependencies: &[&str]) -> ModPackage {
+        let manifest = format!(
+            "[project]\nid = \"{id}\"\nversion = \"0.0.1\"\n\n[mod]\ndependencies = {dependencies:?}\n"
+        );
+        ModPackage {
+            manifest: ProjectManifest::parse(&manifest).unwrap(),
+            metadata: Metadata::parse("client_component_paths = []\nserver_component_paths = []")
+                .unwrap(),
+            build_url: AbsAssetUrl::parse(format!("https://example.com/{id}/")).unwrap(),
+        }
+    }
+
+    fn ids(mods: &[ModPackage]) -> Vec<&str> {
+        mods.iter().map(|package| package.id().as_ref()).collect()
+    }
+
+    fn rejected_ids(resolved: &ResolvedMods) -> Vec<&str> {
+        resolved
+            .rejected
+            .iter()
+            .map(|(package, _)| package.id().as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn dependencies_are_loaded_first() {
+        let resolved = resolve(vec![
+            package("weapons", &["base"]),
+            package("armor", &[]),
+            package("base", &[]),
+            package("skins", &["weapons", "armor"]),
+        ]);
+        assert_eq!(
+            ids(&resolved.load_order),
+            ["armor", "base", "weapons", "skins"]
+        );
+        assert!(resolved.rejected.is_empty());
+    }
+
+    #[test]
+    fn missing_dependencies_are_rejected() {
+        let resolved = resolve(vec![
+            package("weapons", &["base"]),
+            package("skins", &["weapons"]),
+            package("armor", &[]),
+        ]);
+        assert_eq!(ids(&resolved.load_order), ["armor"]);
+        assert_eq!(rejected_ids(&resolved), ["weapons", "skins"]);
+    }
+
+    #[test]
+    fn cycles_and_duplicates_are_rejected() {
+        let resolved = resolve(vec![
+            package("a", &["b"]),
+            package("b", &["a"]),
+            package("c", &[]),
+            package("c", &["a"]),
+        ]);
+        assert_eq!(ids(&resolved.load_order), ["c"]);
+        assert_eq!(rejected_ids(&resolved), ["c", "a", "b"]);
+    }
+}
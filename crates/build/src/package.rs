@@ -0,0 +1,136 @@
+use std::path::{Path, PathBuf};
+
+use ambient_std::{
+    pak::{self, PakKey},
+    path::path_to_unix_string,
+};
+use anyhow::Context;
+use walkdir::WalkDir;
+
+/// The name of the pak the files of a packaged build are packed into, in its build directory
+pub const ASSETS_PAK: &str = "assets.pak";
+
+/// Files of the build that are kept next to the pak, so that they can be read and compared without opening it
+pub(crate) const LOOSE_FILES: &[&str] = &["ambient.toml", "metadata.toml"];
+
+/// This takes the path to a built Ambient project and packages it into `output_path`:
+///
+/// server/  The runtime, the manifest of the project and its build, with everything but the manifests packed into a pak.
+///          Run it with `ambient serve --no-build` from this directory, and update it with `ambient update`
+/// client/  The runtime, and a script to join a server with it
+///
+/// If `encrypt` is set, the pak is encrypted with the key in `AMBIENT_PAK_KEY`, or with a new key that is written to
+/// `pak.key` in `output_path` if there is none.
+pub fn package(project_path: &Path, output_path: &Path, encrypt: bool) -> anyhow::Result<()> {
+    let build_path = project_path.join("build");
+    anyhow::ensure!(
+        build_path.join("metadata.toml").exists(),
+        "The project at {project_path:?} has not been built"
+    );
+    let runtime = std::env::current_exe().context("Failed to find the runtime executable")?;
+    std::fs::create_dir_all(output_path)?;
+    let key = if encrypt {
+        Some(provision_key(output_path)?)
+    } else {
+        None
+    };
+
+    let server_path = output_path.join("server");
+    let server_build_path = server_path.join("build");
+    if server_path.exists() {
+        std::fs::remove_dir_all(&server_path)
+            .with_context(|| format!("Failed to clear {server_path:?}"))?;
+    }
+    std::fs::create_dir_all(&server_build_path)?;
+    copy_runtime(&runtime, &server_path)?;
+    std::fs::copy(
+        project_path.join("ambient.toml"),
+        server_path.join("ambient.toml"),
+    )?;
+    for name in LOOSE_FILES {
+        std::fs::copy(build_path.join(name), server_build_path.join(name))?;
+    }
+    let index = pak::write_pak(
+        &server_build_path.join(ASSETS_PAK),
+        packed_files(&build_path),
+        key.as_ref(),
+    )?;
+    write_script(&server_path, &runtime, "serve", "serve --no-build .")?;
+    write_script(&server_path, &runtime, "update", "update")?;
+    log::info!(
+        "Packed {} files into {} chunks",
+        index.files.len(),
+        index.chunks.len()
+    );
+
+    let client_path = output_path.join("client");
+    std::fs::create_dir_all(&client_path)?;
+    copy_runtime(&runtime, &client_path)?;
+    write_script(&client_path, &runtime, "join", "join")?;
+
+    Ok(())
+}
+
+/// The files of the build at `build_path` that go into the pak, by their path in the pak
+fn packed_files(build_path: &Path) -> Vec<(String, PathBuf)> {
+    let mut files = WalkDir::new(build_path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.metadata().map(|x| x.is_file()).unwrap_or(false))
+        .map(|e| e.into_path())
+        .filter_map(|path| {
+            let relative = path_to_unix_string(path.strip_prefix(build_path).ok()?);
+            (!LOOSE_FILES.contains(&relative.as_str())).then_some((relative, path))
+        })
+        .collect::<Vec<_>>();
+    // Keep the chunks of a file in the same place between packages, as far as possible
+    files.sort();
+    files
+}
+
+/// The key to encrypt the pak with: the one the runtime was given, or a new one
+fn provision_key(output_path: &Path) -> anyhow::Result<PakKey> {
+    if let Some(key) = PakKey::from_env()? {
+        return Ok(key);
+    }
+    let (key, hex) = PakKey::generate()?;
+    let key_path = output_path.join("pak.key");
+    std::fs::write(&key_path, hex)?;
+    log::info!(
+        "Generated a pak key at {key_path:?}. Give it to the packaged server with {}, or compile the runtime with it, \
+         and keep it out of the package",
+        pak::PAK_KEY_VAR
+    );
+    Ok(key)
+}
+
+fn copy_runtime(runtime: &Path, dir: &Path) -> anyhow::Result<()> {
+    let file_name = runtime.file_name().context("no filename")?;
+    std::fs::copy(runtime, dir.join(file_name))
+        .with_context(|| format!("Failed to copy the runtime to {dir:?}"))?;
+    Ok(())
+}
+
+/// Writes a script that runs the runtime next to it with `args`, followed by the arguments of the script
+fn write_script(dir: &Path, runtime: &Path, name: &str, args: &str) -> anyhow::Result<()> {
+    let runtime = runtime
+        .file_name()
+        .context("no filename")?
+        .to_string_lossy();
+    #[cfg(windows)]
+    {
+        let script = format!("@echo off\r\n\"%~dp0{runtime}\" {args} %*\r\n");
+        std::fs::write(dir.join(format!("{name}.bat")), script)?;
+    }
+    #[cfg(not(windows))]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.join(format!("{name}.sh"));
+        let script =
+            format!("#!/bin/sh\ncd \"$(dirname \"$0\")\"\nexec ./{runtime} {args} \"$@\"\n");
+        std::fs::write(&path, script)?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
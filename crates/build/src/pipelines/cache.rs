@@ -0,0 +1,238 @@
+//! The persistent cache of the asset pipelines, which lets a build skip the work that the previous builds already did.
+//!
+//! The work of a pipeline is split into units: each of the files it processes, or the whole pipeline for the ones that
+//! process their files together. A unit is skipped if the content of its pipeline file, of its input file and of every
+//! file it read the last time it ran are unchanged, and the files it wrote still exist. The files a unit reads are
+//! recorded as it resolves them through its [PipelineCtx](super::context::PipelineCtx), so editing a texture only
+//! rebuilds the units that used it.
+//!
+//! The cache is stored in `build/.cache/pipelines.json`; deleting it rebuilds every asset.
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::UNIX_EPOCH,
+};
+
+use ambient_std::asset_url::AbsAssetUrl;
+use anyhow::Context;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+/// Changing the version of the pipelines rebuilds every asset, as their output may have changed
+const PIPELINES_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CacheData {
+    version: String,
+    /// The content hashes of the input files, by their path relative to the assets directory
+    files: HashMap<String, FileRecord>,
+    /// The units that completed, by their key
+    units: HashMap<String, UnitRecord>,
+}
+
+/// The content hash of a file, with the size and modification time it had when it was hashed, so that unchanged files
+/// aren't read again
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FileRecord {
+    size: u64,
+    modified: u128,
+    hash: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct UnitRecord {
+    /// The content hash of every file the unit read, including its pipeline file and input file
+    dependencies: BTreeMap<String, String>,
+    /// The files the unit wrote, relative to the output directory
+    outputs: Vec<String>,
+}
+impl UnitRecord {
+    /// Whether the unit would write the same files again, given the files it reads before it runs and the current
+    /// content hashes of the input files
+    fn is_fresh(&self, inputs: &BTreeSet<String>, hash: impl Fn(&str) -> Option<String>, output_exists: impl Fn(&str) -> bool) -> bool {
+        inputs.iter().all(|path| self.dependencies.contains_key(path))
+            && self.dependencies.iter().all(|(path, recorded)| hash(path).as_ref() == Some(recorded))
+            && self.outputs.iter().all(|path| output_exists(path))
+    }
+}
+
+/// The cache of a build; shared by all of its pipelines.
+pub struct BuildCache {
+    path: PathBuf,
+    in_root: AbsAssetUrl,
+    out_dir: PathBuf,
+    previous: CacheData,
+    current: Mutex<CacheData>,
+}
+impl BuildCache {
+    /// Loads the cache at `path`, for a build from `in_root` to `out_dir`. A missing or unreadable cache is empty.
+    pub fn load(path: PathBuf, in_root: AbsAssetUrl, out_dir: PathBuf) -> Self {
+        let previous = std::fs::read(&path)
+            .ok()
+            .and_then(|data| match serde_json::from_slice::<CacheData>(&data) {
+                Ok(data) => Some(data),
+                Err(err) => {
+                    log::warn!("Ignoring the asset pipeline cache at {path:?}: {err:?}");
+                    None
+                }
+            })
+            .filter(|data| data.version == PIPELINES_VERSION)
+            .unwrap_or_default();
+        Self {
+            path,
+            in_root,
+            out_dir,
+            current: Mutex::new(CacheData { version: PIPELINES_VERSION.to_string(), files: previous.files.clone(), units: HashMap::new() }),
+            previous,
+        }
+    }
+
+    /// Writes the units that completed in this build, and drops the ones that didn't run.
+    pub fn save(&self) -> anyhow::Result<()> {
+        let data = serde_json::to_vec(&*self.current.lock())?;
+        std::fs::create_dir_all(self.path.parent().context("The cache path has no parent")?)?;
+        std::fs::write(&self.path, data).with_context(|| format!("Failed to write the asset pipeline cache to {:?}", self.path))
+    }
+
+    fn relative_path(&self, url: &AbsAssetUrl) -> String {
+        self.in_root.relative_path(url.path()).to_string()
+    }
+
+    /// The content hash of the input file at `path`, relative to the assets directory; `None` if it doesn't exist
+    fn hash(&self, path: &str) -> Option<String> {
+        let file_path = self.in_root.push(path).ok()?.to_file_path().ok()??;
+        let metadata = std::fs::metadata(&file_path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_nanos();
+        if let Some(record) = self.current.lock().files.get(path) {
+            if record.size == metadata.len() && record.modified == modified {
+                return Some(record.hash.clone());
+            }
+        }
+        let hash = blake3::hash(&std::fs::read(&file_path).ok()?).to_hex().to_string();
+        self.current.lock().files.insert(path.to_string(), FileRecord { size: metadata.len(), modified, hash: hash.clone() });
+        Some(hash)
+    }
+
+    fn relative_paths(&self, unit: &CacheUnit) -> BTreeSet<String> {
+        unit.dependencies.lock().iter().map(|url| self.relative_path(url)).collect()
+    }
+
+    /// Whether the unit `key`, which hasn't run yet, can be skipped; if it can, it is kept in the cache.
+    pub(crate) async fn is_fresh(self: &Arc<Self>, key: &str, unit: &CacheUnit) -> bool {
+        let Some(record) = self.previous.units.get(key).cloned() else {
+            return false;
+        };
+        let inputs = self.relative_paths(unit);
+        let cache = self.clone();
+        let key = key.to_string();
+        // Hashing reads the files, so it's kept off of the async workers
+        tokio::task::spawn_blocking(move || {
+            let fresh = record.is_fresh(&inputs, |path| cache.hash(path), |path| cache.out_dir.join(path).is_file());
+            if fresh {
+                cache.current.lock().units.insert(key, record);
+            }
+            fresh
+        })
+        .await
+        .unwrap_or(false)
+    }
+
+    /// Records that the unit `key` completed, having read and written the files recorded in `unit`.
+    pub(crate) async fn complete(self: &Arc<Self>, key: String, unit: &CacheUnit) {
+        let dependencies = self.relative_paths(unit);
+        let outputs = unit.outputs.lock().clone();
+        let cache = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let dependencies = dependencies.into_iter().map(|path| cache.hash(&path).map(|hash| (path, hash))).collect::<Option<_>>();
+            // A unit that read a file that's gone can't be checked, so it runs again next time
+            if let Some(dependencies) = dependencies {
+                cache.current.lock().units.insert(key, UnitRecord { dependencies, outputs });
+            }
+        })
+        .await
+        .ok();
+    }
+}
+
+/// The files that a unit of work of a pipeline read and wrote.
+#[derive(Debug, Default)]
+pub struct CacheUnit {
+    dependencies: Mutex<BTreeSet<AbsAssetUrl>>,
+    outputs: Mutex<Vec<String>>,
+}
+impl CacheUnit {
+    /// A unit that reads `files`.
+    pub fn new(files: impl IntoIterator<Item = AbsAssetUrl>) -> Self {
+        Self { dependencies: Mutex::new(files.into_iter().map(without_fragment).collect()), outputs: Default::default() }
+    }
+    /// Records that the unit read `url`.
+    pub fn add_dependency(&self, url: &AbsAssetUrl) {
+        self.dependencies.lock().insert(without_fragment(url.clone()));
+    }
+    /// Records that the unit wrote `path`, relative to the output directory.
+    pub fn add_output(&self, path: impl AsRef<Path>) {
+        self.outputs.lock().push(path.as_ref().to_string_lossy().to_string());
+    }
+}
+
+/// The pipelines of a `pipeline.json` are told apart by the fragment of its URL
+fn without_fragment(mut url: AbsAssetUrl) -> AbsAssetUrl {
+    url.0.set_fragment(None);
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> UnitRecord {
+        UnitRecord {
+            dependencies: [("pipeline.json", "a"), ("table.glb", "b"), ("wood.png", "c")]
+                .into_iter()
+                .map(|(path, hash)| (path.to_string(), hash.to_string()))
+                .collect(),
+            outputs: vec!["table.glb/models/main.json".to_string()],
+        }
+    }
+
+    fn hashes(changed: Option<&str>) -> impl Fn(&str) -> Option<String> + '_ {
+        move |path| {
+            let hash = match path {
+                "pipeline.json" => "a",
+                "table.glb" => "b",
+                "wood.png" => "c",
+                _ => return None,
+            };
+            Some(if Some(path) == changed { "changed".to_string() } else { hash.to_string() })
+        }
+    }
+
+    fn inputs(paths: &[&str]) -> BTreeSet<String> {
+        paths.iter().map(|path| path.to_string()).collect()
+    }
+
+    #[test]
+    fn unchanged_units_are_fresh() {
+        assert!(record().is_fresh(&inputs(&["pipeline.json", "table.glb"]), hashes(None), |_| true));
+    }
+
+    #[test]
+    fn changing_a_dependency_rebuilds_the_unit() {
+        let inputs = inputs(&["pipeline.json", "table.glb"]);
+        assert!(!record().is_fresh(&inputs, hashes(Some("wood.png")), |_| true));
+        assert!(!record().is_fresh(&inputs, hashes(Some("pipeline.json")), |_| true));
+    }
+
+    #[test]
+    fn new_inputs_rebuild_the_unit() {
+        assert!(!record().is_fresh(&inputs(&["pipeline.json", "table.glb", "chair.glb"]), hashes(None), |_| true));
+    }
+
+    #[test]
+    fn missing_files_rebuild_the_unit() {
+        let inputs = inputs(&["pipeline.json", "table.glb"]);
+        assert!(!record().is_fresh(&inputs, |path| hashes(None)(path).filter(|_| path != "wood.png"), |_| true));
+        assert!(!record().is_fresh(&inputs, hashes(None), |_| false));
+    }
+}
@@ -11,7 +11,7 @@ use itertools::Itertools;
 use relative_path::{RelativePath, RelativePathBuf};
 use tokio::sync::Semaphore;
 
-use super::{out_asset::OutAsset, FileCollection, Pipeline, ProcessCtx};
+use super::{cache::CacheUnit, out_asset::OutAsset, FileCollection, Pipeline, ProcessCtx};
 
 #[derive(Clone)]
 pub struct PipelineCtx {
@@ -21,6 +21,8 @@ pub struct PipelineCtx {
     pub root_path: RelativePathBuf,
 
     pub pipeline: Arc<Pipeline>,
+    /// The unit of the build cache that is being processed, which records the files it reads and writes
+    pub cache_unit: Option<Arc<CacheUnit>>,
 }
 impl PipelineCtx {
     pub fn assets(&self) -> &AssetCache {
@@ -46,7 +48,42 @@ impl PipelineCtx {
         self.out_root().push(path).unwrap().as_directory().into()
     }
     pub async fn write_file(&self, path: impl AsRef<str>, content: Vec<u8>) -> AbsAssetUrl {
-        (self.process_ctx.write_file)(self.root_path.join(path.as_ref()).to_string(), content).await
+        let path = self.root_path.join(path.as_ref()).to_string();
+        if let Some(unit) = &self.cache_unit {
+            unit.add_output(&path);
+        }
+        (self.process_ctx.write_file)(path, content).await
+    }
+    /// Records that the output of the current file depends on `url`, so that it is processed again when `url` changes.
+    pub fn add_dependency(&self, url: &AbsAssetUrl) {
+        if let Some(unit) = &self.cache_unit {
+            unit.add_dependency(url);
+        }
+    }
+    /// Runs `process` as the unit `key` of the build cache, which reads `inputs`, unless it's unchanged since the last build.
+    async fn process_cached<F: Future<Output = anyhow::Result<Vec<OutAsset>>>>(
+        &self,
+        key: String,
+        inputs: impl IntoIterator<Item = AbsAssetUrl>,
+        process: impl FnOnce(PipelineCtx) -> F,
+    ) -> anyhow::Result<Vec<OutAsset>> {
+        let Some(cache) = self.process_ctx.cache.clone() else {
+            return process(self.clone()).await;
+        };
+        let mut inputs = inputs.into_iter().chain([self.pipeline_file.clone()]).collect_vec();
+        if self.pipeline.pipeline.depends_on_all_files() {
+            inputs.extend(self.files.0.iter().cloned());
+        }
+        let unit = Arc::new(CacheUnit::new(inputs));
+        if cache.is_fresh(&key, &unit).await {
+            (self.process_ctx.on_status)(format!("Skipping unchanged {key}")).await;
+            return Ok(Vec::new());
+        }
+        let res = process(PipelineCtx { cache_unit: Some(unit.clone()), ..self.clone() }).await;
+        if res.is_ok() {
+            cache.complete(key, &unit).await;
+        }
+        res
     }
     pub async fn process_single<F: Future<Output = anyhow::Result<Vec<OutAsset>>> + Send>(
         &self,
@@ -54,7 +91,11 @@ impl PipelineCtx {
     ) -> Vec<OutAsset> {
         let res = tokio::spawn({
             let ctx = self.clone();
-            async move { process(ctx.clone()).await.with_context(|| format!("In pipeline {}", ctx.pipeline_path())) }
+            async move {
+                ctx.process_cached(ctx.pipeline_path().to_string(), [], process)
+                    .await
+                    .with_context(|| format!("In pipeline {}", ctx.pipeline_path()))
+            }
         })
         .await
         .with_context(|| format!("In pipeline {}", self.pipeline_path()));
@@ -120,9 +161,11 @@ impl PipelineCtx {
                             file_path
                         ))
                         .await;
-                        process_file(ctx.clone(), file.clone())
-                            .await
-                            .with_context(|| format!("In pipeline {}, at file {}", ctx.pipeline_path(), file_path))
+                        ctx.process_cached(format!("{}:{}", ctx.pipeline_path(), file_path), [file.clone()], |ctx| {
+                            process_file(ctx, file.clone())
+                        })
+                        .await
+                        .with_context(|| format!("In pipeline {}, at file {}", ctx.pipeline_path(), file_path))
                     }
                 })
                 .await
@@ -142,15 +185,17 @@ impl PipelineCtx {
         .collect()
     }
     pub fn get_downloadable_url(&self, url: &AbsAssetUrl) -> anyhow::Result<&AbsAssetUrl> {
-        self.process_ctx.files.0.iter().find(|x| x.path() == url.path()).with_context(|| format!("No such file: {url}"))
+        let file = self.process_ctx.files.0.iter().find(|x| x.path() == url.path()).with_context(|| format!("No such file: {url}"))?;
+        self.add_dependency(file);
+        Ok(file)
     }
 }
 
-/// Limit the number of concurent file processings to 20
+/// Limit the number of concurent file processings to the number of cores
 #[derive(Debug)]
 struct PipelineFileSemaphore;
 impl SyncAssetKey<Arc<Semaphore>> for PipelineFileSemaphore {
     fn load(&self, _assets: AssetCache) -> Arc<Semaphore> {
-        Arc::new(Semaphore::new(20))
+        Arc::new(Semaphore::new(std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4)))
     }
 }
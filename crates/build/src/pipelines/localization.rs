@@ -0,0 +1,67 @@
+use ambient_localization::StringTable;
+use ambient_std::asset_url::AssetType;
+use serde::{Deserialize, Serialize};
+use tracing::{info_span, Instrument};
+
+use super::{
+    context::PipelineCtx,
+    out_asset::{asset_id_from_url, OutAsset, OutAssetContent, OutAssetPreview},
+};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LocalizationPipeline {
+    /// The language whose table fills in the strings missing from the other tables.
+    /// For example, `"en"` would use the English strings for anything that has not been translated yet.
+    #[serde(default)]
+    pub fallback_language: Option<String>,
+}
+
+pub async fn pipeline(ctx: &PipelineCtx, config: LocalizationPipeline) -> Vec<OutAsset> {
+    let fallback_url = config.fallback_language.as_ref().map(|language| ctx.in_root().push(format!("{language}.toml")).unwrap());
+    let fallback = match &fallback_url {
+        Some(url) => match url.download_string(ctx.assets()).await.and_then(|source| StringTable::parse(&source)) {
+            Ok(table) => Some(table),
+            Err(err) => {
+                tracing::warn!("Failed to load the fallback string table {url}: {err:?}");
+                None
+            }
+        },
+        None => None,
+    };
+
+    ctx.process_files(
+        |file| file.extension().as_deref() == Some("toml"),
+        move |ctx, file| {
+            let fallback = fallback.clone();
+            let fallback_url = fallback_url.clone();
+            async move {
+                // The output has the strings of the fallback table, so it's stale once that changes
+                if let Some(url) = &fallback_url {
+                    ctx.add_dependency(url);
+                }
+                let mut table = StringTable::parse(&file.download_string(ctx.assets()).await?)?;
+                if let Some(fallback) = &fallback {
+                    table.merge_fallback(fallback);
+                }
+
+                let name = file.path().file_stem().unwrap().to_string();
+                let rel_path = ctx.in_root().relative_path(file.path());
+                let content_url = ctx.write_file(&rel_path, table.to_toml().into_bytes()).await;
+
+                Ok(vec![OutAsset {
+                    id: asset_id_from_url(&file),
+                    type_: AssetType::StringTable,
+                    hidden: false,
+                    name,
+                    tags: Vec::new(),
+                    categories: Default::default(),
+                    preview: OutAssetPreview::None,
+                    content: OutAssetContent::Content(content_url),
+                    source: Some(file.clone()),
+                }])
+            }
+        },
+    )
+    .instrument(info_span!("localization_pipeline"))
+    .await
+}
@@ -8,7 +8,10 @@ use ambient_model_import::{
     ModelTextureSize,
 };
 use ambient_physics::collider::{collider, collider_type};
-use ambient_renderer::materials::pbr_material::PbrMaterialDesc;
+use ambient_renderer::materials::{
+    custom_material::{CustomMaterialDesc, MaterialTextureSlot},
+    pbr_material::PbrMaterialDesc,
+};
 use ambient_std::{
     asset_url::{AbsAssetUrl, AssetType, AssetUrl},
     download_asset::AssetResult,
@@ -39,6 +42,9 @@ pub enum MaterialsImporter {
     Single(PipelinePbrMaterial),
     /// Import Quixel materials.
     Quixel,
+    /// Import a single custom material, with its own shader, texture slots and uniforms.
+    /// The shader and textures will be resolved during the build process.
+    Custom(CustomMaterialDesc),
 }
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MaterialsPipeline {
@@ -75,6 +81,27 @@ pub async fn pipeline(ctx: &PipelineCtx, config: MaterialsPipeline) -> Vec<OutAs
             .await
         }
         MaterialsImporter::Quixel => quixel_surfaces::pipeline(ctx, config.clone()).await,
+        MaterialsImporter::Custom(mat) => {
+            ctx.process_single(move |ctx| async move {
+                let name = mat.name.clone().unwrap_or_else(|| ctx.pipeline_path().to_string());
+
+                let mat_out_url = ctx.out_root().join(ctx.pipeline_path())?.as_directory();
+                let material = pipe_custom_material(&ctx, &mat, &mat_out_url).await?;
+                let mat_url = ctx.write_file(ctx.pipeline_path().join("mat.json"), serde_json::to_vec(&material).unwrap()).await;
+                Ok(vec![OutAsset {
+                    id: asset_id_from_url(&ctx.out_root()),
+                    type_: AssetType::Material,
+                    hidden: false,
+                    name,
+                    tags: Default::default(),
+                    categories: Default::default(),
+                    preview: OutAssetPreview::None,
+                    content: OutAssetContent::Content(mat_url),
+                    source: None,
+                }])
+            })
+            .await
+        }
     };
     if config.output_decals {
         let mut res = materials.clone();
@@ -206,6 +233,28 @@ impl PipelinePbrMaterial {
     }
 }
 
+/// Copies the shader and textures of a custom material to the output, and returns the material with its URLs relative to `out_root`.
+async fn pipe_custom_material(ctx: &PipelineCtx, mat: &CustomMaterialDesc, out_root: &AbsAssetUrl) -> anyhow::Result<CustomMaterialDesc> {
+    let mat = mat.resolve(&ctx.in_root())?;
+    let shader = match &mat.shader {
+        Some(url) => {
+            let url = ctx.get_downloadable_url(&url.clone().unwrap_abs())?;
+            let source = url.download_string(ctx.assets()).await?;
+            Some(AssetUrl::from(ctx.write_file(ctx.in_root().relative_path(url.path()), source.into_bytes()).await))
+        }
+        None => None,
+    };
+    let mut textures = Vec::new();
+    for slot in &mat.textures {
+        let url = match &slot.url {
+            Some(url) => Some(AssetUrl::from(PipeImage::resolve(ctx, url.clone().unwrap_abs()).get(ctx.assets()).await?)),
+            None => None,
+        };
+        textures.push(MaterialTextureSlot { url, ..slot.clone() });
+    }
+    Ok(CustomMaterialDesc { shader, textures, ..mat }.relative_path_from(out_root))
+}
+
 #[clonable]
 pub trait ImageTransformer: std::fmt::Debug + Clone + Sync + Send {
     fn transform(&self, image: &mut RgbaImage, second_image: Option<&RgbaImage>);
@@ -9,13 +9,23 @@ use image::ImageFormat;
 use out_asset::{OutAsset, OutAssetContent, OutAssetPreview};
 use serde::{Deserialize, Serialize};
 
-use self::{audio::AudioPipeline, materials::MaterialsPipeline, models::ModelsPipeline};
+use self::{
+    audio::AudioPipeline,
+    cache::BuildCache,
+    localization::LocalizationPipeline,
+    materials::{MaterialsImporter, MaterialsPipeline},
+    models::ModelsPipeline,
+    plugin::PluginPipeline,
+};
 
 pub mod audio;
+pub mod cache;
 pub mod context;
+pub mod localization;
 pub mod materials;
 pub mod models;
 pub mod out_asset;
+pub mod plugin;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
@@ -29,6 +39,23 @@ pub enum PipelineConfig {
     /// The audio asset pipeline.
     /// Will import supported audio file formats and produce Ogg Vorbis or WAV files to be used by the runtime.
     Audio(AudioPipeline),
+    /// The localization asset pipeline.
+    /// Will validate string tables (`<language>.toml`) and write them with their keys flattened, to be loaded at runtime.
+    Localization(LocalizationPipeline),
+    /// A pipeline that runs a custom processor provided by the project as a WebAssembly module.
+    /// Can import any format, such as the levels of a custom level editor.
+    Plugin(PluginPipeline),
+}
+impl PipelineConfig {
+    /// Whether the output of each file may depend on any of the files of the pipeline, in ways that aren't recorded in
+    /// the build cache; such pipelines are processed again whenever any of their files change.
+    pub fn depends_on_all_files(&self) -> bool {
+        match self {
+            PipelineConfig::Models(config) => config.depends_on_all_files(),
+            PipelineConfig::Materials(config) => matches!(*config.importer, MaterialsImporter::Quixel),
+            PipelineConfig::Audio(_) | PipelineConfig::Localization(_) | PipelineConfig::Plugin(_) => false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,6 +81,8 @@ impl Pipeline {
             PipelineConfig::Models(config) => models::pipeline(&ctx, config.clone()).await,
             PipelineConfig::Materials(config) => materials::pipeline(&ctx, config.clone()).await,
             PipelineConfig::Audio(config) => audio::pipeline(&ctx, config.clone()).await,
+            PipelineConfig::Localization(config) => localization::pipeline(&ctx, config.clone()).await,
+            PipelineConfig::Plugin(config) => plugin::pipeline(&ctx, config.clone()).await,
         };
         for asset in &mut assets {
             asset.tags.extend(self.tags.clone());
@@ -109,6 +138,7 @@ pub async fn process_pipelines(ctx: &ProcessCtx) -> Vec<OutAsset> {
                 pipeline: Arc::new(pipeline.clone()),
                 pipeline_file,
                 root_path: ctx.in_root.relative_path(root.path()),
+                cache_unit: None,
             };
             tokio::spawn(async move { pipeline.process(ctx).await })
         })
@@ -131,6 +161,8 @@ pub struct ProcessCtx {
     pub package_name: String,
     pub in_root: AbsAssetUrl,
     pub out_root: AbsAssetUrl,
+    /// Skips the files that are unchanged since the last build, if set
+    pub cache: Option<Arc<BuildCache>>,
     pub write_file: Arc<dyn Fn(String, Vec<u8>) -> BoxFuture<'static, AbsAssetUrl> + Sync + Send>,
     pub on_status: Arc<dyn Fn(String) -> BoxFuture<'static, ()> + Sync + Send>,
     pub on_error: Arc<dyn Fn(anyhow::Error) -> BoxFuture<'static, ()> + Sync + Send>,
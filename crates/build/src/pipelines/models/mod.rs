@@ -1,7 +1,9 @@
 use std::{path::PathBuf, sync::Arc};
 
+use ambient_animation::AnimationCompression;
 use ambient_core::hierarchy::children;
 use ambient_ecs::Entity;
+use ambient_light_baking::{LightProbeBakeConfig, LightmapBakeConfig};
 use ambient_model_import::{model_crate::ModelCrate, MaterialFilter, ModelTextureSize, ModelTransform, TextureResolver};
 use ambient_physics::collider::{collider_type, ColliderType};
 use ambient_std::asset_url::AssetType;
@@ -77,6 +79,10 @@ pub struct ModelsPipeline {
     /// Output the animations that belonged to this model.
     #[serde(default = "true_value")]
     output_animations: bool,
+    /// If specified, the animations are compressed: keyframes that the curve through their neighbours passes close enough to
+    /// are removed, and rotations are quantized.
+    #[serde(default)]
+    animation_compression: Option<AnimationCompression>,
     /// If specified, these components will be added to the prefabs produced by `output_prefabs`.
     ///
     /// This is a great way to specify additional information about your prefab that can be used by gameplay logic.
@@ -93,8 +99,20 @@ pub struct ModelsPipeline {
     /// These will be applied in sequence.
     #[serde(default)]
     transforms: Vec<ModelTransform>,
+    /// If specified, the indirect light of the model is baked into a lightmap, which static meshes with a second UV set
+    /// will be rendered with. Other entities keep using the sun's ambient light.
+    #[serde(default)]
+    lightmap: Option<LightmapBakeConfig>,
+    /// If specified, a grid of light probes is baked around the model, which lights the entities that do not use the lightmap,
+    /// such as characters moving through the model.
+    #[serde(default)]
+    light_probes: Option<LightProbeBakeConfig>,
 }
 impl ModelsPipeline {
+    /// The Unity and Quixel importers look up the files they use across the whole pipeline.
+    pub(crate) fn depends_on_all_files(&self) -> bool {
+        !matches!(self.importer, ModelImporter::Regular)
+    }
     pub async fn apply(
         &self,
         ctx: &PipelineCtx,
@@ -112,7 +130,16 @@ impl ModelsPipeline {
         if let Some(max_size) = self.cap_texture_sizes {
             model_crate.cap_texture_sizes(max_size.size());
         }
+        if let Some(config) = &self.animation_compression {
+            model_crate.compress_animations(config);
+        }
         model_crate.finalize_model();
+        if let Some(config) = &self.lightmap {
+            model_crate.bake_lightmap(config)?;
+        }
+        if let Some(config) = &self.light_probes {
+            model_crate.bake_light_probes(config);
+        }
         match self.collider {
             Collider::None => {}
             Collider::FromModel { flip_normals, reverse_indices } => {
@@ -183,6 +210,7 @@ fn create_texture_resolver(ctx: &PipelineCtx) -> TextureResolver {
             let path: PathBuf = path.into();
             let filename = path.file_name().unwrap().to_str().unwrap().to_string();
             if let Some(file) = ctx.files.0.iter().find(|file| file.path().as_str().contains(&filename)) {
+                ctx.add_dependency(file);
                 match download_image(&ctx.process_ctx.assets, file).await {
                     Ok(img) => Some(img.into_rgba8()),
                     Err(err) => {
@@ -20,6 +20,14 @@ pub async fn pipeline(ctx: &PipelineCtx, config: ModelsPipeline) -> Vec<OutAsset
             async move {
                 let mut res = Vec::new();
 
+                // The buffers of a .gltf are separate files, which are loaded by the importer
+                if file.extension_is("gltf") {
+                    let dir = file.path().parent().map(|dir| dir.to_owned());
+                    for other in ctx.files.0.iter().filter(|other| other.path().parent().map(|dir| dir.to_owned()) == dir) {
+                        ctx.add_dependency(other);
+                    }
+                }
+
                 let mut model_crate = ModelCrate::new();
                 model_crate
                     .import(&ctx.process_ctx.assets, &file, true, config.force_assimp, create_texture_resolver(&ctx))
@@ -0,0 +1,329 @@
+//! Runs the asset processors that projects provide as WebAssembly modules, so that they can import their own formats.
+//!
+//! A processor is a core WebAssembly module that exports its `memory` and the functions:
+//! - `alloc(len: i32) -> i32`, which allocates `len` bytes for the host to write to.
+//! - `process(request: i32, request_len: i32) -> i64`, which processes one file. The request is a JSON object with the
+//!   `path` of the file and the `config` of the pipeline, and the response is a JSON object with the `assets` that the
+//!   processor wrote, each with the `path` of its file, its `type` and an optional `name`, and the `dependencies` of the
+//!   assets that it didn't read; or with an `error`.
+//!
+//! It can import these functions from the `ambient_pipeline` module:
+//! - `read_file(path: i32, path_len: i32) -> i64`, which reads a file of the pipeline; -1 if it doesn't exist.
+//! - `write_file(path: i32, path_len: i32, data: i32, data_len: i32)`, which writes a file to the output of the pipeline.
+//!
+//! All paths are relative to the `pipeline.json`, and strings are UTF-8. The `i64`s hold the address of a buffer in
+//! their upper 32 bits and its length in their lower 32 bits; the host allocates the buffers it returns with `alloc`.
+//! Every file that is read is a dependency of the assets, so they are processed again when it changes.
+//!
+//! A processor has `FUEL_PER_FILE` and `MAX_MEMORY` for each file, so that one that hangs or runs away fails the file
+//! instead of the build.
+use std::collections::HashMap;
+
+use ambient_std::{
+    asset_cache::{AssetCache, SyncAssetKey, SyncAssetKeyExt},
+    asset_url::AssetType,
+};
+use anyhow::Context;
+use relative_path::{Component, RelativePath};
+use serde::{Deserialize, Serialize};
+use tracing::{info_span, Instrument};
+use wasmtime::{Caller, Config, Engine, Extern, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use super::{
+    context::PipelineCtx,
+    out_asset::{asset_id_from_url, OutAsset, OutAssetContent, OutAssetPreview},
+};
+
+/// The fuel that a processor gets for each file, which is roughly the number of instructions it can run
+const FUEL_PER_FILE: u64 = 100_000_000_000;
+/// The most bytes of memory that a processor can have
+const MAX_MEMORY: usize = 2 * 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginPipeline {
+    /// The WebAssembly module that processes the files, relative to the `pipeline.json`.
+    /// Use `sources` to choose the files it processes.
+    pub processor: String,
+    /// The configuration to pass on to the processor.
+    #[serde(default)]
+    pub config: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+struct ProcessRequest<'a> {
+    path: &'a str,
+    config: &'a serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProcessResponse {
+    #[serde(default)]
+    assets: Vec<PluginAsset>,
+    #[serde(default)]
+    dependencies: Vec<String>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PluginAsset {
+    path: String,
+    #[serde(rename = "type")]
+    type_: AssetType,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+pub async fn pipeline(ctx: &PipelineCtx, config: PluginPipeline) -> Vec<OutAsset> {
+    let module = async {
+        let processor = ctx.get_downloadable_url(&ctx.in_root().push(&config.processor)?)?.clone();
+        let bytes = processor.download_bytes(ctx.assets()).await?;
+        let module = Module::new(&PluginEngineKey.get(ctx.assets()), &bytes)?;
+        Ok::<_, anyhow::Error>((processor, module))
+    }
+    .await
+    .with_context(|| format!("In pipeline {}, failed to load the processor {}", ctx.pipeline_path(), config.processor));
+    let (processor, module) = match module {
+        Ok(res) => res,
+        Err(err) => {
+            (ctx.process_ctx.on_error)(err).await;
+            return Vec::new();
+        }
+    };
+
+    ctx.process_files(
+        {
+            let processor = processor.clone();
+            move |file| file != &processor && !file.path().as_str().ends_with("pipeline.json")
+        },
+        move |ctx, file| {
+            let config = config.clone();
+            let processor = processor.clone();
+            let module = module.clone();
+            async move {
+                ctx.add_dependency(&processor);
+                let path = ctx.in_root().relative_path(file.path()).to_string();
+                let request = serde_json::to_vec(&ProcessRequest { path: &path, config: &config.config })?;
+                let (response, written) = tokio::task::spawn_blocking({
+                    let state = HostState::new({
+                        let ctx = ctx.clone();
+                        let runtime = tokio::runtime::Handle::current();
+                        move |path| read_file(&ctx, &runtime, path)
+                    });
+                    move || run(&module, state, &request)
+                })
+                .await??;
+                if let Some(error) = response.error {
+                    anyhow::bail!("The processor failed: {error}");
+                }
+                for dependency in &response.dependencies {
+                    ctx.add_dependency(&ctx.in_root().push(dependency)?);
+                }
+
+                let mut urls = HashMap::new();
+                for (path, data) in written {
+                    let url = ctx.write_file(&path, data).await;
+                    urls.insert(path, url);
+                }
+                response
+                    .assets
+                    .into_iter()
+                    .map(|asset| {
+                        let url = urls.get(&asset.path).with_context(|| format!("The processor didn't write the asset {}", asset.path))?;
+                        Ok(OutAsset {
+                            id: asset_id_from_url(url),
+                            type_: asset.type_,
+                            hidden: false,
+                            name: asset.name.unwrap_or_else(|| file.path().file_name().unwrap().to_string()),
+                            tags: Vec::new(),
+                            categories: Default::default(),
+                            preview: OutAssetPreview::None,
+                            content: OutAssetContent::Content(url.clone()),
+                            source: Some(file.clone()),
+                        })
+                    })
+                    .collect()
+            }
+        },
+    )
+    .instrument(info_span!("plugin_pipeline"))
+    .await
+}
+
+type ReadFile = Box<dyn Fn(&str) -> anyhow::Result<Option<Vec<u8>>> + Send>;
+
+struct HostState {
+    /// Reads a file of the pipeline, if it exists
+    read: ReadFile,
+    written: Vec<(String, Vec<u8>)>,
+    limits: StoreLimits,
+}
+impl HostState {
+    fn new(read: impl Fn(&str) -> anyhow::Result<Option<Vec<u8>>> + Send + 'static) -> Self {
+        Self { read: Box::new(read), written: Vec::new(), limits: StoreLimitsBuilder::new().memory_size(MAX_MEMORY).build() }
+    }
+}
+
+/// Only the files of the pipeline can be read
+fn read_file(ctx: &PipelineCtx, runtime: &tokio::runtime::Handle, path: &str) -> anyhow::Result<Option<Vec<u8>>> {
+    let url = ctx.in_root().push(path)?;
+    let Some(file) = ctx.files.0.iter().find(|file| file.path() == url.path()) else {
+        return Ok(None);
+    };
+    ctx.add_dependency(file);
+    Ok(Some(runtime.block_on(file.download_bytes(ctx.assets()))?))
+}
+
+/// Processes a file with a new instance of `module`, so that files can't affect each other
+fn run(module: &Module, state: HostState, request: &[u8]) -> anyhow::Result<(ProcessResponse, Vec<(String, Vec<u8>)>)> {
+    let mut linker = Linker::new(module.engine());
+    linker.func_wrap("ambient_pipeline", "read_file", |mut caller: Caller<'_, HostState>, path: i32, path_len: i32| {
+        let path = read_string(&mut caller, path, path_len)?;
+        match (caller.data().read)(&path)? {
+            Some(data) => write_buffer(&mut caller, &data),
+            None => Ok(-1),
+        }
+    })?;
+    linker.func_wrap(
+        "ambient_pipeline",
+        "write_file",
+        |mut caller: Caller<'_, HostState>, path: i32, path_len: i32, data: i32, data_len: i32| {
+            let path = read_string(&mut caller, path, path_len)?;
+            if RelativePath::new(&path).components().any(|component| component == Component::ParentDir) {
+                anyhow::bail!("The processor can't write outside of the output of the pipeline: {path}");
+            }
+            let data = read_buffer(&mut caller, data, data_len)?;
+            caller.data_mut().written.push((path, data));
+            Ok(())
+        },
+    )?;
+
+    let mut store = Store::new(module.engine(), state);
+    store.limiter(|state| &mut state.limits);
+    store.add_fuel(FUEL_PER_FILE)?;
+    let instance = linker.instantiate(&mut store, module)?;
+    let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+    let process = instance.get_typed_func::<(i32, i32), i64>(&mut store, "process")?;
+    let memory = instance.get_memory(&mut store, "memory").context("The processor doesn't export its memory")?;
+
+    let ptr = alloc.call(&mut store, request.len() as i32)?;
+    memory.write(&mut store, ptr as u32 as usize, request)?;
+    let (ptr, len) = unpack(process.call(&mut store, (ptr, request.len() as i32))?);
+    let mut response = vec![0; len];
+    memory.read(&store, ptr, &mut response)?;
+    let response = serde_json::from_slice(&response).context("The processor returned an invalid response")?;
+    Ok((response, store.into_data().written))
+}
+
+fn memory(caller: &mut Caller<'_, HostState>) -> anyhow::Result<Memory> {
+    caller.get_export("memory").and_then(Extern::into_memory).context("The processor doesn't export its memory")
+}
+
+fn read_buffer(caller: &mut Caller<'_, HostState>, ptr: i32, len: i32) -> anyhow::Result<Vec<u8>> {
+    let mut data = vec![0; len as u32 as usize];
+    memory(caller)?.read(&*caller, ptr as u32 as usize, &mut data)?;
+    Ok(data)
+}
+
+fn read_string(caller: &mut Caller<'_, HostState>, ptr: i32, len: i32) -> anyhow::Result<String> {
+    Ok(String::from_utf8(read_buffer(caller, ptr, len)?)?)
+}
+
+/// Copies `data` to a buffer allocated by the processor
+fn write_buffer(caller: &mut Caller<'_, HostState>, data: &[u8]) -> anyhow::Result<i64> {
+    let alloc = caller.get_export("alloc").and_then(Extern::into_func).context("The processor doesn't export alloc")?;
+    let ptr = alloc.typed::<i32, i32>(&*caller)?.call(&mut *caller, data.len() as i32)?;
+    memory(caller)?.write(&mut *caller, ptr as u32 as usize, data)?;
+    Ok(((ptr as u32 as u64) << 32 | data.len() as u64) as i64)
+}
+
+fn unpack(value: i64) -> (usize, usize) {
+    ((value as u64 >> 32) as usize, (value as u64 & 0xffff_ffff) as usize)
+}
+
+#[derive(Debug)]
+struct PluginEngineKey;
+impl SyncAssetKey<Engine> for PluginEngineKey {
+    fn load(&self, _assets: AssetCache) -> Engine {
+        engine()
+    }
+}
+fn engine() -> Engine {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    Engine::new(&config).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A processor that copies `in.txt` to the file at `$PATH`, and returns it as a string table
+    const COPY: &str = r#"
+        (module
+            (import "ambient_pipeline" "read_file" (func $read_file (param i32 i32) (result i64)))
+            (import "ambient_pipeline" "write_file" (func $write_file (param i32 i32 i32 i32)))
+            (memory (export "memory") 1)
+            (global $next (mut i32) (i32.const 1024))
+            (data (i32.const 0) "in.txt")
+            (data (i32.const 16) "$PATH")
+            (data (i32.const 256) "$RESPONSE")
+            (func (export "alloc") (param $len i32) (result i32)
+                (local $ptr i32)
+                (local.set $ptr (global.get $next))
+                (global.set $next (i32.add (global.get $next) (local.get $len)))
+                (local.get $ptr))
+            (func (export "process") (param i32 i32) (result i64)
+                (local $file i64)
+                (local.set $file (call $read_file (i32.const 0) (i32.const 6)))
+                (call $write_file
+                    (i32.const 16) (i32.const $PATH_LEN)
+                    (i32.wrap_i64 (i64.shr_u (local.get $file) (i64.const 32)))
+                    (i32.wrap_i64 (local.get $file)))
+                (i64.or (i64.shl (i64.const 256) (i64.const 32)) (i64.const $RESPONSE_LEN))))
+    "#;
+
+    fn copy(path: &str) -> anyhow::Result<(ProcessResponse, Vec<(String, Vec<u8>)>)> {
+        let response = format!(r#"{{"assets":[{{"path":"{path}","type":"StringTable"}}]}}"#);
+        let wat = COPY
+            .replace("$PATH_LEN", &path.len().to_string())
+            .replace("$PATH", path)
+            .replace("$RESPONSE_LEN", &response.len().to_string())
+            .replace("$RESPONSE", &response.replace('"', "\\\""));
+        let module = Module::new(&engine(), wat)?;
+        let state = HostState::new(|path| Ok((path == "in.txt").then(|| b"hello = \"Hello\"".to_vec())));
+        run(&module, state, b"{}")
+    }
+
+    #[test]
+    fn processors_read_and_write_files() {
+        let (response, written) = copy("out/strings.toml").unwrap();
+        assert_eq!(response.assets.len(), 1);
+        assert_eq!(response.assets[0].path, "out/strings.toml");
+        assert_eq!(written, vec![("out/strings.toml".to_string(), b"hello = \"Hello\"".to_vec())]);
+    }
+
+    #[test]
+    fn processors_cant_write_outside_of_the_output() {
+        assert!(copy("../strings.toml").is_err());
+        assert!(copy("out/../../strings.toml").is_err());
+    }
+
+    #[test]
+    fn processors_run_out_of_fuel() {
+        let wat = r#"
+            (module
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) (i32.const 0))
+                (func (export "process") (param i32 i32) (result i64) (loop $forever (br $forever)) (i64.const 0)))
+        "#;
+        let module = Module::new(&engine(), wat).unwrap();
+        assert!(run(&module, HostState::new(|_| Ok(None)), b"{}").is_err());
+    }
+
+    #[test]
+    fn buffers_are_unpacked() {
+        assert_eq!(unpack((1024 << 32) | 5), (1024, 5));
+        assert_eq!(unpack(((0x8000_0000u64 << 32) | 0xffff_ffff) as i64), (0x8000_0000, 0xffff_ffff));
+    }
+}
@@ -0,0 +1,246 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use ambient_std::{
+    asset_cache::{AssetCache, SyncAssetKeyExt},
+    asset_url::AbsAssetUrl,
+    download_asset::ReqwestClientKey,
+    pak::{self, Pak, PakIndex, PakKey},
+};
+use anyhow::Context;
+use itertools::Itertools;
+
+use crate::package::{ASSETS_PAK, LOOSE_FILES};
+
+/// What an [update] transferred
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UpdateSummary {
+    pub downloaded_chunks: usize,
+    /// The compressed size of the downloaded chunks
+    pub downloaded_bytes: u64,
+    /// The chunks that were taken from the local pak instead
+    pub reused_chunks: usize,
+}
+
+/// Updates the packaged build at `build_path` to the release at `release_url`, which is the build directory of a
+/// package (see [crate::package::package]) served over HTTP or on disk.
+///
+/// Only the chunks of the release's pak that the local pak doesn't have are downloaded, with range requests, and every
+/// chunk is checked against its hash. The new build is put together next to the current one and swapped in once it is
+/// complete, so an interrupted update leaves the current build intact.
+///
+/// Returns `None` if the build is already up to date.
+pub async fn update(
+    assets: &AssetCache,
+    build_path: &Path,
+    release_url: &AbsAssetUrl,
+) -> anyhow::Result<Option<UpdateSummary>> {
+    recover(build_path)?;
+
+    let index_name = pak::index_path(Path::new(ASSETS_PAK));
+    let release_index: PakIndex = release_url
+        .push(index_name.to_string_lossy())?
+        .download_json(assets)
+        .await
+        .context("Failed to download the index of the release")?;
+    let mut loose_files = Vec::new();
+    for name in LOOSE_FILES {
+        let data = release_url
+            .push(name)?
+            .download_bytes(assets)
+            .await
+            .with_context(|| format!("Failed to download {name} of the release"))?;
+        loose_files.push((name, data));
+    }
+
+    let key = if release_index.encrypted {
+        Some(PakKey::from_env()?.with_context(|| {
+            format!(
+                "The release is encrypted, but no key was provided with {}",
+                pak::PAK_KEY_VAR
+            )
+        })?)
+    } else {
+        None
+    };
+    let local = Pak::open(build_path.join(ASSETS_PAK)).ok();
+    let up_to_date = local
+        .as_ref()
+        .map_or(false, |pak| pak.index() == &release_index)
+        && loose_files.iter().all(|(name, data)| {
+            std::fs::read(build_path.join(name)).map_or(false, |local| &local == data)
+        });
+    if up_to_date {
+        return Ok(None);
+    }
+
+    let staging_path = sibling(build_path, "new");
+    if staging_path.exists() {
+        std::fs::remove_dir_all(&staging_path)?;
+    }
+    std::fs::create_dir_all(&staging_path)?;
+    for (name, data) in &loose_files {
+        std::fs::write(staging_path.join(name), data)?;
+    }
+    let pak_path = staging_path.join(ASSETS_PAK);
+    let summary = write_release_pak(
+        assets,
+        &pak_path,
+        local.as_ref(),
+        &release_index,
+        key.as_ref(),
+        &release_url.push(ASSETS_PAK)?,
+    )
+    .await?;
+    std::fs::write(
+        pak::index_path(&pak_path),
+        serde_json::to_string_pretty(&release_index)?,
+    )?;
+
+    swap(build_path, &staging_path)?;
+    Ok(Some(summary))
+}
+
+/// Writes the pak of the release to `path`, chunk by chunk in the order of the release's pak so that the offsets in
+/// its index stay valid
+async fn write_release_pak(
+    assets: &AssetCache,
+    path: &Path,
+    local: Option<&Pak>,
+    release_index: &PakIndex,
+    key: Option<&PakKey>,
+    release_pak_url: &AbsAssetUrl,
+) -> anyhow::Result<UpdateSummary> {
+    let mut summary = UpdateSummary::default();
+    let mut file = BufWriter::new(File::create(path)?);
+    let mut local_file = local.map(|pak| File::open(pak.path())).transpose()?;
+    let mut release_file = release_pak_url
+        .to_file_path()?
+        .map(File::open)
+        .transpose()?;
+    let client = ReqwestClientKey.get(assets);
+    let download_url = release_pak_url.to_download_url(assets)?;
+
+    let mut offset = 0;
+    for (hash, chunk) in release_index
+        .chunks
+        .iter()
+        .sorted_by_key(|(_, chunk)| chunk.offset)
+    {
+        anyhow::ensure!(
+            chunk.offset == offset,
+            "The index of the release has a gap at {offset}"
+        );
+        // Chunks of the local pak that turn out to be corrupt are downloaded again
+        let reused = match (
+            local.and_then(|pak| pak.index().chunks.get(hash)),
+            &mut local_file,
+        ) {
+            (Some(local_chunk), Some(local_file)) => {
+                read_range(local_file, local_chunk.offset, local_chunk.compressed_size)
+                    .ok()
+                    .filter(|compressed| {
+                        pak::decompress_chunk(compressed, hash, local_chunk.size, key).is_ok()
+                    })
+            }
+            _ => None,
+        };
+        let compressed = if let Some(compressed) = reused {
+            summary.reused_chunks += 1;
+            compressed
+        } else {
+            let compressed = match &mut release_file {
+                Some(release_file) => {
+                    read_range(release_file, chunk.offset, chunk.compressed_size)?
+                }
+                None => {
+                    download_range(&client, &download_url, chunk.offset, chunk.compressed_size)
+                        .await?
+                }
+            };
+            pak::decompress_chunk(&compressed, hash, chunk.size, key)?;
+            summary.downloaded_chunks += 1;
+            summary.downloaded_bytes += chunk.compressed_size;
+            compressed
+        };
+        file.write_all(&compressed)?;
+        offset += chunk.compressed_size;
+    }
+    file.flush()?;
+    Ok(summary)
+}
+
+fn read_range(file: &mut File, offset: u64, size: u64) -> anyhow::Result<Vec<u8>> {
+    let mut data = vec![0; size as usize];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut data)?;
+    Ok(data)
+}
+
+async fn download_range(
+    client: &reqwest::Client,
+    url: &AbsAssetUrl,
+    offset: u64,
+    size: u64,
+) -> anyhow::Result<Vec<u8>> {
+    let response = client
+        .get(url.0.clone())
+        .header(
+            reqwest::header::RANGE,
+            format!("bytes={}-{}", offset, offset + size - 1),
+        )
+        .send()
+        .await
+        .with_context(|| format!("Failed to download {url}"))?
+        .error_for_status()?;
+    anyhow::ensure!(
+        response.status() == reqwest::StatusCode::PARTIAL_CONTENT,
+        "{url} doesn't support range requests"
+    );
+    let data = response.bytes().await?;
+    anyhow::ensure!(
+        data.len() as u64 == size,
+        "Expected {size} bytes from {url}, got {}",
+        data.len()
+    );
+    Ok(data.to_vec())
+}
+
+/// Replaces the build at `build_path` with the one at `staging_path`. The current build is moved aside first, and only
+/// removed once the new one is in place.
+fn swap(build_path: &Path, staging_path: &Path) -> anyhow::Result<()> {
+    let old_path = sibling(build_path, "old");
+    if old_path.exists() {
+        std::fs::remove_dir_all(&old_path)?;
+    }
+    if build_path.exists() {
+        std::fs::rename(build_path, &old_path)
+            .with_context(|| format!("Failed to move {build_path:?} aside"))?;
+    }
+    if let Err(err) = std::fs::rename(staging_path, build_path) {
+        std::fs::rename(&old_path, build_path).ok();
+        return Err(err).with_context(|| format!("Failed to move the update to {build_path:?}"));
+    }
+    std::fs::remove_dir_all(&old_path).ok();
+    Ok(())
+}
+
+/// Restores the previous build if an update was interrupted while it was being swapped in
+fn recover(build_path: &Path) -> anyhow::Result<()> {
+    let old_path = sibling(build_path, "old");
+    if !build_path.exists() && old_path.exists() {
+        log::warn!("Restoring {build_path:?} after an interrupted update");
+        std::fs::rename(&old_path, build_path)?;
+    }
+    Ok(())
+}
+
+/// `build.new` for `build`
+fn sibling(build_path: &Path, suffix: &str) -> PathBuf {
+    let mut name = build_path.file_name().unwrap_or_default().to_owned();
+    name.push(format!(".{suffix}"));
+    build_path.with_file_name(name)
+}
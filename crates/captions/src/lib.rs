@@ -0,0 +1,283 @@
+use std::{sync::Arc, time::Duration};
+
+use ambient_core::{
+    asset_cache,
+    async_ecs::async_run,
+    camera::{get_active_camera, inv_local_to_world, projection},
+    main_scene,
+    player::local_user_id,
+    remove_at_time, runtime, time,
+    transform::{get_world_position, translation},
+};
+use ambient_ecs::{
+    components,
+    generated::components::core::{
+        layout::{
+            align_horizontal_center, docking_bottom, fit_horizontal_parent, fit_vertical_children,
+            orientation_vertical, space_between_items,
+        },
+        network::is_remote_entity,
+        rendering::color,
+        text::font_size,
+    },
+    query, Entity, SystemGroup, World,
+};
+use ambient_element::{element_component, Element, ElementComponentExt, Hooks};
+use ambient_gpu::settings::{AccessibilitySettings, SettingsKey};
+use ambient_std::{
+    asset_cache::{AsyncAssetKeyExt, SyncAssetKeyExt},
+    asset_url::AbsAssetUrl,
+    download_asset::BytesFromUrl,
+};
+use ambient_ui_native::{Flow, FlowRow, FontAwesomeIcon, Text, UIExt, WindowSized, STREET};
+use glam::Vec3;
+
+mod track;
+pub use track::*;
+
+pub use ambient_ecs::generated::components::core::captions::{
+    caption, caption_duration, caption_speaker, caption_speaker_color, caption_track_from_url,
+    captions_enabled,
+};
+
+components!("captions", {
+    caption_track: Arc<CaptionTrack>,
+    caption_track_start_time: Duration,
+});
+
+/// Despawns locally-spawned captions once their duration has elapsed. Runs on both the client and server.
+pub fn systems() -> SystemGroup {
+    SystemGroup::new(
+        "captions",
+        vec![query(caption_duration())
+            .excl(is_remote_entity())
+            .excl(remove_at_time())
+            .to_system(|q, world, qs, _| {
+                let time = *world.resource(time());
+                for (id, duration) in q.collect_cloned(world, qs) {
+                    world
+                        .add_component(
+                            id,
+                            remove_at_time(),
+                            time + Duration::from_secs_f32(duration.max(0.)),
+                        )
+                        .ok();
+                }
+            })],
+    )
+}
+
+/// Loads and plays back caption tracks.
+pub fn client_systems() -> SystemGroup {
+    SystemGroup::new(
+        "captions_client",
+        vec![
+            query(caption_track_from_url().changed()).to_system(|q, world, qs, _| {
+                for (id, url) in q.collect_cloned(world, qs) {
+                    let url = match AbsAssetUrl::parse(url) {
+                        Ok(value) => value,
+                        Err(err) => {
+                            log::warn!("Failed to parse caption_track_from_url url: {:?}", err);
+                            continue;
+                        }
+                    };
+                    let assets = world.resource(asset_cache()).clone();
+                    let async_run = world.resource(async_run()).clone();
+                    world.resource(runtime()).spawn(async move {
+                        let track = BytesFromUrl::new(url, true)
+                            .get(&assets)
+                            .await
+                            .map_err(anyhow::Error::from)
+                            .and_then(|bytes| {
+                                CaptionTrack::parse_vtt(&String::from_utf8_lossy(&bytes))
+                            });
+                        match track {
+                            Err(err) => {
+                                log::warn!("Failed to load caption track: {:?}", err);
+                            }
+                            Ok(track) => {
+                                async_run.run(move |world| {
+                                    let time = *world.resource(time());
+                                    world
+                                        .add_components(
+                                            id,
+                                            Entity::new()
+                                                .with(caption_track(), Arc::new(track))
+                                                .with(caption_track_start_time(), time),
+                                        )
+                                        .ok();
+                                });
+                            }
+                        }
+                    });
+                }
+            }),
+            query((caption_track(), caption_track_start_time())).to_system(|q, world, qs, _| {
+                let time = *world.resource(time());
+                for (id, (track, start_time)) in q.collect_cloned(world, qs) {
+                    let elapsed = time.saturating_sub(start_time).as_secs_f32();
+                    if elapsed >= track.duration() {
+                        if world.has_component(id, is_remote_entity()) {
+                            world
+                                .remove_components(
+                                    id,
+                                    vec![caption_track().desc(), caption().desc()],
+                                )
+                                .ok();
+                        } else {
+                            world.despawn(id);
+                        }
+                        continue;
+                    }
+                    match track.active_cue(elapsed) {
+                        Some(cue) => {
+                            world.add_component(id, caption(), cue.text.clone()).ok();
+                            match &cue.speaker {
+                                Some(speaker) => world
+                                    .add_component(id, caption_speaker(), speaker.clone())
+                                    .ok(),
+                                None => world.remove_component(id, caption_speaker()).ok(),
+                            };
+                        }
+                        None => {
+                            world.remove_component(id, caption()).ok();
+                        }
+                    }
+                }
+            }),
+        ],
+    )
+}
+
+/// The resources for captions on the client, initialized from the user's accessibility settings.
+pub fn client_resources(world: &World) -> Entity {
+    let settings = SettingsKey.get(world.resource(asset_cache()));
+    Entity::new().with(captions_enabled(), settings.accessibility.captions)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OffscreenSide {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct CaptionLine {
+    speaker: Option<String>,
+    speaker_color: Vec3,
+    text: String,
+    offscreen: Option<OffscreenSide>,
+}
+
+fn collect_lines(world: &World) -> Vec<CaptionLine> {
+    if !world
+        .resource_opt(captions_enabled())
+        .copied()
+        .unwrap_or(false)
+    {
+        return Vec::new();
+    }
+    let camera = get_active_camera(world, main_scene(), world.resource_opt(local_user_id()));
+    let projection_view = camera.and_then(|camera| {
+        Some(
+            *world.get_ref(camera, projection()).ok()?
+                * *world.get_ref(camera, inv_local_to_world()).ok()?,
+        )
+    });
+
+    let mut lines = query(caption())
+        .iter(world, None)
+        .map(|(id, text)| {
+            let offscreen = projection_view
+                .filter(|_| world.has_component(id, translation()))
+                .and_then(|pv| {
+                    let clip = pv * get_world_position(world, id).ok()?.extend(1.);
+                    // Points behind the camera are mirrored, so flip them
+                    let x = clip.x / clip.w.abs().max(f32::EPSILON);
+                    if clip.w > 0. && x.abs() <= 1. && (clip.y / clip.w).abs() <= 1. {
+                        None
+                    } else if x < 0. {
+                        Some(OffscreenSide::Left)
+                    } else {
+                        Some(OffscreenSide::Right)
+                    }
+                });
+            (
+                id,
+                CaptionLine {
+                    speaker: world.get_cloned(id, caption_speaker()).ok(),
+                    speaker_color: world.get(id, caption_speaker_color()).unwrap_or(Vec3::ONE),
+                    text: text.clone(),
+                    offscreen,
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+    // Keep the order stable between frames
+    lines.sort_by_key(|(id, _)| *id);
+    lines.into_iter().map(|(_, line)| line).collect()
+}
+
+/// Displays all active captions at the bottom of the screen.
+#[element_component]
+pub fn Captions(hooks: &mut Hooks) -> Element {
+    let (lines, set_lines) = hooks.use_state(Vec::<CaptionLine>::new());
+    let (settings, _) =
+        hooks.use_state_with(|world| SettingsKey.get(world.resource(asset_cache())).accessibility);
+    {
+        let lines = lines.clone();
+        hooks.use_frame(move |world| {
+            let next = collect_lines(world);
+            if next != lines {
+                set_lines(next);
+            }
+        });
+    }
+
+    WindowSized::el([Flow::el(
+        lines
+            .into_iter()
+            .map(|line| caption_line(line, &settings))
+            .collect::<Vec<_>>(),
+    )
+    .with_default(docking_bottom())
+    .with_default(orientation_vertical())
+    .with_default(align_horizontal_center())
+    .with_default(fit_horizontal_parent())
+    .with_default(fit_vertical_children())
+    .with(space_between_items(), STREET)
+    .with_padding_even(STREET * 4.)])
+}
+
+fn caption_line(line: CaptionLine, settings: &AccessibilitySettings) -> Element {
+    let size = 16. * settings.caption_scale;
+    let arrow = |side: OffscreenSide| {
+        if line.offscreen == Some(side) {
+            FontAwesomeIcon::el(
+                if side == OffscreenSide::Left {
+                    0xf053
+                } else {
+                    0xf054
+                },
+                true,
+            )
+            .with(font_size(), size)
+        } else {
+            Element::new()
+        }
+    };
+    FlowRow::el([
+        arrow(OffscreenSide::Left),
+        match &line.speaker {
+            Some(speaker) => Text::el(format!("{speaker}:"))
+                .with(font_size(), size)
+                .with(color(), line.speaker_color.extend(1.)),
+            None => Element::new(),
+        },
+        Text::el(line.text.clone()).with(font_size(), size),
+        arrow(OffscreenSide::Right),
+    ])
+    .with(space_between_items(), STREET)
+    .with_padding_even(STREET)
+    .with_background(Vec3::ZERO.extend(settings.caption_background_opacity))
+}
@@ -0,0 +1,160 @@
+use anyhow::Context;
+
+/// A single timed caption, shown from `start` to `end` seconds into the track.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptionCue {
+    pub start: f32,
+    pub end: f32,
+    pub speaker: Option<String>,
+    pub text: String,
+}
+
+/// A timed caption track, parsed from a subset of [WebVTT](https://www.w3.org/TR/webvtt1/).
+///
+/// Cue settings, regions and styling are ignored. The speaker is taken from the `<v Speaker>` voice tag,
+/// and all other tags are stripped.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CaptionTrack {
+    pub cues: Vec<CaptionCue>,
+}
+
+impl CaptionTrack {
+    pub fn parse_vtt(source: &str) -> anyhow::Result<Self> {
+        let source = source.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+        let mut blocks = source
+            .split("\n\n")
+            .map(str::trim)
+            .filter(|b| !b.is_empty());
+
+        let header = blocks.next().unwrap_or_default();
+        anyhow::ensure!(
+            header.starts_with("WEBVTT"),
+            "Caption track is missing the WEBVTT header"
+        );
+
+        let mut cues = Vec::new();
+        for block in blocks {
+            if block.starts_with("NOTE")
+                || block.starts_with("STYLE")
+                || block.starts_with("REGION")
+            {
+                continue;
+            }
+            let mut lines = block.lines();
+            let mut timing = lines.next().unwrap_or_default();
+            if !timing.contains("-->") {
+                // Skip the cue identifier
+                timing = lines.next().unwrap_or_default();
+            }
+            let (start, end) = timing
+                .split_once("-->")
+                .with_context(|| format!("Invalid cue timing: {timing:?}"))?;
+            let start = parse_timestamp(start.trim())?;
+            // Anything after the end timestamp is cue settings
+            let end = parse_timestamp(end.split_whitespace().next().unwrap_or_default())?;
+
+            let mut speaker = None;
+            let text = lines
+                .map(|line| strip_tags(line, &mut speaker))
+                .collect::<Vec<_>>()
+                .join("\n");
+            cues.push(CaptionCue {
+                start,
+                end,
+                speaker,
+                text,
+            });
+        }
+        cues.sort_by(|a, b| a.start.total_cmp(&b.start));
+        Ok(Self { cues })
+    }
+
+    /// Returns the cue to show at `time` seconds into the track. If cues overlap, the latest one wins.
+    pub fn active_cue(&self, time: f32) -> Option<&CaptionCue> {
+        self.cues
+            .iter()
+            .rev()
+            .find(|cue| cue.start <= time && time < cue.end)
+    }
+
+    /// The time at which the last cue ends.
+    pub fn duration(&self) -> f32 {
+        self.cues.iter().map(|cue| cue.end).fold(0., f32::max)
+    }
+}
+
+/// Parses `hh:mm:ss.ttt` or `mm:ss.ttt` into seconds.
+fn parse_timestamp(value: &str) -> anyhow::Result<f32> {
+    let parts = value.split(':').collect::<Vec<_>>();
+    let (hours, minutes, seconds) = match parts.as_slice() {
+        [h, m, s] => (*h, *m, *s),
+        [m, s] => ("0", *m, *s),
+        _ => anyhow::bail!("Invalid timestamp: {value:?}"),
+    };
+    let parse = |v: &str| {
+        v.parse::<f32>()
+            .with_context(|| format!("Invalid timestamp: {value:?}"))
+    };
+    Ok(parse(hours)? * 3600. + parse(minutes)? * 60. + parse(seconds)?)
+}
+
+/// Removes all `<...>` tags from `line`, writing the speaker of a voice tag to `speaker`.
+fn strip_tags(line: &str, speaker: &mut Option<String>) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(open) = rest.find('<') {
+        out.push_str(&rest[..open]);
+        let Some(close) = rest[open..].find('>') else {
+            rest = &rest[open..];
+            break;
+        };
+        let tag = &rest[open + 1..open + close];
+        if let Some(voice) = tag.strip_prefix('v') {
+            // `<v Name>` or `<v.class Name>`
+            if let Some((_, name)) = voice.split_once(' ') {
+                *speaker = Some(name.trim().to_string());
+            }
+        }
+        rest = &rest[open + close + 1..];
+    }
+    out.push_str(rest);
+    out.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_vtt() {
+        let track = CaptionTrack::parse_vtt(
+            "WEBVTT - Intro\n\nNOTE This is ignored\n\n1\n00:00:01.000 --> 00:00:02.500 align:start\n<v.loud Guard>Halt!</v>\n\n02:03.500 --> 02:05.000\n<i>[door creaks]</i>\nsecond line\n",
+        )
+        .unwrap();
+        assert_eq!(
+            track.cues,
+            vec![
+                CaptionCue {
+                    start: 1.,
+                    end: 2.5,
+                    speaker: Some("Guard".to_string()),
+                    text: "Halt!".to_string()
+                },
+                CaptionCue {
+                    start: 123.5,
+                    end: 125.,
+                    speaker: None,
+                    text: "[door creaks]\nsecond line".to_string()
+                },
+            ]
+        );
+        assert_eq!(track.duration(), 125.);
+        assert_eq!(track.active_cue(2.).unwrap().text, "Halt!");
+        assert!(track.active_cue(2.5).is_none());
+    }
+
+    #[test]
+    fn missing_header() {
+        assert!(CaptionTrack::parse_vtt("00:01.000 --> 00:02.000\nHi").is_err());
+    }
+}
@@ -8,9 +8,9 @@ use itertools::Itertools;
 use ordered_float::OrderedFloat;
 
 pub use ambient_ecs::generated::components::core::camera::{
-    active_camera, aspect_ratio, aspect_ratio_from_window, far, fog, fovy, near, orthographic, orthographic_bottom,
-    orthographic_from_window, orthographic_left, orthographic_right, orthographic_top, perspective, perspective_infinite_reverse,
-    projection, projection_view, shadows_far,
+    active_camera, aperture, aspect_ratio, aspect_ratio_from_window, far, focus_distance, fog, fovy, iso, motion_blur, near,
+    orthographic, orthographic_bottom, orthographic_from_window, orthographic_left, orthographic_right, orthographic_top,
+    perspective, perspective_infinite_reverse, projection, projection_view, shadows_far, shutter_speed,
 };
 
 use crate::{
@@ -435,6 +435,28 @@ impl Camera {
             shadows_far: far,
         }
     }
+    /// This will create a camera which looks straight down on the `size` by `size` meters around this camera, and
+    /// which will snap to pixels in a map of `resolution` pixels. What it sees first is what is open to the sky.
+    pub fn create_snapping_sky_camera(&self, size: f32, resolution: u32) -> Self {
+        let mut sky_view = Mat4::look_at_lh(Vec3::Z, Vec3::ZERO, Vec3::Y);
+        let center = sky_view.project_point3(self.position());
+
+        let texel_size = size / resolution as f32;
+        let center_xy_snapped = (center.xy() / texel_size).floor() * texel_size;
+        sky_view = Mat4::from_translation(-center_xy_snapped.extend(0.)) * sky_view;
+
+        let half_size = size / 2.;
+        let far = center.z + 300.0;
+        Self {
+            view: sky_view,
+            projection: Projection::Orthographic {
+                rect: OrthographicRect { left: -half_size, right: half_size, bottom: -half_size, top: half_size },
+                near: center.z - 300.0,
+                far,
+            },
+            shadows_far: far,
+        }
+    }
     pub fn fitted_ortographic(eye: Vec3, lookat: Vec3, up: Vec3, fit: BoundingBox, aspect: f32) -> Self {
         let view = Mat4::look_at_lh(eye, lookat, up);
         let bounding = fit.transform(&view).to_aabb();
@@ -493,6 +515,10 @@ pub fn shadow_cameras_from_world(
         .collect()
 }
 
+pub fn sky_camera_from_world(world: &World, size: f32, resolution: u32, scene: Component<()>, user_id: Option<&String>) -> Camera {
+    Camera::get_active(world, scene, user_id).unwrap().create_snapping_sky_camera(size, resolution)
+}
+
 #[test]
 fn test_frustum() {
     let projection = Projection::Orthographic { rect: OrthographicRect { left: -5., right: 5., bottom: -5., top: 5. }, near: -5., far: 5. };
@@ -0,0 +1,133 @@
+//! The weather of the world, which the renderer uploads to the GPU every frame for foliage, rain and wet surfaces, and
+//! which cloth and particles can sample on the CPU with [Environment::wind_at].
+use ambient_ecs::{
+    components, generated::components::core::network::synced_resources, query, Component, ComponentValue, EntityId, FnSystem, SystemGroup,
+    World,
+};
+use glam::Vec3;
+use itertools::Itertools;
+
+pub use ambient_ecs::generated::components::core::environment::{
+    precipitation, puddles, temperature, wetness, wind_direction, wind_gustiness, wind_strength,
+};
+
+use crate::dtime;
+
+/// The seconds it takes a downpour to soak a dry world
+const WETTING_TIME: f32 = 120.;
+/// The seconds it takes a soaked world to dry
+const DRYING_TIME: f32 = 600.;
+/// The seconds it takes a downpour to fill the puddles
+const PUDDLE_FILLING_TIME: f32 = 900.;
+/// The seconds it takes full puddles to dry up
+const PUDDLE_DRYING_TIME: f32 = 1800.;
+/// The smallest change of the `wetness` and `puddles` that is sent to the clients, so that they aren't sent every frame
+const WETNESS_STEP: f32 = 0.01;
+
+components!("environment", {
+    /// The `wetness` before it is rounded to [WETNESS_STEP]
+    precise_wetness: f32,
+    /// The `puddles` before they are rounded to [WETNESS_STEP]
+    precise_puddles: f32,
+});
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Environment {
+    /// The normalized direction the wind blows towards.
+    pub wind_direction: Vec3,
+    pub wind_strength: f32,
+    pub wind_gustiness: f32,
+    pub temperature: f32,
+    pub wetness: f32,
+    pub puddles: f32,
+    pub precipitation: f32,
+}
+impl Default for Environment {
+    fn default() -> Self {
+        Self {
+            wind_direction: Vec3::X,
+            wind_strength: 0.,
+            wind_gustiness: 0.5,
+            temperature: 20.,
+            wetness: 0.,
+            puddles: 0.,
+            precipitation: 0.,
+        }
+    }
+}
+impl Environment {
+    /// Reads the environment from the synchronized resources of `world`, falling back to its resources, so that the
+    /// weather set by the server can be overridden locally.
+    pub fn from_world(world: &World) -> Self {
+        let synced = query(()).incl(synced_resources()).iter(world, None).map(|(id, _)| id).next();
+        let get = |component: Component<f32>, default: f32| read(world, synced, component).unwrap_or(default);
+        let default = Self::default();
+        Self {
+            wind_direction: read(world, synced, wind_direction()).and_then(|dir| dir.try_normalize()).unwrap_or(default.wind_direction),
+            wind_strength: get(wind_strength(), default.wind_strength),
+            wind_gustiness: get(wind_gustiness(), default.wind_gustiness),
+            temperature: get(temperature(), default.temperature),
+            wetness: get(wetness(), default.wetness),
+            puddles: get(puddles(), default.puddles),
+            precipitation: get(precipitation(), default.precipitation),
+        }
+    }
+
+    /// Whether the precipitation falls as snow rather than rain.
+    pub fn is_snowing(&self) -> bool {
+        self.temperature < 0.
+    }
+
+    /// The wind velocity at `position` after `time` seconds, including the gusts that travel along it.
+    ///
+    /// This matches `wind_at` in the renderer's shaders, given the same time.
+    pub fn wind_at(&self, position: Vec3, time: f32) -> Vec3 {
+        self.wind_direction
+            * self.wind_strength
+            * (1. + self.wind_gustiness * gust(position.dot(self.wind_direction), time, self.wind_strength))
+    }
+}
+
+/// Makes the world wetter and fills its puddles while it rains, and dries them while it doesn't, on the entities that
+/// hold the weather. Runs on the server, which shares the `wetness` and `puddles` with the clients.
+pub fn environment_systems() -> SystemGroup {
+    SystemGroup::new(
+        "environment",
+        vec![Box::new(FnSystem::new(|world, _| {
+            let dtime = *world.resource(dtime());
+            let entities = query(()).incl(precipitation()).iter(world, None).chain(query(()).incl(wetness()).iter(world, None));
+            let entities = entities.chain(query(()).incl(puddles()).iter(world, None));
+            for id in entities.map(|(id, _)| id).unique().collect_vec() {
+                let raining = world.get(id, temperature()).unwrap_or(Environment::default().temperature) >= 0.;
+                let rain = if raining { world.get(id, precipitation()).unwrap_or(0.) } else { 0. };
+                let wetting = if rain > 0. { rain / WETTING_TIME } else { -1. / DRYING_TIME };
+                accumulate(world, id, wetness(), precise_wetness(), wetting * dtime);
+                let filling = if rain > 0. { rain / PUDDLE_FILLING_TIME } else { -1. / PUDDLE_DRYING_TIME };
+                accumulate(world, id, puddles(), precise_puddles(), filling * dtime);
+            }
+        }))],
+    )
+}
+
+/// Moves the `shared` value of `id` by `delta`, keeping it from 0 to 1, and only shares it when it has moved by
+/// [WETNESS_STEP] since it was last shared, or reached either end
+fn accumulate(world: &mut World, id: EntityId, shared: Component<f32>, precise: Component<f32>, delta: f32) {
+    let shared_value = world.get(id, shared).unwrap_or(0.);
+    // Someone else set the value if it moved further than it is rounded by
+    let current = world.get(id, precise).ok().filter(|value| (value - shared_value).abs() < WETNESS_STEP).unwrap_or(shared_value);
+    let value = (current + delta).clamp(0., 1.);
+    world.add_component(id, precise, value).ok();
+    if (value - shared_value).abs() >= WETNESS_STEP || (value != shared_value && (value == 0. || value == 1.)) {
+        world.add_component(id, shared, value).ok();
+    }
+}
+
+fn read<T: ComponentValue + Copy>(world: &World, synced: Option<EntityId>, component: Component<T>) -> Option<T> {
+    synced.and_then(|id| world.get(id, component).ok()).or_else(|| world.resource_opt(component).copied())
+}
+
+/// Noise from -1 to 1 that moves along the wind at its speed, at `distance` along it
+fn gust(distance: f32, time: f32, strength: f32) -> f32 {
+    let phase = (distance - time * strength) * 0.1;
+    (phase.sin() * 0.5 + (phase * 2.3 + 1.7).sin() * 0.3 + (phase * 5.1 + 4.2).sin() * 0.2).clamp(-1., 1.)
+}
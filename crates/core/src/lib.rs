@@ -12,15 +12,18 @@ use ambient_ecs::{
 use ambient_gpu::{gpu::Gpu, mesh_buffer::GpuMesh};
 
 use ambient_std::asset_cache::{AssetCache, SyncAssetKey};
+use anyhow::Context;
 pub use paste;
 use serde::{Deserialize, Serialize};
 
 pub mod async_ecs;
 pub mod bounding;
 pub mod camera;
+pub mod environment;
 pub mod gpu_ecs;
 pub mod hierarchy;
 pub mod player;
+pub mod portals;
 pub mod transform;
 pub mod window;
 
@@ -68,6 +71,8 @@ pub fn init_all_components() {
     async_ecs::init_components();
     gpu_ecs::init_components();
     camera::init_components();
+    environment::init_components();
+    portals::init_components();
     transform::init_components();
     transform::init_gpu_components();
     bounding::init_components();
@@ -95,6 +100,21 @@ pub fn remove_at_time_system() -> DynSystem {
     })
 }
 
+/// The directory the `category` of data of the current project (e.g. `saves`) is stored in, on this machine
+pub fn project_data_dir(world: &World, category: &str) -> anyhow::Result<std::path::PathBuf> {
+    let dirs = directories::ProjectDirs::from("com", "Ambient", "Ambient")
+        .context("Failed to open home directory")?;
+    let project = world
+        .resource_opt(project_name())
+        .map(|name| {
+            name.chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect::<String>()
+        })
+        .unwrap_or_else(|| "Ambient".to_string());
+    Ok(dirs.data_dir().join(category).join(project))
+}
+
 #[derive(Debug)]
 pub struct FixedTimestepSystem {
     system: DynSystem,
@@ -1,6 +1,9 @@
 use ambient_ecs::{query, EntityId, World};
 
-pub use ambient_ecs::generated::components::core::player::{local_user_id, player, user_id};
+pub use ambient_ecs::generated::components::core::player::{
+    local_user_id, module_log_receiver, player, player_suspended, reconnect_grace_period, spectator, spectator_hidden,
+    user_id,
+};
 
 /// Returns the player entity for the given user ID, if it exists
 pub fn get_by_user_id(world: &World, user_id: &str) -> Option<EntityId> {
@@ -8,3 +11,8 @@ pub fn get_by_user_id(world: &World, user_id: &str) -> Option<EntityId> {
     // O(N) might get a bit finicky with large numbers of players
     query(self::user_id()).incl(player()).iter(world, None).find_map(|(id, uid)| if uid == user_id { Some(id) } else { None })
 }
+
+/// Returns the spectator entity for the given user ID, if it exists
+pub fn get_spectator_by_user_id(world: &World, user_id: &str) -> Option<EntityId> {
+    query(self::user_id()).incl(spectator()).iter(world, None).find_map(|(id, uid)| if uid == user_id { Some(id) } else { None })
+}
@@ -0,0 +1,224 @@
+//! The cells and portals of indoor scenes. The scene is split into [visibility_cell]s connected by portals, which the
+//! renderer uses to only draw the cells that can be seen from the cell the camera is in, and the audio to carry sounds
+//! around corners.
+
+use ambient_ecs::{components, query, EntityId, Resource, World};
+use ambient_std::shapes::Sphere;
+use glam::{vec2, Mat4, UVec4, Vec2, Vec3};
+use itertools::Itertools;
+
+use crate::transform::{get_world_position, local_to_world};
+
+pub use ambient_ecs::generated::components::core::rendering::{
+    portal_cells, portal_size, visibility_cell,
+};
+
+/// The most cells there can be; the visible cells are given as a bitset of this size
+pub const MAX_VISIBILITY_CELLS: usize = 256;
+/// How far in front of and behind a portal its cells are looked for, in meters
+const PORTAL_PROBE_DISTANCE: f32 = 0.1;
+/// How many portals deep the cells are traversed, and how many portals are looked through at most per frame
+const MAX_PORTAL_DEPTH: usize = 16;
+const MAX_PORTAL_STEPS: usize = 4096;
+
+components!("rendering", {
+    /// The cells and portals of the scene
+    @[Resource]
+    portal_graph: PortalGraph,
+});
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Cell {
+    id: EntityId,
+    min: Vec3,
+    max: Vec3,
+}
+impl Cell {
+    fn contains_point(&self, point: Vec3) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+    fn contains_sphere(&self, sphere: &Sphere) -> bool {
+        (sphere.center - sphere.radius).cmpge(self.min).all()
+            && (sphere.center + sphere.radius).cmple(self.max).all()
+    }
+    fn volume(&self) -> f32 {
+        (self.max - self.min)
+            .max(Vec3::ZERO)
+            .to_array()
+            .iter()
+            .product()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Portal {
+    corners: [Vec3; 4],
+    cells: [usize; 2],
+}
+impl Portal {
+    /// The bounds of the portal on the screen, in normalized device coordinates, or `None` if it's behind the camera
+    fn screen_rect(&self, projection_view: Mat4) -> Option<Rect> {
+        let clip = self
+            .corners
+            .map(|corner| projection_view * corner.extend(1.));
+        if clip.iter().all(|corner| corner.w <= 0.) {
+            return None;
+        }
+        // The portal passes by the camera, so it may cover any part of the screen
+        if clip.iter().any(|corner| corner.w <= 0.) {
+            return Some(Rect::SCREEN);
+        }
+        let points = clip.map(|corner| vec2(corner.x, corner.y) / corner.w);
+        Rect {
+            min: points.iter().copied().reduce(Vec2::min).unwrap(),
+            max: points.iter().copied().reduce(Vec2::max).unwrap(),
+        }
+        .intersect(&Rect::SCREEN)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rect {
+    min: Vec2,
+    max: Vec2,
+}
+impl Rect {
+    const SCREEN: Self = Self {
+        min: Vec2::NEG_ONE,
+        max: Vec2::ONE,
+    };
+    fn intersect(&self, other: &Self) -> Option<Self> {
+        let rect = Self {
+            min: self.min.max(other.min),
+            max: self.max.min(other.max),
+        };
+        (rect.min.cmplt(rect.max).all()).then_some(rect)
+    }
+}
+
+/// The cells and portals of a world, see the [module docs](self)
+#[derive(Debug, Clone, Default)]
+pub struct PortalGraph {
+    cells: Vec<Cell>,
+    portals: Vec<Portal>,
+}
+impl PortalGraph {
+    pub fn from_world(world: &World) -> Self {
+        let cells = query(visibility_cell())
+            .iter(world, None)
+            .filter_map(|(id, size)| {
+                let center = get_world_position(world, id).ok()?;
+                Some(Cell {
+                    id,
+                    min: center - *size / 2.,
+                    max: center + *size / 2.,
+                })
+            })
+            .sorted_by_key(|cell| cell.id)
+            .take(MAX_VISIBILITY_CELLS)
+            .collect_vec();
+        let mut graph = Self {
+            cells,
+            portals: Vec::new(),
+        };
+
+        graph.portals = query((portal_size(), local_to_world()))
+            .iter(world, None)
+            .filter_map(|(id, (size, transform))| {
+                let normal = transform.transform_vector3(Vec3::Z).normalize_or_zero();
+                let center = transform.transform_point3(Vec3::ZERO);
+                let cells = match world.get_ref(id, portal_cells()) {
+                    Ok(cells) => [
+                        graph.cell_index(cells.get(0)?)?,
+                        graph.cell_index(cells.get(1)?)?,
+                    ],
+                    Err(_) => [
+                        graph.cell_at(center + normal * PORTAL_PROBE_DISTANCE)?,
+                        graph.cell_at(center - normal * PORTAL_PROBE_DISTANCE)?,
+                    ],
+                };
+                if cells[0] == cells[1] {
+                    return None;
+                }
+                let corners = [(-1., -1.), (1., -1.), (1., 1.), (-1., 1.)]
+                    .map(|(x, y)| transform.transform_point3((vec2(x, y) * *size / 2.).extend(0.)));
+                Some(Portal { corners, cells })
+            })
+            .collect();
+        graph
+    }
+
+    /// Whether `other` has the same cells, which means that everything is still in the same cell
+    pub fn has_same_cells(&self, other: &Self) -> bool {
+        self.cells == other.cells
+    }
+
+    fn cell_index(&self, id: &EntityId) -> Option<usize> {
+        self.cells.iter().position(|cell| cell.id == *id)
+    }
+
+    /// The index of the smallest cell that contains `point`
+    pub fn cell_at(&self, point: Vec3) -> Option<usize> {
+        (0..self.cells.len())
+            .filter(|&i| self.cells[i].contains_point(point))
+            .min_by(|&a, &b| self.cells[a].volume().total_cmp(&self.cells[b].volume()))
+    }
+
+    /// The center of every portal, and the indices of the two cells it connects
+    pub fn portals(&self) -> impl Iterator<Item = (Vec3, [usize; 2])> + '_ {
+        self.portals.iter().map(|portal| {
+            let center = portal.corners.iter().sum::<Vec3>() / 4.;
+            (center, portal.cells)
+        })
+    }
+
+    /// The smallest cell that `sphere` fits in
+    pub fn cell_of(&self, sphere: &Sphere) -> Option<usize> {
+        (0..self.cells.len())
+            .filter(|&i| self.cells[i].contains_sphere(sphere))
+            .min_by(|&a, &b| self.cells[a].volume().total_cmp(&self.cells[b].volume()))
+    }
+
+    /// The cells that can be seen by a camera at `position`, as a bitset of [MAX_VISIBILITY_CELLS] bits, or `None` if
+    /// portal culling is off because the camera isn't inside a cell. The screen space bounds of the portals are
+    /// narrowed down through every portal that is looked through, so a cell is only visible if there is a line of sight
+    /// to it through all of them.
+    pub fn visible_cells(&self, position: Vec3, projection_view: Mat4) -> Option<[UVec4; 2]> {
+        let start = self.cell_at(position)?;
+        let mut visible = [UVec4::ZERO; 2];
+        set_visible(&mut visible, start);
+
+        let mut stack = vec![(start, Rect::SCREEN, 0)];
+        let mut steps = 0;
+        while let Some((cell, rect, depth)) = stack.pop() {
+            if depth >= MAX_PORTAL_DEPTH {
+                continue;
+            }
+            for portal in &self.portals {
+                let next = match portal.cells {
+                    [a, b] if a == cell => b,
+                    [a, b] if b == cell => a,
+                    _ => continue,
+                };
+                steps += 1;
+                // Too many paths through the portals to follow; everything is rendered instead
+                if steps > MAX_PORTAL_STEPS {
+                    return None;
+                }
+                let Some(rect) = portal
+                    .screen_rect(projection_view)
+                    .and_then(|portal_rect| portal_rect.intersect(&rect))
+                else {
+                    continue;
+                };
+                set_visible(&mut visible, next);
+                stack.push((next, rect, depth + 1));
+            }
+        }
+        Some(visible)
+    }
+}
+
+fn set_visible(visible: &mut [UVec4; 2], cell: usize) {
+    visible[cell / 128][(cell / 32) % 4] |= 1 << (cell % 32);
+}
@@ -1,8 +1,8 @@
 use std::collections::HashSet;
 
 use ambient_ecs::{
-    components, ensure_has_component, query, query_mut, Debuggable, ECSError, EntityId, FrameEvent,
-    Networked, QueryState, Store, System, SystemGroup, World,
+    components, ensure_has_component, query, query_mut, Debuggable, ECSError, EntityId, FnSystem,
+    FrameEvent, Networked, QueryState, Store, System, SystemGroup, World,
 };
 use glam::*;
 
@@ -16,9 +16,9 @@ use crate::{
 };
 
 pub use ambient_ecs::generated::components::core::transform::{
-    cylindrical_billboard_z, euler_rotation, inv_local_to_world, local_to_parent, local_to_world,
-    lookat_target, lookat_up, mesh_to_local, mesh_to_world, reset_scale, rotation, scale,
-    spherical_billboard, translation,
+    cylindrical_billboard_z, euler_rotation, inv_local_to_world, large_scale_positions,
+    local_to_parent, local_to_world, lookat_target, lookat_up, mesh_to_local, mesh_to_world,
+    reset_scale, rotation, scale, spherical_billboard, translation, translation_f64, world_origin,
 };
 
 components!("transform", {
@@ -63,6 +63,10 @@ impl TransformSystem {
                             }
                         },
                     ),
+                    ensure_has_component(translation_f64(), translation(), Default::default()),
+                    Box::new(FnSystem::new(|world, _| {
+                        update_large_scale_translations(world)
+                    })),
                     query_mut(
                         (local_to_parent(),),
                         (
@@ -539,6 +543,51 @@ fn cylindrical_billboard_z_matrix(local_to_world: &mut Mat4, inv_view: &Mat4) {
     // local_to_world.as_mut()[2*4 + 2] = inv_view.as_ref()[2*4 + 2];
 }
 
+/// Converts a `translation_f64` or `world_origin` value to a vector; `None` if it doesn't have three elements
+pub fn dvec3_from_slice(value: &[f64]) -> Option<DVec3> {
+    match value {
+        &[x, y, z] => Some(dvec3(x, y, z)),
+        _ => None,
+    }
+}
+
+/// Sets the `translation` of the entities with a `translation_f64` to it relative to the `world_origin`, when
+/// `large_scale_positions` is enabled. The origin follows the active camera when it has a `translation_f64`, so that
+/// the positions closest to the camera are the most precise once converted to `f32`.
+fn update_large_scale_translations(world: &mut World) {
+    if query(())
+        .incl(large_scale_positions())
+        .iter(world, None)
+        .next()
+        .is_none()
+    {
+        return;
+    }
+
+    // Only clients have a local user, and the server keeps its origin where the modules put it
+    let camera = world
+        .resource_opt(local_user_id())
+        .and_then(|user_id| get_active_camera(world, main_scene(), Some(user_id)));
+    let origin = camera
+        .and_then(|camera| world.get_ref(camera, translation_f64()).ok())
+        .or_else(|| world.resource_opt(world_origin()))
+        .and_then(|origin| dvec3_from_slice(origin))
+        .unwrap_or(DVec3::ZERO);
+    let origin_value = origin.to_array().to_vec();
+    if world.resource_opt(world_origin()) != Some(&origin_value) {
+        world.add_resource(world_origin(), origin_value);
+    }
+
+    let translations = query(translation_f64())
+        .incl(translation())
+        .iter(world, None)
+        .filter_map(|(id, position)| Some((id, (dvec3_from_slice(position)? - origin).as_vec3())))
+        .collect::<Vec<_>>();
+    for (id, relative) in translations {
+        world.set_if_changed(id, translation(), relative).ok();
+    }
+}
+
 pub fn get_world_transform(world: &World, entity: EntityId) -> Result<Mat4, ECSError> {
     match world.get(entity, local_to_world()) {
         Ok(ltw) => Ok(ltw),
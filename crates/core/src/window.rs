@@ -38,3 +38,16 @@ pub enum WindowCtl {
     SetTitle(String),
     SetFullscreen(bool),
 }
+
+/// Grabs the cursor with `mode`, falling back to the other grab mode where the platform doesn't support it: locking isn't
+/// supported on Windows and X11, and confining isn't supported on macOS.
+pub fn set_cursor_grab(window: &Window, mode: CursorGrabMode) {
+    let fallback = match mode {
+        CursorGrabMode::Locked => CursorGrabMode::Confined,
+        CursorGrabMode::Confined => CursorGrabMode::Locked,
+        CursorGrabMode::None => CursorGrabMode::None,
+    };
+    if let Err(err) = window.set_cursor_grab(mode).or_else(|_| window.set_cursor_grab(fallback)) {
+        tracing::warn!("Failed to grab the cursor: {err}");
+    }
+}
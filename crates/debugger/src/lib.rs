@@ -1,4 +1,4 @@
-use std::{sync::Arc};
+use std::{sync::Arc, time::Duration};
 
 use ambient_core::{
     asset_cache,
@@ -8,19 +8,30 @@ use ambient_core::{
     main_scene,
     player::local_user_id,
     runtime,
+    transform::translation,
+};
+use ambient_ecs::{
+    generated::components::core::{rect::background_color, rendering::color, text::font_size},
+    query, World,
 };
-use ambient_ecs::{query, World};
 use ambient_element::{element_component, Element, ElementComponentExt, Hooks};
 use ambient_gizmos::{gizmos, GizmoPrimitive};
 use ambient_network::{client::GameClient, server::RpcArgs as ServerRpcArgs};
 use ambient_renderer::{RenderTarget, Renderer};
 use ambient_rpc::RpcRegistry;
 use ambient_shared_types::{ModifiersState, VirtualKeyCode};
-use ambient_std::{asset_cache::SyncAssetKeyExt, color::Color, download_asset::AssetsCacheDir, line_hash, Cb};
+use ambient_std::{asset_cache::SyncAssetKeyExt, cb, color::Color, download_asset::AssetsCacheDir, line_hash, Cb};
 use ambient_ui_native::{
-    fit_horizontal, height, space_between_items, width, Button, ButtonStyle, Dropdown, Fit, FlowColumn, FlowRow, Image, UIExt,
+    fit_horizontal, height, space_between_items, width, Button, ButtonStyle, Dropdown, Fit, FlowColumn, FlowRow, Image,
+    Rectangle, Text, TextEditor, UIExt,
+};
+use ambient_wasm::shared::{
+    module_log::{module_logs, ModuleLog},
+    profiling::{module_profile, ModuleProfile},
+    MessageType,
 };
-use glam::Vec3;
+use glam::{vec3, Vec3};
+use itertools::Itertools;
 
 type GetDebuggerState = Cb<dyn Fn(&mut dyn FnMut(&mut Renderer, &RenderTarget, &mut World)) + Sync + Send>;
 
@@ -32,13 +43,67 @@ pub async fn rpc_dump_world_hierarchy(args: ServerRpcArgs, _: ()) -> Option<Stri
     Some(String::from_utf8(res).unwrap())
 }
 
+pub async fn rpc_module_profile(args: ServerRpcArgs, _: ()) -> Option<ModuleProfile> {
+    let mut state = args.state.lock();
+    let world = state.get_player_world_mut(&args.user_id)?;
+    world.resource_opt(module_profile()).cloned()
+}
+
+pub async fn rpc_tweakables(args: ServerRpcArgs, _: ()) -> Option<Vec<(String, String)>> {
+    let mut state = args.state.lock();
+    let world = state.get_player_world_mut(&args.user_id)?;
+    Some(ambient_tweakables::list(world))
+}
+
+/// Sets a server tweakable, given its name and value as text; returns why it couldn't be set if it failed. Only for the
+/// admins of the server
+pub async fn rpc_set_tweakable(args: ServerRpcArgs, (name, value): (String, String)) -> Option<Result<(), String>> {
+    let mut state = args.state.lock();
+    let world = state.get_player_world_mut(&args.user_id)?;
+    if !args.is_admin(world) {
+        return Some(Err("Only the admins of the server can set its tweakables".to_string()));
+    }
+    Some(ambient_tweakables::set_from_text(world, &name, &value).map_err(|err| format!("{err:#}")))
+}
+
+/// The mods loaded by the server, with whether they are enabled. Only for the admins of the server
+pub async fn rpc_mods(args: ServerRpcArgs, _: ()) -> Option<Vec<(String, bool)>> {
+    let mut state = args.state.lock();
+    let world = state.get_player_world_mut(&args.user_id)?;
+    if !args.is_admin(world) {
+        return None;
+    }
+    Some(ambient_wasm::server::mods::list(world).into_iter().collect())
+}
+
+/// Enables or disables a mod, along with the mods that it depends on or that depend on it. Only for the admins of the
+/// server
+pub async fn rpc_set_mod_enabled(args: ServerRpcArgs, (id, enabled): (String, bool)) -> Option<()> {
+    let mut state = args.state.lock();
+    let world = state.get_player_world_mut(&args.user_id)?;
+    if !args.is_admin(world) {
+        return None;
+    }
+    ambient_wasm::server::mods::set_enabled(world, &id, enabled);
+    Some(())
+}
+
 pub fn register_server_rpcs(reg: &mut RpcRegistry<ServerRpcArgs>) {
     reg.register(rpc_dump_world_hierarchy);
+    reg.register(rpc_module_profile);
+    reg.register(rpc_tweakables);
+    reg.register(rpc_set_tweakable);
+    reg.register(rpc_mods);
+    reg.register(rpc_set_mod_enabled);
 }
 
 #[element_component]
 pub fn Debugger(hooks: &mut Hooks, get_state: GetDebuggerState) -> Element {
     let (show_shadows, set_show_shadows) = hooks.use_state(false);
+    let (show_module_profile, set_show_module_profile) = hooks.use_state(false);
+    let (show_module_console, set_show_module_console) = hooks.use_state(false);
+    let (show_tweakables, set_show_tweakables) = hooks.use_state(false);
+    let (show_mods, set_show_mods) = hooks.use_state(false);
     let (game_client, _) = hooks.consume_context::<GameClient>().unwrap();
     FlowColumn::el([
         FlowRow(vec![
@@ -156,11 +221,53 @@ pub fn Debugger(hooks: &mut Hooks, get_state: GetDebuggerState) -> Element {
             .hotkey(VirtualKeyCode::F8)
             .style(ButtonStyle::Flat)
             .el(),
+            Button::new("Show Module Profile", {
+                move |_| {
+                    set_show_module_profile(!show_module_profile);
+                }
+            })
+            .hotkey_modifier(ModifiersState::SHIFT)
+            .hotkey(VirtualKeyCode::F9)
+            .style(ButtonStyle::Flat)
+            .toggled(show_module_profile)
+            .el(),
+            Button::new("Show Module Console", {
+                move |_| {
+                    set_show_module_console(!show_module_console);
+                }
+            })
+            .hotkey_modifier(ModifiersState::SHIFT)
+            .hotkey(VirtualKeyCode::F10)
+            .style(ButtonStyle::Flat)
+            .toggled(show_module_console)
+            .el(),
+            Button::new("Show Tweakables", {
+                move |_| {
+                    set_show_tweakables(!show_tweakables);
+                }
+            })
+            .hotkey_modifier(ModifiersState::SHIFT)
+            .hotkey(VirtualKeyCode::F11)
+            .style(ButtonStyle::Flat)
+            .toggled(show_tweakables)
+            .el(),
+            Button::new("Show Mods", {
+                move |_| {
+                    set_show_mods(!show_mods);
+                }
+            })
+            .style(ButtonStyle::Flat)
+            .toggled(show_mods)
+            .el(),
             ShaderDebug { get_state: get_state.clone() }.el(),
         ])
         .el()
         .with(space_between_items(), 5.),
         if show_shadows { ShadowMapsViz { get_state: get_state.clone() }.el() } else { Element::new() },
+        if show_module_profile { ModuleProfileViz { get_state: get_state.clone() }.el() } else { Element::new() },
+        if show_module_console { ModuleConsole { get_state: get_state.clone() }.el() } else { Element::new() },
+        if show_tweakables { TweakablesPanel { get_state: get_state.clone() }.el() } else { Element::new() },
+        if show_mods { ModsPanel.el() } else { Element::new() },
     ])
     .with_background(Color::rgba(0., 0., 0., 1.).into())
     .with(fit_horizontal(), Fit::Parent)
@@ -199,6 +306,309 @@ fn ShadowMapViz(hooks: &mut Hooks, get_state: GetDebuggerState, cascade: u32) ->
     Image { texture }.el().with(width(), 200.).with(height(), 200.)
 }
 
+/// How often the module profiles are refreshed; each refresh shows the last frame of the client and server.
+const MODULE_PROFILE_REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+const FLAMEGRAPH_WIDTH: f32 = 800.;
+const FLAMEGRAPH_ROW_HEIGHT: f32 = 16.;
+
+/// Flamegraphs of the calls to the client and server modules during a frame, and of the profiling scopes they reported.
+#[element_component]
+fn ModuleProfileViz(hooks: &mut Hooks, get_state: GetDebuggerState) -> Element {
+    let (client_profile, set_client_profile) = hooks.use_state(ModuleProfile::default());
+    let (server_profile, set_server_profile) = hooks.use_state(ModuleProfile::default());
+    let (game_client, _) = hooks.consume_context::<GameClient>().unwrap();
+    hooks.use_spawn(move |world| {
+        let task = world.resource(runtime()).spawn(async move {
+            let mut interval = ambient_sys::time::interval(MODULE_PROFILE_REFRESH_INTERVAL);
+            loop {
+                interval.tick().await;
+                let mut profile = None;
+                get_state(&mut |_, _, world| profile = world.resource_opt(module_profile()).cloned());
+                set_client_profile(profile.unwrap_or_default());
+                if let Ok(Some(profile)) = game_client.rpc(rpc_module_profile, ()).await {
+                    set_server_profile(profile);
+                }
+            }
+        });
+        move |_| {
+            task.abort();
+        }
+    });
+    FlowColumn::el([
+        Flamegraph { title: "Client modules".to_string(), profile: client_profile }.el(),
+        Flamegraph { title: "Server modules".to_string(), profile: server_profile }.el(),
+    ])
+    .with(space_between_items(), 5.)
+}
+
+#[element_component]
+fn Flamegraph(_hooks: &mut Hooks, title: String, profile: ModuleProfile) -> Element {
+    let scale = FLAMEGRAPH_WIDTH / profile.duration.max(f32::EPSILON);
+    let rows = profile.spans.iter().map(|span| span.depth + 1).max().unwrap_or(1);
+    let module_time: f32 = profile.spans.iter().filter(|span| span.depth == 0).map(|span| span.duration).sum();
+    let bars = profile
+        .spans
+        .iter()
+        .map(|span| {
+            let bar_width = (span.duration * scale).max(1.);
+            // Each module gets its own hue, so that its calls and scopes can be told apart
+            let hue = span.module.bytes().fold(0u32, |hash, b| hash.wrapping_mul(31).wrapping_add(b as u32)) % 360;
+            let label = if bar_width > 80. {
+                Text::el(format!("{} {} ({:.2} ms)", span.module, span.name, span.duration * 1000.))
+                    .with(font_size(), 10.)
+                    .with(translation(), vec3(2., 1., -0.01))
+            } else {
+                Element::new()
+            };
+            Rectangle
+                .el()
+                .with(width(), bar_width)
+                .with(height(), FLAMEGRAPH_ROW_HEIGHT - 1.)
+                .with(background_color(), Color::hsl(hue as f32, 0.6, 0.35).into())
+                .with(translation(), vec3(span.start * scale, span.depth as f32 * FLAMEGRAPH_ROW_HEIGHT, -0.01))
+                .children(vec![label])
+        })
+        .collect_vec();
+    FlowColumn::el([
+        Text::el(format!("{title}: {:.2} ms of a {:.2} ms frame", module_time * 1000., profile.duration * 1000.)),
+        Rectangle
+            .el()
+            .with(width(), FLAMEGRAPH_WIDTH)
+            .with(height(), rows as f32 * FLAMEGRAPH_ROW_HEIGHT)
+            .with(background_color(), Color::rgba(0.1, 0.1, 0.1, 1.).into())
+            .children(bars),
+    ])
+    .with(space_between_items(), 2.)
+}
+
+const MODULE_CONSOLE_REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+/// How many of the most recent logs that match the filters are shown
+const MODULE_CONSOLE_LINES: usize = 20;
+
+/// The logs of the client modules, and of the server modules if the server forwards them to this client (see
+/// `module_log_receiver`), filtered by module and severity.
+#[element_component]
+fn ModuleConsole(hooks: &mut Hooks, get_state: GetDebuggerState) -> Element {
+    let (logs, set_logs) = hooks.use_state(Vec::<ModuleLog>::new());
+    let (module_filter, set_module_filter) = hooks.use_state(String::new());
+    let (hidden_types, set_hidden_types) = hooks.use_state(Vec::<MessageType>::new());
+    hooks.use_interval(MODULE_CONSOLE_REFRESH_INTERVAL.as_secs_f32(), move || {
+        let mut logs = Vec::new();
+        get_state(&mut |_, _, world| {
+            if let Some(module_logs) = world.resource_opt(module_logs()) {
+                logs = module_logs.lock().iter().cloned().collect();
+            }
+        });
+        set_logs(logs);
+    });
+
+    let type_toggles = [
+        (MessageType::Info, "Info"),
+        (MessageType::Warn, "Warnings"),
+        (MessageType::Error, "Errors"),
+        (MessageType::Stdout, "Stdout"),
+        (MessageType::Stderr, "Stderr"),
+    ]
+    .into_iter()
+    .map(|(type_, label)| {
+        let hidden = hidden_types.contains(&type_);
+        Button::new(label, {
+            let hidden_types = hidden_types.clone();
+            let set_hidden_types = set_hidden_types.clone();
+            move |_| {
+                let mut hidden_types = hidden_types.clone();
+                if hidden {
+                    hidden_types.retain(|hidden_type| *hidden_type != type_);
+                } else {
+                    hidden_types.push(type_);
+                }
+                set_hidden_types(hidden_types);
+            }
+        })
+        .style(ButtonStyle::Flat)
+        .toggled(!hidden)
+        .el()
+    })
+    .collect_vec();
+
+    let mut lines = logs
+        .iter()
+        .rev()
+        .filter(|log| !hidden_types.contains(&log.type_) && log.module.contains(module_filter.as_str()))
+        .take(MODULE_CONSOLE_LINES)
+        .map(|log| {
+            let (label, line_color) = match log.type_ {
+                MessageType::Info => ("info", Color::rgba(1., 1., 1., 1.)),
+                MessageType::Warn => ("warn", Color::rgba(1., 0.8, 0.2, 1.)),
+                MessageType::Error => ("error", Color::rgba(1., 0.3, 0.3, 1.)),
+                MessageType::Stdout => ("stdout", Color::rgba(0.8, 0.8, 0.8, 1.)),
+                MessageType::Stderr => ("stderr", Color::rgba(1., 0.6, 0.4, 1.)),
+            };
+            let side = if log.server { "server" } else { "client" };
+            Text::el(format!("[{side}] [{}] {label}: {}", log.module, log.message)).with(color(), line_color.into())
+        })
+        .collect_vec();
+    lines.reverse();
+
+    FlowColumn::el([
+        FlowRow::el(
+            [TextEditor::new(module_filter, cb(move |filter| set_module_filter(filter)))
+                .placeholder(Some("Filter by module"))
+                .el()
+                .with(width(), 200.)]
+            .into_iter()
+            .chain(type_toggles),
+        )
+        .with(space_between_items(), 5.),
+        FlowColumn::el(lines),
+    ])
+    .with(space_between_items(), 5.)
+}
+
+/// How often the tweakables are refreshed, so that changes made by the modules show up.
+const TWEAKABLES_REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The tweakables of the client and server, which can be edited by submitting a new value.
+#[element_component]
+fn TweakablesPanel(hooks: &mut Hooks, get_state: GetDebuggerState) -> Element {
+    let (client_tweakables, set_client_tweakables) = hooks.use_state(Vec::<(String, String)>::new());
+    let (server_tweakables, set_server_tweakables) = hooks.use_state(Vec::<(String, String)>::new());
+    let (game_client, _) = hooks.consume_context::<GameClient>().unwrap();
+    let (runtime_handle, _) = hooks.use_state_with(|world| world.resource(runtime()).clone());
+    hooks.use_spawn({
+        let get_state = get_state.clone();
+        let game_client = game_client.clone();
+        move |world| {
+            let task = world.resource(runtime()).spawn(async move {
+                let mut interval = ambient_sys::time::interval(TWEAKABLES_REFRESH_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    let mut tweakables = Vec::new();
+                    get_state(&mut |_, _, world| tweakables = ambient_tweakables::list(world));
+                    set_client_tweakables(tweakables);
+                    if let Ok(Some(tweakables)) = game_client.rpc(rpc_tweakables, ()).await {
+                        set_server_tweakables(tweakables);
+                    }
+                }
+            });
+            move |_| {
+                task.abort();
+            }
+        }
+    });
+
+    let client_rows = client_tweakables.into_iter().map(|(name, value)| {
+        let get_state = get_state.clone();
+        TweakableRow {
+            label: format!("[client] {name}"),
+            value,
+            on_submit: cb(move |value| {
+                get_state(&mut |_, _, world| {
+                    if let Err(err) = ambient_tweakables::set_from_text(world, &name, &value) {
+                        log::warn!("Failed to set the tweakable: {err:#}");
+                    }
+                });
+            }),
+        }
+        .el()
+    });
+    let server_rows = server_tweakables.into_iter().map(|(name, value)| {
+        let game_client = game_client.clone();
+        let runtime_handle = runtime_handle.clone();
+        TweakableRow {
+            label: format!("[server] {name}"),
+            value,
+            on_submit: cb(move |value| {
+                let game_client = game_client.clone();
+                let name = name.clone();
+                runtime_handle.spawn(async move {
+                    if let Ok(Some(Err(err))) = game_client.rpc(rpc_set_tweakable, (name, value)).await {
+                        log::warn!("Failed to set the tweakable: {err}");
+                    }
+                });
+            }),
+        }
+        .el()
+    });
+    FlowColumn::el(client_rows.chain(server_rows).collect_vec()).with(space_between_items(), 5.)
+}
+
+#[element_component]
+fn TweakableRow(hooks: &mut Hooks, label: String, value: String, on_submit: Cb<dyn Fn(String) + Sync + Send>) -> Element {
+    // What is being typed, until it is submitted; until then, the value isn't replaced when it is refreshed
+    let (draft, set_draft) = hooks.use_state(None::<String>);
+    FlowRow::el([
+        Text::el(label).with(width(), 250.),
+        TextEditor::new(
+            draft.unwrap_or(value),
+            cb({
+                let set_draft = set_draft.clone();
+                move |draft| set_draft(Some(draft))
+            }),
+        )
+        .on_submit(move |value| {
+            on_submit(value);
+            set_draft(None);
+        })
+        .el()
+        .with(width(), 200.),
+    ])
+    .with(space_between_items(), 5.)
+}
+
+/// How often the mods are refreshed, as enabling or disabling a mod can change others too.
+const MODS_REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The mods loaded by the server, which can be enabled and disabled.
+#[element_component]
+fn ModsPanel(hooks: &mut Hooks) -> Element {
+    let (mods, set_mods) = hooks.use_state(Vec::<(String, bool)>::new());
+    let (game_client, _) = hooks.consume_context::<GameClient>().unwrap();
+    let (runtime_handle, _) = hooks.use_state_with(|world| world.resource(runtime()).clone());
+    hooks.use_spawn({
+        let game_client = game_client.clone();
+        move |world| {
+            let task = world.resource(runtime()).spawn(async move {
+                let mut interval = ambient_sys::time::interval(MODS_REFRESH_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    if let Ok(Some(mods)) = game_client.rpc(rpc_mods, ()).await {
+                        set_mods(mods);
+                    }
+                }
+            });
+            move |_| {
+                task.abort();
+            }
+        }
+    });
+
+    if mods.is_empty() {
+        return Text::el("No mods are loaded");
+    }
+    let rows = mods.into_iter().map(|(id, enabled)| {
+        let game_client = game_client.clone();
+        let runtime_handle = runtime_handle.clone();
+        FlowRow::el([
+            Text::el(id.clone()).with(width(), 250.),
+            Button::new(if enabled { "Enabled" } else { "Disabled" }, move |_| {
+                let game_client = game_client.clone();
+                let id = id.clone();
+                runtime_handle.spawn(async move {
+                    if let Err(err) = game_client.rpc(rpc_set_mod_enabled, (id, !enabled)).await {
+                        log::warn!("Failed to enable or disable the mod: {err:?}");
+                    }
+                });
+            })
+            .style(ButtonStyle::Flat)
+            .toggled(enabled)
+            .el(),
+        ])
+        .with(space_between_items(), 5.)
+    });
+    FlowColumn::el(rows.collect_vec()).with(space_between_items(), 5.)
+}
+
 #[element_component]
 fn ShaderDebug(hooks: &mut Hooks, get_state: GetDebuggerState) -> Element {
     let (show, set_show) = hooks.use_state(false);
@@ -0,0 +1,352 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use anyhow::Context;
+use serde::Deserialize;
+
+/// The variables of a conversation's participant, which conditions check and nodes and choices change.
+pub type Variables = BTreeMap<String, String>;
+
+/// How many branches can be followed in a row before the graph is considered to be looping.
+const MAX_REDIRECTS: usize = 64;
+
+/// A branching dialogue graph.
+///
+/// Graphs are TOML files. Each node shows a line, and is followed either by the choices the player can pick from or
+/// by its `next` node. The text of lines and choices are localization keys:
+///
+/// ```toml
+/// start = "greeting"
+///
+/// [nodes.greeting]
+/// # Nodes can redirect to another node before they are shown; the first branch whose condition is met is taken
+/// branches = [{ condition = "wolf_quest == done", next = "thanks" }]
+/// speaker = "guard.name"
+/// text = "guard.greeting"
+/// choices = [
+///     { text = "guard.ask_work", next = "quest", condition = "!wolf_quest" },
+///     { text = "guard.bye" },
+/// ]
+///
+/// [nodes.quest]
+/// speaker = "guard.name"
+/// text = "guard.quest"
+/// set = { wolf_quest = "accepted" }
+/// ```
+///
+/// A conversation ends when a choice or node without a `next` is picked.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DialogueGraph {
+    pub start: String,
+    pub nodes: BTreeMap<String, DialogueNode>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DialogueNode {
+    pub speaker: Option<String>,
+    pub text: String,
+    pub choices: Vec<DialogueChoice>,
+    /// The node that follows this one if it has no choices
+    pub next: Option<String>,
+    pub branches: Vec<DialogueBranch>,
+    /// The variables that are set when this node is shown; an empty value removes the variable
+    pub set: Variables,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DialogueChoice {
+    pub text: String,
+    pub next: Option<String>,
+    /// The choice is only offered if this is met
+    pub condition: Option<Condition>,
+    /// The variables that are set when this choice is picked; an empty value removes the variable
+    pub set: Variables,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DialogueBranch {
+    pub condition: Option<Condition>,
+    pub next: String,
+}
+
+/// A condition on the variables: clauses joined by `&&`, each of which is `name` (the variable is set), `!name` (it
+/// isn't), `name == value` or `name != value`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Condition(Vec<Clause>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Clause {
+    IsSet(String),
+    IsNotSet(String),
+    Equals(String, String),
+    NotEquals(String, String),
+}
+
+impl FromStr for Condition {
+    type Err = anyhow::Error;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let clauses = source
+            .split("&&")
+            .map(|clause| {
+                let clause = clause.trim();
+                let clause = if let Some((name, value)) = clause.split_once("!=") {
+                    Clause::NotEquals(name.trim().to_string(), value.trim().to_string())
+                } else if let Some((name, value)) = clause.split_once("==") {
+                    Clause::Equals(name.trim().to_string(), value.trim().to_string())
+                } else if let Some(name) = clause.strip_prefix('!') {
+                    Clause::IsNotSet(name.trim().to_string())
+                } else {
+                    Clause::IsSet(clause.to_string())
+                };
+                match &clause {
+                    Clause::IsSet(name)
+                    | Clause::IsNotSet(name)
+                    | Clause::Equals(name, _)
+                    | Clause::NotEquals(name, _)
+                        if name.is_empty() || name.contains(char::is_whitespace) =>
+                    {
+                        anyhow::bail!("Invalid condition {source:?}")
+                    }
+                    _ => Ok(clause),
+                }
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self(clauses))
+    }
+}
+impl TryFrom<String> for Condition {
+    type Error = anyhow::Error;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        source.parse()
+    }
+}
+impl Condition {
+    pub fn is_met(&self, variables: &Variables) -> bool {
+        self.0.iter().all(|clause| match clause {
+            Clause::IsSet(name) => variables.contains_key(name),
+            Clause::IsNotSet(name) => !variables.contains_key(name),
+            Clause::Equals(name, value) => variables.get(name) == Some(value),
+            Clause::NotEquals(name, value) => variables.get(name) != Some(value),
+        })
+    }
+}
+
+fn is_met(condition: &Option<Condition>, variables: &Variables) -> bool {
+    condition
+        .as_ref()
+        .map(|condition| condition.is_met(variables))
+        .unwrap_or(true)
+}
+
+fn apply(set: &Variables, variables: &mut Variables) {
+    for (name, value) in set {
+        if value.is_empty() {
+            variables.remove(name);
+        } else {
+            variables.insert(name.clone(), value.clone());
+        }
+    }
+}
+
+impl DialogueGraph {
+    /// Parses a graph, and checks that all the nodes it refers to exist.
+    pub fn parse(source: &str) -> anyhow::Result<Self> {
+        let graph: Self = toml::from_str(source).context("Failed to parse dialogue graph")?;
+        let check = |next: &str, from: &str| {
+            anyhow::ensure!(
+                graph.nodes.contains_key(next),
+                "The node {next:?} (referred to by {from}) does not exist"
+            );
+            Ok(())
+        };
+        check(&graph.start, "`start`")?;
+        for (id, node) in &graph.nodes {
+            let nexts = node
+                .next
+                .iter()
+                .chain(
+                    node.choices
+                        .iter()
+                        .filter_map(|choice| choice.next.as_ref()),
+                )
+                .chain(node.branches.iter().map(|branch| &branch.next));
+            for next in nexts {
+                check(next, &format!("{id:?}"))?;
+            }
+        }
+        Ok(graph)
+    }
+
+    pub fn node(&self, id: &str) -> Option<&DialogueNode> {
+        self.nodes.get(id)
+    }
+
+    /// Enters the node `id`, following its branches, and applies the variables of the node that is shown. Returns the
+    /// id of that node, or `None` if the branches loop.
+    pub fn enter(&self, id: &str, variables: &mut Variables) -> Option<String> {
+        let mut id = id.to_string();
+        for _ in 0..MAX_REDIRECTS {
+            let node = self.node(&id)?;
+            match node
+                .branches
+                .iter()
+                .find(|branch| is_met(&branch.condition, variables))
+            {
+                Some(branch) => id = branch.next.clone(),
+                None => {
+                    apply(&node.set, variables);
+                    return Some(id);
+                }
+            }
+        }
+        log::warn!("The dialogue branches loop at {id:?}");
+        None
+    }
+
+    /// The indices of the choices of the node `id` that are offered, given the variables
+    pub fn available_choices(&self, id: &str, variables: &Variables) -> Vec<usize> {
+        match self.node(id) {
+            Some(node) => node
+                .choices
+                .iter()
+                .enumerate()
+                .filter(|(_, choice)| is_met(&choice.condition, variables))
+                .map(|(index, _)| index)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Picks the `choice`th of the available choices of the node `id`, or continues to its `next` node if it has no
+    /// choices, and enters the node that follows. Returns that node, or `None` if the conversation ended. Returns an
+    /// error if there is no such choice.
+    pub fn choose(
+        &self,
+        id: &str,
+        choice: usize,
+        variables: &mut Variables,
+    ) -> anyhow::Result<Option<String>> {
+        let node = self
+            .node(id)
+            .with_context(|| format!("The node {id:?} does not exist"))?;
+        let next = if node.choices.is_empty() {
+            anyhow::ensure!(choice == 0, "The node {id:?} has no choices");
+            node.next.as_ref()
+        } else {
+            let index = *self
+                .available_choices(id, variables)
+                .get(choice)
+                .with_context(|| format!("The node {id:?} has no choice {choice}"))?;
+            let choice = &node.choices[index];
+            apply(&choice.set, variables);
+            choice.next.as_ref()
+        };
+        Ok(next.and_then(|next| self.enter(next, variables)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GUARD: &str = r#"
+        start = "greeting"
+
+        [nodes.greeting]
+        branches = [{ condition = "wolf_quest == done", next = "thanks" }]
+        speaker = "guard.name"
+        text = "guard.greeting"
+        choices = [
+            { text = "guard.ask_work", next = "quest", condition = "!wolf_quest" },
+            { text = "guard.bye" },
+        ]
+
+        [nodes.quest]
+        text = "guard.quest"
+        set = { wolf_quest = "accepted" }
+        next = "greeting"
+
+        [nodes.thanks]
+        text = "guard.thanks"
+    "#;
+
+    fn variables(pairs: &[(&str, &str)]) -> Variables {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn conditions() {
+        let condition: Condition = "quest == done && !failed && met_guard".parse().unwrap();
+        assert!(condition.is_met(&variables(&[("quest", "done"), ("met_guard", "true")])));
+        assert!(!condition.is_met(&variables(&[("quest", "done")])));
+        assert!(!condition.is_met(&variables(&[
+            ("quest", "done"),
+            ("met_guard", "true"),
+            ("failed", "true")
+        ])));
+        assert!("a != b"
+            .parse::<Condition>()
+            .unwrap()
+            .is_met(&Variables::new()));
+        assert!("== b".parse::<Condition>().is_err());
+        assert!("a b".parse::<Condition>().is_err());
+    }
+
+    #[test]
+    fn parse_checks_references() {
+        assert!(DialogueGraph::parse(GUARD).is_ok());
+        let err = DialogueGraph::parse(&GUARD.replace("next = \"quest\"", "next = \"qest\""))
+            .unwrap_err();
+        assert!(err.to_string().contains("qest"));
+        assert!(DialogueGraph::parse("start = \"a\"\n[nodes.b]\ntext = \"b\"").is_err());
+    }
+
+    #[test]
+    fn conversation() {
+        let graph = DialogueGraph::parse(GUARD).unwrap();
+        let mut vars = Variables::new();
+
+        let node = graph.enter(&graph.start, &mut vars).unwrap();
+        assert_eq!(node, "greeting");
+        assert_eq!(graph.available_choices(&node, &vars), [0, 1]);
+        let node = graph.choose(&node, 0, &mut vars).unwrap().unwrap();
+        assert_eq!(node, "quest");
+        assert_eq!(vars["wolf_quest"], "accepted");
+
+        // Nodes without choices continue to their next node
+        assert!(graph.choose(&node, 1, &mut vars).is_err());
+        let node = graph.choose(&node, 0, &mut vars).unwrap().unwrap();
+        assert_eq!(node, "greeting");
+        // The quest can't be asked for again, so the only choice left is leaving
+        assert_eq!(graph.available_choices(&node, &vars), [1]);
+        assert!(graph.choose(&node, 1, &mut vars).is_err());
+        assert_eq!(graph.choose(&node, 0, &mut vars).unwrap(), None);
+
+        vars.insert("wolf_quest".to_string(), "done".to_string());
+        assert_eq!(graph.enter(&graph.start, &mut vars).unwrap(), "thanks");
+    }
+
+    #[test]
+    fn looping_branches() {
+        let graph = DialogueGraph::parse(
+            r#"
+            start = "a"
+            [nodes.a]
+            branches = [{ next = "b" }]
+            [nodes.b]
+            branches = [{ next = "a" }]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(graph.enter("a", &mut Variables::new()), None);
+    }
+}
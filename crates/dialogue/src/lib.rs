@@ -0,0 +1,179 @@
+use std::sync::Arc;
+
+use ambient_core::{asset_cache, async_ecs::async_run, runtime};
+use ambient_ecs::{
+    components, generated::messages, query, Entity, EntityId, FnSystem, Resource, SystemGroup,
+    World,
+};
+use ambient_std::asset_url::AbsAssetUrl;
+
+mod graph;
+pub use graph::*;
+
+pub use ambient_ecs::generated::components::core::dialogue::{
+    dialogue_choice, dialogue_choices, dialogue_from_url, dialogue_node, dialogue_participant,
+    dialogue_speaker, dialogue_text, dialogue_variable_keys, dialogue_variable_values,
+};
+
+components!("dialogue", {
+    dialogue_graph: Arc<DialogueGraph>,
+    /// The choices made this frame, which are sent to the modules as `DialogueChoice` messages
+    @[Resource]
+    dialogue_choice_events: Vec<messages::DialogueChoice>,
+    /// The conversations that ended this frame, which are sent to the modules as `DialogueEnd` messages
+    @[Resource]
+    dialogue_end_events: Vec<messages::DialogueEnd>,
+});
+
+pub fn server_resources() -> Entity {
+    Entity::new()
+        .with_default(dialogue_choice_events())
+        .with_default(dialogue_end_events())
+}
+
+/// The entity whose variables a conversation uses: its participant, or the conversation itself if it has none
+fn participant(world: &World, conversation: EntityId) -> EntityId {
+    world
+        .get(conversation, dialogue_participant())
+        .unwrap_or(conversation)
+}
+
+fn read_variables(world: &World, participant: EntityId) -> Variables {
+    let keys = world
+        .get_cloned(participant, dialogue_variable_keys())
+        .unwrap_or_default();
+    let values = world
+        .get_cloned(participant, dialogue_variable_values())
+        .unwrap_or_default();
+    keys.into_iter().zip(values).collect()
+}
+
+fn write_variables(world: &mut World, participant: EntityId, variables: Variables) {
+    if read_variables(world, participant) == variables {
+        return;
+    }
+    let (keys, values): (Vec<_>, Vec<_>) = variables.into_iter().unzip();
+    world
+        .add_components(
+            participant,
+            Entity::new()
+                .with(dialogue_variable_keys(), keys)
+                .with(dialogue_variable_values(), values),
+        )
+        .ok();
+}
+
+/// Shows the line of `node`, or ends the conversation if there is none
+fn show(world: &mut World, conversation: EntityId, node: Option<String>, variables: &Variables) {
+    let graph = world.get_cloned(conversation, dialogue_graph()).unwrap();
+    let Some((id, node)) = node.and_then(|id| Some((id.clone(), graph.node(&id)?))) else {
+        let node = world
+            .get_cloned(conversation, dialogue_node())
+            .unwrap_or_default();
+        let participant = participant(world, conversation);
+        world
+            .resource_mut(dialogue_end_events())
+            .push(messages::DialogueEnd::new(conversation, node, participant));
+        world.despawn(conversation);
+        return;
+    };
+    let choices = graph
+        .available_choices(&id, variables)
+        .into_iter()
+        .map(|index| node.choices[index].text.clone())
+        .collect::<Vec<_>>();
+    let data = Entity::new()
+        .with(dialogue_node(), id)
+        .with(dialogue_text(), node.text.clone())
+        .with(dialogue_choices(), choices);
+    match &node.speaker {
+        Some(speaker) => world
+            .add_components(conversation, data.with(dialogue_speaker(), speaker.clone()))
+            .ok(),
+        None => {
+            world
+                .remove_component(conversation, dialogue_speaker())
+                .ok();
+            world.add_components(conversation, data).ok()
+        }
+    };
+}
+
+/// Loads dialogue graphs and runs the conversations on the server.
+pub fn systems() -> SystemGroup {
+    SystemGroup::new(
+        "dialogue",
+        vec![
+            Box::new(FnSystem::new(|world, _| {
+                world.resource_mut(dialogue_choice_events()).clear();
+                world.resource_mut(dialogue_end_events()).clear();
+            })),
+            query(dialogue_from_url().changed()).to_system(|q, world, qs, _| {
+                for (id, url) in q.collect_cloned(world, qs) {
+                    let url = match AbsAssetUrl::parse(url) {
+                        Ok(value) => value,
+                        Err(err) => {
+                            log::warn!("Failed to parse dialogue_from_url url: {:?}", err);
+                            continue;
+                        }
+                    };
+                    let assets = world.resource(asset_cache()).clone();
+                    let async_run = world.resource(async_run()).clone();
+                    world.resource(runtime()).spawn(async move {
+                        let graph = url
+                            .download_string(&assets)
+                            .await
+                            .and_then(|source| DialogueGraph::parse(&source));
+                        match graph {
+                            Err(err) => {
+                                log::warn!("Failed to load dialogue graph: {:?}", err);
+                            }
+                            Ok(graph) => {
+                                async_run.run(move |world| {
+                                    if !world.exists(id) {
+                                        return;
+                                    }
+                                    let participant = participant(world, id);
+                                    let mut variables = read_variables(world, participant);
+                                    let node = graph.enter(&graph.start, &mut variables);
+                                    world
+                                        .add_component(id, dialogue_graph(), Arc::new(graph))
+                                        .ok();
+                                    show(world, id, node, &variables);
+                                    write_variables(world, participant, variables);
+                                });
+                            }
+                        }
+                    });
+                }
+            }),
+            query((dialogue_graph(), dialogue_node(), dialogue_choice())).to_system(
+                |q, world, qs, _| {
+                    for (id, (graph, node, choice)) in q.collect_cloned(world, qs) {
+                        world.remove_component(id, dialogue_choice()).ok();
+                        let participant = participant(world, id);
+                        let mut variables = read_variables(world, participant);
+                        let text = graph
+                            .available_choices(&node, &variables)
+                            .get(choice as usize)
+                            .and_then(|index| graph.node(&node)?.choices.get(*index))
+                            .map(|choice| choice.text.clone())
+                            .unwrap_or_default();
+                        let next = match graph.choose(&node, choice as usize, &mut variables) {
+                            Ok(next) => next,
+                            Err(err) => {
+                                log::warn!("Invalid dialogue choice: {:?}", err);
+                                continue;
+                            }
+                        };
+                        world.resource_mut(dialogue_choice_events()).push(
+                            messages::DialogueChoice::new(choice, id, node, participant, text),
+                        );
+                        show(world, id, next, &variables);
+                        write_variables(world, participant, variables);
+                    }
+                },
+            ),
+        ],
+    )
+}
@@ -220,6 +220,16 @@ where
     }
 }
 
+/// The component is the URL of an asset, or a list of them. The modules that set it must be permitted to load them
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AssetUrl;
+impl ComponentAttribute for AssetUrl {}
+impl<T: ComponentValue> AttributeConstructor<T, ()> for AssetUrl {
+    fn construct(store: &mut AttributeStore, _: ()) {
+        store.set(Self)
+    }
+}
+
 pub(crate) struct ComponentPath(pub String);
 impl ComponentAttribute for ComponentPath {}
 
@@ -33,6 +33,15 @@ pub fn with_component_registry<R>(f: impl FnOnce(&ComponentRegistry) -> R + Sync
 pub(crate) struct RegistryComponent {
     pub(crate) desc: ComponentDesc,
     pub(crate) primitive_component: Option<PrimitiveComponent>,
+    pub(crate) stable_id: u64,
+}
+
+/// An id for the component at `path` that is the same in every process, unlike its index, which depends on the order
+/// the components were registered in. It's the 64-bit FNV-1a hash of the path.
+pub fn stable_component_id(path: &str) -> u64 {
+    path.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -114,6 +123,8 @@ pub struct ComponentRegistry {
     pub(crate) components: Vec<RegistryComponent>,
     pub component_paths: HashMap<String, u32>,
     pub next_index: u32,
+    /// From [stable_component_id] to index
+    stable_ids: HashMap<u64, u32>,
 
     /// Handlers are called with a write-lock on ComponentRegistry, which will result in deadlock if your operation
     /// requires a read-lock on ComponentRegistry. Consider deferring your operation to a later time.
@@ -159,6 +170,17 @@ impl ComponentRegistry {
 
                 let desc = ComponentDesc::new(index, vtable);
 
+                let stable_id = stable_component_id(&path);
+                match self.stable_ids.entry(stable_id) {
+                    Entry::Occupied(other) => log::error!(
+                        "The stable id of {path} collides with that of {}, so it can't be replicated",
+                        self.components[*other.get() as usize].desc.path()
+                    ),
+                    Entry::Vacant(slot) => {
+                        slot.insert(index);
+                    }
+                }
+
                 // If a PrimitiveComponentType can be created from this component's type, create a PrimitiveComponent for it
                 let primitive_component = TYPE_ID_TO_PRIMITIVE_TYPE
                     .get(&(vtable.get_type_id)())
@@ -168,6 +190,7 @@ impl ComponentRegistry {
                 self.components.push(RegistryComponent {
                     desc,
                     primitive_component,
+                    stable_id,
                 });
 
                 index
@@ -221,6 +244,15 @@ impl ComponentRegistry {
         Some(self.components[index as usize].desc)
     }
 
+    pub fn get_by_stable_id(&self, stable_id: u64) -> Option<ComponentDesc> {
+        let index = *self.stable_ids.get(&stable_id)?;
+        Some(self.components[index as usize].desc)
+    }
+
+    pub fn stable_id(&self, desc: ComponentDesc) -> u64 {
+        self.components[desc.index() as usize].stable_id
+    }
+
     pub fn get_by_index(&self, index: u32) -> Option<ComponentDesc> {
         self.components.get(index as usize).map(|b| b.desc)
     }
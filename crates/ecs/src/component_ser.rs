@@ -41,7 +41,10 @@ impl<'de> Deserialize<'de> for ComponentEntry {
             {
                 let desc: ComponentDesc = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
 
-                let ser = *desc.attribute::<Serializable>().expect("Component is not serializable");
+                // The entries come from other peers too, which can name any component
+                let ser = *desc
+                    .attribute::<Serializable>()
+                    .ok_or_else(|| de::Error::custom(format!("Component {} is not deserializable", desc.path())))?;
 
                 seq.next_element_seed(ser.deserializer(desc))?.ok_or_else(|| de::Error::invalid_length(0, &self))
             }
@@ -73,6 +76,7 @@ mod test {
     components!("test", {
         @[Serializable]
         ser_test: String,
+        not_ser_test: String,
     });
 
     fn init() {
@@ -99,6 +103,13 @@ mod test {
         assert_eq!(source.downcast_ref::<String>(), deser.downcast_ref::<String>());
     }
 
+    #[test]
+    pub fn test_not_serializable() {
+        init();
+        let ser = serde_json::to_string(&(not_ser_test().desc(), "hello")).unwrap();
+        assert!(serde_json::from_str::<ComponentEntry>(&ser).is_err());
+    }
+
     #[test]
     pub fn test_json_value() {
         init();
@@ -0,0 +1,406 @@
+//! A compact binary encoding of [WorldDiff]s for replication.
+//!
+//! The serde encoding of a diff writes the full path of every component it contains, and goes through the generic
+//! serde machinery for the framing as well as the values. This one identifies components by their
+//! [stable_component_id], writes counts and lengths as varints, and only uses bincode for the component values.
+//!
+//! Layout:
+//! - `u8` version
+//! - varint change count, followed by the changes, each starting with a `u8` tag:
+//!   - `0` spawn with id / `1` spawn without id: `[id]` entity
+//!   - `2` despawn: id
+//!   - `3` add components: id, entity
+//!   - `4` remove components: id, varint count, `u64` stable ids
+//!   - `5` set: id, entry
+//!
+//! where an id is 16 little-endian bytes, an entity is a varint count followed by entries, and an entry is a `u64`
+//! stable id, a varint length and the bincode-encoded value.
+//!
+//! Decoding happens in two passes: the framing of the whole diff is first read into the frame arena, borrowing the
+//! values from the input, so that a truncated or corrupt diff is rejected before any value is deserialized.
+
+use std::io::Write;
+
+use ambient_std::frame_arena::{with_frame_arena, ArenaVec, Bump};
+use bincode::Options;
+use serde::de::DeserializeSeed;
+use thiserror::Error;
+
+use crate::{
+    stable_component_id, with_component_registry, ComponentDesc, ComponentEntry, Entity, EntityId,
+    Serializable, WorldChange, WorldDiff,
+};
+
+pub const DIFF_ENCODING_VERSION: u8 = 1;
+
+const TAG_SPAWN: u8 = 0;
+const TAG_SPAWN_WITHOUT_ID: u8 = 1;
+const TAG_DESPAWN: u8 = 2;
+const TAG_ADD_COMPONENTS: u8 = 3;
+const TAG_REMOVE_COMPONENTS: u8 = 4;
+const TAG_SET: u8 = 5;
+
+#[derive(Debug, Error)]
+pub enum DiffDecodeError {
+    #[error("Unsupported diff encoding version {0}")]
+    UnsupportedVersion(u8),
+    #[error("Unexpected end of diff")]
+    UnexpectedEnd,
+    #[error("Invalid change tag {0}")]
+    InvalidTag(u8),
+    #[error("Invalid varint")]
+    InvalidVarint,
+    #[error("{0} trailing bytes after the diff")]
+    TrailingBytes(usize),
+    #[error("Failed to decode the value of {path}")]
+    Value {
+        path: String,
+        #[source]
+        source: bincode::Error,
+    },
+}
+
+fn value_options() -> impl Options {
+    bincode::DefaultOptions::new()
+}
+
+impl WorldDiff {
+    /// Writes the diff in the binary encoding, see the [module docs](crate::diff_encoding)
+    pub fn encode(&self, mut writer: impl Write) -> bincode::Result<()> {
+        let writer = &mut writer;
+        writer.write_all(&[DIFF_ENCODING_VERSION])?;
+        write_varint(writer, self.changes.len() as u64)?;
+        for change in &self.changes {
+            write_change(writer, change)?;
+        }
+        Ok(())
+    }
+
+    pub fn encode_to_vec(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.encode(&mut buf)
+            .expect("Failed to encode a component value");
+        buf
+    }
+
+    /// Reads a diff written by [WorldDiff::encode]. Components that aren't registered in this process are skipped.
+    pub fn decode(data: &[u8]) -> Result<Self, DiffDecodeError> {
+        with_frame_arena(|arena| {
+            let raw = RawDiff::read(&mut Reader(data), arena)?;
+            raw.decode()
+        })
+    }
+}
+
+impl WorldChange {
+    /// The size of the change in the binary encoding
+    pub fn encoded_size(&self) -> usize {
+        struct Counter(usize);
+        impl Write for Counter {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0 += buf.len();
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let mut counter = Counter(0);
+        write_change(&mut counter, self).expect("Failed to encode a component value");
+        counter.0
+    }
+}
+
+fn write_change(writer: &mut impl Write, change: &WorldChange) -> bincode::Result<()> {
+    match change {
+        WorldChange::Spawn(Some(id), entity) => {
+            writer.write_all(&[TAG_SPAWN])?;
+            write_id(writer, *id)?;
+            write_entity(writer, entity)?;
+        }
+        WorldChange::Spawn(None, entity) => {
+            writer.write_all(&[TAG_SPAWN_WITHOUT_ID])?;
+            write_entity(writer, entity)?;
+        }
+        WorldChange::Despawn(id) => {
+            writer.write_all(&[TAG_DESPAWN])?;
+            write_id(writer, *id)?;
+        }
+        WorldChange::AddComponents(id, entity) => {
+            writer.write_all(&[TAG_ADD_COMPONENTS])?;
+            write_id(writer, *id)?;
+            write_entity(writer, entity)?;
+        }
+        WorldChange::RemoveComponents(id, components) => {
+            writer.write_all(&[TAG_REMOVE_COMPONENTS])?;
+            write_id(writer, *id)?;
+            write_varint(writer, components.len() as u64)?;
+            for &desc in components {
+                writer.write_all(&stable_id(desc).to_le_bytes())?;
+            }
+        }
+        WorldChange::Set(id, entry) => {
+            writer.write_all(&[TAG_SET])?;
+            write_id(writer, *id)?;
+            write_entry(writer, entry)?;
+        }
+    }
+    Ok(())
+}
+
+fn stable_id(desc: ComponentDesc) -> u64 {
+    with_component_registry(|registry| registry.stable_id(desc))
+}
+
+fn write_varint(writer: &mut impl Write, mut value: u64) -> std::io::Result<()> {
+    let mut buf = [0u8; 10];
+    let mut len = 0;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf[len] = byte;
+            len += 1;
+            break;
+        }
+        buf[len] = byte | 0x80;
+        len += 1;
+    }
+    writer.write_all(&buf[..len])
+}
+
+fn write_id(writer: &mut impl Write, id: EntityId) -> std::io::Result<()> {
+    writer.write_all(&id.0.to_le_bytes())
+}
+
+fn write_entity(writer: &mut impl Write, entity: &Entity) -> bincode::Result<()> {
+    write_varint(writer, entity.len() as u64)?;
+    for entry in entity.iter() {
+        write_entry(writer, entry)?;
+    }
+    Ok(())
+}
+
+fn write_entry(writer: &mut impl Write, entry: &ComponentEntry) -> bincode::Result<()> {
+    let ser = entry
+        .attribute::<Serializable>()
+        .expect("Component is not serializable");
+    let value = ser.serialize(entry);
+    writer.write_all(&stable_id(entry.desc()).to_le_bytes())?;
+    write_varint(writer, value_options().serialized_size(value)?)?;
+    value_options().serialize_into(writer, value)
+}
+
+struct Reader<'a>(&'a [u8]);
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], DiffDecodeError> {
+        if self.0.len() < len {
+            return Err(DiffDecodeError::UnexpectedEnd);
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+    fn u8(&mut self) -> Result<u8, DiffDecodeError> {
+        Ok(self.bytes(1)?[0])
+    }
+    fn u64(&mut self) -> Result<u64, DiffDecodeError> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+    fn id(&mut self) -> Result<EntityId, DiffDecodeError> {
+        Ok(EntityId(u128::from_le_bytes(
+            self.bytes(16)?.try_into().unwrap(),
+        )))
+    }
+    fn varint(&mut self) -> Result<u64, DiffDecodeError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            let bits = (byte & 0x7f) as u64;
+            if shift == 63 && bits > 1 {
+                return Err(DiffDecodeError::InvalidVarint);
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(DiffDecodeError::InvalidVarint)
+    }
+    /// A count of items that each take at least `min_size` bytes, which can't be larger than what's left of the input.
+    /// This keeps a corrupt count from reserving a huge amount of memory.
+    fn count(&mut self, min_size: usize) -> Result<usize, DiffDecodeError> {
+        let count = self.varint()?;
+        if count > (self.0.len() / min_size) as u64 {
+            return Err(DiffDecodeError::UnexpectedEnd);
+        }
+        Ok(count as usize)
+    }
+}
+
+/// The smallest encoded change: a tag and an empty entity
+const MIN_CHANGE_SIZE: usize = 2;
+/// The smallest encoded entry: a stable id and an empty value
+const MIN_ENTRY_SIZE: usize = 9;
+
+struct RawEntry<'a> {
+    stable_id: u64,
+    value: &'a [u8],
+}
+
+enum RawChange<'a, 'b> {
+    Spawn(Option<EntityId>, ArenaVec<'b, RawEntry<'a>>),
+    Despawn(EntityId),
+    AddComponents(EntityId, ArenaVec<'b, RawEntry<'a>>),
+    RemoveComponents(EntityId, ArenaVec<'b, u64>),
+    Set(EntityId, RawEntry<'a>),
+}
+
+struct RawDiff<'a, 'b> {
+    changes: ArenaVec<'b, RawChange<'a, 'b>>,
+}
+impl<'a, 'b> RawDiff<'a, 'b> {
+    fn read(reader: &mut Reader<'a>, arena: &'b Bump) -> Result<Self, DiffDecodeError> {
+        let version = reader.u8()?;
+        if version != DIFF_ENCODING_VERSION {
+            return Err(DiffDecodeError::UnsupportedVersion(version));
+        }
+        let count = reader.count(MIN_CHANGE_SIZE)?;
+        let mut changes = ArenaVec::with_capacity_in(count, arena);
+        for _ in 0..count {
+            let change = match reader.u8()? {
+                TAG_SPAWN => RawChange::Spawn(Some(reader.id()?), read_entity(reader, arena)?),
+                TAG_SPAWN_WITHOUT_ID => RawChange::Spawn(None, read_entity(reader, arena)?),
+                TAG_DESPAWN => RawChange::Despawn(reader.id()?),
+                TAG_ADD_COMPONENTS => {
+                    RawChange::AddComponents(reader.id()?, read_entity(reader, arena)?)
+                }
+                TAG_REMOVE_COMPONENTS => {
+                    let id = reader.id()?;
+                    let count = reader.count(8)?;
+                    let mut components = ArenaVec::with_capacity_in(count, arena);
+                    for _ in 0..count {
+                        components.push(reader.u64()?);
+                    }
+                    RawChange::RemoveComponents(id, components)
+                }
+                TAG_SET => RawChange::Set(reader.id()?, read_entry(reader)?),
+                tag => return Err(DiffDecodeError::InvalidTag(tag)),
+            };
+            changes.push(change);
+        }
+        if !reader.0.is_empty() {
+            return Err(DiffDecodeError::TrailingBytes(reader.0.len()));
+        }
+        Ok(Self { changes })
+    }
+
+    fn decode(self) -> Result<WorldDiff, DiffDecodeError> {
+        let mut changes = Vec::with_capacity(self.changes.len());
+        for change in self.changes {
+            changes.push(match change {
+                RawChange::Spawn(id, entries) => WorldChange::Spawn(id, decode_entity(&entries)?),
+                RawChange::Despawn(id) => WorldChange::Despawn(id),
+                RawChange::AddComponents(id, entries) => {
+                    WorldChange::AddComponents(id, decode_entity(&entries)?)
+                }
+                RawChange::RemoveComponents(id, stable_ids) => WorldChange::RemoveComponents(
+                    id,
+                    stable_ids
+                        .iter()
+                        .filter_map(|&stable_id| component(stable_id))
+                        .collect(),
+                ),
+                RawChange::Set(id, entry) => match decode_entry(&entry)? {
+                    Some(entry) => WorldChange::Set(id, entry),
+                    None => continue,
+                },
+            });
+        }
+        Ok(WorldDiff { changes })
+    }
+}
+
+fn read_entry<'a>(reader: &mut Reader<'a>) -> Result<RawEntry<'a>, DiffDecodeError> {
+    let stable_id = reader.u64()?;
+    let len = reader.varint()?;
+    let len = usize::try_from(len).map_err(|_| DiffDecodeError::UnexpectedEnd)?;
+    Ok(RawEntry {
+        stable_id,
+        value: reader.bytes(len)?,
+    })
+}
+
+fn read_entity<'a, 'b>(
+    reader: &mut Reader<'a>,
+    arena: &'b Bump,
+) -> Result<ArenaVec<'b, RawEntry<'a>>, DiffDecodeError> {
+    let count = reader.count(MIN_ENTRY_SIZE)?;
+    let mut entries = ArenaVec::with_capacity_in(count, arena);
+    for _ in 0..count {
+        entries.push(read_entry(reader)?);
+    }
+    Ok(entries)
+}
+
+fn component(stable_id: u64) -> Option<ComponentDesc> {
+    let desc = with_component_registry(|registry| registry.get_by_stable_id(stable_id));
+    if desc.is_none() {
+        log::warn!("Skipping unknown component with stable id {stable_id:x} in diff");
+    }
+    desc
+}
+
+fn decode_entry(entry: &RawEntry) -> Result<Option<ComponentEntry>, DiffDecodeError> {
+    let Some(desc) = component(entry.stable_id) else {
+        return Ok(None);
+    };
+    let Some(ser) = desc.attribute::<Serializable>().map(|ser| *ser) else {
+        log::warn!(
+            "Skipping component {} in diff, which is not serializable",
+            desc.path()
+        );
+        return Ok(None);
+    };
+    value_options()
+        .deserialize_seed(ser.deserializer(desc), entry.value)
+        .map(Some)
+        .map_err(|source| DiffDecodeError::Value {
+            path: desc.path(),
+            source,
+        })
+}
+
+fn decode_entity(entries: &[RawEntry]) -> Result<Entity, DiffDecodeError> {
+    let mut entity = Entity::new();
+    for entry in entries {
+        if let Some(entry) = decode_entry(entry)? {
+            entity.set_entry(entry);
+        }
+    }
+    Ok(entity)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn varint_roundtrip() {
+        for value in [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+            let mut buf = Vec::new();
+            write_varint(&mut buf, value).unwrap();
+            assert_eq!(Reader(&buf).varint().unwrap(), value);
+        }
+        assert!(matches!(
+            Reader(&[0xff; 11]).varint(),
+            Err(DiffDecodeError::InvalidVarint)
+        ));
+    }
+
+    #[test]
+    fn stable_ids_are_stable() {
+        assert_eq!(stable_component_id(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(stable_component_id("a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
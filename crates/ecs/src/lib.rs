@@ -33,14 +33,17 @@ mod component_entry;
 mod component_registry;
 mod component_ser;
 mod component_traits;
+pub mod diff_encoding;
 mod entity;
 mod events;
 mod index;
 mod location;
+mod parallel;
 mod primitive_component;
 mod query;
 mod serialization;
 mod stream;
+mod world_hash;
 pub use ambient_project_rt::message_serde::*;
 pub use archetype::*;
 pub use attributes::*;
@@ -52,10 +55,12 @@ pub use entity::*;
 pub use events::*;
 pub use index::*;
 pub use location::*;
+pub use parallel::*;
 pub use primitive_component::*;
 pub use query::*;
 pub use serialization::*;
 pub use stream::*;
+pub use world_hash::*;
 
 pub struct DebugWorldArchetypes<'a> {
     world: &'a World,
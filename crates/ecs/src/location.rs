@@ -1,16 +1,42 @@
 use std::{
-    fmt::{self, Debug}, hash::{BuildHasher, Hasher}, str::FromStr
+    fmt::{self, Debug}, hash::{BuildHasher, Hasher}, str::FromStr, sync::atomic::{AtomicBool, Ordering}
 };
 
 use data_encoding::BASE64URL_NOPAD;
+use parking_lot::{const_mutex, Mutex};
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
 use serde::{
     de::{self, Visitor}, Deserialize, Deserializer, Serialize, Serializer
 };
 
+static DETERMINISTIC: AtomicBool = AtomicBool::new(false);
+static DETERMINISTIC_IDS: Mutex<Option<Pcg64>> = const_mutex(None);
+
+/// Turns the deterministic mode of this process on with `seed`, or off with `None`.
+///
+/// In the deterministic mode, [EntityId::new] draws the ids from a generator seeded with `seed`, so that a simulation
+/// that spawns its entities in the same order gets the same ids every time it is run, on every platform. Systems can
+/// check [is_deterministic] to trade speed for reproducibility.
+pub fn set_deterministic(seed: Option<u64>) {
+    *DETERMINISTIC_IDS.lock() = seed.map(Pcg64::seed_from_u64);
+    DETERMINISTIC.store(seed.is_some(), Ordering::SeqCst);
+}
+/// Whether the deterministic mode is on; see [set_deterministic]
+pub fn is_deterministic() -> bool {
+    DETERMINISTIC.load(Ordering::Relaxed)
+}
+
 #[derive(Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord)]
 pub struct EntityId(pub u128);
 impl EntityId {
     pub fn new() -> Self {
+        if is_deterministic() {
+            if let Some(rng) = DETERMINISTIC_IDS.lock().as_mut() {
+                // Skips the ids that are reserved for the null and resources entities
+                return Self(rng.gen_range(2..=u128::MAX));
+            }
+        }
         Self(rand::random())
     }
     pub fn null() -> Self {
@@ -0,0 +1,167 @@
+use std::borrow::Cow;
+
+use itertools::Itertools;
+
+use crate::{Commands, ComponentDesc, ComponentSet, FrameEvent, System, World};
+
+/// The components a [ParallelSystem] reads and writes. Adding, removing, spawning or despawning entities with a component
+/// counts as writing it, and so does setting a resource.
+#[derive(Debug, Clone)]
+pub struct SystemAccess {
+    reads: ComponentSet,
+    writes: ComponentSet,
+    exclusive: bool,
+}
+impl SystemAccess {
+    pub fn new() -> Self {
+        Self {
+            reads: ComponentSet::new(),
+            writes: ComponentSet::new(),
+            exclusive: false,
+        }
+    }
+    /// Access to the whole world, which conflicts with every other system
+    pub fn exclusive() -> Self {
+        Self {
+            exclusive: true,
+            ..Self::new()
+        }
+    }
+    pub fn read(mut self, component: impl Into<ComponentDesc>) -> Self {
+        self.reads.insert(component.into());
+        self
+    }
+    pub fn write(mut self, component: impl Into<ComponentDesc>) -> Self {
+        self.writes.insert(component.into());
+        self
+    }
+    /// Two systems conflict if one of them writes a component that the other reads or writes, in which case they
+    /// can't run at the same time
+    pub fn conflicts_with(&self, other: &SystemAccess) -> bool {
+        self.exclusive
+            || other.exclusive
+            || !self.writes.is_disjoint(&other.reads)
+            || !self.writes.is_disjoint(&other.writes)
+            || !other.writes.is_disjoint(&self.reads)
+    }
+}
+impl Default for SystemAccess {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A system that only reads the world, and returns the changes it wants to make as [Commands]. This lets it run on the
+/// compute thread pool at the same time as the systems it doesn't conflict with.
+pub trait ParallelSystem<E = FrameEvent>: Send + Sync + std::fmt::Debug {
+    fn access(&self) -> &SystemAccess;
+    fn run(&mut self, world: &World, event: &E) -> Commands;
+}
+
+#[allow(clippy::type_complexity)]
+pub struct ParallelFnSystem<E = FrameEvent> {
+    access: SystemAccess,
+    func: Box<dyn FnMut(&World, &E) -> Commands + Send + Sync>,
+}
+impl<E> ParallelFnSystem<E> {
+    pub fn new<F>(access: SystemAccess, func: F) -> Self
+    where
+        F: FnMut(&World, &E) -> Commands + Send + Sync + 'static,
+    {
+        Self {
+            access,
+            func: Box::new(func),
+        }
+    }
+}
+impl<E> ParallelSystem<E> for ParallelFnSystem<E> {
+    fn access(&self) -> &SystemAccess {
+        &self.access
+    }
+    fn run(&mut self, world: &World, event: &E) -> Commands {
+        (self.func)(world, event)
+    }
+}
+impl<E> std::fmt::Debug for ParallelFnSystem<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ParallelFnSystem")
+    }
+}
+
+pub type DynParallelSystem<E = FrameEvent> = Box<dyn ParallelSystem<E>>;
+
+/// Runs its systems as a task graph: each system waits for the systems before it that it conflicts with, and runs at
+/// the same time as the others. The commands of the systems are applied in the order the systems were added, so the
+/// result is the same as running them one after the other.
+pub struct ParallelSystemGroup<E = FrameEvent> {
+    label: Cow<'static, str>,
+    systems: Vec<DynParallelSystem<E>>,
+    stages: Vec<Vec<usize>>,
+}
+impl<E> ParallelSystemGroup<E> {
+    pub fn new(label: &'static str, systems: Vec<DynParallelSystem<E>>) -> Self {
+        let stages = schedule(&systems);
+        Self {
+            label: Cow::Borrowed(label),
+            systems,
+            stages,
+        }
+    }
+    pub fn add(&mut self, system: DynParallelSystem<E>) -> &mut Self {
+        self.systems.push(system);
+        self.stages = schedule(&self.systems);
+        self
+    }
+    /// The indices of the systems that run at the same time, in the order they run
+    pub fn stages(&self) -> &[Vec<usize>] {
+        &self.stages
+    }
+}
+
+/// Puts every system in the first stage after the last one with a system it conflicts with
+fn schedule<E>(systems: &[DynParallelSystem<E>]) -> Vec<Vec<usize>> {
+    let mut stages: Vec<Vec<usize>> = Vec::new();
+    for (i, system) in systems.iter().enumerate() {
+        let stage = stages
+            .iter()
+            .rposition(|stage| {
+                stage
+                    .iter()
+                    .any(|&j| systems[j].access().conflicts_with(system.access()))
+            })
+            .map_or(0, |stage| stage + 1);
+        match stages.get_mut(stage) {
+            Some(stage) => stage.push(i),
+            None => stages.push(vec![i]),
+        }
+    }
+    stages
+}
+
+impl<E: Sync> System<E> for ParallelSystemGroup<E> {
+    fn run(&mut self, world: &mut World, event: &E) {
+        ambient_profiling::scope!("ParallelSystemGroup::run", &self.label);
+        let _span = tracing::debug_span!("ParallelSystemGroup::run", "{}", &self.label).entered();
+        for stage in &self.stages {
+            let mut systems = self
+                .systems
+                .iter_mut()
+                .enumerate()
+                .filter(|(i, _)| stage.contains(i))
+                .map(|(_, system)| system)
+                .collect_vec();
+            let shared: &World = world;
+            let commands =
+                ambient_sys::task::par_map(&mut systems, |system| system.run(shared, event));
+            for mut commands in commands {
+                commands.soft_apply(world);
+            }
+        }
+    }
+}
+
+impl<E> std::fmt::Debug for ParallelSystemGroup<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ParallelSystemGroup({}, {:?})", self.label, self.stages)
+    }
+}
@@ -212,10 +212,29 @@ impl WorldStreamFilter {
             component_filter,
         }
     }
+    /// Excludes the entities with `component` from the stream
+    pub fn excl(self, component: impl Into<ComponentDesc>) -> Self {
+        Self {
+            arch_filter: self.arch_filter.excl(component),
+            component_filter: self.component_filter,
+        }
+    }
+    /// Only includes the entities with `component` in the stream
+    pub fn incl(self, component: impl Into<ComponentDesc>) -> Self {
+        Self {
+            arch_filter: self.arch_filter.incl(component),
+            component_filter: self.component_filter,
+        }
+    }
     pub fn initial_diff(&self, world: &World) -> WorldDiff {
+        self.spawn_diff(world, self.all_entities(world))
+    }
+    /// Spawns the entities with `ids` that pass the filter, with their components that pass it
+    pub fn spawn_diff(&self, world: &World, ids: impl IntoIterator<Item = EntityId>) -> WorldDiff {
         WorldDiff {
-            changes: self
-                .all_entities(world)
+            changes: ids
+                .into_iter()
+                .filter(|&id| self.arch_filter.matches_entity(world, id))
                 .map(|id| {
                     WorldChange::Spawn(Some(id), self.read_entity_components(world, id).into())
                 })
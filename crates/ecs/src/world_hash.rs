@@ -0,0 +1,123 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    hash::Hasher,
+};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{Serializable, World};
+
+/// A hash of the serializable components of a [World], and of each of them, which is stable across runs, builds and
+/// platforms, so that two simulations can be checked for being in the same state.
+///
+/// The resources are left out, as they hold the state of the process rather than that of the simulation.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorldHash {
+    pub total: u64,
+    /// The hash of every component, by its path
+    pub components: BTreeMap<String, u64>,
+}
+impl WorldHash {
+    pub fn new(world: &World) -> Self {
+        let mut hashers = HashMap::<String, StableHasher>::new();
+        let entities = world.entities().into_iter().filter(|(id, _)| !id.is_resources()).sorted_by_key(|(id, _)| *id);
+        for (id, entity) in entities {
+            for entry in entity.iter() {
+                let desc = entry.desc();
+                let Some(ser) = desc.attribute::<Serializable>() else {
+                    continue;
+                };
+                let Ok(value) = bincode::serialize(ser.serialize(entry)) else {
+                    continue;
+                };
+                let hasher = hashers.entry(desc.path()).or_default();
+                hasher.write(&id.0.to_le_bytes());
+                hasher.write(&(value.len() as u64).to_le_bytes());
+                hasher.write(&value);
+            }
+        }
+
+        let components: BTreeMap<_, _> = hashers.into_iter().map(|(path, hasher)| (path, hasher.finish())).collect();
+        let mut total = StableHasher::default();
+        for (path, hash) in &components {
+            total.write(path.as_bytes());
+            total.write(&hash.to_le_bytes());
+        }
+        Self { total: total.finish(), components }
+    }
+
+    /// The paths of the components that hash differently in `other`, including those that only one of them has
+    pub fn diverging_components(&self, other: &Self) -> Vec<String> {
+        if self.total == other.total {
+            return Vec::new();
+        }
+        self.components
+            .keys()
+            .chain(other.components.keys())
+            .unique()
+            .filter(|path| self.components.get(*path) != other.components.get(*path))
+            .sorted()
+            .cloned()
+            .collect()
+    }
+}
+
+/// The [WorldHash] of a simulation after each of its ticks
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorldHashTrace {
+    pub ticks: Vec<WorldHash>,
+}
+impl WorldHashTrace {
+    pub fn push(&mut self, world: &World) {
+        self.ticks.push(WorldHash::new(world));
+    }
+
+    /// The first tick after which `other` isn't in the same state as this, if any
+    pub fn first_divergence(&self, other: &Self) -> Option<Divergence> {
+        let diverged = self.ticks.iter().zip(&other.ticks).enumerate().find(|(_, (a, b))| a.total != b.total);
+        match diverged {
+            Some((tick, (a, b))) => Some(Divergence { tick, components: a.diverging_components(b) }),
+            None if self.ticks.len() != other.ticks.len() => {
+                Some(Divergence { tick: self.ticks.len().min(other.ticks.len()), components: Vec::new() })
+            }
+            None => None,
+        }
+    }
+}
+
+/// Where two [WorldHashTrace]s stop matching
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    pub tick: usize,
+    /// The components that differ after the tick; empty if one of the traces ends before it
+    pub components: Vec<String>,
+}
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.components.is_empty() {
+            write!(f, "One of the traces ends at tick {}", self.tick)
+        } else {
+            write!(f, "Diverged at tick {}, in {}", self.tick, self.components.join(", "))
+        }
+    }
+}
+
+/// 64-bit FNV-1a, which, unlike the hashers of std, is the same in every build
+struct StableHasher(u64);
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+}
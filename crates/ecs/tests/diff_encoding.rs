@@ -0,0 +1,148 @@
+use ambient_ecs::{
+    components, diff_encoding::DiffDecodeError, ComponentEntry, Entity, EntityId, Serializable,
+    WorldChange, WorldDiff,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+components!("test", {
+    @[Serializable]
+    a: f32,
+    @[Serializable]
+    b: String,
+    @[Serializable]
+    c: Vec<u32>,
+    @[Serializable]
+    d: (),
+});
+
+fn init() {
+    init_components();
+}
+
+fn random_entity(rng: &mut StdRng) -> Entity {
+    let mut entity = Entity::new();
+    if rng.gen() {
+        entity.set(a(), rng.gen());
+    }
+    if rng.gen() {
+        entity.set(b(), "x".repeat(rng.gen_range(0..300)));
+    }
+    if rng.gen() {
+        entity.set(c(), (0..rng.gen_range(0..20)).map(|_| rng.gen()).collect());
+    }
+    if rng.gen() {
+        entity.set(d(), ());
+    }
+    entity
+}
+
+fn random_diff(rng: &mut StdRng) -> WorldDiff {
+    let changes = (0..rng.gen_range(0..30))
+        .map(|_| {
+            let id = EntityId(rng.gen());
+            match rng.gen_range(0..6) {
+                0 => WorldChange::Spawn(Some(id), random_entity(rng)),
+                1 => WorldChange::Spawn(None, random_entity(rng)),
+                2 => WorldChange::Despawn(id),
+                3 => WorldChange::AddComponents(id, random_entity(rng)),
+                4 => WorldChange::RemoveComponents(id, vec![a().desc(), c().desc()]),
+                _ => WorldChange::Set(
+                    id,
+                    random_entity(rng)
+                        .iter()
+                        .next()
+                        .cloned()
+                        .unwrap_or_else(|| ComponentEntry::new(d(), ())),
+                ),
+            }
+        })
+        .collect();
+    WorldDiff { changes }
+}
+
+#[test]
+fn roundtrip() {
+    init();
+    let id = EntityId(7);
+    let diff = WorldDiff::new()
+        .add_component(id, b(), "hello".to_string())
+        .set(id, a(), 2.5)
+        .remove_component(id, c().desc());
+    let decoded = WorldDiff::decode(&diff.encode_to_vec()).unwrap();
+    assert_eq!(decoded.changes.len(), 3);
+    match &decoded.changes[0] {
+        WorldChange::AddComponents(decoded_id, entity) => {
+            assert_eq!(*decoded_id, id);
+            assert_eq!(entity.get_ref(b()).unwrap(), "hello");
+        }
+        change => panic!("Unexpected change {change:?}"),
+    }
+    match &decoded.changes[1] {
+        WorldChange::Set(_, entry) => assert_eq!(*entry.downcast_ref::<f32>(), 2.5),
+        change => panic!("Unexpected change {change:?}"),
+    }
+    match &decoded.changes[2] {
+        WorldChange::RemoveComponents(_, components) => assert_eq!(components, &[c().desc()]),
+        change => panic!("Unexpected change {change:?}"),
+    }
+}
+
+#[test]
+fn smaller_than_serde() {
+    init();
+    let mut rng = StdRng::seed_from_u64(1);
+    let diff = WorldDiff {
+        changes: (0..100)
+            .map(|_| WorldChange::Set(EntityId(rng.gen()), ComponentEntry::new(a(), rng.gen())))
+            .collect(),
+    };
+    assert!(diff.encode_to_vec().len() < bincode::serialize(&diff).unwrap().len());
+}
+
+#[test]
+fn fuzz_roundtrip() {
+    init();
+    let mut rng = StdRng::seed_from_u64(0);
+    for _ in 0..500 {
+        let diff = random_diff(&mut rng);
+        let encoded = diff.encode_to_vec();
+        let decoded = WorldDiff::decode(&encoded).unwrap();
+        assert_eq!(decoded.encode_to_vec(), encoded);
+    }
+}
+
+#[test]
+fn fuzz_corrupt_input() {
+    init();
+    let mut rng = StdRng::seed_from_u64(0);
+    for _ in 0..500 {
+        let mut encoded = random_diff(&mut rng).encode_to_vec();
+        match rng.gen_range(0..3) {
+            0 => encoded.truncate(rng.gen_range(0..encoded.len())),
+            1 => {
+                for _ in 0..rng.gen_range(1..8) {
+                    let i = rng.gen_range(0..encoded.len());
+                    encoded[i] = rng.gen();
+                }
+            }
+            _ => encoded = (0..rng.gen_range(0..256)).map(|_| rng.gen()).collect(),
+        }
+        // Anything is fine, as long as it doesn't panic or allocate unbounded memory
+        let _ = WorldDiff::decode(&encoded);
+    }
+}
+
+#[test]
+fn rejects_truncated_and_trailing_bytes() {
+    init();
+    let mut encoded = WorldDiff::new().set(EntityId(1), a(), 1.).encode_to_vec();
+    assert!(matches!(
+        WorldDiff::decode(&encoded[..encoded.len() - 1]),
+        Err(DiffDecodeError::UnexpectedEnd)
+    ));
+    encoded.push(0);
+    assert!(matches!(
+        WorldDiff::decode(&encoded),
+        Err(DiffDecodeError::TrailingBytes(1))
+    ));
+}
@@ -0,0 +1,65 @@
+use ambient_ecs::{
+    components, query, Commands, DynParallelSystem, Entity, FrameEvent, ParallelFnSystem,
+    ParallelSystemGroup, System, SystemAccess, World,
+};
+
+components!("test", {
+    a: f32,
+    b: f32,
+    c: f32,
+});
+
+fn init() {
+    init_components();
+}
+
+fn copy_system(
+    from: ambient_ecs::Component<f32>,
+    to: ambient_ecs::Component<f32>,
+    scale: f32,
+) -> DynParallelSystem {
+    Box::new(ParallelFnSystem::new(
+        SystemAccess::new().read(from).write(to),
+        move |world, _| {
+            let mut commands = Commands::new();
+            let q = query(from);
+            for (id, value) in q.iter(world, None) {
+                commands.set(id, to, *value * scale);
+            }
+            commands
+        },
+    ))
+}
+
+#[test]
+fn non_conflicting_systems_share_a_stage() {
+    init();
+    let group = ParallelSystemGroup::<FrameEvent>::new(
+        "test",
+        vec![
+            copy_system(a(), b(), 2.),
+            copy_system(a(), c(), 3.),
+            copy_system(b(), a(), 10.),
+        ],
+    );
+    assert_eq!(group.stages(), &[vec![0, 1], vec![2]]);
+}
+
+#[test]
+fn parallel_systems_run_in_order() {
+    init();
+    let mut world = World::new("parallel_systems_run_in_order");
+    let id = world.spawn(Entity::new().with(a(), 1.).with(b(), 0.).with(c(), 0.));
+    let mut group = ParallelSystemGroup::new(
+        "test",
+        vec![
+            copy_system(a(), b(), 2.),
+            copy_system(a(), c(), 3.),
+            copy_system(b(), a(), 10.),
+        ],
+    );
+    group.run(&mut world, &FrameEvent);
+    assert_eq!(world.get(id, b()).unwrap(), 2.);
+    assert_eq!(world.get(id, c()).unwrap(), 3.);
+    assert_eq!(world.get(id, a()).unwrap(), 20.);
+}
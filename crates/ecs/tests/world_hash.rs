@@ -0,0 +1,89 @@
+use std::sync::Mutex;
+
+use ambient_ecs::{components, set_deterministic, Entity, EntityId, Serializable, World, WorldHash, WorldHashTrace};
+use glam::Vec3;
+
+components!("test", {
+    @[Serializable]
+    position: Vec3,
+    @[Serializable]
+    health: f32,
+    scratch: Vec<u8>,
+});
+
+/// The deterministic mode is global, so the tests that spawn entities take turns
+static DETERMINISTIC_MODE: Mutex<()> = Mutex::new(());
+
+fn init() {
+    init_components();
+}
+
+fn simulate(ticks: usize, faulty_tick: Option<usize>) -> WorldHashTrace {
+    set_deterministic(Some(7));
+    let mut world = World::new("simulate");
+    let mut trace = WorldHashTrace::default();
+    let a = world.spawn(Entity::new().with(position(), Vec3::ZERO).with(health(), 100.));
+    let b = world.spawn(Entity::new().with(position(), Vec3::X).with(health(), 50.).with(scratch(), vec![]));
+    for tick in 0..ticks {
+        world.set(a, position(), Vec3::Z * tick as f32).unwrap();
+        if faulty_tick == Some(tick) {
+            world.set(b, health(), 49.).unwrap();
+        }
+        // Not serializable, so it doesn't count
+        world.set(b, scratch(), vec![tick as u8]).unwrap();
+        trace.push(&world);
+    }
+    set_deterministic(None);
+    trace
+}
+
+#[test]
+fn same_simulations_match() {
+    init();
+    let _guard = DETERMINISTIC_MODE.lock().unwrap();
+    assert_eq!(simulate(10, None).first_divergence(&simulate(10, None)), None);
+}
+
+#[test]
+fn reports_first_divergent_tick_and_component() {
+    init();
+    let _guard = DETERMINISTIC_MODE.lock().unwrap();
+    let divergence = simulate(10, None).first_divergence(&simulate(10, Some(4))).unwrap();
+    assert_eq!(divergence.tick, 4);
+    assert_eq!(divergence.components, vec![health().path()]);
+
+    let divergence = simulate(10, None).first_divergence(&simulate(6, None)).unwrap();
+    assert_eq!(divergence.tick, 6);
+    assert!(divergence.components.is_empty());
+}
+
+#[test]
+fn hash_depends_on_values_and_ids() {
+    init();
+    let _guard = DETERMINISTIC_MODE.lock().unwrap();
+    let mut world = World::new("hash_depends_on_values_and_ids");
+    let empty = WorldHash::new(&world);
+    let id = world.spawn(Entity::new().with(health(), 1.));
+    let spawned = WorldHash::new(&world);
+    assert_ne!(empty, spawned);
+    assert_eq!(spawned.diverging_components(&empty), vec![health().path()]);
+
+    let mut other = World::new("hash_depends_on_values_and_ids");
+    other.spawn(Entity::new().with(health(), 1.));
+    assert_ne!(WorldHash::new(&other), spawned);
+
+    world.set(id, health(), 2.).unwrap();
+    assert_ne!(WorldHash::new(&world), spawned);
+}
+
+#[test]
+fn deterministic_ids_repeat_with_the_seed() {
+    let _guard = DETERMINISTIC_MODE.lock().unwrap();
+    set_deterministic(Some(42));
+    let first = (0..10).map(|_| EntityId::new()).collect::<Vec<_>>();
+    set_deterministic(Some(42));
+    let second = (0..10).map(|_| EntityId::new()).collect::<Vec<_>>();
+    set_deterministic(None);
+    assert_eq!(first, second);
+    assert!(first.iter().all(|id| !id.is_null() && !id.is_resources()));
+}
@@ -705,6 +705,15 @@ pub fn register_intents(reg: &mut IntentRegistry) {
         use_old_state,
     );
 
+    reg.register_targets(intent_translate(), |intent| intent.targets.clone());
+    reg.register_targets(intent_place_ray(), |intent| intent.targets.clone());
+    reg.register_targets(intent_set_transform(), |intent| intent.entities.clone());
+    reg.register_targets(intent_reset_terrain_offset(), |(entities, _)| {
+        entities.clone()
+    });
+    reg.register_targets(intent_delete(), |entities| entities.clone());
+    reg.register_targets(intent_component_change(), |(id, _)| vec![*id]);
+
     ambient_terrain::intents::register_intents(reg);
     // Box::new(common_intent_systems()),
     // ],
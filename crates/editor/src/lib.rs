@@ -1,4 +1,5 @@
-use ambient_ecs::{components, EntityId};
+use ambient_ecs::{components, EntityId, Networked};
+use glam::Vec3;
 use std::iter::Cloned;
 
 #[macro_use]
@@ -8,8 +9,13 @@ pub mod rpc;
 pub mod ui;
 
 components!("editor", {
+    /// What the user has selected; set on their player entity so the others in the session can see it
+    @[Networked]
     selection: Selection,
     prev_selection: Selection,
+    /// Where the user is pointing in the scene; set on their player entity so the others in the session can see it
+    @[Networked]
+    editor_cursor: Vec3,
 });
 
 pub fn init_all_components() {
@@ -0,0 +1,395 @@
+//! Manipulators drawn over the selection, which can be dragged with the mouse to translate, rotate or scale it.
+//!
+//! Every drag is a single [EditorAction], so the edit is applied by the server as it happens, is seen live by every
+//! other editor connected to the session, and is undone as a whole.
+
+use std::{f32::consts::PI, str::FromStr, sync::Arc};
+
+use ambient_core::{
+    runtime,
+    transform::translation,
+    window::{cursor_position, screen_to_clip_space},
+};
+use ambient_ecs::{generated::messages, EntityId};
+use ambient_element::{Element, ElementComponent, ElementComponentExt, Hooks};
+use ambient_gizmos::{gizmos, GizmoPrimitive, GizmoScope};
+use ambient_network::{client::GameClient, client_game_state::ClientGameState};
+use ambient_shared_types::{MouseButton, VirtualKeyCode};
+use ambient_std::{
+    line_hash,
+    shapes::{Plane, Ray, RayIntersectable},
+};
+use ambient_ui_native::{UIExt, WindowSized};
+use glam::{Mat4, Quat, Vec2, Vec3};
+use itertools::Itertools;
+
+use super::{
+    transform::{get_world_transforms, TRANSFORM_THROTTLE},
+    EditorAction, GizmoMode,
+};
+use crate::{
+    intents::{intent_set_transform, IntentTransform, TerrainOffset},
+    ui::EditorPrefs,
+};
+
+/// The size of the manipulators, relative to their distance to the camera, so they keep the same size on screen
+const GIZMO_SCREEN_SIZE: f32 = 0.15;
+/// The sizes of the handles, relative to the size of the manipulator
+const LINE_RADIUS: f32 = 0.015;
+const TIP_RADIUS: f32 = 0.06;
+const PLANE_HANDLE_OFFSET: f32 = 0.3;
+const PLANE_HANDLE_SIZE: f32 = 0.08;
+/// How close the mouse has to pass to a handle to grab it, relative to the size of the manipulator
+const PICK_RADIUS: f32 = 0.06;
+const RING_SEGMENTS: usize = 48;
+/// The steps that rotations and scales are snapped to when snapping is on; translations snap to the grid
+const ROTATION_SNAP: f32 = PI / 12.;
+const SCALE_SNAP: f32 = 0.1;
+const MIN_SCALE: f32 = 0.01;
+
+const AXIS_COLORS: [Vec3; 3] = [Vec3::new(0.9, 0.2, 0.2), Vec3::new(0.2, 0.8, 0.2), Vec3::new(0.2, 0.4, 1.)];
+const CENTER_COLOR: Vec3 = Vec3::ONE;
+const ACTIVE_COLOR: Vec3 = Vec3::new(1., 0.8, 0.1);
+
+fn gizmo_scope() -> u64 {
+    line_hash!()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Handle {
+    /// Moves or scales along an axis
+    Axis(usize),
+    /// Moves along the plane perpendicular to an axis
+    Plane(usize),
+    /// Rotates around an axis
+    Ring(usize),
+    /// Scales uniformly
+    Center,
+}
+
+/// Where the manipulator is drawn, and the space it works in
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    origin: Vec3,
+    rotation: Quat,
+    size: f32,
+}
+
+impl Frame {
+    /// The manipulator sits at the midpoint of the targets, and is aligned to the last one of them unless the editor
+    /// works in world space
+    fn from_state(state: &ClientGameState, targets: &[EntityId], prefs: EditorPrefs) -> Option<Self> {
+        let transforms = get_world_transforms(&state.world, targets).ok()?;
+        let (_, rotation, _) = transforms.last()?.to_scale_rotation_translation();
+        let origin = transforms.iter().map(|v| v.transform_point3(Vec3::ZERO)).sum::<Vec3>() / transforms.len() as f32;
+        let camera_pos = state.view()?.inverse().transform_point3(Vec3::ZERO);
+
+        Some(Self {
+            origin,
+            rotation: if prefs.use_global_coordinates { Quat::IDENTITY } else { rotation },
+            size: camera_pos.distance(origin).max(1.) * GIZMO_SCREEN_SIZE,
+        })
+    }
+
+    fn axis(&self, index: usize) -> Vec3 {
+        self.rotation * Vec3::AXES[index]
+    }
+
+    /// The two axes that span the plane perpendicular to `index`
+    fn tangents(&self, index: usize) -> (Vec3, Vec3) {
+        (self.axis((index + 1) % 3), self.axis((index + 2) % 3))
+    }
+
+    fn plane_handle(&self, index: usize) -> Vec3 {
+        let (u, v) = self.tangents(index);
+        self.origin + (u + v) * self.size * PLANE_HANDLE_OFFSET
+    }
+
+    fn ring(&self, index: usize) -> impl Iterator<Item = (Vec3, Vec3)> + '_ {
+        let (u, v) = self.tangents(index);
+        (0..=RING_SEGMENTS)
+            .map(move |i| {
+                let angle = i as f32 / RING_SEGMENTS as f32 * 2. * PI;
+                self.origin + (u * angle.cos() + v * angle.sin()) * self.size
+            })
+            .tuple_windows()
+    }
+
+    /// The handles of `mode`
+    fn handles(mode: GizmoMode) -> Vec<Handle> {
+        match mode {
+            GizmoMode::Translate => (0..3).map(Handle::Plane).chain((0..3).map(Handle::Axis)).collect(),
+            GizmoMode::Rotate => (0..3).map(Handle::Ring).collect(),
+            GizmoMode::Scale => (0..3).map(Handle::Axis).chain([Handle::Center]).collect(),
+        }
+    }
+
+    /// How far the picking ray passes from `handle`, or `None` if it misses it
+    fn distance(&self, handle: Handle, ray: Ray) -> Option<f32> {
+        match handle {
+            Handle::Axis(i) => ray_segment_distance(ray, self.origin, self.origin + self.axis(i) * self.size),
+            Handle::Plane(i) => {
+                let hit = intersect_plane(ray, self.axis(i), self.origin)? - self.plane_handle(i);
+                let (u, v) = self.tangents(i);
+                let extent = self.size * PLANE_HANDLE_SIZE;
+                (hit.dot(u).abs() <= extent && hit.dot(v).abs() <= extent).then_some(0.)
+            }
+            Handle::Ring(i) => self.ring(i).filter_map(|(a, b)| ray_segment_distance(ray, a, b)).min_by(f32::total_cmp),
+            Handle::Center => ray_segment_distance(ray, self.origin, self.origin),
+        }
+    }
+
+    /// The handle that the picking ray passes closest to, if it is close enough to any
+    fn pick(&self, mode: GizmoMode, ray: Ray) -> Option<Handle> {
+        Self::handles(mode)
+            .into_iter()
+            .filter_map(|handle| Some((handle, self.distance(handle, ray)?)))
+            .filter(|(_, distance)| *distance <= self.size * PICK_RADIUS)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(handle, _)| handle)
+    }
+
+    fn draw(&self, scope: &mut GizmoScope, mode: GizmoMode, active: Option<Handle>) {
+        let color = |handle: Handle, color: Vec3| if Some(handle) == active { ACTIVE_COLOR } else { color };
+        let radius = self.size * LINE_RADIUS;
+
+        for handle in Self::handles(mode) {
+            match handle {
+                Handle::Axis(i) => {
+                    let color = color(handle, AXIS_COLORS[i]);
+                    let tip = self.origin + self.axis(i) * self.size;
+                    scope.draw(GizmoPrimitive::line(self.origin, tip, radius).with_color(color));
+                    if mode == GizmoMode::Scale {
+                        scope.draw(GizmoPrimitive::sphere(tip, self.size * TIP_RADIUS).with_color(color));
+                    }
+                }
+                Handle::Plane(i) => {
+                    let extents = Vec2::splat(self.size * PLANE_HANDLE_SIZE);
+                    scope.draw(GizmoPrimitive::rect(self.plane_handle(i), extents, 0., self.axis(i)).with_color(color(handle, AXIS_COLORS[i])));
+                }
+                Handle::Ring(i) => {
+                    scope.draw(self.ring(i).map(|(a, b)| GizmoPrimitive::line(a, b, radius).with_color(color(handle, AXIS_COLORS[i]))));
+                }
+                Handle::Center => {
+                    scope.draw(GizmoPrimitive::sphere(self.origin, self.size * TIP_RADIUS).with_color(color(handle, CENTER_COLOR)));
+                }
+            }
+        }
+    }
+}
+
+/// The distance between the ray and the segment from `a` to `b` where they pass closest, or `None` if that is
+/// behind the ray
+fn ray_segment_distance(ray: Ray, a: Vec3, b: Vec3) -> Option<f32> {
+    let u = b - a;
+    let w = ray.origin - a;
+    let du = ray.dir.dot(u);
+    let denom = u.length_squared() - du * du;
+    let s = if denom > f32::EPSILON { ((u.dot(w) - du * ray.dir.dot(w)) / denom).clamp(0., 1.) } else { 0. };
+
+    let point = a + u * s;
+    let t = (point - ray.origin).dot(ray.dir);
+    (t > 0.).then(|| (ray.origin + ray.dir * t).distance(point))
+}
+
+fn intersect_plane(ray: Ray, normal: Vec3, point: Vec3) -> Option<Vec3> {
+    let plane = Plane::from_normal_and_point(normal, point)?;
+    Some(ray.origin + ray.dir * plane.ray_intersect(ray)?)
+}
+
+fn snap_step(value: f32, step: f32, snap: bool) -> f32 {
+    if snap {
+        (value / step).round() * step
+    } else {
+        value
+    }
+}
+
+/// A handle being dragged
+#[derive(Debug)]
+struct Drag {
+    handle: Handle,
+    /// The manipulator and the transforms of the targets when the drag started
+    frame: Frame,
+    transforms: Vec<Mat4>,
+    /// The plane that the mouse is moved along, and where it first grabbed the handle
+    normal: Vec3,
+    start: Vec3,
+    action: EditorAction<IntentTransform>,
+}
+
+impl Drag {
+    fn new(handle: Handle, frame: Frame, transforms: Vec<Mat4>, ray: Ray, action: EditorAction<IntentTransform>) -> Option<Self> {
+        let normal = match handle {
+            // Face the camera as much as possible while containing the axis
+            Handle::Axis(i) => {
+                let axis = frame.axis(i);
+                axis.cross(ray.dir).cross(axis).try_normalize().unwrap_or(ray.dir)
+            }
+            Handle::Plane(i) | Handle::Ring(i) => frame.axis(i),
+            Handle::Center => ray.dir,
+        };
+        let start = intersect_plane(ray, normal, frame.origin)?;
+        Some(Self { handle, frame, transforms, normal, start, action })
+    }
+
+    /// The transform that the drag to `hit` applies on top of the initial transforms
+    fn transform(&self, hit: Vec3, mode: GizmoMode, prefs: EditorPrefs) -> Mat4 {
+        let Frame { origin, rotation, size } = self.frame;
+        let snap = prefs.snap.is_some();
+        let delta = hit - self.start;
+        // Scales and rotates around the manipulator, in its space
+        let around_origin = |transform: Mat4| {
+            Mat4::from_translation(origin)
+                * Mat4::from_quat(rotation)
+                * transform
+                * Mat4::from_quat(rotation.inverse())
+                * Mat4::from_translation(-origin)
+        };
+
+        match (mode, self.handle) {
+            (GizmoMode::Translate, Handle::Axis(i) | Handle::Plane(i)) => {
+                let mut local = rotation.inverse() * delta;
+                if matches!(self.handle, Handle::Axis(_)) {
+                    local = Vec3::AXES[i] * local[i];
+                } else {
+                    local[i] = 0.;
+                }
+                Mat4::from_translation(rotation * prefs.snap(local))
+            }
+            (GizmoMode::Rotate, Handle::Ring(i)) => {
+                let axis = self.frame.axis(i);
+                let from = (self.start - origin).reject_from(axis);
+                let to = (hit - origin).reject_from(axis);
+                let angle = from.cross(to).dot(axis).atan2(from.dot(to));
+                around_origin(Mat4::from_axis_angle(Vec3::AXES[i], snap_step(angle, ROTATION_SNAP, snap)))
+            }
+            (GizmoMode::Scale, Handle::Axis(i)) => {
+                let factor = 1. + delta.dot(self.frame.axis(i)) / size;
+                let mut scale = Vec3::ONE;
+                scale[i] = snap_step(factor, SCALE_SNAP, snap).max(MIN_SCALE);
+                around_origin(Mat4::from_scale(scale))
+            }
+            (GizmoMode::Scale, Handle::Center) => {
+                let factor = 1. + (hit.distance(origin) - self.start.distance(origin)) / size;
+                around_origin(Mat4::from_scale(Vec3::splat(snap_step(factor, SCALE_SNAP, snap).max(MIN_SCALE))))
+            }
+            _ => Mat4::IDENTITY,
+        }
+    }
+
+    fn update(&mut self, ray: Ray, mode: GizmoMode, prefs: EditorPrefs, targets: &[EntityId]) {
+        let Some(hit) = intersect_plane(ray, self.normal, self.frame.origin) else {
+            return;
+        };
+        let transform = self.transform(hit, mode, prefs);
+        self.action.push_intent(IntentTransform {
+            entities: targets.to_vec(),
+            transforms: self.transforms.iter().map(|&v| transform * v).collect(),
+            terrain_offset: TerrainOffset::Update,
+        });
+    }
+}
+
+/// Draws the manipulator of `mode` over the targets, and lets its handles be dragged. The drag is committed when the
+/// mouse is released, and reverted with escape.
+#[derive(Debug, Clone)]
+pub(super) struct TransformGizmo {
+    pub targets: Arc<[EntityId]>,
+    pub mode: GizmoMode,
+}
+
+impl ElementComponent for TransformGizmo {
+    fn render(self: Box<Self>, hooks: &mut Hooks) -> Element {
+        let Self { targets, mode } = *self;
+        let (game_client, _) = hooks.consume_context::<GameClient>().unwrap();
+        let (prefs, _) = hooks.consume_context::<EditorPrefs>().unwrap();
+        let (hovered, set_hovered) = hooks.use_state(None as Option<Handle>);
+        let drag = hooks.use_ref_with(|_| None as Option<Drag>);
+
+        {
+            let game_state = game_client.game_state.clone();
+            hooks.use_spawn(move |_| {
+                move |_| {
+                    // Clear the manipulator
+                    game_state.lock().world.resource(gizmos()).scope(gizmo_scope());
+                }
+            });
+        }
+
+        hooks.use_frame({
+            let game_state = game_client.game_state.clone();
+            let targets = targets.clone();
+            let drag = drag.clone();
+            move |world| {
+                let mouse_clip_pos = screen_to_clip_space(world, *world.resource(cursor_position()));
+                let state = game_state.lock();
+                let mut scope = state.world.resource(gizmos()).scope(gizmo_scope());
+                let Some(frame) = Frame::from_state(&state, &targets, prefs) else {
+                    return;
+                };
+                let ray = state.screen_ray(mouse_clip_pos);
+
+                let active = match &mut *drag.lock() {
+                    Some(drag) => {
+                        drag.update(ray, mode, prefs, &targets);
+                        Some(drag.handle)
+                    }
+                    None => {
+                        let handle = frame.pick(mode, ray);
+                        if handle != hovered {
+                            set_hovered(handle);
+                        }
+                        handle
+                    }
+                };
+                frame.draw(&mut scope, mode, active);
+            }
+        });
+
+        hooks.use_runtime_message::<messages::WindowMouseInput>({
+            let drag = drag.clone();
+            move |_, event| {
+                if !event.pressed && MouseButton::from(event.button) == MouseButton::Left {
+                    if let Some(mut drag) = drag.lock().take() {
+                        drag.action.confirm();
+                    }
+                }
+            }
+        });
+
+        hooks.use_runtime_message::<messages::WindowKeyboardInput>({
+            let drag = drag.clone();
+            move |_, event| {
+                let keycode = event.keycode.as_deref().and_then(|v| VirtualKeyCode::from_str(v).ok());
+                if event.pressed && keycode == Some(VirtualKeyCode::Escape) {
+                    // Dropping the action reverts the drag
+                    drag.lock().take();
+                }
+            }
+        });
+
+        let Some(handle) = hovered else {
+            return Element::new();
+        };
+
+        // Grab the click before it reaches the selection area
+        WindowSized(vec![])
+            .el()
+            .with_clickarea()
+            .on_mouse_down(move |world, _, button| {
+                if button != MouseButton::Left {
+                    return;
+                }
+                let mouse_clip_pos = screen_to_clip_space(world, *world.resource(cursor_position()));
+                let state = game_client.game_state.lock();
+                let (Some(frame), Ok(transforms)) = (Frame::from_state(&state, &targets, prefs), get_world_transforms(&state.world, &targets)) else {
+                    return;
+                };
+                let action = EditorAction::new(world.resource(runtime()).clone(), game_client.clone(), intent_set_transform(), TRANSFORM_THROTTLE);
+                *drag.lock() = Drag::new(handle, frame, transforms, state.screen_ray(mouse_clip_pos), action);
+            })
+            .el()
+            .with(translation(), -Vec3::Z * 0.99)
+    }
+}
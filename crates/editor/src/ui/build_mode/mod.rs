@@ -7,10 +7,12 @@ use ambient_ecs::{generated::messages, Component, ComponentValue, EntityId};
 use ambient_element::{Element, ElementComponent, ElementComponentExt, Hooks};
 use ambient_intent::{client_push_intent, rpc_undo_head_exact};
 use ambient_network::client::GameClient;
+use ambient_renderer::outline_recursive;
 use ambient_shared_types::MouseButton;
 use ambient_sys::task::RuntimeHandle;
 use derive_more::Display;
 use futures_signals::signal::SignalExt;
+use glam::Vec4;
 use itertools::Itertools;
 
 use ambient_shared_types::VirtualKeyCode;
@@ -37,12 +39,14 @@ use crate::{
 };
 
 mod entity_browser;
+mod gizmo;
 mod grid_material;
 mod guide;
 mod select_area;
 mod selection_panel;
 mod transform;
 
+use gizmo::*;
 use guide::*;
 use select_area::*;
 use selection_panel::*;
@@ -137,6 +141,8 @@ impl<T: ComponentValue> Drop for EditorAction<T> {
     }
 }
 
+const SELECTION_OUTLINE_COLOR: Vec4 = Vec4::new(1., 0.6, 0.1, 1.);
+
 #[derive(Debug, Clone)]
 pub struct EditorBuildMode;
 impl ElementComponent for EditorBuildMode {
@@ -208,6 +214,24 @@ impl ElementComponent for EditorBuildMode {
         // Make sure to get the value *after* the `use_interval_deps`
         let targets = targets.lock();
 
+        // Outline the selection; this is only done on this client, so other users don't see it
+        hooks.use_effect(targets.clone(), {
+            let game_state = game_client.game_state.clone();
+            move |_, targets| {
+                let mut state = game_state.lock();
+                for &id in targets.iter() {
+                    state.world.add_component(id, outline_recursive(), SELECTION_OUTLINE_COLOR).ok();
+                }
+                let targets = targets.clone();
+                move |_| {
+                    let mut state = game_state.lock();
+                    for &id in targets.iter() {
+                        state.world.remove_component(id, outline_recursive()).ok();
+                    }
+                }
+            }
+        });
+
         Dock(vec![
             EditorPlayerInputHandler.el(),
             ScreenContainer(screen).el(),
@@ -357,6 +381,28 @@ enum TransformMode {
     Place,
 }
 
+/// The manipulator drawn over the selection when no transform mode is active
+#[derive(Display, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GizmoMode {
+    #[default]
+    #[display(fmt = "Translate")]
+    Translate,
+    #[display(fmt = "Rotate")]
+    Rotate,
+    #[display(fmt = "Scale")]
+    Scale,
+}
+
+impl GizmoMode {
+    fn next(self) -> Self {
+        match self {
+            GizmoMode::Translate => GizmoMode::Rotate,
+            GizmoMode::Rotate => GizmoMode::Scale,
+            GizmoMode::Scale => GizmoMode::Translate,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TransformControls {
     targets: Arc<[EntityId]>,
@@ -370,6 +416,8 @@ impl ElementComponent for TransformControls {
         let (prefs, set_prefs) = hooks.consume_context::<EditorPrefs>().unwrap();
         let set = set_prefs.clone();
         let set_snap_mode = move |snap| (set)(EditorPrefs { snap, ..prefs });
+        let set = set_prefs.clone();
+        let set_gizmo_mode = move |gizmo| (set)(EditorPrefs { gizmo, ..prefs });
         let set_global_coordinates = move |use_global| {
             (set_prefs)(EditorPrefs {
                 use_global_coordinates: use_global,
@@ -412,6 +460,17 @@ impl ElementComponent for TransformControls {
             .hotkey(VirtualKeyCode::U)
             .toggled(prefs.use_global_coordinates)
             .el(),
+            Button::new(
+                match prefs.gizmo {
+                    GizmoMode::Translate => "",
+                    GizmoMode::Rotate => "北",
+                    GizmoMode::Scale => "ﬕ",
+                },
+                move |_| set_gizmo_mode(prefs.gizmo.next()),
+            )
+            .tooltip(format!("Gizmo: {}", prefs.gizmo))
+            .hotkey(VirtualKeyCode::M)
+            .el(),
             Separator { vertical: true }.el(),
             mode_button(TransformMode::Translate, "", VirtualKeyCode::Key1).el(),
             mode_button(TransformMode::Rotate, "北", VirtualKeyCode::Key2).el(),
@@ -426,7 +485,15 @@ impl ElementComponent for TransformControls {
             }
         });
 
-        if srt_mode.is_some() {
+        if srt_mode.is_none() && !targets.is_empty() {
+            items.push(
+                TransformGizmo {
+                    targets,
+                    mode: prefs.gizmo,
+                }
+                .el(),
+            );
+        } else if srt_mode.is_some() {
             items.extend(vec![
                 match (targets.is_empty(), srt_mode) {
                     (false, Some(TransformMode::Translate)) => {
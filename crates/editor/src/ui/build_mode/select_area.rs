@@ -1,10 +1,11 @@
 use ambient_core::{
-    runtime,
+    hierarchy::parent,
+    runtime, selectable,
     transform::{get_world_position, translation},
     window::cursor_position,
     window::{window_logical_size, window_scale_factor},
 };
-use ambient_ecs::generated::messages;
+use ambient_ecs::{generated::messages, EntityId, World};
 use ambient_element::{Element, ElementComponent, ElementComponentExt, Hooks};
 use ambient_network::{client::GameClient, log_network_result};
 use ambient_std::{color::Color, math::interpolate};
@@ -17,6 +18,7 @@ use glam::{vec2, vec3, Vec2, Vec3Swizzles};
 use crate::{
     intents::SelectMode,
     rpc::{rpc_select, SelectMethod},
+    Selection,
 };
 
 #[derive(Debug, Clone)]
@@ -97,15 +99,21 @@ impl ElementComponent for SelectArea {
                     }
                 }
 
-                let ray = {
-                    let state = game_client.game_state.lock();
+                let (picked, ray) = {
+                    let mut state = game_client.game_state.lock();
                     let p = interpolate(mouse_pos, Vec2::ZERO, screen_size, vec2(-1., 1.), vec2(1., -1.));
-                    state.screen_ray(p)
+                    (state.pick(mouse_pos), state.screen_ray(p))
                 };
 
                 let game_client = game_client.clone();
                 world.resource(runtime()).clone().spawn(async move {
-                    log_network_result!(game_client.rpc(rpc_select, (SelectMethod::Ray(ray), select_mode)).await);
+                    // Prefer the pixel-perfect GPU pick; the raycast is only used for what isn't rendered opaque
+                    let picked = picked.await.and_then(|id| selectable_ancestor(&game_client.game_state.lock().world, id));
+                    let method = match picked {
+                        Some(id) => SelectMethod::Manual(Selection::new([id])),
+                        None => SelectMethod::Ray(ray),
+                    };
+                    log_network_result!(game_client.rpc(rpc_select, (method, select_mode)).await);
                 });
             }
         });
@@ -141,3 +149,13 @@ impl ElementComponent for SelectArea {
             }])
     }
 }
+
+/// The entity itself, or its closest ancestor, that can be selected; picking hits the primitives of models
+fn selectable_ancestor(world: &World, mut id: EntityId) -> Option<EntityId> {
+    loop {
+        if world.has_component(id, selectable()) {
+            return Some(id);
+        }
+        id = world.get(id, parent()).ok()?;
+    }
+}
@@ -24,9 +24,9 @@ use crate::{
         EditorPrefs,
     },
 };
-const TRANSFORM_THROTTLE: Duration = Duration::from_millis(60);
+pub(super) const TRANSFORM_THROTTLE: Duration = Duration::from_millis(60);
 
-fn get_world_transforms(world: &World, targets: &[EntityId]) -> anyhow::Result<Vec<Mat4>> {
+pub(super) fn get_world_transforms(world: &World, targets: &[EntityId]) -> anyhow::Result<Vec<Mat4>> {
     targets
         .iter()
         .map(|id| {
@@ -2,6 +2,7 @@ use std::{collections::HashMap, fmt::Debug, io::Cursor};
 
 mod build_mode;
 pub mod entity_editor;
+mod presence;
 mod terrain_mode;
 
 use ambient_core::{game_mode, runtime, transform::translation, GameMode};
@@ -39,6 +40,7 @@ use build_mode::*;
 use glam::{vec3, Vec3};
 use image::{DynamicImage, ImageOutputFormat, RgbImage};
 use itertools::Itertools;
+use presence::*;
 use terrain_mode::*;
 
 use crate::{selection, Selection};
@@ -62,6 +64,7 @@ impl EditorPrefs {
 struct EditorPrefs {
     pub use_global_coordinates: bool,
     pub snap: Option<f32>,
+    pub gizmo: GizmoMode,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -92,6 +95,7 @@ pub fn EditorUI(hooks: &mut Hooks) -> Element {
 
     let (game_client, _) = hooks.consume_context::<GameClient>().unwrap();
     let (hide_ui, set_hide_ui) = hooks.use_state(false);
+    let (show_session, set_show_session) = hooks.use_state(false);
     let (user_settings, _) = hooks.consume_context::<EditorSettings>().unwrap();
     let (screen, _set_screen) = hooks.use_state(None);
 
@@ -146,6 +150,7 @@ pub fn EditorUI(hooks: &mut Hooks) -> Element {
 
     Group(vec![
         Crosshair.el(),
+        EditorPresence.el(),
         WindowSized(vec![
             ScreenContainer(screen).el(),
             FlowColumn::el([FlowRow::el([
@@ -203,6 +208,10 @@ pub fn EditorUI(hooks: &mut Hooks) -> Element {
                     .hotkey_modifier(command_modifier())
                     .tooltip("Hide UI")
                     .el(),
+                Button::new(FontAwesomeIcon::el(0xf0c0, true), closure!(clone set_show_session, |_| set_show_session(!show_session)))
+                    .toggled(show_session)
+                    .tooltip("Session")
+                    .el(),
                 // UploadThumbnailButton.el(),
                 Button::new_async(FontAwesomeIcon::el(0xf2ea, true), {
                     let game_client = game_client.clone();
@@ -237,6 +246,15 @@ pub fn EditorUI(hooks: &mut Hooks) -> Element {
             } else {
                 Element::new()
             },
+            if show_session {
+                SessionPanel.el()
+                    .floating_panel()
+                    .with(margin(), Borders::even(STREET).into())
+                    .with(docking(), Docking::Left)
+                    .with(width(), 300.)
+            } else {
+                Element::new()
+            },
             match editor_mode {
                 EditorMode::Experience => EditorExperienceMode.el(),
                 EditorMode::Terrain => EditorTerrainMode.el(),
@@ -0,0 +1,170 @@
+//! The presence of the other users in an editing session: where their cursors are and what they have selected, drawn
+//! in their color over the scene, and listed with the history of the edits of everyone in the session.
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use ambient_core::{
+    bounding::world_bounding_sphere,
+    player::{get_by_user_id, player, user_id},
+    runtime,
+    window::{cursor_position, screen_to_clip_space},
+};
+use ambient_ecs::{query, World, WorldDiff};
+use ambient_element::{element_component, Element, ElementComponentExt, Hooks};
+use ambient_gizmos::{gizmos, GizmoPrimitive};
+use ambient_intent::IntentFeed;
+use ambient_network::{client::GameClient, log_network_result, rpc::rpc_world_diff};
+use ambient_std::{color::Color, line_hash};
+use ambient_ui_native::{
+    layout::{height, space_between_items, width},
+    FlowColumn, FlowRow, Rectangle, Text, UIExt, STREET,
+};
+use glam::{Vec3, Vec4};
+use itertools::Itertools;
+
+use crate::{editor_cursor, selection, Selection};
+
+/// How often the cursor of this user is sent to the others, in seconds
+const CURSOR_INTERVAL: f32 = 0.1;
+/// How far the cursor is from the camera when it doesn't point at the ground, and how far it is looked for there
+const CURSOR_DEFAULT_DISTANCE: f32 = 10.;
+const CURSOR_MAX_DISTANCE: f32 = 500.;
+/// The size of the cursors of the other users, relative to their distance to the camera
+const CURSOR_SIZE: f32 = 0.01;
+const SELECTION_BORDER: f32 = 0.05;
+
+fn presence_scope() -> u64 {
+    line_hash!()
+}
+
+/// The color that identifies a user to the others
+pub fn user_color(user_id: &str) -> Color {
+    let mut hasher = DefaultHasher::new();
+    user_id.hash(&mut hasher);
+    Color::hsl((hasher.finish() % 360) as f32, 0.8, 0.6)
+}
+
+/// A user in the session, other than this one
+#[derive(Debug, Clone, PartialEq)]
+struct Collaborator {
+    user_id: String,
+    cursor: Option<Vec3>,
+    selection: Selection,
+}
+
+fn collaborators(world: &World, local_user_id: &str) -> Vec<Collaborator> {
+    query(user_id())
+        .incl(player())
+        .iter(world, None)
+        .filter(|(_, uid)| uid.as_str() != local_user_id)
+        .map(|(id, uid)| Collaborator {
+            user_id: uid.clone(),
+            cursor: world.get(id, editor_cursor()).ok(),
+            selection: world.get_cloned(id, selection()).unwrap_or_default(),
+        })
+        .sorted_by(|a, b| a.user_id.cmp(&b.user_id))
+        .collect()
+}
+
+/// Sends the cursor of this user to the others, and draws theirs and their selections
+#[element_component]
+pub fn EditorPresence(hooks: &mut Hooks) -> Element {
+    let (game_client, _) = hooks.consume_context::<GameClient>().unwrap();
+
+    {
+        let game_state = game_client.game_state.clone();
+        hooks.use_spawn(move |_| {
+            move |_| {
+                game_state.lock().world.resource(gizmos()).scope(presence_scope());
+            }
+        });
+    }
+
+    let last_cursor = hooks.use_ref_with(|_| None as Option<Vec3>);
+    let runtime = hooks.world.resource(runtime()).clone();
+    hooks.use_frame({
+        let game_client = game_client.clone();
+        let last_cursor = last_cursor.clone();
+        move |world| {
+            let mouse_clip_pos = screen_to_clip_space(world, *world.resource(cursor_position()));
+            let state = game_client.game_state.lock();
+            let ray = state.screen_ray(mouse_clip_pos);
+            let distance = match -ray.origin.z / ray.dir.z {
+                t if t > 0. && t < CURSOR_MAX_DISTANCE => t,
+                _ => CURSOR_DEFAULT_DISTANCE,
+            };
+            *last_cursor.lock() = Some(ray.origin + ray.dir * distance);
+
+            let camera_pos = state.view().unwrap_or_default().inverse().transform_point3(Vec3::ZERO);
+            let mut scope = state.world.resource(gizmos()).scope(presence_scope());
+            for collaborator in collaborators(&state.world, &game_client.user_id) {
+                let color: Vec3 = user_color(&collaborator.user_id).into();
+                if let Some(cursor) = collaborator.cursor {
+                    scope.draw(GizmoPrimitive::sphere(cursor, cursor.distance(camera_pos) * CURSOR_SIZE).with_color(color));
+                }
+                for id in &collaborator.selection {
+                    if let Ok(sphere) = state.world.get(id, world_bounding_sphere()) {
+                        scope.draw(GizmoPrimitive::torus(sphere.center, sphere.radius, sphere.radius * SELECTION_BORDER).with_color(color));
+                    }
+                }
+            }
+        }
+    });
+
+    let sent_cursor = hooks.use_ref_with(|_| None as Option<Vec3>);
+    hooks.use_interval(CURSOR_INTERVAL, move || {
+        let Some(cursor) = *last_cursor.lock() else {
+            return;
+        };
+        if sent_cursor.lock().replace(cursor) == Some(cursor) {
+            return;
+        }
+        let Some(player_id) = get_by_user_id(&game_client.game_state.lock().world, &game_client.user_id) else {
+            return;
+        };
+        let game_client = game_client.clone();
+        runtime.spawn(async move {
+            let diff = WorldDiff::new().add_component(player_id, editor_cursor(), cursor);
+            log_network_result!(game_client.rpc(rpc_world_diff, diff).await);
+        });
+    });
+
+    Element::new()
+}
+
+/// Lists the other users in the session and what they have selected, and the edits that everyone has made
+#[element_component]
+pub fn SessionPanel(hooks: &mut Hooks) -> Element {
+    let (game_client, _) = hooks.consume_context::<GameClient>().unwrap();
+    let (others, set_others) = hooks.use_state(Vec::new());
+    hooks.use_interval(0.5, {
+        let others = others.clone();
+        move || {
+            let new = collaborators(&game_client.game_state.lock().world, &game_client.user_id);
+            if new != others {
+                set_others(new);
+            }
+        }
+    });
+
+    FlowColumn::el([
+        Text::el(match others.len() {
+            0 => "No one else is editing".to_string(),
+            1 => "1 other editor".to_string(),
+            n => format!("{n} other editors"),
+        }),
+        FlowColumn::el(others.into_iter().map(|collaborator| {
+            FlowRow::el([
+                Rectangle.el().with(width(), 12.).with(height(), 12.).with_background(Vec4::from(user_color(&collaborator.user_id))),
+                Text::el(format!("{} ({} selected)", collaborator.user_id, collaborator.selection.len())),
+            ])
+            .with(space_between_items(), STREET)
+        }))
+        .with(space_between_items(), STREET),
+        IntentFeed.el(),
+    ])
+    .with(space_between_items(), STREET)
+}
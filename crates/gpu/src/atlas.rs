@@ -0,0 +1,323 @@
+//! Packs small images, such as UI icons, into shared atlas textures, so that they don't each need a texture and a
+//! material of their own.
+use std::sync::Arc;
+
+use ambient_std::{
+    asset_cache::{AssetCache, AsyncAssetKey, SyncAssetKey, SyncAssetKeyExt},
+    asset_url::AbsAssetUrl,
+    download_asset::AssetError,
+};
+use ambient_sys::task;
+use async_trait::async_trait;
+use glam::{uvec2, UVec2, Vec2, Vec4};
+use image::RgbaImage;
+use parking_lot::Mutex;
+
+use crate::{
+    gpu::{Gpu, GpuKey},
+    texture::{Texture, TextureView},
+    texture_loaders::image_from_url,
+};
+
+/// The width and height of each page of an atlas, in pixels.
+pub const ATLAS_PAGE_SIZE: u32 = 2048;
+/// Images wider or taller than this get a texture of their own instead.
+pub const MAX_ATLAS_IMAGE_SIZE: u32 = 256;
+/// The pixels around each image, which repeat its edges so that filtering doesn't bleed into its neighbours
+const PADDING: u32 = 2;
+
+/// Where an image is stored: a region of a page of the [TextureAtlas], or a texture of its own.
+#[derive(Debug, Clone)]
+pub struct AtlasRegion {
+    /// The texture that holds the image.
+    pub view: Arc<TextureView>,
+    /// The index of the page of the atlas that holds the image; `None` if it has a texture of its own.
+    pub page: Option<usize>,
+    /// The texture coordinates of the top left corner of the image.
+    pub uv_min: Vec2,
+    /// The texture coordinates of the bottom right corner of the image.
+    pub uv_max: Vec2,
+    /// The size of the image, in pixels.
+    pub size: UVec2,
+}
+impl AtlasRegion {
+    /// A region covering the whole of `view`.
+    pub fn whole(view: Arc<TextureView>) -> Self {
+        let size = uvec2(view.texture.size.width, view.texture.size.height);
+        Self {
+            view,
+            page: None,
+            uv_min: Vec2::ZERO,
+            uv_max: Vec2::ONE,
+            size,
+        }
+    }
+    /// The texture coordinates of the region, as `uv_min` followed by `uv_max`.
+    pub fn uv_rect(&self) -> Vec4 {
+        self.uv_min.extend(self.uv_max.x).extend(self.uv_max.y)
+    }
+    /// Maps texture coordinates within the image to texture coordinates within its texture.
+    pub fn map_uv(&self, uv: Vec2) -> Vec2 {
+        self.uv_min + (self.uv_max - self.uv_min) * uv
+    }
+}
+
+/// Places rectangles on horizontal shelves, each as tall as the first rectangle put on it.
+#[derive(Debug)]
+struct ShelfPacker {
+    size: u32,
+    shelves: Vec<Shelf>,
+}
+#[derive(Debug)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    /// Where the next rectangle goes
+    x: u32,
+}
+impl ShelfPacker {
+    fn new(size: u32) -> Self {
+        Self {
+            size,
+            shelves: Vec::new(),
+        }
+    }
+
+    /// The top left corner of a free `width` x `height` rectangle; `None` if there is no room for it.
+    fn allocate(&mut self, width: u32, height: u32) -> Option<UVec2> {
+        // Shelves much taller than the rectangle are skipped, to not waste the space above it
+        let index = self.shelves.iter().position(|shelf| {
+            shelf.height >= height && shelf.height <= height * 2 && shelf.x + width <= self.size
+        });
+        let index = match index {
+            Some(index) => index,
+            None => {
+                let y = self
+                    .shelves
+                    .last()
+                    .map_or(0, |shelf| shelf.y + shelf.height);
+                if y + height > self.size || width > self.size {
+                    return None;
+                }
+                self.shelves.push(Shelf { y, height, x: 0 });
+                self.shelves.len() - 1
+            }
+        };
+        let shelf = &mut self.shelves[index];
+        let position = uvec2(shelf.x, shelf.y);
+        shelf.x += width;
+        Some(position)
+    }
+}
+
+struct AtlasPage {
+    texture: Arc<Texture>,
+    view: Arc<TextureView>,
+    packer: ShelfPacker,
+}
+
+/// Packs images into pages of [ATLAS_PAGE_SIZE] x [ATLAS_PAGE_SIZE] pixels, adding a page whenever the others are
+/// full. Images stay in the atlas for as long as it lives.
+pub struct TextureAtlas {
+    gpu: Arc<Gpu>,
+    format: wgpu::TextureFormat,
+    pages: Mutex<Vec<AtlasPage>>,
+}
+impl std::fmt::Debug for TextureAtlas {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TextureAtlas")
+            .field("format", &self.format)
+            .field("pages", &self.page_count())
+            .finish()
+    }
+}
+impl TextureAtlas {
+    pub fn new(gpu: Arc<Gpu>, format: wgpu::TextureFormat) -> Self {
+        Self {
+            gpu,
+            format,
+            pages: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.lock().len()
+    }
+
+    /// The texture of the page `index`.
+    pub fn page(&self, index: usize) -> Option<Arc<TextureView>> {
+        self.pages.lock().get(index).map(|page| page.view.clone())
+    }
+
+    /// Copies `image` into the atlas; `None` if it is empty or larger than [MAX_ATLAS_IMAGE_SIZE].
+    pub fn insert(&self, image: &RgbaImage) -> Option<AtlasRegion> {
+        let (width, height) = image.dimensions();
+        if width == 0
+            || height == 0
+            || width > MAX_ATLAS_IMAGE_SIZE
+            || height > MAX_ATLAS_IMAGE_SIZE
+        {
+            return None;
+        }
+        let padded = pad(image);
+
+        let mut pages = self.pages.lock();
+        let allocation = pages.iter_mut().enumerate().find_map(|(index, page)| {
+            page.packer
+                .allocate(padded.width(), padded.height())
+                .map(|position| (index, position))
+        });
+        let (index, position) = match allocation {
+            Some(allocation) => allocation,
+            None => {
+                let mut page = self.create_page(pages.len());
+                let position = page.packer.allocate(padded.width(), padded.height())?;
+                pages.push(page);
+                (pages.len() - 1, position)
+            }
+        };
+        let page = &pages[index];
+        self.gpu.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &page.texture.handle,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: position.x,
+                    y: position.y,
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            &padded,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded.width() * 4),
+                rows_per_image: Some(padded.height()),
+            },
+            wgpu::Extent3d {
+                width: padded.width(),
+                height: padded.height(),
+                depth_or_array_layers: 1,
+            },
+        );
+
+        let min = position + UVec2::splat(PADDING);
+        Some(AtlasRegion {
+            view: page.view.clone(),
+            page: Some(index),
+            uv_min: min.as_vec2() / ATLAS_PAGE_SIZE as f32,
+            uv_max: (min + uvec2(width, height)).as_vec2() / ATLAS_PAGE_SIZE as f32,
+            size: uvec2(width, height),
+        })
+    }
+
+    fn create_page(&self, index: usize) -> AtlasPage {
+        let texture = Arc::new(Texture::new(
+            self.gpu.clone(),
+            &wgpu::TextureDescriptor {
+                size: wgpu::Extent3d {
+                    width: ATLAS_PAGE_SIZE,
+                    height: ATLAS_PAGE_SIZE,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: self.format,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                label: Some(&format!("TextureAtlas.page_{index}")),
+                view_formats: &[],
+            },
+        ));
+        AtlasPage {
+            view: Arc::new(texture.create_view(&Default::default())),
+            texture,
+            packer: ShelfPacker::new(ATLAS_PAGE_SIZE),
+        }
+    }
+}
+
+/// Surrounds `image` with [PADDING] pixels that repeat its edges
+fn pad(image: &RgbaImage) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    RgbaImage::from_fn(width + 2 * PADDING, height + 2 * PADDING, |x, y| {
+        let x = x.saturating_sub(PADDING).min(width - 1);
+        let y = y.saturating_sub(PADDING).min(height - 1);
+        *image.get_pixel(x, y)
+    })
+}
+
+/// The atlas of sRGB images, shared by everything that draws small images.
+#[derive(Debug)]
+pub struct TextureAtlasKey;
+impl SyncAssetKey<Arc<TextureAtlas>> for TextureAtlasKey {
+    fn load(&self, assets: AssetCache) -> Arc<TextureAtlas> {
+        Arc::new(TextureAtlas::new(
+            GpuKey.get(&assets),
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+        ))
+    }
+}
+
+/// Loads the sRGB image at `url` into the [TextureAtlasKey] atlas, or into a texture of its own if it is too large.
+#[derive(Debug, Clone)]
+pub struct AtlasRegionFromUrl {
+    pub url: AbsAssetUrl,
+}
+#[async_trait]
+impl AsyncAssetKey<Result<Arc<AtlasRegion>, AssetError>> for AtlasRegionFromUrl {
+    async fn load(self, assets: AssetCache) -> Result<Arc<AtlasRegion>, AssetError> {
+        let image = image_from_url(assets.clone(), self.url.clone())
+            .await?
+            .into_rgba8();
+        task::block_in_place(|| {
+            if let Some(region) = TextureAtlasKey.get(&assets).insert(&image) {
+                return Ok(Arc::new(region));
+            }
+            let texture = Arc::new(Texture::from_rgba8_image_mipmapped(
+                &assets,
+                &image,
+                wgpu::TextureFormat::Rgba8UnormSrgb,
+                Some(&self.url.to_string()),
+            ));
+            Ok(Arc::new(AtlasRegion::whole(Arc::new(
+                texture.create_view(&Default::default()),
+            ))))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shelves_are_filled_before_adding_more() {
+        let mut packer = ShelfPacker::new(64);
+        assert_eq!(packer.allocate(32, 16), Some(uvec2(0, 0)));
+        assert_eq!(packer.allocate(16, 12), Some(uvec2(32, 0)));
+        assert_eq!(packer.allocate(32, 16), Some(uvec2(0, 16)));
+        // Too short for the shelves, which would waste the space above it
+        assert_eq!(packer.allocate(8, 4), Some(uvec2(0, 32)));
+    }
+
+    #[test]
+    fn full_pages_reject_rectangles() {
+        let mut packer = ShelfPacker::new(64);
+        assert_eq!(packer.allocate(64, 64), Some(uvec2(0, 0)));
+        assert_eq!(packer.allocate(1, 1), None);
+        assert_eq!(ShelfPacker::new(64).allocate(65, 1), None);
+    }
+
+    #[test]
+    fn padding_repeats_the_edges() {
+        let image = RgbaImage::from_fn(2, 1, |x, _| image::Rgba([x as u8, 0, 0, 255]));
+        let padded = pad(&image);
+        assert_eq!(padded.dimensions(), (2 + 2 * PADDING, 1 + 2 * PADDING));
+        assert_eq!(padded.get_pixel(0, 0).0[0], 0);
+        assert_eq!(
+            padded.get_pixel(padded.width() - 1, padded.height() - 1).0[0],
+            1
+        );
+    }
+}
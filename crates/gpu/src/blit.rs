@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use ambient_std::asset_cache::{AssetCache, SyncAssetKey, SyncAssetKeyExt};
+use glam::Mat3;
 use wgpu::{
     BindGroupLayoutDescriptor, BindGroupLayoutEntry, FilterMode, PipelineLayoutDescriptor,
     ShaderStages, TextureSampleType,
@@ -15,6 +16,8 @@ pub struct BlitterKey {
     pub format: wgpu::ColorTargetState,
     pub min_filter: FilterMode,
     pub gamma_correction: Option<f32>,
+    /// Applied to the linear RGB of the source before gamma correction; used for color vision filters
+    pub color_matrix: Option<Mat3>,
 }
 
 impl SyncAssetKey<Arc<Blitter>> for BlitterKey {
@@ -39,13 +42,20 @@ impl Blitter {
         } else {
             "color".to_string()
         };
+        let filter = if let Some(matrix) = conf.color_matrix {
+            let cols = matrix.to_cols_array().map(|x| format!("{x:?}")).join(", ");
+            format!("vec4<f32>(mat3x3<f32>({cols}) * sampled.xyz, sampled.w)")
+        } else {
+            "sampled".to_string()
+        };
 
         let shader = Shader::new(
             assets,
             "blitter",
             &[],
             &ShaderModule::new("blitter", include_str!("blit.wgsl"))
-                .with_ident(ShaderIdent::raw("COLORSPACE_EXPR", colorspace)),
+                .with_ident(ShaderIdent::raw("COLORSPACE_EXPR", colorspace))
+                .with_ident(ShaderIdent::raw("COLOR_FILTER_EXPR", filter)),
         )
         .unwrap();
 
@@ -32,7 +32,8 @@ var r_sampler: sampler;
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let color = textureSample(r_color, r_sampler, in.tex_coords);
+    let sampled = textureSample(r_color, r_sampler, in.tex_coords);
+    let color = COLOR_FILTER_EXPR;
 
     return COLORSPACE_EXPR;
 }
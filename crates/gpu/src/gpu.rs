@@ -3,6 +3,7 @@ use std::sync::Arc;
 use ambient_std::asset_cache::SyncAssetKey;
 use bytemuck::{Pod, Zeroable};
 use glam::{uvec2, UVec2, UVec3, UVec4, Vec2, Vec3, Vec4};
+use parking_lot::Mutex;
 use wgpu::{InstanceDescriptor, PresentMode, TextureFormat};
 use winit::window::Window;
 
@@ -19,7 +20,10 @@ impl SyncAssetKey<Arc<Gpu>> for GpuKey {}
 
 #[derive(Debug)]
 pub struct Gpu {
-    pub surface: Option<wgpu::Surface>,
+    pub instance: wgpu::Instance,
+    /// The surface is dropped while the app is suspended (e.g. when an Android activity is paused),
+    /// and recreated on resume; see [Gpu::suspend] and [Gpu::resume].
+    pub surface: Mutex<Option<wgpu::Surface>>,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
     pub swapchain_format: Option<TextureFormat>,
@@ -60,7 +64,12 @@ impl Gpu {
             // https://docs.rs/wgpu/latest/wgpu/enum.Dx12Compiler.html
             dx12_shader_compiler: wgpu::Dx12Compiler::Fxc,
         });
+        // On Android the native window only exists between `Resumed` and `Suspended`, so the surface
+        // is created when the app is first resumed instead.
+        #[cfg(not(target_os = "android"))]
         let surface = window.map(|window| unsafe { instance.create_surface(window).unwrap() });
+        #[cfg(target_os = "android")]
+        let surface: Option<wgpu::Surface> = None;
         #[cfg(not(target_os = "unknown"))]
         {
             tracing::debug!("Available adapters:");
@@ -102,6 +111,9 @@ impl Gpu {
                         max_bind_groups: 8,
                         max_storage_buffer_binding_size: adapter_limits
                             .max_storage_buffer_binding_size,
+                        // The mesh, skinning and morph target data alone take up 6 storage buffers
+                        max_storage_buffers_per_shader_stage: adapter_limits
+                            .max_storage_buffers_per_shader_stage,
                         ..Default::default()
                     },
                 },
@@ -112,11 +124,14 @@ impl Gpu {
 
         tracing::info!("Device limits:\n{:#?}", device.limits());
 
+        #[cfg(not(target_os = "android"))]
         let swapchain_format = surface
             .as_ref()
             .map(|surface| surface.get_capabilities(&adapter).formats[0]);
+        #[cfg(target_os = "android")]
+        let swapchain_format = window.map(|_| TextureFormat::Rgba8UnormSrgb);
         tracing::debug!("Swapchain format: {swapchain_format:?}");
-        let swapchain_mode = if surface.is_some() {
+        let swapchain_mode = if swapchain_format.is_some() {
             if settings.vsync() {
                 // From wgpu docs:
                 // "Chooses FifoRelaxed -> Fifo based on availability."
@@ -143,8 +158,9 @@ impl Gpu {
         tracing::debug!("Created gpu");
 
         Self {
+            instance,
             device,
-            surface,
+            surface: Mutex::new(surface),
             queue,
             swapchain_format,
             swapchain_mode,
@@ -154,13 +170,43 @@ impl Gpu {
     }
 
     pub fn resize(&self, size: winit::dpi::PhysicalSize<u32>) {
-        if let Some(surface) = &self.surface {
+        if let Some(surface) = &*self.surface.lock() {
             if size.width > 0 && size.height > 0 {
                 tracing::info!("Resizing to {size:?}");
                 surface.configure(&self.device, &self.sc_desc(uvec2(size.width, size.height)));
             }
         }
     }
+    /// Drops the surface. Must be called when the window is suspended, as the underlying native window
+    /// may be destroyed until the next resume.
+    pub fn suspend(&self) {
+        if self.surface.lock().take().is_some() {
+            tracing::info!("Surface dropped on suspend");
+        }
+    }
+    /// Recreates the surface for `window` if it was dropped by [Gpu::suspend] (or never created).
+    pub fn resume(&self, window: &Window) {
+        let mut surface = self.surface.lock();
+        if surface.is_some() {
+            return;
+        }
+        let new_surface = match unsafe { self.instance.create_surface(window) } {
+            Ok(surface) => surface,
+            Err(err) => {
+                tracing::error!("Failed to recreate surface on resume: {err:?}");
+                return;
+            }
+        };
+        let size = window.inner_size();
+        if size.width > 0 && size.height > 0 {
+            new_surface.configure(&self.device, &self.sc_desc(uvec2(size.width, size.height)));
+        }
+        tracing::info!("Surface recreated on resume");
+        *surface = Some(new_surface);
+    }
+    pub fn has_surface(&self) -> bool {
+        self.surface.lock().is_some()
+    }
     pub fn swapchain_format(&self) -> TextureFormat {
         self.swapchain_format
             .unwrap_or(TextureFormat::Rgba8UnormSrgb)
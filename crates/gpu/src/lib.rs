@@ -1,3 +1,4 @@
+pub mod atlas;
 pub mod blit;
 pub mod fill;
 pub mod gpu;
@@ -9,7 +9,7 @@ use std::{
 use ambient_std::{
     asset_cache::{AssetCache, AsyncAssetKey, AsyncAssetKeyExt, SyncAssetKey, SyncAssetKeyExt},
     asset_url::AbsAssetUrl,
-    download_asset::{AssetResult, MeshFromUrl},
+    download_asset::{AssetError, AssetResult, MeshFromUrl},
     mesh::Mesh,
 };
 use async_trait::async_trait;
@@ -71,9 +71,9 @@ pub struct GpuMeshFromUrl {
 }
 
 impl GpuMeshFromUrl {
-    pub fn new(url: impl AsRef<str>, cache_on_disk: bool) -> anyhow::Result<Self> {
+    pub fn new(url: impl AsRef<str>, cache_on_disk: bool) -> AssetResult<Self> {
         Ok(Self {
-            url: AbsAssetUrl::parse(url)?,
+            url: AbsAssetUrl::parse(url).map_err(AssetError::invalid_url)?,
             cache_on_disk,
         })
     }
@@ -97,7 +97,8 @@ pub struct BaseMesh {
     normal: Vec4,
     tangent: Vec4,
     texcoord0: Vec2,
-    _padding: Vec2,
+    /// The lightmap UVs, if the mesh has a second UV set
+    texcoord1: Vec2,
 }
 
 #[repr(C)]
@@ -107,6 +108,15 @@ pub struct SkinnedMesh {
     weights: Vec4,
 }
 
+/// The offsets of one vertex for one morph target
+#[repr(C)]
+#[derive(Default, Clone, Copy, Pod, Zeroable)]
+pub struct MorphDelta {
+    position: Vec4,
+    normal: Vec4,
+    tangent: Vec4,
+}
+
 /// Gpu mesh buffer which holds all meshes in an Elements application.
 ///
 /// A GpuMesh in the application just keeps an index into the metadata_buffer, and
@@ -119,6 +129,8 @@ pub struct MeshBuffer {
     pub metadata_buffer: TypedBuffer<MeshMetadata>,
     pub base_buffer: AttributeBuffer<BaseMesh>,
     pub skinned_buffer: AttributeBuffer<SkinnedMesh>,
+    /// The morph targets of each mesh, one after another, with one delta per vertex for each target
+    pub morph_buffer: AttributeBuffer<MorphDelta>,
 
     pub index_buffer: AttributeBuffer<u32>,
     meshes: Vec<Option<InternalMesh>>,
@@ -166,6 +178,15 @@ impl MeshBuffer {
                     | wgpu::BufferUsages::COPY_DST
                     | wgpu::BufferUsages::COPY_SRC,
             ),
+            morph_buffer: AttributeBuffer::new(
+                gpu.clone(),
+                "MeshBuffer.morph_buffer",
+                4,
+                0,
+                wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::COPY_SRC,
+            ),
             meshes: Vec::new(),
             to_remove: Arc::new(Mutex::new(Vec::new())),
             free_indices: Vec::new(),
@@ -179,6 +200,10 @@ impl MeshBuffer {
             skinned_offset: self.skinned_buffer.front.len() as u32,
             index_offset: self.index_buffer.front.len() as u32,
             index_count: mesh.index_count(),
+            morph_offset: self.morph_buffer.front.len() as u32,
+            morph_target_count: 0,
+            vertex_count: 0,
+            _padding: 0,
         };
 
         let mut internal_mesh = InternalMesh {
@@ -212,6 +237,11 @@ impl MeshBuffer {
             uv.iter()
                 .zip(&mut data)
                 .for_each(|(src, dst)| dst.texcoord0 = *src);
+            if let Some(uv1) = mesh.texcoords.get(1) {
+                uv1.iter()
+                    .zip(&mut data)
+                    .for_each(|(src, dst)| dst.texcoord1 = *src);
+            }
 
             self.base_buffer
                 .front
@@ -220,6 +250,42 @@ impl MeshBuffer {
                 .front
                 .write(metadata.base_offset as u64, &data);
             internal_mesh.base_count += data.len() as u64;
+            internal_mesh.metadata.vertex_count = data.len() as u32;
+        }
+
+        if !mesh.morph_targets().is_empty() {
+            let vertex_count = internal_mesh.base_count as usize;
+            let mut data = vec![MorphDelta::default(); vertex_count * mesh.morph_targets().len()];
+            for (target, data) in mesh
+                .morph_targets()
+                .iter()
+                .zip(data.chunks_exact_mut(vertex_count))
+            {
+                target
+                    .positions
+                    .iter()
+                    .zip(&mut *data)
+                    .for_each(|(src, dst)| dst.position = src.extend(0.0));
+                target
+                    .normals
+                    .iter()
+                    .zip(&mut *data)
+                    .for_each(|(src, dst)| dst.normal = src.extend(0.0));
+                target
+                    .tangents
+                    .iter()
+                    .zip(&mut *data)
+                    .for_each(|(src, dst)| dst.tangent = src.extend(0.0));
+            }
+
+            self.morph_buffer
+                .front
+                .resize(self.morph_buffer.front.len() + data.len() as u64, true);
+            self.morph_buffer
+                .front
+                .write(metadata.morph_offset as u64, &data);
+            internal_mesh.morph_count = data.len() as u64;
+            internal_mesh.metadata.morph_target_count = mesh.morph_targets().len() as u32;
         }
 
         if !mesh.joint_indices().is_empty() && !mesh.joint_weights().is_empty() {
@@ -331,6 +397,7 @@ impl MeshBuffer {
             sizes.base_offset += mesh.base_count as u32;
             sizes.skinned_offset += mesh.skinned_count as u32;
             sizes.index_offset += mesh.index_count as u32;
+            sizes.morph_offset += mesh.morph_count as u32;
         }
 
         self.base_buffer.tmp.resize(sizes.base_offset as u64, true);
@@ -340,6 +407,9 @@ impl MeshBuffer {
         self.index_buffer
             .tmp
             .resize(sizes.index_offset as u64, true);
+        self.morph_buffer
+            .tmp
+            .resize(sizes.morph_offset as u64, true);
 
         let mut cursor = MeshMetadata::default();
         for (index, mesh) in update_meshes_sorted {
@@ -348,6 +418,8 @@ impl MeshBuffer {
                 base_offset: base_metadata.base_offset + cursor.base_offset,
                 skinned_offset: base_metadata.skinned_offset + cursor.skinned_offset,
                 index_offset: base_metadata.index_offset + cursor.index_offset,
+                morph_offset: base_metadata.morph_offset + cursor.morph_offset,
+                ..mesh.metadata
             };
 
             macro_rules! copy_buff {
@@ -382,6 +454,14 @@ impl MeshBuffer {
                 index_offset,
                 index_count
             );
+            copy_buff!(
+                encoder,
+                mesh,
+                cursor,
+                morph_buffer,
+                morph_offset,
+                morph_count
+            );
         }
 
         macro_rules! copy_back_buff {
@@ -402,6 +482,7 @@ impl MeshBuffer {
         copy_back_buff!(encoder, base_metadata, base_buffer, base_offset);
         copy_back_buff!(encoder, base_metadata, skinned_buffer, skinned_offset);
         copy_back_buff!(encoder, base_metadata, index_buffer, index_offset);
+        copy_back_buff!(encoder, base_metadata, morph_buffer, morph_offset);
 
         let metadata = self
             .meshes
@@ -423,6 +504,7 @@ impl MeshBuffer {
             + self.base_buffer.front.byte_size()
             + self.skinned_buffer.front.byte_size()
             + self.index_buffer.front.byte_size()
+            + self.morph_buffer.front.byte_size()
     }
 
     pub fn n_meshes(&self) -> usize {
@@ -449,12 +531,17 @@ impl MeshBuffer {
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct MeshMetadata {
-    /// position, normal, tangent, texcoord0 are grouped
+    /// position, normal, tangent, texcoord0, texcoord1 are grouped
     pub base_offset: u32,
     pub skinned_offset: u32,
     pub index_offset: u32,
 
     pub index_count: u32,
+
+    pub morph_offset: u32,
+    pub morph_target_count: u32,
+    pub vertex_count: u32,
+    pub _padding: u32,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -463,6 +550,7 @@ struct InternalMesh {
     base_count: u64,
     skinned_count: u64,
     index_count: u64,
+    morph_count: u64,
 }
 
 pub struct AttributeBuffer<T: bytemuck::Pod> {
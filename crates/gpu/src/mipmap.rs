@@ -16,6 +16,7 @@ pub fn generate_mipmaps(
         format: format.into(),
         min_filter: wgpu::FilterMode::Linear,
         gamma_correction: None,
+        color_matrix: None,
     }
     .get(assets);
 
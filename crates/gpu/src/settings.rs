@@ -1,13 +1,121 @@
+use ambient_shared_types::{ColorVisionDeficiency, ColorVisionFilter};
+use ambient_std::asset_cache::{AssetCache, SyncAssetKey};
 use anyhow::{bail, Context, Result};
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
 
+/// The settings the app was started with.
+#[derive(Debug)]
+pub struct SettingsKey;
+impl SyncAssetKey<Settings> for SettingsKey {
+    fn load(&self, _assets: AssetCache) -> Settings {
+        Settings::default()
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Default, Debug)]
 pub struct Settings {
     #[serde(default)]
     resolution: Resolution,
     #[serde(default)]
     vsync: Vsync,
+    #[serde(default)]
+    pub accessibility: AccessibilitySettings,
+    #[serde(default)]
+    pub rendering: RenderingSettings,
+    #[serde(default)]
+    pub audio: AudioSettings,
+    /// The language to show text in, such as `en` or `ja`. Defaults to the system language
+    #[serde(default)]
+    pub language: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct AccessibilitySettings {
+    /// Whether captions for speech and sounds are shown
+    pub captions: bool,
+    /// Multiplier for the caption font size
+    pub caption_scale: f32,
+    /// Opacity of the background behind captions, from 0 to 1
+    pub caption_background_opacity: f32,
+    /// The color vision deficiency to filter the final image for, if any
+    pub color_vision_deficiency: Option<ColorVisionDeficiency>,
+    /// Whether the color vision filter corrects for or simulates the deficiency
+    pub color_vision_filter: ColorVisionFilter,
+    /// Whether the UI uses stronger borders and text colors
+    pub high_contrast: bool,
+}
+
+impl Default for AccessibilitySettings {
+    fn default() -> Self {
+        Self {
+            captions: false,
+            caption_scale: 1.,
+            caption_background_opacity: 0.6,
+            color_vision_deficiency: None,
+            color_vision_filter: ColorVisionFilter::Correct,
+            high_contrast: false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct RenderingSettings {
+    /// How light is bounced dynamically between surfaces
+    pub global_illumination: GlobalIllumination,
+    /// The quality of the global illumination, from 0 to 1
+    pub global_illumination_quality: f32,
+}
+
+impl Default for RenderingSettings {
+    fn default() -> Self {
+        Self {
+            global_illumination: GlobalIllumination::Off,
+            global_illumination_quality: 0.5,
+        }
+    }
+}
+
+/// The user's volumes of the default buses of the audio mixer, as multipliers
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub master_volume: f32,
+    pub sfx_volume: f32,
+    pub music_volume: f32,
+    pub voice_volume: f32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.,
+            sfx_volume: 1.,
+            music_volume: 1.,
+            voice_volume: 1.,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GlobalIllumination {
+    #[default]
+    Off,
+    /// A screen-space pass that bounces the light of what is on screen onto nearby geometry
+    ScreenSpace,
+}
+
+impl GlobalIllumination {
+    /// The value of the `global_illumination` resource for this mode
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Off => "",
+            Self::ScreenSpace => "screen_space",
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
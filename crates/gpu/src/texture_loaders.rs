@@ -37,7 +37,7 @@ impl AsyncAssetKey<Result<Arc<image::RgbaImage>, AssetError>> for Rgba8ImageFrom
     }
 }
 
-async fn image_from_url(assets: AssetCache, url: AbsAssetUrl) -> Result<DynamicImage, AssetError> {
+pub(crate) async fn image_from_url(assets: AssetCache, url: AbsAssetUrl) -> Result<DynamicImage, AssetError> {
     let data = BytesFromUrl::new(url.clone(), true).get(&assets).await?;
 
     let extension = url.extension().context("No extension")?;
@@ -121,7 +121,7 @@ impl AsyncAssetKey<Result<Arc<Texture>, AssetError>> for TextureFromBytes {
         asset.as_ref().ok().map(|asset| asset.size_in_bytes)
     }
     async fn load(self, assets: AssetCache) -> Result<Arc<Texture>, AssetError> {
-        let texture = task::spawn_blocking(move || -> anyhow::Result<Arc<Texture>> {
+        let texture = task::spawn_compute(move || -> anyhow::Result<Arc<Texture>> {
             let image = image::load_from_memory(&self.bytes[..])
                 .context("Failed to load image from bytes")?;
             Ok(Arc::new(Texture::from_image_mipmapped(
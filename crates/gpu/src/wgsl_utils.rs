@@ -30,3 +30,29 @@ pub fn wgsl_interpolate() -> String {
     ]
     .join("\n")
 }
+
+/// Whether `name` can be used as an identifier in WGSL, such as for a field or binding declared by user content
+pub fn is_wgsl_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && name != "_"
+        && !name.starts_with("__")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identifiers() {
+        for name in ["intensity", "_tint", "color2", "night_vision"] {
+            assert!(is_wgsl_identifier(name), "{name}");
+        }
+        for name in ["", "_", "__tint", "2color", "tint-color", "tint color", "ü"] {
+            assert!(!is_wgsl_identifier(name), "{name}");
+        }
+    }
+}
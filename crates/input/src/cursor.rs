@@ -0,0 +1,73 @@
+use ambient_core::window::{window_ctl, WindowCtl};
+use ambient_ecs::{FnSystem, SystemGroup, World};
+use winit::window::CursorGrabMode;
+
+pub use ambient_ecs::generated::components::core::input::{
+    cursor_confined, cursor_image_hotspot, cursor_image_size, cursor_image_url, cursor_locked,
+    cursor_visible,
+};
+
+/// The cursor state the client modules asked for on the resources entity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CursorState {
+    grab: CursorGrabMode,
+    visible: bool,
+}
+impl CursorState {
+    fn from_world(world: &World) -> Self {
+        let locked = world
+            .resource_opt(cursor_locked())
+            .copied()
+            .unwrap_or_default();
+        let confined = world
+            .resource_opt(cursor_confined())
+            .copied()
+            .unwrap_or_default();
+        let grab = if locked {
+            CursorGrabMode::Locked
+        } else if confined {
+            CursorGrabMode::Confined
+        } else {
+            CursorGrabMode::None
+        };
+        // A locked cursor is hidden, and so is the system cursor while a custom image is drawn in its place
+        let visible = world
+            .resource_opt(cursor_visible())
+            .copied()
+            .unwrap_or(true)
+            && !locked
+            && world.resource_opt(cursor_image_url()).is_none();
+        Self { grab, visible }
+    }
+}
+
+/// Applies the cursor components of the resources entity to the window. The window releases the cursor while it isn't
+/// focused, and grabs it again when it regains focus.
+pub fn client_systems() -> SystemGroup {
+    SystemGroup::new(
+        "cursor",
+        vec![Box::new(FnSystem::new({
+            let mut last_state = None;
+            move |world, _| {
+                let state = CursorState::from_world(world);
+                if last_state == Some(state) {
+                    return;
+                }
+                // The window starts with a free and visible cursor
+                let previous = last_state.unwrap_or(CursorState {
+                    grab: CursorGrabMode::None,
+                    visible: true,
+                });
+                last_state = Some(state);
+
+                let ctl = world.resource(window_ctl());
+                if state.grab != previous.grab {
+                    ctl.send(WindowCtl::GrabCursor(state.grab)).ok();
+                }
+                if state.visible != previous.visible {
+                    ctl.send(WindowCtl::ShowCursor(state.visible)).ok();
+                }
+            }
+        }))],
+    )
+}
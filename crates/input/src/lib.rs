@@ -1,13 +1,26 @@
-use std::collections::HashSet;
-
-use ambient_ecs::{components, generated::messages, world_events, Debuggable, Entity, Resource, System, SystemGroup, WorldEventsExt};
+use std::{collections::HashSet, path::PathBuf};
+
+use ambient_core::{
+    async_ecs::async_run,
+    runtime,
+    window::{cursor_position, window_scale_factor},
+};
+use ambient_ecs::{components, generated::messages, world_events, Debuggable, Entity, Resource, System, SystemGroup, World, WorldEventsExt};
 use glam::{vec2, Vec2};
 use serde::{Deserialize, Serialize};
 use winit::event::ModifiersState;
-pub use winit::event::{DeviceEvent, ElementState, Event, KeyboardInput, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent};
+pub use winit::event::{
+    DeviceEvent, ElementState, Event, KeyboardInput, MouseButton, MouseScrollDelta, Touch, TouchPhase, VirtualKeyCode, WindowEvent,
+};
+
+pub use ambient_ecs::generated::components::core::input::{touch_ids, touch_positions};
 
+pub mod cursor;
 pub mod picking;
 
+/// Dropped files larger than this are sent without their contents
+const MAX_DROPPED_FILE_SIZE: u64 = 32 * 1024 * 1024;
+
 #[derive(Clone, Default, Debug, Serialize, Deserialize)]
 pub struct PlayerRawInput {
     pub keys: HashSet<ambient_shared_types::VirtualKeyCode>,
@@ -36,18 +49,20 @@ pub fn event_systems() -> SystemGroup<Event<'static, ()>> {
 }
 
 pub fn resources() -> Entity {
-    Entity::new().with_default(player_raw_input()).with_default(player_prev_raw_input())
+    Entity::new().with_default(player_raw_input()).with_default(player_prev_raw_input()).with_default(touch_ids()).with_default(touch_positions())
 }
 
 #[derive(Debug)]
 pub struct InputSystem {
     modifiers: ModifiersState,
     is_focused: bool,
+    /// The fingers on the touch screen, and their logical positions
+    touches: Vec<(u64, Vec2)>,
 }
 
 impl InputSystem {
     pub fn new() -> Self {
-        Self { modifiers: ModifiersState::empty(), is_focused: true }
+        Self { modifiers: ModifiersState::empty(), is_focused: true, touches: Vec::new() }
     }
 }
 
@@ -102,6 +117,18 @@ impl System<Event<'static, ()>> for InputSystem {
                     ));
                 }
 
+                WindowEvent::HoveredFile(path) => {
+                    world.resource_mut(world_events()).add_message(messages::WindowFileHover::new(path.to_string_lossy().to_string()));
+                }
+
+                WindowEvent::HoveredFileCancelled => {
+                    world.resource_mut(world_events()).add_message(messages::WindowFileHoverCancel::new());
+                }
+
+                WindowEvent::DroppedFile(path) => read_dropped_file(world, path.clone()),
+
+                WindowEvent::Touch(touch) => self.touch(world, touch),
+
                 _ => {}
             },
 
@@ -113,6 +140,63 @@ impl System<Event<'static, ()>> for InputSystem {
     }
 }
 
+impl InputSystem {
+    fn touch(&mut self, world: &mut World, touch: &Touch) {
+        let scale_factor = world.resource_opt(window_scale_factor()).copied().unwrap_or(1.) as f32;
+        let position = vec2(touch.location.x as f32, touch.location.y as f32) / scale_factor;
+        // The first finger on the touch screen also acts as the mouse, so that the UI can be used by touch. Windows already
+        // turns touches into mouse input.
+        let is_primary = self.touches.first().map_or(touch.phase == TouchPhase::Started, |(id, _)| *id == touch.id);
+        match touch.phase {
+            TouchPhase::Started => self.touches.push((touch.id, position)),
+            TouchPhase::Moved => {
+                if let Some((_, pos)) = self.touches.iter_mut().find(|(id, _)| *id == touch.id) {
+                    *pos = position;
+                }
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => self.touches.retain(|(id, _)| *id != touch.id),
+        }
+        world.set(world.resource_entity(), touch_ids(), self.touches.iter().map(|(id, _)| *id).collect()).unwrap();
+        world.set(world.resource_entity(), touch_positions(), self.touches.iter().map(|(_, pos)| *pos).collect()).unwrap();
+
+        let phase = match touch.phase {
+            TouchPhase::Started => 0,
+            TouchPhase::Moved => 1,
+            TouchPhase::Ended => 2,
+            TouchPhase::Cancelled => 3,
+        };
+        world.resource_mut(world_events()).add_message(messages::WindowTouch::new(touch.id, phase, position));
+
+        if is_primary && !cfg!(target_os = "windows") {
+            world.set(world.resource_entity(), cursor_position(), position).unwrap();
+            if touch.phase != TouchPhase::Moved {
+                world
+                    .resource_mut(world_events())
+                    .add_message(messages::WindowMouseInput::new(ambient_shared_types::MouseButton::Left, touch.phase == TouchPhase::Started));
+            }
+        }
+    }
+}
+
+/// Reads a file that was dropped onto the window off the main thread, and sends it in a [messages::WindowFileDrop]
+fn read_dropped_file(world: &World, path: PathBuf) {
+    let async_run = world.resource(async_run()).clone();
+    world.resource(runtime()).spawn_blocking(move || {
+        let size = std::fs::metadata(&path).map(|metadata| metadata.len()).unwrap_or_default();
+        let data = if size <= MAX_DROPPED_FILE_SIZE {
+            std::fs::read(&path).unwrap_or_else(|err| {
+                tracing::warn!("Failed to read dropped file {path:?}: {err}");
+                Vec::new()
+            })
+        } else {
+            Vec::new()
+        };
+        async_run.run(move |world| {
+            world.resource_mut(world_events()).add_message(messages::WindowFileDrop::new(data, path.to_string_lossy().to_string(), size));
+        });
+    });
+}
+
 #[derive(Clone)]
 pub struct MouseInput {
     pub state: ElementState,
@@ -9,7 +9,7 @@ use ambient_ecs::{components, query, Debuggable, Entity, EntityId, MaybeResource
 use ambient_std::shapes::{RayIntersectable, AABB};
 use glam::Vec2;
 
-pub use ambient_ecs::generated::components::core::input::{mouse_over, mouse_pickable_max, mouse_pickable_min};
+pub use ambient_ecs::generated::components::core::input::{mouse_over, mouse_passthrough, mouse_pickable_max, mouse_pickable_min};
 
 components!("input", {
     @[MaybeResource, Debuggable]
@@ -55,7 +55,9 @@ pub fn frame_systems() -> SystemGroup {
                     let prev_intersecting = world.get(id, picker_intersecting()).unwrap_or_default();
 
                     let mut intersecting: Option<PickerIntersection> = None;
-                    for (id2, (pickable, local_to_world)) in query((mouse_pickable(), local_to_world())).iter(world, None) {
+                    for (id2, (pickable, local_to_world)) in
+                        query((mouse_pickable(), local_to_world())).excl(mouse_passthrough()).iter(world, None)
+                    {
                         if local_to_world.is_nan() {
                             continue;
                         }
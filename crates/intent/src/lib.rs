@@ -1,11 +1,11 @@
 pub mod logic;
 mod registry;
 
-use std::{fmt::Debug, sync::Arc};
+use std::{cmp::Reverse, collections::HashMap, fmt::Debug, sync::Arc};
 
 use ambient_ecs::{
-    components, index_system, query, ArchetypeFilter, Component, ComponentValue, Debuggable, Entity, EntityId, Index, IndexColumns,
-    Networked, QueryState, Resource, Store, SystemGroup,
+    components, index_system, query, with_component_registry, ArchetypeFilter, Component, ComponentValue, Debuggable, Entity, EntityId, Index,
+    IndexColumns, Networked, QueryState, Resource, Store, SystemGroup, World,
 };
 use ambient_element::{Element, ElementComponent, ElementComponentExt, Hooks};
 use ambient_network::{
@@ -16,7 +16,7 @@ use ambient_network::{
 };
 use ambient_rpc::RpcRegistry;
 use ambient_ui_native::{FlowColumn, StylesExt, Text};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Local, Utc};
 use itertools::Itertools;
 use logic::{create_intent, push_intent, redo_intent, undo_head, undo_head_exact};
 pub use registry::*;
@@ -46,6 +46,9 @@ components!("intent", {
     intent_success: (),
     @[Debuggable, Networked, Store]
     intent_no_state: (),
+    /// The entities that the intent edits, for the kinds of intents that declare them
+    @[Debuggable, Networked, Store]
+    intent_targets: Vec<EntityId>,
 
     @[Debuggable, Resource]
     intent_registry: Arc<IntentRegistry>,
@@ -59,6 +62,9 @@ components!("intent", {
     intent_index_reverted: Index,
     @[Debuggable, Resource]
     intent_index_applied: Index,
+    /// The intents that edit each entity, by the entities in their [intent_targets]
+    @[Debuggable, Resource]
+    intent_target_index: HashMap<EntityId, Vec<EntityId>>,
 });
 
 pub async fn client_push_intent<T: ComponentValue>(
@@ -193,6 +199,82 @@ impl ElementComponent for IntentVisualizer {
     }
 }
 
+/// How many of the latest intents the [IntentFeed] shows
+const FEED_LENGTH: usize = 30;
+
+/// An intent, as it is shown in the [IntentFeed]
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntentFeedEntry {
+    pub user_id: String,
+    /// The kind of intent
+    pub name: String,
+    pub timestamp: DateTime<Utc>,
+    /// How many entities the intent edits, for the kinds of intents that declare them
+    pub targets: Option<usize>,
+    pub reverted: bool,
+    pub failed: Option<String>,
+}
+
+/// The latest `len` intents of all users, newest first. As the edits of every user are intents, this is the shared
+/// history of an editing session.
+pub fn intent_feed(world: &World, len: usize) -> Vec<IntentFeedEntry> {
+    query((intent(), intent_user_id(), intent_timestamp()))
+        .iter(world, None)
+        .map(|(id, (&index, user_id, &timestamp))| IntentFeedEntry {
+            user_id: user_id.clone(),
+            name: with_component_registry(|r| r.get_by_index(index))
+                .map(|desc| desc.path_last().trim_start_matches("intent_").replace('_', " "))
+                .unwrap_or_default(),
+            timestamp,
+            targets: world.get_ref(id, intent_targets()).ok().map(|targets| targets.len()),
+            reverted: world.has_component(id, intent_reverted()),
+            failed: world.get_cloned(id, intent_failed()).ok(),
+        })
+        .sorted_by_key(|entry| Reverse(entry.timestamp))
+        .take(len)
+        .collect()
+}
+
+/// Shows the [intent_feed] of the world of the client
+#[derive(Debug, Clone)]
+pub struct IntentFeed;
+impl ElementComponent for IntentFeed {
+    fn render(self: Box<Self>, hooks: &mut Hooks) -> Element {
+        let (game_client, _) = hooks.consume_context::<GameClient>().unwrap();
+        let (entries, set_entries) = hooks.use_state(Vec::new());
+        hooks.use_interval(0.5, move || {
+            let feed = intent_feed(&game_client.game_state.lock().world, FEED_LENGTH);
+            if feed != entries {
+                set_entries(feed);
+            }
+        });
+
+        FlowColumn::el(
+            entries
+                .into_iter()
+                .map(|entry| {
+                    let targets = match entry.targets {
+                        Some(1) => " (1 entity)".to_string(),
+                        Some(n) => format!(" ({n} entities)"),
+                        None => String::new(),
+                    };
+                    let status = match (&entry.failed, entry.reverted) {
+                        (Some(err), _) => format!(", failed: {err}"),
+                        (None, true) => ", undone".to_string(),
+                        (None, false) => String::new(),
+                    };
+                    Text::el(format!(
+                        "{} {}: {}{targets}{status}",
+                        entry.timestamp.with_timezone(&Local).format("%H:%M:%S"),
+                        entry.user_id,
+                        entry.name
+                    ))
+                })
+                .collect_vec(),
+        )
+    }
+}
+
 /// Helper functions for collapsing absolute state intents
 pub fn use_old_state<T: Clone + Debug, U: Clone + Debug>(_old_arg: &T, old_state: &U, new_arg: &T, _new_state: &U) -> (T, U) {
     (new_arg.clone(), old_state.clone())
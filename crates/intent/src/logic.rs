@@ -1,13 +1,20 @@
+use std::time::Duration;
+
 use ambient_ecs::{query, Component, ComponentValue, Entity, EntityId, IndexField, IndexKey, World};
 use ambient_network::server::SharedServerState;
 use ambient_std::friendly_id;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
 
 use crate::{
-    intent, intent_applied, intent_id, intent_index, intent_index_applied, intent_index_reverted, intent_registry, intent_reverted,
-    intent_timestamp, intent_user_id,
+    intent, intent_applied, intent_id, intent_index, intent_index_applied, intent_index_reverted, intent_registry, intent_reverted, intent_success,
+    intent_target_index, intent_targets, intent_timestamp, intent_user_id,
 };
 
+/// How long a user keeps holding the entities they edit after their last edit of them. The intents of other users that
+/// edit the same entities are rejected in the meantime, so that simultaneous edits don't fight over them.
+pub const EDIT_HOLD_DURATION: Duration = Duration::from_secs(2);
+
 fn despawn_reverted_intents(world: &mut World, user_id: &str) {
     for (id, u) in query(intent_user_id()).incl(intent_reverted()).collect_cloned(world, None) {
         if u == user_id {
@@ -26,6 +33,8 @@ pub fn push_intent(state: SharedServerState, user_id: String, mut data: Entity)
         despawn_reverted_intents(world, &user_id);
 
         data.set(intent_user_id(), user_id.clone());
+        // Order the intents of all users by the clock of the server, rather than those of their clients
+        data.set(intent_timestamp(), Utc::now());
         let intent = data.get(intent()).expect("Missing intent kind for intent");
 
         let id = data.spawn(world);
@@ -60,6 +69,10 @@ pub fn undo_head_exact(state: SharedServerState, user_id: &str, intent: &str) ->
         if intent != intent_id {
             return None;
         }
+        if let Some(editor) = get_later_editor(world, id) {
+            tracing::warn!("Not reverting intent {intent}, as {editor} has edited its entities since");
+            return None;
+        }
 
         tracing::info!("Reverting intent: {intent}");
         let reg = world.resource(intent_registry()).clone();
@@ -77,6 +90,10 @@ pub fn undo_head(state: SharedServerState, user_id: &str) -> Option<EntityId> {
         let world = guard.get_player_world_mut(user_id).unwrap();
 
         if let Some(id) = get_head_intent(world, user_id) {
+            if let Some(editor) = get_later_editor(world, id) {
+                tracing::warn!("Can't undo, as {editor} has edited the same entities since");
+                return None;
+            }
             let reg = world.resource(intent_registry()).clone();
             let intent = world.get(id, intent()).expect("Not an intent");
 
@@ -115,6 +132,53 @@ pub async fn redo_intent(state: SharedServerState, user_id: &str) -> Option<Enti
 
 // Internal
 
+/// Adds the intent `id` to the [intent_target_index] of every entity in `targets`. The intents that no longer exist are
+/// dropped from the index of those entities on the way.
+pub(crate) fn index_targets(world: &mut World, id: EntityId, targets: &[EntityId]) {
+    let mut index = world.resource_opt(intent_target_index()).cloned().unwrap_or_default();
+    for target in targets {
+        let intents = index.entry(*target).or_default();
+        intents.retain(|intent| world.exists(*intent));
+        if !intents.contains(&id) {
+            intents.push(id);
+        }
+    }
+    world.add_resource(intent_target_index(), index);
+}
+
+/// The applied intents of users other than `user_id` that edit any of `targets`, with their user and timestamp
+fn edits_of_others<'a>(world: &'a World, user_id: &str, targets: &[EntityId]) -> Vec<(&'a String, DateTime<Utc>)> {
+    let Some(index) = world.resource_opt(intent_target_index()) else {
+        return Vec::new();
+    };
+    targets
+        .iter()
+        .filter_map(|target| index.get(target))
+        .flatten()
+        .unique()
+        .filter(|&&id| world.has_component(id, intent_success()) && !world.has_component(id, intent_reverted()))
+        .filter_map(|&id| Some((world.get_ref(id, intent_user_id()).ok()?, world.get(id, intent_timestamp()).ok()?)))
+        .filter(|(user, _)| user.as_str() != user_id)
+        .collect()
+}
+
+/// The user other than `user_id` that is still holding any of `targets`, see [EDIT_HOLD_DURATION]
+pub(crate) fn get_holder(world: &World, user_id: &str, targets: &[EntityId]) -> Option<String> {
+    let now = Utc::now();
+    edits_of_others(world, user_id, targets)
+        .into_iter()
+        .find(|(_, timestamp)| now.signed_duration_since(*timestamp).to_std().map_or(true, |age| age < EDIT_HOLD_DURATION))
+        .map(|(user, _)| user.clone())
+}
+
+/// The user that has edited the entities of `intent` after it, if any
+pub(crate) fn get_later_editor(world: &World, intent: EntityId) -> Option<String> {
+    let user_id = world.get_ref(intent, intent_user_id()).ok()?;
+    let timestamp = world.get(intent, intent_timestamp()).ok()?;
+    let targets = world.get_ref(intent, intent_targets()).ok()?;
+    edits_of_others(world, user_id, targets).into_iter().find(|(_, edited_at)| *edited_at > timestamp).map(|(user, _)| user.clone())
+}
+
 pub(crate) fn get_head_intent(world: &World, user_id: &str) -> Option<EntityId> {
     let start = IndexKey::min(vec![IndexField::exact(intent_user_id(), user_id.to_string()), IndexField::Min]);
     let end = IndexKey::max(vec![IndexField::exact(intent_user_id(), user_id.to_string()), IndexField::Max]);
@@ -11,7 +11,8 @@ use tracing::info_span;
 
 use crate::{
     common_intent_systems, intent, intent_applied, intent_failed, intent_id, intent_id_index, intent_index, intent_index_applied,
-    intent_index_reverted, intent_reverted, intent_success, logic::get_head_applied_intent,
+    intent_index_reverted, intent_reverted, intent_success, intent_targets,
+    logic::{get_head_applied_intent, get_holder, index_targets},
 };
 
 pub struct IntentContext<'a> {
@@ -145,19 +146,13 @@ where
                 let world = &mut ctx.world;
                 world.despawn(head).unwrap();
 
-                world.sync_index(intent_id_index(), head, ArchetypeFilter::new().excl(intent_reverted()));
-                world.sync_index(intent_index(), head, ArchetypeFilter::new().excl(intent_reverted()));
-                world.sync_index(intent_index_reverted(), head, ArchetypeFilter::new().incl(intent_reverted()));
-                world.sync_index(intent_index_applied(), head, ArchetypeFilter::new().incl(intent_applied()).excl(intent_reverted()));
+                sync_indices(world, head);
             }
         }
 
         let world = &mut ctx.world;
         // Update the indices
-        world.sync_index(intent_id_index(), id, ArchetypeFilter::new().excl(intent_reverted()));
-        world.sync_index(intent_index(), id, ArchetypeFilter::new().excl(intent_reverted()));
-        world.sync_index(intent_index_reverted(), id, ArchetypeFilter::new().incl(intent_reverted()));
-        world.sync_index(intent_index_applied(), id, ArchetypeFilter::new().incl(intent_applied()).excl(intent_reverted()));
+        sync_indices(world, id);
     }
 
     fn revert(&'a self, mut ctx: IntentContext<'a>, id: EntityId) {
@@ -183,10 +178,7 @@ where
         }
 
         // Update the indices
-        world.sync_index(intent_id_index(), id, ArchetypeFilter::new().excl(intent_reverted()));
-        world.sync_index(intent_index(), id, ArchetypeFilter::new().excl(intent_reverted()));
-        world.sync_index(intent_index_reverted(), id, ArchetypeFilter::new().incl(intent_reverted()));
-        world.sync_index(intent_index_applied(), id, ArchetypeFilter::new().incl(intent_applied()).excl(intent_reverted()));
+        sync_indices(world, id);
     }
 
     fn merge(&self, ctx: &mut IntentContext<'_>, a: EntityId, b: EntityId) {
@@ -206,8 +198,35 @@ where
     }
 }
 
+/// Updates the indices of the intent `id` after it was applied, reverted or despawned
+fn sync_indices(world: &mut World, id: EntityId) {
+    world.sync_index(intent_id_index(), id, ArchetypeFilter::new().excl(intent_reverted()));
+    world.sync_index(intent_index(), id, ArchetypeFilter::new().excl(intent_reverted()));
+    world.sync_index(intent_index_reverted(), id, ArchetypeFilter::new().incl(intent_reverted()));
+    world.sync_index(intent_index_applied(), id, ArchetypeFilter::new().incl(intent_applied()).excl(intent_reverted()));
+}
+
+/// Marks the intent `id` as failed without applying it
+fn reject(world: &mut World, user_id: &str, id: EntityId, err: anyhow::Error) {
+    tracing::warn!("Rejected intent: {id}\n\n{err:?}");
+
+    // Keep a single rejected intent for a whole action, rather than one for every update of it
+    if let Some(head) = get_head_applied_intent(world, user_id) {
+        if world.has_component(head, intent_failed()) && world.get_ref(head, intent_id()).ok() == world.get_ref(id, intent_id()).ok() {
+            world.despawn(head).unwrap();
+            sync_indices(world, head);
+        }
+    }
+
+    world.add_components(id, Entity::new().with(intent_applied(), format!("failed: {err:#}")).with(intent_failed(), format!("{err:#}"))).unwrap();
+    sync_indices(world, id);
+}
+
+type TargetsFn = Box<dyn Fn(&World, EntityId) -> Option<Vec<EntityId>> + Send + Sync>;
+
 pub struct IntentRegistry {
     handlers: HashMap<u32, Box<dyn for<'x> Handler<'x>>>,
+    targets: HashMap<u32, TargetsFn>,
 }
 
 impl Debug for IntentRegistry {
@@ -223,7 +242,7 @@ impl Debug for IntentRegistry {
 
 impl IntentRegistry {
     pub fn new() -> Self {
-        Self { handlers: HashMap::new() }
+        Self { handlers: HashMap::new(), targets: HashMap::new() }
     }
 
     /// Register a new intent.
@@ -273,6 +292,16 @@ impl IntentRegistry {
         self.handlers.insert(intent.index(), Box::new(handler));
     }
 
+    /// Declares the entities that an intent edits.
+    ///
+    /// This resolves the edits that different users make to the same entities at the same time: while a user is
+    /// editing some entities, the intents of the other users that edit any of them are rejected (see
+    /// [EDIT_HOLD_DURATION](crate::logic::EDIT_HOLD_DURATION)), and an intent can no longer be undone once another
+    /// user has edited its entities after it, as that would overwrite their edits.
+    pub fn register_targets<Arg: ComponentValue>(&mut self, intent: Component<Arg>, targets: impl Fn(&Arg) -> Vec<EntityId> + Send + Sync + 'static) {
+        self.targets.insert(intent.index(), Box::new(move |world, id| Some(targets(world.get_ref(id, intent).ok()?))));
+    }
+
     pub fn get_intent_name(&self, intent: u32) -> Option<String> {
         Some(self.handlers.get(&intent)?.name().to_string())
     }
@@ -289,6 +318,15 @@ impl IntentRegistry {
             assert!(ctx.world.exists(head), "Head intent does not exist");
         }
 
+        if let Some(targets) = self.targets.get(&intent_arg).and_then(|targets| targets(ctx.world, id)) {
+            if let Some(holder) = get_holder(ctx.world, user_id, &targets) {
+                reject(ctx.world, user_id, id, anyhow::anyhow!("The entities are being edited by {holder}"));
+                return;
+            }
+            index_targets(ctx.world, id, &targets);
+            ctx.world.add_component(id, intent_targets(), targets).unwrap();
+        }
+
         // Check if it is possible to collapse the intents
         let handler = self.handlers.get(&intent_arg).expect("No handler for intent");
 
@@ -4,8 +4,8 @@ use ambient_ecs::{
     components, query, query_mut, Entity, EntityId, FrameEvent, Networked, Store, System, World,
 };
 use ambient_intent::{
-    common_intent_systems, intent_registry,
-    logic::{create_intent, push_intent, redo_intent, undo_head},
+    common_intent_systems, intent_failed, intent_registry,
+    logic::{create_intent, push_intent, redo_intent, undo_head, EDIT_HOLD_DURATION},
     use_old_state, IntentRegistry,
 };
 use ambient_network::{
@@ -31,6 +31,10 @@ components!("intent", {
     intent_fail: (),
     @[Networked, Store]
     intent_fail_undo: (),
+    @[Networked, Store]
+    intent_set: (EntityId, f32),
+    @[Networked, Store]
+    intent_set_undo: (EntityId, f32),
 
     @[Networked, Store]
     value: f32,
@@ -727,3 +731,106 @@ async fn collapse() {
         assert!(world.exists(y));
     }
 }
+
+/// Sets up a session of two users, with an intent that edits a single entity and declares it as its target
+fn setup_session() -> (SharedServerState, EntityId, EntityId) {
+    init_components();
+    ambient_intent::init_components();
+
+    let state = setup_state();
+    let mut guard = state.lock();
+    guard.players.insert(
+        "user2".to_string(),
+        Player::new_local(MAIN_INSTANCE_ID.to_string()),
+    );
+    let world = guard.get_player_world_mut("user1").unwrap();
+
+    let mut reg = IntentRegistry::new();
+    reg.register(
+        intent_set(),
+        intent_set_undo(),
+        |ctx, (id, v)| {
+            let old = ctx.world.get(id, value())?;
+            ctx.world.set(id, value(), v)?;
+            Ok((id, old))
+        },
+        |ctx, (id, v)| {
+            ctx.world.set(id, value(), v)?;
+            Ok(())
+        },
+        use_old_state,
+    );
+    reg.register_targets(intent_set(), |(id, _)| vec![*id]);
+    world.add_resource(intent_registry(), Arc::new(reg));
+
+    let a = Entity::new().with(value(), 0.0).spawn(world);
+    let b = Entity::new().with(value(), 0.0).spawn(world);
+    drop(guard);
+    (state, a, b)
+}
+
+fn set(state: &SharedServerState, user_id: &str, id: EntityId, v: f32) -> EntityId {
+    push_intent(
+        state.clone(),
+        user_id.to_string(),
+        create_intent(intent_set(), (id, v), None),
+    )
+}
+
+fn get_value(state: &SharedServerState, id: EntityId) -> f32 {
+    state
+        .lock()
+        .get_player_world("user1")
+        .unwrap()
+        .get(id, value())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn held_entities_reject_the_edits_of_others() {
+    let (state, a, b) = setup_session();
+
+    set(&state, "user1", a, 1.0);
+    // The user holding the entity can keep editing it
+    set(&state, "user1", a, 2.0);
+    let rejected = set(&state, "user2", a, 3.0);
+    set(&state, "user2", b, 4.0);
+
+    assert_eq!(get_value(&state, a), 2.0);
+    assert_eq!(get_value(&state, b), 4.0);
+    let guard = state.lock();
+    let world = guard.get_player_world("user1").unwrap();
+    assert!(world.has_component(rejected, intent_failed()));
+}
+
+#[tokio::test]
+async fn entities_are_released_after_a_while() {
+    let (state, a, _) = setup_session();
+
+    set(&state, "user1", a, 1.0);
+    tokio::time::sleep(EDIT_HOLD_DURATION).await;
+    set(&state, "user2", a, 2.0);
+
+    assert_eq!(get_value(&state, a), 2.0);
+}
+
+#[tokio::test]
+async fn edits_overwritten_by_others_cant_be_undone() {
+    let (state, a, b) = setup_session();
+
+    set(&state, "user1", b, 1.0);
+    set(&state, "user1", a, 1.0);
+    tokio::time::sleep(EDIT_HOLD_DURATION).await;
+    set(&state, "user2", a, 2.0);
+
+    // Undoing would overwrite the edit of the other user
+    assert!(undo_head(state.clone(), "user1").is_none());
+    assert_eq!(get_value(&state, a), 2.0);
+
+    // The other user can still undo their edit, after which the first one is free to undo theirs
+    assert!(undo_head(state.clone(), "user2").is_some());
+    assert_eq!(get_value(&state, a), 1.0);
+    assert!(undo_head(state.clone(), "user1").is_some());
+    assert_eq!(get_value(&state, a), 0.0);
+    assert_eq!(get_value(&state, b), 1.0);
+}
@@ -0,0 +1,76 @@
+/// A square region of a lightmap atlas, in texels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtlasRect {
+    pub x: u32,
+    pub y: u32,
+    pub size: u32,
+}
+
+/// Packs squares of the given sizes into an atlas of `atlas_size` x `atlas_size` texels, keeping `padding` texels
+/// between them. The squares are placed on shelves, largest first.
+///
+/// Returns the rects in the same order as `sizes`, or `None` if they do not fit.
+pub fn pack(sizes: &[u32], atlas_size: u32, padding: u32) -> Option<Vec<AtlasRect>> {
+    let mut order = (0..sizes.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| std::cmp::Reverse(sizes[i]));
+
+    let mut rects = vec![
+        AtlasRect {
+            x: 0,
+            y: 0,
+            size: 0
+        };
+        sizes.len()
+    ];
+    let (mut x, mut y, mut shelf_height) = (padding, padding, 0);
+    for i in order {
+        let size = sizes[i];
+        if x + size + padding > atlas_size {
+            x = padding;
+            y += shelf_height + padding;
+            shelf_height = 0;
+        }
+        if x + size + padding > atlas_size || y + size + padding > atlas_size {
+            return None;
+        }
+        rects[i] = AtlasRect { x, y, size };
+        x += size + padding;
+        shelf_height = shelf_height.max(size);
+    }
+    Some(rects)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packing() {
+        let rects = pack(&[8, 32, 16, 16], 64, 2).unwrap();
+        assert_eq!(
+            rects[1],
+            AtlasRect {
+                x: 2,
+                y: 2,
+                size: 32
+            }
+        );
+        assert_eq!(
+            rects[2],
+            AtlasRect {
+                x: 36,
+                y: 2,
+                size: 16
+            }
+        );
+        for (i, a) in rects.iter().enumerate() {
+            assert!(a.x + a.size <= 62 && a.y + a.size <= 62);
+            for b in &rects[i + 1..] {
+                let overlap_x = a.x < b.x + b.size + 2 && b.x < a.x + a.size + 2;
+                let overlap_y = a.y < b.y + b.size + 2 && b.y < a.y + a.size + 2;
+                assert!(!(overlap_x && overlap_y), "{a:?} overlaps {b:?}");
+            }
+        }
+        assert_eq!(pack(&[64], 64, 2), None);
+    }
+}
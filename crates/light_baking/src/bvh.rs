@@ -0,0 +1,226 @@
+use glam::Vec3;
+
+const LEAF_SIZE: usize = 4;
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    min: Vec3,
+    max: Vec3,
+    /// The first triangle for leaves, or the left child for inner nodes; the right child always follows the left one's subtree
+    start: u32,
+    /// The number of triangles for leaves, or 0 for inner nodes
+    count: u32,
+    right: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Hit {
+    pub t: f32,
+    pub triangle: usize,
+    /// The geometric normal of the triangle that was hit, facing against the ray
+    pub normal: Vec3,
+}
+
+/// A bounding volume hierarchy over triangles, for tracing rays on the cpu
+pub struct Bvh {
+    nodes: Vec<Node>,
+    triangles: Vec<[Vec3; 3]>,
+    /// Maps the (sorted) triangles back to their index in the input
+    indices: Vec<usize>,
+}
+impl Bvh {
+    pub fn new(triangles: Vec<[Vec3; 3]>) -> Self {
+        let mut indices = (0..triangles.len()).collect::<Vec<_>>();
+        let centroids = triangles
+            .iter()
+            .map(|t| (t[0] + t[1] + t[2]) / 3.)
+            .collect::<Vec<_>>();
+        let mut nodes = Vec::new();
+        if !triangles.is_empty() {
+            Self::build(&mut nodes, &triangles, &centroids, &mut indices, 0);
+        }
+        let triangles = indices.iter().map(|&i| triangles[i]).collect();
+        Self {
+            nodes,
+            triangles,
+            indices,
+        }
+    }
+
+    fn build(
+        nodes: &mut Vec<Node>,
+        triangles: &[[Vec3; 3]],
+        centroids: &[Vec3],
+        indices: &mut [usize],
+        start: usize,
+    ) -> usize {
+        let (mut min, mut max) = (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN));
+        for &i in indices.iter() {
+            for p in triangles[i] {
+                min = min.min(p);
+                max = max.max(p);
+            }
+        }
+        let index = nodes.len();
+        nodes.push(Node {
+            min,
+            max,
+            start: start as u32,
+            count: indices.len() as u32,
+            right: 0,
+        });
+        if indices.len() <= LEAF_SIZE {
+            return index;
+        }
+
+        let extent = max - min;
+        let axis = if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+        indices.sort_by(|&a, &b| centroids[a][axis].total_cmp(&centroids[b][axis]));
+        let mid = indices.len() / 2;
+        let (left, right) = indices.split_at_mut(mid);
+        let left = Self::build(nodes, triangles, centroids, left, start);
+        let right = Self::build(nodes, triangles, centroids, right, start + mid);
+        nodes[index].start = left as u32;
+        nodes[index].count = 0;
+        nodes[index].right = right as u32;
+        index
+    }
+
+    /// The minimum and maximum corners of the box around all triangles
+    pub fn bounds(&self) -> Option<(Vec3, Vec3)> {
+        self.nodes.first().map(|node| (node.min, node.max))
+    }
+
+    /// Finds the closest triangle hit by the ray within `max_t`
+    pub fn intersect(&self, origin: Vec3, dir: Vec3, max_t: f32) -> Option<Hit> {
+        self.trace(origin, dir, max_t, false)
+    }
+
+    /// Returns true if any triangle is hit by the ray within `max_t`
+    pub fn occluded(&self, origin: Vec3, dir: Vec3, max_t: f32) -> bool {
+        self.trace(origin, dir, max_t, true).is_some()
+    }
+
+    fn trace(&self, origin: Vec3, dir: Vec3, max_t: f32, any_hit: bool) -> Option<Hit> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        let inv_dir = dir.recip();
+        let mut closest: Option<(f32, usize)> = None;
+        let mut max_t = max_t;
+        let mut stack = vec![0usize];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !ray_aabb(origin, inv_dir, node.min, node.max, max_t) {
+                continue;
+            }
+            if node.count == 0 {
+                stack.push(node.right as usize);
+                stack.push(node.start as usize);
+                continue;
+            }
+            for i in node.start as usize..(node.start + node.count) as usize {
+                if let Some(t) = ray_triangle(origin, dir, &self.triangles[i]) {
+                    if t < max_t {
+                        if any_hit {
+                            return Some(Hit {
+                                t,
+                                triangle: self.indices[i],
+                                normal: Vec3::ZERO,
+                            });
+                        }
+                        max_t = t;
+                        closest = Some((t, i));
+                    }
+                }
+            }
+        }
+        closest.map(|(t, i)| {
+            let [a, b, c] = self.triangles[i];
+            let normal = (b - a).cross(c - a).normalize_or_zero();
+            Hit {
+                t,
+                triangle: self.indices[i],
+                normal: if normal.dot(dir) > 0. {
+                    -normal
+                } else {
+                    normal
+                },
+            }
+        })
+    }
+}
+
+fn ray_aabb(origin: Vec3, inv_dir: Vec3, min: Vec3, max: Vec3, max_t: f32) -> bool {
+    let t0 = (min - origin) * inv_dir;
+    let t1 = (max - origin) * inv_dir;
+    let near = t0.min(t1).max_element().max(0.);
+    let far = t0.max(t1).min_element().min(max_t);
+    near <= far
+}
+
+/// Möller–Trumbore intersection; returns the distance along the ray
+fn ray_triangle(origin: Vec3, dir: Vec3, [a, b, c]: &[Vec3; 3]) -> Option<f32> {
+    let e1 = *b - *a;
+    let e2 = *c - *a;
+    let p = dir.cross(e2);
+    let det = e1.dot(p);
+    if det.abs() < 1e-8 {
+        return None;
+    }
+    let inv_det = 1. / det;
+    let s = origin - *a;
+    let u = s.dot(p) * inv_det;
+    if !(0. ..=1.).contains(&u) {
+        return None;
+    }
+    let q = s.cross(e1);
+    let v = dir.dot(q) * inv_det;
+    if v < 0. || u + v > 1. {
+        return None;
+    }
+    let t = e2.dot(q) * inv_det;
+    (t > 1e-5).then_some(t)
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec3;
+
+    use super::*;
+
+    #[test]
+    fn tracing() {
+        // A row of unit quads in the xy plane at increasing z
+        let triangles = (0..10)
+            .flat_map(|i| {
+                let z = i as f32;
+                [
+                    [vec3(0., 0., z), vec3(1., 0., z), vec3(1., 1., z)],
+                    [vec3(0., 0., z), vec3(1., 1., z), vec3(0., 1., z)],
+                ]
+            })
+            .collect();
+        let bvh = Bvh::new(triangles);
+
+        let hit = bvh
+            .intersect(vec3(0.25, 0.5, 3.5), Vec3::Z, f32::MAX)
+            .unwrap();
+        assert!((hit.t - 0.5).abs() < 1e-5);
+        assert_eq!(hit.triangle / 2, 4);
+        assert_eq!(hit.normal, -Vec3::Z);
+
+        assert!(bvh.occluded(vec3(0.5, 0.5, -1.), Vec3::Z, 2.));
+        assert!(!bvh.occluded(vec3(0.5, 0.5, -1.), Vec3::Z, 0.5));
+        assert!(!bvh.occluded(vec3(2., 0.5, -1.), Vec3::Z, f32::MAX));
+        assert!(bvh
+            .intersect(vec3(0.5, 0.5, 9.5), Vec3::Z, f32::MAX)
+            .is_none());
+    }
+}
@@ -0,0 +1,215 @@
+use std::f32::consts::PI;
+
+use ambient_sys::task::par_map;
+use glam::{vec3, Vec3};
+use serde::{Deserialize, Serialize};
+
+pub mod atlas;
+mod bvh;
+pub mod lightmap;
+pub mod probes;
+
+pub use bvh::{Bvh, Hit};
+pub use lightmap::*;
+pub use probes::*;
+
+/// The largest value that can be stored in an RGBM encoded texel
+pub const RGBM_RANGE: f32 = 8.;
+
+/// Offsets rays from the surface they start on, to avoid hitting it again
+const RAY_BIAS: f32 = 1e-3;
+
+/// The lighting of the scene when baking.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LightingConfig {
+    /// The direction towards the sun.
+    pub sun_direction: Vec3,
+    /// The light of the sun, like `light_diffuse`.
+    pub sun_color: Vec3,
+    /// The light of the sky, which is assumed to be the same in all directions.
+    pub sky_color: Vec3,
+    /// How much light surfaces reflect. This is used for all surfaces, as textures are not taken into account.
+    pub albedo: f32,
+    /// The number of rays to trace per texel or probe.
+    pub samples: u32,
+}
+impl Default for LightingConfig {
+    fn default() -> Self {
+        Self {
+            sun_direction: vec3(-0.2, 1., 1.).normalize(),
+            sun_color: Vec3::ONE * 3.,
+            sky_color: Vec3::ONE * 0.2,
+            albedo: 0.5,
+            samples: 64,
+        }
+    }
+}
+
+/// The geometry that light is traced against when baking.
+pub struct BakeScene {
+    bvh: Bvh,
+}
+impl BakeScene {
+    /// Creates a scene from triangles in world space
+    pub fn new(triangles: Vec<[Vec3; 3]>) -> Self {
+        Self {
+            bvh: Bvh::new(triangles),
+        }
+    }
+
+    pub fn bvh(&self) -> &Bvh {
+        &self.bvh
+    }
+
+    /// The average light arriving at `position` from the hemisphere around `normal`, with one bounce of sun and sky light.
+    ///
+    /// This is the value that replaces `light_ambient` for the point, i.e. the irradiance divided by pi.
+    pub fn irradiance(
+        &self,
+        position: Vec3,
+        normal: Vec3,
+        config: &LightingConfig,
+        seed: u32,
+    ) -> Vec3 {
+        let (tangent, bitangent) = normal.any_orthonormal_pair();
+        let origin = position + normal * RAY_BIAS;
+        let rotation = hash(seed);
+        let mut total = Vec3::ZERO;
+        for i in 0..config.samples {
+            let (u, v) = hammersley(i, config.samples);
+            // Cranley-Patterson rotation, so that neighbouring texels do not share the same banding
+            let (u, v) = ((u + rotation.0).fract(), (v + rotation.1).fract());
+            let local = cosine_hemisphere(u, v);
+            let dir = tangent * local.x + bitangent * local.y + normal * local.z;
+            total += self.radiance(origin, dir, config);
+        }
+        total / config.samples.max(1) as f32
+    }
+
+    /// The light arriving at `origin` from the direction `dir`; the sky if nothing is hit, or the sun and sky light
+    /// bounced off the surface that is hit.
+    pub fn radiance(&self, origin: Vec3, dir: Vec3, config: &LightingConfig) -> Vec3 {
+        let sun_direction = config.sun_direction.normalize();
+        match self.bvh.intersect(origin, dir, f32::MAX) {
+            Some(hit) => {
+                let hit_position = origin + dir * hit.t + hit.normal * RAY_BIAS;
+                let ndotl = hit.normal.dot(sun_direction).max(0.);
+                let sun = if ndotl > 0. && !self.bvh.occluded(hit_position, sun_direction, f32::MAX)
+                {
+                    config.sun_color * ndotl / PI
+                } else {
+                    Vec3::ZERO
+                };
+                config.albedo * (sun + config.sky_color)
+            }
+            None => config.sky_color,
+        }
+    }
+
+    /// Computes [BakeScene::irradiance] for each (position, normal) pair on all cores
+    pub fn irradiance_batch(
+        &self,
+        points: &[Option<(Vec3, Vec3)>],
+        config: &LightingConfig,
+    ) -> Vec<Option<Vec3>> {
+        let mut points = points.iter().enumerate().collect::<Vec<_>>();
+        par_map(&mut points, |&mut (i, point)| {
+            point.map(|(position, normal)| self.irradiance(position, normal, config, i as u32))
+        })
+    }
+}
+
+/// Encodes a linear color as RGBM, where the color is `rgb * a * RGBM_RANGE`
+pub fn encode_rgbm(color: Vec3) -> [u8; 4] {
+    let m = (color.max_element() / RGBM_RANGE).clamp(1. / 255., 1.);
+    let m = (m * 255.).ceil() / 255.;
+    let rgb = (color / (m * RGBM_RANGE)).clamp(Vec3::ZERO, Vec3::ONE) * 255.;
+    [
+        rgb.x.round() as u8,
+        rgb.y.round() as u8,
+        rgb.z.round() as u8,
+        (m * 255.).round() as u8,
+    ]
+}
+
+pub fn decode_rgbm(rgbm: [u8; 4]) -> Vec3 {
+    let [r, g, b, m] = rgbm.map(|x| x as f32 / 255.);
+    vec3(r, g, b) * m * RGBM_RANGE
+}
+
+fn hammersley(i: u32, n: u32) -> (f32, f32) {
+    (
+        (i as f32 + 0.5) / n as f32,
+        i.reverse_bits() as f32 / 2f32.powi(32),
+    )
+}
+
+fn cosine_hemisphere(u: f32, v: f32) -> Vec3 {
+    let r = u.sqrt();
+    let phi = 2. * PI * v;
+    vec3(r * phi.cos(), r * phi.sin(), (1. - u).max(0.).sqrt())
+}
+
+fn hash(seed: u32) -> (f32, f32) {
+    let mut x = seed.wrapping_mul(0x9E37_79B9) ^ 0x85EB_CA6B;
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7FEB_352D);
+    x ^= x >> 15;
+    ((x & 0xFFFF) as f32 / 65536., (x >> 16) as f32 / 65536.)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgbm_roundtrip() {
+        for color in [
+            Vec3::ZERO,
+            vec3(0.05, 0.1, 0.2),
+            vec3(1., 0.5, 0.25),
+            vec3(6., 7., 7.9),
+        ] {
+            let decoded = decode_rgbm(encode_rgbm(color));
+            assert!(
+                (decoded - color).abs().max_element() < color.max_element().max(0.1) * 0.02,
+                "{color} != {decoded}"
+            );
+        }
+    }
+
+    #[test]
+    fn sky_occlusion() {
+        let config = LightingConfig {
+            sun_color: Vec3::ZERO,
+            samples: 256,
+            ..Default::default()
+        };
+        // A floor, and a roof above half of it
+        let scene = BakeScene::new(vec![
+            [
+                vec3(-10., -10., 0.),
+                vec3(10., -10., 0.),
+                vec3(10., 10., 0.),
+            ],
+            [
+                vec3(-10., -10., 0.),
+                vec3(10., 10., 0.),
+                vec3(-10., 10., 0.),
+            ],
+            [
+                vec3(-10., -10., 1.),
+                vec3(10., 10., 1.),
+                vec3(10., -10., 1.),
+            ],
+        ]);
+        let open = scene.irradiance(vec3(-5., 5., 0.), Vec3::Z, &config, 0);
+        let covered = scene.irradiance(vec3(5., -5., 0.), Vec3::Z, &config, 0);
+        assert!((open - config.sky_color).length() < 0.05, "{open}");
+        assert!(
+            covered.x < open.x * 0.6,
+            "{covered} should be darker than {open}"
+        );
+    }
+}
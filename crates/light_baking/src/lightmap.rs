@@ -0,0 +1,264 @@
+use glam::{vec2, vec4, Vec2, Vec3, Vec4};
+use image::RgbaImage;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    atlas::{pack, AtlasRect},
+    encode_rgbm, BakeScene, LightingConfig,
+};
+
+/// The number of texels between charts; these are filled by dilating the charts so that bilinear filtering does not bleed.
+const CHART_PADDING: u32 = 2;
+const MIN_CHART_SIZE: u32 = 4;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LightmapBakeConfig {
+    /// The width and height of the lightmap atlas, in texels.
+    pub atlas_size: u32,
+    /// The lightmap resolution to aim for. This is lowered if the charts do not fit in the atlas.
+    pub texels_per_unit: f32,
+    #[serde(flatten)]
+    pub lighting: LightingConfig,
+}
+impl Default for LightmapBakeConfig {
+    fn default() -> Self {
+        Self {
+            atlas_size: 1024,
+            texels_per_unit: 8.,
+            lighting: Default::default(),
+        }
+    }
+}
+
+/// A mesh to bake a lightmap chart for. Its lightmap UVs (the second UV set) must not overlap, and lie within 0..1.
+#[derive(Debug, Clone)]
+pub struct LightmapChart {
+    /// The mesh in world space
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub lightmap_uvs: Vec<Vec2>,
+    pub indices: Vec<u32>,
+}
+impl LightmapChart {
+    fn surface_area(&self) -> f32 {
+        self.indices
+            .iter()
+            .tuples()
+            .map(|(&a, &b, &c)| {
+                let [a, b, c] = [a, b, c].map(|i| self.positions[i as usize]);
+                (b - a).cross(c - a).length() / 2.
+            })
+            .sum()
+    }
+}
+
+pub struct Lightmap {
+    /// The indirect light of all charts, RGBM encoded (see [encode_rgbm])
+    pub image: RgbaImage,
+    /// The scale (xy) and offset (zw) that maps each chart's lightmap UVs into the atlas
+    pub scale_offsets: Vec<Vec4>,
+}
+
+/// Bakes the indirect light that falls on each chart into a single atlas. `scene` holds the geometry that occludes and
+/// bounces light, which should include the charts themselves.
+///
+/// Fails if the charts don't fit in the atlas even at their smallest size.
+pub fn bake_lightmap(
+    scene: &BakeScene,
+    charts: &[LightmapChart],
+    config: &LightmapBakeConfig,
+) -> anyhow::Result<Lightmap> {
+    let atlas_size = config.atlas_size;
+    let areas = charts
+        .iter()
+        .map(|chart| chart.surface_area())
+        .collect_vec();
+    let mut texels_per_unit = config.texels_per_unit;
+    let rects = loop {
+        let sizes = areas
+            .iter()
+            .map(|area| {
+                ((area.sqrt() * texels_per_unit).ceil() as u32)
+                    .clamp(MIN_CHART_SIZE, atlas_size - 2 * CHART_PADDING)
+            })
+            .collect_vec();
+        match pack(&sizes, atlas_size, CHART_PADDING) {
+            Some(rects) => break rects,
+            // Lowering the resolution further can't make them any smaller
+            None if sizes.iter().all(|&size| size == MIN_CHART_SIZE) => anyhow::bail!(
+                "The {} lightmap charts don't fit in a {atlas_size}x{atlas_size} atlas",
+                charts.len()
+            ),
+            None => texels_per_unit *= 0.75,
+        }
+    };
+
+    let mut texels = vec![None; (atlas_size * atlas_size) as usize];
+    for (chart, rect) in charts.iter().zip(&rects) {
+        rasterize_chart(chart, rect, atlas_size, &mut texels);
+    }
+
+    let mut light = scene.irradiance_batch(&texels, &config.lighting);
+    for _ in 0..CHART_PADDING {
+        dilate(&mut light, atlas_size);
+    }
+
+    let mut image = RgbaImage::new(atlas_size, atlas_size);
+    for (pixel, light) in image.pixels_mut().zip(&light) {
+        pixel.0 = encode_rgbm(light.unwrap_or_default());
+    }
+    Ok(Lightmap {
+        image,
+        scale_offsets: rects
+            .iter()
+            .map(|rect| {
+                let size = atlas_size as f32;
+                vec4(
+                    rect.size as f32 / size,
+                    rect.size as f32 / size,
+                    rect.x as f32 / size,
+                    rect.y as f32 / size,
+                )
+            })
+            .collect(),
+    })
+}
+
+/// Writes the world position and normal at the center of each texel covered by the chart
+fn rasterize_chart(
+    chart: &LightmapChart,
+    rect: &AtlasRect,
+    atlas_size: u32,
+    texels: &mut [Option<(Vec3, Vec3)>],
+) {
+    let size = rect.size as f32;
+    for (&a, &b, &c) in chart.indices.iter().tuples() {
+        let [a, b, c] = [a, b, c].map(|i| i as usize);
+        let uv = [a, b, c].map(|i| chart.lightmap_uvs[i] * size);
+        let min = uv[0].min(uv[1]).min(uv[2]).floor().max(Vec2::ZERO);
+        let max = uv[0].max(uv[1]).max(uv[2]).ceil().min(Vec2::splat(size));
+        for y in min.y as u32..max.y as u32 {
+            for x in min.x as u32..max.x as u32 {
+                let Some([wa, wb, wc]) = barycentric(vec2(x as f32 + 0.5, y as f32 + 0.5), uv)
+                else {
+                    continue;
+                };
+                let position =
+                    chart.positions[a] * wa + chart.positions[b] * wb + chart.positions[c] * wc;
+                let normal =
+                    (chart.normals[a] * wa + chart.normals[b] * wb + chart.normals[c] * wc)
+                        .normalize_or_zero();
+                texels[((rect.y + y) * atlas_size + rect.x + x) as usize] =
+                    Some((position, normal));
+            }
+        }
+    }
+}
+
+fn barycentric(p: Vec2, [a, b, c]: [Vec2; 3]) -> Option<[f32; 3]> {
+    let area = (b - a).perp_dot(c - a);
+    if area.abs() < 1e-12 {
+        return None;
+    }
+    let wa = (b - p).perp_dot(c - p) / area;
+    let wb = (c - p).perp_dot(a - p) / area;
+    let wc = 1. - wa - wb;
+    // Allow a little slack so that texels on the edges between triangles are always covered
+    const EPSILON: f32 = -1e-3;
+    (wa >= EPSILON && wb >= EPSILON && wc >= EPSILON).then_some([wa, wb, wc])
+}
+
+/// Fills empty texels with the average of their filled neighbours
+fn dilate(light: &mut [Option<Vec3>], size: u32) {
+    let source = light.to_vec();
+    let size = size as i32;
+    for y in 0..size {
+        for x in 0..size {
+            let index = (y * size + x) as usize;
+            if source[index].is_some() {
+                continue;
+            }
+            let neighbours = [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                .into_iter()
+                .map(|(dx, dy)| (x + dx, y + dy))
+                .filter(|&(x, y)| x >= 0 && y >= 0 && x < size && y < size)
+                .filter_map(|(x, y)| source[(y * size + x) as usize])
+                .collect_vec();
+            if !neighbours.is_empty() {
+                light[index] = Some(neighbours.iter().sum::<Vec3>() / neighbours.len() as f32);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec3;
+
+    use super::*;
+    use crate::decode_rgbm;
+
+    #[test]
+    fn bake_quad() {
+        let chart = LightmapChart {
+            positions: vec![
+                vec3(0., 0., 0.),
+                vec3(4., 0., 0.),
+                vec3(4., 4., 0.),
+                vec3(0., 4., 0.),
+            ],
+            normals: vec![Vec3::Z; 4],
+            lightmap_uvs: vec![vec2(0., 0.), vec2(1., 0.), vec2(1., 1.), vec2(0., 1.)],
+            indices: vec![0, 1, 2, 0, 2, 3],
+        };
+        let scene = BakeScene::new(vec![
+            [chart.positions[0], chart.positions[1], chart.positions[2]],
+            [chart.positions[0], chart.positions[2], chart.positions[3]],
+        ]);
+        let config = LightmapBakeConfig {
+            atlas_size: 64,
+            texels_per_unit: 4.,
+            ..Default::default()
+        };
+        let lightmap = bake_lightmap(&scene, &[chart.clone(), chart], &config).unwrap();
+
+        assert_eq!(lightmap.scale_offsets.len(), 2);
+        assert_eq!(
+            lightmap.scale_offsets[0],
+            vec4(16. / 64., 16. / 64., 2. / 64., 2. / 64.)
+        );
+        // Nothing occludes the quads, so they only receive sky light
+        let texel = decode_rgbm(lightmap.image.get_pixel(10, 10).0);
+        assert!(
+            (texel - config.lighting.sky_color).length() < 0.01,
+            "{texel}"
+        );
+        // Padding next to a chart is dilated, but texels far away from all charts are not
+        assert_ne!(decode_rgbm(lightmap.image.get_pixel(1, 10).0), Vec3::ZERO);
+        assert_eq!(decode_rgbm(lightmap.image.get_pixel(63, 63).0), Vec3::ZERO);
+    }
+
+    #[test]
+    fn too_many_charts() {
+        let chart = LightmapChart {
+            positions: vec![vec3(0., 0., 0.), vec3(1., 0., 0.), vec3(0., 1., 0.)],
+            normals: vec![Vec3::Z; 3],
+            lightmap_uvs: vec![vec2(0., 0.), vec2(1., 0.), vec2(0., 1.)],
+            indices: vec![0, 1, 2],
+        };
+        let scene = BakeScene::new(vec![[
+            chart.positions[0],
+            chart.positions[1],
+            chart.positions[2],
+        ]]);
+        let config = LightmapBakeConfig {
+            atlas_size: 16,
+            ..Default::default()
+        };
+        // With their padding, only four charts of the smallest size fit, so lowering the resolution never helps
+        let charts = (0..5).map(|_| chart.clone()).collect_vec();
+        assert!(bake_lightmap(&scene, &charts, &config).is_err());
+    }
+}
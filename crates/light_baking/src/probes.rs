@@ -0,0 +1,197 @@
+use std::f32::consts::PI;
+
+use ambient_sys::task::par_map;
+use glam::{UVec3, Vec3, Vec4};
+use serde::{Deserialize, Serialize};
+
+use crate::{hammersley, hash, BakeScene, LightingConfig};
+
+const SH_C0: f32 = 0.282_095;
+const SH_C1: f32 = 0.488_603;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LightProbeBakeConfig {
+    /// The distance between neighbouring probes.
+    pub spacing: f32,
+    /// The largest number of probes along any axis. The spacing is increased if the grid would have more.
+    pub max_probes_per_axis: u32,
+    #[serde(flatten)]
+    pub lighting: LightingConfig,
+}
+impl Default for LightProbeBakeConfig {
+    fn default() -> Self {
+        Self {
+            spacing: 2.,
+            max_probes_per_axis: 32,
+            lighting: Default::default(),
+        }
+    }
+}
+
+/// Light probes placed on a regular, axis aligned grid. Probes are ordered along x first, then y, then z.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LightProbeGrid {
+    /// The position of the first probe
+    pub origin: Vec3,
+    pub spacing: Vec3,
+    /// The number of probes along each axis
+    pub counts: UVec3,
+}
+impl LightProbeGrid {
+    /// A grid that covers the box from `min` to `max`
+    pub fn covering(min: Vec3, max: Vec3, config: &LightProbeBakeConfig) -> Self {
+        let size = (max - min).max(Vec3::ZERO);
+        let max_probes = config.max_probes_per_axis.max(2);
+        let spacing = config
+            .spacing
+            .max(size.max_element() / (max_probes - 1) as f32)
+            .max(f32::EPSILON);
+        let counts = (size / spacing).ceil().as_uvec3() + UVec3::ONE;
+        let counts = counts.max(UVec3::splat(2));
+        Self {
+            origin: min,
+            spacing: Vec3::splat(spacing),
+            counts,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        (self.counts.x * self.counts.y * self.counts.z) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn probe_position(&self, index: usize) -> Vec3 {
+        let index = index as u32;
+        let x = index % self.counts.x;
+        let y = (index / self.counts.x) % self.counts.y;
+        let z = index / (self.counts.x * self.counts.y);
+        self.origin + UVec3::new(x, y, z).as_vec3() * self.spacing
+    }
+}
+
+/// The light around a probe, as L1 spherical harmonics that are already convolved with the cosine lobe and divided by
+/// pi; see [evaluate_sh].
+pub type ProbeSh = [Vec3; 4];
+
+/// The light arriving at a surface with the given normal; like [BakeScene::irradiance], this replaces `light_ambient`
+pub fn evaluate_sh(sh: &ProbeSh, normal: Vec3) -> Vec3 {
+    (sh[0] + sh[1] * normal.x + sh[2] * normal.y + sh[3] * normal.z).max(Vec3::ZERO)
+}
+
+/// Traces rays in all directions from `position`, and projects the light onto L1 spherical harmonics
+pub fn bake_probe(
+    scene: &BakeScene,
+    position: Vec3,
+    config: &LightingConfig,
+    seed: u32,
+) -> ProbeSh {
+    let rotation = hash(seed);
+    let mut sh = [Vec3::ZERO; 4];
+    for i in 0..config.samples {
+        let (u, v) = hammersley(i, config.samples);
+        let (u, v) = ((u + rotation.0).fract(), (v + rotation.1).fract());
+        // Uniform directions on the sphere
+        let z = 1. - 2. * u;
+        let r = (1. - z * z).max(0.).sqrt();
+        let phi = 2. * PI * v;
+        let dir = Vec3::new(r * phi.cos(), r * phi.sin(), z);
+        let radiance = scene.radiance(position, dir, config);
+        sh[0] += radiance * SH_C0;
+        sh[1] += radiance * SH_C1 * dir.x;
+        sh[2] += radiance * SH_C1 * dir.y;
+        sh[3] += radiance * SH_C1 * dir.z;
+    }
+    let weight = 4. * PI / config.samples.max(1) as f32;
+    // Convolving with the cosine lobe scales band 0 by pi and band 1 by 2pi/3
+    [
+        sh[0] * weight * SH_C0,
+        sh[1] * weight * SH_C1 * 2. / 3.,
+        sh[2] * weight * SH_C1 * 2. / 3.,
+        sh[3] * weight * SH_C1 * 2. / 3.,
+    ]
+}
+
+/// Bakes all probes of the grid on all cores
+pub fn bake_light_probes(
+    scene: &BakeScene,
+    grid: &LightProbeGrid,
+    config: &LightingConfig,
+) -> Vec<ProbeSh> {
+    let mut positions = (0..grid.len())
+        .map(|i| (i, grid.probe_position(i)))
+        .collect::<Vec<_>>();
+    par_map(&mut positions, |&mut (i, position)| {
+        bake_probe(scene, position, config, i as u32)
+    })
+}
+
+/// Packs the probes the way `light_probe_grid_sh` stores them; four `Vec4`s per probe, with the coefficients in `xyz`
+pub fn pack_probes(probes: &[ProbeSh]) -> Vec<Vec4> {
+    probes
+        .iter()
+        .flat_map(|sh| sh.map(|c| c.extend(0.)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec3;
+
+    use super::*;
+
+    #[test]
+    fn probes() {
+        let config = LightingConfig {
+            sun_color: Vec3::ZERO,
+            samples: 512,
+            ..Default::default()
+        };
+
+        let sky = bake_probe(&BakeScene::new(vec![]), Vec3::ZERO, &config, 0);
+        for normal in [Vec3::X, Vec3::Z, -Vec3::Z] {
+            let light = evaluate_sh(&sky, normal);
+            assert!((light - config.sky_color).length() < 0.02, "{light}");
+        }
+
+        // A large floor below the probe reflects less light than the sky above it
+        let floor = BakeScene::new(vec![
+            [
+                vec3(-100., -100., 0.),
+                vec3(100., -100., 0.),
+                vec3(100., 100., 0.),
+            ],
+            [
+                vec3(-100., -100., 0.),
+                vec3(100., 100., 0.),
+                vec3(-100., 100., 0.),
+            ],
+        ]);
+        let sh = bake_probe(&floor, vec3(0., 0., 1.), &config, 0);
+        let up = evaluate_sh(&sh, Vec3::Z);
+        let down = evaluate_sh(&sh, -Vec3::Z);
+        assert!(down.x < up.x * 0.8, "{down} should be darker than {up}");
+    }
+
+    #[test]
+    fn grid() {
+        let grid = LightProbeGrid::covering(
+            Vec3::ZERO,
+            vec3(10., 3., 0.),
+            &LightProbeBakeConfig::default(),
+        );
+        assert_eq!(grid.counts, UVec3::new(6, 3, 2));
+        assert_eq!(grid.len(), 36);
+        assert_eq!(grid.probe_position(7), vec3(2., 2., 0.));
+
+        let grid = LightProbeGrid::covering(
+            Vec3::ZERO,
+            vec3(1000., 1., 1.),
+            &LightProbeBakeConfig::default(),
+        );
+        assert_eq!(grid.counts.x, 32);
+    }
+}
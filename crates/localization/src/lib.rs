@@ -0,0 +1,123 @@
+use std::fmt::Display;
+
+use ambient_core::{asset_cache, async_ecs::async_run, runtime};
+use ambient_ecs::{query, Entity, SystemGroup, World};
+use ambient_gpu::settings::SettingsKey;
+use ambient_shared_types::interpolate;
+use ambient_std::{asset_cache::SyncAssetKeyExt, asset_url::AbsAssetUrl};
+use ambient_text::fallback_font_url;
+
+mod table;
+pub use table::*;
+
+pub use ambient_ecs::generated::components::core::localization::{
+    language, localization_keys, localization_values,
+};
+
+/// The language that is used for strings missing from the active language's table.
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// The asset path of the string table for `language`.
+pub fn string_table_path(language: &str) -> String {
+    format!("locale/{language}.toml")
+}
+
+/// Loads the string table for the active language whenever it changes.
+pub fn client_systems() -> SystemGroup {
+    SystemGroup::new(
+        "localization",
+        vec![query(language().changed()).to_system(|q, world, qs, _| {
+            for (_, lang) in q.collect_cloned(world, qs) {
+                let assets = world.resource(asset_cache()).clone();
+                let async_run = world.resource(async_run()).clone();
+                world.resource(runtime()).spawn(async move {
+                    let load = |lang: String| {
+                        let assets = assets.clone();
+                        async move {
+                            let url = AbsAssetUrl::from_asset_key(string_table_path(&lang))?;
+                            let table = StringTable::parse(&url.download_string(&assets).await?)?;
+                            let font = match &table.font {
+                                Some(font) => Some(
+                                    AbsAssetUrl::from_asset_key(font)?
+                                        .to_download_url(&assets)?
+                                        .to_string(),
+                                ),
+                                None => None,
+                            };
+                            anyhow::Ok((table, font))
+                        }
+                    };
+                    let (mut table, font) = match load(lang.clone()).await {
+                        Ok(value) => value,
+                        Err(err) => {
+                            log::warn!("Failed to load the string table for {lang:?}: {err:?}");
+                            Default::default()
+                        }
+                    };
+                    if lang != DEFAULT_LANGUAGE {
+                        match load(DEFAULT_LANGUAGE.to_string()).await {
+                            Ok((fallback, _)) => table.merge_fallback(&fallback),
+                            Err(err) => {
+                                log::warn!("Failed to load the default string table: {err:?}")
+                            }
+                        }
+                    }
+                    async_run.run(move |world| {
+                        // The language may have changed again while this table was loading
+                        if world.resource_opt(language()) != Some(&lang) {
+                            return;
+                        }
+                        let (keys, values) = table.strings.into_iter().unzip();
+                        world.add_resource(localization_keys(), keys);
+                        world.add_resource(localization_values(), values);
+                        if let Some(font) = font {
+                            world.add_resource(fallback_font_url(), font);
+                        }
+                    });
+                });
+            }
+        })],
+    )
+}
+
+/// The resources for localization on the client. The language is taken from the user's settings, or the system language
+/// if it is not set.
+pub fn client_resources(world: &World) -> Entity {
+    let settings = SettingsKey.get(world.resource(asset_cache()));
+    let lang = settings
+        .language
+        .clone()
+        .or_else(system_language)
+        .unwrap_or_else(|| DEFAULT_LANGUAGE.to_string());
+    Entity::new()
+        .with(language(), lang)
+        .with_default(localization_keys())
+        .with_default(localization_values())
+}
+
+/// The language code of the system locale, such as `ja` for `ja_JP.UTF-8`.
+fn system_language() -> Option<String> {
+    #[cfg(not(target_os = "unknown"))]
+    {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find_map(|locale| {
+                let code = locale.split(['_', '.', '@', '-']).next()?.to_lowercase();
+                (!code.is_empty() && code != "c" && code != "posix").then_some(code)
+            })
+    }
+    #[cfg(target_os = "unknown")]
+    {
+        None
+    }
+}
+
+/// Returns the localized string for `key` with `args` interpolated, or `key` itself if there is no string for it.
+pub fn localize(world: &World, key: &str, args: &[(&str, &dyn Display)]) -> String {
+    let keys = world.resource(localization_keys());
+    match keys.binary_search_by(|x| x.as_str().cmp(key)) {
+        Ok(index) => interpolate(&world.resource(localization_values())[index], args),
+        Err(_) => key.to_string(),
+    }
+}
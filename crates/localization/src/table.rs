@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+
+/// The table that holds table-wide settings rather than strings.
+const META_TABLE: &str = "meta";
+
+/// A table of localized strings for a single language.
+///
+/// Tables are TOML files where every value is a string. Nested tables are flattened into dotted keys, so `[menu] play = "Play"`
+/// is looked up as `menu.play`. The optional `[meta]` table holds settings for the table itself:
+///
+/// ```toml
+/// [meta]
+/// font = "fonts/NotoSansJP-Regular.otf"
+///
+/// [menu]
+/// play = "プレイ"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StringTable {
+    /// The asset path of a font to use for characters the default font does not have.
+    pub font: Option<String>,
+    pub strings: BTreeMap<String, String>,
+}
+impl StringTable {
+    pub fn parse(source: &str) -> anyhow::Result<Self> {
+        let root: toml::Table = toml::from_str(source).context("Failed to parse string table")?;
+        let mut table = Self::default();
+        for (key, value) in root {
+            if key == META_TABLE {
+                let meta = value.as_table().context("`meta` must be a table")?;
+                if let Some(font) = meta.get("font") {
+                    table.font = Some(
+                        font.as_str()
+                            .context("`meta.font` must be a string")?
+                            .to_string(),
+                    );
+                }
+            } else {
+                table.flatten(key, value)?;
+            }
+        }
+        Ok(table)
+    }
+
+    fn flatten(&mut self, key: String, value: toml::Value) -> anyhow::Result<()> {
+        match value {
+            toml::Value::String(value) => {
+                self.strings.insert(key, value);
+            }
+            toml::Value::Table(table) => {
+                for (child, value) in table {
+                    self.flatten(format!("{key}.{child}"), value)?;
+                }
+            }
+            other => anyhow::bail!(
+                "The value for `{key}` must be a string, but was a {}",
+                other.type_str()
+            ),
+        }
+        Ok(())
+    }
+
+    /// Serializes this table with all keys flattened.
+    pub fn to_toml(&self) -> String {
+        let mut root = toml::Table::new();
+        for (key, value) in &self.strings {
+            root.insert(key.clone(), toml::Value::String(value.clone()));
+        }
+        if let Some(font) = &self.font {
+            let mut meta = toml::Table::new();
+            meta.insert("font".to_string(), toml::Value::String(font.clone()));
+            root.insert(META_TABLE.to_string(), toml::Value::Table(meta));
+        }
+        root.to_string()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.strings.get(key).map(|x| x.as_str())
+    }
+
+    /// Adds the strings from `fallback` that this table does not have.
+    pub fn merge_fallback(&mut self, fallback: &StringTable) {
+        for (key, value) in &fallback.strings {
+            self.strings
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"
+greeting = "Hello, {name}!"
+
+[meta]
+font = "fonts/NotoSansJP-Regular.otf"
+
+[menu]
+play = "Play"
+settings.audio = "Audio"
+"#;
+
+    #[test]
+    fn parse_and_roundtrip() {
+        let table = StringTable::parse(SOURCE).unwrap();
+        assert_eq!(table.font.as_deref(), Some("fonts/NotoSansJP-Regular.otf"));
+        assert_eq!(table.get("greeting"), Some("Hello, {name}!"));
+        assert_eq!(table.get("menu.play"), Some("Play"));
+        assert_eq!(table.get("menu.settings.audio"), Some("Audio"));
+        assert_eq!(table.get("meta.font"), None);
+        assert_eq!(StringTable::parse(&table.to_toml()).unwrap(), table);
+
+        assert!(StringTable::parse("count = 3").is_err());
+    }
+
+    #[test]
+    fn merge_fallback() {
+        let mut table = StringTable::parse(r#"play = "Jouer""#).unwrap();
+        table.merge_fallback(&StringTable::parse(SOURCE).unwrap());
+        assert_eq!(table.get("play"), Some("Jouer"));
+        assert_eq!(table.get("greeting"), Some("Hello, {name}!"));
+        assert_eq!(table.font, None);
+    }
+}
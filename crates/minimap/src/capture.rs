@@ -0,0 +1,122 @@
+use std::{f32::consts::PI, sync::Arc, time::Duration};
+
+use ambient_core::{
+    asset_cache,
+    camera::{
+        far, near, orthographic_rect, orthographic_reverse, projection, projection_view,
+        OrthographicRect,
+    },
+    gpu, main_scene,
+    transform::{inv_local_to_world, local_to_world},
+};
+use ambient_ecs::{Entity, EntityId, World};
+use ambient_gpu::texture::TextureView;
+use ambient_renderer::{RenderTarget, Renderer, RendererConfig, RendererTarget};
+use ambient_std::color::Color;
+use glam::{Mat4, Quat, UVec2, Vec2, Vec4, Vec4Swizzles};
+
+/// The height of the capture camera; everything from `-CAMERA_HEIGHT` to `CAMERA_HEIGHT` is captured
+const CAMERA_HEIGHT: f32 = 1_000.;
+
+/// Renders the world from straight above into a texture. The top left corner of the texture is at the minimum x and y
+/// of the bounds.
+pub(crate) struct MinimapCapture {
+    pub bounds: Vec4,
+    pub resolution: u32,
+    pub last_capture: Option<Duration>,
+    renderer: Renderer,
+    target: RenderTarget,
+    camera: EntityId,
+}
+impl MinimapCapture {
+    pub fn new(world: &mut World, bounds: Vec4, resolution: u32) -> Self {
+        let (min, max) = (bounds.xy(), bounds.zw());
+        let extent = (max - min).max(Vec2::splat(f32::EPSILON));
+        let size = (extent / extent.max_element() * resolution as f32)
+            .round()
+            .as_uvec2()
+            .max(UVec2::ONE);
+
+        // Looking down, with +x to the right and +y towards the bottom of the image
+        let local_to_world_mat = Mat4::from_rotation_translation(
+            Quat::from_rotation_x(PI),
+            ((min + max) / 2.).extend(CAMERA_HEIGHT),
+        );
+        let inv_local_to_world_mat = local_to_world_mat.inverse();
+        let rect = OrthographicRect {
+            left: -extent.x / 2.,
+            right: extent.x / 2.,
+            top: extent.y / 2.,
+            bottom: -extent.y / 2.,
+        };
+        let (near_plane, far_plane) = (0., CAMERA_HEIGHT * 2.);
+        let projection_mat = orthographic_reverse(
+            rect.left,
+            rect.right,
+            rect.bottom,
+            rect.top,
+            near_plane,
+            far_plane,
+        );
+        let camera = Entity::new()
+            .with(local_to_world(), local_to_world_mat)
+            .with(inv_local_to_world(), inv_local_to_world_mat)
+            .with(near(), near_plane)
+            .with(far(), far_plane)
+            .with(orthographic_rect(), rect)
+            .with(projection(), projection_mat)
+            .with(projection_view(), projection_mat * inv_local_to_world_mat)
+            .spawn(world);
+
+        let assets = world.resource(asset_cache()).clone();
+        let gpu = world.resource(gpu()).clone();
+        Self {
+            bounds,
+            resolution,
+            last_capture: None,
+            renderer: Renderer::new(
+                world,
+                assets,
+                RendererConfig {
+                    scene: main_scene(),
+                    shadows: false,
+                    camera: Some(camera),
+                    ..Default::default()
+                },
+            ),
+            target: RenderTarget::new(gpu, size, None),
+            camera,
+        }
+    }
+
+    pub fn texture(&self) -> Arc<TextureView> {
+        Arc::new(self.target.color_buffer.create_view(&Default::default()))
+    }
+
+    pub fn capture(&mut self, world: &mut World, time: Duration) {
+        let gpu = world.resource(gpu()).clone();
+        let mut encoder = gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Minimap.capture"),
+            });
+        let mut post_submit = Vec::new();
+        self.renderer.render(
+            world,
+            &mut encoder,
+            &mut post_submit,
+            RendererTarget::Target(&self.target),
+            Some(Color::rgba(0., 0., 0., 1.)),
+        );
+        gpu.queue.submit(Some(encoder.finish()));
+        for action in post_submit {
+            action();
+        }
+        self.last_capture = Some(time);
+    }
+
+    /// Despawns the capture camera
+    pub fn destroy(&self, world: &mut World) {
+        world.despawn(self.camera);
+    }
+}
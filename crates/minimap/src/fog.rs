@@ -0,0 +1,141 @@
+use glam::{UVec2, Vec2};
+
+/// The alpha of the fog over areas that have been explored, but are not currently visible
+const EXPLORED_ALPHA: u8 = 160;
+
+/// Which parts of the minimap have been seen, on a grid over the minimap's bounds. The first row is at the minimum y,
+/// like the captured minimap image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FogOfWar {
+    min: Vec2,
+    max: Vec2,
+    size: UVec2,
+    explored: Vec<bool>,
+    visible: Vec<bool>,
+}
+impl FogOfWar {
+    pub fn new(min: Vec2, max: Vec2, size: UVec2) -> Self {
+        let size = size.max(UVec2::ONE);
+        let len = (size.x * size.y) as usize;
+        Self {
+            min,
+            max,
+            size,
+            explored: vec![false; len],
+            visible: vec![false; len],
+        }
+    }
+
+    /// The minimum and maximum corners of the area covered by the fog
+    pub fn bounds(&self) -> (Vec2, Vec2) {
+        (self.min, self.max)
+    }
+
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    /// Hides everything that was visible, while keeping it explored; call this before revealing the current view
+    pub fn clear_visible(&mut self) {
+        self.visible.fill(false);
+    }
+
+    /// Makes the cells whose centers are within `radius` of `center` visible and explored
+    pub fn reveal(&mut self, center: Vec2, radius: f32) {
+        let cell_size = (self.max - self.min) / self.size.as_vec2();
+        if radius <= 0. || cell_size.min_element() <= 0. {
+            return;
+        }
+        let lo = ((center - radius - self.min) / cell_size)
+            .floor()
+            .max(Vec2::ZERO);
+        let hi = ((center + radius - self.min) / cell_size)
+            .ceil()
+            .min(self.size.as_vec2());
+        for y in lo.y as u32..hi.y as u32 {
+            for x in lo.x as u32..hi.x as u32 {
+                let cell_center = self.min + (UVec2::new(x, y).as_vec2() + 0.5) * cell_size;
+                if cell_center.distance_squared(center) <= radius * radius {
+                    let index = (y * self.size.x + x) as usize;
+                    self.visible[index] = true;
+                    self.explored[index] = true;
+                }
+            }
+        }
+    }
+
+    fn cell(&self, pos: Vec2) -> Option<usize> {
+        let uv = (pos - self.min) / (self.max - self.min);
+        if !(0. ..=1.).contains(&uv.x) || !(0. ..=1.).contains(&uv.y) {
+            return None;
+        }
+        let cell = (uv * self.size.as_vec2())
+            .as_uvec2()
+            .min(self.size - UVec2::ONE);
+        Some((cell.y * self.size.x + cell.x) as usize)
+    }
+
+    pub fn is_visible(&self, pos: Vec2) -> bool {
+        matches!(self.cell(pos), Some(i) if self.visible[i])
+    }
+
+    pub fn is_explored(&self, pos: Vec2) -> bool {
+        matches!(self.cell(pos), Some(i) if self.explored[i])
+    }
+
+    /// The fog as an RGBA8 overlay; black where nothing has been seen, dimmed where it is not currently visible and
+    /// transparent where it is
+    pub fn to_rgba(&self) -> Vec<u8> {
+        self.visible
+            .iter()
+            .zip(&self.explored)
+            .flat_map(|(&visible, &explored)| {
+                let alpha = if visible {
+                    0
+                } else if explored {
+                    EXPLORED_ALPHA
+                } else {
+                    u8::MAX
+                };
+                [0, 0, 0, alpha]
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec2;
+
+    use super::*;
+
+    #[test]
+    fn reveal() {
+        let mut fog = FogOfWar::new(Vec2::ZERO, vec2(100., 50.), UVec2::new(10, 5));
+        fog.reveal(vec2(25., 25.), 10.);
+        assert!(fog.is_visible(vec2(25., 25.)));
+        assert!(fog.is_visible(vec2(19., 25.)));
+        assert!(!fog.is_visible(vec2(45., 25.)));
+        assert!(!fog.is_visible(vec2(-5., 25.)));
+
+        fog.clear_visible();
+        assert!(!fog.is_visible(vec2(25., 25.)));
+        assert!(fog.is_explored(vec2(25., 25.)));
+        assert!(!fog.is_explored(vec2(75., 25.)));
+
+        // Circles that reach past the edges only reveal the cells inside the bounds
+        fog.reveal(vec2(100., 50.), 15.);
+        assert!(fog.is_visible(vec2(100., 50.)));
+        assert!(fog.is_visible(vec2(95., 45.)));
+    }
+
+    #[test]
+    fn rgba() {
+        let mut fog = FogOfWar::new(Vec2::ZERO, vec2(2., 1.), UVec2::new(2, 1));
+        assert_eq!(fog.to_rgba(), [0, 0, 0, 255, 0, 0, 0, 255]);
+        fog.reveal(vec2(0.5, 0.5), 0.1);
+        assert_eq!(fog.to_rgba(), [0, 0, 0, 0, 0, 0, 0, 255]);
+        fog.clear_visible();
+        assert_eq!(fog.to_rgba(), [0, 0, 0, EXPLORED_ALPHA, 0, 0, 0, 255]);
+    }
+}
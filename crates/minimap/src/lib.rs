@@ -0,0 +1,370 @@
+use std::{sync::Arc, time::Duration};
+
+use ambient_core::{
+    asset_cache,
+    async_ecs::async_run,
+    gpu,
+    player::{local_user_id, user_id},
+    runtime, time,
+    transform::{get_world_position, translation},
+};
+use ambient_ecs::{
+    children, components,
+    generated::components::core::layout::{
+        align_horizontal_end, docking_top, fit_horizontal_parent, fit_vertical_children, height,
+        orientation_vertical, width,
+    },
+    query, EntityId, FnSystem, SystemGroup, World,
+};
+use ambient_element::{element_component, Element, ElementComponentExt, Hooks};
+use ambient_gpu::{
+    texture::{Texture, TextureView},
+    texture_loaders::TextureFromUrl,
+};
+use ambient_renderer::transparency_group;
+use ambient_std::{asset_cache::AsyncAssetKeyExt, asset_url::AbsAssetUrl};
+use ambient_ui_native::{
+    background_color, border_radius, Corners, Flow, Image, Rectangle, UIBase, UIExt, WindowSized,
+    STREET,
+};
+use glam::{vec3, UVec2, Vec2, Vec4, Vec4Swizzles};
+use parking_lot::Mutex;
+
+mod capture;
+mod fog;
+use capture::MinimapCapture;
+pub use fog::*;
+
+pub use ambient_ecs::generated::components::core::minimap::{
+    minimap_bounds, minimap_capture_interval, minimap_fog_of_war, minimap_image_from_url,
+    minimap_marker, minimap_resolution, minimap_size, minimap_vision_radius,
+};
+
+components!("minimap", {
+    @[Resource]
+    minimap_capture: Arc<Mutex<MinimapCapture>>,
+    /// The image of the minimap; either captured from the world or loaded from `minimap_image_from_url`
+    @[Resource]
+    minimap_image: Arc<TextureView>,
+    @[Resource]
+    minimap_fog: FogOfWar,
+    /// The fog of war as an overlay for the minimap image
+    @[Resource]
+    minimap_fog_image: Arc<TextureView>,
+});
+
+const DEFAULT_RESOLUTION: u32 = 512;
+const DEFAULT_CAPTURE_INTERVAL: f32 = 5.;
+const DEFAULT_SIZE: f32 = 200.;
+/// The number of fog of war cells along the longest side of the minimap
+const FOG_RESOLUTION: u32 = 128;
+const MARKER_SIZE: f32 = 8.;
+
+/// Returns the entity that configures the minimap, and its bounds
+fn get_minimap(world: &World) -> Option<(EntityId, Vec4)> {
+    query(minimap_bounds())
+        .iter(world, None)
+        .map(|(id, bounds)| (id, *bounds))
+        .min_by_key(|(id, _)| *id)
+}
+
+/// Returns true if the entity's vision counts for the local player
+fn is_local_vision(world: &World, id: EntityId) -> bool {
+    match world.get_ref(id, user_id()) {
+        Ok(owner) => world.resource_opt(local_user_id()) == Some(owner),
+        Err(_) => true,
+    }
+}
+
+/// Captures the minimap from the world or loads its image, and updates the fog of war from the local player's vision.
+pub fn client_systems() -> SystemGroup {
+    SystemGroup::new(
+        "minimap_client",
+        vec![
+            Box::new(FnSystem::new(|world, _| update_capture(world))),
+            query(minimap_image_from_url().changed()).to_system(|q, world, qs, _| {
+                for (_, url) in q.collect_cloned(world, qs) {
+                    let url = match AbsAssetUrl::parse(url) {
+                        Ok(value) => value,
+                        Err(err) => {
+                            log::warn!("Failed to parse minimap_image_from_url url: {:?}", err);
+                            continue;
+                        }
+                    };
+                    let assets = world.resource(asset_cache()).clone();
+                    let async_run = world.resource(async_run()).clone();
+                    world.resource(runtime()).spawn(async move {
+                        let texture = TextureFromUrl {
+                            url,
+                            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                        }
+                        .get(&assets)
+                        .await;
+                        match texture {
+                            Err(err) => {
+                                log::warn!("Failed to load minimap image: {:?}", err);
+                            }
+                            Ok(texture) => {
+                                let view = Arc::new(texture.create_view(&Default::default()));
+                                async_run.run(move |world| {
+                                    world.add_resource(minimap_image(), view);
+                                });
+                            }
+                        }
+                    });
+                }
+            }),
+            Box::new(FnSystem::new(|world, _| update_fog(world))),
+        ],
+    )
+}
+
+fn remove_capture(world: &mut World) {
+    if let Some(capture) = world.resource_opt(minimap_capture()).cloned() {
+        capture.lock().destroy(world);
+        world
+            .remove_component(world.resource_entity(), minimap_capture())
+            .ok();
+    }
+}
+
+/// Captures the world into the minimap image whenever the capture interval has elapsed, unless an image is provided
+fn update_capture(world: &mut World) {
+    let minimap =
+        get_minimap(world).filter(|(id, _)| !world.has_component(*id, minimap_image_from_url()));
+    let Some((id, bounds)) = minimap else {
+        remove_capture(world);
+        return;
+    };
+    let resolution = world
+        .get(id, minimap_resolution())
+        .unwrap_or(DEFAULT_RESOLUTION)
+        .clamp(1, 4096);
+    let current = world
+        .resource_opt(minimap_capture())
+        .cloned()
+        .filter(|capture| {
+            let capture = capture.lock();
+            capture.bounds == bounds && capture.resolution == resolution
+        });
+    let capture = match current {
+        Some(capture) => capture,
+        None => {
+            remove_capture(world);
+            let capture = MinimapCapture::new(world, bounds, resolution);
+            world.add_resource(minimap_image(), capture.texture());
+            let capture = Arc::new(Mutex::new(capture));
+            world.add_resource(minimap_capture(), capture.clone());
+            capture
+        }
+    };
+
+    let time = *world.resource(time());
+    let interval = world
+        .get(id, minimap_capture_interval())
+        .unwrap_or(DEFAULT_CAPTURE_INTERVAL);
+    let mut capture = capture.lock();
+    let due = match capture.last_capture {
+        None => true,
+        Some(_) if interval <= 0. => false,
+        Some(last) => time >= last + Duration::from_secs_f32(interval),
+    };
+    if due {
+        capture.capture(world, time);
+    }
+}
+
+/// Reveals the fog of war around the local player's entities, and uploads it if it changed
+fn update_fog(world: &mut World) {
+    let minimap =
+        get_minimap(world).filter(|(id, _)| world.has_component(*id, minimap_fog_of_war()));
+    let Some((_, bounds)) = minimap else {
+        world
+            .remove_component(world.resource_entity(), minimap_fog())
+            .ok();
+        return;
+    };
+    let (min, max) = (bounds.xy(), bounds.zw());
+    let extent = (max - min).max(Vec2::splat(f32::EPSILON));
+    let size = (extent / extent.max_element() * FOG_RESOLUTION as f32)
+        .ceil()
+        .as_uvec2();
+    let mut fog = match world.resource_opt(minimap_fog()) {
+        Some(fog) if fog.bounds() == (min, max) && fog.size() == size => fog.clone(),
+        _ => FogOfWar::new(min, max, size),
+    };
+
+    fog.clear_visible();
+    for (id, &radius) in query(minimap_vision_radius()).iter(world, None) {
+        if !is_local_vision(world, id) {
+            continue;
+        }
+        if let Ok(position) = get_world_position(world, id) {
+            fog.reveal(position.truncate(), radius);
+        }
+    }
+
+    if world.resource_opt(minimap_fog()) == Some(&fog) {
+        return;
+    }
+    let image = match world.resource_opt(minimap_fog_image()) {
+        Some(image)
+            if image.texture.size.width == fog.size().x
+                && image.texture.size.height == fog.size().y =>
+        {
+            image.clone()
+        }
+        _ => {
+            let image = Arc::new(
+                Arc::new(create_fog_texture(world, fog.size())).create_view(&Default::default()),
+            );
+            world.add_resource(minimap_fog_image(), image.clone());
+            image
+        }
+    };
+    image.texture.write(&fog.to_rgba());
+    world.add_resource(minimap_fog(), fog);
+}
+
+fn create_fog_texture(world: &World, size: UVec2) -> Texture {
+    Texture::new(
+        world.resource(gpu()).clone(),
+        &wgpu::TextureDescriptor {
+            label: Some("Minimap.fog"),
+            size: wgpu::Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        },
+    )
+}
+
+#[derive(Debug, Clone)]
+struct MinimapView {
+    size: Vec2,
+    image: Option<Arc<TextureView>>,
+    fog: Option<Arc<TextureView>>,
+    /// The positions of the markers in pixels from the top left corner of the minimap, and their colors
+    markers: Vec<(Vec2, Vec4)>,
+}
+impl PartialEq for MinimapView {
+    fn eq(&self, other: &Self) -> bool {
+        fn same(a: &Option<Arc<TextureView>>, b: &Option<Arc<TextureView>>) -> bool {
+            match (a, b) {
+                (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+                (None, None) => true,
+                _ => false,
+            }
+        }
+        self.size == other.size
+            && same(&self.image, &other.image)
+            && same(&self.fog, &other.fog)
+            && self.markers == other.markers
+    }
+}
+
+fn collect_view(world: &World) -> Option<MinimapView> {
+    let (id, bounds) = get_minimap(world)?;
+    let ui_size = world.get(id, minimap_size()).unwrap_or(DEFAULT_SIZE);
+    if ui_size <= 0. {
+        return None;
+    }
+    let (min, max) = (bounds.xy(), bounds.zw());
+    let extent = (max - min).max(Vec2::splat(f32::EPSILON));
+    let size = extent / extent.max_element() * ui_size;
+    let fog = world.resource_opt(minimap_fog());
+
+    let mut markers = query((minimap_marker(), translation()))
+        .iter(world, None)
+        .filter_map(|(id, (&color, _))| {
+            let position = get_world_position(world, id).ok()?.truncate();
+            let uv = (position - min) / extent;
+            if !(0. ..=1.).contains(&uv.x) || !(0. ..=1.).contains(&uv.y) {
+                return None;
+            }
+            // Only markers that the local player can currently see are shown through the fog
+            let hidden = match fog {
+                Some(fog) => {
+                    !fog.is_visible(position)
+                        && !(world.has_component(id, minimap_vision_radius())
+                            && is_local_vision(world, id))
+                }
+                None => false,
+            };
+            (!hidden).then_some((id, (uv * size, color)))
+        })
+        .collect::<Vec<_>>();
+    // Keep the order stable between frames
+    markers.sort_by_key(|(id, _)| *id);
+
+    Some(MinimapView {
+        size,
+        image: world.resource_opt(minimap_image()).cloned(),
+        fog: fog.and(world.resource_opt(minimap_fog_image()).cloned()),
+        markers: markers.into_iter().map(|(_, marker)| marker).collect(),
+    })
+}
+
+/// Displays the minimap in the top right corner of the screen, if there is one.
+#[element_component]
+pub fn Minimap(hooks: &mut Hooks) -> Element {
+    let (view, set_view) = hooks.use_state(None::<MinimapView>);
+    {
+        let view = view.clone();
+        hooks.use_frame(move |world| {
+            let next = collect_view(world);
+            if next != view {
+                set_view(next);
+            }
+        });
+    }
+    let Some(view) = view else {
+        return Element::new();
+    };
+
+    let mut layers = vec![Image {
+        texture: view.image,
+    }
+    .el()
+    .with(width(), view.size.x)
+    .with(height(), view.size.y)];
+    if let Some(fog) = view.fog {
+        layers.push(
+            Image { texture: Some(fog) }
+                .el()
+                .with(width(), view.size.x)
+                .with(height(), view.size.y)
+                .with(transparency_group(), 0)
+                .with(translation(), vec3(0., 0., -0.001)),
+        );
+    }
+    layers.extend(view.markers.into_iter().map(|(position, color)| {
+        Rectangle
+            .el()
+            .with(width(), MARKER_SIZE)
+            .with(height(), MARKER_SIZE)
+            .with(background_color(), color)
+            .with(border_radius(), Corners::even(MARKER_SIZE / 2.))
+            .with(translation(), (position - MARKER_SIZE / 2.).extend(-0.002))
+    }));
+
+    WindowSized::el([Flow::el([UIBase
+        .el()
+        .init_default(children())
+        .with(width(), view.size.x)
+        .with(height(), view.size.y)
+        .children(layers)])
+    .with_default(docking_top())
+    .with_default(orientation_vertical())
+    .with_default(align_horizontal_end())
+    .with_default(fit_horizontal_parent())
+    .with_default(fit_vertical_children())
+    .with_padding_even(STREET)])
+}
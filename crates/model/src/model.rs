@@ -14,7 +14,8 @@ use ambient_core::{
 };
 use ambient_ecs::{query, ComponentDesc, Entity, EntityId, World};
 use ambient_renderer::{
-    cast_shadows, color, gpu_primitives_lod, gpu_primitives_mesh,
+    cast_shadows, color, gpu_primitives_lod, gpu_primitives_mesh, light_probe_grid,
+    light_probe_grid_origin, light_probe_grid_sh, light_probe_grid_spacing, lightmap_url,
     lod::cpu_lod_visible,
     primitives,
     skinning::{self, Skin, SkinsBuffer, SkinsBufferKey},
@@ -100,6 +101,10 @@ impl Model {
             .collect_vec();
             self.0.add_component(id, primitives(), prims).unwrap();
         }
+        if let Some(url) = self.0.resource_opt(lightmap_url()) {
+            let url = model_url.resolve(url)?.to_string();
+            self.0.add_resource(lightmap_url(), url);
+        }
         Ok(())
     }
     pub fn name(&self) -> Option<&String> {
@@ -144,6 +149,33 @@ impl Model {
                 .with(world_bounding_aabb(), aabb)
                 .with(world_bounding_sphere(), aabb.to_sphere())
         }
+        if let Some(url) = self.0.resource_opt(lightmap_url()) {
+            root_components.set(lightmap_url(), url.clone());
+        }
+        if let Some(counts) = self.0.resource_opt(light_probe_grid()) {
+            root_components.set(light_probe_grid(), *counts);
+            root_components.set(
+                light_probe_grid_origin(),
+                self.0
+                    .resource_opt(light_probe_grid_origin())
+                    .copied()
+                    .unwrap_or_default(),
+            );
+            root_components.set(
+                light_probe_grid_spacing(),
+                self.0
+                    .resource_opt(light_probe_grid_spacing())
+                    .copied()
+                    .unwrap_or(Vec3::ONE),
+            );
+            root_components.set(
+                light_probe_grid_sh(),
+                self.0
+                    .resource_opt(light_probe_grid_sh())
+                    .cloned()
+                    .unwrap_or_default(),
+            );
+        }
 
         // See README.md
         let animatable = opts
@@ -14,11 +14,11 @@ use ambient_model::{
     model_skin_ix, model_skins, pbr_renderer_primitives_from_url, Model, ModelSkin,
     PbrRenderPrimitiveFromUrl,
 };
-use ambient_renderer::materials::pbr_material::PbrMaterialDesc;
+use ambient_renderer::{materials::pbr_material::PbrMaterialDesc, morphing::morph_weights};
 use ambient_std::{
     asset_cache::AssetCache,
     asset_url::AbsAssetUrl,
-    mesh::{flip_winding, generate_tangents, MeshBuilder},
+    mesh::{flip_winding, generate_tangents, MeshBuilder, MorphTarget},
     shapes::AABB,
 };
 use anyhow::Context;
@@ -112,6 +112,24 @@ pub async fn import(
                 Vec::new()
             };
 
+            let vertex_count = positions.len();
+            let morph_targets = reader
+                .read_morph_targets()
+                .map(
+                    |(target_positions, target_normals, target_tangents)| MorphTarget {
+                        positions: target_positions
+                            .map(|x| x.map(Vec3::from).collect())
+                            .unwrap_or_else(|| vec![Vec3::ZERO; vertex_count]),
+                        normals: target_normals
+                            .map(|x| x.map(Vec3::from).collect())
+                            .unwrap_or_default(),
+                        tangents: target_tangents
+                            .map(|x| x.map(Vec3::from).collect())
+                            .unwrap_or_default(),
+                    },
+                )
+                .collect_vec();
+
             let cpu_mesh = MeshBuilder {
                 positions,
                 normals,
@@ -120,6 +138,7 @@ pub async fn import(
                 indices,
                 joint_indices,
                 joint_weights,
+                morph_targets,
                 ..MeshBuilder::default()
             }
             .build()?;
@@ -148,7 +167,7 @@ pub async fn import(
                 let target = AnimationTarget::BinderId(animation_bind_id_from_name(
                     channel.target().node().name().unwrap_or(""),
                 ));
-                let inputs = reader.read_inputs().unwrap().collect();
+                let inputs: Vec<f32> = reader.read_inputs().unwrap().collect();
                 match reader.read_outputs() {
                     Some(ReadOutputs::Translations(data)) => AnimationTrack {
                         target,
@@ -174,6 +193,18 @@ pub async fn import(
                             data: data.into_f32().map(|v| Quat::from_slice(&v)).collect(),
                         },
                     },
+                    Some(ReadOutputs::MorphTargetWeights(data)) => {
+                        let data = data.into_f32().collect_vec();
+                        let n_targets = (data.len() / inputs.len().max(1)).max(1);
+                        AnimationTrack {
+                            target,
+                            inputs,
+                            outputs: AnimationOutputs::Weights {
+                                component: morph_weights(),
+                                data: data.chunks(n_targets).map(|x| x.to_vec()).collect(),
+                            },
+                        }
+                    }
                     _ => unimplemented!(),
                 }
             })
@@ -338,6 +369,21 @@ pub async fn import(
                 if let Some(aabb) = AABB::unions(&aabbs) {
                     ed.set(local_bounding_aabb(), aabb);
                 }
+
+                let n_targets = mesh_
+                    .primitives()
+                    .map(|primitive| primitive.morph_targets().len())
+                    .max()
+                    .unwrap_or(0);
+                if n_targets > 0 {
+                    let mut weights = node
+                        .weights()
+                        .or_else(|| mesh_.weights())
+                        .map(|x| x.to_vec())
+                        .unwrap_or_default();
+                    weights.resize(n_targets, 0.);
+                    ed.set(morph_weights(), weights);
+                }
             }
 
             if let Some(skin) = node.skin() {
@@ -347,10 +347,9 @@ impl ModelTransform {
                                         data: data.iter().map(|x| *x * *anim_scale).collect(),
                                     }
                                 }
-                                AnimationOutputs::Quat {
-                                    component: _,
-                                    data: _,
-                                } => unreachable!(),
+                                AnimationOutputs::Quat { .. }
+                                | AnimationOutputs::QuatQuantized { .. }
+                                | AnimationOutputs::Weights { .. } => unreachable!(),
                                 AnimationOutputs::Vec3Field {
                                     component,
                                     field,
@@ -1,6 +1,6 @@
 use std::{collections::HashMap, io::Cursor, path::PathBuf, sync::Arc};
 
-use ambient_animation::{animation_bind_id_from_name, AnimationClip};
+use ambient_animation::{animation_bind_id_from_name, AnimationClip, AnimationCompression};
 use ambient_core::{
     bounding::local_bounding_aabb,
     hierarchy::children,
@@ -10,6 +10,10 @@ use ambient_core::{
 use ambient_ecs::{
     query, query_mut, Component, ComponentValue, Entity, EntityId, FrameEvent, System, World,
 };
+use ambient_light_baking::{
+    bake_light_probes, bake_lightmap, pack_probes, BakeScene, LightProbeBakeConfig, LightProbeGrid,
+    LightmapBakeConfig, LightmapChart,
+};
 use ambient_model::{
     animation_bind_id, model_from_url, model_skin_ix, model_skins,
     pbr_renderer_primitives_from_url, Model, PbrRenderPrimitiveFromUrl,
@@ -23,7 +27,8 @@ use ambient_physics::{
     physx::PhysicsKey,
 };
 use ambient_renderer::{
-    double_sided,
+    double_sided, light_probe_grid, light_probe_grid_origin, light_probe_grid_sh,
+    light_probe_grid_spacing, lightmap_scale_offset, lightmap_url,
     lod::{gpu_lod, lod_cutoffs, LodCutoffs},
     materials::pbr_material::PbrMaterialDesc,
 };
@@ -36,7 +41,7 @@ use ambient_std::{
 };
 use anyhow::Context;
 use futures::FutureExt;
-use glam::{Mat4, Vec3};
+use glam::{Mat3, Mat4, Vec3};
 use image::{ImageOutputFormat, RgbaImage};
 use itertools::Itertools;
 use ordered_float::Float;
@@ -557,6 +562,11 @@ impl ModelCrate {
             cap_texture_size(image, max_size);
         }
     }
+    pub fn compress_animations(&mut self, config: &AnimationCompression) {
+        for clip in self.animations.content.values_mut() {
+            clip.compress(config);
+        }
+    }
     pub fn update_transforms(&mut self) {
         TransformSystem::new().run(self.model_world_mut(), &FrameEvent);
     }
@@ -576,6 +586,114 @@ impl ModelCrate {
         self.model_mut().remove_non_storage_matrices();
     }
 
+    /// Bakes the indirect light of the model into a lightmap atlas. Static nodes whose meshes have a second UV set get
+    /// a region in the atlas; all other nodes still occlude and bounce light. Call this after [ModelCrate::finalize_model].
+    pub fn bake_lightmap(&mut self, config: &LightmapBakeConfig) -> anyhow::Result<()> {
+        let (scene, charts) = self.bake_scene();
+        if charts.is_empty() {
+            log::warn!("No static meshes with lightmap UVs to bake a lightmap for");
+            return Ok(());
+        }
+
+        let (ids, charts): (Vec<_>, Vec<_>) = charts.into_iter().unzip();
+        let lightmap = bake_lightmap(&scene, &charts, config)?;
+        let path = dotdot_path(self.images.insert("lightmap", lightmap.image).path);
+        let world = self.model_world_mut();
+        for (id, scale_offset) in ids.into_iter().zip(lightmap.scale_offsets) {
+            world
+                .add_component(id, lightmap_scale_offset(), scale_offset)
+                .unwrap();
+        }
+        world.add_resource(lightmap_url(), path.to_string());
+        Ok(())
+    }
+    /// Bakes a grid of light probes that covers the model, which lights the entities that are not lightmapped.
+    /// Call this after [ModelCrate::finalize_model].
+    pub fn bake_light_probes(&mut self, config: &LightProbeBakeConfig) {
+        let (scene, _) = self.bake_scene();
+        let Some((min, max)) = scene.bvh().bounds() else {
+            log::warn!("No meshes to bake light probes for");
+            return;
+        };
+        let grid = LightProbeGrid::covering(min, max, config);
+        let probes = bake_light_probes(&scene, &grid, &config.lighting);
+        let world = self.model_world_mut();
+        world.add_resource(light_probe_grid(), grid.counts);
+        world.add_resource(light_probe_grid_origin(), grid.origin);
+        world.add_resource(light_probe_grid_spacing(), grid.spacing);
+        world.add_resource(light_probe_grid_sh(), pack_probes(&probes));
+    }
+    /// Collects the triangles of all nodes in model space, and a lightmap chart for each static node with lightmap UVs
+    fn bake_scene(&mut self) -> (BakeScene, Vec<(EntityId, LightmapChart)>) {
+        self.update_transforms();
+        let world_transform = self.model().get_transform().unwrap_or_default();
+        let world = self.model_world();
+        let mut triangles = Vec::new();
+        let mut charts = Vec::new();
+        for (id, prims) in query(pbr_renderer_primitives_from_url()).collect_cloned(world, None) {
+            let Some(min_lod) = prims.iter().map(|x| x.lod).min() else {
+                continue;
+            };
+            let transform = world_transform
+                * world.get(id, local_to_world()).unwrap_or_default()
+                * world.get(id, mesh_to_local()).unwrap_or_default();
+            let normal_transform = Mat3::from_mat4(transform).inverse().transpose();
+            // Skinned meshes move, so they can't be lightmapped
+            let is_static = !world.has_component(id, model_skin_ix());
+            let mut chart = LightmapChart {
+                positions: Vec::new(),
+                normals: Vec::new(),
+                lightmap_uvs: Vec::new(),
+                indices: Vec::new(),
+            };
+            for primitive in prims.into_iter().filter(|x| x.lod == min_lod) {
+                let Some(mesh) = self
+                    .meshes
+                    .loc
+                    .id_from_path(primitive.mesh.path())
+                    .and_then(|id| self.meshes.content.get(&id))
+                else {
+                    continue;
+                };
+                let positions = mesh
+                    .positions()
+                    .iter()
+                    .map(|&p| transform.transform_point3(p))
+                    .collect_vec();
+                triangles.extend(
+                    mesh.indices()
+                        .iter()
+                        .tuples()
+                        .map(|(&a, &b, &c)| [a, b, c].map(|i| positions[i as usize])),
+                );
+                match mesh.texcoords.get(1) {
+                    Some(uvs)
+                        if is_static
+                            && uvs.len() == positions.len()
+                            && mesh.normals().len() == positions.len() =>
+                    {
+                        let offset = chart.positions.len() as u32;
+                        chart
+                            .indices
+                            .extend(mesh.indices().iter().map(|i| i + offset));
+                        chart.positions.extend(positions);
+                        chart.normals.extend(
+                            mesh.normals()
+                                .iter()
+                                .map(|&n| (normal_transform * n).normalize_or_zero()),
+                        );
+                        chart.lightmap_uvs.extend(uvs);
+                    }
+                    _ => {}
+                }
+            }
+            if !chart.indices.is_empty() {
+                charts.push((id, chart));
+            }
+        }
+        (BakeScene::new(triangles), charts)
+    }
+
     pub fn create_prefab_from_model(&mut self) {
         self.create_prefab(Entity::new().with(
             model_from_url(),
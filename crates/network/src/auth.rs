@@ -0,0 +1,166 @@
+//! Authentication of the users that connect to a server. By default, clients connect as whatever user id they ask
+//! for; a server can instead require a proof of identity from a platform, which the client sends along with its
+//! [ClientRequest::Connect](crate::proto::ClientRequest::Connect).
+
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// A proof of identity that a platform issued to the user of a client
+#[derive(Clone, Serialize, Deserialize)]
+pub enum AuthTicket {
+    /// A session ticket of the Steam user, whose user id is [steam_user_id]
+    Steam(Vec<u8>),
+}
+impl std::fmt::Debug for AuthTicket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The tickets are secrets
+        match self {
+            AuthTicket::Steam(_) => f.write_str("Steam(..)"),
+        }
+    }
+}
+
+/// The user id of the Steam user `steam_id`
+pub fn steam_user_id(steam_id: u64) -> String {
+    format!("steam:{steam_id}")
+}
+
+/// Decides whether a client may connect as the user it claims to be
+#[async_trait]
+pub trait Authenticator: Send + Sync {
+    /// Fails if the client that asked to connect as `user_id` with `ticket` is not that user
+    async fn authenticate(&self, user_id: &str, ticket: Option<&AuthTicket>) -> anyhow::Result<()>;
+}
+
+/// Lets every client connect as any user; the default
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OpenAuthenticator;
+#[async_trait]
+impl Authenticator for OpenAuthenticator {
+    async fn authenticate(
+        &self,
+        _user_id: &str,
+        _ticket: Option<&AuthTicket>,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Only lets Steam users connect, after checking their session tickets with the Steam Web API
+#[derive(Debug, Clone)]
+pub struct SteamAuthenticator {
+    pub app_id: u32,
+    /// The publisher Web API key of the app
+    pub web_api_key: String,
+}
+impl SteamAuthenticator {
+    const AUTHENTICATE_URL: &str =
+        "https://partner.steam-api.com/ISteamUserAuth/AuthenticateUserTicket/v1/";
+}
+#[async_trait]
+impl Authenticator for SteamAuthenticator {
+    async fn authenticate(&self, user_id: &str, ticket: Option<&AuthTicket>) -> anyhow::Result<()> {
+        let Some(AuthTicket::Steam(ticket)) = ticket else {
+            bail!("User {user_id:?} has no Steam session ticket");
+        };
+
+        let app_id = self.app_id.to_string();
+        let ticket = hex::encode(ticket);
+        let response: SteamResponse = reqwest::Client::new()
+            .get(Self::AUTHENTICATE_URL)
+            .query(&[
+                ("key", self.web_api_key.as_str()),
+                ("appid", app_id.as_str()),
+                ("ticket", ticket.as_str()),
+            ])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context("Failed to reach the Steam Web API")?
+            .json()
+            .await
+            .context("Invalid response from the Steam Web API")?;
+
+        response.response.check(user_id)
+    }
+}
+
+#[derive(Deserialize)]
+struct SteamResponse {
+    response: SteamResponseBody,
+}
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SteamResponseBody {
+    Params {
+        steamid: String,
+        #[serde(default)]
+        vacbanned: bool,
+        #[serde(default)]
+        publisherbanned: bool,
+    },
+    Error {
+        errordesc: String,
+    },
+}
+impl SteamResponseBody {
+    /// Fails unless Steam vouched for the ticket of `user_id` and the user is not banned
+    fn check(self, user_id: &str) -> anyhow::Result<()> {
+        match self {
+            SteamResponseBody::Params {
+                steamid,
+                vacbanned,
+                publisherbanned,
+            } => {
+                let steam_id = steamid.parse().context("Invalid Steam id")?;
+                if steam_user_id(steam_id) != user_id {
+                    bail!("The Steam session ticket of {user_id:?} belongs to another user");
+                }
+                if vacbanned {
+                    bail!("User {user_id:?} is VAC banned");
+                }
+                if publisherbanned {
+                    bail!("User {user_id:?} is banned by the publisher");
+                }
+                Ok(())
+            }
+            SteamResponseBody::Error { errordesc } => {
+                bail!("Steam refused the session ticket of {user_id:?}: {errordesc}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(json: &str, user_id: &str) -> anyhow::Result<()> {
+        serde_json::from_str::<SteamResponse>(json)
+            .unwrap()
+            .response
+            .check(user_id)
+    }
+
+    #[test]
+    fn accepts_the_owner_of_the_ticket() {
+        let json = r#"{"response":{"params":{"result":"OK","steamid":"76561197960287930","ownersteamid":"76561197960287930","vacbanned":false,"publisherbanned":false}}}"#;
+        assert!(check(json, "steam:76561197960287930").is_ok());
+        assert!(check(json, "steam:76561197960287931").is_err());
+    }
+
+    #[test]
+    fn rejects_banned_users() {
+        let vac = r#"{"response":{"params":{"result":"OK","steamid":"76561197960287930","vacbanned":true,"publisherbanned":false}}}"#;
+        assert!(check(vac, "steam:76561197960287930").is_err());
+        let publisher = r#"{"response":{"params":{"result":"OK","steamid":"76561197960287930","vacbanned":false,"publisherbanned":true}}}"#;
+        assert!(check(publisher, "steam:76561197960287930").is_err());
+    }
+
+    #[test]
+    fn rejects_refused_tickets() {
+        let json = r#"{"response":{"error":{"errorcode":101,"errordesc":"Invalid ticket"}}}"#;
+        assert!(check(json, "steam:76561197960287930").is_err());
+    }
+}
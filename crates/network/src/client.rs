@@ -1,13 +1,13 @@
-use ambient_core::{gpu, window::window_scale_factor};
+use ambient_core::gpu;
 use ambient_ecs::{components, ComponentValueBase, Resource, World};
 use ambient_element::{element_component, Element, ElementComponentExt, Hooks};
 use ambient_renderer::RenderTarget;
 use ambient_rpc::RpcRegistry;
 use ambient_std::{asset_cache::AssetCache, cb, friendly_id, to_byte_unit, Cb};
-use ambient_ui_native::{Image, MeasureSize};
+use ambient_ui_native::{use_window_scale_factor, Image, MeasureSize};
 use bytes::{BufMut, Bytes, BytesMut};
 use futures::future::BoxFuture;
-use glam::UVec2;
+use glam::{UVec2, Vec2};
 use parking_lot::Mutex;
 use serde::{de::DeserializeOwned, Serialize};
 use std::{
@@ -18,10 +18,14 @@ use std::{
     sync::Arc,
 };
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tracing::Instrument;
 
 use crate::{
-    client_connection::ConnectionKind, client_game_state::ClientGameState, log_network_result,
-    proto::client::SharedClientState, server, NetworkError, MAX_FRAME_SIZE, RPC_BISTREAM_ID,
+    client_connection::ConnectionKind,
+    client_game_state::ClientGameState,
+    log_network_result,
+    proto::{client::SharedClientState, Features, NegotiatedProtocol},
+    server, trace_context, NetworkError, MAX_FRAME_SIZE, RPC_BISTREAM_ID,
 };
 
 components!("network::client", {
@@ -36,6 +40,9 @@ components!("network::client", {
     /// The most recent server performance statistics
     @[Resource]
     client_network_stats: NetworkStats,
+    /// The protocol version and features negotiated with the server
+    @[Resource]
+    server_protocol: NegotiatedProtocol,
 });
 
 pub type DynSend = Pin<Box<dyn AsyncWrite + Send + Sync>>;
@@ -57,6 +64,9 @@ pub trait ClientConnection: 'static + Send + Sync {
     fn request_bi(&self, id: u32, data: Bytes) -> BoxFuture<Result<Bytes, NetworkError>>;
     /// Performs a unidirectional request without waiting for a response.
     fn request_uni(&self, id: u32, data: Bytes) -> BoxFuture<Result<(), NetworkError>>;
+    /// Opens a unidirectional stream that stays open for as long as it is written to, so that what is written to it
+    /// arrives in order.
+    fn open_uni_stream(&self, id: u32) -> BoxFuture<Result<DynSend, NetworkError>>;
     fn send_datagram(&self, id: u32, data: Bytes) -> Result<(), NetworkError>;
 }
 
@@ -87,6 +97,16 @@ impl ClientConnection for quinn::Connection {
         })
     }
 
+    fn open_uni_stream(&self, id: u32) -> BoxFuture<Result<DynSend, NetworkError>> {
+        Box::pin(async move {
+            let mut send = self.open_uni().await?;
+
+            send.write_u32(id).await?;
+
+            Ok(Box::pin(send) as DynSend)
+        })
+    }
+
     fn send_datagram(&self, id: u32, data: Bytes) -> Result<(), NetworkError> {
         let mut bytes = BytesMut::with_capacity(4 + data.len());
         bytes.put_u32(id);
@@ -125,6 +145,16 @@ impl ClientConnection for ConnectionKind {
         })
     }
 
+    fn open_uni_stream(&self, id: u32) -> BoxFuture<Result<DynSend, NetworkError>> {
+        Box::pin(async move {
+            let mut send = self.open_uni().await?;
+
+            send.write_u32(id).await?;
+
+            Ok(Box::pin(send) as DynSend)
+        })
+    }
+
     fn send_datagram(&self, id: u32, data: Bytes) -> Result<(), NetworkError> {
         let mut bytes = BytesMut::with_capacity(4 + data.len());
         bytes.put_u32(id);
@@ -184,7 +214,14 @@ impl GameClient {
         func: F,
         req: Req,
     ) -> Result<Resp, NetworkError> {
-        rpc_request(&*self.connection, self.rpc_registry.clone(), func, req).await
+        rpc_request(
+            &*self.connection,
+            self.rpc_registry.clone(),
+            &self.game_state,
+            func,
+            req,
+        )
+        .await
     }
 
     pub fn make_standalone_rpc_wrapper<
@@ -198,11 +235,18 @@ impl GameClient {
         func: F,
     ) -> Cb<impl Fn(Req)> {
         let runtime = runtime.clone();
-        let (connection, rpc_registry) = (self.connection.clone(), self.rpc_registry.clone());
+        let (connection, rpc_registry, game_state) = (
+            self.connection.clone(),
+            self.rpc_registry.clone(),
+            self.game_state.clone(),
+        );
         cb(move |req| {
-            let (connection, rpc_registry) = (connection.clone(), rpc_registry.clone());
+            let (connection, rpc_registry, game_state) =
+                (connection.clone(), rpc_registry.clone(), game_state.clone());
             runtime.spawn(async move {
-                log_network_result!(rpc_request(&*connection, rpc_registry, func, req).await);
+                log_network_result!(
+                    rpc_request(&*connection, rpc_registry, &game_state, func, req).await
+                );
             });
         })
     }
@@ -221,15 +265,30 @@ async fn rpc_request<
 >(
     conn: &dyn ClientConnection,
     reg: Arc<RpcRegistry<Args>>,
+    game_state: &SharedClientState,
     func: F,
     req: Req,
 ) -> Result<Resp, NetworkError> {
-    let req = reg.serialize_req(func, req);
+    let span = tracing::info_span!("rpc", name = std::any::type_name::<F>());
+    async {
+        // Older servers can't read requests with a tracing context
+        let traced = game_state
+            .lock()
+            .world
+            .resource_opt(server_protocol())
+            .map_or(false, |protocol| {
+                protocol.features.contains(Features::TRACE_CONTEXT)
+            });
+        let trace_context = traced.then(trace_context::current).flatten();
+        let req = reg.serialize_traced_req(func, req, trace_context.as_deref());
 
-    let resp = conn.request_bi(RPC_BISTREAM_ID, req.into()).await?;
+        let resp = conn.request_bi(RPC_BISTREAM_ID, req.into()).await?;
 
-    let resp = reg.deserialize_resp(func, &resp)?;
-    Ok(resp)
+        let resp = reg.deserialize_resp(func, &resp)?;
+        Ok(resp)
+    }
+    .instrument(span)
+    .await
 }
 
 #[derive(Debug, Clone)]
@@ -260,7 +319,23 @@ pub fn GameClientWorld(hooks: &mut Hooks) -> Element {
     let (render_target, set_render_target) =
         hooks.consume_context::<GameClientRenderTarget>().unwrap();
     let gpu = hooks.world.resource(gpu()).clone();
-    let scale_factor = *hooks.world.resource(window_scale_factor());
+    // The render target is recreated when either the size or the scale factor changes, so that it keeps matching the
+    // physical pixels of the window when it is moved to a monitor with a different DPI
+    let scale_factor = use_window_scale_factor(hooks);
+    let (size, set_size) = hooks.use_state(None as Option<Vec2>);
+    hooks.use_effect(
+        (size.map(|size| size.to_array()), scale_factor.to_bits()),
+        move |_, _| {
+            if let Some(size) = size {
+                set_render_target(GameClientRenderTarget(Arc::new(RenderTarget::new(
+                    gpu,
+                    (size * scale_factor as f32).as_uvec2().max(UVec2::ONE),
+                    None,
+                ))));
+            }
+            |_| {}
+        },
+    );
     MeasureSize::el(
         Image {
             texture: Some(Arc::new(
@@ -271,13 +346,7 @@ pub fn GameClientWorld(hooks: &mut Hooks) -> Element {
             )),
         }
         .el(),
-        cb(move |size| {
-            set_render_target(GameClientRenderTarget(Arc::new(RenderTarget::new(
-                gpu.clone(),
-                (size * scale_factor as f32).as_uvec2().max(UVec2::ONE),
-                None,
-            ))))
-        }),
+        cb(move |size| set_size(Some(size))),
     )
 }
 
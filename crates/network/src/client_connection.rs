@@ -1,3 +1,5 @@
+use std::net::IpAddr;
+
 use ambient_proxy::client::ProxiedConnection;
 use bytes::Bytes;
 use quinn::{Connection, RecvStream, SendStream};
@@ -71,4 +73,17 @@ impl ConnectionKind {
             ConnectionKind::Proxied(conn) => Ok(conn.send_datagram(data)?),
         }
     }
+
+    /// Whether the client is on the same machine as the server. Proxied clients never are
+    pub fn is_local(&self) -> bool {
+        match self {
+            ConnectionKind::Direct(conn) => match conn.remote_address().ip() {
+                IpAddr::V4(ip) => ip.is_loopback(),
+                IpAddr::V6(ip) => {
+                    ip.is_loopback() || ip.to_ipv4_mapped().map_or(false, |ip| ip.is_loopback())
+                }
+            },
+            ConnectionKind::Proxied(_) => false,
+        }
+    }
 }
@@ -1,45 +1,77 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use ambient_app::{
     gpu_world_sync_systems, world_instance_resources, world_instance_systems, AppResources,
 };
 use ambient_core::{
-    camera::{get_active_camera, projection_view},
+    async_ecs::async_run,
+    camera::{camera_systems, get_active_camera, projection_view},
     gpu_ecs::GpuWorldSyncEvent,
-    main_scene,
-    transform::local_to_world,
+    main_scene, runtime,
+    transform::{local_to_world, TransformSystem},
     ui_scene,
-    window::window_physical_size,
+    window::{screen_to_clip_space, window_physical_size},
+};
+use ambient_ecs::{
+    components,
+    generated::components::core::rendering::{pick_position, picked_entity},
+    query, Entity, EntityId, FrameEvent, System, SystemGroup, World,
 };
-use ambient_ecs::{components, query, Entity, FrameEvent, System, SystemGroup, World};
 use ambient_gizmos::render::GizmoRenderer;
 use ambient_gpu::gpu::GpuKey;
-use ambient_renderer::{RenderTarget, Renderer, RendererConfig, RendererTarget};
+use ambient_renderer::{
+    motion_blur_enabled, physical_camera_enabled, screen_space_gi_enabled, weather_enabled,
+    MotionBlur, PhysicalCamera, PostProcessStage, PostProcessor, RenderTarget, Renderer,
+    RendererConfig, RendererTarget, ScreenSpaceGi, Weather,
+};
 use ambient_std::{
     asset_cache::{AssetCache, SyncAssetKeyExt},
     color::Color,
     math::interpolate,
     shapes::Ray,
 };
+use futures::Future;
 use glam::{vec2, Mat4, Vec2, Vec3, Vec3Swizzles};
-use ambient_world_audio::systems::{spatial_audio_systems, setup_audio};
+use ambient_world_audio::{
+    mixer_systems, propagation_systems,
+    systems::{setup_audio, spatial_audio_systems},
+};
 
 use ambient_core::player::{player, user_id};
 use tracing::debug_span;
 
+use crate::interpolation;
+
 components!("rendering", {
     game_screen_render_target: Arc<RenderTarget>,
+    pick_requested: (),
 });
 
+/// Sent to the render systems of the client around rendering each frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderEvent {
+    /// After the world has been updated, and before it is rendered. Transforms and cameras are updated again after
+    /// this, so that changes made to them are rendered in this frame.
+    PreRender,
+    /// After the frame has been submitted to the GPU
+    PostRender {
+        /// How long it took to record and submit the frame
+        render_time: Duration,
+    },
+}
+
 #[derive(Debug)]
 /// Holds the material world of the client.
 pub struct ClientGameState {
     pub world: World,
     systems: SystemGroup,
+    render_systems: SystemGroup<RenderEvent>,
+    pre_render_update_systems: SystemGroup,
     temporary_systems: Vec<TempSystem>,
     gpu_world_sync_systems: SystemGroup<GpuWorldSyncEvent>,
     pub renderer: Renderer,
     pub ui_renderer: Renderer,
+    post_processor: PostProcessor,
     pub(crate) assets: AssetCache,
     user_id: String,
 }
@@ -57,6 +89,7 @@ impl ClientGameState {
         player_id: String,
         render_target: Arc<RenderTarget>,
         client_systems: SystemGroup,
+        client_render_systems: SystemGroup<RenderEvent>,
         client_resources: Entity,
     ) -> Self {
         let mut game_world = World::new("client_game_world");
@@ -75,6 +108,8 @@ impl ClientGameState {
                 Box::new(client_systems),
                 Box::new(world_instance_systems(true)),
                 Box::new(spatial_audio_systems()),
+                Box::new(mixer_systems()),
+                Box::new(propagation_systems()),
             ],
         );
         let mut renderer = Renderer::new(
@@ -101,10 +136,20 @@ impl ClientGameState {
         Self {
             world: game_world,
             systems,
+            render_systems: client_render_systems,
+            pre_render_update_systems: SystemGroup::new(
+                "game/pre_render_update",
+                vec![Box::new(TransformSystem::new()), Box::new(camera_systems())],
+            ),
             temporary_systems: Default::default(),
             gpu_world_sync_systems: gpu_world_sync_systems(),
             renderer,
             ui_renderer,
+            post_processor: PostProcessor::new(&assets)
+                .with_pass(screen_space_gi_enabled, ScreenSpaceGi::new)
+                .with_pass(weather_enabled, Weather::new)
+                .with_pass(physical_camera_enabled, PhysicalCamera::new)
+                .with_pass(motion_blur_enabled, MotionBlur::new),
             assets,
             user_id: player_id,
         }
@@ -122,7 +167,14 @@ impl ClientGameState {
         self.systems.run(&mut self.world, &FrameEvent);
         self.temporary_systems
             .retain_mut(|system| !(system.0)(&mut self.world));
+        self.request_picks();
+        self.render_systems
+            .run(&mut self.world, &RenderEvent::PreRender);
+        interpolation::begin_frame(&mut self.world);
+        self.pre_render_update_systems
+            .run(&mut self.world, &FrameEvent);
 
+        let render_start = ambient_sys::time::Instant::now();
         self.gpu_world_sync_systems
             .run(&mut self.world, &GpuWorldSyncEvent);
         let gpu = GpuKey.get(&self.assets);
@@ -132,6 +184,8 @@ impl ClientGameState {
                 label: Some("GameState.render"),
             });
         let mut post_submit = Vec::new();
+        self.post_processor
+            .update(&mut self.world, target.color_buffer.format);
         tracing::debug!("Drawing world");
         self.renderer.render(
             &mut self.world,
@@ -140,6 +194,8 @@ impl ClientGameState {
             RendererTarget::Target(target),
             Some(Color::rgba(0., 0., 0., 1.)),
         );
+        self.post_processor
+            .render(&self.world, &mut encoder, target, PostProcessStage::Scene);
         tracing::debug!("Drawing ui");
         self.ui_renderer.render(
             &mut self.world,
@@ -148,10 +204,52 @@ impl ClientGameState {
             RendererTarget::Target(target),
             None,
         );
+        self.post_processor
+            .render(&self.world, &mut encoder, target, PostProcessStage::Final);
         gpu.queue.submit(Some(encoder.finish()));
         for action in post_submit {
             action();
         }
+        interpolation::end_frame(&mut self.world);
+        self.render_systems.run(
+            &mut self.world,
+            &RenderEvent::PostRender {
+                render_time: render_start.elapsed(),
+            },
+        );
+    }
+    /// Returns the entity rendered at `screen_pos` (in the same space as `cursor_position`). This resolves once the
+    /// next frame has been read back from the GPU. Only opaque (including alpha-tested) entities are pickable.
+    pub fn pick(
+        &mut self,
+        screen_pos: Vec2,
+    ) -> impl Future<Output = Option<EntityId>> + Send + 'static {
+        let clip_space_pos = screen_to_clip_space(&self.world, screen_pos);
+        self.renderer.pick(clip_space_pos)
+    }
+    /// Picks for the `pick_position` requests spawned by guest code, and attaches the result as `picked_entity`
+    fn request_picks(&mut self) {
+        let requests = query(pick_position())
+            .excl(pick_requested())
+            .iter(&self.world, None)
+            .map(|(id, pos)| (id, *pos))
+            .collect::<Vec<_>>();
+        if requests.is_empty() {
+            return;
+        }
+        let runtime = self.world.resource(runtime()).clone();
+        let async_run = self.world.resource(async_run()).clone();
+        for (id, pos) in requests {
+            self.world.add_component(id, pick_requested(), ()).ok();
+            let picked = self.pick(pos);
+            let async_run = async_run.clone();
+            runtime.spawn(async move {
+                let picked = picked.await.unwrap_or_else(EntityId::null);
+                async_run.run(move |world| {
+                    world.add_component(id, picked_entity(), picked).ok();
+                });
+            });
+        }
     }
     /// Adds a temporary system; when it returns true it's removed
     pub fn add_temporary_system(
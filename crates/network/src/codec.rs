@@ -4,6 +4,8 @@ use bytes::{Buf, BufMut};
 use serde::Serialize;
 use tokio_util::codec::Encoder;
 
+use crate::MAX_CODEC_FRAME_SIZE;
+
 pub enum ControlFrame {}
 
 pub struct FramedCodec<T> {
@@ -29,7 +31,11 @@ impl<T: serde::de::DeserializeOwned> tokio_util::codec::Decoder for FramedCodec<
         let len = match self.len {
             Some(len) => len,
             None if src.len() >= 4 => {
-                let len = src.get_u32().try_into().unwrap();
+                let len = src.get_u32() as usize;
+                // Rather than buffering whatever a peer claims to send
+                if len > MAX_CODEC_FRAME_SIZE {
+                    return Err(bincode::ErrorKind::SizeLimit.into());
+                }
                 self.len = Some(len);
                 len
             }
@@ -104,4 +110,17 @@ mod test {
         assert_eq!(recv(), Frame::String("Another".into()));
         assert_eq!(recv(), Frame::Int(42));
     }
+
+    #[test]
+    fn oversized_frame() {
+        use tokio_util::codec::Decoder;
+
+        let mut src = bytes::BytesMut::new();
+        src.put_u32(u32::MAX);
+        assert!(FramedCodec::<String>::new().decode(&mut src).is_err());
+
+        let mut src = bytes::BytesMut::new();
+        src.put_u32(MAX_CODEC_FRAME_SIZE as u32 + 1);
+        assert!(FramedCodec::<String>::new().decode(&mut src).is_err());
+    }
 }
@@ -0,0 +1,327 @@
+//! Render interpolation of remote entities. The server steps the world at its tick rate, which is usually below the
+//! refresh rate of the display, so the client keeps the transform of every remote entity that moves at the last two
+//! ticks it received, and renders it blended between them by how far the frame is into the next tick. The blended
+//! transform is only swapped in while the frame is rendered; the rest of the frame sees the latest tick.
+//!
+//! Entities that jump on purpose, like a player that respawns or goes through a portal, have their `teleport_count`
+//! raised along with their transform, and are shown at their new transform right away instead of gliding to it. Their
+//! buffered [XR poses](crate::pose_sync) are dropped too.
+
+use ambient_core::{
+    time,
+    transform::{rotation, scale, translation},
+};
+use ambient_ecs::{components, query, EntityId, Resource, World, WorldChange, WorldDiff};
+use glam::{Quat, Vec3};
+
+use crate::pose_sync::pose_sync_buffer;
+
+pub use ambient_ecs::generated::components::core::network::{render_interpolation, teleport_count};
+
+/// The time between ticks that is assumed until it has been measured, in seconds
+const DEFAULT_TICK_INTERVAL: f64 = 1. / 60.;
+/// The bounds of the measured time between ticks, in seconds, so that bursts and pauses of the updates don't throw it
+/// off
+const MIN_TICK_INTERVAL: f64 = 1. / 240.;
+const MAX_TICK_INTERVAL: f64 = 0.25;
+/// How much the estimated time between ticks moves towards each measured one
+const TICK_INTERVAL_SMOOTHING: f64 = 0.1;
+
+components!("network::interpolation", {
+    /// The transform of a remote entity at the previous and at the latest tick
+    transform_ticks: TransformTicks,
+    /// When the latest tick was received, and the estimated time between ticks
+    @[Resource]
+    tick_timing: TickTiming,
+});
+
+/// The parts of the transform of an entity that it has
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Transform {
+    translation: Option<Vec3>,
+    rotation: Option<Quat>,
+    scale: Option<Vec3>,
+}
+impl Transform {
+    fn from_world(world: &World, id: EntityId) -> Self {
+        Self {
+            translation: world.get(id, translation()).ok(),
+            rotation: world.get(id, rotation()).ok(),
+            scale: world.get(id, scale()).ok(),
+        }
+    }
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            translation: match (self.translation, other.translation) {
+                (Some(a), Some(b)) => Some(a.lerp(b, t)),
+                (_, b) => b,
+            },
+            rotation: match (self.rotation, other.rotation) {
+                (Some(a), Some(b)) => Some(a.slerp(b, t)),
+                (_, b) => b,
+            },
+            scale: match (self.scale, other.scale) {
+                (Some(a), Some(b)) => Some(a.lerp(b, t)),
+                (_, b) => b,
+            },
+        }
+    }
+    fn apply(&self, world: &mut World, id: EntityId) {
+        if let Some(value) = self.translation {
+            world.set_if_changed(id, translation(), value).ok();
+        }
+        if let Some(value) = self.rotation {
+            world.set_if_changed(id, rotation(), value).ok();
+        }
+        if let Some(value) = self.scale {
+            world.set_if_changed(id, scale(), value).ok();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransformTicks {
+    previous: Transform,
+    latest: Transform,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TickTiming {
+    last: f64,
+    interval: f64,
+}
+impl TickTiming {
+    /// How far `now` is from the latest tick to the next one, from 0 to 1
+    fn alpha(&self, now: f64) -> f32 {
+        ((now - self.last) / self.interval).clamp(0., 1.) as f32
+    }
+}
+
+fn enabled(world: &World) -> bool {
+    world
+        .resource_opt(render_interpolation())
+        .copied()
+        .unwrap_or(true)
+}
+
+fn seconds(world: &World) -> f64 {
+    world.resource(time()).as_secs_f64()
+}
+
+/// An entity whose transform a diff changes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MovedEntity {
+    id: EntityId,
+    /// Its transform before the diff is applied
+    before: Transform,
+    /// Whether the diff also raises its `teleport_count`
+    teleported: bool,
+}
+
+/// The entities whose transform `diff` changes
+pub fn moved_entities(world: &World, diff: &WorldDiff) -> Vec<MovedEntity> {
+    let is_teleported = |id: EntityId| {
+        diff.changes.iter().any(|change| match change {
+            WorldChange::Set(changed, entry) => {
+                *changed == id && entry.index() == teleport_count().index()
+            }
+            WorldChange::AddComponents(changed, entity) => {
+                *changed == id && entity.contains(teleport_count())
+            }
+            _ => false,
+        })
+    };
+    let is_transform = |index: u32| {
+        index == translation().index() || index == rotation().index() || index == scale().index()
+    };
+    let mut moved: Vec<MovedEntity> = Vec::new();
+    for change in &diff.changes {
+        let id = match change {
+            WorldChange::Set(id, entry) if is_transform(entry.index()) => *id,
+            WorldChange::AddComponents(id, entity)
+                if entity.iter().any(|entry| is_transform(entry.index())) =>
+            {
+                *id
+            }
+            _ => continue,
+        };
+        if !moved.iter().any(|moved| moved.id == id) {
+            moved.push(MovedEntity {
+                id,
+                before: Transform::from_world(world, id),
+                teleported: is_teleported(id),
+            });
+        }
+    }
+    moved
+}
+
+/// Records a tick received from the server, after its diff has been applied. `moved` are the [moved_entities] of the
+/// diff.
+pub fn on_tick(world: &mut World, moved: Vec<MovedEntity>) {
+    let now = seconds(world);
+    // Where the entities are rendered right now, which the blend to the new tick starts from
+    let alpha = match world.resource_opt(tick_timing()).copied() {
+        Some(timing) => {
+            let measured = (now - timing.last).clamp(MIN_TICK_INTERVAL, MAX_TICK_INTERVAL);
+            let interval = timing.interval + (measured - timing.interval) * TICK_INTERVAL_SMOOTHING;
+            world.add_resource(
+                tick_timing(),
+                TickTiming {
+                    last: now,
+                    interval,
+                },
+            );
+            timing.alpha(now)
+        }
+        None => {
+            world.add_resource(
+                tick_timing(),
+                TickTiming {
+                    last: now,
+                    interval: DEFAULT_TICK_INTERVAL,
+                },
+            );
+            1.
+        }
+    };
+
+    for MovedEntity {
+        id,
+        before,
+        teleported,
+    } in moved
+    {
+        if !world.exists(id) {
+            continue;
+        }
+        // Teleported entities are rendered at their new transform right away, so they don't glide across the jump
+        if teleported {
+            world.remove_component(id, transform_ticks()).ok();
+            if let Ok(buffer) = world.get_mut(id, pose_sync_buffer()) {
+                buffer.clear();
+            }
+            continue;
+        }
+        let previous = match world.get_ref(id, transform_ticks()) {
+            Ok(ticks) => ticks.previous.lerp(&ticks.latest, alpha),
+            Err(_) => before,
+        };
+        let ticks = TransformTicks {
+            previous,
+            latest: Transform::from_world(world, id),
+        };
+        world.add_component(id, transform_ticks(), ticks).ok();
+    }
+}
+
+/// Swaps in the blended transforms of the entities that moved in the last ticks, before the frame is rendered. They
+/// have to be swapped out again with [end_frame].
+pub fn begin_frame(world: &mut World) {
+    if !enabled(world) {
+        return;
+    }
+    let Some(timing) = world.resource_opt(tick_timing()).copied() else {
+        return;
+    };
+    let alpha = timing.alpha(seconds(world));
+    for (id, ticks) in query(transform_ticks()).collect_cloned(world, None) {
+        // Entities that were moved locally since the tick, or that came to rest, are rendered as they are
+        if alpha >= 1. || Transform::from_world(world, id) != ticks.latest {
+            world.remove_component(id, transform_ticks()).ok();
+            continue;
+        }
+        ticks.previous.lerp(&ticks.latest, alpha).apply(world, id);
+    }
+}
+
+/// Restores the transforms of the latest tick after the frame has been rendered
+pub fn end_frame(world: &mut World) {
+    if !enabled(world) {
+        return;
+    }
+    for (id, ticks) in query(transform_ticks()).collect_cloned(world, None) {
+        ticks.latest.apply(world, id);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use ambient_ecs::Entity;
+    use glam::vec3;
+
+    use super::*;
+
+    fn init() {
+        crate::init_all_components();
+        ambient_core::init_all_components();
+    }
+
+    /// Applies `diff` the way a tick from the server is applied, `seconds` into the test
+    fn tick(world: &mut World, diff: WorldDiff, seconds: f64) {
+        world.add_resource(time(), Duration::from_secs_f64(seconds));
+        let moved = moved_entities(world, &diff);
+        diff.apply(world, Entity::new(), false);
+        on_tick(world, moved);
+    }
+
+    /// Where `id` is rendered `seconds` into the test
+    fn rendered_translation(world: &mut World, id: EntityId, seconds: f64) -> Vec3 {
+        world.add_resource(time(), Duration::from_secs_f64(seconds));
+        begin_frame(world);
+        let rendered = world.get(id, translation()).unwrap();
+        end_frame(world);
+        rendered
+    }
+
+    #[test]
+    fn moves_are_interpolated() {
+        init();
+        let mut world = World::new("moves_are_interpolated");
+        let id = Entity::new()
+            .with(translation(), Vec3::ZERO)
+            .spawn(&mut world);
+        tick(
+            &mut world,
+            WorldDiff::new().set(id, translation(), Vec3::ZERO),
+            0.,
+        );
+        tick(
+            &mut world,
+            WorldDiff::new().set(id, translation(), vec3(1., 0., 0.)),
+            0.1,
+        );
+
+        let interval = world.resource(tick_timing()).interval;
+        let rendered = rendered_translation(&mut world, id, 0.1 + interval / 2.);
+        assert!((rendered.x - 0.5).abs() < 0.001, "{rendered}");
+        // The rest of the frame sees the latest tick
+        assert_eq!(world.get(id, translation()).unwrap(), vec3(1., 0., 0.));
+    }
+
+    #[test]
+    fn teleports_snap() {
+        init();
+        let mut world = World::new("teleports_snap");
+        let id = Entity::new()
+            .with(translation(), Vec3::ZERO)
+            .with(teleport_count(), 0)
+            .spawn(&mut world);
+        tick(
+            &mut world,
+            WorldDiff::new().set(id, translation(), Vec3::ZERO),
+            0.,
+        );
+        let diff = WorldDiff::new()
+            .set(id, translation(), vec3(10., 0., 0.))
+            .set(id, teleport_count(), 1);
+        tick(&mut world, diff, 0.1);
+
+        assert!(!world.has_component(id, transform_ticks()));
+        let interval = world.resource(tick_timing()).interval;
+        let rendered = rendered_translation(&mut world, id, 0.1 + interval / 2.);
+        assert_eq!(rendered, vec3(10., 0., 0.));
+    }
+}
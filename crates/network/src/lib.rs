@@ -9,20 +9,28 @@ use ambient_std::log_error;
 use thiserror::Error;
 
 pub use ambient_ecs::generated::components::core::network::{
-    is_remote_entity, persistent_resources, synced_resources,
+    cluster_ghost, cluster_region_max, cluster_region_min, is_remote_entity, persistent_resources,
+    synced_resources,
 };
 
 pub type AsyncMutex<T> = tokio::sync::Mutex<T>;
+pub mod auth;
 pub mod client;
 pub mod client_connection;
 pub mod client_game_state;
 pub mod codec;
 pub mod hooks;
+pub mod interpolation;
+pub mod metrics;
 pub mod native;
+pub mod ownership;
+pub mod pose_sync;
+pub mod priority;
 pub mod proto;
 pub mod rpc;
 pub mod server;
 pub mod stream;
+pub mod trace_context;
 
 pub const RPC_BISTREAM_ID: u32 = 2;
 
@@ -33,12 +41,29 @@ pub const WASM_UNISTREAM_ID: u32 = 11;
 pub const PLAYER_INPUT_DATAGRAM_ID: u32 = 12;
 pub const WASM_DATAGRAM_ID: u32 = 13;
 
-const MAX_FRAME_SIZE: usize = 1024 * 1024 * 1024;
+pub const MODULE_LOG_UNISTREAM_ID: u32 = 14;
+
+pub const CLIENT_AUTHORITY_DATAGRAM_ID: u32 = 15;
+
+pub const POSE_SYNC_DATAGRAM_ID: u32 = 16;
+
+pub const SURFACE_EFFECT_DATAGRAM_ID: u32 = 17;
+
+pub const MAX_FRAME_SIZE: usize = 1024 * 1024 * 1024;
+/// The largest frame a client can send on its request stream, which only carries small control messages
+pub const MAX_REQUEST_FRAME_SIZE: usize = 1024 * 1024;
+/// The largest RPC request a client can send, with room for the world diffs of the editor
+pub const MAX_RPC_REQUEST_SIZE: usize = 8 * 1024 * 1024;
+/// The largest frame of a [codec::FramedCodec]
+pub const MAX_CODEC_FRAME_SIZE: usize = 1024 * 1024;
 
 pub fn init_all_components() {
     client::init_components();
     server::init_components();
     client_game_state::init_components();
+    interpolation::init_components();
+    pose_sync::init_components();
+    priority::init_components();
 }
 
 pub trait ServerWorldExt {
@@ -144,6 +169,8 @@ pub enum NetworkError {
     ProxyError(#[from] ambient_proxy::Error),
     #[error("Bad frame")]
     FrameError(#[from] FrameError),
+    #[error(transparent)]
+    IncompatibleVersion(#[from] proto::IncompatibleVersion),
 }
 
 impl NetworkError {
@@ -0,0 +1,105 @@
+//! The runtime statistics of the server; see [ambient_std::metrics].
+
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use ambient_std::metrics::{self, MetricKind};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+pub const TICK_DURATION: &str = "ambient_server_tick_duration_seconds";
+pub const ENTITIES: &str = "ambient_server_entities";
+pub const PLAYERS: &str = "ambient_server_players";
+pub const RECEIVED_BYTES: &str = "ambient_network_received_bytes_total";
+pub const SENT_BYTES: &str = "ambient_network_sent_bytes_total";
+
+/// Declares the statistics of the server
+pub fn describe() {
+    metrics::describe(
+        TICK_DURATION,
+        MetricKind::Summary,
+        "How long the server takes to step its worlds and send their changes",
+    );
+    metrics::describe(
+        ENTITIES,
+        MetricKind::Gauge,
+        "The number of entities in a world instance",
+    );
+    metrics::describe(
+        PLAYERS,
+        MetricKind::Gauge,
+        "The number of players connected to a world instance",
+    );
+    metrics::describe(
+        RECEIVED_BYTES,
+        MetricKind::Counter,
+        "The bytes received by a network handler",
+    );
+    metrics::describe(
+        SENT_BYTES,
+        MetricKind::Counter,
+        "The bytes sent by a network handler",
+    );
+}
+
+/// A stream of a network handler, which counts the bytes that go through it
+pub(crate) struct CountedStream<S> {
+    inner: S,
+    handler: &'static str,
+    kind: &'static str,
+}
+impl<S> CountedStream<S> {
+    pub fn new(inner: S, handler: &'static str, kind: &'static str) -> Self {
+        Self {
+            inner,
+            handler,
+            kind,
+        }
+    }
+}
+impl<S: AsyncRead + Unpin> AsyncRead for CountedStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        let res = Pin::new(&mut self.inner).poll_read(cx, buf);
+        let read = buf.filled().len() - filled;
+        if read > 0 {
+            metrics::add(
+                RECEIVED_BYTES,
+                &[("handler", self.handler), ("kind", self.kind)],
+                read as f64,
+            );
+        }
+        res
+    }
+}
+impl<S: AsyncWrite + Unpin> AsyncWrite for CountedStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let res = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = res {
+            metrics::add(
+                SENT_BYTES,
+                &[("handler", self.handler), ("kind", self.kind)],
+                written as f64,
+            );
+        }
+        res
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
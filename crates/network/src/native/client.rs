@@ -1,9 +1,13 @@
 use crate::{
-    client::{GameClient, GameClientRenderTarget, LoadedFunc, NetworkStats},
-    client_game_state::ClientGameState,
+    auth::AuthTicket,
+    client::{
+        server_protocol, CleanupFunc, GameClient, GameClientRenderTarget, LoadedFunc, NetworkStats,
+    },
+    client_game_state::{ClientGameState, RenderEvent},
     proto::{
+        self,
         client::{ClientState, SharedClientState},
-        ClientRequest,
+        compat, ClientRequest, Features, Handshake,
     },
     server::RpcArgs,
     stream::{self, RecvStream, SendStream},
@@ -18,8 +22,10 @@ use ambient_rpc::RpcRegistry;
 use ambient_std::{cb, Cb};
 use ambient_ui_native::{Centered, FlowColumn, FlowRow, Text, Throbber};
 use anyhow::Context;
+use directories::ProjectDirs;
 use futures::{SinkExt, StreamExt};
 use glam::uvec2;
+use once_cell::sync::Lazy;
 use parking_lot::Mutex;
 use quinn::{ClientConfig, Connection, Endpoint, TransportConfig};
 use rand::Rng;
@@ -29,13 +35,44 @@ use std::{
     sync::Arc,
     time::Duration,
 };
+use uuid::Uuid;
+
+/// Identifies this client to the server, so that it can reconnect to its player if its connection is lost. It is kept in
+/// the data directory, so that the client can also reconnect after it restarts
+static IDENTITY_TOKEN: Lazy<String> = Lazy::new(|| {
+    load_identity_token().unwrap_or_else(|err| {
+        tracing::warn!("Failed to persist the identity token, so the client can't reconnect after a restart: {err:?}");
+        Uuid::new_v4().to_string()
+    })
+});
+
+/// Reads the identity token of this machine, creating it if there isn't one yet
+fn load_identity_token() -> anyhow::Result<String> {
+    let dirs =
+        ProjectDirs::from("com", "Ambient", "Ambient").context("Failed to open home directory")?;
+    let path = dirs.data_dir().join("identity_token");
+    if let Ok(token) = std::fs::read_to_string(&path) {
+        if !token.trim().is_empty() {
+            return Ok(token.trim().to_string());
+        }
+    }
+    let token = Uuid::new_v4().to_string();
+    std::fs::create_dir_all(dirs.data_dir())?;
+    std::fs::write(&path, &token).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(token)
+}
 
 #[derive(Debug, Clone)]
 pub struct GameClientView {
     pub server_addr: SocketAddr,
     pub cert: Option<Vec<u8>>,
     pub user_id: String,
-    pub systems_and_resources: Cb<dyn Fn() -> (SystemGroup, Entity) + Sync + Send>,
+    /// The proof that the user is `user_id`, for servers that require one
+    pub auth_ticket: Option<AuthTicket>,
+    /// Join the server as a spectator instead of a player
+    pub spectator: bool,
+    pub systems_and_resources:
+        Cb<dyn Fn() -> (SystemGroup, SystemGroup<RenderEvent>, Entity) + Sync + Send>,
     pub error_view: Cb<dyn Fn(String) -> Element + Sync + Send>,
     pub on_loaded: LoadedFunc,
     pub create_rpc_registry: Cb<dyn Fn() -> RpcRegistry<RpcArgs> + Sync + Send>,
@@ -47,6 +84,8 @@ impl ElementComponent for GameClientView {
         let Self {
             server_addr,
             user_id,
+            auth_ticket,
+            spectator,
             error_view,
             systems_and_resources,
             create_rpc_registry,
@@ -64,7 +103,7 @@ impl ElementComponent for GameClientView {
 
         let assets = hooks.world.resource(asset_cache()).clone();
         let game_state = hooks.use_ref_with(|world| {
-            let (systems, resources) = systems_and_resources();
+            let (systems, render_systems, resources) = systems_and_resources();
 
             ClientGameState::new(
                 world,
@@ -72,6 +111,7 @@ impl ElementComponent for GameClientView {
                 user_id.clone(),
                 render_target.0.clone(),
                 systems,
+                render_systems,
                 resources,
             )
         });
@@ -127,45 +167,70 @@ impl ElementComponent for GameClientView {
 
         hooks.use_task(move |_| {
             let task = async move {
-                let conn = open_connection(server_addr, cert.map(Certificate))
-                    .await
-                    .with_context(|| format!("Failed to connect to endpoint: {server_addr:?}"))?;
-
-                tracing::info!("Connected to the server");
-
-                // Create a handle for the game client
-                let game_client = GameClient::new(
-                    Arc::new(conn.clone()),
-                    Arc::new(create_rpc_registry()),
-                    game_state.clone(),
-                    user_id.clone(),
-                );
-
-                handle_connection(
-                    game_client,
-                    conn,
-                    user_id,
-                    ClientCallbacks {
-                        on_loaded: cb(move |game_client| {
-                            let game_state = &game_client.game_state;
-                            {
-                                // Updates the game client context in the Ui tree
-                                set_game_client(Some(game_client.clone()));
-                                // Update the resources on the client side world to reflect the new connection
-                                // state
-                                let world = &mut game_state.lock().world;
-                                world.add_resource(
-                                    crate::client::game_client(),
-                                    Some(game_client.clone()),
-                                );
-                            }
+                let set_game_client = move |game_client: &GameClient| {
+                    // Updates the game client context in the Ui tree
+                    set_game_client(Some(game_client.clone()));
+                    // Update the resources on the client side world to reflect the new connection state
+                    let world = &mut game_client.game_state.lock().world;
+                    world.add_resource(crate::client::game_client(), Some(game_client.clone()));
+                };
+
+                let mut server_addr = server_addr;
+                let mut redirected = false;
+                loop {
+                    let conn = open_connection(server_addr, cert.clone().map(Certificate))
+                        .await
+                        .with_context(|| {
+                            format!("Failed to connect to endpoint: {server_addr:?}")
+                        })?;
+
+                    tracing::info!("Connected to the server");
+
+                    // Create a handle for the game client
+                    let game_client = GameClient::new(
+                        Arc::new(conn.clone()),
+                        Arc::new(create_rpc_registry()),
+                        game_state.clone(),
+                        user_id.clone(),
+                    );
+
+                    // The client is only loaded once; when it's redirected to another shard of a cluster, it only
+                    // switches over to the new connection
+                    let set_game_client = set_game_client.clone();
+                    let on_loaded = on_loaded.clone();
+                    let on_loaded: LoadedFunc = if redirected {
+                        cb(move |game_client: GameClient| {
+                            set_game_client(&game_client);
+                            Ok(Box::new(|| {}) as CleanupFunc)
+                        })
+                    } else {
+                        cb(move |game_client: GameClient| {
+                            set_game_client(&game_client);
                             (on_loaded)(game_client)
-                        }),
-                    },
-                    game_state,
-                    control_rx,
-                )
-                .await?;
+                        })
+                    };
+
+                    let redirect = handle_connection(
+                        game_client,
+                        conn,
+                        user_id.clone(),
+                        auth_ticket.clone(),
+                        spectator,
+                        ClientCallbacks { on_loaded },
+                        game_state.clone(),
+                        control_rx.clone(),
+                        redirected,
+                    )
+                    .await?;
+
+                    match redirect {
+                        Some(address) => {
+                            server_addr = address;
+                            redirected = true;
+                        }
+                        None => break,
+                    }
+                }
 
                 tracing::info!("Finished handling connection");
 
@@ -179,6 +244,11 @@ impl ElementComponent for GameClientView {
                     }
                     Err(err) => {
                         if let Some(err) = err.downcast_ref::<NetworkError>() {
+                            if let NetworkError::IncompatibleVersion(err) = err {
+                                tracing::error!("Incompatible server: {err}");
+                                set_error(Some(err.to_string()));
+                                return;
+                            }
                             if let NetworkError::ConnectionClosed = err {
                                 tracing::info!("Connection closed by peer");
                             } else {
@@ -224,15 +294,22 @@ pub enum Control {
     Disconnect,
 }
 
+/// Runs the connection to a server, and returns the address of the server to connect to instead if the client was
+/// redirected to another shard of a cluster. The first diff of a `redirected` client replaces the world of the previous
+/// server
+#[allow(clippy::too_many_arguments)]
 #[tracing::instrument(name = "client", level = "info", skip(conn))]
 async fn handle_connection(
     game_client: GameClient,
     conn: quinn::Connection,
     user_id: String,
+    auth_ticket: Option<AuthTicket>,
+    spectator: bool,
     callbacks: ClientCallbacks,
     state: SharedClientState,
     control_rx: flume::Receiver<Control>,
-) -> anyhow::Result<()> {
+    redirected: bool,
+) -> anyhow::Result<Option<SocketAddr>> {
     tracing::info!("Handling client connection");
     tracing::info!("Opening control stream");
 
@@ -247,13 +324,20 @@ async fn handle_connection(
     tracing::info!("Attempting to connect using {user_id:?}");
 
     request_send
-        .send(ClientRequest::Connect(user_id.clone()))
+        .send(ClientRequest::Connect {
+            user_id: user_id.clone(),
+            identity_token: IDENTITY_TOKEN.clone(),
+            spectator,
+            handshake: Handshake::current(),
+            auth_ticket,
+        })
         .await?;
 
     let mut client = ClientState::Connecting(user_id);
 
     tracing::info!("Accepting control stream from server");
-    let mut push_recv = stream::RecvStream::new(conn.accept_uni().await?);
+    let mut push_recv =
+        stream::RecvStream::with_decoder(conn.accept_uni().await?, compat::decode_server_push);
 
     tracing::info!("Entering client loop");
     while client.is_connecting() {
@@ -264,7 +348,18 @@ async fn handle_connection(
     }
 
     tracing::info!("Accepting diff stream");
-    let mut diff_stream = RecvStream::new(conn.accept_uni().await?);
+    let binary_diffs = state
+        .lock()
+        .world
+        .resource_opt(server_protocol())
+        .map_or(false, |protocol| {
+            protocol.features.contains(Features::BINARY_DIFFS)
+        });
+    let mut diff_stream = if binary_diffs {
+        RecvStream::with_decoder(conn.accept_uni().await?, proto::decode_binary_diff)
+    } else {
+        RecvStream::new(conn.accept_uni().await?)
+    };
 
     let cleanup = (callbacks.on_loaded)(game_client)?;
     let on_disconnect = move || {
@@ -282,6 +377,14 @@ async fn handle_connection(
 
     tracing::info!("Client connected");
 
+    if let ClientState::Connected(connected) = &mut client {
+        if redirected {
+            if let Some(diff) = diff_stream.next().await {
+                connected.process_redirected_diff(&state, diff?)?;
+            }
+        }
+    }
+
     while let ClientState::Connected(connected) = &mut client {
         tokio::select! {
             Some(frame) = push_recv.next() => {
@@ -324,8 +427,13 @@ async fn handle_connection(
         }
     }
 
+    if let ClientState::Redirected(address) = client {
+        conn.close(quinn::VarInt::from_u32(0), b"Redirected");
+        return Ok(Some(address));
+    }
+
     tracing::info!("Client entered disconnected state");
-    Ok(())
+    Ok(None)
 }
 
 /// Connnect to the server endpoint.
@@ -0,0 +1,832 @@
+//! Cluster mode, in which several server processes (shards) share one large world.
+//!
+//! Each shard has authority over a region of the XY plane of the world. When an entity of a shard moves into the
+//! region of another shard, it is handed off to that shard along with its descendants; players are handed off with
+//! their clients, which are redirected to the new shard once it has taken them over. The entities near the border of
+//! another shard are mirrored to it as ghosts (see `cluster_ghost`), so that its players and systems can see across
+//! the border, and the changes its systems make to ghosts are proxied back to the shard that owns them.
+//!
+//! Every shard opens a link to every other shard over QUIC, with the certificate of the server, and only sends on the
+//! links it opened. A link starts with the secret of the cluster, and nothing else is accepted on a link without it.
+
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use ambient_core::{
+    hierarchy::{children, parent},
+    player::player_suspended,
+    transform::{rotation, scale, translation},
+};
+use ambient_ecs::{
+    query, ComponentDesc, Entity, EntityId, QueryState, World, WorldChange, WorldDiff, WorldStream,
+    WorldStreamFilter,
+};
+use ambient_std::log_result;
+use anyhow::Context;
+use futures::{SinkExt, StreamExt};
+use glam::Vec2;
+use itertools::Itertools;
+use quinn::{ClientConfig, Connection, Endpoint};
+use rustls::{Certificate, RootCertStore};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cluster_ghost, cluster_region_max, cluster_region_min,
+    native::server::Crypto,
+    proto::{server::Player, Features},
+    server::{
+        get_connected_by_user_id, player_connection, player_connection_id, player_entity_stream,
+        player_features, ServerState, MAIN_INSTANCE_ID,
+    },
+    stream,
+};
+
+/// The ALPN protocol of the links between shards, which tells them apart from the connections of clients
+pub(crate) const CLUSTER_ALPN: &[u8] = b"ambient-cluster-01";
+
+/// How long a shard waits before trying to link to another shard again
+const LINK_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The shards of a cluster. Every shard is started with the same configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterConfig {
+    /// Proves that a link comes from another shard, as the links can change the world and take over players
+    pub secret: ClusterSecret,
+    pub shards: Vec<ShardConfig>,
+    /// How far from the region of a shard, in meters, entities are mirrored to it as ghosts
+    #[serde(default = "default_border")]
+    pub border: f32,
+}
+fn default_border() -> f32 {
+    20.
+}
+impl ClusterConfig {
+    /// The shard that has authority over the entities at `position`, if any
+    pub fn shard_at(&self, position: Vec2) -> Option<usize> {
+        self.shards
+            .iter()
+            .position(|shard| shard.contains(position))
+    }
+}
+
+/// The secret that the shards of a cluster share. It isn't shown in logs
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ClusterSecret(pub String);
+impl ClusterSecret {
+    /// Whether `secret` is this one, in a time that doesn't depend on where they differ
+    fn matches(&self, secret: &str) -> bool {
+        let (a, b) = (self.0.as_bytes(), secret.as_bytes());
+        a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+    }
+}
+impl std::fmt::Debug for ClusterSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ClusterSecret(..)")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShardConfig {
+    /// The QUIC address of the shard, which its players and the other shards connect to
+    pub address: SocketAddr,
+    /// The lower corner of the region of the shard
+    pub min: Vec2,
+    /// The upper corner of the region of the shard
+    pub max: Vec2,
+}
+impl ShardConfig {
+    pub fn contains(&self, position: Vec2) -> bool {
+        position.cmpge(self.min).all() && position.cmplt(self.max).all()
+    }
+    /// The distance from `position` to the region of the shard
+    pub fn distance(&self, position: Vec2) -> f32 {
+        position.clamp(self.min, self.max).distance(position)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClusterSettings {
+    pub config: ClusterConfig,
+    /// The index of the shard of this server in the config
+    pub shard: usize,
+}
+impl ClusterSettings {
+    /// Checks that the cluster has a secret and the shard `shard`
+    pub fn new(config: ClusterConfig, shard: usize) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            shard < config.shards.len(),
+            "The cluster has no shard {shard}"
+        );
+        anyhow::ensure!(!config.secret.0.is_empty(), "The cluster has no secret");
+        Ok(Self { config, shard })
+    }
+
+    /// The shard of this server
+    pub fn own(&self) -> &ShardConfig {
+        &self.config.shards[self.shard]
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum ShardMessage {
+    /// The first message on a link, with the index of the shard that opened it and the secret of the cluster
+    Hello { shard: usize, secret: String },
+    /// The receiver takes over the authority over an entity and its descendants, and over the player the entity is
+    Handoff {
+        entities: Vec<(EntityId, Entity)>,
+        player: Option<HandedOffPlayer>,
+    },
+    /// The sender has taken over the player of `user_id`, so its client can be redirected to the sender
+    PlayerTakenOver { user_id: String },
+    /// The sender's entities that came near the border of the receiver, the changes to those that already were, and
+    /// the ghosts of the entities that no longer are
+    Ghosts {
+        spawned: Vec<(EntityId, Entity)>,
+        changed: WorldDiff,
+        removed: Vec<EntityId>,
+    },
+    /// The changes that the systems of the sender made to the ghosts of the receiver's entities
+    Proxied(WorldDiff),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct HandedOffPlayer {
+    user_id: String,
+    /// The secret the client reconnects with, which it uses to connect to the shard it's handed off to
+    identity_token: String,
+}
+
+struct Link {
+    tx: flume::Sender<ShardMessage>,
+    up: Arc<AtomicBool>,
+}
+
+/// The state of the shard of a server in a cluster, which is stepped along with the server
+pub struct Cluster {
+    settings: ClusterSettings,
+    /// The links to each shard; the link to this shard is never up
+    links: Vec<Link>,
+    /// The messages received from the other shards, and `None` when the link from a shard is lost
+    incoming_tx: flume::Sender<(usize, Option<ShardMessage>)>,
+    incoming_rx: flume::Receiver<(usize, Option<ShardMessage>)>,
+    /// The entities that were in the region of this shard the last time they moved
+    inside: HashSet<EntityId>,
+    moved_qs: QueryState,
+    /// The entities of this shard that each shard has ghosts of
+    ghosted: Vec<HashSet<EntityId>>,
+    /// The shard that owns each ghost
+    ghost_owners: HashMap<EntityId, usize>,
+    ghost_stream: WorldStream,
+    /// The changes to the entities of this shard, which are sent to the shards that have ghosts of them
+    owned_stream: WorldStream,
+    stream_filter: WorldStreamFilter,
+}
+
+impl Cluster {
+    /// Links to the other shards of the cluster, and sets the region of the shard in `world`
+    pub(crate) fn start(
+        settings: ClusterSettings,
+        link_config: ClientConfig,
+        endpoint: Endpoint,
+        world: &mut World,
+        stream_filter: WorldStreamFilter,
+    ) -> Self {
+        let (cluster, link_rxs) = Self::new(settings, world, stream_filter);
+        for (shard, rx) in link_rxs.into_iter().enumerate() {
+            if shard != cluster.settings.shard {
+                tokio::spawn(run_link(
+                    endpoint.clone(),
+                    link_config.clone(),
+                    cluster.settings.shard,
+                    cluster.settings.config.secret.clone(),
+                    shard,
+                    cluster.settings.config.shards[shard].address,
+                    rx,
+                    cluster.links[shard].up.clone(),
+                ));
+            }
+        }
+        cluster
+    }
+
+    /// The state of the shard, and the receivers of the messages for each shard, without linking to them
+    fn new(
+        settings: ClusterSettings,
+        world: &mut World,
+        stream_filter: WorldStreamFilter,
+    ) -> (Self, Vec<flume::Receiver<ShardMessage>>) {
+        world.add_resource(cluster_region_min(), settings.own().min);
+        world.add_resource(cluster_region_max(), settings.own().max);
+
+        let (links, link_rxs): (Vec<_>, Vec<_>) = settings
+            .config
+            .shards
+            .iter()
+            .map(|_| {
+                let (tx, rx) = flume::unbounded();
+                let up = Arc::new(AtomicBool::new(false));
+                (Link { tx, up }, rx)
+            })
+            .unzip();
+        let (incoming_tx, incoming_rx) = flume::unbounded();
+
+        let mut owned_stream = WorldStream::new(stream_filter.clone().excl(cluster_ghost()));
+        // The entities that exist already are sent in full when they come near a border
+        owned_stream.next_diff(world);
+        let cluster = Self {
+            ghosted: vec![HashSet::new(); settings.config.shards.len()],
+            settings,
+            links,
+            incoming_tx,
+            incoming_rx,
+            inside: HashSet::new(),
+            moved_qs: QueryState::new(),
+            ghost_owners: HashMap::new(),
+            ghost_stream: WorldStream::new(stream_filter.clone().incl(cluster_ghost())),
+            owned_stream,
+            stream_filter,
+        };
+        (cluster, link_rxs)
+    }
+
+    /// Receives the messages of another shard over a link it opened
+    pub(crate) fn accept_link(&self, conn: Connection) -> impl Future<Output = ()> {
+        let incoming_tx = self.incoming_tx.clone();
+        let shards = self.settings.config.shards.len();
+        let own = self.settings.shard;
+        let cluster_secret = self.settings.config.secret.clone();
+        async move {
+            let result = async {
+                let mut recv = stream::RecvStream::new(conn.accept_uni().await?);
+                let shard = match recv.next().await.transpose()? {
+                    Some(ShardMessage::Hello { shard, secret })
+                        if cluster_secret.matches(&secret) =>
+                    {
+                        anyhow::ensure!(shard < shards && shard != own, "Invalid shard {shard}");
+                        shard
+                    }
+                    // The message isn't logged, as it may be a guess of the secret
+                    _ => anyhow::bail!(
+                        "Refused a link from {} without the secret of the cluster",
+                        conn.remote_address()
+                    ),
+                };
+                tracing::info!(shard, "Linked from shard");
+
+                let result = async {
+                    while let Some(msg) = recv.next().await {
+                        incoming_tx.send((shard, Some(msg?))).ok();
+                    }
+                    anyhow::Ok(())
+                }
+                .await;
+                incoming_tx.send((shard, None)).ok();
+                result
+            }
+            .await;
+            log_result!(result);
+        }
+    }
+
+    /// Exchanges the entities at the borders with the other shards. Called every tick, after the instances were
+    /// stepped and before their diffs are broadcast
+    pub fn step(&mut self, state: &mut ServerState) {
+        let Some(instance) = state.instances.get(MAIN_INSTANCE_ID) else {
+            return;
+        };
+        // The changes to ghosts since the last step were made by the systems of this shard, as the changes of the
+        // other shards were skipped
+        self.proxy_ghost_changes(&instance.world);
+
+        for (shard, msg) in self.incoming_rx.try_iter().collect_vec() {
+            self.receive(state, shard, msg);
+        }
+        self.hand_off(state);
+
+        let world = &state.instances.get(MAIN_INSTANCE_ID).unwrap().world;
+        let changes = self.owned_stream.next_diff(world);
+        self.send_ghosts(world, changes);
+        self.ghost_stream.next_diff(world);
+    }
+
+    fn send(&self, shard: usize, msg: ShardMessage) {
+        self.links[shard].tx.send(msg).ok();
+    }
+
+    fn is_linked(&self, shard: usize) -> bool {
+        self.links[shard].up.load(Ordering::Relaxed)
+    }
+
+    fn proxy_ghost_changes(&mut self, world: &World) {
+        let mut proxied = HashMap::<usize, Vec<WorldChange>>::new();
+        for change in self.ghost_stream.next_diff(world).changes {
+            let id = match &change {
+                // The physics of this shard moves the ghosts too, but only the owner's movement counts
+                WorldChange::Set(_, entry) if is_simulated(entry.desc()) => continue,
+                WorldChange::AddComponents(id, _)
+                | WorldChange::RemoveComponents(id, _)
+                | WorldChange::Set(id, _) => *id,
+                // Spawning and despawning ghosts doesn't affect the entities they are copies of
+                WorldChange::Spawn(..) | WorldChange::Despawn(_) => continue,
+            };
+            if let Some(&owner) = self.ghost_owners.get(&id) {
+                proxied.entry(owner).or_default().push(change);
+            }
+        }
+        for (owner, changes) in proxied {
+            self.send(owner, ShardMessage::Proxied(WorldDiff { changes }));
+        }
+    }
+
+    fn receive(&mut self, state: &mut ServerState, from: usize, msg: Option<ShardMessage>) {
+        let ServerState {
+            instances, players, ..
+        } = state;
+        let world = &mut instances.get_mut(MAIN_INSTANCE_ID).unwrap().world;
+
+        match msg {
+            None => {
+                // The ghosts of a shard that is gone can't be kept up to date
+                tracing::warn!(shard = from, "Lost the link from shard");
+                let ghosts = self
+                    .ghost_owners
+                    .iter()
+                    .filter(|(_, &owner)| owner == from)
+                    .map(|(&id, _)| id)
+                    .collect_vec();
+                for id in ghosts {
+                    self.ghost_owners.remove(&id);
+                    world.despawn(id);
+                }
+            }
+            Some(ShardMessage::Hello { .. }) => {}
+            Some(ShardMessage::Handoff { entities, player }) => {
+                let Some(&(root, _)) = entities.first() else {
+                    return;
+                };
+                for (id, entity) in entities {
+                    // The entity replaces its ghost
+                    if self.ghost_owners.remove(&id).is_some() {
+                        world.despawn(id);
+                    }
+                    if !world.spawn_with_id(id, entity) {
+                        tracing::warn!(?id, "A handed off entity already exists");
+                    }
+                    // The sender keeps a ghost of it, until it's no longer near its border
+                    self.ghosted[from].insert(id);
+                }
+                self.inside.insert(root);
+
+                if let Some(HandedOffPlayer {
+                    user_id,
+                    identity_token,
+                }) = player
+                {
+                    tracing::info!(user_id, shard = from, "Took over a player");
+                    world.add_component(root, player_suspended(), ()).ok();
+                    players.insert(
+                        user_id.clone(),
+                        Player::new_handed_off(MAIN_INSTANCE_ID, identity_token),
+                    );
+                    self.send(from, ShardMessage::PlayerTakenOver { user_id });
+                }
+            }
+            Some(ShardMessage::PlayerTakenOver { user_id }) => {
+                let Some(player) = players.remove(&user_id) else {
+                    return;
+                };
+                player.redirect(self.settings.config.shards[from].address);
+                // The player entity is a ghost now, which no longer belongs to the client
+                if let Some(id) = get_connected_by_user_id(world, &user_id) {
+                    world
+                        .remove_components(
+                            id,
+                            vec![
+                                player_connection().desc(),
+                                player_entity_stream().desc(),
+                                player_connection_id().desc(),
+                            ],
+                        )
+                        .ok();
+                }
+            }
+            Some(ShardMessage::Ghosts {
+                spawned,
+                changed,
+                removed,
+            }) => {
+                for (id, entity) in spawned {
+                    match self.ghost_owners.get(&id) {
+                        Some(&owner) if owner == from => {
+                            world.add_components(id, entity).ok();
+                        }
+                        // This shard or another one has authority over it
+                        Some(_) => {}
+                        None if world.exists(id) => {}
+                        None => {
+                            world.spawn_with_id(id, entity.with(cluster_ghost(), ()));
+                            self.ghost_owners.insert(id, from);
+                        }
+                    }
+                }
+                let changes = changed
+                    .changes
+                    .into_iter()
+                    .filter(|change| match change {
+                        WorldChange::AddComponents(id, _)
+                        | WorldChange::RemoveComponents(id, _)
+                        | WorldChange::Set(id, _) => self.ghost_owners.get(id) == Some(&from),
+                        WorldChange::Spawn(..) | WorldChange::Despawn(_) => false,
+                    })
+                    .collect_vec();
+                WorldDiff { changes }.apply(world, Entity::new(), false);
+                for id in removed {
+                    if self.ghost_owners.get(&id) == Some(&from) {
+                        self.ghost_owners.remove(&id);
+                        world.despawn(id);
+                    }
+                }
+            }
+            Some(ShardMessage::Proxied(diff)) => {
+                let changes = diff
+                    .changes
+                    .into_iter()
+                    .filter(|change| match change {
+                        WorldChange::AddComponents(id, _)
+                        | WorldChange::RemoveComponents(id, _)
+                        | WorldChange::Set(id, _) => {
+                            world.exists(*id) && !self.ghost_owners.contains_key(id)
+                        }
+                        WorldChange::Spawn(..) | WorldChange::Despawn(_) => false,
+                    })
+                    .collect_vec();
+                WorldDiff { changes }.apply(world, Entity::new(), false);
+            }
+        }
+    }
+
+    /// Hands the entities that moved into the region of another shard off to it
+    fn hand_off(&mut self, state: &mut ServerState) {
+        let ServerState {
+            instances, players, ..
+        } = state;
+        let world = &mut instances.get_mut(MAIN_INSTANCE_ID).unwrap().world;
+        let own = self.settings.own();
+
+        let moved = query(translation().changed())
+            .excl(cluster_ghost())
+            .excl(parent())
+            .iter(world, Some(&mut self.moved_qs))
+            .map(|(id, translation)| (id, translation.truncate()))
+            .collect_vec();
+        for (id, position) in moved {
+            if own.contains(position) {
+                self.inside.insert(id);
+                continue;
+            }
+            // The entities that this shard spawned outside of its region, and those that left every region, stay
+            let Some(target) = self.settings.config.shard_at(position) else {
+                continue;
+            };
+            if !self.inside.contains(&id) || !self.is_linked(target) {
+                continue;
+            }
+
+            let player = match world.get_cloned(id, ambient_core::player::user_id()) {
+                Ok(user_id) => {
+                    // Clients that can't follow their player to another shard keep it here
+                    let follows = world
+                        .get(id, player_features())
+                        .map_or(false, |features| features.contains(Features::CLUSTER));
+                    let Some(player) = players.get(&user_id).filter(|_| follows) else {
+                        continue;
+                    };
+                    Some(HandedOffPlayer {
+                        identity_token: player.identity_token().to_string(),
+                        user_id,
+                    })
+                }
+                Err(_) => None,
+            };
+
+            tracing::debug!(?id, shard = target, "Handing off entity");
+            let ids = hierarchy(world, id);
+            let entities = ids
+                .iter()
+                .filter_map(|&id| Some((id, world.clone_entity(id).ok()?)))
+                .collect_vec();
+            self.inside.remove(&id);
+            self.ghosted[target].retain(|id| !ids.contains(id));
+            // This shard keeps a ghost of it until the target removes it
+            for id in ids {
+                world.add_component(id, cluster_ghost(), ()).ok();
+                self.ghost_owners.insert(id, target);
+            }
+            self.send(target, ShardMessage::Handoff { entities, player });
+        }
+        self.inside.retain(|&id| world.exists(id));
+    }
+
+    /// Mirrors the entities near the border of each shard to it. The entities that come near it are sent in full, and
+    /// of those that already were, only the `changes` since the last step are sent.
+    fn send_ghosts(&mut self, world: &World, changes: WorldDiff) {
+        let border = self.settings.config.border;
+        for (shard, config) in self.settings.config.shards.iter().enumerate() {
+            if !self.is_linked(shard) {
+                // The shard drops the ghosts of a lost link, so they are sent in full once it's back
+                self.ghosted[shard].clear();
+                continue;
+            }
+            let ids: HashSet<EntityId> = query(translation())
+                .excl(cluster_ghost())
+                .excl(parent())
+                .iter(world, None)
+                .filter(|(_, translation)| config.distance(translation.truncate()) <= border)
+                .flat_map(|(id, _)| hierarchy(world, id))
+                .collect();
+            let ghosted = &self.ghosted[shard];
+            let removed = ghosted.difference(&ids).copied().collect_vec();
+            let spawned = self
+                .stream_filter
+                .spawn_diff(world, ids.difference(ghosted).copied())
+                .changes
+                .into_iter()
+                .filter_map(|change| match change {
+                    WorldChange::Spawn(Some(id), entity) => Some((id, entity)),
+                    _ => None,
+                })
+                .collect_vec();
+            let changed = changes
+                .changes
+                .iter()
+                .filter(|change| match change {
+                    WorldChange::AddComponents(id, _)
+                    | WorldChange::RemoveComponents(id, _)
+                    | WorldChange::Set(id, _) => ghosted.contains(id) && ids.contains(id),
+                    WorldChange::Spawn(..) | WorldChange::Despawn(_) => false,
+                })
+                .cloned()
+                .collect_vec();
+            self.ghosted[shard] = ids;
+            if !spawned.is_empty() || !changed.is_empty() || !removed.is_empty() {
+                self.send(
+                    shard,
+                    ShardMessage::Ghosts {
+                        spawned,
+                        changed: WorldDiff { changes: changed },
+                        removed,
+                    },
+                );
+            }
+        }
+    }
+}
+
+/// Is `conn` a link from another shard, rather than the connection of a client?
+pub(crate) fn is_link(conn: &Connection) -> bool {
+    conn.handshake_data()
+        .and_then(|data| data.downcast::<quinn::crypto::rustls::HandshakeData>().ok())
+        .and_then(|data| data.protocol)
+        .map_or(false, |protocol| protocol == CLUSTER_ALPN)
+}
+
+/// The configuration of the links to the other shards, which use the same certificate as this server
+pub(crate) fn link_config(crypto: &Crypto) -> anyhow::Result<ClientConfig> {
+    let mut roots = RootCertStore::empty();
+    roots
+        .add(&Certificate(crypto.cert.clone()))
+        .context("Invalid certificate")?;
+    let mut tls_config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    tls_config.alpn_protocols = vec![CLUSTER_ALPN.to_vec()];
+    Ok(ClientConfig::new(Arc::new(tls_config)))
+}
+
+/// Sends the messages for the shard `to` over a link to it, and links to it again whenever the link is lost
+#[allow(clippy::too_many_arguments)]
+async fn run_link(
+    endpoint: Endpoint,
+    config: ClientConfig,
+    from: usize,
+    secret: ClusterSecret,
+    to: usize,
+    address: SocketAddr,
+    rx: flume::Receiver<ShardMessage>,
+    up: Arc<AtomicBool>,
+) {
+    loop {
+        let result = async {
+            let conn = endpoint
+                .connect_with(config.clone(), address, "localhost")?
+                .await?;
+            let mut send = stream::SendStream::new(conn.open_uni().await?);
+            send.send(ShardMessage::Hello {
+                shard: from,
+                secret: secret.0.clone(),
+            })
+            .await?;
+            tracing::info!(shard = to, %address, "Linked to shard");
+            up.store(true, Ordering::Relaxed);
+
+            while let Ok(msg) = rx.recv_async().await {
+                send.send(msg).await?;
+            }
+            anyhow::Ok(())
+        }
+        .await;
+        up.store(false, Ordering::Relaxed);
+        match result {
+            Ok(()) => return,
+            Err(err) => tracing::debug!(shard = to, %address, "No link to shard: {err:?}"),
+        }
+        // The messages that were queued are out of date by the time the link is back
+        while rx.try_recv().is_ok() {}
+        tokio::time::sleep(LINK_RETRY_INTERVAL).await;
+    }
+}
+
+/// Is `desc` one of the components that the simulation of every shard changes?
+fn is_simulated(desc: ComponentDesc) -> bool {
+    [translation().desc(), rotation().desc(), scale().desc()].contains(&desc)
+}
+
+/// `id` and all of its descendants
+fn hierarchy(world: &World, id: EntityId) -> Vec<EntityId> {
+    let mut ids = vec![id];
+    let mut i = 0;
+    while i < ids.len() {
+        if let Ok(children) = world.get_ref(ids[i], children()) {
+            ids.extend(children.iter().copied());
+        }
+        i += 1;
+    }
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use ambient_ecs::ArchetypeFilter;
+    use ambient_std::asset_cache::AssetCache;
+    use glam::{vec2, vec3};
+
+    use super::*;
+
+    fn init() {
+        ambient_ecs::init_components();
+        ambient_core::init_all_components();
+        crate::init_all_components();
+    }
+
+    fn config() -> ClusterConfig {
+        let shard = |port, min, max| ShardConfig {
+            address: SocketAddr::from(([127, 0, 0, 1], port)),
+            min,
+            max,
+        };
+        ClusterConfig {
+            secret: ClusterSecret("secret".to_string()),
+            shards: vec![
+                shard(9000, vec2(-100., -100.), vec2(0., 100.)),
+                shard(9001, vec2(0., -100.), vec2(100., 100.)),
+            ],
+            border: default_border(),
+        }
+    }
+
+    /// A shard whose links are up, and the messages it sends to each shard
+    fn shard(index: usize) -> (ServerState, Cluster, Vec<flume::Receiver<ShardMessage>>) {
+        let mut state = ServerState::new_local(AssetCache::new(tokio::runtime::Handle::current()));
+        let world = world(&mut state);
+        world.init_shape_change_tracking();
+        let stream_filter = WorldStreamFilter::new(ArchetypeFilter::new(), Arc::new(|_, _| true));
+        let settings = ClusterSettings::new(config(), index).unwrap();
+        let (cluster, link_rxs) = Cluster::new(settings, world, stream_filter);
+        for link in &cluster.links {
+            link.up.store(true, Ordering::Relaxed);
+        }
+        (state, cluster, link_rxs)
+    }
+
+    fn world(state: &mut ServerState) -> &mut World {
+        &mut state.instances.get_mut(MAIN_INSTANCE_ID).unwrap().world
+    }
+
+    #[test]
+    fn shard_at_finds_the_region() {
+        let config = config();
+        assert_eq!(config.shard_at(vec2(-50., 0.)), Some(0));
+        // The regions include their lower corner, but not their upper one
+        assert_eq!(config.shard_at(vec2(0., 0.)), Some(1));
+        assert_eq!(config.shard_at(vec2(50., 99.)), Some(1));
+        assert_eq!(config.shard_at(vec2(50., 100.)), None);
+        assert_eq!(config.shard_at(vec2(-200., 0.)), None);
+    }
+
+    #[test]
+    fn settings_need_a_secret_and_a_shard() {
+        assert!(ClusterSettings::new(config(), 1).is_ok());
+        assert!(ClusterSettings::new(config(), 2).is_err());
+        let mut config = config();
+        config.secret = ClusterSecret(String::new());
+        assert!(ClusterSettings::new(config, 0).is_err());
+
+        let secret = ClusterSecret("secret".to_string());
+        assert!(secret.matches("secret"));
+        assert!(!secret.matches("secrex"));
+        assert!(!secret.matches("secret2"));
+        assert!(!format!("{secret:?}").contains("secret"));
+    }
+
+    #[tokio::test]
+    async fn entities_are_handed_off_with_their_ids() {
+        init();
+        let (mut from_state, mut from, from_rxs) = shard(0);
+        let (mut to_state, mut to, _) = shard(1);
+        let id = Entity::new()
+            .with(translation(), vec3(-50., 0., 0.))
+            .spawn(world(&mut from_state));
+        from.hand_off(&mut from_state);
+        assert!(from_rxs[1].try_recv().is_err());
+
+        world(&mut from_state)
+            .set(id, translation(), vec3(50., 0., 0.))
+            .unwrap();
+        from.hand_off(&mut from_state);
+        let msg = from_rxs[1].try_recv().unwrap();
+        let ShardMessage::Handoff {
+            entities,
+            player: None,
+        } = &msg
+        else {
+            panic!("Expected a handoff, got {msg:?}");
+        };
+        assert_eq!(entities.iter().map(|(id, _)| *id).collect_vec(), vec![id]);
+        // The sender keeps a ghost of it until the target removes it
+        assert!(world(&mut from_state).has_component(id, cluster_ghost()));
+        assert_eq!(from.ghost_owners.get(&id), Some(&1));
+
+        to.receive(&mut to_state, 0, Some(msg));
+        let to_world = world(&mut to_state);
+        assert_eq!(to_world.get(id, translation()).unwrap(), vec3(50., 0., 0.));
+        assert!(!to_world.has_component(id, cluster_ghost()));
+        assert!(to.inside.contains(&id));
+    }
+
+    #[tokio::test]
+    async fn ghosts_are_sent_in_full_once_and_then_as_changes() {
+        init();
+        let (mut from_state, mut from, from_rxs) = shard(0);
+        let (mut to_state, mut to, _) = shard(1);
+        let mut step = |from_state: &mut ServerState, to_state: &mut ServerState| {
+            let world = world(from_state);
+            let changes = from.owned_stream.next_diff(world);
+            from.send_ghosts(world, changes);
+            let msg = from_rxs[1].try_recv().ok();
+            if let Some(ShardMessage::Ghosts {
+                spawned,
+                changed,
+                removed,
+            }) = &msg
+            {
+                let counts = (spawned.len(), changed.changes.len(), removed.len());
+                to.receive(to_state, 0, msg);
+                counts
+            } else {
+                (0, 0, 0)
+            }
+        };
+
+        let id = Entity::new()
+            .with(translation(), vec3(-10., 0., 0.))
+            .spawn(world(&mut from_state));
+        assert_eq!(step(&mut from_state, &mut to_state), (1, 0, 0));
+        assert!(world(&mut to_state).has_component(id, cluster_ghost()));
+        assert_eq!(step(&mut from_state, &mut to_state), (0, 0, 0));
+
+        world(&mut from_state)
+            .set(id, translation(), vec3(-5., 0., 0.))
+            .unwrap();
+        assert_eq!(step(&mut from_state, &mut to_state), (0, 1, 0));
+        assert_eq!(
+            world(&mut to_state).get(id, translation()).unwrap(),
+            vec3(-5., 0., 0.)
+        );
+
+        world(&mut from_state)
+            .set(id, translation(), vec3(-50., 0., 0.))
+            .unwrap();
+        assert_eq!(step(&mut from_state, &mut to_state), (0, 0, 1));
+        assert!(!world(&mut to_state).exists(id));
+    }
+}
@@ -2,4 +2,5 @@
 //!
 //! This included quinn server+client and webtransport server using `h3`
 pub mod client;
+pub mod cluster;
 pub mod server;
@@ -7,17 +7,19 @@ use std::{
 
 use ambient_core::{asset_cache, no_sync, project_name};
 use ambient_ecs::{
-    ArchetypeFilter, ComponentDesc, ComponentRegistry, System, SystemGroup, World, WorldStream,
-    WorldStreamCompEvent, WorldStreamFilter,
+    generated::components::core::instance::instance_id, ArchetypeFilter, ComponentDesc,
+    ComponentRegistry, System, SystemGroup, World, WorldStream, WorldStreamCompEvent,
+    WorldStreamFilter,
 };
 use ambient_proxy::client::AllocatedEndpoint;
 use ambient_std::{
     asset_cache::{AssetCache, SyncAssetKeyExt},
     asset_url::{AbsAssetUrl, ServerBaseUrlKey},
     fps_counter::FpsCounter,
-    log_result,
+    log_result, metrics,
 };
 use ambient_sys::time::Instant;
+use bytes::BytesMut;
 use colored::Colorize;
 use futures::StreamExt;
 use parking_lot::{Mutex, RwLock};
@@ -27,17 +29,20 @@ use tokio::time::{interval, MissedTickBehavior};
 use uuid::Uuid;
 
 use crate::{
+    auth::{Authenticator, OpenAuthenticator},
     client_connection::ConnectionKind,
+    metrics::TICK_DURATION,
+    native::cluster::{self, Cluster, ClusterSettings},
     proto::{
-        self,
+        self, compat,
         server::{handle_diffs, ConnectionData},
-        ServerInfo, ServerPush, VERSION,
+        ClientRequest, Handshake, ServerInfo, ServerPush, VERSION,
     },
     server::{
         server_stats, ForkingEvent, ProxySettings, ServerState, SharedServerState, ShutdownEvent,
-        WorldInstance, MAIN_INSTANCE_ID,
+        SpectatorStream, WorldInstance, DEFAULT_TICK_RATE, MAIN_INSTANCE_ID,
     },
-    stream, ServerWorldExt,
+    stream, ServerWorldExt, MAX_REQUEST_FRAME_SIZE,
 };
 
 #[derive(Debug, Clone)]
@@ -53,17 +58,26 @@ pub struct GameServer {
     /// Shuts down the server if there are no players
     pub use_inactivity_shutdown: bool,
     proxy_settings: Option<ProxySettings>,
+    /// The shard of a cluster this server is, and the configuration of the links to the other shards
+    cluster: Option<(ClusterSettings, ClientConfig)>,
+    /// Decides which users may connect; everyone by default
+    pub authenticator: Arc<dyn Authenticator>,
 }
 impl GameServer {
     pub async fn new_with_port(
         port: u16,
         use_inactivity_shutdown: bool,
         proxy_settings: Option<ProxySettings>,
+        cluster: Option<ClusterSettings>,
         crypto: &Crypto,
     ) -> anyhow::Result<Self> {
         let server_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), port);
 
         let endpoint = create_server(server_addr, crypto)?;
+        let cluster = match cluster {
+            Some(settings) => Some((settings, cluster::link_config(crypto)?)),
+            None => None,
+        };
 
         tracing::debug!("GameServer listening on port {}", port);
         Ok(Self {
@@ -71,12 +85,15 @@ impl GameServer {
             port,
             use_inactivity_shutdown,
             proxy_settings,
+            cluster,
+            authenticator: Arc::new(OpenAuthenticator),
         })
     }
     pub async fn new_with_port_in_range(
         port_range: Range<u16>,
         use_inactivity_shutdown: bool,
         proxy_settings: Option<ProxySettings>,
+        cluster: Option<ClusterSettings>,
         crypto: &Crypto,
     ) -> anyhow::Result<Self> {
         for port in port_range {
@@ -84,6 +101,7 @@ impl GameServer {
                 port,
                 use_inactivity_shutdown,
                 proxy_settings.clone(),
+                cluster.clone(),
                 crypto,
             )
             .await
@@ -110,11 +128,27 @@ impl GameServer {
         let Self {
             endpoint,
             proxy_settings,
+            cluster,
+            authenticator,
             ..
         } = self;
         let assets = world.resource(asset_cache()).clone();
+        if let Some(id) = world.synced_resource_entity() {
+            world
+                .add_component(id, instance_id(), MAIN_INSTANCE_ID.to_string())
+                .unwrap();
+        }
         let world_stream_filter =
             WorldStreamFilter::new(ArchetypeFilter::new().excl(no_sync()), is_sync_component);
+        let mut cluster = cluster.map(|(settings, link_config)| {
+            Cluster::start(
+                settings,
+                link_config,
+                endpoint.clone(),
+                &mut world,
+                world_stream_filter.clone(),
+            )
+        });
         let state = Arc::new(Mutex::new(ServerState::new(
             assets.clone(),
             [(
@@ -123,6 +157,9 @@ impl GameServer {
                     systems: create_server_systems(&mut world),
                     world,
                     world_stream: WorldStream::new(world_stream_filter.clone()),
+                    last_step: None,
+                    spectator_stream: SpectatorStream::new(&world_stream_filter),
+                    diff_buffer: BytesMut::new(),
                 },
             )]
             .into_iter()
@@ -133,7 +170,10 @@ impl GameServer {
         )));
 
         let mut fps_counter = FpsCounter::new();
-        let mut sim_interval = interval(Duration::from_secs_f32(1. / 60.));
+        // The loop runs at the tick rate of the fastest instance (but at least the default one, so that new players
+        // are picked up quickly); slower instances skip some of its ticks
+        let mut loop_tick_rate = DEFAULT_TICK_RATE;
+        let mut sim_interval = interval(Duration::from_secs_f32(1. / loop_tick_rate));
         sim_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
         let mut inactivity_interval = interval(Duration::from_secs_f32(5.));
@@ -143,6 +183,7 @@ impl GameServer {
             let endpoint = endpoint.clone();
             let state = state.clone();
             let world_stream_filter = world_stream_filter.clone();
+            let authenticator = authenticator.clone();
             let assets = assets.clone();
             tokio::spawn(async move {
                 start_proxy_connection(
@@ -150,6 +191,7 @@ impl GameServer {
                     proxy_settings,
                     state.clone(),
                     world_stream_filter.clone(),
+                    authenticator,
                     assets.clone(),
                 )
                 .await;
@@ -171,25 +213,43 @@ impl GameServer {
                     };
 
 
+                    if let Some(cluster) = cluster.as_ref().filter(|_| cluster::is_link(&conn)) {
+                        tokio::spawn(cluster.accept_link(conn));
+                        continue;
+                    }
+
                     tracing::debug!("Accepted connection");
-                    let fut = handle_quinn_connection(conn.into(), state.clone(), world_stream_filter.clone(), ServerBaseUrlKey.get(&assets));
+                    let fut = handle_quinn_connection(conn.into(), state.clone(), world_stream_filter.clone(), authenticator.clone(), ServerBaseUrlKey.get(&assets));
                     tokio::spawn(async move {  log_result!(fut.await) });
                 }
                 _ = sim_interval.tick() => {
                     fps_counter.frame_start();
+                    let tick_start = Instant::now();
                     let mut state = state.lock();
                     tokio::task::block_in_place(|| {
                         ambient_profiling::finish_frame!();
                         ambient_profiling::scope!("sim_tick");
                         state.step();
+                        if let Some(cluster) = &mut cluster {
+                            cluster.step(&mut state);
+                        }
                         state.broadcast_diffs();
+                        metrics::observe(TICK_DURATION, &[], tick_start.elapsed().as_secs_f64());
+                        ambient_std::frame_arena::take_frame_arena_stats();
                         if let Some(sample) = fps_counter.frame_end() {
                             for instance in state.instances.values_mut() {
                                 let id = instance.world.synced_resource_entity().unwrap();
                                 instance.world.add_component(id, server_stats(), sample.clone()).unwrap();
                             }
+                            state.record_metrics();
                         }
                     });
+                    let tick_rate = state.max_tick_rate().max(DEFAULT_TICK_RATE);
+                    if tick_rate != loop_tick_rate {
+                        loop_tick_rate = tick_rate;
+                        sim_interval = interval(Duration::from_secs_f32(1. / loop_tick_rate));
+                        sim_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                    }
                 }
                 _ = inactivity_interval.tick(), if self.use_inactivity_shutdown => {
                     if state.lock().player_count() == 0 {
@@ -227,6 +287,7 @@ async fn handle_quinn_connection(
     conn: ConnectionKind,
     state: SharedServerState,
     world_stream_filter: WorldStreamFilter,
+    authenticator: Arc<dyn Authenticator>,
     content_base_url: AbsAssetUrl,
 ) -> anyhow::Result<()> {
     tracing::info!("Handling server connection");
@@ -246,13 +307,16 @@ async fn handle_quinn_connection(
             content_base_url,
             version: VERSION.into(),
             external_components,
+            handshake: Handshake::current(),
         }
     };
 
     let mut server = proto::server::ServerState::default();
 
     tracing::info!("Accepting request stream from client");
-    let mut request_recv = stream::RecvStream::new(conn.accept_uni().await?);
+    let mut request_recv =
+        stream::RecvStream::with_decoder(conn.accept_uni().await?, compat::decode_client_request)
+            .with_max_frame_size(MAX_REQUEST_FRAME_SIZE);
     tracing::info!("Opening control stream");
     let mut push_send = stream::SendStream::new(conn.open_uni().await?);
 
@@ -272,12 +336,31 @@ async fn handle_quinn_connection(
         diff_tx: diffs_tx,
         connection_id: Uuid::new_v4(),
         world_stream_filter,
+        local: conn.is_local(),
     };
 
     while server.is_pending_connection() {
         tracing::info!("Waiting for connect request");
         if let Some(frame) = request_recv.next().await {
-            server.process_control(&data, frame?)?;
+            let frame = frame?;
+            if let ClientRequest::Connect {
+                user_id,
+                auth_ticket,
+                ..
+            } = &frame
+            {
+                if let Err(err) = authenticator
+                    .authenticate(user_id, auth_ticket.as_ref())
+                    .await
+                {
+                    tracing::warn!(
+                        user_id,
+                        "Refusing a client that failed to authenticate: {err:#}"
+                    );
+                    return Err(err);
+                }
+            }
+            server.process_control(&data, frame)?;
         }
     }
 
@@ -289,26 +372,36 @@ async fn handle_quinn_connection(
     ));
 
     // Before a connection has been established, only process the control stream
-    while let proto::server::ServerState::Connected(connected) = &mut server {
-        tokio::select! {
-            Some(frame) = request_recv.next() => {
-                server.process_control(&data, frame?)?;
-            }
-            stream = conn.accept_uni() => {
-                connected.process_uni(&data, stream?).await?;
-            }
-            stream = conn.accept_bi() => {
-                let (send, recv) = stream?;
-                connected.process_bi(&data, send, recv).await?;
-            }
-            datagram = conn.read_datagram() => {
-                connected.process_datagram(&data, datagram?).await?;
-            }
-            Some(msg) = connected.control_rx.next() => {
-                push_send.send(&msg).await?;
+    let result = async {
+        while let proto::server::ServerState::Connected(connected) = &mut server {
+            tokio::select! {
+                Some(frame) = request_recv.next() => {
+                    server.process_control(&data, frame?)?;
+                }
+                stream = conn.accept_uni() => {
+                    connected.process_uni(&data, stream?).await?;
+                }
+                stream = conn.accept_bi() => {
+                    let (send, recv) = stream?;
+                    connected.process_bi(&data, send, recv).await?;
+                }
+                datagram = conn.read_datagram() => {
+                    connected.process_datagram(&data, datagram?).await?;
+                }
+                Some(msg) = connected.control_rx.next() => {
+                    push_send.send(&msg).await?;
+                }
             }
         }
+        anyhow::Ok(())
+    }
+    .await;
+
+    if result.is_err() {
+        // The client didn't disconnect, so keep its player around for it to reconnect to
+        server.process_connection_lost(&data);
     }
+    result?;
 
     tracing::info!("Client disconnected");
 
@@ -320,6 +413,7 @@ async fn start_proxy_connection(
     settings: ProxySettings,
     state: SharedServerState,
     world_stream_filter: WorldStreamFilter,
+    authenticator: Arc<dyn Authenticator>,
     assets: AssetCache,
 ) {
     // start with content base url being the same as for direct connections
@@ -365,6 +459,7 @@ async fn start_proxy_connection(
                     conn.into(),
                     state.clone(),
                     world_stream_filter.clone(),
+                    authenticator.clone(),
                     content_base_url.read().clone(),
                 );
 
@@ -437,6 +532,7 @@ fn create_server(server_addr: SocketAddr, crypto: &Crypto) -> anyhow::Result<End
         b"h3-30".to_vec(),
         b"h3-29".to_vec(),
         b"ambient-02".to_vec(),
+        cluster::CLUSTER_ALPN.to_vec(),
     ];
 
     tls_config.alpn_protocols = alpn;
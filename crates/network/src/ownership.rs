@@ -0,0 +1,263 @@
+//! Client-authoritative components: components of entities owned by a client, which that client sets and sends to the
+//! server instead of the other way around, so that fast-changing values like an aim direction don't make a round trip.
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use ambient_core::{
+    no_sync,
+    player::{local_user_id, user_id},
+    remove_at_time, time,
+};
+use ambient_ecs::{
+    dont_store, query, ComponentDesc, ComponentRegistry, Entity, EntityId, FnSystem, SystemGroup,
+    World, WorldChange, WorldDiff,
+};
+use ambient_std::asset_cache::AssetCache;
+use bytes::Bytes;
+use itertools::Itertools;
+
+pub use ambient_ecs::generated::components::core::network::{
+    client_authoritative, client_authority_accepted, client_authority_corrected,
+    client_authority_proposal, client_authority_validated, owner,
+};
+use ambient_ecs::generated::components::core::physics::sleeping;
+
+use crate::{
+    client::{self, game_client, server_protocol},
+    log_network_result,
+    proto::Features,
+    server::{self, get_connected_by_user_id, player_connection, SharedServerState},
+    CLIENT_AUTHORITY_DATAGRAM_ID,
+};
+
+/// How long a proposal waits for the validators of its component before it is dropped
+const PROPOSAL_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Is `component` of the entity `id` set by the client of `user_id`?
+pub fn is_client_authoritative(
+    world: &World,
+    id: EntityId,
+    user_id: &str,
+    component: ComponentDesc,
+) -> bool {
+    // Only the server simulates physics, so it alone decides which bodies are asleep
+    if component == sleeping() {
+        return false;
+    }
+    world
+        .get_ref(id, owner())
+        .map_or(false, |owner| owner == user_id)
+        && world
+            .get_ref(id, client_authoritative())
+            .map_or(false, |components| components.contains(&component.path()))
+}
+
+/// The client-authoritative component whose value the `proposal` entity holds
+pub fn proposed_component(world: &World, proposal: EntityId) -> Option<ComponentDesc> {
+    let entity = world.get(proposal, client_authority_proposal()).ok()?;
+    world
+        .get_ref(entity, client_authoritative())
+        .ok()?
+        .iter()
+        .filter_map(|path| ComponentRegistry::get().get_by_path(path))
+        .find(|&component| world.has_component(proposal, component))
+}
+
+/// Applies the values of client-authoritative components that the client of `user_id` sent. Values for entities the
+/// client doesn't own are ignored, and values of components with validators are spawned as proposals instead.
+pub fn apply_client_update(world: &mut World, user_id: &str, diff: WorldDiff) {
+    let validated = world
+        .resource_opt(client_authority_validated())
+        .cloned()
+        .unwrap_or_default();
+    let expires_at = *world.resource(time()) + PROPOSAL_TIMEOUT;
+    for change in diff.changes {
+        let WorldChange::Set(id, entry) = change else {
+            continue;
+        };
+        let component = entry.desc();
+        if !is_client_authoritative(world, id, user_id, component) {
+            log::warn!(
+                "User {user_id:?} tried to set {} of {id}, which it doesn't own",
+                component.path()
+            );
+            continue;
+        }
+        if validated.contains(&component.path()) {
+            let mut data = Entity::new()
+                .with(client_authority_proposal(), id)
+                .with(ambient_core::player::user_id(), user_id.to_string())
+                .with(remove_at_time(), expires_at)
+                .with(no_sync(), ())
+                .with_default(dont_store());
+            data.set_entry(entry);
+            data.spawn(world);
+        } else {
+            world.set_entry(id, entry).ok();
+        }
+    }
+}
+
+/// Removes the values of the client-authoritative components of the entities the local user owns from a `diff` sent
+/// by the server, as the local values are newer
+pub fn filter_server_diff(world: &World, mut diff: WorldDiff) -> WorldDiff {
+    let Some(local_user_id) = world.resource_opt(local_user_id()) else {
+        return diff;
+    };
+    diff.changes.retain(|change| match change {
+        WorldChange::Set(id, entry) => {
+            !is_client_authoritative(world, *id, local_user_id, entry.desc())
+        }
+        _ => true,
+    });
+    diff
+}
+
+pub fn register_server_datagram_handler(handlers: &mut server::DatagramHandlers) {
+    handlers.insert(
+        CLIENT_AUTHORITY_DATAGRAM_ID,
+        ("server_client_authority", Arc::new(on_server_datagram)),
+    );
+}
+
+pub fn register_client_datagram_handler(handlers: &mut client::DatagramHandlers) {
+    handlers.insert(
+        CLIENT_AUTHORITY_DATAGRAM_ID,
+        ("client_client_authority", Arc::new(on_client_datagram)),
+    );
+}
+
+fn on_server_datagram(state: SharedServerState, _assets: AssetCache, user_id: &str, bytes: Bytes) {
+    let mut state = state.lock();
+    let Some(world) = state.get_player_world_mut(user_id) else {
+        log::warn!(
+            "Failed to find player world for {user_id} when processing client authority update"
+        );
+        return;
+    };
+    match bincode::deserialize(&bytes) {
+        Ok(diff) => apply_client_update(world, user_id, diff),
+        Err(err) => log::warn!("Failed to decode client authority update from {user_id}: {err:?}"),
+    }
+}
+
+/// Applies the corrections of the server to values the local client sent
+fn on_client_datagram(world: &mut World, _assets: AssetCache, bytes: Bytes) {
+    match bincode::deserialize::<WorldDiff>(&bytes) {
+        Ok(diff) => {
+            diff.apply(world, Entity::new(), false);
+        }
+        Err(err) => log::warn!("Failed to decode client authority correction: {err:?}"),
+    }
+}
+
+/// Applies the proposals that validators have decided on, and sends the resulting values to the owners whose values
+/// were changed or rejected
+pub fn server_systems() -> SystemGroup {
+    SystemGroup::new(
+        "ownership/server",
+        vec![
+            query((client_authority_proposal(), user_id())).to_system(|q, world, qs, _| {
+                let decided = q
+                    .iter(world, qs)
+                    .filter(|(id, _)| {
+                        world.has_component(*id, client_authority_accepted())
+                            || world.has_component(*id, client_authority_corrected())
+                    })
+                    .map(|(id, (entity, user_id))| (id, *entity, user_id.clone()))
+                    .collect_vec();
+                for (proposal, entity, user_id) in decided {
+                    let component = proposed_component(world, proposal);
+                    let data = world.despawn(proposal).unwrap();
+                    let Some(component) = component else {
+                        continue;
+                    };
+                    if data.contains(client_authority_accepted()) {
+                        let entry = data.get_entry(component).unwrap().clone();
+                        world.set_entry(entity, entry).ok();
+                    }
+                    if data.contains(client_authority_corrected()) {
+                        send_correction(world, &user_id, entity, component);
+                    }
+                }
+            }),
+        ],
+    )
+}
+
+fn send_correction(world: &World, user_id: &str, entity: EntityId, component: ComponentDesc) {
+    let (Some(player), Ok(entry)) = (
+        get_connected_by_user_id(world, user_id),
+        world.get_entry(entity, component),
+    ) else {
+        return;
+    };
+    let Ok(connection) = world.get_ref(player, player_connection()) else {
+        return;
+    };
+    let diff = WorldDiff::new().set_entry(entity, entry);
+    let bytes = bincode::serialize(&diff).unwrap();
+    log_network_result!(connection.send_datagram(CLIENT_AUTHORITY_DATAGRAM_ID, bytes.into()));
+}
+
+/// Sends the values of the client-authoritative components of the entities the local user owns to the server when
+/// they change
+pub fn client_systems() -> SystemGroup {
+    // The content versions of the values that were last sent, by entity and component index
+    let mut sent_versions = HashMap::<(EntityId, u32), u64>::new();
+    SystemGroup::new(
+        "ownership/client",
+        vec![Box::new(FnSystem::new(move |world, _| {
+            let Some(Some(game_client)) = world.resource_opt(game_client()) else {
+                return;
+            };
+            if !world
+                .resource_opt(server_protocol())
+                .map_or(false, |protocol| {
+                    protocol.features.contains(Features::CLIENT_AUTHORITY)
+                })
+            {
+                return;
+            }
+            let local_user_id = world.resource(local_user_id());
+
+            let mut diff = WorldDiff::new();
+            for (id, (owner, components)) in
+                query((owner(), client_authoritative())).iter(world, None)
+            {
+                if owner != local_user_id {
+                    continue;
+                }
+                for component in components
+                    .iter()
+                    .filter_map(|path| ComponentRegistry::get().get_by_path(path))
+                {
+                    let Ok(version) = world.get_component_content_version(id, component.index())
+                    else {
+                        continue;
+                    };
+                    // The value the entity had when it was first seen came from the server
+                    let previous = sent_versions.insert((id, component.index()), version);
+                    if matches!(previous, Some(sent) if sent < version) {
+                        diff = diff.set_entry(id, world.get_entry(id, component).unwrap());
+                    }
+                }
+            }
+            sent_versions.retain(|(id, _), _| world.exists(*id));
+
+            if diff.is_empty() {
+                return;
+            }
+            let bytes = match bincode::serialize(&diff) {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    log::warn!("Failed to encode client-authoritative components: {err:?}");
+                    return;
+                }
+            };
+            log_network_result!(game_client
+                .connection
+                .send_datagram(CLIENT_AUTHORITY_DATAGRAM_ID, bytes.into()));
+        }))],
+    )
+}
@@ -0,0 +1,495 @@
+//! Replication of the tracked poses of XR players. Clients send the poses of their player to the server in datagrams,
+//! and the server sends them on to the other players, less often the further away (or out of view) they are. The
+//! receiving clients buffer the poses and play them back slightly delayed, so they can interpolate between them.
+
+use std::{collections::HashMap, f32::consts::PI, sync::Arc};
+
+use ambient_core::{
+    player::{get_by_user_id, local_user_id, player_suspended},
+    time,
+    transform::get_world_position,
+};
+use ambient_ecs::{components, query, EntityId, FnSystem, SystemGroup, World};
+use ambient_std::asset_cache::AssetCache;
+use bytes::Bytes;
+use glam::{Mat4, Quat, Vec3};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+pub use ambient_ecs::generated::components::core::xr::{
+    head_pose, left_hand_pose, pose_sync_falloff, pose_sync_min_rate, pose_sync_rate,
+    right_hand_pose,
+};
+
+use crate::{
+    client::{self, game_client, server_protocol},
+    log_network_result,
+    proto::Features,
+    server::{
+        self, get_connected_by_user_id, player_connection, player_features, SharedServerState,
+    },
+    POSE_SYNC_DATAGRAM_ID,
+};
+
+const DEFAULT_RATE: f32 = 30.;
+const DEFAULT_FALLOFF: f32 = 5.;
+const DEFAULT_MIN_RATE: f32 = 2.;
+/// How often the poses are sent while they don't change, in seconds, so that the receivers can tell a player that
+/// holds still from one whose poses are lost
+const KEEPALIVE_INTERVAL: f64 = 1.;
+/// How many players' poses go in one datagram, to stay below the MTU
+const SAMPLES_PER_DATAGRAM: usize = 8;
+/// How far past the last pose a player's poses are extrapolated when the next one is late, in seconds
+const MAX_EXTRAPOLATION: f64 = 0.1;
+/// How much the estimated clock offset of a sender may grow per second, so that it follows clock drift
+const CLOCK_DRIFT: f64 = 0.01;
+/// Played back poses lag this much more than the interval between them, to absorb the jitter of the network
+const JITTER_MARGIN: f64 = 0.02;
+/// The cutoff frequency of the hand smoothing at rest, in Hz; lower hides more jitter
+const HAND_MIN_CUTOFF: f32 = 1.5;
+/// How much the cutoff frequency of the hand smoothing rises with the speed of the hand, so that fast movements don't lag
+const HAND_SPEED_CUTOFF: f32 = 8.;
+
+components!("network::pose_sync", {
+    /// The last poses the server received from the player's client
+    pose_sync_sample: PoseSample,
+    /// The poses a client received for a remote player, which it plays back
+    pose_sync_buffer: PoseBuffer,
+});
+
+/// A tracked pose in world space
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Pose {
+    pub position: Vec3,
+    pub rotation: Quat,
+}
+impl Pose {
+    fn from_mat4(transform: Mat4) -> Self {
+        let (_, rotation, position) = transform.to_scale_rotation_translation();
+        Self { position, rotation }
+    }
+    fn to_mat4(self) -> Mat4 {
+        Mat4::from_rotation_translation(self.rotation, self.position)
+    }
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Self {
+            position: self.position.lerp(other.position, t),
+            rotation: self.rotation.slerp(other.rotation, t),
+        }
+    }
+}
+
+/// The poses of a player at one point in time
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PoseSample {
+    /// When the poses were sampled, in seconds, on the clock of the client they came from
+    pub time: f64,
+    pub head: Option<Pose>,
+    pub left_hand: Option<Pose>,
+    pub right_hand: Option<Pose>,
+}
+impl PoseSample {
+    fn from_world(world: &World, id: EntityId, time: f64) -> Self {
+        Self {
+            time,
+            head: world.get(id, head_pose()).ok().map(Pose::from_mat4),
+            left_hand: world.get(id, left_hand_pose()).ok().map(Pose::from_mat4),
+            right_hand: world.get(id, right_hand_pose()).ok().map(Pose::from_mat4),
+        }
+    }
+    fn is_empty(&self) -> bool {
+        self.head.is_none() && self.left_hand.is_none() && self.right_hand.is_none()
+    }
+    fn same_poses(&self, other: &Self) -> bool {
+        self.head == other.head
+            && self.left_hand == other.left_hand
+            && self.right_hand == other.right_hand
+    }
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        let lerp = |a: Option<Pose>, b: Option<Pose>| match (a, b) {
+            (Some(a), Some(b)) => Some(a.lerp(b, t)),
+            (a, b) => b.or(a),
+        };
+        Self {
+            time: self.time + (other.time - self.time) * t as f64,
+            head: lerp(self.head, other.head),
+            left_hand: lerp(self.left_hand, other.left_hand),
+            right_hand: lerp(self.right_hand, other.right_hand),
+        }
+    }
+    fn apply(&self, world: &mut World, id: EntityId) {
+        for (pose, component) in [
+            (self.head, head_pose()),
+            (self.left_hand, left_hand_pose()),
+            (self.right_hand, right_hand_pose()),
+        ] {
+            match pose {
+                Some(pose) => world.add_component(id, component, pose.to_mat4()).unwrap(),
+                None => world.remove_component(id, component).unwrap(),
+            }
+        }
+    }
+}
+
+/// The poses a client received for a remote player, and the state of their playback
+#[derive(Debug, Clone, Default)]
+pub struct PoseBuffer {
+    samples: Vec<PoseSample>,
+    /// The smallest difference seen between when a sample arrived, on the local clock, and its time
+    clock_offset: f64,
+    last_received: f64,
+    /// The average time between two samples, in seconds
+    interval: f64,
+    /// The smoothed hand poses that were applied last, and when
+    hands: Option<(f64, Option<Pose>, Option<Pose>)>,
+}
+impl PoseBuffer {
+    fn push(&mut self, sample: PoseSample, now: f64) {
+        if self
+            .samples
+            .last()
+            .map_or(false, |last| last.time >= sample.time)
+        {
+            return;
+        }
+        let offset = now - sample.time;
+        if let Some(last) = self.samples.last() {
+            let interval = sample.time - last.time;
+            self.interval += (interval - self.interval) * 0.1;
+            self.clock_offset =
+                offset.min(self.clock_offset + (now - self.last_received) * CLOCK_DRIFT);
+        } else {
+            self.interval = 1. / DEFAULT_RATE as f64;
+            self.clock_offset = offset;
+        }
+        self.last_received = now;
+        self.samples.push(sample);
+    }
+    /// The poses at `now` on the local clock, which lag behind the newest sample by about one and a half intervals
+    fn sample(&mut self, now: f64) -> Option<PoseSample> {
+        let time = now - self.clock_offset - self.interval * 1.5 - JITTER_MARGIN;
+        // Only the last sample before `time` is needed to interpolate from, and the last two to extrapolate from
+        let past = self.samples.iter().take_while(|s| s.time <= time).count();
+        let stale = past
+            .saturating_sub(1)
+            .min(self.samples.len().saturating_sub(2));
+        self.samples.drain(..stale);
+        match &self.samples[..] {
+            [] => None,
+            [only] => Some(only.clone()),
+            [first, ..] if time <= first.time => Some(first.clone()),
+            [.., previous, last] if time > last.time => {
+                // Late: keep moving the way the poses were moving for a while
+                let span = last.time - previous.time;
+                let t = (time - last.time).min(MAX_EXTRAPOLATION) / span.max(f64::EPSILON);
+                let extrapolate = |a: Option<Pose>, b: Option<Pose>| match (a, b) {
+                    (Some(a), Some(b)) => Some(Pose {
+                        position: b.position + (b.position - a.position) * t as f32,
+                        rotation: b.rotation,
+                    }),
+                    (_, b) => b,
+                };
+                Some(PoseSample {
+                    time,
+                    head: extrapolate(previous.head, last.head),
+                    left_hand: extrapolate(previous.left_hand, last.left_hand),
+                    right_hand: extrapolate(previous.right_hand, last.right_hand),
+                })
+            }
+            [from, to, ..] => {
+                let t = ((time - from.time) / (to.time - from.time).max(f64::EPSILON)) as f32;
+                Some(from.lerp(to, t.clamp(0., 1.)))
+            }
+        }
+    }
+    /// Drops the buffered poses, e.g. when the player teleported, so that the playback doesn't glide across the jump.
+    /// The estimated clock offset and interval are kept.
+    pub(crate) fn clear(&mut self) {
+        self.samples.clear();
+        self.hands = None;
+    }
+    /// Smooths the hands of `sample`, whose tracking jitters more than the head's
+    fn smooth_hands(&mut self, mut sample: PoseSample, now: f64) -> PoseSample {
+        if let Some((last, left_hand, right_hand)) = self.hands {
+            let dt = (now - last) as f32;
+            sample.left_hand = smooth_pose(left_hand, sample.left_hand, dt);
+            sample.right_hand = smooth_pose(right_hand, sample.right_hand, dt);
+        }
+        self.hands = Some((now, sample.left_hand, sample.right_hand));
+        sample
+    }
+}
+
+/// A one euro filter: smooths a lot while the pose is still, where jitter shows, and little while it moves fast
+fn smooth_pose(previous: Option<Pose>, next: Option<Pose>, dt: f32) -> Option<Pose> {
+    let (Some(previous), Some(next)) = (previous, next) else {
+        return next;
+    };
+    if dt <= 0. {
+        return Some(previous);
+    }
+    let speed = previous.position.distance(next.position) / dt;
+    let cutoff = HAND_MIN_CUTOFF + HAND_SPEED_CUTOFF * speed;
+    let alpha = 1. / (1. + 1. / (2. * PI * cutoff * dt));
+    Some(previous.lerp(next, alpha))
+}
+
+/// The rate, in updates per second, at which the poses of a player at `position` are sent to a player whose head has
+/// the world transform `viewer`
+pub fn scaled_pose_rate(
+    rate: f32,
+    falloff: f32,
+    min_rate: f32,
+    viewer: Mat4,
+    position: Vec3,
+) -> f32 {
+    let offset = position - viewer.w_axis.truncate();
+    let mut scaled = rate / (1. + offset.length() / falloff.max(0.01));
+    if offset.dot(viewer.z_axis.truncate()) < 0. {
+        scaled *= 0.5;
+    }
+    scaled.max(min_rate).min(rate)
+}
+
+fn seconds(world: &World) -> f64 {
+    world.resource(time()).as_secs_f64()
+}
+
+pub fn register_server_datagram_handler(handlers: &mut server::DatagramHandlers) {
+    handlers.insert(
+        POSE_SYNC_DATAGRAM_ID,
+        ("server_pose_sync", Arc::new(on_server_datagram)),
+    );
+}
+
+pub fn register_client_datagram_handler(handlers: &mut client::DatagramHandlers) {
+    handlers.insert(
+        POSE_SYNC_DATAGRAM_ID,
+        ("client_pose_sync", Arc::new(on_client_datagram)),
+    );
+}
+
+/// Stores the poses the client of `user_id` sent on its player entity
+fn on_server_datagram(state: SharedServerState, _assets: AssetCache, user_id: &str, bytes: Bytes) {
+    let mut state = state.lock();
+    let Some(world) = state.get_player_world_mut(user_id) else {
+        return;
+    };
+    let Some(id) = get_connected_by_user_id(world, user_id) else {
+        return;
+    };
+    let sample: PoseSample = match bincode::deserialize(&bytes) {
+        Ok(sample) => sample,
+        Err(err) => {
+            log::warn!("Failed to decode poses from {user_id}: {err:?}");
+            return;
+        }
+    };
+    // Datagrams can arrive out of order
+    if let Ok(last) = world.get_ref(id, pose_sync_sample()) {
+        if last.time >= sample.time {
+            return;
+        }
+    }
+    sample.apply(world, id);
+    world.add_component(id, pose_sync_sample(), sample).unwrap();
+}
+
+/// Buffers the poses of the remote players the server sent
+fn on_client_datagram(world: &mut World, _assets: AssetCache, bytes: Bytes) {
+    let samples: Vec<(EntityId, PoseSample)> = match bincode::deserialize(&bytes) {
+        Ok(samples) => samples,
+        Err(err) => {
+            log::warn!("Failed to decode poses: {err:?}");
+            return;
+        }
+    };
+    let now = seconds(world);
+    for (id, sample) in samples {
+        if !world.exists(id) {
+            continue;
+        }
+        if !world.has_component(id, pose_sync_buffer()) {
+            world
+                .add_component(id, pose_sync_buffer(), PoseBuffer::default())
+                .unwrap();
+        }
+        world
+            .get_mut(id, pose_sync_buffer())
+            .unwrap()
+            .push(sample, now);
+    }
+}
+
+/// Sends the poses of each player to the other players, at a rate scaled by [scaled_pose_rate]
+pub fn server_systems() -> SystemGroup {
+    // When the last pose of each player was sent to each receiving player, by receiver and sender
+    let mut last_sent = HashMap::<(EntityId, EntityId), f64>::new();
+    SystemGroup::new(
+        "pose_sync/server",
+        vec![Box::new(FnSystem::new(move |world, _| {
+            let now = seconds(world);
+            let rate = world
+                .resource_opt(pose_sync_rate())
+                .copied()
+                .unwrap_or(DEFAULT_RATE);
+            let falloff = world
+                .resource_opt(pose_sync_falloff())
+                .copied()
+                .unwrap_or(DEFAULT_FALLOFF);
+            let min_rate = world
+                .resource_opt(pose_sync_min_rate())
+                .copied()
+                .unwrap_or(DEFAULT_MIN_RATE);
+
+            let senders = query(pose_sync_sample())
+                .iter(world, None)
+                .map(|(id, sample)| {
+                    let position = sample
+                        .head
+                        .map(|head| head.position)
+                        .or_else(|| get_world_position(world, id).ok());
+                    (id, sample, position)
+                })
+                .collect_vec();
+            for (receiver, (connection, features)) in
+                query((player_connection(), player_features()))
+                    .excl(player_suspended())
+                    .iter(world, None)
+            {
+                if !features.contains(Features::XR_POSES) {
+                    continue;
+                }
+                let viewer = world.get(receiver, head_pose()).ok();
+                let mut batch = Vec::new();
+                for (sender, sample, position) in &senders {
+                    if *sender == receiver {
+                        continue;
+                    }
+                    let rate = match (viewer, position) {
+                        (Some(viewer), Some(position)) => {
+                            scaled_pose_rate(rate, falloff, min_rate, viewer, *position)
+                        }
+                        _ => rate,
+                    };
+                    let key = (receiver, *sender);
+                    if let Some(&sent) = last_sent.get(&key) {
+                        if now - sent < 1. / rate as f64 {
+                            continue;
+                        }
+                    }
+                    last_sent.insert(key, now);
+                    batch.push((*sender, (*sample).clone()));
+                }
+                for chunk in batch.chunks(SAMPLES_PER_DATAGRAM) {
+                    let bytes = bincode::serialize(chunk).unwrap();
+                    log_network_result!(
+                        connection.send_datagram(POSE_SYNC_DATAGRAM_ID, bytes.into())
+                    );
+                }
+            }
+            last_sent
+                .retain(|(receiver, sender), _| world.exists(*receiver) && world.exists(*sender));
+        }))],
+    )
+}
+
+/// Sends the poses of the local player to the server when they change, or every [KEEPALIVE_INTERVAL] while they
+/// don't, and plays back the poses of the remote players
+pub fn client_systems() -> SystemGroup {
+    let mut last_sent: Option<PoseSample> = None;
+    SystemGroup::new(
+        "pose_sync/client",
+        vec![
+            Box::new(FnSystem::new(move |world, _| {
+                let Some(Some(game_client)) = world.resource_opt(game_client()) else {
+                    return;
+                };
+                if !world
+                    .resource_opt(server_protocol())
+                    .map_or(false, |protocol| {
+                        protocol.features.contains(Features::XR_POSES)
+                    })
+                {
+                    return;
+                }
+                let Some(player) = get_by_user_id(world, world.resource(local_user_id())) else {
+                    return;
+                };
+                let now = seconds(world);
+                let sample = PoseSample::from_world(world, player, now);
+                let rate = world
+                    .resource_opt(pose_sync_rate())
+                    .copied()
+                    .unwrap_or(DEFAULT_RATE);
+                if let Some(last) = &last_sent {
+                    let elapsed = now - last.time;
+                    let keepalive = elapsed >= KEEPALIVE_INTERVAL;
+                    if (sample.same_poses(last) && !keepalive) || elapsed < 1. / rate as f64 {
+                        return;
+                    }
+                } else if sample.is_empty() {
+                    return;
+                }
+                let bytes = bincode::serialize(&sample).unwrap();
+                log_network_result!(game_client
+                    .connection
+                    .send_datagram(POSE_SYNC_DATAGRAM_ID, bytes.into()));
+                last_sent = Some(sample);
+            })),
+            query(())
+                .incl(pose_sync_buffer())
+                .to_system(|q, world, qs, _| {
+                    let now = seconds(world);
+                    for (id, _) in q.collect_cloned(world, qs) {
+                        let buffer = world.get_mut(id, pose_sync_buffer()).unwrap();
+                        let Some(sample) = buffer.sample(now) else {
+                            continue;
+                        };
+                        let sample = buffer.smooth_hands(sample, now);
+                        sample.apply(world, id);
+                    }
+                }),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use glam::vec3;
+
+    use super::*;
+
+    #[test]
+    fn pose_rate_falls_off_with_distance_and_view() {
+        let viewer = Mat4::IDENTITY;
+        let near = scaled_pose_rate(30., 5., 2., viewer, vec3(0., 0., 1.));
+        let far = scaled_pose_rate(30., 5., 2., viewer, vec3(0., 0., 10.));
+        let behind = scaled_pose_rate(30., 5., 2., viewer, vec3(0., 0., -10.));
+        let very_far = scaled_pose_rate(30., 5., 2., viewer, vec3(0., 0., 1000.));
+        assert!(near > far);
+        assert_eq!(far, 10.);
+        assert_eq!(behind, 5.);
+        assert_eq!(very_far, 2.);
+    }
+
+    #[test]
+    fn pose_buffer_interpolates() {
+        let sample = |time: f64, x: f32| PoseSample {
+            time,
+            head: Some(Pose {
+                position: vec3(x, 0., 0.),
+                rotation: Quat::IDENTITY,
+            }),
+            ..Default::default()
+        };
+        let mut buffer = PoseBuffer::default();
+        // The samples arrive 0.5 seconds after they were taken, 0.1 seconds apart
+        for i in 0..10 {
+            buffer.push(sample(i as f64 * 0.1, i as f32), i as f64 * 0.1 + 0.5);
+        }
+        let now = 0.9 + 0.5 + buffer.interval * 1.5 + JITTER_MARGIN - 0.05;
+        let head = buffer.sample(now).unwrap().head.unwrap();
+        assert!((head.position.x - 8.5).abs() < 0.001);
+    }
+}
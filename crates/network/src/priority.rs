@@ -0,0 +1,275 @@
+//! Bandwidth-aware replication. When the server has a `replication_budget`, every player gets its own cut of the world
+//! diff each tick: the component updates that don't fit in the budget are held back, and the entities they belong to
+//! accumulate priority until they are sent, so that important entities are updated more often instead of every update
+//! slowing down uniformly.
+
+use std::collections::HashMap;
+
+use ambient_core::{player::user_id, transform::get_world_position};
+use ambient_ecs::{components, ComponentEntry, EntityId, World, WorldChange, WorldDiff};
+use glam::Vec3;
+use itertools::Itertools;
+
+pub use ambient_ecs::generated::components::core::network::{
+    replication_budget, replication_falloff, replication_priority,
+};
+
+use crate::ownership::owner;
+
+const DEFAULT_FALLOFF: f32 = 20.;
+/// How much faster the priority of an entity grows in the tick it changed
+const CHANGED_BOOST: f32 = 2.;
+/// How much faster the priority of an entity grows for the player that owns it
+const OWNED_BOOST: f32 = 4.;
+
+components!("network::priority", {
+    /// The component updates that haven't been sent to the player yet
+    replication_state: ReplicationState,
+});
+
+#[derive(Debug, Clone, Default)]
+pub struct ReplicationState {
+    pending: HashMap<EntityId, PendingEntity>,
+}
+impl ReplicationState {
+    /// The number of entities with updates that haven't been sent
+    pub fn pending_entities(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct PendingEntity {
+    priority: f32,
+    changed: bool,
+    /// The latest value of each component that changed, at most one per component
+    entries: Vec<ComponentEntry>,
+}
+impl PendingEntity {
+    fn set(&mut self, entry: ComponentEntry) {
+        self.changed = true;
+        match self
+            .entries
+            .iter_mut()
+            .find(|pending| pending.index() == entry.index())
+        {
+            Some(pending) => *pending = entry,
+            None => self.entries.push(entry),
+        }
+    }
+    fn remove(&mut self, index: u32) {
+        self.entries.retain(|entry| entry.index() != index);
+    }
+}
+
+/// How fast the priority of the entity `id` grows for the `player`
+fn priority_growth(world: &World, player: EntityId, id: EntityId, viewer: Option<Vec3>) -> f32 {
+    let mut growth = world.get(id, replication_priority()).unwrap_or(1.);
+    if let (Some(viewer), Ok(position)) = (viewer, get_world_position(world, id)) {
+        let falloff = world
+            .resource_opt(replication_falloff())
+            .copied()
+            .unwrap_or(DEFAULT_FALLOFF)
+            .max(0.01);
+        growth /= 1. + viewer.distance(position) / falloff;
+    }
+    let owned = world
+        .get_ref(id, owner())
+        .ok()
+        .zip(world.get_ref(player, user_id()).ok())
+        .map_or(false, |(owner, user_id)| owner == user_id);
+    if owned {
+        growth *= OWNED_BOOST;
+    }
+    growth
+}
+
+/// Returns the part of `diff` that is sent to `player` this tick, within `budget` bytes of the binary encoding. The
+/// component updates that are held back are kept in the player's [ReplicationState], and are replaced by newer values
+/// when they change again.
+pub fn prioritize(
+    world: &mut World,
+    player: EntityId,
+    diff: &WorldDiff,
+    budget: usize,
+) -> WorldDiff {
+    let mut state = world
+        .get_mut(player, replication_state())
+        .map(std::mem::take)
+        .unwrap_or_default();
+
+    // Everything except the component updates is sent right away, after dropping the held back updates it makes stale
+    let mut sent = WorldDiff::new();
+    for change in &diff.changes {
+        match change {
+            WorldChange::Set(id, entry) => {
+                state.pending.entry(*id).or_default().set(entry.clone());
+                continue;
+            }
+            WorldChange::Spawn(Some(id), _) | WorldChange::Despawn(id) => {
+                state.pending.remove(id);
+            }
+            WorldChange::AddComponents(id, entity) => {
+                if let Some(pending) = state.pending.get_mut(id) {
+                    for entry in entity.iter() {
+                        pending.remove(entry.index());
+                    }
+                }
+            }
+            WorldChange::RemoveComponents(id, components) => {
+                if let Some(pending) = state.pending.get_mut(id) {
+                    for component in components {
+                        pending.remove(component.index());
+                    }
+                }
+            }
+            WorldChange::Spawn(None, _) => {}
+        }
+        sent.changes.push(change.clone());
+    }
+    state
+        .pending
+        .retain(|_, pending| !pending.entries.is_empty());
+
+    let viewer = get_world_position(world, player).ok();
+    for (&id, pending) in &mut state.pending {
+        let mut growth = priority_growth(world, player, id, viewer);
+        if pending.changed {
+            growth *= CHANGED_BOOST;
+        }
+        pending.priority += growth;
+        pending.changed = false;
+    }
+
+    let mut remaining = budget.saturating_sub(
+        sent.changes
+            .iter()
+            .map(|change| change.encoded_size())
+            .sum(),
+    );
+    let order = state
+        .pending
+        .iter()
+        .map(|(&id, pending)| (id, pending.priority))
+        .sorted_by(|(_, a), (_, b)| b.total_cmp(a))
+        .map(|(id, _)| id)
+        .collect_vec();
+    let mut sent_any = false;
+    for id in order {
+        let sets = state.pending[&id]
+            .entries
+            .iter()
+            .map(|entry| WorldChange::Set(id, entry.clone()))
+            .collect_vec();
+        let size = sets
+            .iter()
+            .map(|change| change.encoded_size())
+            .sum::<usize>();
+        // The most important entity is always sent, so that the updates can't stall
+        if size > remaining && sent_any {
+            continue;
+        }
+        remaining = remaining.saturating_sub(size);
+        sent_any = true;
+        sent.changes.extend(sets);
+        state.pending.remove(&id);
+    }
+
+    if let Ok(slot) = world.get_mut(player, replication_state()) {
+        *slot = state;
+    } else {
+        world.add_component(player, replication_state(), state).ok();
+    }
+    sent
+}
+
+#[cfg(test)]
+mod test {
+    use ambient_core::transform::translation;
+    use ambient_ecs::{Entity, Serializable};
+    use glam::vec3;
+
+    use super::*;
+
+    components!("test", {
+        @[Serializable]
+        value: f32,
+    });
+
+    fn init() {
+        crate::init_all_components();
+        ambient_core::init_all_components();
+        init_components();
+    }
+
+    fn sent_entities(diff: &WorldDiff) -> Vec<EntityId> {
+        diff.changes
+            .iter()
+            .filter_map(|change| match change {
+                WorldChange::Set(id, _) => Some(*id),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn close_entities_are_sent_more_often() {
+        init();
+        let mut world = World::new("close_entities_are_sent_more_often");
+        let player = Entity::new()
+            .with(translation(), Vec3::ZERO)
+            .spawn(&mut world);
+        let near = Entity::new()
+            .with(translation(), vec3(1., 0., 0.))
+            .spawn(&mut world);
+        let far = Entity::new()
+            .with(translation(), vec3(200., 0., 0.))
+            .spawn(&mut world);
+
+        // Room for one update per tick
+        let budget = WorldChange::Set(near, ComponentEntry::new(value(), 0.)).encoded_size();
+        let (mut near_updates, mut far_updates) = (0, 0);
+        for i in 0..100 {
+            let diff = WorldDiff::new()
+                .set(near, value(), i as f32)
+                .set(far, value(), i as f32);
+            let sent = sent_entities(&prioritize(&mut world, player, &diff, budget));
+            assert_eq!(sent.len(), 1);
+            near_updates += sent.iter().filter(|&&id| id == near).count();
+            far_updates += sent.iter().filter(|&&id| id == far).count();
+        }
+        assert!(near_updates > far_updates);
+        // The far entity still gets its turn
+        assert!(far_updates > 0);
+    }
+
+    #[test]
+    fn held_back_updates_are_replaced_and_dropped() {
+        init();
+        let mut world = World::new("held_back_updates_are_replaced_and_dropped");
+        let player = world.spawn(Entity::new());
+        let a = world.spawn(Entity::new());
+        let b = world.spawn(Entity::new());
+
+        let diff = WorldDiff::new()
+            .set(a, value(), 1.)
+            .set(b, value(), 1.)
+            .set(b, value(), 2.);
+        let sent = prioritize(&mut world, player, &diff, 0);
+        assert_eq!(sent.changes.len(), 1);
+        let pending = world.get_ref(player, replication_state()).unwrap();
+        assert_eq!(pending.pending_entities(), 1);
+
+        // Despawning drops the held back update
+        let held = if sent_entities(&sent) == [a] { b } else { a };
+        let sent = prioritize(&mut world, player, &WorldDiff::new().despawn(vec![held]), 0);
+        assert_eq!(sent.changes.len(), 1);
+        assert_eq!(
+            world
+                .get_ref(player, replication_state())
+                .unwrap()
+                .pending_entities(),
+            0
+        );
+    }
+}
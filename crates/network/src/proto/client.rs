@@ -1,22 +1,27 @@
-use std::sync::Arc;
+use std::{collections::HashSet, net::SocketAddr, sync::Arc};
 
+use ambient_core::project_name;
 use ambient_ecs::{
-    generated::components::core::network::is_remote_entity, ComponentRegistry, Entity, WorldDiff,
+    generated::components::core::network::is_remote_entity, query, ComponentRegistry, Entity,
+    World, WorldChange, WorldDiff,
 };
 use ambient_std::{asset_cache::SyncAssetKeyExt, asset_url::ContentBaseUrlKey};
-use anyhow::{bail, Context};
-use bytes::{Buf, Bytes};
+use anyhow::Context;
+use bytes::Bytes;
+use itertools::Itertools;
 use parking_lot::Mutex;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite};
 use tracing::debug_span;
 
 use crate::{
     client::{
-        bi_stream_handlers, client_network_stats, datagram_handlers, uni_stream_handlers,
-        NetworkStats,
+        bi_stream_handlers, client_network_stats, datagram_handlers, server_protocol,
+        uni_stream_handlers, NetworkStats,
     },
     client_game_state::ClientGameState,
+    interpolation, ownership,
     proto::*,
+    NetworkError,
 };
 
 /// The client logic handler in a connected state
@@ -29,6 +34,8 @@ pub(crate) struct ConnectedClient {}
 pub(crate) enum ClientState {
     Connecting(String),
     Connected(ConnectedClient),
+    /// The player was handed off to another shard of the cluster, at this address
+    Redirected(SocketAddr),
     Disconnected,
 }
 
@@ -53,8 +60,21 @@ impl ClientState {
             (ServerPush::ServerInfo(server_info), Self::Connecting(_user_id)) => {
                 tracing::info!(?server_info, "Received server info");
 
-                let state = state.lock();
+                let protocol = Handshake::negotiate(
+                    &Handshake::current(),
+                    &server_info.handshake,
+                    &server_info.version,
+                )
+                .map_err(NetworkError::from)?;
+                tracing::info!(?protocol, "Negotiated protocol");
+
+                let mut state = state.lock();
+                state.world.add_resource(server_protocol(), protocol);
                 ContentBaseUrlKey.insert(&state.assets, server_info.content_base_url.clone());
+                // Lets the client side know which project it is running, e.g. to keep its save slots apart
+                state
+                    .world
+                    .add_resource(project_name(), server_info.project_name.clone());
                 tracing::debug!(?server_info.external_components, "Adding external components");
                 ComponentRegistry::get_mut().add_external(server_info.external_components);
 
@@ -70,6 +90,11 @@ impl ClientState {
                 self.process_disconnect();
                 Ok(())
             }
+            (ServerPush::Redirect(address), _) => {
+                tracing::info!(%address, "Redirected to another shard");
+                *self = Self::Redirected(address);
+                Ok(())
+            }
         }
     }
 
@@ -97,14 +122,28 @@ impl ConnectedClient {
     ) -> anyhow::Result<()> {
         let mut gs = state.lock();
         tracing::debug!(?diff, "Applying diff");
+        let diff = ownership::filter_server_diff(&gs.world, diff);
+        let moved = interpolation::moved_entities(&gs.world, &diff);
         diff.apply(
             &mut gs.world,
             Entity::new().with(is_remote_entity(), ()),
             false,
         );
+        interpolation::on_tick(&mut gs.world, moved);
         Ok(())
     }
 
+    /// Applies the first diff of a server the client was redirected to, which replaces the world of the previous
+    /// server. The entities that both servers have, like the player's own entity, are updated instead of respawned
+    pub fn process_redirected_diff(
+        &mut self,
+        state: &SharedClientState,
+        diff: WorldDiff,
+    ) -> anyhow::Result<()> {
+        let diff = replace_remote_entities(&mut state.lock().world, diff);
+        self.process_diff(state, diff)
+    }
+
     /// Processes a server initiated bidirectional stream
     #[tracing::instrument(level = "debug", skip(send, recv))]
     pub async fn process_bi<R, S>(
@@ -170,13 +209,9 @@ impl ConnectedClient {
     pub fn process_datagram(
         &mut self,
         state: &SharedClientState,
-        mut data: Bytes,
+        data: Bytes,
     ) -> anyhow::Result<()> {
-        if data.len() < 4 {
-            bail!("Received malformed datagram");
-        }
-
-        let id = data.get_u32();
+        let (id, data) = super::decode_datagram(data)?;
 
         let mut gs = state.lock();
         let gs = &mut *gs;
@@ -195,3 +230,81 @@ impl ConnectedClient {
         Ok(())
     }
 }
+
+/// Despawns the entities of the previous server that `diff` doesn't spawn, and turns the spawns of those it does into
+/// updates of them
+fn replace_remote_entities(world: &mut World, diff: WorldDiff) -> WorldDiff {
+    let kept: HashSet<_> = diff
+        .changes
+        .iter()
+        .filter_map(|change| match change {
+            WorldChange::Spawn(Some(id), _) => Some(*id),
+            _ => None,
+        })
+        .collect();
+    let stale = query(is_remote_entity())
+        .iter(world, None)
+        .map(|(id, _)| id)
+        .filter(|id| !kept.contains(id))
+        .collect_vec();
+    for id in stale {
+        world.despawn(id);
+    }
+
+    let changes = diff
+        .changes
+        .into_iter()
+        .map(|change| match change {
+            WorldChange::Spawn(Some(id), data) if world.exists(id) => {
+                WorldChange::AddComponents(id, data)
+            }
+            change => change,
+        })
+        .collect();
+    WorldDiff { changes }
+}
+
+#[cfg(test)]
+mod tests {
+    use ambient_core::name;
+    use ambient_ecs::EntityId;
+
+    use super::*;
+
+    #[test]
+    fn redirected_diff_replaces_remote_entities() {
+        ambient_ecs::init_components();
+        ambient_core::init_all_components();
+        let mut world = World::new("redirected_diff_replaces_remote_entities");
+        let remote = |label: &str| {
+            Entity::new()
+                .with(is_remote_entity(), ())
+                .with(name(), label.to_string())
+        };
+        let player = remote("player").spawn(&mut world);
+        let stale = remote("stale").spawn(&mut world);
+        let local = Entity::new().spawn(&mut world);
+        let new = EntityId::new();
+
+        let diff = replace_remote_entities(
+            &mut world,
+            WorldDiff {
+                changes: vec![
+                    WorldChange::Spawn(Some(player), remote("moved player")),
+                    WorldChange::Spawn(Some(new), remote("new")),
+                ],
+            },
+        );
+        assert!(world.exists(player));
+        assert!(!world.exists(stale));
+        assert!(world.exists(local));
+        assert!(matches!(
+            diff.changes.as_slice(),
+            [WorldChange::AddComponents(a, _), WorldChange::Spawn(Some(b), _)] if *a == player && *b == new
+        ));
+
+        diff.apply(&mut world, Entity::new(), false);
+        assert_eq!(world.get_cloned(player, name()).unwrap(), "moved player");
+        assert_eq!(world.get_cloned(new, name()).unwrap(), "new");
+    }
+}
@@ -0,0 +1,273 @@
+//! Shims for peers of older protocol versions, back to [MIN_PROTOCOL_VERSION](super::MIN_PROTOCOL_VERSION).
+//!
+//! The frames of the control streams are decoded as the current version first, and as the older versions if that
+//! fails, as their frames lack the fields that were added since.
+
+use super::{
+    ClientRequest, Features, Handshake, ProtocolVersion, ServerInfo, ServerPush, PROTOCOL_VERSION,
+};
+
+/// The handshake of a peer of protocol 1.0, which predates handshakes and all of the features
+pub(crate) const V1_0_HANDSHAKE: Handshake = Handshake {
+    version: ProtocolVersion::new(1, 0),
+    min_version: ProtocolVersion::new(1, 0),
+    features: Features::empty(),
+};
+
+mod v1_1 {
+    use crate::proto::Handshake;
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    pub enum ClientRequest {
+        Connect {
+            user_id: String,
+            identity_token: String,
+            spectator: bool,
+            handshake: Handshake,
+        },
+        Disconnect,
+    }
+}
+
+mod v1_0 {
+    use ambient_ecs::ExternalComponentDesc;
+    use ambient_std::asset_url::AbsAssetUrl;
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    pub enum ClientRequest {
+        /// The user id of the client
+        Connect(String),
+        Disconnect,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    pub enum ServerPush {
+        ServerInfo(ServerInfo),
+        Disconnect,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    pub struct ServerInfo {
+        pub project_name: String,
+        pub content_base_url: AbsAssetUrl,
+        pub version: String,
+        pub external_components: Vec<ExternalComponentDesc>,
+    }
+}
+
+/// Decodes a request of a client of any supported protocol version
+pub fn decode_client_request(bytes: &[u8]) -> bincode::Result<ClientRequest> {
+    let current = match bincode::deserialize(bytes) {
+        Ok(request) => return Ok(request),
+        Err(err) => err,
+    };
+    let v1_1 = match bincode::deserialize::<v1_1::ClientRequest>(bytes) {
+        Ok(request) => {
+            return Ok(match request {
+                v1_1::ClientRequest::Connect {
+                    user_id,
+                    identity_token,
+                    spectator,
+                    handshake,
+                } => ClientRequest::Connect {
+                    user_id,
+                    identity_token,
+                    spectator,
+                    handshake,
+                    auth_ticket: None,
+                },
+                v1_1::ClientRequest::Disconnect => ClientRequest::Disconnect,
+            })
+        }
+        Err(err) => err,
+    };
+    match bincode::deserialize::<v1_0::ClientRequest>(bytes) {
+        // Clients of 1.0 can't reconnect, so they don't have an identity token to prove who they are
+        Ok(v1_0::ClientRequest::Connect(user_id)) => Ok(ClientRequest::Connect {
+            user_id,
+            identity_token: String::new(),
+            spectator: false,
+            handshake: V1_0_HANDSHAKE,
+            auth_ticket: None,
+        }),
+        Ok(v1_0::ClientRequest::Disconnect) => Ok(ClientRequest::Disconnect),
+        Err(v1_0) => Err(undecodable(
+            "client request",
+            &[
+                (PROTOCOL_VERSION, current),
+                (ProtocolVersion::new(1, 1), v1_1),
+                (ProtocolVersion::new(1, 0), v1_0),
+            ],
+        )),
+    }
+}
+
+/// Decodes a push of a server of any supported protocol version
+pub fn decode_server_push(bytes: &[u8]) -> bincode::Result<ServerPush> {
+    let current = match bincode::deserialize(bytes) {
+        Ok(push) => return Ok(push),
+        Err(err) => err,
+    };
+    match bincode::deserialize::<v1_0::ServerPush>(bytes) {
+        Ok(v1_0::ServerPush::ServerInfo(info)) => Ok(ServerPush::ServerInfo(ServerInfo {
+            project_name: info.project_name,
+            content_base_url: info.content_base_url,
+            version: info.version,
+            external_components: info.external_components,
+            handshake: V1_0_HANDSHAKE,
+        })),
+        Ok(v1_0::ServerPush::Disconnect) => Ok(ServerPush::Disconnect),
+        Err(v1_0) => Err(undecodable(
+            "server push",
+            &[
+                (PROTOCOL_VERSION, current),
+                (ProtocolVersion::new(1, 0), v1_0),
+            ],
+        )),
+    }
+}
+
+/// An error that includes why a frame couldn't be decoded as each of the versions, as any of them may be the one that
+/// the peer speaks
+fn undecodable(frame: &str, errors: &[(ProtocolVersion, bincode::Error)]) -> bincode::Error {
+    let errors = errors
+        .iter()
+        .map(|(version, err)| format!("as {version}: {err}"))
+        .collect::<Vec<_>>()
+        .join("; ");
+    Box::new(bincode::ErrorKind::Custom(format!(
+        "Failed to decode the {frame} ({errors})"
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use ambient_std::asset_url::AbsAssetUrl;
+
+    use super::*;
+    use crate::{auth::AuthTicket, proto::NegotiatedProtocol};
+
+    #[test]
+    fn v1_0_connect() {
+        // `ClientRequest::Connect("user")` as encoded by 1.0: the variant index, then the length of the string
+        let mut bytes = vec![0, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0];
+        bytes.extend_from_slice(b"user");
+        let ClientRequest::Connect {
+            user_id,
+            identity_token,
+            spectator,
+            handshake,
+            auth_ticket,
+        } = decode_client_request(&bytes).unwrap()
+        else {
+            panic!("Expected a connect request");
+        };
+        assert_eq!(user_id, "user");
+        assert!(identity_token.is_empty());
+        assert!(!spectator);
+        assert_eq!(handshake, V1_0_HANDSHAKE);
+        assert!(auth_ticket.is_none());
+
+        let protocol = Handshake::negotiate(&handshake, &Handshake::current(), "0.3.0").unwrap();
+        assert_eq!(protocol.features, Features::empty());
+
+        assert!(matches!(
+            decode_client_request(&[1, 0, 0, 0]).unwrap(),
+            ClientRequest::Disconnect
+        ));
+    }
+
+    #[test]
+    fn undecodable_request_reports_every_version() {
+        let err = decode_client_request(&[9, 0, 0, 0])
+            .unwrap_err()
+            .to_string();
+        for version in [
+            PROTOCOL_VERSION,
+            ProtocolVersion::new(1, 1),
+            ProtocolVersion::new(1, 0),
+        ] {
+            assert!(err.contains(&format!("as {version}:")), "{err}");
+        }
+    }
+
+    #[test]
+    fn v1_1_connect() {
+        let bytes = bincode::serialize(&v1_1::ClientRequest::Connect {
+            user_id: "user".to_string(),
+            identity_token: "token".to_string(),
+            spectator: false,
+            handshake: Handshake::current(),
+        })
+        .unwrap();
+        let ClientRequest::Connect {
+            user_id,
+            handshake,
+            auth_ticket,
+            ..
+        } = decode_client_request(&bytes).unwrap()
+        else {
+            panic!("Expected a connect request");
+        };
+        assert_eq!(user_id, "user");
+        assert_eq!(handshake, Handshake::current());
+        assert!(auth_ticket.is_none());
+    }
+
+    #[test]
+    fn current_connect_is_readable_by_v1_1() {
+        let bytes = bincode::serialize(&ClientRequest::Connect {
+            user_id: "user".to_string(),
+            identity_token: "token".to_string(),
+            spectator: false,
+            handshake: Handshake::current(),
+            auth_ticket: Some(AuthTicket::Steam(vec![1, 2, 3])),
+        })
+        .unwrap();
+        assert!(matches!(
+            bincode::deserialize::<v1_1::ClientRequest>(&bytes).unwrap(),
+            v1_1::ClientRequest::Connect {
+                spectator: false,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn v1_0_server_info() {
+        let bytes = bincode::serialize(&v1_0::ServerPush::ServerInfo(v1_0::ServerInfo {
+            project_name: "Project".to_string(),
+            content_base_url: AbsAssetUrl::parse("http://localhost:8999/content/").unwrap(),
+            version: "0.2.1".to_string(),
+            external_components: Vec::new(),
+        }))
+        .unwrap();
+        let ServerPush::ServerInfo(info) = decode_server_push(&bytes).unwrap() else {
+            panic!("Expected server info");
+        };
+        assert_eq!(info.project_name, "Project");
+        assert_eq!(
+            Handshake::negotiate(&Handshake::current(), &info.handshake, &info.version),
+            Ok(NegotiatedProtocol {
+                version: ProtocolVersion::new(1, 0),
+                features: V1_0_HANDSHAKE.features,
+            })
+        );
+    }
+
+    #[test]
+    fn incompatible_versions() {
+        let client = Handshake {
+            version: ProtocolVersion::new(PROTOCOL_VERSION.major, PROTOCOL_VERSION.minor + 2),
+            min_version: ProtocolVersion::new(PROTOCOL_VERSION.major, PROTOCOL_VERSION.minor + 1),
+            features: Features::SUPPORTED,
+        };
+        let err = Handshake::negotiate(&client, &Handshake::current(), "0.3.0").unwrap_err();
+        assert!(!err.client_is_outdated());
+        assert_eq!(err.min_client, client.min_version);
+
+        let err = Handshake::negotiate(&Handshake::current(), &client, "0.4.0").unwrap_err();
+        assert!(err.client_is_outdated());
+        assert_eq!(err.min_server, client.min_version);
+    }
+}
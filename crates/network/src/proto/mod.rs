@@ -1,14 +1,34 @@
-use ambient_ecs::ExternalComponentDesc;
-use ambient_std::asset_url::AbsAssetUrl;
+use std::{fmt::Display, net::SocketAddr};
+
+use ambient_ecs::{ExternalComponentDesc, WorldDiff};
+use ambient_std::{asset_url::AbsAssetUrl, frame_arena::record_allocation};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use thiserror::Error;
+
+use crate::auth::AuthTicket;
 
 pub mod client;
+pub(crate) mod compat;
 pub mod server;
 
+pub use compat::{decode_client_request, decode_server_push};
+
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 /// Request sent by the client to the server
 pub enum ClientRequest {
     /// Connect to the server with the specified user id
-    Connect(String),
+    Connect {
+        user_id: String,
+        /// A secret generated by the client, which it has to reconnect with to get its player back after losing its
+        /// connection
+        identity_token: String,
+        /// Join as a spectator, which receives the world but has no player entity
+        spectator: bool,
+        /// The protocol of the client. Added in protocol 1.1; see [compat] for older clients
+        handshake: Handshake,
+        /// The proof that the client is `user_id`, if it has one; see [crate::auth]. Added in protocol 1.2
+        auth_ticket: Option<AuthTicket>,
+    },
     /// Client wants to disconnect
     Disconnect,
 }
@@ -19,6 +39,9 @@ pub enum ServerPush {
     ServerInfo(ServerInfo),
     /// Graceful disconnect
     Disconnect,
+    /// The player was handed off to another shard of the cluster, which the client has to connect to instead. Only
+    /// sent with [Features::CLUSTER]
+    Redirect(SocketAddr),
 }
 
 pub(crate) const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -37,4 +60,193 @@ pub struct ServerInfo {
     /// TODO: use semver
     pub version: String,
     pub external_components: Vec<ExternalComponentDesc>,
+    /// The protocol of the server. Added in protocol 1.1; see [compat] for older servers
+    pub handshake: Handshake,
+}
+
+/// The version of the network protocol, which is versioned separately from the runtime.
+///
+/// Fields are only ever added to the end of [ClientRequest::Connect] and [ServerInfo], so that older peers can still
+/// read them; peers of different minor versions talk the protocol of the older one, as long as it is no older than
+/// [MIN_PROTOCOL_VERSION]. Peers of different major versions can't talk to each other.
+#[derive(
+    serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord,
+)]
+pub struct ProtocolVersion {
+    pub major: u16,
+    pub minor: u16,
+}
+impl ProtocolVersion {
+    pub const fn new(major: u16, minor: u16) -> Self {
+        Self { major, minor }
+    }
+}
+impl Display for ProtocolVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// The protocol version of this runtime
+pub const PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion::new(1, 2);
+/// The oldest protocol version that this runtime can still talk, with the shims in [compat]
+pub const MIN_PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion::new(1, 0);
+
+/// Optional parts of the protocol, which are only used if both peers support them
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Features(u64);
+impl Features {
+    /// Client-authoritative components; see [crate::ownership]
+    pub const CLIENT_AUTHORITY: Self = Self(1 << 0);
+    /// Joining as a spectator, without a player entity
+    pub const SPECTATORS: Self = Self(1 << 1);
+    /// Reconnecting to a player after losing the connection, with an identity token
+    pub const RECONNECT: Self = Self(1 << 2);
+    /// Replicating the tracked poses of XR players; see [crate::pose_sync]
+    pub const XR_POSES: Self = Self(1 << 3);
+    /// Sending world diffs in the binary encoding of [ambient_ecs::diff_encoding] instead of bincode
+    pub const BINARY_DIFFS: Self = Self(1 << 4);
+    /// Sending the tracing context of RPCs along with them; see [crate::trace_context]
+    pub const TRACE_CONTEXT: Self = Self(1 << 5);
+    /// Following the player to another shard of a cluster; see [crate::native::cluster]
+    pub const CLUSTER: Self = Self(1 << 6);
+    /// Playing the surface effects that the server sends in datagrams
+    pub const SURFACE_EFFECTS: Self = Self(1 << 7);
+
+    /// The features this runtime supports
+    pub const SUPPORTED: Self = Self(
+        Self::CLIENT_AUTHORITY.0
+            | Self::SPECTATORS.0
+            | Self::RECONNECT.0
+            | Self::XR_POSES.0
+            | Self::BINARY_DIFFS.0
+            | Self::TRACE_CONTEXT.0
+            | Self::CLUSTER.0
+            | Self::SURFACE_EFFECTS.0,
+    );
+
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+    pub const fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+}
+
+/// Encodes `diff` into `buf` for a client with the protocol `features`, and returns the frame
+pub(crate) fn encode_diff(buf: &mut BytesMut, diff: &WorldDiff, features: Features) -> Bytes {
+    let capacity = buf.capacity();
+    if features.contains(Features::BINARY_DIFFS) {
+        diff.encode((&mut *buf).writer()).unwrap();
+    } else {
+        bincode::serialize_into((&mut *buf).writer(), diff).unwrap();
+    }
+    record_allocation(buf.len(), buf.capacity() > capacity);
+    buf.split().freeze()
+}
+
+/// Splits a datagram into the id of its handler and its payload
+pub fn decode_datagram(mut datagram: Bytes) -> anyhow::Result<(u32, Bytes)> {
+    if datagram.len() < 4 {
+        anyhow::bail!("Received malformed datagram");
+    }
+    let id = datagram.get_u32();
+    Ok((id, datagram))
+}
+
+/// Decodes a diff sent to a client with [Features::BINARY_DIFFS]
+pub fn decode_binary_diff(bytes: &[u8]) -> bincode::Result<WorldDiff> {
+    WorldDiff::decode(bytes).map_err(|err| bincode::ErrorKind::Custom(err.to_string()).into())
+}
+
+/// What a peer tells the other about its protocol when connecting
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Handshake {
+    pub version: ProtocolVersion,
+    /// The oldest protocol version the peer can still talk
+    pub min_version: ProtocolVersion,
+    pub features: Features,
+}
+impl Handshake {
+    /// The handshake of this runtime
+    pub const fn current() -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            min_version: MIN_PROTOCOL_VERSION,
+            features: Features::SUPPORTED,
+        }
+    }
+
+    /// The protocol that a client with the handshake `client` and a server with the handshake `server` talk, or why
+    /// they can't talk to each other. `server_runtime_version` is the version of the runtime of the server, which is
+    /// shown to the user if they can't.
+    pub fn negotiate(
+        client: &Handshake,
+        server: &Handshake,
+        server_runtime_version: &str,
+    ) -> Result<NegotiatedProtocol, IncompatibleVersion> {
+        let compatible = client.version.major == server.version.major
+            && client.version >= server.min_version
+            && server.version >= client.min_version;
+        if !compatible {
+            return Err(IncompatibleVersion {
+                client: client.version,
+                min_client: client.min_version,
+                server: server.version,
+                min_server: server.min_version,
+                server_runtime_version: server_runtime_version.to_string(),
+            });
+        }
+        Ok(NegotiatedProtocol {
+            version: client.version.min(server.version),
+            features: client.features.intersection(server.features),
+        })
+    }
+}
+
+/// The protocol that a client and server talk to each other
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NegotiatedProtocol {
+    pub version: ProtocolVersion,
+    pub features: Features,
+}
+
+/// The client and server can't talk to each other, as their protocols are too far apart
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub struct IncompatibleVersion {
+    pub client: ProtocolVersion,
+    /// The oldest protocol version the client can talk
+    pub min_client: ProtocolVersion,
+    pub server: ProtocolVersion,
+    /// The oldest protocol version the server can talk, and so the minimum version the client needs
+    pub min_server: ProtocolVersion,
+    pub server_runtime_version: String,
+}
+impl IncompatibleVersion {
+    /// Does the client have to be updated to talk to the server, rather than the other way around?
+    pub fn client_is_outdated(&self) -> bool {
+        self.client < self.server
+    }
+}
+impl Display for IncompatibleVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.client_is_outdated() {
+            write!(
+                f,
+                "The server runs Ambient {}, which requires at least protocol version {}; this client only supports \
+                 protocol version {}. Update Ambient to join this server.",
+                self.server_runtime_version, self.min_server, self.client
+            )
+        } else {
+            write!(
+                f,
+                "The server runs Ambient {}, which talks protocol version {}; this client requires at least protocol \
+                 version {}. The server has to be updated to be joined with this client.",
+                self.server_runtime_version, self.server, self.min_client
+            )
+        }
+    }
 }
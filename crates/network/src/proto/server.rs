@@ -1,10 +1,11 @@
-use std::sync::Arc;
+use std::{net::SocketAddr, sync::Arc};
 
-use ambient_core::player::get_by_user_id;
+use ambient_core::player::{get_by_user_id, player_suspended};
 use ambient_ecs::{WorldDiff, WorldStreamFilter};
-use ambient_std::{fps_counter::FpsSample, log_result};
+use ambient_std::{fps_counter::FpsSample, log_result, metrics};
+use ambient_sys::time::Instant;
 use anyhow::{bail, Context};
-use bytes::{Buf, Bytes};
+use bytes::{Bytes, BytesMut};
 use futures::{Stream, StreamExt};
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite};
 use tracing::{debug_span, Instrument};
@@ -13,15 +14,17 @@ use uuid::Uuid;
 use crate::{
     client::ClientConnection,
     log_network_result,
-    proto::ServerPush,
+    metrics::{CountedStream, RECEIVED_BYTES, SENT_BYTES},
+    proto::{encode_diff, ServerPush},
     server::{
-        bi_stream_handlers, create_player_entity_data, datagram_handlers, uni_stream_handlers,
+        bi_stream_handlers, create_player_entity_data, create_spectator_entity_data,
+        datagram_handlers, get_connected_by_user_id, player_features, uni_stream_handlers,
     },
     server::{SharedServerState, MAIN_INSTANCE_ID},
     stream,
 };
 
-use super::ClientRequest;
+use super::{ClientRequest, Features, Handshake, VERSION};
 
 /// The server can be in multiple states depending on what has been received from the client.
 ///
@@ -81,6 +84,10 @@ pub struct Player {
     pub instance: String,
     control_tx: flume::Sender<ServerPush>,
     connection_id: Uuid,
+    /// The secret the client connected with, which it has to reconnect with to get the player back
+    identity_token: String,
+    /// When the connection to the player was lost, if it was lost unexpectedly and the player hasn't reconnected yet
+    pub suspended_since: Option<Instant>,
 }
 
 impl Player {
@@ -91,13 +98,38 @@ impl Player {
             instance: instance.into(),
             control_tx,
             connection_id: Uuid::new_v4(),
+            identity_token: String::new(),
+            suspended_since: None,
         }
     }
 
+    /// A player that another shard of the cluster handed off to this one, which waits for its client like a suspended
+    /// player
+    pub(crate) fn new_handed_off(instance: impl Into<String>, identity_token: String) -> Self {
+        let (control_tx, _) = flume::unbounded();
+
+        Self {
+            instance: instance.into(),
+            control_tx,
+            connection_id: Uuid::new_v4(),
+            identity_token,
+            suspended_since: Some(Instant::now()),
+        }
+    }
+
+    pub(crate) fn identity_token(&self) -> &str {
+        &self.identity_token
+    }
+
     /// Notifies the existing connection handler to shut down
     pub fn abort(&self) {
         self.control_tx.send(ServerPush::Disconnect).ok();
     }
+
+    /// Tells the client to connect to the server at `address` instead
+    pub(crate) fn redirect(&self, address: SocketAddr) {
+        self.control_tx.send(ServerPush::Redirect(address)).ok();
+    }
 }
 
 impl ServerState {
@@ -112,13 +144,37 @@ impl ServerState {
                 tracing::info!("Client is disconnected, ignoring control frame");
                 Ok(())
             }
-            (ClientRequest::Connect(user_id), Self::PendingConnection) => {
+            (
+                ClientRequest::Connect {
+                    user_id,
+                    identity_token,
+                    spectator,
+                    handshake,
+                    // Checked by the authenticator of the server before the request gets here
+                    auth_ticket: _,
+                },
+                Self::PendingConnection,
+            ) => {
+                let protocol = match Handshake::negotiate(
+                    &handshake,
+                    &Handshake::current(),
+                    VERSION,
+                ) {
+                    Ok(protocol) => protocol,
+                    Err(err) => {
+                        tracing::warn!(user_id, %err, "Refusing a client of an incompatible version");
+                        return Err(err.into());
+                    }
+                };
+                if spectator && !protocol.features.contains(Features::SPECTATORS) {
+                    bail!("The client asked to spectate, but does not support spectators");
+                }
+
                 // Connect the user
-                tracing::info!("User connected");
-                self.process_connect(data, user_id);
-                Ok(())
+                tracing::info!(?protocol, "User connected");
+                self.process_connect(data, user_id, identity_token, spectator, protocol.features)
             }
-            (ClientRequest::Connect(_), Self::Connected(_)) => {
+            (ClientRequest::Connect { .. }, Self::Connected(_)) => {
                 tracing::warn!("Client already connected");
                 Ok(())
             }
@@ -129,59 +185,141 @@ impl ServerState {
         }
     }
 
-    #[tracing::instrument(level = "debug")]
-    fn process_connect(&mut self, data: &ConnectionData, user_id: String) {
+    #[tracing::instrument(level = "debug", skip(identity_token))]
+    fn process_connect(
+        &mut self,
+        data: &ConnectionData,
+        user_id: String,
+        identity_token: String,
+        spectator: bool,
+        features: Features,
+    ) -> anyhow::Result<()> {
         tracing::debug!("[{}] Locking world", user_id);
         let mut state = data.state.lock();
 
+        if let Some(player) = state.players.get(&user_id) {
+            if player.identity_token != identity_token {
+                bail!("User {user_id:?} is already connected from another client");
+            }
+        }
+
         let (control_tx, control_rx) = flume::unbounded();
 
+        // Players that reconnect stay in the instance they were in
+        let instance_id = state
+            .players
+            .get(&user_id)
+            .map(|player| player.instance.clone())
+            .unwrap_or_else(|| MAIN_INSTANCE_ID.to_string());
         let old_player = state.players.insert(
             user_id.clone(),
             Player {
-                instance: MAIN_INSTANCE_ID.to_string(),
+                instance: instance_id.clone(),
                 control_tx,
                 connection_id: data.connection_id,
+                identity_token,
+                suspended_since: None,
             },
         );
 
-        let instance = state.instances.get_mut(MAIN_INSTANCE_ID).unwrap();
+        let instance = state.instances.get_mut(&instance_id).unwrap();
 
         // Bring world stream up to the current time
         tracing::debug!("[{}] Broadcasting diffs", user_id);
         instance.broadcast_diffs();
         tracing::debug!("[{}] Creating init diff", user_id);
 
-        let diff = data.world_stream_filter.initial_diff(&instance.world);
-        let diff = bincode::serialize(&diff).unwrap().into();
+        // A reconnecting client keeps the role it had, regardless of what it asked for. Players that another shard of
+        // the cluster handed off haven't been connected to this server yet
+        let existing = old_player
+            .is_some()
+            .then(|| {
+                get_connected_by_user_id(&instance.world, &user_id)
+                    .or_else(|| get_by_user_id(&instance.world, &user_id))
+            })
+            .flatten();
+        let spectator = match existing {
+            Some(id) => instance
+                .world
+                .has_component(id, ambient_core::player::spectator()),
+            None => spectator,
+        };
+
+        let diff = if spectator {
+            instance
+                .spectator_stream
+                .filter()
+                .initial_diff(&instance.world)
+        } else {
+            data.world_stream_filter.initial_diff(&instance.world)
+        };
+        let diff = encode_diff(&mut BytesMut::new(), &diff, features);
 
         log_result!(data.diff_tx.send(diff));
         tracing::debug!("[{}] Init diff sent", user_id);
 
-        let entity_data = create_player_entity_data(
+        let create_entity_data = if spectator {
+            create_spectator_entity_data
+        } else {
+            create_player_entity_data
+        };
+        let entity_data = create_entity_data(
             data.conn.clone(),
             user_id.clone(),
             data.diff_tx.clone(),
             data.connection_id,
-        );
+        )
+        .with(player_features(), features);
 
         if let Some(old_player) = old_player {
             old_player.control_tx.send(ServerPush::Disconnect).ok();
 
-            let id = get_by_user_id(&instance.world, &user_id).unwrap();
+            let id = existing.context("The player entity does not exist")?;
 
             instance.world.add_components(id, entity_data).unwrap();
+            instance.world.remove_component(id, player_suspended()).ok();
 
             tracing::info!(user_id, ?id, "Player reconnected");
         } else {
             let id = instance.spawn_player(entity_data);
-            tracing::info!(user_id, ?id, "Player connected");
+            tracing::info!(user_id, ?id, spectator, "Player connected");
         }
 
         *self = Self::Connected(ConnectedClient {
             user_id,
             control_rx: control_rx.into_stream(),
         });
+        Ok(())
+    }
+
+    /// Suspends the player when the connection to it was lost without it disconnecting. The player entity is kept,
+    /// with `player_suspended`, until the client reconnects or the reconnect grace period of its instance runs out.
+    pub fn process_connection_lost(&mut self, data: &ConnectionData) {
+        if let Self::Connected(ConnectedClient { user_id, .. }) = self {
+            let mut state = data.state.lock();
+
+            let Some(player) = state.players.get_mut(user_id) else {
+                return;
+            };
+            if player.connection_id != data.connection_id {
+                tracing::debug!("Lost ownership of player entity, not suspending it");
+                return;
+            }
+
+            tracing::info!(?user_id, "Lost connection to user, suspending player");
+            player.suspended_since = Some(Instant::now());
+            let instance_id = player.instance.clone();
+            if let Some(instance) = state.instances.get_mut(&instance_id) {
+                if let Some(id) = get_connected_by_user_id(&instance.world, user_id) {
+                    instance
+                        .world
+                        .add_component(id, player_suspended(), ())
+                        .unwrap();
+                }
+            }
+        }
+
+        *self = Self::Disconnected;
     }
 
     pub fn process_disconnect(&mut self, data: &ConnectionData) {
@@ -237,13 +375,9 @@ impl ConnectedClient {
     pub async fn process_datagram(
         &mut self,
         data: &ConnectionData,
-        mut payload: Bytes,
+        payload: Bytes,
     ) -> anyhow::Result<()> {
-        if payload.len() < 4 {
-            bail!("Received malformed datagram");
-        }
-
-        let id = payload.get_u32();
+        let (id, payload) = super::decode_datagram(payload)?;
 
         let ((name, handler), assets) = {
             let mut state = data.state.lock();
@@ -260,6 +394,12 @@ impl ConnectedClient {
             )
         };
 
+        metrics::add(
+            RECEIVED_BYTES,
+            &[("handler", name), ("kind", "datagram")],
+            payload.len() as f64,
+        );
+
         let _span = debug_span!("handle_datagram", name, id).entered();
         handler(data.state.clone(), assets, &self.user_id, payload);
 
@@ -293,6 +433,7 @@ impl ConnectedClient {
         };
 
         let _span = debug_span!("handle_uni", name, id).entered();
+        let stream = CountedStream::new(stream, name, "uni");
         handler(data.state.clone(), assets, &self.user_id, Box::pin(stream));
 
         Ok(())
@@ -331,8 +472,8 @@ impl ConnectedClient {
             data.state.clone(),
             assets,
             &self.user_id,
-            Box::pin(send),
-            Box::pin(recv),
+            Box::pin(CountedStream::new(send, name, "bi")),
+            Box::pin(CountedStream::new(recv, name, "bi")),
         );
 
         Ok(())
@@ -357,6 +498,11 @@ pub async fn handle_diffs<S>(
     S: Unpin + AsyncWrite,
 {
     while let Some(msg) = diffs_rx.next().await {
+        metrics::add(
+            SENT_BYTES,
+            &[("handler", "world_diff"), ("kind", "uni")],
+            msg.len() as f64,
+        );
         let span = tracing::debug_span!("send_world_diff", ?msg);
         stream.send_bytes(msg).instrument(span).await.unwrap();
     }
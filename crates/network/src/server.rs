@@ -2,26 +2,41 @@ use std::{collections::HashMap, fmt::Debug, sync::Arc, time::Duration};
 
 use crate::{
     client::{ClientConnection, DynRecv, DynSend},
-    proto::server::Player,
-    NetworkError, RPC_BISTREAM_ID,
+    cluster_ghost,
+    metrics::{ENTITIES, PLAYERS},
+    priority::{self, replication_budget},
+    proto::{encode_diff, server::Player, Features},
+    trace_context, NetworkError, ServerWorldExt, MAX_RPC_REQUEST_SIZE, RPC_BISTREAM_ID,
 };
 use ambient_core::{
     name,
-    player::{get_by_user_id, player},
+    player::{
+        get_by_user_id, player, player_suspended, reconnect_grace_period, spectator,
+        spectator_hidden, user_id,
+    },
 };
 use ambient_ecs::{
-    components, dont_store, query, ArchetypeFilter, Entity, EntityId, FrameEvent, Networked,
-    Resource, System, SystemGroup, World, WorldStream, WorldStreamFilter,
+    components, dont_store,
+    generated::components::core::instance::{
+        instance_create, instance_destroy, instance_error, instance_id, instance_idle_tick_rate,
+        instance_ids, instance_list, instance_modules, instance_move, instance_tick_rate,
+        instance_user,
+    },
+    query, ArchetypeFilter, ComponentDesc, Entity, EntityId, FrameEvent, Networked, QueryState,
+    Resource, System, SystemGroup, World, WorldDiff, WorldStream, WorldStreamFilter,
 };
 use ambient_rpc::RpcRegistry;
 use ambient_std::{
-    asset_cache::AssetCache, asset_url::AbsAssetUrl, fps_counter::FpsSample, log_result,
+    asset_cache::AssetCache, asset_url::AbsAssetUrl, fps_counter::FpsSample, log_result, metrics,
 };
-use ambient_sys::time::SystemTime;
-use bytes::Bytes;
+use ambient_sys::time::{Instant, SystemTime};
+use anyhow::Context;
+use bytes::{Bytes, BytesMut};
 use flume::Sender;
+use itertools::Itertools;
 use parking_lot::Mutex;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tracing::Instrument;
 use uuid::Uuid;
 
 components!("network::server", {
@@ -35,6 +50,14 @@ components!("network::server", {
     player_entity_stream: Sender<Bytes>,
     player_connection_id: Uuid,
     player_connection: Arc<dyn ClientConnection>,
+    /// The protocol features that both the server and the player's client support
+    player_features: Features,
+    /// The player can administer the server, like enabling mods with the debugger. The host, whose client runs on the
+    /// same machine, always can
+    player_admin: (),
+    /// The user ids of the players that can administer the server, besides the host
+    @[Resource]
+    server_admins: Vec<String>,
     // synced resource
     @[Networked]
     server_stats: FpsSample,
@@ -62,6 +85,55 @@ pub struct WorldInstance {
     pub world: World,
     pub world_stream: WorldStream,
     pub systems: SystemGroup,
+    /// The time of the last step, if the instance has been stepped
+    pub last_step: Option<Duration>,
+    pub spectator_stream: SpectatorStream,
+    /// Reused to serialize the diffs, so that broadcasting them doesn't allocate every frame
+    pub diff_buffer: BytesMut,
+}
+
+/// The world stream of the spectators, which doesn't contain the entities with `spectator_hidden`
+#[derive(Clone)]
+pub struct SpectatorStream {
+    stream: WorldStream,
+    hidden_qs: QueryState,
+    shown_qs: QueryState,
+}
+impl SpectatorStream {
+    pub fn new(filter: &WorldStreamFilter) -> Self {
+        Self {
+            stream: WorldStream::new(filter.clone().excl(spectator_hidden())),
+            hidden_qs: QueryState::new(),
+            shown_qs: QueryState::new(),
+        }
+    }
+    pub fn filter(&self) -> &WorldStreamFilter {
+        self.stream.filter()
+    }
+    /// Like [WorldStream::next_diff], but entities that got hidden are despawned and entities that are no longer hidden
+    /// are spawned again
+    pub fn next_diff(&mut self, world: &World) -> WorldDiff {
+        let shown = query(())
+            .incl(spectator_hidden())
+            .despawned()
+            .iter(world, Some(&mut self.shown_qs))
+            .map(|(id, _)| id)
+            .filter(|&id| world.exists(id))
+            .collect_vec();
+        let hidden = query(())
+            .incl(spectator_hidden())
+            .spawned()
+            .iter(world, Some(&mut self.hidden_qs))
+            .map(|(id, _)| id)
+            .collect_vec();
+
+        // The entities that are shown again are spawned first, as the changes to them are now part of the stream
+        let mut diff = self.stream.filter().spawn_diff(world, shown);
+        diff.changes.extend(self.stream.next_diff(world).changes);
+        diff.changes
+            .extend(WorldDiff::new().despawn(hidden).changes);
+        diff
+    }
 }
 
 #[derive(Clone)]
@@ -73,6 +145,11 @@ impl RpcArgs {
     pub fn get_player(&self, world: &World) -> Option<EntityId> {
         get_by_user_id(world, &self.user_id)
     }
+    /// Whether the player that sent the request can administer the server (see [player_admin])
+    pub fn is_admin(&self, world: &World) -> bool {
+        get_connected_by_user_id(world, &self.user_id)
+            .map_or(false, |id| world.has_component(id, player_admin()))
+    }
 }
 
 pub fn create_player_entity_data(
@@ -91,6 +168,31 @@ pub fn create_player_entity_data(
         .with_default(dont_store())
 }
 
+/// Like [create_player_entity_data], but for a spectator, which doesn't have a `player` component
+pub fn create_spectator_entity_data(
+    conn: Arc<dyn ClientConnection>,
+    user_id: String,
+    entities_tx: Sender<Bytes>,
+    connection_id: Uuid,
+) -> Entity {
+    Entity::new()
+        .with(name(), format!("Spectator {}", user_id))
+        .with(spectator(), ())
+        .with(ambient_core::player::user_id(), user_id)
+        .with(player_connection(), conn)
+        .with(player_entity_stream(), entities_tx)
+        .with(player_connection_id(), connection_id)
+        .with_default(dont_store())
+}
+
+/// Returns the entity of the player or spectator that is connected with `user_id`, if it exists
+pub fn get_connected_by_user_id(world: &World, user_id: &str) -> Option<EntityId> {
+    query(ambient_core::player::user_id())
+        .incl(player_connection_id())
+        .iter(world, None)
+        .find_map(|(id, uid)| (uid == user_id).then_some(id))
+}
+
 pub fn register_rpc_bi_stream_handler(
     handlers: &mut BiStreamHandlers,
     rpc_registry: RpcRegistry<RpcArgs>,
@@ -106,12 +208,24 @@ pub fn register_rpc_bi_stream_handler(
                 tokio::spawn(async move {
                     let try_block = || async {
                         let mut buf = Vec::new();
-                        recv.take(1024 * 1024 * 1024).read_to_end(&mut buf).await?;
+                        // One more byte than allowed, to tell requests at the limit from larger ones
+                        recv.take(MAX_RPC_REQUEST_SIZE as u64 + 1)
+                            .read_to_end(&mut buf)
+                            .await?;
+                        if buf.len() > MAX_RPC_REQUEST_SIZE {
+                            return Err(NetworkError::BadMsgFormat(
+                                bincode::ErrorKind::SizeLimit.into(),
+                            ));
+                        }
+                        let span = tracing::info_span!("player_rpc", user_id);
+                        if let Some(parent) = ambient_rpc::trace_context(&buf) {
+                            trace_context::set_parent(&span, parent);
+                        }
                         let args = RpcArgs {
                             state,
                             user_id: user_id.to_string(),
                         };
-                        let resp = rpc_registry.run_req(args, &buf).await?;
+                        let resp = rpc_registry.run_req(args, &buf).instrument(span).await?;
                         send.write_all(&resp).await?;
                         // send.finish().await?;
                         Ok(()) as Result<(), NetworkError>
@@ -128,35 +242,117 @@ impl WorldInstance {
     pub fn spawn_player(&mut self, ed: Entity) -> EntityId {
         ed.spawn(&mut self.world)
     }
+    /// Despawns the player or spectator entity of `user_id`
     pub fn despawn_player(&mut self, user_id: &str) -> Option<Entity> {
-        self.world.despawn(get_by_user_id(&self.world, user_id)?)
+        // Players handed off by another shard of the cluster may not have been connected to this server
+        let id = get_connected_by_user_id(&self.world, user_id).or_else(|| {
+            get_by_user_id(&self.world, user_id)
+                .filter(|&id| !self.world.has_component(id, cluster_ghost()))
+        })?;
+        self.world.despawn(id)
     }
     pub fn broadcast_diffs(&mut self) {
         let diff = self.world_stream.next_diff(&self.world);
-        if diff.is_empty() {
-            return;
-        }
-        let msg: Bytes = bincode::serialize(&diff).unwrap().into();
+        let spectator_diff = self.spectator_stream.next_diff(&self.world);
+        let budget = self.world.resource_opt(replication_budget()).copied();
 
         ambient_profiling::scope!("Send MsgEntities");
-        for (_, (entity_stream,)) in query((player_entity_stream(),)).iter(&self.world, None) {
-            if let Err(_err) = entity_stream.send(msg.clone()) {
-                log::warn!("Failed to broadcast diff to player");
+        for (diff, is_spectator) in [(diff, false), (spectator_diff, true)] {
+            // With a budget, the players may still have held back updates to send
+            if diff.is_empty() && budget.is_none() {
+                continue;
+            }
+            let players = query((player_entity_stream(),))
+                .excl(player_suspended())
+                .iter(&self.world, None)
+                .filter(|(id, _)| self.world.has_component(*id, spectator()) == is_spectator)
+                .map(|(id, (entity_stream,))| {
+                    let features = self.world.get(id, player_features()).unwrap_or_default();
+                    (id, entity_stream.clone(), features)
+                })
+                .collect_vec();
+            // Without a budget, all players get the same diff, which is encoded on demand, as most of the time they
+            // all use the same encoding
+            let mut msgs = HashMap::<bool, Bytes>::new();
+            for (id, entity_stream, features) in players {
+                let msg = match budget {
+                    Some(budget) => {
+                        let diff =
+                            priority::prioritize(&mut self.world, id, &diff, budget as usize);
+                        if diff.is_empty() {
+                            continue;
+                        }
+                        encode_diff(&mut self.diff_buffer, &diff, features)
+                    }
+                    None => msgs
+                        .entry(features.contains(Features::BINARY_DIFFS))
+                        .or_insert_with(|| encode_diff(&mut self.diff_buffer, &diff, features))
+                        .clone(),
+                };
+                if let Err(_err) = entity_stream.send(msg) {
+                    log::warn!("Failed to broadcast diff to player");
+                }
             }
         }
     }
+    /// The filter of the stream the player or spectator `id` receives
+    pub fn stream_filter(&self, id: EntityId) -> &WorldStreamFilter {
+        if self.world.has_component(id, spectator()) {
+            self.spectator_stream.filter()
+        } else {
+            self.world_stream.filter()
+        }
+    }
     pub fn player_count(&self) -> usize {
         query((player(),)).iter(&self.world, None).count()
     }
+    /// How many times per second this instance is currently stepped: its `instance_idle_tick_rate` while it has no
+    /// players, if it has one, and its `instance_tick_rate` otherwise
+    pub fn tick_rate(&self) -> f32 {
+        let idle_tick_rate = self.world.resource_opt(instance_idle_tick_rate());
+        let tick_rate = match idle_tick_rate {
+            Some(&rate) if self.player_count() == 0 => rate,
+            _ => self
+                .world
+                .resource_opt(instance_tick_rate())
+                .copied()
+                .unwrap_or(DEFAULT_TICK_RATE),
+        };
+        tick_rate.clamp(MIN_TICK_RATE, MAX_TICK_RATE)
+    }
+    /// Steps the instance if a tick has passed since its last step at its current tick rate. The `dtime` resource is
+    /// set to the time since the last step.
     pub fn step(&mut self, time: Duration) {
+        let tick = Duration::from_secs_f32(1. / self.tick_rate());
+        let dtime = match self.last_step {
+            // Allow for some jitter in the server loop, so that instances ticking at the rate of the loop don't skip
+            // every other tick
+            Some(last_step) if time.saturating_sub(last_step) < tick.mul_f32(0.9) => return,
+            Some(last_step) => time.saturating_sub(last_step),
+            None => tick,
+        };
+        self.last_step = Some(time);
+
+        let resources = self.world.resource_entity();
+        self.world
+            .set(resources, ambient_core::time(), time)
+            .unwrap();
         self.world
-            .set(self.world.resource_entity(), ambient_core::time(), time)
+            .set(resources, ambient_core::dtime(), dtime.as_secs_f32())
             .unwrap();
         self.systems.run(&mut self.world, &FrameEvent);
         self.world.next_frame();
     }
 }
 
+/// How long suspended players are kept, in seconds, in instances that don't have a `reconnect_grace_period`
+pub const DEFAULT_RECONNECT_GRACE_PERIOD: f32 = 30.;
+
+/// The tick rate of instances that don't have an `instance_tick_rate`
+pub const DEFAULT_TICK_RATE: f32 = 60.;
+const MIN_TICK_RATE: f32 = 0.1;
+const MAX_TICK_RATE: f32 = 240.;
+
 pub const MAIN_INSTANCE_ID: &str = "main";
 
 pub type SharedServerState = Arc<Mutex<ServerState>>;
@@ -180,8 +376,11 @@ impl ServerState {
                 MAIN_INSTANCE_ID.to_string(),
                 WorldInstance {
                     world: World::new("main_server"),
-                    world_stream: WorldStream::new(world_stream_filter),
+                    world_stream: WorldStream::new(world_stream_filter.clone()),
                     systems: SystemGroup::new("", vec![]),
+                    last_step: None,
+                    spectator_stream: SpectatorStream::new(&world_stream_filter),
+                    diff_buffer: BytesMut::new(),
                 },
             )]
             .into(),
@@ -215,6 +414,8 @@ impl ServerState {
         for instance in self.instances.values_mut() {
             instance.step(time);
         }
+        self.process_instance_requests();
+        self.despawn_expired_players();
     }
     pub fn broadcast_diffs(&mut self) {
         for instance in self.instances.values_mut() {
@@ -224,6 +425,26 @@ impl ServerState {
     pub fn player_count(&self) -> usize {
         self.instances.values().map(|i| i.player_count()).sum()
     }
+    /// Updates the statistics of the instances, and drops those of the instances that no longer exist
+    pub fn record_metrics(&self) {
+        if !metrics::is_enabled() {
+            return;
+        }
+        for (id, instance) in &self.instances {
+            metrics::set(ENTITIES, &[("instance", id)], instance.world.len() as f64);
+            metrics::set(PLAYERS, &[("instance", id)], instance.player_count() as f64);
+        }
+        for name in [ENTITIES, PLAYERS] {
+            metrics::retain(name, |labels| self.instances.contains_key(&labels[0].1));
+        }
+    }
+    /// The tick rate of the fastest instance, which the server loop needs to run at
+    pub fn max_tick_rate(&self) -> f32 {
+        self.instances
+            .values()
+            .map(|i| i.tick_rate())
+            .fold(MIN_TICK_RATE, f32::max)
+    }
     pub fn get_player_world_instance_mut(&mut self, user_id: &str) -> Option<&mut WorldInstance> {
         self.players
             .get(user_id)
@@ -248,6 +469,292 @@ impl ServerState {
         sys.run(&mut old_instance.world, &ShutdownEvent);
         self.instances.remove(instance_id);
     }
+
+    /// Creates the instance `id` as a copy of the instance `source_id` without its players, with the extra `resources`
+    /// and `synced_res`. The forking systems run on the new world, which restart its modules; if `modules` is given,
+    /// only the modules with those names are enabled in it.
+    pub fn fork_instance(
+        &mut self,
+        source_id: &str,
+        id: &str,
+        resources: Entity,
+        synced_res: Entity,
+        modules: Option<Vec<String>>,
+    ) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            !self.instances.contains_key(id),
+            "The instance {id:?} already exists"
+        );
+        let source = self
+            .instances
+            .get(source_id)
+            .with_context(|| format!("There is no instance {source_id:?}"))?;
+        let mut world = source.world.clone();
+
+        for (id, _) in query(user_id()).collect_cloned(&world, None) {
+            world.despawn(id);
+        }
+        // The pending instance requests were made by the source instance
+        for (id, _) in InstanceRequest::pending(&world) {
+            world.despawn(id);
+        }
+        let mut resources = resources.with_merge(ambient_core::async_ecs::async_ecs_resources());
+        if let Some(modules) = modules {
+            resources.set(instance_modules(), modules);
+        }
+        world
+            .add_components(world.resource_entity(), resources)
+            .unwrap();
+        world
+            .add_components(
+                world.synced_resource_entity().unwrap(),
+                synced_res.with(instance_id(), id.to_string()),
+            )
+            .unwrap();
+
+        let mut on_forking = (self.create_on_forking_systems)();
+        on_forking.run(&mut world, &ForkingEvent);
+
+        world.reset_events();
+
+        let new_instance = WorldInstance {
+            systems: (self.create_server_systems)(&mut world),
+            world,
+            world_stream: source.world_stream.clone(),
+            last_step: None,
+            spectator_stream: source.spectator_stream.clone(),
+            diff_buffer: BytesMut::new(),
+        };
+        self.instances.insert(id.to_string(), new_instance);
+        Ok(())
+    }
+
+    /// Moves the player `user_id` to the instance `instance_id`. The player entity is despawned from its current
+    /// instance and spawned in the new one, and the client is sent the difference between the two worlds.
+    pub fn move_player(&mut self, user_id: &str, instance_id: &str) -> anyhow::Result<()> {
+        let old_instance_id = self
+            .players
+            .get(user_id)
+            .with_context(|| format!("There is no player {user_id:?}"))?
+            .instance
+            .clone();
+        if old_instance_id == instance_id {
+            return Ok(());
+        }
+        anyhow::ensure!(
+            self.instances.contains_key(instance_id),
+            "There is no instance {instance_id:?}"
+        );
+
+        let instances = &mut self.instances;
+
+        // Borrow the new world mutably to broadcast its diffs.
+        instances.get_mut(instance_id).unwrap().broadcast_diffs();
+
+        // Borrow both worlds immutably to extract the diff between the two.
+        let (diff, is_spectator) = {
+            let (old_instance, new_instance) = instances
+                .get(&old_instance_id)
+                .zip(instances.get(instance_id))
+                .unwrap();
+            let id = get_connected_by_user_id(&old_instance.world, user_id)
+                .context("The player entity does not exist")?;
+            let diff = WorldDiff::from_a_to_b(
+                old_instance.stream_filter(id).clone(),
+                &old_instance.world,
+                &new_instance.world,
+            );
+            (diff, old_instance.world.has_component(id, spectator()))
+        };
+
+        // Borrow the old world mutably to remove the player and their streams.
+        let mut ed = instances
+            .get_mut(&old_instance_id)
+            .unwrap()
+            .despawn_player(user_id)
+            .context("The player entity does not exist")?;
+        let entities_tx = ed.remove_self(player_entity_stream()).unwrap();
+        let connection_id = ed.remove_self(player_connection_id()).unwrap();
+        let conn = ed.remove_self(player_connection()).unwrap();
+        let features = ed.remove_self(player_features()).unwrap_or_default();
+
+        // Borrow the new world mutably to spawn the player in with their old streams.
+        let create_entity_data = if is_spectator {
+            create_spectator_entity_data
+        } else {
+            create_player_entity_data
+        };
+        instances.get_mut(instance_id).unwrap().spawn_player(
+            create_entity_data(
+                conn,
+                user_id.to_string(),
+                entities_tx.clone(),
+                connection_id,
+            )
+            .with(player_features(), features),
+        );
+        self.players.get_mut(user_id).unwrap().instance = instance_id.to_string();
+
+        let msg = encode_diff(&mut BytesMut::new(), &diff, features);
+        entities_tx.send(msg).ok();
+        Ok(())
+    }
+
+    /// Destroys the instance `instance_id`, after moving its players to the main instance
+    pub fn destroy_instance(&mut self, instance_id: &str) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            instance_id != MAIN_INSTANCE_ID,
+            "The main instance can't be destroyed"
+        );
+        anyhow::ensure!(
+            self.instances.contains_key(instance_id),
+            "There is no instance {instance_id:?}"
+        );
+        let users = self
+            .players
+            .iter()
+            .filter(|(_, player)| player.instance == instance_id)
+            .map(|(user_id, _)| user_id.clone())
+            .collect_vec();
+        for user_id in users {
+            self.move_player(&user_id, MAIN_INSTANCE_ID)?;
+        }
+        self.remove_instance(instance_id);
+        Ok(())
+    }
+
+    /// Despawns the suspended players that didn't reconnect within the reconnect grace period of their instance
+    fn despawn_expired_players(&mut self) {
+        let now = Instant::now();
+        let expired = self
+            .players
+            .iter()
+            .filter(|(_, player)| {
+                let Some(suspended_since) = player.suspended_since else {
+                    return false;
+                };
+                let grace_period = self
+                    .instances
+                    .get(&player.instance)
+                    .and_then(|i| i.world.resource_opt(reconnect_grace_period()).copied())
+                    .unwrap_or(DEFAULT_RECONNECT_GRACE_PERIOD);
+                now.duration_since(suspended_since).as_secs_f32() >= grace_period
+            })
+            .map(|(user_id, _)| user_id.clone())
+            .collect_vec();
+
+        for user_id in expired {
+            log::info!("User {user_id:?} did not reconnect in time, despawning player");
+            let player = self.players.remove(&user_id).unwrap();
+            if let Some(instance) = self.instances.get_mut(&player.instance) {
+                instance.despawn_player(&user_id);
+            }
+        }
+    }
+
+    /// Answers the instance requests that were spawned in the instances during their last frame
+    fn process_instance_requests(&mut self) {
+        let requests = self
+            .instances
+            .iter()
+            .flat_map(|(source_id, instance)| {
+                InstanceRequest::pending(&instance.world)
+                    .into_iter()
+                    .map(|(request_id, request)| (source_id.clone(), request_id, request))
+            })
+            .collect_vec();
+
+        for (source_id, request_id, request) in requests {
+            let mut answer = Entity::new();
+            let result = match request {
+                InstanceRequest::Create { id, modules } => {
+                    self.fork_instance(&source_id, &id, Entity::new(), Entity::new(), modules)
+                }
+                InstanceRequest::Destroy { id } => self.destroy_instance(&id),
+                InstanceRequest::Move { id, user_id } => self.move_player(&user_id, &id),
+                InstanceRequest::List => {
+                    answer.set(
+                        instance_ids(),
+                        self.instances.keys().cloned().sorted().collect(),
+                    );
+                    Ok(())
+                }
+            };
+            // The request is gone if its instance was destroyed
+            let Some(source) = self.instances.get_mut(&source_id) else {
+                continue;
+            };
+            let error = match result {
+                Ok(()) => String::new(),
+                Err(err) => format!("{err:#}"),
+            };
+            source
+                .world
+                .add_components(request_id, answer.with(instance_error(), error))
+                .ok();
+        }
+    }
+}
+
+enum InstanceRequest {
+    Create {
+        id: String,
+        modules: Option<Vec<String>>,
+    },
+    Destroy {
+        id: String,
+    },
+    Move {
+        id: String,
+        user_id: String,
+    },
+    List,
+}
+impl InstanceRequest {
+    /// The requests in `world` that haven't been answered yet. Only the entities with one of the request components
+    /// are looked at, so that this doesn't go through the whole world every tick.
+    fn pending(world: &World) -> Vec<(EntityId, Self)> {
+        let components: [ComponentDesc; 4] = [
+            instance_create().into(),
+            instance_destroy().into(),
+            instance_move().into(),
+            instance_list().into(),
+        ];
+        components
+            .into_iter()
+            .flat_map(|component| {
+                query(())
+                    .incl(component)
+                    .excl(instance_error())
+                    .iter(world, None)
+                    .map(|(id, _)| id)
+                    .collect_vec()
+            })
+            .unique()
+            .filter_map(|id| Some((id, Self::read(world, id)?)))
+            .collect()
+    }
+
+    fn read(world: &World, request_id: EntityId) -> Option<Self> {
+        let get = |component| world.get_cloned(request_id, component).ok();
+        Some(if let Some(id) = get(instance_create()) {
+            Self::Create {
+                id,
+                modules: world.get_cloned(request_id, instance_modules()).ok(),
+            }
+        } else if let Some(id) = get(instance_destroy()) {
+            Self::Destroy { id }
+        } else if let Some(id) = get(instance_move()) {
+            Self::Move {
+                id,
+                user_id: get(instance_user())?,
+            }
+        } else if world.has_component(request_id, instance_list()) {
+            Self::List
+        } else {
+            return None;
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
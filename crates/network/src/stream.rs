@@ -1,11 +1,11 @@
 use std::{any::type_name, marker::PhantomData, pin::Pin, task::Poll};
 
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use futures::{ready, Sink, SinkExt, Stream};
 use pin_project::pin_project;
 use thiserror::Error;
 use tokio::io::{AsyncRead, AsyncWrite};
-use tokio_util::codec::{FramedRead, FramedWrite, LengthDelimitedCodec};
+use tokio_util::codec::{Decoder, FramedRead, FramedWrite, LengthDelimitedCodec};
 
 use crate::{NetworkError, MAX_FRAME_SIZE};
 
@@ -14,7 +14,7 @@ use crate::{NetworkError, MAX_FRAME_SIZE};
 pub struct RecvStream<T, S> {
     #[pin]
     read: FramedRead<S, LengthDelimitedCodec>,
-    _marker: PhantomData<T>,
+    decode: fn(&[u8]) -> bincode::Result<T>,
 }
 
 impl<T, S> RecvStream<T, S>
@@ -23,13 +23,62 @@ where
     T: serde::de::DeserializeOwned,
 {
     pub fn new(stream: S) -> Self {
-        let mut codec = LengthDelimitedCodec::new();
-        codec.set_max_frame_length(MAX_FRAME_SIZE);
+        Self::with_decoder(stream, |bytes| bincode::deserialize(bytes))
+    }
+
+    /// Creates a stream that decodes its frames with `decode`, e.g. to fall back to older versions of `T`
+    pub fn with_decoder(stream: S, decode: fn(&[u8]) -> bincode::Result<T>) -> Self {
         Self {
-            read: FramedRead::new(stream, codec),
-            _marker: PhantomData,
+            read: FramedRead::new(stream, frame_codec(MAX_FRAME_SIZE)),
+            decode,
+        }
+    }
+
+    /// Fails the stream on frames larger than `max_frame_size`, rather than buffering them
+    pub fn with_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.read.decoder_mut().set_max_frame_length(max_frame_size);
+        self
+    }
+}
+
+fn frame_codec(max_frame_size: usize) -> LengthDelimitedCodec {
+    let mut codec = LengthDelimitedCodec::new();
+    codec.set_max_frame_length(max_frame_size);
+    codec
+}
+
+fn decode_frame<T>(frame: &[u8], decode: fn(&[u8]) -> bincode::Result<T>) -> Result<T, FrameError> {
+    decode(frame).map_err(|e| FrameError::Payload(e, type_name::<T>()))
+}
+
+/// Splits `bytes` into frames and decodes them like a [RecvStream] that reads them with `decode`, up to the first
+/// error. Frames that `bytes` ends in the middle of are left out.
+pub fn decode_frames<T>(
+    bytes: &[u8],
+    max_frame_size: usize,
+    decode: fn(&[u8]) -> bincode::Result<T>,
+) -> Vec<Result<T, FrameError>> {
+    let mut codec = frame_codec(max_frame_size);
+    let mut buf = BytesMut::from(bytes);
+    let mut frames = Vec::new();
+    loop {
+        match codec.decode(&mut buf) {
+            Ok(Some(frame)) => {
+                let frame = decode_frame(&frame, decode);
+                let failed = frame.is_err();
+                frames.push(frame);
+                if failed {
+                    break;
+                }
+            }
+            Ok(None) => break,
+            Err(err) => {
+                frames.push(Err(err.into()));
+                break;
+            }
         }
     }
+    frames
 }
 
 impl<T, S> Stream for RecvStream<T, S>
@@ -48,9 +97,7 @@ where
         let bytes = ready!(p.read.poll_next(cx)?);
 
         match bytes {
-            Some(v) => Poll::Ready(Some(
-                bincode::deserialize(&v).map_err(|e| FrameError::Payload(e, type_name::<T>())),
-            )),
+            Some(v) => Poll::Ready(Some(decode_frame(&v, *p.decode))),
             None => Poll::Ready(None),
         }
     }
@@ -70,10 +117,8 @@ where
     T: serde::Serialize,
 {
     pub fn new(stream: S) -> Self {
-        let mut codec = LengthDelimitedCodec::new();
-        codec.set_max_frame_length(MAX_FRAME_SIZE);
         Self {
-            write: FramedWrite::new(stream, codec),
+            write: FramedWrite::new(stream, frame_codec(MAX_FRAME_SIZE)),
             _marker: PhantomData,
         }
     }
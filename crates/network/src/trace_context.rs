@@ -0,0 +1,37 @@
+//! Propagation of tracing contexts across the network, so that the spans of an RPC request on the server continue the
+//! trace of the client that made it. Only RPC requests carry a context; streams, datagrams and responses don't.
+//! Contexts are W3C `traceparent` headers; without the `otlp` feature there are no traces to continue, so nothing is
+//! sent and received contexts are ignored.
+
+#[cfg(feature = "otlp")]
+const TRACEPARENT: &str = "traceparent";
+
+/// The context of the current span, if it is part of a trace that is being exported
+#[cfg(feature = "otlp")]
+pub fn current() -> Option<String> {
+    use opentelemetry::{propagation::TextMapPropagator, sdk::propagation::TraceContextPropagator};
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    let mut carrier = std::collections::HashMap::new();
+    TraceContextPropagator::new().inject_context(&tracing::Span::current().context(), &mut carrier);
+    carrier.remove(TRACEPARENT)
+}
+
+#[cfg(not(feature = "otlp"))]
+pub fn current() -> Option<String> {
+    None
+}
+
+/// Makes `span` continue the trace of a context received from a peer
+#[cfg(feature = "otlp")]
+pub fn set_parent(span: &tracing::Span, traceparent: &str) {
+    use opentelemetry::{propagation::TextMapPropagator, sdk::propagation::TraceContextPropagator};
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    let carrier =
+        std::collections::HashMap::from([(TRACEPARENT.to_string(), traceparent.to_string())]);
+    span.set_parent(TraceContextPropagator::new().extract(&carrier));
+}
+
+#[cfg(not(feature = "otlp"))]
+pub fn set_parent(_span: &tracing::Span, _traceparent: &str) {}
@@ -0,0 +1,401 @@
+//! Local avoidance for AI agents, using [ORCA](https://gamma.cs.unc.edu/ORCA/) (optimal reciprocal collision
+//! avoidance). Agents are avoided on the XY plane; the Z of their desired velocity is passed through.
+
+use ambient_core::{dtime, transform::translation};
+use ambient_ecs::{query, EntityId, FnSystem, SystemGroup};
+use glam::{Vec2, Vec3, Vec3Swizzles};
+use itertools::Itertools;
+use ordered_float::OrderedFloat;
+
+use crate::{avoidance_desired_velocity, avoidance_max_speed, avoidance_radius, unit_velocity};
+
+/// How far ahead, in seconds, agents avoid each other
+const TIME_HORIZON: f32 = 2.;
+/// How far ahead, in seconds, agents avoid static obstacles
+const OBSTACLE_TIME_HORIZON: f32 = 1.;
+/// Only the closest neighbors within this many seconds of travel at the agent's maximum speed are considered
+const NEIGHBOR_TIME: f32 = 2.;
+/// The maximum number of neighbors considered for each agent
+const MAX_NEIGHBORS: usize = 10;
+const EPSILON: f32 = 0.00001;
+/// The shortest time until the next update that collisions are resolved within, so that a paused world or the first
+/// frame (where `dt` is 0) doesn't divide by zero
+const MIN_DT: f32 = 1. / 240.;
+
+/// An agent or obstacle, as seen by the agents avoiding it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AvoidanceBody {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub radius: f32,
+    /// Static obstacles don't avoid agents, so the agents take full responsibility for avoiding them
+    pub is_static: bool,
+}
+
+/// A half-plane of permitted velocities: the ones to the left of `direction` through `point`
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Line {
+    point: Vec2,
+    direction: Vec2,
+}
+
+/// Returns the velocity closest to `preferred_velocity` (at most `max_speed`) that avoids colliding with the
+/// `neighbors` within the time horizon. `dt` is the time until the next update, which is used to resolve collisions
+/// that have already happened.
+pub fn orca_velocity(
+    agent: &AvoidanceBody,
+    preferred_velocity: Vec2,
+    max_speed: f32,
+    neighbors: &[AvoidanceBody],
+    dt: f32,
+) -> Vec2 {
+    let lines = neighbors
+        .iter()
+        .map(|neighbor| {
+            let (time_horizon, responsibility) = if neighbor.is_static {
+                (OBSTACLE_TIME_HORIZON, 1.)
+            } else {
+                (TIME_HORIZON, 0.5)
+            };
+            orca_line(agent, neighbor, time_horizon, responsibility, dt)
+        })
+        .collect_vec();
+
+    let mut result = Vec2::ZERO;
+    let failed_line = linear_program_2(&lines, max_speed, preferred_velocity, false, &mut result);
+    if failed_line < lines.len() {
+        linear_program_3(&lines, failed_line, max_speed, &mut result);
+    }
+    result
+}
+
+fn orca_line(
+    agent: &AvoidanceBody,
+    neighbor: &AvoidanceBody,
+    time_horizon: f32,
+    responsibility: f32,
+    dt: f32,
+) -> Line {
+    let relative_position = neighbor.position - agent.position;
+    let relative_velocity = agent.velocity - neighbor.velocity;
+    let distance_sq = relative_position.length_squared();
+    let combined_radius = agent.radius + neighbor.radius;
+    let combined_radius_sq = combined_radius * combined_radius;
+
+    let (direction, u) = if distance_sq > combined_radius_sq {
+        // No collision yet; w is the vector from the cutoff center to the relative velocity
+        let w = relative_velocity - relative_position / time_horizon;
+        let w_length_sq = w.length_squared();
+        let dot = w.dot(relative_position);
+        if dot < 0. && dot * dot > combined_radius_sq * w_length_sq {
+            // Project on the cutoff circle
+            let w_length = w_length_sq.sqrt();
+            let unit_w = w / w_length;
+            (
+                Vec2::new(unit_w.y, -unit_w.x),
+                (combined_radius / time_horizon - w_length) * unit_w,
+            )
+        } else {
+            // Project on the legs
+            let leg = (distance_sq - combined_radius_sq).sqrt();
+            let direction = if relative_position.perp_dot(w) > 0. {
+                Vec2::new(
+                    relative_position.x * leg - relative_position.y * combined_radius,
+                    relative_position.x * combined_radius + relative_position.y * leg,
+                ) / distance_sq
+            } else {
+                -Vec2::new(
+                    relative_position.x * leg + relative_position.y * combined_radius,
+                    -relative_position.x * combined_radius + relative_position.y * leg,
+                ) / distance_sq
+            };
+            (
+                direction,
+                relative_velocity.dot(direction) * direction - relative_velocity,
+            )
+        }
+    } else {
+        // Already colliding; get apart within the next update
+        let dt = dt.max(MIN_DT);
+        let w = relative_velocity - relative_position / dt;
+        let w_length = w.length().max(EPSILON);
+        let unit_w = w / w_length;
+        (
+            Vec2::new(unit_w.y, -unit_w.x),
+            (combined_radius / dt - w_length) * unit_w,
+        )
+    };
+    Line {
+        point: agent.velocity + responsibility * u,
+        direction,
+    }
+}
+
+/// Solves a one-dimensional linear program on the line `lines[line_no]`, constrained by the lines before it and a
+/// circle of `radius`
+fn linear_program_1(
+    lines: &[Line],
+    line_no: usize,
+    radius: f32,
+    optimization_velocity: Vec2,
+    direction_opt: bool,
+    result: &mut Vec2,
+) -> bool {
+    let line = lines[line_no];
+    let dot = line.point.dot(line.direction);
+    let discriminant = dot * dot + radius * radius - line.point.length_squared();
+    if discriminant < 0. {
+        // The maximum speed circle fully invalidates the line
+        return false;
+    }
+    let discriminant = discriminant.sqrt();
+    let mut t_left = -dot - discriminant;
+    let mut t_right = -dot + discriminant;
+
+    for other in &lines[..line_no] {
+        let denominator = line.direction.perp_dot(other.direction);
+        let numerator = other.direction.perp_dot(line.point - other.point);
+        if denominator.abs() <= EPSILON {
+            // The lines are (almost) parallel
+            if numerator < 0. {
+                return false;
+            }
+            continue;
+        }
+        let t = numerator / denominator;
+        if denominator >= 0. {
+            t_right = t_right.min(t);
+        } else {
+            t_left = t_left.max(t);
+        }
+        if t_left > t_right {
+            return false;
+        }
+    }
+
+    *result = if direction_opt {
+        if optimization_velocity.dot(line.direction) > 0. {
+            line.point + t_right * line.direction
+        } else {
+            line.point + t_left * line.direction
+        }
+    } else {
+        let t = line.direction.dot(optimization_velocity - line.point);
+        line.point + t.clamp(t_left, t_right) * line.direction
+    };
+    true
+}
+
+/// Solves a two-dimensional linear program constrained by the lines and a circle of `radius`. Returns the index of
+/// the line it failed at, or the number of lines if it succeeded.
+fn linear_program_2(
+    lines: &[Line],
+    radius: f32,
+    optimization_velocity: Vec2,
+    direction_opt: bool,
+    result: &mut Vec2,
+) -> usize {
+    *result = if direction_opt {
+        // The optimization velocity is a unit direction here
+        optimization_velocity * radius
+    } else if optimization_velocity.length_squared() > radius * radius {
+        optimization_velocity.normalize() * radius
+    } else {
+        optimization_velocity
+    };
+
+    for i in 0..lines.len() {
+        if lines[i].direction.perp_dot(lines[i].point - *result) > 0. {
+            // The result doesn't satisfy this constraint
+            let previous = *result;
+            if !linear_program_1(
+                lines,
+                i,
+                radius,
+                optimization_velocity,
+                direction_opt,
+                result,
+            ) {
+                *result = previous;
+                return i;
+            }
+        }
+    }
+    lines.len()
+}
+
+/// When there is no velocity that satisfies all the constraints, finds the one that violates them the least
+fn linear_program_3(lines: &[Line], begin_line: usize, radius: f32, result: &mut Vec2) {
+    let mut distance = 0.;
+    for i in begin_line..lines.len() {
+        let line = lines[i];
+        if line.direction.perp_dot(line.point - *result) <= distance {
+            continue;
+        }
+        let projected_lines = lines[..i]
+            .iter()
+            .filter_map(|other| {
+                let determinant = line.direction.perp_dot(other.direction);
+                let point = if determinant.abs() <= EPSILON {
+                    if line.direction.dot(other.direction) > 0. {
+                        // The lines point in the same direction
+                        return None;
+                    }
+                    0.5 * (line.point + other.point)
+                } else {
+                    line.point
+                        + (other.direction.perp_dot(line.point - other.point) / determinant)
+                            * line.direction
+                };
+                Some(Line {
+                    point,
+                    direction: (other.direction - line.direction).normalize(),
+                })
+            })
+            .collect_vec();
+
+        let previous = *result;
+        if linear_program_2(
+            &projected_lines,
+            radius,
+            Vec2::new(-line.direction.y, line.direction.x),
+            true,
+            result,
+        ) < projected_lines.len()
+        {
+            // This should in principle not happen, as the result is by definition already in the feasible region of
+            // this linear program; if it fails, it's due to small floating point errors
+            *result = previous;
+        }
+        distance = line.direction.perp_dot(line.point - *result);
+    }
+}
+
+/// Steers the entities with `avoidance_desired_velocity` around each other and around static obstacles (entities that
+/// only have an `avoidance_radius`), writing the result to their `unit_velocity`.
+pub fn systems() -> SystemGroup {
+    SystemGroup::new(
+        "avoidance",
+        vec![Box::new(FnSystem::new(|world, _| {
+            ambient_profiling::scope!("avoidance");
+            let dt = *world.resource(dtime());
+            let bodies = query((translation(), avoidance_radius()))
+                .iter(world, None)
+                .map(|(id, (position, radius))| {
+                    let desired = world.get(id, avoidance_desired_velocity()).ok();
+                    let velocity = world.get(id, unit_velocity()).ok().or(desired);
+                    (
+                        id,
+                        AvoidanceBody {
+                            position: position.xy(),
+                            velocity: velocity.unwrap_or_default().xy(),
+                            radius: *radius,
+                            is_static: desired.is_none(),
+                        },
+                    )
+                })
+                .collect_vec();
+
+            let mut velocities: Vec<(EntityId, Vec3)> = Vec::new();
+            for (id, agent) in bodies.iter().filter(|(_, body)| !body.is_static) {
+                let desired = world.get(*id, avoidance_desired_velocity()).unwrap();
+                let max_speed = world
+                    .get(*id, avoidance_max_speed())
+                    .unwrap_or_else(|_| desired.xy().length());
+                let range = max_speed * NEIGHBOR_TIME + agent.radius;
+                let neighbors = bodies
+                    .iter()
+                    .filter(|(other, body)| {
+                        other != id && body.position.distance(agent.position) < range + body.radius
+                    })
+                    .map(|(_, body)| *body)
+                    .sorted_by_key(|body| OrderedFloat(body.position.distance(agent.position)))
+                    .take(MAX_NEIGHBORS)
+                    .collect_vec();
+                let velocity = orca_velocity(agent, desired.xy(), max_speed, &neighbors, dt);
+                velocities.push((*id, velocity.extend(desired.z)));
+            }
+            for (id, velocity) in velocities {
+                world.add_component(id, unit_velocity(), velocity).ok();
+            }
+        }))],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(x: f32, y: f32, velocity: Vec2) -> AvoidanceBody {
+        AvoidanceBody {
+            position: Vec2::new(x, y),
+            velocity,
+            radius: 0.5,
+            is_static: false,
+        }
+    }
+
+    #[test]
+    fn unobstructed_agents_keep_their_velocity() {
+        let agent = body(0., 0., Vec2::X);
+        let far_away = body(0., 20., Vec2::ZERO);
+        assert_eq!(
+            orca_velocity(&agent, Vec2::X, 1., &[far_away], 1. / 60.),
+            Vec2::X
+        );
+        // The preferred velocity is limited to the maximum speed
+        assert_eq!(
+            orca_velocity(&agent, Vec2::X * 3., 2., &[], 1. / 60.),
+            Vec2::X * 2.
+        );
+    }
+
+    #[test]
+    fn head_on_agents_sidestep_in_opposite_directions() {
+        let a = body(-2., 0., Vec2::X);
+        let b = body(2., 0., -Vec2::X);
+        let va = orca_velocity(&a, Vec2::X, 1., &[b], 1. / 60.);
+        let vb = orca_velocity(&b, -Vec2::X, 1., &[a], 1. / 60.);
+        assert!(va.y.abs() > 0.01);
+        // ORCA is reciprocal, so they pass each other on the same side (from their own point of view)
+        assert!(va.y * vb.y < 0.);
+        assert!(va.x > 0. && vb.x < 0.);
+    }
+
+    #[test]
+    fn agents_take_full_responsibility_for_static_obstacles() {
+        let mut agent = body(-2., 0.1, Vec2::X);
+        let obstacle = AvoidanceBody {
+            is_static: true,
+            ..body(0., 0., Vec2::ZERO)
+        };
+        // Simulate the agent going past the obstacle, and check that it goes around it without overlapping it
+        let mut max_y: f32 = 0.;
+        for _ in 0..600 {
+            let dt = 1. / 60.;
+            agent.velocity = orca_velocity(&agent, Vec2::X, 1., &[obstacle], dt);
+            agent.position += agent.velocity * dt;
+            assert!(agent.position.distance(obstacle.position) > 0.99);
+            max_y = max_y.max(agent.position.y);
+        }
+        assert!(max_y > 0.9);
+        assert!(agent.position.x > 2.);
+    }
+
+    #[test]
+    fn colliding_agents_separate() {
+        let a = body(0., 0., Vec2::ZERO);
+        let b = body(0.5, 0., Vec2::ZERO);
+        let va = orca_velocity(&a, Vec2::ZERO, 5., &[b], 1. / 60.);
+        assert!(va.x < 0.);
+    }
+
+    #[test]
+    fn colliding_agents_separate_when_paused() {
+        let a = body(0., 0., Vec2::ZERO);
+        let b = body(0.5, 0., Vec2::ZERO);
+        let va = orca_velocity(&a, Vec2::ZERO, 5., &[b], 0.);
+        assert!(va.is_finite());
+        assert!(va.x < 0.);
+    }
+}
@@ -32,6 +32,7 @@ use physxx::{
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    helpers::{set_shape_ccd, update_ccd},
     main_controller_manager, make_physics_static,
     mesh::{PhysxGeometry, PhysxGeometryFromUrl},
     physx::{
@@ -209,8 +210,10 @@ pub fn server_systems() -> SystemGroup {
                         desc.position = pos.as_dvec3();
                         desc.up_direction = vec3(0., 0., 1.);
                         let controller = controller_manager.create_controller(&desc);
+                        let shapes_ccd = world.get(id, ccd_shapes()).unwrap_or(true);
                         for shape in controller.get_actor().get_shapes() {
                             shape.set_flag(PxShapeFlag::VISUALIZATION, false);
+                            set_shape_ccd(&shape, shapes_ccd);
                         }
                         let actor = controller.get_actor();
                         actor.as_actor().set_user_data(id);
@@ -348,7 +351,7 @@ pub fn server_systems() -> SystemGroup {
                         };
                         if let Some(actor) = actor.to_rigid_body() {
                             actor.set_rigid_body_flag(PxRigidBodyFlag::KINEMATIC, is_kinematic);
-                            actor.set_rigid_body_flag(PxRigidBodyFlag::ENABLE_CCD, !is_kinematic);
+                            update_ccd(world, id, &actor);
                         }
                         actor
                             .as_actor()
@@ -363,6 +366,7 @@ pub fn server_systems() -> SystemGroup {
                         };
                         let coff = world.get(id, contact_offset()).ok();
                         let roff = world.get(id, rest_offset()).ok();
+                        let shapes_ccd = world.get(id, ccd_shapes()).unwrap_or(true);
                         for shape in shapes.iter_mut() {
                             if !actor.attach_shape(shape) {
                                 log::error!("Failed to attach shape to entity {}", id);
@@ -377,6 +381,7 @@ pub fn server_systems() -> SystemGroup {
                             if let Some(roff) = roff {
                                 shape.set_rest_offset(roff);
                             }
+                            set_shape_ccd(shape, shapes_ccd);
                             shape.update_user_data::<PxShapeUserData>(&|ud| ud.entity = id);
                         }
                         if let Some(actor) = actor.to_rigid_dynamic() {
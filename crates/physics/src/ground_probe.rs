@@ -0,0 +1,68 @@
+use ambient_core::{hierarchy::parent, transform::get_world_position};
+use ambient_ecs::{
+    generated::components::core::animation::{
+        ik_ground_probe, ik_ground_probe_range, ik_target_position,
+    },
+    query, EntityId, SystemGroup, World,
+};
+use glam::Vec3;
+
+use crate::projectile::first_hit;
+
+/// How far above and below the probe the ground is looked for if it doesn't have an `ik_ground_probe_range`
+const DEFAULT_RANGE: f32 = 0.5;
+/// How far the ground has to move before the `ik_target_position` is updated, so that it isn't sent to the clients on
+/// every frame that the probe moves a little
+const MIN_CHANGE: f32 = 0.005;
+
+/// Casts a ray down through every entity with an `ik_ground_probe`, and sets its `ik_target_position` to the ground
+/// below. The clients solve the IK of the joints that target these entities.
+pub fn server_systems() -> SystemGroup {
+    SystemGroup::new(
+        "ground_probe",
+        vec![query(ik_ground_probe()).to_system(|q, world, qs, _| {
+            for (id, height) in q.collect_cloned(world, qs) {
+                let Ok(position) = get_world_position(world, id) else {
+                    continue;
+                };
+                let range = world
+                    .get(id, ik_ground_probe_range())
+                    .unwrap_or(DEFAULT_RANGE);
+                // The character's own colliders are above the ground, so they are skipped
+                let ignored = ancestors(world, id);
+                match first_hit(
+                    world,
+                    position + Vec3::Z * range,
+                    position - Vec3::Z * range,
+                    None,
+                    &ignored,
+                ) {
+                    Some(hit) => {
+                        let target = hit.position + Vec3::Z * height;
+                        match world.get(id, ik_target_position()) {
+                            Ok(old) if old.distance(target) < MIN_CHANGE => {}
+                            Ok(_) => world.set(id, ik_target_position(), target).unwrap(),
+                            Err(_) => world
+                                .add_component(id, ik_target_position(), target)
+                                .unwrap(),
+                        }
+                    }
+                    None => {
+                        if world.has_component(id, ik_target_position()) {
+                            world.remove_component(id, ik_target_position()).unwrap();
+                        }
+                    }
+                }
+            }
+        })],
+    )
+}
+
+fn ancestors(world: &World, mut id: EntityId) -> Vec<EntityId> {
+    let mut res = vec![id];
+    while let Ok(parent) = world.get(id, parent()) {
+        res.push(parent);
+        id = parent;
+    }
+    res
+}
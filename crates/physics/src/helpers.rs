@@ -13,12 +13,16 @@ use physxx::{
 };
 
 use crate::{
+    ccd, ccd_min_advance, ccd_speculative,
     collider::{collider_shapes_convex, collider_type, kinematic},
-    main_physics_scene,
+    main_physics_scene, max_depenetration_velocity,
     physx::{physics, physics_controlled, physics_shape, revolute_joint, rigid_actor, rigid_dynamic, rigid_static},
-    unit_mass, unit_velocity, ColliderScene, PxActorUserData, PxShapeUserData,
+    unit_mass, unit_velocity, ColliderScene, PxActorUserData, PxShapeUserData, FILTER_NO_CCD,
 };
 
+/// The `ccd_min_advance` of bodies that don't set it, which is PhysX's default
+const DEFAULT_CCD_MIN_ADVANCE: f32 = 0.15;
+
 pub fn convert_rigid_static_to_dynamic(world: &mut World, id: EntityId) {
     convert_rigid_static_dynamic(world, id, true);
 }
@@ -46,7 +50,7 @@ pub fn convert_rigid_static_dynamic(world: &mut World, id: EntityId, to_dynamic:
     let new_actor = if to_dynamic {
         let actor = PxRigidDynamicRef::new(physics.physics, &old_actor.get_global_pose());
         actor.set_rigid_body_flag(PxRigidBodyFlag::KINEMATIC, is_kinematic);
-        actor.set_rigid_body_flag(PxRigidBodyFlag::ENABLE_CCD, !is_kinematic);
+        update_ccd(world, id, &actor);
         actor.as_rigid_actor()
     } else {
         PxRigidStaticRef::new(physics.physics, &old_actor.get_global_pose()).as_rigid_actor()
@@ -135,6 +139,28 @@ pub fn get_shapes(world: &World, id: EntityId) -> impl Iterator<Item = PxShape>
         .chain(world.get_ref(id, collider_shapes_convex()).into_iter().flatten().cloned())
 }
 
+/// Applies the `ccd`, `ccd_speculative`, `ccd_min_advance` and `max_depenetration_velocity` of `id` to its `body`,
+/// which must already be kinematic if it is going to be
+pub fn update_ccd(world: &World, id: EntityId, body: &impl PxRigidBody) {
+    // PhysX doesn't sweep kinematic bodies
+    let is_kinematic = body.get_rigid_body_flags().contains(PxRigidBodyFlag::KINEMATIC);
+    body.set_rigid_body_flag(PxRigidBodyFlag::ENABLE_CCD, !is_kinematic && world.get(id, ccd()).unwrap_or(true));
+    body.set_rigid_body_flag(PxRigidBodyFlag::ENABLE_SPECULATIVE_CCD, world.get(id, ccd_speculative()).unwrap_or(false));
+    body.set_min_ccd_advance_coefficient(world.get(id, ccd_min_advance()).unwrap_or(DEFAULT_CCD_MIN_ADVANCE).clamp(0., 1.));
+    body.set_max_depenetration_velocity(world.get(id, max_depenetration_velocity()).unwrap_or(f32::MAX).max(0.));
+}
+
+/// Sets whether the swept CCD of other bodies tests `shape`, through its simulation filter data
+pub fn set_shape_ccd(shape: &PxShape, enabled: bool) {
+    let mut data = shape.get_simulation_filter_data();
+    if enabled {
+        data[3] &= !FILTER_NO_CCD;
+    } else {
+        data[3] |= FILTER_NO_CCD;
+    }
+    shape.set_simulation_filter_data(data);
+}
+
 pub fn scale_shape(shape: PxShape, scale: Vec3) {
     tracing::debug!("Scaling shape");
     let geo = shape.get_geometry();
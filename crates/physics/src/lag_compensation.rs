@@ -0,0 +1,266 @@
+use std::{collections::VecDeque, time::Duration};
+
+use ambient_core::time;
+use ambient_ecs::{components, query, EntityId, FnSystem, Resource, SystemGroup, World};
+use ambient_std::shapes::Ray;
+use glam::{Quat, Vec3};
+use itertools::Itertools;
+use ordered_float::OrderedFloat;
+use physxx::{AsPxRigidActor, PxHitFlags, PxRigidActor, PxRigidActorRef, PxShape, PxTransform};
+
+use crate::{
+    intersection, lag_compensated,
+    physx::{character_controller, physics_shape},
+    raycast_rewound_direction, raycast_rewound_hit_distances, raycast_rewound_hit_entities,
+    raycast_rewound_latency, raycast_rewound_origin, PxShapeUserData,
+};
+
+/// How far back hitboxes can be rewound
+pub const MAX_REWIND: Duration = Duration::from_secs(1);
+
+components!("physics", {
+    @[Resource]
+    lag_compensation_history: LagCompensationHistory,
+});
+
+/// The world space pose of one of an entity's shapes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HitboxPose {
+    pub translation: Vec3,
+    pub rotation: Quat,
+}
+impl HitboxPose {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            translation: self.translation.lerp(other.translation, t),
+            rotation: self.rotation.slerp(other.rotation, t),
+        }
+    }
+}
+
+/// The poses of the shapes of each entity
+pub type Hitboxes = Vec<(EntityId, Vec<HitboxPose>)>;
+
+/// The poses of the shapes of all `lag_compensated` entities over the last [MAX_REWIND]
+#[derive(Debug, Clone, Default)]
+pub struct LagCompensationHistory {
+    frames: VecDeque<(Duration, Hitboxes)>,
+}
+impl LagCompensationHistory {
+    pub fn record(&mut self, time: Duration, poses: Hitboxes) {
+        // Frames can't be recorded out of order; this only happens if the time was reset
+        if matches!(self.frames.back(), Some((last, _)) if *last >= time) {
+            self.frames.clear();
+        }
+        self.frames.push_back((time, poses));
+        // Keep one frame older than the maximum rewind, so that it can be interpolated towards
+        while self.frames.len() > 2 && time.saturating_sub(self.frames[1].0) >= MAX_REWIND {
+            self.frames.pop_front();
+        }
+    }
+
+    /// The poses at `time`, interpolated between the recorded frames. Entities that were not recorded in both of the
+    /// surrounding frames are left out, as are those whose number of shapes changed.
+    pub fn sample(&self, time: Duration) -> Hitboxes {
+        let next = self.frames.iter().position(|(t, _)| *t >= time);
+        let (from, to, t) = match next {
+            None => match self.frames.back() {
+                Some(last) => (last, last, 0.),
+                None => return Vec::new(),
+            },
+            Some(0) => (&self.frames[0], &self.frames[0], 0.),
+            Some(i) => {
+                let (from, to) = (&self.frames[i - 1], &self.frames[i]);
+                let t = (time - from.0).as_secs_f32() / (to.0 - from.0).as_secs_f32();
+                (from, to, t)
+            }
+        };
+        from.1
+            .iter()
+            .filter_map(|(id, from_poses)| {
+                let (_, to_poses) = to.1.iter().find(|(to_id, _)| to_id == id)?;
+                (from_poses.len() == to_poses.len()).then(|| {
+                    let poses = from_poses
+                        .iter()
+                        .zip(to_poses)
+                        .map(|(a, b)| a.lerp(b, t))
+                        .collect();
+                    (*id, poses)
+                })
+            })
+            .collect()
+    }
+}
+
+/// The shapes of an entity that are used as its hitboxes, along with the actors they are attached to
+fn hitbox_shapes(world: &World, id: EntityId) -> Vec<(PxShape, PxRigidActorRef)> {
+    if let Some(actor) = world
+        .get_ref(id, physics_shape())
+        .ok()
+        .and_then(|shape| shape.get_actor())
+    {
+        actor
+            .borrow_shapes()
+            .into_iter()
+            .filter(|shape| {
+                matches!(shape.get_user_data::<PxShapeUserData>(), Some(ud) if ud.entity == id)
+            })
+            .map(|shape| (shape, actor))
+            .collect()
+    } else if let Ok(controller) = world.get_ref(id, character_controller()) {
+        let actor = controller.get_actor().as_rigid_actor();
+        actor
+            .borrow_shapes()
+            .into_iter()
+            .map(|shape| (shape, actor))
+            .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Casts a ray against the world, with the `lag_compensated` entities moved back to where they were `latency`
+/// seconds ago. Returns the entities that were hit and their distances, sorted from nearest to furthest.
+pub fn raycast_rewound(world: &World, ray: Ray, latency: f32) -> Vec<(EntityId, f32)> {
+    let rewind = Duration::from_secs_f32(latency.max(0.)).min(MAX_REWIND);
+    let rewound = world
+        .resource(lag_compensation_history())
+        .sample(world.resource(time()).saturating_sub(rewind));
+
+    let mut hits = intersection::raycast(world, ray)
+        .into_iter()
+        .filter(|(id, _)| rewound.iter().all(|(rewound_id, _)| rewound_id != id))
+        .collect_vec();
+    for (id, poses) in &rewound {
+        let shapes = hitbox_shapes(world, *id);
+        if shapes.len() != poses.len() {
+            continue;
+        }
+        let distance = shapes
+            .iter()
+            .zip(poses)
+            .flat_map(|((shape, _), pose)| {
+                physxx::raycast(
+                    ray.origin,
+                    ray.dir,
+                    &shape.get_geometry(),
+                    &PxTransform::new(pose.translation, pose.rotation),
+                    f32::MAX,
+                    PxHitFlags::DEFAULT,
+                    1,
+                )
+            })
+            .map(|hit| OrderedFloat(hit.distance))
+            .min();
+        if let Some(distance) = distance {
+            hits.push((*id, distance.0));
+        }
+    }
+    hits.sort_by_key(|(_, distance)| OrderedFloat(*distance));
+    hits
+}
+
+/// Records the hitboxes of `lag_compensated` entities, and answers the `raycast_rewound_origin` requests. Run this
+/// after the guest systems, so that requests made this frame are answered with this frame's history.
+pub fn systems() -> SystemGroup {
+    SystemGroup::new(
+        "lag_compensation",
+        vec![
+            Box::new(FnSystem::new(|world, _| {
+                ambient_profiling::scope!("lag_compensation_record");
+                let poses = query(())
+                    .incl(lag_compensated())
+                    .iter(world, None)
+                    .map(|(id, _)| {
+                        let poses = hitbox_shapes(world, id)
+                            .into_iter()
+                            .map(|(shape, actor)| {
+                                let pose = shape.get_global_pose(actor);
+                                HitboxPose {
+                                    translation: pose.translation(),
+                                    rotation: pose.rotation(),
+                                }
+                            })
+                            .collect_vec();
+                        (id, poses)
+                    })
+                    .filter(|(_, poses)| !poses.is_empty())
+                    .collect_vec();
+                let time = *world.resource(time());
+                world
+                    .resource_mut(lag_compensation_history())
+                    .record(time, poses);
+            })),
+            query((raycast_rewound_origin(), raycast_rewound_direction()))
+                .excl(raycast_rewound_hit_distances())
+                .to_system(|q, world, qs, _| {
+                    for (id, (origin, direction)) in q.collect_cloned(world, qs) {
+                        let latency = world.get(id, raycast_rewound_latency()).unwrap_or(0.);
+                        let (entities, distances) =
+                            raycast_rewound(world, Ray::new(origin, direction), latency)
+                                .into_iter()
+                                .unzip();
+                        world
+                            .add_component(id, raycast_rewound_hit_entities(), entities)
+                            .unwrap();
+                        world
+                            .add_component(id, raycast_rewound_hit_distances(), distances)
+                            .unwrap();
+                    }
+                }),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pose(x: f32) -> HitboxPose {
+        HitboxPose {
+            translation: Vec3::new(x, 0., 0.),
+            rotation: Quat::IDENTITY,
+        }
+    }
+
+    #[test]
+    fn sample() {
+        let id = EntityId::new();
+        let mut history = LagCompensationHistory::default();
+        assert!(history.sample(Duration::ZERO).is_empty());
+
+        history.record(Duration::from_millis(100), vec![(id, vec![pose(0.)])]);
+        history.record(Duration::from_millis(200), vec![(id, vec![pose(10.)])]);
+        assert_eq!(
+            history.sample(Duration::from_millis(150)),
+            vec![(id, vec![pose(5.)])]
+        );
+        // Times outside of the history are clamped to it
+        assert_eq!(history.sample(Duration::ZERO), vec![(id, vec![pose(0.)])]);
+        assert_eq!(
+            history.sample(Duration::from_secs(1)),
+            vec![(id, vec![pose(10.)])]
+        );
+
+        // Entities that appear or change their shapes are not interpolated
+        history.record(Duration::from_millis(300), vec![]);
+        assert!(history.sample(Duration::from_millis(250)).is_empty());
+    }
+
+    #[test]
+    fn prune() {
+        let id = EntityId::new();
+        let mut history = LagCompensationHistory::default();
+        for i in 0..30 {
+            history.record(
+                Duration::from_millis(i * 100),
+                vec![(id, vec![pose(i as f32)])],
+            );
+        }
+        assert_eq!(history.frames.len(), 11);
+        assert_eq!(
+            history.sample(Duration::from_millis(2900) - MAX_REWIND),
+            vec![(id, vec![pose(19.)])]
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use ambient_core::asset_cache;
+use ambient_core::{asset_cache, dtime, hierarchy::parent};
 use ambient_ecs::{
     components, query, Debuggable, DynSystem, Entity, EntityId, FnSystem, Resource, SystemGroup,
     World,
@@ -24,12 +24,17 @@ use serde::{Deserialize, Serialize};
 
 use crate::physx::PhysicsKey;
 
+pub mod avoidance;
 pub mod collider;
+pub mod ground_probe;
 pub mod helpers;
 pub mod intersection;
+pub mod lag_compensation;
 pub mod mesh;
 pub mod physx;
+pub mod projectile;
 pub mod rc_asset;
+pub mod scene_query;
 pub mod visualization;
 
 pub use ambient_ecs::generated::components::core::physics::*;
@@ -52,10 +57,18 @@ pub fn init_all_components() {
     init_components();
     physx::init_components();
     collider::init_components();
+    lag_compensation::init_components();
+    projectile::init_components();
     visualization::init_components();
 }
 
 pub const GRAVITY: f32 = 9.82;
+/// The longest step that the scene is simulated in. Instances that tick slower than this, like idle ones, are simulated
+/// in several steps, as long steps let bodies pass through each other and make joints unstable.
+const MAX_STEP: f32 = 1. / 30.;
+/// The most steps a tick is simulated in, so that a slow tick can't stall the server. The time beyond these steps is
+/// dropped, which slows down the simulation of instances that tick very slowly.
+const MAX_STEPS: u32 = 8;
 pub fn create_server_resources(assets: &AssetCache, server_resources: &mut Entity) {
     let physics = PhysicsKey.get(assets);
     server_resources.set(crate::physx::physics(), physics.clone());
@@ -64,6 +77,10 @@ pub fn create_server_resources(assets: &AssetCache, server_resources: &mut Entit
     main_scene_desc.set_cpu_dispatcher(&physics.dispatcher);
     main_scene_desc.set_gravity(vec3(0., 0., -GRAVITY));
     main_scene_desc.update_flags(|flags| flags | PxSceneFlags::ENABLE_CCD);
+    if ambient_ecs::is_deterministic() {
+        // Otherwise the results depend on the order the actors were added in, and on how the work was split up
+        main_scene_desc.update_flags(|flags| flags | PxSceneFlags::ENABLE_ENHANCED_DETERMINISM);
+    }
     main_scene_desc.set_filter_shader(main_physx_scene_filter_shader, true);
     let collisions = Arc::new(Mutex::new(Vec::new()));
     {
@@ -91,6 +108,13 @@ pub fn create_server_resources(assets: &AssetCache, server_resources: &mut Entit
     let main_scene = PxSceneRef::new(&physics.physics, &main_scene_desc);
     server_resources.set(self::collisions(), collisions);
     server_resources.set(self::collider_loads(), vec![]);
+    server_resources.set(physx::fallen_asleep(), vec![]);
+    server_resources.set(physx::woken_up(), vec![]);
+    server_resources.set(
+        lag_compensation::lag_compensation_history(),
+        Default::default(),
+    );
+    server_resources.set(projectile::projectile_impacts(), vec![]);
 
     main_scene.get_scene_pvd_client().set_scene_pvd_flags(
         PxPvdSceneFlag::TRANSMIT_CONSTRAINTS
@@ -181,14 +205,20 @@ impl SyncAssetKey<PxMaterial> for PxWoodMaterialKey {
     }
 }
 
+/// Set in the last word of the simulation filter data of the shapes that the swept CCD of other bodies doesn't test
+pub(crate) const FILTER_NO_CCD: u32 = 1;
+
 unsafe extern "C" fn main_physx_scene_filter_shader(
     mut info: *mut physxx::sys::FilterShaderCallbackInfo,
 ) -> u16 {
-    (*(*info).pairFlags).mBits |= (physxx::sys::PxPairFlag::eSOLVE_CONTACT
+    let mut flags = physxx::sys::PxPairFlag::eSOLVE_CONTACT
         | physxx::sys::PxPairFlag::eDETECT_DISCRETE_CONTACT
-        | physxx::sys::PxPairFlag::eDETECT_CCD_CONTACT
         | physxx::sys::PxPairFlag::eCONTACT_DEFAULT
-        | physxx::sys::PxPairFlag::eNOTIFY_TOUCH_FOUND) as u16;
+        | physxx::sys::PxPairFlag::eNOTIFY_TOUCH_FOUND;
+    if ((*info).filterData0.word3 | (*info).filterData1.word3) & FILTER_NO_CCD == 0 {
+        flags |= physxx::sys::PxPairFlag::eDETECT_CCD_CONTACT;
+    }
+    (*(*info).pairFlags).mBits |= flags as u16;
     (physxx::sys::PxFilterFlag::eDEFAULT) as u16
 }
 
@@ -223,13 +253,22 @@ pub fn server_systems() -> SystemGroup {
                     }
                 }),
             Box::new(collider::server_systems()),
+            Box::new(ground_probe::server_systems()),
+            Box::new(avoidance::systems()),
+            Box::new(projectile::server_systems()),
             Box::new(visualization::server_systems()),
         ],
     )
 }
 
 pub fn client_systems() -> SystemGroup {
-    SystemGroup::new("physics", vec![Box::new(visualization::client_systems())])
+    SystemGroup::new(
+        "physics",
+        vec![
+            Box::new(projectile::client_systems()),
+            Box::new(visualization::client_systems()),
+        ],
+    )
 }
 
 /// Starts the physx simulation step concurrently.
@@ -238,11 +277,25 @@ pub fn client_systems() -> SystemGroup {
 pub fn run_simulation_system() -> DynSystem {
     Box::new(FnSystem::new(|world, _| {
         ambient_profiling::scope!("run_simulation_system");
+        // The collisions of all of the steps of this tick are reported after `fetch_simulation_system`
+        world.resource(collisions()).lock().clear();
         let scene = world.resource(main_physics_scene());
-        scene.simulate(1. / 60.);
+        // Instances can tick at different rates, so the time to simulate is the time since their last tick
+        let (steps, step) = substeps(*world.resource(dtime()));
+        for _ in 1..steps {
+            scene.simulate(step);
+            scene.fetch_results(true);
+        }
+        scene.simulate(step);
     }))
 }
 
+/// The number of steps to simulate `dtime` in, and how long each of them is
+fn substeps(dtime: f32) -> (u32, f32) {
+    let steps = (dtime / MAX_STEP).ceil().clamp(1., MAX_STEPS as f32) as u32;
+    (steps, dtime.min(MAX_STEP * MAX_STEPS as f32) / steps as f32)
+}
+
 /// Ensures the physx simulation data is available.
 ///
 /// Must only be called once per [`run_simulation_system`]
@@ -250,8 +303,9 @@ pub fn fetch_simulation_system() -> DynSystem {
     Box::new(FnSystem::new(|world, _| {
         ambient_profiling::scope!("fetch_simulation_system");
 
-        world.resource(collisions()).lock().clear();
         world.resource_mut(collider_loads()).clear();
+        world.resource_mut(physx::fallen_asleep()).clear();
+        world.resource_mut(physx::woken_up()).clear();
         let scene = world.resource(main_physics_scene());
         // Ensure the previous simulation has completed
         scene.fetch_results(true);
@@ -344,3 +398,48 @@ pub fn on_shutdown_systems() -> SystemGroup<ShutdownEvent> {
         }))],
     )
 }
+
+/// The `surface_material` of `id`, or of the nearest of its ancestors that has one, as models usually put their
+/// colliders on child entities.
+pub fn surface_material_of(world: &World, id: EntityId) -> Option<String> {
+    let mut current = Some(id);
+    while let Some(id) = current {
+        if let Ok(material) = world.get_ref(id, surface_material()) {
+            return Some(material.clone());
+        }
+        current = world.get(id, parent()).ok();
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn slow_ticks_are_substepped() {
+        assert_eq!(substeps(1. / 60.), (1, 1. / 60.));
+        assert_eq!(substeps(MAX_STEP), (1, MAX_STEP));
+
+        let (steps, step) = substeps(0.09);
+        assert_eq!(steps, 3);
+        assert!((step - 0.03).abs() < 1e-6);
+
+        // Idle instances ticking once a second only simulate up to the longest tick
+        assert_eq!(substeps(1.), (MAX_STEPS, MAX_STEP));
+    }
+
+    #[test]
+    fn children_use_the_material_of_their_parent() {
+        ambient_core::init_all_components();
+        init_all_components();
+        let mut world = World::new("children_use_the_material_of_their_parent");
+        let root = Entity::new()
+            .with(surface_material(), "wood".to_string())
+            .spawn(&mut world);
+        let child = Entity::new().with(parent(), root).spawn(&mut world);
+        let unset = world.spawn(Entity::new());
+        assert_eq!(surface_material_of(&world, child), Some("wood".to_string()));
+        assert_eq!(surface_material_of(&world, unset), None);
+    }
+}
@@ -4,13 +4,13 @@ use ambient_core::{
     dtime,
     transform::{rotation, scale, translation},
 };
-use ambient_ecs::{components, ensure_has_component, query, FnSystem, QueryState, Resource, SystemGroup};
+use ambient_ecs::{components, ensure_has_component, query, EntityId, FnSystem, QueryState, Resource, SystemGroup};
 use ambient_std::asset_cache::SyncAssetKey;
 use glam::{EulerRot, Quat, Vec3};
 use parking_lot::Mutex;
 use physxx::{articulation_reduced_coordinate::*, *};
 
-use crate::helpers::{get_shapes, scale_shape};
+use crate::helpers::{get_shapes, scale_shape, set_shape_ccd, update_ccd};
 
 pub use ambient_ecs::generated::components::core::physics::*;
 
@@ -28,8 +28,17 @@ components!("physics", {
     articulation_link: PxArticulationLinkRef,
     articulation_cache: Option<PxArticulationCacheRef>,
     character_controller: PxControllerRef,
+    /// The bodies that fell asleep during the frame, which are sent to the modules as a `PhysicsSleep` message
+    @[Resource]
+    fallen_asleep: Vec<EntityId>,
+    /// The bodies that woke up during the frame, which are sent to the modules as a `PhysicsWake` message
+    @[Resource]
+    woken_up: Vec<EntityId>,
 });
 
+/// The `sleep_threshold` of bodies that don't set it, which is PhysX's default
+const DEFAULT_SLEEP_THRESHOLD: f32 = 0.005;
+
 #[derive(Debug)]
 pub struct PhysicsKey;
 impl SyncAssetKey<Physics> for PhysicsKey {
@@ -112,6 +121,10 @@ pub fn sync_ecs_physics() -> SystemGroup {
     let angular_velocity_q = query(angular_velocity().changed()).incl(physics_controlled());
     let angular_velocity_q2 = scale_q.query.clone();
 
+    let sleeping_qs = Arc::new(Mutex::new(QueryState::new()));
+    let sleeping_q = query(sleeping().changed()).incl(rigid_dynamic());
+    let sleeping_q2 = sleeping_q.query.clone();
+
     SystemGroup::new(
         "sync_ecs_physics",
         vec![
@@ -131,6 +144,7 @@ pub fn sync_ecs_physics() -> SystemGroup {
             }),
             ensure_has_component(rigid_dynamic(), linear_velocity(), Vec3::default()),
             ensure_has_component(rigid_dynamic(), angular_velocity(), Vec3::default()),
+            ensure_has_component(rigid_dynamic(), sleeping(), false),
             // Sync ECS changes to PhysX.
             translation_rotation_q.to_system({
                 let translation_rotation_qs = translation_rotation_qs.clone();
@@ -248,6 +262,49 @@ pub fn sync_ecs_physics() -> SystemGroup {
                     }
                 }
             }),
+            query(rigid_dynamic())
+                .optional_changed(ccd())
+                .optional_changed(ccd_speculative())
+                .optional_changed(ccd_min_advance())
+                .optional_changed(max_depenetration_velocity())
+                .to_system(|q, world, qs, _| {
+                    for (id, body) in q.iter(world, qs) {
+                        update_ccd(world, id, body);
+                    }
+                }),
+            query(ccd_shapes().changed()).to_system(|q, world, qs, _| {
+                for (id, &enabled) in q.iter(world, qs) {
+                    let controller_shapes = world.get_ref(id, character_controller()).into_iter().flat_map(|x| x.get_actor().get_shapes());
+                    for shape in get_shapes(world, id).chain(controller_shapes) {
+                        set_shape_ccd(&shape, enabled);
+                    }
+                }
+            }),
+            query(rigid_dynamic()).optional_changed(sleep_threshold()).to_system(|q, world, qs, _| {
+                for (id, body) in q.iter(world, qs) {
+                    body.set_sleep_threshold(world.get(id, sleep_threshold()).unwrap_or(DEFAULT_SLEEP_THRESHOLD).max(0.));
+                }
+            }),
+            sleeping_q.to_system({
+                let sleeping_qs = sleeping_qs.clone();
+                move |q, world, _, _| {
+                    let mut qs = sleeping_qs.lock();
+                    for (id, sleep) in q.collect_cloned(world, Some(&mut *qs)) {
+                        let body = world.get(id, rigid_dynamic()).unwrap();
+                        // Kinematic bodies are moved by their targets, so they can't be put to sleep
+                        if sleep == body.is_sleeping() || world.has_component(id, kinematic()) {
+                            continue;
+                        }
+                        if sleep {
+                            body.put_to_sleep();
+                            world.resource_mut(fallen_asleep()).push(id);
+                        } else {
+                            body.wake_up();
+                            world.resource_mut(woken_up()).push(id);
+                        }
+                    }
+                }
+            }),
             // Sync PhysX changes to ECS.
             query((rigid_dynamic(), translation(), rotation())).incl(physics_controlled()).to_system(|q, world, qs, _| {
                 for (id, (rigid_dynamic, pos, rot)) in q.collect_cloned(world, qs) {
@@ -337,6 +394,28 @@ pub fn sync_ecs_physics() -> SystemGroup {
                     }
                 }
             }),
+            // Sync the sleep state. Sleeping bodies don't move, so nothing of them is sent to the clients until they wake
+            // up; when one falls asleep, it gets the exact pose and velocity it came to rest with, as the changes below
+            // the thresholds above aren't synced.
+            query(rigid_dynamic()).incl(physics_controlled()).excl(kinematic()).to_system(|q, world, qs, _| {
+                for (id, body) in q.collect_cloned(world, qs) {
+                    let asleep = body.is_sleeping();
+                    if world.get(id, sleeping()).unwrap_or(false) == asleep {
+                        continue;
+                    }
+                    if asleep {
+                        let pose = body.get_global_pose();
+                        world.set_if_changed(id, translation(), pose.translation()).unwrap();
+                        world.set_if_changed(id, rotation(), pose.rotation()).unwrap();
+                        world.set_if_changed(id, linear_velocity(), Vec3::ZERO).ok();
+                        world.set_if_changed(id, angular_velocity(), Vec3::ZERO).ok();
+                        world.resource_mut(fallen_asleep()).push(id);
+                    } else {
+                        world.resource_mut(woken_up()).push(id);
+                    }
+                    world.add_component(id, sleeping(), asleep).unwrap();
+                }
+            }),
             Box::new(FnSystem::new(move |world, _| {
                 // Fast forward queries
                 let mut translation_rotation_qs = translation_rotation_qs.lock();
@@ -349,6 +428,8 @@ pub fn sync_ecs_physics() -> SystemGroup {
                 for _ in linear_velocity_q2.iter(world, Some(&mut *linear_velocity_qs)) {}
                 let mut angular_velocity_qs = angular_velocity_qs.lock();
                 for _ in angular_velocity_q2.iter(world, Some(&mut *angular_velocity_qs)) {}
+                let mut sleeping_qs = sleeping_qs.lock();
+                for _ in sleeping_q2.iter(world, Some(&mut *sleeping_qs)) {}
             })),
         ],
     )
@@ -0,0 +1,279 @@
+use ambient_core::{dtime, transform::translation};
+use ambient_ecs::{
+    components,
+    generated::{
+        components::core::projectile::{
+            projectile_drag, projectile_gravity, projectile_inactive, projectile_lifetime,
+            projectile_owner, projectile_pool, projectile_radius, projectile_velocity,
+        },
+        messages::ProjectileImpact,
+    },
+    query, EntityId, FnSystem, Resource, SystemGroup, World,
+};
+use ambient_network::client::client_network_stats;
+use ambient_renderer::lod::cpu_lod_visible;
+use glam::Vec3;
+use ordered_float::OrderedFloat;
+use physxx::{
+    PxQueryFilterData, PxQueryFlag, PxRaycastCallback, PxSphereGeometry, PxTransform, PxUserData,
+};
+
+use crate::{main_physics_scene, surface_material_of, PxShapeUserData, GRAVITY};
+
+/// The number of seconds a projectile flies for if it doesn't have a `projectile_lifetime`
+const DEFAULT_LIFETIME: f32 = 10.;
+/// The step used when predicting projectiles ahead by more than a frame, to match the server's simulation
+const PREDICTION_STEP: f32 = 1. / 60.;
+/// The most colliders a cast looks through for one that isn't ignored
+const MAX_TOUCHES: usize = 64;
+
+components!("physics", {
+    /// How long the projectile has been flying for, in seconds
+    projectile_age: f32,
+    /// The translation and velocity the client last predicted for a projectile; if the translation no longer matches,
+    /// the server has sent an update
+    projectile_prediction: (Vec3, Vec3),
+    /// The impacts of the current frame, which are sent to the modules as `ProjectileImpact` messages
+    @[Resource]
+    projectile_impacts: Vec<ProjectileImpact>,
+});
+
+/// Advances a projectile by `dt` seconds. The server and the clients both use this, so that the clients' predictions
+/// match the server's simulation.
+pub fn ballistic_step(
+    position: Vec3,
+    velocity: Vec3,
+    gravity: f32,
+    drag: f32,
+    dt: f32,
+) -> (Vec3, Vec3) {
+    let velocity = (velocity - Vec3::Z * gravity * dt) * (1. - drag * dt).max(0.);
+    (position + velocity * dt, velocity)
+}
+
+/// Advances a projectile by `duration` seconds, in steps of at most [PREDICTION_STEP]
+fn ballistic_predict(
+    mut position: Vec3,
+    mut velocity: Vec3,
+    gravity: f32,
+    drag: f32,
+    mut duration: f32,
+) -> (Vec3, Vec3) {
+    while duration > 0. {
+        let dt = duration.min(PREDICTION_STEP);
+        (position, velocity) = ballistic_step(position, velocity, gravity, drag, dt);
+        duration -= dt;
+    }
+    (position, velocity)
+}
+
+fn ballistics(world: &World, id: EntityId) -> (f32, f32) {
+    (
+        world.get(id, projectile_gravity()).unwrap_or(GRAVITY),
+        world.get(id, projectile_drag()).unwrap_or(0.),
+    )
+}
+
+pub(crate) struct Hit {
+    pub(crate) entity: EntityId,
+    pub(crate) position: Vec3,
+    pub(crate) normal: Vec3,
+}
+
+/// Finds the first collider in the physics scene between `from` and `to`, skipping the colliders of the `ignored`
+/// entities. If a `radius` is given, a sphere of that radius is swept instead of casting a ray.
+pub(crate) fn first_hit(
+    world: &World,
+    from: Vec3,
+    to: Vec3,
+    radius: Option<f32>,
+    ignored: &[EntityId],
+) -> Option<Hit> {
+    let scene = world.resource(main_physics_scene());
+    let length = from.distance(to);
+    if length <= 0. {
+        return None;
+    }
+    let dir = (to - from) / length;
+    let mut filter_data = PxQueryFilterData::new();
+    // Every hit is reported as a touch, so that the ignored entities can be skipped
+    filter_data.set_flags(PxQueryFlag::STATIC | PxQueryFlag::DYNAMIC | PxQueryFlag::NO_BLOCK);
+    let hits: Vec<_> = match radius {
+        Some(radius) => scene
+            .sweep(
+                &PxSphereGeometry::new(radius),
+                &PxTransform::from_translation(from),
+                dir,
+                length,
+                filter_data,
+            )
+            .touches()
+            .into_iter()
+            .map(|hit| (hit.shape, hit.position, hit.normal, hit.distance))
+            .collect(),
+        None => {
+            let mut hits = PxRaycastCallback::new(MAX_TOUCHES);
+            scene.raycast(from, dir, length, &mut hits, None, &filter_data);
+            hits.touches()
+                .into_iter()
+                .map(|hit| (hit.shape, hit.position, hit.normal, hit.distance))
+                .collect()
+        }
+    };
+    hits.into_iter()
+        .filter_map(|(shape, position, normal, distance)| {
+            let entity = shape?.get_user_data::<PxShapeUserData>()?.entity;
+            Some((
+                Hit {
+                    entity,
+                    position,
+                    normal,
+                },
+                distance,
+            ))
+        })
+        .filter(|(hit, _)| !ignored.contains(&hit.entity))
+        .min_by_key(|(_, distance)| OrderedFloat(*distance))
+        .map(|(hit, _)| hit)
+}
+
+/// Takes a projectile out of play; pooled projectiles are hidden until they are reused, and others are despawned
+fn retire(world: &mut World, id: EntityId, position: Vec3) {
+    if world.has_component(id, projectile_pool()) {
+        world.set(id, translation(), position).ok();
+        world.remove_component(id, projectile_velocity()).ok();
+        world.remove_component(id, projectile_age()).ok();
+        world.add_component(id, projectile_inactive(), ()).ok();
+        world.add_component(id, cpu_lod_visible(), false).ok();
+    } else {
+        world.despawn(id);
+    }
+}
+
+pub fn server_systems() -> SystemGroup {
+    SystemGroup::new(
+        "projectile",
+        vec![
+            // New projectiles, and pooled ones that are being reused
+            query(())
+                .incl(projectile_velocity())
+                .excl(projectile_age())
+                .to_system(|q, world, qs, _| {
+                    for (id, _) in q.collect_cloned(world, qs) {
+                        world.add_component(id, projectile_age(), 0.).unwrap();
+                        world.remove_component(id, projectile_inactive()).ok();
+                        if world.has_component(id, cpu_lod_visible()) {
+                            world.set(id, cpu_lod_visible(), true).unwrap();
+                        }
+                    }
+                }),
+            Box::new(FnSystem::new(|world, _| {
+                ambient_profiling::scope!("projectile_simulation");
+                world.resource_mut(projectile_impacts()).clear();
+                let dt = *world.resource(dtime());
+                let projectiles = query((translation(), projectile_velocity(), projectile_age()))
+                    .collect_cloned(world, None);
+                for (id, (position, velocity, age)) in projectiles {
+                    let (gravity, drag) = ballistics(world, id);
+                    let (next_position, next_velocity) =
+                        ballistic_step(position, velocity, gravity, drag, dt);
+
+                    let mut ignored = vec![id];
+                    ignored.extend(world.get(id, projectile_owner()));
+                    let radius = world.get(id, projectile_radius()).ok();
+                    if let Some(hit) = first_hit(world, position, next_position, radius, &ignored) {
+                        let material = surface_material_of(world, hit.entity).unwrap_or_default();
+                        world
+                            .resource_mut(projectile_impacts())
+                            .push(ProjectileImpact::new(
+                                hit.entity,
+                                hit.normal,
+                                hit.position,
+                                id,
+                                material,
+                            ));
+                        retire(world, id, hit.position);
+                        continue;
+                    }
+
+                    let lifetime = world
+                        .get(id, projectile_lifetime())
+                        .unwrap_or(DEFAULT_LIFETIME);
+                    if age + dt >= lifetime {
+                        retire(world, id, next_position);
+                        continue;
+                    }
+                    world.set(id, translation(), next_position).unwrap();
+                    world.set(id, projectile_velocity(), next_velocity).unwrap();
+                    world.set(id, projectile_age(), age + dt).unwrap();
+                }
+            })),
+        ],
+    )
+}
+
+/// Moves the projectiles in between updates from the server. When the server sends a new position, the projectile is
+/// moved ahead of it by half the round-trip time, which is roughly where the server has it by the time it is shown.
+pub fn client_systems() -> SystemGroup {
+    SystemGroup::new(
+        "projectile",
+        vec![Box::new(FnSystem::new(|world, _| {
+            ambient_profiling::scope!("projectile_prediction");
+            let dt = *world.resource(dtime());
+            let latency = world
+                .resource_opt(client_network_stats())
+                .map(|stats| stats.latency_ms as f32 / 1000. / 2.)
+                .unwrap_or_default();
+            let projectiles = query((translation(), projectile_velocity()))
+                .excl(projectile_inactive())
+                .collect_cloned(world, None);
+            for (id, (position, velocity)) in projectiles {
+                let (gravity, drag) = ballistics(world, id);
+                let (position, velocity) = match world.get(id, projectile_prediction()) {
+                    Ok((predicted_position, predicted_velocity))
+                        if predicted_position == position =>
+                    {
+                        ballistic_step(position, predicted_velocity, gravity, drag, dt)
+                    }
+                    _ => ballistic_predict(position, velocity, gravity, drag, latency),
+                };
+                world.set(id, translation(), position).unwrap();
+                world
+                    .add_component(id, projectile_prediction(), (position, velocity))
+                    .unwrap();
+            }
+        }))],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ballistic_step_matches_prediction() {
+        let (position, velocity) = (Vec3::ZERO, Vec3::new(100., 0., 10.));
+        let (mut stepped_position, mut stepped_velocity) = (position, velocity);
+        for _ in 0..30 {
+            (stepped_position, stepped_velocity) =
+                ballistic_step(stepped_position, stepped_velocity, GRAVITY, 0.1, 1. / 60.);
+        }
+        let (predicted_position, predicted_velocity) =
+            ballistic_predict(position, velocity, GRAVITY, 0.1, 0.5);
+        assert!(stepped_position.abs_diff_eq(predicted_position, 1e-3));
+        assert!(stepped_velocity.abs_diff_eq(predicted_velocity, 1e-3));
+    }
+
+    #[test]
+    fn ballistic_step_gravity_and_drag() {
+        // Without drag, a projectile keeps its horizontal speed and falls about g/2 meters in the first second
+        let (position, velocity) =
+            ballistic_predict(Vec3::ZERO, Vec3::new(10., 0., 0.), GRAVITY, 0., 1.);
+        assert_eq!(velocity.x, 10.);
+        assert!((velocity.z + GRAVITY).abs() < 1e-3);
+        assert!((position.z + GRAVITY / 2.).abs() < 0.1);
+
+        let (_, velocity) = ballistic_predict(Vec3::ZERO, Vec3::new(10., 0., 0.), 0., 0.5, 1.);
+        assert!(velocity.x < 10. && velocity.x > 5.);
+    }
+}
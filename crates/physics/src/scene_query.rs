@@ -0,0 +1,165 @@
+//! Batches of raycasts and sweeps that modules request with `scene_query_origins`, which are done in parallel at the
+//! end of the frame, so that hundreds of agents can check what they see without each waiting on the physics scene.
+use ambient_ecs::{query, EntityId, SystemGroup};
+use ambient_sys::task::par_map;
+use glam::Vec3;
+use itertools::Itertools;
+use ordered_float::OrderedFloat;
+use physxx::{
+    PxQueryFilterData, PxQueryFlag, PxRaycastCallback, PxSceneRef, PxShape, PxSphereGeometry,
+    PxTransform, PxUserData,
+};
+
+use crate::{
+    main_physics_scene, scene_query_directions, scene_query_hit_distances,
+    scene_query_hit_entities, scene_query_ignored, scene_query_max_distances, scene_query_origins,
+    scene_query_radius, PxShapeUserData,
+};
+
+/// The furthest PhysX can sweep, which queries without a max distance go
+const MAX_DISTANCE: f32 = 1e8;
+/// The most hits a query looks through for one that isn't ignored
+const MAX_TOUCHES: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SceneQuery {
+    pub origin: Vec3,
+    /// Normalized
+    pub direction: Vec3,
+    pub max_distance: f32,
+    /// Sweeps a sphere of this radius if set, and casts a ray otherwise
+    pub radius: Option<f32>,
+}
+
+/// The nearest entity that `query` hits in `scene`, skipping the `ignored` ones, and its distance.
+///
+/// This only reads the scene, so it can be called from several threads at once while the scene isn't simulating.
+pub fn first_hit(
+    scene: PxSceneRef,
+    query: &SceneQuery,
+    ignored: &[EntityId],
+) -> Option<(EntityId, f32)> {
+    let direction = query.direction.try_normalize()?;
+    let max_distance = query.max_distance.clamp(0., MAX_DISTANCE);
+    let mut filter_data = PxQueryFilterData::new();
+    // Every hit is reported as a touch, so that the ignored entities can be skipped
+    filter_data.set_flags(PxQueryFlag::STATIC | PxQueryFlag::DYNAMIC | PxQueryFlag::NO_BLOCK);
+    let hits: Vec<(Option<PxShape>, f32)> = match query.radius {
+        Some(radius) => scene
+            .sweep(
+                &PxSphereGeometry::new(radius),
+                &PxTransform::from_translation(query.origin),
+                direction,
+                max_distance,
+                filter_data,
+            )
+            .touches()
+            .into_iter()
+            .map(|hit| (hit.shape, hit.distance))
+            .collect(),
+        None => {
+            let mut hit = PxRaycastCallback::new(MAX_TOUCHES);
+            scene.raycast(
+                query.origin,
+                direction,
+                max_distance,
+                &mut hit,
+                None,
+                &filter_data,
+            );
+            hit.touches()
+                .into_iter()
+                .map(|hit| (hit.shape, hit.distance))
+                .collect()
+        }
+    };
+    hits.into_iter()
+        .filter_map(|(shape, distance)| {
+            Some((shape?.get_user_data::<PxShapeUserData>()?.entity, distance))
+        })
+        .filter(|(id, _)| !ignored.contains(id))
+        .min_by_key(|(_, distance)| OrderedFloat(*distance))
+}
+
+struct Batch {
+    id: EntityId,
+    queries: Vec<SceneQuery>,
+    ignored: Vec<EntityId>,
+}
+
+/// Answers the `scene_query_origins` requests. Run this after the guest systems, so that requests made this frame are
+/// answered on it.
+pub fn systems() -> SystemGroup {
+    SystemGroup::new(
+        "scene_query",
+        vec![query((scene_query_origins(), scene_query_directions()))
+            .excl(scene_query_hit_distances())
+            .to_system(|q, world, qs, _| {
+                ambient_profiling::scope!("scene_query_batches");
+                let batches = q
+                    .collect_cloned(world, qs)
+                    .into_iter()
+                    .map(|(id, (origins, directions))| {
+                        let max_distances = world
+                            .get_cloned(id, scene_query_max_distances())
+                            .unwrap_or_default();
+                        let radius = world.get(id, scene_query_radius()).ok();
+                        let queries = origins
+                            .into_iter()
+                            .zip(directions)
+                            .enumerate()
+                            .map(|(index, (origin, direction))| SceneQuery {
+                                origin,
+                                direction,
+                                max_distance: max_distances
+                                    .get(index)
+                                    .copied()
+                                    .unwrap_or(MAX_DISTANCE),
+                                radius,
+                            })
+                            .collect_vec();
+                        Batch {
+                            id,
+                            queries,
+                            ignored: world
+                                .get_cloned(id, scene_query_ignored())
+                                .unwrap_or_default(),
+                        }
+                    })
+                    .collect_vec();
+                if batches.is_empty() {
+                    return;
+                }
+
+                // The queries of all batches are spread over the threads together, as most batches are small
+                let scene = *world.resource(main_physics_scene());
+                let mut queries = batches
+                    .iter()
+                    .flat_map(|batch| {
+                        batch
+                            .queries
+                            .iter()
+                            .map(|query| (*query, batch.ignored.as_slice()))
+                    })
+                    .collect_vec();
+                let mut hits = par_map(&mut queries, |(query, ignored)| {
+                    first_hit(scene, query, ignored)
+                })
+                .into_iter();
+
+                for batch in batches {
+                    let (entities, distances): (Vec<_>, Vec<_>) = hits
+                        .by_ref()
+                        .take(batch.queries.len())
+                        .map(|hit| hit.unwrap_or((EntityId::null(), -1.)))
+                        .unzip();
+                    world
+                        .add_component(batch.id, scene_query_hit_entities(), entities)
+                        .ok();
+                    world
+                        .add_component(batch.id, scene_query_hit_distances(), distances)
+                        .ok();
+                }
+            })],
+    )
+}
@@ -2,7 +2,7 @@ use std::{collections::HashMap, sync::Arc};
 
 use ambient_core::{asset_cache, async_ecs::async_run, hierarchy::children, runtime};
 use ambient_decals::decal;
-use ambient_ecs::{query, query_mut, DeserWorldWithWarnings, EntityId, SystemGroup, World};
+use ambient_ecs::{components, query, query_mut, DeserWorldWithWarnings, EntityId, SystemGroup, World};
 use ambient_model::model_from_url;
 use ambient_physics::collider::collider;
 use ambient_std::{
@@ -16,35 +16,44 @@ use async_trait::async_trait;
 
 pub use ambient_ecs::generated::components::core::prefab::{prefab_from_url, spawned};
 
+pub mod stream;
+
+components!("prefab", {
+    scene_stream: stream::SceneStream,
+});
+
 pub fn systems() -> SystemGroup {
     SystemGroup::new(
         "prefab",
-        vec![query(prefab_from_url()).spawned().to_system(|q, world, qs, _| {
-            let mut to_load = HashMap::<String, Vec<EntityId>>::new();
-            for (id, url) in q.collect_cloned(world, qs) {
-                let url = if url.ends_with("/prefabs/main.json") { url } else { format!("{url}/prefabs/main.json") };
-                to_load.entry(url).or_default().push(id);
-            }
-            for (url, ids) in to_load {
-                let assets = world.resource(asset_cache()).clone();
-                let url = unwrap_log_err!(AssetUrl::parse(url));
-                let url = PrefabFromUrl(url);
-                let runtime = world.resource(runtime()).clone();
-                let async_run = world.resource(async_run()).clone();
-                runtime.spawn(async move {
-                    let obj = unwrap_log_err!(url.get(&assets).await);
-                    let base_ent_id = obj.resource(children())[0];
-                    // TODO: This only handles prefabs with a single entity
-                    let entity = obj.clone_entity(base_ent_id).unwrap();
-                    async_run.run(move |world| {
-                        for id in ids {
-                            world.add_components(id, entity.clone()).unwrap();
-                            world.add_component(id, spawned(), ()).unwrap();
-                        }
+        vec![
+            query(prefab_from_url()).spawned().to_system(|q, world, qs, _| {
+                let mut to_load = HashMap::<String, Vec<EntityId>>::new();
+                for (id, url) in q.collect_cloned(world, qs) {
+                    let url = if url.ends_with("/prefabs/main.json") { url } else { format!("{url}/prefabs/main.json") };
+                    to_load.entry(url).or_default().push(id);
+                }
+                for (url, ids) in to_load {
+                    let assets = world.resource(asset_cache()).clone();
+                    let url = unwrap_log_err!(AssetUrl::parse(url));
+                    let url = PrefabFromUrl(url);
+                    let runtime = world.resource(runtime()).clone();
+                    let async_run = world.resource(async_run()).clone();
+                    runtime.spawn(async move {
+                        let obj = unwrap_log_err!(url.get(&assets).await);
+                        let base_ent_id = obj.resource(children())[0];
+                        // TODO: This only handles prefabs with a single entity
+                        let entity = obj.clone_entity(base_ent_id).unwrap();
+                        async_run.run(move |world| {
+                            for id in ids {
+                                world.add_components(id, entity.clone()).unwrap();
+                                world.add_component(id, spawned(), ()).unwrap();
+                            }
+                        });
                     });
-                });
-            }
-        })],
+                }
+            }),
+            Box::new(stream::systems()),
+        ],
     )
 }
 
@@ -0,0 +1,257 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use ambient_core::{
+    asset_cache,
+    async_ecs::async_run,
+    hierarchy::{children, despawn_recursive, parent},
+    player::player,
+    runtime,
+    transform::{get_world_position, translation},
+};
+use ambient_ecs::{query, EntityId, SystemGroup, World};
+use ambient_std::{asset_cache::AsyncAssetKeyExt, asset_url::AssetUrl, unwrap_log_err};
+use ambient_sys::time::Instant;
+use glam::{IVec3, Vec3};
+use itertools::Itertools;
+
+use crate::{scene_stream, PrefabFromUrl};
+
+pub use ambient_ecs::generated::components::core::prefab::{
+    scene_stream_budget, scene_stream_chunk_size, scene_stream_from_url, scene_stream_progress,
+    scene_stream_volume,
+};
+
+const DEFAULT_CHUNK_SIZE: f32 = 32.;
+/// Milliseconds per frame
+const DEFAULT_BUDGET: f32 = 2.;
+
+/// The state of a `scene_stream_from_url` entity
+#[derive(Debug, Clone, Default)]
+pub struct SceneStream {
+    url: String,
+    /// `None` while the scene is loading
+    scene: Option<Arc<World>>,
+    chunks: Vec<SceneChunk>,
+}
+impl SceneStream {
+    fn progress(&self) -> f32 {
+        let (spawned, total) = self.chunks.iter().fold((0, 0), |(spawned, total), chunk| {
+            (spawned + chunk.next, total + chunk.roots.len())
+        });
+        if total == 0 {
+            0.
+        } else {
+            spawned as f32 / total as f32
+        }
+    }
+    fn unload_all(&mut self, world: &mut World) {
+        for chunk in &mut self.chunks {
+            chunk.unload(world);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SceneChunk {
+    center: Vec3,
+    /// The top-level entities of the chunk in the scene, which are spawned with their descendants
+    roots: Vec<EntityId>,
+    /// How many of the `roots` have been spawned
+    next: usize,
+    /// The spawned top-level entities in the world
+    spawned: Vec<EntityId>,
+}
+impl SceneChunk {
+    fn unload(&mut self, world: &mut World) {
+        for id in self.spawned.drain(..) {
+            despawn_recursive(world, id);
+        }
+        self.next = 0;
+    }
+}
+
+/// Loads the scenes of the `scene_stream_from_url` entities, and spawns and despawns their chunks within the
+/// `scene_stream_budget`
+pub fn systems() -> SystemGroup {
+    SystemGroup::new(
+        "scene_stream",
+        vec![
+            query(scene_stream())
+                .despawned()
+                .to_system(|q, world, qs, _| {
+                    for (_, mut stream) in q.collect_cloned(world, qs) {
+                        stream.unload_all(world);
+                    }
+                }),
+            query(scene_stream())
+                .excl(scene_stream_from_url())
+                .to_system(|q, world, qs, _| {
+                    for (id, mut stream) in q.collect_cloned(world, qs) {
+                        stream.unload_all(world);
+                        world.remove_component(id, scene_stream()).unwrap();
+                        world.remove_component(id, scene_stream_progress()).ok();
+                    }
+                }),
+            query(scene_stream_from_url()).to_system(|q, world, qs, _| {
+                let budget = world
+                    .resource_opt(scene_stream_budget())
+                    .copied()
+                    .unwrap_or(DEFAULT_BUDGET);
+                let deadline = Instant::now() + Duration::from_secs_f32(budget.max(0.) / 1000.);
+                let viewers = query(translation())
+                    .incl(player())
+                    .iter(world, None)
+                    .map(|(_, &position)| position)
+                    .collect_vec();
+                for (id, url) in q.collect_cloned(world, qs) {
+                    let mut stream = world
+                        .get_ref(id, scene_stream())
+                        .cloned()
+                        .unwrap_or_default();
+                    if stream.url != url {
+                        stream.unload_all(world);
+                        stream = SceneStream {
+                            url: url.clone(),
+                            ..Default::default()
+                        };
+                        load(world, id, url);
+                    }
+                    let Some(scene) = stream.scene.clone() else {
+                        world.add_component(id, scene_stream(), stream).unwrap();
+                        continue;
+                    };
+
+                    let position = get_world_position(world, id).unwrap_or_default();
+                    let active = match world.get(id, scene_stream_volume()) {
+                        Ok(half_extents) => viewers
+                            .iter()
+                            .any(|viewer| (*viewer - position).abs().cmple(half_extents).all()),
+                        Err(_) => true,
+                    };
+                    let viewers = if viewers.is_empty() {
+                        vec![position]
+                    } else {
+                        viewers.clone()
+                    };
+                    let distance = |chunk: &SceneChunk| {
+                        viewers
+                            .iter()
+                            .map(|viewer| viewer.distance(chunk.center))
+                            .fold(f32::INFINITY, f32::min)
+                    };
+                    let mut order = (0..stream.chunks.len()).collect_vec();
+                    order.sort_by(|&a, &b| {
+                        distance(&stream.chunks[a]).total_cmp(&distance(&stream.chunks[b]))
+                    });
+
+                    if active {
+                        'load: for i in order {
+                            let chunk = &mut stream.chunks[i];
+                            while chunk.next < chunk.roots.len() {
+                                if Instant::now() >= deadline {
+                                    break 'load;
+                                }
+                                if let Some(spawned) =
+                                    spawn_tree(world, &scene, chunk.roots[chunk.next], None)
+                                {
+                                    chunk.spawned.push(spawned);
+                                }
+                                chunk.next += 1;
+                            }
+                        }
+                    } else {
+                        // The furthest chunks go first
+                        for i in order.into_iter().rev() {
+                            if Instant::now() >= deadline {
+                                break;
+                            }
+                            stream.chunks[i].unload(world);
+                        }
+                    }
+                    world
+                        .add_component(id, scene_stream_progress(), stream.progress())
+                        .unwrap();
+                    world.add_component(id, scene_stream(), stream).unwrap();
+                }
+            }),
+        ],
+    )
+}
+
+fn load(world: &World, id: EntityId, url: String) {
+    let chunk_size = world
+        .get(id, scene_stream_chunk_size())
+        .unwrap_or(DEFAULT_CHUNK_SIZE)
+        .max(f32::EPSILON);
+    let assets = world.resource(asset_cache()).clone();
+    let async_run = world.resource(async_run()).clone();
+    world.resource(runtime()).spawn(async move {
+        let key = PrefabFromUrl(unwrap_log_err!(AssetUrl::parse(&url)));
+        let scene = unwrap_log_err!(key.get(&assets).await);
+        let chunks = chunks(&scene, chunk_size);
+        async_run.run(move |world| {
+            // The url may have changed while the scene was loading
+            if let Ok(stream) = world.get_mut(id, scene_stream()) {
+                if stream.url == url {
+                    stream.scene = Some(scene);
+                    stream.chunks = chunks;
+                }
+            }
+        });
+    });
+}
+
+/// Groups the top-level entities of the scene by the grid cell they are in
+fn chunks(scene: &World, chunk_size: f32) -> Vec<SceneChunk> {
+    let roots = match scene.resource_opt(children()) {
+        Some(roots) => roots.clone(),
+        None => query(())
+            .excl(parent())
+            .iter(scene, None)
+            .map(|(id, _)| id)
+            .filter(|&id| id != scene.resource_entity())
+            .collect(),
+    };
+    let mut cells = HashMap::<IVec3, Vec<(EntityId, Vec3)>>::new();
+    for root in roots {
+        let position = scene.get(root, translation()).unwrap_or_default();
+        let cell = (position / chunk_size).floor().as_ivec3();
+        cells.entry(cell).or_default().push((root, position));
+    }
+    cells
+        .into_values()
+        .map(|entities| SceneChunk {
+            center: entities.iter().map(|(_, position)| *position).sum::<Vec3>()
+                / entities.len() as f32,
+            roots: entities.into_iter().map(|(id, _)| id).collect(),
+            next: 0,
+            spawned: Vec::new(),
+        })
+        .collect()
+}
+
+/// Spawns a copy of `id` and its descendants in the scene, and returns the id of the copy
+fn spawn_tree(
+    world: &mut World,
+    scene: &World,
+    id: EntityId,
+    parent_id: Option<EntityId>,
+) -> Option<EntityId> {
+    let mut entity = scene.clone_entity(id).ok()?;
+    let scene_children = entity.remove_self(children()).unwrap_or_default();
+    entity.remove_self(parent());
+    if let Some(parent_id) = parent_id {
+        entity.set(parent(), parent_id);
+    }
+    let spawned = entity.spawn(world);
+    let spawned_children = scene_children
+        .into_iter()
+        .filter_map(|child| spawn_tree(world, scene, child, Some(spawned)))
+        .collect_vec();
+    if !spawned_children.is_empty() {
+        world
+            .add_component(spawned, children(), spawned_children)
+            .unwrap();
+    }
+    Some(spawned)
+}
@@ -1,13 +1,18 @@
 use std::sync::Arc;
 
 use ambient_core::{
-    asset_cache, mesh,
+    asset_cache,
+    async_ecs::async_run,
+    mesh, runtime,
     transform::{local_to_world, mesh_to_local, mesh_to_world, rotation, scale, translation},
 };
-use ambient_ecs::{ensure_has_component, ensure_has_component_with_default, query, Entity, SystemGroup};
+use ambient_ecs::{components, ensure_has_component, ensure_has_component_with_default, query, Entity, EntityId, SystemGroup, World};
 use ambient_gpu::{
+    atlas::{AtlasRegion, AtlasRegionFromUrl, TextureAtlasKey},
     gpu::GpuKey,
     shader_module::{BindGroupDesc, ShaderModule},
+    std_assets::{DefaultSamplerKey, PixelTextureViewKey},
+    texture::TextureView,
     typed_buffer::TypedBuffer,
 };
 use ambient_layout::{gpu_ui_size, height, mesh_to_local_from_size, width};
@@ -17,18 +22,25 @@ use ambient_renderer::{
     RendererShader, SharedMaterial, StandardShaderKey, MATERIAL_BIND_GROUP,
 };
 use ambient_std::{
-    asset_cache::{AssetCache, SyncAssetKey, SyncAssetKeyExt},
+    asset_cache::{AssetCache, AsyncAssetKeyExt, SyncAssetKey, SyncAssetKeyExt},
+    asset_url::AbsAssetUrl,
     cb,
     color::Color,
     friendly_id, include_file,
 };
-use glam::{vec4, Quat, UVec3, Vec3, Vec4};
+use glam::{vec2, vec3, vec4, Mat4, Quat, Vec2, Vec3, Vec4};
 use wgpu::{BindGroup, BindGroupLayoutEntry};
 
 pub use ambient_ecs::generated::components::core::rect::{
-    background_color, border_color, border_radius, border_thickness, line_from, line_to, line_width, rect,
+    background_color, background_image_slice, background_image_url, border_color, border_radius, border_thickness, ellipse, line_from,
+    line_to, line_width, polygon_sides, rect, shadow_blur, shadow_color, shadow_offset,
 };
 
+components!("rect", {
+    /// The image loaded from `background_image_url`; small images share the pages of the texture atlas
+    background_image: Arc<AtlasRegion>,
+});
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Corners {
@@ -79,9 +91,46 @@ pub fn systems() -> SystemGroup {
             ensure_has_component_with_default(rect(), mesh_to_world()),
             ensure_has_component_with_default(rect(), local_to_world()),
             ensure_has_component(rect(), scale(), Vec3::ONE),
-            ensure_has_component_with_default(rect(), mesh_to_local_from_size()),
             ensure_has_component_with_default(rect(), gpu_primitives_mesh()),
             ensure_has_component_with_default(rect(), gpu_primitives_lod()),
+            // Rects without a shadow are drawn on a quad the size of the rect, which the layout keeps up to date
+            query(()).incl(rect()).excl(mesh_to_local_from_size()).excl(shadow_color()).to_system(|q, world, qs, _| {
+                for (id, _) in q.collect_cloned(world, qs) {
+                    let mut data = Entity::new().with_default(mesh_to_local_from_size());
+                    if let (Ok(width), Ok(height)) = (world.get(id, width()), world.get(id, height())) {
+                        data.set(mesh_to_local(), Mat4::from_scale(vec3(width, height, 1.)));
+                    }
+                    // The shadow has been removed, so the material no longer leaves room for it
+                    if world.has_component(id, material()) {
+                        data.set(material(), rect_material(world, id));
+                    }
+                    world.add_components(id, data).unwrap();
+                }
+            }),
+            // Rects with a shadow are drawn on a larger quad, so that the shadow fits around it
+            query(())
+                .incl(rect())
+                .incl(shadow_color())
+                .excl(line_from())
+                .optional_changed(width())
+                .optional_changed(height())
+                .optional_changed(shadow_color())
+                .optional_changed(shadow_offset())
+                .optional_changed(shadow_blur())
+                .to_system(|q, world, qs, _| {
+                    for (id, _) in q.collect_cloned(world, qs) {
+                        let size = vec2(world.get(id, width()).unwrap_or(0.), world.get(id, height()).unwrap_or(0.));
+                        let padding = shadow_padding(world, id);
+                        world.remove_component(id, mesh_to_local_from_size()).ok();
+                        world
+                            .add_component(
+                                id,
+                                mesh_to_local(),
+                                Mat4::from_translation(-padding.extend(0.)) * Mat4::from_scale((size + 2. * padding).extend(1.)),
+                            )
+                            .unwrap();
+                    }
+                }),
             query(()).incl(rect()).excl(mesh()).to_system(|q, world, qs, _| {
                 let assets = world.resource(asset_cache()).clone();
                 for (id, _) in q.collect_cloned(world, qs) {
@@ -102,37 +151,100 @@ pub fn systems() -> SystemGroup {
                         .unwrap();
                 }
             }),
+            query(background_image_url().changed()).incl(rect()).to_system(|q, world, qs, _| {
+                for (id, url) in q.collect_cloned(world, qs) {
+                    let abs_url = match AbsAssetUrl::parse(&url) {
+                        Ok(value) => value,
+                        Err(err) => {
+                            log::warn!("Failed to parse background_image_url url: {:?}", err);
+                            continue;
+                        }
+                    };
+                    let assets = world.resource(asset_cache()).clone();
+                    let async_run = world.resource(async_run()).clone();
+                    world.resource(runtime()).spawn(async move {
+                        let region = AtlasRegionFromUrl { url: abs_url }.get(&assets).await;
+                        match region {
+                            Err(err) => {
+                                log::warn!("Failed to load background image: {:?}", err);
+                            }
+                            Ok(region) => {
+                                async_run.run(move |world| {
+                                    // The url may have changed while the image was loading
+                                    if world.get_ref(id, background_image_url()).map_or(false, |current| *current == url) {
+                                        world.add_component(id, background_image(), region).ok();
+                                    }
+                                });
+                            }
+                        }
+                    });
+                }
+            }),
+            query(()).incl(background_image()).excl(background_image_url()).to_system(|q, world, qs, _| {
+                for (id, _) in q.collect_cloned(world, qs) {
+                    world.remove_component(id, background_image()).unwrap();
+                }
+            }),
             query(())
                 .incl(rect())
                 .optional_changed(background_color())
                 .optional_changed(border_color())
                 .optional_changed(border_radius())
                 .optional_changed(border_thickness())
+                .optional_changed(shadow_color())
+                .optional_changed(shadow_offset())
+                .optional_changed(shadow_blur())
+                .optional_changed(background_image())
+                .optional_changed(background_image_slice())
+                .optional_changed(ellipse())
+                .optional_changed(polygon_sides())
                 .to_system(|q, world, qs, _| {
-                    let assets = world.resource(asset_cache()).clone();
                     for (id, _) in q.collect_cloned(world, qs) {
-                        world
-                            .add_component(
-                                id,
-                                material(),
-                                RectMaterialKey {
-                                    params: RectMaterialParams {
-                                        background_color: world.get(id, background_color()).unwrap_or(Color::WHITE.into()),
-                                        border_color: world.get(id, border_color()).unwrap_or(Color::WHITE.into()),
-                                        border_radius: world.get(id, border_radius()).unwrap_or_default().into(),
-                                        border_thickness: world.get(id, border_thickness()).unwrap_or(0.),
-                                        _padding: Default::default(),
-                                    },
-                                }
-                                .get(&assets),
-                            )
-                            .unwrap();
+                        world.add_component(id, material(), rect_material(world, id)).unwrap();
                     }
                 }),
         ],
     )
 }
 
+fn rect_material(world: &World, id: EntityId) -> SharedMaterial {
+    let assets = world.resource(asset_cache());
+    let image = world.get_cloned(id, background_image()).ok();
+    let has_shadow = world.has_component(id, shadow_color());
+    let params = RectMaterialParams {
+        background_color: world.get(id, background_color()).unwrap_or(Color::WHITE.into()),
+        border_color: world.get(id, border_color()).unwrap_or(Color::WHITE.into()),
+        border_radius: world.get(id, border_radius()).unwrap_or_default().into(),
+        shadow_color: world.get(id, shadow_color()).unwrap_or_default(),
+        image_slice: world.get(id, background_image_slice()).unwrap_or_default(),
+        image_uv: image.as_ref().map_or(vec4(0., 0., 1., 1.), |image| image.uv_rect()),
+        shadow_offset: if has_shadow { world.get(id, shadow_offset()).unwrap_or_default() } else { Vec2::ZERO },
+        quad_padding: shadow_padding(world, id),
+        image_size: image.as_ref().map_or(Vec2::ONE, |image| image.size.as_vec2()),
+        border_thickness: world.get(id, border_thickness()).unwrap_or(0.),
+        shadow_blur: if has_shadow { world.get(id, shadow_blur()).unwrap_or(0.).max(0.) } else { 0. },
+        ellipse: if world.has_component(id, ellipse()) { 1. } else { 0. },
+        polygon_sides: world.get(id, polygon_sides()).unwrap_or(0) as f32,
+        padding: Vec2::ZERO,
+    };
+    match image {
+        // Rects with the same look and an image from the same page of the atlas share their material
+        Some(image) if image.page.is_none() => SharedMaterial::new(RectMaterial::new(assets.clone(), params, Some(image.view.clone()))),
+        _ => RectMaterialKey { params, atlas_page: image.and_then(|image| image.page) }.get(assets),
+    }
+}
+
+/// How far the quad of the rect `id` reaches outside of it on each side, to fit its drop shadow
+fn shadow_padding(world: &World, id: EntityId) -> Vec2 {
+    if !world.has_component(id, shadow_color()) || world.has_component(id, line_from()) {
+        return Vec2::ZERO;
+    }
+    let blur = world.get(id, shadow_blur()).unwrap_or(0.).max(0.);
+    let offset = world.get(id, shadow_offset()).unwrap_or_default();
+    // One more pixel leaves room for the anti-aliased edge
+    Vec2::splat(blur + 1.) + offset.abs()
+}
+
 #[derive(Debug)]
 pub struct RectMaterialShaderKey;
 impl SyncAssetKey<Arc<MaterialShader>> for RectMaterialShaderKey {
@@ -147,12 +259,30 @@ impl SyncAssetKey<Arc<MaterialShader>> for RectMaterialShaderKey {
 
 fn get_rect_layout() -> BindGroupDesc<'static> {
     BindGroupDesc {
-        entries: vec![BindGroupLayoutEntry {
-            binding: 0,
-            visibility: wgpu::ShaderStages::FRAGMENT,
-            ty: wgpu::BindingType::Buffer { ty: wgpu::BufferBindingType::Uniform, has_dynamic_offset: false, min_binding_size: None },
-            count: None,
-        }],
+        entries: vec![
+            BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer { ty: wgpu::BufferBindingType::Uniform, has_dynamic_offset: false, min_binding_size: None },
+                count: None,
+            },
+            BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
         label: MATERIAL_BIND_GROUP.into(),
     }
 }
@@ -162,24 +292,43 @@ pub fn get_rect_shader(assets: &AssetCache, config: &RendererConfig) -> Arc<Rend
         .get(assets)
 }
 
+/// The material of a rect without a background image, or with one from the page `atlas_page` of the texture atlas
 #[derive(Debug)]
 pub struct RectMaterialKey {
     pub params: RectMaterialParams,
+    pub atlas_page: Option<usize>,
 }
 impl SyncAssetKey<SharedMaterial> for RectMaterialKey {
     fn load(&self, assets: AssetCache) -> SharedMaterial {
-        SharedMaterial::new(RectMaterial::new(assets, self.params))
+        let image = self.atlas_page.and_then(|page| TextureAtlasKey.get(&assets).page(page));
+        SharedMaterial::new(RectMaterial::new(assets, self.params, image))
     }
 }
 
+/// The parameters of a rect, as laid out in `rect.wgsl`; sizes and offsets are in pixels
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct RectMaterialParams {
     pub background_color: Vec4,
     pub border_color: Vec4,
     pub border_radius: Corners,
+    pub shadow_color: Vec4,
+    /// The nine-slice insets of the background image: left, top, right and bottom
+    pub image_slice: Vec4,
+    /// The texture coordinates of the top left and bottom right corners of the background image in its texture
+    pub image_uv: Vec4,
+    pub shadow_offset: Vec2,
+    /// How far the quad reaches outside of the rect on each side
+    pub quad_padding: Vec2,
+    pub image_size: Vec2,
     pub border_thickness: f32,
-    pub _padding: UVec3,
+    pub shadow_blur: f32,
+    /// 1 if the rect is drawn as an ellipse, and 0 otherwise
+    pub ellipse: f32,
+    /// The number of sides of the regular polygon the rect is drawn as, or 0 if it isn't one
+    pub polygon_sides: f32,
+    /// Rounds the struct up to the 16 byte alignment of uniform buffers
+    pub padding: Vec2,
 }
 
 pub struct RectMaterial {
@@ -188,7 +337,7 @@ pub struct RectMaterial {
     transparent: Option<bool>,
 }
 impl RectMaterial {
-    pub fn new(assets: AssetCache, params: RectMaterialParams) -> Self {
+    pub fn new(assets: AssetCache, params: RectMaterialParams, image: Option<Arc<TextureView>>) -> Self {
         let gpu = GpuKey.get(&assets);
         let layout = get_rect_layout().get(&assets);
 
@@ -198,18 +347,24 @@ impl RectMaterial {
             wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             &[params],
         );
+        let transparent = image.is_some() || params.background_color.w != 0. || params.border_color.w != 0. || params.shadow_color.w != 0.;
+        let image = image.unwrap_or_else(|| PixelTextureViewKey::white().get(&assets));
 
         Self {
             id: friendly_id(),
             bind_group: gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
                 layout: &layout,
-                entries: &[wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::Buffer(buffer.buffer().as_entire_buffer_binding()),
-                }],
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::Buffer(buffer.buffer().as_entire_buffer_binding()),
+                    },
+                    wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&image) },
+                    wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Sampler(&DefaultSamplerKey.get(&assets)) },
+                ],
                 label: Some("RectMaterial.bind_group"),
             }),
-            transparent: Some(params.background_color.w != 0. || params.border_color.w != 0.),
+            transparent: Some(transparent),
         }
     }
 }
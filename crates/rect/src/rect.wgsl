@@ -3,21 +3,87 @@ struct RectMaterialParams {
     background: vec4<f32>,
     border_color: vec4<f32>,
     border_radius: vec4<f32>,
+    shadow_color: vec4<f32>,
+    image_slice: vec4<f32>,
+    image_uv: vec4<f32>,
+    shadow_offset: vec2<f32>,
+    quad_padding: vec2<f32>,
+    image_size: vec2<f32>,
     border_thickness: f32,
+    shadow_blur: f32,
+    ellipse: f32,
+    polygon_sides: f32,
+    padding: vec2<f32>,
 }
 @group(MATERIAL_BIND_GROUP)
 @binding(0)
 var<uniform> rect_params: RectMaterialParams;
 
-fn get_corner_from_tc(tc: vec2<f32>) -> u32 {
+@group(MATERIAL_BIND_GROUP)
+@binding(1)
+var image_texture: texture_2d<f32>;
+
+@group(MATERIAL_BIND_GROUP)
+@binding(2)
+var image_sampler: sampler;
+
+// Signed distance from p, relative to the center of the rect, to the edge of a rect of size with rounded corners;
+// negative inside
+fn rounded_rect_distance(p: vec2<f32>, size: vec2<f32>) -> f32 {
     var corner = 0u;
-    if tc.x >= 0.5 {
+    if p.x >= 0. {
         corner += 1u;
     }
-    if tc.y >= 0.5 {
+    if p.y >= 0. {
         corner += 2u;
     }
-    return corner;
+    let half_size = size / 2.;
+    let radius = min(rect_params.border_radius[corner], min(half_size.x, half_size.y));
+    let q = abs(p) - half_size + radius;
+    return length(max(q, vec2<f32>(0.))) + min(max(q.x, q.y), 0.) - radius;
+}
+
+// Approximate signed distance from p, relative to the center of the rect, to the edge of the ellipse that fills it;
+// exact on the axes, and close enough elsewhere for anti-aliasing
+fn ellipse_distance(p: vec2<f32>, size: vec2<f32>) -> f32 {
+    let radius = max(size / 2., vec2<f32>(0.0001));
+    let k0 = length(p / radius);
+    let k1 = max(length(p / (radius * radius)), 0.0001);
+    return k0 * (k0 - 1.) / k1;
+}
+
+// Signed distance from p, relative to the center of the rect, to the edge of a regular polygon with a corner pointing
+// up, stretched to fill the rect
+fn polygon_distance(p: vec2<f32>, size: vec2<f32>, sides: f32) -> f32 {
+    let half_size = max(size / 2., vec2<f32>(0.0001));
+    let radius = min(half_size.x, half_size.y);
+    let q = p / half_size * radius;
+    let an = 3.14159265 / sides;
+    let sector = 2. * an;
+    let angle = atan2(q.x, -q.y);
+    let bn = angle - sector * floor(angle / sector) - an;
+    var e = length(q) * vec2<f32>(cos(bn), abs(sin(bn))) - radius * vec2<f32>(cos(an), sin(an));
+    e.y += clamp(-e.y, 0., radius * sin(an));
+    return length(e) * sign(e.x);
+}
+
+fn shape_distance(p: vec2<f32>, size: vec2<f32>) -> f32 {
+    if rect_params.polygon_sides >= 3. {
+        return polygon_distance(p, size, rect_params.polygon_sides);
+    }
+    if rect_params.ellipse > 0. {
+        return ellipse_distance(p, size);
+    }
+    return rounded_rect_distance(p, size);
+}
+
+// The texture coordinate of one axis of the image at p, with the insets at either end keeping their size and the
+// middle stretching over the rest of the rect
+fn nine_slice(p: f32, size: f32, image_size: f32, start: f32, end: f32) -> f32 {
+    let middle = max(size - start - end, 0.0001);
+    let stretched = start + (p - start) / middle * (image_size - start - end);
+    let pixel = select(select(stretched, image_size - (size - p), p > size - end), p, p < start);
+    return pixel / image_size;
 }
 
 fn get_material(in: MaterialInput) -> MaterialOutput {
@@ -25,23 +91,39 @@ fn get_material(in: MaterialInput) -> MaterialOutput {
     out.roughness = 0.4;
     out.metallic = 0.5;
     let size = get_entity_ui_size(in.entity_loc).xy;
-    let p = (0.5 - abs(in.texcoord - 0.5)) * size; // Normalized to top left
-    let corner = get_corner_from_tc(in.texcoord);
-    let border_radius = rect_params.border_radius[corner];
-    let d = distance(vec2(border_radius), p);
+    // In pixels from the top left corner of the rect; the quad reaches outside of it when it has a shadow
+    let p = in.texcoord * (size + 2. * rect_params.quad_padding) - rect_params.quad_padding;
+    let d = shape_distance(p - size / 2., size);
+    let aa = max(fwidth(d), 0.0001);
+
+    let image_p = clamp(p, vec2<f32>(0.), size);
+    let slice = rect_params.image_slice;
+    let image_size = rect_params.image_size;
+    let image_tc = vec2<f32>(
+        nine_slice(image_p.x, size.x, image_size.x, slice.x, slice.z),
+        nine_slice(image_p.y, size.y, image_size.y, slice.y, slice.w),
+    );
+    // The image may be a region of an atlas
+    let image = textureSample(image_texture, image_sampler, mix(rect_params.image_uv.xy, rect_params.image_uv.zw, image_tc));
 
     let entity_color = get_entity_color_or(in.entity_loc, vec4<f32>(1., 1., 1., 1.));
-    let border_color = rect_params.border_color * entity_color;
-    var color = rect_params.background * entity_color;
-    if max(p.x, p.y) <= border_radius {
-        if d > border_radius {
-            color.a = 0.;
-        } else if d > border_radius - rect_params.border_thickness {
-            color = border_color;
-        }
-    } else if min(p.x, p.y) < rect_params.border_thickness {
-        color = border_color;
+    var color = rect_params.background * image * entity_color;
+    if rect_params.border_thickness > 0. {
+        let border = clamp(0.5 + (d + rect_params.border_thickness) / aa, 0., 1.);
+        color = mix(color, rect_params.border_color * entity_color, border);
+    }
+    color.a *= clamp(0.5 - d / aa, 0., 1.);
+
+    // The shadow is drawn behind the rect, fading out over the blur on either side of its edge
+    let shadow_distance = shape_distance(p - rect_params.shadow_offset - size / 2., size);
+    let blur = max(rect_params.shadow_blur, aa);
+    let shadow = rect_params.shadow_color * entity_color;
+    let shadow_alpha = shadow.a * (1. - smoothstep(-blur, blur, shadow_distance)) * (1. - color.a);
+    let alpha = color.a + shadow_alpha;
+    if alpha > 0. {
+        color = vec4<f32>((color.rgb * color.a + shadow.rgb * shadow_alpha) / alpha, alpha);
     }
+
     out.opacity = color.a;
     out.alpha_cutoff = 0.;
     out.base_color = from_srgb_to_linear(color.rgb);
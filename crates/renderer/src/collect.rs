@@ -10,6 +10,7 @@ use ambient_gpu::{
 };
 use ambient_std::{
     asset_cache::{AssetCache, SyncAssetKey, SyncAssetKeyExt},
+    frame_arena::{with_frame_arena, ArenaVec},
     include_file,
 };
 use glam::{uvec2, UVec2, UVec3};
@@ -236,16 +237,19 @@ impl RendererCollect {
         input_primitives: &TypedMultiBuffer<CollectPrimitive>,
         output: &mut RendererCollectState,
         primitives_count: u32,
-        material_layouts: Vec<UVec2>,
+        material_layouts: &[UVec2],
     ) {
         if primitives_count == 0 {
             return;
         }
 
         output.commands.resize(primitives_count as u64, true);
-        let counts = vec![0; material_layouts.len()];
-        output.counts.fill(&counts, |_| {});
-        output.material_layouts.fill(&material_layouts, |_| {});
+        with_frame_arena(|arena| {
+            let counts =
+                ArenaVec::from_iter_in(std::iter::repeat(0).take(material_layouts.len()), arena);
+            output.counts.fill(&counts, |_| {});
+        });
+        output.material_layouts.fill(material_layouts, |_| {});
 
         let bind_group = self
             .gpu
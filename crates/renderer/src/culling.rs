@@ -2,9 +2,10 @@ use std::{f32::INFINITY, sync::Arc};
 
 use ambient_core::{
     bounding::world_bounding_sphere,
-    camera::{shadow_cameras_from_world, Camera},
+    camera::{shadow_cameras_from_world, sky_camera_from_world, Camera},
     gpu_components,
     gpu_ecs::{GpuComponentFormat, GpuWorldUpdater},
+    main_scene,
     player::local_user_id,
 };
 use ambient_ecs::{ArchetypeFilter, World};
@@ -18,10 +19,10 @@ use ambient_std::{
     include_file,
     shapes::Plane,
 };
-use glam::{Mat4, UVec3, Vec2, Vec3, Vec3Swizzles, Vec4};
+use glam::{Mat4, UVec2, UVec3, UVec4, Vec2, Vec3, Vec3Swizzles, Vec4};
 use wgpu::{BindGroupLayout, BindGroupLayoutEntry, BindingType, BufferBindingType, ShaderStages};
 
-use crate::{get_sun_light_direction, RendererConfig};
+use crate::{get_sun_light_direction, portal_graph, RendererConfig, SKY_OCCLUSION_SIZE};
 
 gpu_components! {
     world_bounding_sphere() => renderer_cameras_visible: GpuComponentFormat::Mat4,
@@ -66,14 +67,19 @@ impl From<Camera> for CullCamera {
 }
 
 pub const MAX_SHADOW_CASCADES: u32 = 6;
+/// The index of the sky occlusion map in `renderer_cameras_visible`, after the main camera and the shadow cascades
+pub const SKY_CAMERA: u32 = MAX_SHADOW_CASCADES + 1;
 
 #[repr(C)]
 #[derive(Debug, Clone, Default, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 struct CullingParams {
     pub main_camera: CullCamera,
     pub shadow_cameras: [CullCamera; MAX_SHADOW_CASCADES as usize],
+    pub sky_camera: CullCamera,
     pub lod_cutoff_scaling: f32,
-    pub _padding: UVec3,
+    pub portal_culling: u32,
+    pub _padding: UVec2,
+    pub visible_cells: [UVec4; 2],
 }
 
 pub struct Culling {
@@ -111,6 +117,7 @@ impl Culling {
                 "MAX_SHADOW_CASCADES",
                 MAX_SHADOW_CASCADES,
             ))
+            .with_ident(ShaderIdent::constant("SKY_CAMERA", SKY_CAMERA))
             .with_binding_desc(get_culling_layout());
 
         Self {
@@ -140,19 +147,28 @@ impl Culling {
 
     #[ambient_profiling::function]
     pub fn run<'a>(&mut self, encoder: &'a mut wgpu::CommandEncoder, world: &World) {
-        let main_camera = if let Some(camera) = Camera::get_active(
-            world,
-            self.config.scene,
-            world.resource_opt(local_user_id()),
-        ) {
+        let main_camera = if let Some(camera) = self
+            .config
+            .get_camera(world)
+            .and_then(|camera| Camera::from_world(world, camera))
+        {
             camera
         } else {
             // log::warn!("No valid camera");
             return;
         };
 
+        // Only the entities of the main scene are in cells
+        let visible_cells = world
+            .resource_opt(portal_graph())
+            .filter(|_| self.config.scene == main_scene())
+            .and_then(|graph| {
+                graph.visible_cells(main_camera.position(), main_camera.projection_view())
+            });
         let mut params = CullingParams {
             lod_cutoff_scaling: self.config.lod_cutoff_scaling,
+            portal_culling: visible_cells.is_some() as u32,
+            visible_cells: visible_cells.unwrap_or_default(),
             main_camera: main_camera.into(),
             ..Default::default()
         };
@@ -169,6 +185,14 @@ impl Culling {
             for i in 0..(self.config.shadow_cascades as usize) {
                 params.shadow_cameras[i] = shadow_cameras[i].clone().into();
             }
+            params.sky_camera = sky_camera_from_world(
+                world,
+                SKY_OCCLUSION_SIZE,
+                self.config.shadow_map_resolution,
+                self.config.scene,
+                world.resource_opt(local_user_id()),
+            )
+            .into();
         }
 
         self.params.fill(&[params], |_| {});
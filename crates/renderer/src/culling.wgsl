@@ -22,7 +22,10 @@ struct Camera {
 struct Params {
     main_camera: Camera,
     shadow_cameras: array<Camera, MAX_SHADOW_CASCADES>,
+    sky_camera: Camera,
     lod_cutoff_scaling: f32,
+    portal_culling: u32,
+    visible_cells: array<vec4<u32>, 2>,
 };
 
 @group(LODDING_BIND_GROUP)
@@ -72,13 +75,22 @@ fn get_lod(entity_loc: vec2<u32>) -> u32 {
     return 0u;
 }
 
+/// Whether the portal culling hides the entity, because it is in a cell that can't be seen from the camera
+fn portal_culled(entity_loc: vec2<u32>) -> bool {
+    if params.portal_culling == 0u || !has_entity_gpu_visibility_cell(entity_loc) {
+        return false;
+    }
+    let cell = u32(get_entity_gpu_visibility_cell(entity_loc).x) - 1u;
+    return (params.visible_cells[cell / 128u][(cell / 32u) % 4u] & (1u << (cell % 32u))) == 0u;
+}
+
 fn update(entity_loc: vec2<u32>) {
     if has_entity_gpu_lod(entity_loc) {
         set_entity_gpu_lod(entity_loc, vec4<f32>(f32(get_lod(entity_loc)), 0.0, 0.0, 0.0));
     }
     var cameras: mat4x4<f32>;
     let bounding_sphere = get_entity_world_bounding_sphere(entity_loc);
-    cameras[0][0] = f32(cull_camera(params.main_camera, bounding_sphere).inside);
+    cameras[0][0] = f32(cull_camera(params.main_camera, bounding_sphere).inside && !portal_culled(entity_loc));
 
     for (var i = 1u; i <= SHADOW_CASCADESu; i = i + 1u) {
         let a = i >> 2u;
@@ -86,6 +98,9 @@ fn update(entity_loc: vec2<u32>) {
 
         cameras[a][b] = 0.0;
     }
+    if SHADOW_CASCADESu > 0u {
+        cameras[SKY_CAMERAu >> 2u][SKY_CAMERAu & 3u] = f32(cull_camera(params.sky_camera, bounding_sphere).inside);
+    }
     for (var i = 0u; i < SHADOW_CASCADESu; i = i + 1u) {
         let radius = bounding_sphere.w;
         let pixel_size = vec2<f32>(radius) * 2. / params.shadow_cameras[i].orthographic_size;
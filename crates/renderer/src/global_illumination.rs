@@ -0,0 +1,104 @@
+use ambient_core::{
+    camera::{get_active_camera, projection},
+    main_scene,
+    player::local_user_id,
+};
+use ambient_ecs::World;
+use ambient_gpu::shader_module::ShaderModule;
+use ambient_std::{asset_cache::AssetCache, include_file};
+use glam::{Mat4, Vec2};
+
+use crate::{FullscreenPass, PassInput, PostProcessContext, PostProcessPass};
+
+pub use ambient_ecs::generated::components::core::rendering::{
+    global_illumination, global_illumination_quality,
+};
+
+/// The value of [global_illumination] for [ScreenSpaceGi]
+pub const SCREEN_SPACE_GI: &str = "screen_space";
+
+/// Whether the [global_illumination] resource of `world` asks for [ScreenSpaceGi]
+pub fn screen_space_gi_enabled(world: &World) -> bool {
+    world
+        .resource_opt(global_illumination())
+        .map_or(false, |mode| mode == SCREEN_SPACE_GI)
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct GiParams {
+    projection: Mat4,
+    inv_projection: Mat4,
+    resolution: Vec2,
+    radius: f32,
+    intensity: f32,
+    samples: u32,
+    steps: u32,
+    frame: u32,
+    _padding: u32,
+}
+
+/// Screen-space global illumination: traces short rays against the depth buffer from every pixel of the scene, and adds
+/// the light of the surfaces they hit, so that lights and emissive surfaces light the geometry around them. Only what is
+/// on screen contributes.
+///
+/// Runs when the [global_illumination] resource is [SCREEN_SPACE_GI] (see [screen_space_gi_enabled]), with the
+/// quality of [global_illumination_quality].
+pub struct ScreenSpaceGi {
+    pass: FullscreenPass,
+    frame: u32,
+}
+impl ScreenSpaceGi {
+    pub fn new(assets: &AssetCache, format: wgpu::TextureFormat) -> Self {
+        Self {
+            pass: FullscreenPass::new(
+                assets,
+                "ScreenSpaceGi",
+                ShaderModule::new(
+                    "global_illumination",
+                    include_file!("global_illumination.wgsl"),
+                ),
+                format,
+                std::mem::size_of::<GiParams>(),
+                &[PassInput::Depth],
+            ),
+            frame: 0,
+        }
+    }
+}
+impl PostProcessPass for ScreenSpaceGi {
+    /// Adds the bounced light to the color buffer of the target, which holds the rendered scene and its depth
+    fn render(&mut self, encoder: &mut wgpu::CommandEncoder, context: &PostProcessContext) {
+        let world = context.world;
+        let color_buffer = &context.target.color_buffer;
+        let Some(projection) =
+            get_active_camera(world, main_scene(), world.resource_opt(local_user_id()))
+                .and_then(|camera| world.get(camera, projection()).ok())
+        else {
+            return;
+        };
+
+        let quality = world
+            .resource_opt(global_illumination_quality())
+            .copied()
+            .unwrap_or(0.5)
+            .clamp(0., 1.);
+        self.frame = self.frame.wrapping_add(1);
+        let params = GiParams {
+            projection,
+            inv_projection: projection.inverse(),
+            resolution: Vec2::new(
+                color_buffer.size.width as f32,
+                color_buffer.size.height as f32,
+            ),
+            radius: 1. + 3. * quality,
+            intensity: 1.,
+            samples: 2 + (quality * 14.).round() as u32,
+            steps: 4 + (quality * 12.).round() as u32,
+            frame: self.frame,
+            _padding: 0,
+        };
+        self.pass
+            .render(encoder, context, bytemuck::bytes_of(&params));
+    }
+}
@@ -0,0 +1,99 @@
+struct GiParams {
+    projection: mat4x4<f32>,
+    inv_projection: mat4x4<f32>,
+    resolution: vec2<f32>,
+    radius: f32,
+    intensity: f32,
+    samples: u32,
+    steps: u32,
+    frame: u32,
+    _padding: u32,
+};
+
+@group(0)
+@binding(0)
+var<uniform> params: GiParams;
+@group(0)
+@binding(1)
+var scene_texture: texture_2d<f32>;
+@group(0)
+@binding(2)
+var depth_texture: texture_depth_2d;
+
+fn load_depth(pixel: vec2<i32>) -> f32 {
+    let max_pixel = vec2<i32>(params.resolution) - vec2<i32>(1, 1);
+    return textureLoad(depth_texture, clamp(pixel, vec2<i32>(0, 0), max_pixel), 0);
+}
+
+fn view_position(pixel: vec2<i32>, depth: f32) -> vec3<f32> {
+    let uv = (vec2<f32>(pixel) + 0.5) / params.resolution;
+    let p = params.inv_projection * vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, depth, 1.0);
+    return p.xyz / p.w;
+}
+
+/// The pixel coordinates and depth of a view space position
+fn project(position: vec3<f32>) -> vec3<f32> {
+    let clip = params.projection * vec4<f32>(position, 1.0);
+    let ndc = clip.xyz / clip.w;
+    return vec3<f32>(vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5) * params.resolution, ndc.z);
+}
+
+// Interleaved gradient noise, from http://www.iryoku.com/next-generation-post-processing-in-call-of-duty-advanced-warfare
+fn noise(p: vec2<f32>) -> f32 {
+    return fract(52.9829189 * fract(dot(p, vec2<f32>(0.06711056, 0.00583715))));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let pixel = vec2<i32>(in.position.xy);
+    let color = textureLoad(scene_texture, pixel, 0);
+    let depth = load_depth(pixel);
+    // The depth is reversed, so nothing was drawn here
+    if depth <= 0.0 {
+        return color;
+    }
+
+    let position = view_position(pixel, depth);
+    let right = view_position(pixel + vec2<i32>(1, 0), load_depth(pixel + vec2<i32>(1, 0)));
+    let down = view_position(pixel + vec2<i32>(0, 1), load_depth(pixel + vec2<i32>(0, 1)));
+    var normal = normalize(cross(right - position, down - position));
+    if dot(normal, position) > 0.0 {
+        normal = -normal;
+    }
+    let up = select(vec3<f32>(0.0, 0.0, 1.0), vec3<f32>(1.0, 0.0, 0.0), abs(normal.z) > 0.9);
+    let tangent = normalize(cross(up, normal));
+    let bitangent = cross(normal, tangent);
+
+    let jitter = noise(in.position.xy + f32(params.frame % 64u) * 5.588238);
+    var indirect = vec3<f32>(0.0);
+    for (var i = 0u; i < params.samples; i++) {
+        // Cosine-weighted directions over the hemisphere around the normal
+        let angle = fract(f32(i) * 0.618034 + jitter) * 6.2831853;
+        let v = (f32(i) + 0.5) / f32(params.samples);
+        let r = sqrt(v);
+        let direction = tangent * (r * cos(angle)) + bitangent * (r * sin(angle)) + normal * sqrt(1.0 - v);
+
+        for (var step = 0u; step < params.steps; step++) {
+            let t = params.radius * (f32(step) + jitter + 0.1) / f32(params.steps);
+            let ray = project(position + direction * t);
+            if any(ray.xy < vec2<f32>(0.0)) || any(ray.xy >= params.resolution) {
+                break;
+            }
+            let hit_pixel = vec2<i32>(ray.xy);
+            let hit_depth = load_depth(hit_pixel);
+            if hit_depth > ray.z {
+                // Only count surfaces that are close to the ray, not the ones in front of it
+                let hit = view_position(hit_pixel, hit_depth);
+                if distance(hit, position + direction * t) < params.radius * 0.5 {
+                    indirect += textureLoad(scene_texture, hit_pixel, 0).rgb * (1.0 - t / params.radius);
+                }
+                break;
+            }
+        }
+    }
+    indirect /= f32(params.samples);
+
+    // The lit color stands in for the albedo, which isn't known here
+    let albedo = color.rgb / max(max(color.r, max(color.g, color.b)), 0.001);
+    return vec4<f32>(color.rgb + indirect * albedo * params.intensity, color.a);
+}
@@ -2,27 +2,33 @@ use std::sync::Arc;
 
 use ambient_core::{
     camera::{far, fog, get_active_camera, projection_view},
+    environment::Environment,
+    main_scene,
     player::local_user_id,
+    time,
     transform::{get_world_position, get_world_rotation, local_to_world},
 };
-use ambient_ecs::{Component, ECSError, World};
+use ambient_ecs::{Component, ECSError, EntityId, World};
 use ambient_gpu::{
     gpu::{Gpu, GpuKey},
     mesh_buffer::MeshBuffer,
     shader_module::{BindGroupDesc, DEPTH_FORMAT},
     std_assets::DefaultSamplerKey,
     texture::{Texture, TextureView},
+    typed_buffer::TypedBuffer,
 };
 use ambient_std::asset_cache::{AssetCache, SyncAssetKeyExt};
-use glam::{vec3, Mat4, UVec2, Vec3, Vec4};
+use glam::{vec3, Mat4, UVec2, UVec4, Vec3, Vec4};
 use wgpu::{BindGroup, BindGroupLayout, Buffer, Sampler};
 
 use super::{
     fog_color, get_active_sun, light_ambient, light_diffuse, RenderTarget, ShadowCameraData,
 };
 use crate::{
-    fog_density, fog_height_falloff, skinning::SkinsBufferKey, GLOBALS_BIND_GROUP,
-    MESH_BASE_BINDING, MESH_METADATA_BINDING, MESH_SKIN_BINDING, SKINS_BINDING,
+    dummy_lightmap, fog_density, fog_height_falloff, get_light_probe_grid, lightmap_texture,
+    morphing::MorphWeightsBufferKey, physical_camera::camera_exposure, skinning::SkinsBufferKey,
+    GLOBALS_BIND_GROUP, GLOBALS_BIND_GROUP_SIZE, MESH_BASE_BINDING, MESH_METADATA_BINDING,
+    MESH_MORPH_BINDING, MESH_SKIN_BINDING, MORPH_WEIGHTS_BINDING, SKINS_BINDING,
 };
 
 #[repr(C)]
@@ -53,6 +59,22 @@ pub(crate) struct GlobalParams {
     pub fog_height_falloff: f32,
     pub fog_density: f32,
     pub debug_params: ShaderDebugParams,
+    pub light_probe_grid_origin: Vec4,
+    pub light_probe_grid_spacing: Vec4,
+    /// The number of probes along each axis, and 1 in `w` if there is a light probe grid
+    pub light_probe_grid_counts: UVec4,
+    /// The direction of the wind times its strength, and its gustiness in `w`
+    pub wind: Vec4,
+    /// The temperature in `x`, the wetness in `y`, the time of the world in seconds in `z`, which the wind moves with
+    /// so that it matches [Environment::wind_at] on the CPU, and the precipitation in `w`
+    pub weather: Vec4,
+    /// The `projection_view` of the camera when the previous frame was rendered, which the velocity buffer is written with
+    pub previous_projection_view: Mat4,
+    /// What the light of the scene is multiplied by, from the physical settings of the camera
+    pub exposure: f32,
+    /// How full the puddles are
+    pub puddles: f32,
+    pub _padding: [f32; 2],
 }
 
 impl Default for GlobalParams {
@@ -73,6 +95,15 @@ impl Default for GlobalParams {
             fog_height_falloff: 0.5,
             fog_density: 0.5,
             debug_params: Default::default(),
+            light_probe_grid_origin: Vec4::ZERO,
+            light_probe_grid_spacing: Vec4::ONE,
+            light_probe_grid_counts: UVec4::ZERO,
+            wind: Vec4::ZERO,
+            weather: Vec4::ZERO,
+            previous_projection_view: Default::default(),
+            exposure: 1.,
+            puddles: 0.,
+            _padding: [0.; 2],
         }
     }
 }
@@ -156,6 +187,26 @@ pub fn globals_layout() -> BindGroupDesc<'static> {
                 },
                 count: None,
             },
+            wgpu::BindGroupLayoutEntry {
+                binding: 8,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 9,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
         ],
         label: GLOBALS_BIND_GROUP.into(),
     }
@@ -167,8 +218,15 @@ pub(crate) struct ForwardGlobals {
     shadow_cameras_buffer: wgpu::Buffer,
     shadow_sampler: wgpu::Sampler,
     dummy_shadow_texture: TextureView,
+    lightmap: Option<Arc<TextureView>>,
+    light_probes: TypedBuffer<Vec4>,
+    light_probes_data: Vec<Vec4>,
     pub(crate) params: GlobalParams,
     scene: Component<()>,
+    /// Overrides the active camera of the scene
+    camera: Option<EntityId>,
+    /// The camera of the previous frame, whose `projection_view` is still in [GlobalParams::projection_view]
+    previous_camera: Option<EntityId>,
     start_time: ambient_sys::time::Instant,
     layout: Arc<wgpu::BindGroupLayout>,
 }
@@ -179,6 +237,7 @@ impl ForwardGlobals {
         layout: Arc<wgpu::BindGroupLayout>,
         shadow_cascades: u32,
         scene: Component<()>,
+        camera: Option<EntityId>,
     ) -> Self {
         log::debug!("Setting up forward globals");
         let buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
@@ -190,7 +249,8 @@ impl ForwardGlobals {
         let shadow_cameras_buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("ForwardGlobals.shadow_cameras_buffer"),
             usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-            size: shadow_cascades as u64 * std::mem::size_of::<ShadowCameraData>() as u64,
+            // The sky occlusion map follows the shadow cascades
+            size: (shadow_cascades as u64 + 1) * std::mem::size_of::<ShadowCameraData>() as u64,
             mapped_at_creation: false,
         });
 
@@ -206,6 +266,14 @@ impl ForwardGlobals {
             ..Default::default()
         });
 
+        let light_probes = TypedBuffer::new(
+            gpu.clone(),
+            "ForwardGlobals.light_probes",
+            4,
+            4,
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        );
+
         let params = GlobalParams::default();
 
         Self {
@@ -218,9 +286,14 @@ impl ForwardGlobals {
                     ..Default::default()
                 },
             ),
+            lightmap: None,
+            light_probes,
+            light_probes_data: Vec::new(),
             params,
             gpu,
             scene,
+            camera,
+            previous_camera: None,
             start_time: ambient_sys::time::Instant::now(),
             layout,
         }
@@ -237,6 +310,12 @@ impl ForwardGlobals {
 
         let skins = SkinsBufferKey.get(assets);
         let skins = skins.lock();
+        let morph_weights = MorphWeightsBufferKey.get(assets);
+        let morph_weights = morph_weights.lock();
+        let lightmap = match &self.lightmap {
+            Some(lightmap) => lightmap.clone(),
+            None => dummy_lightmap(assets),
+        };
         self.gpu
             .device
             .create_bind_group(&wgpu::BindGroupDescriptor {
@@ -287,21 +366,37 @@ impl ForwardGlobals {
                         ),
                     },
                     wgpu::BindGroupEntry {
-                        binding: 8 + MESH_METADATA_BINDING,
+                        binding: 8,
+                        resource: wgpu::BindingResource::TextureView(&lightmap),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 9,
+                        resource: self.light_probes.buffer().as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: GLOBALS_BIND_GROUP_SIZE + MESH_METADATA_BINDING,
                         resource: mesh_buffer.metadata_buffer.buffer().as_entire_binding(),
                     },
                     wgpu::BindGroupEntry {
-                        binding: 8 + MESH_BASE_BINDING,
+                        binding: GLOBALS_BIND_GROUP_SIZE + MESH_BASE_BINDING,
                         resource: mesh_buffer.base_buffer.buffer().as_entire_binding(),
                     },
                     wgpu::BindGroupEntry {
-                        binding: 8 + MESH_SKIN_BINDING,
+                        binding: GLOBALS_BIND_GROUP_SIZE + MESH_SKIN_BINDING,
                         resource: mesh_buffer.skinned_buffer.buffer().as_entire_binding(),
                     },
                     wgpu::BindGroupEntry {
-                        binding: 8 + SKINS_BINDING,
+                        binding: GLOBALS_BIND_GROUP_SIZE + SKINS_BINDING,
                         resource: skins.buffer.buffer().as_entire_binding(),
                     },
+                    wgpu::BindGroupEntry {
+                        binding: GLOBALS_BIND_GROUP_SIZE + MESH_MORPH_BINDING,
+                        resource: mesh_buffer.morph_buffer.buffer().as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: GLOBALS_BIND_GROUP_SIZE + MORPH_WEIGHTS_BINDING,
+                        resource: morph_weights.buffer.buffer().as_entire_binding(),
+                    },
                 ],
                 label: Some("ForwardGlobals"),
             })
@@ -310,9 +405,19 @@ impl ForwardGlobals {
     #[tracing::instrument(level = "debug", skip_all, fields(scene = ?self.scene, user = ?world.resource_opt(local_user_id())))]
     pub fn update(&mut self, world: &World, shadow_cameras: &[ShadowCameraData]) {
         let mut p = &mut self.params;
-        if let Some(id) = get_active_camera(world, self.scene, world.resource_opt(local_user_id()))
-        {
-            p.projection_view = world.get(id, projection_view()).unwrap_or_default();
+        let camera = self
+            .camera
+            .or_else(|| get_active_camera(world, self.scene, world.resource_opt(local_user_id())));
+        let previous_camera = std::mem::replace(&mut self.previous_camera, camera);
+        if let Some(id) = camera {
+            let projection_view = world.get(id, projection_view()).unwrap_or_default();
+            // A camera that was just switched to has no motion
+            p.previous_projection_view = if previous_camera == Some(id) {
+                p.projection_view
+            } else {
+                projection_view
+            };
+            p.projection_view = projection_view;
             p.inv_projection_view = p.projection_view.inverse();
             p.camera_position = get_world_position(world, id).unwrap_or_default().extend(1.);
             p.camera_forward = world
@@ -321,6 +426,7 @@ impl ForwardGlobals {
                 .transform_vector3(Vec3::Z);
             p.camera_far = world.get(id, far()).unwrap_or(1e3);
             p.fog = world.has_component(id, fog()) as i32;
+            p.exposure = camera_exposure(world, id);
             p.forward_camera_position = p.camera_position;
             tracing::debug!(
                 "Found active camera for {} {} {}",
@@ -356,6 +462,23 @@ impl ForwardGlobals {
             );
             update(&mut p.fog_density, world.get(sun, fog_density()), |v| v);
         }
+        self.lightmap = world.resource_opt(lightmap_texture()).cloned();
+        match get_light_probe_grid(world) {
+            Some(grid) => {
+                p.light_probe_grid_origin = grid.origin.extend(0.);
+                p.light_probe_grid_spacing = grid.spacing.extend(0.);
+                p.light_probe_grid_counts = grid.counts.extend(1);
+                if grid.sh != self.light_probes_data {
+                    self.light_probes.fill(grid.sh, |_| {});
+                    self.light_probes_data = grid.sh.to_vec();
+                }
+            }
+            None => p.light_probe_grid_counts = UVec4::ZERO,
+        }
+        // The weather doesn't reach the UI
+        if self.scene == main_scene() {
+            set_environment(p, world);
+        }
         self.params.time = ambient_sys::time::Instant::now()
             .duration_since(self.start_time)
             .as_secs_f32();
@@ -370,6 +493,20 @@ impl ForwardGlobals {
     }
 }
 
+fn set_environment(params: &mut GlobalParams, world: &World) {
+    let environment = Environment::from_world(world);
+    params.wind =
+        (environment.wind_direction * environment.wind_strength).extend(environment.wind_gustiness);
+    let time = world.resource_opt(time()).copied().unwrap_or_default();
+    params.weather = Vec4::new(
+        environment.temperature,
+        environment.wetness,
+        time.as_secs_f32(),
+        environment.precipitation,
+    );
+    params.puddles = environment.puddles;
+}
+
 fn create_dummy_shadow_texture(gpu: Arc<Gpu>) -> Arc<Texture> {
     Arc::new(Texture::new(
         gpu,
@@ -398,6 +535,7 @@ pub struct ShadowAndUIGlobals {
     shadow_sampler: Sampler,
     shadow_view: TextureView,
     dummy_prev_frame: RenderTarget,
+    dummy_light_probes: Buffer,
     buffer: wgpu::Buffer,
     bind_group: Option<BindGroup>,
 }
@@ -431,6 +569,13 @@ impl ShadowAndUIGlobals {
             ..Default::default()
         });
 
+        let dummy_light_probes = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("ShadowGlobals.dummy_light_probes"),
+            usage: wgpu::BufferUsages::STORAGE,
+            size: std::mem::size_of::<Vec4>() as u64,
+            mapped_at_creation: false,
+        });
+
         let shadow_texture = create_dummy_shadow_texture(gpu.clone());
         let dummy_prev_frame = RenderTarget::new(gpu.clone(), UVec2::ONE, None);
         let shadow_view = shadow_texture.create_view(&wgpu::TextureViewDescriptor {
@@ -446,6 +591,7 @@ impl ShadowAndUIGlobals {
             shadow_sampler,
             shadow_view,
             dummy_prev_frame,
+            dummy_light_probes,
             assets,
             bind_group: None,
         }
@@ -454,6 +600,8 @@ impl ShadowAndUIGlobals {
     pub fn create_bind_group(&mut self, mesh_buffer: &MeshBuffer) -> &BindGroup {
         let skins = SkinsBufferKey.get(&self.assets);
         let skins = skins.lock();
+        let morph_weights = MorphWeightsBufferKey.get(&self.assets);
+        let morph_weights = morph_weights.lock();
 
         let bind_group = self
             .gpu
@@ -506,21 +654,37 @@ impl ShadowAndUIGlobals {
                         ),
                     },
                     wgpu::BindGroupEntry {
-                        binding: 8 + MESH_METADATA_BINDING,
+                        binding: 8,
+                        resource: wgpu::BindingResource::TextureView(&dummy_lightmap(&self.assets)),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 9,
+                        resource: self.dummy_light_probes.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: GLOBALS_BIND_GROUP_SIZE + MESH_METADATA_BINDING,
                         resource: mesh_buffer.metadata_buffer.buffer().as_entire_binding(),
                     },
                     wgpu::BindGroupEntry {
-                        binding: 8 + MESH_BASE_BINDING,
+                        binding: GLOBALS_BIND_GROUP_SIZE + MESH_BASE_BINDING,
                         resource: mesh_buffer.base_buffer.buffer().as_entire_binding(),
                     },
                     wgpu::BindGroupEntry {
-                        binding: 8 + MESH_SKIN_BINDING,
+                        binding: GLOBALS_BIND_GROUP_SIZE + MESH_SKIN_BINDING,
                         resource: mesh_buffer.skinned_buffer.buffer().as_entire_binding(),
                     },
                     wgpu::BindGroupEntry {
-                        binding: 8 + SKINS_BINDING,
+                        binding: GLOBALS_BIND_GROUP_SIZE + SKINS_BINDING,
                         resource: skins.buffer.buffer().as_entire_binding(),
                     },
+                    wgpu::BindGroupEntry {
+                        binding: GLOBALS_BIND_GROUP_SIZE + MESH_MORPH_BINDING,
+                        resource: mesh_buffer.morph_buffer.buffer().as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: GLOBALS_BIND_GROUP_SIZE + MORPH_WEIGHTS_BINDING,
+                        resource: morph_weights.buffer.buffer().as_entire_binding(),
+                    },
                 ],
                 label: Some("ShadowGlobals.bind_group"),
             });
@@ -541,6 +705,8 @@ impl ShadowAndUIGlobals {
             params.forward_camera_position =
                 get_world_position(world, id).unwrap_or_default().extend(1.);
         }
+        // Swaying foliage casts shadows that sway with it
+        set_environment(&mut params, world);
         self.gpu
             .queue
             .write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[params]));
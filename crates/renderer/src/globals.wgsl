@@ -22,6 +22,21 @@ struct ForwardGlobalParams {
     debug_metallic_roughness: f32,
     debug_normals: f32,
     debug_shading: f32,
+
+    light_probe_grid_origin: vec4<f32>,
+    light_probe_grid_spacing: vec4<f32>,
+    light_probe_grid_counts: vec4<u32>,
+
+    // The direction of the wind times its strength, and its gustiness in w
+    wind: vec4<f32>,
+    // The temperature, the wetness, the time of the world in seconds and the precipitation
+    weather: vec4<f32>,
+    // The projection_view of the camera when the previous frame was rendered
+    previous_projection_view: mat4x4<f32>,
+    // What the light of the scene is multiplied by, from the physical settings of the camera
+    exposure: f32,
+    // How full the puddles are
+    puddles: f32,
 };
 
 struct ShadowCamera {
@@ -61,6 +76,18 @@ var solids_screen_depth: texture_depth_2d;
 @binding(7)
 var solids_screen_normal_quat: texture_2d<f32>;
 
+@group(GLOBALS_BIND_GROUP)
+@binding(8)
+var lightmap: texture_2d<f32>;
+
+struct LightProbes {
+    data: array<vec4<f32>>,
+};
+
+@group(GLOBALS_BIND_GROUP)
+@binding(9)
+var<storage> light_probes: LightProbes;
+
 fn inside(v: vec3<f32>) -> bool {
     return v.x > -1. && v.x < 1. && v.y > -1. && v.y < 1. && v.z > 0. && v.z < 1.;
 }
@@ -94,6 +121,17 @@ fn fetch_shadow(light_angle: f32, world_position: vec4<f32>) -> f32 {
     return 1.;
 }
 
+/// How much of `world_position` is open to the sky, from the sky occlusion map which follows the shadow cascades. It is
+/// only rendered while the world is wet, and everything outside of it counts as open.
+fn fetch_sky_visibility(world_position: vec4<f32>) -> f32 {
+    let cam = shadow_cameras.cameras[SHADOW_CASCADES].viewproj * world_position;
+    let p = cam.xyz / cam.w;
+    if inside(p) {
+        return fetch_shadow_cascade(SHADOW_CASCADES, p);
+    }
+    return 1.;
+}
+
 fn screen_pixel_to_uv(pixel_position: vec2<f32>, screen_size: vec2<f32>) -> vec2<f32> {
     return pixel_position / screen_size;
 }
@@ -164,8 +202,21 @@ struct MaterialOutput {
 struct MainFsOut {
             @location(0) color: vec4<f32>,
             @location(1) normal: vec4<f32>,
+            @location(2) velocity: vec4<f32>,
         }
 
+/// How far a surface moved on the screen since the previous frame, in UV units, for the velocity buffer. The previous
+/// position is the one the surface had in the world then.
+fn screen_velocity(world_position: vec4<f32>, previous_world_position: vec4<f32>) -> vec4<f32> {
+    let clip = global_params.projection_view * world_position;
+    let previous_clip = global_params.previous_projection_view * previous_world_position;
+    if clip.w <= 0.0 || previous_clip.w <= 0.0 {
+        return vec4<f32>(0.0);
+    }
+    let velocity = (clip.xy / clip.w - previous_clip.xy / previous_clip.w) * vec2<f32>(0.5, -0.5);
+    return vec4<f32>(velocity, 0.0, 0.0);
+}
+
 fn apply_fog(color: vec3<f32>, camera_pos: vec3<f32>, world_pos: vec3<f32>) -> vec3<f32> {
     // From https://developer.amd.com/wordpress/media/2012/10/Wenzel-Real-time_Atmospheric_Effects_in_Games.pdf
     let camera_to_world_pos = world_pos - camera_pos;
@@ -181,6 +232,52 @@ fn apply_fog(color: vec3<f32>, camera_pos: vec3<f32>, world_pos: vec3<f32>) -> v
     return mix(color, global_params.fog_color.rgb, clamp(fog_amount, 0., 1.));
 }
 
+/// The wind velocity at `world_position`, including the gusts that travel along it
+fn wind_at(world_position: vec3<f32>) -> vec3<f32> {
+    return wind_velocity(global_params.wind, global_params.weather.z, world_position);
+}
+
+fn puddle_hash(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2<f32>(127.1, 311.7))) * 43758.5453);
+}
+
+/// Value noise from 0 to 1 over the ground; puddles fill its lowest values first
+fn puddle_mask(p: vec2<f32>) -> f32 {
+    let q = p * 0.3;
+    let i = floor(q);
+    let f = fract(q);
+    let u = f * f * (3. - 2. * f);
+    return mix(
+        mix(puddle_hash(i), puddle_hash(i + vec2<f32>(1., 0.)), u.x),
+        mix(puddle_hash(i + vec2<f32>(0., 1.)), puddle_hash(i + vec2<f32>(1., 1.)), u.x),
+        u.y
+    );
+}
+
+/// Darkens and smooths the material where the rain reaches it as the world gets wet, and pools water in puddles on
+/// flat ground as they fill up
+fn apply_wetness(material: MaterialOutput, world_position: vec3<f32>) -> MaterialOutput {
+    var out = material;
+    if (global_params.weather.y <= 0. && global_params.puddles <= 0.) || material.shading == 0. {
+        return out;
+    }
+    // Interiors and the ground under roofs stay dry
+    let sky_visibility = fetch_sky_visibility(vec4<f32>(world_position, 1.));
+    let wetness = global_params.weather.y * sky_visibility;
+    // Rough, non-metallic surfaces are porous and soak up the most water
+    let porosity = (1. - material.metallic) * material.roughness;
+    out.base_color = material.base_color * mix(1., 0.5, wetness * porosity);
+    out.roughness = mix(material.roughness, 0.1, wetness * 0.7);
+
+    let flat_ground = smoothstep(0.9, 0.98, material.normal.z);
+    let puddle = flat_ground * sky_visibility * smoothstep(0., 0.1, global_params.puddles * 1.1 - puddle_mask(world_position.xy));
+    out.base_color = out.base_color * mix(1., 0.7, puddle);
+    out.roughness = mix(out.roughness, 0.02, puddle);
+    out.metallic = mix(out.metallic, 0., puddle);
+    out.normal = normalize(mix(out.normal, vec3<f32>(0., 0., 1.), puddle));
+    return out;
+}
+
 fn fresnel(ndoth: f32, f0: vec3<f32>) -> vec3<f32> {
     let v = clamp(1.0 - ndoth, 0.0, 1.0);
     return f0 + (1.0 - f0) * pow(v, 5.0);
@@ -226,10 +323,52 @@ fn geometry_smith(normal: vec3<f32>, v: vec3<f32>, l: vec3<f32>, roughness: f32)
           geometry_schlick_ggx(ndotv, k) * geometry_schlick_ggx(ndotl, k);
 }
 
+/// Decodes the RGBM encoded indirect light in the lightmap atlas
+fn sample_lightmap(uv: vec2<f32>) -> vec3<f32> {
+    let rgbm = textureSampleLevel(lightmap, default_sampler, uv, 0.);
+    return rgbm.rgb * rgbm.a * LIGHTMAP_RGBM_RANGE;
+}
+
+fn light_probe(index: vec3<u32>, normal: vec3<f32>) -> vec3<f32> {
+    let counts = global_params.light_probe_grid_counts.xyz;
+    let i = 4u * (index.x + counts.x * (index.y + counts.y * index.z));
+    let sh = light_probes.data[i].xyz +
+        light_probes.data[i + 1u].xyz * normal.x +
+        light_probes.data[i + 2u].xyz * normal.y +
+        light_probes.data[i + 3u].xyz * normal.z;
+    return max(sh, vec3<f32>(0.));
+}
+
+/// Interpolates the light of the eight light probes around `world_position`
+fn sample_light_probes(world_position: vec3<f32>, normal: vec3<f32>) -> vec3<f32> {
+    let counts = global_params.light_probe_grid_counts.xyz;
+    let grid_position = clamp(
+        (world_position - global_params.light_probe_grid_origin.xyz) / global_params.light_probe_grid_spacing.xyz,
+        vec3<f32>(0.),
+        vec3<f32>(counts - 1u)
+    );
+    let base = vec3<u32>(floor(grid_position));
+    let next = min(base + 1u, counts - 1u);
+    let t = grid_position - vec3<f32>(base);
+    var result = vec3<f32>(0.);
+    for (var i = 0u; i < 8u; i += 1u) {
+        let corner = vec3<u32>(i & 1u, (i >> 1u) & 1u, (i >> 2u) & 1u) == vec3<u32>(1u);
+        let weights = select(1. - t, t, corner);
+        result += light_probe(select(base, next, corner), normal) * weights.x * weights.y * weights.z;
+    }
+    return result;
+}
+
 fn shading(material: MaterialOutput, world_position: vec4<f32>) -> vec4<f32> {
+    return shading_with_ambient(material, world_position, global_params.sun_ambient.rgb);
+}
+
+/// Shades the material with `ambient` as the indirect light, instead of the sun's ambient light
+fn shading_with_ambient(material_in: MaterialOutput, world_position: vec4<f32>, ambient: vec3<f32>) -> vec4<f32> {
     if global_params.debug_shading > 0.0 {
-        return vec4(material.base_color.rgb, material.opacity);
+        return vec4(material_in.base_color.rgb, material_in.opacity);
     }
+    let material = apply_wetness(material_in, world_position.xyz);
 
     let v = normalize(global_params.camera_position.xyz - world_position.xyz);
 
@@ -296,18 +435,20 @@ fn shading(material: MaterialOutput, world_position: vec4<f32>) -> vec4<f32> {
 
     let direct = (lambert + specular) * radiance * ndotl * in_shadow;
 
-    let indirect = albedo * global_params.sun_ambient.rgb;
+    let indirect = albedo * ambient;
 
     let lum = direct + indirect;
 
     var color = mix(material.base_color.rgb, lum, material.shading) + material.emissive_factor;
 
-    color = mix(color, vec3(metallic, roughness, 0.0), global_params.debug_metallic_roughness);
-    color = mix(color, normal, global_params.debug_normals);
-
     if global_params.fog != 0 {
         color = apply_fog(color, global_params.camera_position.xyz, world_position.xyz);
     }
+    // The camera exposes the light of the scene before it is clamped to the color buffer
+    color *= global_params.exposure;
+
+    color = mix(color, vec3(metallic, roughness, 0.0), global_params.debug_metallic_roughness);
+    color = mix(color, normal, global_params.debug_normals);
 
     // let color = vec3<f32>(roughness, metallic, 0.0);
     // color = color + u32_to_color(u32(get_shadow_cascade(world_position))) * 0.2;
@@ -21,17 +21,25 @@ use ambient_gpu::{
 use ambient_std::{asset_cache::*, asset_url::AbsAssetUrl, cb, include_file, Cb};
 use derive_more::*;
 use downcast_rs::{impl_downcast, DowncastSync};
-use glam::{uvec4, UVec2, UVec4, Vec3};
+use glam::{uvec4, UVec2, UVec4, Vec3, Vec4};
 use serde::{Deserialize, Serialize};
 
 pub mod bind_groups;
 mod collect;
 mod culling;
+mod global_illumination;
 mod globals;
+mod lightmaps;
 pub mod lod;
 pub mod materials;
+pub mod morphing;
+mod motion_blur;
 mod outlines;
 mod overlay_renderer;
+mod physical_camera;
+mod picking;
+mod portals;
+mod post_processing;
 mod renderer;
 mod shaders;
 mod shadow_renderer;
@@ -39,26 +47,39 @@ pub mod skinning;
 mod target;
 mod transparent_renderer;
 mod tree_renderer;
+mod weather;
 use ambient_ecs::{query, Component};
 pub use collect::*;
 pub use culling::*;
+pub use global_illumination::*;
 pub use globals::*;
-use materials::pbr_material::PbrMaterialFromUrl;
+pub use lightmaps::*;
 pub use materials::*;
+pub use motion_blur::*;
+use materials::{
+    custom_material::{CustomMaterial, CustomMaterialFromUrl},
+    pbr_material::PbrMaterialFromUrl,
+};
 use ordered_float::OrderedFloat;
 pub use outlines::*;
+pub use physical_camera::*;
+pub use picking::*;
+pub use portals::*;
+pub use post_processing::*;
 pub use renderer::*;
 pub use shaders::*;
 pub use shadow_renderer::*;
 pub use target::*;
 pub use transparent_renderer::*;
 pub use tree_renderer::*;
+pub use weather::*;
 
 pub const MAX_PRIMITIVE_COUNT: usize = 16;
 
 pub use ambient_ecs::generated::components::core::rendering::{
     cast_shadows, color, double_sided, fog_color, fog_density, fog_height_falloff, light_ambient,
-    light_diffuse, overlay, pbr_material_from_url, sun, transparency_group,
+    light_diffuse, material_from_url, material_uniform_names, material_uniform_values, overlay,
+    pbr_material_from_url, shader_params, sun, transparency_group, wind_sway,
 };
 
 components!("rendering", {
@@ -69,6 +90,9 @@ components!("rendering", {
     gpu_primitives_mesh: [u32; MAX_PRIMITIVE_COUNT],
     gpu_primitives_lod: [u32; MAX_PRIMITIVE_COUNT],
 
+    /// The `wind_sway` in x, as the gpu only stores vectors and matrices
+    gpu_wind_sway: Vec4,
+
     renderer_shader: RendererShaderProducer,
     material: SharedMaterial,
     @[Resource]
@@ -76,18 +100,39 @@ components!("rendering", {
 });
 gpu_components! {
     color() => color: GpuComponentFormat::Vec4,
+    shader_params() => shader_params: GpuComponentFormat::Mat4,
+    gpu_wind_sway() => wind_sway: GpuComponentFormat::Vec4,
     gpu_primitives_mesh() => gpu_primitives_mesh: GpuComponentFormat::Mat4,
     gpu_primitives_lod() => gpu_primitives_lod: GpuComponentFormat::Mat4,
 }
 pub fn init_all_components() {
     init_components();
     init_gpu_components();
+    outlines::init_components();
     outlines::init_gpu_components();
     culling::init_gpu_components();
     lod::init_components();
     lod::init_gpu_components();
     skinning::init_components();
     skinning::init_gpu_components();
+    morphing::init_components();
+    morphing::init_gpu_components();
+    motion_blur::init_components();
+    motion_blur::init_gpu_components();
+    post_processing::init_components();
+    portals::init_components();
+    portals::init_gpu_components();
+    lightmaps::init_components();
+    lightmaps::init_gpu_components();
+}
+
+/// Overrides the uniforms of a custom material with the values of `material_uniform_names` and `material_uniform_values`.
+fn set_material_uniforms(mat: &CustomMaterial, names: &[String], values: &[Vec4]) {
+    for (name, value) in names.iter().zip(values) {
+        if !mat.set_uniform(name, *value) {
+            log::warn!("Material {:?} has no uniform {:?}", mat.name(), name);
+        }
+    }
 }
 
 pub fn systems() -> SystemGroup {
@@ -129,6 +174,57 @@ pub fn systems() -> SystemGroup {
                     });
                 }
             }),
+            query(material_from_url().changed()).to_system(|q, world, qs, _| {
+                for (id, url) in q.collect_cloned(world, qs) {
+                    let url = match AbsAssetUrl::parse(url) {
+                        Ok(value) => value,
+                        Err(err) => {
+                            log::warn!("Failed to parse material_from_url url: {:?}", err);
+                            continue;
+                        }
+                    };
+                    let assets = world.resource(asset_cache()).clone();
+                    let async_run = world.resource(async_run()).clone();
+                    world.resource(runtime()).spawn(async move {
+                        match CustomMaterialFromUrl(url).get(&assets).await {
+                            Err(err) => {
+                                log::warn!("Failed to load material from url: {:?}", err);
+                            }
+                            Ok(def) => {
+                                let mat = def.instantiate(&assets);
+                                async_run.run(move |world| {
+                                    if let (Ok(names), Ok(values)) = (
+                                        world.get_ref(id, material_uniform_names()),
+                                        world.get_ref(id, material_uniform_values()),
+                                    ) {
+                                        set_material_uniforms(&mat, names, values);
+                                    }
+                                    world
+                                        .add_components(
+                                            id,
+                                            Entity::new()
+                                                .with(renderer_shader(), def.shader_producer())
+                                                .with(material(), mat.into()),
+                                        )
+                                        .ok();
+                                });
+                            }
+                        }
+                    });
+                }
+            }),
+            query((
+                material_uniform_names().changed(),
+                material_uniform_values().changed(),
+                material(),
+            ))
+            .to_system(|q, world, qs, _| {
+                for (_, (names, values, mat)) in q.iter(world, qs) {
+                    if let Some(mat) = mat.downcast_ref::<CustomMaterial>() {
+                        set_material_uniforms(mat, names, values);
+                    }
+                }
+            }),
             query_mut(
                 (primitives(),),
                 (
@@ -165,7 +261,25 @@ pub fn systems() -> SystemGroup {
                     }
                 }
             }),
+            query(wind_sway())
+                .optional_changed(wind_sway())
+                .to_system(|q, world, qs, _| {
+                    for (id, sway) in q.collect_cloned(world, qs) {
+                        world
+                            .add_component(id, gpu_wind_sway(), Vec4::new(sway, 0., 0., 0.))
+                            .ok();
+                    }
+                }),
+            query(wind_sway()).despawned().to_system(|q, world, qs, _| {
+                for (id, _) in q.collect_cloned(world, qs) {
+                    world.remove_component(id, gpu_wind_sway()).ok();
+                }
+            }),
             Box::new(outlines::systems()),
+            Box::new(motion_blur::systems()),
+            Box::new(post_processing::systems()),
+            Box::new(portals::systems()),
+            Box::new(lightmaps::systems()),
         ],
     )
 }
@@ -180,6 +294,16 @@ pub fn gpu_world_systems() -> SystemGroup<GpuWorldSyncEvent> {
                 color(),
                 gpu_components::color(),
             )),
+            Box::new(ComponentToGpuSystem::new(
+                GpuComponentFormat::Mat4,
+                shader_params(),
+                gpu_components::shader_params(),
+            )),
+            Box::new(ComponentToGpuSystem::new(
+                GpuComponentFormat::Vec4,
+                gpu_wind_sway(),
+                gpu_components::wind_sway(),
+            )),
             Box::new(ComponentToGpuSystem::new(
                 GpuComponentFormat::Mat4,
                 gpu_primitives_mesh(),
@@ -192,6 +316,10 @@ pub fn gpu_world_systems() -> SystemGroup<GpuWorldSyncEvent> {
             )),
             Box::new(lod::gpu_world_system()),
             Box::new(skinning::gpu_world_systems()),
+            Box::new(morphing::gpu_world_systems()),
+            Box::new(lightmaps::gpu_world_systems()),
+            Box::new(motion_blur::gpu_world_systems()),
+            Box::new(portals::gpu_world_systems()),
         ],
     )
 }
@@ -313,6 +441,14 @@ pub fn get_mesh_data_module(bind_group_offset: u32) -> Arc<ShaderModule> {
                 "SKINS_BINDING",
                 bind_group_offset + SKINS_BINDING,
             ))
+            .with_ident(ShaderIdent::constant(
+                "MESH_MORPH_BINDING",
+                bind_group_offset + MESH_MORPH_BINDING,
+            ))
+            .with_ident(ShaderIdent::constant(
+                "MORPH_WEIGHTS_BINDING",
+                bind_group_offset + MORPH_WEIGHTS_BINDING,
+            ))
             .with_binding_desc(get_mesh_data_layout(bind_group_offset))
             .with_dependency(get_mesh_meta_module(bind_group_offset)),
     )
@@ -364,10 +500,17 @@ pub fn get_globals_module(_assets: &AssetCache, shadow_cascades: u32) -> Arc<Sha
     Arc::new(
         ShaderModule::new("globals", include_file!("globals.wgsl"))
             .with_ident(ShaderIdent::constant("SHADOW_CASCADES", shadow_cascades))
-            .with_binding_desc(globals_layout()),
+            .with_ident(ShaderIdent::constant("LIGHTMAP_RGBM_RANGE", LIGHTMAP_RGBM_RANGE))
+            .with_binding_desc(globals_layout())
+            .with_dependency(get_wind_module()),
     )
 }
 
+/// Contains `wind_velocity`, for shaders that don't have the scene globals
+pub fn get_wind_module() -> Arc<ShaderModule> {
+    Arc::new(ShaderModule::new("wind", include_file!("wind.wgsl")))
+}
+
 pub fn get_forward_modules(assets: &AssetCache, shadow_cascades: u32) -> Vec<Arc<ShaderModule>> {
     vec![
         get_defs_module(),
@@ -438,6 +581,7 @@ pub enum FSMain {
     Forward,
     Shadow,
     Outline,
+    Picking,
 }
 
 pub struct RendererShader {
@@ -447,6 +591,7 @@ pub struct RendererShader {
     pub fs_shadow_main: String,
     pub fs_forward_main: String,
     pub fs_outline_main: String,
+    pub fs_picking_main: String,
     pub transparent: bool,
     pub double_sided: bool,
     /// TODO: Apply to tree renderer too (only applies to transparent now)
@@ -459,6 +604,7 @@ impl RendererShader {
             FSMain::Forward => &self.fs_forward_main,
             FSMain::Shadow => &self.fs_shadow_main,
             FSMain::Outline => &self.fs_outline_main,
+            FSMain::Picking => &self.fs_picking_main,
         }
     }
 }
@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use ambient_core::{
+    asset_cache,
+    async_ecs::async_run,
+    gpu_components,
+    gpu_ecs::{ComponentToGpuSystem, GpuComponentFormat, GpuWorldSyncEvent},
+    runtime,
+    transform::get_world_position,
+};
+use ambient_ecs::{components, query, Resource, SystemGroup, World};
+use ambient_gpu::{
+    std_assets::PixelTextureViewKey, texture::TextureView, texture_loaders::TextureFromUrl,
+};
+use ambient_std::{
+    asset_cache::{AssetCache, AsyncAssetKeyExt, SyncAssetKeyExt},
+    asset_url::AbsAssetUrl,
+};
+use glam::{UVec3, UVec4, Vec3, Vec4};
+
+pub use ambient_ecs::generated::components::core::rendering::{
+    light_probe_grid, light_probe_grid_origin, light_probe_grid_sh, light_probe_grid_spacing,
+    lightmap_scale_offset, lightmap_url,
+};
+
+/// Lightmaps store indirect light as RGBM, where the light is `rgb * a * LIGHTMAP_RGBM_RANGE`.
+/// This has to match the range used when baking.
+pub const LIGHTMAP_RGBM_RANGE: f32 = 8.;
+
+components!("rendering", {
+    @[Resource]
+    lightmap_texture: Arc<TextureView>,
+});
+gpu_components! {
+    lightmap_scale_offset() => lightmap_scale_offset: GpuComponentFormat::Vec4,
+}
+
+/// Loads the lightmap atlas whenever `lightmap_url` changes
+pub fn systems() -> SystemGroup {
+    SystemGroup::new(
+        "lightmaps",
+        vec![
+            query(lightmap_url().changed()).to_system(|q, world, qs, _| {
+                for (_, url) in q.collect_cloned(world, qs) {
+                    let url = match AbsAssetUrl::parse(url) {
+                        Ok(value) => value,
+                        Err(err) => {
+                            log::warn!("Failed to parse lightmap_url url: {:?}", err);
+                            continue;
+                        }
+                    };
+                    let assets = world.resource(asset_cache()).clone();
+                    let async_run = world.resource(async_run()).clone();
+                    world.resource(runtime()).spawn(async move {
+                        let texture = TextureFromUrl {
+                            url,
+                            format: wgpu::TextureFormat::Rgba8Unorm,
+                        }
+                        .get(&assets)
+                        .await;
+                        match texture {
+                            Err(err) => {
+                                log::warn!("Failed to load lightmap: {:?}", err);
+                            }
+                            Ok(texture) => {
+                                let view = Arc::new(texture.create_view(&Default::default()));
+                                async_run.run(move |world| {
+                                    world.add_resource(lightmap_texture(), view);
+                                });
+                            }
+                        }
+                    });
+                }
+            }),
+            query(lightmap_url())
+                .despawned()
+                .to_system(|q, world, qs, _| {
+                    if q.iter(world, qs).next().is_some()
+                        && query(lightmap_url()).iter(world, None).next().is_none()
+                    {
+                        let resources = world.resource_entity();
+                        world.remove_component(resources, lightmap_texture()).ok();
+                    }
+                }),
+        ],
+    )
+}
+
+pub fn gpu_world_systems() -> SystemGroup<GpuWorldSyncEvent> {
+    SystemGroup::new(
+        "lightmaps/gpu_world_update",
+        vec![Box::new(ComponentToGpuSystem::new(
+            GpuComponentFormat::Vec4,
+            lightmap_scale_offset(),
+            gpu_components::lightmap_scale_offset(),
+        ))],
+    )
+}
+
+/// Bound in place of the lightmap when there is none; it is never sampled, as no entity has a `lightmap_scale_offset` then
+pub(crate) fn dummy_lightmap(assets: &AssetCache) -> Arc<TextureView> {
+    PixelTextureViewKey { color: UVec4::ZERO }.get(assets)
+}
+
+pub(crate) struct LightProbeGrid<'a> {
+    /// The world position of the first probe
+    pub origin: Vec3,
+    pub spacing: Vec3,
+    pub counts: UVec3,
+    pub sh: &'a [Vec4],
+}
+
+/// The first light probe grid in the world, if its spherical harmonics match its size
+pub(crate) fn get_light_probe_grid(world: &World) -> Option<LightProbeGrid> {
+    let (id, counts) = query(light_probe_grid()).iter(world, None).next()?;
+    let sh = world.get_ref(id, light_probe_grid_sh()).ok()?;
+    let spacing = world.get(id, light_probe_grid_spacing()).ok()?;
+    let n_probes = counts.x as usize * counts.y as usize * counts.z as usize;
+    if n_probes == 0 || sh.len() != n_probes * 4 || spacing.min_element() <= 0. {
+        return None;
+    }
+    Some(LightProbeGrid {
+        origin: get_world_position(world, id).unwrap_or_default()
+            + world.get(id, light_probe_grid_origin()).unwrap_or_default(),
+        spacing,
+        counts: *counts,
+        sh,
+    })
+}
@@ -0,0 +1,494 @@
+use std::sync::Arc;
+
+use ambient_gpu::{
+    gpu::{Gpu, GpuKey},
+    shader_module::{BindGroupDesc, ShaderModule},
+    std_assets::{DefaultNormalMapViewKey, DefaultSamplerKey, PixelTextureViewKey},
+    texture::TextureView,
+    texture_loaders::TextureFromUrl,
+    wgsl_utils::is_wgsl_identifier,
+};
+use ambient_std::{
+    asset_cache::{AssetCache, AsyncAssetKey, AsyncAssetKeyExt, SyncAssetKeyExt},
+    asset_url::{AbsAssetUrl, AssetUrl},
+    cb,
+    download_asset::{AssetError, JsonFromUrl},
+    friendly_id, include_file,
+};
+use anyhow::Context;
+use async_trait::async_trait;
+use glam::{uvec4, Vec4};
+use itertools::Itertools;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use wgpu::{util::DeviceExt, BindGroup};
+
+use super::super::{Material, MaterialShader, RendererShaderProducer, MATERIAL_BIND_GROUP};
+use crate::StandardShaderKey;
+
+pub const MAX_CUSTOM_MATERIAL_UNIFORMS: usize = 16;
+pub const MAX_CUSTOM_MATERIAL_TEXTURES: usize = 8;
+
+/// Names that are already used by the generated material shader
+const RESERVED_NAMES: &[&str] = &["material", "material_sampler", "get_material", "in", "out"];
+
+/// The texture that is bound to a slot when it has no URL
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DefaultTexture {
+    #[default]
+    White,
+    Black,
+    /// A flat normal map
+    Normal,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MaterialTextureSlot {
+    /// The name the texture is bound to in the shader.
+    pub name: String,
+    pub url: Option<AssetUrl>,
+    /// The texture to use if `url` is not set.
+    #[serde(default)]
+    pub default: DefaultTexture,
+    /// Whether the texture holds data rather than colors (e.g. normal maps), and should not be converted from sRGB.
+    #[serde(default)]
+    pub linear: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MaterialUniform {
+    /// The name of the uniform in the shader; it is available as `material.<name>`.
+    pub name: String,
+    /// The initial value of the uniform.
+    #[serde(default)]
+    pub value: Vec4,
+}
+
+/// A material definition: the shader, render state, texture slots and uniforms of a material.
+///
+/// The shader is WGSL that defines `fn get_material(in: MaterialInput) -> MaterialOutput`. Uniforms are `vec4<f32>`
+/// fields of `material`, and textures are `texture_2d<f32>`s that can be sampled with `material_sampler`.
+/// If no shader is set, the PBR shader is used, with the slots and uniforms of a [PbrMaterial](super::pbr_material::PbrMaterial).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct CustomMaterialDesc {
+    pub name: Option<String>,
+    pub shader: Option<AssetUrl>,
+    /// Whether the material ignores lighting.
+    pub unlit: bool,
+    pub transparent: Option<bool>,
+    pub double_sided: Option<bool>,
+    pub depth_write_enabled: Option<bool>,
+    pub textures: Vec<MaterialTextureSlot>,
+    pub uniforms: Vec<MaterialUniform>,
+}
+impl CustomMaterialDesc {
+    pub fn resolve(&self, base_url: &AbsAssetUrl) -> anyhow::Result<Self> {
+        let resolve = |url: &Option<AssetUrl>| -> anyhow::Result<Option<AssetUrl>> {
+            Ok(match url {
+                Some(url) => Some(url.resolve(base_url)?.into()),
+                None => None,
+            })
+        };
+        Ok(Self {
+            shader: resolve(&self.shader)?,
+            textures: self
+                .textures
+                .iter()
+                .map(|slot| {
+                    Ok(MaterialTextureSlot {
+                        url: resolve(&slot.url)?,
+                        ..slot.clone()
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?,
+            ..self.clone()
+        })
+    }
+    pub fn relative_path_from(&self, base_url: &AbsAssetUrl) -> Self {
+        let relative = |url: &Option<AssetUrl>| {
+            url.as_ref()
+                .map(|x| base_url.relative_path(x.path()).into())
+        };
+        Self {
+            shader: relative(&self.shader),
+            textures: self
+                .textures
+                .iter()
+                .map(|slot| MaterialTextureSlot {
+                    url: relative(&slot.url),
+                    ..slot.clone()
+                })
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// The texture slots and uniforms, including the defaults of the PBR shader if no shader is set
+    fn slots(&self) -> (Vec<MaterialTextureSlot>, Vec<MaterialUniform>) {
+        let (mut textures, mut uniforms) = (Vec::new(), Vec::new());
+        if self.shader.is_none() {
+            let texture = |name: &str, default, linear| MaterialTextureSlot {
+                name: name.to_string(),
+                url: None,
+                default,
+                linear,
+            };
+            textures = vec![
+                texture("base_color", DefaultTexture::White, false),
+                texture("normalmap", DefaultTexture::Normal, true),
+                texture("metallic_roughness", DefaultTexture::White, true),
+            ];
+            let uniform = |name: &str, value| MaterialUniform {
+                name: name.to_string(),
+                value,
+            };
+            uniforms = vec![
+                uniform("base_color_factor", Vec4::ONE),
+                uniform("emissive_factor", Vec4::ZERO),
+                uniform("alpha_cutoff", Vec4::splat(0.5)),
+                uniform("metallic", Vec4::ONE),
+                uniform("roughness", Vec4::ONE),
+            ];
+        }
+        for slot in &self.textures {
+            match textures.iter_mut().find(|x| x.name == slot.name) {
+                Some(existing) => *existing = slot.clone(),
+                None => textures.push(slot.clone()),
+            }
+        }
+        for value in &self.uniforms {
+            match uniforms.iter_mut().find(|x| x.name == value.name) {
+                Some(existing) => *existing = value.clone(),
+                None => uniforms.push(value.clone()),
+            }
+        }
+        (textures, uniforms)
+    }
+
+    fn validate(
+        textures: &[MaterialTextureSlot],
+        uniforms: &[MaterialUniform],
+    ) -> anyhow::Result<()> {
+        if uniforms.len() > MAX_CUSTOM_MATERIAL_UNIFORMS {
+            anyhow::bail!(
+                "At most {MAX_CUSTOM_MATERIAL_UNIFORMS} uniforms can be declared, but {} were",
+                uniforms.len()
+            );
+        }
+        if textures.len() > MAX_CUSTOM_MATERIAL_TEXTURES {
+            anyhow::bail!(
+                "At most {MAX_CUSTOM_MATERIAL_TEXTURES} textures can be declared, but {} were",
+                textures.len()
+            );
+        }
+        let names = textures
+            .iter()
+            .map(|x| &x.name)
+            .chain(uniforms.iter().map(|x| &x.name));
+        if let Some(name) = names.clone().duplicates().next() {
+            anyhow::bail!("{name:?} is declared more than once");
+        }
+        for name in names {
+            if !is_wgsl_identifier(name) {
+                anyhow::bail!("{name:?} is not a valid WGSL identifier");
+            }
+            if RESERVED_NAMES.contains(&name.as_str()) {
+                anyhow::bail!("{name:?} is reserved by the material shader");
+            }
+        }
+        Ok(())
+    }
+}
+
+fn get_custom_material_layout(texture_count: usize) -> BindGroupDesc<'static> {
+    let mut entries = vec![
+        wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        },
+        wgpu::BindGroupLayoutEntry {
+            binding: 1,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            count: None,
+        },
+    ];
+    for i in 0..texture_count {
+        entries.push(wgpu::BindGroupLayoutEntry {
+            binding: 2 + i as u32,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        });
+    }
+    BindGroupDesc {
+        label: MATERIAL_BIND_GROUP.into(),
+        entries,
+    }
+}
+
+/// A loaded material definition, which [CustomMaterial]s are instantiated from
+pub struct CustomMaterialDef {
+    pub desc: CustomMaterialDesc,
+    pub material_shader: Arc<MaterialShader>,
+    uniforms: Vec<MaterialUniform>,
+    textures: Vec<Arc<TextureView>>,
+}
+impl CustomMaterialDef {
+    /// Creates the renderer shader for materials of this definition
+    pub fn shader_producer(&self) -> RendererShaderProducer {
+        let material_shader = self.material_shader.clone();
+        let lit = !self.desc.unlit;
+        cb(move |assets, config| {
+            StandardShaderKey {
+                material_shader: material_shader.clone(),
+                lit,
+                shadow_cascades: config.shadow_cascades,
+            }
+            .get(assets)
+        })
+    }
+
+    /// Creates a new material with its own uniforms, which start at the definition's values
+    pub fn instantiate(&self, assets: &AssetCache) -> CustomMaterial {
+        let gpu = GpuKey.get(assets);
+        let layout = get_custom_material_layout(self.textures.len()).get(assets);
+        let values = self.uniforms.iter().map(|x| x.value).collect_vec();
+        // The params struct always has at least one field
+        let contents: &[Vec4] = if values.is_empty() {
+            &[Vec4::ZERO]
+        } else {
+            &values
+        };
+        let buffer = gpu
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("CustomMaterial.buffer"),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                contents: bytemuck::cast_slice(contents),
+            });
+        let sampler = DefaultSamplerKey.get(assets);
+
+        let mut entries = vec![
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(buffer.as_entire_buffer_binding()),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(&sampler),
+            },
+        ];
+        for (i, texture) in self.textures.iter().enumerate() {
+            entries.push(wgpu::BindGroupEntry {
+                binding: 2 + i as u32,
+                resource: wgpu::BindingResource::TextureView(&texture.handle),
+            });
+        }
+        let bind_group = gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &layout,
+            entries: &entries,
+            label: Some("CustomMaterial.bind_group"),
+        });
+
+        CustomMaterial {
+            gpu: gpu.clone(),
+            id: friendly_id(),
+            name: self
+                .desc
+                .name
+                .clone()
+                .unwrap_or_else(|| self.material_shader.id.clone()),
+            transparent: self.desc.transparent,
+            double_sided: self.desc.double_sided,
+            depth_write_enabled: self.desc.depth_write_enabled,
+            uniform_names: self.uniforms.iter().map(|x| x.name.clone()).collect(),
+            values: Mutex::new(values),
+            buffer,
+            bind_group,
+        }
+    }
+}
+impl std::fmt::Debug for CustomMaterialDef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CustomMaterialDef")
+            .field("id", &self.material_shader.id)
+            .field("desc", &self.desc)
+            .finish()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CustomMaterialFromUrl(pub AbsAssetUrl);
+#[async_trait]
+impl AsyncAssetKey<Result<Arc<CustomMaterialDef>, AssetError>> for CustomMaterialFromUrl {
+    async fn load(self, assets: AssetCache) -> Result<Arc<CustomMaterialDef>, AssetError> {
+        let desc = JsonFromUrl::<CustomMaterialDesc>::new(self.0.clone(), true)
+            .get(&assets)
+            .await?;
+        let desc = desc.resolve(&self.0)?;
+
+        let (slots, uniforms) = desc.slots();
+        CustomMaterialDesc::validate(&slots, &uniforms)?;
+
+        let body = match &desc.shader {
+            Some(url) => url
+                .clone()
+                .unwrap_abs()
+                .download_string(&assets)
+                .await
+                .with_context(|| format!("Failed to load material shader {url}"))?,
+            None => include_file!("custom_material_pbr.wgsl"),
+        };
+        let fields = if uniforms.is_empty() {
+            "    _unused: vec4<f32>,\n".to_string()
+        } else {
+            uniforms
+                .iter()
+                .map(|x| format!("    {}: vec4<f32>,\n", x.name))
+                .join("")
+        };
+        let bindings = slots
+            .iter()
+            .enumerate()
+            .map(|(i, slot)| {
+                format!(
+                    "@group(MATERIAL_BIND_GROUP)\n@binding({})\nvar {}: texture_2d<f32>;\n",
+                    2 + i,
+                    slot.name
+                )
+            })
+            .join("\n");
+        let source = format!(
+            "struct CustomMaterialParams {{\n{fields}}};\n\n\
+             @group(MATERIAL_BIND_GROUP)\n@binding(0)\nvar<uniform> material: CustomMaterialParams;\n\n\
+             @group(MATERIAL_BIND_GROUP)\n@binding(1)\nvar material_sampler: sampler;\n\n\
+             {bindings}\n{body}"
+        );
+
+        let mut textures = Vec::new();
+        for slot in &slots {
+            let view = match &slot.url {
+                Some(url) => {
+                    let format = if slot.linear {
+                        wgpu::TextureFormat::Rgba8Unorm
+                    } else {
+                        wgpu::TextureFormat::Rgba8UnormSrgb
+                    };
+                    Arc::new(
+                        TextureFromUrl {
+                            url: url.clone().unwrap_abs(),
+                            format,
+                        }
+                        .get(&assets)
+                        .await?
+                        .create_view(&Default::default()),
+                    )
+                }
+                None => match slot.default {
+                    DefaultTexture::White => PixelTextureViewKey::white().get(&assets),
+                    DefaultTexture::Black => PixelTextureViewKey {
+                        color: uvec4(0, 0, 0, 255),
+                    }
+                    .get(&assets),
+                    DefaultTexture::Normal => DefaultNormalMapViewKey.get(&assets),
+                },
+            };
+            textures.push(view);
+        }
+
+        let id = format!("custom_material_{}", self.0);
+        Ok(Arc::new(CustomMaterialDef {
+            material_shader: Arc::new(MaterialShader {
+                id: id.clone(),
+                shader: Arc::new(
+                    ShaderModule::new(id, source)
+                        .with_binding_desc(get_custom_material_layout(slots.len())),
+                ),
+            }),
+            desc,
+            uniforms,
+            textures,
+        }))
+    }
+}
+
+/// An instance of a [CustomMaterialDef], whose uniforms can be changed at runtime
+pub struct CustomMaterial {
+    gpu: Arc<Gpu>,
+    id: String,
+    name: String,
+    transparent: Option<bool>,
+    double_sided: Option<bool>,
+    depth_write_enabled: Option<bool>,
+    uniform_names: Vec<String>,
+    values: Mutex<Vec<Vec4>>,
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+impl CustomMaterial {
+    pub fn uniform_names(&self) -> &[String] {
+        &self.uniform_names
+    }
+    pub fn uniform(&self, name: &str) -> Option<Vec4> {
+        let index = self.uniform_names.iter().position(|x| x == name)?;
+        Some(self.values.lock()[index])
+    }
+    /// Sets the value of a uniform. Returns false if the material has no uniform with that name
+    pub fn set_uniform(&self, name: &str, value: Vec4) -> bool {
+        let index = match self.uniform_names.iter().position(|x| x == name) {
+            Some(index) => index,
+            None => return false,
+        };
+        let mut values = self.values.lock();
+        if values[index] != value {
+            values[index] = value;
+            self.gpu.queue.write_buffer(
+                &self.buffer,
+                (index * std::mem::size_of::<Vec4>()) as u64,
+                bytemuck::bytes_of(&value),
+            );
+        }
+        true
+    }
+}
+impl std::fmt::Debug for CustomMaterial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CustomMaterial")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .finish()
+    }
+}
+impl Material for CustomMaterial {
+    fn bind_group(&self) -> &BindGroup {
+        &self.bind_group
+    }
+    fn id(&self) -> &str {
+        &self.id
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn transparent(&self) -> Option<bool> {
+        self.transparent
+    }
+    fn double_sided(&self) -> Option<bool> {
+        self.double_sided
+    }
+    fn depth_write_enabled(&self) -> Option<bool> {
+        self.depth_write_enabled
+    }
+}
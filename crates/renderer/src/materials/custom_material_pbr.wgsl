@@ -0,0 +1,17 @@
+fn get_material(in: MaterialInput) -> MaterialOutput {
+    var out: MaterialOutput;
+    let base_color_sample = textureSample(base_color, material_sampler, in.texcoord);
+    let mr = textureSample(metallic_roughness, material_sampler, in.texcoord);
+    let color = base_color_sample * material.base_color_factor * get_entity_color_or(in.entity_loc, vec4<f32>(1., 1., 1., 1.));
+    out.opacity = color.a;
+    out.metallic = mr.r * material.metallic.x;
+    out.roughness = mr.g * material.roughness.x;
+
+    out.alpha_cutoff = material.alpha_cutoff.x;
+    out.base_color = color.rgb;
+    out.emissive_factor = material.emissive_factor.rgb;
+    out.shading = 1.;
+    let normal = textureSample(normalmap, material_sampler, in.texcoord).xyz * 2. - 1.;
+    out.normal = in.normal_matrix * normal;
+    return out;
+}
@@ -3,6 +3,7 @@ struct MeshBase {
     normal: vec3<f32>,
     tangent: vec3<f32>,
     texcoord0: vec2<f32>,
+    texcoord1: vec2<f32>,
 }
 
 struct MeshSkinned {
@@ -10,6 +11,12 @@ struct MeshSkinned {
     weights: vec4<f32>,
 }
 
+struct MeshMorph {
+    position: vec3<f32>,
+    normal: vec3<f32>,
+    tangent: vec3<f32>,
+}
+
 @group(GLOBALS_BIND_GROUP)
 @binding(MESH_BASE_BINDING)
 var<storage> mesh_base: array<MeshBase>;
@@ -22,6 +29,14 @@ var<storage> mesh_skinned: array<MeshSkinned>;
 @binding(SKINS_BINDING)
 var<storage> skins: Mat4x4Buffer;
 
+@group(GLOBALS_BIND_GROUP)
+@binding(MESH_MORPH_BINDING)
+var<storage> mesh_morph: array<MeshMorph>;
+
+@group(GLOBALS_BIND_GROUP)
+@binding(MORPH_WEIGHTS_BINDING)
+var<storage> morph_weights_buffer: array<f32>;
+
 
 fn get_raw_mesh_position(vertex_index: u32) -> vec3<f32> {
     return mesh_base[vertex_index].position.xyz;
@@ -38,3 +53,19 @@ fn get_mesh_base(mesh_id: u32, vertex_index: u32) -> MeshBase {
 fn get_mesh_skinned(mesh_id: u32, vertex_index: u32) -> MeshSkinned {
     return mesh_skinned[mesh_metadatas[mesh_id].skinned_offset + vertex_index];
 }
+
+/// Adds the morph targets of the mesh to `base`, weighted by the `weights_count` weights at `weights_offset`
+fn apply_mesh_morph(mesh_id: u32, vertex_index: u32, weights_offset: u32, weights_count: u32, base: MeshBase) -> MeshBase {
+    let metadata = mesh_metadatas[mesh_id];
+    var result = base;
+    for (var i = 0u; i < min(metadata.morph_target_count, weights_count); i++) {
+        let weight = morph_weights_buffer[weights_offset + i];
+        if weight != 0.0 {
+            let delta = mesh_morph[metadata.morph_offset + i * metadata.vertex_count + vertex_index];
+            result.position += delta.position * weight;
+            result.normal += delta.normal * weight;
+            result.tangent += delta.tangent * weight;
+        }
+    }
+    return result;
+}
@@ -4,6 +4,11 @@ struct MeshMetadata {
     index_offset: u32,
 
     index_count: u32,
+
+    morph_offset: u32,
+    morph_target_count: u32,
+    vertex_count: u32,
+    _padding: u32,
 };
 
 @group(GLOBALS_BIND_GROUP)
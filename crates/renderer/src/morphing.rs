@@ -0,0 +1,209 @@
+use std::{
+    ops::Range,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+};
+
+use ambient_core::{
+    asset_cache, gpu_components,
+    gpu_ecs::{GpuComponentFormat, GpuWorldSyncEvent, MappedComponentToGpuSystem},
+};
+use ambient_ecs::{
+    components, query, Commands, DynParallelSystem, ParallelFnSystem, QueryState, SystemAccess,
+    SystemGroup,
+};
+use ambient_gpu::{
+    gpu::{Gpu, GpuKey},
+    typed_buffer::TypedBuffer,
+};
+use ambient_std::asset_cache::{AssetCache, SyncAssetKey, SyncAssetKeyExt};
+use glam::vec4;
+use parking_lot::Mutex;
+
+pub use ambient_ecs::generated::components::core::rendering::morph_weights;
+
+components!("rendering", {
+    morph_weights_range: MorphWeightsRange,
+});
+gpu_components! {
+    morph_weights_range() => morph_weights: GpuComponentFormat::Vec4,
+}
+
+/// The location of an entity's morph weights in the [MorphWeightsBuffer]. Created for every entity with `morph_weights`.
+#[derive(Debug, Clone)]
+pub struct MorphWeightsRange(Arc<MorphWeightsAllocation>);
+impl MorphWeightsRange {
+    pub fn get_offset(&self) -> u32 {
+        self.0.offset.load(Ordering::SeqCst)
+    }
+    pub fn len(&self) -> u32 {
+        self.0.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.len == 0
+    }
+}
+
+#[derive(Debug)]
+struct MorphWeightsAllocation {
+    offset: AtomicU32,
+    len: u32,
+    // Notify the buffer to free this range on drop
+    to_remove: Arc<Mutex<Vec<Range<u32>>>>,
+}
+impl Drop for MorphWeightsAllocation {
+    fn drop(&mut self) {
+        let offset = self.offset.load(Ordering::SeqCst);
+        self.to_remove.lock().push(offset..offset + self.len);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MorphWeightsBufferKey;
+impl SyncAssetKey<Arc<Mutex<MorphWeightsBuffer>>> for MorphWeightsBufferKey {
+    fn load(&self, assets: AssetCache) -> Arc<Mutex<MorphWeightsBuffer>> {
+        let gpu = GpuKey.get(&assets);
+        Arc::new(Mutex::new(MorphWeightsBuffer::new(gpu)))
+    }
+}
+
+/// The morph weights of all entities, packed into a single storage buffer. Works like the
+/// [SkinsBuffer](crate::skinning::SkinsBuffer): weights are uploaded in one write per frame, and the ranges of dropped
+/// [MorphWeightsRange]s are re-used.
+pub struct MorphWeightsBuffer {
+    pub buffer: TypedBuffer<f32>,
+    data: Vec<f32>,
+    dirty: Option<Range<usize>>,
+    free_ranges: Vec<Range<u32>>,
+    to_remove: Arc<Mutex<Vec<Range<u32>>>>,
+}
+impl MorphWeightsBuffer {
+    fn new(gpu: Arc<Gpu>) -> Self {
+        Self {
+            buffer: TypedBuffer::new(
+                gpu,
+                "MorphWeightsBuffer.buffer",
+                1,
+                1,
+                wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_SRC
+                    | wgpu::BufferUsages::COPY_DST,
+            ),
+            // Storage buffers can't be empty
+            data: vec![0.],
+            dirty: None,
+            free_ranges: Vec::new(),
+            to_remove: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+    pub fn create(&mut self, size: u32) -> MorphWeightsRange {
+        self.reclaim();
+        let offset = match self
+            .free_ranges
+            .iter()
+            .position(|range| range.len() >= size as usize)
+        {
+            Some(index) => {
+                let range = &mut self.free_ranges[index];
+                let offset = range.start;
+                range.start += size;
+                if range.is_empty() {
+                    self.free_ranges.swap_remove(index);
+                }
+                offset
+            }
+            None => {
+                let offset = self.data.len() as u32;
+                self.data.resize(self.data.len() + size as usize, 0.);
+                self.buffer.resize(self.data.len() as u64, true);
+                offset
+            }
+        };
+        MorphWeightsRange(Arc::new(MorphWeightsAllocation {
+            offset: AtomicU32::new(offset),
+            len: size,
+            to_remove: self.to_remove.clone(),
+        }))
+    }
+    pub fn update(&mut self, range: &MorphWeightsRange, weights: &[f32]) {
+        let offset = range.get_offset() as usize;
+        let len = weights.len().min(range.len() as usize);
+        if len == 0 {
+            return;
+        }
+        self.data[offset..offset + len].copy_from_slice(&weights[..len]);
+        let range = offset..offset + len;
+        self.dirty = Some(match self.dirty.take() {
+            Some(dirty) => dirty.start.min(range.start)..dirty.end.max(range.end),
+            None => range,
+        });
+    }
+    /// Uploads all weights that were updated since the last flush
+    pub fn flush(&mut self) {
+        if let Some(dirty) = self.dirty.take() {
+            self.buffer.write(dirty.start as u64, &self.data[dirty]);
+        }
+    }
+    fn reclaim(&mut self) {
+        let to_remove = std::mem::take(&mut *self.to_remove.lock());
+        if to_remove.is_empty() {
+            return;
+        }
+        self.free_ranges.extend(to_remove);
+        self.free_ranges.sort_by_key(|x| x.start);
+        let mut merged: Vec<Range<u32>> = Vec::with_capacity(self.free_ranges.len());
+        for range in self.free_ranges.drain(..) {
+            match merged.last_mut() {
+                Some(last) if last.end == range.start => last.end = range.end,
+                _ => merged.push(range),
+            }
+        }
+        self.free_ranges = merged;
+    }
+}
+
+/// Uploads the `morph_weights` of every entity whose weights changed, allocating their range in the
+/// [MorphWeightsBuffer]. Only reads the world, so that it can run at the same time as
+/// [skinning_system](crate::skinning::skinning_system).
+pub fn morphing_system() -> DynParallelSystem {
+    let q = query(morph_weights().changed());
+    let mut qs = QueryState::new();
+    Box::new(ParallelFnSystem::new(
+        SystemAccess::new()
+            .read(asset_cache())
+            .read(morph_weights())
+            .write(morph_weights_range()),
+        move |world, _| {
+            let buffer_h = MorphWeightsBufferKey.get(world.resource(asset_cache()));
+            let mut buffer = buffer_h.lock();
+            let mut commands = Commands::new();
+            for (id, weights) in q.iter(world, Some(&mut qs)) {
+                let range = match world.get_ref(id, morph_weights_range()) {
+                    Ok(range) if range.len() as usize == weights.len() => range.clone(),
+                    _ => {
+                        let range = buffer.create(weights.len() as u32);
+                        commands.add_component(id, morph_weights_range(), range.clone());
+                        range
+                    }
+                };
+                buffer.update(&range, weights);
+            }
+            buffer.flush();
+            commands
+        },
+    ))
+}
+
+pub fn gpu_world_systems() -> SystemGroup<GpuWorldSyncEvent> {
+    SystemGroup::new(
+        "morphing/gpu_world",
+        vec![Box::new(MappedComponentToGpuSystem::new(
+            GpuComponentFormat::Vec4,
+            morph_weights_range(),
+            gpu_components::morph_weights(),
+            Box::new(|_, _, range| vec4(range.get_offset() as f32, range.len() as f32, 0.0, 0.0)),
+        ))],
+    )
+}
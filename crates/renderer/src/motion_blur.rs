@@ -0,0 +1,160 @@
+use ambient_core::{
+    camera::{get_active_camera, motion_blur, projection_view},
+    gpu_components,
+    gpu_ecs::{ComponentToGpuSystem, GpuComponentFormat, GpuWorldSyncEvent},
+    main_scene,
+    player::local_user_id,
+    transform::mesh_to_world,
+};
+use ambient_ecs::{components, query, EntityId, SystemGroup, World};
+use ambient_gpu::shader_module::ShaderModule;
+use ambient_std::{asset_cache::AssetCache, include_file};
+use glam::{Mat4, Vec2};
+use itertools::Itertools;
+
+use crate::{FullscreenPass, PassInput, PostProcessContext, PostProcessPass};
+
+components!("rendering", {
+    /// The [mesh_to_world] of the entity when the previous frame was rendered, which the forward pass writes the
+    /// velocity of the entity with
+    mesh_to_world_previous: Mat4,
+});
+
+gpu_components! {
+    mesh_to_world_previous() => mesh_to_world_previous: GpuComponentFormat::Mat4,
+}
+
+/// The longest blur, in pixels of a 1080p image
+const MAX_BLUR_LENGTH: f32 = 48.;
+const MAX_SAMPLES: u32 = 16;
+
+/// The active camera of the main scene, if it has [motion_blur]
+fn active_motion_blur_camera(world: &World) -> Option<EntityId> {
+    let camera = get_active_camera(world, main_scene(), world.resource_opt(local_user_id()))?;
+    world.has_component(camera, motion_blur()).then_some(camera)
+}
+
+/// Whether the active camera of `world` asks for [MotionBlur]
+pub fn motion_blur_enabled(world: &World) -> bool {
+    active_motion_blur_camera(world).is_some()
+}
+
+/// Keeps [mesh_to_world_previous] one frame behind [mesh_to_world]. Runs before the transforms are updated.
+pub fn systems() -> SystemGroup {
+    SystemGroup::new(
+        "motion_blur",
+        vec![
+            query(mesh_to_world())
+                .excl(mesh_to_world_previous())
+                .to_system(|q, world, qs, _| {
+                    for (id, transform) in q.collect_cloned(world, qs) {
+                        world
+                            .add_component(id, mesh_to_world_previous(), transform)
+                            .ok();
+                    }
+                }),
+            query((mesh_to_world_previous(), mesh_to_world())).to_system(|q, world, qs, _| {
+                let moved = q
+                    .iter(world, qs)
+                    .filter(|(_, (previous, transform))| previous != transform)
+                    .map(|(id, (_, &transform))| (id, transform))
+                    .collect_vec();
+                for (id, transform) in moved {
+                    world.set(id, mesh_to_world_previous(), transform).ok();
+                }
+            }),
+        ],
+    )
+}
+
+pub fn gpu_world_systems() -> SystemGroup<GpuWorldSyncEvent> {
+    SystemGroup::new(
+        "motion_blur/gpu_world_update",
+        vec![Box::new(ComponentToGpuSystem::new(
+            GpuComponentFormat::Mat4,
+            mesh_to_world_previous(),
+            gpu_components::mesh_to_world_previous(),
+        ))],
+    )
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct MotionBlurParams {
+    inv_projection_view: Mat4,
+    previous_projection_view: Mat4,
+    resolution: Vec2,
+    /// The part of the motion of a frame that is blurred over
+    shutter: f32,
+    max_blur_length: f32,
+    max_samples: u32,
+    _padding: [u32; 3],
+}
+
+/// Motion blur: blurs every pixel along the motion of its surface on the screen since the previous frame, for the
+/// shutter angle in [motion_blur]. The motion is read from the velocity buffer of the [RenderTarget], so objects that
+/// move on their own are blurred too; the background didn't write to it, so its motion is found by reprojecting it with
+/// the projection-view of the active camera in the previous frame. The blur is clamped to a maximum length, and takes
+/// at most one sample per pixel it covers.
+///
+/// Runs when the active camera has [motion_blur] (see [motion_blur_enabled]).
+pub struct MotionBlur {
+    pass: FullscreenPass,
+    /// The camera and its projection-view in the previous frame
+    previous: Option<(EntityId, Mat4)>,
+}
+impl MotionBlur {
+    pub fn new(assets: &AssetCache, format: wgpu::TextureFormat) -> Self {
+        Self {
+            pass: FullscreenPass::new(
+                assets,
+                "MotionBlur",
+                ShaderModule::new("motion_blur", include_file!("motion_blur.wgsl")),
+                format,
+                std::mem::size_of::<MotionBlurParams>(),
+                &[PassInput::Depth, PassInput::Velocity],
+            ),
+            previous: None,
+        }
+    }
+}
+impl PostProcessPass for MotionBlur {
+    /// Blurs the color buffer of the target, which holds the rendered scene, its depth and its velocity
+    fn render(&mut self, encoder: &mut wgpu::CommandEncoder, context: &PostProcessContext) {
+        let world = context.world;
+        let color_buffer = &context.target.color_buffer;
+        let Some((camera, projection_view)) = active_motion_blur_camera(world)
+            .and_then(|camera| Some((camera, world.get(camera, projection_view()).ok()?)))
+        else {
+            self.previous = None;
+            return;
+        };
+        // There is no motion to blur in the first frame of a camera
+        let previous_projection_view = match self.previous {
+            Some((previous_camera, previous)) if previous_camera == camera => previous,
+            _ => projection_view,
+        };
+        self.previous = Some((camera, projection_view));
+
+        let shutter_angle = world.get(camera, motion_blur()).unwrap_or(180.);
+        let params = MotionBlurParams {
+            inv_projection_view: projection_view.inverse(),
+            previous_projection_view,
+            resolution: Vec2::new(
+                color_buffer.size.width as f32,
+                color_buffer.size.height as f32,
+            ),
+            shutter: shutter_angle.clamp(0., 360.) / 360.,
+            max_blur_length: MAX_BLUR_LENGTH * color_buffer.size.height as f32 / 1080.,
+            max_samples: MAX_SAMPLES,
+            _padding: [0; 3],
+        };
+        self.pass
+            .render(encoder, context, bytemuck::bytes_of(&params));
+    }
+
+    /// Forgets the motion of the camera, as it may have moved anywhere while the blur was off
+    fn reset(&mut self) {
+        self.previous = None;
+    }
+}
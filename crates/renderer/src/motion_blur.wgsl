@@ -0,0 +1,70 @@
+struct MotionBlurParams {
+    inv_projection_view: mat4x4<f32>,
+    previous_projection_view: mat4x4<f32>,
+    resolution: vec2<f32>,
+    shutter: f32,
+    max_blur_length: f32,
+    max_samples: u32,
+    _padding0: u32,
+    _padding1: u32,
+    _padding2: u32,
+};
+
+@group(0)
+@binding(0)
+var<uniform> params: MotionBlurParams;
+@group(0)
+@binding(1)
+var scene_texture: texture_2d<f32>;
+@group(0)
+@binding(2)
+var depth_texture: texture_depth_2d;
+@group(0)
+@binding(3)
+var velocity_texture: texture_2d<f32>;
+
+/// How far the surface at `pixel` moved on the screen since the previous frame, in pixels
+fn velocity(pixel: vec2<i32>) -> vec2<f32> {
+    let depth = textureLoad(depth_texture, pixel, 0);
+    // Everything that was drawn wrote its motion to the velocity buffer, in UV units
+    if depth > 0.0 {
+        return textureLoad(velocity_texture, pixel, 0).xy * params.resolution;
+    }
+    let uv = (vec2<f32>(pixel) + 0.5) / params.resolution;
+    let ndc = vec2<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0);
+    // The background is at a depth of 0, as the depth is reversed, which is a point at infinity in world space. It's
+    // kept homogeneous, so that it still moves with the rotation of the camera.
+    let world = params.inv_projection_view * vec4<f32>(ndc, depth, 1.0);
+    let previous = params.previous_projection_view * world;
+    if previous.w <= 0.0 {
+        return vec2<f32>(0.0);
+    }
+    let previous_ndc = previous.xy / previous.w;
+    return (ndc - previous_ndc) * vec2<f32>(0.5, -0.5) * params.resolution;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let pixel = vec2<i32>(in.position.xy);
+    let color = textureLoad(scene_texture, pixel, 0);
+
+    var blur = velocity(pixel) * params.shutter;
+    let blur_length = length(blur);
+    if blur_length < 0.5 {
+        return color;
+    }
+    if blur_length > params.max_blur_length {
+        blur *= params.max_blur_length / blur_length;
+    }
+
+    // The blur is centered on the pixel, so that it covers the motion before and after the middle of the frame
+    let samples = clamp(u32(min(blur_length, params.max_blur_length)), 2u, params.max_samples);
+    let max_pixel = vec2<i32>(params.resolution) - vec2<i32>(1, 1);
+    var sum = vec3<f32>(0.0);
+    for (var i = 0u; i < samples; i++) {
+        let t = (f32(i) + 0.5) / f32(samples) - 0.5;
+        let sample_pixel = clamp(pixel + vec2<i32>(round(blur * t)), vec2<i32>(0, 0), max_pixel);
+        sum += textureLoad(scene_texture, sample_pixel, 0).rgb;
+    }
+    return vec4<f32>(sum / f32(samples), color.a);
+}
@@ -5,7 +5,7 @@ use ambient_core::{
     gpu_ecs::{ComponentToGpuSystem, GpuComponentFormat, GpuWorldSyncEvent},
     hierarchy::children,
 };
-use ambient_ecs::{query, ArchetypeFilter, Component, SystemGroup, World};
+use ambient_ecs::{components, query, ArchetypeFilter, Component, EntityId, SystemGroup, World};
 use ambient_gpu::{
     gpu::{Gpu, GpuKey},
     mesh_buffer::MeshBuffer,
@@ -16,6 +16,7 @@ use ambient_std::{
     asset_cache::{AssetCache, SyncAssetKeyExt},
     include_file,
 };
+use glam::Vec4;
 use wgpu::{BindGroupLayoutEntry, BindingType, PrimitiveTopology, ShaderStages};
 
 use super::{
@@ -24,19 +25,36 @@ use super::{
 };
 use crate::{bind_groups::BindGroups, RendererConfig};
 
-pub use ambient_ecs::generated::components::core::rendering::{outline, outline_recursive};
+pub use ambient_ecs::generated::components::core::rendering::{
+    outline, outline_recursive, outline_thickness,
+};
+
+components!("rendering", {
+    /// The color of the outline in rgb, and its thickness in w
+    gpu_outline: Vec4,
+});
 
 gpu_components! {
     outline() => outline: GpuComponentFormat::Vec4,
 }
 
+/// The thickness of outlines, in pixels, of entities without `outline_thickness`
+pub const DEFAULT_OUTLINE_THICKNESS: f32 = 3.;
+/// The largest `outline_thickness` that is rendered
+pub const MAX_OUTLINE_THICKNESS: f32 = 32.;
+
 pub struct OutlinesConfig {
     pub scene: Component<()>,
     pub renderer_resources: RendererResources,
 }
 
+/// Draws outlines around entities with `outline`. The entities are rendered to a texture, and the closest entity pixel to
+/// each pixel is found with jump flooding, so that outlines can be thick without sampling every pixel within reach.
 pub struct Outlines {
     outlines: Arc<Texture>,
+    seeds: [Arc<Texture>; 2],
+    seed_pipeline: GraphicsPipeline,
+    jump_flood_pipeline: GraphicsPipeline,
     pipeline: GraphicsPipeline,
     renderer: TreeRenderer,
     collect_state: RendererCollectState,
@@ -47,17 +65,18 @@ pub struct Outlines {
 const OUTLINES_BIND_GROUP: &str = "OUTLINES_BIND_GROUP";
 
 fn get_outlines_layout() -> BindGroupDesc<'static> {
+    let texture = |binding| BindGroupLayoutEntry {
+        binding,
+        visibility: ShaderStages::FRAGMENT,
+        ty: BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        },
+        count: None,
+    };
     BindGroupDesc {
-        entries: vec![BindGroupLayoutEntry {
-            binding: 0,
-            visibility: ShaderStages::FRAGMENT,
-            ty: BindingType::Texture {
-                sample_type: wgpu::TextureSampleType::Float { filterable: false },
-                view_dimension: wgpu::TextureViewDimension::D2,
-                multisampled: false,
-            },
-            count: None,
-        }],
+        entries: vec![texture(0), texture(1)],
         label: OUTLINES_BIND_GROUP.into(),
     }
 }
@@ -82,32 +101,51 @@ impl Outlines {
         let pipeline = shader.to_pipeline(
             &gpu,
             GraphicsPipelineInfo {
-                targets: &[Some(gpu.swapchain_format().into())],
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.swapchain_format(),
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::all(),
+                })],
+                topology: PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+        );
+        let seed_pipeline = shader.to_pipeline(
+            &gpu,
+            GraphicsPipelineInfo {
+                fs_main: "fs_seed_main",
+                targets: &[Some(Self::SEEDS_FORMAT.into())],
+                topology: PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+        );
+        let jump_flood_pipeline = shader.to_pipeline(
+            &gpu,
+            GraphicsPipelineInfo {
+                fs_main: "fs_jump_flood_main",
+                targets: &[Some(Self::SEEDS_FORMAT.into())],
                 topology: PrimitiveTopology::TriangleStrip,
                 ..Default::default()
             },
         );
 
+        let size = wgpu::Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 1,
+        };
         Self {
-            outlines: Self::create_outline_texture(
-                gpu.clone(),
-                wgpu::Extent3d {
-                    width: 1,
-                    height: 1,
-                    depth_or_array_layers: 1,
-                },
-            ),
+            outlines: Self::create_texture(gpu.clone(), size, Self::FORMAT),
+            seeds: [(); 2].map(|_| Self::create_texture(gpu.clone(), size, Self::SEEDS_FORMAT)),
+            seed_pipeline,
+            jump_flood_pipeline,
             pipeline,
             collect_state: RendererCollectState::new(assets),
             renderer: TreeRenderer::new(TreeRendererConfig {
                 gpu: gpu.clone(),
                 assets: assets.clone(),
                 renderer_config,
-                targets: vec![Some(wgpu::ColorTargetState {
-                    format: Outlines::FORMAT,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::all(),
-                })],
+                targets: vec![Some(Outlines::FORMAT.into())],
                 filter: ArchetypeFilter::new().incl(config.scene).incl(outline()),
                 renderer_resources: config.renderer_resources.clone(),
                 fs_main: FSMain::Outline,
@@ -122,8 +160,13 @@ impl Outlines {
     }
 
     pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba32Float;
+    const SEEDS_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rg32Float;
 
-    fn create_outline_texture(gpu: Arc<Gpu>, size: wgpu::Extent3d) -> Arc<Texture> {
+    fn create_texture(
+        gpu: Arc<Gpu>,
+        size: wgpu::Extent3d,
+        format: wgpu::TextureFormat,
+    ) -> Arc<Texture> {
         Arc::new(Texture::new(
             gpu,
             &wgpu::TextureDescriptor {
@@ -132,7 +175,7 @@ impl Outlines {
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: wgpu::TextureDimension::D2,
-                format: Self::FORMAT,
+                format,
                 usage: wgpu::TextureUsages::RENDER_ATTACHMENT
                     | wgpu::TextureUsages::TEXTURE_BINDING,
                 view_formats: &[],
@@ -152,9 +195,12 @@ impl Outlines {
         let bind_group_layout = self.pipeline.pipeline().get_bind_group_layout(0);
 
         if self.outlines.size != target.size() {
-            self.outlines = Self::create_outline_texture(self.gpu.clone(), target.size());
+            self.outlines = Self::create_texture(self.gpu.clone(), target.size(), Self::FORMAT);
+            self.seeds = [(); 2]
+                .map(|_| Self::create_texture(self.gpu.clone(), target.size(), Self::SEEDS_FORMAT));
         }
         let outlines = self.outlines.create_view(&Default::default());
+        let seeds = [0, 1].map(|i| self.seeds[i].create_view(&Default::default()));
 
         self.collect_state.set_camera(0);
         self.renderer.update(world);
@@ -193,34 +239,78 @@ impl Outlines {
             }
         }
 
-        let bind_group = self
-            .gpu
-            .device
-            .create_bind_group(&wgpu::BindGroupDescriptor {
-                layout: &bind_group_layout,
-                entries: &[wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&outlines),
-                }],
-                label: None,
+        let bind_group = |seeds: &wgpu::TextureView| {
+            self.gpu
+                .device
+                .create_bind_group(&wgpu::BindGroupDescriptor {
+                    layout: &bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(&outlines),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::TextureView(seeds),
+                        },
+                    ],
+                    label: None,
+                })
+        };
+        let fullscreen_pass = |encoder: &mut wgpu::CommandEncoder,
+                               view: &wgpu::TextureView,
+                               load: wgpu::LoadOp<wgpu::Color>,
+                               pipeline: &GraphicsPipeline,
+                               bind_group: &wgpu::BindGroup,
+                               step: u32| {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Outlines"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations { load, store: true },
+                })],
+                depth_stencil_attachment: None,
             });
+            rpass.set_pipeline(pipeline.pipeline());
+            rpass.set_bind_group(0, bind_group, &[]);
+            rpass.draw(0..4, step..step + 1);
+        };
 
-        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: None,
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: target.color(),
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Load,
-                    store: true,
-                },
-            })],
-            depth_stencil_attachment: None,
-        });
+        // Seed the pixels covered by outlined entities, then flood them outwards with halving steps. The seeds are
+        // ping-ponged between the two textures, as a pass can't read the texture it writes to.
+        let clear = wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT);
+        fullscreen_pass(
+            encoder,
+            &seeds[0],
+            clear,
+            &self.seed_pipeline,
+            &bind_group(&seeds[1]),
+            0,
+        );
+        let mut current = 0;
+        let mut step = MAX_OUTLINE_THICKNESS as u32;
+        while step >= 1 {
+            fullscreen_pass(
+                encoder,
+                &seeds[1 - current],
+                clear,
+                &self.jump_flood_pipeline,
+                &bind_group(&seeds[current]),
+                step,
+            );
+            current = 1 - current;
+            step /= 2;
+        }
 
-        rpass.set_pipeline(self.pipeline.pipeline());
-        rpass.set_bind_group(0, &bind_group, &[]);
-        rpass.draw(0..4, 0..1);
+        fullscreen_pass(
+            encoder,
+            target.color(),
+            wgpu::LoadOp::Load,
+            &self.pipeline,
+            &bind_group(&seeds[current]),
+            0,
+        );
     }
     pub fn dump(&self, f: &mut dyn std::io::Write) {
         self.renderer.dump(f);
@@ -259,16 +349,59 @@ pub fn systems() -> SystemGroup {
                         }
                     }
                 }),
+            query((outline_recursive(), outline_thickness(), children()))
+                .optional_changed(outline_thickness())
+                .optional_changed(children())
+                .to_system(|q, world, qs, _| {
+                    for (_, (_, thickness, childs)) in q.collect_cloned(world, qs) {
+                        for c in childs {
+                            world.add_component(c, outline_thickness(), thickness).ok();
+                        }
+                    }
+                }),
+            query(outline())
+                .optional_changed(outline())
+                .optional_changed(outline_thickness())
+                .to_system(|q, world, qs, _| {
+                    for (id, _) in q.collect_cloned(world, qs) {
+                        update_gpu_outline(world, id);
+                    }
+                }),
+            query(outline_thickness())
+                .despawned()
+                .to_system(|q, world, qs, _| {
+                    for (id, _) in q.collect_cloned(world, qs) {
+                        update_gpu_outline(world, id);
+                    }
+                }),
+            query(outline()).despawned().to_system(|q, world, qs, _| {
+                for (id, _) in q.collect_cloned(world, qs) {
+                    world.remove_component(id, gpu_outline()).ok();
+                }
+            }),
         ],
     )
 }
 
+fn update_gpu_outline(world: &mut World, id: EntityId) {
+    let Ok(color) = world.get(id, outline()) else {
+        return;
+    };
+    let thickness = world
+        .get(id, outline_thickness())
+        .unwrap_or(DEFAULT_OUTLINE_THICKNESS)
+        .clamp(0., MAX_OUTLINE_THICKNESS);
+    world
+        .add_component(id, gpu_outline(), color.truncate().extend(thickness))
+        .ok();
+}
+
 pub fn gpu_world_systems() -> SystemGroup<GpuWorldSyncEvent> {
     SystemGroup::new(
         "outlines/gpu_world_update",
         vec![Box::new(ComponentToGpuSystem::new(
             GpuComponentFormat::Vec4,
-            outline(),
+            gpu_outline(),
             gpu_components::outline(),
         ))],
     )
@@ -1,10 +1,12 @@
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) tex_coords: vec2<f32>,
+    @location(1) @interpolate(flat) step: i32,
 };
 
+// The jump flood step is passed as the instance index, so that each pass doesn't need its own uniform buffer
 @vertex
-fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+fn vs_main(@builtin(vertex_index) vertex_index: u32, @builtin(instance_index) instance_index: u32) -> VertexOutput {
     var out: VertexOutput;
     let x = i32(vertex_index) / 2;
     let y = i32(vertex_index) & 1;
@@ -19,26 +21,72 @@ fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
         1.0
     );
     out.tex_coords = tc;
+    out.step = i32(instance_index);
     return out;
 }
 
+// The outlined entities; rgb is the color of the outline, and a its thickness in pixels (0 where there is no entity)
 @group(OUTLINES_BIND_GROUP)
 @binding(0)
 var r_color: texture_2d<f32>;
 
+// The closest outlined pixel to each pixel, or -1 if none has been found
+@group(OUTLINES_BIND_GROUP)
+@binding(1)
+var r_seeds: texture_2d<f32>;
+
+fn pixel(in: VertexOutput) -> vec2<i32> {
+    return vec2<i32>(in.tex_coords * vec2<f32>(textureDimensions(r_color)));
+}
+
+@fragment
+fn fs_seed_main(in: VertexOutput) -> @location(0) vec2<f32> {
+    let p = pixel(in);
+    if textureLoad(r_color, p, 0).a > 0. {
+        return vec2<f32>(p);
+    }
+    return vec2<f32>(-1.);
+}
+
+@fragment
+fn fs_jump_flood_main(in: VertexOutput) -> @location(0) vec2<f32> {
+    let p = pixel(in);
+    let size = vec2<i32>(textureDimensions(r_seeds));
+    var best = vec2<f32>(-1.);
+    var best_distance = 3.402823e+38;
+    for (var y = -1; y <= 1; y += 1) {
+        for (var x = -1; x <= 1; x += 1) {
+            let q = p + vec2<i32>(x, y) * in.step;
+            if any(q < vec2<i32>(0)) || any(q >= size) {
+                continue;
+            }
+            let seed = textureLoad(r_seeds, q, 0).xy;
+            if seed.x < 0. {
+                continue;
+            }
+            let d = distance(seed, vec2<f32>(p));
+            if d < best_distance {
+                best = seed;
+                best_distance = d;
+            }
+        }
+    }
+    return best;
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let p = vec2<i32>(in.tex_coords * vec2<f32>(textureDimensions(r_color)));
-    let center_color = textureLoad(r_color, p, 0);
-    let center = center_color.a > 0.5;
-    let size = 3;
-    let top = textureLoad(r_color, p + vec2<i32>(0, -size), 0).a > 0.5;
-    let bottom = textureLoad(r_color, p + vec2<i32>(0, size), 0).a > 0.5;
-    let left = textureLoad(r_color, p + vec2<i32>(-size, 0), 0).a > 0.5;
-    let right = textureLoad(r_color, p + vec2<i32>(size, 0), 0).a > 0.5;
-    if !(center && (!top || !bottom || !left || !right)) {
+    let p = pixel(in);
+    let seed = textureLoad(r_seeds, p, 0).xy;
+    // The outline is drawn around the entities, so they remain visible
+    if seed.x < 0. || textureLoad(r_color, p, 0).a > 0. {
+        discard;
+    }
+    let outline = textureLoad(r_color, vec2<i32>(seed), 0);
+    let d = distance(seed, vec2<f32>(p));
+    if d > outline.a {
         discard;
     }
 
-    return vec4<f32>(center_color.rgb, 1.);
+    return vec4<f32>(outline.rgb, clamp(outline.a + 0.5 - d, 0., 1.));
 }
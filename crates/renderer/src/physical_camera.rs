@@ -0,0 +1,144 @@
+use ambient_core::{
+    camera::{
+        aperture, focus_distance, fovy, get_active_camera, iso, orthographic, projection,
+        shutter_speed,
+    },
+    main_scene,
+    player::local_user_id,
+};
+use ambient_ecs::{EntityId, World};
+use ambient_gpu::shader_module::ShaderModule;
+use ambient_std::{asset_cache::AssetCache, include_file};
+use glam::{Mat4, Vec2};
+
+use crate::{FullscreenPass, PassInput, PostProcessContext, PostProcessPass};
+
+/// The exposure the scene lighting is made for: f/16, 1/125 s and ISO 100, the "sunny 16" rule
+const DEFAULT_APERTURE: f32 = 16.;
+const DEFAULT_SHUTTER_SPEED: f32 = 1. / 125.;
+const DEFAULT_ISO: f32 = 100.;
+/// The height of a 35mm film frame, in meters, which turns the field of view into a focal length
+const SENSOR_HEIGHT: f32 = 0.024;
+/// The largest blur radius, in pixels of a 1080p image
+const MAX_BLUR_RADIUS: f32 = 16.;
+const BOKEH_SAMPLES: u32 = 48;
+
+/// The active camera of the main scene, if it has a [focus_distance]
+fn active_focused_camera(world: &World) -> Option<EntityId> {
+    let camera = get_active_camera(world, main_scene(), world.resource_opt(local_user_id()))?;
+    world
+        .has_component(camera, focus_distance())
+        .then_some(camera)
+}
+
+/// Whether the active camera of `world` asks for [PhysicalCamera]
+pub fn physical_camera_enabled(world: &World) -> bool {
+    active_focused_camera(world).is_some()
+}
+
+/// The exposure multiplier of a camera with these settings, relative to the default exposure
+fn exposure(aperture: f32, shutter_speed: f32, iso: f32) -> f32 {
+    let ev100 = |aperture: f32, shutter_speed: f32, iso: f32| {
+        (aperture * aperture / shutter_speed * 100. / iso).log2()
+    };
+    (ev100(DEFAULT_APERTURE, DEFAULT_SHUTTER_SPEED, DEFAULT_ISO)
+        - ev100(aperture, shutter_speed, iso))
+    .exp2()
+}
+
+fn camera_aperture(world: &World, camera: EntityId) -> f32 {
+    world
+        .get(camera, aperture())
+        .unwrap_or(DEFAULT_APERTURE)
+        .max(0.5)
+}
+
+/// The exposure multiplier of `camera` from its [aperture], [shutter_speed] and [iso]; the shading multiplies the light
+/// of the scene by it, before it is clamped to the color buffer
+pub(crate) fn camera_exposure(world: &World, camera: EntityId) -> f32 {
+    let shutter_speed = world
+        .get(camera, shutter_speed())
+        .unwrap_or(DEFAULT_SHUTTER_SPEED)
+        .max(1e-6);
+    let iso = world.get(camera, iso()).unwrap_or(DEFAULT_ISO).max(1.);
+    exposure(camera_aperture(world, camera), shutter_speed, iso)
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct PhysicalCameraParams {
+    inv_projection: Mat4,
+    resolution: Vec2,
+    focus_distance: f32,
+    /// The blur radius in pixels of something infinitely far away, or 0 without a depth of field
+    blur_scale: f32,
+    max_blur_radius: f32,
+    samples: u32,
+    _padding: [u32; 2],
+}
+
+/// The depth of field of a physical camera: blurs the scene around the [focus_distance] of the active camera with a
+/// bokeh gather over the depth buffer, as much as its [aperture] does. The exposure of the camera is applied by the
+/// shading instead, see [camera_exposure].
+///
+/// Runs when the active camera has a [focus_distance] (see [physical_camera_enabled]).
+pub struct PhysicalCamera {
+    pass: FullscreenPass,
+}
+impl PhysicalCamera {
+    pub fn new(assets: &AssetCache, format: wgpu::TextureFormat) -> Self {
+        Self {
+            pass: FullscreenPass::new(
+                assets,
+                "PhysicalCamera",
+                ShaderModule::new("physical_camera", include_file!("physical_camera.wgsl")),
+                format,
+                std::mem::size_of::<PhysicalCameraParams>(),
+                &[PassInput::Depth],
+            ),
+        }
+    }
+}
+impl PostProcessPass for PhysicalCamera {
+    /// Blurs the color buffer of the target, which holds the rendered scene and its depth
+    fn render(&mut self, encoder: &mut wgpu::CommandEncoder, context: &PostProcessContext) {
+        let world = context.world;
+        let color_buffer = &context.target.color_buffer;
+        let Some((camera, projection)) = active_focused_camera(world)
+            .and_then(|camera| Some((camera, world.get(camera, projection()).ok()?)))
+        else {
+            return;
+        };
+        let resolution = Vec2::new(
+            color_buffer.size.width as f32,
+            color_buffer.size.height as f32,
+        );
+
+        let aperture = camera_aperture(world, camera);
+
+        // The thin lens model: the circle of confusion of something at distance `d` has a diameter of
+        // `f² / (N (S - f)) * |1 - S / d|` on the sensor, for a focal length `f` and focus distance `S`
+        let focus = world.get(camera, focus_distance()).ok();
+        let (focus_distance, blur_scale) = match (focus, world.get(camera, fovy())) {
+            (Some(focus), Ok(fovy)) if !world.has_component(camera, orthographic()) => {
+                let focal_length = SENSOR_HEIGHT / (2. * (fovy / 2.).tan());
+                let focus = focus.max(2. * focal_length);
+                let diameter = focal_length * focal_length / (aperture * (focus - focal_length));
+                (focus, 0.5 * diameter / SENSOR_HEIGHT * resolution.y)
+            }
+            _ => (0., 0.),
+        };
+
+        let params = PhysicalCameraParams {
+            inv_projection: projection.inverse(),
+            resolution,
+            focus_distance,
+            blur_scale,
+            max_blur_radius: MAX_BLUR_RADIUS * resolution.y / 1080.,
+            samples: BOKEH_SAMPLES,
+            _padding: [0; 2],
+        };
+        self.pass
+            .render(encoder, context, bytemuck::bytes_of(&params));
+    }
+}
@@ -0,0 +1,72 @@
+struct PhysicalCameraParams {
+    inv_projection: mat4x4<f32>,
+    resolution: vec2<f32>,
+    focus_distance: f32,
+    blur_scale: f32,
+    max_blur_radius: f32,
+    samples: u32,
+    _padding0: u32,
+    _padding1: u32,
+};
+
+@group(0)
+@binding(0)
+var<uniform> params: PhysicalCameraParams;
+@group(0)
+@binding(1)
+var scene_texture: texture_2d<f32>;
+@group(0)
+@binding(2)
+var depth_texture: texture_depth_2d;
+
+fn clamp_pixel(pixel: vec2<i32>) -> vec2<i32> {
+    return clamp(pixel, vec2<i32>(0, 0), vec2<i32>(params.resolution) - vec2<i32>(1, 1));
+}
+
+/// The view space distance along the view direction of what was drawn at `pixel`
+fn view_distance(pixel: vec2<i32>) -> f32 {
+    let depth = textureLoad(depth_texture, pixel, 0);
+    // The depth is reversed, so nothing was drawn here
+    if depth <= 0.0 {
+        return 1e10;
+    }
+    let uv = (vec2<f32>(pixel) + 0.5) / params.resolution;
+    let p = params.inv_projection * vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, depth, 1.0);
+    return p.z / p.w;
+}
+
+/// The radius in pixels of the circle of confusion of something at the view distance `z`
+fn blur_radius(z: f32) -> f32 {
+    return min(params.blur_scale * abs(1.0 - params.focus_distance / z), params.max_blur_radius);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let pixel = vec2<i32>(in.position.xy);
+    let color = textureLoad(scene_texture, pixel, 0);
+    if params.blur_scale <= 0.0 {
+        return color;
+    }
+
+    let pixel_distance = view_distance(pixel);
+    let radius = blur_radius(pixel_distance);
+    // Gathers the samples of a disk as large as the largest blur, in a golden angle spiral. Every sample covers this
+    // pixel if its own circle of confusion reaches it, except that what is behind this pixel can't spread further
+    // over it than this pixel is blurred, so that sharp foregrounds don't bleed into blurry backgrounds.
+    var sum = color.rgb;
+    var weight = 1.0;
+    for (var i = 0u; i < params.samples; i++) {
+        let r = params.max_blur_radius * sqrt((f32(i) + 0.5) / f32(params.samples));
+        let angle = f32(i) * 2.3999632;
+        let sample_pixel = clamp_pixel(pixel + vec2<i32>(round(vec2<f32>(cos(angle), sin(angle)) * r)));
+        let sample_distance = view_distance(sample_pixel);
+        var sample_radius = blur_radius(sample_distance);
+        if sample_distance > pixel_distance {
+            sample_radius = min(sample_radius, radius);
+        }
+        let w = clamp(sample_radius - r + 1.0, 0.0, 1.0);
+        sum += textureLoad(scene_texture, sample_pixel, 0).rgb * w;
+        weight += w;
+    }
+    return vec4<f32>(sum / weight, color.a);
+}
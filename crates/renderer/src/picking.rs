@@ -0,0 +1,287 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use ambient_ecs::{ArchetypeFilter, EntityId, World};
+use ambient_gpu::{
+    gpu::Gpu,
+    mesh_buffer::MeshBuffer,
+    shader_module::DEPTH_FORMAT,
+    texture::{Texture, TextureView},
+};
+use ambient_std::math::interpolate;
+use futures::{channel::oneshot, Future};
+use glam::{vec2, UVec2, UVec3, Vec2};
+
+use super::{
+    FSMain, RendererCollectState, RendererResources, RendererTarget, TreeRenderer,
+    TreeRendererConfig,
+};
+use crate::{bind_groups::BindGroups, RendererConfig};
+
+/// The value of pixels that are not covered by any entity
+const NO_ENTITY: u32 = u32::MAX;
+/// The size of one texel of the id buffer
+const TEXEL_SIZE: u64 = 8;
+
+/// Renders the location of the entity that covers each pixel to an id buffer, and reads back the pixels that were
+/// picked. The id buffer is only rendered on frames that have pick requests.
+pub(crate) struct Picking {
+    gpu: Arc<Gpu>,
+    renderer: TreeRenderer,
+    collect_state: RendererCollectState,
+    ids: Arc<Texture>,
+    depth: Arc<Texture>,
+    /// Clip space positions to pick, and where to send the results
+    requests: Vec<(Vec2, oneshot::Sender<Option<EntityId>>)>,
+    readbacks: Vec<Readback>,
+}
+
+struct Readback {
+    buffer: Arc<wgpu::Buffer>,
+    senders: Vec<oneshot::Sender<Option<EntityId>>>,
+    mapped: Arc<AtomicBool>,
+}
+
+impl Picking {
+    pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rg32Uint;
+
+    pub fn new(
+        gpu: Arc<Gpu>,
+        assets: &ambient_std::asset_cache::AssetCache,
+        renderer_config: RendererConfig,
+        renderer_resources: RendererResources,
+    ) -> Self {
+        let size = wgpu::Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 1,
+        };
+        Self {
+            ids: Self::create_texture(gpu.clone(), size, Self::FORMAT),
+            depth: Self::create_texture(gpu.clone(), size, DEPTH_FORMAT),
+            collect_state: RendererCollectState::new(assets),
+            renderer: TreeRenderer::new(TreeRendererConfig {
+                gpu: gpu.clone(),
+                assets: assets.clone(),
+                filter: ArchetypeFilter::new().incl(renderer_config.scene),
+                renderer_config,
+                targets: vec![Some(Self::FORMAT.into())],
+                renderer_resources,
+                fs_main: FSMain::Picking,
+                opaque_only: true,
+                depth_stencil: true,
+                cull_mode: Some(wgpu::Face::Back),
+                depth_bias: Default::default(),
+            }),
+            requests: Vec::new(),
+            readbacks: Vec::new(),
+            gpu,
+        }
+    }
+
+    fn create_texture(
+        gpu: Arc<Gpu>,
+        size: wgpu::Extent3d,
+        format: wgpu::TextureFormat,
+    ) -> Arc<Texture> {
+        Arc::new(Texture::new(
+            gpu,
+            &wgpu::TextureDescriptor {
+                label: Some("Renderer.picking"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+                view_formats: &[],
+            },
+        ))
+    }
+
+    /// Returns the entity that covers the clip space position in the next rendered frame, if any
+    pub fn pick(
+        &mut self,
+        clip_space_pos: Vec2,
+    ) -> impl Future<Output = Option<EntityId>> + Send + 'static {
+        let (tx, rx) = oneshot::channel();
+        self.requests.push((clip_space_pos, tx));
+        async move { rx.await.ok().flatten() }
+    }
+
+    pub fn render(
+        &mut self,
+        world: &mut World,
+        encoder: &mut wgpu::CommandEncoder,
+        post_submit: &mut Vec<Box<dyn FnOnce() + Send + Send>>,
+        target: &RendererTarget,
+        bind_groups: &BindGroups,
+        mesh_buffer: &MeshBuffer,
+    ) {
+        self.resolve_readbacks(world);
+        if self.requests.is_empty() {
+            return;
+        }
+        ambient_profiling::scope!("Picking");
+
+        if self.ids.size != target.size() {
+            self.ids = Self::create_texture(self.gpu.clone(), target.size(), Self::FORMAT);
+            self.depth = Self::create_texture(self.gpu.clone(), target.size(), DEPTH_FORMAT);
+        }
+        let ids: TextureView = self.ids.create_view(&Default::default());
+        let depth: TextureView = self.depth.create_view(&Default::default());
+
+        self.collect_state.set_camera(0);
+        self.renderer.update(world);
+        self.renderer.run_collect(
+            encoder,
+            post_submit,
+            bind_groups.mesh_meta,
+            bind_groups.entities,
+            &mut self.collect_state,
+        );
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Picking"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &ids,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color {
+                            r: NO_ENTITY as f64,
+                            g: NO_ENTITY as f64,
+                            b: 0.,
+                            a: 0.,
+                        }),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &depth,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(0.0),
+                        store: true,
+                    }),
+                    stencil_ops: None,
+                }),
+            });
+            render_pass.set_index_buffer(
+                mesh_buffer.index_buffer.buffer().slice(..),
+                wgpu::IndexFormat::Uint32,
+            );
+
+            self.renderer
+                .render(&mut render_pass, &self.collect_state, bind_groups);
+        }
+
+        let requests = std::mem::take(&mut self.requests);
+        let buffer = Arc::new(self.gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Picking.readback"),
+            size: requests.len() as u64 * TEXEL_SIZE,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        }));
+        let size = UVec2::new(self.ids.size.width, self.ids.size.height);
+        for (i, (clip_space_pos, _)) in requests.iter().enumerate() {
+            let pixel = interpolate(
+                *clip_space_pos,
+                vec2(-1., 1.),
+                vec2(1., -1.),
+                Vec2::ZERO,
+                size.as_vec2(),
+            )
+            .max(Vec2::ZERO)
+            .as_uvec2()
+            .min(size - UVec2::ONE);
+            encoder.copy_texture_to_buffer(
+                wgpu::ImageCopyTexture {
+                    texture: &self.ids.handle,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: pixel.x,
+                        y: pixel.y,
+                        z: 0,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                wgpu::ImageCopyBuffer {
+                    buffer: &buffer,
+                    layout: wgpu::ImageDataLayout {
+                        offset: i as u64 * TEXEL_SIZE,
+                        bytes_per_row: None,
+                        rows_per_image: None,
+                    },
+                },
+                wgpu::Extent3d {
+                    width: 1,
+                    height: 1,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        let mapped = Arc::new(AtomicBool::new(false));
+        post_submit.push(Box::new({
+            let buffer = buffer.clone();
+            let mapped = mapped.clone();
+            move || {
+                buffer
+                    .slice(..)
+                    .map_async(wgpu::MapMode::Read, move |result| match result {
+                        Ok(()) => mapped.store(true, Ordering::SeqCst),
+                        Err(err) => log::warn!("Failed to read back picked entities: {:?}", err),
+                    });
+            }
+        }));
+        self.readbacks.push(Readback {
+            buffer,
+            senders: requests.into_iter().map(|(_, tx)| tx).collect(),
+            mapped,
+        });
+    }
+
+    /// Sends the entities of the pick requests whose pixels have been read back
+    fn resolve_readbacks(&mut self, world: &World) {
+        if !self.readbacks.is_empty() && !self.gpu.will_be_polled {
+            self.gpu.device.poll(wgpu::Maintain::Poll);
+        }
+        self.readbacks.retain_mut(|readback| {
+            if readback.senders.iter().all(|tx| tx.is_canceled()) {
+                return false;
+            }
+            if !readback.mapped.load(Ordering::SeqCst) {
+                return true;
+            }
+            let data = readback.buffer.slice(..).get_mapped_range();
+            let locs: &[UVec2] = bytemuck::cast_slice(&data);
+            for (tx, loc) in readback.senders.drain(..).zip(locs) {
+                tx.send(entity_from_loc(world, *loc)).ok();
+            }
+            drop(data);
+            readback.buffer.unmap();
+            false
+        });
+    }
+
+    pub fn dump(&self, f: &mut dyn std::io::Write) {
+        self.renderer.dump(f);
+    }
+}
+
+/// Finds the entity at the location that was written to the id buffer; if it moved to another archetype since,
+/// nothing is returned.
+fn entity_from_loc(world: &World, loc: UVec2) -> Option<EntityId> {
+    if loc.x == NO_ENTITY {
+        return None;
+    }
+    let archetype = world.archetypes().get(loc.x as usize)?;
+    if loc.y as usize >= archetype.entity_count() {
+        return None;
+    }
+    let id = archetype.get_entity_id_from_index(loc.y as usize);
+    let current = world.entity_loc(id)?;
+    (UVec3::new(current.archetype as u32, current.index as u32, 0) == loc.extend(0)).then_some(id)
+}
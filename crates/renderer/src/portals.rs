@@ -0,0 +1,90 @@
+//! Portal culling for indoor scenes. The scene is split into [visibility_cell]s connected by portals (see
+//! [ambient_core::portals]); when the camera is inside a cell, only the cells that can be seen through a chain of
+//! portals from it are visible. Every renderable entity that fits inside a cell is tagged with its cell on the GPU, and
+//! the culling pass hides the entities of the cells that aren't visible.
+
+use ambient_core::{
+    bounding::world_bounding_sphere,
+    gpu_components,
+    gpu_ecs::{ComponentToGpuSystem, GpuComponentFormat, GpuWorldSyncEvent},
+    main_scene,
+};
+use ambient_ecs::{components, query, EntityId, FnSystem, SystemGroup, World};
+use ambient_std::shapes::Sphere;
+use glam::Vec4;
+
+pub use ambient_core::portals::{
+    portal_cells, portal_graph, portal_size, visibility_cell, PortalGraph, MAX_VISIBILITY_CELLS,
+};
+
+components!("rendering", {
+    /// The index of the cell this entity fits in, plus one, in `x`
+    gpu_visibility_cell: Vec4,
+});
+gpu_components! {
+    gpu_visibility_cell() => gpu_visibility_cell: GpuComponentFormat::Vec4,
+}
+
+/// Tags the renderable entity `id` with the cell it fits in, if any
+fn assign_cell(world: &mut World, graph: &PortalGraph, id: EntityId, sphere: &Sphere) {
+    match graph.cell_of(sphere) {
+        Some(cell) => {
+            let value = Vec4::new(cell as f32 + 1., 0., 0., 0.);
+            if world.get(id, gpu_visibility_cell()).ok() != Some(value) {
+                world.add_component(id, gpu_visibility_cell(), value).ok();
+            }
+        }
+        None => {
+            if world.has_component(id, gpu_visibility_cell()) {
+                world.remove_component(id, gpu_visibility_cell()).ok();
+            }
+        }
+    }
+}
+
+/// Keeps the [portal_graph] up to date, and the cells of the renderable entities of the main scene
+pub fn systems() -> SystemGroup {
+    SystemGroup::new(
+        "portals",
+        vec![
+            Box::new(FnSystem::new(|world, _| {
+                let graph = PortalGraph::from_world(world);
+                let cells_changed = match world.resource_opt(portal_graph()) {
+                    Some(previous) => !previous.has_same_cells(&graph),
+                    None if graph.cells.is_empty() => return,
+                    None => true,
+                };
+                if cells_changed {
+                    for (id, sphere) in query(world_bounding_sphere())
+                        .incl(main_scene())
+                        .collect_cloned(world, None)
+                    {
+                        assign_cell(world, &graph, id, &sphere);
+                    }
+                }
+                world.add_resource(portal_graph(), graph);
+            })),
+            query(world_bounding_sphere().changed())
+                .incl(main_scene())
+                .to_system(|q, world, qs, _| {
+                    let Some(graph) = world.resource_opt(portal_graph()).cloned() else {
+                        return;
+                    };
+                    for (id, sphere) in q.collect_cloned(world, qs) {
+                        assign_cell(world, &graph, id, &sphere);
+                    }
+                }),
+        ],
+    )
+}
+
+pub fn gpu_world_systems() -> SystemGroup<GpuWorldSyncEvent> {
+    SystemGroup::new(
+        "portals/gpu_world_update",
+        vec![Box::new(ComponentToGpuSystem::new(
+            GpuComponentFormat::Vec4,
+            gpu_visibility_cell(),
+            gpu_components::gpu_visibility_cell(),
+        ))],
+    )
+}
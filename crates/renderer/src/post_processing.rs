@@ -0,0 +1,775 @@
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+
+use ambient_core::{app_start_time, asset_cache, async_ecs::async_run, runtime, time};
+use ambient_ecs::{components, query, EntityId, SystemGroup, World};
+use ambient_gpu::{
+    gpu::{Gpu, GpuKey},
+    shader_module::{Shader, ShaderIdent, ShaderModule},
+    texture::Texture,
+    texture_loaders::TextureFromUrl,
+    wgsl_utils::is_wgsl_identifier,
+};
+use ambient_std::{
+    asset_cache::{AssetCache, AsyncAssetKeyExt, SyncAssetKeyExt},
+    asset_url::AbsAssetUrl,
+    include_file,
+};
+use futures::FutureExt;
+use glam::Vec4;
+use itertools::Itertools;
+use wgpu::{BindGroupLayoutEntry, BindingType, ShaderStages, TextureSampleType};
+
+use crate::RenderTarget;
+
+pub use ambient_ecs::generated::components::core::post_processing::{
+    post_process_error, post_process_order, post_process_shader, post_process_stage,
+    post_process_texture_names, post_process_texture_urls, post_process_uniform_names,
+    post_process_uniform_values,
+};
+
+components!("post_processing", {
+    post_process_textures: Vec<Arc<Texture>>,
+});
+
+pub const MAX_POST_PROCESS_UNIFORMS: usize = 16;
+pub const MAX_POST_PROCESS_TEXTURES: usize = 4;
+
+/// Names that are already used by the post-process shader, and can't be declared as uniforms or textures
+const RESERVED_NAMES: &[&str] = &[
+    "params",
+    "resolution",
+    "time",
+    "scene_texture",
+    "scene_sampler",
+    "sample_scene",
+    "post_process",
+    "vs_main",
+    "fs_main",
+    "in",
+    "out",
+];
+
+/// Where in the frame a post-process pass runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PostProcessStage {
+    /// After the 3D scene, before the UI
+    #[default]
+    Scene,
+    /// After the UI
+    Final,
+}
+impl FromStr for PostProcessStage {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "scene" => Ok(Self::Scene),
+            "final" => Ok(Self::Final),
+            _ => anyhow::bail!("Unknown post-process stage {s:?}; expected `scene` or `final`"),
+        }
+    }
+}
+
+/// Loads the textures of post-process passes
+pub fn systems() -> SystemGroup {
+    SystemGroup::new(
+        "post_processing",
+        vec![
+            query(post_process_texture_urls().changed()).to_system(|q, world, qs, _| {
+                for (id, urls) in q.collect_cloned(world, qs) {
+                    world.remove_component(id, post_process_textures()).ok();
+                    let assets = world.resource(asset_cache()).clone();
+                    let async_run = world.resource(async_run()).clone();
+                    world.resource(runtime()).spawn(async move {
+                        let mut textures = Vec::new();
+                        for url in urls {
+                            let texture = match AbsAssetUrl::parse(&url) {
+                                Ok(url) => TextureFromUrl {
+                                    url,
+                                    format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                                }
+                                .get(&assets)
+                                .await
+                                .map_err(anyhow::Error::from),
+                                Err(err) => Err(err.into()),
+                            };
+                            match texture {
+                                Ok(texture) => textures.push(texture),
+                                Err(err) => {
+                                    let message =
+                                        format!("Failed to load texture {url:?}: {err:?}");
+                                    log::warn!("{message}");
+                                    async_run.run(move |world| {
+                                        world.add_component(id, post_process_error(), message).ok();
+                                    });
+                                    return;
+                                }
+                            }
+                        }
+                        async_run.run(move |world| {
+                            world
+                                .add_component(id, post_process_textures(), textures)
+                                .ok();
+                        });
+                    });
+                }
+            }),
+        ],
+    )
+}
+
+/// Contains the `vs_main` of a fullscreen pass, which draws a triangle strip of 4 vertices over the whole target
+pub fn get_fullscreen_module() -> Arc<ShaderModule> {
+    Arc::new(ShaderModule::new(
+        "fullscreen",
+        include_file!("fullscreen.wgsl"),
+    ))
+}
+
+/// What a pass that is built into the [PostProcessor] draws with, besides the world
+pub struct PostProcessContext<'a> {
+    pub world: &'a World,
+    pub target: &'a RenderTarget,
+    scratch: &'a Texture,
+    scratch_view: &'a wgpu::TextureView,
+}
+impl<'a> PostProcessContext<'a> {
+    /// Copies the color buffer of the target, so that a pass can read the scene while it draws over it
+    pub fn copy_scene(&self, encoder: &mut wgpu::CommandEncoder) -> &'a wgpu::TextureView {
+        let color_buffer = &self.target.color_buffer;
+        encoder.copy_texture_to_texture(
+            color_buffer.handle.as_image_copy(),
+            self.scratch.handle.as_image_copy(),
+            color_buffer.size,
+        );
+        self.scratch_view
+    }
+}
+
+/// A pass that is built into the [PostProcessor], see [PostProcessor::with_pass]. It runs on the scene before the
+/// passes of the guest.
+pub trait PostProcessPass: Send + Sync {
+    fn render(&mut self, encoder: &mut wgpu::CommandEncoder, context: &PostProcessContext);
+    /// Called instead of [PostProcessPass::render] while the pass is disabled, to forget what it keeps between frames
+    fn reset(&mut self) {}
+}
+
+/// A buffer of the [RenderTarget] that a [FullscreenPass] reads, after the copy of the scene
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PassInput {
+    Depth,
+    Velocity,
+}
+
+/// A pass that draws a shader over the whole color buffer. The shader gets `vs_main` from [get_fullscreen_module],
+/// and has to define `fs_main`. It binds its uniform at 0, the copy of the scene at 1, and its [PassInput]s from 2 on.
+pub struct FullscreenPass {
+    gpu: Arc<Gpu>,
+    label: String,
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    uniform_buffer: wgpu::Buffer,
+    inputs: Vec<PassInput>,
+}
+impl FullscreenPass {
+    pub fn new(
+        assets: &AssetCache,
+        label: &str,
+        shader: ShaderModule,
+        format: wgpu::TextureFormat,
+        uniform_size: usize,
+        inputs: &[PassInput],
+    ) -> Self {
+        let gpu = GpuKey.get(assets);
+        let shader = Shader::new(
+            assets,
+            label,
+            &[],
+            &shader.with_dependency(get_fullscreen_module()),
+        )
+        .unwrap();
+
+        let mut entries = vec![
+            BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            BindGroupLayoutEntry {
+                binding: 1,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    sample_type: TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+        ];
+        for (i, input) in inputs.iter().enumerate() {
+            entries.push(BindGroupLayoutEntry {
+                binding: 2 + i as u32,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    sample_type: match input {
+                        PassInput::Depth => TextureSampleType::Depth,
+                        PassInput::Velocity => TextureSampleType::Float { filterable: false },
+                    },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            });
+        }
+        let bind_group_layout =
+            gpu.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some(label),
+                    entries: &entries,
+                });
+        let layout = gpu
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some(label),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let pipeline = gpu
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[Some(format.into())],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleStrip,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            });
+        let uniform_buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: uniform_size as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            gpu,
+            label: label.to_string(),
+            pipeline,
+            bind_group_layout,
+            uniform_buffer,
+            inputs: inputs.to_vec(),
+        }
+    }
+
+    /// Draws over the color buffer of the target with `params` as the uniform, reading a fresh copy of the scene
+    pub fn render(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        context: &PostProcessContext,
+        params: &[u8],
+    ) {
+        self.gpu.queue.write_buffer(&self.uniform_buffer, 0, params);
+        let scene = context.copy_scene(encoder);
+
+        let target = context.target;
+        let mut entries = vec![
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: self.uniform_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(scene),
+            },
+        ];
+        for (i, input) in self.inputs.iter().enumerate() {
+            entries.push(wgpu::BindGroupEntry {
+                binding: 2 + i as u32,
+                resource: wgpu::BindingResource::TextureView(match input {
+                    PassInput::Depth => &target.depth_buffer_view,
+                    PassInput::Velocity => &target.velocity_buffer_view,
+                }),
+            });
+        }
+        let bind_group = self
+            .gpu
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(&self.label),
+                layout: &self.bind_group_layout,
+                entries: &entries,
+            });
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(&self.label),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target.color_buffer_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..4, 0..1);
+    }
+}
+
+type CreatePass =
+    Box<dyn Fn(&AssetCache, wgpu::TextureFormat) -> Box<dyn PostProcessPass> + Send + Sync>;
+
+struct BuiltinPass {
+    enabled: fn(&World) -> bool,
+    create: CreatePass,
+    /// Created the first time the pass is enabled, and again when the format of the target changes
+    pass: Option<(wgpu::TextureFormat, Box<dyn PostProcessPass>)>,
+}
+
+/// Everything a pass is compiled from; the pass is recompiled when this changes
+#[derive(Debug, Clone, PartialEq)]
+struct PassKey {
+    source: String,
+    uniform_names: Vec<String>,
+    texture_names: Vec<String>,
+    format: wgpu::TextureFormat,
+}
+impl PassKey {
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.uniform_names.len() > MAX_POST_PROCESS_UNIFORMS {
+            anyhow::bail!(
+                "At most {MAX_POST_PROCESS_UNIFORMS} uniforms can be declared, but {} were",
+                self.uniform_names.len()
+            );
+        }
+        if self.texture_names.len() > MAX_POST_PROCESS_TEXTURES {
+            anyhow::bail!(
+                "At most {MAX_POST_PROCESS_TEXTURES} textures can be declared, but {} were",
+                self.texture_names.len()
+            );
+        }
+        let names = self.uniform_names.iter().chain(&self.texture_names);
+        if let Some(name) = names.clone().duplicates().next() {
+            anyhow::bail!("{name:?} is declared more than once");
+        }
+        for name in names {
+            if !is_wgsl_identifier(name) {
+                anyhow::bail!("{name:?} is not a valid WGSL identifier");
+            }
+            if RESERVED_NAMES.contains(&name.as_str()) {
+                anyhow::bail!("{name:?} is reserved by the post-process shader");
+            }
+        }
+        if !self.source.contains("fn post_process") {
+            anyhow::bail!("The shader must define `fn post_process(uv: vec2<f32>) -> vec4<f32>`");
+        }
+        Ok(())
+    }
+}
+
+struct CompiledPass {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    uniform_buffer: wgpu::Buffer,
+}
+
+/// Runs the built-in passes added with [PostProcessor::with_pass], and then the post-process passes defined by entities
+/// with a [post_process_shader], on a [RenderTarget]
+pub struct PostProcessor {
+    gpu: Arc<Gpu>,
+    assets: AssetCache,
+    builtin: Vec<BuiltinPass>,
+    /// Passes that failed to compile are kept as `None`, so that they are not recompiled every frame
+    passes: HashMap<EntityId, (PassKey, Option<CompiledPass>)>,
+    scratch: Option<Arc<Texture>>,
+    sampler: wgpu::Sampler,
+}
+impl PostProcessor {
+    pub fn new(assets: &AssetCache) -> Self {
+        let gpu = GpuKey.get(assets);
+        let sampler = gpu.device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("PostProcessor.sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        Self {
+            gpu,
+            assets: assets.clone(),
+            builtin: Vec::new(),
+            passes: HashMap::new(),
+            scratch: None,
+            sampler,
+        }
+    }
+
+    /// Adds a pass that runs on the scene while `enabled` is true for the world, in the order the passes are added. It is
+    /// created with `create` the first time it runs.
+    pub fn with_pass<P: PostProcessPass + 'static>(
+        mut self,
+        enabled: fn(&World) -> bool,
+        create: fn(&AssetCache, wgpu::TextureFormat) -> P,
+    ) -> Self {
+        self.builtin.push(BuiltinPass {
+            enabled,
+            create: Box::new(move |assets, format| Box::new(create(assets, format))),
+            pass: None,
+        });
+        self
+    }
+
+    /// Compiles new and changed passes, and drops the passes of entities that no longer have a shader
+    pub fn update(&mut self, world: &mut World, format: wgpu::TextureFormat) {
+        let shaders = query(post_process_shader()).collect_cloned(world, None);
+        self.passes
+            .retain(|id, _| shaders.iter().any(|(shader_id, _)| shader_id == id));
+
+        for (id, source) in shaders {
+            let key = PassKey {
+                source,
+                uniform_names: world
+                    .get_cloned(id, post_process_uniform_names())
+                    .unwrap_or_default(),
+                texture_names: world
+                    .get_cloned(id, post_process_texture_names())
+                    .unwrap_or_default(),
+                format,
+            };
+            if matches!(self.passes.get(&id), Some((existing, _)) if *existing == key) {
+                continue;
+            }
+
+            match self.compile(&format!("post_process_{id}"), &key) {
+                Ok(pass) => {
+                    world.remove_component(id, post_process_error()).ok();
+                    self.passes.insert(id, (key, Some(pass)));
+                }
+                Err(err) => {
+                    log::warn!("Failed to compile post-process pass {id}: {err:?}");
+                    world
+                        .add_component(id, post_process_error(), format!("{err:#}"))
+                        .ok();
+                    self.passes.insert(id, (key, None));
+                }
+            }
+        }
+    }
+
+    fn compile(&self, label: &str, key: &PassKey) -> anyhow::Result<CompiledPass> {
+        key.validate()?;
+
+        let params_fields = key
+            .uniform_names
+            .iter()
+            .map(|name| format!("    {name}: vec4<f32>,\n"))
+            .join("");
+        let texture_bindings = key
+            .texture_names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                format!(
+                    "@group(0)\n@binding({})\nvar {name}: texture_2d<f32>;\n",
+                    i + 3
+                )
+            })
+            .join("");
+
+        let mut entries = vec![
+            BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            BindGroupLayoutEntry {
+                binding: 2,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ];
+        for binding in std::iter::once(1).chain(3..3 + key.texture_names.len() as u32) {
+            entries.push(BindGroupLayoutEntry {
+                binding,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    sample_type: TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            });
+        }
+
+        // Guest shaders may be invalid, so catch the validation errors instead of letting them panic
+        self.gpu
+            .device
+            .push_error_scope(wgpu::ErrorFilter::Validation);
+        let result = Shader::new(
+            &self.assets,
+            label.to_string(),
+            &[],
+            &ShaderModule::new("post_process", include_file!("post_processing.wgsl"))
+                .with_ident(ShaderIdent::raw("PARAMS_FIELDS", params_fields))
+                .with_ident(ShaderIdent::raw("TEXTURE_BINDINGS", texture_bindings))
+                .with_ident(ShaderIdent::raw("POST_PROCESS_SOURCE", key.source.clone())),
+        )
+        .map(|shader| {
+            let bind_group_layout =
+                self.gpu
+                    .device
+                    .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                        label: Some(label),
+                        entries: &entries,
+                    });
+            let layout = self
+                .gpu
+                .device
+                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some(label),
+                    bind_group_layouts: &[&bind_group_layout],
+                    push_constant_ranges: &[],
+                });
+            let pipeline =
+                self.gpu
+                    .device
+                    .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                        label: Some(label),
+                        layout: Some(&layout),
+                        vertex: wgpu::VertexState {
+                            module: &shader,
+                            entry_point: "vs_main",
+                            buffers: &[],
+                        },
+                        fragment: Some(wgpu::FragmentState {
+                            module: &shader,
+                            entry_point: "fs_main",
+                            targets: &[Some(key.format.into())],
+                        }),
+                        primitive: wgpu::PrimitiveState {
+                            topology: wgpu::PrimitiveTopology::TriangleStrip,
+                            ..Default::default()
+                        },
+                        depth_stencil: None,
+                        multisample: wgpu::MultisampleState::default(),
+                        multiview: None,
+                    });
+            (bind_group_layout, pipeline)
+        });
+        // The error is only known immediately on native; on the web, a broken pass just draws nothing
+        if let Some(Some(err)) = self.gpu.device.pop_error_scope().now_or_never() {
+            anyhow::bail!("{err}");
+        }
+        let (bind_group_layout, pipeline) = result?;
+
+        let uniform_buffer = self.gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: (std::mem::size_of::<Vec4>() * (1 + key.uniform_names.len())) as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Ok(CompiledPass {
+            pipeline,
+            bind_group_layout,
+            uniform_buffer,
+        })
+    }
+
+    /// Runs the passes of `stage` on the color buffer of `target`, in order; the built-in passes run in the
+    /// [PostProcessStage::Scene] stage, before the passes of the guest
+    pub fn render(
+        &mut self,
+        world: &World,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &RenderTarget,
+        stage: PostProcessStage,
+    ) {
+        let mut builtin = Vec::new();
+        if stage == PostProcessStage::Scene {
+            for builtin_pass in &mut self.builtin {
+                if (builtin_pass.enabled)(world) {
+                    builtin.push(builtin_pass);
+                } else if let Some((_, pass)) = &mut builtin_pass.pass {
+                    pass.reset();
+                }
+            }
+        }
+        let passes = self
+            .passes
+            .iter()
+            .filter_map(|(&id, (key, pass))| Some((id, key, pass.as_ref()?)))
+            .filter(|(id, _, _)| {
+                let pass_stage = world
+                    .get_ref(*id, post_process_stage())
+                    .ok()
+                    .and_then(|stage| stage.parse().ok())
+                    .unwrap_or_default();
+                pass_stage == stage
+            })
+            .sorted_by_key(|(id, _, _)| (world.get(*id, post_process_order()).unwrap_or(0), *id))
+            .collect_vec();
+        if builtin.is_empty() && passes.is_empty() {
+            return;
+        }
+
+        let color_buffer = &target.color_buffer;
+        let scratch = match &self.scratch {
+            Some(scratch)
+                if scratch.size == color_buffer.size && scratch.format == color_buffer.format =>
+            {
+                scratch.clone()
+            }
+            _ => {
+                let scratch = Arc::new(Texture::new(
+                    self.gpu.clone(),
+                    &wgpu::TextureDescriptor {
+                        label: Some("PostProcessor.scratch"),
+                        size: color_buffer.size,
+                        mip_level_count: 1,
+                        sample_count: 1,
+                        dimension: wgpu::TextureDimension::D2,
+                        format: color_buffer.format,
+                        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                        view_formats: &[],
+                    },
+                ));
+                self.scratch = Some(scratch.clone());
+                scratch
+            }
+        };
+        let scratch_view = scratch.create_view(&Default::default());
+        let context = PostProcessContext {
+            world,
+            target,
+            scratch: &scratch,
+            scratch_view: &scratch_view,
+        };
+
+        let format = color_buffer.format;
+        for builtin_pass in builtin {
+            if !matches!(&builtin_pass.pass, Some((pass_format, _)) if *pass_format == format) {
+                builtin_pass.pass = Some((format, (builtin_pass.create)(&self.assets, format)));
+            }
+            if let Some((_, pass)) = &mut builtin_pass.pass {
+                pass.render(encoder, &context);
+            }
+        }
+
+        let time = match (
+            world.resource_opt(time()),
+            world.resource_opt(app_start_time()),
+        ) {
+            (Some(time), Some(start)) => time.saturating_sub(*start).as_secs_f32(),
+            _ => 0.,
+        };
+
+        for (id, key, pass) in passes {
+            let textures = world
+                .get_ref(id, post_process_textures())
+                .map(|textures| {
+                    textures
+                        .iter()
+                        .map(|x| x.create_view(&Default::default()))
+                        .collect_vec()
+                })
+                .unwrap_or_default();
+            if textures.len() < key.texture_names.len() {
+                // Still loading
+                continue;
+            }
+
+            let values = world
+                .get_ref(id, post_process_uniform_values())
+                .map(|x| x.as_slice())
+                .unwrap_or_default();
+            let mut uniforms = vec![Vec4::new(
+                color_buffer.size.width as f32,
+                color_buffer.size.height as f32,
+                time,
+                0.,
+            )];
+            uniforms.extend(
+                (0..key.uniform_names.len()).map(|i| values.get(i).copied().unwrap_or_default()),
+            );
+            self.gpu
+                .queue
+                .write_buffer(&pass.uniform_buffer, 0, bytemuck::cast_slice(&uniforms));
+
+            let scene = context.copy_scene(encoder);
+
+            let mut entries = vec![
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: pass.uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(scene),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ];
+            for (i, texture) in textures.iter().take(key.texture_names.len()).enumerate() {
+                entries.push(wgpu::BindGroupEntry {
+                    binding: 3 + i as u32,
+                    resource: wgpu::BindingResource::TextureView(texture),
+                });
+            }
+            let bind_group = self
+                .gpu
+                .device
+                .create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("PostProcessor.bind_group"),
+                    layout: &pass.bind_group_layout,
+                    entries: &entries,
+                });
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("PostProcessor"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &target.color_buffer_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            render_pass.set_pipeline(&pass.pipeline);
+            render_pass.set_bind_group(0, &bind_group, &[]);
+            render_pass.draw(0..4, 0..1);
+        }
+    }
+}
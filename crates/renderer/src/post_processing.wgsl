@@ -0,0 +1,53 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let x = i32(vertex_index) / 2;
+    let y = i32(vertex_index) & 1;
+    let tc = vec2<f32>(
+        f32(x) * 2.0,
+        f32(y) * 2.0
+    );
+    out.position = vec4<f32>(
+        tc.x * 2.0 - 1.0,
+        1.0 - tc.y * 2.0,
+        0.0,
+        1.0
+    );
+    out.tex_coords = tc;
+
+    return out;
+}
+
+struct PostProcessParams {
+    resolution: vec2<f32>,
+    time: f32,
+    _padding: f32,
+PARAMS_FIELDS
+};
+
+@group(0)
+@binding(0)
+var<uniform> params: PostProcessParams;
+@group(0)
+@binding(1)
+var scene_texture: texture_2d<f32>;
+@group(0)
+@binding(2)
+var scene_sampler: sampler;
+TEXTURE_BINDINGS
+
+fn sample_scene(uv: vec2<f32>) -> vec4<f32> {
+    return textureSample(scene_texture, scene_sampler, uv);
+}
+
+POST_PROCESS_SOURCE
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return post_process(in.tex_coords);
+}
@@ -1,17 +1,18 @@
 use super::{
     overlay_renderer::{OverlayConfig, OverlayRenderer},
     shadow_renderer::ShadowsRenderer,
-    Culling, FSMain, ForwardGlobals, Outlines, OutlinesConfig, RenderTarget, RendererCollect,
-    RendererCollectState, TransparentRenderer, TransparentRendererConfig, TreeRenderer,
-    TreeRendererConfig,
+    Culling, FSMain, ForwardGlobals, Outlines, OutlinesConfig, Picking, RenderTarget,
+    RendererCollect, RendererCollectState, TransparentRenderer, TransparentRendererConfig,
+    TreeRenderer, TreeRendererConfig,
 };
 use crate::{
-    bind_groups::BindGroups, get_common_layout, globals_layout, to_linear_format, ShaderDebugParams,
+    bind_groups::BindGroups, get_common_layout, globals_layout, to_linear_format,
+    ShaderDebugParams, VELOCITY_FORMAT,
 };
 use ambient_core::{
     asset_cache, camera::*, gpu, gpu_ecs::gpu_world, player::local_user_id, ui_scene,
 };
-use ambient_ecs::{ArchetypeFilter, Component, World};
+use ambient_ecs::{ArchetypeFilter, Component, EntityId, World};
 use ambient_gpu::mesh_buffer::MeshBufferKey;
 use ambient_gpu::{
     gpu::{Gpu, GpuKey},
@@ -22,7 +23,9 @@ use ambient_std::{
     asset_cache::{AssetCache, SyncAssetKey, SyncAssetKeyExt},
     color::Color,
 };
-use glam::uvec2;
+use futures::Future;
+use glam::{uvec2, Vec2};
+use itertools::Itertools;
 use std::sync::Arc;
 use tracing::debug_span;
 use wgpu::{BindGroupLayout, BindGroupLayoutEntry, TextureView};
@@ -30,12 +33,14 @@ use wgpu::{BindGroupLayout, BindGroupLayoutEntry, TextureView};
 pub const GLOBALS_BIND_GROUP: &str = "GLOBALS_BIND_GROUP";
 pub const MATERIAL_BIND_GROUP: &str = "MATERIAL_BIND_GROUP";
 pub const PRIMITIVES_BIND_GROUP: &str = "PRIMITIVES_BIND_GROUP";
-pub const GLOBALS_BIND_GROUP_SIZE: u32 = 8;
+pub const GLOBALS_BIND_GROUP_SIZE: u32 = 10;
 
 pub const MESH_METADATA_BINDING: u32 = 0;
 pub const MESH_BASE_BINDING: u32 = 1;
 pub const MESH_SKIN_BINDING: u32 = 2;
 pub const SKINS_BINDING: u32 = 3;
+pub const MESH_MORPH_BINDING: u32 = 4;
+pub const MORPH_WEIGHTS_BINDING: u32 = 5;
 
 #[derive(Clone)]
 pub struct RendererResources {
@@ -86,6 +91,8 @@ pub struct RendererConfig {
     pub shadow_map_resolution: u32,
     pub shadow_cascades: u32,
     pub lod_cutoff_scaling: f32,
+    /// The camera to render from; if not set, the active camera of the scene is used
+    pub camera: Option<EntityId>,
 }
 
 impl Default for RendererConfig {
@@ -96,16 +103,25 @@ impl Default for RendererConfig {
             shadow_map_resolution: 1024,
             shadow_cascades: 5,
             lod_cutoff_scaling: 1.,
+            camera: None,
         }
     }
 }
 
+impl RendererConfig {
+    pub fn get_camera(&self, world: &World) -> Option<EntityId> {
+        self.camera
+            .or_else(|| get_active_camera(world, self.scene, world.resource_opt(local_user_id())))
+    }
+}
+
 pub enum RendererTarget<'a> {
     Target(&'a RenderTarget),
     Direct {
         color: &'a TextureView,
         depth: &'a TextureView,
         normals: &'a TextureView,
+        velocity: &'a TextureView,
         size: wgpu::Extent3d,
     },
 }
@@ -139,6 +155,13 @@ impl<'a> RendererTarget<'a> {
         }
     }
 
+    pub fn velocity(&self) -> &'a TextureView {
+        match self {
+            RendererTarget::Target(target) => &target.velocity_buffer_view,
+            RendererTarget::Direct { velocity, .. } => velocity,
+        }
+    }
+
     pub fn size(&self) -> wgpu::Extent3d {
         match self {
             RendererTarget::Target(target) => target.color_buffer.size,
@@ -148,6 +171,35 @@ impl<'a> RendererTarget<'a> {
 }
 
 pub type PostSubmitFunc = Box<dyn FnOnce() + Send + Send>;
+/// Records a pass into its own command encoder, see [record_in_parallel]
+pub type RecordFunc<'a> =
+    Box<dyn FnOnce(&mut wgpu::CommandEncoder, &mut Vec<PostSubmitFunc>) + Send + 'a>;
+
+/// Calls every function with a new command encoder on the compute thread pool, and returns the command buffers in the same
+/// order. The post submit functions of all of them are added to `post_submit`.
+pub fn record_in_parallel(
+    gpu: &Gpu,
+    funcs: Vec<RecordFunc>,
+    post_submit: &mut Vec<PostSubmitFunc>,
+) -> Vec<wgpu::CommandBuffer> {
+    ambient_profiling::scope!("record_in_parallel");
+    let mut funcs = funcs.into_iter().map(Some).collect_vec();
+    ambient_sys::task::par_map(&mut funcs, |func| {
+        let mut encoder = gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        let mut post_submit = Vec::new();
+        (func.take().unwrap())(&mut encoder, &mut post_submit);
+        (encoder.finish(), post_submit)
+    })
+    .into_iter()
+    .map(|(commands, funcs)| {
+        post_submit.extend(funcs);
+        commands
+    })
+    .collect()
+}
+
 pub trait SubRenderer: std::fmt::Debug + Send + Sync {
     fn render<'a>(
         &'a mut self,
@@ -175,6 +227,7 @@ pub struct Renderer {
     transparent: TransparentRenderer,
     solids_frame: RenderTarget,
     outlines: Outlines,
+    picking: Picking,
     pub post_forward: Option<Box<dyn SubRenderer>>,
     pub post_transparent: Option<Box<dyn SubRenderer>>,
 }
@@ -207,6 +260,7 @@ impl Renderer {
                 renderer_resources.globals_layout.clone(),
                 shadow_cascades,
                 config.scene,
+                config.camera,
             ),
             forward_collect_state: RendererCollectState::new(&assets),
             shadows,
@@ -224,7 +278,11 @@ impl Renderer {
                 gpu: gpu.clone(),
                 assets: assets.clone(),
                 renderer_config: config.clone(),
-                targets: vec![Some(gpu.swapchain_format().into()), Some(normals_format)],
+                targets: vec![
+                    Some(gpu.swapchain_format().into()),
+                    Some(normals_format),
+                    Some(VELOCITY_FORMAT.into()),
+                ],
                 filter: ArchetypeFilter::new().incl(config.scene),
                 renderer_resources: renderer_resources.clone(),
                 fs_main: FSMain::Forward,
@@ -264,6 +322,12 @@ impl Renderer {
                 },
                 config.clone(),
             ),
+            picking: Picking::new(
+                gpu.clone(),
+                &assets,
+                config.clone(),
+                renderer_resources.clone(),
+            ),
             mesh_meta_layout: renderer_resources.mesh_meta_layout,
             config,
             shader_debug_params: Default::default(),
@@ -316,12 +380,11 @@ impl Renderer {
             gpu_world.create_bind_group(true)
         };
 
-        let main_camera = Camera::get_active(
-            world,
-            self.config.scene,
-            world.resource_opt(local_user_id()),
-        )
-        .unwrap_or_default();
+        let main_camera = self
+            .config
+            .get_camera(world)
+            .and_then(|camera| Camera::from_world(world, camera))
+            .unwrap_or_default();
         {
             ambient_profiling::scope!("Update");
             self.culling.run(encoder, world);
@@ -369,65 +432,98 @@ impl Renderer {
             mesh_meta: &mesh_meta_bind_group,
         };
 
-        if let Some(shadows) = &mut self.shadows {
-            shadows.render(&mesh_buffer, encoder, &bind_groups, post_submit);
-        }
-
+        // The shadow cascades and the forward pass don't depend on each other until they are executed, so they are recorded
+        // on worker threads. The commands recorded so far, like the culling, have to be submitted before them.
         {
-            ambient_profiling::scope!("Forward");
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Forward"),
-                color_attachments: &[
-                    Some(wgpu::RenderPassColorAttachment {
-                        view: target.color(),
-                        resolve_target: None,
-                        ops: wgpu::Operations {
-                            load: if let Some(clear) = clear {
-                                wgpu::LoadOp::Clear(clear.into())
-                            } else {
-                                wgpu::LoadOp::Load
-                            },
-                            store: true,
-                        },
-                    }),
-                    Some(wgpu::RenderPassColorAttachment {
-                        view: target.normals(),
-                        resolve_target: None,
-                        ops: wgpu::Operations {
-                            /// clear color is ignored as the normal buffer should always be initialized with black
-                            load: if clear.is_some() {
-                                wgpu::LoadOp::Clear(Color::BLACK.into())
-                            } else {
-                                wgpu::LoadOp::Load
-                            },
-                            store: true,
-                        },
-                    }),
-                ],
-                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                    view: target.depth_stencil(),
-                    depth_ops: Some(wgpu::Operations {
-                        load: if clear.is_some() {
-                            wgpu::LoadOp::Clear(0.0)
-                        } else {
-                            wgpu::LoadOp::Load
-                        },
-                        store: true,
-                    }),
-                    stencil_ops: None,
-                }),
-            });
-            render_pass.set_index_buffer(
-                mesh_buffer.index_buffer.buffer().slice(..),
-                wgpu::IndexFormat::Uint32,
+            let mut funcs = match &mut self.shadows {
+                Some(shadows) => shadows.render(&mesh_buffer, &bind_groups),
+                None => Vec::new(),
+            };
+            let forward = &self.forward;
+            let forward_collect_state = &self.forward_collect_state;
+            let target = &target;
+            let mesh_buffer: &MeshBuffer = &mesh_buffer;
+            let bind_groups = &bind_groups;
+            funcs.push(Box::new(
+                move |encoder: &mut wgpu::CommandEncoder, _: &mut Vec<PostSubmitFunc>| {
+                    ambient_profiling::scope!("Forward");
+                    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                        label: Some("Forward"),
+                        color_attachments: &[
+                            Some(wgpu::RenderPassColorAttachment {
+                                view: target.color(),
+                                resolve_target: None,
+                                ops: wgpu::Operations {
+                                    load: if let Some(clear) = clear {
+                                        wgpu::LoadOp::Clear(clear.into())
+                                    } else {
+                                        wgpu::LoadOp::Load
+                                    },
+                                    store: true,
+                                },
+                            }),
+                            Some(wgpu::RenderPassColorAttachment {
+                                view: target.normals(),
+                                resolve_target: None,
+                                ops: wgpu::Operations {
+                                    /// clear color is ignored as the normal buffer should always be initialized with black
+                                    load: if clear.is_some() {
+                                        wgpu::LoadOp::Clear(Color::BLACK.into())
+                                    } else {
+                                        wgpu::LoadOp::Load
+                                    },
+                                    store: true,
+                                },
+                            }),
+                            Some(wgpu::RenderPassColorAttachment {
+                                view: target.velocity(),
+                                resolve_target: None,
+                                ops: wgpu::Operations {
+                                    load: if clear.is_some() {
+                                        wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT)
+                                    } else {
+                                        wgpu::LoadOp::Load
+                                    },
+                                    store: true,
+                                },
+                            }),
+                        ],
+                        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                            view: target.depth_stencil(),
+                            depth_ops: Some(wgpu::Operations {
+                                load: if clear.is_some() {
+                                    wgpu::LoadOp::Clear(0.0)
+                                } else {
+                                    wgpu::LoadOp::Load
+                                },
+                                store: true,
+                            }),
+                            stencil_ops: None,
+                        }),
+                    });
+                    render_pass.set_index_buffer(
+                        mesh_buffer.index_buffer.buffer().slice(..),
+                        wgpu::IndexFormat::Uint32,
+                    );
+
+                    forward.render(&mut render_pass, forward_collect_state, bind_groups);
+                    {
+                        ambient_profiling::scope!("Drop render pass");
+                        drop(render_pass);
+                    }
+                },
+            ));
+            let recorded = record_in_parallel(&self.gpu, funcs, post_submit);
+            let before = std::mem::replace(
+                encoder,
+                self.gpu
+                    .device
+                    .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None }),
             );
-
-            self.forward
-                .render(&mut render_pass, &self.forward_collect_state, &bind_groups);
-            {
-                ambient_profiling::scope!("Drop render pass");
-                drop(render_pass);
-            }
+            ambient_profiling::scope!("Submit");
+            self.gpu
+                .queue
+                .submit(std::iter::once(before.finish()).chain(recorded));
         }
 
         if let Some(post_forward) = &mut self.post_forward {
@@ -516,6 +612,24 @@ impl Renderer {
             &bind_groups,
             &mesh_buffer,
         );
+
+        self.picking.render(
+            world,
+            encoder,
+            post_submit,
+            &target,
+            &bind_groups,
+            &mesh_buffer,
+        );
+    }
+
+    /// Finds the entity that covers the clip space position in the next frame. Only opaque (including alpha-tested) entities are
+    /// pickable; transparent ones are ignored.
+    pub fn pick(
+        &mut self,
+        clip_space_pos: Vec2,
+    ) -> impl Future<Output = Option<EntityId>> + Send + 'static {
+        self.picking.pick(clip_space_pos)
     }
 
     pub fn dump_to_tmp_file(&self) {
@@ -558,6 +672,8 @@ impl Renderer {
         self.transparent.dump(f);
         writeln!(f, "  outlines").unwrap();
         self.outlines.dump(f);
+        writeln!(f, "  picking").unwrap();
+        self.picking.dump(f);
     }
 }
 
@@ -587,6 +703,8 @@ pub(crate) fn get_mesh_data_layout(bind_group_offset: u32) -> BindGroupDesc<'sta
             resource_storage_entry(bind_group_offset + MESH_BASE_BINDING),
             resource_storage_entry(bind_group_offset + MESH_SKIN_BINDING),
             resource_storage_entry(bind_group_offset + SKINS_BINDING),
+            resource_storage_entry(bind_group_offset + MESH_MORPH_BINDING),
+            resource_storage_entry(bind_group_offset + MORPH_WEIGHTS_BINDING),
         ],
         label: GLOBALS_BIND_GROUP.into(),
     }
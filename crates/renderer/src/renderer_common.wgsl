@@ -7,9 +7,12 @@ var<storage> primitives: UVec4Buffer;
 struct ModelToWorld {
     local: vec4<f32>,
     pos: vec4<f32>,
+    // Where the vertex was in the previous frame; only the motion of the whole entity is known, not its skinning
+    previous_pos: vec4<f32>,
     normal: vec3<f32>,
     tangent: vec3<f32>,
     texcoord: vec2<f32>,
+    lightmap_texcoord: vec2<f32>,
 }
 
 
@@ -17,8 +20,13 @@ struct ModelToWorld {
 // joint matrices (if applicable) and transformation matrices
 fn model_to_world(loc: vec2<u32>, mesh_index: u32, vertex_index: u32) -> ModelToWorld {
     let model = get_entity_mesh_to_world(loc);
+    let previous_model = get_entity_mesh_to_world_previous_or(loc, model);
 
-    let mesh = get_mesh_base(mesh_index, vertex_index);
+    var mesh = get_mesh_base(mesh_index, vertex_index);
+    if has_entity_morph_weights(loc) {
+        let weights = get_entity_morph_weights(loc);
+        mesh = apply_mesh_morph(mesh_index, vertex_index, u32(weights.x), u32(weights.y), mesh);
+    }
 
     let pos = vec4<f32>(mesh.position.xyz, 1.0);
     let normal = vec4<f32>(mesh.normal.xyz, 0.0);
@@ -26,6 +34,7 @@ fn model_to_world(loc: vec2<u32>, mesh_index: u32, vertex_index: u32) -> ModelTo
     let texcoord: vec2<f32> = mesh.texcoord0;
 
     var result: ModelToWorld;
+    result.lightmap_texcoord = mesh.texcoord1;
 
     if has_entity_skin(loc) {
 
@@ -67,16 +76,26 @@ fn model_to_world(loc: vec2<u32>, mesh_index: u32, vertex_index: u32) -> ModelTo
 
         result.local = total_pos;
         result.pos = model * total_pos;
+        result.previous_pos = previous_model * total_pos;
         result.normal = normalize((model * total_norm).xyz);
         result.tangent = normalize((model * total_tangent).xyz);
         result.texcoord = texcoord;
     } else {
         result.local = pos;
         result.pos = model * pos;
+        result.previous_pos = previous_model * pos;
         result.normal = normalize((model * normal).xyz);
         result.tangent = normalize((model * tangent).xyz);
         result.texcoord = texcoord;
     }
 
+    if has_entity_wind_sway(loc) {
+        // The whole entity samples the wind at its origin, so that it bends in one piece
+        let height = max(result.local.z, 0.);
+        let offset = wind_at(model[3].xyz) * get_entity_wind_sway(loc).x * height * height;
+        result.pos = vec4<f32>(result.pos.xyz + offset * result.pos.w, result.pos.w);
+        result.previous_pos = vec4<f32>(result.previous_pos.xyz + offset * result.previous_pos.w, result.previous_pos.w);
+    }
+
     return result;
 }
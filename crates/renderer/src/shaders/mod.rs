@@ -55,6 +55,7 @@ impl SyncAssetKey<Arc<RendererShader>> for StandardShaderKey {
                 "fs_forward_unlit_main".to_string()
             },
             fs_outline_main: "fs_outlines_main".to_string(),
+            fs_picking_main: "fs_picking_main".to_string(),
             transparent: false,
             double_sided: false,
             depth_write_enabled: true,
@@ -8,6 +8,8 @@ struct VertexOutput {
     @location(4) world_bitangent: vec3<f32>,
     @location(5) world_normal: vec3<f32>,
     @location(6) local_position: vec3<f32>,
+    @location(7) lightmap_texcoord: vec2<f32>,
+    @location(8) previous_world_position: vec4<f32>,
 };
 
 fn get_entity_primitive_mesh(loc: vec2<u32>, index: u32) -> u32 {
@@ -29,11 +31,13 @@ fn vs_main(@builtin(instance_index) instance_index: u32, @builtin(vertex_index)
     let world = model_to_world(entity_loc, mesh_index, vertex_index);
     out.instance_index = instance_index;
     out.texcoord = world.texcoord;
+    out.lightmap_texcoord = world.lightmap_texcoord;
 
     out.world_normal = world.normal;
     out.world_tangent = world.tangent;
     out.world_bitangent = cross(world.normal, world.tangent);
     out.world_position = world.pos;
+    out.previous_world_position = world.previous_pos;
     out.local_position = world.local.xyz;
 
     let clip = global_params.projection_view * world.pos;
@@ -88,9 +92,18 @@ fn fs_forward_lit_main(in: VertexOutput, @builtin(front_facing) is_front: bool)
 
     material.normal = normalize(material.normal);
 
+    var ambient = global_params.sun_ambient.rgb;
+    if has_entity_lightmap_scale_offset(material_in.entity_loc) {
+        let scale_offset = get_entity_lightmap_scale_offset(material_in.entity_loc);
+        ambient = sample_lightmap(in.lightmap_texcoord * scale_offset.xy + scale_offset.zw);
+    } else if global_params.light_probe_grid_counts.w != 0u {
+        ambient = sample_light_probes(material_in.world_position, material.normal);
+    }
+
     return MainFsOut(
-        shading(material, in.world_position),
-        quat_from_mat3(material_in.normal_matrix)
+        shading_with_ambient(material, in.world_position, ambient),
+        quat_from_mat3(material_in.normal_matrix),
+        screen_velocity(in.world_position, in.previous_world_position)
     );
 }
 
@@ -105,7 +118,8 @@ fn fs_forward_unlit_main(in: VertexOutput, @builtin(front_facing) is_front: bool
 
     return MainFsOut(
         vec4<f32>(material.base_color, material.opacity),
-        quat_from_mat3(material_in.normal_matrix)
+        quat_from_mat3(material_in.normal_matrix),
+        screen_velocity(in.world_position, in.previous_world_position)
     );
 }
 
@@ -118,3 +132,13 @@ fn fs_outlines_main(in: VertexOutput, @builtin(front_facing) is_front: bool) ->
     }
     return get_outline(in.instance_index);
 }
+
+@fragment
+fn fs_picking_main(in: VertexOutput, @builtin(front_facing) is_front: bool) -> @location(0) vec2<u32> {
+    var material = get_material(get_material_in(in, is_front));
+
+    if material.opacity < material.alpha_cutoff {
+        discard;
+    }
+    return primitives.data[in.instance_index].xy;
+}
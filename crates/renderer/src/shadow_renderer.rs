@@ -1,6 +1,8 @@
 use std::sync::Arc;
 
-use ambient_core::{camera::Camera, main_scene, player::local_user_id, transform::*};
+use ambient_core::{
+    camera::Camera, environment::Environment, main_scene, player::local_user_id, transform::*,
+};
 use ambient_ecs::{ArchetypeFilter, World};
 use ambient_gpu::{
     gpu::GpuKey,
@@ -17,13 +19,22 @@ use wgpu::DepthBiasState;
 
 use super::{
     cast_shadows, get_active_sun, FSMain, RendererCollectState, RendererResources,
-    ShadowAndUIGlobals, TreeRenderer, TreeRendererConfig, MAX_SHADOW_CASCADES,
+    ShadowAndUIGlobals, TreeRenderer, TreeRendererConfig, MAX_SHADOW_CASCADES, SKY_CAMERA,
+};
+use crate::{
+    bind_groups::BindGroups, default_sun_direction, PostSubmitFunc, RecordFunc, RendererConfig,
 };
-use crate::{bind_groups::BindGroups, default_sun_direction, PostSubmitFunc, RendererConfig};
 
+/// The meters around the camera that the sky occlusion map covers
+pub const SKY_OCCLUSION_SIZE: f32 = 128.;
+
+/// Renders the shadow cascades of the sun, followed by the sky occlusion map, which looks straight down so that
+/// `fetch_sky_visibility` can tell what the weather reaches
 pub struct ShadowsRenderer {
     renderer: TreeRenderer,
     cascades: Vec<ShadowCascade>,
+    /// The sky occlusion map is only rendered while the weather needs it
+    sky_occlusion: bool,
     pub shadow_texture: Arc<Texture>,
     config: RendererConfig,
     pub shadow_view: TextureView,
@@ -53,7 +64,7 @@ impl ShadowsRenderer {
                 size: wgpu::Extent3d {
                     width: config.shadow_map_resolution,
                     height: config.shadow_map_resolution,
-                    depth_or_array_layers: config.shadow_cascades,
+                    depth_or_array_layers: config.shadow_cascades + 1,
                 },
                 mip_level_count: 1,
                 sample_count: 1,
@@ -91,7 +102,7 @@ impl ShadowsRenderer {
                     clamp: 0.0,
                 },
             }),
-            cascades: (0..config.shadow_cascades)
+            cascades: (0..=config.shadow_cascades)
                 .map(|i| ShadowCascade {
                     dynamic_target: shadow_texture.create_view(&wgpu::TextureViewDescriptor {
                         label: Some("Renderer.shadow_target_views"),
@@ -111,16 +122,18 @@ impl ShadowsRenderer {
                     collect_state: RendererCollectState::new(&assets),
                 })
                 .collect_vec(),
+            sky_occlusion: false,
             shadow_texture,
             shadow_view,
             config,
         }
     }
-    pub fn get_cameras(&self) -> SmallVec<[ShadowCameraData; MAX_SHADOW_CASCADES as usize]> {
+    /// The cameras of the shadow cascades, followed by the camera of the sky occlusion map
+    pub fn get_cameras(&self) -> SmallVec<[ShadowCameraData; MAX_SHADOW_CASCADES as usize + 1]> {
         self.cascades.iter().map(|v| (&v.camera).into()).collect()
     }
     pub fn n_cascades(&self) -> usize {
-        self.cascades.len()
+        self.config.shadow_cascades as usize
     }
 
     #[ambient_profiling::function]
@@ -135,21 +148,34 @@ impl ShadowsRenderer {
             default_sun_direction()
         };
 
+        let environment = Environment::from_world(world);
+        self.sky_occlusion = environment.wetness > 0. || environment.puddles > 0.;
+
         self.renderer.update(world);
 
         for (i, cascade) in self.cascades.iter_mut().enumerate() {
             ambient_profiling::scope!("Shadow cascade update");
-            let new_camera = main_camera.create_snapping_shadow_camera(
-                sun_direction,
-                i as u32,
-                self.config.shadow_cascades,
-                self.config.shadow_map_resolution,
-            );
+            let i = i as u32;
+            let (new_camera, collect_camera) = if i < self.config.shadow_cascades {
+                let camera = main_camera.create_snapping_shadow_camera(
+                    sun_direction,
+                    i,
+                    self.config.shadow_cascades,
+                    self.config.shadow_map_resolution,
+                );
+                (camera, i + 1)
+            } else {
+                let camera = main_camera.create_snapping_sky_camera(
+                    SKY_OCCLUSION_SIZE,
+                    self.config.shadow_map_resolution,
+                );
+                (camera, SKY_CAMERA)
+            };
             cascade
                 .globals
                 .update(world, main_scene(), new_camera.projection_view());
             cascade.camera = new_camera;
-            cascade.collect_state.set_camera(i as u32 + 1);
+            cascade.collect_state.set_camera(collect_camera);
         }
     }
 
@@ -169,55 +195,74 @@ impl ShadowsRenderer {
 }
 
 impl ShadowsRenderer {
+    /// Returns a function per cascade that records its collect and render passes, so that the cascades can be recorded
+    /// in parallel (see [record_in_parallel](crate::record_in_parallel))
     pub fn render<'a>(
         &'a mut self,
-        mesh_buffer: &MeshBuffer,
-        encoder: &mut wgpu::CommandEncoder,
-        bind_groups: &BindGroups<'a>,
-        post_submit: &mut Vec<PostSubmitFunc>,
-    ) {
-        for (i, cascade) in self.cascades.iter_mut().enumerate() {
-            ambient_profiling::scope!("Shadow dynamic render");
-            self.renderer.run_collect(
-                encoder,
-                post_submit,
-                bind_groups.mesh_meta,
-                bind_groups.entities,
-                &mut cascade.collect_state,
-            );
-            let label = format!("Shadow cascade {i}");
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some(&label),
-                color_attachments: &[],
-                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                    view: &cascade.dynamic_target,
-                    depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(0.0),
-                        store: true,
-                    }),
-                    stencil_ops: None,
-                }),
-            });
-
-            let globals = cascade.globals.create_bind_group(mesh_buffer);
-
-            render_pass.set_index_buffer(
-                mesh_buffer.index_buffer.buffer().slice(..),
-                wgpu::IndexFormat::Uint32,
-            );
-            self.renderer.render(
-                &mut render_pass,
-                &cascade.collect_state,
-                &BindGroups {
-                    globals,
-                    ..*bind_groups
-                },
-            );
-            {
-                ambient_profiling::scope!("Drop render pass");
-                drop(render_pass);
-            }
-        }
+        mesh_buffer: &'a MeshBuffer,
+        bind_groups: &'a BindGroups<'a>,
+    ) -> Vec<RecordFunc<'a>> {
+        let renderer = &self.renderer;
+        let n_cascades = if self.sky_occlusion {
+            self.cascades.len()
+        } else {
+            self.config.shadow_cascades as usize
+        };
+        self.cascades
+            .iter_mut()
+            .take(n_cascades)
+            .enumerate()
+            .map(|(i, cascade)| {
+                Box::new(
+                    move |encoder: &mut wgpu::CommandEncoder,
+                          post_submit: &mut Vec<PostSubmitFunc>| {
+                        ambient_profiling::scope!("Shadow dynamic render");
+                        renderer.run_collect(
+                            encoder,
+                            post_submit,
+                            bind_groups.mesh_meta,
+                            bind_groups.entities,
+                            &mut cascade.collect_state,
+                        );
+                        let label = format!("Shadow cascade {i}");
+                        let mut render_pass =
+                            encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                                label: Some(&label),
+                                color_attachments: &[],
+                                depth_stencil_attachment: Some(
+                                    wgpu::RenderPassDepthStencilAttachment {
+                                        view: &cascade.dynamic_target,
+                                        depth_ops: Some(wgpu::Operations {
+                                            load: wgpu::LoadOp::Clear(0.0),
+                                            store: true,
+                                        }),
+                                        stencil_ops: None,
+                                    },
+                                ),
+                            });
+
+                        let globals = cascade.globals.create_bind_group(mesh_buffer);
+
+                        render_pass.set_index_buffer(
+                            mesh_buffer.index_buffer.buffer().slice(..),
+                            wgpu::IndexFormat::Uint32,
+                        );
+                        renderer.render(
+                            &mut render_pass,
+                            &cascade.collect_state,
+                            &BindGroups {
+                                globals,
+                                ..*bind_groups
+                            },
+                        );
+                        {
+                            ambient_profiling::scope!("Drop render pass");
+                            drop(render_pass);
+                        }
+                    },
+                ) as RecordFunc<'a>
+            })
+            .collect()
     }
 }
 
@@ -1,20 +1,32 @@
-use std::sync::{
-    atomic::{AtomicU32, Ordering},
-    Arc,
+use std::{
+    collections::HashMap,
+    ops::Range,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
 };
 
 use ambient_core::{
-    asset_cache, gpu_components,
+    asset_cache,
+    bounding::{local_bounding_aabb, visibility_from, world_bounding_aabb, world_bounding_sphere},
+    gpu_components,
     gpu_ecs::{GpuComponentFormat, GpuWorldSyncEvent, MappedComponentToGpuSystem},
     transform::{inv_local_to_world, local_to_world},
 };
-use ambient_ecs::{components, query, Commands, Networked, Store, SystemGroup};
+use ambient_ecs::{
+    components, query, Commands, DynParallelSystem, EntityId, Networked, ParallelFnSystem,
+    QueryState, Store, SystemAccess, SystemGroup,
+};
 use ambient_gpu::{
     gpu::{Gpu, GpuKey},
     typed_buffer::TypedBuffer,
 };
-use ambient_std::asset_cache::{AssetCache, SyncAssetKey, SyncAssetKeyExt};
-use glam::{vec4, Mat4};
+use ambient_std::{
+    asset_cache::{AssetCache, SyncAssetKey, SyncAssetKeyExt},
+    shapes::AABB,
+};
+use glam::{vec4, Mat4, Vec3};
 use itertools::Itertools;
 use parking_lot::Mutex;
 
@@ -28,19 +40,50 @@ components!("rendering", {
 
     @[Networked, Store]
     joints_by_fbx_id: Vec<i64>,
+
+    /// How far the mesh of a skinned entity reaches beyond its joints, at the scale of its bind pose
+    skinned_bounds_padding: f32,
 });
 gpu_components! {
     skin() => skin: GpuComponentFormat::Vec4,
 }
 
+/// The location of an entity's joint matrices in the [SkinsBuffer]. Skinned entities are drawn instanced, and each instance
+/// looks up its own joint matrices through its skin offset.
 #[derive(Debug, Clone)]
-pub struct Skin(Arc<AtomicU32>);
+pub struct Skin(Arc<SkinAllocation>);
 impl Skin {
     pub fn get_offset(&self) -> u32 {
-        self.0.load(Ordering::SeqCst)
+        self.0.offset.load(Ordering::SeqCst)
+    }
+    pub fn len(&self) -> u32 {
+        self.0.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.len == 0
     }
     pub fn null() -> Self {
-        Self(Arc::new(AtomicU32::new(0)))
+        Self(Arc::new(SkinAllocation {
+            offset: AtomicU32::new(0),
+            len: 0,
+            to_remove: None,
+        }))
+    }
+}
+
+#[derive(Debug)]
+struct SkinAllocation {
+    offset: AtomicU32,
+    len: u32,
+    // Notify the buffer to free this range on drop
+    to_remove: Option<Arc<Mutex<Vec<Range<u32>>>>>,
+}
+impl Drop for SkinAllocation {
+    fn drop(&mut self) {
+        if let Some(to_remove) = &self.to_remove {
+            let offset = self.offset.load(Ordering::SeqCst);
+            to_remove.lock().push(offset..offset + self.len);
+        }
     }
 }
 
@@ -53,10 +96,16 @@ impl SyncAssetKey<Arc<Mutex<SkinsBuffer>>> for SkinsBufferKey {
     }
 }
 
-// TODO: The skins are currently leaking memory as they are never cleaned up. Need to implement something similar to how MeshBuffer
-// works; keep an index buffer and a data buffer, and re-use indices
+/// All joint matrices of all skinned entities, packed into a single storage buffer.
+///
+/// Joint matrices are written to a cpu copy of the buffer, and uploaded in one write per frame by [SkinsBuffer::flush].
+/// The ranges of dropped [Skin]s are re-used by new skins.
 pub struct SkinsBuffer {
     pub buffer: TypedBuffer<Mat4>,
+    data: Vec<Mat4>,
+    dirty: Option<Range<usize>>,
+    free_ranges: Vec<Range<u32>>,
+    to_remove: Arc<Mutex<Vec<Range<u32>>>>,
 }
 impl SkinsBuffer {
     fn new(gpu: Arc<Gpu>) -> Self {
@@ -70,33 +119,110 @@ impl SkinsBuffer {
                     | wgpu::BufferUsages::COPY_SRC
                     | wgpu::BufferUsages::COPY_DST,
             ),
+            // The first matrix is used by null skins
+            data: vec![Mat4::IDENTITY],
+            dirty: None,
+            free_ranges: Vec::new(),
+            to_remove: Arc::new(Mutex::new(Vec::new())),
         }
     }
     pub fn create(&mut self, size: u32) -> Skin {
-        let skin = Skin(Arc::new(AtomicU32::new(self.buffer.len() as u32)));
-        self.buffer.resize(self.buffer.len() + size as u64, true);
-        skin
+        self.reclaim();
+        let offset = match self
+            .free_ranges
+            .iter()
+            .position(|range| range.len() >= size as usize)
+        {
+            Some(index) => {
+                let range = &mut self.free_ranges[index];
+                let offset = range.start;
+                range.start += size;
+                if range.is_empty() {
+                    self.free_ranges.swap_remove(index);
+                }
+                offset
+            }
+            None => {
+                let offset = self.data.len() as u32;
+                self.data
+                    .resize(self.data.len() + size as usize, Mat4::IDENTITY);
+                self.buffer.resize(self.data.len() as u64, true);
+                offset
+            }
+        };
+        Skin(Arc::new(SkinAllocation {
+            offset: AtomicU32::new(offset),
+            len: size,
+            to_remove: Some(self.to_remove.clone()),
+        }))
+    }
+    pub fn update(&mut self, skin: &Skin, joint_matrices: &[Mat4]) {
+        let offset = skin.get_offset() as usize;
+        let len = joint_matrices.len().min(skin.len() as usize);
+        if len == 0 {
+            return;
+        }
+        self.data[offset..offset + len].copy_from_slice(&joint_matrices[..len]);
+        let range = offset..offset + len;
+        self.dirty = Some(match self.dirty.take() {
+            Some(dirty) => dirty.start.min(range.start)..dirty.end.max(range.end),
+            None => range,
+        });
+    }
+    /// Uploads all joint matrices that were updated since the last flush
+    pub fn flush(&mut self) {
+        if let Some(dirty) = self.dirty.take() {
+            self.buffer.write(dirty.start as u64, &self.data[dirty]);
+        }
+    }
+    /// The number of joint matrices in use
+    pub fn n_matrices(&self) -> usize {
+        self.data.len() - self.free_ranges.iter().map(|x| x.len()).sum::<usize>()
     }
-    pub fn update(&self, skin: &Skin, joint_matrices: &[Mat4]) {
-        self.buffer.write(skin.get_offset() as u64, joint_matrices);
+    fn reclaim(&mut self) {
+        let to_remove = std::mem::take(&mut *self.to_remove.lock());
+        if to_remove.is_empty() {
+            return;
+        }
+        self.free_ranges.extend(to_remove);
+        // Merge adjacent ranges so that larger skins can re-use them
+        self.free_ranges.sort_by_key(|x| x.start);
+        let mut merged: Vec<Range<u32>> = Vec::with_capacity(self.free_ranges.len());
+        for range in self.free_ranges.drain(..) {
+            match merged.last_mut() {
+                Some(last) if last.end == range.start => last.end = range.end,
+                _ => merged.push(range),
+            }
+        }
+        self.free_ranges = merged;
     }
 }
 
-pub fn skinning_systems() -> SystemGroup {
-    SystemGroup::new(
-        "skinning_systems",
-        vec![query((
-            inv_local_to_world(),
-            inverse_bind_matrices(),
-            joints(),
-            skin(),
-        ))
-        .to_system(|q, world, qs, _| {
+/// Computes the joint matrices of the skinned entities, and uploads them to the [SkinsBuffer]. Only reads the world,
+/// so that it can run at the same time as [morphing_system](crate::morphing::morphing_system).
+pub fn skinning_system() -> DynParallelSystem {
+    let q = query((
+        inv_local_to_world(),
+        inverse_bind_matrices(),
+        joints(),
+        skin(),
+    ));
+    let mut qs = QueryState::new();
+    Box::new(ParallelFnSystem::new(
+        SystemAccess::new()
+            .read(asset_cache())
+            .read(inv_local_to_world())
+            .read(inverse_bind_matrices())
+            .read(joints())
+            .read(skin())
+            .read(local_to_world())
+            .write(joint_matrices()),
+        move |world, _| {
             let skins_h = SkinsBufferKey.get(world.resource(asset_cache()));
-            let skins = skins_h.lock();
+            let mut skins = skins_h.lock();
             let mut commands = Commands::new();
             for (id, (&inv_local_to_world, inverse_bind_matrices, joints, skin)) in
-                q.iter(world, qs)
+                q.iter(world, Some(&mut qs))
             {
                 let joint_matrices = joints
                     .iter()
@@ -112,9 +238,10 @@ pub fn skinning_systems() -> SystemGroup {
                 skins.update(skin, &joint_matrices);
                 commands.set(id, self::joint_matrices(), joint_matrices);
             }
-            commands.apply(world).unwrap();
-        })],
-    )
+            skins.flush();
+            commands
+        },
+    ))
 }
 
 pub fn gpu_world_systems() -> SystemGroup<GpuWorldSyncEvent> {
@@ -128,3 +255,101 @@ pub fn gpu_world_systems() -> SystemGroup<GpuWorldSyncEvent> {
         ))],
     )
 }
+
+/// Keeps the bounds of skinned models around their animated joints, so that they aren't culled when they move away
+/// from their bind pose. Runs after the bounding systems, and replaces the bounds of the entity each skinned entity
+/// takes its visibility from.
+pub fn skinned_bounds_systems() -> SystemGroup {
+    SystemGroup::new(
+        "skinned_bounds_systems",
+        vec![
+            query((inverse_bind_matrices(), joints()))
+                .excl(skinned_bounds_padding())
+                .to_system(|q, world, qs, _| {
+                    let mut commands = Commands::new();
+                    for (id, (inverse_bind_matrices, _)) in q.iter(world, qs) {
+                        let mesh_aabb = world.get(id, local_bounding_aabb()).ok();
+                        commands.add_component(
+                            id,
+                            skinned_bounds_padding(),
+                            bounds_padding(inverse_bind_matrices, mesh_aabb),
+                        );
+                    }
+                    commands.apply(world).unwrap();
+                }),
+            query((inverse_bind_matrices(), joints(), skinned_bounds_padding())).to_system(
+                |q, world, qs, _| {
+                    let mut bounds = HashMap::<EntityId, AABB>::new();
+                    for (id, (inverse_bind_matrices, joints, &padding)) in q.iter(world, qs) {
+                        let mut aabb = AABB::new_invalid();
+                        let mut scale = 0f32;
+                        for (i, joint) in joints.iter().enumerate() {
+                            let Ok(joint_to_world) = world.get(*joint, local_to_world()) else {
+                                continue;
+                            };
+                            aabb.take_point(joint_to_world.w_axis.truncate());
+                            let mesh_to_world = joint_to_world
+                                * *inverse_bind_matrices
+                                    .get(i)
+                                    .unwrap_or(&glam::Mat4::IDENTITY);
+                            scale = scale.max(max_scale(&mesh_to_world));
+                        }
+                        if aabb.min.x > aabb.max.x {
+                            continue;
+                        }
+                        let aabb = AABB::new(
+                            aabb.min - Vec3::splat(padding * scale),
+                            aabb.max + Vec3::splat(padding * scale),
+                        );
+                        let target = match world.get(id, visibility_from()) {
+                            Ok(root) if !root.is_null() => root,
+                            _ => id,
+                        };
+                        bounds
+                            .entry(target)
+                            .and_modify(|bounds| *bounds = bounds.union(&aabb))
+                            .or_insert(aabb);
+                    }
+                    for (id, aabb) in bounds {
+                        if world.has_component(id, world_bounding_aabb()) {
+                            world.set(id, world_bounding_aabb(), aabb).ok();
+                        }
+                        if world.has_component(id, world_bounding_sphere()) {
+                            world
+                                .set(id, world_bounding_sphere(), aabb.to_sphere())
+                                .ok();
+                        }
+                    }
+                },
+            ),
+        ],
+    )
+}
+
+/// How far a mesh with the bounds `mesh_aabb` in its bind pose reaches beyond the joints of its skin; a quarter of
+/// the size of the skeleton if its bounds are unknown
+fn bounds_padding(inverse_bind_matrices: &[Mat4], mesh_aabb: Option<AABB>) -> f32 {
+    let joints = inverse_bind_matrices
+        .iter()
+        .map(|inverse_bind| inverse_bind.inverse().w_axis.truncate())
+        .collect_vec();
+    if joints.is_empty() {
+        return 0.;
+    }
+    let joints_aabb = AABB::from_points(&joints);
+    match mesh_aabb {
+        Some(mesh) => (joints_aabb.min - mesh.min)
+            .max(mesh.max - joints_aabb.max)
+            .max(Vec3::ZERO)
+            .max_element(),
+        None => joints_aabb.size().max_element() / 4.,
+    }
+}
+
+fn max_scale(mat: &Mat4) -> f32 {
+    mat.x_axis
+        .truncate()
+        .length()
+        .max(mat.y_axis.truncate().length())
+        .max(mat.z_axis.truncate().length())
+}
@@ -24,6 +24,9 @@ pub(crate) fn to_linear_format(format: TextureFormat) -> TextureFormat {
     }
 }
 
+/// The format of [RenderTarget::velocity_buffer]
+pub const VELOCITY_FORMAT: TextureFormat = TextureFormat::Rg16Float;
+
 #[derive(Debug)]
 pub struct RenderTarget {
     pub depth_buffer: Arc<Texture>,
@@ -33,6 +36,10 @@ pub struct RenderTarget {
     pub color_buffer_view: TextureView,
     pub normals_quat_buffer: Arc<Texture>,
     pub normals_quat_buffer_view: TextureView,
+    /// How far the opaque surface in every pixel moved on the screen since the previous frame, in UV units, for motion
+    /// blur and temporal anti-aliasing. The background isn't written to.
+    pub velocity_buffer: Arc<Texture>,
+    pub velocity_buffer_view: TextureView,
 }
 impl RenderTarget {
     pub fn new(gpu: Arc<Gpu>, size: UVec2, usage: Option<wgpu::TextureUsages>) -> Self {
@@ -77,7 +84,7 @@ impl RenderTarget {
             },
         ));
         let normals_buffer = Arc::new(Texture::new(
-            gpu,
+            gpu.clone(),
             &wgpu::TextureDescriptor {
                 label: Some("RenderTarget.normals_quat_buffer"),
                 size: wgpu::Extent3d {
@@ -93,6 +100,23 @@ impl RenderTarget {
                 view_formats: &[],
             },
         ));
+        let velocity_buffer = Arc::new(Texture::new(
+            gpu,
+            &wgpu::TextureDescriptor {
+                label: Some("RenderTarget.velocity_buffer"),
+                size: wgpu::Extent3d {
+                    width: sc_desc.width,
+                    height: sc_desc.height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: VELOCITY_FORMAT,
+                usage,
+                view_formats: &[],
+            },
+        ));
         Self {
             depth_buffer_view: depth_buffer.create_view(&TextureViewDescriptor {
                 aspect: wgpu::TextureAspect::DepthOnly,
@@ -106,6 +130,8 @@ impl RenderTarget {
             color_buffer,
             normals_quat_buffer_view: normals_buffer.create_view(&Default::default()),
             normals_quat_buffer: normals_buffer,
+            velocity_buffer_view: velocity_buffer.create_view(&Default::default()),
+            velocity_buffer,
         }
     }
 }
@@ -8,9 +8,11 @@ use ambient_gpu::{
     shader_module::{GraphicsPipeline, GraphicsPipelineInfo, DEPTH_FORMAT},
     typed_buffer::TypedBuffer,
 };
-use ambient_std::asset_cache::AssetCache;
+use ambient_std::{
+    asset_cache::AssetCache,
+    frame_arena::{with_frame_arena, ArenaVec},
+};
 use glam::{Mat4, UVec4, Vec3};
-use itertools::Itertools;
 use ordered_float::OrderedFloat;
 
 use super::{
@@ -172,14 +174,15 @@ impl TransparentRenderer {
                 self.gpu_primitives.buffer(),
             );
         }
-        self.gpu_primitives.write(
-            0,
-            &self
-                .primitives
-                .iter()
-                .map(|e| get_gpu_primitive_id(world, e.id, e.primitive_index, 0))
-                .collect_vec(),
-        );
+        with_frame_arena(|arena| {
+            let primitive_ids = ArenaVec::from_iter_in(
+                self.primitives
+                    .iter()
+                    .map(|e| get_gpu_primitive_id(world, e.id, e.primitive_index, 0)),
+                arena,
+            );
+            self.gpu_primitives.write(0, &primitive_ids);
+        });
     }
 
     fn remove(&mut self, id: EntityId, primitive_index: usize) {
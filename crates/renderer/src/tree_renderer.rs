@@ -9,7 +9,10 @@ use ambient_gpu::{
     multi_buffer::{MultiBufferSizeStrategy, SubBufferId, TypedMultiBuffer},
     shader_module::{GraphicsPipeline, GraphicsPipelineInfo},
 };
-use ambient_std::asset_cache::AssetCache;
+use ambient_std::{
+    asset_cache::AssetCache,
+    frame_arena::{with_frame_arena, ArenaVec},
+};
 use glam::{uvec2, UVec2};
 use itertools::Itertools;
 use wgpu::DepthBiasState;
@@ -223,28 +226,33 @@ impl TreeRenderer {
             self.primitives.total_len(),
             self.tree.keys().collect_vec()
         );
-        let mut material_layouts = vec![UVec2::ZERO; self.material_indices.counter as usize];
-        for node in self.tree.values() {
-            for mat in node.tree.values() {
-                let offset = self
-                    .primitives
-                    .buffer_offset(mat.primitives_subbuffer)
-                    .unwrap();
-                material_layouts[mat.material_index as usize] =
-                    uvec2(offset as u32, mat.primitives.len() as u32);
+        with_frame_arena(|arena| {
+            let mut material_layouts = ArenaVec::from_iter_in(
+                std::iter::repeat(UVec2::ZERO).take(self.material_indices.counter as usize),
+                arena,
+            );
+            for node in self.tree.values() {
+                for mat in node.tree.values() {
+                    let offset = self
+                        .primitives
+                        .buffer_offset(mat.primitives_subbuffer)
+                        .unwrap();
+                    material_layouts[mat.material_index as usize] =
+                        uvec2(offset as u32, mat.primitives.len() as u32);
+                }
             }
-        }
 
-        self.config.renderer_resources.collect.run(
-            encoder,
-            post_submit,
-            resources_bind_group,
-            entities_bind_group,
-            &self.primitives,
-            collect_state,
-            self.primitives.total_len() as u32,
-            material_layouts,
-        );
+            self.config.renderer_resources.collect.run(
+                encoder,
+                post_submit,
+                resources_bind_group,
+                entities_bind_group,
+                &self.primitives,
+                collect_state,
+                self.primitives.total_len() as u32,
+                &material_layouts,
+            );
+        });
     }
 
     fn insert(
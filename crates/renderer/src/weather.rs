@@ -0,0 +1,255 @@
+use std::sync::Arc;
+
+use ambient_core::{
+    camera::{get_active_camera, projection_view},
+    environment::Environment,
+    main_scene,
+    player::local_user_id,
+    time,
+    transform::{get_world_position, local_to_world},
+};
+use ambient_ecs::World;
+use ambient_gpu::{
+    gpu::{Gpu, GpuKey},
+    shader_module::{Shader, ShaderModule, DEPTH_FORMAT},
+};
+use ambient_std::{
+    asset_cache::{AssetCache, SyncAssetKeyExt},
+    include_file,
+};
+use glam::{Mat4, Vec2, Vec3, Vec4};
+use wgpu::{BindGroupLayoutEntry, BindingType, ShaderStages};
+
+use crate::{
+    get_fullscreen_module, get_wind_module, FullscreenPass, PostProcessContext, PostProcessPass,
+};
+
+/// The drops or flakes around the camera when the precipitation is 1
+const MAX_PARTICLES: u32 = 16384;
+/// The width of the box around the camera that the particles fill, in meters
+const PARTICLE_BOX_SIZE: f32 = 30.;
+
+/// Whether it rains or snows in `world`, so [Weather] has something to draw
+pub fn weather_enabled(world: &World) -> bool {
+    Environment::from_world(world).precipitation > 0.
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct WeatherParams {
+    projection_view: Mat4,
+    camera_position: Vec4,
+    wind: Vec4,
+    time: f32,
+    snowing: f32,
+    box_size: f32,
+    /// How many drops there are on the lens, from 0 to 1
+    lens_rain: f32,
+    resolution: Vec2,
+    _padding: Vec2,
+}
+
+/// Draws the precipitation of the [Environment]: rain or snow falling through a box around the active camera of the
+/// main scene, hidden by the scene in front of it, and drops of rain on the lens while the camera looks up at it.
+///
+/// The particles aren't simulated; each is placed from its index and the time, so that they cost nothing on the CPU.
+pub struct Weather {
+    gpu: Arc<Gpu>,
+    particle_pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+    uniform_buffer: wgpu::Buffer,
+    lens: FullscreenPass,
+}
+impl Weather {
+    pub fn new(assets: &AssetCache, format: wgpu::TextureFormat) -> Self {
+        let gpu = GpuKey.get(assets);
+        let label = "Weather";
+        // The lens is drawn by a fullscreen pass, which adds `vs_main` to the module itself
+        let module = || {
+            ShaderModule::new("weather", include_file!("weather.wgsl"))
+                .with_dependency(get_wind_module())
+        };
+        let shader = Shader::new(
+            assets,
+            label,
+            &[],
+            &module().with_dependency(get_fullscreen_module()),
+        )
+        .unwrap();
+
+        let bind_group_layout =
+            gpu.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some(label),
+                    entries: &[BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::VERTEX_FRAGMENT,
+                        ty: BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    }],
+                });
+        let layout = gpu
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some(label),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let particle_pipeline =
+            gpu.device
+                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some("Weather.particles"),
+                    layout: Some(&layout),
+                    vertex: wgpu::VertexState {
+                        module: &shader,
+                        entry_point: "vs_particle",
+                        buffers: &[],
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: &shader,
+                        entry_point: "fs_particle",
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format,
+                            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                            write_mask: wgpu::ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::TriangleStrip,
+                        ..Default::default()
+                    },
+                    // Hidden by the scene, so that they stop at the surfaces they fall on
+                    depth_stencil: Some(wgpu::DepthStencilState {
+                        format: DEPTH_FORMAT,
+                        depth_write_enabled: false,
+                        depth_compare: wgpu::CompareFunction::Greater,
+                        stencil: wgpu::StencilState::default(),
+                        bias: wgpu::DepthBiasState::default(),
+                    }),
+                    multisample: wgpu::MultisampleState::default(),
+                    multiview: None,
+                });
+        let uniform_buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: std::mem::size_of::<WeatherParams>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Weather.bind_group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        Self {
+            lens: FullscreenPass::new(
+                assets,
+                "Weather.lens",
+                module(),
+                format,
+                std::mem::size_of::<WeatherParams>(),
+                &[],
+            ),
+            gpu,
+            particle_pipeline,
+            bind_group,
+            uniform_buffer,
+        }
+    }
+}
+impl PostProcessPass for Weather {
+    /// Draws the precipitation over the rendered scene in the target
+    fn render(&mut self, encoder: &mut wgpu::CommandEncoder, context: &PostProcessContext) {
+        let world = context.world;
+        let target = context.target;
+        let environment = Environment::from_world(world);
+        let Some(camera) =
+            get_active_camera(world, main_scene(), world.resource_opt(local_user_id()))
+        else {
+            return;
+        };
+        let Ok(projection_view) = world.get(camera, projection_view()) else {
+            return;
+        };
+        let precipitation = environment.precipitation.clamp(0., 1.);
+        let particles = (MAX_PARTICLES as f32 * precipitation) as u32;
+        if particles == 0 {
+            return;
+        }
+        let snowing = environment.is_snowing();
+        let forward = world
+            .get(camera, local_to_world())
+            .unwrap_or_default()
+            .transform_vector3(Vec3::Z)
+            .normalize_or_zero();
+        // Rain only lands on the lens when the camera faces the sky
+        let lens_rain = if snowing {
+            0.
+        } else {
+            precipitation * ((forward.z + 0.1) / 0.6).clamp(0., 1.)
+        };
+
+        let color_buffer = &target.color_buffer;
+        let params = WeatherParams {
+            projection_view,
+            camera_position: get_world_position(world, camera)
+                .unwrap_or_default()
+                .extend(1.),
+            wind: (environment.wind_direction * environment.wind_strength)
+                .extend(environment.wind_gustiness),
+            time: world
+                .resource_opt(time())
+                .copied()
+                .unwrap_or_default()
+                .as_secs_f32(),
+            snowing: snowing as u32 as f32,
+            box_size: PARTICLE_BOX_SIZE,
+            lens_rain,
+            resolution: Vec2::new(
+                color_buffer.size.width as f32,
+                color_buffer.size.height as f32,
+            ),
+            _padding: Vec2::ZERO,
+        };
+        self.gpu
+            .queue
+            .write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&params));
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Weather.particles"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &target.color_buffer_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &target.depth_stencil_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    }),
+                    stencil_ops: None,
+                }),
+            });
+            render_pass.set_pipeline(&self.particle_pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.draw(0..4, 0..particles);
+        }
+
+        if lens_rain > 0. {
+            self.lens
+                .render(encoder, context, bytemuck::bytes_of(&params));
+        }
+    }
+}
@@ -0,0 +1,124 @@
+struct WeatherParams {
+    projection_view: mat4x4<f32>,
+    camera_position: vec4<f32>,
+    wind: vec4<f32>,
+    time: f32,
+    snowing: f32,
+    box_size: f32,
+    lens_rain: f32,
+    resolution: vec2<f32>,
+    _padding: vec2<f32>,
+};
+
+@group(0)
+@binding(0)
+var<uniform> params: WeatherParams;
+@group(0)
+@binding(1)
+var scene_texture: texture_2d<f32>;
+
+fn hash(n: u32) -> f32 {
+    var x = n * 747796405u + 2891336453u;
+    x = ((x >> ((x >> 28u) + 4u)) ^ x) * 277803737u;
+    return f32((x >> 22u) ^ x) / 4294967295.;
+}
+
+fn hash2(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2<f32>(127.1, 311.7))) * 43758.5453);
+}
+
+// Precipitation
+
+struct ParticleOutput {
+    @builtin(position) position: vec4<f32>,
+    // Across the particle in x, and along it in y, from -1 to 1
+    @location(0) quad: vec2<f32>,
+    @location(1) alpha: f32,
+};
+
+@vertex
+fn vs_particle(@builtin(vertex_index) vertex_index: u32, @builtin(instance_index) instance_index: u32) -> ParticleOutput {
+    var out: ParticleOutput;
+    let snow = params.snowing > 0.5;
+    let box_size = params.box_size;
+    let camera = params.camera_position.xyz;
+
+    let seed = instance_index * 4u;
+    let start = vec3<f32>(hash(seed), hash(seed + 1u), hash(seed + 2u)) * box_size;
+    let fall_speed = select(8., 1., snow) * (0.8 + 0.4 * hash(seed + 3u));
+    // The particles drift with the average wind, and wrap around in a box that follows the camera
+    var position = start + (params.wind.xyz * select(1., 0.6, snow) - vec3<f32>(0., 0., fall_speed)) * params.time;
+    if snow {
+        let phase = params.time * 1.3 + hash(seed + 3u) * 6.28;
+        position += vec3<f32>(sin(phase), cos(phase * 0.8), 0.) * 0.3;
+    }
+    let corner = camera - vec3<f32>(box_size * 0.5);
+    position -= floor((position - corner) / box_size) * box_size;
+
+    let velocity = wind_velocity(params.wind, params.time, position) * select(1., 0.6, snow) - vec3<f32>(0., 0., fall_speed);
+    let to_camera = normalize(camera - position);
+    var along: vec3<f32>;
+    var across: vec3<f32>;
+    if snow {
+        across = normalize(cross(vec3<f32>(0., 0., 1.), to_camera)) * 0.02;
+        along = normalize(cross(to_camera, across)) * 0.02;
+    } else {
+        // Rain drops are streaks as long as the distance they fall while the shutter is open
+        let direction = normalize(velocity);
+        along = direction * length(velocity) * 0.015;
+        across = normalize(cross(direction, to_camera)) * 0.005;
+    }
+
+    let quad = vec2<f32>(f32(vertex_index & 1u), f32(vertex_index >> 1u)) * 2. - 1.;
+    let world = position + across * quad.x + along * quad.y;
+    out.position = params.projection_view * vec4<f32>(world, 1.);
+    out.quad = quad;
+    // Fade out towards the edges of the box, so that the particles don't pop in as they wrap around
+    out.alpha = 1. - smoothstep(0.3, 0.5, length(position - camera) / box_size);
+    return out;
+}
+
+@fragment
+fn fs_particle(in: ParticleOutput) -> @location(0) vec4<f32> {
+    if params.snowing > 0.5 {
+        let alpha = 1. - smoothstep(0.5, 1., length(in.quad));
+        return vec4<f32>(1., 1., 1., 0.9 * alpha * in.alpha);
+    }
+    let alpha = (1. - abs(in.quad.x)) * (1. - abs(in.quad.y));
+    return vec4<f32>(0.7, 0.75, 0.8, 0.35 * alpha * in.alpha);
+}
+
+// Rain on the lens
+
+/// The offset of the drop on the lens that covers `uv`, scaled by its thickness; zero if there is none
+fn lens_drop(uv: vec2<f32>, cells: f32, layer: f32) -> vec2<f32> {
+    let aspect = params.resolution.x / params.resolution.y;
+    let grid = vec2<f32>(uv.x * aspect, uv.y) * cells;
+    let cell = floor(grid) + layer * 17.;
+    let seed = hash2(cell);
+    // Each drop lands, slides down for a moment and dries, at its own pace
+    let life = fract(params.time * (0.1 + 0.2 * seed) + seed * 7.);
+    if hash2(cell + 3.) > params.lens_rain || life > 0.8 {
+        return vec2<f32>(0.);
+    }
+    let center = vec2<f32>(0.2 + 0.6 * hash2(cell + 1.), 0.2 + 0.6 * hash2(cell + 2.) + life * 0.2);
+    let radius = 0.15 + 0.2 * fract(seed * 13.);
+    let offset = (fract(grid) - center) / radius;
+    let d = length(offset);
+    if d > 1. {
+        return vec2<f32>(0.);
+    }
+    let thickness = sqrt(1. - d * d) * (1. - life / 0.8);
+    return offset * thickness;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let uv = in.position.xy / params.resolution;
+    let drop = lens_drop(uv, 6., 0.) + lens_drop(uv, 11., 1.) * 0.6;
+    // Drops act as small lenses, which show the scene around them flipped and a bit darker
+    let refracted = clamp(uv - drop * 0.05, vec2<f32>(0.), vec2<f32>(1.));
+    let pixel = vec2<i32>(refracted * (params.resolution - 1.));
+    let color = textureLoad(scene_texture, pixel, 0);
+    return vec4<f32>(color.rgb * (1. - length(drop) * 0.15), 1.);
+}
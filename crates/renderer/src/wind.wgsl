@@ -0,0 +1,12 @@
+/// The wind velocity at `position` after `time` seconds, for the wind `wind.xyz` (its direction times its strength)
+/// with the gustiness `wind.w`. Matches `Environment::wind_at`.
+fn wind_velocity(wind: vec4<f32>, time: f32, position: vec3<f32>) -> vec3<f32> {
+    let strength = length(wind.xyz);
+    if strength == 0. {
+        return vec3<f32>(0.);
+    }
+    let direction = wind.xyz / strength;
+    let phase = (dot(position, direction) - time * strength) * 0.1;
+    let gust = clamp(sin(phase) * 0.5 + sin(phase * 2.3 + 1.7) * 0.3 + sin(phase * 5.1 + 4.2) * 0.2, -1., 1.);
+    return wind.xyz * (1. + wind.w * gust);
+}
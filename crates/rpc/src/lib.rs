@@ -1,8 +1,4 @@
-use std::{
-    collections::HashMap,
-    io::{BufRead, Cursor, Write},
-    sync::Arc,
-};
+use std::{collections::HashMap, io::Write, sync::Arc};
 
 use futures::{future::BoxFuture, Future, FutureExt};
 use serde::{de::DeserializeOwned, Serialize};
@@ -53,29 +49,37 @@ impl<Args: Send + 'static> RpcRegistry<Args> {
         &self,
         _func: F,
         req: Req,
+    ) -> Vec<u8> {
+        self.serialize_traced_req(func, req, None)
+    }
+    /// Like [Self::serialize_req], with the tracing context of the caller (see [trace_context]), which only the
+    /// registries that know about it can run
+    pub fn serialize_traced_req<
+        Req: Serialize + DeserializeOwned,
+        Resp: Serialize + DeserializeOwned,
+        F: Fn(Args, Req) -> L + 'static,
+        L: Future<Output = Resp> + Send,
+    >(
+        &self,
+        _func: F,
+        req: Req,
+        trace_context: Option<&str>,
     ) -> Vec<u8> {
         let name = std::any::type_name::<F>().to_string();
         let mut res = Vec::new();
-        writeln!(&mut res, "{name}").unwrap();
+        match trace_context {
+            Some(trace_context) => writeln!(&mut res, "{name}{TRACE_CONTEXT_SEPARATOR}{trace_context}").unwrap(),
+            None => writeln!(&mut res, "{name}").unwrap(),
+        }
         let req = bincode::serialize(&req).unwrap();
         res.write_all(&req).unwrap();
         res
     }
     pub async fn run_req(&self, args: Args, req: &[u8]) -> Result<Vec<u8>, RpcError> {
-        let mut reader = Cursor::new(req);
-        let mut name = String::new();
-        reader.read_line(&mut name)?;
-        if name.is_empty() {
-            return Err(RpcError::IOError(std::io::ErrorKind::InvalidData.into()));
-        }
-        let name = name[0..(name.len() - 1)].to_string();
-        match self.registry.get(&name) {
-            Some(func) => {
-                let buf = reader.get_ref();
-                let pos = (reader.position() as usize).min(buf.len());
-                func(args, &buf[pos..]).await
-            }
-            None => Err(RpcError::NoSuchFunction(name)),
+        let (name, body) = parse_req(req)?;
+        match self.registry.get(name) {
+            Some(func) => func(args, body).await,
+            None => Err(RpcError::NoSuchFunction(name.to_string())),
         }
     }
     pub fn deserialize_resp<
@@ -91,6 +95,29 @@ impl<Args: Send + 'static> RpcRegistry<Args> {
         bincode::deserialize(resp)
     }
 }
+/// Separates the name of the function from the tracing context in the first line of a request; type names can't
+/// contain it
+const TRACE_CONTEXT_SEPARATOR: char = '\t';
+
+/// Splits the request `req` into the name of the function it calls and its serialized argument
+pub fn parse_req(req: &[u8]) -> Result<(&str, &[u8]), RpcError> {
+    let invalid = || RpcError::IOError(std::io::ErrorKind::InvalidData.into());
+    let end = req.iter().position(|&byte| byte == b'\n').ok_or_else(invalid)?;
+    let line = std::str::from_utf8(&req[..end]).map_err(|_| invalid())?;
+    let name = line.split(TRACE_CONTEXT_SEPARATOR).next().unwrap_or_default();
+    if name.is_empty() {
+        return Err(invalid());
+    }
+    Ok((name, &req[end + 1..]))
+}
+
+/// The tracing context that the request `req` was made in, if it was serialized with one
+pub fn trace_context(req: &[u8]) -> Option<&str> {
+    let line = req.split(|&byte| byte == b'\n').next()?;
+    let (_, trace_context) = std::str::from_utf8(line).ok()?.split_once(TRACE_CONTEXT_SEPARATOR)?;
+    Some(trace_context)
+}
+
 impl<T> std::fmt::Debug for RpcRegistry<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("RpcRegistry").finish()
@@ -124,4 +151,30 @@ mod tests {
         let resp = reg.deserialize_resp(testy, &resp).unwrap();
         println!("resp={resp:?}");
     }
+
+    #[tokio::test]
+    async fn malformed_requests() {
+        let mut reg = RpcRegistry::new();
+        reg.register(testy);
+        for req in [&b""[..], b"\n", "é".as_bytes(), b"\xff\n", b"\t\n"] {
+            assert!(reg.run_req((), req).await.is_err());
+        }
+        let mut req = reg.serialize_req(testy, 6);
+        req.truncate(req.len() - 1);
+        assert!(reg.run_req((), &req).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn traced_request() {
+        let mut reg = RpcRegistry::new();
+        reg.register(testy);
+        let traceparent = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+        let req = reg.serialize_traced_req(testy, 6, Some(traceparent));
+        assert_eq!(crate::trace_context(&req), Some(traceparent));
+        let resp = reg.run_req((), &req).await.unwrap();
+        assert_eq!(reg.deserialize_resp(testy, &resp).unwrap(), 12);
+
+        let req = reg.serialize_req(testy, 6);
+        assert_eq!(crate::trace_context(&req), None);
+    }
 }
@@ -0,0 +1,263 @@
+use std::path::PathBuf;
+
+use ambient_core::{async_ecs::async_run, project_data_dir, runtime};
+use ambient_ecs::{
+    components, query, DeserWorldWithWarnings, Entity, EntityId, Serializable, SystemGroup, World,
+};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+pub use ambient_ecs::generated::components::core::save::{
+    save_components, save_data, save_delete, save_entities, save_error, save_list, save_load,
+    save_slot, save_slots, save_version,
+};
+
+components!("save", {
+    /// Attached to requests while their files are being read or written
+    save_pending: (),
+});
+
+const SLOT_EXTENSION: &str = "json";
+const MAX_SLOT_NAME_LENGTH: usize = 64;
+
+#[derive(Serialize)]
+struct SaveFile<'a> {
+    version: u32,
+    data: &'a str,
+    world: &'a World,
+}
+
+#[derive(Deserialize)]
+struct LoadedSaveFile {
+    version: u32,
+    #[serde(default)]
+    data: String,
+    world: DeserWorldWithWarnings,
+}
+
+/// Slot names are used as file names, so they are limited to letters, digits, `-` and `_`.
+pub fn is_valid_slot_name(slot: &str) -> bool {
+    !slot.is_empty()
+        && slot.len() <= MAX_SLOT_NAME_LENGTH
+        && slot
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The directory the save slots of the current project are stored in, on this `side` (`server` or `client`)
+fn save_dir(world: &World, side: &str) -> anyhow::Result<PathBuf> {
+    Ok(project_data_dir(world, "saves")?.join(side))
+}
+
+fn slot_path(world: &World, side: &str, slot: &str) -> anyhow::Result<PathBuf> {
+    anyhow::ensure!(is_valid_slot_name(slot), "Invalid save slot name {slot:?}");
+    Ok(save_dir(world, side)?.join(format!("{slot}.{SLOT_EXTENSION}")))
+}
+
+/// Serializes the `entities` with their stored components, or only the `components` (by index) if there are any.
+pub fn serialize_slot(
+    world: &World,
+    entities: &[EntityId],
+    components: &[u32],
+    version: u32,
+    data: &str,
+) -> anyhow::Result<String> {
+    let mut saved = World::new_with_config("save", false);
+    for &id in entities {
+        let mut entity = world
+            .clone_entity(id)
+            .with_context(|| format!("Entity {id} does not exist"))?;
+        entity.filter(&|desc| {
+            desc.has_attribute::<Serializable>()
+                && (components.is_empty() || components.contains(&desc.index()))
+        });
+        saved.spawn_with_id(id, entity);
+    }
+    Ok(serde_json::to_string_pretty(&SaveFile {
+        version,
+        data,
+        world: &saved,
+    })?)
+}
+
+/// Deserializes a save slot into its version, module-defined data and entities. Components that no longer exist are
+/// skipped with a warning, so that they can be migrated.
+pub fn deserialize_slot(source: &str) -> anyhow::Result<(u32, String, World)> {
+    let file: LoadedSaveFile = serde_json::from_str(source).context("Failed to parse save slot")?;
+    file.world.warnings.log_warnings();
+    Ok((file.version, file.data, file.world.world))
+}
+
+/// Puts the saved entities back into the world, with the same ids; entities that still exist get their saved
+/// components back, and the others are respawned.
+fn restore(world: &mut World, saved: &World) -> Vec<EntityId> {
+    query(())
+        .iter(saved, None)
+        .filter_map(|(id, _)| {
+            let entity = saved.clone_entity(id).ok()?;
+            if world.exists(id) {
+                world.add_components(id, entity).ok()?;
+            } else {
+                world.spawn_with_id(id, entity);
+            }
+            Some(id)
+        })
+        .collect()
+}
+
+fn finish(world: &mut World, request: EntityId, result: anyhow::Result<Entity>) {
+    if !world.exists(request) {
+        return;
+    }
+    world.remove_component(request, save_pending()).ok();
+    let data = match result {
+        Ok(data) => data.with(save_error(), String::new()),
+        Err(err) => {
+            log::warn!("Save request failed: {err:?}");
+            Entity::new().with(save_error(), format!("{err:#}"))
+        }
+    };
+    world.add_components(request, data).ok();
+}
+
+/// Answers the save, load, delete and list requests of the modules on this `side` (`server` or `client`), which store
+/// their slots separately.
+pub fn systems(side: &'static str) -> SystemGroup {
+    SystemGroup::new(
+        "save",
+        vec![
+            query((save_slot(), save_version()))
+                .excl(save_load())
+                .excl(save_delete())
+                .excl(save_error())
+                .excl(save_pending())
+                .to_system(move |q, world, qs, _| {
+                    for (id, (slot, version)) in q.collect_cloned(world, qs) {
+                        let entities = world.get_cloned(id, save_entities()).unwrap_or_default();
+                        let components =
+                            world.get_cloned(id, save_components()).unwrap_or_default();
+                        let data = world.get_cloned(id, save_data()).unwrap_or_default();
+                        let file = slot_path(world, side, &slot).and_then(|path| {
+                            Ok((
+                                path,
+                                serialize_slot(world, &entities, &components, version, &data)?,
+                            ))
+                        });
+                        world.add_component(id, save_pending(), ()).unwrap();
+                        let async_run = world.resource(async_run()).clone();
+                        world.resource(runtime()).spawn(async move {
+                            let result = async {
+                                let (path, source) = file?;
+                                ambient_sys::fs::create_dir_all(path.parent().unwrap()).await?;
+                                ambient_sys::fs::write(&path, source).await?;
+                                anyhow::Ok(Entity::new())
+                            }
+                            .await;
+                            async_run.run(move |world| finish(world, id, result));
+                        });
+                    }
+                }),
+            query(save_slot())
+                .incl(save_load())
+                .excl(save_error())
+                .excl(save_pending())
+                .to_system(move |q, world, qs, _| {
+                    for (id, slot) in q.collect_cloned(world, qs) {
+                        let path = slot_path(world, side, &slot);
+                        world.add_component(id, save_pending(), ()).unwrap();
+                        let async_run = world.resource(async_run()).clone();
+                        world.resource(runtime()).spawn(async move {
+                            let loaded = async {
+                                let path = path?;
+                                let source =
+                                    ambient_sys::fs::read_to_string(&path).await.with_context(
+                                        || format!("Failed to read {}", path.display()),
+                                    )?;
+                                deserialize_slot(&source)
+                            }
+                            .await;
+                            async_run.run(move |world| {
+                                let result = loaded.map(|(version, data, saved)| {
+                                    Entity::new()
+                                        .with(save_version(), version)
+                                        .with(save_data(), data)
+                                        .with(save_entities(), restore(world, &saved))
+                                });
+                                finish(world, id, result);
+                            });
+                        });
+                    }
+                }),
+            query(save_slot())
+                .incl(save_delete())
+                .excl(save_error())
+                .excl(save_pending())
+                .to_system(move |q, world, qs, _| {
+                    for (id, slot) in q.collect_cloned(world, qs) {
+                        let path = slot_path(world, side, &slot);
+                        world.add_component(id, save_pending(), ()).unwrap();
+                        let async_run = world.resource(async_run()).clone();
+                        world.resource(runtime()).spawn(async move {
+                            let result = async {
+                                ambient_sys::fs::remove_file(path?).await?;
+                                anyhow::Ok(Entity::new())
+                            }
+                            .await;
+                            async_run.run(move |world| finish(world, id, result));
+                        });
+                    }
+                }),
+            query(())
+                .incl(save_list())
+                .excl(save_error())
+                .excl(save_pending())
+                .to_system(move |q, world, qs, _| {
+                    for (id, _) in q.collect_cloned(world, qs) {
+                        let dir = save_dir(world, side);
+                        world.add_component(id, save_pending(), ()).unwrap();
+                        let async_run = world.resource(async_run()).clone();
+                        world.resource(runtime()).spawn(async move {
+                            let result = async {
+                                // No slots have been saved yet if the directory doesn't exist
+                                let paths = ambient_sys::fs::read_dir_paths(dir?)
+                                    .await
+                                    .unwrap_or_default();
+                                let mut slots = paths
+                                    .into_iter()
+                                    .filter(|path| {
+                                        path.extension()
+                                            .map(|ext| ext == SLOT_EXTENSION)
+                                            .unwrap_or(false)
+                                    })
+                                    .filter_map(|path| {
+                                        Some(path.file_stem()?.to_str()?.to_string())
+                                    })
+                                    .filter(|slot| is_valid_slot_name(slot))
+                                    .collect::<Vec<_>>();
+                                slots.sort();
+                                anyhow::Ok(Entity::new().with(save_slots(), slots))
+                            }
+                            .await;
+                            async_run.run(move |world| finish(world, id, result));
+                        });
+                    }
+                }),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slot_names() {
+        assert!(is_valid_slot_name("autosave"));
+        assert!(is_valid_slot_name("slot_2-b"));
+        assert!(!is_valid_slot_name(""));
+        assert!(!is_valid_slot_name("../settings"));
+        assert!(!is_valid_slot_name("a/b"));
+        assert!(!is_valid_slot_name("a.json"));
+        assert!(!is_valid_slot_name(&"a".repeat(MAX_SLOT_NAME_LENGTH + 1)));
+    }
+}
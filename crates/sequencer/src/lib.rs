@@ -0,0 +1,406 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use ambient_animation::{
+    animation_controller, AnimationAction, AnimationActionTime, AnimationClipRef,
+    AnimationController,
+};
+use ambient_audio::{AudioFromUrl, Source};
+use ambient_core::{
+    asset_cache,
+    async_ecs::async_run,
+    camera::active_camera,
+    dtime, runtime, time,
+    transform::{rotation, scale, translation},
+};
+use ambient_ecs::{
+    components, generated::messages, query, Entity, EntityId, FnSystem, Resource, SystemGroup,
+    World,
+};
+use ambient_std::{
+    asset_cache::AsyncAssetKeyExt,
+    asset_url::{AbsAssetUrl, TypedAssetUrl},
+};
+use ambient_world_audio::{audio_mixer, sound_bus};
+
+mod recording;
+mod timeline;
+pub use timeline::*;
+
+pub use ambient_ecs::generated::components::core::sequencer::{
+    sequence_actor_names, sequence_actors, sequence_duration, sequence_from_recording,
+    sequence_from_url, sequence_looping, sequence_playing, sequence_record,
+    sequence_record_interval, sequence_seek, sequence_speed, sequence_time,
+};
+
+/// The actor name that is bound to the sequence entity itself, unless the sequence binds it to another entity
+pub const SELF_ACTOR: &str = "self";
+
+/// Jumps of the time of a sequence on the client that are longer than this are seeks, which don't play the sounds that
+/// were skipped over
+const MAX_SOUND_CATCH_UP: f32 = 0.5;
+
+components!("sequencer", {
+    sequence_timeline: Arc<Timeline>,
+    /// What the sequence last applied to its actors on the server
+    sequence_applied: SequenceApplied,
+    /// The time of the sequence up to which its sounds were played on the client
+    sequence_sounds_time: f32,
+    /// The timeline events reached this frame, which are sent to the modules as `SequenceEvent` messages
+    @[Resource]
+    sequence_events: Vec<messages::SequenceEvent>,
+    /// The sequences that reached their end this frame, which are sent to the modules as `SequenceEnd` messages
+    @[Resource]
+    sequence_end_events: Vec<messages::SequenceEnd>,
+});
+
+#[derive(Debug, Clone, Default)]
+pub struct SequenceApplied {
+    /// The clip that each animated actor plays, and its playback speed if it's running
+    animations: HashMap<String, (usize, Option<f32>)>,
+    /// The camera that was cut to
+    camera: Option<EntityId>,
+}
+
+pub fn init_all_components() {
+    init_components();
+    recording::init_components();
+}
+
+pub fn server_resources() -> Entity {
+    Entity::new()
+        .with_default(sequence_events())
+        .with_default(sequence_end_events())
+}
+
+/// The entities bound to the actor names of a sequence, and the sequence itself as [SELF_ACTOR] unless that is bound
+fn actors(world: &World, sequence: EntityId) -> HashMap<String, EntityId> {
+    let names = world
+        .get_cloned(sequence, sequence_actor_names())
+        .unwrap_or_default();
+    let entities = world
+        .get_cloned(sequence, sequence_actors())
+        .unwrap_or_default();
+    let mut actors: HashMap<_, _> = names.into_iter().zip(entities).collect();
+    actors.entry(SELF_ACTOR.to_string()).or_insert(sequence);
+    actors
+}
+
+/// Applies the tracks of the timeline at `time` to the actors of the sequence. `speed` is how fast the sequence plays,
+/// or `None` if it's paused or has ended.
+fn apply(
+    world: &mut World,
+    sequence: EntityId,
+    timeline: &Timeline,
+    time: f32,
+    speed: Option<f32>,
+    applied: &mut SequenceApplied,
+) {
+    let actors = actors(world, sequence);
+    for track in &timeline.transform {
+        let Some(&actor) = actors.get(&track.actor) else {
+            continue;
+        };
+        let (position, orientation, size) = track.sample(time);
+        // Actors that don't have the component yet get it added
+        if let Some(position) = position {
+            if world
+                .set_if_changed(actor, translation(), position)
+                .is_err()
+            {
+                world.add_component(actor, translation(), position).ok();
+            }
+        }
+        if let Some(orientation) = orientation.map(|orientation| orientation.normalize()) {
+            if world
+                .set_if_changed(actor, rotation(), orientation)
+                .is_err()
+            {
+                world.add_component(actor, rotation(), orientation).ok();
+            }
+        }
+        if let Some(size) = size {
+            if world.set_if_changed(actor, scale(), size).is_err() {
+                world.add_component(actor, scale(), size).ok();
+            }
+        }
+    }
+
+    let now = *world.resource(self::time());
+    for track in &timeline.animation {
+        let Some(&actor) = actors.get(&track.actor) else {
+            continue;
+        };
+        let Some((index, clip_time, running)) = track.sample(time) else {
+            applied.animations.remove(&track.actor);
+            continue;
+        };
+        let clip = &track.clips[index];
+        let rate = speed
+            .map(|speed| speed * clip.speed)
+            .filter(|rate| running && *rate > 0.);
+        if applied.animations.get(&track.actor) == Some(&(index, rate)) {
+            continue;
+        }
+        let url = match TypedAssetUrl::parse(&clip.url) {
+            Ok(url) => url,
+            Err(err) => {
+                log::warn!("Invalid animation clip URL {:?}: {:?}", clip.url, err);
+                continue;
+            }
+        };
+        // While the clip runs, the animation system advances it by itself; it only has to be set again when the
+        // sequence is paused, seeked or changes speed
+        let action_time = match rate {
+            Some(rate) => AnimationActionTime::Offset {
+                start_time: now.saturating_sub(Duration::from_secs_f32(clip_time / rate)),
+                speed: rate,
+            },
+            None => AnimationActionTime::Absolute { time: clip_time },
+        };
+        let controller = AnimationController {
+            actions: vec![AnimationAction {
+                clip: AnimationClipRef::FromModelAsset(url),
+                time: action_time,
+                looping: clip.looping,
+                weight: 1.,
+            }],
+            apply_base_pose: false,
+        };
+        world
+            .add_component(actor, animation_controller(), controller)
+            .ok();
+        applied
+            .animations
+            .insert(track.actor.clone(), (index, rate));
+    }
+
+    // The cameras are only cut to while the sequence hasn't ended, so that the previous camera is used again after it
+    let ended = speed.is_none() && time >= timeline.duration();
+    let cut = timeline
+        .camera
+        .as_ref()
+        .filter(|_| !ended)
+        .and_then(|track| {
+            let camera = *actors.get(&track.sample(time)?.camera)?;
+            Some((camera, track.priority))
+        });
+    if applied.camera != cut.map(|(camera, _)| camera) {
+        if let Some(previous) = applied.camera.take() {
+            world.remove_component(previous, active_camera()).ok();
+        }
+        if let Some((camera, priority)) = cut {
+            world.add_component(camera, active_camera(), priority).ok();
+            applied.camera = Some(camera);
+        }
+    }
+}
+
+/// Advances the sequence `id` by `dtime`, sends the events it passes, and applies its timeline
+fn advance(world: &mut World, id: EntityId, timeline: &Timeline, time: f32, dtime: f32) {
+    let duration = timeline.duration();
+    let looping = world.get(id, sequence_looping()).unwrap_or(false) && duration > 0.;
+    let playing = world.get(id, sequence_playing()).unwrap_or(true);
+    let speed = world.get(id, sequence_speed()).unwrap_or(1.).max(0.);
+    let mut applied = world.get_cloned(id, sequence_applied()).unwrap_or_default();
+
+    let mut passed = Vec::new();
+    let mut ended = false;
+    let next = if let Ok(seek) = world.get(id, sequence_seek()) {
+        world.remove_component(id, sequence_seek()).ok();
+        // The animations are restarted at the new time, and the events in between are skipped
+        applied.animations.clear();
+        seek.clamp(0., duration)
+    } else if !playing {
+        time
+    } else {
+        let next = time + dtime * speed;
+        if next < duration {
+            passed.push((time, next));
+            next
+        } else if looping {
+            passed.push((time, f32::INFINITY));
+            passed.push((0., next % duration));
+            applied.animations.clear();
+            next % duration
+        } else {
+            passed.push((time, f32::INFINITY));
+            ended = true;
+            duration
+        }
+    };
+
+    let events = passed
+        .into_iter()
+        .flat_map(|(from, to)| timeline.events_between(from, to))
+        .map(|event| messages::SequenceEvent::new(event.name.clone(), id, event.time))
+        .collect::<Vec<_>>();
+    world.resource_mut(sequence_events()).extend(events);
+    if ended {
+        world
+            .resource_mut(sequence_end_events())
+            .push(messages::SequenceEnd::new(id));
+        world.add_component(id, sequence_playing(), false).ok();
+    }
+
+    let running = (playing && !ended).then_some(speed);
+    apply(world, id, timeline, next, running, &mut applied);
+    if next != time {
+        world.add_component(id, sequence_time(), next).ok();
+    }
+    world.add_component(id, sequence_applied(), applied).ok();
+}
+
+/// Plays the loaded `timeline` on the sequence `id`, if it still exists
+fn set_timeline(world: &mut World, id: EntityId, timeline: Timeline) {
+    if world.exists(id) {
+        let duration = timeline.duration();
+        world
+            .add_component(id, sequence_timeline(), Arc::new(timeline))
+            .ok();
+        world.add_component(id, sequence_duration(), duration).ok();
+    }
+}
+
+/// Loads the timelines of the sequences
+fn load_systems() -> SystemGroup {
+    SystemGroup::new(
+        "sequencer/load",
+        vec![
+            query(sequence_from_url().changed()).to_system(|q, world, qs, _| {
+                for (id, url) in q.collect_cloned(world, qs) {
+                    let url = match AbsAssetUrl::parse(url) {
+                        Ok(value) => value,
+                        Err(err) => {
+                            log::warn!("Failed to parse sequence_from_url url: {:?}", err);
+                            continue;
+                        }
+                    };
+                    let assets = world.resource(asset_cache()).clone();
+                    let async_run = world.resource(async_run()).clone();
+                    world.resource(runtime()).spawn(async move {
+                        let timeline = url
+                            .download_string(&assets)
+                            .await
+                            .and_then(|source| Timeline::parse(&source))
+                            .and_then(|mut timeline| {
+                                timeline.resolve_urls(&url)?;
+                                Ok(timeline)
+                            });
+                        match timeline {
+                            Err(err) => {
+                                log::warn!("Failed to load timeline: {:?}", err);
+                            }
+                            Ok(timeline) => {
+                                async_run.run(move |world| set_timeline(world, id, timeline));
+                            }
+                        }
+                    });
+                }
+            }),
+        ],
+    )
+}
+
+/// Loads timelines and plays their sequences on the server.
+pub fn systems() -> SystemGroup {
+    SystemGroup::new(
+        "sequencer",
+        vec![
+            Box::new(FnSystem::new(|world, _| {
+                world.resource_mut(sequence_events()).clear();
+                world.resource_mut(sequence_end_events()).clear();
+            })),
+            Box::new(load_systems()),
+            Box::new(recording::systems()),
+            query(sequence_timeline()).to_system(|q, world, qs, _| {
+                let dtime = *world.resource(dtime());
+                for (id, timeline) in q.collect_cloned(world, qs) {
+                    let time = world.get(id, sequence_time()).unwrap_or_default();
+                    advance(world, id, &timeline, time, dtime);
+                }
+            }),
+            // The camera a despawned sequence cut to stops being active
+            query(sequence_applied())
+                .despawned()
+                .to_system(|q, world, qs, _| {
+                    for (_, applied) in q.collect_cloned(world, qs) {
+                        if let Some(camera) = applied.camera {
+                            world.remove_component(camera, active_camera()).ok();
+                        }
+                    }
+                }),
+        ],
+    )
+}
+
+/// Plays the sounds of the sequences on the client, as their time passes them.
+pub fn client_systems() -> SystemGroup {
+    SystemGroup::new(
+        "sequencer/client",
+        vec![
+            Box::new(load_systems()),
+            query((sequence_timeline(), sequence_time())).to_system(|q, world, qs, _| {
+                for (id, (timeline, time)) in q.collect_cloned(world, qs) {
+                    let previous = world.get(id, sequence_sounds_time()).unwrap_or_default();
+                    if time == previous {
+                        continue;
+                    }
+                    world.add_component(id, sequence_sounds_time(), time).ok();
+
+                    let duration = timeline.duration();
+                    let looping = world.get(id, sequence_looping()).unwrap_or(false);
+                    // The end of the timeline is reached when the time stops at its duration
+                    let end = if time >= duration {
+                        f32::INFINITY
+                    } else {
+                        time
+                    };
+                    let passed = if time > previous && time - previous <= MAX_SOUND_CATCH_UP {
+                        vec![(previous, end)]
+                    } else if time < previous
+                        && looping
+                        && duration - previous + time <= MAX_SOUND_CATCH_UP
+                    {
+                        vec![(previous, f32::INFINITY), (0., end)]
+                    } else {
+                        Vec::new()
+                    };
+                    for (from, to) in passed {
+                        for (track, sound) in timeline.sounds_between(from, to) {
+                            play_sound(world, track, sound);
+                        }
+                    }
+                }
+            }),
+        ],
+    )
+}
+
+fn play_sound(world: &World, track: &AudioTrack, sound: &SoundKey) {
+    let Some(mixer) = world.resource_opt(audio_mixer()).cloned() else {
+        return;
+    };
+    let assets = world.resource(asset_cache()).clone();
+    let url = match AbsAssetUrl::parse(&sound.url).and_then(|url| Ok(url.to_download_url(&assets)?))
+    {
+        Ok(url) => url,
+        Err(err) => {
+            log::warn!("Invalid sound URL {:?}: {:?}", sound.url, err);
+            return;
+        }
+    };
+    let bus = track
+        .bus
+        .clone()
+        .unwrap_or_else(|| sound_bus(world, &url.to_string()));
+    let volume = sound.volume;
+    world.resource(runtime()).spawn(async move {
+        let track = AudioFromUrl { url }.get(&assets).await;
+        match track {
+            Ok(track) => {
+                mixer.play_on_bus(&bus, track.decode().gain(volume));
+            }
+            Err(err) => log::warn!("Failed to load sequence sound: {:?}", err),
+        }
+    });
+}
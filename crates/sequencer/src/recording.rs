@@ -0,0 +1,169 @@
+//! Recordings of the transforms of entities, which are written as timelines with a single transform track for
+//! [SELF_ACTOR], and played back on other entities (e.g. ghosts) with `sequence_from_recording`.
+
+use std::path::PathBuf;
+
+use ambient_core::{
+    async_ecs::async_run,
+    dtime, project_data_dir, runtime,
+    transform::{rotation, scale, translation},
+};
+use ambient_ecs::{components, query, EntityId, SystemGroup, World};
+use ambient_save::is_valid_slot_name;
+use anyhow::Context;
+
+use super::{
+    sequence_from_recording, sequence_record, sequence_record_interval, set_timeline, Timeline,
+    TransformKey, TransformTrack, SELF_ACTOR,
+};
+
+/// The time between the keys of a recording, unless the entity sets its `sequence_record_interval`
+const DEFAULT_RECORD_INTERVAL: f32 = 0.1;
+
+components!("sequencer", {
+    /// The recording of the transform of an entity, until it is written
+    sequence_recorder: Recorder,
+});
+
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    name: String,
+    /// The time since the recording started, in seconds
+    time: f32,
+    /// The time at which the next key is recorded
+    next_key: f32,
+    keys: Vec<TransformKey>,
+}
+impl Recorder {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            time: 0.,
+            next_key: 0.,
+            keys: Vec::new(),
+        }
+    }
+
+    fn key(&self, world: &World, id: EntityId) -> TransformKey {
+        TransformKey {
+            time: self.time,
+            translation: world.get(id, translation()).ok(),
+            rotation: world.get(id, rotation()).ok(),
+            scale: world.get(id, scale()).ok(),
+        }
+    }
+
+    fn into_timeline(self) -> Timeline {
+        Timeline {
+            transform: vec![TransformTrack {
+                actor: SELF_ACTOR.to_string(),
+                keys: self.keys,
+            }],
+            ..Default::default()
+        }
+    }
+}
+
+fn recording_path(world: &World, name: &str) -> anyhow::Result<PathBuf> {
+    anyhow::ensure!(is_valid_slot_name(name), "Invalid recording name {name:?}");
+    Ok(project_data_dir(world, "recordings")?.join(format!("{name}.toml")))
+}
+
+/// Writes the `recorder` in the background
+fn write(world: &World, recorder: Recorder) {
+    let path = recording_path(world, &recorder.name);
+    let source = toml::to_string(&recorder.into_timeline());
+    world.resource(runtime()).spawn(async move {
+        let result = async {
+            let path = path?;
+            ambient_sys::fs::create_dir_all(path.parent().unwrap()).await?;
+            ambient_sys::fs::write(&path, source?).await?;
+            anyhow::Ok(())
+        }
+        .await;
+        if let Err(err) = result {
+            log::warn!("Failed to write recording: {err:?}");
+        }
+    });
+}
+
+/// Records the entities with a `sequence_record`, and loads the recordings that are played back, on the server.
+pub(crate) fn systems() -> SystemGroup {
+    SystemGroup::new(
+        "sequencer/recording",
+        vec![
+            query(sequence_record().changed()).to_system(|q, world, qs, _| {
+                for (id, name) in q.collect_cloned(world, qs) {
+                    // Renaming a recording starts a new one
+                    if let Ok(previous) = world.get_cloned(id, sequence_recorder()) {
+                        if previous.name == name {
+                            continue;
+                        }
+                        write(world, previous);
+                    }
+                    world
+                        .add_component(id, sequence_recorder(), Recorder::new(name))
+                        .ok();
+                }
+            }),
+            query(sequence_recorder()).to_system(|q, world, qs, _| {
+                let dtime = *world.resource(dtime());
+                let ids = q.iter(world, qs).map(|(id, _)| id).collect::<Vec<_>>();
+                for id in ids {
+                    let interval = world
+                        .get(id, sequence_record_interval())
+                        .unwrap_or(DEFAULT_RECORD_INTERVAL)
+                        .max(0.);
+                    let recorder = world.get_ref(id, sequence_recorder()).unwrap();
+                    let key = (recorder.time >= recorder.next_key).then(|| recorder.key(world, id));
+                    let recorder = world.get_mut(id, sequence_recorder()).unwrap();
+                    if let Some(key) = key {
+                        recorder.keys.push(key);
+                        recorder.next_key += interval;
+                    }
+                    recorder.time += dtime;
+                }
+            }),
+            query(sequence_recorder())
+                .excl(sequence_record())
+                .to_system(|q, world, qs, _| {
+                    for (id, mut recorder) in q.collect_cloned(world, qs) {
+                        world.remove_component(id, sequence_recorder()).ok();
+                        // The recording ends where the entity is now, rather than at its last key
+                        let key = recorder.key(world, id);
+                        recorder.keys.push(key);
+                        write(world, recorder);
+                    }
+                }),
+            query(sequence_recorder())
+                .despawned()
+                .to_system(|q, world, qs, _| {
+                    for (_, recorder) in q.collect_cloned(world, qs) {
+                        write(world, recorder);
+                    }
+                }),
+            query(sequence_from_recording().changed()).to_system(|q, world, qs, _| {
+                for (id, name) in q.collect_cloned(world, qs) {
+                    let path = recording_path(world, &name);
+                    let async_run = world.resource(async_run()).clone();
+                    world.resource(runtime()).spawn(async move {
+                        let timeline = async {
+                            let path = path?;
+                            let source = ambient_sys::fs::read_to_string(&path)
+                                .await
+                                .with_context(|| format!("Failed to read {}", path.display()))?;
+                            Timeline::parse(&source)
+                        }
+                        .await;
+                        match timeline {
+                            Ok(timeline) => {
+                                async_run.run(move |world| set_timeline(world, id, timeline));
+                            }
+                            Err(err) => log::warn!("Failed to load recording: {err:?}"),
+                        }
+                    });
+                }
+            }),
+        ],
+    )
+}
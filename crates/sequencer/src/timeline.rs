@@ -0,0 +1,455 @@
+use ambient_std::asset_url::AbsAssetUrl;
+use anyhow::Context;
+use glam::{Quat, Vec3};
+use serde::{Deserialize, Serialize};
+
+/// The `active_camera` value of the cameras a camera track cuts to, unless it sets its own `priority`.
+const DEFAULT_CAMERA_PRIORITY: f32 = 100.;
+
+/// A timeline of tracks, which a sequence plays back.
+///
+/// Timelines are TOML files. The times of the keys of the tracks are in seconds from the start of the timeline, and
+/// the entities that the tracks animate are referred to by actor names, which are bound to entities when the timeline
+/// is played; `self` is the entity that plays it, unless it is bound to another one:
+///
+/// ```toml
+/// # Defaults to the time of the last key
+/// duration = 6.0
+///
+/// [[transform]]
+/// actor = "door"
+/// # Keys may leave out any of translation, rotation (a quaternion) and scale; they are interpolated linearly
+/// keys = [
+///     { time = 0.0, translation = [0.0, 0.0, 0.0] },
+///     { time = 2.0, translation = [0.0, 0.0, 3.0], rotation = [0.0, 0.0, 0.0, 1.0] },
+/// ]
+///
+/// [[animation]]
+/// actor = "guard"
+/// # Each clip plays until the next one starts, or for its duration
+/// clips = [{ time = 0.5, url = "guard.fbx/animations/wave.anim", duration = 2.0 }]
+///
+/// [[audio]]
+/// bus = "voice"
+/// sounds = [{ time = 1.0, url = "guard_hello.ogg", volume = 0.8 }]
+///
+/// [camera]
+/// cuts = [{ time = 0.0, camera = "wide" }, { time = 4.0, camera = "close_up" }]
+///
+/// [[events]]
+/// time = 3.0
+/// name = "door_opened"
+/// ```
+///
+/// Relative URLs are relative to the timeline.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Timeline {
+    pub duration: Option<f32>,
+    pub transform: Vec<TransformTrack>,
+    pub animation: Vec<AnimationTrack>,
+    pub audio: Vec<AudioTrack>,
+    pub camera: Option<CameraTrack>,
+    pub events: Vec<TimelineEvent>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransformTrack {
+    pub actor: String,
+    pub keys: Vec<TransformKey>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransformKey {
+    pub time: f32,
+    pub translation: Option<Vec3>,
+    pub rotation: Option<Quat>,
+    pub scale: Option<Vec3>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AnimationTrack {
+    pub actor: String,
+    pub clips: Vec<AnimationClipKey>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AnimationClipKey {
+    pub time: f32,
+    pub url: String,
+    #[serde(default)]
+    pub looping: bool,
+    #[serde(default = "one")]
+    pub speed: f32,
+    /// How long the clip plays for; after that, it holds its last frame until the next clip starts
+    pub duration: Option<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AudioTrack {
+    /// The bus of the mixer the sounds play on; defaults to the bus the sound's URL is routed to
+    pub bus: Option<String>,
+    pub sounds: Vec<SoundKey>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SoundKey {
+    pub time: f32,
+    pub url: String,
+    #[serde(default = "one")]
+    pub volume: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CameraTrack {
+    /// The `active_camera` value of the camera that is cut to; it has to be higher than the other cameras' for the cut
+    /// to show
+    #[serde(default = "default_camera_priority")]
+    pub priority: f32,
+    pub cuts: Vec<CameraCut>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CameraCut {
+    pub time: f32,
+    /// The actor name of the camera
+    pub camera: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TimelineEvent {
+    pub time: f32,
+    pub name: String,
+}
+
+fn one() -> f32 {
+    1.
+}
+
+fn default_camera_priority() -> f32 {
+    DEFAULT_CAMERA_PRIORITY
+}
+
+fn sort_by_time<T>(keys: &mut [T], time: impl Fn(&T) -> f32) {
+    keys.sort_by(|a, b| time(a).total_cmp(&time(b)));
+}
+
+/// Interpolates between the keys around `time` that have a value, holding the first and last values before and after
+/// them
+fn sample<T: Copy>(
+    keys: &[TransformKey],
+    time: f32,
+    value: impl Fn(&TransformKey) -> Option<T>,
+    lerp: impl Fn(T, T, f32) -> T,
+) -> Option<T> {
+    let mut previous = None;
+    for key in keys {
+        let Some(value) = value(key) else {
+            continue;
+        };
+        if key.time >= time {
+            return Some(match previous {
+                Some((previous_time, previous)) => lerp(
+                    previous,
+                    value,
+                    (time - previous_time) / (key.time - previous_time),
+                ),
+                None => value,
+            });
+        }
+        previous = Some((key.time, value));
+    }
+    previous.map(|(_, value)| value)
+}
+
+impl Timeline {
+    /// Parses a timeline, checks its times and sorts the keys of its tracks by time.
+    pub fn parse(source: &str) -> anyhow::Result<Self> {
+        let mut timeline: Self = toml::from_str(source).context("Failed to parse timeline")?;
+        let mut times = timeline
+            .transform
+            .iter()
+            .flat_map(|track| track.keys.iter().map(|key| key.time))
+            .chain(timeline.animation.iter().flat_map(|track| {
+                track
+                    .clips
+                    .iter()
+                    .flat_map(|clip| [Some(clip.time), clip.duration].into_iter().flatten())
+            }))
+            .chain(
+                timeline
+                    .audio
+                    .iter()
+                    .flat_map(|track| track.sounds.iter().map(|sound| sound.time)),
+            )
+            .chain(
+                timeline
+                    .camera
+                    .iter()
+                    .flat_map(|track| track.cuts.iter().map(|cut| cut.time)),
+            )
+            .chain(timeline.events.iter().map(|event| event.time))
+            .chain(timeline.duration);
+        if let Some(time) = times.find(|time| !time.is_finite() || *time < 0.) {
+            anyhow::bail!("Invalid time {time} in timeline; times can't be negative");
+        }
+        for track in &timeline.animation {
+            if let Some(clip) = track
+                .clips
+                .iter()
+                .find(|clip| !clip.speed.is_finite() || clip.speed < 0.)
+            {
+                anyhow::bail!("Invalid speed {} of the clip {:?}", clip.speed, clip.url);
+            }
+        }
+
+        for track in &mut timeline.transform {
+            sort_by_time(&mut track.keys, |key| key.time);
+        }
+        for track in &mut timeline.animation {
+            sort_by_time(&mut track.clips, |clip| clip.time);
+        }
+        for track in &mut timeline.audio {
+            sort_by_time(&mut track.sounds, |sound| sound.time);
+        }
+        if let Some(track) = &mut timeline.camera {
+            sort_by_time(&mut track.cuts, |cut| cut.time);
+        }
+        sort_by_time(&mut timeline.events, |event| event.time);
+        Ok(timeline)
+    }
+
+    /// Resolves the URLs of the clips and sounds that are relative to the timeline, which is at `base`
+    pub fn resolve_urls(&mut self, base: &AbsAssetUrl) -> anyhow::Result<()> {
+        let clips = self
+            .animation
+            .iter_mut()
+            .flat_map(|track| track.clips.iter_mut().map(|clip| &mut clip.url));
+        let sounds = self
+            .audio
+            .iter_mut()
+            .flat_map(|track| track.sounds.iter_mut().map(|sound| &mut sound.url));
+        for url in clips.chain(sounds) {
+            *url = base
+                .resolve(&*url)
+                .with_context(|| format!("Invalid URL {url:?} in timeline"))?
+                .to_string();
+        }
+        Ok(())
+    }
+
+    /// The `duration` of the timeline, or the time of its last key if it has none
+    pub fn duration(&self) -> f32 {
+        if let Some(duration) = self.duration {
+            return duration;
+        }
+        let transforms = self
+            .transform
+            .iter()
+            .filter_map(|track| Some(track.keys.last()?.time));
+        let clips = self
+            .animation
+            .iter()
+            .filter_map(|track| track.clips.last())
+            .map(|clip| clip.time + clip.duration.unwrap_or_default());
+        let sounds = self
+            .audio
+            .iter()
+            .filter_map(|track| Some(track.sounds.last()?.time));
+        let cuts = self
+            .camera
+            .iter()
+            .filter_map(|track| Some(track.cuts.last()?.time));
+        let events = self.events.last().map(|event| event.time);
+        transforms
+            .chain(clips)
+            .chain(sounds)
+            .chain(cuts)
+            .chain(events)
+            .fold(0., f32::max)
+    }
+
+    /// The events at or after `from`, and before `to`
+    pub fn events_between(&self, from: f32, to: f32) -> impl Iterator<Item = &TimelineEvent> {
+        self.events
+            .iter()
+            .filter(move |event| event.time >= from && event.time < to)
+    }
+
+    /// The sounds that start at or after `from`, and before `to`, with their tracks
+    pub fn sounds_between(
+        &self,
+        from: f32,
+        to: f32,
+    ) -> impl Iterator<Item = (&AudioTrack, &SoundKey)> {
+        self.audio.iter().flat_map(move |track| {
+            track
+                .sounds
+                .iter()
+                .filter(move |sound| sound.time >= from && sound.time < to)
+                .map(move |sound| (track, sound))
+        })
+    }
+}
+
+impl TransformTrack {
+    /// The translation, rotation and scale of the actor at `time`; those that none of the keys set are `None`
+    pub fn sample(&self, time: f32) -> (Option<Vec3>, Option<Quat>, Option<Vec3>) {
+        (
+            sample(&self.keys, time, |key| key.translation, Vec3::lerp),
+            sample(&self.keys, time, |key| key.rotation, Quat::slerp),
+            sample(&self.keys, time, |key| key.scale, Vec3::lerp),
+        )
+    }
+}
+
+impl AnimationTrack {
+    /// The index of the clip that plays at `time`, the time within the clip, and whether it is still running or holds
+    /// its last frame. `None` before the first clip.
+    pub fn sample(&self, time: f32) -> Option<(usize, f32, bool)> {
+        let index = self.clips.iter().rposition(|clip| clip.time <= time)?;
+        let clip = &self.clips[index];
+        let elapsed = time - clip.time;
+        Some(match clip.duration {
+            Some(duration) if elapsed >= duration => (index, duration * clip.speed, false),
+            _ => (index, elapsed * clip.speed, true),
+        })
+    }
+}
+
+impl CameraTrack {
+    /// The cut that is shown at `time`, or `None` before the first cut
+    pub fn sample(&self, time: f32) -> Option<&CameraCut> {
+        self.cuts.iter().rev().find(|cut| cut.time <= time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOOR: &str = r#"
+        [[transform]]
+        actor = "door"
+        keys = [
+            { time = 2.0, translation = [0.0, 0.0, 4.0], scale = [2.0, 2.0, 2.0] },
+            { time = 0.0, translation = [0.0, 0.0, 0.0] },
+            { time = 1.0, scale = [1.0, 1.0, 1.0] },
+        ]
+
+        [[animation]]
+        actor = "guard"
+        clips = [
+            { time = 0.5, url = "wave.anim", duration = 1.0, speed = 2.0 },
+            { time = 3.0, url = "idle.anim", looping = true },
+        ]
+
+        [[audio]]
+        sounds = [{ time = 1.0, url = "creak.ogg" }, { time = 2.5, url = "slam.ogg", volume = 0.5 }]
+
+        [camera]
+        cuts = [{ time = 4.0, camera = "close_up" }, { time = 0.0, camera = "wide" }]
+
+        [[events]]
+        time = 2.0
+        name = "opened"
+    "#;
+
+    #[test]
+    fn parse_sorts_and_checks() {
+        let timeline = Timeline::parse(DOOR).unwrap();
+        let times = timeline.transform[0]
+            .keys
+            .iter()
+            .map(|key| key.time)
+            .collect::<Vec<_>>();
+        assert_eq!(times, [0., 1., 2.]);
+        assert_eq!(timeline.camera.as_ref().unwrap().cuts[0].camera, "wide");
+        assert_eq!(
+            timeline.camera.as_ref().unwrap().priority,
+            DEFAULT_CAMERA_PRIORITY
+        );
+        assert_eq!(timeline.audio[0].sounds[0].volume, 1.);
+        assert_eq!(timeline.duration(), 4.);
+
+        assert!(Timeline::parse("[[events]]\ntime = -1.0\nname = \"a\"").is_err());
+        assert!(Timeline::parse("[[events]]\ntime = 1.0\nnmae = \"a\"").is_err());
+        assert_eq!(Timeline::parse("duration = 10.0").unwrap().duration(), 10.);
+    }
+
+    #[test]
+    fn serialize_round_trip() {
+        let timeline = Timeline::parse(DOOR).unwrap();
+        let source = toml::to_string(&timeline).unwrap();
+        assert_eq!(Timeline::parse(&source).unwrap(), timeline);
+    }
+
+    #[test]
+    fn resolve_urls() {
+        let mut timeline = Timeline::parse(DOOR).unwrap();
+        let base = AbsAssetUrl::parse("https://example.com/assets/cutscenes/door.toml").unwrap();
+        timeline.resolve_urls(&base).unwrap();
+        assert_eq!(
+            timeline.animation[0].clips[0].url,
+            "https://example.com/assets/cutscenes/wave.anim"
+        );
+        assert_eq!(
+            timeline.audio[0].sounds[1].url,
+            "https://example.com/assets/cutscenes/slam.ogg"
+        );
+    }
+
+    #[test]
+    fn sample_transforms() {
+        let timeline = Timeline::parse(DOOR).unwrap();
+        let track = &timeline.transform[0];
+
+        let (translation, rotation, scale) = track.sample(1.);
+        assert_eq!(translation, Some(Vec3::new(0., 0., 2.)));
+        assert_eq!(rotation, None);
+        assert_eq!(scale, Some(Vec3::ONE));
+        // The scale holds its first value before its first key, and both hold their last value after the end
+        assert_eq!(track.sample(0.).2, Some(Vec3::ONE));
+        assert_eq!(track.sample(1.5).2, Some(Vec3::splat(1.5)));
+        assert_eq!(track.sample(5.).0, Some(Vec3::new(0., 0., 4.)));
+    }
+
+    #[test]
+    fn sample_clips_and_cuts() {
+        let timeline = Timeline::parse(DOOR).unwrap();
+        let track = &timeline.animation[0];
+        assert_eq!(track.sample(0.), None);
+        assert_eq!(track.sample(1.), Some((0, 1., true)));
+        // The first clip holds its last frame from the end of its duration until the next one starts
+        assert_eq!(track.sample(2.), Some((0, 2., false)));
+        assert_eq!(track.sample(4.), Some((1, 1., true)));
+
+        let cameras = timeline.camera.as_ref().unwrap();
+        assert_eq!(cameras.sample(3.9).unwrap().camera, "wide");
+        assert_eq!(cameras.sample(4.).unwrap().camera, "close_up");
+    }
+
+    #[test]
+    fn events_and_sounds_between() {
+        let timeline = Timeline::parse(DOOR).unwrap();
+        assert_eq!(timeline.events_between(0., 2.).count(), 0);
+        assert_eq!(timeline.events_between(2., 2.1).count(), 1);
+        assert_eq!(timeline.events_between(2.1, f32::INFINITY).count(), 0);
+
+        let sounds = timeline
+            .sounds_between(0., 3.)
+            .map(|(_, sound)| sound.url.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(sounds, ["creak.ogg", "slam.ogg"]);
+        assert_eq!(timeline.sounds_between(1., 1.).count(), 0);
+    }
+}
@@ -140,7 +140,8 @@ fn fs_forward_main(in: VertexOutput) -> @location(0) vec4<f32> {
     // let color = get_sky_color(in.uv, global_params.camera_position.xyz, dir, scene, planet);
     let depth = (1. - textureSampleLevel(solids_screen_depth, default_sampler, in.uv, 0.)) * global_params.camera_far;
     var color = get_sky_color(depth, global_params.camera_position.xyz, dir);
-    color = 1.0 - exp(-color);
+    // Exposed like the rest of the scene, before the sky is tonemapped
+    color = 1.0 - exp(-color * global_params.exposure);
 
     return vec4<f32>(apply_fog(color, global_params.camera_position.xyz, in.world_position.xyz), 1.0);
 }
@@ -228,6 +228,7 @@ impl SyncAssetKey<Arc<RendererShader>> for CloudShaderKey {
             fs_forward_main: "fs_forward_main".to_string(),
             fs_shadow_main: "fs_shadow_main".to_string(),
             fs_outline_main: "fs_outlines_main".to_string(),
+            fs_picking_main: "fs_picking_main".to_string(),
             transparent: true,
             double_sided: false,
             depth_write_enabled: true,
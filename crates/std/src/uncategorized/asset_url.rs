@@ -18,7 +18,7 @@ use url::Url;
 use crate::{
     asset_cache::{AssetCache, SyncAssetKey, SyncAssetKeyExt},
     download_asset::{download, AssetsCacheDir},
-    Cb,
+    pak, sandbox, Cb,
 };
 
 pub use url::ParseError;
@@ -152,7 +152,15 @@ impl AbsAssetUrl {
     pub fn to_file_path(&self) -> anyhow::Result<Option<PathBuf>> {
         if self.0.scheme() == "file" {
             match self.0.to_file_path() {
-                Ok(path) => Ok(Some(path)),
+                Ok(path) => {
+                    if let Some(limits) = sandbox::limits() {
+                        anyhow::ensure!(
+                            limits.allows_path(&path),
+                            "Reading {path:?} is not allowed in the hardened mode"
+                        );
+                    }
+                    Ok(Some(path))
+                }
                 Err(_) => Err(anyhow::anyhow!("Invalid file url: {:?}", self)),
             }
         } else {
@@ -220,9 +228,7 @@ impl AbsAssetUrl {
     }
     pub async fn download_bytes(&self, assets: &AssetCache) -> anyhow::Result<Vec<u8>> {
         if let Some(path) = self.to_file_path()? {
-            Ok(ambient_sys::fs::read(path)
-                .await
-                .context(format!("Failed to read file at: {:}", self.0))?)
+            Ok(pak::read_file(assets, &path).await?)
         } else {
             Ok(
                 download(assets, self.to_download_raw_url(assets)?, |resp| async {
@@ -235,9 +241,8 @@ impl AbsAssetUrl {
     }
     pub async fn download_string(&self, assets: &AssetCache) -> anyhow::Result<String> {
         if let Some(path) = self.to_file_path()? {
-            Ok(ambient_sys::fs::read_to_string(path)
-                .await
-                .context(format!("Failed to read file at: {:}", self.0))?)
+            Ok(String::from_utf8(pak::read_file(assets, &path).await?)
+                .context(format!("File at {:} is not valid UTF-8", self.0))?)
         } else {
             Ok(
                 download(assets, self.to_download_raw_url(assets)?, |resp| async {
@@ -252,9 +257,7 @@ impl AbsAssetUrl {
         assets: &AssetCache,
     ) -> anyhow::Result<T> {
         if let Some(path) = self.to_file_path()? {
-            let content: Vec<u8> = ambient_sys::fs::read(path)
-                .await
-                .context(format!("Failed to read file at: {:}", self.0))?;
+            let content = pak::read_file(assets, &path).await?;
             Ok(serde_json::from_slice(&content)?)
         } else {
             Ok(
@@ -654,6 +657,9 @@ pub enum AssetType {
     /// Represents a vorbis backed file
     VorbisTrack,
     SoundGraph,
+
+    /// A table of localized strings for one language
+    StringTable,
 }
 
 impl AssetType {
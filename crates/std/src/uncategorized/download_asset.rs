@@ -1,7 +1,7 @@
 use std::{marker::PhantomData, path::PathBuf, sync::Arc, time::Duration};
 
 use ambient_sys::task::wasm_nonsend;
-use anyhow::{anyhow, Context};
+use anyhow::Context;
 use async_trait::async_trait;
 use futures::Future;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
@@ -12,27 +12,97 @@ use crate::{
     asset_cache::{
         AssetCache, AssetKeepalive, AsyncAssetKey, AsyncAssetKeyExt, SyncAssetKey, SyncAssetKeyExt,
     },
-    asset_url::AbsAssetUrl,
+    asset_url::{AbsAssetUrl, ContentBaseUrlKey},
     mesh::Mesh,
+    pak, sandbox,
 };
 
 pub type AssetResult<T> = Result<T, AssetError>;
 
+/// Why an asset could not be loaded. Every kind keeps the underlying error, with the context that was added to it
 #[derive(Clone, Error)]
-#[error(transparent)]
-pub struct AssetError(Arc<anyhow::Error>);
+#[non_exhaustive]
+pub enum AssetError {
+    /// The asset does not exist, on disk or on the server
+    #[error(transparent)]
+    NotFound(Arc<anyhow::Error>),
+    /// The asset could not be read from or written to disk
+    #[error(transparent)]
+    Io(Arc<anyhow::Error>),
+    /// The asset could not be downloaded
+    #[error(transparent)]
+    Network(Arc<anyhow::Error>),
+    /// The asset was read, but is not in the expected format
+    #[error(transparent)]
+    Decode(Arc<anyhow::Error>),
+    /// The URL of the asset is not valid
+    #[error(transparent)]
+    InvalidUrl(Arc<anyhow::Error>),
+    /// Anything else, like a download that the hardened mode doesn't allow. Loaders that return an
+    /// [anyhow::Error] fail with this kind, as they don't say what went wrong
+    #[error(transparent)]
+    Other(Arc<anyhow::Error>),
+}
 
 impl From<anyhow::Error> for AssetError {
     fn from(err: anyhow::Error) -> Self {
-        Self(Arc::new(err))
+        Self::Other(Arc::new(err))
     }
 }
 impl std::fmt::Debug for AssetError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.0.fmt(f)
+        self.inner().fmt(f)
+    }
+}
+impl AssetError {
+    /// Reading or writing a file failed; [AssetError::NotFound] if it doesn't exist
+    pub fn io(
+        err: std::io::Error,
+        context: impl std::fmt::Display + Send + Sync + 'static,
+    ) -> Self {
+        let not_found = err.kind() == std::io::ErrorKind::NotFound;
+        let err = Arc::new(anyhow::Error::new(err).context(context));
+        if not_found {
+            Self::NotFound(err)
+        } else {
+            Self::Io(err)
+        }
+    }
+
+    /// The asset could not be decoded
+    pub fn decode(
+        err: impl std::error::Error + Send + Sync + 'static,
+        context: impl std::fmt::Display + Send + Sync + 'static,
+    ) -> Self {
+        Self::Decode(Arc::new(anyhow::Error::new(err).context(context)))
+    }
+
+    /// The URL of the asset could not be parsed
+    pub fn invalid_url(err: impl Into<anyhow::Error>) -> Self {
+        Self::InvalidUrl(Arc::new(err.into()))
+    }
+
+    /// The underlying error, with the context that was added to it
+    pub fn inner(&self) -> &anyhow::Error {
+        match self {
+            Self::NotFound(err)
+            | Self::Io(err)
+            | Self::Network(err)
+            | Self::Decode(err)
+            | Self::InvalidUrl(err)
+            | Self::Other(err) => err,
+        }
     }
 }
 
+/// A download that the server answered with an error status
+#[derive(Debug, Clone, Error)]
+#[error("Downloading {url} failed, bad status code: {status:?}")]
+pub struct HttpStatusError {
+    pub url: String,
+    pub status: reqwest::StatusCode,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AssertErrorString(pub String);
 impl From<AssetError> for AssertErrorString {
@@ -75,10 +145,19 @@ pub(crate) async fn download<T: 'static + Send, F: Future<Output = anyhow::Resul
     assets: &AssetCache,
     url: impl reqwest::IntoUrl,
     map: impl 'static + Send + Fn(reqwest::Response) -> F,
-) -> anyhow::Result<T> {
+) -> AssetResult<T> {
     let url_str = url.as_str().to_string();
-    let url = url.into_url()?;
+    let url = url.into_url().map_err(AssetError::invalid_url)?;
     let assets = assets.clone();
+    let limits = sandbox::limits();
+    if let Some(limits) = &limits {
+        if !limits.allows_url(&url, &ContentBaseUrlKey.get(&assets).0) {
+            return Err(anyhow::anyhow!(
+                "Downloading {url_str} is not allowed in the hardened mode"
+            )
+            .into());
+        }
+    }
 
     // reqwest::Client is not Send on wasm
     wasm_nonsend(move || async move {
@@ -99,13 +178,31 @@ pub(crate) async fn download<T: 'static + Send, F: Future<Output = anyhow::Resul
                 .get(url.clone())
                 .send()
                 .await
-                .with_context(|| format!("Failed to download {url_str}"))?;
+                .with_context(|| format!("Failed to download {url_str}"))
+                .map_err(|err| AssetError::Network(Arc::new(err)))?;
             if !resp.status().is_success() {
                 log::warn!("Request for {} failed: {:?}", url_str, resp.status());
-                return Err(anyhow!(
-                    "Downloading {url_str} failed, bad status code: {:?}",
-                    resp.status()
-                ));
+                let status = resp.status();
+                let err = Arc::new(anyhow::Error::new(HttpStatusError {
+                    url: url_str,
+                    status,
+                }));
+                return Err(if status == reqwest::StatusCode::NOT_FOUND {
+                    AssetError::NotFound(err)
+                } else {
+                    AssetError::Network(err)
+                });
+            }
+            if let Some(limits) = &limits {
+                // Bodies of unknown size could be endless, so they are refused too
+                let size = resp.content_length().unwrap_or(u64::MAX);
+                if size > limits.max_asset_size {
+                    return Err(anyhow::anyhow!(
+                        "{url_str} is larger than the {} bytes that assets can be in the hardened mode",
+                        limits.max_asset_size
+                    )
+                    .into());
+                }
             }
             match map(resp).await {
                 Ok(res) => {
@@ -122,7 +219,10 @@ pub(crate) async fn download<T: 'static + Send, F: Future<Output = anyhow::Resul
             }
         }
 
-        Err(anyhow::anyhow!("Failed to download body of {}", url_str))
+        Err(AssetError::Network(Arc::new(anyhow::anyhow!(
+            "Failed to download body of {}",
+            url_str
+        ))))
     })
     .await
 }
@@ -137,9 +237,9 @@ impl BytesFromUrl {
     pub fn new(url: AbsAssetUrl, cache_on_disk: bool) -> Self {
         Self { url, cache_on_disk }
     }
-    pub fn parse_url(url: impl AsRef<str>, cache_on_disk: bool) -> anyhow::Result<Self> {
+    pub fn parse_url(url: impl AsRef<str>, cache_on_disk: bool) -> AssetResult<Self> {
         Ok(Self {
-            url: AbsAssetUrl::parse(url)?,
+            url: AbsAssetUrl::parse(url).map_err(AssetError::invalid_url)?,
             cache_on_disk,
         })
     }
@@ -156,26 +256,20 @@ impl AsyncAssetKey<AssetResult<Arc<Vec<u8>>>> for BytesFromUrl {
             .await?;
             let semaphore = FileReadSemaphore.get(&assets);
             let _permit = semaphore.acquire().await;
-            return Ok(Arc::new(
-                ambient_sys::fs::read(&*path)
-                    .await
-                    .context(format!("Failed to read file: {path:?}"))?,
-            ));
+            return Ok(Arc::new(ambient_sys::fs::read(&*path).await.map_err(
+                |err| AssetError::io(err, format!("Failed to read file: {path:?}")),
+            )?));
         }
 
-        if let Some(path) = self.url.to_file_path()? {
-            return Ok(Arc::new(
-                ambient_sys::fs::read(path)
-                    .await
-                    .context(format!("Failed to read file at: {:}", self.url.0))?,
-            ));
+        if let Some(path) = self.url.to_file_path().map_err(AssetError::invalid_url)? {
+            return Ok(Arc::new(pak::read_file(&assets, &path).await?));
         }
 
         let body = download(
             &assets,
             self.url
                 .to_download_url(&assets)
-                .map_err(anyhow::Error::new)?
+                .map_err(AssetError::invalid_url)?
                 .0,
             |resp| async { Ok(resp.bytes().await?) },
         )
@@ -197,9 +291,9 @@ pub struct BytesFromUrlCachedPath {
     pub url: AbsAssetUrl,
 }
 impl BytesFromUrlCachedPath {
-    pub fn parse_url(url: impl AsRef<str>) -> anyhow::Result<Self> {
+    pub fn parse_url(url: impl AsRef<str>) -> AssetResult<Self> {
         Ok(Self {
-            url: AbsAssetUrl::parse(url)?,
+            url: AbsAssetUrl::parse(url).map_err(AssetError::invalid_url)?,
         })
     }
 }
@@ -223,7 +317,7 @@ impl AsyncAssetKey<AssetResult<Arc<PathBuf>>> for BytesFromUrlCachedPath {
         AssetKeepalive::Forever
     }
     async fn load(self, assets: AssetCache) -> AssetResult<Arc<PathBuf>> {
-        if let Some(path) = self.url.to_file_path()? {
+        if let Some(path) = self.url.to_file_path().map_err(AssetError::invalid_url)? {
             return Ok(Arc::new(path));
         }
 
@@ -232,14 +326,15 @@ impl AsyncAssetKey<AssetResult<Arc<PathBuf>>> for BytesFromUrlCachedPath {
             use tokio::io::AsyncWriteExt;
             let mut dir = path.clone();
             dir.pop();
-            std::fs::create_dir_all(&dir)
-                .context(format!("Failed to create asset dir: {dir:?}"))?;
+            std::fs::create_dir_all(&dir).map_err(|err| {
+                AssetError::io(err, format!("Failed to create asset dir: {dir:?}"))
+            })?;
             let tmp_path = path.with_extension(".downloading");
             download(
                 &assets,
                 self.url
                     .to_download_url(&assets)
-                    .map_err(anyhow::Error::new)?
+                    .map_err(AssetError::invalid_url)?
                     .0,
                 {
                     let tmp_path = tmp_path.clone();
@@ -264,9 +359,12 @@ impl AsyncAssetKey<AssetResult<Arc<PathBuf>>> for BytesFromUrlCachedPath {
                 },
             )
             .await?;
-            std::fs::rename(&tmp_path, &path).context(format!(
-                "Failed to rename tmp file, from: {tmp_path:?}, to: {path:?}"
-            ))?;
+            std::fs::rename(&tmp_path, &path).map_err(|err| {
+                AssetError::io(
+                    err,
+                    format!("Failed to rename tmp file, from: {tmp_path:?}, to: {path:?}"),
+                )
+            })?;
             log::info!("Cached asset at {:?}", path);
         }
 
@@ -316,9 +414,9 @@ impl<T> JsonFromUrl<T> {
             _type: PhantomData,
         }
     }
-    pub fn parse_url(url: impl AsRef<str>, cache_on_disk: bool) -> anyhow::Result<Self> {
+    pub fn parse_url(url: impl AsRef<str>, cache_on_disk: bool) -> AssetResult<Self> {
         Ok(Self {
-            url: AbsAssetUrl::parse(url)?,
+            url: AbsAssetUrl::parse(url).map_err(AssetError::invalid_url)?,
             cache_on_disk,
             _type: PhantomData,
         })
@@ -343,7 +441,7 @@ impl<T: DeserializeOwned + Sync + Send + 'static> AsyncAssetKey<AssetResult<Arc<
         }
         .get(&assets)
         .await?;
-        Ok(serde_json::from_slice(&data).context("Json failed to parse")?)
+        serde_json::from_slice(&data).map_err(|err| AssetError::decode(err, "Json failed to parse"))
     }
 }
 
@@ -371,9 +469,9 @@ impl<T> BincodeFromUrl<T> {
             type_: PhantomData,
         }
     }
-    pub fn parse_url(url: impl AsRef<str>, cache_on_disk: bool) -> anyhow::Result<Self> {
+    pub fn parse_url(url: impl AsRef<str>, cache_on_disk: bool) -> AssetResult<Self> {
         Ok(Self {
-            url: AbsAssetUrl::parse(url)?,
+            url: AbsAssetUrl::parse(url).map_err(AssetError::invalid_url)?,
             cache_on_disk,
             type_: PhantomData,
         })
@@ -390,10 +488,39 @@ impl<T: DeserializeOwned + std::fmt::Debug + Sync + Send + 'static>
         }
         .get(&assets)
         .await?;
-        Ok(Arc::new(
-            bincode::deserialize(&data).context("Failed to deserialize")?,
-        ))
+        Ok(Arc::new(bincode::deserialize(&data).map_err(|err| {
+            AssetError::decode(err, "Failed to deserialize")
+        })?))
     }
 }
 
 pub type MeshFromUrl = BincodeFromUrl<Mesh>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_kinds() {
+        let not_found = std::io::Error::new(std::io::ErrorKind::NotFound, "missing");
+        let err = AssetError::io(not_found, "Failed to read file");
+        assert!(matches!(err, AssetError::NotFound(_)));
+        assert_eq!(err.to_string(), "Failed to read file");
+
+        let denied = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+        assert!(matches!(
+            AssetError::io(denied, "Failed to read file"),
+            AssetError::Io(_)
+        ));
+
+        let decode = serde_json::from_str::<u32>("{").unwrap_err();
+        assert!(matches!(
+            AssetError::decode(decode, "Json failed to parse"),
+            AssetError::Decode(_)
+        ));
+        assert!(matches!(
+            AssetError::from(anyhow::anyhow!("Something else")),
+            AssetError::Other(_)
+        ));
+    }
+}
@@ -0,0 +1,147 @@
+//! Scratch memory for the transient allocations of the hot per-frame paths, so that they don't allocate and free heap
+//! memory every frame.
+
+use std::cell::{Cell, RefCell};
+
+pub use bumpalo::{collections::Vec as ArenaVec, Bump};
+
+thread_local! {
+    static ARENA: RefCell<Bump> = RefCell::new(Bump::new());
+    static DEPTH: Cell<usize> = Cell::new(0);
+    static STATS: Cell<FrameArenaStats> = Cell::new(FrameArenaStats::default());
+}
+
+/// Calls `f` with the bump allocator of this thread. Everything allocated in it is freed at once when the outermost
+/// call on the thread returns, but the memory is kept for the next call, so allocating is just a pointer bump once
+/// the arena has grown to the size a frame needs.
+pub fn with_frame_arena<R>(f: impl FnOnce(&Bump) -> R) -> R {
+    struct Scope;
+    impl Drop for Scope {
+        fn drop(&mut self) {
+            let depth = DEPTH.with(|depth| {
+                depth.set(depth.get() - 1);
+                depth.get()
+            });
+            if depth == 0 {
+                ARENA.with(|arena| {
+                    let mut arena = arena.borrow_mut();
+                    let used = arena
+                        .iter_allocated_chunks()
+                        .map(|chunk| chunk.len())
+                        .sum::<usize>();
+                    record_allocation(used, false);
+                    arena.reset();
+                });
+            }
+        }
+    }
+
+    DEPTH.with(|depth| depth.set(depth.get() + 1));
+    let _scope = Scope;
+    ARENA.with(|arena| {
+        let arena = arena.borrow();
+        let capacity = arena.allocated_bytes();
+        let res = f(&arena);
+        if arena.allocated_bytes() > capacity {
+            update_stats(|stats| stats.heap_allocations += 1);
+        }
+        res
+    })
+}
+
+/// Adds `bytes` of transient memory to the [FrameArenaStats] of this thread, for paths that reuse their own buffers
+/// instead of the frame arena. `heap_allocated` is true if the buffer had to grow.
+pub fn record_allocation(bytes: usize, heap_allocated: bool) {
+    update_stats(|stats| {
+        stats.bytes += bytes;
+        stats.scopes += 1;
+        stats.heap_allocations += heap_allocated as usize;
+    });
+}
+
+fn update_stats(f: impl FnOnce(&mut FrameArenaStats)) {
+    STATS.with(|cell| {
+        let mut stats = cell.get();
+        f(&mut stats);
+        cell.set(stats);
+    });
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameArenaStats {
+    /// The transient bytes that were allocated
+    pub bytes: usize,
+    /// How many times the frame arenas or reused buffers were used
+    pub scopes: usize,
+    /// How many times they had to grow, which allocates on the heap
+    pub heap_allocations: usize,
+}
+
+/// Returns the stats of the arena of this thread since the last call on it, and adds them to the current profiling
+/// scope. Called once per frame, on the thread that runs the frame; the arenas of other threads, such as another app
+/// or server in the same process, keep their own stats.
+pub fn take_frame_arena_stats() -> FrameArenaStats {
+    let stats = STATS.with(|stats| stats.take());
+    ambient_profiling::scope!(
+        "Frame arenas",
+        &format!(
+            "{} bytes in {} scopes, {} heap allocations",
+            stats.bytes, stats.scopes, stats.heap_allocations
+        )
+    );
+    stats
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn arena_is_reused() {
+        with_frame_arena(|arena| {
+            let mut values = ArenaVec::with_capacity_in(2048, arena);
+            values.extend(0..2048u32);
+        });
+        take_frame_arena_stats();
+        with_frame_arena(|arena| {
+            let mut values = ArenaVec::with_capacity_in(1024, arena);
+            values.extend(0..1024u32);
+            // Nested scopes share the arena
+            with_frame_arena(|arena| {
+                arena.alloc(5u32);
+            });
+        });
+        let stats = take_frame_arena_stats();
+        assert_eq!(stats.heap_allocations, 0);
+        assert_eq!(stats.scopes, 1);
+        assert!(stats.bytes >= 1024 * 4);
+    }
+
+    #[test]
+    fn threads_keep_their_own_stats() {
+        take_frame_arena_stats();
+        record_allocation(100, true);
+        let other = std::thread::spawn(|| {
+            record_allocation(10, false);
+            take_frame_arena_stats()
+        })
+        .join()
+        .unwrap();
+        assert_eq!(
+            other,
+            FrameArenaStats {
+                bytes: 10,
+                scopes: 1,
+                heap_allocations: 0
+            }
+        );
+        assert_eq!(
+            take_frame_arena_stats(),
+            FrameArenaStats {
+                bytes: 100,
+                scopes: 1,
+                heap_allocations: 1
+            }
+        );
+    }
+}
@@ -12,6 +12,7 @@ pub struct MeshBuilder {
     pub texcoords: Vec<Vec<Vec2>>,
     pub joint_indices: Vec<UVec4>,
     pub joint_weights: Vec<Vec4>,
+    pub morph_targets: Vec<MorphTarget>,
     pub indices: Vec<u32>,
 }
 
@@ -33,6 +34,11 @@ impl MeshBuilder {
                     .iter()
                     .all(|tc| tc.len() == self.positions.len())
         );
+        ensure!(self.morph_targets.iter().all(|target| {
+            target.positions.len() == self.positions.len()
+                && (target.normals.is_empty() || target.normals.len() == self.positions.len())
+                && (target.tangents.is_empty() || target.tangents.len() == self.positions.len())
+        }));
 
         let mut aabb: crate::shapes::Cuboid = AABB::new_invalid();
         for &position in &self.positions {
@@ -48,12 +54,23 @@ impl MeshBuilder {
             texcoords: self.texcoords,
             joint_indices: self.joint_indices,
             joint_weights: self.joint_weights,
+            morph_targets: self.morph_targets,
             indices: self.indices,
             aabb,
         })
     }
 }
 
+/// A morph target (blendshape): the offsets that are added to the vertices of a mesh, times the target's weight
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MorphTarget {
+    pub positions: Vec<Vec3>,
+    /// Either empty, or one offset per vertex
+    pub normals: Vec<Vec3>,
+    /// Either empty, or one offset per vertex
+    pub tangents: Vec<Vec3>,
+}
+
 pub fn generate_tangents(positions: &[Vec3], texcoords: &[Vec2], indices: &[u32]) -> Vec<Vec3> {
     let mut tangents = vec![Vec3::ZERO; positions.len()];
     let mut tangent_counts = vec![0.0; positions.len()];
@@ -105,6 +122,7 @@ pub struct Mesh {
     texcoords: Vec<Vec<Vec2>>,
     joint_indices: Vec<UVec4>,
     joint_weights: Vec<Vec4>,
+    morph_targets: Vec<MorphTarget>,
     indices: Vec<u32>,
     aabb: AABB,
 }
@@ -144,6 +162,10 @@ impl Mesh {
         &self.joint_weights
     }
 
+    pub fn morph_targets(&self) -> &[MorphTarget] {
+        &self.morph_targets
+    }
+
     pub fn indices(&self) -> &[u32] {
         &self.indices
     }
@@ -170,6 +192,15 @@ impl Mesh {
             .iter()
             .map(|x| std::mem::size_of_val(x.as_slice()))
             .sum::<usize>();
+        byte_size += self
+            .morph_targets
+            .iter()
+            .map(|x| {
+                std::mem::size_of_val(x.positions.as_slice())
+                    + std::mem::size_of_val(x.normals.as_slice())
+                    + std::mem::size_of_val(x.tangents.as_slice())
+            })
+            .sum::<usize>();
 
         byte_size
     }
@@ -190,6 +221,20 @@ impl Mesh {
         for n in &mut self.normals {
             *n = transform.transform_vector3(*n).normalize();
         }
+        for t in &mut self.tangents {
+            *t = transform.transform_vector3(*t).normalize_or_zero();
+        }
+        for target in &mut self.morph_targets {
+            for p in &mut target.positions {
+                *p = transform.transform_vector3(*p);
+            }
+            for n in &mut target.normals {
+                *n = transform.transform_vector3(*n);
+            }
+            for t in &mut target.tangents {
+                *t = transform.transform_vector3(*t);
+            }
+        }
         self
     }
 
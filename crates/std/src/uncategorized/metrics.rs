@@ -0,0 +1,156 @@
+//! Runtime statistics of the process, which a server can expose in the Prometheus text format so that a fleet of
+//! servers can be monitored. Nothing is recorded until [enable] is called, so the recording functions are cheap to
+//! call from hot paths when the statistics aren't exposed.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static METRICS: Lazy<Mutex<BTreeMap<&'static str, Metric>>> = Lazy::new(Default::default);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    /// A value that only goes up, like a number of bytes sent
+    Counter,
+    /// A value that goes up and down, like a number of players
+    Gauge,
+    /// The sum and count of observations, like the durations of ticks
+    Summary,
+}
+impl MetricKind {
+    fn name(self) -> &'static str {
+        match self {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+            MetricKind::Summary => "summary",
+        }
+    }
+}
+
+type Labels = Vec<(&'static str, String)>;
+
+#[derive(Debug)]
+struct Metric {
+    kind: MetricKind,
+    help: &'static str,
+    /// The value (or sum) and count of observations of each set of labels
+    values: BTreeMap<Labels, (f64, u64)>,
+}
+
+/// Starts recording statistics
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Declares a metric; values recorded for metrics that haven't been declared are ignored
+pub fn describe(name: &'static str, kind: MetricKind, help: &'static str) {
+    METRICS.lock().entry(name).or_insert_with(|| Metric { kind, help, values: BTreeMap::new() });
+}
+
+fn record(name: &'static str, labels: &[(&'static str, &str)], update: impl FnOnce(&mut (f64, u64))) {
+    if !is_enabled() {
+        return;
+    }
+    let mut metrics = METRICS.lock();
+    let Some(metric) = metrics.get_mut(name) else {
+        return;
+    };
+    let labels = labels.iter().map(|(key, value)| (*key, value.to_string())).collect();
+    update(metric.values.entry(labels).or_default());
+}
+
+/// Adds to a [MetricKind::Counter]
+pub fn add(name: &'static str, labels: &[(&'static str, &str)], value: f64) {
+    record(name, labels, |(total, _)| *total += value);
+}
+
+/// Sets a [MetricKind::Gauge]
+pub fn set(name: &'static str, labels: &[(&'static str, &str)], value: f64) {
+    record(name, labels, |(current, _)| *current = value);
+}
+
+/// Adds an observation to a [MetricKind::Summary]
+pub fn observe(name: &'static str, labels: &[(&'static str, &str)], value: f64) {
+    record(name, labels, |(sum, count)| {
+        *sum += value;
+        *count += 1;
+    });
+}
+
+/// Keeps only the values of a metric whose labels `keep` returns true for, to drop the gauges of something that no
+/// longer exists
+pub fn retain(name: &'static str, mut keep: impl FnMut(&[(&'static str, String)]) -> bool) {
+    if let Some(metric) = METRICS.lock().get_mut(name) {
+        metric.values.retain(|labels, _| keep(labels));
+    }
+}
+
+/// All the metrics that have values, in the Prometheus text exposition format
+pub fn render() -> String {
+    let metrics = METRICS.lock();
+    let mut out = String::new();
+    for (name, metric) in metrics.iter().filter(|(_, metric)| !metric.values.is_empty()) {
+        writeln!(out, "# HELP {name} {}", metric.help).unwrap();
+        writeln!(out, "# TYPE {name} {}", metric.kind.name()).unwrap();
+        for (labels, (value, count)) in &metric.values {
+            let labels = render_labels(labels);
+            if metric.kind == MetricKind::Summary {
+                writeln!(out, "{name}_sum{labels} {value}").unwrap();
+                writeln!(out, "{name}_count{labels} {count}").unwrap();
+            } else {
+                writeln!(out, "{name}{labels} {value}").unwrap();
+            }
+        }
+    }
+    out
+}
+
+fn render_labels(labels: &[(&'static str, String)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+    let labels = labels
+        .iter()
+        .map(|(key, value)| format!("{key}=\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")))
+        .collect::<Vec<_>>();
+    format!("{{{}}}", labels.join(","))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn renders_prometheus_text() {
+        enable();
+        describe("test_sent_bytes_total", MetricKind::Counter, "Bytes sent");
+        describe("test_tick_seconds", MetricKind::Summary, "Tick durations");
+        add("test_sent_bytes_total", &[("handler", "rpc")], 10.);
+        add("test_sent_bytes_total", &[("handler", "rpc")], 5.);
+        add("test_sent_bytes_total", &[("handler", "say \"hi\"")], 1.);
+        observe("test_tick_seconds", &[], 0.25);
+        observe("test_tick_seconds", &[], 0.5);
+        // Not declared, so ignored
+        add("test_undeclared_total", &[], 1.);
+
+        let text = render();
+        assert!(text.contains("# TYPE test_sent_bytes_total counter\n"));
+        assert!(text.contains("test_sent_bytes_total{handler=\"rpc\"} 15\n"));
+        assert!(text.contains("test_sent_bytes_total{handler=\"say \\\"hi\\\"\"} 1\n"));
+        assert!(text.contains("test_tick_seconds_sum 0.75\ntest_tick_seconds_count 2\n"));
+        assert!(!text.contains("test_undeclared_total"));
+
+        retain("test_sent_bytes_total", |labels| labels[0].1 == "rpc");
+        assert!(!render().contains("hi"));
+    }
+}
@@ -7,9 +7,13 @@ pub mod disk_cache;
 pub mod download_asset;
 pub mod encode;
 pub mod fps_counter;
+pub mod frame_arena;
 
 pub mod mesh;
+pub mod metrics;
 pub mod ordered_glam;
+pub mod pak;
+pub mod sandbox;
 pub mod shapes;
 pub mod sparse_vec;
 pub mod time;
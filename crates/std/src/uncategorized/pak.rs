@@ -0,0 +1,503 @@
+//! Pak archives: the files of a build split into compressed, content-addressed chunks and packed into a single file,
+//! with an index of the chunks that make up each file stored next to it. Chunks are shared between identical content,
+//! and a file that didn't change between two releases keeps its chunks, so patches only need to carry the chunks that
+//! did.
+//!
+//! Chunks can be encrypted with AES-256-GCM, so that the assets of a shipped project aren't trivially extractable. The
+//! key is provisioned when packaging, and given to the runtime with `AMBIENT_PAK_KEY`, either in the environment or
+//! when the runtime is compiled.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::Context;
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use parking_lot::RwLock;
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    rand::{SecureRandom, SystemRandom},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset_cache::{AssetCache, SyncAssetKey, SyncAssetKeyExt},
+    download_asset::{AssetError, AssetResult},
+    path::path_to_unix_string,
+};
+
+/// Files are split into chunks of at most this many bytes before they are compressed
+pub const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Describes the contents of a pak. Stored next to the pak at [index_path].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PakIndex {
+    /// By the path of the file relative to the directory it was packed from, with `/` separators
+    pub files: BTreeMap<String, PakFile>,
+    /// By the hash of their uncompressed content
+    pub chunks: BTreeMap<String, PakChunk>,
+    /// Whether the chunks are encrypted
+    #[serde(default)]
+    pub encrypted: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PakFile {
+    /// The uncompressed size
+    pub size: u64,
+    /// The hashes of the chunks of the file, in order
+    pub chunks: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PakChunk {
+    /// Where the compressed chunk starts in the pak
+    pub offset: u64,
+    /// The size of the chunk in the pak, including the nonce and tag of encrypted chunks
+    pub compressed_size: u64,
+    pub size: u64,
+}
+
+/// The environment variable the key of encrypted paks is read from, at runtime or when the runtime is compiled
+pub const PAK_KEY_VAR: &str = "AMBIENT_PAK_KEY";
+
+/// An AES-256-GCM key that the chunks of a pak are encrypted with
+pub struct PakKey(LessSafeKey);
+impl PakKey {
+    /// Parses a key from 64 hexadecimal digits
+    pub fn from_hex(hex: &str) -> anyhow::Result<Self> {
+        let bytes = data_encoding::HEXLOWER_PERMISSIVE
+            .decode(hex.trim().as_bytes())
+            .context("The pak key is not hexadecimal")?;
+        let key = UnboundKey::new(&AES_256_GCM, &bytes)
+            .map_err(|_| anyhow::anyhow!("The pak key must be 32 bytes long"))?;
+        Ok(Self(LessSafeKey::new(key)))
+    }
+    /// Generates a new key, and returns it with its hexadecimal representation
+    pub fn generate() -> anyhow::Result<(Self, String)> {
+        let mut bytes = [0; 32];
+        SystemRandom::new()
+            .fill(&mut bytes)
+            .map_err(|_| anyhow::anyhow!("Failed to generate a pak key"))?;
+        let hex = data_encoding::HEXLOWER.encode(&bytes);
+        Ok((Self::from_hex(&hex)?, hex))
+    }
+    /// The key in [PAK_KEY_VAR], preferring the environment over the key the runtime was compiled with
+    pub fn from_env() -> anyhow::Result<Option<Self>> {
+        std::env::var(PAK_KEY_VAR)
+            .ok()
+            .as_deref()
+            .or(option_env!("AMBIENT_PAK_KEY"))
+            .map(Self::from_hex)
+            .transpose()
+    }
+
+    /// Encrypts a chunk with a random nonce, which is prepended to it. The hash of the chunk is authenticated with it,
+    /// so that chunks can't be swapped around.
+    fn seal(&self, data: &[u8], hash: &str) -> anyhow::Result<Vec<u8>> {
+        let mut nonce = [0; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| anyhow::anyhow!("Failed to generate a nonce"))?;
+        let mut sealed = data.to_vec();
+        self.0
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(hash.as_bytes()),
+                &mut sealed,
+            )
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt chunk {hash}"))?;
+        Ok([nonce.as_slice(), sealed.as_slice()].concat())
+    }
+
+    fn open(&self, data: &[u8], hash: &str) -> anyhow::Result<Vec<u8>> {
+        anyhow::ensure!(data.len() >= NONCE_LEN, "Chunk {hash} is corrupt");
+        let (nonce, sealed) = data.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce)
+            .map_err(|_| anyhow::anyhow!("Chunk {hash} is corrupt"))?;
+        let mut opened = sealed.to_vec();
+        let len = self
+            .0
+            .open_in_place(nonce, Aad::from(hash.as_bytes()), &mut opened)
+            .map_err(|_| anyhow::anyhow!("Failed to decrypt chunk {hash}; is the pak key right?"))?
+            .len();
+        opened.truncate(len);
+        Ok(opened)
+    }
+}
+impl std::fmt::Debug for PakKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PakKey").finish_non_exhaustive()
+    }
+}
+
+/// The path of the index of the pak at `pak_path`, e.g. `assets.pak.json` for `assets.pak`
+pub fn index_path(pak_path: &Path) -> PathBuf {
+    let mut path = pak_path.as_os_str().to_owned();
+    path.push(".json");
+    PathBuf::from(path)
+}
+
+/// The hash a chunk is identified by
+pub fn chunk_hash(data: &[u8]) -> String {
+    let digest = ring::digest::digest(&ring::digest::SHA256, data);
+    data_encoding::HEXLOWER.encode(digest.as_ref())
+}
+
+/// Compresses a chunk with the given `hash`, and encrypts it if there is a `key`
+pub fn compress_chunk(data: &[u8], hash: &str, key: Option<&PakKey>) -> anyhow::Result<Vec<u8>> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    let compressed = encoder.finish()?;
+    match key {
+        Some(key) => key.seal(&compressed, hash),
+        None => Ok(compressed),
+    }
+}
+
+/// Decrypts a chunk if there is a `key`, decompresses it, and checks that its content is `size` bytes long and matches
+/// its `hash`
+pub fn decompress_chunk(
+    compressed: &[u8],
+    hash: &str,
+    size: u64,
+    key: Option<&PakKey>,
+) -> anyhow::Result<Vec<u8>> {
+    anyhow::ensure!(
+        size <= CHUNK_SIZE as u64,
+        "Chunk {hash} is larger than chunks can be"
+    );
+    let opened;
+    let compressed = match key {
+        Some(key) => {
+            opened = key.open(compressed, hash)?;
+            &opened
+        }
+        None => compressed,
+    };
+    let mut data = Vec::with_capacity(size as usize);
+    // Decompressing stops right after the size, so that a corrupt chunk can't expand without bounds
+    DeflateDecoder::new(compressed)
+        .take(size + 1)
+        .read_to_end(&mut data)
+        .with_context(|| format!("Failed to decompress chunk {hash}"))?;
+    anyhow::ensure!(
+        data.len() as u64 == size,
+        "Chunk {hash} doesn't decompress to its size of {size} bytes"
+    );
+    anyhow::ensure!(chunk_hash(&data) == hash, "Chunk {hash} is corrupt");
+    Ok(data)
+}
+
+/// Packs the `files`, given as their path in the pak and their path on disk, into a pak at `pak_path`, and writes its
+/// index next to it. The chunks are encrypted if there is a `key`.
+pub fn write_pak(
+    pak_path: &Path,
+    files: impl IntoIterator<Item = (String, PathBuf)>,
+    key: Option<&PakKey>,
+) -> anyhow::Result<PakIndex> {
+    let mut pak = BufWriter::new(
+        File::create(pak_path).with_context(|| format!("Failed to create pak {pak_path:?}"))?,
+    );
+    let mut index = PakIndex {
+        encrypted: key.is_some(),
+        ..Default::default()
+    };
+    let mut offset = 0;
+    for (path, fs_path) in files {
+        let data =
+            std::fs::read(&fs_path).with_context(|| format!("Failed to read file {fs_path:?}"))?;
+        let mut file = PakFile {
+            size: data.len() as u64,
+            chunks: Vec::new(),
+        };
+        for chunk in data.chunks(CHUNK_SIZE) {
+            let hash = chunk_hash(chunk);
+            if !index.chunks.contains_key(&hash) {
+                let compressed = compress_chunk(chunk, &hash, key)?;
+                pak.write_all(&compressed)?;
+                index.chunks.insert(
+                    hash.clone(),
+                    PakChunk {
+                        offset,
+                        compressed_size: compressed.len() as u64,
+                        size: chunk.len() as u64,
+                    },
+                );
+                offset += compressed.len() as u64;
+            }
+            file.chunks.push(hash);
+        }
+        index.files.insert(path, file);
+    }
+    pak.flush()?;
+    std::fs::write(index_path(pak_path), serde_json::to_string_pretty(&index)?)?;
+    Ok(index)
+}
+
+/// A pak opened for reading
+#[derive(Debug)]
+pub struct Pak {
+    path: PathBuf,
+    index: PakIndex,
+    key: Option<PakKey>,
+}
+impl Pak {
+    /// Opens the pak at `path`, with the key from [PakKey::from_env] if it is encrypted
+    pub fn open(path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let key = PakKey::from_env()?;
+        Self::open_with_key(path, key)
+    }
+    pub fn open_with_key(path: impl Into<PathBuf>, key: Option<PakKey>) -> anyhow::Result<Self> {
+        let path = path.into();
+        let index_path = index_path(&path);
+        let index = std::fs::read(&index_path)
+            .with_context(|| format!("Failed to read pak index {index_path:?}"))?;
+        let index: PakIndex = serde_json::from_slice(&index)
+            .with_context(|| format!("Failed to parse pak index {index_path:?}"))?;
+        anyhow::ensure!(
+            !index.encrypted || key.is_some(),
+            "The pak {path:?} is encrypted, but no key was provided with {PAK_KEY_VAR}"
+        );
+        Ok(Self {
+            key: if index.encrypted { key } else { None },
+            path,
+            index,
+        })
+    }
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    pub fn index(&self) -> &PakIndex {
+        &self.index
+    }
+    /// The key the chunks are encrypted with, if they are
+    pub fn key(&self) -> Option<&PakKey> {
+        self.key.as_ref()
+    }
+    /// Reads the file at `path` in the pak; `None` if the pak doesn't contain it. The index is checked against the pak
+    /// before anything is read, as it could have been tampered with.
+    pub fn read(&self, path: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let Some(file) = self.index.files.get(path) else {
+            return Ok(None);
+        };
+        let chunks = file
+            .chunks
+            .iter()
+            .map(|hash| {
+                let chunk = self
+                    .index
+                    .chunks
+                    .get(hash)
+                    .with_context(|| format!("Chunk {hash} is missing from the index"))?;
+                Ok((hash, *chunk))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let size = chunks
+            .iter()
+            .try_fold(0u64, |size, (_, chunk)| size.checked_add(chunk.size));
+        anyhow::ensure!(
+            size == Some(file.size),
+            "The chunks of {path} don't add up to its size"
+        );
+
+        let mut pak = File::open(&self.path)
+            .with_context(|| format!("Failed to open pak {:?}", self.path))?;
+        let pak_size = pak.metadata()?.len();
+        let mut data = Vec::new();
+        for (hash, chunk) in chunks {
+            let end = chunk.offset.checked_add(chunk.compressed_size);
+            anyhow::ensure!(
+                end.map_or(false, |end| end <= pak_size),
+                "Chunk {hash} is outside of the pak {:?}",
+                self.path
+            );
+            let mut compressed = vec![0; chunk.compressed_size as usize];
+            pak.seek(SeekFrom::Start(chunk.offset))?;
+            pak.read_exact(&mut compressed)?;
+            data.extend(decompress_chunk(
+                &compressed,
+                hash,
+                chunk.size,
+                self.key.as_ref(),
+            )?);
+        }
+        Ok(Some(data))
+    }
+}
+
+/// The mounted paks, with the directories they are mounted at
+#[derive(Debug, Clone)]
+pub struct MountedPaksKey;
+impl SyncAssetKey<Arc<RwLock<Vec<(PathBuf, Arc<Pak>)>>>> for MountedPaksKey {
+    fn load(&self, _assets: AssetCache) -> Arc<RwLock<Vec<(PathBuf, Arc<Pak>)>>> {
+        Default::default()
+    }
+}
+
+/// Mounts `pak` at `dir`, so that the files under `dir` that are read through [read_file], like the assets loaded from
+/// local URLs, are read from the pak. Paks mounted later take precedence.
+pub fn mount(assets: &AssetCache, dir: impl Into<PathBuf>, pak: Pak) {
+    MountedPaksKey
+        .get(assets)
+        .write()
+        .push((dir.into(), Arc::new(pak)));
+}
+
+/// Reads the file at `path` from the mounted paks; `None` if none of them contain it. The pak is read on a blocking
+/// thread, so that the runtime isn't held up.
+pub async fn read_mounted(assets: &AssetCache, path: &Path) -> anyhow::Result<Option<Vec<u8>>> {
+    let paks = MountedPaksKey
+        .get(assets)
+        .read()
+        .iter()
+        .rev()
+        .filter_map(|(dir, pak)| {
+            let relative = path.strip_prefix(dir).ok()?;
+            Some((path_to_unix_string(relative), pak.clone()))
+        })
+        .collect::<Vec<_>>();
+    if paks.is_empty() {
+        return Ok(None);
+    }
+    ambient_sys::task::spawn_blocking(move || {
+        for (relative, pak) in paks {
+            if let Some(data) = pak.read(&relative)? {
+                return Ok(Some(data));
+            }
+        }
+        Ok(None)
+    })
+    .await?
+}
+
+/// Reads the file at `path` from the mounted paks if one of them contains it, and from the disk otherwise
+pub async fn read_file(assets: &AssetCache, path: &Path) -> AssetResult<Vec<u8>> {
+    let mounted = read_mounted(assets, path)
+        .await
+        .map_err(|err| AssetError::Io(Arc::new(err)))?;
+    if let Some(data) = mounted {
+        return Ok(data);
+    }
+    ambient_sys::fs::read(path)
+        .await
+        .map_err(|err| AssetError::io(err, format!("Failed to read file at: {path:?}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_and_read() {
+        let dir = std::env::temp_dir().join(format!("ambient_pak_test_{}", crate::ulid()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let large = (0..CHUNK_SIZE * 2 + 10)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>();
+        std::fs::write(dir.join("large.bin"), &large).unwrap();
+        std::fs::write(dir.join("copy.bin"), &large).unwrap();
+        std::fs::write(dir.join("empty.txt"), "").unwrap();
+
+        let pak_path = dir.join("assets.pak");
+        let index = write_pak(
+            &pak_path,
+            ["large.bin", "copy.bin", "empty.txt"].map(|name| (name.to_string(), dir.join(name))),
+            None,
+        )
+        .unwrap();
+        // The copy shares the chunks of the original
+        assert_eq!(index.chunks.len(), 3);
+
+        let pak = Pak::open_with_key(&pak_path, None).unwrap();
+        assert_eq!(pak.index(), &index);
+        assert_eq!(pak.read("large.bin").unwrap().unwrap(), large);
+        assert_eq!(pak.read("copy.bin").unwrap().unwrap(), large);
+        assert_eq!(pak.read("empty.txt").unwrap().unwrap(), Vec::<u8>::new());
+        assert!(pak.read("missing.txt").unwrap().is_none());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn encrypted() {
+        let dir = std::env::temp_dir().join(format!("ambient_pak_test_{}", crate::ulid()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("secret.txt"), "the cake is a lie").unwrap();
+
+        let (key, hex) = PakKey::generate().unwrap();
+        let pak_path = dir.join("assets.pak");
+        let index = write_pak(
+            &pak_path,
+            [("secret.txt".to_string(), dir.join("secret.txt"))],
+            Some(&key),
+        )
+        .unwrap();
+        assert!(index.encrypted);
+
+        assert!(Pak::open_with_key(&pak_path, None).is_err());
+        let pak = Pak::open_with_key(&pak_path, Some(PakKey::from_hex(&hex).unwrap())).unwrap();
+        assert_eq!(
+            pak.read("secret.txt").unwrap().unwrap(),
+            b"the cake is a lie"
+        );
+        let (other_key, _) = PakKey::generate().unwrap();
+        let pak = Pak::open_with_key(&pak_path, Some(other_key)).unwrap();
+        assert!(pak.read("secret.txt").is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn tampered_index() {
+        let dir = std::env::temp_dir().join(format!("ambient_pak_test_{}", crate::ulid()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("zeros.bin"), vec![0; 1000]).unwrap();
+
+        let pak_path = dir.join("assets.pak");
+        let index = write_pak(
+            &pak_path,
+            [("zeros.bin".to_string(), dir.join("zeros.bin"))],
+            None,
+        )
+        .unwrap();
+        let read = |tamper: fn(&mut PakIndex)| {
+            let mut index = index.clone();
+            tamper(&mut index);
+            Pak {
+                path: pak_path.clone(),
+                index,
+                key: None,
+            }
+            .read("zeros.bin")
+        };
+        assert_eq!(read(|_| {}).unwrap().unwrap(), vec![0; 1000]);
+
+        // The chunk lies outside of the pak
+        assert!(read(|index| {
+            let chunk = index.chunks.values_mut().next().unwrap();
+            chunk.compressed_size = u64::MAX;
+        })
+        .is_err());
+        // The chunks don't add up to the size of the file
+        assert!(read(|index| index.files.get_mut("zeros.bin").unwrap().size = u64::MAX).is_err());
+        // The chunk decompresses to more or less than its size
+        for size in [999, 1001] {
+            let mut index = index.clone();
+            index.files.get_mut("zeros.bin").unwrap().size = size;
+            index.chunks.values_mut().next().unwrap().size = size;
+            let pak = Pak {
+                path: pak_path.clone(),
+                index,
+                key: None,
+            };
+            assert!(pak.read("zeros.bin").is_err());
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
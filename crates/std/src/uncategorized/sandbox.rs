@@ -0,0 +1,215 @@
+//! The limits of the hardened mode, in which a server hosts projects that it doesn't trust, like those of a public
+//! fleet that plays any project. Nothing is limited until [enable] is called, which has to happen before the first
+//! module is loaded.
+
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+static LIMITS: Lazy<RwLock<Option<Arc<SandboxLimits>>>> = Lazy::new(Default::default);
+
+/// What a hosted project may use. The defaults are meant for a small game; a server can load others from a TOML file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SandboxLimits {
+    /// The most entities the world can have for the modules to spawn more
+    pub max_entities: usize,
+    /// The most bytes that a module can set one component to, serialized
+    pub max_component_size: u64,
+    /// The fuel each module gets per tick, which is roughly the number of instructions it can run
+    pub max_fuel_per_tick: u64,
+    /// The most bytes an asset can be downloaded with
+    pub max_asset_size: u64,
+    /// The hosts that assets can be downloaded from, besides that of the content of the project. A leading `*.`
+    /// matches the subdomains of a host.
+    pub allowed_hosts: Vec<String>,
+    /// The directories that files can be read from; usually the project and its build
+    #[serde(skip)]
+    pub allowed_roots: Vec<PathBuf>,
+}
+impl Default for SandboxLimits {
+    fn default() -> Self {
+        Self {
+            max_entities: 50_000,
+            max_component_size: 1024 * 1024,
+            max_fuel_per_tick: 100_000_000,
+            max_asset_size: 256 * 1024 * 1024,
+            allowed_hosts: Vec::new(),
+            allowed_roots: Vec::new(),
+        }
+    }
+}
+impl SandboxLimits {
+    pub fn allows_host(&self, host: &str) -> bool {
+        self.allowed_hosts
+            .iter()
+            .any(|pattern| host_matches(pattern, host))
+    }
+
+    /// Whether `url` can be downloaded, given that the content of the project is at `content_base`
+    pub fn allows_url(&self, url: &Url, content_base: &Url) -> bool {
+        match url.scheme() {
+            "http" | "https" => {
+                let Some(host) = url.host_str() else {
+                    return false;
+                };
+                // The content is trusted on its own scheme and port only, as others on its host may be internal
+                url.origin() == content_base.origin() || self.allows_host(host)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the file at `path` can be read. Symbolic links are followed, so that they can't lead out of the roots,
+    /// and paths that go up a directory are refused.
+    pub fn allows_path(&self, path: &Path) -> bool {
+        if path
+            .components()
+            .any(|component| component == Component::ParentDir)
+        {
+            return false;
+        }
+        let path = resolve_links(path);
+        self.allowed_roots.iter().any(|root| path.starts_with(root))
+    }
+}
+
+/// Whether `host` is `pattern`, or one of its subdomains if the pattern starts with `*.`
+pub fn host_matches(pattern: &str, host: &str) -> bool {
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host
+            .strip_suffix(domain)
+            .map_or(false, |sub| sub.ends_with('.')),
+        None => pattern == host,
+    }
+}
+
+/// Whether `url` is `base` or under it: on the same scheme, host and port, with the path segments of `base` as a prefix
+/// of its own. Unlike comparing them as strings, `https://example.com/project` doesn't contain
+/// `https://example.com/project-other` or `https://example.com.evil.net`
+pub fn url_within(url: &Url, base: &Url) -> bool {
+    let same_origin = url.scheme() == base.scheme()
+        && url.host_str() == base.host_str()
+        && url.port_or_known_default() == base.port_or_known_default();
+    let (Some(segments), Some(base_segments)) = (url.path_segments(), base.path_segments()) else {
+        return false;
+    };
+    // The empty segment of a trailing slash doesn't have to be in `url`
+    let base_segments = base_segments
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+    same_origin
+        && segments
+            .take(base_segments.len())
+            .eq(base_segments.iter().copied())
+}
+
+/// `path` with the symbolic links resolved in as much of it as exists
+fn resolve_links(path: &Path) -> PathBuf {
+    let mut existing = path;
+    let mut missing = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return missing
+                .iter()
+                .rev()
+                .fold(canonical, |path, name| path.join(name));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
+
+/// Starts enforcing `limits` in this process
+pub fn enable(mut limits: SandboxLimits) {
+    for root in &mut limits.allowed_roots {
+        if let Ok(canonical) = root.canonicalize() {
+            *root = canonical;
+        }
+    }
+    *LIMITS.write() = Some(Arc::new(limits));
+}
+
+/// The limits that are enforced, if the process is hardened
+pub fn limits() -> Option<Arc<SandboxLimits>> {
+    LIMITS.read().clone()
+}
+
+pub fn is_enabled() -> bool {
+    LIMITS.read().is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hosts() {
+        let limits = SandboxLimits {
+            allowed_hosts: vec![
+                "assets.example.com".to_string(),
+                "*.cdn.example.com".to_string(),
+            ],
+            ..Default::default()
+        };
+        let content = Url::parse("https://cdn.run/game/").unwrap();
+        let allows = |url: &str| limits.allows_url(&Url::parse(url).unwrap(), &content);
+        assert!(allows("https://cdn.run/game/assets/a.glb"));
+        assert!(allows("https://assets.example.com/a.glb"));
+        assert!(allows("http://eu.cdn.example.com/a.glb"));
+        assert!(!allows("https://cdn.example.com/a.glb"));
+        assert!(!allows("https://evilcdn.example.com/a.glb"));
+        assert!(!allows("https://example.com/a.glb"));
+        assert!(!allows("http://169.254.169.254/latest/meta-data"));
+        assert!(!allows("https://content.ambient.run:8080/admin"));
+        assert!(!allows("http://content.ambient.run/project/a.glb"));
+        assert!(!allows("ftp://assets.example.com/a.glb"));
+    }
+
+    #[test]
+    fn urls_within() {
+        let within = |url: &str, base: &str| {
+            url_within(&Url::parse(url).unwrap(), &Url::parse(base).unwrap())
+        };
+        let base = "https://cdn.run/game/";
+        assert!(within("https://cdn.run/game/assets/a.glb", base));
+        assert!(within("https://cdn.run:443/game/a.glb", base));
+        assert!(within("https://cdn.run/game/b/../a.glb", base));
+        assert!(!within("https://cdn.run/game-other/a.glb", base));
+        assert!(!within("https://cdn.run/game/../a.glb", base));
+        assert!(!within("https://cdn.run.evil.net/game/a.glb", base));
+        assert!(!within("http://cdn.run/game/a.glb", base));
+        assert!(!within("https://cdn.run:8443/game/a.glb", base));
+
+        let base = "file:///home/user/project/build/";
+        assert!(within("file:///home/user/project/build/a.glb", base));
+        assert!(!within("file:///home/user/project/a.glb", base));
+    }
+
+    #[test]
+    fn paths() {
+        let root = std::env::temp_dir().join(format!("ambient_sandbox_test_{}", crate::ulid()));
+        std::fs::create_dir_all(root.join("assets")).unwrap();
+        std::fs::write(root.join("assets/a.txt"), "a").unwrap();
+        let limits = SandboxLimits {
+            allowed_roots: vec![root.canonicalize().unwrap()],
+            ..Default::default()
+        };
+        assert!(limits.allows_path(&root.join("assets/a.txt")));
+        assert!(limits.allows_path(&root.join("assets/missing.txt")));
+        assert!(!limits.allows_path(&root.join("assets/../../outside.txt")));
+        assert!(!limits.allows_path(Path::new("/etc/passwd")));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
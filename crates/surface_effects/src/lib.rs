@@ -0,0 +1,353 @@
+//! Footsteps, impacts and other effects on surfaces, which play the sounds and spawn the decals and prefabs that the
+//! `surface_effects_kind` entities map their kind and `surface_material` to.
+//!
+//! Effects are requested by spawning `surface_effect` entities. The server picks what is played for them and sends it
+//! to the clients in a datagram, and despawns them on the frame they were spawned on, so they never reach the clients
+//! themselves. Effects requested on a client are only played on that client.
+use std::{sync::Arc, time::Duration};
+
+use ambient_audio::{Attenuation, AudioEmitter, AudioFromUrl, Source};
+use ambient_core::{
+    asset_cache,
+    player::player_suspended,
+    remove_at_time, runtime, time,
+    transform::{rotation, scale, translation},
+};
+use ambient_ecs::{
+    generated::components::core::{
+        physics::{
+            surface_effect, surface_effect_material, surface_effect_normal,
+            surface_effects_decal_size, surface_effects_decals, surface_effects_kind,
+            surface_effects_lifetime, surface_effects_material, surface_effects_prefabs,
+            surface_effects_sounds,
+        },
+        prefab::prefab_from_url,
+        rendering::decal_from_url,
+    },
+    query, Entity, SystemGroup, World,
+};
+use ambient_network::{
+    client, log_network_result,
+    proto::Features,
+    server::{player_connection, player_features},
+    SURFACE_EFFECT_DATAGRAM_ID,
+};
+use ambient_std::{
+    asset_cache::{AssetCache, AsyncAssetKeyExt},
+    asset_url::AbsAssetUrl,
+};
+use ambient_world_audio::{audio_listener, audio_mixer, hrtf_lib, sound_bus};
+use bytes::Bytes;
+use glam::{Quat, Vec3};
+use parking_lot::Mutex;
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+/// The material whose mappings are used for the materials that have none of their own
+pub const DEFAULT_SURFACE_MATERIAL: &str = "default";
+
+const DEFAULT_DECAL_SIZE: f32 = 0.3;
+const DEFAULT_LIFETIME: f32 = 30.;
+
+/// What is played for a kind of effect on a material.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SurfaceEffects {
+    pub sounds: Vec<String>,
+    pub decals: Vec<String>,
+    pub decal_size: f32,
+    pub prefabs: Vec<String>,
+    /// How many seconds the decals and prefabs stay for
+    pub lifetime: f32,
+}
+impl SurfaceEffects {
+    /// The effects that `kind` is mapped to on `material`, or on the [DEFAULT_SURFACE_MATERIAL] if it has no mapping.
+    pub fn find(world: &World, kind: &str, material: &str) -> Option<Self> {
+        let mappings = query((surface_effects_kind(), surface_effects_material()))
+            .iter(world, None)
+            .filter(|(_, (mapped_kind, _))| mapped_kind.as_str() == kind)
+            .map(|(id, (_, mapped_material))| (id, mapped_material.as_str()))
+            .collect::<Vec<_>>();
+        let id = [material, DEFAULT_SURFACE_MATERIAL]
+            .into_iter()
+            .find_map(|material| {
+                mappings
+                    .iter()
+                    .find(|(_, mapped_material)| *mapped_material == material)
+            })?
+            .0;
+        Some(Self {
+            sounds: world
+                .get_cloned(id, surface_effects_sounds())
+                .unwrap_or_default(),
+            decals: world
+                .get_cloned(id, surface_effects_decals())
+                .unwrap_or_default(),
+            decal_size: world
+                .get(id, surface_effects_decal_size())
+                .unwrap_or(DEFAULT_DECAL_SIZE),
+            prefabs: world
+                .get_cloned(id, surface_effects_prefabs())
+                .unwrap_or_default(),
+            lifetime: world
+                .get(id, surface_effects_lifetime())
+                .unwrap_or(DEFAULT_LIFETIME),
+        })
+    }
+
+    /// Picks one of each of the effects at random, so that every client plays the same ones
+    pub fn pick(&self, position: Vec3, normal: Vec3) -> PlayedSurfaceEffect {
+        let mut rng = rand::thread_rng();
+        PlayedSurfaceEffect {
+            position,
+            // Turned around the normal at random, so that repeated decals don't line up
+            orientation: Quat::from_rotation_arc(Vec3::Z, normal)
+                * Quat::from_rotation_z(rng.gen::<f32>() * std::f32::consts::TAU),
+            sound: self.sounds.choose(&mut rng).cloned(),
+            decal: self.decals.choose(&mut rng).cloned(),
+            decal_size: self.decal_size,
+            prefab: self.prefabs.choose(&mut rng).cloned(),
+            lifetime: self.lifetime,
+        }
+    }
+}
+
+/// A surface effect with its sound, decal and prefab picked, as the server sends it to the clients
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayedSurfaceEffect {
+    pub position: Vec3,
+    /// Turns up to the normal of the surface
+    pub orientation: Quat,
+    pub sound: Option<String>,
+    pub decal: Option<String>,
+    pub decal_size: f32,
+    pub prefab: Option<String>,
+    pub lifetime: f32,
+}
+
+/// Despawns the `surface_effect` entities, and picks the effects for them. Returns the effects that were mapped.
+fn take_requests(world: &mut World) -> Vec<PlayedSurfaceEffect> {
+    let requests = query((surface_effect(), translation())).collect_cloned(world, None);
+    let mut played = Vec::new();
+    for (id, (kind, position)) in requests {
+        let material = world
+            .get_cloned(id, surface_effect_material())
+            .unwrap_or_default();
+        let normal = world
+            .get(id, surface_effect_normal())
+            .ok()
+            .and_then(|normal| normal.try_normalize())
+            .unwrap_or(Vec3::Z);
+        world.despawn(id);
+        if let Some(effects) = SurfaceEffects::find(world, &kind, &material) {
+            played.push(effects.pick(position, normal));
+        }
+    }
+    played
+}
+
+/// Sends the `surface_effect`s that were requested on the server to the clients. Runs after the guest systems, so that
+/// the requests are despawned before the world is sent to the clients.
+pub fn server_systems() -> SystemGroup {
+    SystemGroup::new(
+        "surface_effects",
+        vec![query(surface_effect()).to_system(|q, world, qs, _| {
+            if q.iter(world, qs).next().is_none() {
+                return;
+            }
+            let played = take_requests(world);
+            if played.is_empty() {
+                return;
+            }
+            let bytes = Bytes::from(bincode::serialize(&played).unwrap());
+            for (_, (connection, features)) in query((player_connection(), player_features()))
+                .excl(player_suspended())
+                .iter(world, None)
+            {
+                if features.contains(Features::SURFACE_EFFECTS) {
+                    log_network_result!(
+                        connection.send_datagram(SURFACE_EFFECT_DATAGRAM_ID, bytes.clone())
+                    );
+                }
+            }
+        })],
+    )
+}
+
+/// Plays the `surface_effect`s that were requested on this client
+pub fn client_systems() -> SystemGroup {
+    SystemGroup::new(
+        "surface_effects/client",
+        vec![query(surface_effect()).to_system(|q, world, qs, _| {
+            if q.iter(world, qs).next().is_none() {
+                return;
+            }
+            for effect in take_requests(world) {
+                play(world, &effect);
+            }
+        })],
+    )
+}
+
+pub fn register_client_datagram_handler(handlers: &mut client::DatagramHandlers) {
+    handlers.insert(
+        SURFACE_EFFECT_DATAGRAM_ID,
+        ("client_surface_effects", Arc::new(on_client_datagram)),
+    );
+}
+
+/// Plays the surface effects the server sent
+fn on_client_datagram(world: &mut World, _assets: AssetCache, bytes: Bytes) {
+    let effects: Vec<PlayedSurfaceEffect> = match bincode::deserialize(&bytes) {
+        Ok(effects) => effects,
+        Err(err) => {
+            log::warn!("Failed to decode surface effects: {err:?}");
+            return;
+        }
+    };
+    for effect in &effects {
+        play(world, effect);
+    }
+}
+
+fn play(world: &mut World, effect: &PlayedSurfaceEffect) {
+    let remove_at = *world.resource(time()) + Duration::from_secs_f32(effect.lifetime.max(0.));
+    if let Some(sound) = &effect.sound {
+        play_sound(world, sound, effect.position);
+    }
+    if let Some(decal) = &effect.decal {
+        Entity::new()
+            .with(decal_from_url(), decal.clone())
+            .with(translation(), effect.position)
+            .with(rotation(), effect.orientation)
+            .with(scale(), Vec3::splat(effect.decal_size / 2.))
+            .with(remove_at_time(), remove_at)
+            .spawn(world);
+    }
+    if let Some(prefab) = &effect.prefab {
+        Entity::new()
+            .with(prefab_from_url(), prefab.clone())
+            .with(translation(), effect.position)
+            .with(rotation(), effect.orientation)
+            .with(remove_at_time(), remove_at)
+            .spawn(world);
+    }
+}
+
+/// Plays `sound` from `position`, or without spatialization if nothing is listening
+fn play_sound(world: &World, sound: &str, position: Vec3) {
+    let Some(mixer) = world.resource_opt(audio_mixer()).cloned() else {
+        return;
+    };
+    let assets = world.resource(asset_cache()).clone();
+    let url = match AbsAssetUrl::parse(sound).and_then(|url| Ok(url.to_download_url(&assets)?)) {
+        Ok(url) => url,
+        Err(err) => {
+            log::warn!("Invalid surface effect sound URL {:?}: {:?}", sound, err);
+            return;
+        }
+    };
+    let bus = sound_bus(world, &url.to_string());
+    let spatial = world.resource_opt(hrtf_lib()).cloned().zip(
+        query(audio_listener())
+            .iter(world, None)
+            .map(|(_, listener)| listener.clone())
+            .next(),
+    );
+    world.resource(runtime()).spawn(async move {
+        let track = AudioFromUrl { url }.get(&assets).await;
+        let track = match track {
+            Ok(track) => track,
+            Err(err) => {
+                log::warn!("Failed to load surface effect sound: {:?}", err);
+                return;
+            }
+        };
+        match spatial {
+            Some((hrtf_lib, listener)) => {
+                let emitter = Arc::new(Mutex::new(AudioEmitter {
+                    amplitude: 5.0,
+                    attenuation: Attenuation::InversePoly {
+                        quad: 0.1,
+                        lin: 0.0,
+                        constant: 1.0,
+                    },
+                    pos: position,
+                    ..Default::default()
+                }));
+                mixer.play_on_bus(&bus, track.decode().spatial(&hrtf_lib, listener, emitter));
+            }
+            None => {
+                mixer.play_on_bus(&bus, track.decode());
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn init() {
+        ambient_ecs::init_components();
+        ambient_core::init_all_components();
+    }
+
+    fn mapping(world: &mut World, kind: &str, material: &str, sound: &str) {
+        Entity::new()
+            .with(surface_effects_kind(), kind.to_string())
+            .with(surface_effects_material(), material.to_string())
+            .with(surface_effects_sounds(), vec![sound.to_string()])
+            .spawn(world);
+    }
+
+    #[test]
+    fn unmapped_materials_use_the_default() {
+        init();
+        let mut world = World::new("unmapped_materials_use_the_default");
+        mapping(&mut world, "footstep", "default", "step.ogg");
+        mapping(&mut world, "footstep", "metal", "clang.ogg");
+        mapping(&mut world, "impact", "wood", "thud.ogg");
+
+        let sounds = |kind, material| {
+            SurfaceEffects::find(&world, kind, material).map(|effects| effects.sounds)
+        };
+        assert_eq!(
+            sounds("footstep", "metal"),
+            Some(vec!["clang.ogg".to_string()])
+        );
+        assert_eq!(
+            sounds("footstep", "grass"),
+            Some(vec!["step.ogg".to_string()])
+        );
+        assert_eq!(sounds("impact", "metal"), None);
+        assert_eq!(
+            SurfaceEffects::find(&world, "footstep", "").map(|effects| effects.decal_size),
+            Some(DEFAULT_DECAL_SIZE)
+        );
+    }
+
+    #[test]
+    fn requests_are_despawned_and_picked() {
+        init();
+        let mut world = World::new("requests_are_despawned_and_picked");
+        mapping(&mut world, "footstep", "default", "step.ogg");
+        let request = |world: &mut World, kind: &str| {
+            Entity::new()
+                .with(surface_effect(), kind.to_string())
+                .with(surface_effect_material(), "metal".to_string())
+                .with(translation(), Vec3::X)
+                .spawn(world)
+        };
+        let mapped = request(&mut world, "footstep");
+        let unmapped = request(&mut world, "impact");
+
+        let played = take_requests(&mut world);
+        assert!(!world.exists(mapped));
+        assert!(!world.exists(unmapped));
+        assert_eq!(played.len(), 1);
+        assert_eq!(played[0].position, Vec3::X);
+        assert_eq!(played[0].sound.as_deref(), Some("step.ogg"));
+        assert_eq!(played[0].decal, None);
+        assert!((played[0].orientation * Vec3::Z).distance(Vec3::Z) < 0.001);
+    }
+}
@@ -29,3 +29,7 @@ pub(crate) mod platform;
 ///
 /// **Note**: wasm file io always return Err, but do *not* panic.
 pub use platform::fs;
+
+/// Android-specific functionality, such as access to the assets packaged in the APK.
+#[cfg(target_os = "android")]
+pub use platform::android;
@@ -0,0 +1,54 @@
+//! Android-specific platform functionality.
+//!
+//! Assets packaged into an APK can't be accessed through the file system, so they are unpacked into
+//! the app's data directory on startup. The packaging step writes an index of all packaged files to
+//! [ASSET_INDEX], as the NDK asset manager is unable to enumerate subdirectories.
+
+use std::{
+    ffi::CString,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+pub use ndk::asset::AssetManager;
+
+/// Name of the file listing every asset below an asset directory, one relative path per line.
+pub const ASSET_INDEX: &str = "ambient_assets.txt";
+
+fn open(manager: &AssetManager, path: &str) -> io::Result<ndk::asset::Asset> {
+    let name = CString::new(path).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    manager.open(&name).ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("APK asset {path:?} not found")))
+}
+
+/// Reads a single asset from the APK.
+pub fn read_asset(manager: &AssetManager, path: &str) -> io::Result<Vec<u8>> {
+    let mut asset = open(manager, path)?;
+    let mut data = Vec::with_capacity(asset.get_length());
+    asset.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Unpacks the asset directory `dir` of the APK into `dest`, returning the path of the unpacked directory.
+///
+/// Files that already exist with the same size are skipped, so this is cheap to call on every launch.
+pub fn unpack_assets(manager: &AssetManager, dir: &str, dest: &Path) -> io::Result<PathBuf> {
+    let index = read_asset(manager, &format!("{dir}/{ASSET_INDEX}"))?;
+    let index = String::from_utf8(index).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+    let root = dest.join(dir);
+    for file in index.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let target = root.join(file);
+        let mut asset = open(manager, &format!("{dir}/{file}"))?;
+        if std::fs::metadata(&target).map(|m| m.len() as usize == asset.get_length()).unwrap_or(false) {
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut data = Vec::with_capacity(asset.get_length());
+        asset.read_to_end(&mut data)?;
+        std::fs::write(&target, data)?;
+    }
+    tracing::info!("Unpacked APK assets {dir:?} to {root:?}");
+    Ok(root)
+}
@@ -1 +1,16 @@
-pub use tokio::fs::{read, read_to_string, write};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+pub use tokio::fs::{create_dir_all, read, read_to_string, remove_file, write};
+
+/// The paths of the entries of the directory at `path`.
+pub async fn read_dir_paths(path: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+    let mut entries = tokio::fs::read_dir(path).await?;
+    let mut paths = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        paths.push(entry.path());
+    }
+    Ok(paths)
+}
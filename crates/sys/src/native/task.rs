@@ -87,6 +87,34 @@ where
     ctl
 }
 
+pub fn spawn_compute<R, F>(f: F) -> crate::task::JoinHandle<R>
+where
+    F: 'static + Send + FnOnce() -> R,
+    R: 'static + Send,
+{
+    let (tx, rx) = flume::bounded(1);
+    rayon::spawn(move || {
+        // A panic would abort the process from the thread pool, so it is passed on to the joining task instead
+        tx.send(std::panic::catch_unwind(std::panic::AssertUnwindSafe(f))).ok();
+    });
+    crate::task::spawn(async move {
+        match rx.recv_async().await.expect("The compute task was dropped") {
+            Ok(value) => value,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    })
+}
+
+pub fn par_map<T, R, F>(items: &mut [T], f: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(&mut T) -> R + Send + Sync,
+{
+    use rayon::prelude::*;
+    items.par_iter_mut().map(f).collect()
+}
+
 #[inline(always)]
 pub async fn wasm_nonsend<F, Fut, T>(func: F) -> T
 where
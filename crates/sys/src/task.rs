@@ -38,6 +38,30 @@ where
     RuntimeHandle::current().spawn_blocking(f)
 }
 
+/// Runs a CPU-bound function, like decoding an asset, on the shared compute thread pool. This is a work-stealing pool
+/// with a thread per core, which the parallel systems of the ECS run on too (see [par_map]).
+///
+/// On wasm, this runs like [spawn_blocking].
+pub fn spawn_compute<R, F>(f: F) -> JoinHandle<R>
+where
+    F: 'static + Send + FnOnce() -> R,
+    R: 'static + Send,
+{
+    platform::task::spawn_compute(f)
+}
+
+/// Calls `f` on all the `items` at the same time on the compute thread pool, and returns the results in order.
+///
+/// On wasm, the items are processed one after the other.
+pub fn par_map<T, R, F>(items: &mut [T], f: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(&mut T) -> R + Send + Sync,
+{
+    platform::task::par_map(items, f)
+}
+
 /// Spawn a non-send future by sending a constructor to a worker thread.
 ///
 /// The future will run to completion on the worker thread.
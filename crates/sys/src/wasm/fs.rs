@@ -1,4 +1,7 @@
-use std::{io, path::Path};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
 
 pub async fn read(_path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
     Err(io::Error::new(io::ErrorKind::Unsupported, "File IO on wasm it not supported"))
@@ -11,3 +14,15 @@ pub async fn read_to_string(_path: impl AsRef<Path>) -> io::Result<String> {
 pub async fn write(_path: impl AsRef<Path>, _contents: impl AsRef<[u8]>) -> io::Result<()> {
     Err(io::Error::new(io::ErrorKind::Unsupported, "File IO on wasm it not supported"))
 }
+
+pub async fn create_dir_all(_path: impl AsRef<Path>) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "File IO on wasm it not supported"))
+}
+
+pub async fn remove_file(_path: impl AsRef<Path>) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "File IO on wasm it not supported"))
+}
+
+pub async fn read_dir_paths(_path: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "File IO on wasm it not supported"))
+}
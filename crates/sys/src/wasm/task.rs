@@ -4,6 +4,23 @@ use crate::control::{control_future, ControlHandle};
 
 pub type JoinHandle<T> = ControlHandle<T>;
 
+pub fn spawn_compute<R, F>(f: F) -> crate::task::JoinHandle<R>
+where
+    F: 'static + Send + FnOnce() -> R,
+    R: 'static + Send,
+{
+    crate::task::spawn_blocking(f)
+}
+
+pub fn par_map<T, R, F>(items: &mut [T], f: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(&mut T) -> R + Send + Sync,
+{
+    items.iter_mut().map(f).collect()
+}
+
 #[inline(always)]
 pub async fn wasm_nonsend<F, Fut, T>(func: F) -> T
 where
@@ -276,7 +276,9 @@ fn fs_forward_main(in: VertexOutput) -> MainFsOut {
 
     return MainFsOut(
         shading(material, in.world_position),
-        quat_from_mat3(normal_mat)
+        quat_from_mat3(normal_mat),
+        // The terrain doesn't move, so only the camera moves it on the screen
+        screen_velocity(in.world_position, in.world_position)
     );
 }
 
@@ -285,3 +287,8 @@ fn fs_outlines_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let entity_loc = primitives.data[in.instance_index].xy;
     return get_entity_outline_or(entity_loc, vec4<f32>(0., 0., 0., 0.));
 }
+
+@fragment
+fn fs_picking_main(in: VertexOutput) -> @location(0) vec2<u32> {
+    return primitives.data[in.instance_index].xy;
+}
@@ -5,14 +5,16 @@ use ambient_core::{
     asset_cache, async_ecs::async_run, gpu, mesh, runtime, transform::*,
     window::window_scale_factor,
 };
-use ambient_ecs::{components, ensure_has_component, query, Debuggable, Entity, SystemGroup};
+use ambient_ecs::{
+    components, ensure_has_component, query, Debuggable, Entity, SystemGroup, World,
+};
 use ambient_gpu::{mesh_buffer::GpuMesh, texture::Texture};
 use ambient_layout::{height, max_height, max_width, min_height, min_width, width};
 use ambient_renderer::{
     gpu_primitives_lod, gpu_primitives_mesh, material, primitives, renderer_shader, SharedMaterial,
 };
 use ambient_std::{
-    asset_cache::{AssetCache, AsyncAssetKey, AsyncAssetKeyExt},
+    asset_cache::{AssetCache, AsyncAssetKey, AsyncAssetKeyExt, SyncAssetKey, SyncAssetKeyExt},
     asset_url::AbsAssetUrl,
     cb,
     download_asset::{AssetResult, BytesFromUrl},
@@ -24,7 +26,7 @@ use async_trait::async_trait;
 use glam::*;
 use glyph_brush::{
     ab_glyph::{Font, FontArc, PxScale, Rect},
-    BrushAction, BrushError, GlyphBrush, GlyphBrushBuilder, GlyphCruncher, Section,
+    BrushAction, BrushError, FontId, GlyphBrush, GlyphBrushBuilder, GlyphCruncher, Section,
 };
 use log::info;
 use parking_lot::Mutex;
@@ -32,19 +34,30 @@ use parking_lot::Mutex;
 use crate::text_material::{get_text_shader, TextMaterial};
 use strum::EnumString;
 
+mod sdf;
 mod text_material;
+mod world_text;
 
 pub use ambient_ecs::generated::components::core::text::{
-    font_family, font_size, font_style, text,
+    emoji_font_url, fallback_font_url, font_family, font_size, font_style, text, world_text,
+    world_text_fade_distance, world_text_outline_color, world_text_outline_width,
+    world_text_shadow_color, world_text_shadow_offset, world_text_size,
 };
+pub use world_text::WorldTextAtlas;
 
 components!("text", {
     @[Debuggable]
     text_case: TextCase,
     font_arc: Arc<FontArc>,
+    /// Resource: the font used for characters that an entity's font does not have
+    fallback_font_arc: Arc<FontArc>,
+    /// Resource: the font used for emoji that neither an entity's font nor the fallback font have
+    emoji_font_arc: Arc<FontArc>,
 
     glyph_brush: Arc<Mutex<GlyphBrush<GlyphVertex>>>,
     text_texture: Arc<Texture>,
+    /// The atlas of the font of an entity with `world_text`
+    world_text_atlas: Arc<WorldTextAtlas>,
 });
 
 #[derive(Debug, Clone, Copy)]
@@ -283,12 +296,57 @@ pub fn systems(use_gpu: bool) -> SystemGroup {
                     }
                 },
             ),
-            query(font_arc().changed()).to_system(|q, world, qs, _| {
-                for (id, font) in q.collect_cloned(world, qs) {
-                    let brush = Arc::new(Mutex::new(
-                        GlyphBrushBuilder::using_font(font.deref().clone()).build(),
-                    ));
-                    world.add_component(id, glyph_brush(), brush).unwrap();
+            query(fallback_font_url().changed()).to_system(|q, world, qs, _| {
+                for (_, url) in q.collect_cloned(world, qs) {
+                    let url = unwrap_log_warn!(AbsAssetUrl::parse(url));
+                    let async_run = world.resource(async_run()).clone();
+                    let assets = world.resource(asset_cache()).clone();
+                    world.resource(runtime()).spawn(async move {
+                        let font = unwrap_log_warn!(FontFromUrl(url).get(&assets).await);
+                        async_run.run(move |world| {
+                            world.add_resource(fallback_font_arc(), font);
+                        });
+                    });
+                }
+            }),
+            query(emoji_font_url().changed()).to_system(|q, world, qs, _| {
+                for (_, url) in q.collect_cloned(world, qs) {
+                    let url = unwrap_log_warn!(AbsAssetUrl::parse(url));
+                    let async_run = world.resource(async_run()).clone();
+                    let assets = world.resource(asset_cache()).clone();
+                    world.resource(runtime()).spawn(async move {
+                        let font = unwrap_log_warn!(FontFromUrl(url).get(&assets).await);
+                        async_run.run(move |world| {
+                            world.add_resource(emoji_font_arc(), font);
+                        });
+                    });
+                }
+            }),
+            query(font_arc().changed())
+                .incl(text())
+                .to_system(|q, world, qs, _| {
+                    let fallbacks = fallback_fonts(world);
+                    for (id, font) in q.collect_cloned(world, qs) {
+                        world
+                            .add_component(id, glyph_brush(), create_glyph_brush(&font, &fallbacks))
+                            .unwrap();
+                    }
+                }),
+            query(fallback_font_arc().changed()).to_system(|q, world, qs, _| {
+                if q.iter(world, qs).next().is_some() {
+                    recreate_glyph_brushes(world);
+                }
+            }),
+            query(emoji_font_arc().changed()).to_system(|q, world, qs, _| {
+                if q.iter(world, qs).next().is_some() {
+                    recreate_glyph_brushes(world);
+                }
+            }),
+            // The glyphs are rasterized at the DPI of the window, so they are rasterized and laid out again when it
+            // changes, e.g. when the window is moved to another monitor
+            query(window_scale_factor().changed()).to_system(|q, world, qs, _| {
+                if q.iter(world, qs).next().is_some() {
+                    recreate_glyph_brushes(world);
                 }
             }),
             query(())
@@ -332,19 +390,43 @@ pub fn systems(use_gpu: bool) -> SystemGroup {
                 for (id, (glyph_brush, text, font_size, font)) in q.collect_cloned(world, qs) {
                     let assets = world.resource(asset_cache()).clone();
                     let text = world.get(id, text_case()).unwrap_or_default().format(text);
+                    // Emoji sequences are drawn one emoji at a time, without the invisible characters that join them
+                    let text = text.replace(is_emoji_joiner, "");
                     let min_width = world.get(id, min_width()).unwrap_or(0.);
                     let min_height = world.get(id, min_height()).unwrap_or(0.);
                     let max_width = world.get(id, max_width()).unwrap_or(f32::MAX);
                     let max_height = world.get(id, max_height()).unwrap_or(f32::MAX);
+                    let fonts = glyph_brush.lock().fonts().to_vec();
+                    // The fallback fonts replace the brushes, which lay this text out again
+                    for c in text.chars().filter(|&c| {
+                        !c.is_whitespace() && fonts.iter().all(|font| font.glyph_id(c).0 == 0)
+                    }) {
+                        if is_emoji(c) {
+                            if world.resource_opt(emoji_font_arc()).is_none() {
+                                if let Some(emoji) = SystemEmojiFontKey.get(&assets) {
+                                    world.add_resource(emoji_font_arc(), emoji);
+                                }
+                            }
+                        } else if world.resource_opt(fallback_font_arc()).is_none() {
+                            if let Some(fallback) = SystemFallbackFontKey.get(&assets) {
+                                world.add_resource(fallback_font_arc(), fallback);
+                            }
+                        }
+                    }
+                    let scale = pt_size_to_px_scale(&*font, font_size, scale_factor);
 
                     loop {
                         let process_result = {
                             let mut brush = glyph_brush.lock();
-                            let section = Section::default()
-                                .with_bounds((max_width, max_height))
-                                .add_text(glyph_brush::Text::new(&text).with_scale(
-                                    pt_size_to_px_scale(&*font, font_size, scale_factor),
-                                ));
+                            let mut section =
+                                Section::default().with_bounds((max_width, max_height));
+                            for (run, font_id) in font_runs(&text, &fonts) {
+                                section = section.add_text(
+                                    glyph_brush::Text::new(run)
+                                        .with_scale(scale)
+                                        .with_font_id(font_id),
+                                );
+                            }
                             if let Some(bounds) = brush.glyph_bounds(&section) {
                                 if world.has_component(id, width()) {
                                     world
@@ -466,10 +548,141 @@ pub fn systems(use_gpu: bool) -> SystemGroup {
                     }
                 }
             }),
+            Box::new(world_text::systems(use_gpu)),
         ],
     )
 }
 
+fn create_glyph_brush(
+    font: &Arc<FontArc>,
+    fallbacks: &[Arc<FontArc>],
+) -> Arc<Mutex<GlyphBrush<GlyphVertex>>> {
+    let mut fonts = vec![font.deref().clone()];
+    fonts.extend(fallbacks.iter().map(|fallback| fallback.deref().clone()));
+    Arc::new(Mutex::new(GlyphBrushBuilder::using_fonts(fonts).build()))
+}
+
+/// The fonts for the characters that the font of an entity does not have, in the order they are tried
+fn fallback_fonts(world: &World) -> Vec<Arc<FontArc>> {
+    [fallback_font_arc(), emoji_font_arc()]
+        .into_iter()
+        .filter_map(|font| world.resource_opt(font).cloned())
+        .collect()
+}
+
+/// Recreates the brushes of all of the text, e.g. with new fallback fonts, which lays it out again
+fn recreate_glyph_brushes(world: &mut World) {
+    let fallbacks = fallback_fonts(world);
+    for (id, font) in query(font_arc()).incl(text()).collect_cloned(world, None) {
+        world
+            .add_component(id, glyph_brush(), create_glyph_brush(&font, &fallbacks))
+            .unwrap();
+    }
+}
+
+/// Splits `text` into runs that are each drawn with the first of the `fonts` (the entity's font, then the fallback
+/// fonts) that has their characters; the characters that none of them have are drawn with the entity's font.
+fn font_runs<'a>(text: &'a str, fonts: &[FontArc]) -> Vec<(&'a str, FontId)> {
+    if fonts.len() < 2 {
+        return vec![(text, FontId(0))];
+    }
+    let mut runs: Vec<(&'a str, FontId)> = Vec::new();
+    let mut start = 0;
+    let mut current = FontId(0);
+    for (index, c) in text.char_indices() {
+        // Whitespace stays in the current run so that runs are not split between words
+        let font_id = if c.is_whitespace() {
+            current
+        } else {
+            FontId(
+                fonts
+                    .iter()
+                    .position(|font| font.glyph_id(c).0 != 0)
+                    .unwrap_or(0),
+            )
+        };
+        if font_id != current {
+            if index > start {
+                runs.push((&text[start..index], current));
+            }
+            start = index;
+            current = font_id;
+        }
+    }
+    if start < text.len() {
+        runs.push((&text[start..], current));
+    }
+    runs
+}
+
+/// Whether `c` is in one of the blocks of emoji and pictographs
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32, 0x2600..=0x27BF | 0x2B00..=0x2BFF | 0x1F000..=0x1FAFF)
+}
+
+/// Whether `c` is one of the invisible characters that join emoji into sequences or select how they are presented
+fn is_emoji_joiner(c: char) -> bool {
+    matches!(c, '\u{200D}' | '\u{FE00}'..='\u{FE0F}')
+}
+
+/// A font installed on the system that covers CJK characters, used when no `fallback_font_url`
+/// has been set.
+#[derive(Debug, Clone)]
+struct SystemFallbackFontKey;
+impl SyncAssetKey<Option<Arc<FontArc>>> for SystemFallbackFontKey {
+    #[cfg(not(target_os = "unknown"))]
+    fn load(&self, _assets: AssetCache) -> Option<Arc<FontArc>> {
+        const PATHS: &[&str] = &[
+            "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+            "/System/Library/Fonts/PingFang.ttc",
+            "/System/Library/Fonts/Hiragino Sans GB.ttc",
+            "C:\\Windows\\Fonts\\msyh.ttc",
+            "C:\\Windows\\Fonts\\YuGothR.ttc",
+        ];
+        PATHS.iter().find_map(|path| {
+            let data = std::fs::read(path).ok()?;
+            let font = FontArc::try_from_vec(data).ok()?;
+            info!("Using {path} as the fallback font");
+            Some(Arc::new(font))
+        })
+    }
+    #[cfg(target_os = "unknown")]
+    fn load(&self, _assets: AssetCache) -> Option<Arc<FontArc>> {
+        None
+    }
+}
+
+/// A font installed on the system that has outlines for emoji, used when no `emoji_font_url` has been set. Fonts with
+/// only color bitmaps for emoji can't be drawn.
+#[derive(Debug, Clone)]
+struct SystemEmojiFontKey;
+impl SyncAssetKey<Option<Arc<FontArc>>> for SystemEmojiFontKey {
+    #[cfg(not(target_os = "unknown"))]
+    fn load(&self, _assets: AssetCache) -> Option<Arc<FontArc>> {
+        const PATHS: &[&str] = &[
+            "/usr/share/fonts/truetype/noto/NotoEmoji-Regular.ttf",
+            "/usr/share/fonts/noto/NotoEmoji-Regular.ttf",
+            "/usr/share/fonts/google-noto-emoji/NotoEmoji-Regular.ttf",
+            "/usr/share/fonts/truetype/ancient-scripts/Symbola_hint.ttf",
+            "/System/Library/Fonts/Apple Symbols.ttf",
+            "C:\\Windows\\Fonts\\seguiemj.ttf",
+            "C:\\Windows\\Fonts\\seguisym.ttf",
+        ];
+        PATHS.iter().find_map(|path| {
+            let data = std::fs::read(path).ok()?;
+            let font = FontArc::try_from_vec(data).ok()?;
+            info!("Using {path} as the emoji font");
+            Some(Arc::new(font))
+        })
+    }
+    #[cfg(target_os = "unknown")]
+    fn load(&self, _assets: AssetCache) -> Option<Arc<FontArc>> {
+        None
+    }
+}
+
 // From: https://docs.rs/glyph_brush/latest/glyph_brush/ab_glyph/trait.Font.html#units
 fn pt_size_to_px_scale<F: Font>(font: &F, pt_size: f32, screen_scale_factor: f32) -> PxScale {
     let px_per_em = pt_size * screen_scale_factor; // * (96.0 / 72.0); // this part is used in the example but seems to make the scale wrong, hence disabled
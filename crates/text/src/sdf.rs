@@ -0,0 +1,277 @@
+//! Signed distance fields of glyphs, which world text is drawn with so that it stays sharp at any distance and can
+//! be outlined and shadowed in the shader.
+
+use std::collections::HashMap;
+
+use glam::{uvec2, vec2, UVec2, Vec2};
+use glyph_brush::ab_glyph::{Font, FontArc, GlyphId, PxScale, ScaleFont};
+
+use crate::pt_size_to_px_scale;
+
+/// The size of the glyphs in the atlas, in pixels per em
+pub const GLYPH_PX: f32 = 40.;
+/// How far the distance fields reach outside of the glyphs, in pixels; this bounds outlines and shadows
+pub const SPREAD: u32 = 6;
+/// The width and height of the atlas, in pixels
+pub const ATLAS_SIZE: u32 = 1024;
+
+/// A glyph in the atlas
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SdfGlyph {
+    /// The corners of the glyph in the atlas, in texture coordinates
+    pub uv_min: Vec2,
+    pub uv_max: Vec2,
+    /// The top left corner of the glyph, including its spread, relative to its origin on the baseline, in pixels
+    /// with y down
+    pub offset: Vec2,
+    /// The size of the glyph, including its spread, in pixels
+    pub size: Vec2,
+}
+
+/// The distance fields of the glyphs of a font that have been drawn so far, packed in rows into one single-channel
+/// image. A distance of 0 from the edge of a glyph is stored as 0.5, and [SPREAD] pixels inside or outside of it as 1
+/// or 0.
+pub struct SdfAtlas {
+    font: FontArc,
+    scale: PxScale,
+    glyphs: HashMap<GlyphId, Option<SdfGlyph>>,
+    pixels: Vec<u8>,
+    cursor: UVec2,
+    row_height: u32,
+    dirty: bool,
+}
+impl SdfAtlas {
+    pub fn new(font: FontArc) -> Self {
+        Self {
+            scale: pt_size_to_px_scale(&font, GLYPH_PX, 1.),
+            font,
+            glyphs: HashMap::new(),
+            pixels: vec![0; (ATLAS_SIZE * ATLAS_SIZE) as usize],
+            cursor: UVec2::ZERO,
+            row_height: 0,
+            dirty: false,
+        }
+    }
+
+    /// The glyph `id`, which is drawn into the atlas the first time; `None` if it has no outline (e.g. a space) or
+    /// the atlas is full
+    pub fn glyph(&mut self, id: GlyphId) -> Option<SdfGlyph> {
+        if let Some(glyph) = self.glyphs.get(&id) {
+            return *glyph;
+        }
+        let glyph = self.draw(id);
+        self.glyphs.insert(id, glyph);
+        glyph
+    }
+
+    fn draw(&mut self, id: GlyphId) -> Option<SdfGlyph> {
+        let outlined = self.font.outline_glyph(id.with_scale(self.scale))?;
+        let bounds = outlined.px_bounds();
+        let spread = SPREAD as usize;
+        let width = bounds.width() as usize + 2 * spread;
+        let height = bounds.height() as usize + 2 * spread;
+
+        // Rows are filled from left to right, and a new row is started below the tallest glyph of the last one
+        if self.cursor.x + width as u32 > ATLAS_SIZE {
+            self.cursor = uvec2(0, self.cursor.y + self.row_height);
+            self.row_height = 0;
+        }
+        if self.cursor.y + height as u32 > ATLAS_SIZE {
+            log::warn!("The world text atlas is full; glyph {id:?} is not drawn");
+            return None;
+        }
+        let corner = self.cursor;
+        self.cursor.x += width as u32;
+        self.row_height = self.row_height.max(height as u32);
+
+        let mut inside = vec![false; width * height];
+        outlined.draw(|x, y, coverage| {
+            if coverage >= 0.5 {
+                inside[(y as usize + spread) * width + x as usize + spread] = true;
+            }
+        });
+        let outside = inside.iter().map(|inside| !inside).collect::<Vec<_>>();
+        let to_inside = distance_transform(&inside, width, height);
+        let to_outside = distance_transform(&outside, width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let i = y * width + x;
+                // The edge is half way between the pixels on either side of it
+                let distance = if inside[i] {
+                    to_outside[i] - 0.5
+                } else {
+                    0.5 - to_inside[i]
+                };
+                let value = (0.5 + distance / (2 * SPREAD) as f32).clamp(0., 1.);
+                let pixel = (corner.y as usize + y) * ATLAS_SIZE as usize + corner.x as usize + x;
+                self.pixels[pixel] = (value * 255.).round() as u8;
+            }
+        }
+        self.dirty = true;
+
+        let size = vec2(width as f32, height as f32);
+        Some(SdfGlyph {
+            uv_min: corner.as_vec2() / ATLAS_SIZE as f32,
+            uv_max: (corner.as_vec2() + size) / ATLAS_SIZE as f32,
+            offset: vec2(bounds.min.x, bounds.min.y) - SPREAD as f32,
+            size,
+        })
+    }
+
+    /// Lays out the lines of `text`, each centered horizontally, with the whole text centered on the origin, drawing
+    /// its glyphs into the atlas. Returns the glyphs and their origins on the baseline, in pixels with y down.
+    pub fn layout(&mut self, text: &str) -> Vec<(SdfGlyph, Vec2)> {
+        let font = self.font.clone();
+        let font = font.as_scaled(self.scale);
+        let line_height = font.height() + font.line_gap();
+        let lines = text.lines().collect::<Vec<_>>();
+        let top = -(line_height * lines.len() as f32 - font.line_gap()) / 2.;
+
+        let mut glyphs = Vec::new();
+        for (row, line) in lines.into_iter().enumerate() {
+            let ids = line.chars().map(|c| font.glyph_id(c)).collect::<Vec<_>>();
+            let kerning = |i: usize| match i {
+                0 => 0.,
+                _ => font.kern(ids[i - 1], ids[i]),
+            };
+            let width = (0..ids.len())
+                .map(|i| kerning(i) + font.h_advance(ids[i]))
+                .sum::<f32>();
+            let mut caret = vec2(-width / 2., top + font.ascent() + row as f32 * line_height);
+            for (i, &id) in ids.iter().enumerate() {
+                caret.x += kerning(i);
+                if let Some(glyph) = self.glyph(id) {
+                    glyphs.push((glyph, caret));
+                }
+                caret.x += font.h_advance(id);
+            }
+        }
+        glyphs
+    }
+
+    /// The pixels of the atlas, if glyphs have been drawn since they were last taken
+    pub fn take_dirty(&mut self) -> Option<&[u8]> {
+        std::mem::take(&mut self.dirty).then_some(self.pixels.as_slice())
+    }
+}
+
+/// The distance from each cell of a `width` by `height` grid to the nearest of the `features`, as the square roots of
+/// the 1D squared distance transforms of its columns and then its rows (Felzenszwalb and Huttenlocher)
+fn distance_transform(features: &[bool], width: usize, height: usize) -> Vec<f32> {
+    let mut grid = features
+        .iter()
+        .map(|&feature| if feature { 0. } else { FAR })
+        .collect::<Vec<f64>>();
+    let mut line = vec![0.; width.max(height)];
+    let mut transformed = vec![0.; width.max(height)];
+    for x in 0..width {
+        for (y, cell) in line.iter_mut().enumerate().take(height) {
+            *cell = grid[y * width + x];
+        }
+        distance_transform_1d(&line[..height], &mut transformed);
+        for (y, &distance) in transformed.iter().enumerate().take(height) {
+            grid[y * width + x] = distance;
+        }
+    }
+    for y in 0..height {
+        line[..width].copy_from_slice(&grid[y * width..(y + 1) * width]);
+        distance_transform_1d(&line[..width], &mut transformed);
+        grid[y * width..(y + 1) * width].copy_from_slice(&transformed[..width]);
+    }
+    grid.into_iter()
+        .map(|squared| squared.sqrt() as f32)
+        .collect()
+}
+
+/// The squared distance of cells without a feature; far enough that it never wins, but finite so that it can be
+/// subtracted from itself
+const FAR: f64 = 1e20;
+
+/// The lower envelope of the parabolas rooted at each cell of `f`, at the height of `f`, into `out`
+fn distance_transform_1d(f: &[f64], out: &mut [f64]) {
+    let n = f.len();
+    if n == 0 {
+        return;
+    }
+    // The cells whose parabolas make up the envelope, and where each of them starts
+    let mut v = vec![0; n];
+    let mut z = vec![0.; n + 1];
+    let intersection = |r: usize, q: usize| {
+        ((f[q] + (q * q) as f64) - (f[r] + (r * r) as f64)) / (2 * q - 2 * r) as f64
+    };
+    let mut k = 0;
+    z[0] = f64::NEG_INFINITY;
+    z[1] = f64::INFINITY;
+    for q in 1..n {
+        let mut s = intersection(v[k], q);
+        while s <= z[k] {
+            k -= 1;
+            s = intersection(v[k], q);
+        }
+        k += 1;
+        v[k] = q;
+        z[k] = s;
+        z[k + 1] = f64::INFINITY;
+    }
+    k = 0;
+    for (q, out) in out.iter_mut().enumerate().take(n) {
+        while z[k + 1] < q as f64 {
+            k += 1;
+        }
+        let r = v[k];
+        *out = (q as f64 - r as f64).powi(2) + f[r];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distance_to_nearest_feature() {
+        let mut features = vec![false; 5 * 3];
+        features[5 + 1] = true;
+        features[2 * 5 + 4] = true;
+        let distances = distance_transform(&features, 5, 3);
+        assert_eq!(distances[5 + 1], 0.);
+        assert_eq!(distances[1], 1.);
+        assert!((distances[5 + 3] - 2f32.sqrt()).abs() < 1e-6);
+        assert!((distances[2 * 5] - 2f32.sqrt()).abs() < 1e-6);
+        assert_eq!(distances[4], 2.);
+    }
+
+    #[test]
+    fn layout_draws_glyphs_once() {
+        let font = FontArc::try_from_slice(include_bytes!(
+            "../fonts/Ubuntu/Ubuntu Nerd Font Complete.ttf"
+        ))
+        .unwrap();
+        let mut atlas = SdfAtlas::new(font);
+        let glyphs = atlas.layout("Hi there\nHi");
+        // Spaces have no outline
+        assert_eq!(glyphs.len(), 9);
+        assert!(atlas.take_dirty().is_some());
+        assert!(atlas.take_dirty().is_none());
+
+        // The lines are centered, and the second line is below the first
+        let (first, second) = glyphs.split_at(7);
+        let left = first[0].1.x + first[0].0.offset.x;
+        let (last, origin) = first.last().unwrap();
+        let right = origin.x + last.offset.x + last.size.x;
+        assert!((left + right).abs() < GLYPH_PX * 0.5);
+        assert!(second[0].1.y > first[0].1.y);
+        assert_eq!(first[0].0, second[0].0);
+
+        let again = atlas.layout("Hi");
+        assert!(atlas.take_dirty().is_none());
+        assert_eq!(again[0].0, first[0].0);
+
+        // The middle of the stem of the H is inside, and the corner of its cell is outside
+        let h = first[0].0;
+        let corner = (h.uv_min * ATLAS_SIZE as f32).as_uvec2();
+        let pixel = |x: u32, y: u32| atlas.pixels[(y * ATLAS_SIZE + x) as usize];
+        assert_eq!(pixel(corner.x, corner.y), 0);
+        let stem = corner + uvec2(SPREAD + 2, h.size.y as u32 / 2);
+        assert!(pixel(stem.x, stem.y) > 128);
+    }
+}
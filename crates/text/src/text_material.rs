@@ -11,7 +11,8 @@ use ambient_std::{
     asset_cache::{AssetCache, SyncAssetKey, SyncAssetKeyExt},
     friendly_id, include_file,
 };
-use wgpu::BindGroup;
+use glam::{Vec2, Vec4};
+use wgpu::{util::DeviceExt, BindGroup};
 
 #[derive(Debug, Clone)]
 pub struct TextMaterialShaderKey;
@@ -94,3 +95,101 @@ impl Material for TextMaterial {
         Some(true)
     }
 }
+
+#[derive(Debug, Clone)]
+pub struct WorldTextMaterialShaderKey;
+impl SyncAssetKey<Arc<MaterialShader>> for WorldTextMaterialShaderKey {
+    fn load(&self, _: AssetCache) -> Arc<MaterialShader> {
+        Arc::new(MaterialShader {
+            id: "world_text_material_shader".to_string(),
+            shader: Arc::new(
+                ShaderModule::new("WorldTextMaterial", include_file!("world_text_material.wgsl")).with_binding_desc(get_world_text_layout()),
+            ),
+        })
+    }
+}
+
+fn get_world_text_layout() -> BindGroupDesc<'static> {
+    let mut layout = get_text_layout();
+    layout.entries.push(wgpu::BindGroupLayoutEntry {
+        binding: 2,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Buffer { ty: wgpu::BufferBindingType::Uniform, has_dynamic_offset: false, min_binding_size: None },
+        count: None,
+    });
+    layout
+}
+
+pub fn get_world_text_shader(assets: &AssetCache, config: &RendererConfig) -> Arc<RendererShader> {
+    StandardShaderKey { material_shader: WorldTextMaterialShaderKey.get(assets), lit: false, shadow_cascades: config.shadow_cascades }
+        .get(assets)
+}
+
+/// The style of world text, as laid out in `world_text_material.wgsl`; distances are in the units of the distance
+/// field, and offsets in texture coordinates
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct WorldTextParams {
+    pub outline_color: Vec4,
+    pub shadow_color: Vec4,
+    pub shadow_offset: Vec2,
+    /// The distances from the camera at which the text starts fading, and is invisible; the text doesn't fade if
+    /// they are 0
+    pub fade_distance: Vec2,
+    pub outline_width: f32,
+    pub _padding: [f32; 3],
+}
+
+pub struct WorldTextMaterial {
+    _gpu: Arc<Gpu>,
+    id: String,
+    _buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+impl WorldTextMaterial {
+    pub fn new(assets: AssetCache, atlas: Arc<TextureView>, params: WorldTextParams) -> Self {
+        let gpu = GpuKey.get(&assets);
+        let buffer = gpu.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("WorldTextMaterial.buffer"),
+            usage: wgpu::BufferUsages::UNIFORM,
+            contents: bytemuck::bytes_of(&params),
+        });
+
+        Self {
+            id: friendly_id(),
+            bind_group: gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &get_world_text_layout().get(&assets),
+                entries: &[
+                    wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&atlas) },
+                    wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(&DefaultSamplerKey.get(&assets)) },
+                    wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::Buffer(buffer.as_entire_buffer_binding()) },
+                ],
+                label: Some("WorldTextMaterial.bind_group"),
+            }),
+            _buffer: buffer,
+            _gpu: gpu.clone(),
+        }
+    }
+}
+
+impl std::fmt::Debug for WorldTextMaterial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WorldTextMaterial").field("id", &self.id).finish()
+    }
+}
+
+impl Material for WorldTextMaterial {
+    fn bind_group(&self) -> &BindGroup {
+        &self.bind_group
+    }
+    fn id(&self) -> &str {
+        &self.id
+    }
+    fn transparent(&self) -> Option<bool> {
+        Some(true)
+    }
+    // Fixed signs can be read from behind, mirrored, rather than disappearing
+    fn double_sided(&self) -> Option<bool> {
+        Some(true)
+    }
+}
@@ -0,0 +1,285 @@
+use std::{fmt, sync::Arc};
+
+use ambient_core::{
+    asset_cache,
+    bounding::{local_bounding_aabb, world_bounding_aabb, world_bounding_sphere},
+    gpu, main_scene, mesh,
+    transform::{local_to_world, mesh_to_world},
+};
+use ambient_ecs::{ensure_has_component, query, Entity, SystemGroup};
+use ambient_gpu::{
+    gpu::{Gpu, GpuKey},
+    mesh_buffer::GpuMesh,
+    texture::{Texture, TextureView},
+};
+use ambient_renderer::{
+    gpu_primitives_lod, gpu_primitives_mesh, material, primitives, renderer_shader, SharedMaterial,
+};
+use ambient_std::{
+    asset_cache::{AssetCache, SyncAssetKey, SyncAssetKeyExt},
+    cb,
+    mesh::{Mesh, MeshBuilder},
+};
+use glam::{vec2, vec3, Vec2, Vec3, Vec4};
+use glyph_brush::ab_glyph::FontArc;
+use parking_lot::Mutex;
+
+use crate::{
+    font_arc, font_family, font_style,
+    sdf::{SdfAtlas, ATLAS_SIZE, GLYPH_PX, SPREAD},
+    text_material::{get_world_text_shader, WorldTextMaterial, WorldTextParams},
+    world_text, world_text_atlas, world_text_fade_distance, world_text_outline_color,
+    world_text_outline_width, world_text_shadow_color, world_text_shadow_offset, world_text_size,
+    FontFamily, FontStyle,
+};
+
+const DEFAULT_SIZE: f32 = 0.25;
+/// The widest outline and the furthest shadow, as fractions of the size of the text, that fit in the spread of the
+/// distance fields
+const MAX_OFFSET: f32 = SPREAD as f32 / GLYPH_PX;
+
+/// The distance fields of the glyphs of a font, shared by all of the world text in that font, and their texture
+pub struct WorldTextAtlas {
+    sdf: Mutex<SdfAtlas>,
+    texture: Arc<Texture>,
+    pub view: Arc<TextureView>,
+}
+impl fmt::Debug for WorldTextAtlas {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WorldTextAtlas").finish_non_exhaustive()
+    }
+}
+impl WorldTextAtlas {
+    fn new(gpu: Arc<Gpu>, font: FontArc) -> Self {
+        let texture = Arc::new(Texture::new(
+            gpu,
+            &wgpu::TextureDescriptor {
+                size: wgpu::Extent3d {
+                    width: ATLAS_SIZE,
+                    height: ATLAS_SIZE,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::R8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                label: Some("WorldTextAtlas.texture"),
+                view_formats: &[],
+            },
+        ));
+        Self {
+            sdf: Mutex::new(SdfAtlas::new(font)),
+            view: Arc::new(texture.create_view(&wgpu::TextureViewDescriptor::default())),
+            texture,
+        }
+    }
+
+    /// The mesh of `text`, centered on the origin in the XY plane, with a font of `size` meters; `None` if it has no
+    /// visible glyphs. The glyphs that haven't been drawn yet are uploaded to the texture.
+    fn mesh(&self, gpu: &Gpu, text: &str, size: f32) -> Option<Mesh> {
+        let mut sdf = self.sdf.lock();
+        let glyphs = sdf.layout(text);
+        if let Some(pixels) = sdf.take_dirty() {
+            gpu.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &self.texture.handle,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                pixels,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(ATLAS_SIZE),
+                    rows_per_image: Some(ATLAS_SIZE),
+                },
+                wgpu::Extent3d {
+                    width: ATLAS_SIZE,
+                    height: ATLAS_SIZE,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+        if glyphs.is_empty() {
+            return None;
+        }
+
+        // The glyphs are laid out in pixels with y down, and the text is upright along +Y
+        let to_local = |pixels: Vec2| vec3(pixels.x, -pixels.y, 0.) * size / GLYPH_PX;
+        let mut mesh = MeshBuilder {
+            texcoords: vec![Vec::new()],
+            ..Default::default()
+        };
+        for (glyph, origin) in glyphs {
+            let start = mesh.positions.len() as u32;
+            let min = origin + glyph.offset;
+            let max = min + glyph.size;
+            mesh.positions.extend([
+                to_local(min),
+                to_local(vec2(max.x, min.y)),
+                to_local(vec2(min.x, max.y)),
+                to_local(max),
+            ]);
+            mesh.texcoords[0].extend([
+                glyph.uv_min,
+                vec2(glyph.uv_max.x, glyph.uv_min.y),
+                vec2(glyph.uv_min.x, glyph.uv_max.y),
+                glyph.uv_max,
+            ]);
+            // The text faces the camera when it is billboarded, which looks along +Z
+            mesh.normals.extend([-Vec3::Z; 4]);
+            mesh.indices
+                .extend([0, 2, 1, 1, 2, 3].map(|index| start + index));
+        }
+        mesh.build().ok()
+    }
+}
+
+/// The atlas of the font `family` in `style`; the font is only used to create it, so that the atlases are shared by
+/// font rather than by instance of the font
+#[derive(Clone)]
+struct WorldTextAtlasKey {
+    family: String,
+    style: String,
+    font: Arc<FontArc>,
+}
+impl fmt::Debug for WorldTextAtlasKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("WorldTextAtlasKey")
+            .field(&self.family)
+            .field(&self.style)
+            .finish()
+    }
+}
+impl SyncAssetKey<Arc<WorldTextAtlas>> for WorldTextAtlasKey {
+    fn load(&self, assets: AssetCache) -> Arc<WorldTextAtlas> {
+        Arc::new(WorldTextAtlas::new(
+            GpuKey.get(&assets),
+            self.font.as_ref().clone(),
+        ))
+    }
+}
+
+/// Draws the entities with `world_text` once their font has loaded; the text systems load the font, and the material
+/// is rebuilt when the style changes.
+pub(crate) fn systems(use_gpu: bool) -> SystemGroup {
+    SystemGroup::new(
+        "world_text",
+        vec![
+            ensure_has_component(world_text(), font_family(), FontFamily::Default.to_string()),
+            ensure_has_component(
+                world_text(),
+                font_style(),
+                format!("{:?}", FontStyle::Regular),
+            ),
+            query((world_text().changed(), font_arc().changed()))
+                .optional_changed(world_text_size())
+                .to_system(move |q, world, qs, _| {
+                    if !use_gpu {
+                        return;
+                    }
+                    let assets = world.resource(asset_cache()).clone();
+                    let gpu = world.resource(gpu()).clone();
+                    for (id, (text, font)) in q.collect_cloned(world, qs) {
+                        let atlas = WorldTextAtlasKey {
+                            family: world.get_cloned(id, font_family()).unwrap_or_default(),
+                            style: world.get_cloned(id, font_style()).unwrap_or_default(),
+                            font,
+                        }
+                        .get(&assets);
+                        let size = world.get(id, world_text_size()).unwrap_or(DEFAULT_SIZE);
+
+                        let mut data = Entity::new();
+                        let same_atlas = world
+                            .get_ref(id, world_text_atlas())
+                            .map_or(false, |existing| Arc::ptr_eq(existing, &atlas));
+                        if !same_atlas {
+                            data.set(world_text_atlas(), atlas.clone());
+                        }
+                        if !world.has_component(id, renderer_shader()) {
+                            data = data
+                                .with(renderer_shader(), cb(get_world_text_shader))
+                                .with(primitives(), vec![])
+                                .with_default(gpu_primitives_mesh())
+                                .with_default(gpu_primitives_lod())
+                                .with(main_scene(), ())
+                                .with_default(local_to_world())
+                                .with_default(mesh_to_world());
+                        }
+                        match atlas.mesh(&gpu, &text, size) {
+                            Some(cpu_mesh) => {
+                                let aabb = cpu_mesh.aabb();
+                                data = data
+                                    .with(mesh(), GpuMesh::from_mesh(&assets, &cpu_mesh))
+                                    .with(local_bounding_aabb(), aabb)
+                                    .with(world_bounding_aabb(), aabb)
+                                    .with(world_bounding_sphere(), aabb.to_sphere());
+                            }
+                            // Nothing is drawn until the text has visible glyphs
+                            None => {
+                                data.set(primitives(), vec![]);
+                                world.remove_component(id, mesh()).ok();
+                            }
+                        }
+                        world.add_components(id, data).unwrap();
+                    }
+                }),
+            query(world_text_atlas().changed())
+                .incl(world_text())
+                .optional_changed(world_text_outline_width())
+                .optional_changed(world_text_outline_color())
+                .optional_changed(world_text_shadow_offset())
+                .optional_changed(world_text_shadow_color())
+                .optional_changed(world_text_fade_distance())
+                .to_system(|q, world, qs, _| {
+                    let assets = world.resource(asset_cache()).clone();
+                    for (id, atlas) in q.collect_cloned(world, qs) {
+                        let max_offset = Vec2::splat(MAX_OFFSET);
+                        let shadow_offset = world
+                            .get(id, world_text_shadow_offset())
+                            .ok()
+                            .map(|offset| offset.clamp(-max_offset, max_offset));
+                        let params = WorldTextParams {
+                            outline_color: world
+                                .get(id, world_text_outline_color())
+                                .unwrap_or(Vec4::W),
+                            shadow_color: match shadow_offset {
+                                Some(_) => world
+                                    .get(id, world_text_shadow_color())
+                                    .unwrap_or(Vec4::new(0., 0., 0., 0.5)),
+                                None => Vec4::ZERO,
+                            },
+                            // Offsets along +Y move the shadow up, which is towards the top of the atlas
+                            shadow_offset: shadow_offset.unwrap_or_default()
+                                * vec2(1., -1.)
+                                * GLYPH_PX
+                                / ATLAS_SIZE as f32,
+                            fade_distance: world
+                                .get(id, world_text_fade_distance())
+                                .unwrap_or_default(),
+                            // The distance fields store the spread on either side of the edge between 0 and 1
+                            outline_width: world
+                                .get(id, world_text_outline_width())
+                                .unwrap_or(0.)
+                                .clamp(0., MAX_OFFSET)
+                                * GLYPH_PX
+                                / (2 * SPREAD) as f32,
+                            _padding: Default::default(),
+                        };
+                        world
+                            .add_component(
+                                id,
+                                material(),
+                                SharedMaterial::new(WorldTextMaterial::new(
+                                    assets.clone(),
+                                    atlas.view.clone(),
+                                    params,
+                                )),
+                            )
+                            .unwrap();
+                    }
+                }),
+        ],
+    )
+}
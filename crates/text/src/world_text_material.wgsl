@@ -0,0 +1,58 @@
+
+struct WorldTextParams {
+    outline_color: vec4<f32>,
+    shadow_color: vec4<f32>,
+    shadow_offset: vec2<f32>,
+    fade_distance: vec2<f32>,
+    outline_width: f32,
+};
+
+@group(MATERIAL_BIND_GROUP)
+@binding(0)
+var font_atlas: texture_2d<f32>;
+
+@group(MATERIAL_BIND_GROUP)
+@binding(1)
+var font_sampler: sampler;
+
+@group(MATERIAL_BIND_GROUP)
+@binding(2)
+var<uniform> params: WorldTextParams;
+
+// The coverage of the shape within `edge` of the distance field, anti-aliased over about a pixel on screen
+fn coverage(distance: f32, edge: f32, smoothing: f32) -> f32 {
+    return smoothstep(edge - smoothing, edge + smoothing, distance);
+}
+
+fn get_material(in: MaterialInput) -> MaterialOutput {
+    var out: MaterialOutput;
+    let color = get_entity_color_or(in.entity_loc, vec4<f32>(1., 1., 1., 1.));
+    let distance = textureSample(font_atlas, font_sampler, in.texcoord).x;
+    let shadow_distance = textureSample(font_atlas, font_sampler, in.texcoord - params.shadow_offset).x;
+    let smoothing = max(fwidth(distance) * 0.75, 0.001);
+
+    // The glyph is drawn over its outline, which is drawn over its shadow
+    let fill = coverage(distance, 0.5, smoothing);
+    let outline_edge = 0.5 - params.outline_width;
+    let outline = coverage(distance, outline_edge, smoothing) * params.outline_color.a;
+    let glyph = vec4<f32>(mix(params.outline_color.rgb, color.rgb, fill), mix(outline, color.a, fill));
+    let shadow = coverage(shadow_distance, outline_edge, smoothing) * params.shadow_color.a;
+    let opacity = glyph.a + shadow * (1. - glyph.a);
+    let rgb = (glyph.rgb * glyph.a + params.shadow_color.rgb * shadow * (1. - glyph.a)) / max(opacity, 0.0001);
+
+    var fade = 1.;
+    if params.fade_distance.y > 0. {
+        let camera_distance = length(global_params.camera_position.xyz - in.world_position);
+        fade = 1. - smoothstep(params.fade_distance.x, params.fade_distance.y, camera_distance);
+    }
+
+    out.roughness = 0.4;
+    out.metallic = 0.5;
+    out.opacity = opacity * fade;
+    out.alpha_cutoff = 0.01;
+    out.base_color = from_srgb_to_linear(rgb);
+    out.emissive_factor = vec3<f32>(0., 0., 0.);
+    out.shading = 1.;
+    out.normal = in.normal;
+    return out;
+}
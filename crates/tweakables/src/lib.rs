@@ -0,0 +1,261 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use ambient_core::{async_ecs::async_run, no_sync, project_data_dir, runtime};
+use ambient_ecs::{
+    components, generated::components::core::network::is_remote_entity, query, Entity, EntityId,
+    FnSystem, Resource, SystemGroup, World,
+};
+use anyhow::Context;
+
+mod value;
+pub use value::*;
+
+pub use ambient_ecs::generated::components::core::tweakable::{
+    tweakable_bool, tweakable_default, tweakable_f32, tweakable_i32, tweakable_max, tweakable_min,
+    tweakable_name, tweakable_replicated, tweakable_string,
+};
+
+components!("tweakable", {
+    /// The values of the tweakables on this side that differ from their defaults, by name
+    @[Resource]
+    persisted_tweakables: PersistedTweakables,
+});
+
+#[derive(Debug, Clone, Default)]
+pub enum PersistedTweakables {
+    #[default]
+    NotLoaded,
+    Loading,
+    /// Also contains the values of tweakables that have not been declared since the project started, so that they are
+    /// kept until they are
+    Loaded(BTreeMap<String, String>),
+}
+
+pub fn resources() -> Entity {
+    Entity::new().with_default(persisted_tweakables())
+}
+
+/// The file the tweakables of the current project are persisted to, on this `side` (`server` or `client`)
+fn persisted_path(world: &World, side: &str) -> anyhow::Result<PathBuf> {
+    Ok(project_data_dir(world, "tweakables")?.join(format!("{side}.json")))
+}
+
+/// Finds the tweakable named `name` that was declared on this side
+pub fn find(world: &World, name: &str) -> Option<EntityId> {
+    query(tweakable_name())
+        .excl(is_remote_entity())
+        .iter(world, None)
+        .find(|(_, tweakable)| *tweakable == name)
+        .map(|(id, _)| id)
+}
+
+pub fn get(world: &World, name: &str) -> Option<TweakableValue> {
+    TweakableValue::read(world, find(world, name)?)
+}
+
+/// Declares a tweakable named `name` on this side, with a `default` value and, for numeric tweakables, an optional
+/// `range`. If the tweakable already exists, it is returned instead.
+pub fn declare(
+    world: &mut World,
+    name: &str,
+    default: TweakableValue,
+    range: Option<(f32, f32)>,
+    replicated: bool,
+) -> EntityId {
+    if let Some(id) = find(world, name) {
+        return id;
+    }
+    let mut data = Entity::new()
+        .with(tweakable_name(), name.to_string())
+        .with(tweakable_default(), default.to_string());
+    if let Some((min, max)) = range {
+        data = data.with(tweakable_min(), min).with(tweakable_max(), max);
+    }
+    if replicated {
+        data = data.with(tweakable_replicated(), ());
+    }
+    let id = data.spawn(world);
+    default.write(world, id).unwrap();
+    id
+}
+
+/// Sets the tweakable `name` to `text` parsed as a value of its type, e.g. when it is edited in the debugger
+pub fn set_from_text(world: &mut World, name: &str, text: &str) -> anyhow::Result<()> {
+    let id = find(world, name).with_context(|| format!("There is no tweakable named {name:?}"))?;
+    let value = TweakableValue::read(world, id)
+        .with_context(|| format!("The tweakable {name:?} has no value"))?
+        .parse_like(text)?
+        .clamped(world, id);
+    value.write(world, id)
+}
+
+/// The names and values of the tweakables declared on this side, sorted by name
+pub fn list(world: &World) -> Vec<(String, String)> {
+    let mut tweakables = query(tweakable_name())
+        .excl(is_remote_entity())
+        .iter(world, None)
+        .filter_map(|(id, name)| Some((name.clone(), TweakableValue::read(world, id)?.to_string())))
+        .collect::<Vec<_>>();
+    tweakables.sort();
+    tweakables
+}
+
+/// Sets the persisted value of the tweakable `id`, if there is one
+fn restore(world: &mut World, persisted: &BTreeMap<String, String>, id: EntityId) {
+    let Ok(name) = world.get_cloned(id, tweakable_name()) else {
+        return;
+    };
+    let (Some(text), Some(value)) = (persisted.get(&name), TweakableValue::read(world, id)) else {
+        return;
+    };
+    match value.parse_like(text) {
+        Ok(value) => {
+            let value = value.clamped(world, id);
+            value.write(world, id).ok();
+        }
+        Err(err) => log::warn!("Failed to restore the tweakable {name:?}: {err:?}"),
+    }
+}
+
+/// Restores, clamps, replicates and persists the tweakables on this `side` (`server` or `client`), which are persisted
+/// separately.
+pub fn systems(side: &'static str) -> SystemGroup {
+    SystemGroup::new(
+        "tweakables",
+        vec![
+            Box::new(FnSystem::new(move |world, _| {
+                if !matches!(
+                    world.resource(persisted_tweakables()),
+                    PersistedTweakables::NotLoaded
+                ) {
+                    return;
+                }
+                *world.resource_mut(persisted_tweakables()) = PersistedTweakables::Loading;
+                let path = persisted_path(world, side);
+                let async_run = world.resource(async_run()).clone();
+                world.resource(runtime()).spawn(async move {
+                    let persisted = async {
+                        let path = path?;
+                        // Nothing has been persisted yet if the file doesn't exist
+                        let Ok(source) = ambient_sys::fs::read_to_string(&path).await else {
+                            return anyhow::Ok(BTreeMap::new());
+                        };
+                        serde_json::from_str::<BTreeMap<String, String>>(&source)
+                            .with_context(|| format!("Failed to parse {}", path.display()))
+                    }
+                    .await
+                    .unwrap_or_else(|err| {
+                        log::warn!("Failed to load the persisted tweakables: {err:?}");
+                        BTreeMap::new()
+                    });
+                    async_run.run(move |world| {
+                        for id in query(())
+                            .incl(tweakable_name())
+                            .excl(is_remote_entity())
+                            .collect_ids(world, None)
+                        {
+                            restore(world, &persisted, id);
+                        }
+                        world.add_resource(
+                            persisted_tweakables(),
+                            PersistedTweakables::Loaded(persisted),
+                        );
+                    });
+                });
+            })),
+            query(())
+                .incl(tweakable_name())
+                .excl(is_remote_entity())
+                .spawned()
+                .to_system(|q, world, qs, _| {
+                    let PersistedTweakables::Loaded(persisted) =
+                        world.resource(persisted_tweakables()).clone()
+                    else {
+                        return;
+                    };
+                    for (id, _) in q.collect_cloned(world, qs) {
+                        restore(world, &persisted, id);
+                    }
+                }),
+            query(tweakable_f32().changed())
+                .excl(is_remote_entity())
+                .to_system(|q, world, qs, _| {
+                    for (id, value) in q.collect_cloned(world, qs) {
+                        TweakableValue::F32(value)
+                            .clamped(world, id)
+                            .write(world, id)
+                            .ok();
+                    }
+                }),
+            query(tweakable_i32().changed())
+                .excl(is_remote_entity())
+                .to_system(|q, world, qs, _| {
+                    for (id, value) in q.collect_cloned(world, qs) {
+                        TweakableValue::I32(value)
+                            .clamped(world, id)
+                            .write(world, id)
+                            .ok();
+                    }
+                }),
+            query(())
+                .incl(tweakable_name())
+                .excl(tweakable_replicated())
+                .excl(is_remote_entity())
+                .excl(no_sync())
+                .to_system(|q, world, qs, _| {
+                    for (id, _) in q.collect_cloned(world, qs) {
+                        world.add_component(id, no_sync(), ()).ok();
+                    }
+                }),
+            query(())
+                .incl(tweakable_replicated())
+                .incl(no_sync())
+                .to_system(|q, world, qs, _| {
+                    for (id, _) in q.collect_cloned(world, qs) {
+                        world.remove_component(id, no_sync()).ok();
+                    }
+                }),
+            // Returns before reading the changes until the persisted tweakables are loaded, so that none are missed
+            query((tweakable_name(), tweakable_default()))
+                .optional_changed(tweakable_bool())
+                .optional_changed(tweakable_f32())
+                .optional_changed(tweakable_i32())
+                .optional_changed(tweakable_string())
+                .excl(is_remote_entity())
+                .to_system(move |q, world, qs, _| {
+                    let PersistedTweakables::Loaded(persisted) =
+                        world.resource(persisted_tweakables())
+                    else {
+                        return;
+                    };
+                    let mut updated = persisted.clone();
+                    for (id, (name, default)) in q.collect_cloned(world, qs) {
+                        match TweakableValue::read(world, id).map(|value| value.to_string()) {
+                            Some(value) if value != default => updated.insert(name, value),
+                            _ => updated.remove(&name),
+                        };
+                    }
+                    if updated == *persisted {
+                        return;
+                    }
+
+                    let source = serde_json::to_string_pretty(&updated).unwrap();
+                    world
+                        .add_resource(persisted_tweakables(), PersistedTweakables::Loaded(updated));
+                    let path = persisted_path(world, side);
+                    world.resource(runtime()).spawn(async move {
+                        let result = async {
+                            let path = path?;
+                            ambient_sys::fs::create_dir_all(path.parent().unwrap()).await?;
+                            ambient_sys::fs::write(&path, source).await?;
+                            anyhow::Ok(())
+                        }
+                        .await;
+                        if let Err(err) = result {
+                            log::warn!("Failed to persist the tweakables: {err:?}");
+                        }
+                    });
+                }),
+        ],
+    )
+}
@@ -0,0 +1,132 @@
+use std::fmt::Display;
+
+use ambient_ecs::{EntityId, World};
+use anyhow::Context;
+
+use crate::{
+    tweakable_bool, tweakable_f32, tweakable_i32, tweakable_max, tweakable_min, tweakable_string,
+};
+
+/// The value of a tweakable. Values are formatted as text to be shown, edited and persisted.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TweakableValue {
+    F32(f32),
+    I32(i32),
+    Bool(bool),
+    String(String),
+}
+impl Display for TweakableValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::F32(value) => value.fmt(f),
+            Self::I32(value) => value.fmt(f),
+            Self::Bool(value) => value.fmt(f),
+            Self::String(value) => value.fmt(f),
+        }
+    }
+}
+impl TweakableValue {
+    /// Reads the value of the tweakable `id`
+    pub fn read(world: &World, id: EntityId) -> Option<Self> {
+        if let Ok(value) = world.get(id, tweakable_f32()) {
+            Some(Self::F32(value))
+        } else if let Ok(value) = world.get(id, tweakable_i32()) {
+            Some(Self::I32(value))
+        } else if let Ok(value) = world.get(id, tweakable_bool()) {
+            Some(Self::Bool(value))
+        } else {
+            world
+                .get_cloned(id, tweakable_string())
+                .ok()
+                .map(Self::String)
+        }
+    }
+
+    /// Sets the value of the tweakable `id`, if it changed
+    pub fn write(self, world: &mut World, id: EntityId) -> anyhow::Result<()> {
+        match self {
+            Self::F32(value) => world.set_if_changed(id, tweakable_f32(), value)?,
+            Self::I32(value) => world.set_if_changed(id, tweakable_i32(), value)?,
+            Self::Bool(value) => world.set_if_changed(id, tweakable_bool(), value)?,
+            Self::String(value) => world.set_if_changed(id, tweakable_string(), value)?,
+        }
+        Ok(())
+    }
+
+    /// Parses `text` as a value of the same type as this one
+    pub fn parse_like(&self, text: &str) -> anyhow::Result<Self> {
+        let text = text.trim();
+        Ok(match self {
+            Self::F32(_) => Self::F32(
+                text.parse()
+                    .with_context(|| format!("{text:?} is not a number"))?,
+            ),
+            Self::I32(_) => Self::I32(
+                text.parse()
+                    .with_context(|| format!("{text:?} is not an integer"))?,
+            ),
+            Self::Bool(_) => Self::Bool(
+                text.parse()
+                    .with_context(|| format!("{text:?} is not `true` or `false`"))?,
+            ),
+            Self::String(_) => Self::String(text.to_string()),
+        })
+    }
+
+    /// Clamps numeric values to the range of the tweakable `id`
+    pub fn clamped(self, world: &World, id: EntityId) -> Self {
+        let min = world.get(id, tweakable_min()).unwrap_or(f32::MIN);
+        let max = world.get(id, tweakable_max()).unwrap_or(f32::MAX);
+        self.clamp(min, max)
+    }
+
+    fn clamp(self, min: f32, max: f32) -> Self {
+        if min > max {
+            return self;
+        }
+        match self {
+            Self::F32(value) => Self::F32(value.clamp(min, max)),
+            Self::I32(value) => Self::I32(value.clamp(min.ceil() as i32, max.floor() as i32)),
+            value => value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_format() {
+        let value = TweakableValue::F32(0.).parse_like(" 2.5 ").unwrap();
+        assert_eq!(value, TweakableValue::F32(2.5));
+        assert_eq!(value.to_string(), "2.5");
+        assert_eq!(TweakableValue::F32(5.).to_string(), "5");
+        assert_eq!(
+            TweakableValue::I32(0).parse_like("-3").unwrap(),
+            TweakableValue::I32(-3)
+        );
+        assert!(TweakableValue::I32(0).parse_like("2.5").is_err());
+        assert_eq!(
+            TweakableValue::Bool(false).parse_like("true").unwrap(),
+            TweakableValue::Bool(true)
+        );
+        assert!(TweakableValue::Bool(false).parse_like("yes").is_err());
+    }
+
+    #[test]
+    fn clamp() {
+        assert_eq!(
+            TweakableValue::F32(12.).clamp(0., 10.),
+            TweakableValue::F32(10.)
+        );
+        assert_eq!(
+            TweakableValue::I32(-4).clamp(-2.5, 10.),
+            TweakableValue::I32(-2)
+        );
+        assert_eq!(
+            TweakableValue::Bool(true).clamp(0., 1.),
+            TweakableValue::Bool(true)
+        );
+    }
+}
@@ -55,6 +55,7 @@ use ambient_shared_types::MouseButton;
 pub fn init_all_components() {
     layout::init_all_components();
     layout::init_gpu_components();
+    rect::init_components();
     text::init_components();
 }
 
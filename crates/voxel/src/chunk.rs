@@ -0,0 +1,131 @@
+use glam::{IVec3, UVec3};
+
+/// The number of blocks along each side of a chunk
+pub const CHUNK_SIZE: usize = 16;
+/// The number of blocks in a chunk
+pub const CHUNK_VOLUME: usize = CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE;
+
+/// A kind of block; [AIR] is empty, and the others are solid
+pub type Block = u8;
+pub const AIR: Block = 0;
+
+/// The chunk that the block at `position` is in, and its position within the chunk
+pub fn chunk_of(position: IVec3) -> (IVec3, UVec3) {
+    let size = CHUNK_SIZE as i32;
+    (
+        position.div_euclid(IVec3::splat(size)),
+        position.rem_euclid(IVec3::splat(size)).as_uvec3(),
+    )
+}
+
+/// The index of the block at `local` within a chunk; x first, then y, then z
+pub fn block_index(local: UVec3) -> usize {
+    local.x as usize + local.y as usize * CHUNK_SIZE + local.z as usize * CHUNK_SIZE * CHUNK_SIZE
+}
+
+/// Packs the edit that sets the block at `index` to `block`, as in `voxel_chunk_edits`
+pub fn pack_edit(index: usize, block: Block) -> u32 {
+    (index as u32) << 8 | block as u32
+}
+
+pub fn unpack_edit(edit: u32) -> (usize, Block) {
+    ((edit >> 8) as usize, edit as Block)
+}
+
+/// The blocks of a chunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VoxelChunk {
+    blocks: Vec<Block>,
+}
+impl VoxelChunk {
+    pub fn empty() -> Self {
+        Self {
+            blocks: vec![AIR; CHUNK_VOLUME],
+        }
+    }
+
+    /// The chunk with the replicated `blocks` (which are empty if there are none), with the `edits` applied
+    pub fn from_replicated(blocks: &[Block], edits: &[u32]) -> Self {
+        let mut chunk = if blocks.len() == CHUNK_VOLUME {
+            Self {
+                blocks: blocks.to_vec(),
+            }
+        } else {
+            Self::empty()
+        };
+        for &edit in edits {
+            let (index, block) = unpack_edit(edit);
+            if let Some(existing) = chunk.blocks.get_mut(index) {
+                *existing = block;
+            }
+        }
+        chunk
+    }
+
+    /// The blocks, as replicated in `voxel_chunk_blocks`
+    pub fn into_blocks(self) -> Vec<Block> {
+        if self.is_empty() {
+            Vec::new()
+        } else {
+            self.blocks
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.iter().all(|&block| block == AIR)
+    }
+
+    pub fn get(&self, local: UVec3) -> Block {
+        self.blocks[block_index(local)]
+    }
+
+    /// The block at `local`, or `None` if it is outside of the chunk
+    pub fn get_signed(&self, local: IVec3) -> Option<Block> {
+        let size = CHUNK_SIZE as i32;
+        if local.cmpge(IVec3::ZERO).all() && local.cmplt(IVec3::splat(size)).all() {
+            Some(self.get(local.as_uvec3()))
+        } else {
+            None
+        }
+    }
+
+    pub fn set(&mut self, local: UVec3, block: Block) {
+        self.blocks[block_index(local)] = block;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunk_of_negative_positions() {
+        assert_eq!(
+            chunk_of(IVec3::new(0, 15, 16)),
+            (IVec3::new(0, 0, 1), UVec3::new(0, 15, 0))
+        );
+        assert_eq!(
+            chunk_of(IVec3::new(-1, -16, -17)),
+            (IVec3::new(-1, -1, -2), UVec3::new(15, 0, 15))
+        );
+    }
+
+    #[test]
+    fn edits_apply_on_top_of_blocks() {
+        let mut chunk = VoxelChunk::empty();
+        chunk.set(UVec3::new(1, 2, 3), 7);
+        let index = block_index(UVec3::new(1, 2, 3));
+        assert_eq!(unpack_edit(pack_edit(index, 9)), (index, 9));
+
+        let edited = VoxelChunk::from_replicated(
+            &chunk.clone().into_blocks(),
+            &[pack_edit(index, 9), pack_edit(0, 1)],
+        );
+        assert_eq!(edited.get(UVec3::new(1, 2, 3)), 9);
+        assert_eq!(edited.get(UVec3::ZERO), 1);
+
+        // Empty chunks aren't replicated block by block
+        assert!(VoxelChunk::empty().into_blocks().is_empty());
+        assert_eq!(VoxelChunk::from_replicated(&[], &[]), VoxelChunk::empty());
+    }
+}
@@ -0,0 +1,430 @@
+use std::{collections::HashMap, sync::Arc};
+
+use ambient_core::{
+    asset_cache,
+    async_ecs::async_run,
+    bounding::{local_bounding_aabb, world_bounding_aabb, world_bounding_sphere},
+    main_scene, runtime,
+    transform::{local_to_world, mesh_to_world, scale, translation},
+};
+use ambient_ecs::{components, query, Entity, EntityId, SystemGroup, World};
+use ambient_gpu::mesh_buffer::GpuMesh;
+use ambient_physics::{
+    collider::{collider_type, ColliderType},
+    main_physics_scene,
+    physx::{physics, physics_shape, rigid_static},
+    wood_physics_material, PxActorUserData, PxShapeUserData,
+};
+use ambient_renderer::{
+    color, gpu_primitives_lod, gpu_primitives_mesh,
+    materials::{
+        flat_material::{get_flat_shader, FlatMaterialKey},
+        pbr_material::{get_pbr_shader, PbrMaterialFromUrl},
+    },
+    primitives, RenderPrimitive, RendererShaderProducer, SharedMaterial,
+};
+use ambient_std::{
+    asset_cache::{AsyncAssetKeyExt, SyncAssetKeyExt},
+    asset_url::AbsAssetUrl,
+    cb,
+    shapes::AABB,
+};
+use futures::future::join_all;
+use glam::{IVec3, Vec3, Vec4};
+use itertools::Itertools;
+use physxx::{
+    AsPxActor, PxActor, PxActorFlag, PxDefaultMemoryInputData, PxDefaultMemoryOutputStream,
+    PxMeshScale, PxRigidActor, PxRigidStaticRef, PxShapeFlag, PxTransform, PxTriangleMesh,
+    PxTriangleMeshCookingResult, PxTriangleMeshDesc, PxTriangleMeshGeometry, PxUserData,
+};
+
+mod chunk;
+mod mesher;
+pub use chunk::*;
+pub use mesher::*;
+
+pub use ambient_ecs::generated::components::core::voxel::{
+    voxel_block_faces, voxel_block_size, voxel_chunk, voxel_chunk_blocks, voxel_chunk_edits,
+    voxel_chunk_position, voxel_materials, voxel_world,
+};
+
+/// Chunks with more edits than this have them merged into their blocks by the server, so that the edits of a chunk
+/// stay cheap to replicate
+const MAX_CHUNK_EDITS: usize = 256;
+
+components!("voxel", {
+    /// Attached to the chunks whose mesh (on the client) or collider (on the server) is out of date
+    voxel_chunk_dirty: (),
+    /// The `voxel_materials` of a voxel world, once they have loaded on the client; `None` for those that failed to
+    voxel_loaded_materials: Arc<Vec<Option<SharedMaterial>>>,
+});
+
+/// The blocks of the chunk `id`, with its edits applied
+pub fn chunk_blocks(world: &World, id: EntityId) -> VoxelChunk {
+    let blocks = world.get_ref(id, voxel_chunk_blocks());
+    let edits = world.get_ref(id, voxel_chunk_edits());
+    VoxelChunk::from_replicated(
+        blocks.map(Vec::as_slice).unwrap_or_default(),
+        edits.map(Vec::as_slice).unwrap_or_default(),
+    )
+}
+
+fn chunk_position(position: Vec3) -> IVec3 {
+    position.round().as_ivec3()
+}
+
+/// The chunks of the voxel worlds, by their world and position
+fn chunks_by_position(world: &World) -> HashMap<(EntityId, IVec3), EntityId> {
+    query((voxel_chunk(), voxel_chunk_position()))
+        .iter(world, None)
+        .map(|(id, (&owner, &position))| ((owner, chunk_position(position)), id))
+        .collect()
+}
+
+/// Looks up the blocks around the chunk at `position` in the world `owner`, by their position relative to the chunk
+fn outside_blocks(
+    world: &World,
+    chunks: &HashMap<(EntityId, IVec3), EntityId>,
+    owner: EntityId,
+    position: IVec3,
+) -> impl Fn(IVec3) -> Block {
+    let neighbours = Face::ALL
+        .into_iter()
+        .filter_map(|face| {
+            let neighbour = position + face.normal().as_ivec3();
+            let id = *chunks.get(&(owner, neighbour))?;
+            Some((neighbour, chunk_blocks(world, id)))
+        })
+        .collect::<HashMap<_, _>>();
+    move |local| {
+        let (neighbour, local) = chunk_of(position * CHUNK_SIZE as i32 + local);
+        neighbours
+            .get(&neighbour)
+            .map_or(AIR, |chunk| chunk.get(local))
+    }
+}
+
+/// Marks the chunks `ids` and their neighbours as dirty, as the faces between them may have changed
+fn mark_dirty(world: &mut World, ids: impl IntoIterator<Item = EntityId>) {
+    let chunks = chunks_by_position(world);
+    let mut dirty = Vec::new();
+    for id in ids {
+        let (Ok(owner), Ok(position)) = (
+            world.get(id, voxel_chunk()),
+            world.get(id, voxel_chunk_position()),
+        ) else {
+            continue;
+        };
+        let position = chunk_position(position);
+        dirty.push(id);
+        dirty.extend(
+            Face::ALL
+                .into_iter()
+                .filter_map(|face| chunks.get(&(owner, position + face.normal().as_ivec3()))),
+        );
+    }
+    for id in dirty.into_iter().unique() {
+        world.add_component(id, voxel_chunk_dirty(), ()).ok();
+    }
+}
+
+/// The chunks of the voxel world `owner`
+fn chunks_of(world: &World, owner: EntityId) -> Vec<EntityId> {
+    query(voxel_chunk())
+        .iter(world, None)
+        .filter(|(_, chunk_owner)| **chunk_owner == owner)
+        .map(|(id, _)| id)
+        .collect()
+}
+
+/// Marks the chunks whose blocks or materials changed as dirty
+fn dirty_systems() -> SystemGroup {
+    SystemGroup::new(
+        "voxel/dirty",
+        vec![
+            query(voxel_chunk_blocks().changed()).to_system(|q, world, qs, _| {
+                let ids = q.iter(world, qs).map(|(id, _)| id).collect_vec();
+                mark_dirty(world, ids);
+            }),
+            query(voxel_chunk_edits().changed()).to_system(|q, world, qs, _| {
+                let ids = q.iter(world, qs).map(|(id, _)| id).collect_vec();
+                mark_dirty(world, ids);
+            }),
+            query(voxel_block_faces().changed()).to_system(|q, world, qs, _| {
+                for (owner, _) in q.collect_cloned(world, qs) {
+                    for id in chunks_of(world, owner) {
+                        world.add_component(id, voxel_chunk_dirty(), ()).ok();
+                    }
+                }
+            }),
+        ],
+    )
+}
+
+/// Replaces the collider of the chunk `id` with one for its `blocks`
+fn update_collider(
+    world: &mut World,
+    id: EntityId,
+    blocks: &VoxelChunk,
+    outside: impl Fn(IVec3) -> Block,
+) {
+    if let Ok(actor) = world.get(id, rigid_static()) {
+        if let Some(scene) = actor.get_scene() {
+            scene.remove_actor(&actor, false);
+            for shape in actor.get_shapes() {
+                shape.remove_user_data::<PxShapeUserData>();
+            }
+            actor.as_actor().remove_user_data::<PxActorUserData>();
+            actor.release();
+        }
+        world.remove_component(id, rigid_static()).ok();
+        world.remove_component(id, physics_shape()).ok();
+    }
+
+    let (points, indices) = collision_triangles(blocks, outside);
+    if indices.is_empty() {
+        return;
+    }
+    let physics = world.resource(physics());
+    let stream = PxDefaultMemoryOutputStream::new();
+    let mut result = PxTriangleMeshCookingResult::Success;
+    let desc = PxTriangleMeshDesc {
+        points,
+        indices,
+        flags: None,
+    };
+    if !physics
+        .cooking
+        .cook_triangle_mesh(&desc, &stream, &mut result)
+    {
+        log::error!("Failed to cook voxel chunk collider: {:?}", result);
+        return;
+    }
+    let mesh = PxTriangleMesh::new(
+        physics.physics,
+        &PxDefaultMemoryInputData::new(stream.get_data()),
+    );
+    let block_size = world.get(id, scale()).unwrap_or(Vec3::ONE);
+    let position = world.get(id, translation()).unwrap_or_default();
+    let actor = PxRigidStaticRef::new_with_geometry(
+        physics.physics,
+        &PxTransform::from_translation(position),
+        &PxTriangleMeshGeometry::new(&mesh, Some(PxMeshScale::from_scale(block_size)), None),
+        world.resource(wood_physics_material()),
+        &PxTransform::identity(),
+    );
+    actor
+        .as_actor()
+        .set_user_data(PxActorUserData { serialize: false });
+    actor.set_actor_flag(PxActorFlag::VISUALIZATION, false);
+    let shape = actor.get_shapes()[0].clone();
+    shape.set_flag(PxShapeFlag::SCENE_QUERY_SHAPE, true);
+    shape.set_user_data(PxShapeUserData {
+        entity: id,
+        ..Default::default()
+    });
+    world.resource(main_physics_scene()).add_actor(&actor);
+
+    world
+        .add_components(
+            id,
+            Entity::new()
+                .with(physics_shape(), shape)
+                .with(rigid_static(), actor)
+                .with(collider_type(), ColliderType::Static),
+        )
+        .unwrap();
+}
+
+/// Merges the edits of the chunks, places them in their worlds and keeps their colliders up to date, on the server.
+pub fn server_systems() -> SystemGroup {
+    SystemGroup::new(
+        "voxel",
+        vec![
+            query(voxel_chunk_edits().changed()).to_system(|q, world, qs, _| {
+                for (id, edits) in q.collect_cloned(world, qs) {
+                    if edits.len() > MAX_CHUNK_EDITS {
+                        let blocks = chunk_blocks(world, id).into_blocks();
+                        world.add_component(id, voxel_chunk_blocks(), blocks).ok();
+                        world
+                            .add_component(id, voxel_chunk_edits(), Vec::new())
+                            .ok();
+                    }
+                }
+            }),
+            query((voxel_chunk(), voxel_chunk_position().changed())).to_system(
+                |q, world, qs, _| {
+                    for (id, (owner, position)) in q.collect_cloned(world, qs) {
+                        let origin = world.get(owner, translation()).unwrap_or_default();
+                        let block_size = world.get(owner, voxel_block_size()).unwrap_or(1.);
+                        let offset =
+                            chunk_position(position).as_vec3() * CHUNK_SIZE as f32 * block_size;
+                        world
+                            .add_components(
+                                id,
+                                Entity::new()
+                                    .with(translation(), origin + offset)
+                                    .with(scale(), Vec3::splat(block_size)),
+                            )
+                            .ok();
+                    }
+                },
+            ),
+            Box::new(dirty_systems()),
+            query((voxel_chunk(), voxel_chunk_position()))
+                .incl(voxel_chunk_dirty())
+                .to_system(|q, world, qs, _| {
+                    let dirty = q.collect_cloned(world, qs);
+                    if dirty.is_empty() {
+                        return;
+                    }
+                    let chunks = chunks_by_position(world);
+                    for (id, (owner, position)) in dirty {
+                        world.remove_component(id, voxel_chunk_dirty()).ok();
+                        let blocks = chunk_blocks(world, id);
+                        let outside =
+                            outside_blocks(world, &chunks, owner, chunk_position(position));
+                        update_collider(world, id, &blocks, outside);
+                    }
+                }),
+        ],
+    )
+}
+
+/// Loads the materials of the voxel worlds, and meshes their chunks, on the client.
+pub fn client_systems() -> SystemGroup {
+    SystemGroup::new(
+        "voxel/client",
+        vec![
+            query(voxel_materials().changed())
+                .incl(voxel_world())
+                .to_system(|q, world, qs, _| {
+                    for (id, urls) in q.collect_cloned(world, qs) {
+                        let assets = world.resource(asset_cache()).clone();
+                        let async_run = world.resource(async_run()).clone();
+                        world.resource(runtime()).spawn(async move {
+                            let materials = join_all(urls.into_iter().map(|url| {
+                                let assets = assets.clone();
+                                async move {
+                                    let url = match AbsAssetUrl::parse(&url) {
+                                        Ok(url) => url,
+                                        Err(err) => {
+                                            log::warn!(
+                                                "Invalid voxel material URL {url:?}: {err:?}"
+                                            );
+                                            return None;
+                                        }
+                                    };
+                                    match PbrMaterialFromUrl(url).get(&assets).await {
+                                        Ok(material) => Some(SharedMaterial::from(material)),
+                                        Err(err) => {
+                                            log::warn!("Failed to load voxel material: {err:?}");
+                                            None
+                                        }
+                                    }
+                                }
+                            }))
+                            .await;
+                            async_run.run(move |world| {
+                                if world.exists(id) {
+                                    world
+                                        .add_component(
+                                            id,
+                                            voxel_loaded_materials(),
+                                            Arc::new(materials),
+                                        )
+                                        .ok();
+                                    for chunk in chunks_of(world, id) {
+                                        world.add_component(chunk, voxel_chunk_dirty(), ()).ok();
+                                    }
+                                }
+                            });
+                        });
+                    }
+                }),
+            Box::new(dirty_systems()),
+            query((voxel_chunk(), voxel_chunk_position()))
+                .incl(voxel_chunk_dirty())
+                .to_system(|q, world, qs, _| {
+                    let dirty = q.collect_cloned(world, qs);
+                    if dirty.is_empty() {
+                        return;
+                    }
+                    let chunks = chunks_by_position(world);
+                    for (id, (owner, position)) in dirty {
+                        // The chunks are meshed once the materials of their world have loaded
+                        let materials = match world.get_cloned(owner, voxel_loaded_materials()) {
+                            Ok(materials) => materials,
+                            Err(_) if world.has_component(owner, voxel_materials()) => continue,
+                            Err(_) => Default::default(),
+                        };
+                        world.remove_component(id, voxel_chunk_dirty()).ok();
+                        let block_faces = world
+                            .get_cloned(owner, voxel_block_faces())
+                            .unwrap_or_default();
+                        let blocks = chunk_blocks(world, id);
+                        let outside =
+                            outside_blocks(world, &chunks, owner, chunk_position(position));
+                        let quads = greedy_quads(&blocks, outside, |block, face| {
+                            face_material(&block_faces, block, face)
+                        });
+                        let primitives = chunk_primitives(world, &quads, &materials);
+                        let aabb = AABB {
+                            min: Vec3::ZERO,
+                            max: Vec3::splat(CHUNK_SIZE as f32),
+                        };
+                        world
+                            .add_components(
+                                id,
+                                Entity::new()
+                                    .with(self::primitives(), primitives)
+                                    .with_default(gpu_primitives_mesh())
+                                    .with_default(gpu_primitives_lod())
+                                    .with(color(), Vec4::ONE)
+                                    .with(main_scene(), ())
+                                    .with_default(local_to_world())
+                                    .with_default(mesh_to_world())
+                                    .with(local_bounding_aabb(), aabb)
+                                    .with(world_bounding_aabb(), aabb)
+                                    .with(world_bounding_sphere(), aabb.to_sphere()),
+                            )
+                            .ok();
+                    }
+                }),
+        ],
+    )
+}
+
+/// A primitive for the quads of each material; the faces without a loaded material are drawn white
+fn chunk_primitives(
+    world: &World,
+    quads: &[Quad<Option<u32>>],
+    materials: &[Option<SharedMaterial>],
+) -> Vec<RenderPrimitive> {
+    let assets = world.resource(asset_cache());
+    quads
+        .iter()
+        .into_group_map_by(|quad| {
+            quad.material
+                .filter(|&index| matches!(materials.get(index as usize), Some(Some(_))))
+        })
+        .into_iter()
+        .sorted_by_key(|(material, _)| *material)
+        .filter_map(|(material, quads)| {
+            let mesh = quads_mesh(quads)?;
+            let material = material.and_then(|index| materials[index as usize].clone());
+            let shader: RendererShaderProducer = if material.is_some() {
+                cb(get_pbr_shader)
+            } else {
+                cb(get_flat_shader)
+            };
+            let material = material.unwrap_or_else(|| FlatMaterialKey::white().get(assets));
+            Some(RenderPrimitive {
+                material,
+                shader,
+                mesh: GpuMesh::from_mesh(assets, &mesh),
+                lod: 0,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,274 @@
+use ambient_std::mesh::{Mesh, MeshBuilder};
+use glam::{vec2, IVec3, Vec2, Vec3};
+
+use crate::chunk::{Block, VoxelChunk, AIR, CHUNK_SIZE};
+
+/// The directions that the faces of blocks face
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Face {
+    PosX,
+    NegX,
+    PosY,
+    NegY,
+    PosZ,
+    NegZ,
+}
+impl Face {
+    pub const ALL: [Face; 6] = [
+        Face::PosX,
+        Face::NegX,
+        Face::PosY,
+        Face::NegY,
+        Face::PosZ,
+        Face::NegZ,
+    ];
+
+    fn axis(self) -> usize {
+        match self {
+            Face::PosX | Face::NegX => 0,
+            Face::PosY | Face::NegY => 1,
+            Face::PosZ | Face::NegZ => 2,
+        }
+    }
+
+    fn positive(self) -> bool {
+        matches!(self, Face::PosX | Face::PosY | Face::PosZ)
+    }
+
+    fn step(self) -> IVec3 {
+        let mut step = IVec3::ZERO;
+        step[self.axis()] = if self.positive() { 1 } else { -1 };
+        step
+    }
+
+    pub fn normal(self) -> Vec3 {
+        self.step().as_vec3()
+    }
+}
+
+/// The material of the `face` of `block`, from the `voxel_block_faces` of its world; `None` if it has none
+pub fn face_material(block_faces: &[u32], block: Block, face: Face) -> Option<u32> {
+    if block == AIR {
+        return None;
+    }
+    let side = match face {
+        Face::PosZ => 0,
+        Face::NegZ => 2,
+        _ => 1,
+    };
+    block_faces.get((block as usize - 1) * 3 + side).copied()
+}
+
+/// The faces of several neighbouring blocks, merged into one rectangle
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quad<M> {
+    pub face: Face,
+    pub material: M,
+    /// Counter-clockwise, seen from the side the quad faces, in blocks from the corner of the chunk
+    pub corners: [Vec3; 4],
+}
+impl<M> Quad<M> {
+    /// The texture coordinates of the corners, in blocks, so that textures tile once per block and are upright on the
+    /// sides
+    fn texcoords(&self) -> [Vec2; 4] {
+        self.corners.map(|corner| match self.face.axis() {
+            0 => vec2(corner.y, -corner.z),
+            1 => vec2(corner.x, -corner.z),
+            _ => vec2(corner.x, corner.y),
+        })
+    }
+
+    fn tangent(&self) -> Vec3 {
+        match self.face.axis() {
+            0 => Vec3::Y,
+            _ => Vec3::X,
+        }
+    }
+}
+
+/// Merges the visible faces of the blocks of `chunk` into as few quads as possible, without merging faces of different
+/// `material`s. Faces are visible if the block they face is [AIR]; the blocks beyond the edges of the chunk are looked
+/// up with `outside`, by their position relative to the chunk.
+pub fn greedy_quads<M: Copy + PartialEq>(
+    chunk: &VoxelChunk,
+    outside: impl Fn(IVec3) -> Block,
+    material: impl Fn(Block, Face) -> M,
+) -> Vec<Quad<M>> {
+    let size = CHUNK_SIZE as i32;
+    let index = |i: i32, j: i32| (i + j * size) as usize;
+    let mut quads = Vec::new();
+    let mut mask = vec![None; CHUNK_SIZE * CHUNK_SIZE];
+    for face in Face::ALL {
+        let d = face.axis();
+        let (u, v) = ((d + 1) % 3, (d + 2) % 3);
+        for slice in 0..size {
+            // The materials of the visible faces in this slice
+            for j in 0..size {
+                for i in 0..size {
+                    let mut position = IVec3::ZERO;
+                    position[d] = slice;
+                    position[u] = i;
+                    position[v] = j;
+                    let block = chunk.get(position.as_uvec3());
+                    let facing = position + face.step();
+                    let facing = chunk.get_signed(facing).unwrap_or_else(|| outside(facing));
+                    mask[index(i, j)] =
+                        (block != AIR && facing == AIR).then(|| material(block, face));
+                }
+            }
+
+            // Each quad grows as wide as it can, and then as high as its whole width allows
+            for j in 0..size {
+                let mut i = 0;
+                while i < size {
+                    let Some(material) = mask[index(i, j)] else {
+                        i += 1;
+                        continue;
+                    };
+                    let mut width = 1;
+                    while i + width < size && mask[index(i + width, j)] == Some(material) {
+                        width += 1;
+                    }
+                    let mut height = 1;
+                    while j + height < size
+                        && (0..width).all(|k| mask[index(i + k, j + height)] == Some(material))
+                    {
+                        height += 1;
+                    }
+                    for y in j..j + height {
+                        for x in i..i + width {
+                            mask[index(x, y)] = None;
+                        }
+                    }
+
+                    let mut origin = Vec3::ZERO;
+                    origin[d] = (slice + face.positive() as i32) as f32;
+                    origin[u] = i as f32;
+                    origin[v] = j as f32;
+                    let mut across = Vec3::ZERO;
+                    across[u] = width as f32;
+                    let mut up = Vec3::ZERO;
+                    up[v] = height as f32;
+                    let corners = if face.positive() {
+                        [origin, origin + across, origin + across + up, origin + up]
+                    } else {
+                        [origin, origin + up, origin + across + up, origin + across]
+                    };
+                    quads.push(Quad {
+                        face,
+                        material,
+                        corners,
+                    });
+                    i += width;
+                }
+            }
+        }
+    }
+    quads
+}
+
+/// A mesh of the `quads`, with normals, tangents and tiling texture coordinates
+pub fn quads_mesh<'a, M: 'a>(quads: impl IntoIterator<Item = &'a Quad<M>>) -> Option<Mesh> {
+    let mut mesh = MeshBuilder {
+        texcoords: vec![Vec::new()],
+        ..Default::default()
+    };
+    for quad in quads {
+        let start = mesh.positions.len() as u32;
+        mesh.positions.extend(quad.corners);
+        mesh.normals.extend([quad.face.normal(); 4]);
+        mesh.tangents.extend([quad.tangent(); 4]);
+        mesh.texcoords[0].extend(quad.texcoords());
+        mesh.indices.extend([0, 1, 2, 0, 2, 3].map(|i| start + i));
+    }
+    mesh.build().ok()
+}
+
+/// The triangles of the surface of the blocks of `chunk`, as positions in blocks and indices, for its collider
+pub fn collision_triangles(
+    chunk: &VoxelChunk,
+    outside: impl Fn(IVec3) -> Block,
+) -> (Vec<Vec3>, Vec<u32>) {
+    let mut positions = Vec::new();
+    let mut indices = Vec::new();
+    for quad in greedy_quads(chunk, outside, |_, _| ()) {
+        let start = positions.len() as u32;
+        positions.extend(quad.corners);
+        indices.extend([0, 1, 2, 0, 2, 3].map(|i| start + i));
+    }
+    (positions, indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::UVec3;
+
+    use super::*;
+
+    fn quads(chunk: &VoxelChunk, block_faces: &[u32]) -> Vec<Quad<Option<u32>>> {
+        greedy_quads(
+            chunk,
+            |_| AIR,
+            |block, face| face_material(block_faces, block, face),
+        )
+    }
+
+    #[test]
+    fn single_block() {
+        let mut chunk = VoxelChunk::empty();
+        chunk.set(UVec3::new(3, 4, 5), 1);
+        let quads = quads(&chunk, &[]);
+        assert_eq!(quads.len(), 6);
+        for quad in &quads {
+            // The corners wind counter-clockwise around the normal, and the quad covers one face of the block
+            let [a, b, c, _] = quad.corners;
+            let normal = (b - a).cross(c - a);
+            assert_eq!(normal, quad.face.normal());
+            assert!(quad
+                .corners
+                .iter()
+                .all(|corner| corner.cmpge(Vec3::new(3., 4., 5.)).all()
+                    && corner.cmple(Vec3::new(4., 5., 6.)).all()));
+        }
+    }
+
+    #[test]
+    fn merges_faces_of_the_same_material() {
+        let mut chunk = VoxelChunk::empty();
+        for x in 0..4 {
+            for y in 0..2 {
+                chunk.set(UVec3::new(x, y, 0), 1);
+            }
+        }
+        assert_eq!(quads(&chunk, &[0, 1, 2]).len(), 6);
+
+        // Block 2 has another top, so the top splits in two; its sides and bottom still merge with block 1's
+        chunk.set(UVec3::new(0, 0, 0), 2);
+        let quads = quads(&chunk, &[0, 1, 2, 3, 1, 2]);
+        assert_eq!(quads.len(), 7);
+        let tops = quads
+            .iter()
+            .filter(|quad| quad.face == Face::PosZ)
+            .map(|quad| quad.material)
+            .collect::<Vec<_>>();
+        assert!(tops.contains(&Some(0)) && tops.contains(&Some(3)));
+        assert_eq!(face_material(&[0, 1, 2], 2, Face::PosZ), None);
+    }
+
+    #[test]
+    fn culls_faces_against_neighbouring_chunks() {
+        let mut chunk = VoxelChunk::empty();
+        chunk.set(UVec3::new(0, 0, 0), 1);
+        let quads = greedy_quads(
+            &chunk,
+            |position| (position == IVec3::new(-1, 0, 0)) as Block,
+            |_, _| (),
+        );
+        assert_eq!(quads.len(), 5);
+        assert!(quads.iter().all(|quad| quad.face != Face::NegX));
+
+        let (positions, indices) = collision_triangles(&chunk, |_| AIR);
+        assert_eq!((positions.len(), indices.len()), (24, 36));
+        assert!(quads_mesh(&Vec::<Quad<()>>::new()).is_none());
+    }
+}
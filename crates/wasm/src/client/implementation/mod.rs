@@ -1,7 +1,7 @@
 //! Used to implement all the *shared* host functions on the client.
 //!
 //! If implementing a trait that is only available on the client, it should go in [specific].
-use crate::shared::{self, wit};
+use crate::shared::{self, conversion::FromBindgen, wit};
 
 use super::Bindings;
 
@@ -12,6 +12,7 @@ mod unused;
 impl wit::types::Host for Bindings {}
 impl wit::entity::Host for Bindings {
     fn spawn(&mut self, data: wit::entity::EntityData) -> anyhow::Result<wit::types::EntityId> {
+        self.ensure_urls_permitted(data.iter().map(|(index, value)| (*index, value)))?;
         shared::implementation::entity::spawn(
             unsafe { self.world_ref.world_mut() },
             &mut self.base.spawned_entities,
@@ -23,6 +24,7 @@ impl wit::entity::Host for Bindings {
         &mut self,
         entity: wit::types::EntityId,
     ) -> anyhow::Result<Option<wit::entity::EntityData>> {
+        self.ensure_can_modify(entity.from_bindgen())?;
         shared::implementation::entity::despawn(
             unsafe { self.world_ref.world_mut() },
             &mut self.base.spawned_entities,
@@ -158,6 +160,8 @@ impl wit::component::Host for Bindings {
         index: u32,
         value: wit::component::Value,
     ) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
+        self.ensure_urls_permitted([(index, &value)])?;
         shared::implementation::component::add_component(self.world_mut(), entity, index, value)
     }
 
@@ -166,6 +170,8 @@ impl wit::component::Host for Bindings {
         entity: wit::types::EntityId,
         data: wit::entity::EntityData,
     ) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
+        self.ensure_urls_permitted(data.iter().map(|(index, value)| (*index, value)))?;
         shared::implementation::component::add_components(self.world_mut(), entity, data)
     }
 
@@ -175,6 +181,8 @@ impl wit::component::Host for Bindings {
         index: u32,
         value: wit::component::Value,
     ) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
+        self.ensure_urls_permitted([(index, &value)])?;
         shared::implementation::component::set_component(self.world_mut(), entity, index, value)
     }
 
@@ -183,6 +191,8 @@ impl wit::component::Host for Bindings {
         entity: wit::types::EntityId,
         data: wit::entity::EntityData,
     ) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
+        self.ensure_urls_permitted(data.iter().map(|(index, value)| (*index, value)))?;
         shared::implementation::component::set_components(self.world_mut(), entity, data)
     }
 
@@ -199,6 +209,7 @@ impl wit::component::Host for Bindings {
     }
 
     fn remove_component(&mut self, entity: wit::types::EntityId, index: u32) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
         shared::implementation::component::remove_component(self.world_mut(), entity, index)
     }
 
@@ -207,6 +218,7 @@ impl wit::component::Host for Bindings {
         entity: wit::types::EntityId,
         components: Vec<u32>,
     ) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
         shared::implementation::component::remove_components(self.world_mut(), entity, components)
     }
 
@@ -276,6 +288,7 @@ impl wit::world_audio::Host for Bindings {
         sound: String,
         emitter: wit::types::EntityId,
     ) -> anyhow::Result<()> {
+        shared::ensure_url_permitted(self.world(), self.id, &sound)?;
         shared::implementation::world_audio::play_sound_on_entity(self.world_mut(),sound, emitter)
     }
 }
@@ -6,19 +6,22 @@ use ambient_audio::AudioFromUrl;
 use ambient_core::{
     asset_cache,
     async_ecs::async_run,
-    player::local_user_id,
+    player::{get_by_user_id, get_spectator_by_user_id, local_user_id},
     runtime,
     window::{window_ctl, WindowCtl},
 };
-use ambient_input::{player_prev_raw_input, player_raw_input};
+use ambient_input::{
+    cursor::{cursor_locked, cursor_visible},
+    player_prev_raw_input, player_raw_input,
+};
 use ambient_network::client::game_client;
 use ambient_std::{asset_cache::AsyncAssetKeyExt, asset_url::AbsAssetUrl};
-use ambient_world_audio::{audio_sender, AudioMessage};
+use ambient_world_audio::{audio_sender, sound_bus, AudioMessage};
 use anyhow::Context;
-use winit::window::CursorGrabMode;
 
 use super::Bindings;
 use crate::shared::{
+    self,
     conversion::{FromBindgen, IntoBindgen},
     implementation::message,
     wit,
@@ -39,6 +42,7 @@ impl wit::client_message::Host for Bindings {
 
         match target {
             Target::ServerUnreliable | Target::ServerReliable => {
+                shared::ensure_permitted(world, module_id, "network", |p| p.network)?;
                 let connection = world
                     .resource(game_client())
                     .as_ref()
@@ -64,12 +68,13 @@ impl wit::client_message::Host for Bindings {
 }
 impl wit::client_player::Host for Bindings {
     fn get_local(&mut self) -> anyhow::Result<wit::types::EntityId> {
-        crate::shared::implementation::player::get_by_user_id(
-            self.world(),
-            self.world().resource(local_user_id()).clone(),
-        )
-        .transpose()
-        .unwrap()
+        let world = self.world();
+        let user_id = world.resource(local_user_id());
+        // Spectators don't have a player entity, so they get their spectator entity instead
+        let id = get_by_user_id(world, user_id)
+            .or_else(|| get_spectator_by_user_id(world, user_id))
+            .context("The local player entity does not exist")?;
+        Ok(id.into_bindgen())
     }
 }
 impl wit::client_input::Host for Bindings {
@@ -99,29 +104,13 @@ impl wit::client_input::Host for Bindings {
     }
 
     fn set_cursor_visible(&mut self, visible: bool) -> anyhow::Result<()> {
-        Ok(self
-            .world()
-            .resource(ambient_core::window::window_ctl())
-            .send(ambient_core::window::WindowCtl::ShowCursor(visible))?)
+        self.world_mut().add_resource(cursor_visible(), visible);
+        Ok(())
     }
 
     fn set_cursor_lock(&mut self, lock: bool) -> anyhow::Result<()> {
-        let grab_mode = if lock {
-            if cfg!(target_os = "windows") || cfg!(target_os = "linux") {
-                CursorGrabMode::Confined
-            } else if cfg!(target_os = "macos") {
-                CursorGrabMode::Locked
-            } else {
-                anyhow::bail!("Unsupported platform for cursor lock.")
-            }
-        } else {
-            CursorGrabMode::None
-        };
-
-        Ok(self
-            .world()
-            .resource(ambient_core::window::window_ctl())
-            .send(ambient_core::window::WindowCtl::GrabCursor(grab_mode))?)
+        self.world_mut().add_resource(cursor_locked(), lock);
+        Ok(())
     }
 }
 impl wit::client_camera::Host for Bindings {
@@ -177,6 +166,7 @@ impl wit::client_camera::Host for Bindings {
 impl wit::client_audio::Host for Bindings {
     fn load(&mut self, url: String) -> anyhow::Result<()> {
         let world = self.world();
+        shared::ensure_url_permitted(world, self.id, &url)?;
         let assets = world.resource(asset_cache());
         let audio_url = AudioFromUrl {
             url: AbsAssetUrl::parse(url)?,
@@ -187,6 +177,7 @@ impl wit::client_audio::Host for Bindings {
 
     fn play(&mut self, url: String, looping: bool, volume: f32, uid: u32) -> anyhow::Result<()> {
         let world = self.world();
+        shared::ensure_url_permitted(world, self.id, &url)?;
         let assets = world.resource(asset_cache()).clone();
         let runtime = world.resource(runtime()).clone();
         let async_run = world.resource(async_run()).clone();
@@ -196,9 +187,10 @@ impl wit::client_audio::Host for Bindings {
             async_run.run(move |world| {
                 match track {
                     Ok(track) => {
+                        let bus = sound_bus(world, &url.to_string());
                         let sender = world.resource(audio_sender());
                         sender
-                            .send(AudioMessage::Track(track, looping, volume, url, uid))
+                            .send(AudioMessage::Track(track, looping, volume, url, uid, bus))
                             .unwrap();
                     }
                     Err(e) => log::error!("{e:?}"),
@@ -1,6 +1,18 @@
-use crate::shared::{self, client_bytecode_from_url, module_bytecode, ModuleBytecode};
-use ambient_core::{asset_cache, async_ecs::async_run, runtime};
-use ambient_ecs::{query, EntityId, SystemGroup, World};
+use crate::shared::{
+    self, client_bytecode_from_url, message::RuntimeMessageExt, module_bytecode, ModuleBytecode,
+};
+use ambient_core::{
+    asset_cache,
+    async_ecs::async_run,
+    camera::{get_active_camera, projection, projection_view},
+    dtime, frame_index, main_scene,
+    player::local_user_id,
+    runtime,
+    transform::local_to_world,
+    window::{window_logical_size, window_physical_size, window_scale_factor},
+};
+use ambient_ecs::{generated::messages, query, EntityId, FnSystem, SystemGroup, World};
+use ambient_network::client_game_state::RenderEvent;
 use ambient_std::{
     asset_cache::AsyncAssetKeyExt, asset_url::AbsAssetUrl, download_asset::BytesFromUrl,
 };
@@ -12,16 +24,28 @@ mod network;
 pub fn initialize(
     world: &mut World,
     messenger: Arc<dyn Fn(&World, EntityId, shared::MessageType, &str) + Send + Sync>,
-) -> anyhow::Result<()> {
+) {
     shared::initialize(world, messenger, |id| Bindings {
         base: Default::default(),
         world_ref: Default::default(),
         id,
-    })?;
+    });
 
     network::initialize(world);
+    world.add_resource(shared::module_log::module_logs(), Default::default());
+    world.add_resource(
+        shared::clipboard::clipboard_permissions(),
+        Default::default(),
+    );
+    world.add_resource(shared::clipboard::clipboard_requests(), Default::default());
+}
 
-    Ok(())
+/// Keeps a log of the client module `id` in `module_logs`, where it is shown in the module console of the debugger.
+pub fn record_module_log(world: &World, id: EntityId, type_: shared::MessageType, message: &str) {
+    shared::module_log::push_module_log(
+        world,
+        shared::module_log::ModuleLog::new(world, id, false, type_, message),
+    );
 }
 pub fn systems() -> SystemGroup {
     SystemGroup::new(
@@ -58,11 +82,86 @@ pub fn systems() -> SystemGroup {
                     });
                 }
             }),
+            Box::new(FnSystem::new({
+                let mut last_size = None;
+                move |world, _| {
+                    let (Some(physical_size), Some(logical_size), Some(scale_factor)) = (
+                        world.resource_opt(window_physical_size()).copied(),
+                        world.resource_opt(window_logical_size()).copied(),
+                        world.resource_opt(window_scale_factor()).copied(),
+                    ) else {
+                        return;
+                    };
+                    let size = Some((physical_size, logical_size, scale_factor));
+                    if last_size == size {
+                        return;
+                    }
+                    // The modules can read the initial size from the resources
+                    let is_initial = last_size.is_none();
+                    last_size = size;
+                    if is_initial {
+                        return;
+                    }
+                    messages::WindowResize {
+                        physical_size,
+                        logical_size,
+                        scale_factor: scale_factor as f32,
+                    }
+                    .run(world, None)
+                    .unwrap();
+                }
+            })),
+            Box::new(shared::clipboard::systems()),
             Box::new(shared::systems()),
         ],
     )
 }
 
+/// Sends the frame lifecycle messages to the client modules around rendering each frame.
+pub fn render_systems() -> SystemGroup<RenderEvent> {
+    SystemGroup::new(
+        "core/wasm/client/render",
+        vec![Box::new(FnSystem::new(|world, event| {
+            ambient_profiling::scope!("WASM module render events");
+            let time = ambient_app::get_time_since_app_start(world).as_secs_f32();
+            let delta_time = *world.resource(dtime());
+            let frame = *world.resource(frame_index()) as u64;
+            match event {
+                RenderEvent::PreRender => {
+                    let camera =
+                        get_active_camera(world, main_scene(), world.resource_opt(local_user_id()));
+                    let matrix = |component| {
+                        camera
+                            .and_then(|camera| world.get(camera, component).ok())
+                            .unwrap_or_default()
+                    };
+                    messages::ClientPreRender {
+                        camera: camera.unwrap_or_else(EntityId::null),
+                        view: matrix(local_to_world()).inverse(),
+                        projection: matrix(projection()),
+                        projection_view: matrix(projection_view()),
+                        time,
+                        delta_time,
+                        frame,
+                    }
+                    .run(world, None)
+                    .unwrap();
+                }
+                RenderEvent::PostRender { render_time } => {
+                    messages::ClientPostRender {
+                        time,
+                        delta_time,
+                        frame,
+                        render_time: render_time.as_secs_f32(),
+                    }
+                    .run(world, None)
+                    .unwrap();
+                }
+            }
+        }))],
+    )
+}
+
 #[derive(Clone)]
 struct Bindings {
     base: shared::bindings::BindingsBase,
@@ -76,6 +175,17 @@ impl Bindings {
     pub fn world_mut(&mut self) -> &mut World {
         unsafe { self.world_ref.world_mut() }
     }
+    /// See [shared::ensure_can_modify]
+    fn ensure_can_modify(&self, entity: EntityId) -> anyhow::Result<()> {
+        shared::ensure_can_modify(self.world(), self.id, &self.base.spawned_entities, entity)
+    }
+    /// See [shared::implementation::component::ensure_urls_permitted]
+    fn ensure_urls_permitted<'a>(
+        &self,
+        data: impl IntoIterator<Item = (u32, &'a shared::wit::component::Value)>,
+    ) -> anyhow::Result<()> {
+        shared::implementation::component::ensure_urls_permitted(self.world(), self.id, data)
+    }
 }
 
 impl shared::bindings::BindingsBound for Bindings {
@@ -1,7 +1,9 @@
+use ambient_core::{async_ecs::async_run, runtime};
 use ambient_ecs::World;
 use ambient_network::{
     client::{bi_stream_handlers, datagram_handlers, uni_stream_handlers, DynRecv, DynSend},
-    log_network_result, WASM_BISTREAM_ID, WASM_DATAGRAM_ID, WASM_UNISTREAM_ID,
+    log_network_result, MODULE_LOG_UNISTREAM_ID, WASM_BISTREAM_ID, WASM_DATAGRAM_ID,
+    WASM_UNISTREAM_ID,
 };
 use ambient_std::asset_cache::AssetCache;
 
@@ -9,7 +11,10 @@ use bytes::Bytes;
 
 use std::sync::Arc;
 
-use crate::shared::implementation::message;
+use crate::shared::{
+    implementation::message,
+    module_log::{push_module_log, ModuleLog},
+};
 
 pub fn initialize(world: &mut World) {
     world.resource_mut(datagram_handlers()).insert(
@@ -26,6 +31,11 @@ pub fn initialize(world: &mut World) {
         WASM_UNISTREAM_ID,
         ("client_wasm_uni_stream", Arc::new(on_unistream)),
     );
+
+    world.resource_mut(uni_stream_handlers()).insert(
+        MODULE_LOG_UNISTREAM_ID,
+        ("client_module_log_uni_stream", Arc::new(on_module_log)),
+    );
 }
 
 fn on_datagram(world: &mut World, _asset_cache: AssetCache, bytes: Bytes) {
@@ -44,3 +54,18 @@ fn on_bistream(
 fn on_unistream(world: &mut World, _asset_cache: AssetCache, recv_stream: DynRecv) {
     message::on_unistream(world, None, recv_stream)
 }
+
+/// Receives the logs of the server modules, which the server forwards on one stream so that they arrive in order
+fn on_module_log(world: &mut World, _asset_cache: AssetCache, mut recv_stream: DynRecv) {
+    let async_run = world.resource(async_run()).clone();
+    world.resource(runtime()).spawn(async move {
+        let result = async {
+            while let Some(module_log) = ModuleLog::read_frame(&mut recv_stream).await? {
+                async_run.run(move |world| push_module_log(world, module_log));
+            }
+            anyhow::Ok(())
+        }
+        .await;
+        log_network_result!(result);
+    });
+}
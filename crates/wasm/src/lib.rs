@@ -7,5 +7,9 @@ pub(crate) static WASMTIME_ENGINE: once_cell::sync::Lazy<wasmtime::Engine> =
         let mut config = wasmtime::Config::new();
         config.wasm_backtrace_details(wasmtime::WasmBacktraceDetails::Enable);
         config.wasm_component_model(true);
+        // Fuel is only metered for the runtime statistics and the hardened mode, which are enabled before any module
+        // is loaded
+        config
+            .consume_fuel(ambient_std::metrics::is_enabled() || ambient_std::sandbox::is_enabled());
         wasmtime::Engine::new(&config).unwrap()
     });
@@ -1,7 +1,7 @@
 //! Used to implement all the *shared* host functions on the server.
 //!
 //! If implementing a trait that is only available on the server, it should go in [specific].
-use crate::shared::{self, wit};
+use crate::shared::{self, conversion::FromBindgen, wit};
 
 use super::Bindings;
 
@@ -11,6 +11,7 @@ mod unused;
 impl wit::types::Host for Bindings {}
 impl wit::entity::Host for Bindings {
     fn spawn(&mut self, data: wit::entity::EntityData) -> anyhow::Result<wit::types::EntityId> {
+        self.ensure_urls_permitted(data.iter().map(|(index, value)| (*index, value)))?;
         shared::implementation::entity::spawn(
             unsafe { self.world_ref.world_mut() },
             &mut self.base.spawned_entities,
@@ -22,6 +23,7 @@ impl wit::entity::Host for Bindings {
         &mut self,
         entity: wit::types::EntityId,
     ) -> anyhow::Result<Option<wit::entity::EntityData>> {
+        self.ensure_can_modify(entity.from_bindgen())?;
         shared::implementation::entity::despawn(
             unsafe { self.world_ref.world_mut() },
             &mut self.base.spawned_entities,
@@ -34,6 +36,7 @@ impl wit::entity::Host for Bindings {
         entity: wit::types::EntityId,
         animation_controller: wit::entity::AnimationController,
     ) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
         shared::implementation::entity::set_animation_controller(
             self.world_mut(),
             entity,
@@ -48,6 +51,7 @@ impl wit::entity::Host for Bindings {
         times: Vec<f32>,
         absolute_time: bool,
     ) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
         shared::implementation::entity::set_animation_blend(
             self.world_mut(),
             entity,
@@ -62,6 +66,7 @@ impl wit::entity::Host for Bindings {
         entity: wit::types::EntityId,
         stack: Vec<wit::entity::AnimationActionStack>,
     ) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
         shared::implementation::entity::set_animation_action_stack(self.world_mut(), entity, stack)
     }
 
@@ -70,6 +75,7 @@ impl wit::entity::Host for Bindings {
         entity: wit::types::EntityId,
         mask: Vec<String>,
     ) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
         shared::implementation::entity::set_animation_binder_mask(self.world_mut(), entity, mask)
     }
 
@@ -79,6 +85,7 @@ impl wit::entity::Host for Bindings {
         index: u32,
         mask: Vec<f32>,
     ) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
         shared::implementation::entity::set_animation_binder_weights(
             self.world_mut(),
             entity,
@@ -157,6 +164,8 @@ impl wit::component::Host for Bindings {
         index: u32,
         value: wit::component::Value,
     ) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
+        self.ensure_urls_permitted([(index, &value)])?;
         shared::implementation::component::add_component(self.world_mut(), entity, index, value)
     }
 
@@ -165,6 +174,8 @@ impl wit::component::Host for Bindings {
         entity: wit::types::EntityId,
         data: wit::entity::EntityData,
     ) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
+        self.ensure_urls_permitted(data.iter().map(|(index, value)| (*index, value)))?;
         shared::implementation::component::add_components(self.world_mut(), entity, data)
     }
 
@@ -174,6 +185,8 @@ impl wit::component::Host for Bindings {
         index: u32,
         value: wit::component::Value,
     ) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
+        self.ensure_urls_permitted([(index, &value)])?;
         shared::implementation::component::set_component(self.world_mut(), entity, index, value)
     }
 
@@ -182,6 +195,8 @@ impl wit::component::Host for Bindings {
         entity: wit::types::EntityId,
         data: wit::entity::EntityData,
     ) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
+        self.ensure_urls_permitted(data.iter().map(|(index, value)| (*index, value)))?;
         shared::implementation::component::set_components(self.world_mut(), entity, data)
     }
 
@@ -198,6 +213,7 @@ impl wit::component::Host for Bindings {
     }
 
     fn remove_component(&mut self, entity: wit::types::EntityId, index: u32) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
         shared::implementation::component::remove_component(self.world_mut(), entity, index)
     }
 
@@ -206,6 +222,7 @@ impl wit::component::Host for Bindings {
         entity: wit::types::EntityId,
         components: Vec<u32>,
     ) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
         shared::implementation::component::remove_components(self.world_mut(), entity, components)
     }
 
@@ -260,13 +277,12 @@ impl wit::asset::Host for Bindings {
 
 impl wit::world_audio::Host for Bindings {
     fn set_listener(&mut self, entity: wit::types::EntityId) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
         shared::implementation::world_audio::set_listener(self.world_mut(), entity)
     }
 
-    fn set_emitter(
-        &mut self,
-        entity: wit::types::EntityId,
-    ) -> anyhow::Result<()> {
+    fn set_emitter(&mut self, entity: wit::types::EntityId) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
         shared::implementation::world_audio::set_emitter(self.world_mut(), entity)
     }
     fn play_sound_on_entity(
@@ -274,6 +290,8 @@ impl wit::world_audio::Host for Bindings {
         sound: String,
         emitter: wit::types::EntityId,
     ) -> anyhow::Result<()> {
+        self.ensure_can_modify(emitter.from_bindgen())?;
+        shared::ensure_url_permitted(self.world(), self.id, &sound)?;
         shared::implementation::world_audio::play_sound_on_entity(self.world_mut(), sound, emitter)
     }
 }
@@ -12,6 +12,7 @@ use physxx::{PxControllerCollisionFlag, PxControllerFilters};
 
 use super::super::Bindings;
 use crate::shared::{
+    self,
     conversion::{FromBindgen, IntoBindgen},
     implementation::message,
     wit,
@@ -23,6 +24,7 @@ impl wit::server_physics::Host for Bindings {
         entity: wit::types::EntityId,
         force: wit::types::Vec3,
     ) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
         let _ = ambient_physics::helpers::add_force(
             self.world_mut(),
             entity.from_bindgen(),
@@ -37,6 +39,7 @@ impl wit::server_physics::Host for Bindings {
         entity: wit::types::EntityId,
         force: wit::types::Vec3,
     ) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
         let _ = ambient_physics::helpers::add_force(
             self.world_mut(),
             entity.from_bindgen(),
@@ -53,6 +56,7 @@ impl wit::server_physics::Host for Bindings {
         radius: f32,
         falloff_radius: Option<f32>,
     ) -> anyhow::Result<()> {
+        shared::ensure_unrestricted(self.world(), self.id, "apply radial impulses")?;
         let position = position.from_bindgen();
         ambient_physics::helpers::PhysicsObjectCollection::from_radius(
             self.world_mut(),
@@ -69,6 +73,7 @@ impl wit::server_physics::Host for Bindings {
         force: wit::types::Vec3,
         position: wit::types::Vec3,
     ) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
         let _ = ambient_physics::helpers::add_force_at_position(
             self.world_mut(),
             entity.from_bindgen(),
@@ -85,6 +90,7 @@ impl wit::server_physics::Host for Bindings {
         force: wit::types::Vec3,
         position: wit::types::Vec3,
     ) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
         let _ = ambient_physics::helpers::add_force_at_position(
             self.world_mut(),
             entity.from_bindgen(),
@@ -112,6 +118,7 @@ impl wit::server_physics::Host for Bindings {
     }
 
     fn set_gravity(&mut self, gravity: wit::types::Vec3) -> anyhow::Result<()> {
+        shared::ensure_unrestricted(self.world(), self.id, "change the gravity")?;
         self.world_mut()
             .resource(ambient_physics::main_physics_scene())
             .set_gravity(gravity.from_bindgen());
@@ -119,6 +126,7 @@ impl wit::server_physics::Host for Bindings {
     }
 
     fn unfreeze(&mut self, entity: wit::types::EntityId) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
         ambient_physics::helpers::convert_rigid_static_to_dynamic(
             self.world_mut(),
             entity.from_bindgen(),
@@ -127,6 +135,7 @@ impl wit::server_physics::Host for Bindings {
     }
 
     fn freeze(&mut self, entity: wit::types::EntityId) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
         ambient_physics::helpers::convert_rigid_dynamic_to_static(
             self.world_mut(),
             entity.from_bindgen(),
@@ -135,6 +144,7 @@ impl wit::server_physics::Host for Bindings {
     }
 
     fn start_motor(&mut self, entity: wit::types::EntityId, velocity: f32) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
         let joint = ambient_physics::helpers::get_entity_revolute_joint(
             self.world_mut(),
             entity.from_bindgen(),
@@ -147,6 +157,7 @@ impl wit::server_physics::Host for Bindings {
     }
 
     fn stop_motor(&mut self, entity: wit::types::EntityId) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
         let joint = ambient_physics::helpers::get_entity_revolute_joint(
             self.world_mut(),
             entity.from_bindgen(),
@@ -164,6 +175,8 @@ impl wit::server_physics::Host for Bindings {
         entity1: wit::types::EntityId,
         transform1: wit::types::Mat4,
     ) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity0.from_bindgen())?;
+        self.ensure_can_modify(entity1.from_bindgen())?;
         ambient_physics::helpers::create_revolute_joint(
             self.world_mut(),
             entity0.from_bindgen(),
@@ -210,6 +223,7 @@ impl wit::server_physics::Host for Bindings {
         min_dist: f32,
         elapsed_time: f32,
     ) -> anyhow::Result<wit::server_physics::CharacterCollision> {
+        self.ensure_can_modify(entity.from_bindgen())?;
         match self
             .world()
             .get(entity.from_bindgen(), character_controller())
@@ -241,6 +255,7 @@ impl wit::server_physics::Host for Bindings {
         entity: wit::types::EntityId,
         position: wit::types::Vec3,
     ) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
         self
             .world()
             .get(entity.from_bindgen(), character_controller())?.set_position(position.from_bindgen().as_dvec3());
@@ -253,6 +268,7 @@ impl wit::server_physics::Host for Bindings {
         entity: wit::types::EntityId,
         position: wit::types::Vec3,
     ) -> anyhow::Result<()> {
+        self.ensure_can_modify(entity.from_bindgen())?;
         self
             .world()
             .get(entity.from_bindgen(), character_controller())?.set_foot_position(position.from_bindgen().as_dvec3());
@@ -271,6 +287,9 @@ impl wit::server_message::Host for Bindings {
         use wit::server_message::Target;
         let module_id = self.id;
         let world = self.world_mut();
+        if !matches!(target, Target::LocalBroadcast | Target::Local(_)) {
+            shared::ensure_permitted(world, module_id, "network", |p| p.network)?;
+        }
 
         match target {
             Target::ClientBroadcastUnreliable => {
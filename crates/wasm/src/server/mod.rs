@@ -1,34 +1,111 @@
-use crate::shared;
-use ambient_ecs::{query, EntityId, FnSystem, SystemGroup, World};
-use ambient_network::server::{ForkingEvent, ShutdownEvent};
+use crate::shared::{self, message::RuntimeMessageExt};
+use ambient_core::player::{player_suspended, user_id};
+use ambient_ecs::{
+    generated::{components::core::instance::instance_modules, messages},
+    query, EntityId, FnSystem, SystemGroup, World,
+};
+use ambient_network::{
+    ownership::{self, client_authority_proposal},
+    server::{ForkingEvent, ShutdownEvent},
+};
 use std::sync::Arc;
 
 mod implementation;
+pub mod mods;
 mod network;
 
 pub fn initialize(
     world: &mut World,
     messenger: Arc<dyn Fn(&World, EntityId, shared::MessageType, &str) + Send + Sync>,
-) -> anyhow::Result<()> {
+) {
     shared::initialize(world, messenger, |id| Bindings {
         base: Default::default(),
         world_ref: Default::default(),
         id,
-    })?;
+    });
 
     network::initialize(world);
+}
 
-    Ok(())
+/// Forwards a log of the server module `id` to the clients of the players with `module_log_receiver`, or to all clients
+/// if `to_all` is set, where it is shown in the module console of the debugger.
+pub fn forward_module_log(
+    world: &World,
+    id: EntityId,
+    type_: shared::MessageType,
+    message: &str,
+    to_all: bool,
+) {
+    network::forward_module_log(
+        world,
+        &shared::module_log::ModuleLog::new(world, id, true, type_, message),
+        to_all,
+    );
 }
 
 pub fn systems() -> SystemGroup {
-    shared::systems()
+    SystemGroup::new(
+        "core/wasm/server",
+        vec![
+            Box::new(shared::systems()),
+            query(user_id())
+                .incl(player_suspended())
+                .spawned()
+                .to_system(|q, world, qs, _| {
+                    for (player, user_id) in q.collect_cloned(world, qs) {
+                        messages::PlayerSuspended { player, user_id }
+                            .run(world, None)
+                            .unwrap();
+                    }
+                }),
+            query(())
+                .incl(player_suspended())
+                .despawned()
+                .to_system(|q, world, qs, _| {
+                    for (player, _) in q.collect_cloned(world, qs) {
+                        // Players that didn't reconnect in time are despawned instead
+                        let Ok(user_id) = world.get_cloned(player, user_id()) else {
+                            continue;
+                        };
+                        messages::PlayerResumed { player, user_id }
+                            .run(world, None)
+                            .unwrap();
+                    }
+                }),
+            query((client_authority_proposal(), user_id()))
+                .spawned()
+                .to_system(|q, world, qs, _| {
+                    for (proposal, (entity, user_id)) in q.collect_cloned(world, qs) {
+                        let Some(component) = ownership::proposed_component(world, proposal) else {
+                            continue;
+                        };
+                        messages::ClientAuthorityUpdate {
+                            proposal,
+                            entity,
+                            user_id,
+                            component: component.path(),
+                        }
+                        .run(world, None)
+                        .unwrap();
+                    }
+                }),
+        ],
+    )
 }
 
 pub fn on_forking_systems() -> SystemGroup<ForkingEvent> {
     SystemGroup::new(
         "core/wasm/server/on_forking_systems",
         vec![Box::new(FnSystem::new(move |world, _| {
+            // Instances created with a set of modules only run those.
+            if let Some(modules) = world.resource_opt(instance_modules()).cloned() {
+                for id in query(()).incl(shared::module()).collect_ids(world, None) {
+                    let name = shared::get_module_name(world, id).to_string();
+                    world
+                        .set_if_changed(id, shared::module_enabled(), modules.contains(&name))
+                        .unwrap();
+                }
+            }
             // Reset the states of all the modules when we fork.
             shared::reload_all(world);
         }))],
@@ -60,6 +137,17 @@ impl Bindings {
     pub fn world_mut(&mut self) -> &mut World {
         unsafe { self.world_ref.world_mut() }
     }
+    /// See [shared::ensure_can_modify]
+    fn ensure_can_modify(&self, entity: EntityId) -> anyhow::Result<()> {
+        shared::ensure_can_modify(self.world(), self.id, &self.base.spawned_entities, entity)
+    }
+    /// See [shared::implementation::component::ensure_urls_permitted]
+    fn ensure_urls_permitted<'a>(
+        &self,
+        data: impl IntoIterator<Item = (u32, &'a shared::wit::component::Value)>,
+    ) -> anyhow::Result<()> {
+        shared::implementation::component::ensure_urls_permitted(self.world(), self.id, data)
+    }
 }
 
 impl shared::bindings::BindingsBound for Bindings {
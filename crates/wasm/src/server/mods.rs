@@ -0,0 +1,50 @@
+//! Mods are loaded as modules with a [mod_id](shared::mod_id), which are enabled and disabled together.
+
+use std::collections::{BTreeMap, HashSet};
+
+use ambient_ecs::{query, World};
+use itertools::Itertools;
+
+use crate::shared;
+
+/// The IDs of the mods that were loaded, with whether they are enabled
+pub fn list(world: &World) -> BTreeMap<String, bool> {
+    let mut mods = BTreeMap::new();
+    for (_, (id, enabled)) in query((shared::mod_id(), shared::module_enabled())).iter(world, None)
+    {
+        *mods.entry(id.clone()).or_default() |= *enabled;
+    }
+    mods
+}
+
+/// Enables or disables the modules of the mod `id`. Enabling a mod enables the mods it depends on too, and disabling
+/// it disables the mods that depend on it.
+pub fn set_enabled(world: &mut World, id: &str, enabled: bool) {
+    let modules = query((shared::mod_id(), shared::mod_dependencies()))
+        .iter(world, None)
+        .map(|(module, (id, dependencies))| (module, id.clone(), dependencies.clone()))
+        .collect_vec();
+
+    let mut affected = HashSet::new();
+    let mut pending = vec![id.to_string()];
+    while let Some(id) = pending.pop() {
+        if !affected.insert(id.clone()) {
+            continue;
+        }
+        for (_, mod_id, dependencies) in &modules {
+            if enabled && *mod_id == id {
+                pending.extend(dependencies.iter().cloned());
+            } else if !enabled && dependencies.contains(&id) {
+                pending.push(mod_id.clone());
+            }
+        }
+    }
+
+    for (module, mod_id, _) in modules {
+        if affected.contains(&mod_id) {
+            world
+                .set_if_changed(module, shared::module_enabled(), enabled)
+                .unwrap();
+        }
+    }
+}
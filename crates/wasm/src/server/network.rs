@@ -1,19 +1,32 @@
-use ambient_ecs::World;
+use ambient_core::{
+    player::{module_log_receiver, player},
+    runtime,
+};
+use ambient_ecs::{query, World};
 use ambient_network::{
-    client::{DynRecv, DynSend},
+    client::{ClientConnection, DynRecv, DynSend},
     log_network_result,
-    server::{bi_stream_handlers, datagram_handlers, uni_stream_handlers, SharedServerState},
-    WASM_BISTREAM_ID, WASM_DATAGRAM_ID, WASM_UNISTREAM_ID,
+    server::{
+        bi_stream_handlers, datagram_handlers, player_connection, uni_stream_handlers,
+        SharedServerState,
+    },
+    MODULE_LOG_UNISTREAM_ID, WASM_BISTREAM_ID, WASM_DATAGRAM_ID, WASM_UNISTREAM_ID,
 };
 use ambient_std::asset_cache::AssetCache;
 
 use bytes::Bytes;
+use tokio::io::AsyncWriteExt;
 
 use std::sync::Arc;
 
-use crate::shared::implementation::message;
+use crate::shared::{
+    implementation::message,
+    module_log::{module_log_streams, ModuleLog},
+};
 
 pub fn initialize(world: &mut World) {
+    world.add_resource(module_log_streams(), Default::default());
+
     world.resource_mut(datagram_handlers()).insert(
         WASM_DATAGRAM_ID,
         ("server_wasm_datagram", Arc::new(on_datagram)),
@@ -67,3 +80,52 @@ fn on_unistream(
 
     message::on_unistream(world, Some(user_id.to_owned()), recv_stream)
 }
+
+/// Forwards a server module's log to the clients of the players with `module_log_receiver`, or to all clients if
+/// `to_all` is set. The logs are written to one stream per player, so that they arrive in order.
+pub(super) fn forward_module_log(world: &World, module_log: &ModuleLog, to_all: bool) {
+    let frame = match module_log.to_frame() {
+        Ok(frame) => frame,
+        Err(err) => {
+            log::warn!("Failed to encode module log: {err:?}");
+            return;
+        }
+    };
+    let Some(streams) = world.resource_opt(module_log_streams()) else {
+        return;
+    };
+    let mut streams = streams.lock();
+    // The streams of players that left, or whose connection was lost, are closed
+    streams.retain(|id, stream| world.exists(*id) && !stream.is_disconnected());
+
+    for (id, connection) in query(player_connection())
+        .incl(player())
+        .iter(world, None)
+        .filter(|(id, _)| to_all || world.has_component(*id, module_log_receiver()))
+    {
+        let stream = streams
+            .entry(id)
+            .or_insert_with(|| open_module_log_stream(world, connection.clone()));
+        stream.send(frame.clone()).ok();
+    }
+}
+
+/// Opens the stream that the module logs sent to the returned sender are forwarded to `connection` on
+fn open_module_log_stream(
+    world: &World,
+    connection: Arc<dyn ClientConnection>,
+) -> flume::Sender<Bytes> {
+    let (sender, receiver) = flume::unbounded::<Bytes>();
+    world.resource(runtime()).spawn(async move {
+        let result = async {
+            let mut stream = connection.open_uni_stream(MODULE_LOG_UNISTREAM_ID).await?;
+            while let Ok(frame) = receiver.recv_async().await {
+                stream.write_all(&frame).await?;
+            }
+            anyhow::Ok(())
+        }
+        .await;
+        log_network_result!(result);
+    });
+    sender
+}
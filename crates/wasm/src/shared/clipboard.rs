@@ -0,0 +1,149 @@
+//! Clipboard access for client modules. A module asks for it by attaching `clipboard_write` or `clipboard_read` to the
+//! resources entity during a call, and the user is asked once per module whether it may access the clipboard; the
+//! requests of the module wait until they answer.
+
+use std::collections::HashMap;
+
+use ambient_ecs::{
+    components,
+    generated::{
+        components::core::clipboard::{clipboard_read, clipboard_write},
+        messages,
+    },
+    Debuggable, Description, EntityId, FnSystem, Resource, SystemGroup, World,
+};
+
+use super::{ensure_permitted, message::RuntimeMessageExt, messenger, module_state, MessageType};
+
+components!("wasm::clipboard", {
+    @[Debuggable, Resource, Description["Whether the user allowed each client module to access the clipboard."]]
+    clipboard_permissions: HashMap<EntityId, bool>,
+    @[Debuggable, Resource]
+    clipboard_requests: Vec<ClipboardRequest>,
+});
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClipboardRequest {
+    Write { module: EntityId, text: String },
+    Read { module: EntityId },
+}
+impl ClipboardRequest {
+    pub fn module(&self) -> EntityId {
+        match self {
+            Self::Write { module, .. } | Self::Read { module } => *module,
+        }
+    }
+}
+
+/// Collects the clipboard requests the module `module_id` attached to the resources entity during the last call.
+pub(crate) fn collect_requests(world: &mut World, module_id: EntityId) {
+    let resources = world.resource_entity();
+    let write = world.get_cloned(resources, clipboard_write()).ok();
+    let read = world.has_component(resources, clipboard_read());
+    if write.is_none() && !read {
+        return;
+    }
+    world.remove_component(resources, clipboard_write()).ok();
+    world.remove_component(resources, clipboard_read()).ok();
+
+    // Only the client has a clipboard
+    if world.resource_opt(clipboard_requests()).is_none() {
+        let messenger = world.resource(messenger()).clone();
+        messenger(
+            world,
+            module_id,
+            MessageType::Warn,
+            "The clipboard can only be accessed by client modules",
+        );
+        return;
+    }
+    if let Err(err) = ensure_permitted(world, module_id, "clipboard", |p| p.clipboard) {
+        let messenger = world.resource(messenger()).clone();
+        messenger(world, module_id, MessageType::Warn, &err.to_string());
+        return;
+    }
+    let requests = world.resource_mut(clipboard_requests());
+    if let Some(text) = write {
+        requests.push(ClipboardRequest::Write {
+            module: module_id,
+            text,
+        });
+    }
+    if read {
+        requests.push(ClipboardRequest::Read { module: module_id });
+    }
+}
+
+/// The module that is waiting for the user to allow or deny it access to the clipboard, if any.
+pub fn pending_permission(world: &World) -> Option<EntityId> {
+    let permissions = world.resource_opt(clipboard_permissions())?;
+    world
+        .resource_opt(clipboard_requests())?
+        .iter()
+        .map(|request| request.module())
+        .find(|module| !permissions.contains_key(module))
+}
+
+/// Remembers whether the user allows the module `module` to access the clipboard, which lets its requests through.
+pub fn set_permission(world: &mut World, module: EntityId, allowed: bool) {
+    world
+        .resource_mut(clipboard_permissions())
+        .insert(module, allowed);
+}
+
+pub(crate) fn systems() -> SystemGroup {
+    SystemGroup::new(
+        "core/wasm/clipboard",
+        vec![Box::new(FnSystem::new(|world, _| {
+            ambient_profiling::scope!("WASM module clipboard requests");
+            let Some(requests) = world.resource_mut_opt(clipboard_requests()) else {
+                return;
+            };
+            let requests = std::mem::take(requests);
+            let mut waiting = Vec::new();
+            for request in requests {
+                let module = request.module();
+                // The requests of unloaded modules are dropped
+                if !world.has_component(module, module_state()) {
+                    continue;
+                }
+                let allowed = world
+                    .resource(clipboard_permissions())
+                    .get(&module)
+                    .copied();
+                match allowed {
+                    Some(allowed) => run_request(world, request, allowed),
+                    None => waiting.push(request),
+                }
+            }
+            world.resource_mut(clipboard_requests()).extend(waiting);
+        }))],
+    )
+}
+
+fn run_request(world: &mut World, request: ClipboardRequest, allowed: bool) {
+    match request {
+        ClipboardRequest::Write { text, .. } => {
+            if !allowed {
+                return;
+            }
+            if let Err(err) =
+                arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text))
+            {
+                log::warn!("Failed to write to the clipboard: {err}");
+            }
+        }
+        ClipboardRequest::Read { module } => {
+            let text = if allowed {
+                arboard::Clipboard::new()
+                    .and_then(|mut clipboard| clipboard.get_text())
+                    .ok()
+            } else {
+                None
+            };
+            messages::ClipboardText::new(text)
+                .run(world, Some(module))
+                .unwrap();
+        }
+    }
+}
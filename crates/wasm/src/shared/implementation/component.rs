@@ -1,12 +1,14 @@
 use ambient_ecs::{
-    with_component_registry, Component, ComponentDesc, ComponentEntry, ComponentSet,
+    with_component_registry, AssetUrl, Component, ComponentDesc, ComponentEntry, ComponentSet,
     ComponentValue, Entity, EntityId, PrimitiveComponentType as PCT, QueryEvent, QueryState, World,
 };
 use ambient_shared_types::primitive_component_definitions;
+use ambient_std::sandbox;
 use anyhow::Context;
 use glam::{Mat4, Quat, UVec2, UVec3, UVec4, Vec2, Vec3, Vec4};
 use itertools::Itertools;
 use paste::paste;
+use serde::Serialize;
 use slotmap::Key;
 
 use crate::shared::bindings::QueryStateMap;
@@ -127,7 +129,7 @@ macro_rules! define_component_types {
         // todo: find a nice efficient abstraction to tie these three functions together
         pub(crate) fn convert_components_to_entity_data(
             components: wit::entity::EntityData,
-        ) -> Entity {
+        ) -> anyhow::Result<Entity> {
             use wit::component::{VecValue as VV, OptionValue as OV, Value as V};
             with_component_registry(|cr| {
                 components
@@ -138,9 +140,9 @@ macro_rules! define_component_types {
 
                         match (primitive_component.ty, value) {
                             $(
-                            (PCT::$value, V::[<Type $value>](v))                              => Some(ComponentEntry::from_raw_parts(c, v.from_bindgen())),
-                            (PCT::[<Vec $value>], V::TypeVec(VV::[<Type $value>](v)))      => Some(ComponentEntry::from_raw_parts(c, v.from_bindgen())),
-                            (PCT::[<Option $value>], V::TypeOption(OV::[<Type $value>](v))) => Some(ComponentEntry::from_raw_parts(c, v.from_bindgen()))
+                            (PCT::$value, V::[<Type $value>](v))                              => Some(checked_entry(c, v.from_bindgen())),
+                            (PCT::[<Vec $value>], V::TypeVec(VV::[<Type $value>](v)))      => Some(checked_entry(c, v.from_bindgen())),
+                            (PCT::[<Option $value>], V::TypeOption(OV::[<Type $value>](v))) => Some(checked_entry(c, v.from_bindgen()))
                             ),*,
                             _ => None,
                         }
@@ -162,17 +164,23 @@ macro_rules! define_component_types {
                 $(
                 V::[<Type $value >](value) => {
                     if let Some(component) = get_component_type::<$type>(index) {
-                        world.add_component(entity_id, component, value.from_bindgen())?;
+                        let value = value.from_bindgen();
+                        ensure_component_size(component.desc(), &value)?;
+                        world.add_component(entity_id, component, value)?;
                     }
                 }
                 V::TypeVec(VV::[<Type $value >](value)) => {
                     if let Some(component) = get_component_type::<Vec<$type>>(index) {
-                        world.add_component(entity_id, component, value.from_bindgen())?;
+                        let value = value.from_bindgen();
+                        ensure_component_size(component.desc(), &value)?;
+                        world.add_component(entity_id, component, value)?;
                     }
                 }
                 V::TypeOption(OV::[<Type $value >](value)) => {
                     if let Some(component) = get_component_type::<Option<$type>>(index) {
-                        world.add_component(entity_id, component, value.from_bindgen())?;
+                        let value = value.from_bindgen();
+                        ensure_component_size(component.desc(), &value)?;
+                        world.add_component(entity_id, component, value)?;
                     }
                 }
                 ) *
@@ -194,17 +202,23 @@ macro_rules! define_component_types {
                 $(
                 V::[<Type $value >](value) => {
                     if let Some(component) = get_component_type::<$type>(index) {
-                        world.set(entity_id, component, value.from_bindgen())?;
+                        let value = value.from_bindgen();
+                        ensure_component_size(component.desc(), &value)?;
+                        world.set(entity_id, component, value)?;
                     }
                 }
                 V::TypeVec(VV::[<Type $value >](value)) => {
                     if let Some(component) = get_component_type::<Vec<$type>>(index) {
-                        world.set(entity_id, component, value.from_bindgen())?;
+                        let value = value.from_bindgen();
+                        ensure_component_size(component.desc(), &value)?;
+                        world.set(entity_id, component, value)?;
                     }
                 }
                 V::TypeOption(OV::[<Type $value >](value)) => {
                     if let Some(component) = get_component_type::<Option<$type>>(index) {
-                        world.set(entity_id, component, value.from_bindgen())?;
+                        let value = value.from_bindgen();
+                        ensure_component_size(component.desc(), &value)?;
+                        world.set(entity_id, component, value)?;
                     }
                 }
                 ) *
@@ -217,6 +231,57 @@ macro_rules! define_component_types {
 
 primitive_component_definitions!(define_component_types);
 
+/// Fails if `value` is larger than the components of `desc` can be in the hardened mode
+fn ensure_component_size<T: Serialize>(desc: ComponentDesc, value: &T) -> anyhow::Result<()> {
+    let Some(limits) = sandbox::limits() else {
+        return Ok(());
+    };
+    let size = bincode::serialized_size(value)?;
+    anyhow::ensure!(
+        size <= limits.max_component_size,
+        "{} would be {size} bytes, but components can be at most {} bytes in the hardened mode",
+        desc.path(),
+        limits.max_component_size
+    );
+    Ok(())
+}
+
+fn checked_entry<T: ComponentValue + Serialize>(
+    desc: ComponentDesc,
+    value: T,
+) -> anyhow::Result<ComponentEntry> {
+    ensure_component_size(desc, &value)?;
+    Ok(ComponentEntry::from_raw_parts(desc, value))
+}
+
+/// Fails if `data` sets a URL component (one with the [AssetUrl] attribute) to assets that the module `module_id` can't
+/// load; see [crate::shared::ensure_url_permitted]
+pub(crate) fn ensure_urls_permitted<'a>(
+    world: &World,
+    module_id: EntityId,
+    data: impl IntoIterator<Item = (u32, &'a wit::component::Value)>,
+) -> anyhow::Result<()> {
+    use wit::component::{OptionValue, Value, VecValue};
+
+    for (index, value) in data {
+        let is_url = with_component_registry(|r| r.get_by_index(index))
+            .map_or(false, |desc| desc.has_attribute::<AssetUrl>());
+        if !is_url {
+            continue;
+        }
+        let urls: &[String] = match value {
+            Value::TypeString(url) => std::slice::from_ref(url),
+            Value::TypeVec(VecValue::TypeString(urls)) => urls,
+            Value::TypeOption(OptionValue::TypeString(Some(url))) => std::slice::from_ref(url),
+            _ => &[],
+        };
+        for url in urls {
+            crate::shared::ensure_url_permitted(world, module_id, url)?;
+        }
+    }
+    Ok(())
+}
+
 pub(crate) fn add_components(
     world: &mut World,
     entity_id: wit::types::EntityId,
@@ -224,7 +289,7 @@ pub(crate) fn add_components(
 ) -> anyhow::Result<()> {
     Ok(world.add_components(
         entity_id.from_bindgen(),
-        convert_components_to_entity_data(data),
+        convert_components_to_entity_data(data)?,
     )?)
 }
 
@@ -235,7 +300,7 @@ pub(crate) fn set_components(
 ) -> anyhow::Result<()> {
     Ok(world.set_components(
         entity_id.from_bindgen(),
-        convert_components_to_entity_data(data),
+        convert_components_to_entity_data(data)?,
     )?)
 }
 
@@ -9,6 +9,7 @@ use ambient_ecs::{query as ecs_query, with_component_registry, EntityId, World};
 
 use ambient_model::animation_binder;
 use ambient_network::ServerWorldExt;
+use ambient_std::sandbox;
 
 use anyhow::Context;
 use glam::Mat4;
@@ -26,7 +27,14 @@ pub fn spawn(
     spawned_entities: &mut HashSet<EntityId>,
     data: wit::entity::EntityData,
 ) -> anyhow::Result<wit::types::EntityId> {
-    let id = convert_components_to_entity_data(data).spawn(world);
+    if let Some(limits) = sandbox::limits() {
+        anyhow::ensure!(
+            world.len() < limits.max_entities,
+            "The world can have at most {} entities in the hardened mode",
+            limits.max_entities
+        );
+    }
+    let id = convert_components_to_entity_data(data)?.spawn(world);
     spawned_entities.insert(id);
     Ok(id.into_bindgen())
 }
@@ -16,7 +16,7 @@ use ambient_core::{
 };
 use ambient_audio::{AudioFromUrl, AudioEmitter, AudioListener, Attenuation};
 use ambient_audio::Source;
-use ambient_world_audio::{audio_sender, AudioMessage};
+use ambient_world_audio::{audio_sender, sound_bus, AudioMessage};
 use itertools::Itertools;
 use glam::{Mat4, Vec3};
 
@@ -39,6 +39,7 @@ pub(crate) fn set_emitter(
         amplitude: 5.0,
         attenuation: Attenuation::InversePoly { quad: 0.1, lin: 0.0, constant: 1.0 },
         pos,
+        ..Default::default()
     }));
     world.add_component(entity.from_bindgen(), audio_emitter(), emitter)?;
 
@@ -72,10 +73,11 @@ pub(crate) fn play_sound_on_entity(
             }).unwrap();
             match track {
                 Ok(track) => {
+                    let bus = sound_bus(world, &url.to_string());
                     let sender = world.resource(audio_sender());
                     let source = track.decode().spatial(hrtf_lib, listener.clone(), emitter.clone());
                     sender
-                        .send(AudioMessage::Spatial(source))
+                        .send(AudioMessage::Spatial(source, bus))
                         .unwrap();
                 }
                 Err(e) => log::error!("{e:?}"),
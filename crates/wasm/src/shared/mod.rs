@@ -4,20 +4,35 @@ pub(crate) mod implementation;
 mod module;
 
 pub mod build;
+pub mod clipboard;
 pub mod conversion;
 pub mod host_guest_state;
 pub mod message;
+pub mod module_log;
+pub mod profiling;
 pub mod wit;
 
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
-use ambient_core::async_ecs::async_run;
+use ambient_core::{asset_cache, async_ecs::async_run};
+use ambient_dialogue::{dialogue_choice_events, dialogue_end_events};
 use ambient_ecs::{
     dont_despawn_on_unload, generated::messages, query, world_events, Entity, EntityId, FnSystem,
     Message, SystemGroup, World, WorldEventReader,
 };
-use ambient_physics::{collider_loads, collisions};
-use ambient_project::Identifier;
+use ambient_physics::{
+    collider_loads, collisions,
+    physx::{fallen_asleep, woken_up},
+    projectile::projectile_impacts,
+};
+use ambient_project::{Identifier, Permissions};
+use ambient_sequencer::{sequence_end_events, sequence_events};
+use ambient_std::{
+    asset_cache::SyncAssetKeyExt,
+    asset_url::{AbsAssetUrl, ContentBaseUrlKey},
+    metrics::{self, MetricKind},
+    sandbox,
+};
 use itertools::Itertools;
 pub use module::*;
 
@@ -27,6 +42,7 @@ mod internal {
     use ambient_ecs::{
         components, Debuggable, Description, EntityId, Networked, Resource, Store, World,
     };
+    use ambient_project::Permissions;
 
     use super::{MessageType, ModuleBytecode, ModuleErrors, ModuleState, ModuleStateArgs};
 
@@ -44,6 +60,14 @@ mod internal {
         module_errors: ModuleErrors,
         @[Networked, Debuggable, Description["The ID of the module on the \"other side\" of this module, if available. (e.g. serverside module to clientside module)."]]
         remote_paired_id: EntityId,
+        @[Networked, Store, Debuggable, Description["If attached, this module runs in the restricted capability profile of mods: it can only modify the entities it spawned itself, and can't change the physics of the world."]]
+        module_restricted: (),
+        @[Networked, Store, Debuggable, Description["The ID of the mod this module was loaded from."]]
+        mod_id: String,
+        @[Networked, Store, Debuggable, Description["The IDs of the mods that the mod of this module depends on."]]
+        mod_dependencies: Vec<String>,
+        @[Networked, Store, Debuggable, Description["The permissions that the project of this module declared in its manifest. If attached, the module can only use the capabilities of the host that it was permitted."]]
+        module_permissions: Permissions,
 
         @[Resource, Description["Used to signal messages from the WASM host/runtime."]]
         messenger: Arc<dyn Fn(&World, EntityId, MessageType, &str) + Send + Sync>,
@@ -52,8 +76,9 @@ mod internal {
     });
 }
 pub use internal::{
-    client_bytecode_from_url, messenger, module, module_bytecode, module_enabled, module_errors,
-    module_state, module_state_maker, remote_paired_id,
+    client_bytecode_from_url, messenger, mod_dependencies, mod_id, module, module_bytecode,
+    module_enabled, module_errors, module_permissions, module_restricted, module_state,
+    module_state_maker, remote_paired_id,
 };
 
 use self::message::Source;
@@ -61,7 +86,10 @@ use crate::shared::message::RuntimeMessageExt;
 
 pub fn init_all_components() {
     internal::init_components();
+    clipboard::init_components();
     message::init_components();
+    module_log::init_components();
+    profiling::init_components();
 }
 
 pub const MAXIMUM_ERROR_COUNT: usize = 5;
@@ -81,6 +109,8 @@ pub fn systems() -> SystemGroup {
     SystemGroup::new(
         "core/wasm",
         vec![
+            Box::new(FnSystem::new(|world, _| profiling::next_frame(world))),
+            Box::new(FnSystem::new(|world, _| refuel(world))),
             query((module_bytecode(), module_enabled().changed())).to_system(
                 move |q, world, qs, _| {
                     ambient_profiling::scope!("WASM module reloads");
@@ -106,6 +136,15 @@ pub fn systems() -> SystemGroup {
                     .collect_vec();
 
                 for (name, data) in events {
+                    // The keys that the keyboard navigation of the UI uses don't reach the modules
+                    if name == messages::WindowKeyboardInput::id()
+                        && messages::WindowKeyboardInput::deserialize_message(&data)
+                            .map_or(false, |event| {
+                                ambient_accessibility::uses_keyboard_input(world, &event)
+                            })
+                    {
+                        continue;
+                    }
                     message::run(
                         world,
                         message::SerializedMessage {
@@ -153,6 +192,72 @@ pub fn systems() -> SystemGroup {
                     .run(world, None)
                     .unwrap();
             })),
+            Box::new(FnSystem::new(move |world, _| {
+                ambient_profiling::scope!("WASM module physics sleep events");
+                // trigger the sleep and wake events of the bodies that changed this frame
+                let asleep = world
+                    .resource_opt(fallen_asleep())
+                    .cloned()
+                    .unwrap_or_default();
+                if !asleep.is_empty() {
+                    ambient_ecs::generated::messages::PhysicsSleep::new(asleep)
+                        .run(world, None)
+                        .unwrap();
+                }
+                let awake = world.resource_opt(woken_up()).cloned().unwrap_or_default();
+                if !awake.is_empty() {
+                    ambient_ecs::generated::messages::PhysicsWake::new(awake)
+                        .run(world, None)
+                        .unwrap();
+                }
+            })),
+            Box::new(FnSystem::new(move |world, _| {
+                ambient_profiling::scope!("WASM module projectile impacts");
+                // trigger projectile impact events
+                let impacts = match world.resource_opt(projectile_impacts()) {
+                    Some(impacts) => impacts.clone(),
+                    None => return,
+                };
+                for impact in impacts {
+                    impact.run(world, None).unwrap();
+                }
+            })),
+            Box::new(FnSystem::new(move |world, _| {
+                ambient_profiling::scope!("WASM module dialogue events");
+                // trigger dialogue choice and end events
+                let choices = world
+                    .resource_opt(dialogue_choice_events())
+                    .cloned()
+                    .unwrap_or_default();
+                for choice in choices {
+                    choice.run(world, None).unwrap();
+                }
+                let ends = world
+                    .resource_opt(dialogue_end_events())
+                    .cloned()
+                    .unwrap_or_default();
+                for end in ends {
+                    end.run(world, None).unwrap();
+                }
+            })),
+            Box::new(FnSystem::new(move |world, _| {
+                ambient_profiling::scope!("WASM module sequence events");
+                // trigger sequence events, and the ends of sequences
+                let events = world
+                    .resource_opt(sequence_events())
+                    .cloned()
+                    .unwrap_or_default();
+                for event in events {
+                    event.run(world, None).unwrap();
+                }
+                let ends = world
+                    .resource_opt(sequence_end_events())
+                    .cloned()
+                    .unwrap_or_default();
+                for end in ends {
+                    end.run(world, None).unwrap();
+                }
+            })),
             Box::new(FnSystem::new(move |world, _| {
                 ambient_profiling::scope!("WASM module pending messages");
 
@@ -167,19 +272,35 @@ pub fn systems() -> SystemGroup {
     )
 }
 
+/// Gives every module its fuel for this tick, in the hardened mode
+fn refuel(world: &mut World) {
+    let Some(limits) = sandbox::limits() else {
+        return;
+    };
+    let states = query(module_state())
+        .iter(world, None)
+        .map(|(id, state)| (id, state.clone()))
+        .collect_vec();
+    for (id, mut state) in states {
+        if let Err(err) = state.refuel(limits.max_fuel_per_tick) {
+            log::warn!("Failed to refuel module {id}: {err:?}");
+        }
+    }
+}
+
 pub fn initialize<Bindings: bindings::BindingsBound + 'static>(
     world: &mut World,
     messenger: Arc<dyn Fn(&World, EntityId, MessageType, &str) + Send + Sync>,
     bindings: fn(EntityId) -> Bindings,
-) -> anyhow::Result<()> {
+) {
     world.add_resource(self::messenger(), messenger);
     world.add_resource(
         self::module_state_maker(),
         ModuleState::create_state_maker(bindings),
     );
     world.add_resource(message::pending_messages(), vec![]);
-
-    Ok(())
+    world.add_resource(profiling::module_profile(), Default::default());
+    world.add_resource(profiling::module_profile_frame(), Default::default());
 }
 
 pub(crate) fn reload_all(world: &mut World) {
@@ -280,24 +401,52 @@ fn run(
     message_name: &str,
     message_data: &[u8],
 ) {
-    ambient_profiling::scope!(
-        "run",
-        format!("{} - {}", get_module_name(world, id), message_name)
-    );
+    let module_name = get_module_name(world, id);
+    ambient_profiling::scope!("run", format!("{} - {}", module_name, message_name));
 
     // If it's not in the subscribed events, skip over it
     if !state.supports_message(message_name) {
         return;
     }
 
+    let start = ambient_sys::time::Instant::now();
     let result =
         run_and_catch_panics(|| state.run(world, message_source, message_name, message_data));
+    let fuel = state.take_fuel_consumed();
+    if fuel > 0 {
+        metrics::add(
+            FUEL_CONSUMED_METRIC,
+            &[("module", module_name.as_ref())],
+            fuel as f64,
+        );
+    }
+    profiling::record_call(
+        world,
+        module_name.as_ref(),
+        message_name,
+        start,
+        start.elapsed(),
+    );
+    clipboard::collect_requests(world, id);
 
     if let Err(message) = result {
         update_errors(world, &[(id, message)]);
     }
 }
 
+/// The fuel that each module has consumed; see [ambient_std::metrics]
+pub const FUEL_CONSUMED_METRIC: &str = "ambient_wasm_fuel_consumed_total";
+
+/// Declares the statistics of the modules. Fuel is only metered if the statistics are enabled before the first module
+/// is loaded.
+pub fn describe_metrics() {
+    metrics::describe(
+        FUEL_CONSUMED_METRIC,
+        MetricKind::Counter,
+        "The fuel that a module has consumed, which is roughly the number of instructions it has run",
+    );
+}
+
 pub(crate) fn unload(world: &mut World, module_id: EntityId, reason: &str) {
     if !world.has_component(module_id, module_state()) {
         return;
@@ -352,6 +501,92 @@ pub fn get_module_name(world: &World, id: EntityId) -> Identifier {
     Identifier::new(world.get_cloned(id, ambient_core::name()).unwrap()).unwrap()
 }
 
+/// Fails if the module `module_id` is restricted (see [module_restricted]) and `entity` isn't one of the
+/// `spawned_entities` of the module.
+pub(crate) fn ensure_can_modify(
+    world: &World,
+    module_id: EntityId,
+    spawned_entities: &HashSet<EntityId>,
+    entity: EntityId,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !world.has_component(module_id, module_restricted()) || spawned_entities.contains(&entity),
+        "This module is restricted, and can't modify {entity}, which it didn't spawn"
+    );
+    Ok(())
+}
+
+/// Fails if the module `module_id` is restricted (see [module_restricted]), as it can't do `action`.
+pub(crate) fn ensure_unrestricted(
+    world: &World,
+    module_id: EntityId,
+    action: &str,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !world.has_component(module_id, module_restricted()),
+        "This module is restricted, and can't {action}"
+    );
+    Ok(())
+}
+
+/// Fails if the module `module_id` has [module_permissions] that don't permit `permission`, which it needs.
+pub(crate) fn ensure_permitted(
+    world: &World,
+    module_id: EntityId,
+    permission: &str,
+    permitted: impl FnOnce(&Permissions) -> bool,
+) -> anyhow::Result<()> {
+    if let Ok(permissions) = world.get_ref(module_id, module_permissions()) {
+        anyhow::ensure!(
+            permitted(permissions),
+            "This module needs the `{permission}` permission, which isn't in the `[permissions]` of its project"
+        );
+    }
+    Ok(())
+}
+
+/// Fails if the module `module_id` has [module_permissions] that don't let it load assets from `url`, or if `url` isn't
+/// valid. The assets of the project itself can always be loaded.
+pub(crate) fn ensure_url_permitted(
+    world: &World,
+    module_id: EntityId,
+    url: &str,
+) -> anyhow::Result<()> {
+    let Ok(permissions) = world.get_ref(module_id, module_permissions()) else {
+        return Ok(());
+    };
+    let assets = world.resource(asset_cache());
+    let content = ContentBaseUrlKey.get(assets);
+    // Relative paths are in the content of the project. What can't be resolved can't be checked, so it's refused
+    let Ok(url) = content
+        .resolve(url)
+        .and_then(|url| url.to_download_url(assets))
+    else {
+        anyhow::bail!("This module can't load {url:?}, as it isn't a valid URL");
+    };
+    if sandbox::url_within(&url.0, &content.0) {
+        return Ok(());
+    }
+    match url.0.scheme() {
+        "http" | "https" => {
+            let host = url.0.host_str().unwrap_or_default();
+            anyhow::ensure!(
+                permissions
+                    .http_hosts
+                    .iter()
+                    .any(|pattern| sandbox::host_matches(pattern, host)),
+                "This module can't load {url}, as {host} isn't in the `http_hosts` permission of its project"
+            );
+        }
+        "file" => anyhow::ensure!(
+            permissions.filesystem,
+            "This module can't load {url}, as its project doesn't have the `filesystem` permission"
+        ),
+        scheme => anyhow::bail!("This module can't load assets with `{scheme}` URLs"),
+    }
+    Ok(())
+}
+
 fn run_and_catch_panics<R>(f: impl FnOnce() -> anyhow::Result<R>) -> Result<R, String> {
     let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
     match result {
@@ -1,6 +1,7 @@
 use std::{any::Any, collections::HashSet, sync::Arc};
 
 use ambient_ecs::{EntityId, World};
+use ambient_std::sandbox;
 use data_encoding::BASE64;
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
@@ -80,6 +81,10 @@ pub trait ModuleStateBehavior: Sync + Send {
     fn drain_spawned_entities(&mut self) -> HashSet<EntityId>;
     fn listen_to_message(&mut self, event_name: String);
     fn supports_message(&self, event_name: &str) -> bool;
+    /// The fuel that the module has consumed since the last call, if fuel is metered
+    fn take_fuel_consumed(&mut self) -> u64;
+    /// Tops the fuel of the module back up to `fuel`, if fuel is metered
+    fn refuel(&mut self, fuel: u64) -> anyhow::Result<()>;
 }
 
 pub type Messenger = Box<dyn Fn(&World, &str) + Sync + Send>;
@@ -149,6 +154,14 @@ impl ModuleStateBehavior for ModuleState {
     fn supports_message(&self, message_name: &str) -> bool {
         self.inner.read().supports_message(message_name)
     }
+
+    fn take_fuel_consumed(&mut self) -> u64 {
+        self.inner.write().take_fuel_consumed()
+    }
+
+    fn refuel(&mut self, fuel: u64) -> anyhow::Result<()> {
+        self.inner.write().refuel(fuel)
+    }
 }
 
 struct ModuleStateInnerImpl<Bindings: BindingsBound> {
@@ -159,6 +172,9 @@ struct ModuleStateInnerImpl<Bindings: BindingsBound> {
 
     stdout_consumer: WasiOutputStreamConsumer,
     stderr_consumer: WasiOutputStreamConsumer,
+
+    /// The fuel consumed by the time of the last [ModuleStateBehavior::take_fuel_consumed]
+    fuel_taken: u64,
 }
 
 impl<Bindings: BindingsBound> std::fmt::Debug for ModuleStateInnerImpl<Bindings> {
@@ -188,6 +204,15 @@ impl<Bindings: BindingsBound> ModuleStateInnerImpl<Bindings> {
             },
         );
 
+        if store.fuel_consumed().is_some() {
+            // Modules only run out of fuel in the hardened mode
+            let fuel = match sandbox::limits() {
+                Some(limits) => limits.max_fuel_per_tick,
+                None => i64::MAX as u64,
+            };
+            store.add_fuel(fuel)?;
+        }
+
         let mut linker = wasmtime::component::Linker::<WasmContext<Bindings>>::new(engine);
         wasmtime_wasi::command::add_to_linker(&mut linker, |x| &mut x.wasi)?;
         wit::Bindings::add_to_linker(&mut linker, |x| &mut x.bindings)?;
@@ -207,6 +232,8 @@ impl<Bindings: BindingsBound> ModuleStateInnerImpl<Bindings> {
 
             stdout_consumer,
             stderr_consumer,
+
+            fuel_taken: 0,
         })
     }
 }
@@ -240,7 +267,13 @@ impl<Bindings: BindingsBound> ModuleStateBehavior for ModuleStateInnerImpl<Bindi
         self.stdout_consumer.process_incoming(world);
         self.stderr_consumer.process_incoming(world);
 
-        result
+        result.map_err(|err| {
+            if err.downcast_ref::<wasmtime::Trap>() == Some(&wasmtime::Trap::OutOfFuel) {
+                err.context("The module used up its fuel for this tick")
+            } else {
+                err
+            }
+        })
     }
 
     fn drain_spawned_entities(&mut self) -> HashSet<EntityId> {
@@ -264,6 +297,23 @@ impl<Bindings: BindingsBound> ModuleStateBehavior for ModuleStateInnerImpl<Bindi
             .subscribed_messages
             .contains(event_name)
     }
+
+    fn take_fuel_consumed(&mut self) -> u64 {
+        let consumed = self.store.fuel_consumed().unwrap_or_default();
+        consumed - std::mem::replace(&mut self.fuel_taken, consumed)
+    }
+
+    fn refuel(&mut self, fuel: u64) -> anyhow::Result<()> {
+        if self.store.fuel_consumed().is_none() {
+            return Ok(());
+        }
+        // Consuming nothing tells what is left, without counting as consumed
+        let remaining = self.store.consume_fuel(0)?;
+        if remaining < fuel {
+            self.store.add_fuel(fuel - remaining)?;
+        }
+        Ok(())
+    }
 }
 
 struct WasiOutputStream(flume::Sender<String>);
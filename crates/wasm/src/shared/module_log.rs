@@ -0,0 +1,161 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io::Read,
+    sync::Arc,
+};
+
+use ambient_ecs::{components, Description, EntityId, Resource, World};
+use anyhow::Context;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use parking_lot::Mutex;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use super::{get_module_name, implementation::message::MAX_STREAM_LENGTH, MessageType};
+
+components!("wasm::module_log", {
+    @[Resource, Description["The most recent logs of the client modules, and of the server modules if they are forwarded to this client."]]
+    module_logs: Arc<Mutex<VecDeque<ModuleLog>>>,
+    @[Resource, Description["The streams that the logs of the server modules are forwarded to each player on, by player."]]
+    module_log_streams: Arc<Mutex<HashMap<EntityId, flume::Sender<Bytes>>>>,
+});
+
+/// How many logs are kept in `module_logs`; older logs are dropped.
+pub const MAX_MODULE_LOGS: usize = 1000;
+
+/// A message from a module, as it is shown in the module console.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleLog {
+    /// Whether this was logged by a server module
+    pub server: bool,
+    pub module: String,
+    pub type_: MessageType,
+    pub message: String,
+}
+impl ModuleLog {
+    pub fn new(
+        world: &World,
+        id: EntityId,
+        server: bool,
+        type_: MessageType,
+        message: &str,
+    ) -> Self {
+        Self {
+            server,
+            module: get_module_name(world, id).to_string(),
+            type_,
+            message: message.strip_suffix('\n').unwrap_or(message).to_string(),
+        }
+    }
+
+    /// Encodes a server module's log to be forwarded to clients.
+    pub(crate) fn to_bytes(&self) -> anyhow::Result<Bytes> {
+        let mut payload = BytesMut::new();
+        payload.put_u8(match self.type_ {
+            MessageType::Info => 0,
+            MessageType::Warn => 1,
+            MessageType::Error => 2,
+            MessageType::Stdout => 3,
+            MessageType::Stderr => 4,
+        });
+        payload.put_u32(self.module.len().try_into()?);
+        payload.put(self.module.as_bytes());
+        payload.put(self.message.as_bytes());
+        Ok(payload.freeze())
+    }
+
+    /// Encodes a server module's log to be written to the stream the logs are forwarded on, prefixed with its length.
+    pub(crate) fn to_frame(&self) -> anyhow::Result<Bytes> {
+        let payload = self.to_bytes()?;
+        let mut frame = BytesMut::with_capacity(4 + payload.len());
+        frame.put_u32(payload.len().try_into()?);
+        frame.put(payload);
+        Ok(frame.freeze())
+    }
+
+    /// Reads the next log from the stream the logs are forwarded on, or `None` once the stream has ended.
+    pub(crate) async fn read_frame(
+        stream: &mut (impl AsyncRead + Unpin),
+    ) -> anyhow::Result<Option<Self>> {
+        let len = match stream.read_u32().await {
+            Ok(len) => usize::try_from(len)?,
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        anyhow::ensure!(
+            len <= MAX_STREAM_LENGTH,
+            "Module log of {len} bytes is too long"
+        );
+        let mut bytes = vec![0u8; len];
+        stream.read_exact(&mut bytes).await?;
+        Self::from_bytes(&bytes).map(Some)
+    }
+
+    /// Decodes a log forwarded by the server.
+    pub(crate) fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        anyhow::ensure!(bytes.len() >= 5, "Truncated module log");
+        let mut cursor = std::io::Cursor::new(bytes);
+        let type_ = match cursor.get_u8() {
+            0 => MessageType::Info,
+            1 => MessageType::Warn,
+            2 => MessageType::Error,
+            3 => MessageType::Stdout,
+            4 => MessageType::Stderr,
+            other => anyhow::bail!("Unknown module log type {other}"),
+        };
+        let module_len = usize::try_from(cursor.get_u32())?;
+        let mut module = vec![0u8; module_len];
+        cursor
+            .read_exact(&mut module)
+            .context("Truncated module log")?;
+        let mut message = String::new();
+        cursor.read_to_string(&mut message)?;
+        Ok(Self {
+            server: true,
+            module: String::from_utf8(module)?,
+            type_,
+            message,
+        })
+    }
+}
+
+/// Keeps `log` in `module_logs`, if this world has them.
+pub fn push_module_log(world: &World, log: ModuleLog) {
+    let Some(logs) = world.resource_opt(module_logs()) else {
+        return;
+    };
+    let mut logs = logs.lock();
+    if logs.len() >= MAX_MODULE_LOGS {
+        logs.pop_front();
+    }
+    logs.push_back(log);
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::io::AsyncWriteExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn forwarded_logs_arrive_in_order() {
+        let logs = (0..3)
+            .map(|i| ModuleLog {
+                server: true,
+                module: "module".to_string(),
+                type_: MessageType::Info,
+                message: format!("line {i}"),
+            })
+            .collect::<Vec<_>>();
+        let (mut send, mut recv) = tokio::io::duplex(1024);
+        for log in &logs {
+            send.write_all(&log.to_frame().unwrap()).await.unwrap();
+        }
+        drop(send);
+
+        let mut received = Vec::new();
+        while let Some(log) = ModuleLog::read_frame(&mut recv).await.unwrap() {
+            received.push(log);
+        }
+        assert_eq!(received, logs);
+    }
+}
@@ -0,0 +1,213 @@
+use std::time::Duration;
+
+use ambient_ecs::{
+    components,
+    generated::components::core::profiling::{
+        profiling_span_depths, profiling_span_durations, profiling_span_names,
+        profiling_span_starts,
+    },
+    Debuggable, Description, Resource, World,
+};
+use ambient_sys::time::Instant;
+use serde::{Deserialize, Serialize};
+
+components!("wasm::profiling", {
+    @[Debuggable, Resource, Description["The calls to the modules during the last frame, and the profiling scopes that ended during them."]]
+    module_profile: ModuleProfile,
+    @[Resource]
+    module_profile_frame: ModuleProfileFrame,
+});
+
+/// A call to a module (at depth 0), or a profiling scope of the module (nested under the call).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModuleProfileSpan {
+    pub module: String,
+    pub name: String,
+    /// In seconds since the start of the frame
+    pub start: f32,
+    /// In seconds
+    pub duration: f32,
+    pub depth: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModuleProfile {
+    /// The length of the frame, in seconds
+    pub duration: f32,
+    pub spans: Vec<ModuleProfileSpan>,
+}
+
+/// The frame that is being profiled.
+#[derive(Debug, Clone)]
+pub struct ModuleProfileFrame {
+    start: Instant,
+    spans: Vec<ModuleProfileSpan>,
+}
+impl Default for ModuleProfileFrame {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+            spans: Vec::new(),
+        }
+    }
+}
+
+/// Finishes the profile of the frame that was being profiled, and starts the next one.
+pub(crate) fn next_frame(world: &mut World) {
+    let Some(frame) = world.resource_mut_opt(module_profile_frame()) else {
+        return;
+    };
+    let frame = std::mem::take(frame);
+    let duration = frame.start.elapsed().as_secs_f32();
+    world.add_resource(
+        module_profile(),
+        ModuleProfile {
+            duration,
+            spans: frame.spans,
+        },
+    );
+}
+
+/// Records a call to the module `module`, with the profiling scopes it reported.
+pub(crate) fn record_call(
+    world: &mut World,
+    module: &str,
+    message_name: &str,
+    start: Instant,
+    duration: Duration,
+) {
+    let resources = world.resource_entity();
+    let names = world
+        .get_cloned(resources, profiling_span_names())
+        .unwrap_or_default();
+    let starts = world
+        .get_cloned(resources, profiling_span_starts())
+        .unwrap_or_default();
+    let durations = world
+        .get_cloned(resources, profiling_span_durations())
+        .unwrap_or_default();
+    let depths = world
+        .get_cloned(resources, profiling_span_depths())
+        .unwrap_or_default();
+    if !names.is_empty() {
+        world
+            .remove_component(resources, profiling_span_names())
+            .ok();
+        world
+            .remove_component(resources, profiling_span_starts())
+            .ok();
+        world
+            .remove_component(resources, profiling_span_durations())
+            .ok();
+        world
+            .remove_component(resources, profiling_span_depths())
+            .ok();
+    }
+
+    if puffin::are_scopes_on() {
+        report_to_puffin(
+            module,
+            message_name,
+            start,
+            duration,
+            &names,
+            &starts,
+            &durations,
+            &depths,
+        );
+    }
+
+    let Some(frame) = world.resource_mut_opt(module_profile_frame()) else {
+        return;
+    };
+    let call_start = start.duration_since(frame.start).as_secs_f32();
+    frame.spans.push(ModuleProfileSpan {
+        module: module.to_string(),
+        name: message_name.to_string(),
+        start: call_start,
+        duration: duration.as_secs_f32(),
+        depth: 0,
+    });
+    for (((name, start), duration), depth) in
+        names.into_iter().zip(starts).zip(durations).zip(depths)
+    {
+        tracing::trace!(
+            target: "module_profile",
+            module,
+            scope = %name,
+            duration_ms = duration * 1000.
+        );
+        frame.spans.push(ModuleProfileSpan {
+            module: module.to_string(),
+            name,
+            start: call_start + start,
+            duration,
+            depth: depth + 1,
+        });
+    }
+}
+
+/// Reports a call to the module, and the profiling scopes nested under it, to puffin's global profiler.
+/// Each module gets its own row in the profiler.
+#[allow(clippy::too_many_arguments)]
+fn report_to_puffin(
+    module: &str,
+    message_name: &str,
+    start: Instant,
+    duration: Duration,
+    names: &[String],
+    starts: &[f32],
+    durations: &[f32],
+    depths: &[u32],
+) {
+    let call_start_ns = puffin::now_ns() - start.elapsed().as_nanos() as puffin::NanoSecond;
+    let call_end_ns = call_start_ns + duration.as_nanos() as puffin::NanoSecond;
+    let to_ns = |seconds: f32| call_start_ns + (seconds as f64 * 1e9) as puffin::NanoSecond;
+
+    // puffin wants the scopes in the order they began, parents first
+    let count = names
+        .len()
+        .min(starts.len())
+        .min(durations.len())
+        .min(depths.len());
+    let mut scopes = (0..count).collect::<Vec<_>>();
+    scopes.sort_by(|&a, &b| {
+        starts[a]
+            .total_cmp(&starts[b])
+            .then(depths[a].cmp(&depths[b]))
+    });
+
+    let mut stream = puffin::Stream::default();
+    let call_offset = stream.begin_scope(call_start_ns, message_name, module, "");
+    // The scopes that are still open, with their offset in the stream and their end
+    let mut open: Vec<(usize, puffin::NanoSecond)> = Vec::new();
+    let mut max_depth = 1;
+    for i in scopes {
+        while open.len() > depths[i] as usize {
+            let (offset, end_ns) = open.pop().unwrap();
+            stream.end_scope(offset, end_ns);
+        }
+        let start_ns = to_ns(starts[i]).max(call_start_ns);
+        let end_ns = to_ns(starts[i] + durations[i]).clamp(start_ns, call_end_ns);
+        open.push((stream.begin_scope(start_ns, &names[i], module, ""), end_ns));
+        max_depth = max_depth.max(open.len() + 1);
+    }
+    while let Some((offset, end_ns)) = open.pop() {
+        stream.end_scope(offset, end_ns);
+    }
+    stream.end_scope(call_offset, call_end_ns);
+
+    let stream_info = puffin::StreamInfo {
+        stream,
+        num_scopes: count + 1,
+        depth: max_depth,
+        range_ns: (call_start_ns, call_end_ns),
+    };
+    puffin::GlobalProfiler::lock().report(
+        puffin::ThreadInfo {
+            start_time_ns: None,
+            name: format!("module {module}"),
+        },
+        &stream_info.as_stream_into_ref(),
+    );
+}
@@ -44,6 +44,7 @@ fn spawn_emitters(world: &mut World) {
                 constant: 1.0,
             },
             pos,
+            ..Default::default()
         }));
 
         let id = Cube
@@ -1,10 +1,14 @@
 mod error;
 mod events;
 mod graph;
+mod mixing;
+mod propagation;
 mod sounds;
 pub mod systems;
 pub use ambient_audio as core;
 pub use error::*;
 pub use events::*;
 pub use graph::*;
+pub use mixing::*;
+pub use propagation::*;
 pub use sounds::*;
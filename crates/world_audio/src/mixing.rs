@@ -0,0 +1,173 @@
+use std::{collections::HashMap, time::Duration};
+
+use ambient_audio::{Effect, DEFAULT_BUSES, MASTER_BUS, SFX_BUS};
+use ambient_ecs::{query, FnSystem, SystemGroup, World};
+
+use crate::{audio_mixer, user_bus_volumes};
+
+pub use ambient_ecs::generated::components::core::audio::{
+    active_snapshot, bus, bus_compressor, bus_eq, bus_parent, bus_reverb, bus_sounds, bus_volume,
+    bus_volume_transition, snapshot, snapshot_buses, snapshot_transition, snapshot_volumes,
+};
+
+/// The settings of a bus of the mixer, as given by its [bus] entity
+#[derive(Debug, Clone, PartialEq)]
+struct BusSettings {
+    parent: String,
+    volume: f32,
+    transition: f32,
+    effects: Vec<Effect>,
+}
+
+impl Default for BusSettings {
+    fn default() -> Self {
+        Self {
+            parent: MASTER_BUS.to_string(),
+            volume: 1.,
+            transition: 0.,
+            effects: Vec::new(),
+        }
+    }
+}
+
+fn bus_settings(world: &World) -> HashMap<String, BusSettings> {
+    query(bus())
+        .iter(world, None)
+        .map(|(id, name)| {
+            let effects = [
+                world.get(id, bus_eq()).ok().map(|gains| Effect::Eq {
+                    low: gains.x,
+                    mid: gains.y,
+                    high: gains.z,
+                }),
+                world
+                    .get(id, bus_compressor())
+                    .ok()
+                    .map(|settings| Effect::Compressor {
+                        threshold: settings.x,
+                        ratio: settings.y,
+                        attack: settings.z,
+                        release: settings.w,
+                    }),
+                world
+                    .get(id, bus_reverb())
+                    .ok()
+                    .map(|settings| Effect::Reverb {
+                        room_size: settings.x,
+                        damping: settings.y,
+                        wet: settings.z,
+                    }),
+            ];
+            let settings = BusSettings {
+                parent: world
+                    .get_cloned(id, bus_parent())
+                    .unwrap_or_else(|_| MASTER_BUS.to_string()),
+                volume: world.get(id, bus_volume()).unwrap_or(1.),
+                transition: world.get(id, bus_volume_transition()).unwrap_or_default(),
+                effects: effects.into_iter().flatten().collect(),
+            };
+            (name.clone(), settings)
+        })
+        .collect()
+}
+
+/// The bus volumes of the [active_snapshot], and how long they take to fade in
+fn active_snapshot_volumes(world: &World) -> (HashMap<String, f32>, f32) {
+    let Some(name) = world
+        .resource_opt(active_snapshot())
+        .filter(|name| !name.is_empty())
+    else {
+        return Default::default();
+    };
+    let Some((id, _)) = query(snapshot())
+        .iter(world, None)
+        .find(|(_, snapshot)| *snapshot == name)
+    else {
+        return Default::default();
+    };
+    let buses = world.get_cloned(id, snapshot_buses()).unwrap_or_default();
+    let volumes = world.get_cloned(id, snapshot_volumes()).unwrap_or_default();
+    let transition = world.get(id, snapshot_transition()).unwrap_or_default();
+    (buses.into_iter().zip(volumes).collect(), transition)
+}
+
+/// The bus a sound from `url` plays on, from the [bus_sounds] of the buses
+pub fn sound_bus(world: &World, url: &str) -> String {
+    query((bus(), bus_sounds()))
+        .iter(world, None)
+        .find(|(_, (_, sounds))| sounds.iter().any(|sound| url.contains(sound.as_str())))
+        .map(|(_, (name, _))| name.clone())
+        .unwrap_or_else(|| SFX_BUS.to_string())
+}
+
+/// Keeps the buses of the [audio_mixer] up to date with the [bus] entities, the [active_snapshot] and the user's volume
+/// settings
+pub fn mixer_systems() -> SystemGroup {
+    SystemGroup::new(
+        "audio_mixer",
+        vec![Box::new(FnSystem::new({
+            let mut applied: HashMap<String, (BusSettings, f32)> = HashMap::new();
+            let mut applied_snapshot = (HashMap::new(), 0.);
+            move |world, _| {
+                let Some(mixer) = world.resource_opt(audio_mixer()) else {
+                    return;
+                };
+                let user = world
+                    .resource_opt(user_bus_volumes())
+                    .cloned()
+                    .unwrap_or_default();
+                let snapshot = active_snapshot_volumes(world);
+                // Fading out of a snapshot takes as long as fading into it
+                let snapshot_changed = snapshot.0 != applied_snapshot.0;
+                let snapshot_transition = if snapshot.0.is_empty() {
+                    applied_snapshot.1
+                } else {
+                    snapshot.1
+                };
+
+                let mut buses = bus_settings(world);
+                // Buses whose entity was removed go back to their defaults
+                for name in DEFAULT_BUSES
+                    .iter()
+                    .map(|name| name.to_string())
+                    .chain(applied.keys().cloned())
+                    .chain(snapshot.0.keys().cloned())
+                    .collect::<Vec<_>>()
+                {
+                    buses.entry(name).or_default();
+                }
+
+                for (name, settings) in buses {
+                    let volume = settings.volume
+                        * snapshot.0.get(&name).copied().unwrap_or(1.)
+                        * user.get(&name).copied().unwrap_or(1.);
+                    let previous = applied.get(&name);
+                    if name != MASTER_BUS
+                        && previous.map(|(previous, _)| &previous.parent) != Some(&settings.parent)
+                    {
+                        if let Err(err) = mixer.set_bus_parent(&name, &settings.parent) {
+                            log::warn!("{err}");
+                        }
+                    }
+                    if previous.map(|(previous, _)| &previous.effects) != Some(&settings.effects) {
+                        mixer.set_bus_effects(&name, &settings.effects);
+                    }
+                    if previous.map(|(_, volume)| *volume) != Some(volume) {
+                        let transition = match previous {
+                            Some(_) if snapshot_changed => snapshot_transition,
+                            Some(_) => settings.transition,
+                            None => 0.,
+                        };
+                        mixer.set_bus_volume(
+                            &name,
+                            volume,
+                            Duration::from_secs_f32(transition.max(0.)),
+                        );
+                    }
+                    applied.insert(name, (settings, volume));
+                }
+                applied_snapshot = snapshot;
+            }
+        }))],
+    )
+}
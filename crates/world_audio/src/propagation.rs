@@ -0,0 +1,230 @@
+//! Sound propagation through indoor scenes. When the listener is inside one of the cells of the
+//! [portal_graph], sounds from other cells travel to it along the shortest chain of portals: they seem to come from the
+//! portal they reach the listener through, are attenuated and delayed by the length of their path, and are muffled the
+//! more the path bends. Sounds from cells that can't be reached through the portals are only heard through the walls.
+
+use ambient_audio::Propagation;
+use ambient_core::{portals::portal_graph, transform::local_to_world};
+use ambient_ecs::{query, FnSystem, SystemGroup, World};
+use glam::Vec3;
+use itertools::Itertools;
+
+use crate::{audio_emitter, audio_listener};
+
+pub use ambient_ecs::generated::components::core::audio::sound_propagation;
+
+/// The gain and cutoff frequency of sounds that are only heard through the walls
+const WALL_GAIN: f32 = 0.2;
+const WALL_CUTOFF: f32 = 500.;
+/// The gain of a sound for every portal it passes through
+const PORTAL_GAIN: f32 = 0.9;
+/// The cutoff frequency of a sound that goes around a corner is lowered by an octave for every this many meters that its
+/// path is longer than the straight line to the listener, down to the minimum
+const MAX_CUTOFF: f32 = 20000.;
+const MIN_CUTOFF: f32 = 250.;
+const DETOUR_PER_OCTAVE: f32 = 4.;
+
+/// The shortest path from the listener to a portal
+#[derive(Debug, Clone, Copy)]
+struct PortalPath {
+    length: f32,
+    /// The index of the first portal on the path, which the sound reaches the listener through
+    first: usize,
+    /// How many portals the path passes through
+    portals: i32,
+}
+
+/// The shortest paths from the listener in `listener_cell` to every portal, through the cells that the portals share
+fn portal_paths(
+    portals: &[(Vec3, [usize; 2])],
+    listener: Vec3,
+    listener_cell: usize,
+) -> Vec<Option<PortalPath>> {
+    let mut paths = portals
+        .iter()
+        .enumerate()
+        .map(|(index, (center, cells))| {
+            cells.contains(&listener_cell).then(|| PortalPath {
+                length: center.distance(listener),
+                first: index,
+                portals: 1,
+            })
+        })
+        .collect_vec();
+    let mut done = vec![false; portals.len()];
+    while let Some((current, path)) = (0..portals.len())
+        .filter(|&index| !done[index])
+        .filter_map(|index| Some((index, paths[index]?)))
+        .min_by(|(_, a), (_, b)| a.length.total_cmp(&b.length))
+    {
+        done[current] = true;
+        let (center, cells) = portals[current];
+        for (next, (next_center, next_cells)) in portals.iter().enumerate() {
+            if done[next] || !next_cells.iter().any(|cell| cells.contains(cell)) {
+                continue;
+            }
+            let length = path.length + center.distance(*next_center);
+            if paths[next].map_or(true, |next_path| length < next_path.length) {
+                paths[next] = Some(PortalPath {
+                    length,
+                    first: path.first,
+                    portals: path.portals + 1,
+                });
+            }
+        }
+    }
+    paths
+}
+
+/// The portals of the scene, and the paths to them from the listener
+struct Paths {
+    listener: Vec3,
+    listener_cell: usize,
+    portals: Vec<(Vec3, [usize; 2])>,
+    paths: Vec<Option<PortalPath>>,
+}
+
+impl Paths {
+    fn from_world(world: &World) -> Option<Self> {
+        if !world
+            .resource_opt(sound_propagation())
+            .copied()
+            .unwrap_or(true)
+        {
+            return None;
+        }
+        let graph = world.resource_opt(portal_graph())?;
+        let (_, (_, ltw)) = query((audio_listener(), local_to_world()))
+            .iter(world, None)
+            .next()?;
+        let listener = ltw.transform_point3(Vec3::ZERO);
+        let listener_cell = graph.cell_at(listener)?;
+        let portals = graph.portals().collect_vec();
+        let paths = portal_paths(&portals, listener, listener_cell);
+        Some(Self {
+            listener,
+            listener_cell,
+            portals,
+            paths,
+        })
+    }
+
+    /// How the sound of an emitter in the cell `cell`, at `pos`, reaches the listener
+    fn propagation(&self, cell: Option<usize>, pos: Vec3) -> Propagation {
+        // Sounds outdoors, and in the cell of the listener, go straight to it
+        let Some(cell) = cell.filter(|cell| *cell != self.listener_cell) else {
+            return Propagation::default();
+        };
+        let Some((path, length)) = self
+            .portals
+            .iter()
+            .zip(&self.paths)
+            .filter(|((_, cells), _)| cells.contains(&cell))
+            .filter_map(|((center, _), path)| {
+                let path = (*path)?;
+                Some((path, path.length + center.distance(pos)))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        else {
+            return Propagation {
+                pos: None,
+                gain: WALL_GAIN,
+                cutoff: Some(WALL_CUTOFF),
+            };
+        };
+
+        let (first, _) = self.portals[path.first];
+        let direction = (first - self.listener)
+            .try_normalize()
+            .unwrap_or_else(|| (pos - self.listener).normalize_or_zero());
+        let detour = length - pos.distance(self.listener);
+        Propagation {
+            pos: Some(self.listener + direction * length),
+            gain: PORTAL_GAIN.powi(path.portals),
+            cutoff: Some((MAX_CUTOFF * 0.5f32.powf(detour / DETOUR_PER_OCTAVE)).max(MIN_CUTOFF)),
+        }
+    }
+}
+
+/// Updates the [Propagation] of every audio emitter from the path its sound takes to the listener
+pub fn propagation_systems() -> SystemGroup {
+    SystemGroup::new(
+        "sound_propagation",
+        vec![Box::new(FnSystem::new(|world, _| {
+            let paths = Paths::from_world(world);
+            let graph = world.resource_opt(portal_graph());
+            let emitters = query((audio_emitter(), local_to_world()));
+            for (_, (emitter, ltw)) in emitters.iter(world, None) {
+                let propagation = match (&paths, graph) {
+                    (Some(paths), Some(graph)) => {
+                        let pos = ltw.transform_point3(Vec3::ZERO);
+                        paths.propagation(graph.cell_at(pos), pos)
+                    }
+                    _ => Propagation::default(),
+                };
+                let mut emitter = emitter.lock();
+                if emitter.propagation != propagation {
+                    emitter.propagation = propagation;
+                }
+            }
+        }))],
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use glam::vec3;
+
+    use super::*;
+
+    /// Three rooms in a row along x, with a portal between each pair, and a fourth room off the middle one
+    fn portals() -> Vec<(Vec3, [usize; 2])> {
+        vec![
+            (vec3(10., 0., 0.), [0, 1]),
+            (vec3(20., 0., 0.), [1, 2]),
+            (vec3(15., 10., 0.), [1, 3]),
+        ]
+    }
+
+    #[test]
+    fn portals_of_the_listeners_cell_are_reached_directly() {
+        let paths = portal_paths(&portals(), vec3(5., 0., 0.), 0);
+        let first = paths[0].unwrap();
+        assert_eq!(first.length, 5.);
+        assert_eq!((first.first, first.portals), (0, 1));
+    }
+
+    #[test]
+    fn farther_portals_are_reached_through_the_shortest_chain() {
+        let paths = portal_paths(&portals(), vec3(5., 0., 0.), 0);
+        let next = paths[1].unwrap();
+        assert_eq!(next.length, 15.);
+        assert_eq!((next.first, next.portals), (0, 2));
+        let side = paths[2].unwrap();
+        assert!((side.length - (5. + vec3(5., 10., 0.).length())).abs() < 1e-4);
+        assert_eq!((side.first, side.portals), (0, 2));
+    }
+
+    #[test]
+    fn shorter_paths_win() {
+        // A second way from the first room into the middle one, which is closer to the listener
+        let mut portals = portals();
+        portals.push((vec3(2., 0., 0.), [0, 1]));
+        let paths = portal_paths(&portals, vec3(0., 0., 0.), 0);
+        let next = paths[1].unwrap();
+        assert_eq!(next.first, 3);
+        assert_eq!(next.length, 20.);
+    }
+
+    #[test]
+    fn unconnected_portals_are_unreachable() {
+        let mut portals = portals();
+        portals.push((vec3(50., 0., 0.), [4, 5]));
+        let paths = portal_paths(&portals, vec3(5., 0., 0.), 0);
+        assert!(paths[3].is_none());
+        // Nothing is reachable from a cell without portals
+        assert!(portal_paths(&portals, vec3(5., 0., 0.), 6)
+            .iter()
+            .all(Option::is_none));
+    }
+}
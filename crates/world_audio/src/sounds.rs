@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use ambient_audio::{
     hrtf::HrtfLib, track::TrackDecodeStream,
@@ -29,6 +29,9 @@ components!("audio", {
     audio_sender: Arc<flume::Sender<AudioMessage>>,
     @[Resource]
     audio_mixer: AudioMixer,
+    /// The volumes the user set for the buses of the [audio_mixer] in their settings; the other buses play at full volume
+    @[Resource]
+    user_bus_volumes: HashMap<String, f32>,
 });
 
 pub enum AudioMessage {
@@ -38,9 +41,13 @@ pub enum AudioMessage {
         f32,
         AbsAssetUrl,
         u32,
+        /// The bus to play on
+        String,
     ),
     Spatial(
-        Spatial<TrackDecodeStream, Arc<parking_lot::lock_api::Mutex<RawMutex, AudioListener>>, Arc<parking_lot::lock_api::Mutex<RawMutex, AudioEmitter>>>
+        Spatial<TrackDecodeStream, Arc<parking_lot::lock_api::Mutex<RawMutex, AudioListener>>, Arc<parking_lot::lock_api::Mutex<RawMutex, AudioEmitter>>>,
+        /// The bus to play on
+        String,
     ),
     UpdateVolume(AbsAssetUrl, f32),
     Stop(AbsAssetUrl),
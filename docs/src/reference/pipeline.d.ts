@@ -240,6 +240,15 @@ export type Pipeline = {
     type: "Audio",
     /// Whether or not the audio should be converted to Ogg Vorbis.
     convert?: boolean,
+  } | {
+    /// A pipeline that runs a custom processor provided by the project as a WebAssembly module.
+    /// Can import any format, such as the levels of a custom level editor.
+    type: "Plugin",
+    /// The WebAssembly module that processes the files, relative to the `pipeline.json`.
+    /// Use `sources` to choose the files it processes.
+    processor: string,
+    /// The configuration to pass on to the processor.
+    config?: any,
   },
   /// Filter the sources used to feed this pipeline.
   /// This is a list of glob patterns for accepted files.
@@ -0,0 +1,1807 @@
+// This file is generated by `campfire`. Please do not manually edit it.
+export type Empty = null;
+export type Bool = boolean;
+export type EntityId = string;
+export type F32 = number;
+export type F64 = number;
+export type Mat4 = [number, number, number, number, number, number, number, number, number, number, number, number, number, number, number, number];
+export type I32 = number;
+export type Quat = [number, number, number, number];
+export type String = string;
+export type U8 = number;
+export type U32 = number;
+export type U64 = bigint;
+export type Vec2 = [number, number];
+export type Vec3 = [number, number, number];
+export type Vec4 = [number, number, number, number];
+export type Uvec2 = [number, number];
+export type Uvec3 = [number, number, number];
+export type Uvec4 = [number, number, number, number];
+
+/// The types of the components, by component ID.
+export type Components = {
+  /// **Accessibility focus**
+  /// The focusable entity that has the keyboard focus, or `EntityId::null()` if none has it. It can be set to move the focus.
+  "core::accessibility::accessibility_focus": EntityId,
+  /// **Accessibility focus order**
+  /// The position of this focusable entity in the focus order. Entities are focused in increasing order, and in the order of the UI tree among equal values. Defaults to 0.
+  "core::accessibility::accessibility_focus_order": I32,
+  /// **Accessibility focusable**
+  /// If attached, this UI entity can be focused with the keyboard: Tab and Shift+Tab (or the arrow keys, once something is focused) move the focus, Enter or Space activate the focused entity and Escape clears the focus.
+  /// Activating an entity sends `accessibility_activate`; the built-in buttons are clicked by it.
+  "core::accessibility::accessibility_focusable": Empty,
+  /// **Accessibility label**
+  /// The name screen readers read out for this UI entity, in place of its text. Entities with a label but no role are exposed as groups.
+  "core::accessibility::accessibility_label": String,
+  /// **Accessibility role**
+  /// Exposes this UI entity to screen readers as an element with this role: one of `button`, `checkbox`, `link`, `slider`, `text_input`, `label`, `heading`, `image`, `group`, `list`, `list_item`, `dialog`, `menu`, `menu_item` or `tab`.
+  /// Its name is its `accessibility_label`, or else the text of its descendants. Text entities without a role are exposed as labels.
+  "core::accessibility::accessibility_role": String,
+  /// **Color vision deficiency**
+  /// The color vision deficiency the final image is filtered for: one of `protanopia`, `deuteranopia`, `tritanopia` or `achromatopsia`.
+  /// If empty, no filter is applied. Game code can use this to pick distinguishable team colors.
+  "core::accessibility::color_vision_deficiency": String,
+  /// **Color vision simulate**
+  /// If true, the color vision filter simulates the deficiency instead of correcting for it.
+  /// This is intended for developers checking that their content is readable.
+  "core::accessibility::color_vision_simulate": Bool,
+  /// **High contrast**
+  /// Whether the user has requested a high-contrast UI. The built-in UI elements respond to this.
+  "core::accessibility::high_contrast": Bool,
+  /// **Avatar head target**
+  /// If attached to the root of a model with a humanoid skeleton, the skeleton is posed as a full body that follows this entity,
+  /// such as the tracked headset of a player in XR. The hips are moved under the head and turned the way it faces, the spine is bent
+  /// so that the head reaches the target, and the legs step after the body so that the feet stay on the ground.
+  /// The skeleton's `animation_binder` must have the `Hips`, `Head`, `LeftFoot` and `RightFoot` joints.
+  /// This is solved on the clients, so if the target is networked, other players see the avatar move.
+  "core::animation::avatar_head_target": EntityId,
+  /// **Avatar left hand target**
+  /// If attached to a model with an `avatar_head_target`, the left arm reaches for this entity, such as the left controller of a player in XR.
+  /// The `LeftHand` joint takes the entity's world rotation.
+  "core::animation::avatar_left_hand_target": EntityId,
+  /// **Avatar right hand target**
+  /// If attached to a model with an `avatar_head_target`, the right arm reaches for this entity, such as the right controller of a player in XR.
+  /// The `RightHand` joint takes the entity's world rotation.
+  "core::animation::avatar_right_hand_target": EntityId,
+  /// **Avatar step length**
+  /// How far the body of an avatar with an `avatar_head_target` can move away from a foot before the foot steps after it, in meters.
+  /// Defaults to 0.3.
+  "core::animation::avatar_step_length": F32,
+  /// **IK chain length**
+  /// The number of joints above this one that inverse kinematics may rotate. With 2 (the default), an exact two-bone solver is used,
+  /// which suits arms and legs; longer chains, like spines and tails, are solved with FABRIK.
+  "core::animation::ik_chain_length": U32,
+  /// **IK ground probe**
+  /// If attached to an entity, a ray is cast down through it every frame, and its `ik_target_position` is set to where it
+  /// hits the ground, raised by this height. The ray starts the entity's `ik_ground_probe_range` above it and reaches as far below it.
+  /// If it hits nothing, `ik_target_position` is removed.
+  /// The probe needs the physics scene, so it only runs on the server. To make a foot stand on uneven ground, attach this to a networked
+  /// entity that follows the character under the foot, and make it the foot's `ik_target`.
+  "core::animation::ik_ground_probe": F32,
+  /// **IK ground probe range**
+  /// How far above and below its entity `ik_ground_probe` looks for the ground, in meters. Defaults to 0.5.
+  "core::animation::ik_ground_probe_range": F32,
+  /// **IK pole**
+  /// The world position that the middle joint of a two-bone chain bends towards, such as a point in front of the knee.
+  /// If not attached, the chain keeps bending in the direction it is animated in.
+  "core::animation::ik_pole": Vec3,
+  /// **IK target**
+  /// If attached to a joint, inverse kinematics bends the joints above it so that it reaches the world position of this entity,
+  /// and gives the joint the entity's world rotation. This is applied after the animations are sampled, so it works on top of them;
+  /// use it to place hands on tracked controllers or objects that are being grabbed.
+  /// If the entity has an `ik_target_position`, such as one set by `ik_ground_probe`, the joint reaches that position instead and keeps its animated rotation.
+  "core::animation::ik_target": EntityId,
+  /// **IK target position**
+  /// If attached to a joint that has no `ik_target`, inverse kinematics bends the joints above it so that it reaches this world position.
+  /// The joint keeps its animated rotation. `ik_ground_probe` sets this.
+  "core::animation::ik_target_position": Vec3,
+  /// **IK weight**
+  /// How much inverse kinematics overrides the animation of this chain, from 0 to 1. Defaults to 1.
+  "core::animation::ik_weight": F32,
+  /// **Cursor position**
+  /// Absolute mouse cursor position in screen-space. This is the _logical_ position. Multiply by the `window_scale_factor` to get the physical position.
+  "core::app::cursor_position": Vec2,
+  /// **Description**
+  /// A human-friendly description for this entity.
+  "core::app::description": String,
+  /// **Delta Time**
+  /// How long the previous tick took in seconds.
+  /// Also known as frametime.
+  "core::app::dtime": F32,
+  /// **Element**
+  /// The identifier of the `Element` that controls this entity.
+  /// This is automatically generated by `ElementTree`.
+  "core::app::element": String,
+  /// **Element unmanaged children**
+  /// If this is set, the user is expected to manage the children of the `Element` themselves.
+  "core::app::element_unmanaged_children": Empty,
+  /// **Main scene**
+  /// If attached, this entity belongs to the main scene.
+  "core::app::main_scene": Empty,
+  /// **Map seed**
+  /// A random number seed for this map.
+  "core::app::map_seed": U64,
+  /// **Name**
+  /// A human-friendly name for this entity.
+  "core::app::name": String,
+  /// **Project Name**
+  /// The name of the project, from the manifest.
+  /// Defaults to "Ambient".
+  "core::app::project_name": String,
+  /// **Selectable**
+  /// If attached, this object can be selected in the editor.
+  "core::app::selectable": Empty,
+  /// **Snap to ground**
+  /// This object should automatically be moved with the terrain if the terrain is changed.
+  /// The value is the offset from the terrain.
+  "core::app::snap_to_ground": F32,
+  /// **Tags**
+  /// Tags for categorizing this entity.
+  "core::app::tags": String[],
+  /// **UI scene**
+  /// If attached, this entity belongs to the UI scene.
+  "core::app::ui_scene": Empty,
+  /// **Window logical size**
+  /// The logical size is the physical size divided by the scale factor.
+  "core::app::window_logical_size": Uvec2,
+  /// **Window physical size**
+  /// The physical size is the actual number of pixels on the screen.
+  "core::app::window_physical_size": Uvec2,
+  /// **Window scale factor**
+  /// The DPI/pixel scale factor of the window.
+  /// On standard displays, this is 1, but it can be higher on high-DPI displays like Apple Retina displays.
+  "core::app::window_scale_factor": F64,
+  /// **Active snapshot**
+  /// The name of the mixer `snapshot` whose bus volumes are applied, over its `snapshot_transition`.
+  /// If empty or not attached, no snapshot is applied.
+  "core::audio::active_snapshot": String,
+  /// **Bus**
+  /// If attached, this entity configures the mixer bus with this name with its other `bus_*` components.
+  /// When the entity is removed, the bus goes back to its defaults.
+  "core::audio::bus": String,
+  /// **Bus compressor**
+  /// If attached, the bus is compressed: its gain is reduced by the ratio `y` as far as its level goes over the threshold `x` in dB.
+  /// The compressor reacts within the attack time `z` and recovers within the release time `w`, in seconds. It comes after the `bus_eq`.
+  "core::audio::bus_compressor": Vec4,
+  /// **Bus EQ**
+  /// If attached, the bus is equalized with these gains in dB of the frequencies below 250 Hz, around 1 kHz and above 4 kHz.
+  "core::audio::bus_eq": Vec3,
+  /// **Bus parent**
+  /// The name of the bus this bus is mixed into. Defaults to `master`. Buses can't be mixed into themselves through other buses.
+  "core::audio::bus_parent": String,
+  /// **Bus reverb**
+  /// If attached, the bus is reverberated with the room size `x` and the damping `y`, both from 0 to 1.
+  /// `z` is how much of the reverberated signal is mixed in, from 0 to 1. It comes after the `bus_compressor`.
+  "core::audio::bus_reverb": Vec3,
+  /// **Bus sounds**
+  /// The sounds whose URL contains any of these strings, like `music/`, play on this bus.
+  /// Sounds that don't match any bus play on the `sfx` bus.
+  "core::audio::bus_sounds": String[],
+  /// **Bus volume**
+  /// The volume of the bus, as a multiplier. Defaults to 1. It is multiplied with the volume of the active snapshot and the user's volume settings.
+  "core::audio::bus_volume": F32,
+  /// **Bus volume transition**
+  /// How many seconds changes to the `bus_volume` take to fade in. Defaults to 0.
+  "core::audio::bus_volume_transition": F32,
+  /// **Snapshot**
+  /// If attached, this entity is a mixer snapshot with this name: a set of bus volumes that is applied while it is the `active_snapshot`, like a quieter mix while the game is paused.
+  /// The volumes are given by `snapshot_buses` and `snapshot_volumes`; buses that aren't in the snapshot keep a volume of 1.
+  "core::audio::snapshot": String,
+  /// **Snapshot buses**
+  /// The names of the buses whose volumes the snapshot sets, in the same order as `snapshot_volumes`.
+  "core::audio::snapshot_buses": String[],
+  /// **Snapshot transition**
+  /// How many seconds the bus volumes take to fade to the snapshot's when it becomes the `active_snapshot`, or back when it stops being it. Defaults to 0.
+  "core::audio::snapshot_transition": F32,
+  /// **Snapshot volumes**
+  /// The volumes the snapshot sets for the `snapshot_buses`, as multipliers.
+  "core::audio::snapshot_volumes": F32[],
+  /// **Sound propagation**
+  /// Whether sounds travel through the `visibility_cell`s and portals of the scene: when the listener is inside a cell, sounds from other cells come from the portal they reach it through, and are attenuated, delayed and muffled by the length of their path.
+  /// Sounds from cells that can't be reached through the portals are only heard, muffled, through the walls. Defaults to true.
+  "core::audio::sound_propagation": Bool,
+  /// **Behavior tree blackboard keys**
+  /// The keys of this agent's blackboard, which its behavior tree can check and change. The values are in `behavior_tree_blackboard_values`, in the same order.
+  /// Modules can change the blackboard at any time to steer the tree.
+  "core::behavior_tree::behavior_tree_blackboard_keys": String[],
+  /// **Behavior tree blackboard values**
+  /// The values of this agent's blackboard; see `behavior_tree_blackboard_keys`.
+  "core::behavior_tree::behavior_tree_blackboard_values": String[],
+  /// **Behavior tree debug**
+  /// If attached, the running nodes of this agent's behavior tree are shown above it on the clients.
+  "core::behavior_tree::behavior_tree_debug": Empty,
+  /// **Behavior tree from URL**
+  /// Loads a behavior tree definition (a JSON file) from this URL, and runs it on this entity on the server every frame.
+  /// The tree is made of `sequence`, `selector`, `inverter`, `succeeder`, `repeat`, `wait`, `blackboard_is` and `blackboard_set` nodes, and `task` leaves that are implemented by modules (see `behavior_tree_task`).
+  "core::behavior_tree::behavior_tree_from_url": String,
+  /// **Behavior tree running path**
+  /// The running nodes of this agent's behavior tree, from the root down. Only updated while `behavior_tree_debug` is attached.
+  "core::behavior_tree::behavior_tree_running_path": String,
+  /// **Behavior tree task**
+  /// The name of the `task` leaf that this agent's behavior tree is running. Set by the server when the task starts.
+  /// The module that implements the task should attach `behavior_tree_task_result` once it is done; until then, the task keeps running.
+  "core::behavior_tree::behavior_tree_task": String,
+  /// **Behavior tree task result**
+  /// Attach this to finish the running `behavior_tree_task`; `true` if it succeeded, and `false` if it failed.
+  "core::behavior_tree::behavior_tree_task_result": Bool,
+  /// **Active camera**
+  /// The camera with the highest `active_camera` value will be used for rendering. Cameras are also filtered by the `user_id`.
+  /// If there's no `user_id`, the camera is considered global and potentially applies to all users (if its `active_camera` value is high enough).
+  "core::camera::active_camera": F32,
+  /// **Aperture**
+  /// The aperture of this camera as an f-number, like `2.8` for f/2.8. Lower f-numbers let in more light, and make the
+  /// depth of field shallower when `focus_distance` is set.
+  /// Together with `shutter_speed` and `iso`, this sets the exposure of the image; the missing ones default to f/16,
+  /// 1/125 s and ISO 100, which is the exposure the scene lighting is made for.
+  "core::camera::aperture": F32,
+  /// **Aspect ratio**
+  /// The aspect ratio of this camera.
+  /// If `aspect_ratio_from_window` is set, this will be automatically updated to match the window.
+  "core::camera::aspect_ratio": F32,
+  /// **Aspect ratio from window**
+  /// If attached, the `aspect_ratio` component will be automatically updated to match the aspect ratio of the window. Should point to an entity with a `window_physical_size` component.
+  "core::camera::aspect_ratio_from_window": EntityId,
+  /// **Far plane**
+  /// The far plane of this camera, measured in meters.
+  "core::camera::far": F32,
+  /// **Focus distance**
+  /// If attached, this camera has a depth of field, and is in focus at this distance, measured in meters. Everything
+  /// closer or further away is blurred depending on the `aperture` and `fovy`, like with a 35mm camera.
+  /// Only perspective cameras have a depth of field.
+  "core::camera::focus_distance": F32,
+  /// **Fog**
+  /// If attached, this camera will see/render fog.
+  "core::camera::fog": Empty,
+  /// **Field of View Y**
+  /// The field of view of this camera in the Y/vertical direction, measured in radians.
+  "core::camera::fovy": F32,
+  /// **ISO**
+  /// The sensitivity of this camera, in ISO. Doubling it doubles the brightness of the image.
+  /// See `aperture`.
+  "core::camera::iso": F32,
+  /// **Motion blur**
+  /// If attached, this camera blurs the image along the motion of the camera, like a film camera with this shutter angle,
+  /// measured in degrees: `180` blurs over half of the frame time, and `360` over all of it.
+  /// The motion of the camera is reconstructed from the depth buffer, so objects that move on their own are not blurred.
+  "core::camera::motion_blur": F32,
+  /// **Near plane**
+  /// The near plane of this camera, measured in meters.
+  "core::camera::near": F32,
+  /// **Orthographic projection**
+  /// If attached, this camera will use a standard orthographic projection matrix.
+  /// Ensure that the `orthographic_` components are set, including `left`, right`, `top` and `bottom`, as well as `near` and `far`.
+  "core::camera::orthographic": Empty,
+  /// **Orthographic bottom**
+  /// The bottom bound for this `orthographic` camera.
+  "core::camera::orthographic_bottom": F32,
+  /// **Orthographic from window**
+  /// The bounds of this orthographic camera will be updated to match the window automatically. Should point to an entity with a `window_logical_size` component.
+  "core::camera::orthographic_from_window": EntityId,
+  /// **Orthographic left**
+  /// The left bound for this `orthographic` camera.
+  "core::camera::orthographic_left": F32,
+  /// **Orthographic right**
+  /// The right bound for this `orthographic` camera.
+  "core::camera::orthographic_right": F32,
+  /// **Orthographic top**
+  /// The top bound for this `orthographic` camera.
+  "core::camera::orthographic_top": F32,
+  /// **Perspective projection**
+  /// If attached, this camera will use a standard perspective projection matrix.
+  /// Ensure that `near` and `far` are set.
+  "core::camera::perspective": Empty,
+  /// **Perspective-infinite-reverse projection**
+  /// If attached, this camera will use a perspective-infinite-reverse projection matrix.
+  /// This is well-suited for rendering large worlds as it has no far plane. Ensure `near` is set.
+  "core::camera::perspective_infinite_reverse": Empty,
+  /// **Projection**
+  /// The projection matrix of this camera.
+  /// This can be driven by other components, including `perspective` and `perspective_infinite_reverse`.
+  "core::camera::projection": Mat4,
+  /// **Projection-view**
+  /// The composition of the projection and view (inverse-local-to-world) matrices.
+  "core::camera::projection_view": Mat4,
+  /// **Shadows far plane**
+  /// The far plane for the shadow camera, measured in meters.
+  "core::camera::shadows_far": F32,
+  /// **Shutter speed**
+  /// The time the shutter of this camera is open for, measured in seconds, like `0.008` for 1/125 s. Doubling it doubles
+  /// the brightness of the image.
+  /// See `aperture`.
+  "core::camera::shutter_speed": F32,
+  /// **Caption**
+  /// If attached, this entity will display a caption with the given text for as long as it exists.
+  /// If the entity also has a `translation`, the caption will point towards it when it is off-screen.
+  "core::captions::caption": String,
+  /// **Caption duration**
+  /// The number of seconds a caption should be shown for.
+  /// Locally-spawned entities with this component will be despawned once it has elapsed.
+  "core::captions::caption_duration": F32,
+  /// **Caption speaker**
+  /// The name of the speaker of this caption. It is shown before the caption text.
+  "core::captions::caption_speaker": String,
+  /// **Caption speaker color**
+  /// The color the speaker's name is shown in. Defaults to white.
+  "core::captions::caption_speaker_color": Vec3,
+  /// **Caption track from URL**
+  /// Plays back a timed caption track (a WebVTT file) from the URL, starting when this component is attached.
+  /// The currently active cue is written to the `caption` and `caption_speaker` components of this entity.
+  /// Locally-spawned entities with this component will be despawned once the track has finished.
+  "core::captions::caption_track_from_url": String,
+  /// **Captions enabled**
+  /// Whether or not captions are displayed on this client.
+  /// Defaults to the user's accessibility settings, but can be changed at runtime.
+  "core::captions::captions_enabled": Bool,
+  /// **Clipboard read**
+  /// Reads the text in the clipboard, which is sent back to the module in a `ClipboardText` message.
+  "core::clipboard::clipboard_read": Empty,
+  /// **Clipboard write**
+  /// Replaces the contents of the clipboard with this text.
+  "core::clipboard::clipboard_write": String,
+  /// **Dialogue choice**
+  /// Attach this to a conversation to pick the choice with this index in `dialogue_choices`.
+  /// It is removed once the choice has been made, and a `DialogueChoice` message is sent.
+  "core::dialogue::dialogue_choice": U32,
+  /// **Dialogue choices**
+  /// The localization keys of the choices offered for the current line of this conversation.
+  /// If there are none, the line is continued from by choosing 0.
+  "core::dialogue::dialogue_choices": String[],
+  /// **Dialogue from URL**
+  /// Loads a dialogue graph (a TOML file) from this URL, and starts a conversation with it on the server.
+  /// While the conversation runs, its current line is in `dialogue_speaker`, `dialogue_text` and `dialogue_choices`. Once it ends, this entity is despawned.
+  "core::dialogue::dialogue_from_url": String,
+  /// **Dialogue node**
+  /// The ID of the node of the dialogue graph this conversation is at.
+  "core::dialogue::dialogue_node": String,
+  /// **Dialogue participant**
+  /// The entity (typically a player) taking part in this conversation.
+  /// The conversation's conditions check, and its nodes change, the participant's `dialogue_variable_keys` and `dialogue_variable_values`. Without a participant, the conversation's own variables are used.
+  "core::dialogue::dialogue_participant": EntityId,
+  /// **Dialogue speaker**
+  /// The localization key of the name of the speaker of the current line of this conversation, if it has one.
+  "core::dialogue::dialogue_speaker": String,
+  /// **Dialogue text**
+  /// The localization key of the text of the current line of this conversation.
+  "core::dialogue::dialogue_text": String,
+  /// **Dialogue variable keys**
+  /// The names of this entity's dialogue variables, which conversations check and change. The values are in `dialogue_variable_values`, in the same order.
+  /// Use these to keep track of quest progress; modules can change them at any time.
+  "core::dialogue::dialogue_variable_keys": String[],
+  /// **Dialogue variable values**
+  /// The values of this entity's dialogue variables; see `dialogue_variable_keys`.
+  "core::dialogue::dialogue_variable_values": String[],
+  /// **Children**
+  /// The children of this entity.
+  "core::ecs::children": EntityId[],
+  /// **Don't automatically despawn on module unload**
+  /// Indicates that this entity shouldn't be despawned when the module that spawned it unloads.
+  "core::ecs::dont_despawn_on_unload": Empty,
+  /// **Don't store**
+  /// Indicates that this entity shouldn't be stored on disk.
+  "core::ecs::dont_store": Empty,
+  /// **ID**
+  /// The ID of the entity.
+  "core::ecs::id": EntityId,
+  /// **Parent**
+  /// The parent of this entity.
+  "core::ecs::parent": EntityId,
+  /// **Precipitation**
+  /// How hard it rains or snows, from 0 (not at all) to 1 (a downpour). It snows when the `temperature` is below zero, and rains otherwise.
+  /// Clients render the falling drops or flakes around the camera, and rain on the camera lens when it looks up. Defaults to 0.
+  "core::environment::precipitation": F32,
+  /// **Puddles**
+  /// How full the puddles on flat ground that is open to the sky are, from 0 (none) to 1 (flooded).
+  /// On the server, they fill slowly while it rains and take longer to dry than the `wetness`, so they only need to be set to start off with puddles. Defaults to 0.
+  "core::environment::puddles": F32,
+  /// **Temperature**
+  /// The air temperature, in degrees Celsius. Defaults to 20.
+  "core::environment::temperature": F32,
+  /// **Wetness**
+  /// How wet the world is, from 0 (dry) to 1 (soaked). Wet surfaces that are open to the sky are darker and shinier.
+  /// On the server, it rises while it rains and falls while it doesn't, so it only needs to be set to start off wet. Defaults to 0.
+  "core::environment::wetness": F32,
+  /// **Wind direction**
+  /// The direction the wind blows towards. It does not need to be normalized. Defaults to +X.
+  "core::environment::wind_direction": Vec3,
+  /// **Wind gustiness**
+  /// How much the wind varies in gusts that travel along it, from 0 (a steady wind) to 1 (gusts that range from no wind to twice the `wind_strength`).
+  /// Defaults to 0.5.
+  "core::environment::wind_gustiness": F32,
+  /// **Wind strength**
+  /// The average speed of the wind, in meters per second. Defaults to 0.
+  "core::environment::wind_strength": F32,
+  /// **Cursor confined**
+  /// If set to true on the resources entity of a client, the cursor can't leave the window.
+  /// Where the platform can't confine the cursor, it is locked instead. The cursor is released while the window isn't focused.
+  "core::input::cursor_confined": Bool,
+  /// **Cursor image hotspot**
+  /// The point of the `cursor_image_url` that is at the position of the cursor, in logical pixels from its top-left corner. Defaults to the top-left corner.
+  "core::input::cursor_image_hotspot": Vec2,
+  /// **Cursor image size**
+  /// The size of the `cursor_image_url` in logical pixels. Defaults to 32x32.
+  "core::input::cursor_image_size": Vec2,
+  /// **Cursor image URL**
+  /// If set on the resources entity of a client, the cursor is drawn with the image at this URL instead of the system cursor, at the `cursor_image_size`.
+  /// The image is drawn in the game view, so it is only shown while the cursor is over it.
+  "core::input::cursor_image_url": String,
+  /// **Cursor locked**
+  /// If set to true on the resources entity of a client, the cursor is locked in place and hidden, and only the mouse motion is reported, for mouse look.
+  /// Where the platform can't lock the cursor, it is confined to the window instead. The cursor is released while the window isn't focused.
+  "core::input::cursor_locked": Bool,
+  /// **Cursor visible**
+  /// If set to false on the resources entity of a client, the cursor is hidden while it's over the window.
+  "core::input::cursor_visible": Bool,
+  /// **Mouse over**
+  /// The number of mouse cursors that are currently over this entity.
+  "core::input::mouse_over": U32,
+  /// **Mouse passthrough**
+  /// If attached, the mouse is never over this entity, and clicks reach what is behind it. UI transitions attach it to their elements while they are hidden.
+  "core::input::mouse_passthrough": Empty,
+  /// **Mouse pickable max**
+  /// This entity can be clicked by the mouse, and this component defines the max AABB bound of the click area.
+  "core::input::mouse_pickable_max": Vec3,
+  /// **Mouse pickable min**
+  /// This entity can be clicked by the mouse, and this component defines the min AABB bound of the click area.
+  "core::input::mouse_pickable_min": Vec3,
+  /// **Touch IDs**
+  /// The identifiers of the fingers that are currently on the touch screen, in the order they touched it.
+  /// Each finger keeps its identifier until it is lifted; its position is at the same index in `touch_positions`.
+  "core::input::touch_ids": U64[],
+  /// **Touch positions**
+  /// The positions of the fingers in `touch_ids`, in logical pixels like the `cursor_position`.
+  "core::input::touch_positions": Vec2[],
+  /// **Instance create**
+  /// Makes this a request to create an instance with this ID.
+  /// The new instance starts as a copy of the instance the request was spawned in, without its players, and its modules are restarted. See also `instance_modules`.
+  "core::instance::instance_create": String,
+  /// **Instance destroy**
+  /// Makes this a request to destroy the instance with this ID. The players in it are moved to the `main` instance, which can't be destroyed.
+  "core::instance::instance_destroy": String,
+  /// **Instance error**
+  /// Attached to a request once it is done: an empty string if it succeeded, or the reason it failed.
+  "core::instance::instance_error": String,
+  /// **Instance ID**
+  /// The ID of the instance this world is. Attached to the synchronized resources of each instance, so that clients know it too.
+  "core::instance::instance_id": String,
+  /// **Instance idle tick rate**
+  /// If this resource is attached, the instance is simulated this many times per second while there are no players in it, such as 1 for a dungeon that is waiting for players, instead of at its `instance_tick_rate`.
+  /// This keeps servers with many instances cheap to run.
+  "core::instance::instance_idle_tick_rate": F32,
+  /// **Instance IDs**
+  /// The IDs of the instances, sorted, attached to an `instance_list` request.
+  "core::instance::instance_ids": String[],
+  /// **Instance list**
+  /// Makes this a request to list the instances. Their IDs are attached as `instance_ids`.
+  "core::instance::instance_list": Empty,
+  /// **Instance modules**
+  /// The names of the modules to run in the instance created by an `instance_create` request; the other modules are disabled in it. If this is not attached, the new instance runs the same modules as the one it was created from.
+  /// Also attached as a resource to the worlds of the instances that were created with it.
+  "core::instance::instance_modules": String[],
+  /// **Instance move**
+  /// Makes this a request to move the player with the `instance_user` to the instance with this ID.
+  /// The player entity is despawned from its current instance and spawned in the new one, so it gets a new entity ID.
+  "core::instance::instance_move": String,
+  /// **Instance tick rate**
+  /// How many times per second the world of this instance is simulated, between 0.1 and 240. If this resource is not attached, the instance is simulated 60 times per second.
+  /// The `dtime` resource is the time since the previous tick, so systems that use it adapt to the tick rate.
+  "core::instance::instance_tick_rate": F32,
+  /// **Instance user**
+  /// The user ID of the player an `instance_move` request is for.
+  "core::instance::instance_user": String,
+  /// **Align horizontal begin**
+  /// Layout alignment: horizontal begin.
+  "core::layout::align_horizontal_begin": Empty,
+  /// **Align horizontal center**
+  /// Layout alignment: horizontal center.
+  "core::layout::align_horizontal_center": Empty,
+  /// **Align horizontal end**
+  /// Layout alignment: horizontal end.
+  "core::layout::align_horizontal_end": Empty,
+  /// **Align vertical begin**
+  /// Layout alignment: vertical begin.
+  "core::layout::align_vertical_begin": Empty,
+  /// **Align vertical center**
+  /// Layout alignment: vertical center.
+  "core::layout::align_vertical_center": Empty,
+  /// **Align vertical end**
+  /// Layout alignment: vertical end.
+  "core::layout::align_vertical_end": Empty,
+  /// **Docking bottom**
+  /// Layout docking: bottom.
+  "core::layout::docking_bottom": Empty,
+  /// **Docking fill**
+  /// Layout docking: fill.
+  "core::layout::docking_fill": Empty,
+  /// **Docking left**
+  /// Layout docking: left.
+  "core::layout::docking_left": Empty,
+  /// **Docking right**
+  /// Layout docking: right.
+  "core::layout::docking_right": Empty,
+  /// **Docking top**
+  /// Layout docking: top.
+  "core::layout::docking_top": Empty,
+  /// **Fit horizontal children**
+  /// Layout fit: horizontal children.
+  "core::layout::fit_horizontal_children": Empty,
+  /// **Fit horizontal none**
+  /// Layout fit: horizontal none.
+  "core::layout::fit_horizontal_none": Empty,
+  /// **Fit horizontal parent**
+  /// Layout fit: horizontal parent.
+  "core::layout::fit_horizontal_parent": Empty,
+  /// **Fit vertical children**
+  /// Layout fit: vertical children.
+  "core::layout::fit_vertical_children": Empty,
+  /// **Fit vertical none**
+  /// Layout fit: vertical none.
+  "core::layout::fit_vertical_none": Empty,
+  /// **Fit vertical parent**
+  /// Layout fit: vertical parent.
+  "core::layout::fit_vertical_parent": Empty,
+  /// **GPU UI size**
+  /// Upload the width and height of this UI element to the GPU.
+  "core::layout::gpu_ui_size": Vec4,
+  /// **Height**
+  /// The height of a UI element.
+  "core::layout::height": F32,
+  /// **Is book file**
+  /// This is a file in a `layout_bookcase`.
+  "core::layout::is_book_file": Empty,
+  /// **Bookcase layout**
+  /// Min-max bookcase layout.
+  "core::layout::layout_bookcase": Empty,
+  /// **Dock layout**
+  /// Top-down dock layout.
+  "core::layout::layout_dock": Empty,
+  /// **Flow layout**
+  /// Bottom-up flow layout.
+  "core::layout::layout_flow": Empty,
+  /// **Layout width to children**
+  /// Width to children.
+  "core::layout::layout_width_to_children": Empty,
+  /// **Margin**
+  /// Layout margin: [top, right, bottom, left].
+  "core::layout::margin": Vec4,
+  /// **Maximum height**
+  /// The maximum height of a UI element.
+  "core::layout::max_height": F32,
+  /// **Maximum width**
+  /// The maximum width of a UI element.
+  "core::layout::max_width": F32,
+  /// **Mesh to local from size**
+  /// Update the `mesh_to_local` based on the width and height of this entity.
+  "core::layout::mesh_to_local_from_size": Empty,
+  /// **Minimum height**
+  /// The minimum height of a UI element.
+  "core::layout::min_height": F32,
+  /// **Minimum width**
+  /// The minimum width of a UI element.
+  "core::layout::min_width": F32,
+  /// **Orientation horizontal**
+  /// Layout orientation: horizontal.
+  "core::layout::orientation_horizontal": Empty,
+  /// **Orientation vertical**
+  /// Layout orientation: vertical.
+  "core::layout::orientation_vertical": Empty,
+  /// **Padding**
+  /// Layout padding: [top, right, bottom, left].
+  "core::layout::padding": Vec4,
+  /// **Screen**
+  /// This entity will be treated as a screen. Used by the Screen ui component.
+  "core::layout::screen": Empty,
+  /// **Space between items**
+  /// Space between items in a layout.
+  "core::layout::space_between_items": F32,
+  /// **Width**
+  /// The width of a UI element.
+  "core::layout::width": F32,
+  /// **Language**
+  /// The language strings are localized to, as a language code such as `en` or `ja`.
+  /// Changing this at runtime loads the matching table from the project's `locale` assets.
+  "core::localization::language": String,
+  /// **Localization keys**
+  /// The keys of the active string table, in sorted order. The value for each key is at the same index in `localization_values`.
+  "core::localization::localization_keys": String[],
+  /// **Localization values**
+  /// The localized strings of the active string table. See `localization_keys`.
+  "core::localization::localization_values": String[],
+  /// **Minimap bounds**
+  /// The area of the world shown by the minimap, as `(min_x, min_y, max_x, max_y)`.
+  /// Attaching this component to an entity turns the minimap on, and the other minimap components on the same entity configure it. Only one minimap is shown.
+  "core::minimap::minimap_bounds": Vec4,
+  /// **Minimap capture interval**
+  /// The number of seconds between top-down captures of the world for the minimap. Defaults to 5.
+  /// If this is 0, the world is only captured once.
+  "core::minimap::minimap_capture_interval": F32,
+  /// **Minimap fog of war**
+  /// If attached, the minimap is covered by fog, except for the areas that the local player has seen.
+  /// Areas that are not currently visible are dimmed, and the markers in them are hidden. See `minimap_vision_radius`.
+  "core::minimap::minimap_fog_of_war": Empty,
+  /// **Minimap image from URL**
+  /// If attached, this image is shown as the minimap instead of capturing the world. It should cover the `minimap_bounds`.
+  "core::minimap::minimap_image_from_url": String,
+  /// **Minimap marker**
+  /// If attached to an entity with a `translation`, the entity is shown on the minimap as a dot of this color.
+  "core::minimap::minimap_marker": Vec4,
+  /// **Minimap resolution**
+  /// The size in pixels of the longest side of the captured minimap image. Defaults to 512.
+  "core::minimap::minimap_resolution": U32,
+  /// **Minimap size**
+  /// The size in pixels of the longest side of the minimap on the screen. Defaults to 200; 0 hides the minimap.
+  "core::minimap::minimap_size": F32,
+  /// **Minimap vision radius**
+  /// The distance that this entity reveals the fog of war around it.
+  /// Entities with a `user_id` only reveal the fog for that player; other entities reveal it for all players.
+  "core::minimap::minimap_vision_radius": F32,
+  /// **Model animatable**
+  /// Controls whether this model can be animated.
+  "core::model::model_animatable": Bool,
+  /// **Model from URL**
+  /// Load a model from the given URL or relative path.
+  "core::model::model_from_url": String,
+  /// **Model loaded**
+  /// If attached, this entity has a model attached to it.
+  "core::model::model_loaded": Empty,
+  /// **Client authoritative**
+  /// The IDs of the components of this entity (e.g. `my_project::aim_direction`) that are set by the client of its `owner` instead of the server, so that they don't have to make a round trip to the server.
+  /// The owner's client sends their values to the server when they change, and ignores the values the server sends for them. The server ignores the values of clients that don't own the entity, and passes the values of components with validators to them first (see `client_authority_validated`).
+  "core::network::client_authoritative": String[],
+  /// **Client authority accepted**
+  /// If attached to a proposal by a validator, the value of the proposal (which the validator may have changed) is applied to its entity.
+  "core::network::client_authority_accepted": Empty,
+  /// **Client authority corrected**
+  /// If attached to a proposal by a validator, the owner is sent the value its entity has once the proposal is decided on, replacing its own.
+  /// Validators attach this when they change or reject a value, without `client_authority_accepted` when they reject it.
+  "core::network::client_authority_corrected": Empty,
+  /// **Client authority proposal**
+  /// This entity is a value for a client-authoritative component of the entity this points to, which is waiting for its validators.
+  /// It has the `user_id` of the client that sent it, and the proposed value. Proposals that aren't decided on within a second are dropped.
+  "core::network::client_authority_proposal": EntityId,
+  /// **Client authority validated**
+  /// The IDs of the client-authoritative components that have validators on the server.
+  /// Values of these components that are sent by their owners are not applied directly; instead, a proposal entity is spawned and a `ClientAuthorityUpdate` message is sent for the validators to accept, change or reject the value.
+  "core::network::client_authority_validated": String[],
+  /// **Cluster ghost**
+  /// If attached, this entity is a copy of an entity near the border of another shard of the cluster, which has authority over it. Ghosts are kept up to date by that shard, so that the players and systems of this shard can see across the border.
+  /// The changes that this shard makes to a ghost are sent to the shard that owns it, which applies them to the real entity; spawning and despawning ghosts has no effect there.
+  "core::network::cluster_ghost": Empty,
+  /// **Cluster region max**
+  /// The upper corner, on the XY plane, of the region of the world this server has authority over, if it is a shard of a cluster; see `cluster_region_min`.
+  "core::network::cluster_region_max": Vec2,
+  /// **Cluster region min**
+  /// The lower corner, on the XY plane, of the region of the world this server has authority over, if it is a shard of a cluster. Entities that move out of the region into the region of another shard are handed off to that shard.
+  /// Every shard runs the same modules, so they can use this to only spawn the content of their own region.
+  "core::network::cluster_region_min": Vec2,
+  /// **Is remote entity**
+  /// If attached, this entity was not spawned locally (e.g. if this is the client, it was spawned by the server).
+  "core::network::is_remote_entity": Empty,
+  /// **Owner**
+  /// The user ID of the client that owns this entity. The owner's client sets the components of the entity that are listed in `client_authoritative`.
+  /// Ownership is transferred by changing this on the server, and given back to the server by removing it.
+  "core::network::owner": String,
+  /// **Persistent resources**
+  /// If attached, this entity contains global resources that are persisted to disk and synchronized to clients.
+  "core::network::persistent_resources": Empty,
+  /// **Render interpolation**
+  /// Whether the client renders the remote entities that move blended between the last two ticks it received, so that their movement is smooth on displays that refresh faster than the server ticks. This delays their rendered transform by up to a tick. Defaults to true.
+  /// Only the rendered transform is blended; the `translation`, `rotation` and `scale` that modules see are always those of the latest tick.
+  "core::network::render_interpolation": Bool,
+  /// **Replication budget**
+  /// The number of bytes of component updates the server sends to each client per tick. If set, the updates of the entities with the highest accumulated priority are sent first (see `replication_priority`), and the others are delayed; otherwise, every update is sent every tick.
+  /// Spawning and despawning entities and adding and removing components are always sent, and count towards the budget.
+  "core::network::replication_budget": U32,
+  /// **Replication falloff**
+  /// The distance in meters at which the priority of an entity grows half as fast as it does next to the player, and a third as fast at twice the distance, and so on. Defaults to 20.
+  "core::network::replication_falloff": F32,
+  /// **Replication priority**
+  /// How important it is for clients to receive the changes to this entity, relative to other entities. Defaults to 1.
+  /// Only used when the updates are limited by a `replication_budget`: the changes of an entity that isn't sent wait with a priority that grows every tick by this, scaled up for entities that are close to the player, that just changed, or that the player owns.
+  "core::network::replication_priority": F32,
+  /// **Synchronized resources**
+  /// If attached, this entity contains global resources that are synchronized to clients, but not persisted.
+  "core::network::synced_resources": Empty,
+  /// **Teleport count**
+  /// The number of times this entity has been teleported. When it changes in the same tick as the transform of the entity, clients show the entity at its new transform right away, instead of interpolating to it from where it was.
+  /// Use `entity::teleport` to move an entity and raise it together, e.g. when a player respawns or goes through a portal.
+  "core::network::teleport_count": U32,
+  /// **Angular velocity**
+  /// Angular velocity (radians/second) of this entity in the physics scene.
+  /// Updating this component will update the entity's angular velocity in the physics scene.
+  "core::physics::angular_velocity": Vec3,
+  /// **Avoidance desired velocity**
+  /// The velocity this agent wants to move at, e.g. towards the next point of its path.
+  /// If an entity has both this and an `avoidance_radius`, it will be steered around other agents and obstacles, and the resulting velocity is written to its `unit_velocity`.
+  "core::physics::avoidance_desired_velocity": Vec3,
+  /// **Avoidance max speed**
+  /// The maximum speed this agent can move at while avoiding others.
+  /// If not set, the length of its `avoidance_desired_velocity` is used.
+  "core::physics::avoidance_max_speed": F32,
+  /// **Avoidance radius**
+  /// The radius of the circle that this entity takes up on the ground for local avoidance.
+  /// Entities with this but without an `avoidance_desired_velocity` are static obstacles that agents steer around.
+  "core::physics::avoidance_radius": F32,
+  /// **CCD**
+  /// Whether this dynamic body uses swept continuous collision detection: each step, its shapes are swept along their motion, and it stops at the first thing they would hit.
+  /// This keeps fast objects, like thrown grenades, from tunneling through thin walls. Defaults to `true`; it has no effect on kinematic bodies, which can use `ccd_speculative` instead.
+  "core::physics::ccd": Bool,
+  /// **CCD min advance**
+  /// How far, as a fraction of its smallest dimension, swept CCD lets this body advance into what it hits, from 0 to 1. Defaults to 0.15.
+  /// Lower values stop it closer to the surface, so that less slips through, but it can get stuck or jitter against it.
+  "core::physics::ccd_min_advance": F32,
+  /// **CCD shapes**
+  /// Whether the shapes of this entity are tested by the swept CCD of other bodies. Defaults to `true`.
+  /// Turn it off for things that fast bodies don't need to stop at, like debris or foliage, to save the cost of the sweeps.
+  "core::physics::ccd_shapes": Bool,
+  /// **CCD speculative**
+  /// Whether this body uses speculative continuous collision detection: contacts are created ahead of time with everything within the distance it moves in a step.
+  /// It is cheaper than swept `ccd`, works for kinematic and quickly rotating bodies, and can be combined with it, but can make bodies bump into things they only pass by. Defaults to `false`.
+  "core::physics::ccd_speculative": Bool,
+  /// **Character controller height**
+  /// The height of the physics character controller attached to this entity.
+  /// If an entity has both this and a `character_controller_radius`, it will be given a physical character collider.
+  "core::physics::character_controller_height": F32,
+  /// **Character controller radius**
+  /// The radius of the physics character controller attached to this entity.
+  /// If an entity has both this and a `character_controller_height`, it will be given a physical character collider.
+  "core::physics::character_controller_radius": F32,
+  /// **Collider from URL**
+  /// This entity will load its physics collider from the URL.
+  /// The value is the URL to load from.
+  "core::physics::collider_from_url": String,
+  /// **Collider loaded**
+  /// This component is automatically attached to an entity once the collider has been loaded (through e.g. `collider_from_url`).
+  "core::physics::collider_loaded": Empty,
+  /// **Collider loads**
+  /// Contains all colliders that were loaded in this physics tick.
+  "core::physics::collider_loads": EntityId[],
+  /// **Contact offset**
+  /// Contact offset (in meters) of this entity in the physics scene.
+  /// Updating this component will update the entity's contact offset for each attached shape in the physics scene.
+  "core::physics::contact_offset": F32,
+  /// **Cube collider**
+  /// If attached, this entity will have a cube physics collider.
+  /// `x, y, z` is the size of the cube.
+  "core::physics::cube_collider": Vec3,
+  /// **Density**
+  /// The density of this entity.
+  /// This is used to update the `mass` when the entity is rescaled.
+  "core::physics::density": F32,
+  /// **Dynamic**
+  /// If this is true, the entity will be dynamic (i.e. be able to move). Otherwise, it will be static.
+  "core::physics::dynamic": Bool,
+  /// **Kinematic**
+  /// If attached, and this entity is dynamic, this entity will also be kinematic (i.e. unable to be affected by other entities motion). Otherwise, it will receive forces normally.
+  "core::physics::kinematic": Empty,
+  /// **Lag compensated**
+  /// If attached, the recent positions of this entity's colliders (or character controller) are recorded on the server, so that `raycast_rewound` can test against where a shooter saw them.
+  /// Entities without it are always tested at their current position.
+  "core::physics::lag_compensated": Empty,
+  /// **Linear velocity**
+  /// Linear velocity (meters/second) of this entity in the physics scene.
+  /// Updating this component will update the entity's linear velocity in the physics scene.
+  "core::physics::linear_velocity": Vec3,
+  /// **Make physics static**
+  /// All physics objects will be made static when loaded.
+  "core::physics::make_physics_static": Bool,
+  /// **Mass**
+  /// The mass of this entity, measured in kilograms.
+  "core::physics::mass": F32,
+  /// **Max depenetration velocity**
+  /// The fastest this dynamic body is pushed out of the shapes it overlaps, in meters per second.
+  /// Limiting it keeps bodies that CCD caught deep inside a wall from being shot out of it. Defaults to no limit.
+  "core::physics::max_depenetration_velocity": F32,
+  /// **Physics controlled**
+  /// If attached, this entity will be controlled by physics.
+  /// Note that this requires the entity to have a collider.
+  "core::physics::physics_controlled": Empty,
+  /// **Plane collider**
+  /// If attached, this entity will have a plane physics collider.
+  "core::physics::plane_collider": Empty,
+  /// **Raycast rewound direction**
+  /// The normalized direction of a lag-compensated raycast request; see `raycast_rewound_origin`.
+  "core::physics::raycast_rewound_direction": Vec3,
+  /// **Raycast rewound hit distances**
+  /// The distances to the entities in `raycast_rewound_hit_entities`, sorted from nearest to furthest.
+  /// Attached to the request on the server once the raycast has been done.
+  "core::physics::raycast_rewound_hit_distances": F32[],
+  /// **Raycast rewound hit entities**
+  /// The entities hit by a lag-compensated raycast request, in the same order as `raycast_rewound_hit_distances`.
+  "core::physics::raycast_rewound_hit_entities": EntityId[],
+  /// **Raycast rewound latency**
+  /// How many seconds to rewind the `lag_compensated` entities by for a lag-compensated raycast request; typically the shooter's latency.
+  /// Clamped to one second. Defaults to 0 if not attached.
+  "core::physics::raycast_rewound_latency": F32,
+  /// **Raycast rewound origin**
+  /// Requests a raycast on the server from this origin in `raycast_rewound_direction`, against `lag_compensated` entities where they were `raycast_rewound_latency` seconds ago.
+  /// Once done, `raycast_rewound_hit_entities` and `raycast_rewound_hit_distances` are attached to this entity.
+  "core::physics::raycast_rewound_origin": Vec3,
+  /// **Rest offset**
+  /// Rest offset (in meters) of this entity in the physics scene.
+  /// Updating this component will update the entity's rest offset for each attached shape in the physics scene.
+  "core::physics::rest_offset": F32,
+  /// **Scene query directions**
+  /// The normalized directions of the queries of a batch, in the same order as `scene_query_origins`.
+  "core::physics::scene_query_directions": Vec3[],
+  /// **Scene query hit distances**
+  /// The distance to the first hit of each query of a batch, in the same order as `scene_query_origins`, or -1 if the query hit nothing.
+  /// Attached to the request on the server once the queries have been done.
+  "core::physics::scene_query_hit_distances": F32[],
+  /// **Scene query hit entities**
+  /// The first entity hit by each query of a batch, in the same order as `scene_query_hit_distances`, or the null entity if the query hit nothing.
+  "core::physics::scene_query_hit_entities": EntityId[],
+  /// **Scene query ignored**
+  /// Entities whose colliders the queries of a batch pass through, like the agents that are looking. Defaults to none.
+  "core::physics::scene_query_ignored": EntityId[],
+  /// **Scene query max distances**
+  /// How far each query of a batch goes, in the same order as `scene_query_origins`. Queries without one go as far as the physics scene allows.
+  "core::physics::scene_query_max_distances": F32[],
+  /// **Scene query origins**
+  /// Requests a batch of queries against the colliders of the physics scene on the server, one from each of these origins along the `scene_query_directions` with the same index.
+  /// The queries of all batches are done in parallel at the end of the frame, and then `scene_query_hit_entities` and `scene_query_hit_distances` are attached to this entity.
+  "core::physics::scene_query_origins": Vec3[],
+  /// **Scene query radius**
+  /// If attached, the queries of a batch sweep a sphere of this radius instead of casting rays, and their distances are how far the center of the sphere got.
+  "core::physics::scene_query_radius": F32,
+  /// **Sleep threshold**
+  /// The kinetic energy per unit of mass below which this dynamic body can fall asleep. Defaults to 0.005, which is PhysX's default.
+  /// Raise it for debris and props that should come to rest quickly, so that they stop being simulated and sent to the clients sooner.
+  "core::physics::sleep_threshold": F32,
+  /// **Sleeping**
+  /// Whether this dynamic body has come to rest and is no longer simulated. It is set by the server, and replicated to the clients; while it is `true`, the body doesn't move, so none of its transform is sent.
+  /// The server is the authority for it: the clients never simulate the body, and only follow this value. The body is woken on the server when something disturbs it: an awake body touches it, or its transform or velocity is set (including by a client that owns it).
+  /// Setting it to `true` on the server puts the body to sleep, and setting it to `false` wakes it. `PhysicsSleep` and `PhysicsWake` messages are sent when it changes.
+  "core::physics::sleeping": Bool,
+  /// **Sphere collider**
+  /// If attached, this entity will have a sphere physics collider.
+  /// The value corresponds to the radius of the sphere.
+  "core::physics::sphere_collider": F32,
+  /// **Surface effect**
+  /// If attached, this entity is a one-off effect of this kind (e.g. `"footstep"` or `"impact"`) on a surface at its `translation`.
+  /// The sounds, decals and prefabs that the `surface_effects_kind` entities map the kind and its `surface_effect_material` to are played for it, and it is despawned on the frame it is spawned on.
+  /// On the server, the effect is sent to all clients; on a client, it is only played there.
+  "core::physics::surface_effect": String,
+  /// **Surface effect material**
+  /// The `surface_material` of the surface the `surface_effect` happens on. Effects without one, or with one that isn't mapped, use the mapping of the `default` material.
+  "core::physics::surface_effect_material": String,
+  /// **Surface effect normal**
+  /// The normal of the surface the `surface_effect` happens on, which its decals are projected along. Defaults to up.
+  "core::physics::surface_effect_normal": Vec3,
+  /// **Surface effects decal size**
+  /// The width of the decals of the `surface_effects_kind`, in meters. Defaults to 0.3.
+  "core::physics::surface_effects_decal_size": F32,
+  /// **Surface effects decals**
+  /// The URLs of the decal materials of the `surface_effects_kind`. One of them, picked at random, is projected onto the surface.
+  "core::physics::surface_effects_decals": String[],
+  /// **Surface effects kind**
+  /// If attached, this entity maps the `surface_effect`s of this kind on the `surface_effects_material` to the effects that are played for them: `surface_effects_sounds`, `surface_effects_decals` and `surface_effects_prefabs`.
+  /// This lets games describe the footsteps and impacts of each material in data, for example in a prefab that is spawned on start.
+  "core::physics::surface_effects_kind": String,
+  /// **Surface effects lifetime**
+  /// How many seconds the decals and prefabs of the `surface_effects_kind` stay before they are despawned. Defaults to 30.
+  "core::physics::surface_effects_lifetime": F32,
+  /// **Surface effects material**
+  /// The `surface_material` that the `surface_effects_kind` is mapped for. `default` is used for the materials that have no mapping of their own.
+  "core::physics::surface_effects_material": String,
+  /// **Surface effects prefabs**
+  /// The URLs of the prefabs, like bursts of dust or sparks, of the `surface_effects_kind`. One of them, picked at random, is spawned at the effect, facing along its normal.
+  "core::physics::surface_effects_prefabs": String[],
+  /// **Surface effects sounds**
+  /// The URLs of the sounds of the `surface_effects_kind`. One of them, picked at random, is played at the effect.
+  "core::physics::surface_effects_sounds": String[],
+  /// **Surface material**
+  /// The name of the material of this entity's colliders (e.g. `"metal"` or `"wood"`), for games to pick impact effects and sounds with.
+  /// Children without one of their own use their parent's. It is included in `ProjectileImpact` messages, and mapped to effects by the `surface_effects_kind` entities.
+  "core::physics::surface_material": String,
+  /// **Unit mass**
+  /// The mass of a character/unit.
+  "core::physics::unit_mass": F32,
+  /// **Unit velocity**
+  /// The velocity of a character/unit.
+  "core::physics::unit_velocity": Vec3,
+  /// **Unit yaw**
+  /// The yaw of a character/unit.
+  "core::physics::unit_yaw": F32,
+  /// **Visualizing**
+  /// If attached, the physics state of this object will be rendered for debugging purposes.
+  "core::physics::visualizing": Empty,
+  /// **Local user ID**
+  /// The user ID of the local player.
+  "core::player::local_user_id": String,
+  /// **Module log receiver**
+  /// If attached to a player entity on the server, the logs of the server modules are forwarded to the player's client, where they are shown in the module console of the debugger.
+  /// Only attach this to players that are trusted to see them, such as the developers of the project.
+  "core::player::module_log_receiver": Empty,
+  /// **Player**
+  /// This entity is a player.
+  /// Note that this is a logical construct; a player's body may be separate from the player itself.
+  "core::player::player": Empty,
+  /// **Player suspended**
+  /// Attached to a player on the server when its connection was lost unexpectedly, while the server waits for its client to reconnect. The player entity and its components are kept, and are given back to the client if it reconnects within the `reconnect_grace_period`; otherwise, the player is despawned.
+  /// The `PlayerSuspended` and `PlayerResumed` messages are sent to the server modules when this is attached and removed.
+  "core::player::player_suspended": Empty,
+  /// **Reconnect grace period**
+  /// How long, in seconds, the players of this server instance are kept after their connection was lost unexpectedly, for their clients to reconnect. If this resource is not attached, they are kept for 30 seconds.
+  /// Players that disconnect on purpose are despawned immediately.
+  "core::player::reconnect_grace_period": F32,
+  /// **Rich presence**
+  /// If set on the resources entity of a client, what the local player is doing in the game, e.g. "In the lobby". It is shown to their friends on the platforms that support it, such as Steam and Discord when the runtime is built with the `steam` and `discord` features.
+  /// Removing it clears the status.
+  "core::player::rich_presence": String,
+  /// **Rich presence party max**
+  /// If set on the resources entity of a client, how many players the party of the local player can have. Shown along with the `rich_presence_party_size`.
+  "core::player::rich_presence_party_max": U32,
+  /// **Rich presence party size**
+  /// If set on the resources entity of a client, how many players are in the party of the local player, e.g. their team or the players on the server. It is shown along with the `rich_presence` on the platforms that support it, such as Discord and Steam.
+  /// Friends can only ask to join the party on Discord while this is set.
+  "core::player::rich_presence_party_size": U32,
+  /// **Spectator**
+  /// This entity is a spectator: a client that joined with `--spectate`, which receives the state of the world but can't take part in it.
+  /// Spectators have a `user_id` but no `player` component, so they are not picked up by the logic for players. Their client controls a spectator camera, which can fly around freely or follow the players.
+  "core::player::spectator": Empty,
+  /// **Spectator hidden**
+  /// If attached to an entity on the server, it is not sent to spectators, e.g. to hide the positions of a team's units from spectators that could tell its opponents.
+  /// Spectators that can already see the entity have it despawned when this is attached, and spawned again when it is removed.
+  "core::player::spectator_hidden": Empty,
+  /// **User ID**
+  /// An identifier attached to all things owned by a user, and supplied by the user.
+  /// This can be attached to more than just the player; by convention, it is also attached to related entities, including their camera and body.
+  "core::player::user_id": String,
+  /// **Post-process error**
+  /// Set by the client when this pass fails to validate or compile, and removed once it succeeds.
+  "core::post_processing::post_process_error": String,
+  /// **Post-process order**
+  /// The order of this pass within its stage; lower values run first. Defaults to 0.
+  "core::post_processing::post_process_order": I32,
+  /// **Post-process shader**
+  /// If attached, this entity is a post-process pass that runs the given WGSL on every pixel of the client's image.
+  /// The source must define `fn post_process(uv: vec2<f32>) -> vec4<f32>`, which returns the new color of the pixel at `uv`.
+  /// It can use `sample_scene(uv)` to read the image, `params.time` and `params.resolution`, and the declared uniforms and textures.
+  /// If the source fails to compile, `post_process_error` is set and the pass is skipped.
+  "core::post_processing::post_process_shader": String,
+  /// **Post-process stage**
+  /// Where in the frame the pass runs: `scene` (the default) runs after the 3D scene is drawn and before the UI,
+  /// and `final` runs after the UI.
+  "core::post_processing::post_process_stage": String,
+  /// **Post-process texture names**
+  /// The names of the textures this pass declares. Each is available to the shader as a `texture_2d<f32>` that can be sampled
+  /// with `scene_sampler`. The textures are loaded from the URLs in `post_process_texture_urls`, and the pass is skipped until they have loaded.
+  "core::post_processing::post_process_texture_names": String[],
+  /// **Post-process texture URLs**
+  /// The URLs of the textures in `post_process_texture_names`, in the same order.
+  "core::post_processing::post_process_texture_urls": String[],
+  /// **Post-process uniform names**
+  /// The names of the uniforms this pass declares. Each is available to the shader as a `vec4<f32>` field of `params`;
+  /// for example, `intensity` is read as `params.intensity`. The values are in `post_process_uniform_values`.
+  "core::post_processing::post_process_uniform_names": String[],
+  /// **Post-process uniform values**
+  /// The values of the uniforms in `post_process_uniform_names`, in the same order. Missing values are zero.
+  "core::post_processing::post_process_uniform_values": Vec4[],
+  /// **Prefab from URL**
+  /// Load and attach a prefab from a URL or relative path.
+  /// When loaded, the components from this prefab will add to or replace the existing components for the entity.
+  "core::prefab::prefab_from_url": String,
+  /// **Scene stream budget**
+  /// How many milliseconds each frame may spend spawning and despawning streamed scene entities. Defaults to 2.
+  "core::prefab::scene_stream_budget": F32,
+  /// **Scene stream chunk size**
+  /// The size, in meters, of the grid cells the entities of a `scene_stream_from_url` are grouped into. Defaults to 32.
+  "core::prefab::scene_stream_chunk_size": F32,
+  /// **Scene stream from URL**
+  /// Streams the prefab at this URL or relative path into the world over several frames, instead of all at once.
+  /// Its entities are grouped into chunks on a grid of `scene_stream_chunk_size`, and spawned within the `scene_stream_budget` of each frame, starting with the chunks nearest to the players.
+  /// The entities are despawned again when this entity is despawned, or when no player is inside its `scene_stream_volume`.
+  "core::prefab::scene_stream_from_url": String,
+  /// **Scene stream progress**
+  /// The fraction of the entities of a `scene_stream_from_url` that have been spawned, from 0 to 1.
+  "core::prefab::scene_stream_progress": F32,
+  /// **Scene stream volume**
+  /// The half extents of a box around the `translation` of a `scene_stream_from_url` entity.
+  /// If attached, the scene is only loaded while a player is inside the box, and unloaded when the last one leaves.
+  "core::prefab::scene_stream_volume": Vec3,
+  /// **Spawned**
+  /// If attached, this entity was built from a prefab that has finished spawning.
+  "core::prefab::spawned": Empty,
+  /// **Capsule**
+  /// If attached to an entity alongside the other `capsule_*` components, the entity will be converted to a capsule primitive.
+  /// To easily instantiate a default `capsule`, consider using the `capsule` concept (e.g. `make_capsule`).
+  "core::primitives::capsule": Empty,
+  /// **Capsule half-height**
+  /// Set the half-height of the `capsule` entity, spanning Z-axis, excluding the caps.
+  "core::primitives::capsule_half_height": F32,
+  /// **Capsule latitudes**
+  /// Set the number of latitudinal sections. Should be even.
+  "core::primitives::capsule_latitudes": U32,
+  /// **Capsule longitudes**
+  /// Set the number of longitudinal sections.
+  "core::primitives::capsule_longitudes": U32,
+  /// **Capsule radius**
+  /// Set the radius of a `capsule` entity, spanning XY-plane.
+  "core::primitives::capsule_radius": F32,
+  /// **Capsule rings**
+  /// Set the number of sections between the caps.
+  "core::primitives::capsule_rings": U32,
+  /// **Cube**
+  /// If attached to an entity, the entity will be converted to a cube primitive.
+  /// The cube is unit-sized (i.e. 0.5 metres out to each side).
+  "core::primitives::cube": Empty,
+  /// **Quad**
+  /// If attached to an entity, the entity will be converted to a quad primitive.
+  /// The quad is unit-sized on the XY axes, and flat on the Z axis (i.e. 0.5 metres out to the XY axes).
+  "core::primitives::quad": Empty,
+  /// **Sphere**
+  /// If attached to an entity alongside the other `sphere_*` components, the entity will be converted to a sphere primitive.
+  /// To easily instantiate a unit-diameter `sphere`, consider using the `sphere` concept (e.g. `make_sphere`).
+  "core::primitives::sphere": Empty,
+  /// **Sphere radius**
+  /// Set the radius of a `sphere` entity.
+  "core::primitives::sphere_radius": F32,
+  /// **Sphere sectors**
+  /// Set the longitudinal sectors of a `sphere` entity.
+  "core::primitives::sphere_sectors": U32,
+  /// **Sphere stacks**
+  /// Set the latitudinal stacks of a `sphere` entity.
+  "core::primitives::sphere_stacks": U32,
+  /// **Torus**
+  /// If attached to an entity alongside the other `torus_*` components, the entity will be converted to a torus primitive.
+  /// To easily instantiate a default `torus`, consider using the `torus` concept (e.g. `make_torus`).
+  "core::primitives::torus": Empty,
+  /// **Torus inner radius**
+  /// Set the inner radius of a `torus` entity, spanning XY-plane.
+  "core::primitives::torus_inner_radius": F32,
+  /// **Torus loops**
+  /// Set the loops of a `torus` entity, spanning XY-plane.
+  "core::primitives::torus_loops": U32,
+  /// **Torus outer radius**
+  /// Set the outer radius of a `torus` entity, spanning XY-plane.
+  "core::primitives::torus_outer_radius": F32,
+  /// **Torus slices**
+  /// Set the slices of a `torus` entity, spanning XY-plane.
+  "core::primitives::torus_slices": U32,
+  /// **Profiling span depths**
+  /// How many scopes were open when each scope started, so that nested scopes can be drawn under their parents.
+  "core::profiling::profiling_span_depths": U32[],
+  /// **Profiling span durations**
+  /// How long each scope lasted, in seconds.
+  "core::profiling::profiling_span_durations": F32[],
+  /// **Profiling span names**
+  /// The names of the scopes that ended during the call.
+  "core::profiling::profiling_span_names": String[],
+  /// **Profiling span starts**
+  /// When each scope started, in seconds since the start of the call.
+  /// Scopes that are held across an `await` can have started during an earlier call, in which case this is negative.
+  "core::profiling::profiling_span_starts": F32[],
+  /// **Projectile drag**
+  /// The fraction of its velocity this projectile loses per second due to air resistance. Defaults to 0.
+  "core::projectile::projectile_drag": F32,
+  /// **Projectile gravity**
+  /// The downwards acceleration of this projectile, in meters per second squared. Defaults to the gravity of the physics scene (9.82).
+  "core::projectile::projectile_gravity": F32,
+  /// **Projectile inactive**
+  /// Attached by the server to pooled projectiles (see `projectile_pool`) that are waiting to be reused; they are hidden and not simulated.
+  /// Remove it and attach `projectile_velocity` to reuse the projectile.
+  "core::projectile::projectile_inactive": Empty,
+  /// **Projectile lifetime**
+  /// The number of seconds after which this projectile is removed if it hasn't hit anything. Defaults to 10.
+  "core::projectile::projectile_lifetime": F32,
+  /// **Projectile owner**
+  /// The entity that fired this projectile (e.g. a player). Projectiles pass through their owner and themselves.
+  "core::projectile::projectile_owner": EntityId,
+  /// **Projectile pool**
+  /// If attached, this projectile is deactivated (see `projectile_inactive`) instead of despawned when it hits something or its lifetime runs out.
+  /// Inactive projectiles are reused for new projectiles of the same pool, which is cheaper than spawning new entities.
+  "core::projectile::projectile_pool": String,
+  /// **Projectile radius**
+  /// If attached, this projectile is swept through the physics scene as a sphere of this radius, instead of as a ray.
+  /// Use it for fast projectiles that are large enough to clip the edges of colliders that a ray would pass.
+  "core::projectile::projectile_radius": F32,
+  /// **Projectile velocity**
+  /// The current velocity of this projectile. Attaching this component (along with `translation`) to an entity on the server turns it into a projectile.
+  /// Each frame, the server moves the projectile along its velocity, and sends a `ProjectileImpact` message if it hits a collider on the way.
+  /// Clients move it in between updates from the server, ahead by half of their round-trip time, so that it is shown where the server has it.
+  "core::projectile::projectile_velocity": Vec3,
+  /// **Background color**
+  /// Background color of an entity with a `rect` component.
+  "core::rect::background_color": Vec4,
+  /// **Background image slice**
+  /// Insets of the nine-slice regions of the `background_image_url` of an entity with a `rect` component, in pixels of the image.
+  /// `x` = left, `y` = top, `z` = right, `w` = bottom. The corners keep their size, the edges stretch along the rect and the center stretches in both directions.
+  /// If not set, the whole image is stretched over the rect.
+  "core::rect::background_image_slice": Vec4,
+  /// **Background image URL**
+  /// URL of an image drawn inside of an entity with a `rect` component, tinted by its `background_color`. See `background_image_slice` for nine-slice scaling.
+  "core::rect::background_image_url": String,
+  /// **Border color**
+  /// Border color of an entity with a `rect` component.
+  "core::rect::border_color": Vec4,
+  /// **Border radius**
+  /// Radius for each corner of an entity with a `rect` component.
+  /// `x` = top-left, `y` = top-right, `z` = bottom-left, `w` = bottom-right.
+  "core::rect::border_radius": Vec4,
+  /// **Border thickness**
+  /// Border thickness of an entity with a `rect` component.
+  "core::rect::border_thickness": F32,
+  /// **Ellipse**
+  /// If attached to an entity with a `rect` component, it is drawn as the ellipse that fills the rect instead. Its border and drop shadow follow the ellipse.
+  "core::rect::ellipse": Empty,
+  /// **Line from**
+  /// Start point of a line.
+  "core::rect::line_from": Vec3,
+  /// **Line to**
+  /// End point of a line.
+  "core::rect::line_to": Vec3,
+  /// **Line width**
+  /// Width of line.
+  "core::rect::line_width": F32,
+  /// **Polygon sides**
+  /// If attached to an entity with a `rect` component, it is drawn as a regular polygon with this many sides instead (e.g. 3 for a triangle, or 6 for a hexagon), with a corner pointing up.
+  /// The polygon is stretched to fill the rect, and its border and drop shadow follow its edges. Values below 3 are ignored.
+  "core::rect::polygon_sides": U32,
+  /// **Rect**
+  /// If attached to an entity, the entity will be converted to a UI rectangle, with optionally rounded corners and borders.
+  "core::rect::rect": Empty,
+  /// **Shadow blur**
+  /// How far the drop shadow of an entity with a `rect` component fades out, in pixels.
+  "core::rect::shadow_blur": F32,
+  /// **Shadow color**
+  /// Color of the drop shadow of an entity with a `rect` component. The rect only has a shadow if this is set.
+  "core::rect::shadow_color": Vec4,
+  /// **Shadow offset**
+  /// Offset of the drop shadow of an entity with a `rect` component from the rect, in pixels.
+  "core::rect::shadow_offset": Vec2,
+  /// **Cast shadows**
+  /// If attached, this entity will cast shadows.
+  "core::rendering::cast_shadows": Empty,
+  /// **Color**
+  /// This entity will be tinted with the specified color if the color is not black.
+  "core::rendering::color": Vec4,
+  /// **Decal material from URL**
+  /// Load a Decal material from the URL and attach it to this entity.
+  "core::rendering::decal_from_url": String,
+  /// **Double-sided**
+  /// If this is set, the entity will be rendered with double-sided rendering.
+  "core::rendering::double_sided": Bool,
+  /// **Fog color**
+  /// The color of the fog for this `sun`.
+  "core::rendering::fog_color": Vec3,
+  /// **Fog density**
+  /// The density of the fog for this `sun`.
+  "core::rendering::fog_density": F32,
+  /// **Fog height fall-off**
+  /// The height at which the fog will fall off (i.e. stop being visible) for this `sun`.
+  "core::rendering::fog_height_falloff": F32,
+  /// **Global illumination**
+  /// The dynamic global illumination mode of the client: `screen_space` bounces the light of what is on screen onto nearby
+  /// geometry, so that dynamic lights and emissive surfaces light their surroundings without a baked lightmap.
+  /// If empty, there is no dynamic global illumination. Defaults to the user's settings.
+  "core::rendering::global_illumination": String,
+  /// **Global illumination quality**
+  /// The quality of the dynamic global illumination, from 0 to 1. Higher qualities trace more and longer rays, which costs
+  /// more GPU time. Defaults to the user's settings.
+  "core::rendering::global_illumination_quality": F32,
+  /// **Joint Matrices**
+  /// Contains the matrices for each joint of this skinned mesh.
+  /// This should be used in combination with `joints`.
+  "core::rendering::joint_matrices": Mat4[],
+  /// **Joints**
+  /// Contains the joints that comprise this skinned mesh.
+  "core::rendering::joints": EntityId[],
+  /// **Light ambient**
+  /// The ambient light color of the `sun`.
+  "core::rendering::light_ambient": Vec3,
+  /// **Light diffuse**
+  /// The diffuse light color of the `sun`.
+  "core::rendering::light_diffuse": Vec3,
+  /// **Light probe grid**
+  /// Places a grid of light probes with this many probes along each axis. Entities without a lightmap take their indirect light from the probes around them.
+  /// The grid is axis aligned, and starts at the entity's world position plus `light_probe_grid_origin`; only one grid is used at a time.
+  /// Models that were built with baked light probes attach this to their root.
+  "core::rendering::light_probe_grid": Uvec3,
+  /// **Light probe grid origin**
+  /// The position of the first probe of the `light_probe_grid`, relative to the entity's world position.
+  "core::rendering::light_probe_grid_origin": Vec3,
+  /// **Light probe grid spherical harmonics**
+  /// The light around each probe of the `light_probe_grid`, ordered along x first, then y, then z. Each probe has four L1 spherical harmonics coefficients, in `xyz`;
+  /// the light from a direction `n` is `sh[0] + sh[1] * n.x + sh[2] * n.y + sh[3] * n.z`.
+  /// This can be updated at any time, for instance to refine the probes progressively.
+  "core::rendering::light_probe_grid_sh": Vec4[],
+  /// **Light probe grid spacing**
+  /// The distance between neighbouring probes of the `light_probe_grid` along each axis.
+  "core::rendering::light_probe_grid_spacing": Vec3,
+  /// **Lightmap scale and offset**
+  /// The region of the lightmap atlas that holds the indirect light for this entity. The second UV set of the entity's mesh is
+  /// scaled by `xy` and offset by `zw` to find its lightmap texels. If not attached, the entity uses the sun's ambient light.
+  "core::rendering::lightmap_scale_offset": Vec4,
+  /// **Lightmap URL**
+  /// The lightmap atlas with the baked indirect light for the scene. Only one lightmap is used at a time.
+  /// Models that were built with a baked lightmap attach this to their root.
+  "core::rendering::lightmap_url": String,
+  /// **Material from URL**
+  /// Load a custom material definition from the URL and attach an instance of it to this entity.
+  /// Its uniforms can be overridden with `material_uniform_names` and `material_uniform_values`.
+  "core::rendering::material_from_url": String,
+  /// **Material uniform names**
+  /// The names of the uniforms of this entity's custom material to override. Each name corresponds to the value at the same index in `material_uniform_values`.
+  "core::rendering::material_uniform_names": String[],
+  /// **Material uniform values**
+  /// The values of the uniforms named in `material_uniform_names`.
+  "core::rendering::material_uniform_values": Vec4[],
+  /// **Morph weights**
+  /// The weights of the morph targets (blendshapes) of this entity's meshes, in the order the targets were imported in.
+  /// Each target moves the vertices of the mesh by its offsets times its weight; use this for facial animation and other
+  /// deformations that joints can't express. Models that were imported with morph targets attach this to their mesh nodes,
+  /// and animations can drive it.
+  "core::rendering::morph_weights": F32[],
+  /// **Outline**
+  /// If attached, this entity will be rendered with an outline with the color specified. The outline is drawn around the entity, even where it is occluded; see `outline_thickness`.
+  "core::rendering::outline": Vec4,
+  /// **Outline (recursive)**
+  /// If attached, this entity and all of its children will be rendered with an outline with the color specified.
+  /// You do not need to attach `outline` if you have attached `outline_recursive`.
+  "core::rendering::outline_recursive": Vec4,
+  /// **Outline thickness**
+  /// The thickness of this entity's outline, in pixels. Defaults to 3 if not attached, and is at most 32.
+  /// If attached next to `outline_recursive`, it applies to all of the entity's children too.
+  "core::rendering::outline_thickness": F32,
+  /// **Overlay**
+  /// If attached, this entity will be rendered with an overlay.
+  "core::rendering::overlay": Empty,
+  /// **PBR material from URL**
+  /// Load a PBR material from the URL and attach it to this entity.
+  "core::rendering::pbr_material_from_url": String,
+  /// **Pick position**
+  /// Requests the entity rendered at this screen position (in the same space as `cursor_position`) on the client.
+  /// Once the frame has been read back from the GPU, `picked_entity` is attached to this entity.
+  /// Only opaque (including alpha-tested) entities can be picked.
+  "core::rendering::pick_position": Vec2,
+  /// **Picked entity**
+  /// The entity that was rendered at `pick_position`, or the null entity if there was none.
+  "core::rendering::picked_entity": EntityId,
+  /// **Portal cells**
+  /// The two `visibility_cell`s this portal connects. If not attached, they are found from the position of the portal.
+  "core::rendering::portal_cells": EntityId[],
+  /// **Portal size**
+  /// If attached, this entity is a portal between two `visibility_cell`s, like a doorway or a window: a rectangle of this
+  /// size in the XY plane of the entity, through which one cell can see into the other.
+  /// The cells are the ones in front of and behind the portal, unless they are set with `portal_cells`.
+  "core::rendering::portal_size": Vec2,
+  /// **Shader parameters**
+  /// Sixteen values of this entity that its material shader can read, for effects that differ between instances of the same material, such as a dissolve amount or a team color.
+  /// Shaders read them with `get_entity_shader_params_or(in.entity_loc, mat4x4<f32>())`, which returns them as the columns of a matrix: the first four values are its first column, and so on.
+  "core::rendering::shader_params": Mat4,
+  /// **Sky**
+  /// Add a realistic skybox to the scene.
+  "core::rendering::sky": Empty,
+  /// **Sun**
+  /// Marks this entity as a sun (i.e. its rotation will be used to control the global light direction).
+  /// The entity with the highest `sun` value takes precedence.
+  "core::rendering::sun": F32,
+  /// **Transparency group**
+  /// Controls when this transparent object will be rendered. Transparent objects are sorted by `(transparency_group, z-depth)`.
+  "core::rendering::transparency_group": I32,
+  /// **Visibility cell**
+  /// If attached, this entity is a cell of an indoor scene for portal culling: a box of this size, centered on the entity's
+  /// position and aligned with the world axes, like a room. When the camera is inside a cell, the entities that fit inside
+  /// other cells are only rendered if those cells can be seen through the `portal_size` portals that lead to them.
+  /// Entities that don't fit inside any cell, like the ones outdoors, are always rendered.
+  "core::rendering::visibility_cell": Vec3,
+  /// **Water**
+  /// Add a realistic water plane to this entity.
+  "core::rendering::water": Empty,
+  /// **Wind sway**
+  /// If attached, this entity bends in the wind of the `core::environment`, like grass, plants and trees.
+  /// Each vertex is pushed along the wind by this times the square of its height in the space of its mesh, so the base stays in place and the top sways the most.
+  /// Around 0.01 suits a tree a few meters tall.
+  "core::rendering::wind_sway": F32,
+  /// **Save components**
+  /// The indices of the components to save. If this is empty or not attached, all stored components of the `save_entities` are saved.
+  "core::save::save_components": U32[],
+  /// **Save data**
+  /// Module-defined data to store in the slot, such as a serialized struct.
+  /// On a load request, this is attached with the data the slot was saved with.
+  "core::save::save_data": String,
+  /// **Save delete**
+  /// Makes this a request to delete the `save_slot`.
+  "core::save::save_delete": Empty,
+  /// **Save entities**
+  /// The entities to save, along with all their stored components (or only the `save_components`).
+  /// On a load request, this is attached with the entities that were loaded. They keep the IDs they were saved with; entities that still exist get their saved components back, and the others are respawned.
+  "core::save::save_entities": EntityId[],
+  /// **Save error**
+  /// Attached to a request once it is done: an empty string if it succeeded, or the reason it failed.
+  "core::save::save_error": String,
+  /// **Save list**
+  /// Makes this a request to list the save slots. The names of the slots are attached as `save_slots`.
+  "core::save::save_list": Empty,
+  /// **Save load**
+  /// Makes this a request to load the `save_slot`.
+  "core::save::save_load": Empty,
+  /// **Save slot**
+  /// The name of the save slot a request is for. Slot names can only contain letters, digits, `-` and `_`.
+  /// With a `save_version`, this is a request to save to the slot; see also `save_load` and `save_delete`.
+  "core::save::save_slot": String,
+  /// **Save slots**
+  /// The names of the save slots, sorted, attached to a `save_list` request.
+  "core::save::save_slots": String[],
+  /// **Save version**
+  /// The version of the module's save format, which is stored with the slot so that older slots can be migrated when they are loaded.
+  /// On a load request, this is attached with the version the slot was saved with.
+  "core::save::save_version": U32,
+  /// **Sequence actor names**
+  /// The actor names of the timeline that are bound to the `sequence_actors`, in the same order.
+  "core::sequencer::sequence_actor_names": String[],
+  /// **Sequence actors**
+  /// The entities bound to the `sequence_actor_names` of this sequence. Cameras that the timeline cuts to are actors too.
+  "core::sequencer::sequence_actors": EntityId[],
+  /// **Sequence duration**
+  /// The duration of the timeline of this sequence, in seconds. Set once the timeline has loaded.
+  "core::sequencer::sequence_duration": F32,
+  /// **Sequence from recording**
+  /// Loads the recording with this name (see `sequence_record`) on the server, and plays it on this entity.
+  /// This is how ghosts are replayed: the entity follows the recorded transform, without being controlled by anything else.
+  "core::sequencer::sequence_from_recording": String,
+  /// **Sequence from URL**
+  /// Loads a timeline (a TOML file) from this URL, and plays it on this entity.
+  /// The entities the tracks of the timeline animate are bound to its actor names with `sequence_actor_names` and `sequence_actors`.
+  /// The actor name `self` is bound to this entity, unless it is bound explicitly.
+  "core::sequencer::sequence_from_url": String,
+  /// **Sequence looping**
+  /// If true, this sequence starts over when it reaches its end, instead of stopping.
+  "core::sequencer::sequence_looping": Bool,
+  /// **Sequence playing**
+  /// Whether this sequence is playing; set it to false to pause it. Defaults to true.
+  /// It is set to false when the sequence reaches its end, and a `SequenceEnd` message is sent.
+  "core::sequencer::sequence_playing": Bool,
+  /// **Sequence record**
+  /// Attach this to an entity on the server to record its translation, rotation and scale over time, as a timeline with a transform track for the actor `self`.
+  /// The recording is written with this name once this component is removed, or the entity is despawned, and can be played back with `sequence_from_recording`.
+  /// Names may only contain letters, digits, `-` and `_`. Recordings are stored per project, on the machine of the server.
+  "core::sequencer::sequence_record": String,
+  /// **Sequence record interval**
+  /// The time between the keys of a recording, in seconds. Defaults to 0.1.
+  "core::sequencer::sequence_record_interval": F32,
+  /// **Sequence seek**
+  /// Attach this to a sequence to jump to this time, in seconds. It is removed once the sequence has jumped.
+  /// The events between the previous time and this one are skipped, and so are the sounds, unless the jump is shorter than half a second.
+  "core::sequencer::sequence_seek": F32,
+  /// **Sequence speed**
+  /// How fast this sequence plays, as a multiplier. Defaults to 1.
+  "core::sequencer::sequence_speed": F32,
+  /// **Sequence time**
+  /// The time of this sequence, in seconds from the start of its timeline. To jump to another time, use `sequence_seek`.
+  "core::sequencer::sequence_time": F32,
+  /// **Emoji font URL**
+  /// The URL of a font to use for emoji that neither the entity's font nor the fallback font have.
+  /// Only the outlines of emoji are drawn, in the color of the text, so it should be a monochrome font such as Noto Emoji.
+  /// If not set, a system font is used if one can be found.
+  "core::text::emoji_font_url": String,
+  /// **Fallback font URL**
+  /// The URL of a font to use for characters that the entity's font does not have, such as CJK text.
+  /// If not set, a system font is used if one can be found.
+  "core::text::fallback_font_url": String,
+  /// **Font family**
+  /// Font family to be used. Can either be 'Default', 'FontAwesome', 'FontAwesomeSolid', 'Code' or a url to a font.
+  "core::text::font_family": String,
+  /// **Font size**
+  /// Size of the font.
+  "core::text::font_size": F32,
+  /// **Font style**
+  /// One of Bold, BoldItalic, Medium, MediumItalic, Regular, Italic, Light or LightItalic.
+  "core::text::font_style": String,
+  /// **Text**
+  /// Create a text mesh on this entity.
+  "core::text::text": String,
+  /// **World text**
+  /// Draws this text in the world, as a label centered on this entity, e.g. for names above players or signs.
+  /// It is drawn with a signed distance field of its font, so that it stays sharp at any distance, in the `font_family`, `font_style` and `color` of the entity.
+  /// The text lies in the XY plane of the entity, upright along its Y axis, and is read looking along its Z axis; add `spherical_billboard` or `cylindrical_billboard_z` to make it face the camera.
+  "core::text::world_text": String,
+  /// **World text fade distance**
+  /// If attached, the `world_text` of this entity fades out with its distance to the camera: it starts fading at the first distance, in meters, and is invisible beyond the second.
+  "core::text::world_text_fade_distance": Vec2,
+  /// **World text outline color**
+  /// The color of the outline of the `world_text` of this entity. Defaults to black.
+  "core::text::world_text_outline_color": Vec4,
+  /// **World text outline width**
+  /// The width of the outline around the `world_text` of this entity, as a fraction of its size, up to 0.15.
+  "core::text::world_text_outline_width": F32,
+  /// **World text shadow color**
+  /// The color of the drop shadow of the `world_text` of this entity. Defaults to translucent black.
+  "core::text::world_text_shadow_color": Vec4,
+  /// **World text shadow offset**
+  /// If attached, the `world_text` of this entity casts a drop shadow at this offset, as a fraction of its size along the X and Y axes of the text.
+  /// The offset is clamped to 0.15 in each direction.
+  "core::text::world_text_shadow_offset": Vec2,
+  /// **World text size**
+  /// The size of the font of the `world_text` of this entity, in meters. Defaults to 0.25.
+  "core::text::world_text_size": F32,
+  /// **Cylindrical billboard Z**
+  /// If attached, this ensures this entity is always aligned with the camera, except on the Z-axis.
+  /// This is useful for decorations that the player will be looking at from roughly the same altitude.
+  "core::transform::cylindrical_billboard_z": Empty,
+  /// **Euler rotation**
+  /// The Euler rotation of this entity in ZYX order.
+  "core::transform::euler_rotation": Vec3,
+  /// **Inverse Local to World**
+  /// Converts a world position to a local position.
+  /// This is automatically updated.
+  "core::transform::inv_local_to_world": Mat4,
+  /// **Large-scale positions**
+  /// If attached to the synchronized resources, `translation` is derived from `translation_f64` for the entities that have it, relative to the `world_origin`.
+  /// This allows for worlds far larger than `f32` positions can represent precisely, like solar systems.
+  "core::transform::large_scale_positions": Empty,
+  /// **Local to Parent**
+  /// Transformation from the entity's local space to the parent's space.
+  "core::transform::local_to_parent": Mat4,
+  /// **Local to World**
+  /// Transformation from the entity's local space to worldspace.
+  "core::transform::local_to_world": Mat4,
+  /// **Look-at target**
+  /// The position that this entity should be looking at.
+  "core::transform::lookat_target": Vec3,
+  /// **Look-at up**
+  /// When combined with `lookat_target`, the up vector for this entity.
+  "core::transform::lookat_up": Vec3,
+  /// **Mesh to Local**
+  /// Transformation from mesh-space to the entity's local space.
+  "core::transform::mesh_to_local": Mat4,
+  /// **Mesh to World**
+  /// Transformation from mesh-space to world space.
+  /// This is automatically updated when `mesh_to_local` and `local_to_world` change.
+  "core::transform::mesh_to_world": Mat4,
+  /// **Reset scale**
+  /// If attached to a transform hierarchy, the scale will be reset at that point, with only rotation/translation considered.
+  "core::transform::reset_scale": Empty,
+  /// **Rotation**
+  /// The rotation of this entity.
+  "core::transform::rotation": Quat,
+  /// **Scale**
+  /// The scale of this entity.
+  "core::transform::scale": Vec3,
+  /// **Spherical billboard**
+  /// If attached, this ensures that this entity is always aligned with the camera.
+  "core::transform::spherical_billboard": Empty,
+  /// **Translation**
+  /// The translation/position of this entity.
+  "core::transform::translation": Vec3,
+  /// **Translation (f64)**
+  /// The world-space position of this entity in double precision, as `[x, y, z]`.
+  /// When `large_scale_positions` is enabled, the `translation` of this entity is set to this position relative to the `world_origin` every frame.
+  "core::transform::translation_f64": F64[],
+  /// **World origin**
+  /// The double-precision position, as `[x, y, z]`, that `translation`s are relative to when `large_scale_positions` is enabled.
+  /// On the client, this follows the active camera if it has a `translation_f64`, so that everything is rendered relative to the camera.
+  "core::transform::world_origin": F64[],
+  /// **Tweakable bool**
+  /// The value of a boolean tweakable.
+  "core::tweakable::tweakable_bool": Bool,
+  /// **Tweakable default**
+  /// The default value of this tweakable, formatted as text. Values that equal the default are not persisted.
+  "core::tweakable::tweakable_default": String,
+  /// **Tweakable f32**
+  /// The value of a floating-point tweakable.
+  "core::tweakable::tweakable_f32": F32,
+  /// **Tweakable i32**
+  /// The value of an integer tweakable.
+  "core::tweakable::tweakable_i32": I32,
+  /// **Tweakable max**
+  /// The largest value a numeric tweakable can have; values above it are clamped.
+  "core::tweakable::tweakable_max": F32,
+  /// **Tweakable min**
+  /// The smallest value a numeric tweakable can have; values below it are clamped.
+  "core::tweakable::tweakable_min": F32,
+  /// **Tweakable name**
+  /// The unique name of this tweakable variable, such as `player.jump_height`.
+  "core::tweakable::tweakable_name": String,
+  /// **Tweakable replicated**
+  /// If attached to a tweakable on the server, it is replicated to the clients. Other server tweakables are only visible on the server.
+  "core::tweakable::tweakable_replicated": Empty,
+  /// **Tweakable string**
+  /// The value of a string tweakable.
+  "core::tweakable::tweakable_string": String,
+  /// **Voxel block faces**
+  /// The materials of the faces of each kind of block of this voxel world, as indices into its `voxel_materials`.
+  /// There are three per kind of block, starting with block 1: the material of its top, its sides, and its bottom. Faces without a material are drawn white.
+  "core::voxel::voxel_block_faces": U32[],
+  /// **Voxel block size**
+  /// The size of the blocks of this voxel world, in meters. Defaults to 1.
+  "core::voxel::voxel_block_size": F32,
+  /// **Voxel chunk**
+  /// The voxel world that this chunk of blocks belongs to. Chunks are placed in their world by the server.
+  "core::voxel::voxel_chunk": EntityId,
+  /// **Voxel chunk blocks**
+  /// The blocks of this chunk: 16×16×16 of them, with x changing fastest, then y, then z. It is empty if all of the blocks are.
+  /// The `voxel_chunk_edits` apply on top of these.
+  "core::voxel::voxel_chunk_blocks": U8[],
+  /// **Voxel chunk edits**
+  /// The blocks of this chunk that were set since its `voxel_chunk_blocks` last changed, in order, each as `index << 8 | block` (where `index` is the index of the block in `voxel_chunk_blocks`).
+  /// Edits are replicated this way so that setting a few blocks only sends a few bytes; once there are many, the server merges them into `voxel_chunk_blocks`.
+  "core::voxel::voxel_chunk_edits": U32[],
+  /// **Voxel chunk position**
+  /// The position of this chunk in its voxel world, in chunks; it has whole coordinates. The chunk holds the blocks from 16 times this position.
+  "core::voxel::voxel_chunk_position": Vec3,
+  /// **Voxel materials**
+  /// The URLs of the PBR materials (as with `pbr_material_from_url`) that the faces of the blocks of this voxel world are drawn with. Their textures are tiled once per block.
+  "core::voxel::voxel_materials": String[],
+  /// **Voxel world**
+  /// An entity with this component is a voxel world. Its `translation` is the corner of the block at (0, 0, 0); voxel worlds can't be rotated.
+  /// Its blocks are stored in the chunk entities that have this entity as their `voxel_chunk`.
+  "core::voxel::voxel_world": Empty,
+  /// **Head pose**
+  /// The world transform of the headset of the player this is attached to. Like a camera, it looks along its local Z axis.
+  /// The player's client sets this; it is sent to the server and on to the other clients, which interpolate it, so it is not synchronized like `Networked` components.
+  "core::xr::head_pose": Mat4,
+  /// **Left hand pose**
+  /// The world transform of the left controller or hand of the player this is attached to.
+  /// It is replicated like `head_pose`, and smoothed on the other clients to hide tracking jitter.
+  "core::xr::left_hand_pose": Mat4,
+  /// **Pose sync falloff**
+  /// The distance in meters at which the server sends poses at half the `pose_sync_rate`, and a third at twice the distance, and so on.
+  /// Poses of players behind the receiving player's head are sent at half the rate again. Defaults to 5.
+  "core::xr::pose_sync_falloff": F32,
+  /// **Pose sync minimum rate**
+  /// The lowest rate, in updates per second, at which the server sends the poses of a player, however far away it is. Defaults to 2.
+  "core::xr::pose_sync_min_rate": F32,
+  /// **Pose sync rate**
+  /// How many times per second the server sends the poses of a player to the players near it. Defaults to 30.
+  /// Clients send their own poses at this rate too.
+  "core::xr::pose_sync_rate": F32,
+  /// **Right hand pose**
+  /// The world transform of the right controller or hand of the player this is attached to.
+  /// It is replicated like `head_pose`, and smoothed on the other clients to hide tracking jitter.
+  "core::xr::right_hand_pose": Mat4,
+};
+
+/// The fields of the messages, by message ID.
+export type Messages = {
+  /// Sent to client modules when a UI entity is activated without the mouse: with Enter or Space while it has the `core::accessibility::accessibility_focus`, or by a screen reader.
+  /// The built-in buttons are clicked when they are activated.
+  "accessibility_activate": {
+    entity: EntityId,
+  },
+  /// Sent when a screen reader asks to move the keyboard focus to a UI entity. The focus is moved to it if it is focusable.
+  "accessibility_focus_request": {
+    entity: EntityId,
+  },
+  /// Sent on the server when the owner of `entity` sent a value for its client-authoritative `component`, which has validators. The value is on the `proposal` entity; see `client_authority_proposal`.
+  "client_authority_update": {
+    component: String,
+    entity: EntityId,
+    proposal: EntityId,
+    user_id: String,
+  },
+  /// Sent to client modules every frame, after the frame has been submitted to the GPU, with how long it took to record and submit it (in seconds).
+  "client_post_render": {
+    delta_time: F32,
+    frame: U64,
+    render_time: F32,
+    time: F32,
+  },
+  /// Sent to client modules every frame, after the world has been updated and before it is rendered, with the matrices of the active camera (or `EntityId::null()` and identity matrices if there is none).
+  /// Transforms and cameras changed while handling it are updated before rendering, so this can be used for cosmetic effects like screen shake.
+  "client_pre_render": {
+    camera: EntityId,
+    delta_time: F32,
+    frame: U64,
+    projection: Mat4,
+    projection_view: Mat4,
+    time: F32,
+    view: Mat4,
+  },
+  /// Sent to a client module in response to its request to read the clipboard (see `core::clipboard::clipboard_read`).
+  /// `text` is `None` if the user denied the module access to the clipboard, or if it doesn't contain text.
+  "clipboard_text": {
+    text: String | null,
+  },
+  /// Sent when colliders load.
+  "collider_loads": {
+    ids: EntityId[],
+  },
+  /// Sent when a collision occurs.
+  "collision": {
+    ids: EntityId[],
+  },
+  /// Sent on the server when a choice is made in a conversation, with the node it was made at and the localization key of the choice's text (or an empty string if the line had no choices).
+  "dialogue_choice": {
+    choice: U32,
+    conversation: EntityId,
+    node: String,
+    participant: EntityId,
+    text: String,
+  },
+  /// Sent on the server when a conversation ends, with the last node it was at. The conversation entity is despawned.
+  "dialogue_end": {
+    conversation: EntityId,
+    node: String,
+    participant: EntityId,
+  },
+  /// Sent to all modules every frame.
+  "frame": {
+  },
+  /// Sent to a module when it loads.
+  "module_load": {
+  },
+  /// Sent to a module when it unloads.
+  "module_unload": {
+  },
+  /// Sent on the server when dynamic bodies come to rest and fall asleep (see `core::physics::sleeping`).
+  "physics_sleep": {
+    ids: EntityId[],
+  },
+  /// Sent on the server when sleeping dynamic bodies are woken up, e.g. by being hit or moved.
+  "physics_wake": {
+    ids: EntityId[],
+  },
+  /// Sent on the server when the client of a suspended player has reconnected to it.
+  "player_resumed": {
+    player: EntityId,
+    user_id: String,
+  },
+  /// Sent on the server when the connection to a player was lost unexpectedly. The player is kept with `player_suspended` until its client reconnects or the `reconnect_grace_period` runs out.
+  "player_suspended": {
+    player: EntityId,
+    user_id: String,
+  },
+  /// Sent on the server when a projectile hits a collider, with the point and normal of the hit and the `surface_material` of the entity that was hit (or an empty string).
+  "projectile_impact": {
+    entity: EntityId,
+    normal: Vec3,
+    position: Vec3,
+    projectile: EntityId,
+    surface_material: String,
+  },
+  /// Sent on the server when a sequence that doesn't loop reaches the end of its timeline. The sequence stays at its end, paused, until it is seeked.
+  "sequence_end": {
+    sequence: EntityId,
+  },
+  /// Sent on the server when a sequence reaches an event of its timeline, with the event's name and time.
+  "sequence_event": {
+    name: String,
+    sequence: EntityId,
+    time: F32,
+  },
+  /// Sent when the window is closed.
+  "window_close": {
+  },
+  /// Sent when a file is dropped onto the window, once it has been read. Sent once per file.
+  /// `size` is the size of the file in bytes; `data` is empty if the file is larger than 32 MiB or can't be read.
+  "window_file_drop": {
+    data: U8[],
+    path: String,
+    size: U64,
+  },
+  /// Sent when a file is dragged over the window. Sent once per file.
+  "window_file_hover": {
+    path: String,
+  },
+  /// Sent when the files dragged over the window leave it without being dropped.
+  "window_file_hover_cancel": {
+  },
+  /// Sent when the window gains or loses focus.
+  "window_focus_change": {
+    focused: Bool,
+  },
+  /// Sent when the window receives a character from the keyboard.
+  "window_keyboard_character": {
+    character: String,
+  },
+  /// Sent when the window receives a keyboard input.
+  "window_keyboard_input": {
+    keycode: String | null,
+    modifiers: U32,
+    pressed: Bool,
+  },
+  /// Sent when the window's keyboard modifiers change.
+  "window_keyboard_modifiers_change": {
+    modifiers: U32,
+  },
+  /// Sent when the window receives a mouse input.
+  "window_mouse_input": {
+    button: U32,
+    pressed: Bool,
+  },
+  /// Sent when the window receives a mouse motion input.
+  "window_mouse_motion": {
+    delta: Vec2,
+  },
+  /// Sent when the window receives a mouse wheel input.
+  "window_mouse_wheel": {
+    delta: Vec2,
+    pixels: Bool,
+  },
+  /// Sent to client modules when the size of the game view or the scale factor of the window changes.
+  "window_resize": {
+    logical_size: Uvec2,
+    physical_size: Uvec2,
+    scale_factor: F32,
+  },
+  /// Sent when a finger touches, moves on, or is lifted from the touch screen.
+  /// `id` identifies the finger until it is lifted; `phase` is 0 when it starts touching, 1 when it moves, 2 when it's lifted and 3 when the touch is cancelled. `position` is in logical pixels.
+  "window_touch": {
+    id: U64,
+    phase: U32,
+    position: Vec2,
+  },
+};
+
+/// **Camera**
+/// Base components for a camera. You will need other components to make a fully-functioning camera.
+export type Camera = Transformable & {
+  "core::app::name": String,
+  "core::camera::active_camera": F32,
+  "core::camera::near": F32,
+  "core::camera::projection": Mat4,
+  "core::camera::projection_view": Mat4,
+  "core::transform::inv_local_to_world": Mat4,
+  "core::transform::local_to_world": Mat4,
+};
+
+/// **Capsule**
+/// A primitive capsule. Defined as a cylinder capped by hemispheres.
+export type Capsule = {
+  "core::primitives::capsule": Empty,
+  "core::primitives::capsule_half_height": F32,
+  "core::primitives::capsule_latitudes": U32,
+  "core::primitives::capsule_longitudes": U32,
+  "core::primitives::capsule_radius": F32,
+  "core::primitives::capsule_rings": U32,
+};
+
+/// **Orthographic Camera**
+/// An orthographic camera.
+export type OrthographicCamera = Camera & {
+  "core::camera::far": F32,
+  "core::camera::near": F32,
+  "core::camera::orthographic": Empty,
+  "core::camera::orthographic_bottom": F32,
+  "core::camera::orthographic_left": F32,
+  "core::camera::orthographic_right": F32,
+  "core::camera::orthographic_top": F32,
+};
+
+/// **Perspective Camera**
+/// A perspective camera.
+export type PerspectiveCamera = PerspectiveCommonCamera & {
+  "core::camera::far": F32,
+  "core::camera::perspective": Empty,
+};
+
+/// **Perspective Common Camera**
+/// Base components for a perspective camera. Consider `perspective_camera` or `perspective_infinite_reverse_camera`.
+export type PerspectiveCommonCamera = Camera & {
+  "core::camera::aspect_ratio": F32,
+  "core::camera::fovy": F32,
+};
+
+/// **Perspective-Infinite-Reverse Camera**
+/// A perspective-infinite-reverse camera. This is recommended for most use-cases.
+export type PerspectiveInfiniteReverseCamera = PerspectiveCommonCamera & {
+  "core::camera::perspective_infinite_reverse": Empty,
+};
+
+/// **Sphere**
+/// A primitive sphere.
+export type Sphere = {
+  "core::primitives::sphere": Empty,
+  "core::primitives::sphere_radius": F32,
+  "core::primitives::sphere_sectors": U32,
+  "core::primitives::sphere_stacks": U32,
+};
+
+/// **Torus**
+/// A primitive Torus, surface of revolution generated by revolving a circle in three-dimensional space one full revolution.
+export type Torus = {
+  "core::primitives::torus": Empty,
+  "core::primitives::torus_inner_radius": F32,
+  "core::primitives::torus_loops": U32,
+  "core::primitives::torus_outer_radius": F32,
+  "core::primitives::torus_slices": U32,
+};
+
+/// **Transformable**
+/// Can be translated, rotated and scaled.
+export type Transformable = {
+  "core::transform::rotation": Quat,
+  "core::transform::scale": Vec3,
+  "core::transform::translation": Vec3,
+};
@@ -0,0 +1,26 @@
+#![no_main]
+//! The datagrams that clients send to the server, and the server to the clients.
+use ambient_ecs::{EntityId, WorldDiff};
+use ambient_network::{
+    pose_sync::PoseSample, proto::decode_datagram, CLIENT_AUTHORITY_DATAGRAM_ID,
+    POSE_SYNC_DATAGRAM_ID,
+};
+use bytes::Bytes;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    ambient_fuzz::init();
+    let Ok((id, payload)) = decode_datagram(Bytes::copy_from_slice(data)) else {
+        return;
+    };
+    match id {
+        CLIENT_AUTHORITY_DATAGRAM_ID => {
+            let _ = bincode::deserialize::<WorldDiff>(&payload);
+        }
+        POSE_SYNC_DATAGRAM_ID => {
+            let _ = bincode::deserialize::<PoseSample>(&payload);
+            let _ = bincode::deserialize::<Vec<(EntityId, PoseSample)>>(&payload);
+        }
+        _ => {}
+    }
+});
@@ -0,0 +1,12 @@
+#![no_main]
+//! The world diffs that the server streams to the clients, in the binary encoding and with serde.
+use ambient_ecs::WorldDiff;
+use ambient_network::{proto::decode_binary_diff, stream::decode_frames, MAX_FRAME_SIZE};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    ambient_fuzz::init();
+    let _ = WorldDiff::decode(data);
+    decode_frames(data, MAX_FRAME_SIZE, decode_binary_diff);
+    let _ = bincode::deserialize::<WorldDiff>(data);
+});
@@ -0,0 +1,31 @@
+#![no_main]
+//! The requests that clients make to the server's RPCs, with the same arguments.
+use ambient_ecs::WorldDiff;
+use ambient_network::rpc::RpcForkInstance;
+use ambient_rpc::RpcRegistry;
+use futures::FutureExt;
+use libfuzzer_sys::fuzz_target;
+
+async fn world_diff(_: (), _: WorldDiff) {}
+async fn fork_instance(_: (), _: RpcForkInstance) {}
+async fn join_instance(_: (), _: String) {}
+
+fuzz_target!(|data: &[u8]| {
+    ambient_fuzz::init();
+    let mut registry = RpcRegistry::new();
+    registry.register(world_diff);
+    registry.register(fork_instance);
+    registry.register(join_instance);
+    let _ = ambient_rpc::trace_context(data);
+    let _ = registry.run_req((), data).now_or_never();
+
+    // The name of the function is fixed, so that the fuzzer spends its time on the arguments
+    for func in [
+        registry.serialize_req(world_diff, WorldDiff::new()),
+        registry.serialize_req(join_instance, String::new()),
+    ] {
+        let name_len = func.iter().position(|&byte| byte == b'\n').unwrap() + 1;
+        let req = [&func[..name_len], data].concat();
+        let _ = registry.run_req((), &req).now_or_never();
+    }
+});
@@ -0,0 +1,14 @@
+#![no_main]
+//! The framing of the control streams, and the requests and pushes that are sent on them.
+use ambient_network::{
+    proto::{decode_client_request, decode_server_push},
+    stream::decode_frames,
+    MAX_FRAME_SIZE, MAX_REQUEST_FRAME_SIZE,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    ambient_fuzz::init();
+    decode_frames(data, MAX_REQUEST_FRAME_SIZE, decode_client_request);
+    decode_frames(data, MAX_FRAME_SIZE, decode_server_push);
+});
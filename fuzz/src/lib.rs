@@ -0,0 +1,11 @@
+//! Shared setup of the fuzz targets, which decode bytes the way the server and clients decode what their peers send.
+use std::sync::Once;
+
+/// Registers the components, so that the diffs and entities in the inputs can refer to them
+pub fn init() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        ambient_core::init_all_components();
+        ambient_network::init_all_components();
+    });
+}
@@ -0,0 +1,35 @@
+use crate::{components::core::accessibility, entity, global::Vec3};
+
+pub use ambient_shared_types::{
+    ColorVisionDeficiency, ColorVisionFilter, COLOR_VISION_SAFE_PALETTE,
+};
+
+/// The color vision deficiency the user has configured, if any.
+///
+/// Use this to adapt colors that carry meaning, like team colors, so that they remain distinguishable.
+pub fn color_vision_deficiency() -> Option<ColorVisionDeficiency> {
+    entity::get_component(
+        entity::resources(),
+        accessibility::color_vision_deficiency(),
+    )?
+    .parse()
+    .ok()
+}
+
+/// Whether the user has requested a high-contrast UI.
+pub fn high_contrast() -> bool {
+    entity::get_component(entity::resources(), accessibility::high_contrast()).unwrap_or(false)
+}
+
+/// Returns `count` team colors (in sRGB).
+///
+/// If the user has a color vision deficiency configured, these are taken from the [COLOR_VISION_SAFE_PALETTE];
+/// otherwise, `preferred` is returned unchanged, repeating it if it is too short.
+pub fn team_colors(preferred: &[Vec3], count: usize) -> Vec<Vec3> {
+    let palette: &[Vec3] = if color_vision_deficiency().is_some() || preferred.is_empty() {
+        &COLOR_VISION_SAFE_PALETTE
+    } else {
+        preferred
+    };
+    palette.iter().copied().cycle().take(count).collect()
+}
@@ -1,6 +1,9 @@
 use crate::{
+    components::core::rendering::{pick_position, picked_entity},
+    entity,
     global::{EntityId, Ray, Vec2, Vec3},
     internal::{
+        component::Entity,
         conversion::{FromBindgen, IntoBindgen},
         wit,
     },
@@ -27,4 +30,14 @@ pub fn screen_to_world_direction(camera: EntityId, screen_position: Vec2) -> Ray
 /// Converts a world-space position to a screen position (e.g. mouse position).
 pub fn world_to_screen(camera: EntityId, world_position: Vec3) -> Vec2 {
     wit::client_camera::world_to_screen(camera.into_bindgen(), world_position.into_bindgen()).from_bindgen()
-}
\ No newline at end of file
+}
+/// Returns the entity rendered at a screen position (e.g. mouse position), or `None` if there is none.
+///
+/// Unlike a raycast, this is pixel-perfect, as it reads back what the GPU rendered; it resolves a few frames later.
+/// Transparent entities cannot be picked.
+pub async fn pick(screen_position: Vec2) -> Option<EntityId> {
+    let request = entity::spawn(&Entity::new().with(pick_position(), screen_position));
+    let picked = entity::wait_for_component(request, picked_entity()).await;
+    entity::despawn(request);
+    (!picked.is_null()).then_some(picked)
+}
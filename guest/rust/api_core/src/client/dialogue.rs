@@ -0,0 +1,60 @@
+use std::fmt::Display;
+
+use crate::{
+    client::localization::localize,
+    components::core::dialogue::{
+        dialogue_choices, dialogue_participant, dialogue_speaker, dialogue_text,
+        dialogue_variable_keys, dialogue_variable_values,
+    },
+    ecs::query,
+    entity,
+    global::EntityId,
+};
+
+/// The current line of a conversation, localized to the active language.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialogueLine {
+    /// The name of the speaker, if the line has one
+    pub speaker: Option<String>,
+    /// What is being said
+    pub text: String,
+    /// The choices the participant can pick from; if there are none, the line is continued from by choosing 0
+    pub choices: Vec<String>,
+}
+
+/// Returns the conversation `participant` (e.g. [player::get_local](crate::player::get_local)) is taking part in, if any.
+pub fn conversation(participant: EntityId) -> Option<EntityId> {
+    query(dialogue_participant())
+        .requires(dialogue_text())
+        .build()
+        .evaluate()
+        .into_iter()
+        .find_map(|(id, p)| (p == participant).then_some(id))
+}
+
+/// Returns the current line of the `conversation`, if it has started.
+///
+/// The strings are localized with the participant's dialogue variables as arguments, so a line like
+/// `"Bring me {pelts_left} more pelts."` shows the value of the `pelts_left` variable.
+pub fn line(conversation: EntityId) -> Option<DialogueLine> {
+    let text = entity::get_component(conversation, dialogue_text())?;
+    let participant =
+        entity::get_component(conversation, dialogue_participant()).unwrap_or(conversation);
+    let keys = entity::get_component(participant, dialogue_variable_keys()).unwrap_or_default();
+    let values = entity::get_component(participant, dialogue_variable_values()).unwrap_or_default();
+    let args = keys
+        .iter()
+        .zip(&values)
+        .map(|(k, v)| (k.as_str(), v as &dyn Display))
+        .collect::<Vec<_>>();
+    Some(DialogueLine {
+        speaker: entity::get_component(conversation, dialogue_speaker())
+            .map(|speaker| localize(&speaker, &args)),
+        text: localize(&text, &args),
+        choices: entity::get_component(conversation, dialogue_choices())
+            .unwrap_or_default()
+            .iter()
+            .map(|choice| localize(choice, &args))
+            .collect(),
+    })
+}
@@ -1,8 +1,14 @@
 use std::collections::HashSet;
 
 use crate::{
+    components::core::input::{
+        cursor_confined, cursor_image_hotspot, cursor_image_size, cursor_image_url, touch_ids,
+        touch_positions,
+    },
+    entity,
     global::{CursorIcon, Vec2},
     internal::{
+        component::Entity,
         conversion::{FromBindgen, IntoBindgen},
         wit,
     },
@@ -39,13 +45,41 @@ pub fn set_cursor_visible(visible: bool) {
     wit::client_input::set_cursor_visible(visible);
 }
 
-/// Sets the cursor's lock state. If set, the cursor will not be able to move outside of the window.
+/// Sets the cursor's lock state. If set, the cursor is kept in place and hidden, and only [Input::mouse_delta] changes,
+/// which is what you want for mouse look. Where the platform can't lock the cursor, it is confined to the window instead.
 ///
-/// You may want to combine this with [set_cursor_visible] or use [CursorLockGuard] instead.
+/// The cursor is released while the window isn't focused, and locked again when it regains focus.
+/// You may want to use [CursorLockGuard] instead.
 pub fn set_cursor_lock(locked: bool) {
     wit::client_input::set_cursor_lock(locked);
 }
 
+/// Sets whether the cursor is confined to the window. Unlike [set_cursor_lock], the cursor still moves within the window.
+///
+/// The cursor is released while the window isn't focused, and confined again when it regains focus.
+pub fn set_cursor_confined(confined: bool) {
+    entity::add_component(entity::resources(), cursor_confined(), confined);
+}
+
+/// Draws the cursor with the image at `url` instead of the system cursor, at `size` in logical pixels.
+/// `hotspot` is the point of the image that is at the position of the cursor, in logical pixels from its top-left corner.
+///
+/// The image is drawn in the game view, so it is only shown while the cursor is over it.
+pub fn set_cursor_image(url: impl Into<String>, size: Vec2, hotspot: Vec2) {
+    entity::add_components(
+        entity::resources(),
+        Entity::new()
+            .with(cursor_image_url(), url.into())
+            .with(cursor_image_size(), size)
+            .with(cursor_image_hotspot(), hotspot),
+    );
+}
+
+/// Goes back to the system cursor after [set_cursor_image].
+pub fn clear_cursor_image() {
+    entity::remove_component(entity::resources(), cursor_image_url());
+}
+
 /// Helper utility that will lock and hide the cursor if necessary.
 ///
 /// Will unlock the cursor when dropped.
@@ -102,6 +136,123 @@ impl Drop for CursorLockGuard {
     }
 }
 
+/// A finger on the touch screen. Get these with [get_touches].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Touch {
+    /// Identifies the finger until it is lifted.
+    pub id: u64,
+    /// The position of the finger, like [Input::mouse_position].
+    pub position: Vec2,
+}
+
+/// Returns the fingers that are currently on the touch screen, in the order they touched it.
+///
+/// The first finger also acts as the left mouse button, so UIs that work with the mouse also work by touch.
+/// Each change is also sent as a [WindowTouch](crate::messages::WindowTouch) message.
+pub fn get_touches() -> Vec<Touch> {
+    let ids = entity::get_component(entity::resources(), touch_ids()).unwrap_or_default();
+    let positions =
+        entity::get_component(entity::resources(), touch_positions()).unwrap_or_default();
+    ids.into_iter()
+        .zip(positions)
+        .map(|(id, position)| Touch { id, position })
+        .collect()
+}
+
+/// How the fingers on the touch screen moved between two sets of [Touch]es. Use a [TouchGestures] to get these
+/// every frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TouchGesture {
+    /// How far the fingers moved together, on average.
+    pub pan: Vec2,
+    /// How much the fingers spread apart (above 1) or came together (below 1), for zooming.
+    /// This is 1 with less than two fingers.
+    pub pinch: f32,
+    /// How much the fingers turned around their center, in radians, clockwise on the screen.
+    /// This is 0 with less than two fingers.
+    pub rotation: f32,
+}
+impl Default for TouchGesture {
+    fn default() -> Self {
+        Self {
+            pan: Vec2::ZERO,
+            pinch: 1.,
+            rotation: 0.,
+        }
+    }
+}
+impl TouchGesture {
+    /// Returns the gesture from `previous` to `current`, made by the fingers that are in both.
+    pub fn between(previous: &[Touch], current: &[Touch]) -> Self {
+        let pairs = current
+            .iter()
+            .filter_map(|touch| {
+                let previous = previous.iter().find(|previous| previous.id == touch.id)?;
+                Some((previous.position, touch.position))
+            })
+            .collect::<Vec<_>>();
+        if pairs.is_empty() {
+            return Self::default();
+        }
+
+        let count = pairs.len() as f32;
+        let previous_center = pairs.iter().map(|(previous, _)| *previous).sum::<Vec2>() / count;
+        let current_center = pairs.iter().map(|(_, current)| *current).sum::<Vec2>() / count;
+        let mut gesture = Self {
+            pan: current_center - previous_center,
+            ..Default::default()
+        };
+        if pairs.len() < 2 {
+            return gesture;
+        }
+
+        let spread = |center: Vec2, position: fn(&(Vec2, Vec2)) -> Vec2| {
+            pairs
+                .iter()
+                .map(|pair| position(pair).distance(center))
+                .sum::<f32>()
+                / count
+        };
+        let previous_spread = spread(previous_center, |(previous, _)| *previous);
+        if previous_spread > f32::EPSILON {
+            gesture.pinch = spread(current_center, |(_, current)| *current) / previous_spread;
+        }
+        gesture.rotation = pairs
+            .iter()
+            .map(|(previous, current)| {
+                let from = *previous - previous_center;
+                let to = *current - current_center;
+                from.perp_dot(to).atan2(from.dot(to))
+            })
+            .sum::<f32>()
+            / count;
+        gesture
+    }
+}
+
+/// Tracks the touches between calls to [Self::update], which returns the [TouchGesture] since the last call.
+///
+/// ```ignore
+/// let mut gestures = TouchGestures::default();
+/// Frame::subscribe(move |_| {
+///     let gesture = gestures.update();
+///     zoom /= gesture.pinch;
+/// });
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TouchGestures {
+    previous: Vec<Touch>,
+}
+impl TouchGestures {
+    /// Returns the [TouchGesture] made since the last call.
+    pub fn update(&mut self) -> TouchGesture {
+        let current = get_touches();
+        let gesture = TouchGesture::between(&self.previous, &current);
+        self.previous = current;
+        gesture
+    }
+}
+
 #[allow(missing_docs)]
 /// The code associated with a key on the keyboard.
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
@@ -0,0 +1,62 @@
+use std::fmt::Display;
+
+use crate::{components::core::localization, entity};
+
+pub use ambient_shared_types::interpolate;
+
+/// The language strings are localized to, such as `en` or `ja`.
+pub fn language() -> String {
+    entity::get_component(entity::resources(), localization::language()).unwrap_or_default()
+}
+
+/// Switches the language strings are localized to. The string table for `language` is loaded from the project's
+/// `locale` assets; until it has loaded, the strings of the previous language are used.
+pub fn set_language(language: impl Into<String>) {
+    entity::set_component(
+        entity::resources(),
+        localization::language(),
+        language.into(),
+    );
+}
+
+/// Returns the localized string for `key` with `args` interpolated, or `key` itself if the active string table has no
+/// string for it.
+///
+/// Prefer the [loc](crate::loc) macro, which builds `args` for you.
+pub fn localize(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    let resources = entity::resources();
+    let keys =
+        entity::get_component(resources, localization::localization_keys()).unwrap_or_default();
+    match keys.binary_search_by(|x| x.as_str().cmp(key)) {
+        Ok(index) => {
+            let values = entity::get_component(resources, localization::localization_values())
+                .unwrap_or_default();
+            values
+                .get(index)
+                .map(|value| interpolate(value, args))
+                .unwrap_or_else(|| key.to_string())
+        }
+        Err(_) => key.to_string(),
+    }
+}
+
+/// Looks up a localized string, interpolating any named arguments into its `{name}` placeholders.
+///
+/// ```ignore
+/// Text::el(loc!("menu.play"));
+/// Text::el(loc!("hud.score", name = player_name, score = 10));
+/// ```
+///
+/// If the active string table has no string for the key, the key itself is returned.
+#[macro_export]
+macro_rules! loc {
+    ($key:expr) => {
+        $crate::client::localization::localize($key, &[])
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::client::localization::localize(
+            $key,
+            &[$((stringify!($name), &$value as &dyn ::std::fmt::Display)),+],
+        )
+    };
+}
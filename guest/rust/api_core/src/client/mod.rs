@@ -1,11 +1,20 @@
+/// **\[Client-only\]** The user's accessibility preferences, such as color vision filters and high contrast.
+pub mod accessibility;
+
 /// **\[Client-only\]** Audio functionality, including loading sounds and playback.
 pub mod audio;
 
+/// **\[Client-only\]** The current lines of conversations, localized to the active language.
+pub mod dialogue;
+
 /// **\[Client-only\]** Input retrieval and manipulation.
 pub mod input;
 
 /// **\[Client-only\]** Helper functions for the camera.
 pub mod camera;
 
-/// **\[Client-only\]** Window-specific functionality.
+/// **\[Client-only\]** Localized strings and the active language. See also the [loc](crate::loc) macro.
+pub mod localization;
+
+/// **\[Client-only\]** Window-specific functionality, including the clipboard.
 pub mod window;
@@ -1,6 +1,41 @@
-use crate::internal::wit;
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    components::core::clipboard::{clipboard_read, clipboard_write},
+    entity,
+    global::block_until,
+    internal::wit,
+    message::RuntimeMessage,
+    messages::ClipboardText,
+};
 
 /// Request that the window enters or exits fullscreen mode.
 pub fn set_fullscreen(fullscreen: bool) {
     wit::client_window::set_fullscreen(fullscreen)
 }
+
+/// Replaces the contents of the clipboard with `text`.
+///
+/// The user is asked whether this module may access the clipboard the first time it does; nothing happens if they deny it.
+pub fn set_clipboard(text: impl Into<String>) {
+    entity::add_component(entity::resources(), clipboard_write(), text.into());
+}
+
+/// Returns the text in the clipboard, or `None` if it doesn't contain text or the user denied this module access to it.
+///
+/// The user is asked whether this module may access the clipboard the first time it does.
+pub async fn get_clipboard() -> Option<String> {
+    let text = Rc::new(RefCell::new(None));
+    let listener = ClipboardText::subscribe({
+        let text = text.clone();
+        move |message| *text.borrow_mut() = Some(message.text)
+    });
+    entity::add_component(entity::resources(), clipboard_read(), ());
+    block_until({
+        let text = text.clone();
+        move || text.borrow().is_some()
+    })
+    .await;
+    listener.stop();
+    text.take().flatten()
+}
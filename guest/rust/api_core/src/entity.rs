@@ -1,5 +1,6 @@
 use crate::{
-    global::{EntityId, Vec3},
+    components::core::{network::teleport_count, rendering::shader_params, transform::translation},
+    global::{EntityId, Mat4, Vec3},
     internal::{
         component::{Component, Entity, SupportedValue, UntypedComponent},
         conversion::{FromBindgen, IntoBindgen},
@@ -225,6 +226,35 @@ pub fn mutate_component_with_default<T: SupportedValue + SupportedValue + Clone
     }
 }
 
+/// Sets the value at `index` (from 0 to 15) of the [shader_params](crate::components::core::rendering::shader_params)
+/// of `entity`, which its material shader can read, keeping the others. They start at 0.
+///
+/// This will not set the component if the value is the same.
+pub fn set_shader_param(entity: EntityId, index: usize, value: f32) {
+    assert!(index < 16, "There are only 16 shader parameters");
+    let mut params = get_component(entity, shader_params()).unwrap_or(Mat4::ZERO);
+    if params.col(index / 4)[index % 4] != value {
+        params.col_mut(index / 4)[index % 4] = value;
+        add_component(entity, shader_params(), params);
+    }
+}
+
+/// Sets all of the [shader_params](crate::components::core::rendering::shader_params) of `entity`, which its
+/// material shader can read.
+pub fn set_shader_params(entity: EntityId, params: [f32; 16]) {
+    add_component(entity, shader_params(), Mat4::from_cols_array(&params));
+}
+
+/// Moves `entity` to `position` in one jump, like a respawn or a trip through a portal, so that clients show it there
+/// right away instead of gliding to it. Changes to its rotation and scale in the same frame jump along with it.
+///
+/// This raises its [teleport_count](crate::components::core::network::teleport_count).
+pub fn teleport(entity: EntityId, position: Vec3) {
+    add_component(entity, translation(), position);
+    let count = get_component(entity, teleport_count()).unwrap_or_default();
+    add_component(entity, teleport_count(), count.wrapping_add(1));
+}
+
 /// Gets the resource entity. The components of this entity contain global state for this ECS world.
 ///
 /// Components with the `Resource` attribute can be found here.
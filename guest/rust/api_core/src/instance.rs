@@ -0,0 +1,112 @@
+use thiserror::Error;
+
+use crate::{
+    components::core::{
+        instance::{
+            instance_create, instance_destroy, instance_error, instance_id,
+            instance_idle_tick_rate, instance_ids, instance_list, instance_modules, instance_move,
+            instance_tick_rate, instance_user,
+        },
+        player::user_id,
+    },
+    entity,
+    global::EntityId,
+    internal::component::Entity,
+};
+
+/// The ID of the instance the server starts with, which players join when they connect.
+pub const MAIN: &str = "main";
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+/// Errors that can occur when managing instances.
+pub enum InstanceError {
+    #[error("Instance request failed: {0}")]
+    /// The runtime could not complete the request, e.g. because the instance does not exist.
+    Request(String),
+    #[error("{0} is not a player")]
+    /// The entity to move is not a player.
+    NotAPlayer(EntityId),
+}
+
+/// Returns the ID of the instance this module is running in.
+pub fn current() -> String {
+    entity::get_component(entity::synchronized_resources(), instance_id())
+        .unwrap_or_else(|| MAIN.to_string())
+}
+
+/// Sets how many times per second the current instance is simulated (60 by default). Systems and modules that use
+/// [frametime](crate::global::frametime) adapt to it.
+pub fn set_tick_rate(rate: f32) {
+    entity::add_component(entity::resources(), instance_tick_rate(), rate);
+}
+
+/// Sets how many times per second the current instance is simulated while there are no players in it, such as 1 for
+/// an instance that is waiting for players. With `None`, it is simulated at its normal tick rate.
+pub fn set_idle_tick_rate(rate: Option<f32>) {
+    match rate {
+        Some(rate) => entity::add_component(entity::resources(), instance_idle_tick_rate(), rate),
+        None => entity::remove_component(entity::resources(), instance_idle_tick_rate()),
+    }
+}
+
+/// Runs a request, and returns it once it is done.
+async fn request(data: Entity) -> Result<EntityId, InstanceError> {
+    let request = entity::spawn(&data);
+    let error = entity::wait_for_component(request, instance_error()).await;
+    if error.is_empty() {
+        Ok(request)
+    } else {
+        entity::despawn(request);
+        Err(InstanceError::Request(error))
+    }
+}
+
+/// Creates the instance `id` as a copy of the current instance, without its players. The modules of the new instance
+/// are restarted, and run independently from the ones in this instance.
+pub async fn create(id: &str) -> Result<(), InstanceError> {
+    let request = request(Entity::new().with(instance_create(), id.to_string())).await?;
+    entity::despawn(request);
+    Ok(())
+}
+
+/// Like [create], but only the modules named `modules` run in the new instance, e.g. to run the match logic in a
+/// match instance but not the lobby logic.
+pub async fn create_with_modules(id: &str, modules: &[&str]) -> Result<(), InstanceError> {
+    let request = request(Entity::new().with(instance_create(), id.to_string()).with(
+        instance_modules(),
+        modules.iter().map(|m| m.to_string()).collect(),
+    ))
+    .await?;
+    entity::despawn(request);
+    Ok(())
+}
+
+/// Destroys the instance `id`. The players in it are moved to the [MAIN] instance, which can't be destroyed.
+pub async fn destroy(id: &str) -> Result<(), InstanceError> {
+    let request = request(Entity::new().with(instance_destroy(), id.to_string())).await?;
+    entity::despawn(request);
+    Ok(())
+}
+
+/// Moves the `player` to the instance `id`.
+///
+/// The player entity is despawned from this instance and spawned in the new one, so it gets a new ID there.
+pub async fn move_player(player: EntityId, id: &str) -> Result<(), InstanceError> {
+    let user = entity::get_component(player, user_id()).ok_or(InstanceError::NotAPlayer(player))?;
+    let request = request(
+        Entity::new()
+            .with(instance_move(), id.to_string())
+            .with(instance_user(), user),
+    )
+    .await?;
+    entity::despawn(request);
+    Ok(())
+}
+
+/// Returns the IDs of the instances, sorted.
+pub async fn list() -> Result<Vec<String>, InstanceError> {
+    let request = request(Entity::new().with_default(instance_list())).await?;
+    let ids = entity::get_component(request, instance_ids()).unwrap_or_default();
+    entity::despawn(request);
+    Ok(ids)
+}
@@ -18,7 +18,9 @@ impl wit::guest::Guest for Guest {
     }
 
     fn exec(time: f32, source: wit::guest::Source, message_name: String, message_data: Vec<u8>) {
+        crate::profiling::begin_call();
         EXECUTOR.execute(FrameState::new(time), source, message_name, message_data);
+        crate::profiling::end_call();
     }
 }
 
@@ -23,10 +23,23 @@ pub mod ecs;
 pub mod entity;
 /// Global functions and types for your convenience.
 pub mod global;
+/// Server world instances, such as lobbies, matches or instanced dungeons.
+#[cfg(feature = "server")]
+pub mod instance;
 /// Messaging to other modules and to the other side of the networking.
 pub mod message;
 /// Player-specific functionality.
 pub mod player;
+/// Profiling scopes, for finding out which parts of a module are slow.
+pub mod profiling;
+/// Save slots, for storing entities and module data between runs.
+pub mod save;
+/// Double-precision positions, for worlds larger than `f32` positions can represent precisely.
+pub mod transform;
+/// Tweakable variables, which can be changed while the project is running.
+pub mod tweakable;
+/// Voxel worlds of blocks, for block-building projects.
+pub mod voxel;
 
 /// Helpful imports that almost all Ambient projects will use.
 pub mod prelude;
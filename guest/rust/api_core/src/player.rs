@@ -9,6 +9,8 @@ pub fn get_by_user_id(user_id: &str) -> Option<EntityId> {
 }
 
 /// Get the local player's entity ID.
+///
+/// If the local client joined as a spectator, this is the ID of its spectator entity, which has no `player` component.
 #[cfg(feature = "client")]
 pub fn get_local() -> EntityId {
     wit::client_player::get_local().from_bindgen()
@@ -16,6 +16,8 @@ pub use crate::client::{
     audio, camera,
     input::{self, KeyCode, MouseButton},
 };
+#[cfg(feature = "client")]
+pub use crate::loc;
 
 #[cfg(feature = "server")]
 pub use crate::server::physics;
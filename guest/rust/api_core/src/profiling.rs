@@ -0,0 +1,122 @@
+use std::{cell::RefCell, time::Instant};
+
+use crate::{
+    components::core::profiling::{
+        profiling_span_depths, profiling_span_durations, profiling_span_names,
+        profiling_span_starts,
+    },
+    entity,
+    internal::component::Entity,
+};
+
+struct Span {
+    name: String,
+    start: Instant,
+    duration: f32,
+    depth: u32,
+}
+
+#[derive(Default)]
+struct Profiler {
+    call_start: Option<Instant>,
+    depth: u32,
+    spans: Vec<Span>,
+}
+
+thread_local! {
+    static PROFILER: RefCell<Profiler> = RefCell::new(Profiler::default());
+}
+
+/// A profiling scope, which ends when it is dropped. See [scope].
+#[must_use = "the scope ends when it is dropped"]
+pub struct Scope {
+    name: String,
+    start: Instant,
+    depth: u32,
+}
+impl Drop for Scope {
+    fn drop(&mut self) {
+        let duration = self.start.elapsed().as_secs_f32();
+        PROFILER.with(|profiler| {
+            let mut profiler = profiler.borrow_mut();
+            profiler.depth = profiler.depth.saturating_sub(1);
+            profiler.spans.push(Span {
+                name: std::mem::take(&mut self.name),
+                start: self.start,
+                duration,
+                depth: self.depth,
+            });
+        });
+    }
+}
+
+/// Starts a profiling scope named `name`, which lasts until the returned [Scope] is dropped.
+///
+/// The scopes of each module are shown in the flamegraph overlay of the debugger (run with `--debug`), nested under
+/// the message the module was handling, and are logged by the runtime at the `trace` level:
+///
+/// ```ignore
+/// let _scope = profiling::scope("pathfinding");
+/// ```
+pub fn scope(name: impl Into<String>) -> Scope {
+    let depth = PROFILER.with(|profiler| {
+        let mut profiler = profiler.borrow_mut();
+        profiler.depth += 1;
+        profiler.depth - 1
+    });
+    Scope {
+        name: name.into(),
+        start: Instant::now(),
+        depth,
+    }
+}
+
+/// Called by the runtime when a call to the module starts.
+pub(crate) fn begin_call() {
+    PROFILER.with(|profiler| profiler.borrow_mut().call_start = Some(Instant::now()));
+}
+
+/// Called by the runtime when a call to the module ends, to report the scopes that ended during it.
+pub(crate) fn end_call() {
+    let (call_start, spans) = PROFILER.with(|profiler| {
+        let mut profiler = profiler.borrow_mut();
+        (
+            profiler.call_start.take(),
+            std::mem::take(&mut profiler.spans),
+        )
+    });
+    let Some(call_start) = call_start else {
+        return;
+    };
+    if spans.is_empty() {
+        return;
+    }
+
+    let starts = spans
+        .iter()
+        .map(|span| {
+            if span.start >= call_start {
+                (span.start - call_start).as_secs_f32()
+            } else {
+                -(call_start - span.start).as_secs_f32()
+            }
+        })
+        .collect::<Vec<_>>();
+    entity::add_components(
+        entity::resources(),
+        Entity::new()
+            .with(profiling_span_starts(), starts)
+            .with(
+                profiling_span_durations(),
+                spans.iter().map(|span| span.duration).collect(),
+            )
+            .with(
+                profiling_span_depths(),
+                spans.iter().map(|span| span.depth).collect(),
+            )
+            .with(
+                profiling_span_names(),
+                spans.into_iter().map(|span| span.name).collect(),
+            ),
+    );
+}
@@ -0,0 +1,172 @@
+use std::{cell::RefCell, collections::BTreeMap};
+
+use thiserror::Error;
+
+use crate::{
+    components::core::save::{
+        save_components, save_data, save_delete, save_entities, save_error, save_list, save_load,
+        save_slot, save_slots, save_version,
+    },
+    entity,
+    global::EntityId,
+    internal::component::{Component, Entity, UntypedComponent},
+};
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+/// Errors that can occur when using save slots.
+pub enum SaveError {
+    #[error("Save request failed: {0}")]
+    /// The runtime could not complete the request, e.g. because the slot does not exist or its name is invalid.
+    Request(String),
+    #[error("The slot was saved with version {saved}, which is newer than {current}")]
+    /// The slot was saved by a newer version of the module.
+    NewerVersion {
+        /// The version the slot was saved with.
+        saved: u32,
+        /// The version that was asked for.
+        current: u32,
+    },
+}
+
+/// What to store in a save slot.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SaveData {
+    /// The entities to save, with all their stored components (or only the ones added with [SaveData::with_component]).
+    pub entities: Vec<EntityId>,
+    /// Module-defined data, such as a serialized struct.
+    pub data: String,
+    components: Vec<u32>,
+}
+impl SaveData {
+    /// Creates empty save data.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `entities` to save.
+    pub fn with_entities(mut self, entities: impl IntoIterator<Item = EntityId>) -> Self {
+        self.entities.extend(entities);
+        self
+    }
+
+    /// Only saves `component` (and the other components added with this) of the entities, instead of all their
+    /// stored components.
+    pub fn with_component<T>(mut self, component: Component<T>) -> Self {
+        self.components.push(component.index());
+        self
+    }
+
+    /// Sets the module-defined data to save.
+    pub fn with_data(mut self, data: impl Into<String>) -> Self {
+        self.data = data.into();
+        self
+    }
+}
+
+/// A loaded save slot.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoadedSave {
+    /// The version of the save format; once migrations have run, this is the version that was asked for.
+    pub version: u32,
+    /// The entities that were loaded.
+    pub entities: Vec<EntityId>,
+    /// The module-defined data the slot was saved with.
+    pub data: String,
+}
+
+type Migration = Box<dyn Fn(&mut LoadedSave)>;
+thread_local! {
+    static MIGRATIONS: RefCell<BTreeMap<u32, Migration>> = RefCell::new(BTreeMap::new());
+}
+
+/// Registers a migration of slots saved with version `from` to version `from + 1`.
+///
+/// When a slot with an older version is loaded with [load], the migrations between the two versions run in order on the
+/// loaded save, so that they can update its data and entities. Versions without a migration are assumed to be
+/// compatible with the next one.
+pub fn register_migration(from: u32, migrate: impl Fn(&mut LoadedSave) + 'static) {
+    MIGRATIONS.with(|migrations| migrations.borrow_mut().insert(from, Box::new(migrate)));
+}
+
+/// Runs a request, and returns it once it is done.
+async fn request(data: Entity) -> Result<EntityId, SaveError> {
+    let request = entity::spawn(&data);
+    let error = entity::wait_for_component(request, save_error()).await;
+    if error.is_empty() {
+        Ok(request)
+    } else {
+        entity::despawn(request);
+        Err(SaveError::Request(error))
+    }
+}
+
+/// Stores `data` in the save slot named `slot`, with the `version` of the module's save format.
+///
+/// Slots are stored on the side (server or client) this is called on; slot names can only contain letters, digits, `-`
+/// and `_`. An existing slot with the same name is replaced.
+pub async fn save(slot: &str, version: u32, data: SaveData) -> Result<(), SaveError> {
+    let request = request(
+        Entity::new()
+            .with(save_slot(), slot.to_string())
+            .with(save_version(), version)
+            .with(save_entities(), data.entities)
+            .with(save_components(), data.components)
+            .with(save_data(), data.data),
+    )
+    .await?;
+    entity::despawn(request);
+    Ok(())
+}
+
+/// Loads the save slot named `slot`, restoring its entities, and migrates it to `version` (see [register_migration]).
+pub async fn load(slot: &str, version: u32) -> Result<LoadedSave, SaveError> {
+    let request = request(
+        Entity::new()
+            .with(save_slot(), slot.to_string())
+            .with_default(save_load()),
+    )
+    .await?;
+    let mut loaded = LoadedSave {
+        version: entity::get_component(request, save_version()).unwrap_or_default(),
+        entities: entity::get_component(request, save_entities()).unwrap_or_default(),
+        data: entity::get_component(request, save_data()).unwrap_or_default(),
+    };
+    entity::despawn(request);
+
+    if loaded.version > version {
+        return Err(SaveError::NewerVersion {
+            saved: loaded.version,
+            current: version,
+        });
+    }
+    MIGRATIONS.with(|migrations| {
+        let migrations = migrations.borrow();
+        while loaded.version < version {
+            if let Some(migrate) = migrations.get(&loaded.version) {
+                migrate(&mut loaded);
+            }
+            loaded.version += 1;
+        }
+    });
+    Ok(loaded)
+}
+
+/// Deletes the save slot named `slot`.
+pub async fn delete(slot: &str) -> Result<(), SaveError> {
+    let request = request(
+        Entity::new()
+            .with(save_slot(), slot.to_string())
+            .with_default(save_delete()),
+    )
+    .await?;
+    entity::despawn(request);
+    Ok(())
+}
+
+/// Returns the names of the save slots on this side, sorted.
+pub async fn list() -> Result<Vec<String>, SaveError> {
+    let request = request(Entity::new().with_default(save_list())).await?;
+    let slots = entity::get_component(request, save_slots()).unwrap_or_default();
+    entity::despawn(request);
+    Ok(slots)
+}
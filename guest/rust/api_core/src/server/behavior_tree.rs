@@ -0,0 +1,63 @@
+use crate::{
+    components::core::behavior_tree::{
+        behavior_tree_blackboard_keys, behavior_tree_blackboard_values, behavior_tree_task,
+        behavior_tree_task_result,
+    },
+    ecs::query,
+    entity,
+    global::EntityId,
+    message::Listener,
+};
+
+/// Implements the `task` leaves named `name` of all behavior trees.
+///
+/// `task` is called each frame for each agent that is running the task, until it returns a result: `Some(true)` if
+/// the task succeeded, `Some(false)` if it failed, or `None` if it is still running.
+pub fn register_task(name: &str, task: impl Fn(EntityId) -> Option<bool> + 'static) -> Listener {
+    let name = name.to_string();
+    query(behavior_tree_task())
+        .excludes(behavior_tree_task_result())
+        .each_frame(move |agents| {
+            for (agent, running) in agents {
+                if running != name {
+                    continue;
+                }
+                if let Some(success) = task(agent) {
+                    entity::add_component(agent, behavior_tree_task_result(), success);
+                }
+            }
+        })
+}
+
+/// Returns the value of `key` on the `agent`'s blackboard, if it is set.
+pub fn get_blackboard(agent: EntityId, key: &str) -> Option<String> {
+    let keys = entity::get_component(agent, behavior_tree_blackboard_keys())?;
+    let values = entity::get_component(agent, behavior_tree_blackboard_values())?;
+    keys.into_iter()
+        .zip(values)
+        .find_map(|(k, v)| (k == key).then_some(v))
+}
+
+/// Sets `key` to `value` on the `agent`'s blackboard, or removes it if `value` is `None`.
+pub fn set_blackboard(agent: EntityId, key: &str, value: Option<&str>) {
+    let mut keys =
+        entity::get_component(agent, behavior_tree_blackboard_keys()).unwrap_or_default();
+    let mut values =
+        entity::get_component(agent, behavior_tree_blackboard_values()).unwrap_or_default();
+    values.resize(keys.len(), String::new());
+    let index = keys.iter().position(|k| k == key);
+    match (index, value) {
+        (Some(index), Some(value)) => values[index] = value.to_string(),
+        (Some(index), None) => {
+            keys.remove(index);
+            values.remove(index);
+        }
+        (None, Some(value)) => {
+            keys.push(key.to_string());
+            values.push(value.to_string());
+        }
+        (None, None) => return,
+    }
+    entity::add_component(agent, behavior_tree_blackboard_keys(), keys);
+    entity::add_component(agent, behavior_tree_blackboard_values(), values);
+}
@@ -0,0 +1,60 @@
+use crate::{
+    components::core::dialogue::{
+        dialogue_choice, dialogue_from_url, dialogue_participant, dialogue_variable_keys,
+        dialogue_variable_values,
+    },
+    entity,
+    global::EntityId,
+    internal::component::Entity,
+};
+
+/// Starts a conversation with `participant` using the dialogue graph at `url` (e.g. from [asset::url](crate::asset::url)),
+/// and returns the conversation entity.
+///
+/// A [DialogueChoice](crate::messages::DialogueChoice) message is sent for each choice that is made, and a
+/// [DialogueEnd](crate::messages::DialogueEnd) message once the conversation ends.
+pub fn start(url: impl Into<String>, participant: EntityId) -> EntityId {
+    entity::spawn(
+        &Entity::new()
+            .with(dialogue_from_url(), url.into())
+            .with(dialogue_participant(), participant),
+    )
+}
+
+/// Picks the `choice`th of the choices currently offered in the `conversation`, or continues the current line if it
+/// has no choices and `choice` is 0. Typically called when the participant's client sends its choice to the server.
+pub fn choose(conversation: EntityId, choice: u32) {
+    entity::add_component(conversation, dialogue_choice(), choice);
+}
+
+/// Returns the value of the `participant`'s dialogue variable `name`, if it is set.
+pub fn get_variable(participant: EntityId, name: &str) -> Option<String> {
+    let keys = entity::get_component(participant, dialogue_variable_keys())?;
+    let values = entity::get_component(participant, dialogue_variable_values())?;
+    keys.into_iter()
+        .zip(values)
+        .find_map(|(k, v)| (k == name).then_some(v))
+}
+
+/// Sets the `participant`'s dialogue variable `name` to `value`, or removes it if `value` is `None`.
+pub fn set_variable(participant: EntityId, name: &str, value: Option<&str>) {
+    let mut keys = entity::get_component(participant, dialogue_variable_keys()).unwrap_or_default();
+    let mut values =
+        entity::get_component(participant, dialogue_variable_values()).unwrap_or_default();
+    values.resize(keys.len(), String::new());
+    let index = keys.iter().position(|k| k == name);
+    match (index, value) {
+        (Some(index), Some(value)) => values[index] = value.to_string(),
+        (Some(index), None) => {
+            keys.remove(index);
+            values.remove(index);
+        }
+        (None, Some(value)) => {
+            keys.push(name.to_string());
+            values.push(value.to_string());
+        }
+        (None, None) => return,
+    }
+    entity::add_component(participant, dialogue_variable_keys(), keys);
+    entity::add_component(participant, dialogue_variable_values(), values);
+}
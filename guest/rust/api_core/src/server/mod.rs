@@ -1,2 +1,12 @@
+/// **\[Server-only\]** Behavior trees for AI agents: implementing their tasks and accessing their blackboards.
+pub mod behavior_tree;
+/// **\[Server-only\]** Branching conversations: starting them, making choices, and the variables they use.
+pub mod dialogue;
+/// **\[Server-only\]** Entity ownership, and components that the owner's client sets instead of the server.
+pub mod ownership;
 /// **\[Server-only\]** Physics-related functionality, including applying forces, changing physical properties, and more.
 pub mod physics;
+/// **\[Server-only\]** Ballistic projectiles that are simulated on the server and predicted on the clients.
+pub mod projectile;
+/// **\[Server-only\]** Sequences that play timelines for cutscenes: starting, pausing and seeking them.
+pub mod sequencer;
@@ -0,0 +1,86 @@
+use crate::{
+    components::core::network::{
+        client_authoritative, client_authority_accepted, client_authority_corrected,
+        client_authority_validated, owner,
+    },
+    entity,
+    global::EntityId,
+    internal::component::{__internal_get_component, Component, SupportedValue},
+    message::{Listener, RuntimeMessage},
+    messages::ClientAuthorityUpdate,
+};
+
+/// Makes the client of `user_id` the owner of `entity`, or gives the entity back to the server with `None`.
+///
+/// The owner's client sets the components of the entity that were made client-authoritative with
+/// [set_client_authoritative], instead of the server.
+pub fn set_owner(entity: EntityId, user_id: Option<&str>) {
+    match user_id {
+        Some(user_id) => entity::add_component(entity, owner(), user_id.to_string()),
+        None => entity::remove_component(entity, owner()),
+    }
+}
+
+/// Returns the user ID of the client that owns `entity`, if it is owned by a client.
+pub fn get_owner(entity: EntityId) -> Option<String> {
+    entity::get_component(entity, owner())
+}
+
+/// Lets the owner of `entity` set the components with the IDs `components` (e.g. `"my_project::aim_direction"`). The
+/// owner's client sends their values to the server when they change, so that they don't make a round trip, and the
+/// server applies them after they are checked by the validators of the components (see [validate]).
+///
+/// Use this for small components that change often, like input state; they are sent unreliably.
+pub fn set_client_authoritative(entity: EntityId, components: &[&str]) {
+    entity::add_component(
+        entity,
+        client_authoritative(),
+        components.iter().map(|c| c.to_string()).collect(),
+    );
+}
+
+/// Validates the values that owners send for the client-authoritative component with the ID `component_id`, before
+/// they are applied.
+///
+/// The `validator` is called with the entity, the user ID of its owner and the value it sent, and returns the value to
+/// apply, or `None` to reject it. If the value is changed or rejected, the owner's client is sent the value the entity
+/// ends up with, so that cheating or mispredicting clients are corrected.
+///
+/// Values that aren't validated within a second are dropped. Stopping the returned listener stops validating, and
+/// values are then dropped until another validator is registered for the component.
+pub fn validate<T: SupportedValue + Clone + PartialEq + 'static>(
+    component_id: &str,
+    mut validator: impl FnMut(EntityId, &str, T) -> Option<T> + 'static,
+) -> Listener {
+    let component: Component<T> = __internal_get_component(component_id);
+    let component_id = component_id.to_string();
+    entity::mutate_component_with_default(
+        entity::resources(),
+        client_authority_validated(),
+        vec![component_id.clone()],
+        |validated| {
+            if !validated.contains(&component_id) {
+                validated.push(component_id.clone());
+            }
+        },
+    );
+
+    ClientAuthorityUpdate::subscribe(move |update| {
+        if update.component != component_id {
+            return;
+        }
+        let Some(value) = entity::get_component(update.proposal, component) else {
+            return;
+        };
+        match validator(update.entity, &update.user_id, value.clone()) {
+            Some(accepted) => {
+                if accepted != value {
+                    entity::set_component(update.proposal, component, accepted);
+                    entity::add_component(update.proposal, client_authority_corrected(), ());
+                }
+                entity::add_component(update.proposal, client_authority_accepted(), ());
+            }
+            None => entity::add_component(update.proposal, client_authority_corrected(), ()),
+        }
+    })
+}
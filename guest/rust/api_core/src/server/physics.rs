@@ -1,6 +1,20 @@
 use crate::{
+    components::core::{
+        app::name,
+        ecs::parent,
+        physics::{
+            raycast_rewound_direction, raycast_rewound_hit_distances, raycast_rewound_hit_entities,
+            raycast_rewound_latency, raycast_rewound_origin, scene_query_directions,
+            scene_query_hit_distances, scene_query_hit_entities, scene_query_ignored,
+            scene_query_max_distances, scene_query_origins, scene_query_radius, surface_effect,
+            surface_effect_material, surface_effect_normal, surface_material,
+        },
+        transform::translation,
+    },
+    entity,
     global::{EntityId, Vec3},
     internal::{
+        component::Entity,
         conversion::{FromBindgen, IntoBindgen},
         wit,
     },
@@ -153,6 +167,13 @@ pub struct RaycastHit {
     /// The entity that was hit.
     pub entity: EntityId,
 }
+impl RaycastHit {
+    /// The [surface_material](crate::components::core::physics::surface_material) of the entity that was hit,
+    /// or of the nearest of its ancestors that has one.
+    pub fn surface_material(&self) -> Option<String> {
+        surface_material_of(self.entity)
+    }
+}
 /// Casts a ray from `origin` in `direction`, and returns the [RaycastHit]s along the way.
 ///
 /// `direction` must be normalized.
@@ -169,6 +190,111 @@ pub fn raycast_first(origin: Vec3, direction: Vec3) -> Option<RaycastHit> {
     wit::server_physics::raycast_first(origin.into_bindgen(), direction.into_bindgen())
         .map(|(entity, distance)| raycast_result_to_hit(origin, direction, entity, distance))
 }
+/// Casts a ray from `origin` in `direction` like [raycast], but tests it against the
+/// [lag_compensated](crate::components::core::physics::lag_compensated) entities where they were
+/// `shooter_latency` seconds ago, so that a shot hits what the shooter saw when they fired it.
+/// Other entities are tested where they are now. The latency is clamped to one second.
+///
+/// The raycast is done at the end of the current frame, so this resolves on the next one.
+///
+/// `direction` must be normalized.
+pub async fn raycast_rewound(
+    origin: Vec3,
+    direction: Vec3,
+    shooter_latency: f32,
+) -> Vec<RaycastHit> {
+    let request = entity::spawn(
+        &Entity::new()
+            .with(raycast_rewound_origin(), origin)
+            .with(raycast_rewound_direction(), direction)
+            .with(raycast_rewound_latency(), shooter_latency),
+    );
+    let distances = entity::wait_for_component(request, raycast_rewound_hit_distances()).await;
+    let entities =
+        entity::get_component(request, raycast_rewound_hit_entities()).unwrap_or_default();
+    entity::despawn(request);
+    entities
+        .into_iter()
+        .zip(distances)
+        .map(|(entity, distance)| RaycastHit {
+            position: origin + direction * distance,
+            distance,
+            entity,
+        })
+        .collect()
+}
+/// One of the queries of a [raycast_batch] or [sweep_sphere_batch].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BatchQuery {
+    /// Where the query starts.
+    pub origin: Vec3,
+    /// The normalized direction of the query.
+    pub direction: Vec3,
+    /// How far the query goes.
+    pub max_distance: f32,
+}
+/// Casts many rays at once, like AI agents checking whether they can see their targets, and returns the first
+/// [RaycastHit] of each [BatchQuery], in the same order, or `None` if it hit nothing.
+///
+/// The rays of all batches are cast in parallel at the end of the current frame, so this resolves on the next one;
+/// this is much faster than many calls to [raycast_first]. Only the colliders of the physics scene are hit, and the
+/// colliders of the `ignored` entities (such as the agents themselves) are passed through.
+pub async fn raycast_batch(
+    queries: &[BatchQuery],
+    ignored: &[EntityId],
+) -> Vec<Option<RaycastHit>> {
+    scene_query_batch(queries, None, ignored).await
+}
+/// Sweeps a sphere of `radius` along each of the `queries` at once, like [raycast_batch], and returns the first
+/// [RaycastHit] of each. The position of a hit is where the center of the sphere was when it hit.
+pub async fn sweep_sphere_batch(
+    queries: &[BatchQuery],
+    radius: f32,
+    ignored: &[EntityId],
+) -> Vec<Option<RaycastHit>> {
+    scene_query_batch(queries, Some(radius), ignored).await
+}
+async fn scene_query_batch(
+    queries: &[BatchQuery],
+    radius: Option<f32>,
+    ignored: &[EntityId],
+) -> Vec<Option<RaycastHit>> {
+    if queries.is_empty() {
+        return Vec::new();
+    }
+    let mut request = Entity::new()
+        .with(
+            scene_query_origins(),
+            queries.iter().map(|query| query.origin).collect(),
+        )
+        .with(
+            scene_query_directions(),
+            queries.iter().map(|query| query.direction).collect(),
+        )
+        .with(
+            scene_query_max_distances(),
+            queries.iter().map(|query| query.max_distance).collect(),
+        )
+        .with(scene_query_ignored(), ignored.to_vec());
+    if let Some(radius) = radius {
+        request.set(scene_query_radius(), radius);
+    }
+    let request = entity::spawn(&request);
+    let distances = entity::wait_for_component(request, scene_query_hit_distances()).await;
+    let entities = entity::get_component(request, scene_query_hit_entities()).unwrap_or_default();
+    entity::despawn(request);
+    queries
+        .iter()
+        .zip(entities.into_iter().zip(distances))
+        .map(|(query, (entity, distance))| {
+            (distance >= 0.).then(|| RaycastHit {
+                position: query.origin + query.direction * distance,
+                distance,
+                entity,
+            })
+        })
+        .collect()
+}
 fn raycast_result_to_hit(
     origin: Vec3,
     direction: Vec3,
@@ -234,3 +360,38 @@ pub fn set_character_foot_position(
         position.into_bindgen(),
     );
 }
+
+/// The [surface_material](crate::components::core::physics::surface_material) of `entity`, or of the nearest of its
+/// ancestors that has one, as models usually put their colliders on child entities.
+pub fn surface_material_of(entity: EntityId) -> Option<String> {
+    let mut current = Some(entity);
+    while let Some(entity) = current {
+        if let Some(material) = entity::get_component(entity, surface_material()) {
+            return Some(material);
+        }
+        current = entity::get_component(entity, parent());
+    }
+    None
+}
+
+/// Plays an effect of `kind` (e.g. `"footstep"` or `"impact"`) at `position` on a surface of `surface_material`,
+/// facing along its `normal`, on all clients.
+///
+/// The sounds, decals and prefabs of the effect are chosen by the entities with a
+/// [surface_effects_kind](crate::components::core::physics::surface_effects_kind), so that each material can sound
+/// and look different. Materials without a mapping of their own use the one of the `"default"` material.
+pub fn spawn_surface_effect(
+    kind: &str,
+    position: Vec3,
+    normal: Vec3,
+    surface_material: &str,
+) -> EntityId {
+    entity::spawn(
+        &Entity::new()
+            .with(name(), format!("Surface effect: {kind}"))
+            .with(surface_effect(), kind.to_string())
+            .with(surface_effect_material(), surface_material.to_string())
+            .with(surface_effect_normal(), normal)
+            .with(translation(), position),
+    )
+}
@@ -0,0 +1,51 @@
+use crate::{
+    components::core::{
+        projectile::{projectile_inactive, projectile_pool, projectile_velocity},
+        transform::translation,
+    },
+    ecs::query,
+    entity,
+    global::{EntityId, Vec3},
+    internal::component::Entity,
+};
+
+/// Spawns a projectile at `position`, moving at `velocity`, with the additional `data` (e.g. its model,
+/// [projectile_gravity](crate::components::core::projectile::projectile_gravity) or
+/// [projectile_owner](crate::components::core::projectile::projectile_owner)).
+///
+/// The projectile is simulated on the server, and predicted on the clients. When it hits something, a
+/// [ProjectileImpact](crate::messages::ProjectileImpact) message is sent and the projectile is despawned.
+pub fn spawn(position: Vec3, velocity: Vec3, data: Entity) -> EntityId {
+    entity::spawn(
+        &data
+            .with(translation(), position)
+            .with(projectile_velocity(), velocity),
+    )
+}
+
+/// Like [spawn], but reuses an inactive projectile from the same `pool` if there is one, and deactivates the
+/// projectile instead of despawning it once it is done. Use this for frequently fired projectiles, like bullets.
+///
+/// The `data` is added to the reused projectile, replacing its components of the same type; components that are not
+/// in `data` keep their values from the projectile's last use.
+pub fn spawn_pooled(pool: &str, position: Vec3, velocity: Vec3, data: Entity) -> EntityId {
+    let data = data
+        .with(translation(), position)
+        .with(projectile_velocity(), velocity)
+        .with(projectile_pool(), pool.to_string());
+    let inactive = query(projectile_pool())
+        .requires(projectile_inactive())
+        .build()
+        .evaluate()
+        .into_iter()
+        .find(|(_, p)| p == pool);
+    match inactive {
+        Some((id, _)) => {
+            // Removed here rather than by the server, so that it isn't reused again before the server sees it
+            entity::remove_component(id, projectile_inactive());
+            entity::add_components(id, data);
+            id
+        }
+        None => entity::spawn(&data),
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{
+    components::core::sequencer::{
+        sequence_actor_names, sequence_actors, sequence_from_recording, sequence_from_url,
+        sequence_looping, sequence_playing, sequence_record, sequence_record_interval,
+        sequence_seek, sequence_speed, sequence_time,
+    },
+    entity,
+    global::EntityId,
+    internal::component::Entity,
+};
+
+/// Plays the timeline at `url` (e.g. from [asset::url](crate::asset::url)), with its actor names bound to the
+/// `actors`, and returns the sequence entity.
+///
+/// A [SequenceEvent](crate::messages::SequenceEvent) message is sent for each event of the timeline that is reached,
+/// and a [SequenceEnd](crate::messages::SequenceEnd) message once the sequence reaches its end. Despawn the sequence
+/// to stop it.
+pub fn play(url: impl Into<String>, actors: &[(&str, EntityId)]) -> EntityId {
+    entity::spawn(
+        &Entity::new()
+            .with(sequence_from_url(), url.into())
+            .with(
+                sequence_actor_names(),
+                actors.iter().map(|(name, _)| name.to_string()).collect(),
+            )
+            .with(
+                sequence_actors(),
+                actors.iter().map(|(_, id)| *id).collect(),
+            )
+            .with(sequence_time(), 0.),
+    )
+}
+
+/// Starts recording the translation, rotation and scale of the `entity` as `name`, with a key every `interval` seconds.
+/// Names may only contain letters, digits, `-` and `_`; an earlier recording with the same name is replaced.
+///
+/// The recording is written when it is [stop_recording]ed or the `entity` is despawned.
+pub fn record(entity: EntityId, name: impl Into<String>, interval: f32) {
+    entity::add_components(
+        entity,
+        Entity::new()
+            .with(sequence_record(), name.into())
+            .with(sequence_record_interval(), interval),
+    );
+}
+
+/// Stops recording the `entity`, and writes its recording.
+pub fn stop_recording(entity: EntityId) {
+    entity::remove_component(entity, sequence_record());
+}
+
+/// Replays the recording `name` on the `ghost` (e.g. an entity with the same model as the recorded one, but no physics
+/// or input), which then plays as a sequence: it can be paused, [seek]ed and sped up like any other.
+pub fn play_recording(ghost: EntityId, name: impl Into<String>) {
+    entity::add_components(
+        ghost,
+        Entity::new()
+            .with(sequence_from_recording(), name.into())
+            .with(sequence_time(), 0.),
+    );
+}
+
+/// Pauses the `sequence`.
+pub fn pause(sequence: EntityId) {
+    entity::add_component(sequence, sequence_playing(), false);
+}
+
+/// Resumes the `sequence` if it was paused. A sequence that reached its end has to be [seek]ed back first.
+pub fn resume(sequence: EntityId) {
+    entity::add_component(sequence, sequence_playing(), true);
+}
+
+/// Jumps to `time` (in seconds) in the `sequence`, skipping the events in between.
+pub fn seek(sequence: EntityId, time: f32) {
+    entity::add_component(sequence, sequence_seek(), time);
+}
+
+/// Returns the time of the `sequence`, in seconds from the start of its timeline.
+pub fn time(sequence: EntityId) -> Option<f32> {
+    entity::get_component(sequence, sequence_time())
+}
+
+/// Sets how fast the `sequence` plays, as a multiplier.
+pub fn set_speed(sequence: EntityId, speed: f32) {
+    entity::add_component(sequence, sequence_speed(), speed);
+}
+
+/// Sets whether the `sequence` starts over when it reaches its end.
+pub fn set_looping(sequence: EntityId, looping: bool) {
+    entity::add_component(sequence, sequence_looping(), looping);
+}
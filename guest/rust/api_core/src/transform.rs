@@ -0,0 +1,59 @@
+use glam::{DVec3, Vec3};
+
+use crate::{
+    components::core::transform::{
+        self as components, large_scale_positions, translation, translation_f64,
+    },
+    entity,
+    global::EntityId,
+};
+
+/// Enables or disables large-scale positions, with which the `translation` of entities with a
+/// [translation_f64](crate::components::core::transform::translation_f64) is derived from it relative to the
+/// [world_origin]. This allows for worlds far larger than `f32` positions can represent precisely, like solar systems.
+///
+/// On the client, the origin follows the active camera if it has a `translation_f64`, so that everything is rendered
+/// relative to the camera. Call this from the server to enable it on all clients too.
+pub fn set_large_scale_positions(enabled: bool) {
+    let resources = entity::synchronized_resources();
+    if enabled {
+        entity::add_component(resources, large_scale_positions(), ());
+    } else {
+        entity::remove_component(resources, large_scale_positions());
+    }
+}
+
+/// Sets the double-precision world-space position of `entity`.
+pub fn set_world_position(entity: EntityId, position: DVec3) {
+    entity::add_component(entity, translation_f64(), position.to_array().to_vec());
+}
+
+/// Gets the double-precision world-space position of `entity`, falling back to its `translation` relative to the
+/// [world_origin] if it doesn't have one.
+pub fn get_world_position(entity: EntityId) -> Option<DVec3> {
+    entity::get_component(entity, translation_f64())
+        .and_then(|position| dvec3_from_slice(&position))
+        .or_else(|| {
+            let translation = entity::get_component(entity, translation())?;
+            Some(translation.as_dvec3() + world_origin())
+        })
+}
+
+/// The double-precision position that `translation`s are relative to when large-scale positions are enabled.
+pub fn world_origin() -> DVec3 {
+    entity::get_component(entity::resources(), components::world_origin())
+        .and_then(|origin| dvec3_from_slice(&origin))
+        .unwrap_or(DVec3::ZERO)
+}
+
+/// Converts a double-precision world-space `position` to a `translation`, relative to the [world_origin].
+pub fn to_translation(position: DVec3) -> Vec3 {
+    (position - world_origin()).as_vec3()
+}
+
+fn dvec3_from_slice(value: &[f64]) -> Option<DVec3> {
+    match value {
+        &[x, y, z] => Some(DVec3::new(x, y, z)),
+        _ => None,
+    }
+}
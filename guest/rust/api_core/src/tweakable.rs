@@ -0,0 +1,118 @@
+use std::{fmt::Display, ops::RangeInclusive};
+
+use crate::{
+    components::core::{
+        network::is_remote_entity,
+        tweakable::{
+            tweakable_bool, tweakable_default, tweakable_f32, tweakable_i32, tweakable_max,
+            tweakable_min, tweakable_name, tweakable_replicated, tweakable_string,
+        },
+    },
+    ecs::query,
+    entity,
+    global::EntityId,
+    internal::component::{Component, Entity, SupportedValue},
+};
+
+/// A variable that can be changed while the project is running, e.g. from the debugger.
+///
+/// Changed values are persisted per project, and restored the next time the tweakable is declared; the restored value
+/// is available from the frame after it was first declared.
+#[derive(Debug, Clone)]
+pub struct Tweakable<T> {
+    id: EntityId,
+    component: Component<T>,
+    default: T,
+}
+impl<T: SupportedValue + Clone> Tweakable<T> {
+    /// The entity of this tweakable.
+    pub fn id(&self) -> EntityId {
+        self.id
+    }
+
+    /// The current value of this tweakable.
+    pub fn get(&self) -> T {
+        entity::get_component(self.id, self.component).unwrap_or_else(|| self.default.clone())
+    }
+
+    /// Sets the value of this tweakable. Numeric values are clamped to its range.
+    pub fn set(&self, value: T) {
+        entity::set_component(self.id, self.component, value);
+    }
+
+    /// Replicates this tweakable to the clients. Only has an effect on tweakables declared on the server.
+    pub fn replicated(self) -> Self {
+        entity::add_component(self.id, tweakable_replicated(), ());
+        self
+    }
+}
+
+/// Declares a floating-point tweakable named `name`, which is clamped to `range`.
+///
+/// If a tweakable with this name was already declared by this side, it is returned instead.
+pub fn f32(name: &str, default: f32, range: RangeInclusive<f32>) -> Tweakable<f32> {
+    declare(
+        name,
+        tweakable_f32(),
+        default,
+        Some((*range.start(), *range.end())),
+    )
+}
+
+/// Declares an integer tweakable named `name`, which is clamped to `range`.
+///
+/// If a tweakable with this name was already declared by this side, it is returned instead.
+pub fn i32(name: &str, default: i32, range: RangeInclusive<i32>) -> Tweakable<i32> {
+    declare(
+        name,
+        tweakable_i32(),
+        default,
+        Some((*range.start() as f32, *range.end() as f32)),
+    )
+}
+
+/// Declares a boolean tweakable named `name`.
+///
+/// If a tweakable with this name was already declared by this side, it is returned instead.
+pub fn bool(name: &str, default: bool) -> Tweakable<bool> {
+    declare(name, tweakable_bool(), default, None)
+}
+
+/// Declares a string tweakable named `name`.
+///
+/// If a tweakable with this name was already declared by this side, it is returned instead.
+pub fn string(name: &str, default: &str) -> Tweakable<String> {
+    declare(name, tweakable_string(), default.to_string(), None)
+}
+
+fn declare<T: SupportedValue + Clone + Display>(
+    name: &str,
+    component: Component<T>,
+    default: T,
+    range: Option<(f32, f32)>,
+) -> Tweakable<T> {
+    let existing = query(tweakable_name())
+        .excludes(is_remote_entity())
+        .build()
+        .evaluate()
+        .into_iter()
+        .find(|(_, n)| n == name);
+    let id = match existing {
+        Some((id, _)) => id,
+        None => {
+            let mut data = Entity::new()
+                .with(tweakable_name(), name.to_string())
+                .with(tweakable_default(), default.to_string())
+                .with(component, default.clone());
+            if let Some((min, max)) = range {
+                data = data.with(tweakable_min(), min).with(tweakable_max(), max);
+            }
+            entity::spawn(&data)
+        }
+    };
+    Tweakable {
+        id,
+        component,
+        default,
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use glam::{IVec3, Vec3};
+
+use crate::{
+    components::core::{
+        transform::translation,
+        voxel::{
+            voxel_block_faces, voxel_block_size, voxel_chunk, voxel_chunk_blocks,
+            voxel_chunk_edits, voxel_chunk_position, voxel_materials, voxel_world,
+        },
+    },
+    ecs::query,
+    entity,
+    global::EntityId,
+    internal::component::Entity,
+};
+
+/// The number of blocks along each side of a chunk.
+pub const CHUNK_SIZE: i32 = 16;
+
+/// The chunk that the block at `position` is in, and the index of the block within the chunk.
+fn chunk_of(position: IVec3) -> (IVec3, usize) {
+    let chunk = position.div_euclid(IVec3::splat(CHUNK_SIZE));
+    let local = position.rem_euclid(IVec3::splat(CHUNK_SIZE));
+    let index = local.x + local.y * CHUNK_SIZE + local.z * CHUNK_SIZE * CHUNK_SIZE;
+    (chunk, index as usize)
+}
+
+/// The chunks of the voxel `world`, by their position.
+fn chunks(world: EntityId) -> HashMap<IVec3, EntityId> {
+    query((voxel_chunk(), voxel_chunk_position()))
+        .build()
+        .evaluate()
+        .into_iter()
+        .filter(|(_, (owner, _))| *owner == world)
+        .map(|(id, (_, position))| (position.round().as_ivec3(), id))
+        .collect()
+}
+
+/// Spawns an empty voxel world with its corner at `origin` and blocks of `block_size` meters, and returns it.
+///
+/// The faces of the blocks are drawn with the `materials` (URLs of PBR materials, e.g. from
+/// [asset::url](crate::asset::url)), at the indices in `block_faces`: three per kind of block, starting with block 1,
+/// for its top, its sides and its bottom.
+pub fn spawn_world(
+    origin: Vec3,
+    block_size: f32,
+    materials: Vec<String>,
+    block_faces: Vec<u32>,
+) -> EntityId {
+    entity::spawn(
+        &Entity::new()
+            .with_default(voxel_world())
+            .with(translation(), origin)
+            .with(voxel_block_size(), block_size)
+            .with(voxel_materials(), materials)
+            .with(voxel_block_faces(), block_faces),
+    )
+}
+
+/// Returns the position of the block of the voxel `world` that contains `point`.
+pub fn block_at(world: EntityId, point: Vec3) -> IVec3 {
+    let origin = entity::get_component(world, translation()).unwrap_or_default();
+    let block_size = entity::get_component(world, voxel_block_size()).unwrap_or(1.);
+    ((point - origin) / block_size).floor().as_ivec3()
+}
+
+/// Returns the block at `position` in the voxel `world`; 0 if it is empty.
+pub fn get_block(world: EntityId, position: IVec3) -> u8 {
+    let (chunk, index) = chunk_of(position);
+    let Some(&id) = chunks(world).get(&chunk) else {
+        return 0;
+    };
+    // The latest edit of the block wins over the blocks of the chunk
+    let edits = entity::get_component(id, voxel_chunk_edits()).unwrap_or_default();
+    if let Some(edit) = edits
+        .iter()
+        .rev()
+        .find(|edit| (**edit >> 8) as usize == index)
+    {
+        return *edit as u8;
+    }
+    entity::get_component(id, voxel_chunk_blocks())
+        .and_then(|blocks| blocks.get(index).copied())
+        .unwrap_or_default()
+}
+
+/// Sets the block at `position` in the voxel `world` to `block`; 0 empties it.
+///
+/// On the server, the edit is replicated to the clients.
+pub fn set_block(world: EntityId, position: IVec3, block: u8) {
+    set_blocks(world, [(position, block)]);
+}
+
+/// Sets each of the `blocks` in the voxel `world`, by their position; this is faster than setting them one at a time.
+///
+/// On the server, the edits are replicated to the clients.
+pub fn set_blocks(world: EntityId, blocks: impl IntoIterator<Item = (IVec3, u8)>) {
+    let mut edits = HashMap::<IVec3, Vec<u32>>::new();
+    for (position, block) in blocks {
+        let (chunk, index) = chunk_of(position);
+        edits
+            .entry(chunk)
+            .or_default()
+            .push((index as u32) << 8 | block as u32);
+    }
+
+    let chunks = chunks(world);
+    for (chunk, edits) in edits {
+        match chunks.get(&chunk) {
+            Some(&id) => {
+                entity::mutate_component_with_default(
+                    id,
+                    voxel_chunk_edits(),
+                    edits.clone(),
+                    |existing| existing.extend(edits.iter().copied()),
+                );
+            }
+            None => {
+                entity::spawn(
+                    &Entity::new()
+                        .with(voxel_chunk(), world)
+                        .with(voxel_chunk_position(), chunk.as_vec3())
+                        .with(voxel_chunk_blocks(), Vec::new())
+                        .with(voxel_chunk_edits(), edits),
+                );
+            }
+        }
+    }
+}
+
+/// Fills the blocks from `min` to `max` (inclusive) in the voxel `world` with `block`; 0 empties them.
+///
+/// On the server, the edits are replicated to the clients.
+pub fn fill(world: EntityId, min: IVec3, max: IVec3, block: u8) {
+    set_blocks(
+        world,
+        (min.z..=max.z).flat_map(move |z| {
+            (min.y..=max.y)
+                .flat_map(move |y| (min.x..=max.x).map(move |x| (IVec3::new(x, y, z), block)))
+        }),
+    );
+}
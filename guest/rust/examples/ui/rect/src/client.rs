@@ -13,6 +13,38 @@ fn App(_hooks: &mut Hooks) -> Element {
                 .with(border_color(), vec4(0., 1., 0., 1.))
                 .with(border_thickness(), 10.)
                 .with(border_radius(), vec4(20., 10., 5., 0.)),
+            Rectangle
+                .el()
+                .with(width(), 150.)
+                .with(height(), 50.)
+                .with_skin(
+                    &Skin::solid(vec4(0.2, 0.2, 0.25, 1.))
+                        .rounded(8.)
+                        .shadow(Shadow::soft()),
+                ),
+            Rectangle
+                .el()
+                .with(width(), 150.)
+                .with(height(), 50.)
+                .with_skin(&Theme::default().floating_panel),
+            FlowRow::el([
+                Ellipse
+                    .el()
+                    .with(width(), 80.)
+                    .with(height(), 50.)
+                    .with(background_color(), vec4(0.2, 0.6, 1., 1.))
+                    .with(border_color(), vec4(1., 1., 1., 1.))
+                    .with(border_thickness(), 2.),
+                Polygon::el(3)
+                    .with(width(), 50.)
+                    .with(height(), 50.)
+                    .with(background_color(), vec4(1., 0.8, 0.2, 1.)),
+                Polygon::el(6)
+                    .with(width(), 50.)
+                    .with(height(), 50.)
+                    .with_skin(&Skin::solid(vec4(0.4, 0.9, 0.4, 1.)).shadow(Shadow::soft())),
+            ])
+            .with(space_between_items(), 10.),
         ])
         .with(space_between_items(), 10.)
         .with_padding_even(STREET),
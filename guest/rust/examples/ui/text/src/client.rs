@@ -11,6 +11,11 @@ fn App(_hooks: &mut Hooks) -> Element {
         Text::el("Custom size").with(font_size(), 40.),
         Text::el("Custom color").with(color(), vec4(1., 0., 0., 1.)),
         Text::el("Multi\n\nLine"),
+        RichText::parse(
+            "[b]Bold[/b], [i]italic[/i], [color=#ff8000]orange[/color], [code]code[/code], emoji \u{1F389}\nand a [link=ambient]link[/link]",
+        )
+        .on_link(|_, link| println!("Clicked the {link} link"))
+        .el(),
     ])
     .with_padding_even(STREET)
     .with(space_between_items(), 10.)
@@ -0,0 +1,64 @@
+use ambient_api::prelude::*;
+
+#[element_component]
+fn Menu(hooks: &mut Hooks) -> Element {
+    let (open, set_open) = hooks.use_state(true);
+    let items = ["New game", "Load game", "Settings", "Quit"]
+        .into_iter()
+        .map(|item| Button::new(item, |_| {}).el());
+
+    FlowColumn::el([
+        Button::new(if open { "Close menu" } else { "Open menu" }, move |_| {
+            set_open(!open)
+        })
+        .el(),
+        // The menu slides away and fades out when it is closed, and comes back when it is opened
+        Transition::new(
+            FlowColumn::el(stagger(
+                items,
+                TransitionState::hidden().with_offset(vec2(-30., 0.)),
+                TransitionState::visible(),
+                0.08,
+            ))
+            .with(space_between_items(), STREET)
+            .floating_panel(),
+            TransitionState::hidden(),
+            if open {
+                TransitionState::visible()
+            } else {
+                TransitionState::hidden().with_offset(vec2(0., -20.))
+            },
+        )
+        .duration(0.3)
+        .el(),
+    ])
+    .with(space_between_items(), STREET)
+}
+
+#[element_component]
+fn App(_hooks: &mut Hooks) -> Element {
+    FlowRow::el([
+        Menu.el(),
+        Transition::pop_in(Text::el("Pop!").header_style(), 0.5)
+            .duration(0.6)
+            .delay(0.5)
+            .el(),
+        Transition::new(
+            Rectangle
+                .el()
+                .with(background_color(), vec4(0.9, 0.5, 0.1, 1.)),
+            TransitionState::visible().with_scale(0.),
+            TransitionState::visible(),
+        )
+        .easing(Easing::ElasticOut)
+        .duration(1.5)
+        .el(),
+    ])
+    .with(space_between_items(), STREET)
+    .with_padding_even(STREET)
+}
+
+#[main]
+pub fn main() {
+    App.el().spawn_interactive();
+}
@@ -238,6 +238,10 @@ pub trait PxRigidBody {
     fn get_rigid_body_flags(&self) -> PxRigidBodyFlag;
     fn set_rigid_body_flag(&self, flag: PxRigidBodyFlag, value: bool);
     fn set_rigid_body_flags(&self, flags: PxRigidBodyFlag);
+    fn get_min_ccd_advance_coefficient(&self) -> f32;
+    fn set_min_ccd_advance_coefficient(&self, coefficient: f32);
+    fn get_max_depenetration_velocity(&self) -> f32;
+    fn set_max_depenetration_velocity(&self, velocity: f32);
 }
 impl<T: AsPxRigidBody + ?Sized> PxRigidBody for T {
     fn update_mass_and_inertia(
@@ -324,6 +328,18 @@ impl<T: AsPxRigidBody + ?Sized> PxRigidBody for T {
             physx_sys::PxRigidBody_setRigidBodyFlags_mut(self.as_rigid_body().0, physx_sys::PxRigidBodyFlags { mBits: flags.bits as u8 })
         }
     }
+    fn get_min_ccd_advance_coefficient(&self) -> f32 {
+        unsafe { physx_sys::PxRigidBody_getMinCCDAdvanceCoefficient(self.as_rigid_body().0) }
+    }
+    fn set_min_ccd_advance_coefficient(&self, coefficient: f32) {
+        unsafe { physx_sys::PxRigidBody_setMinCCDAdvanceCoefficient_mut(self.as_rigid_body().0, coefficient) }
+    }
+    fn get_max_depenetration_velocity(&self) -> f32 {
+        unsafe { physx_sys::PxRigidBody_getMaxDepenetrationVelocity(self.as_rigid_body().0) }
+    }
+    fn set_max_depenetration_velocity(&self, velocity: f32) {
+        unsafe { physx_sys::PxRigidBody_setMaxDepenetrationVelocity_mut(self.as_rigid_body().0, velocity) }
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -374,6 +390,18 @@ impl PxRigidDynamicRef {
     pub fn wake_up(&self) {
         unsafe { physx_sys::PxRigidDynamic_wakeUp_mut(self.0) }
     }
+    pub fn put_to_sleep(&self) {
+        unsafe { physx_sys::PxRigidDynamic_putToSleep_mut(self.0) }
+    }
+    pub fn is_sleeping(&self) -> bool {
+        unsafe { physx_sys::PxRigidDynamic_isSleeping(self.0) }
+    }
+    pub fn get_sleep_threshold(&self) -> f32 {
+        unsafe { physx_sys::PxRigidDynamic_getSleepThreshold(self.0) }
+    }
+    pub fn set_sleep_threshold(&self, threshold: f32) {
+        unsafe { physx_sys::PxRigidDynamic_setSleepThreshold_mut(self.0, threshold) }
+    }
     pub fn set_kinematic_target(&self, destination: &PxTransform) {
         unsafe {
             physx_sys::PxRigidDynamic_setKinematicTarget_mut(self.0, &destination.0);
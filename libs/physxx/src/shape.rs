@@ -103,6 +103,15 @@ impl PxShape {
     pub fn set_rest_offset(&self, offset: f32) {
         unsafe { physx_sys::PxShape_setRestOffset_mut(self.0, offset) }
     }
+    /// The four words of user data that the filter shader of the scene receives for the pairs this shape is in
+    pub fn get_simulation_filter_data(&self) -> [u32; 4] {
+        let data = unsafe { physx_sys::PxShape_getSimulationFilterData(self.0) };
+        [data.word0, data.word1, data.word2, data.word3]
+    }
+    pub fn set_simulation_filter_data(&self, words: [u32; 4]) {
+        let data = physx_sys::PxFilterData { word0: words[0], word1: words[1], word2: words[2], word3: words[3] };
+        unsafe { physx_sys::PxShape_setSimulationFilterData_mut(self.0, &data) }
+    }
 }
 impl AsPxBase for PxShape {
     fn as_base(&self) -> PxBaseRef {
@@ -0,0 +1,54 @@
+#!/bin/node
+const util = require('node:util');
+const exec = util.promisify(require('node:child_process').exec);
+const { exit } = require("process");
+const { argv } = require('node:process');
+
+// The examples whose simulations are checked, and the number of ticks they are run for
+let samples = [
+    ["guest/rust/examples/basics/physics", 600],
+    ["guest/rust/examples/basics/primitives", 120],
+    ["guest/rust/examples/games/minigolf", 600],
+    ["guest/rust/examples/games/pong", 600],
+]
+
+async function run(samples, just_build, no_build, update) {
+    console.time("time");
+    let errors = [];
+    for (const [index, [path, ticks]] of samples.entries()) {
+        const command = just_build ? `build ${path}` : `serve ${path} ${no_build ? '--no-build' : ''} --no-proxy --determinism-test ${ticks} ${update ? '--determinism-record' : ''} --quic-interface-port ${9000 + index} --http-interface-port ${10000 + index}`;
+        let fullCommand = `cargo run --release -- ${command}`;
+        console.timeLog("time", path, fullCommand);
+        try {
+            await exec(fullCommand);
+            console.timeLog("time", path, "\x1b[32mwas ok\x1b[0m");
+        } catch (err) {
+            console.timeLog("time", path, "\x1b[31mfailed\x1b[0m");
+            errors.push({ path, err });
+        }
+    }
+    for (const { path, err } of errors) {
+        console.timeLog("time", `===================================== ERRORS FOR ${path} =====================================`);
+        console.timeLog("time", err);
+    }
+    if (errors.length > 0) {
+        console.timeLog("time", "Exiting with status code 1");
+        exit(1);
+    }
+}
+let just_build = false;
+let no_build = false;
+let update = false;
+for (let i = 2; i < argv.length; i++) {
+    if (argv[i] == "--build") {
+        just_build = true;
+    } else if (argv[i] == "--no-build") {
+        no_build = true;
+    } else if (argv[i] == "--update") {
+        update = true;
+    } else {
+        samples = samples.filter(([path]) => path.includes(argv[i]));
+    }
+}
+
+run(samples, just_build, no_build, update);
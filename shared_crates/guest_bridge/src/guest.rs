@@ -46,6 +46,11 @@ pub mod ecs {
             api::entity::add_components(entity_id, components);
             Ok(())
         }
+        pub fn remove_component<T: ComponentValue>(&self, entity_id: EntityId, component: Component<T>) -> Result<(), ECSError> {
+            // TODO: remove_component needs to return errors
+            api::entity::remove_component(entity_id, component);
+            Ok(())
+        }
         pub fn get<T: ComponentValue>(&self, entity_id: EntityId, component: Component<T>) -> Result<T, ECSError> {
             api::entity::get_component(entity_id, component).ok_or_else(|| ECSError::EntityDoesntHaveComponent)
         }
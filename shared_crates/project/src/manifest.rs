@@ -11,6 +11,10 @@ pub struct Manifest {
     pub project: Project,
     #[serde(default)]
     pub build: Build,
+    #[serde(default, rename = "mod")]
+    pub mod_: Mod,
+    #[serde(default)]
+    pub permissions: Option<Permissions>,
     #[serde(default)]
     pub components: BTreeMap<IdentifierPathBuf, NamespaceOr<Component>>,
     #[serde(default)]
@@ -86,6 +90,34 @@ impl Default for BuildRust {
     }
 }
 
+/// Settings for when the project is loaded as a mod of another project
+#[derive(Deserialize, Clone, Debug, PartialEq, Default, Serialize)]
+pub struct Mod {
+    /// The IDs of the mods that have to be loaded before this one
+    #[serde(default)]
+    pub dependencies: Vec<Identifier>,
+}
+
+/// The capabilities of the host that the modules of the project use. A project that declares them can only use the
+/// ones it declared; one that doesn't can use all of them, unless it is hosted in the hardened mode.
+#[derive(Deserialize, Clone, Debug, PartialEq, Default, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Permissions {
+    /// Sending messages to the other side of the network
+    #[serde(default)]
+    pub network: bool,
+    /// Loading assets from files outside of the project, with `file://` URLs or paths
+    #[serde(default)]
+    pub filesystem: bool,
+    /// Asking the user to read or write their clipboard
+    #[serde(default)]
+    pub clipboard: bool,
+    /// The hosts that assets can be loaded from, besides that of the project. A leading `*.` matches the subdomains of
+    /// a host.
+    #[serde(default)]
+    pub http_hosts: Vec<String>,
+}
+
 #[derive(Deserialize, Debug, Clone, PartialEq, Serialize)]
 pub struct Namespace {
     pub name: Option<String>,
@@ -136,7 +168,7 @@ mod tests {
 
     use crate::{
         Build, BuildRust, Component, ComponentType, Concept, Identifier, IdentifierPathBuf,
-        Manifest, Namespace, Project, Version, VersionSuffix,
+        Manifest, Namespace, Permissions, Project, Version, VersionSuffix,
     };
 
     #[test]
@@ -174,6 +206,8 @@ mod tests {
                         feature_multibuild: vec!["client".to_string(), "server".to_string()]
                     }
                 },
+                mod_: Default::default(),
+                permissions: None,
                 components: BTreeMap::from_iter([(
                     IdentifierPathBuf::new("cell").unwrap(),
                     Component {
@@ -232,6 +266,8 @@ mod tests {
                         feature_multibuild: vec!["client".to_string()]
                     }
                 },
+                mod_: Default::default(),
+                permissions: None,
                 components: BTreeMap::new(),
                 concepts: BTreeMap::new(),
                 messages: BTreeMap::new(),
@@ -239,6 +275,38 @@ mod tests {
         )
     }
 
+    #[test]
+    fn can_parse_permissions() {
+        const TOML: &str = r#"
+        [project]
+        id = "tictactoe"
+        version = "0.0.1"
+
+        [permissions]
+        network = true
+        http_hosts = ["assets.example.com"]
+        "#;
+
+        assert_eq!(
+            Manifest::parse(TOML).unwrap().permissions,
+            Some(Permissions {
+                network: true,
+                http_hosts: vec!["assets.example.com".to_string()],
+                ..Default::default()
+            })
+        );
+
+        const UNKNOWN: &str = r#"
+        [project]
+        id = "tictactoe"
+        version = "0.0.1"
+
+        [permissions]
+        microphone = true
+        "#;
+        assert!(Manifest::parse(UNKNOWN).is_err());
+    }
+
     #[test]
     fn can_parse_manifest_with_namespaces() {
         const TOML: &str = r#"
@@ -271,6 +339,8 @@ mod tests {
                         feature_multibuild: vec!["client".to_string(), "server".to_string()]
                     }
                 },
+                mod_: Default::default(),
+                permissions: None,
                 components: BTreeMap::from_iter([
                     (
                         IdentifierPathBuf::new("core").unwrap(),
@@ -344,6 +414,8 @@ mod tests {
                         feature_multibuild: vec!["client".to_string(), "server".to_string()]
                     }
                 },
+                mod_: Default::default(),
+                permissions: None,
                 components: BTreeMap::from_iter([
                     (
                         IdentifierPathBuf::new("core::transform::rotation").unwrap(),
@@ -20,7 +20,7 @@ pub fn tree_to_token_stream(
                 let namespace_path = IdentifierPath(ns.path.split_first().unwrap().1).to_string();
                 quote! {
                     use glam::{Vec2, Vec3, Vec4, UVec2, UVec3, UVec4, Mat4, Quat};
-                    use crate::{EntityId, Debuggable, Networked, Store, Resource, MaybeResource, Name, Description};
+                    use crate::{EntityId, Debuggable, Networked, Store, Resource, MaybeResource, AssetUrl, Name, Description};
                     crate::components!(#namespace_path, {
                         #ts
                     });
@@ -0,0 +1,123 @@
+use glam::{Mat3, Vec3};
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+/// A type of color vision deficiency.
+///
+/// The simulation matrices are from Machado, Oliveira and Fernandes (2009) at full severity, and operate on linear RGB.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, EnumString, EnumIter, Display, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case", ascii_case_insensitive)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorVisionDeficiency {
+    /// Missing or defective long-wavelength (red) cones.
+    Protanopia,
+    /// Missing or defective medium-wavelength (green) cones.
+    Deuteranopia,
+    /// Missing or defective short-wavelength (blue) cones.
+    Tritanopia,
+    /// No color vision at all; only luminance is perceived.
+    Achromatopsia,
+}
+impl ColorVisionDeficiency {
+    /// The matrix that maps a linear RGB color to how it is perceived with this deficiency.
+    pub fn simulation_matrix(&self) -> Mat3 {
+        let rows = match self {
+            Self::Protanopia => [[0.152286, 1.052583, -0.204868], [0.114503, 0.786281, 0.099216], [-0.003882, -0.048116, 1.051998]],
+            Self::Deuteranopia => [[0.367322, 0.860646, -0.227968], [0.280085, 0.672501, 0.047413], [-0.011820, 0.042940, 0.968881]],
+            Self::Tritanopia => [[1.255528, -0.076749, -0.178779], [-0.078411, 0.930809, 0.147602], [0.004733, 0.691367, 0.303900]],
+            Self::Achromatopsia => [[0.2126, 0.7152, 0.0722]; 3],
+        };
+        Mat3::from_cols_array_2d(&rows).transpose()
+    }
+
+    /// The matrix that maps a linear RGB color to one that is easier to tell apart with this deficiency.
+    ///
+    /// This uses daltonization: the information lost in the simulation is redistributed to the channels that can still be perceived.
+    /// There is nothing to redistribute to with [ColorVisionDeficiency::Achromatopsia], so it is the identity.
+    pub fn correction_matrix(&self) -> Mat3 {
+        let shift = match self {
+            Self::Protanopia | Self::Deuteranopia => [[0., 0., 0.], [0.7, 1., 0.], [0.7, 0., 1.]],
+            Self::Tritanopia => [[1., 0., 0.7], [0., 1., 0.7], [0., 0., 0.]],
+            Self::Achromatopsia => return Mat3::IDENTITY,
+        };
+        let shift = Mat3::from_cols_array_2d(&shift).transpose();
+        Mat3::IDENTITY + shift * (Mat3::IDENTITY - self.simulation_matrix())
+    }
+
+    /// Returns how `color` (linear RGB) is perceived with this deficiency.
+    pub fn simulate(&self, color: Vec3) -> Vec3 {
+        (self.simulation_matrix() * color).clamp(Vec3::ZERO, Vec3::ONE)
+    }
+
+    /// Returns `color` (linear RGB) adjusted to be easier to distinguish with this deficiency.
+    pub fn correct(&self, color: Vec3) -> Vec3 {
+        (self.correction_matrix() * color).clamp(Vec3::ZERO, Vec3::ONE)
+    }
+}
+
+/// How a [ColorVisionDeficiency] filter is applied to the final image.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default, EnumString, Display, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case", ascii_case_insensitive)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorVisionFilter {
+    /// Adjust colors so that they are easier to tell apart. Intended for players.
+    #[default]
+    Correct,
+    /// Show colors as they would be perceived. Intended for developers checking their content.
+    Simulate,
+}
+impl ColorVisionFilter {
+    /// The matrix to apply to linear RGB colors for the given deficiency.
+    pub fn matrix(&self, deficiency: ColorVisionDeficiency) -> Mat3 {
+        match self {
+            Self::Correct => deficiency.correction_matrix(),
+            Self::Simulate => deficiency.simulation_matrix(),
+        }
+    }
+}
+
+/// A palette of colors (in sRGB) that remain distinguishable with all common color vision deficiencies.
+///
+/// These are the Okabe-Ito colors, minus black. Use them for things like team colors.
+pub const COLOR_VISION_SAFE_PALETTE: [Vec3; 7] = [
+    Vec3::new(0.902, 0.624, 0.),
+    Vec3::new(0.337, 0.706, 0.914),
+    Vec3::new(0., 0.620, 0.451),
+    Vec3::new(0.941, 0.894, 0.259),
+    Vec3::new(0., 0.447, 0.698),
+    Vec3::new(0.835, 0.369, 0.),
+    Vec3::new(0.800, 0.475, 0.655),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn parse() {
+        assert_eq!("deuteranopia".parse::<ColorVisionDeficiency>().unwrap(), ColorVisionDeficiency::Deuteranopia);
+        assert_eq!("Protanopia".parse::<ColorVisionDeficiency>().unwrap(), ColorVisionDeficiency::Protanopia);
+        assert_eq!(ColorVisionDeficiency::Tritanopia.to_string(), "tritanopia");
+        assert!("".parse::<ColorVisionDeficiency>().is_err());
+    }
+
+    #[test]
+    fn preserves_grays() {
+        for deficiency in ColorVisionDeficiency::iter() {
+            for filter in [ColorVisionFilter::Correct, ColorVisionFilter::Simulate] {
+                let gray = filter.matrix(deficiency) * Vec3::splat(0.5);
+                assert!(gray.abs_diff_eq(Vec3::splat(0.5), 1e-3), "{deficiency} {filter}: {gray}");
+            }
+        }
+    }
+
+    #[test]
+    fn correction_separates_red_and_green() {
+        let deficiency = ColorVisionDeficiency::Deuteranopia;
+        let (red, green) = (Vec3::new(0.8, 0.2, 0.2), Vec3::new(0.2, 0.6, 0.2));
+        let before = deficiency.simulate(red).distance(deficiency.simulate(green));
+        let after = deficiency.simulate(deficiency.correct(red)).distance(deficiency.simulate(deficiency.correct(green)));
+        assert!(after > before);
+    }
+}
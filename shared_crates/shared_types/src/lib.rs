@@ -25,6 +25,11 @@ macro_rules! primitive_component_definitions {
     };
 }
 
+mod color_vision;
+pub use color_vision::*;
+mod localization;
+pub use localization::*;
+
 // The following types are copied from winit, but without everything else winit comes with so that we can use this package in our guest code.
 
 use bitflags::bitflags;
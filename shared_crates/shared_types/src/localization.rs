@@ -0,0 +1,49 @@
+use std::fmt::{Display, Write};
+
+/// Replaces each `{name}` in `template` with the matching argument.
+///
+/// `{{` and `}}` produce literal braces. Placeholders without a matching argument are left as-is, so that missing arguments are visible.
+pub fn interpolate(template: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(index) = rest.find(['{', '}']) {
+        out.push_str(&rest[..index]);
+        let tail = &rest[index..];
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            out.push_str(&tail[..1]);
+            rest = &tail[2..];
+            continue;
+        }
+        let placeholder = tail.strip_prefix('{').and_then(|x| Some((x, x.find('}')?)));
+        match placeholder {
+            Some((inner, end)) => {
+                let name = &inner[..end];
+                match args.iter().find(|(arg, _)| *arg == name) {
+                    Some((_, value)) => write!(out, "{value}").unwrap(),
+                    None => out.push_str(&tail[..end + 2]),
+                }
+                rest = &inner[end + 1..];
+            }
+            None => {
+                out.push_str(&tail[..1]);
+                rest = &tail[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolation() {
+        assert_eq!(interpolate("Hello, {name}!", &[("name", &"Ambient")]), "Hello, Ambient!");
+        assert_eq!(interpolate("{a} + {a} = {b}", &[("a", &1), ("b", &2)]), "1 + 1 = 2");
+        assert_eq!(interpolate("{{literal}} {missing}", &[]), "{literal} {missing}");
+        assert_eq!(interpolate("unclosed { brace }", &[]), "unclosed { brace }");
+        assert_eq!(interpolate("こんにちは、{name}さん", &[("name", &"太郎")]), "こんにちは、太郎さん");
+    }
+}
@@ -0,0 +1,489 @@
+//! Animates UI elements: transitions of their position, scale and opacity with easing curves, and staggered lists.
+//!
+//! A [Transition] animates its element from one [TransitionState] to another when it is spawned, and again whenever
+//! the state it is rendered with changes, so menus can animate by re-rendering with a new state. While the element is
+//! completely faded out, the mouse passes through it.
+use std::{collections::HashMap, f32::consts::PI};
+
+use ambient_element::{to_owned, Element, ElementComponent, ElementComponentExt, Hooks};
+use ambient_guest_bridge::{
+    components::{
+        app::dtime,
+        ecs::children,
+        input::mouse_passthrough,
+        layout::{height, width},
+        rendering::color,
+        transform::{scale, translation},
+    },
+    ecs::{EntityId, World},
+};
+use glam::{vec2, vec3, Vec2, Vec4};
+
+use crate::UIBase;
+
+/// An easing curve, which maps the progress of a transition from 0 to 1 to how far along its values are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+    /// Constant speed.
+    Linear,
+    /// Starts slowly.
+    QuadIn,
+    /// Ends slowly.
+    QuadOut,
+    /// Starts and ends slowly.
+    QuadInOut,
+    /// Starts slowly, more sharply than [Easing::QuadIn].
+    CubicIn,
+    /// Ends slowly, more sharply than [Easing::QuadOut].
+    CubicOut,
+    /// Starts and ends slowly, more sharply than [Easing::QuadInOut].
+    CubicInOut,
+    /// Overshoots the end and settles back.
+    BackOut,
+    /// Overshoots the end and springs back and forth before settling.
+    ElasticOut,
+}
+impl Default for Easing {
+    fn default() -> Self {
+        Self::CubicOut
+    }
+}
+impl Easing {
+    /// How far along the values are at `t`, from 0 to 1; may leave that range for the curves that overshoot.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0., 1.);
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1. - (1. - t).powi(2),
+            Easing::QuadInOut => {
+                if t < 0.5 {
+                    2. * t * t
+                } else {
+                    1. - (2. - 2. * t).powi(2) / 2.
+                }
+            }
+            Easing::CubicIn => t.powi(3),
+            Easing::CubicOut => 1. - (1. - t).powi(3),
+            Easing::CubicInOut => {
+                if t < 0.5 {
+                    4. * t.powi(3)
+                } else {
+                    1. - (2. - 2. * t).powi(3) / 2.
+                }
+            }
+            Easing::BackOut => {
+                const OVERSHOOT: f32 = 1.70158;
+                1. + (OVERSHOOT + 1.) * (t - 1.).powi(3) + OVERSHOOT * (t - 1.).powi(2)
+            }
+            Easing::ElasticOut => {
+                if t == 0. || t == 1. {
+                    t
+                } else {
+                    2f32.powf(-10. * t) * ((t * 10. - 0.75) * 2. * PI / 3.).sin() + 1.
+                }
+            }
+        }
+    }
+}
+
+/// The animated properties of an element in a [Transition].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransitionState {
+    /// How far the element is moved from where the layout puts it, in pixels.
+    pub offset: Vec2,
+    /// The scale of the element, around its center.
+    pub scale: f32,
+    /// The opacity of the element and its children, from 0 to 1.
+    pub opacity: f32,
+}
+impl Default for TransitionState {
+    fn default() -> Self {
+        Self::visible()
+    }
+}
+impl TransitionState {
+    /// The element as it is laid out.
+    pub fn visible() -> Self {
+        Self {
+            offset: Vec2::ZERO,
+            scale: 1.,
+            opacity: 1.,
+        }
+    }
+    /// The element faded out completely.
+    pub fn hidden() -> Self {
+        Self::visible().with_opacity(0.)
+    }
+    /// Moves the element by `offset` pixels.
+    pub fn with_offset(mut self, offset: Vec2) -> Self {
+        self.offset = offset;
+        self
+    }
+    /// Scales the element by `scale` around its center.
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+    /// Sets the opacity of the element to `opacity`.
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+    /// The state `t` of the way from `self` to `other`.
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            offset: self.offset.lerp(other.offset, t),
+            scale: self.scale + (other.scale - self.scale) * t,
+            // The opacity can't overshoot
+            opacity: (self.opacity + (other.opacity - self.opacity) * t).clamp(0., 1.),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Animation {
+    from: TransitionState,
+    to: TransitionState,
+    current: TransitionState,
+    elapsed: f32,
+    delay: f32,
+    /// Whether the end of the animation has been applied
+    settled: bool,
+}
+impl Animation {
+    fn advance(&mut self, dtime: f32, duration: f32, easing: Easing) {
+        self.elapsed += dtime;
+        let t = if duration > 0. {
+            ((self.elapsed - self.delay) / duration).clamp(0., 1.)
+        } else if self.elapsed >= self.delay {
+            1.
+        } else {
+            0.
+        };
+        self.current = self.from.lerp(&self.to, easing.apply(t));
+    }
+    fn done(&self, duration: f32) -> bool {
+        self.elapsed >= self.delay + duration
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Animates `inner` from the state `from` to `to` when it is spawned, and from wherever it is to `to` whenever `to`
+/// changes.
+///
+/// The layout places the transition where it would place `inner`, so moving or scaling `inner` doesn't move the
+/// elements around it.
+pub struct Transition {
+    /// The element to animate.
+    pub inner: Element,
+    /// The state that `inner` starts in when it is spawned.
+    pub from: TransitionState,
+    /// The state that `inner` animates to.
+    pub to: TransitionState,
+    /// How long the animation takes, in seconds.
+    pub duration: f32,
+    /// How long to wait before animating, in seconds.
+    pub delay: f32,
+    /// The easing curve of the animation.
+    pub easing: Easing,
+}
+impl Transition {
+    /// Animates `inner` from `from` to `to`, over a quarter of a second.
+    pub fn new(inner: Element, from: TransitionState, to: TransitionState) -> Self {
+        Self {
+            inner,
+            from,
+            to,
+            duration: 0.25,
+            delay: 0.,
+            easing: Easing::default(),
+        }
+    }
+    /// Fades `inner` in.
+    pub fn fade_in(inner: Element) -> Self {
+        Self::new(inner, TransitionState::hidden(), TransitionState::visible())
+    }
+    /// Fades `inner` in while sliding it from `offset` pixels away.
+    pub fn slide_in(inner: Element, offset: Vec2) -> Self {
+        Self::new(
+            inner,
+            TransitionState::hidden().with_offset(offset),
+            TransitionState::visible(),
+        )
+    }
+    /// Fades `inner` in while growing it from `scale`.
+    pub fn pop_in(inner: Element, scale: f32) -> Self {
+        Self::new(
+            inner,
+            TransitionState::hidden().with_scale(scale),
+            TransitionState::visible(),
+        )
+        .easing(Easing::BackOut)
+    }
+    /// Sets how long the animation takes, in seconds.
+    pub fn duration(mut self, duration: f32) -> Self {
+        self.duration = duration;
+        self
+    }
+    /// Sets how long to wait before animating, in seconds.
+    pub fn delay(mut self, delay: f32) -> Self {
+        self.delay = delay;
+        self
+    }
+    /// Sets the easing curve of the animation.
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+}
+impl ElementComponent for Transition {
+    fn render(self: Box<Self>, hooks: &mut Hooks) -> Element {
+        let Self {
+            inner,
+            from,
+            to,
+            duration,
+            delay,
+            easing,
+        } = *self;
+        let animation = hooks.use_ref_with(|_| Animation {
+            from,
+            to,
+            current: from,
+            elapsed: 0.,
+            delay,
+            settled: false,
+        });
+        {
+            let mut animation = animation.lock();
+            if animation.to != to {
+                *animation = Animation {
+                    from: animation.current,
+                    to,
+                    current: animation.current,
+                    elapsed: 0.,
+                    delay,
+                    settled: false,
+                };
+            }
+        }
+        let wrapper = hooks.use_ref_with(|_| None);
+        let faded = hooks.use_ref_with(|_| HashMap::new());
+
+        hooks.use_frame({
+            to_owned![animation, wrapper, faded];
+            move |world| {
+                let Some(wrapper) = *wrapper.lock() else {
+                    return;
+                };
+                let Some(inner) = first_child(world, wrapper) else {
+                    return;
+                };
+                fit_to(world, wrapper, inner);
+
+                let mut animation = animation.lock();
+                // Entities spawned inside of a faded element are faded too
+                if animation.settled && animation.current.opacity == 1. {
+                    return;
+                }
+                #[allow(clippy::clone_on_copy)]
+                let dtime = world.resource(dtime()).clone();
+                animation.advance(dtime, duration, easing);
+                animation.settled = animation.done(duration);
+                apply(world, inner, &animation.current, &mut faded.lock());
+            }
+        });
+
+        let current = animation.lock().current;
+        UIBase
+            .el()
+            .children(vec![inner])
+            .on_spawned(move |world, id, _| {
+                *wrapper.lock() = Some(id);
+                // The first frame is drawn before the animation starts
+                if let Some(inner) = first_child(world, id) {
+                    fit_to(world, id, inner);
+                    apply(world, inner, &current, &mut faded.lock());
+                }
+            })
+    }
+}
+
+/// Wraps each of the `items` in a [Transition] from `from` to `to`, each starting `interval` seconds after the one
+/// before it, e.g. for the entries of a list appearing one after the other.
+pub fn stagger(
+    items: impl IntoIterator<Item = Element>,
+    from: TransitionState,
+    to: TransitionState,
+    interval: f32,
+) -> Vec<Element> {
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| {
+            Transition::new(item, from, to)
+                .delay(i as f32 * interval)
+                .el()
+        })
+        .collect()
+}
+
+fn first_child(world: &World, id: EntityId) -> Option<EntityId> {
+    world
+        .get_ref(id, children())
+        .ok()
+        .and_then(|children| children.first().copied())
+}
+
+/// Gives the transition the size of its element, so that the layout places it like the element
+fn fit_to(world: &mut World, wrapper: EntityId, inner: EntityId) {
+    for size in [width(), height()] {
+        let value = world.get(inner, size).unwrap_or(0.);
+        if world.get(wrapper, size).unwrap_or(0.) != value {
+            world.add_component(wrapper, size, value).ok();
+        }
+    }
+}
+
+fn apply(
+    world: &mut World,
+    inner: EntityId,
+    state: &TransitionState,
+    faded: &mut HashMap<EntityId, Faded>,
+) {
+    let size = vec2(
+        world.get(inner, width()).unwrap_or(0.),
+        world.get(inner, height()).unwrap_or(0.),
+    );
+    // The element is scaled around its center rather than its top left corner
+    let position = state.offset + size * (1. - state.scale) / 2.;
+    let current = world.get(inner, translation()).unwrap_or_default();
+    if current.truncate() != position {
+        world
+            .add_component(inner, translation(), position.extend(current.z))
+            .ok();
+    }
+    let scaled = vec3(state.scale, state.scale, 1.);
+    if world.get(inner, scale()).ok() != Some(scaled) {
+        world.add_component(inner, scale(), scaled).ok();
+    }
+    // Only the entities that are still inside of the element are kept, so despawned ones are forgotten
+    let previous = std::mem::take(faded);
+    apply_opacity(world, inner, state.opacity, &previous, faded);
+}
+
+/// What a [Transition] did to an entity inside of its element
+#[derive(Debug, Clone, Copy)]
+struct Faded {
+    /// The color the entity had before it was faded
+    original: Vec4,
+    /// The color it was last given, to notice when it is changed by something else
+    set: Vec4,
+    /// Whether the transition made it [mouse_passthrough] while hidden
+    passthrough: bool,
+}
+
+/// Multiplies the alpha of the colors of `id` and its descendants by `opacity`, and lets the mouse through them while
+/// they are hidden; `previous` is what was done to them before, and `faded` receives what is done now
+fn apply_opacity(
+    world: &mut World,
+    id: EntityId,
+    opacity: f32,
+    previous: &HashMap<EntityId, Faded>,
+    faded: &mut HashMap<EntityId, Faded>,
+) {
+    let before = previous.get(&id);
+    let current = world.get(id, color()).ok();
+    let original = match (before, current) {
+        (Some(before), Some(current)) if current == before.set => before.original,
+        (_, Some(current)) => current,
+        (_, None) => Vec4::ONE,
+    };
+    let set = original * Vec4::new(1., 1., 1., opacity);
+    if current != Some(set) {
+        world.add_component(id, color(), set).ok();
+    }
+
+    // Entities that let the mouse through by themselves keep doing so
+    let mut passthrough = before.map_or(false, |before| before.passthrough);
+    let hidden = opacity <= 0.;
+    if hidden && !passthrough && !world.has_component(id, mouse_passthrough()) {
+        world.add_component(id, mouse_passthrough(), ()).ok();
+        passthrough = true;
+    } else if !hidden && passthrough {
+        world.remove_component(id, mouse_passthrough()).ok();
+        passthrough = false;
+    }
+    faded.insert(
+        id,
+        Faded {
+            original,
+            set,
+            passthrough,
+        },
+    );
+
+    let children = world
+        .get_ref(id, children())
+        .map(|children| children.to_vec())
+        .unwrap_or_default();
+    for child in children {
+        apply_opacity(world, child, opacity, previous, faded);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EASINGS: [Easing; 9] = [
+        Easing::Linear,
+        Easing::QuadIn,
+        Easing::QuadOut,
+        Easing::QuadInOut,
+        Easing::CubicIn,
+        Easing::CubicOut,
+        Easing::CubicInOut,
+        Easing::BackOut,
+        Easing::ElasticOut,
+    ];
+
+    #[test]
+    fn easings_start_at_0_and_end_at_1() {
+        for easing in EASINGS {
+            assert!(easing.apply(0.).abs() < 1e-5, "{easing:?}");
+            assert!((easing.apply(1.) - 1.).abs() < 1e-5, "{easing:?}");
+            // The progress is clamped
+            assert_eq!(easing.apply(-1.), easing.apply(0.), "{easing:?}");
+            assert_eq!(easing.apply(2.), easing.apply(1.), "{easing:?}");
+        }
+    }
+
+    #[test]
+    fn easings_without_overshoot_are_monotonic() {
+        for easing in &EASINGS[..7] {
+            let values = (0..=100).map(|i| easing.apply(i as f32 / 100.));
+            let mut last = 0.;
+            for value in values {
+                assert!((0. ..=1.).contains(&value), "{easing:?}");
+                assert!(value >= last, "{easing:?}");
+                last = value;
+            }
+        }
+    }
+
+    #[test]
+    fn easings_have_their_shape() {
+        assert_eq!(Easing::Linear.apply(0.25), 0.25);
+        assert!(Easing::QuadIn.apply(0.5) < 0.5);
+        assert!(Easing::QuadOut.apply(0.5) > 0.5);
+        assert!(Easing::CubicIn.apply(0.5) < Easing::QuadIn.apply(0.5));
+        assert!(Easing::CubicOut.apply(0.5) > Easing::QuadOut.apply(0.5));
+        assert!((Easing::QuadInOut.apply(0.5) - 0.5).abs() < 1e-5);
+        assert!((Easing::CubicInOut.apply(0.5) - 0.5).abs() < 1e-5);
+        // The curves that overshoot go past the end before settling
+        assert!((0..100).any(|i| Easing::BackOut.apply(i as f32 / 100.) > 1.));
+        assert!((0..100).any(|i| Easing::ElasticOut.apply(i as f32 / 100.) > 1.));
+    }
+}
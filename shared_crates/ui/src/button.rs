@@ -16,11 +16,11 @@ use ambient_element::{
 };
 use ambient_guest_bridge::{
     components::{
+        accessibility::{accessibility_focusable, accessibility_role},
         layout::{
             align_vertical_center, fit_horizontal_parent, height, margin, min_height, padding,
             space_between_items,
         },
-        rect::{border_color, border_radius, border_thickness},
         rendering::color,
         text::font_style,
     },
@@ -33,9 +33,12 @@ use glam::*;
 use parking_lot::Mutex;
 
 use crate::{
-    default_theme::{cutout_color, primary_color, secondary_color},
+    default_theme::{
+        high_contrast_border_color, primary_color, secondary_color, use_high_contrast,
+    },
     dropdown::Tooltip,
     layout::{FlowColumn, FlowRow},
+    skin::{use_theme, Skin},
     text::Text,
     UIBase, UIElement, UIExt,
 };
@@ -72,7 +75,7 @@ impl ButtonCb {
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 /// The style of a button.
 pub enum ButtonStyle {
-    /// A regular button: uses variants of the cutout color for all states except hover, where it uses the secondary color.
+    /// A regular button: uses variants of the background of the theme's button skin for all states except toggled, where it uses the primary color.
     Regular,
     /// A primary button: uses variants of the secondary color.
     Primary,
@@ -92,23 +95,27 @@ impl ButtonStyle {
         disabled: bool,
         toggled: bool,
         hover: bool,
+        focused: bool,
+        high_contrast: bool,
         hotkey: Option<VirtualKeyCode>,
         hotkey_modifier: ModifiersState,
         tooltip: Option<Element>,
         content: Element,
+        skin: &Skin,
     ) -> Element {
+        let base = Color::from(skin.background);
         let background = match self {
             ButtonStyle::Regular | ButtonStyle::Card => {
                 if is_pressed {
-                    cutout_color().lighten(0.1)
+                    base.lighten(0.1)
                 } else if disabled || is_working {
-                    cutout_color()
+                    base
                 } else if toggled {
                     primary_color()
                 } else if hover && *self == ButtonStyle::Card {
-                    cutout_color().lighten(0.05)
+                    base.lighten(0.05)
                 } else {
-                    cutout_color()
+                    base
                 }
             }
             ButtonStyle::Primary => {
@@ -130,15 +137,23 @@ impl ButtonStyle {
                 if is_pressed {
                     Color::rgba(1., 1., 1., 1.)
                 } else if disabled || is_working {
-                    Color::rgba(0.3, 0.3, 0.3, 1.)
+                    if high_contrast {
+                        Color::rgba(0.6, 0.6, 0.6, 1.)
+                    } else {
+                        Color::rgba(0.3, 0.3, 0.3, 1.)
+                    }
                 } else if toggled {
                     if *self == Self::Flat || *self == Self::Inline {
                         primary_color()
                     } else {
                         Color::rgba(1., 1., 1., 1.)
                     }
-                } else if hover {
-                    Color::rgba(0.8, 0.8, 0.8, 1.)
+                } else if hover || high_contrast {
+                    if high_contrast {
+                        Color::WHITE
+                    } else {
+                        Color::rgba(0.8, 0.8, 0.8, 1.)
+                    }
                 } else {
                     Color::hex("B3B3B3").unwrap()
                 }
@@ -205,17 +220,26 @@ impl ButtonStyle {
                     ),
                 )
                 .with_default(align_vertical_center())
-                .with_background(background.into())
-                .with(
-                    border_radius(),
-                    match self {
-                        Self::Card => Vec4::ONE * 3.,
-                        Self::Flat => Vec4::ONE * 3.,
-                        _ => Vec4::ONE * 26. / 2.,
-                    },
-                )
-                .with(border_thickness(), 0.)
-                .with(border_color(), Color::WHITE.into());
+                .with_skin(&{
+                    let mut skin = Skin {
+                        background: background.into(),
+                        ..skin.clone()
+                    };
+                    if matches!(self, Self::Card | Self::Flat) {
+                        skin = skin.rounded(3.);
+                    }
+                    if *self == Self::Flat {
+                        skin.shadow = None;
+                    }
+                    // The keyboard focus is shown with a ring around the button
+                    if focused {
+                        skin.border(primary_color().into(), 2.)
+                    } else if high_contrast && *self != Self::Flat {
+                        skin.border(high_contrast_border_color().into(), 2.)
+                    } else {
+                        skin
+                    }
+                });
             if *self != Self::Flat {
                 el = el.with(min_height(), 26.);
             }
@@ -253,8 +277,11 @@ pub fn Button(
 ) -> Element {
     let (is_pressed, set_is_pressed) = hooks.use_state(false);
     let (hover, set_hover) = hooks.use_state(false);
+    let (focused, set_focused) = hooks.use_state(false);
     let (is_working, set_is_working) = hooks.use_state(false);
     let (is_pressed_immediate, _) = hooks.use_state_with(|_| Arc::new(AtomicBool::new(false)));
+    let high_contrast = use_high_contrast(hooks);
+    let theme = use_theme(hooks);
 
     hooks.use_effect(is_pressed, move |world, _| {
         if let Some(on_is_pressed_changed) = on_is_pressed_changed {
@@ -290,10 +317,13 @@ pub fn Button(
             disabled,
             toggled,
             hover,
+            focused,
+            high_contrast,
             hotkey,
             hotkey_modifier,
             tooltip,
             content,
+            &theme.button,
         )
         .with_clickarea()
         .on_mouse_enter({
@@ -307,7 +337,23 @@ pub fn Button(
             set_hover(false);
             ambient_guest_bridge::window::set_cursor(world, CursorIcon::Default);
         })
-        .el();
+        .on_activate({
+            to_owned![on_invoked, set_is_working];
+            move |world, _| {
+                if !disabled {
+                    on_invoked.invoke(world, set_is_working.clone());
+                }
+            }
+        })
+        .on_focus_change(move |_, _, focused| set_focused(focused))
+        .el()
+        .with(accessibility_role(), "button".to_string());
+    // Disabled buttons are skipped when moving the focus
+    let content = if disabled {
+        content
+    } else {
+        content.with(accessibility_focusable(), ())
+    };
 
     if disabled {
         content
@@ -3,7 +3,10 @@
 use ambient_cb::{cb, Cb};
 use ambient_element::{to_owned, Element, ElementComponent, Hooks};
 use ambient_guest_bridge::{
-    components::input::{mouse_over, mouse_pickable_max, mouse_pickable_min},
+    components::{
+        accessibility::accessibility_focus,
+        input::{mouse_over, mouse_pickable_max, mouse_pickable_min},
+    },
     ecs::{EntityId, World},
     messages,
 };
@@ -43,6 +46,10 @@ pub struct ClickArea {
     pub on_mouse_input: Vec<Cb<dyn Fn(&mut World, EntityId, MouseInput, MouseButton) + Sync + Send>>,
     /// Callback for when the mouse wheel is scrolled.
     pub on_mouse_wheel: Vec<Cb<dyn Fn(&mut World, EntityId, Vec2, bool) + Sync + Send>>,
+    /// Callback for when the area is activated with the keyboard or a screen reader.
+    pub on_activate: Vec<Cb<dyn Fn(&mut World, EntityId) + Sync + Send>>,
+    /// Callback for when the area gains or loses the keyboard focus.
+    pub on_focus_change: Vec<Cb<dyn Fn(&mut World, EntityId, bool) + Sync + Send>>,
 }
 impl ClickArea {
     /// Create a new ClickArea.
@@ -54,6 +61,8 @@ impl ClickArea {
             on_mouse_hover: Vec::new(),
             on_mouse_input: Vec::new(),
             on_mouse_wheel: Vec::new(),
+            on_activate: Vec::new(),
+            on_focus_change: Vec::new(),
         }
     }
     /// Set the callback for when the mouse hovers over the area.
@@ -81,6 +90,16 @@ impl ClickArea {
         self.on_mouse_wheel.push(cb(handle));
         self
     }
+    /// Set the callback for when the area is activated with the keyboard or a screen reader; see `accessibility_focusable`.
+    pub fn on_activate<F: Fn(&mut World, EntityId) + Sync + Send + 'static>(mut self, handle: F) -> Self {
+        self.on_activate.push(cb(handle));
+        self
+    }
+    /// Set the callback for when the area gains or loses the keyboard focus.
+    pub fn on_focus_change<F: Fn(&mut World, EntityId, bool) + Sync + Send + 'static>(mut self, handle: F) -> Self {
+        self.on_focus_change.push(cb(handle));
+        self
+    }
     /// Set the callback for when a mouse button is pressed.
     pub fn on_mouse_down<F: Fn(&mut World, EntityId, MouseButton) + Sync + Send + 'static>(self, handle: F) -> Self {
         self.on_mouse_input(move |world, id, state, button| {
@@ -100,13 +119,26 @@ impl ClickArea {
 }
 impl ElementComponent for ClickArea {
     fn render(self: Box<Self>, hooks: &mut Hooks) -> Element {
-        let Self { inner, on_mouse_enter, on_mouse_leave, on_mouse_hover, on_mouse_input, on_mouse_wheel } = *self;
+        let Self { inner, on_mouse_enter, on_mouse_leave, on_mouse_hover, on_mouse_input, on_mouse_wheel, on_activate, on_focus_change } =
+            *self;
         let id = hooks.use_ref_with(|_| None);
         let mouse_over_count = hooks.use_ref_with(|_| 0);
+        let focused = hooks.use_ref_with(|_| false);
         hooks.use_frame({
             to_owned![id, mouse_over_count];
             move |world| {
                 if let Some(id) = *id.lock() {
+                    if !on_focus_change.is_empty() {
+                        let next = world.get(EntityId::resources(), accessibility_focus()).ok() == Some(id);
+                        let mut focused = focused.lock();
+                        if *focused != next {
+                            *focused = next;
+                            for handler in &on_focus_change {
+                                handler(world, id, next);
+                            }
+                        }
+                    }
+
                     let next = world.get(id, mouse_over()).unwrap_or(0);
                     let mut state = mouse_over_count.lock();
                     if *state == 0 && next > 0 {
@@ -155,6 +187,17 @@ impl ElementComponent for ClickArea {
             }
         });
 
+        hooks.use_runtime_message::<messages::AccessibilityActivate>({
+            to_owned![id];
+            move |world, event| {
+                if *id.lock() == Some(event.entity) {
+                    for handler in &on_activate {
+                        handler(world, event.entity);
+                    }
+                }
+            }
+        });
+
         inner.init(mouse_pickable_min(), Vec3::ZERO).init(mouse_pickable_max(), Vec3::ZERO).on_spawned(move |_, new_id, _| {
             *id.lock() = Some(new_id);
         })
@@ -1,14 +1,20 @@
 //! Defines the default theme for the UI.
-use glam::{vec4, Vec4};
+use glam::vec4;
 
-use crate::UIExt;
+use crate::{
+    skin::{ThemeSkin, Themed},
+    UIExt,
+};
 use ambient_color::Color;
-use ambient_element::Element;
-use ambient_guest_bridge::components::{
-    layout::{align_vertical_center, space_between_items},
-    rect::border_radius,
-    rendering::color,
-    text::font_size,
+use ambient_element::{Element, ElementComponentExt, Hooks};
+use ambient_guest_bridge::{
+    components::{
+        accessibility::high_contrast,
+        layout::{align_vertical_center, space_between_items},
+        rendering::color,
+        text::font_size,
+    },
+    ecs::{EntityId, World},
 };
 
 /// The primary color.
@@ -35,6 +41,30 @@ pub fn cutout_color() -> Color {
 pub fn tooltip_background_color() -> Color {
     Color::rgba(0., 0., 0., 0.9)
 }
+/// The color of hyperlinks in [RichText](crate::text::RichText).
+pub fn link_color() -> Color {
+    Color::hex("4DA6FF").unwrap()
+}
+
+/// The color of the border drawn around interactive elements when high contrast is enabled.
+pub fn high_contrast_border_color() -> Color {
+    Color::WHITE
+}
+
+/// Returns whether the user has requested a high-contrast UI. The element is re-rendered when this changes.
+pub fn use_high_contrast(hooks: &mut Hooks) -> bool {
+    let (value, set_value) = hooks.use_state_with(|world| get_high_contrast(world));
+    hooks.use_frame(move |world| {
+        let new_value = get_high_contrast(world);
+        if new_value != value {
+            set_value(new_value);
+        }
+    });
+    value
+}
+fn get_high_contrast(world: &World) -> bool {
+    world.get(EntityId::resources(), high_contrast()).unwrap_or(false)
+}
 
 /// Default margin/padding.
 pub const STREET: f32 = 10.;
@@ -51,9 +81,9 @@ pub trait StylesExt {
     fn small_style(self) -> Self;
     /// Apply the default style for an error text.
     fn error_text_style(self) -> Self;
-    /// Apply the default style for a floating panel.
+    /// Apply the style for a floating panel of the [Theme](crate::skin::Theme) provided further up the tree.
     fn floating_panel(self) -> Self;
-    /// Apply the default style for a panel.
+    /// Apply the style for a panel of the [Theme](crate::skin::Theme) provided further up the tree.
     fn panel(self) -> Self;
     /// A list of items with some space between them.
     fn keyboard(self) -> Self;
@@ -71,14 +101,11 @@ impl StylesExt for Element {
     fn error_text_style(self) -> Self {
         self.with(color(), vec4(1., 0.5, 0.5, 1.))
     }
-    #[allow(clippy::clone_on_copy)]
     fn floating_panel(self) -> Self {
-        self.with_background(Color::hex("1D1C22").unwrap().set_a(0.9).clone().into())
-            .with(border_radius(), Vec4::ONE * 5.)
-            .with_padding_even(STREET)
+        Themed { inner: self, skin: ThemeSkin::FloatingPanel }.el().with_padding_even(STREET)
     }
     fn panel(self) -> Self {
-        self.with_background(Color::rgba(1., 1., 1., 0.01).into()).with(border_radius(), Vec4::ONE * 5.)
+        Themed { inner: self, skin: ThemeSkin::Panel }.el()
     }
     fn keyboard(self) -> Self {
         self.with(space_between_items(), STREET).with_padding_even(STREET).with_default(align_vertical_center())
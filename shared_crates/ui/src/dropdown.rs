@@ -1,16 +1,10 @@
 //! Implements a dropdown element.
 
 use ambient_element::{element_component, to_owned, Element, ElementComponentExt, Hooks};
-use ambient_guest_bridge::components::{
-    layout::margin, rect::border_radius, transform::translation,
-};
+use ambient_guest_bridge::components::{layout::margin, transform::translation};
 use glam::{vec3, Vec4};
 
-use crate::{
-    default_theme::{tooltip_background_color, SMALL_ROUNDING, STREET},
-    layout::FlowColumn,
-    UIBase, UIExt,
-};
+use crate::{default_theme::STREET, layout::FlowColumn, skin::use_theme, UIBase, UIExt};
 
 #[element_component]
 /// A dropdown element: shows the `dropdown` when `show` is specified.
@@ -45,13 +39,13 @@ pub fn Tooltip(
     tooltip: Element,
 ) -> Element {
     let (hover, set_hover) = hooks.use_state(false);
+    let theme = use_theme(hooks);
     Dropdown {
         content: inner,
         dropdown: FlowColumn(vec![tooltip])
             .el()
             .with_padding_even(STREET)
-            .with_background(tooltip_background_color().into())
-            .with(border_radius(), Vec4::ONE * SMALL_ROUNDING)
+            .with_skin(&theme.tooltip)
             .with(margin(), Vec4::X * STREET),
         show: hover,
     }
@@ -6,7 +6,7 @@ use ambient_cb::{cb, Cb};
 use ambient_element::{element_component, to_owned, Element, ElementComponentExt, Hooks};
 use ambient_guest_bridge::{
     components::{
-        layout::{height, min_height, min_width, width},
+        layout::{height, min_height, min_width, padding, width},
         rendering::color,
         text::text,
         transform::translation,
@@ -21,7 +21,7 @@ use glam::*;
 use itertools::Itertools;
 
 use super::{Editor, EditorOpts};
-use crate::{layout::FlowRow, text::Text, use_focus, Rectangle, UIBase, UIExt};
+use crate::{layout::FlowRow, skin::use_theme, text::Text, use_focus, Rectangle, UIBase, UIExt};
 
 /// A text editor.
 #[element_component]
@@ -41,6 +41,7 @@ pub fn TextEditor(
     auto_focus: bool,
 ) -> Element {
     let (focused, set_focused) = use_focus(hooks);
+    let theme = use_theme(hooks);
     let (command, set_command) = hooks.use_state(false);
     let intermediate_value = hooks.use_ref_with(|_| value.clone());
     let cursor_position = hooks.use_ref_with(|_| value.len());
@@ -189,12 +190,15 @@ pub fn TextEditor(
             FlowRow::el([Cursor.el(), b])
         }
     } else if value.is_empty() && !focused && placeholder.is_some() {
-        Text.el().with(text(), placeholder.unwrap()).with(color(), vec4(1., 1., 1., 0.2))
+        // The text is wrapped, as it can't be a rect itself
+        FlowRow::el([Text.el().with(text(), placeholder.unwrap()).with(color(), vec4(1., 1., 1., 0.2))])
     } else {
         FlowRow::el([a, b])
     }
     .with(min_width(), 3.)
     .with(min_height(), 13.)
+    .with_skin(&theme.cutout)
+    .with(padding(), vec4(2., 4., 2., 4.))
     .with_clickarea()
     .on_mouse_up(move |_, _, _| {
         set_focused(true);
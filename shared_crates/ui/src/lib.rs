@@ -35,9 +35,9 @@ use ambient_element::{
 };
 use ambient_guest_bridge::{
     components::{
-        app::{ui_scene, window_logical_size, window_physical_size},
+        app::{ui_scene, window_logical_size, window_physical_size, window_scale_factor},
         layout::{gpu_ui_size, height, margin, mesh_to_local_from_size, padding, width},
-        rect::{background_color, rect},
+        rect::{background_color, ellipse, polygon_sides, rect},
         transform::{
             local_to_parent, local_to_world, mesh_to_local, mesh_to_world, scale, translation,
         },
@@ -46,7 +46,9 @@ use ambient_guest_bridge::{
 };
 use clickarea::ClickArea;
 use glam::{vec3, Mat4, UVec2, Vec3, Vec4};
+use skin::Skin;
 
+pub mod animation;
 pub mod button;
 pub mod clickarea;
 pub mod default_theme;
@@ -58,6 +60,7 @@ pub mod prompt;
 pub mod screens;
 pub mod scroll_area;
 pub mod select;
+pub mod skin;
 pub mod tabs;
 pub mod text;
 pub mod throbber;
@@ -109,6 +112,20 @@ pub fn use_window_logical_resolution(hooks: &mut Hooks) -> UVec2 {
     });
     res
 }
+// We need `clone` as resource is a ref on host and a copy on guest
+#[allow(clippy::clone_on_copy)]
+/// A hook that returns the current window scale factor, which changes when the window is moved to a monitor with a
+/// different DPI
+pub fn use_window_scale_factor(hooks: &mut Hooks) -> f64 {
+    let (factor, set_factor) = hooks.use_state(hooks.world.resource(window_scale_factor()).clone());
+    hooks.use_frame(move |world| {
+        let new_factor = world.resource(window_scale_factor()).clone();
+        if new_factor != factor {
+            set_factor(new_factor);
+        }
+    });
+    factor
+}
 
 /// A simple UI rect. Use components like `width`, `height`, `background_color`, `border_color`, `border_radius` and `border_thickness`
 /// to control its appearance.
@@ -131,6 +148,20 @@ pub fn with_rect(element: Element) -> Element {
         .init(ui_scene(), ())
 }
 
+/// A UI ellipse that fills its `width` and `height`. Use components like `background_color`, `border_color`,
+/// `border_thickness` and `shadow_color` to control its appearance.
+#[element_component]
+pub fn Ellipse(_hooks: &mut Hooks) -> Element {
+    Rectangle.el().with_default(ellipse())
+}
+
+/// A regular UI polygon with `sides` sides and a corner pointing up, stretched to fill its `width` and `height`.
+/// Use components like `background_color`, `border_color`, `border_thickness` and `shadow_color` to control its appearance.
+#[element_component]
+pub fn Polygon(_hooks: &mut Hooks, sides: u32) -> Element {
+    Rectangle.el().with(polygon_sides(), sides)
+}
+
 /// A simple UI line. Use components like `line_from`, `line_to`, `line_width`, `background_color`, `border_color`, `border_radius` and `border_thickness`
 /// to control its appearance.
 #[element_component]
@@ -211,6 +242,8 @@ pub trait UIExt {
     fn with_clickarea(self) -> ClickArea;
     /// Adds a background color to this element.
     fn with_background(self, color: Vec4) -> Self;
+    /// Converts this element into a rect with the look of `skin`.
+    fn with_skin(self, skin: &Skin) -> Self;
     /// Adds padding to all sides of this element.
     fn with_padding_even(self, padding: f32) -> Self;
     /// Adds margin to all sides of this element.
@@ -223,6 +256,9 @@ impl UIExt for Element {
     fn with_background(self, background: Vec4) -> Self {
         with_rect(self).with(background_color(), background)
     }
+    fn with_skin(self, skin: &Skin) -> Self {
+        skin.apply(self)
+    }
     fn with_padding_even(self, value: f32) -> Self {
         self.with(padding(), Vec4::ONE * value)
     }
@@ -1,17 +1,15 @@
 //! Defines elements that can be used to select an item from a list.
 use ambient_cb::Cb;
 use ambient_element::{to_owned, Element, ElementComponent, ElementComponentExt, Hooks};
-use ambient_guest_bridge::{
-    components::{layout::margin, rect::border_radius},
-    messages,
-};
-use glam::{vec4, Vec4};
+use ambient_guest_bridge::{components::layout::margin, messages};
+use glam::vec4;
 
 use crate::{
     button::{Button, ButtonStyle},
-    default_theme::{tooltip_background_color, SMALL_ROUNDING, STREET},
+    default_theme::STREET,
     dropdown::Dropdown,
     layout::{FlowColumn, FlowRow},
+    skin::use_theme,
     text::Text,
     UIExt,
 };
@@ -37,6 +35,7 @@ impl ElementComponent for DropdownSelect {
             inline,
         } = *self;
         let (show, set_show) = hooks.use_state(false);
+        let theme = use_theme(hooks);
         hooks.use_runtime_message::<messages::WindowMouseInput>({
             to_owned![set_show];
             move |_world, event| {
@@ -96,8 +95,7 @@ impl ElementComponent for DropdownSelect {
             )
             .el()
             .with_padding_even(STREET)
-            .with(border_radius(), Vec4::ONE * SMALL_ROUNDING)
-            .with_background(tooltip_background_color().into()),
+            .with_skin(&theme.tooltip),
             show,
         }
         .el()
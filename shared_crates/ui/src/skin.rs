@@ -0,0 +1,219 @@
+//! Skins describe the look of UI rects: their fill, border, rounded corners, drop shadow and nine-slice background
+//! image. A [Theme] groups the skins of the standard widgets, and can be replaced for a part of the UI with
+//! [Hooks::provide_context].
+use ambient_color::Color;
+use ambient_element::{element_component, Element, Hooks};
+use ambient_guest_bridge::components::rect::{
+    background_color, background_image_slice, background_image_url, border_color, border_radius,
+    border_thickness, shadow_blur, shadow_color, shadow_offset,
+};
+use glam::{vec2, Vec2, Vec4};
+
+use crate::{
+    default_theme::{
+        app_background_color, cutout_color, primary_color, tooltip_background_color, SMALL_ROUNDING,
+    },
+    with_rect,
+};
+
+/// A drop shadow behind a rect.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shadow {
+    /// The color of the shadow.
+    pub color: Vec4,
+    /// How far the shadow is moved from the rect, in pixels.
+    pub offset: Vec2,
+    /// How far the shadow fades out, in pixels.
+    pub blur: f32,
+}
+impl Shadow {
+    /// A soft shadow, slightly below the rect.
+    pub fn soft() -> Self {
+        Self {
+            color: Vec4::new(0., 0., 0., 0.4),
+            offset: vec2(0., 2.),
+            blur: 8.,
+        }
+    }
+}
+
+/// An image stretched over a rect with nine-slice scaling: the corners keep their size, the edges stretch along the
+/// rect and the center stretches in both directions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NineSlice {
+    /// The URL of the image, e.g. from `asset::url`.
+    pub url: String,
+    /// The size of the corners, in pixels of the image: left, top, right and bottom.
+    pub insets: Vec4,
+}
+impl NineSlice {
+    /// The image at `url`, with corners of `inset` pixels on every side.
+    pub fn new(url: impl Into<String>, inset: f32) -> Self {
+        Self {
+            url: url.into(),
+            insets: Vec4::splat(inset),
+        }
+    }
+}
+
+/// The look of a rect.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Skin {
+    /// The color of the rect; the image is tinted with it.
+    pub background: Vec4,
+    /// The color of the border.
+    pub border_color: Vec4,
+    /// The thickness of the border, in pixels.
+    pub border_thickness: f32,
+    /// The radius of each corner, in pixels: top-left, top-right, bottom-left and bottom-right.
+    pub border_radius: Vec4,
+    /// The drop shadow behind the rect, if any.
+    pub shadow: Option<Shadow>,
+    /// The image drawn inside of the rect, if any.
+    pub image: Option<NineSlice>,
+}
+impl Default for Skin {
+    fn default() -> Self {
+        Self {
+            background: Vec4::ONE,
+            border_color: Vec4::ZERO,
+            border_thickness: 0.,
+            border_radius: Vec4::ZERO,
+            shadow: None,
+            image: None,
+        }
+    }
+}
+impl Skin {
+    /// A skin with a solid `background`.
+    pub fn solid(background: Vec4) -> Self {
+        Self {
+            background,
+            ..Default::default()
+        }
+    }
+    /// Rounds every corner by `radius` pixels.
+    pub fn rounded(mut self, radius: f32) -> Self {
+        self.border_radius = Vec4::splat(radius);
+        self
+    }
+    /// Adds a border of `thickness` pixels.
+    pub fn border(mut self, color: Vec4, thickness: f32) -> Self {
+        self.border_color = color;
+        self.border_thickness = thickness;
+        self
+    }
+    /// Adds a drop shadow.
+    pub fn shadow(mut self, shadow: Shadow) -> Self {
+        self.shadow = Some(shadow);
+        self
+    }
+    /// Draws a nine-slice image inside of the rect.
+    pub fn image(mut self, image: NineSlice) -> Self {
+        self.image = Some(image);
+        self
+    }
+
+    /// Converts `element` into a rect with this skin.
+    pub fn apply(&self, element: Element) -> Element {
+        let mut element = with_rect(element)
+            .with(background_color(), self.background)
+            .with(border_color(), self.border_color)
+            .with(border_thickness(), self.border_thickness)
+            .with(border_radius(), self.border_radius);
+        if let Some(shadow) = &self.shadow {
+            element = element
+                .with(shadow_color(), shadow.color)
+                .with(shadow_offset(), shadow.offset)
+                .with(shadow_blur(), shadow.blur);
+        }
+        if let Some(image) = &self.image {
+            element = element
+                .with(background_image_url(), image.url.clone())
+                .with(background_image_slice(), image.insets);
+        }
+        element
+    }
+}
+
+/// The skins of the standard widgets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    /// The skin of a panel inside of another panel.
+    pub panel: Skin,
+    /// The skin of a panel floating above the rest of the UI.
+    pub floating_panel: Skin,
+    /// The skin of a regular button. Pressed, hovered and toggled buttons change its background color, and primary
+    /// buttons use the secondary color.
+    pub button: Skin,
+    /// The skin of a tooltip or a dropdown.
+    pub tooltip: Skin,
+    /// The skin of a sunken area, like a text field.
+    pub cutout: Skin,
+}
+impl Default for Theme {
+    #[allow(clippy::clone_on_copy)]
+    fn default() -> Self {
+        Self {
+            panel: Skin::solid(Vec4::new(1., 1., 1., 0.01)).rounded(5.),
+            floating_panel: Skin::solid(Color::hex("1D1C22").unwrap().set_a(0.9).clone().into())
+                .rounded(5.)
+                .shadow(Shadow::soft()),
+            button: Skin::solid(cutout_color().into()).rounded(13.),
+            tooltip: Skin::solid(tooltip_background_color().into())
+                .rounded(SMALL_ROUNDING)
+                .shadow(Shadow::soft()),
+            cutout: Skin::solid(cutout_color().into())
+                .rounded(3.)
+                .border(app_background_color().into(), 1.),
+        }
+    }
+}
+
+/// Returns the [Theme] provided further up the tree, or the default theme.
+pub fn use_theme(hooks: &mut Hooks) -> Theme {
+    hooks
+        .consume_context::<Theme>()
+        .map(|(theme, _)| theme)
+        .unwrap_or_default()
+}
+
+/// One of the skins of a [Theme].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeSkin {
+    /// [Theme::panel]
+    Panel,
+    /// [Theme::floating_panel]
+    FloatingPanel,
+    /// [Theme::button]
+    Button,
+    /// [Theme::tooltip]
+    Tooltip,
+    /// [Theme::cutout]
+    Cutout,
+}
+impl Theme {
+    /// Returns the skin `skin` of this theme.
+    pub fn skin(&self, skin: ThemeSkin) -> &Skin {
+        match skin {
+            ThemeSkin::Panel => &self.panel,
+            ThemeSkin::FloatingPanel => &self.floating_panel,
+            ThemeSkin::Button => &self.button,
+            ThemeSkin::Tooltip => &self.tooltip,
+            ThemeSkin::Cutout => &self.cutout,
+        }
+    }
+}
+
+/// Gives `inner` the `skin` of the [Theme] provided further up the tree. Use this to skin elements that are built
+/// without access to the hooks.
+#[element_component]
+pub fn Themed(
+    hooks: &mut Hooks,
+    /// The element to skin.
+    inner: Element,
+    /// Which skin of the theme to use.
+    skin: ThemeSkin,
+) -> Element {
+    use_theme(hooks).skin(skin).apply(inner)
+}
@@ -1,15 +1,26 @@
 //! Defines a text element.
 
-use crate::{UIBase, UIElement};
+use crate::{
+    clickarea::ClickArea,
+    default_theme::link_color,
+    layout::{FlowColumn, FlowRow},
+    UIBase, UIElement,
+};
+use ambient_cb::{cb, Cb};
+use ambient_color::Color;
 use ambient_element::{element_component, Element, ElementComponentExt, Hooks};
-use ambient_guest_bridge::components::{
-    app::{name, ui_scene},
-    layout::{height, width},
-    rendering::color,
-    text::{font_family, font_size, text},
-    transform::mesh_to_local,
+use ambient_guest_bridge::{
+    components::{
+        app::{name, ui_scene},
+        layout::{height, width},
+        rendering::color,
+        text::{font_family, font_size, font_style, text},
+        transform::mesh_to_local,
+    },
+    ecs::World,
 };
-use glam::{vec4, Mat4};
+use ambient_shared_types::MouseButton;
+use glam::{vec4, Mat4, Vec4};
 
 /// A text element. Use the [text], [font_size], [font_family] and [color] components to set its state.
 #[element_component(without_el)]
@@ -61,3 +72,275 @@ pub fn FontAwesomeIcon(
     Text::el(char::from_u32(icon).unwrap().to_string())
         .with(font_family(), if solid { "FontAwesomeSolid" } else { "FontAwesome" }.to_string())
 }
+
+/// A span of [RichText], with its own style.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextSpan {
+    /// The text of the span. Line breaks in it start new lines.
+    pub text: String,
+    /// The color of the span. Spans without one are drawn in the default color of [Text], or the [link_color] if
+    /// they are links.
+    pub color: Option<Vec4>,
+    /// Whether the span is bold.
+    pub bold: bool,
+    /// Whether the span is italic.
+    pub italic: bool,
+    /// The font family of the span (see [font_family]), e.g. `Code` for inline code.
+    pub font_family: Option<String>,
+    /// If set, the span is a hyperlink to this target, which is passed to the `on_link` callback of the [RichText]
+    /// when it is clicked.
+    pub link: Option<String>,
+}
+impl TextSpan {
+    /// Creates a new span of unstyled text.
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Parses `markup` into spans. Styles are set with tags in square brackets, which can be nested:
+    /// `[b]bold[/b]`, `[i]italic[/i]`, `[code]code[/code]`, `[color=#ff8000]orange[/color]` and
+    /// `[link=target]a hyperlink[/link]`. `[[` is a literal `[`, and tags that aren't recognized or closed in order
+    /// are kept as text.
+    pub fn parse(markup: &str) -> Vec<Self> {
+        let mut spans = Vec::new();
+        let mut text = String::new();
+        let mut style = Self::default();
+        // The open tags, and the styles from before they were opened
+        let mut open = Vec::<(&str, Self)>::new();
+        let flush = |spans: &mut Vec<Self>, text: &mut String, style: &Self| {
+            if !text.is_empty() {
+                spans.push(Self {
+                    text: std::mem::take(text),
+                    ..style.clone()
+                });
+            }
+        };
+
+        let mut rest = markup;
+        while let Some(start) = rest.find('[') {
+            text.push_str(&rest[..start]);
+            rest = &rest[start..];
+            if let Some(after) = rest.strip_prefix("[[") {
+                text.push('[');
+                rest = after;
+                continue;
+            }
+            let Some(end) = rest.find(']') else {
+                break;
+            };
+            let tag = &rest[1..end];
+            let (name, value) = tag.split_once('=').unwrap_or((tag, ""));
+            let mut next = style.clone();
+            let opened = match (name, value) {
+                ("b", "") => {
+                    next.bold = true;
+                    true
+                }
+                ("i", "") => {
+                    next.italic = true;
+                    true
+                }
+                ("code", "") => {
+                    next.font_family = Some("Code".to_string());
+                    true
+                }
+                ("color", value) => match Color::hex(value.trim_start_matches('#')) {
+                    Ok(color) => {
+                        next.color = Some(color.into());
+                        true
+                    }
+                    Err(_) => false,
+                },
+                ("link", value) if !value.is_empty() => {
+                    next.link = Some(value.to_string());
+                    true
+                }
+                _ => false,
+            };
+            let closes_last = value.is_empty()
+                && name.strip_prefix('/').is_some()
+                && open.last().map(|(open, _)| *open) == name.strip_prefix('/');
+            if opened {
+                flush(&mut spans, &mut text, &style);
+                open.push((name, std::mem::replace(&mut style, next)));
+            } else if closes_last {
+                flush(&mut spans, &mut text, &style);
+                style = open.pop().unwrap().1;
+            } else {
+                text.push_str(&rest[..=end]);
+            }
+            rest = &rest[end + 1..];
+        }
+        text.push_str(rest);
+        flush(&mut spans, &mut text, &style);
+        spans
+    }
+
+    fn font_style(&self) -> &'static str {
+        match (self.bold, self.italic) {
+            (false, false) => "Regular",
+            (true, false) => "Bold",
+            (false, true) => "Italic",
+            (true, true) => "BoldItalic",
+        }
+    }
+}
+
+/// Text made of [TextSpan]s with their own colors, font styles and hyperlinks, e.g. for chat messages and tooltips.
+///
+/// Each line is laid out as a row of [Text]s, so long lines are not wrapped between spans.
+#[element_component]
+pub fn RichText(
+    _hooks: &mut Hooks,
+    /// The spans of the text.
+    spans: Vec<TextSpan>,
+    /// Called with the `link` of a span when it is clicked.
+    on_link: Option<Cb<dyn Fn(&mut World, &str) + Sync + Send>>,
+) -> Element {
+    let mut lines = vec![Vec::new()];
+    for span in spans {
+        for (i, line) in span.text.split('\n').enumerate() {
+            if i > 0 {
+                lines.push(Vec::new());
+            }
+            if line.is_empty() {
+                continue;
+            }
+            let mut el = Text::el(line).with(font_style(), span.font_style().to_string());
+            if let Some(family) = &span.font_family {
+                el = el.with(font_family(), family.clone());
+            }
+            let link_color = span.link.as_ref().map(|_| link_color().into());
+            if let Some(span_color) = span.color.or(link_color) {
+                el = el.with(color(), span_color);
+            }
+            if let (Some(link), Some(on_link)) = (&span.link, &on_link) {
+                let (link, on_link) = (link.clone(), on_link.clone());
+                el = ClickArea::new(el)
+                    .on_mouse_up(move |world, _, button| {
+                        if button == MouseButton::Left {
+                            on_link(world, &link);
+                        }
+                    })
+                    .el();
+            }
+            lines.last_mut().unwrap().push(el);
+        }
+    }
+    FlowColumn::el(lines.into_iter().map(|line| {
+        if line.is_empty() {
+            // Empty lines keep the height of a line
+            Text::el(" ")
+        } else {
+            FlowRow::el(line)
+        }
+    }))
+}
+impl RichText {
+    /// Creates rich text from `markup`; see [TextSpan::parse].
+    pub fn parse(markup: &str) -> Self {
+        Self {
+            spans: TextSpan::parse(markup),
+            on_link: None,
+        }
+    }
+
+    /// Sets the callback for when a hyperlink is clicked, which is called with its target.
+    pub fn on_link(mut self, on_link: impl Fn(&mut World, &str) + Sync + Send + 'static) -> Self {
+        self.on_link = Some(cb(on_link));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn styled(text: &str, style: impl FnOnce(&mut TextSpan)) -> TextSpan {
+        let mut span = TextSpan::new(text);
+        style(&mut span);
+        span
+    }
+
+    #[test]
+    fn plain_text_is_one_span() {
+        assert_eq!(TextSpan::parse("hello"), vec![TextSpan::new("hello")]);
+        assert_eq!(TextSpan::parse(""), vec![]);
+    }
+
+    #[test]
+    fn nested_tags_combine_their_styles() {
+        let orange: Vec4 = Color::hex("ff8000").unwrap().into();
+        assert_eq!(
+            TextSpan::parse(
+                "a[b]b[i]c[color=#ff8000]d[/color][/i]e[/b][link=x][code]f[/code][/link]"
+            ),
+            vec![
+                TextSpan::new("a"),
+                styled("b", |s| s.bold = true),
+                styled("c", |s| {
+                    s.bold = true;
+                    s.italic = true;
+                }),
+                styled("d", |s| {
+                    s.bold = true;
+                    s.italic = true;
+                    s.color = Some(orange);
+                }),
+                styled("e", |s| s.bold = true),
+                styled("f", |s| {
+                    s.link = Some("x".to_string());
+                    s.font_family = Some("Code".to_string());
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn unclosed_tags_style_the_rest() {
+        assert_eq!(
+            TextSpan::parse("a[b]b"),
+            vec![TextSpan::new("a"), styled("b", |s| s.bold = true)]
+        );
+        // A tag without its closing bracket is text
+        assert_eq!(TextSpan::parse("a[b"), vec![TextSpan::new("a[b")]);
+    }
+
+    #[test]
+    fn tags_closed_out_of_order_are_text() {
+        assert_eq!(
+            TextSpan::parse("[b][i]a[/b][/i]b[/b]"),
+            vec![
+                styled("a[/b]", |s| {
+                    s.bold = true;
+                    s.italic = true;
+                }),
+                styled("b", |s| s.bold = true),
+            ]
+        );
+        assert_eq!(TextSpan::parse("a[/b]"), vec![TextSpan::new("a[/b]")]);
+    }
+
+    #[test]
+    fn unknown_tags_are_text() {
+        assert_eq!(
+            TextSpan::parse("[u]a[/u] [color=nope]b[link=]c"),
+            vec![TextSpan::new("[u]a[/u] [color=nope]b[link=]c")]
+        );
+    }
+
+    #[test]
+    fn double_brackets_escape_tags() {
+        assert_eq!(
+            TextSpan::parse("[[b]a[b]]b[/b][[[/b]"),
+            vec![
+                TextSpan::new("[b]a"),
+                styled("]b", |s| s.bold = true),
+                TextSpan::new("[[/b]"),
+            ]
+        );
+    }
+}